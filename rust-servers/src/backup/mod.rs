@@ -0,0 +1,455 @@
+// 备份模块
+// 把配置的目录 (通常是 vault) 打包为 zstd 压缩的 tar 快照，保存在数据目录的 backups 区域 (或请求指定的目录)，
+// 支持按数量 / 天数的保留策略、列出快照内容和按文件恢复。创建快照在后台执行：立即应答 create_started，
+// 过程中发送 progress 事件，完成后发送 created 事件 (失败时为 error 事件)，可以用 system/cancel 取消。
+// 计划任务模块的 backup 动作按同样的参数定时创建快照。客户端声明 vault 时，未指定 dest 的快照保存在该 vault 的子目录
+
+mod snapshot;
+
+use snapshot::{BackupError, Retention};
+
+use crate::jobs::{self, Job, JobKind};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use crate::system::progress::{percent_between, ProgressReporter};
+use futures_util::SinkExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::Mutex as TokioMutex;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Backup", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Backup", format!($($arg)*));
+        }
+    };
+}
+
+/// 省略 name 时的快照名称
+const DEFAULT_NAME: &str = "backup";
+
+/// 同时进行的备份数上限
+const MAX_OPERATIONS: usize = 4;
+
+/// 快照所在目录 (启动时设置)
+static STORE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 设置快照所在目录 (启动时调用一次；未设置时请求必须指定 dest)
+pub fn init_dir(dir: PathBuf) {
+    let _ = STORE_DIR.set(dir);
+}
+
+/// 创建备份模块错误
+fn backup_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Backup, code, message)
+}
+
+/// 快照所在目录：请求指定的 dest，否则为数据目录的 backups 区域 (当前 vault 的子目录)
+fn store_dir(dest: Option<&str>) -> Result<PathBuf, ModuleError> {
+    match dest {
+        Some("") => Err(backup_error(ErrorCode::InvalidParams, "dest 不能为空")),
+        Some(dest) => Ok(PathBuf::from(dest)),
+        None => STORE_DIR
+            .get()
+            .map(|dir| crate::storage::vault_path(dir, crate::storage::current_vault().as_deref()))
+            .ok_or_else(|| backup_error(ErrorCode::InvalidConfig, "未配置数据目录，请指定 dest")),
+    }
+}
+
+/// 在阻塞线程中执行快照操作
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T, BackupError> + Send + 'static) -> Result<T, ModuleError> {
+    let result = tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| backup_error(ErrorCode::Internal, format!("备份任务失败: {}", e)))?;
+    result.map_err(|e| ModuleError::from_error(ModuleType::Backup, &e))
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// create 请求 (也是计划任务 backup 动作的 data)
+#[derive(Debug, Clone, Deserialize)]
+struct CreateRequest {
+    /// 要备份的目录
+    folders: Vec<String>,
+    /// 快照名称 (保留策略按名称分别计算)
+    #[serde(default)]
+    name: Option<String>,
+    /// 快照所在目录
+    #[serde(default)]
+    dest: Option<String>,
+    /// 跳过的文件和目录名 (如 .git、.trash)
+    #[serde(default)]
+    exclude: Vec<String>,
+    /// 创建后按该策略删除同名的旧快照
+    #[serde(flatten)]
+    retention: Retention,
+}
+
+impl CreateRequest {
+    fn parse(payload: &serde_json::Value, msg_type: &str) -> Result<Self, ModuleError> {
+        let request: Self = serde_json::from_value(payload.clone())
+            .map_err(|e| backup_error(ErrorCode::InvalidParams, format!("无效的 {} 请求: {}", msg_type, e)))?;
+        if request.folders.is_empty() {
+            return Err(backup_error(ErrorCode::InvalidParams, "folders 不能为空"));
+        }
+        snapshot::validate_name(request.name())
+            .map_err(|e| ModuleError::from_error(ModuleType::Backup, &e))?;
+        Ok(request)
+    }
+
+    fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(DEFAULT_NAME)
+    }
+}
+
+/// list / files / restore / prune 请求
+#[derive(Debug, Deserialize)]
+struct SnapshotRequest {
+    #[serde(default)]
+    dest: Option<String>,
+    /// files / restore: 快照 ID
+    #[serde(default)]
+    id: Option<String>,
+    /// list / prune: 快照名称
+    #[serde(default)]
+    name: Option<String>,
+    /// files: 只列出该路径下的文件
+    #[serde(default)]
+    prefix: Option<String>,
+    /// restore: 要恢复的文件或目录 (快照中的路径)
+    #[serde(default)]
+    paths: Vec<String>,
+    /// restore: 恢复到该目录 (省略时恢复到原位置)
+    #[serde(default)]
+    target: Option<String>,
+    /// restore: 覆盖已存在的文件
+    #[serde(default)]
+    overwrite: bool,
+    /// prune: 保留策略
+    #[serde(flatten)]
+    retention: Retention,
+}
+
+impl SnapshotRequest {
+    fn id(&self) -> Result<String, ModuleError> {
+        self.id.clone().ok_or_else(|| backup_error(ErrorCode::InvalidParams, "缺少 id 字段"))
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(msg: &ModuleMessage) -> Result<T, ModuleError> {
+    serde_json::from_value(msg.payload.clone())
+        .map_err(|e| backup_error(ErrorCode::InvalidParams, format!("无效的 {} 请求: {}", msg.msg_type, e)))
+}
+
+/// 创建快照并按保留策略清理，返回 created 事件的内容
+fn create_snapshot(
+    dir: &Path,
+    request: &CreateRequest,
+    cancelled: &AtomicBool,
+    report: &dyn Fn(u64, u64),
+) -> Result<serde_json::Value, BackupError> {
+    let folders: Vec<PathBuf> = request.folders.iter().map(PathBuf::from).collect();
+    let info = snapshot::create(dir, request.name(), &folders, &request.exclude, cancelled, report)?;
+    let pruned = snapshot::prune(dir, request.name(), &request.retention)?;
+    let mut payload = to_value(&info);
+    payload["path"] = dir.join(format!("{}{}", info.id, snapshot::EXTENSION)).display().to_string().into();
+    payload["pruned"] = to_value(&pruned);
+    Ok(payload)
+}
+
+// ============================================================================
+// 计划任务动作
+// ============================================================================
+
+/// 计划任务的 backup 动作：任务 data 与 create 请求相同
+pub struct ScheduledBackup;
+
+#[async_trait::async_trait]
+impl crate::scheduler::JobAction for ScheduledBackup {
+    fn validate(&self, data: &serde_json::Value) -> Result<(), ModuleError> {
+        let request = CreateRequest::parse(data, "backup")?;
+        store_dir(request.dest.as_deref()).map(|_| ())
+    }
+
+    async fn run(&self, data: serde_json::Value) -> Result<serde_json::Value, ModuleError> {
+        let request = CreateRequest::parse(&data, "backup")?;
+        let dir = store_dir(request.dest.as_deref())?;
+        log_info!("计划备份开始: {} {:?}", request.name(), request.folders);
+        let payload = blocking(move || create_snapshot(&dir, &request, &AtomicBool::new(false), &|_, _| {})).await?;
+        log_info!("计划备份完成: {}", payload["id"]);
+        Ok(payload)
+    }
+}
+
+// ============================================================================
+// 备份处理器
+// ============================================================================
+
+/// 备份模块处理器
+pub struct BackupHandler {
+    /// 进行中的备份: request_id (没有时为生成的 ID) → 取消标志
+    operations: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    /// WebSocket 发送器 (请求没有来源连接时使用)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl BackupHandler {
+    /// 创建新的备份处理器
+    pub fn new() -> Self {
+        Self {
+            operations: Arc::new(Mutex::new(HashMap::new())),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    /// 处理 create 消息 - 在后台创建快照，立即应答
+    async fn handle_create(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request = CreateRequest::parse(&msg.payload, &msg.msg_type)?;
+        let dir = store_dir(request.dest.as_deref())?;
+
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+        let sender = sender.ok_or_else(|| backup_error(ErrorCode::NotConnected, "WebSocket sender not set"))?;
+
+        let request_id = msg.request_id();
+        let key = request_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        let cancelled = Arc::new(AtomicBool::new(false));
+        {
+            let mut operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+            if operations.len() >= MAX_OPERATIONS {
+                return Err(backup_error(ErrorCode::LimitExceeded, format!("进行中的备份过多 (最多 {} 个)", MAX_OPERATIONS)).into());
+            }
+            if operations.contains_key(&key) {
+                return Err(backup_error(ErrorCode::InvalidParams, format!("请求已在进行中: {}", key)).into());
+            }
+            operations.insert(key.clone(), Arc::clone(&cancelled));
+        }
+        log_info!("开始备份: {} {:?} → {}", request.name(), request.folders, dir.display());
+
+        let started = ServerResponse::new(ModuleType::Backup, "create_started", serde_json::json!({
+            "name": request.name(),
+            "folders": request.folders,
+            "dest": dir.display().to_string(),
+        }));
+        let progress = ProgressReporter::new(ModuleType::Backup, request_id.clone(), Some(sender.clone()));
+        let operations = Arc::clone(&self.operations);
+        let job = jobs::register(
+            Job::new(JobKind::Backup)
+                .with_request_id(request_id.clone())
+                .with_detail(request.name())
+                .with_cancel_flag(Arc::clone(&cancelled)),
+        );
+        tokio::spawn(async move {
+            let _job = job;
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let task = tokio::task::spawn_blocking(move || {
+                let report = |done: u64, total: u64| {
+                    let _ = tx.send((done, total));
+                };
+                create_snapshot(&dir, &request, &cancelled, &report)
+            });
+            let mut last_percent = None;
+            while let Some((done, total)) = rx.recv().await {
+                let percent = percent_between(0, 100, done as usize, total as usize);
+                // 小文件很多时每个百分点只报告一次
+                if last_percent != Some(percent) {
+                    last_percent = Some(percent);
+                    progress.report("archiving", Some(percent), Some(format!("{}/{}", done, total))).await;
+                }
+            }
+            let result = task.await;
+            operations.lock().unwrap_or_else(|e| e.into_inner()).remove(&key);
+
+            let event = match result {
+                Ok(Ok(payload)) => {
+                    log_info!("备份完成: {} ({} 个文件)", payload["id"], payload["files"]);
+                    ServerResponse::new(ModuleType::Backup, "created", payload)
+                }
+                Ok(Err(e)) => {
+                    log_info!("备份失败: {}", e);
+                    let error = ModuleError::from_error(ModuleType::Backup, &e);
+                    ServerResponse::error(ModuleType::Backup, error.code, &error.message)
+                }
+                Err(e) => ServerResponse::error(ModuleType::Backup, ErrorCode::Internal, &format!("备份任务失败: {}", e)),
+            };
+            let event = event.with_request_id(request_id.as_deref());
+            let _ = sender.lock().await.send(Message::Text(event.to_json().into())).await;
+        });
+        Ok(Some(started))
+    }
+
+    /// 处理 list 消息 - 列出快照 (新的在前)
+    async fn handle_list(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SnapshotRequest = parse(msg)?;
+        let dir = store_dir(request.dest.as_deref())?;
+        let snapshots = blocking(move || snapshot::list(&dir, request.name.as_deref())).await?;
+        Ok(Some(ServerResponse::new(ModuleType::Backup, "snapshots", serde_json::json!({ "snapshots": snapshots }))))
+    }
+
+    /// 处理 files 消息 - 列出快照中的文件
+    async fn handle_files(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SnapshotRequest = parse(msg)?;
+        let dir = store_dir(request.dest.as_deref())?;
+        let id = request.id()?;
+        let snapshot_id = id.clone();
+        let files = blocking(move || snapshot::files(&dir, &snapshot_id, request.prefix.as_deref())).await?;
+        Ok(Some(ServerResponse::new(ModuleType::Backup, "files", serde_json::json!({ "id": id, "files": files }))))
+    }
+
+    /// 处理 restore 消息 - 从快照恢复文件或目录
+    async fn handle_restore(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SnapshotRequest = parse(msg)?;
+        let dir = store_dir(request.dest.as_deref())?;
+        let id = request.id()?;
+        if request.paths.is_empty() {
+            return Err(backup_error(ErrorCode::InvalidParams, "paths 不能为空").into());
+        }
+        let snapshot_id = id.clone();
+        let result = blocking(move || {
+            let target = request.target.as_deref().map(Path::new);
+            snapshot::restore(&dir, &snapshot_id, &request.paths, target, request.overwrite)
+        }).await?;
+        for path in &result.restored {
+            crate::audit::record_file_write(ModuleType::Backup, Path::new(path));
+        }
+        log_info!("已从 {} 恢复 {} 个文件 (跳过 {} 个)", id, result.restored.len(), result.skipped.len());
+        let mut payload = to_value(&result);
+        payload["id"] = id.into();
+        Ok(Some(ServerResponse::new(ModuleType::Backup, "restored", payload)))
+    }
+
+    /// 处理 prune 消息 - 按保留策略删除旧快照
+    async fn handle_prune(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SnapshotRequest = parse(msg)?;
+        let dir = store_dir(request.dest.as_deref())?;
+        if request.retention.is_empty() {
+            return Err(backup_error(ErrorCode::InvalidParams, "keep_last 和 keep_days 至少指定一个").into());
+        }
+        let name = request.name.unwrap_or_else(|| DEFAULT_NAME.to_string());
+        snapshot::validate_name(&name).map_err(|e| ModuleError::from_error(ModuleType::Backup, &e))?;
+        let retention = request.retention;
+        let removed = blocking(move || snapshot::prune(&dir, &name, &retention)).await?;
+        if !removed.is_empty() {
+            log_info!("已删除 {} 个旧快照", removed.len());
+        }
+        Ok(Some(ServerResponse::new(ModuleType::Backup, "pruned", serde_json::json!({ "removed": removed }))))
+    }
+}
+
+impl Default for BackupHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn to_value(value: impl serde::Serialize) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+/// 备份模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("create", &[
+        FieldSpec::required("folders", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("name", FieldKind::String),
+        FieldSpec::optional("dest", FieldKind::String),
+        FieldSpec::optional("exclude", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("keep_last", FieldKind::Integer),
+        FieldSpec::optional("keep_days", FieldKind::Integer),
+    ]),
+    MessageSpec::new("list", &[
+        FieldSpec::optional("name", FieldKind::String),
+        FieldSpec::optional("dest", FieldKind::String),
+    ]),
+    MessageSpec::new("files", &[
+        FieldSpec::required("id", FieldKind::String),
+        FieldSpec::optional("prefix", FieldKind::String),
+        FieldSpec::optional("dest", FieldKind::String),
+    ]),
+    MessageSpec::new("restore", &[
+        FieldSpec::required("id", FieldKind::String),
+        FieldSpec::required("paths", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("target", FieldKind::String),
+        FieldSpec::optional("overwrite", FieldKind::Boolean),
+        FieldSpec::optional("dest", FieldKind::String),
+    ]),
+    MessageSpec::new("prune", &[
+        FieldSpec::optional("name", FieldKind::String),
+        FieldSpec::optional("keep_last", FieldKind::Integer),
+        FieldSpec::optional("keep_days", FieldKind::Integer),
+        FieldSpec::optional("dest", FieldKind::String),
+    ]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 备份模块
+    pub const Backup: ModuleType = ModuleType::named("backup");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for BackupHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Backup
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    /// 取消进行中的备份 (连接关闭时调用)
+    async fn cleanup(&self) {
+        let operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        for cancelled in operations.values() {
+            cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// 取消由 request_id 发起的备份
+    async fn cancel(&self, request_id: &str) -> bool {
+        let operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        match operations.get(request_id) {
+            Some(cancelled) if !cancelled.swap(true, Ordering::SeqCst) => {
+                log_info!("取消备份: request_id={}", request_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理备份消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "create" => self.handle_create(msg).await,
+            "list" => self.handle_list(msg).await,
+            "files" => self.handle_files(msg).await,
+            "restore" => self.handle_restore(msg).await,
+            "prune" => self.handle_prune(msg).await,
+            _ => Err(backup_error(ErrorCode::UnknownMessageType, format!("未知的备份消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
@@ -0,0 +1,588 @@
+// 快照文件
+// 每个快照是一个 zstd 压缩的 tar 文件 (`<名称>-<时间>.tar.zst`)：第一项为 manifest.json，记录快照信息和各目录的原始位置，
+// 之后是各目录下的文件，路径为 `<目录别名>/<相对路径>`。写入时先写临时文件，完成后再改名，
+// 中途失败或取消不会留下不完整的快照
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 快照文件扩展名
+pub const EXTENSION: &str = ".tar.zst";
+
+/// 写入中的快照的扩展名
+const PARTIAL_EXTENSION: &str = ".tar.zst.partial";
+
+/// 快照信息在归档中的文件名
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// 快照格式版本
+const FORMAT_VERSION: u32 = 1;
+
+/// zstd 压缩级别 (速度与压缩率的折中)
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// 快照名称的最大长度
+const MAX_NAME_LEN: usize = 64;
+
+/// 备份错误
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("文件读写失败: {0}")]
+    Io(#[from] io::Error),
+    #[error("目录不存在: {0}")]
+    FolderNotFound(String),
+    #[error("快照不存在: {0}")]
+    SnapshotNotFound(String),
+    #[error("无效的快照文件 {0}: {1}")]
+    Corrupt(String, String),
+    #[error("无效的快照名称: '{0}' (只能包含字母、数字、- 和 _，最多 64 个字符)")]
+    InvalidName(String),
+    #[error("备份已取消")]
+    Cancelled,
+}
+
+impl CodedError for BackupError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            BackupError::Io(e) if e.kind() == io::ErrorKind::NotFound => ErrorCode::NotFound,
+            BackupError::Io(_) | BackupError::Corrupt(..) => ErrorCode::IoError,
+            BackupError::FolderNotFound(_) | BackupError::SnapshotNotFound(_) => ErrorCode::NotFound,
+            BackupError::InvalidName(_) => ErrorCode::InvalidParams,
+            BackupError::Cancelled => ErrorCode::Cancelled,
+        }
+    }
+}
+
+/// 快照中的目录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Folder {
+    /// 归档中的顶层目录名
+    pub alias: String,
+    /// 备份时的绝对路径 (恢复到原位置时使用)
+    pub path: String,
+}
+
+/// 快照信息 (归档的第一项)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    name: String,
+    created_at: DateTime<Local>,
+    folders: Vec<Folder>,
+    files: u64,
+    bytes: u64,
+}
+
+/// 快照列表中的一项
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Local>,
+    pub folders: Vec<Folder>,
+    /// 文件数
+    pub files: u64,
+    /// 压缩前的总字节数
+    pub bytes: u64,
+    /// 快照文件大小
+    pub size: u64,
+}
+
+/// 快照中的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileEntry {
+    /// `<目录别名>/<相对路径>`
+    pub path: String,
+    pub size: u64,
+    /// 修改时间 (Unix 秒)
+    pub modified: u64,
+}
+
+/// 恢复结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RestoreResult {
+    /// 已恢复的文件 (写入的位置)
+    pub restored: Vec<String>,
+    /// 目标已存在而跳过的文件
+    pub skipped: Vec<String>,
+    /// 快照中没有的路径
+    pub missing: Vec<String>,
+}
+
+/// 保留策略 (同时设置时满足任一条件即保留；都未设置时不删除)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct Retention {
+    /// 保留最近的 N 个快照
+    #[serde(default)]
+    pub keep_last: Option<usize>,
+    /// 保留最近 N 天内的快照
+    #[serde(default)]
+    pub keep_days: Option<u32>,
+}
+
+impl Retention {
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none() && self.keep_days.is_none()
+    }
+}
+
+/// 检查快照名称
+pub fn validate_name(name: &str) -> Result<(), BackupError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(BackupError::InvalidName(name.to_string()))
+    }
+}
+
+// ============================================================================
+// 创建
+// ============================================================================
+
+/// 待备份的文件
+struct SourceFile {
+    /// 磁盘上的路径
+    path: PathBuf,
+    /// 归档中的路径
+    name: String,
+    size: u64,
+}
+
+/// 为各目录分配归档中的顶层目录名 (目录名重复时追加序号)
+fn assign_aliases(folders: &[PathBuf]) -> Vec<Folder> {
+    let mut used = BTreeSet::new();
+    folders
+        .iter()
+        .map(|path| {
+            let base: String = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "folder".to_string());
+            let mut alias = base.clone();
+            let mut index = 2;
+            while !used.insert(alias.clone()) {
+                alias = format!("{}-{}", base, index);
+                index += 1;
+            }
+            Folder { alias, path: path.display().to_string() }
+        })
+        .collect()
+}
+
+/// 递归列出目录下的文件 (不跟随符号链接，跳过名称在 exclude 中的文件和目录)
+fn collect_files(root: &Path, dir: &Path, alias: &str, exclude: &[String], files: &mut Vec<SourceFile>) -> io::Result<()> {
+    let mut entries: Vec<fs::DirEntry> = fs::read_dir(dir)?.collect::<io::Result<_>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry.file_name();
+        if exclude.iter().any(|excluded| name.to_string_lossy() == *excluded) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_files(root, &path, alias, exclude, files)?;
+        } else if file_type.is_file() {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let relative: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+            files.push(SourceFile {
+                name: format!("{}/{}", alias, relative.join("/")),
+                size: entry.metadata()?.len(),
+                path,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 新快照的 ID (同一秒内重复时追加序号)
+fn new_id(dir: &Path, name: &str, now: DateTime<Local>) -> String {
+    let base = format!("{}-{}", name, now.format("%Y%m%d-%H%M%S"));
+    let mut id = base.clone();
+    let mut index = 2;
+    while dir.join(format!("{}{}", id, EXTENSION)).exists() {
+        id = format!("{}-{}", base, index);
+        index += 1;
+    }
+    id
+}
+
+/// 创建快照
+///
+/// `report(done_bytes, total_bytes)` 在每个文件写入后调用；`cancelled` 置位后在下一个文件前停止
+pub fn create(
+    dir: &Path,
+    name: &str,
+    folders: &[PathBuf],
+    exclude: &[String],
+    cancelled: &AtomicBool,
+    report: &dyn Fn(u64, u64),
+) -> Result<SnapshotInfo, BackupError> {
+    validate_name(name)?;
+    for folder in folders {
+        if !folder.is_dir() {
+            return Err(BackupError::FolderNotFound(folder.display().to_string()));
+        }
+    }
+    let aliases = assign_aliases(folders);
+    let mut files = Vec::new();
+    for (folder, alias) in folders.iter().zip(&aliases) {
+        collect_files(folder, folder, &alias.alias, exclude, &mut files)?;
+    }
+
+    fs::create_dir_all(dir)?;
+    let now = Local::now();
+    let id = new_id(dir, name, now);
+    let manifest = Manifest {
+        version: FORMAT_VERSION,
+        name: name.to_string(),
+        created_at: now,
+        folders: aliases,
+        files: files.len() as u64,
+        bytes: files.iter().map(|file| file.size).sum(),
+    };
+    let partial = dir.join(format!("{}{}", id, PARTIAL_EXTENSION));
+    let result = write_archive(&partial, &manifest, &files, cancelled, report);
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    let path = dir.join(format!("{}{}", id, EXTENSION));
+    fs::rename(&partial, &path)?;
+    Ok(SnapshotInfo {
+        id,
+        name: manifest.name,
+        created_at: manifest.created_at,
+        folders: manifest.folders,
+        files: manifest.files,
+        bytes: manifest.bytes,
+        size: fs::metadata(&path)?.len(),
+    })
+}
+
+fn write_archive(
+    path: &Path,
+    manifest: &Manifest,
+    files: &[SourceFile],
+    cancelled: &AtomicBool,
+    report: &dyn Fn(u64, u64),
+) -> Result<(), BackupError> {
+    let encoder = zstd::Encoder::new(BufWriter::new(File::create(path)?), COMPRESSION_LEVEL)?;
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+
+    let manifest_json = serde_json::to_vec_pretty(manifest).map_err(io::Error::other)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(manifest.created_at.timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_NAME, manifest_json.as_slice())?;
+
+    let mut done = 0;
+    for file in files {
+        if cancelled.load(Ordering::SeqCst) {
+            return Err(BackupError::Cancelled);
+        }
+        match builder.append_path_with_name(&file.path, &file.name) {
+            Ok(()) => {}
+            // 扫描后被删除的文件直接跳过
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        done += file.size;
+        report(done, manifest.bytes);
+    }
+    let encoder = builder.into_inner()?;
+    let writer = encoder.finish()?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(())
+}
+
+// ============================================================================
+// 读取
+// ============================================================================
+
+type Archive = tar::Archive<zstd::Decoder<'static, BufReader<BufReader<File>>>>;
+
+fn snapshot_path(dir: &Path, id: &str) -> Result<PathBuf, BackupError> {
+    // ID 来自客户端，不能包含路径分隔符
+    if validate_name(id).is_err() {
+        return Err(BackupError::SnapshotNotFound(id.to_string()));
+    }
+    let path = dir.join(format!("{}{}", id, EXTENSION));
+    if !path.is_file() {
+        return Err(BackupError::SnapshotNotFound(id.to_string()));
+    }
+    Ok(path)
+}
+
+fn open_archive(path: &Path) -> Result<Archive, BackupError> {
+    let decoder = zstd::Decoder::new(BufReader::new(File::open(path)?))?;
+    Ok(tar::Archive::new(decoder))
+}
+
+/// 读取归档的第一项 (快照信息)
+fn read_manifest(archive: &mut Archive, path: &Path) -> Result<Manifest, BackupError> {
+    let corrupt = |reason: String| BackupError::Corrupt(path.display().to_string(), reason);
+    let mut entries = archive.entries()?;
+    let entry = entries.next().ok_or_else(|| corrupt("空归档".to_string()))??;
+    if entry.path()?.as_os_str() != MANIFEST_NAME {
+        return Err(corrupt("缺少 manifest.json".to_string()));
+    }
+    let manifest: Manifest = serde_json::from_reader(entry).map_err(|e| corrupt(e.to_string()))?;
+    if manifest.version != FORMAT_VERSION {
+        return Err(corrupt(format!("不支持的版本 {}", manifest.version)));
+    }
+    Ok(manifest)
+}
+
+/// 列出快照 (新的在前)，`name` 指定时只列出该名称的快照；无法读取的文件跳过
+pub fn list(dir: &Path, name: Option<&str>) -> Result<Vec<SnapshotInfo>, BackupError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some(id) = path.file_name().and_then(|name| name.to_str()).and_then(|name| name.strip_suffix(EXTENSION)) else {
+            continue;
+        };
+        let Ok(manifest) = open_archive(&path).and_then(|mut archive| read_manifest(&mut archive, &path)) else {
+            continue;
+        };
+        if name.is_some_and(|name| name != manifest.name) {
+            continue;
+        }
+        snapshots.push(SnapshotInfo {
+            id: id.to_string(),
+            name: manifest.name,
+            created_at: manifest.created_at,
+            folders: manifest.folders,
+            files: manifest.files,
+            bytes: manifest.bytes,
+            size: fs::metadata(&path)?.len(),
+        });
+    }
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+    Ok(snapshots)
+}
+
+/// 列出快照中的文件 (`prefix` 指定时只列出该路径下的文件)
+pub fn files(dir: &Path, id: &str, prefix: Option<&str>) -> Result<Vec<FileEntry>, BackupError> {
+    let path = snapshot_path(dir, id)?;
+    let mut archive = open_archive(&path)?;
+    let mut files = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        if name == MANIFEST_NAME || prefix.is_some_and(|prefix| !matches_path(&name, prefix)) {
+            continue;
+        }
+        files.push(FileEntry {
+            path: name,
+            size: entry.header().size()?,
+            modified: entry.header().mtime()?,
+        });
+    }
+    Ok(files)
+}
+
+/// 归档中的路径是否为请求的文件或位于请求的目录下
+fn matches_path(name: &str, requested: &str) -> bool {
+    let requested = requested.trim_end_matches('/');
+    name == requested || name.strip_prefix(requested).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// 归档中的路径 `<别名>/<相对路径>` 拆分后的相对路径 (只允许普通路径组成部分)
+fn split_name(name: &str) -> Option<(&str, PathBuf)> {
+    let (alias, relative) = name.split_once('/')?;
+    let relative = PathBuf::from(relative);
+    let normal = relative.components().all(|component| matches!(component, Component::Normal(_)));
+    (normal && !relative.as_os_str().is_empty()).then_some((alias, relative))
+}
+
+/// 从快照恢复文件或目录
+///
+/// 指定 `target` 时恢复到 `<target>/<别名>/<相对路径>`，否则恢复到备份时的原位置；目标已存在且不覆盖时跳过
+pub fn restore(dir: &Path, id: &str, requested: &[String], target: Option<&Path>, overwrite: bool) -> Result<RestoreResult, BackupError> {
+    let path = snapshot_path(dir, id)?;
+    let mut archive = open_archive(&path)?;
+    let manifest = read_manifest(&mut archive, &path)?;
+    // 读取 manifest 后重新打开，从头遍历文件
+    let mut archive = open_archive(&path)?;
+
+    let mut result = RestoreResult::default();
+    let mut found = vec![false; requested.len()];
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut matched = false;
+        for (index, requested) in requested.iter().enumerate() {
+            if matches_path(&name, requested) {
+                found[index] = true;
+                matched = true;
+            }
+        }
+        if !matched || name == MANIFEST_NAME {
+            continue;
+        }
+        let Some((alias, relative)) = split_name(&name) else {
+            continue;
+        };
+        let destination = match target {
+            Some(target) => target.join(alias).join(&relative),
+            None => match manifest.folders.iter().find(|folder| folder.alias == alias) {
+                Some(folder) => Path::new(&folder.path).join(&relative),
+                None => continue,
+            },
+        };
+        if destination.exists() && !overwrite {
+            result.skipped.push(destination.display().to_string());
+            continue;
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&destination)?;
+        result.restored.push(destination.display().to_string());
+    }
+    result.missing = requested
+        .iter()
+        .zip(found)
+        .filter(|(_, found)| !found)
+        .map(|(requested, _)| requested.clone())
+        .collect();
+    Ok(result)
+}
+
+// ============================================================================
+// 保留策略
+// ============================================================================
+
+/// 按保留策略选出要删除的快照 (`snapshots` 新的在前；最新的快照总是保留)
+pub fn expired<'a>(snapshots: &'a [SnapshotInfo], retention: &Retention, now: DateTime<Local>) -> Vec<&'a SnapshotInfo> {
+    if retention.is_empty() {
+        return Vec::new();
+    }
+    snapshots
+        .iter()
+        .enumerate()
+        .filter(|(index, snapshot)| {
+            let keep_last = retention.keep_last.is_some_and(|keep| *index < keep.max(1));
+            let keep_recent = retention
+                .keep_days
+                .is_some_and(|days| now - snapshot.created_at < chrono::Duration::days(days as i64));
+            *index > 0 && !keep_last && !keep_recent
+        })
+        .map(|(_, snapshot)| snapshot)
+        .collect()
+}
+
+/// 删除名称为 `name` 的快照中按保留策略过期的快照，返回删除的快照 ID
+pub fn prune(dir: &Path, name: &str, retention: &Retention) -> Result<Vec<String>, BackupError> {
+    let snapshots = list(dir, Some(name))?;
+    let mut removed = Vec::new();
+    for snapshot in expired(&snapshots, retention, Local::now()) {
+        fs::remove_file(dir.join(format!("{}{}", snapshot.id, EXTENSION)))?;
+        removed.push(snapshot.id.clone());
+    }
+    Ok(removed)
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("backup-snapshot-{}-{}", label, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_create_and_restore() {
+        let root = temp_dir("roundtrip");
+        let vault = root.join("vault");
+        fs::create_dir_all(vault.join("notes/daily")).unwrap();
+        fs::create_dir_all(vault.join(".git")).unwrap();
+        fs::write(vault.join("notes/a.md"), "# A").unwrap();
+        fs::write(vault.join("notes/daily/2026-10-16.md"), "今天").unwrap();
+        fs::write(vault.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+        let backups = root.join("backups");
+
+        let reported = std::sync::Mutex::new(Vec::new());
+        let info = create(&backups, "vault", std::slice::from_ref(&vault), &[".git".to_string()], &AtomicBool::new(false), &|done, total| {
+            reported.lock().unwrap().push((done, total));
+        }).unwrap();
+        assert_eq!(info.files, 2);
+        assert_eq!(info.folders, vec![Folder { alias: "vault".to_string(), path: vault.display().to_string() }]);
+        assert_eq!(reported.lock().unwrap().last(), Some(&(info.bytes, info.bytes)));
+
+        let listed: Vec<String> = files(&backups, &info.id, None).unwrap().into_iter().map(|file| file.path).collect();
+        assert_eq!(listed, vec!["vault/notes/a.md", "vault/notes/daily/2026-10-16.md"]);
+        assert_eq!(list(&backups, None).unwrap(), vec![info.clone()]);
+
+        // 恢复到原位置：已存在的文件默认跳过
+        fs::write(vault.join("notes/a.md"), "changed").unwrap();
+        fs::remove_file(vault.join("notes/daily/2026-10-16.md")).unwrap();
+        let requested = vec!["vault/notes/daily/".to_string(), "vault/notes/a.md".to_string(), "vault/missing.md".to_string()];
+        let result = restore(&backups, &info.id, &requested, None, false).unwrap();
+        assert_eq!(result.restored, vec![vault.join("notes/daily/2026-10-16.md").display().to_string()]);
+        assert_eq!(result.skipped, vec![vault.join("notes/a.md").display().to_string()]);
+        assert_eq!(result.missing, vec!["vault/missing.md"]);
+        assert_eq!(fs::read_to_string(vault.join("notes/daily/2026-10-16.md")).unwrap(), "今天");
+        restore(&backups, &info.id, &["vault/notes/a.md".to_string()], None, true).unwrap();
+        assert_eq!(fs::read_to_string(vault.join("notes/a.md")).unwrap(), "# A");
+
+        // 取消后不留下文件
+        let cancelled = create(&backups, "vault", std::slice::from_ref(&vault), &[], &AtomicBool::new(true), &|_, _| {});
+        assert!(matches!(cancelled, Err(BackupError::Cancelled)));
+        assert_eq!(fs::read_dir(&backups).unwrap().count(), 1);
+
+        assert!(matches!(restore(&backups, "../vault", &[], None, false), Err(BackupError::SnapshotNotFound(_))));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_expired() {
+        let now = Local::now();
+        let snapshot = |id: &str, days_ago: i64| SnapshotInfo {
+            id: id.to_string(),
+            name: "vault".to_string(),
+            created_at: now - chrono::Duration::days(days_ago),
+            folders: Vec::new(),
+            files: 0,
+            bytes: 0,
+            size: 0,
+        };
+        let snapshots = vec![snapshot("d0", 0), snapshot("d1", 1), snapshot("d5", 5), snapshot("d30", 30)];
+        let ids = |retention: Retention| -> Vec<String> {
+            expired(&snapshots, &retention, now).into_iter().map(|s| s.id.clone()).collect()
+        };
+        assert!(ids(Retention::default()).is_empty());
+        assert_eq!(ids(Retention { keep_last: Some(2), keep_days: None }), vec!["d5", "d30"]);
+        assert_eq!(ids(Retention { keep_last: None, keep_days: Some(7) }), vec!["d30"]);
+        assert_eq!(ids(Retention { keep_last: Some(1), keep_days: Some(2) }), vec!["d5", "d30"]);
+        // 最新的快照总是保留
+        assert_eq!(ids(Retention { keep_last: Some(0), keep_days: None }), vec!["d1", "d5", "d30"]);
+    }
+}
@@ -0,0 +1,106 @@
+// 屏幕截图后端
+// Linux 上通过 X11 直接读取屏幕 (Wayland 桌面上只能截取 XWayland 窗口)，Windows 和 macOS 使用 xcap。
+// 截图以 RGBA 像素返回，区域裁剪和 PNG 编码由模块统一处理
+
+use image::RgbaImage;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 截图错误
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    #[error("截图不可用: {0}")]
+    Unavailable(String),
+
+    #[error("显示器不存在: {0}")]
+    MonitorNotFound(u32),
+
+    #[error("窗口不存在: {0}")]
+    WindowNotFound(u32),
+
+    #[error("截图失败: {0}")]
+    Failed(String),
+}
+
+impl CodedError for CaptureError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            CaptureError::Unavailable(_) | CaptureError::Failed(_) => ErrorCode::DeviceError,
+            CaptureError::MonitorNotFound(_) | CaptureError::WindowNotFound(_) => ErrorCode::NotFound,
+        }
+    }
+}
+
+/// 显示器 (坐标为虚拟桌面坐标)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MonitorInfo {
+    pub id: u32,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub primary: bool,
+}
+
+impl MonitorInfo {
+    /// 点是否在显示器内
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && y >= self.y
+            && i64::from(x) < i64::from(self.x) + i64::from(self.width)
+            && i64::from(y) < i64::from(self.y) + i64::from(self.height)
+    }
+}
+
+/// 顶层窗口
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WindowInfo {
+    pub id: u32,
+    pub title: String,
+    pub app_name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 截图后端
+pub trait CaptureBackend: Send {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, CaptureError>;
+    fn windows(&self) -> Result<Vec<WindowInfo>, CaptureError>;
+    /// 截取整个显示器 (HiDPI 显示器上图片尺寸可能大于显示器的逻辑尺寸)
+    fn capture_monitor(&self, id: u32) -> Result<RgbaImage, CaptureError>;
+    fn capture_window(&self, id: u32) -> Result<RgbaImage, CaptureError>;
+}
+
+/// 打开系统截图后端
+#[cfg(not(any(test, feature = "test-support")))]
+pub fn open() -> Result<Box<dyn CaptureBackend>, CaptureError> {
+    open_system()
+}
+
+/// 打开截图后端 (安装了模拟屏幕时使用模拟屏幕)
+#[cfg(any(test, feature = "test-support"))]
+pub fn open() -> Result<Box<dyn CaptureBackend>, CaptureError> {
+    if crate::testing::capture::is_installed() {
+        return Ok(Box::new(crate::testing::capture::FakeScreen));
+    }
+    open_system()
+}
+
+#[cfg(target_os = "linux")]
+fn open_system() -> Result<Box<dyn CaptureBackend>, CaptureError> {
+    Ok(Box::new(super::x11::X11Capture::connect()?))
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn open_system() -> Result<Box<dyn CaptureBackend>, CaptureError> {
+    Ok(Box::new(super::native::NativeCapture))
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+fn open_system() -> Result<Box<dyn CaptureBackend>, CaptureError> {
+    Err(CaptureError::Unavailable("当前平台不支持".to_string()))
+}
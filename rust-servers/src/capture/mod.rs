@@ -0,0 +1,350 @@
+// 截图模块
+// 截取整个显示器、虚拟桌面中的矩形区域或单个窗口，以 base64 编码的 PNG 返回或保存到指定路径，
+// 供 OCR 和视觉 LLM 的 "截取眼前内容" 工作流使用
+
+mod backend;
+#[cfg(any(windows, target_os = "macos"))]
+mod native;
+#[cfg(target_os = "linux")]
+mod x11;
+
+pub use backend::{CaptureBackend, CaptureError, MonitorInfo, WindowInfo};
+
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use base64::{Engine as _, engine::general_purpose};
+use image::RgbaImage;
+use serde::Deserialize;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Capture", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Capture", format!($($arg)*));
+        }
+    };
+}
+
+/// 创建截图模块错误
+fn capture_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Capture, code, message)
+}
+
+// ============================================================================
+// 区域裁剪
+// ============================================================================
+
+/// 虚拟桌面中的矩形区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Region {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+/// 将区域裁剪到显示器内，并换算为该显示器截图中的像素矩形 (x, y, width, height)
+///
+/// 截图尺寸与显示器尺寸不同 (HiDPI 缩放) 时按比例换算，区域与显示器不相交时返回 None
+fn region_in_image(monitor: &MonitorInfo, image_size: (u32, u32), region: Region) -> Option<(u32, u32, u32, u32)> {
+    let left = i64::from(region.x).max(i64::from(monitor.x));
+    let top = i64::from(region.y).max(i64::from(monitor.y));
+    let right = (i64::from(region.x) + i64::from(region.width)).min(i64::from(monitor.x) + i64::from(monitor.width));
+    let bottom = (i64::from(region.y) + i64::from(region.height)).min(i64::from(monitor.y) + i64::from(monitor.height));
+    if right <= left || bottom <= top || monitor.width == 0 || monitor.height == 0 {
+        return None;
+    }
+    let scale_x = f64::from(image_size.0) / f64::from(monitor.width);
+    let scale_y = f64::from(image_size.1) / f64::from(monitor.height);
+    let to_pixels = |value: i64, scale: f64, limit: u32| ((value as f64 * scale).round() as u32).min(limit);
+    let x = to_pixels(left - i64::from(monitor.x), scale_x, image_size.0);
+    let y = to_pixels(top - i64::from(monitor.y), scale_y, image_size.1);
+    let width = to_pixels(right - i64::from(monitor.x), scale_x, image_size.0) - x;
+    let height = to_pixels(bottom - i64::from(monitor.y), scale_y, image_size.1) - y;
+    (width > 0 && height > 0).then_some((x, y, width, height))
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// capture 请求
+#[derive(Debug, Deserialize)]
+struct CaptureRequest {
+    /// screen / region / window，省略时为 screen
+    #[serde(default)]
+    target: Option<String>,
+    /// screen: 显示器 id，省略时为主显示器
+    #[serde(default)]
+    monitor: Option<u32>,
+    /// region: 虚拟桌面坐标
+    #[serde(default)]
+    x: Option<i32>,
+    #[serde(default)]
+    y: Option<i32>,
+    #[serde(default)]
+    width: Option<u32>,
+    #[serde(default)]
+    height: Option<u32>,
+    /// window: 窗口 id (list_windows 返回)
+    #[serde(default)]
+    window_id: Option<u32>,
+    /// 保存 PNG 的路径，省略时在响应中返回 PNG 数据
+    #[serde(default)]
+    path: Option<String>,
+}
+
+/// 截取的目标
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    Screen(Option<u32>),
+    Region(Region),
+    Window(u32),
+}
+
+impl CaptureRequest {
+    fn target(&self) -> Result<Target, ModuleError> {
+        match self.target.as_deref().unwrap_or("screen") {
+            "screen" => Ok(Target::Screen(self.monitor)),
+            "region" => match (self.x, self.y, self.width, self.height) {
+                (Some(x), Some(y), Some(width), Some(height)) if width > 0 && height > 0 => {
+                    Ok(Target::Region(Region { x, y, width, height }))
+                }
+                _ => Err(capture_error(ErrorCode::InvalidParams, "region 需要 x、y 和正的 width、height")),
+            },
+            "window" => self.window_id.map(Target::Window)
+                .ok_or_else(|| capture_error(ErrorCode::InvalidParams, "window 需要 window_id")),
+            other => Err(capture_error(ErrorCode::InvalidParams, format!("未知的截图目标: {}", other))),
+        }
+    }
+}
+
+// ============================================================================
+// 截图处理器
+// ============================================================================
+
+/// 截图后端 (首次使用时打开，打开失败时下次重试)
+struct CaptureState {
+    backend: Mutex<Option<Box<dyn CaptureBackend>>>,
+}
+
+impl CaptureState {
+    fn with_backend<T>(&self, f: impl FnOnce(&dyn CaptureBackend) -> Result<T, CaptureError>) -> Result<T, CaptureError> {
+        let mut backend = self.backend.lock().unwrap_or_else(|e| e.into_inner());
+        if backend.is_none() {
+            *backend = Some(backend::open()?);
+        }
+        f(backend.as_deref().expect("capture backend opened"))
+    }
+
+    /// 截取目标，返回图片
+    fn capture(&self, target: Target) -> Result<RgbaImage, ModuleError> {
+        let from_error = |e: CaptureError| ModuleError::from_error(ModuleType::Capture, &e);
+        let outside = || capture_error(ErrorCode::InvalidParams, "区域不在任何显示器内");
+        match target {
+            Target::Window(id) => self.with_backend(|backend| backend.capture_window(id)).map_err(from_error),
+            Target::Screen(id) => self.with_backend(|backend| {
+                let monitors = backend.monitors()?;
+                let monitor = match id {
+                    Some(id) => monitors.iter().find(|monitor| monitor.id == id)
+                        .ok_or(CaptureError::MonitorNotFound(id))?,
+                    None => monitors.iter().find(|monitor| monitor.primary).or(monitors.first())
+                        .ok_or_else(|| CaptureError::Unavailable("没有可用的显示器".to_string()))?,
+                };
+                backend.capture_monitor(monitor.id)
+            }).map_err(from_error),
+            // 区域按左上角所在的显示器截取
+            Target::Region(region) => {
+                let (monitor, image) = self.with_backend(|backend| {
+                    let Some(monitor) = backend.monitors()?.into_iter().find(|monitor| monitor.contains(region.x, region.y)) else {
+                        return Ok(None);
+                    };
+                    let image = backend.capture_monitor(monitor.id)?;
+                    Ok(Some((monitor, image)))
+                }).map_err(from_error)?.ok_or_else(outside)?;
+                let (x, y, width, height) = region_in_image(&monitor, image.dimensions(), region).ok_or_else(outside)?;
+                Ok(image::imageops::crop_imm(&image, x, y, width, height).to_image())
+            }
+        }
+    }
+}
+
+/// 在阻塞线程中访问截图后端
+async fn blocking<T: Send + 'static>(
+    state: &Arc<CaptureState>,
+    f: impl FnOnce(&CaptureState) -> Result<T, ModuleError> + Send + 'static,
+) -> Result<T, RouterError> {
+    let state = Arc::clone(state);
+    Ok(tokio::task::spawn_blocking(move || f(&state))
+        .await
+        .map_err(|e| capture_error(ErrorCode::Internal, format!("截图任务失败: {}", e)))??)
+}
+
+/// 编码为 PNG
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, ModuleError> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| capture_error(ErrorCode::Internal, format!("PNG 编码失败: {}", e)))?;
+    Ok(png)
+}
+
+/// 截图模块处理器
+pub struct CaptureHandler {
+    state: Arc<CaptureState>,
+}
+
+impl CaptureHandler {
+    /// 创建新的截图处理器
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(CaptureState { backend: Mutex::new(None) }),
+        }
+    }
+
+    /// 处理 list_monitors 消息 - 列出显示器
+    async fn handle_list_monitors(&self) -> Result<Option<ServerResponse>, RouterError> {
+        let monitors = blocking(&self.state, |state| {
+            state.with_backend(|backend| backend.monitors())
+                .map_err(|e| ModuleError::from_error(ModuleType::Capture, &e))
+        }).await?;
+        Ok(Some(ServerResponse::new(ModuleType::Capture, "monitors", serde_json::json!({ "monitors": monitors }))))
+    }
+
+    /// 处理 list_windows 消息 - 列出可截取的顶层窗口
+    async fn handle_list_windows(&self) -> Result<Option<ServerResponse>, RouterError> {
+        let windows = blocking(&self.state, |state| {
+            state.with_backend(|backend| backend.windows())
+                .map_err(|e| ModuleError::from_error(ModuleType::Capture, &e))
+        }).await?;
+        Ok(Some(ServerResponse::new(ModuleType::Capture, "windows", serde_json::json!({ "windows": windows }))))
+    }
+
+    /// 处理 capture 消息 - 截图并返回 PNG 数据或保存路径
+    async fn handle_capture(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: CaptureRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| capture_error(ErrorCode::InvalidParams, format!("无效的 capture 请求: {}", e)))?;
+        let target = request.target()?;
+        let path = request.path.map(PathBuf::from);
+        if let Some(parent) = path.as_ref().and_then(|path| path.parent()) {
+            if !parent.as_os_str().is_empty() && !parent.is_dir() {
+                return Err(capture_error(ErrorCode::NotFound, format!("目录不存在: {}", parent.display())).into());
+            }
+        }
+
+        let save_path = path.clone();
+        let (width, height, data) = blocking(&self.state, move |state| {
+            let image = state.capture(target)?;
+            let png = encode_png(&image)?;
+            let data = match &save_path {
+                Some(path) => {
+                    std::fs::write(path, &png)
+                        .map_err(|e| capture_error(ErrorCode::IoError, format!("无法保存截图: {}", e)))?;
+                    None
+                }
+                None => Some(general_purpose::STANDARD.encode(&png)),
+            };
+            Ok((image.width(), image.height(), data))
+        }).await?;
+
+        let mut payload = serde_json::json!({
+            "target": request.target.as_deref().unwrap_or("screen"),
+            "width": width,
+            "height": height,
+        });
+        match (&path, data) {
+            (Some(path), _) => {
+                crate::audit::record_file_write(ModuleType::Capture, path);
+                payload["path"] = path.display().to_string().into();
+                log_info!("截图已保存: {:?} {}x{} → {}", target, width, height, path.display());
+            }
+            (None, data) => {
+                payload["data"] = data.into();
+                log_debug!("截图: {:?} {}x{}", target, width, height);
+            }
+        }
+        Ok(Some(ServerResponse::new(ModuleType::Capture, "captured", payload)))
+    }
+}
+
+impl Default for CaptureHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 截图模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("list_monitors", &[]),
+    MessageSpec::new("list_windows", &[]),
+    MessageSpec::new("capture", &[
+        FieldSpec::optional("target", FieldKind::String).one_of(&["screen", "region", "window"]),
+        FieldSpec::optional("monitor", FieldKind::Integer),
+        FieldSpec::optional("x", FieldKind::Integer),
+        FieldSpec::optional("y", FieldKind::Integer),
+        FieldSpec::optional("width", FieldKind::Integer),
+        FieldSpec::optional("height", FieldKind::Integer),
+        FieldSpec::optional("window_id", FieldKind::Integer),
+        FieldSpec::optional("path", FieldKind::String),
+    ]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 截图模块
+    pub const Capture: ModuleType = ModuleType::named("capture");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for CaptureHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Capture
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理截图消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "list_monitors" => self.handle_list_monitors().await,
+            "list_windows" => self.handle_list_windows().await,
+            "capture" => self.handle_capture(msg).await,
+            _ => Err(capture_error(ErrorCode::UnknownMessageType, format!("未知的截图消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_in_image() {
+        let monitor = MonitorInfo { id: 1, name: String::new(), x: 100, y: 0, width: 200, height: 100, primary: true };
+        let region = |x, y, width, height| Region { x, y, width, height };
+
+        assert_eq!(region_in_image(&monitor, (200, 100), region(110, 10, 50, 20)), Some((10, 10, 50, 20)));
+        // 超出显示器的部分被裁掉
+        assert_eq!(region_in_image(&monitor, (200, 100), region(250, 80, 100, 100)), Some((150, 80, 50, 20)));
+        assert_eq!(region_in_image(&monitor, (200, 100), region(0, 0, 50, 50)), None);
+        // HiDPI: 截图为显示器尺寸的两倍
+        assert_eq!(region_in_image(&monitor, (400, 200), region(110, 10, 50, 20)), Some((20, 20, 100, 40)));
+    }
+}
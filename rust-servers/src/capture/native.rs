@@ -0,0 +1,66 @@
+// Windows / macOS 截图 (xcap)
+
+use image::RgbaImage;
+
+use super::backend::{CaptureBackend, CaptureError, MonitorInfo, WindowInfo};
+
+fn failed(error: xcap::XCapError) -> CaptureError {
+    CaptureError::Failed(error.to_string())
+}
+
+/// xcap 截图后端 (显示器和窗口每次调用时重新枚举)
+pub struct NativeCapture;
+
+fn monitor_info(monitor: &xcap::Monitor) -> Result<MonitorInfo, xcap::XCapError> {
+    Ok(MonitorInfo {
+        id: monitor.id()?,
+        name: monitor.name()?,
+        x: monitor.x()?,
+        y: monitor.y()?,
+        width: monitor.width()?,
+        height: monitor.height()?,
+        primary: monitor.is_primary()?,
+    })
+}
+
+fn window_info(window: &xcap::Window) -> Result<WindowInfo, xcap::XCapError> {
+    Ok(WindowInfo {
+        id: window.id()?,
+        title: window.title()?,
+        app_name: window.app_name()?,
+        x: window.x()?,
+        y: window.y()?,
+        width: window.width()?,
+        height: window.height()?,
+    })
+}
+
+impl CaptureBackend for NativeCapture {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, CaptureError> {
+        let monitors = xcap::Monitor::all().map_err(failed)?;
+        monitors.iter().map(|monitor| monitor_info(monitor).map_err(failed)).collect()
+    }
+
+    fn windows(&self) -> Result<Vec<WindowInfo>, CaptureError> {
+        let windows = xcap::Window::all().map_err(failed)?;
+        // 最小化的窗口无法截取，不列出
+        Ok(windows.iter()
+            .filter(|window| !window.is_minimized().unwrap_or(true))
+            .filter_map(|window| window_info(window).ok())
+            .collect())
+    }
+
+    fn capture_monitor(&self, id: u32) -> Result<RgbaImage, CaptureError> {
+        let monitors = xcap::Monitor::all().map_err(failed)?;
+        let monitor = monitors.iter().find(|monitor| monitor.id().ok() == Some(id))
+            .ok_or(CaptureError::MonitorNotFound(id))?;
+        monitor.capture_image().map_err(failed)
+    }
+
+    fn capture_window(&self, id: u32) -> Result<RgbaImage, CaptureError> {
+        let windows = xcap::Window::all().map_err(failed)?;
+        let window = windows.iter().find(|window| window.id().ok() == Some(id))
+            .ok_or(CaptureError::WindowNotFound(id))?;
+        window.capture_image().map_err(failed)
+    }
+}
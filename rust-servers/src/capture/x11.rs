@@ -0,0 +1,152 @@
+// X11 截图
+// 显示器来自 RandR，窗口来自 EWMH 的 _NET_CLIENT_LIST，像素通过 GetImage (ZPixmap) 读取
+
+use image::RgbaImage;
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt as _, Drawable, ImageFormat, ImageOrder, Window};
+use x11rb::rust_connection::RustConnection;
+
+use super::backend::{CaptureBackend, CaptureError, MonitorInfo, WindowInfo};
+
+fn failed(error: impl std::fmt::Display) -> CaptureError {
+    CaptureError::Failed(error.to_string())
+}
+
+/// 窗口属性使用的原子
+struct Atoms {
+    net_client_list: Atom,
+    net_wm_name: Atom,
+    utf8_string: Atom,
+}
+
+/// X11 截图后端
+pub struct X11Capture {
+    conn: RustConnection,
+    root: Window,
+    atoms: Atoms,
+}
+
+impl X11Capture {
+    /// 连接 X 服务器 (DISPLAY 指定的显示)
+    pub fn connect() -> Result<Self, CaptureError> {
+        let (conn, screen) = RustConnection::connect(None)
+            .map_err(|e| CaptureError::Unavailable(format!("无法连接 X11 显示: {}", e)))?;
+        let root = conn.setup().roots[screen].root;
+        let intern = |name: &[u8]| -> Result<Atom, CaptureError> {
+            Ok(conn.intern_atom(false, name).map_err(failed)?.reply().map_err(failed)?.atom)
+        };
+        let atoms = Atoms {
+            net_client_list: intern(b"_NET_CLIENT_LIST")?,
+            net_wm_name: intern(b"_NET_WM_NAME")?,
+            utf8_string: intern(b"UTF8_STRING")?,
+        };
+        Ok(Self { conn, root, atoms })
+    }
+
+    /// 读取字符串属性 (窗口已关闭或没有该属性时返回 None)
+    fn text_property(&self, window: Window, property: impl Into<Atom>, kind: impl Into<Atom>) -> Option<Vec<u8>> {
+        let reply = self.conn.get_property(false, window, property, kind, 0, 4096).ok()?.reply().ok()?;
+        (!reply.value.is_empty()).then_some(reply.value)
+    }
+
+    fn window_info(&self, window: Window) -> Option<WindowInfo> {
+        let geometry = self.conn.get_geometry(window).ok()?.reply().ok()?;
+        let position = self.conn.translate_coordinates(window, self.root, 0, 0).ok()?.reply().ok()?;
+        let title = self.text_property(window, self.atoms.net_wm_name, self.atoms.utf8_string)
+            .or_else(|| self.text_property(window, AtomEnum::WM_NAME, AtomEnum::STRING))
+            .map(|title| String::from_utf8_lossy(&title).into_owned())
+            .unwrap_or_default();
+        // WM_CLASS 为 "实例名\0类名\0"，取类名
+        let app_name = self.text_property(window, AtomEnum::WM_CLASS, AtomEnum::STRING)
+            .map(|class| {
+                let parts: Vec<&[u8]> = class.split(|b| *b == 0).filter(|part| !part.is_empty()).collect();
+                String::from_utf8_lossy(parts.last().copied().unwrap_or_default()).into_owned()
+            })
+            .unwrap_or_default();
+        Some(WindowInfo {
+            id: window,
+            title,
+            app_name,
+            x: i32::from(position.dst_x),
+            y: i32::from(position.dst_y),
+            width: u32::from(geometry.width),
+            height: u32::from(geometry.height),
+        })
+    }
+
+    /// 读取可绘制对象中的矩形区域
+    fn get_image(&self, drawable: Drawable, x: i16, y: i16, width: u16, height: u16) -> Result<RgbaImage, CaptureError> {
+        let reply = self.conn.get_image(ImageFormat::Z_PIXMAP, drawable, x, y, width, height, !0)
+            .map_err(failed)?
+            .reply()
+            .map_err(failed)?;
+        let setup = self.conn.setup();
+        let format = setup.pixmap_formats.iter().find(|format| format.depth == reply.depth);
+        if format.map(|format| format.bits_per_pixel) != Some(32) {
+            return Err(CaptureError::Unavailable(format!("不支持的像素格式 (色深 {})", reply.depth)));
+        }
+        let pixel_count = usize::from(width) * usize::from(height);
+        if reply.data.len() < pixel_count * 4 {
+            return Err(CaptureError::Failed("像素数据不完整".to_string()));
+        }
+        let lsb_first = setup.image_byte_order == ImageOrder::LSB_FIRST;
+        let mut rgba = Vec::with_capacity(pixel_count * 4);
+        for pixel in reply.data[..pixel_count * 4].chunks_exact(4) {
+            let (r, g, b) = if lsb_first { (pixel[2], pixel[1], pixel[0]) } else { (pixel[1], pixel[2], pixel[3]) };
+            rgba.extend_from_slice(&[r, g, b, 255]);
+        }
+        RgbaImage::from_raw(u32::from(width), u32::from(height), rgba)
+            .ok_or_else(|| CaptureError::Failed("像素数据不完整".to_string()))
+    }
+}
+
+impl CaptureBackend for X11Capture {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, CaptureError> {
+        let reply = self.conn.randr_get_monitors(self.root, true).map_err(failed)?.reply().map_err(failed)?;
+        Ok(reply.monitors.iter().map(|monitor| {
+            let name = self.conn.get_atom_name(monitor.name).ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .map(|reply| String::from_utf8_lossy(&reply.name).into_owned())
+                .unwrap_or_default();
+            MonitorInfo {
+                id: monitor.name,
+                name,
+                x: i32::from(monitor.x),
+                y: i32::from(monitor.y),
+                width: u32::from(monitor.width),
+                height: u32::from(monitor.height),
+                primary: monitor.primary,
+            }
+        }).collect())
+    }
+
+    fn windows(&self) -> Result<Vec<WindowInfo>, CaptureError> {
+        let reply = self.conn.get_property(false, self.root, self.atoms.net_client_list, AtomEnum::WINDOW, 0, u32::MAX)
+            .map_err(failed)?
+            .reply()
+            .map_err(failed)?;
+        let Some(windows) = reply.value32() else {
+            return Ok(Vec::new());
+        };
+        // 列出期间关闭的窗口直接跳过
+        Ok(windows.filter_map(|window| self.window_info(window)).collect())
+    }
+
+    fn capture_monitor(&self, id: u32) -> Result<RgbaImage, CaptureError> {
+        let monitor = self.monitors()?.into_iter().find(|monitor| monitor.id == id)
+            .ok_or(CaptureError::MonitorNotFound(id))?;
+        self.get_image(self.root, monitor.x as i16, monitor.y as i16, monitor.width as u16, monitor.height as u16)
+    }
+
+    fn capture_window(&self, id: u32) -> Result<RgbaImage, CaptureError> {
+        let window = self.windows()?.into_iter().find(|window| window.id == id)
+            .ok_or(CaptureError::WindowNotFound(id))?;
+        // 最小化或在其他工作区的窗口没有可读取的内容
+        self.get_image(window.id, 0, 0, window.width as u16, window.height as u16)
+            .map_err(|e| match e {
+                CaptureError::Failed(_) => CaptureError::Failed("窗口不可见或已最小化".to_string()),
+                other => other,
+            })
+    }
+}
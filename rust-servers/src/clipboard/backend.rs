@@ -0,0 +1,150 @@
+// 剪贴板访问
+// 系统剪贴板通过 arboard 访问 (Windows / macOS / X11)，图片在剪贴板中为 RGBA 像素，
+// 与客户端之间以 PNG 传输
+
+use std::io::Cursor;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 剪贴板错误
+#[derive(Debug, Error)]
+pub enum ClipboardError {
+    #[error("剪贴板不可用: {0}")]
+    Unavailable(String),
+
+    #[error("无效的 PNG 图片: {0}")]
+    InvalidImage(String),
+}
+
+impl CodedError for ClipboardError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            ClipboardError::Unavailable(_) => ErrorCode::DeviceError,
+            ClipboardError::InvalidImage(_) => ErrorCode::InvalidParams,
+        }
+    }
+}
+
+impl From<arboard::Error> for ClipboardError {
+    fn from(error: arboard::Error) -> Self {
+        ClipboardError::Unavailable(error.to_string())
+    }
+}
+
+/// 剪贴板中的图片 (RGBA 像素，每像素 4 字节)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+impl Image {
+    /// 从 PNG 解码
+    pub fn from_png(bytes: &[u8]) -> Result<Self, ClipboardError> {
+        let decoded = image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
+            .map_err(|e| ClipboardError::InvalidImage(e.to_string()))?
+            .to_rgba8();
+        Ok(Self {
+            width: decoded.width() as usize,
+            height: decoded.height() as usize,
+            rgba: decoded.into_raw(),
+        })
+    }
+
+    /// 编码为 PNG
+    pub fn to_png(&self) -> Result<Vec<u8>, ClipboardError> {
+        let buffer = image::RgbaImage::from_raw(self.width as u32, self.height as u32, self.rgba.clone())
+            .ok_or_else(|| ClipboardError::InvalidImage("像素数据与尺寸不符".to_string()))?;
+        let mut png = Vec::new();
+        buffer.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(|e| ClipboardError::InvalidImage(e.to_string()))?;
+        Ok(png)
+    }
+}
+
+/// 剪贴板后端 (读取时剪贴板中没有该格式的内容返回 None)
+pub trait ClipboardBackend: Send {
+    fn text(&mut self) -> Result<Option<String>, ClipboardError>;
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError>;
+    fn image(&mut self) -> Result<Option<Image>, ClipboardError>;
+    fn set_image(&mut self, image: &Image) -> Result<(), ClipboardError>;
+}
+
+/// 系统剪贴板
+///
+/// X11 上写入的内容由持有剪贴板的进程提供，因此实例在模块内长期保留
+struct SystemClipboard(arboard::Clipboard);
+
+/// 读取结果中 "没有该格式的内容" 视为 None
+fn optional<T>(result: Result<T, arboard::Error>) -> Result<Option<T>, ClipboardError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+impl ClipboardBackend for SystemClipboard {
+    fn text(&mut self) -> Result<Option<String>, ClipboardError> {
+        optional(self.0.get_text())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        Ok(self.0.set_text(text)?)
+    }
+
+    fn image(&mut self) -> Result<Option<Image>, ClipboardError> {
+        Ok(optional(self.0.get_image())?.map(|image| Image {
+            width: image.width,
+            height: image.height,
+            rgba: image.bytes.into_owned(),
+        }))
+    }
+
+    fn set_image(&mut self, image: &Image) -> Result<(), ClipboardError> {
+        let data = arboard::ImageData {
+            width: image.width,
+            height: image.height,
+            bytes: std::borrow::Cow::Borrowed(&image.rgba),
+        };
+        Ok(self.0.set_image(data)?)
+    }
+}
+
+/// 打开系统剪贴板
+#[cfg(not(any(test, feature = "test-support")))]
+pub fn open() -> Result<Box<dyn ClipboardBackend>, ClipboardError> {
+    Ok(Box::new(SystemClipboard(arboard::Clipboard::new()?)))
+}
+
+/// 打开剪贴板 (安装了内存剪贴板时使用内存剪贴板)
+#[cfg(any(test, feature = "test-support"))]
+pub fn open() -> Result<Box<dyn ClipboardBackend>, ClipboardError> {
+    if crate::testing::clipboard::is_installed() {
+        return Ok(Box::new(crate::testing::clipboard::MemoryClipboard));
+    }
+    Ok(Box::new(SystemClipboard(arboard::Clipboard::new()?)))
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_roundtrip() {
+        let image = Image { width: 2, height: 1, rgba: vec![255, 0, 0, 255, 0, 0, 255, 128] };
+        let png = image.to_png().unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        assert_eq!(Image::from_png(&png).unwrap(), image);
+
+        assert!(matches!(Image::from_png(b"not a png"), Err(ClipboardError::InvalidImage(_))));
+        let broken = Image { width: 3, height: 3, rgba: vec![0; 4] };
+        assert!(broken.to_png().is_err());
+    }
+}
@@ -0,0 +1,383 @@
+// 剪贴板模块
+// 读写系统剪贴板中的文本和图片 (图片以 base64 编码的 PNG 传输)，Obsidian 不在前台时也能使用。
+// 可选的变更事件流定时轮询剪贴板，其他程序复制新内容时推送 clipboard_changed 事件
+// (本模块写入的内容不产生事件)
+
+mod backend;
+
+pub use backend::{ClipboardBackend, ClipboardError, Image};
+
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use base64::{Engine as _, engine::general_purpose};
+use futures_util::SinkExt;
+use serde::Deserialize;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Mutex as TokioMutex;
+use tokio_tungstenite::tungstenite::Message;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Clipboard", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Clipboard", format!($($arg)*));
+        }
+    };
+}
+
+/// 默认轮询间隔 (毫秒)
+const DEFAULT_POLL_INTERVAL_MS: u64 = 500;
+
+/// 轮询间隔的取值范围 (毫秒)
+const POLL_INTERVAL_RANGE_MS: std::ops::RangeInclusive<u64> = 100..=10_000;
+
+/// 创建剪贴板模块错误
+fn clipboard_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Clipboard, code, message)
+}
+
+// ============================================================================
+// 剪贴板状态
+// ============================================================================
+
+/// 剪贴板中的内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Content {
+    Text(String),
+    Image(Image),
+}
+
+impl Content {
+    fn format(&self) -> &'static str {
+        match self {
+            Content::Text(_) => "text",
+            Content::Image(_) => "image",
+        }
+    }
+
+    /// 内容指纹 (用于判断剪贴板是否变化)
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        match self {
+            Content::Text(text) => text.hash(&mut hasher),
+            Content::Image(image) => (image.width, image.height, &image.rgba).hash(&mut hasher),
+        }
+        hasher.finish()
+    }
+}
+
+/// 剪贴板及最近一次见到的内容指纹 (在阻塞线程中访问)
+struct ClipboardState {
+    /// 首次使用时打开，打开失败时下次重试
+    backend: Mutex<Option<Box<dyn ClipboardBackend>>>,
+    /// 最近一次读到或写入的内容指纹 (None 表示剪贴板为空或尚未读取)
+    fingerprint: Mutex<Option<u64>>,
+}
+
+impl ClipboardState {
+    fn new() -> Self {
+        Self {
+            backend: Mutex::new(None),
+            fingerprint: Mutex::new(None),
+        }
+    }
+
+    fn with_backend<T>(&self, f: impl FnOnce(&mut dyn ClipboardBackend) -> Result<T, ClipboardError>) -> Result<T, ClipboardError> {
+        let mut backend = self.backend.lock().unwrap_or_else(|e| e.into_inner());
+        if backend.is_none() {
+            *backend = Some(backend::open()?);
+        }
+        f(backend.as_deref_mut().expect("clipboard opened"))
+    }
+
+    /// 读取文本，没有文本时读取图片
+    fn read_any(&self) -> Result<Option<Content>, ClipboardError> {
+        self.with_backend(|backend| {
+            if let Some(text) = backend.text()? {
+                return Ok(Some(Content::Text(text)));
+            }
+            Ok(backend.image()?.map(Content::Image))
+        })
+    }
+
+    fn write(&self, content: &Content) -> Result<(), ClipboardError> {
+        self.with_backend(|backend| match content {
+            Content::Text(text) => backend.set_text(text),
+            Content::Image(image) => backend.set_image(image),
+        })?;
+        // 自己写入的内容不作为变更上报
+        *self.fingerprint.lock().unwrap_or_else(|e| e.into_inner()) = Some(content.fingerprint());
+        Ok(())
+    }
+
+    /// 读取当前内容，与上次不同时返回 Some(新内容)
+    fn poll_change(&self) -> Result<Option<Option<Content>>, ClipboardError> {
+        let content = self.read_any()?;
+        let fingerprint = content.as_ref().map(Content::fingerprint);
+        let mut last = self.fingerprint.lock().unwrap_or_else(|e| e.into_inner());
+        if *last == fingerprint {
+            return Ok(None);
+        }
+        *last = fingerprint;
+        Ok(Some(content))
+    }
+
+    /// 记录当前内容的指纹 (开始监听时的基准)
+    fn reset_fingerprint(&self) -> Result<(), ClipboardError> {
+        let fingerprint = self.read_any()?.as_ref().map(Content::fingerprint);
+        *self.fingerprint.lock().unwrap_or_else(|e| e.into_inner()) = fingerprint;
+        Ok(())
+    }
+}
+
+/// 在阻塞线程中访问剪贴板
+async fn blocking<T: Send + 'static>(
+    state: &Arc<ClipboardState>,
+    f: impl FnOnce(&ClipboardState) -> Result<T, ClipboardError> + Send + 'static,
+) -> Result<T, RouterError> {
+    let state = Arc::clone(state);
+    tokio::task::spawn_blocking(move || f(&state))
+        .await
+        .map_err(|e| clipboard_error(ErrorCode::Internal, format!("剪贴板任务失败: {}", e)))?
+        .map_err(|e| ModuleError::from_error(ModuleType::Clipboard, &e).into())
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// read 请求
+#[derive(Debug, Deserialize)]
+struct ReadRequest {
+    /// text / image，省略时优先读取文本
+    #[serde(default)]
+    format: Option<String>,
+}
+
+/// write 请求 (text 和 image 二选一)
+#[derive(Debug, Deserialize)]
+struct WriteRequest {
+    #[serde(default)]
+    text: Option<String>,
+    /// base64 编码的 PNG
+    #[serde(default)]
+    image: Option<String>,
+}
+
+/// 内容对应的响应或事件字段
+fn content_payload(format: Option<&str>, content: Option<&Content>) -> Result<serde_json::Value, ClipboardError> {
+    Ok(match content {
+        Some(Content::Text(text)) => serde_json::json!({ "format": "text", "text": text }),
+        Some(Content::Image(image)) => serde_json::json!({
+            "format": "image",
+            "data": general_purpose::STANDARD.encode(image.to_png()?),
+            "width": image.width,
+            "height": image.height,
+        }),
+        None => serde_json::json!({ "format": format }),
+    })
+}
+
+// ============================================================================
+// 剪贴板处理器
+// ============================================================================
+
+/// 剪贴板模块处理器
+pub struct ClipboardHandler {
+    state: Arc<ClipboardState>,
+    /// 变更事件的轮询任务
+    watch_task: TokioMutex<Option<tokio::task::JoinHandle<()>>>,
+    /// WebSocket 发送器 (请求没有来源连接时用于广播事件)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl ClipboardHandler {
+    /// 创建新的剪贴板处理器
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(ClipboardState::new()),
+            watch_task: TokioMutex::new(None),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    /// 处理 read 消息 - 读取剪贴板
+    async fn handle_read(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: ReadRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| clipboard_error(ErrorCode::InvalidParams, format!("无效的 read 请求: {}", e)))?;
+        let format = request.format.clone();
+        let payload = blocking(&self.state, move |state| {
+            let content = match format.as_deref() {
+                Some("text") => state.with_backend(|backend| backend.text())?.map(Content::Text),
+                Some("image") => state.with_backend(|backend| backend.image())?.map(Content::Image),
+                _ => state.read_any()?,
+            };
+            content_payload(format.as_deref(), content.as_ref())
+        }).await?;
+        log_debug!("读取剪贴板: format={:?}", payload["format"]);
+        Ok(Some(ServerResponse::new(ModuleType::Clipboard, "clipboard_content", payload)))
+    }
+
+    /// 处理 write 消息 - 写入文本或 PNG 图片
+    async fn handle_write(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: WriteRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| clipboard_error(ErrorCode::InvalidParams, format!("无效的 write 请求: {}", e)))?;
+        let content = match (request.text, request.image) {
+            (Some(text), None) => Content::Text(text),
+            (None, Some(data)) => {
+                let png = general_purpose::STANDARD.decode(data.as_bytes())
+                    .map_err(|e| clipboard_error(ErrorCode::InvalidParams, format!("无效的 base64 数据: {}", e)))?;
+                let image = tokio::task::spawn_blocking(move || Image::from_png(&png))
+                    .await
+                    .map_err(|e| clipboard_error(ErrorCode::Internal, format!("剪贴板任务失败: {}", e)))?
+                    .map_err(|e| ModuleError::from_error(ModuleType::Clipboard, &e))?;
+                Content::Image(image)
+            }
+            _ => return Err(clipboard_error(ErrorCode::InvalidParams, "必须提供 'text' 或 'image' 之一").into()),
+        };
+        let format = content.format();
+        blocking(&self.state, move |state| state.write(&content)).await?;
+        log_info!("已写入剪贴板: format={}", format);
+        Ok(Some(ServerResponse::new(ModuleType::Clipboard, "clipboard_written", serde_json::json!({ "format": format }))))
+    }
+
+    /// 处理 watch_start 消息 - 开始推送剪贴板变更事件 (已在监听时改用新的间隔和连接)
+    async fn handle_watch_start(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let interval_ms: u64 = msg.get_field("interval_ms").unwrap_or(DEFAULT_POLL_INTERVAL_MS);
+        if !POLL_INTERVAL_RANGE_MS.contains(&interval_ms) {
+            return Err(clipboard_error(
+                ErrorCode::InvalidParams,
+                format!("interval_ms 超出范围: {} (应为 {}-{})", interval_ms, POLL_INTERVAL_RANGE_MS.start(), POLL_INTERVAL_RANGE_MS.end()),
+            ).into());
+        }
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+        let sender = sender.ok_or_else(|| clipboard_error(ErrorCode::NotConnected, "WebSocket sender not set"))?;
+
+        // 以当前内容为基准，之后的变化才上报
+        blocking(&self.state, |state| state.reset_fingerprint()).await?;
+
+        let task = tokio::spawn(watch_changes(Arc::clone(&self.state), Duration::from_millis(interval_ms), sender));
+        if let Some(previous) = self.watch_task.lock().await.replace(task) {
+            previous.abort();
+        }
+        log_info!("开始监听剪贴板变更: interval_ms={}", interval_ms);
+        Ok(Some(ServerResponse::new(
+            ModuleType::Clipboard,
+            "watch_started",
+            serde_json::json!({ "interval_ms": interval_ms }),
+        )))
+    }
+
+    /// 处理 watch_stop 消息 - 停止推送变更事件
+    async fn handle_watch_stop(&self) -> ServerResponse {
+        if let Some(task) = self.watch_task.lock().await.take() {
+            task.abort();
+            log_info!("停止监听剪贴板变更");
+        }
+        ServerResponse::new(ModuleType::Clipboard, "watch_stopped", serde_json::json!({}))
+    }
+}
+
+impl Default for ClipboardHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 定时轮询剪贴板，内容变化时发送 clipboard_changed 事件 (连接断开后结束)
+async fn watch_changes(state: Arc<ClipboardState>, interval: Duration, sender: WsSender) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let polled = Arc::clone(&state);
+        let change = tokio::task::spawn_blocking(move || {
+            let content = polled.poll_change()?;
+            content.map(|content| content_payload(None, content.as_ref())).transpose()
+        }).await;
+        let payload = match change {
+            Ok(Ok(Some(payload))) => payload,
+            Ok(Ok(None)) => continue,
+            // 剪贴板暂时被其他程序占用等情况，下次轮询重试
+            Ok(Err(e)) => {
+                log_debug!("读取剪贴板失败: {}", e);
+                continue;
+            }
+            Err(_) => continue,
+        };
+        log_debug!("剪贴板已变化: format={:?}", payload["format"]);
+        let event = ServerResponse::new(ModuleType::Clipboard, "clipboard_changed", payload);
+        if sender.lock().await.send(Message::Text(event.to_json().into())).await.is_err() {
+            log_info!("连接已断开，停止监听剪贴板变更");
+            return;
+        }
+    }
+}
+
+/// 剪贴板模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("read", &[
+        FieldSpec::optional("format", FieldKind::String).one_of(&["text", "image"]),
+    ]),
+    MessageSpec::new("write", &[
+        FieldSpec::optional("text", FieldKind::String),
+        FieldSpec::optional("image", FieldKind::String),
+    ]),
+    MessageSpec::new("watch_start", &[
+        FieldSpec::optional("interval_ms", FieldKind::Integer),
+    ]),
+    MessageSpec::new("watch_stop", &[]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 剪贴板模块
+    pub const Clipboard: ModuleType = ModuleType::named("clipboard");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for ClipboardHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Clipboard
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    /// 停止推送变更事件 (连接关闭时调用)
+    async fn cleanup(&self) {
+        self.handle_watch_stop().await;
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理剪贴板消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "read" => self.handle_read(msg).await,
+            "write" => self.handle_write(msg).await,
+            "watch_start" => self.handle_watch_start(msg).await,
+            "watch_stop" => Ok(Some(self.handle_watch_stop().await)),
+            _ => Err(clipboard_error(ErrorCode::UnknownMessageType, format!("未知的剪贴板消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
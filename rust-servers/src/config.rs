@@ -0,0 +1,942 @@
+// 服务器配置
+// 启动时从 TOML 配置文件加载服务器级设置 (监听方式、TLS、数据目录、日志级别、代理、出站网络、限制、本地任务并发、模块开关、速率限制、看门狗)，
+// 命令行参数覆盖配置文件中的同名设置
+
+use clap::Parser;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::logging::Level;
+use crate::router::ModuleType;
+use crate::watchdog::TaskKind;
+use crate::workers::WorkerLimits;
+
+/// 固定认证令牌的最小长度
+const MIN_TOKEN_LEN: usize = 16;
+
+/// 默认心跳间隔 (秒)
+const DEFAULT_HEARTBEAT_INTERVAL_SECS: u64 = 15;
+
+/// 默认心跳超时 (秒)
+const DEFAULT_HEARTBEAT_TIMEOUT_SECS: u64 = 45;
+
+/// 默认最大并发连接数
+const DEFAULT_MAX_CONNECTIONS: usize = 16;
+
+/// 默认每个连接出站队列的容量 (消息数)
+const DEFAULT_OUTBOUND_QUEUE_SIZE: usize = 1024;
+
+/// 默认单条消息的大小上限 (字节)
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 8 * 1024 * 1024;
+
+/// 默认分块上传拼接后的大小上限 (字节)
+const DEFAULT_MAX_UPLOAD_SIZE: usize = 64 * 1024 * 1024;
+
+/// 默认空闲退出时间：最后一个连接断开后无新连接的秒数
+const DEFAULT_IDLE_EXIT_SECS: u64 = 300;
+
+/// 默认的事件合并窗口 (`module.type`, 毫秒)
+const DEFAULT_BATCH_WINDOWS: &[(&str, u64)] = &[
+    ("pty.output", 5),
+    ("llm.stream_chunk", 30),
+    ("voice.audio_level", 100),
+];
+
+/// 默认的消息速率限制 (`module` 或 `module.type`, 每秒消息数, 突发容量)
+const DEFAULT_RATE_LIMITS: &[(&str, f64, u32)] = &[
+    ("pty", 100.0, 200),
+    ("voice", 20.0, 40),
+    ("llm", 10.0, 20),
+    ("utils", 50.0, 100),
+    ("pty.init", 1.0, 5),
+    ("voice.start_recording", 1.0, 3),
+];
+
+/// 默认每个主机保留的空闲连接数
+const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 8;
+
+/// 默认空闲连接的保留时间 (秒)
+const DEFAULT_POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+
+/// 默认熔断阈值：同一主机连续失败的次数
+const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+
+/// 默认首次熔断的冷却时间 (秒)
+const DEFAULT_BREAKER_COOLDOWN_SECS: u64 = 10;
+
+/// 默认的任务停滞超时 (秒)
+const DEFAULT_STALL_TIMEOUTS: &[(TaskKind, u64)] = &[
+    (TaskKind::RealtimeAsr, 20),
+    (TaskKind::LlmStream, 120),
+    (TaskKind::PtyRead, 10),
+];
+
+// ============================================================================
+// 命令行参数
+// ============================================================================
+
+/// 命令行参数 (均为可选，设置时覆盖配置文件)
+#[derive(Debug, Default, Parser)]
+#[command(name = "smart-workflow-server", version = crate::SERVER_VERSION, about = "Smart Workflow 统一后端服务器")]
+pub struct Cli {
+    /// TOML 配置文件路径
+    #[arg(short, long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// 监听端口 (0 表示随机端口) [默认: 0]
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    /// 改为监听本地套接字 (Unix 域套接字路径 / Windows 命名管道名称)
+    #[arg(short, long, value_name = "PATH")]
+    pub socket: Option<String>,
+
+    /// TCP 监听的 IP 地址 (非本机地址时为远程访问模式，必须启用 TLS) [默认: 127.0.0.1]
+    #[arg(long, value_name = "ADDR")]
+    pub host: Option<IpAddr>,
+
+    /// 使用 TLS (wss://)，证书为数据目录中的自签名证书
+    #[arg(long)]
+    pub tls: bool,
+
+    /// 数据目录 (日志、缓存、历史等；省略时使用平台数据目录)
+    #[arg(short, long, value_name = "PATH")]
+    pub data_dir: Option<PathBuf>,
+
+    /// 不恢复上次退出时保存的状态 (终端会话等)
+    #[arg(long)]
+    pub fresh: bool,
+
+    /// 最低日志级别 (debug / info / warn / error)
+    #[arg(long, value_name = "LEVEL", value_parser = parse_level)]
+    pub log_level: Option<Level>,
+
+    /// ASR / LLM / 网页剪藏等 HTTP 请求使用的代理 (如 http://127.0.0.1:7890)
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// 最后一个连接断开后无新连接时自动退出的秒数 (0 表示不自动退出) [默认: 300]
+    #[arg(long, value_name = "SECS")]
+    pub idle_exit_secs: Option<u64>,
+
+    /// 在同一监听地址上提供 HTTP REST 接口 (/health、/transcribe、/exec、/detect-language)
+    #[arg(long)]
+    pub http_api: bool,
+
+    /// 在 HTTP 接口上提供数据目录中生成的文件 (GET /files/...，需要 --http-api，仅限本机监听)
+    #[arg(long)]
+    pub http_files: bool,
+
+    /// 禁用的模块 (pty / voice / llm / utils / files / clipboard / hotkeys / capture / git / scheduler / notify / playback / backup / system_info / meeting / workflow / vectors / tts)，可重复或以逗号分隔，与配置文件中禁用的模块合并
+    #[arg(long = "disable-module", value_name = "MODULE", value_delimiter = ',', value_parser = parse_module)]
+    pub disabled_modules: Vec<String>,
+}
+
+fn parse_level(value: &str) -> Result<Level, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_ascii_lowercase()))
+        .map_err(|_| format!("invalid log level: {} (expected debug, info, warn or error)", value))
+}
+
+fn parse_module(value: &str) -> Result<String, String> {
+    let name = value.trim().to_ascii_lowercase();
+    if name == ModuleType::System.name() {
+        return Err("the system module cannot be disabled".to_string());
+    }
+    if name.is_empty() {
+        return Err("module name cannot be empty".to_string());
+    }
+    Ok(name)
+}
+
+// ============================================================================
+// 配置文件
+// ============================================================================
+
+/// 配置文件内容 (所有字段可选)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    /// 监听端口
+    pub port: Option<u16>,
+    /// 本地套接字
+    pub socket: Option<String>,
+    /// TCP 监听的 IP 地址
+    pub host: Option<IpAddr>,
+    /// 固定的认证令牌 (只能在配置文件中设置，避免出现在进程参数中)
+    pub token: Option<String>,
+    /// 数据目录
+    pub data_dir: Option<PathBuf>,
+    /// HTTP 代理
+    pub proxy: Option<String>,
+    /// 日志设置
+    pub log: LogSection,
+    /// HTTP REST 接口
+    pub http: HttpSection,
+    /// TLS
+    pub tls: TlsSection,
+    /// 状态快照
+    pub state: StateSection,
+    /// 资源限制
+    pub limits: LimitsSection,
+    /// CPU 密集的本地任务并发
+    pub workers: WorkersSection,
+    /// 模块开关
+    pub modules: ModulesSection,
+    /// 事件合并窗口 (`module.type` -> 毫秒，0 表示不合并)
+    pub batching: BTreeMap<String, u64>,
+    /// 消息速率限制 (`module` 或 `module.type` -> 限制)
+    pub rate_limits: BTreeMap<String, RateLimitEntry>,
+    /// 任务看门狗
+    pub watchdog: WatchdogSection,
+    /// 出站网络
+    pub network: NetworkSection,
+}
+
+/// `[log]` 配置段
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogSection {
+    /// 最低日志级别
+    pub level: Option<Level>,
+}
+
+/// `[http]` 配置段
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpSection {
+    /// 是否在同一监听地址上提供 REST 接口
+    pub enabled: Option<bool>,
+    /// 是否提供数据目录中生成的文件 (GET /files/...)
+    pub files: Option<bool>,
+}
+
+/// `[tls]` 配置段
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsSection {
+    /// 是否使用 TLS (wss://)
+    pub enabled: Option<bool>,
+}
+
+/// `[state]` 配置段
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateSection {
+    /// 启动时是否恢复上次保存的状态
+    pub restore: Option<bool>,
+}
+
+/// `[limits]` 配置段
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsSection {
+    /// 心跳间隔 (秒)
+    pub heartbeat_interval_secs: Option<u64>,
+    /// 心跳超时 (秒)
+    pub heartbeat_timeout_secs: Option<u64>,
+    /// 最大并发连接数
+    pub max_connections: Option<usize>,
+    /// 每个连接出站队列的容量 (消息数)
+    pub outbound_queue_size: Option<usize>,
+    /// 单条消息的大小上限 (字节)
+    pub max_message_size: Option<usize>,
+    /// 分块上传拼接后的大小上限 (字节)
+    pub max_upload_size: Option<usize>,
+    /// 最后一个连接断开后自动退出的秒数 (0 表示不自动退出)
+    pub idle_exit_secs: Option<u64>,
+    /// 同时进行的 LLM 请求数上限
+    pub max_llm_requests: Option<usize>,
+}
+
+/// `[workers]` 配置段 (CPU 密集的本地任务，省略的项使用默认值)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkersSection {
+    /// 同时运行的任务总数 (也是单次本地推理的线程数)
+    pub threads: Option<usize>,
+    /// 同时运行的本地语音识别任务数
+    pub local_asr: Option<usize>,
+    /// 同时运行的索引任务数
+    pub indexing: Option<usize>,
+}
+
+/// `[watchdog]` 配置段 (任务停滞超时，0 表示不监视该类任务)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchdogSection {
+    /// 实时 ASR 转录无进度的超时 (秒)
+    pub realtime_asr_secs: Option<u64>,
+    /// LLM 流无数据的超时 (秒)
+    pub llm_stream_secs: Option<u64>,
+    /// shell 进程退出后 PTY 读取仍未结束的超时 (秒)
+    pub pty_read_secs: Option<u64>,
+}
+
+/// `[network]` 配置段 (出站 HTTP / WebSocket 连接)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkSection {
+    /// DNS 覆盖 (主机名 -> IP 地址)
+    pub dns: BTreeMap<String, IpAddr>,
+    /// 每个主机保留的空闲连接数 (0 表示不复用连接)
+    pub pool_max_idle_per_host: Option<usize>,
+    /// 空闲连接的保留时间 (秒)
+    pub pool_idle_timeout_secs: Option<u64>,
+    /// 同一主机连续失败多少次后熔断 (0 表示不熔断)
+    pub breaker_threshold: Option<u32>,
+    /// 首次熔断的冷却时间 (秒)，再次熔断时加倍
+    pub breaker_cooldown_secs: Option<u64>,
+}
+
+/// `[rate_limits]` 中的一项
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitEntry {
+    /// 每秒补充的消息数 (0 表示不限制)
+    pub per_sec: f64,
+    /// 突发容量 (默认为每秒消息数，至少为 1)
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+/// `[modules]` 配置段 (模块名称 -> 是否启用，未设置的模块默认启用)
+/// 
+/// 名称在启动时按已注册的模块校验
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct ModulesSection(pub BTreeMap<String, bool>);
+
+impl ModulesSection {
+    /// 设为 false 的模块
+    fn disabled(&self) -> Vec<String> {
+        self.0.iter()
+            .filter(|(_, enabled)| !**enabled)
+            .map(|(module, _)| module.to_ascii_lowercase())
+            .collect()
+    }
+}
+
+/// 配置错误
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("无法读取配置文件 {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+
+    #[error("配置文件格式错误 {path}: {source}")]
+    Parse { path: PathBuf, source: toml::de::Error },
+
+    #[error("配置无效: {0}")]
+    Invalid(String),
+}
+
+impl FileConfig {
+    /// 读取并解析配置文件
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|source| ConfigError::Read { path: path.to_path_buf(), source })?;
+        toml::from_str(&text).map_err(|source| ConfigError::Parse { path: path.to_path_buf(), source })
+    }
+}
+
+// ============================================================================
+// 合并后的配置
+// ============================================================================
+
+/// 资源限制
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    /// 心跳间隔
+    pub heartbeat_interval: Duration,
+    /// 心跳超时
+    pub heartbeat_timeout: Duration,
+    /// 最大并发连接数
+    pub max_connections: usize,
+    /// 每个连接出站队列的容量 (消息数)
+    pub outbound_queue_size: usize,
+    /// 单条消息的大小上限 (字节)
+    pub max_message_size: usize,
+    /// 分块上传拼接后的大小上限 (字节)
+    pub max_upload_size: usize,
+    /// 最后一个连接断开后自动退出前的宽限期 (None 表示不自动退出)
+    pub idle_exit: Option<Duration>,
+    /// 同时进行的 LLM 请求数上限
+    pub max_llm_requests: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(DEFAULT_HEARTBEAT_INTERVAL_SECS),
+            heartbeat_timeout: Duration::from_secs(DEFAULT_HEARTBEAT_TIMEOUT_SECS),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            outbound_queue_size: DEFAULT_OUTBOUND_QUEUE_SIZE,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            max_upload_size: DEFAULT_MAX_UPLOAD_SIZE,
+            idle_exit: Some(Duration::from_secs(DEFAULT_IDLE_EXIT_SECS)),
+            max_llm_requests: crate::llm::queue::DEFAULT_MAX_CONCURRENT,
+        }
+    }
+}
+
+/// 出站网络设置
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkSettings {
+    /// HTTP 代理 (未设置时 HTTP 请求沿用 HTTP_PROXY / HTTPS_PROXY 环境变量)
+    pub proxy: Option<String>,
+    /// DNS 覆盖 (主机名 -> IP 地址)
+    pub dns: BTreeMap<String, IpAddr>,
+    /// 每个主机保留的空闲连接数
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接的保留时间
+    pub pool_idle_timeout: Duration,
+    /// 同一主机连续失败多少次后熔断 (0 表示不熔断)
+    pub breaker_threshold: u32,
+    /// 首次熔断的冷却时间
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            dns: BTreeMap::new(),
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout: Duration::from_secs(DEFAULT_POOL_IDLE_TIMEOUT_SECS),
+            breaker_threshold: DEFAULT_BREAKER_THRESHOLD,
+            breaker_cooldown: Duration::from_secs(DEFAULT_BREAKER_COOLDOWN_SECS),
+        }
+    }
+}
+
+/// 事件合并窗口
+///
+/// 同一连接上窗口内连续的同类事件合并为一条消息发送，未列出或窗口为 0 的事件不合并
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchWindows(BTreeMap<String, Duration>);
+
+impl BatchWindows {
+    /// 不合并任何事件
+    pub fn none() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    /// 设置事件的合并窗口 (`event` 为 `module.type`)
+    pub fn with(mut self, event: &str, window: Duration) -> Self {
+        self.0.insert(event.to_string(), window);
+        self
+    }
+
+    /// 获取事件的合并窗口
+    pub fn get(&self, module: &str, msg_type: &str) -> Option<Duration> {
+        self.0.get(&format!("{}.{}", module, msg_type))
+            .copied()
+            .filter(|window| !window.is_zero())
+    }
+}
+
+impl Default for BatchWindows {
+    fn default() -> Self {
+        DEFAULT_BATCH_WINDOWS.iter()
+            .fold(Self::none(), |windows, (event, ms)| windows.with(event, Duration::from_millis(*ms)))
+    }
+}
+
+/// 任务停滞超时
+///
+/// 超过超时仍未上报进度的任务由看门狗中止，未列出或超时为 0 的任务类型不监视
+#[derive(Debug, Clone, PartialEq)]
+pub struct StallTimeouts(HashMap<TaskKind, Duration>);
+
+impl StallTimeouts {
+    /// 不监视任何任务
+    pub fn none() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// 设置任务类型的停滞超时
+    pub fn with(mut self, kind: TaskKind, timeout: Duration) -> Self {
+        self.0.insert(kind, timeout);
+        self
+    }
+
+    /// 获取任务类型的停滞超时
+    pub fn get(&self, kind: TaskKind) -> Option<Duration> {
+        self.0.get(&kind).copied().filter(|timeout| !timeout.is_zero())
+    }
+}
+
+impl Default for StallTimeouts {
+    fn default() -> Self {
+        DEFAULT_STALL_TIMEOUTS.iter()
+            .fold(Self::none(), |timeouts, (kind, secs)| timeouts.with(*kind, Duration::from_secs(*secs)))
+    }
+}
+
+/// 消息速率限制 (令牌桶)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// 每秒补充的消息数
+    pub per_sec: f64,
+    /// 突发容量
+    pub burst: u32,
+}
+
+/// 各模块和消息类型的速率限制
+///
+/// 每个连接对每一项单独计数；消息需同时满足所属模块 (`module`) 和消息类型 (`module.type`) 的限制，
+/// 未列出或速率为 0 的项不限制
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimits(BTreeMap<String, RateLimit>);
+
+impl RateLimits {
+    /// 不限制任何消息
+    pub fn none() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    /// 设置一项的速率限制 (`key` 为 `module` 或 `module.type`)
+    pub fn with(mut self, key: &str, limit: RateLimit) -> Self {
+        self.0.insert(key.to_string(), limit);
+        self
+    }
+
+    /// 获取一项的速率限制
+    pub fn get(&self, key: &str) -> Option<RateLimit> {
+        self.0.get(key)
+            .copied()
+            .filter(|limit| limit.per_sec > 0.0 && limit.burst > 0)
+    }
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        DEFAULT_RATE_LIMITS.iter()
+            .fold(Self::none(), |limits, (key, per_sec, burst)| {
+                limits.with(key, RateLimit { per_sec: *per_sec, burst: *burst })
+            })
+    }
+}
+
+/// 服务器级配置 (配置文件 + 命令行覆盖)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    /// TCP 端口 (0 表示随机端口)
+    pub port: u16,
+    /// 本地套接字：Unix 上为域套接字路径，Windows 上为命名管道名称
+    pub socket: Option<String>,
+    /// TCP 监听的 IP 地址 (未设置时为 127.0.0.1)
+    pub host: Option<IpAddr>,
+    /// 固定的认证令牌 (未设置时每次启动随机生成)
+    pub token: Option<String>,
+    /// 是否使用 TLS (wss://，仅 TCP 监听)
+    pub tls: bool,
+    /// 数据目录 (未设置时使用平台数据目录，且不启用单实例锁)
+    pub data_dir: Option<PathBuf>,
+    /// 启动时不恢复上次保存的状态
+    pub fresh_start: bool,
+    /// 最低日志级别 (未设置时输出所有级别)
+    pub log_level: Option<Level>,
+    /// 出站网络设置 (代理、DNS 覆盖、连接池、熔断)
+    pub network: NetworkSettings,
+    /// 是否提供 HTTP REST 接口
+    pub http_api: bool,
+    /// 是否在 HTTP 接口上提供生成的文件
+    pub http_files: bool,
+    /// 资源限制
+    pub limits: Limits,
+    /// CPU 密集的本地任务并发上限
+    pub workers: WorkerLimits,
+    /// 事件合并窗口
+    pub batching: BatchWindows,
+    /// 消息速率限制
+    pub rate_limits: RateLimits,
+    /// 任务停滞超时
+    pub stall_timeouts: StallTimeouts,
+    /// 禁用的模块
+    pub disabled_modules: Vec<String>,
+}
+
+impl Config {
+    /// 加载配置：读取 `--config` 指定的文件 (如有)，再应用命令行覆盖
+    pub fn load(cli: Cli) -> Result<Self, ConfigError> {
+        let file = match &cli.config {
+            Some(path) => FileConfig::load(path)?,
+            None => FileConfig::default(),
+        };
+        Self::merge(file, cli)
+    }
+
+    /// 合并配置文件和命令行参数，命令行优先
+    pub fn merge(file: FileConfig, cli: Cli) -> Result<Self, ConfigError> {
+        let defaults = Limits::default();
+        let limits = Limits {
+            heartbeat_interval: file.limits.heartbeat_interval_secs
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.heartbeat_interval),
+            heartbeat_timeout: file.limits.heartbeat_timeout_secs
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.heartbeat_timeout),
+            max_connections: file.limits.max_connections.unwrap_or(defaults.max_connections),
+            outbound_queue_size: file.limits.outbound_queue_size.unwrap_or(defaults.outbound_queue_size),
+            max_message_size: file.limits.max_message_size.unwrap_or(defaults.max_message_size),
+            max_upload_size: file.limits.max_upload_size.unwrap_or(defaults.max_upload_size),
+            idle_exit: match cli.idle_exit_secs.or(file.limits.idle_exit_secs) {
+                Some(0) => None,
+                Some(secs) => Some(Duration::from_secs(secs)),
+                None => defaults.idle_exit,
+            },
+            max_llm_requests: file.limits.max_llm_requests.unwrap_or(defaults.max_llm_requests),
+        };
+
+        let mut batching = BatchWindows::default();
+        for (event, ms) in &file.batching {
+            match event.split_once('.') {
+                Some((module, msg_type)) if !module.is_empty() && !msg_type.is_empty() => {
+                    batching = batching.with(event, Duration::from_millis(*ms));
+                }
+                _ => {
+                    return Err(ConfigError::Invalid(format!("batching 的键应为 module.type: {}", event)));
+                }
+            }
+        }
+
+        let mut rate_limits = RateLimits::default();
+        for (key, entry) in &file.rate_limits {
+            let valid_key = match key.split_once('.') {
+                Some((module, msg_type)) => !module.is_empty() && !msg_type.is_empty(),
+                None => !key.is_empty(),
+            };
+            if !valid_key {
+                return Err(ConfigError::Invalid(format!("rate_limits 的键应为 module 或 module.type: {}", key)));
+            }
+            if !entry.per_sec.is_finite() || entry.per_sec < 0.0 {
+                return Err(ConfigError::Invalid(format!("rate_limits.{} 的 per_sec 不能为负数", key)));
+            }
+            let burst = entry.burst.unwrap_or((entry.per_sec.ceil() as u32).max(1));
+            rate_limits = rate_limits.with(key, RateLimit { per_sec: entry.per_sec, burst });
+        }
+
+        let mut stall_timeouts = StallTimeouts::default();
+        for (kind, secs) in [
+            (TaskKind::RealtimeAsr, file.watchdog.realtime_asr_secs),
+            (TaskKind::LlmStream, file.watchdog.llm_stream_secs),
+            (TaskKind::PtyRead, file.watchdog.pty_read_secs),
+        ] {
+            if let Some(secs) = secs {
+                stall_timeouts = stall_timeouts.with(kind, Duration::from_secs(secs));
+            }
+        }
+
+        let worker_defaults = WorkerLimits::default();
+        let workers = WorkerLimits {
+            threads: file.workers.threads.unwrap_or(worker_defaults.threads),
+            local_asr: file.workers.local_asr.unwrap_or(worker_defaults.local_asr),
+            indexing: file.workers.indexing.unwrap_or(worker_defaults.indexing),
+        };
+
+        let network_defaults = NetworkSettings::default();
+        let network = NetworkSettings {
+            proxy: cli.proxy.or(file.proxy),
+            dns: file.network.dns,
+            pool_max_idle_per_host: file.network.pool_max_idle_per_host
+                .unwrap_or(network_defaults.pool_max_idle_per_host),
+            pool_idle_timeout: file.network.pool_idle_timeout_secs
+                .map(Duration::from_secs)
+                .unwrap_or(network_defaults.pool_idle_timeout),
+            breaker_threshold: file.network.breaker_threshold.unwrap_or(network_defaults.breaker_threshold),
+            breaker_cooldown: file.network.breaker_cooldown_secs
+                .map(Duration::from_secs)
+                .unwrap_or(network_defaults.breaker_cooldown),
+        };
+
+        let mut disabled_modules = file.modules.disabled();
+        if disabled_modules.iter().any(|module| module == ModuleType::System.name()) {
+            return Err(ConfigError::Invalid("system 模块不能被禁用".to_string()));
+        }
+        for module in cli.disabled_modules {
+            if !disabled_modules.contains(&module) {
+                disabled_modules.push(module);
+            }
+        }
+
+        let config = Self {
+            port: cli.port.or(file.port).unwrap_or(0),
+            socket: cli.socket.or(file.socket),
+            host: cli.host.or(file.host),
+            token: file.token,
+            tls: cli.tls || file.tls.enabled.unwrap_or(false),
+            data_dir: cli.data_dir.or(file.data_dir),
+            fresh_start: cli.fresh || file.state.restore == Some(false),
+            log_level: cli.log_level.or(file.log.level),
+            network,
+            http_api: cli.http_api || file.http.enabled.unwrap_or(false),
+            http_files: cli.http_files || file.http.files.unwrap_or(false),
+            limits,
+            workers,
+            batching,
+            rate_limits,
+            stall_timeouts,
+            disabled_modules,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 是否为远程访问模式 (TCP 监听非本机地址，其他机器可以连接)
+    pub fn is_remote(&self) -> bool {
+        self.socket.is_none() && self.host.is_some_and(|host| !host.is_loopback())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.limits.heartbeat_interval.is_zero() {
+            return Err(ConfigError::Invalid("limits.heartbeat_interval_secs 必须大于 0".to_string()));
+        }
+        if self.limits.heartbeat_timeout <= self.limits.heartbeat_interval {
+            return Err(ConfigError::Invalid(
+                "limits.heartbeat_timeout_secs 必须大于 heartbeat_interval_secs".to_string(),
+            ));
+        }
+        if self.limits.max_connections == 0 {
+            return Err(ConfigError::Invalid("limits.max_connections 必须大于 0".to_string()));
+        }
+        if self.limits.outbound_queue_size == 0 {
+            return Err(ConfigError::Invalid("limits.outbound_queue_size 必须大于 0".to_string()));
+        }
+        if self.limits.max_message_size == 0 {
+            return Err(ConfigError::Invalid("limits.max_message_size 必须大于 0".to_string()));
+        }
+        if self.limits.max_llm_requests == 0 {
+            return Err(ConfigError::Invalid("limits.max_llm_requests 必须大于 0".to_string()));
+        }
+        for (key, value) in [
+            ("threads", self.workers.threads),
+            ("local_asr", self.workers.local_asr),
+            ("indexing", self.workers.indexing),
+        ] {
+            if value == 0 {
+                return Err(ConfigError::Invalid(format!("workers.{} 必须大于 0", key)));
+            }
+        }
+        if self.limits.max_upload_size < self.limits.max_message_size {
+            return Err(ConfigError::Invalid(
+                "limits.max_upload_size 不能小于 max_message_size".to_string(),
+            ));
+        }
+        if self.tls && self.socket.is_some() {
+            return Err(ConfigError::Invalid("tls 仅用于 TCP 监听，不能与 socket 同时使用".to_string()));
+        }
+        if self.is_remote() && !self.tls {
+            return Err(ConfigError::Invalid(
+                "host 为非本机地址 (远程访问模式) 时必须启用 tls".to_string(),
+            ));
+        }
+        if self.http_files && !self.http_api {
+            return Err(ConfigError::Invalid("http.files 需要启用 HTTP 接口 (http.enabled 或 --http-api)".to_string()));
+        }
+        if self.http_files && self.is_remote() {
+            return Err(ConfigError::Invalid("http.files 仅用于本机监听，不能在远程访问模式下启用".to_string()));
+        }
+        if let Some(token) = &self.token {
+            let valid = token.len() >= MIN_TOKEN_LEN
+                && token.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+            if !valid {
+                return Err(ConfigError::Invalid(format!(
+                    "token 至少需要 {} 个字符，且只能包含字母、数字、- 和 _",
+                    MIN_TOKEN_LEN
+                )));
+            }
+        }
+        if let Some(proxy) = &self.network.proxy {
+            reqwest::Proxy::all(proxy.as_str())
+                .map_err(|e| ConfigError::Invalid(format!("proxy 地址无效 ({}): {}", proxy, e)))?;
+        }
+        if self.network.breaker_threshold > 0 && self.network.breaker_cooldown.is_zero() {
+            return Err(ConfigError::Invalid("network.breaker_cooldown_secs 必须大于 0".to_string()));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+port = 9000
+host = "0.0.0.0"
+token = "vault-desktop-0123456789"
+data_dir = "/var/lib/smart-workflow"
+proxy = "http://127.0.0.1:7890"
+
+[log]
+level = "warn"
+
+[http]
+enabled = true
+
+[tls]
+enabled = true
+
+[state]
+restore = false
+
+[limits]
+heartbeat_interval_secs = 10
+max_connections = 4
+outbound_queue_size = 64
+max_message_size = 65536
+idle_exit_secs = 0
+max_llm_requests = 2
+
+[workers]
+threads = 3
+
+[modules]
+voice = false
+pty = true
+
+[batching]
+"llm.stream_chunk" = 0
+"voice.transcription_progress" = 20
+
+[rate_limits]
+llm = { per_sec = 0 }
+"pty.init" = { per_sec = 0.5, burst = 2 }
+"utils.clip" = { per_sec = 2 }
+
+[watchdog]
+llm_stream_secs = 300
+pty_read_secs = 0
+
+[network]
+dns = { "api.example.com" = "10.0.0.8" }
+pool_max_idle_per_host = 0
+breaker_threshold = 3
+"#;
+
+    #[test]
+    fn test_parse_file() {
+        let file: FileConfig = toml::from_str(SAMPLE).unwrap();
+        assert_eq!(file.port, Some(9000));
+        assert_eq!(file.log.level, Some(Level::Warn));
+        assert_eq!(file.limits.max_connections, Some(4));
+        assert!(toml::from_str::<FileConfig>("prot = 1").is_err());
+        assert_eq!(toml::from_str::<FileConfig>("").unwrap(), FileConfig::default());
+    }
+
+    #[test]
+    fn test_cli_overrides_file() {
+        let file: FileConfig = toml::from_str(SAMPLE).unwrap();
+        let cli = Cli::parse_from(["smart-workflow-server", "--port", "0", "--log-level", "DEBUG", "--host", "::1", "--http-files"]);
+        let config = Config::merge(file, cli).unwrap();
+
+        assert_eq!(config.port, 0);
+        assert_eq!(config.host, Some(IpAddr::from([0u16, 0, 0, 0, 0, 0, 0, 1])));
+        assert_eq!(config.token.as_deref(), Some("vault-desktop-0123456789"));
+        assert!(config.tls);
+        assert!(!config.is_remote());
+        assert_eq!(config.log_level, Some(Level::Debug));
+        assert_eq!(config.data_dir, Some(PathBuf::from("/var/lib/smart-workflow")));
+        assert_eq!(config.network.proxy.as_deref(), Some("http://127.0.0.1:7890"));
+        assert_eq!(config.network.dns.get("api.example.com"), Some(&IpAddr::from([10, 0, 0, 8])));
+        assert_eq!(config.network.pool_max_idle_per_host, 0);
+        assert_eq!(config.network.pool_idle_timeout, Duration::from_secs(DEFAULT_POOL_IDLE_TIMEOUT_SECS));
+        assert_eq!(config.network.breaker_threshold, 3);
+        assert_eq!(config.network.breaker_cooldown, Duration::from_secs(DEFAULT_BREAKER_COOLDOWN_SECS));
+        assert!(config.http_api);
+        assert!(config.http_files);
+        assert!(config.fresh_start);
+        assert_eq!(config.limits.heartbeat_interval, Duration::from_secs(10));
+        assert_eq!(config.limits.heartbeat_timeout, Duration::from_secs(DEFAULT_HEARTBEAT_TIMEOUT_SECS));
+        assert_eq!(config.limits.max_connections, 4);
+        assert_eq!(config.limits.outbound_queue_size, 64);
+        assert_eq!(config.limits.max_message_size, 65536);
+        assert_eq!(config.limits.max_upload_size, DEFAULT_MAX_UPLOAD_SIZE);
+        assert_eq!(config.limits.idle_exit, None);
+        assert_eq!(config.limits.max_llm_requests, 2);
+        assert_eq!(config.workers.threads, 3);
+        assert_eq!(config.workers.local_asr, WorkerLimits::default().local_asr);
+        assert_eq!(config.disabled_modules, vec!["voice"]);
+        assert_eq!(config.batching.get("llm", "stream_chunk"), None);
+        assert_eq!(config.batching.get("voice", "transcription_progress"), Some(Duration::from_millis(20)));
+        assert_eq!(config.batching.get("pty", "output"), Some(Duration::from_millis(5)));
+        assert_eq!(config.rate_limits.get("llm"), None);
+        assert_eq!(config.rate_limits.get("pty.init"), Some(RateLimit { per_sec: 0.5, burst: 2 }));
+        assert_eq!(config.rate_limits.get("utils.clip"), Some(RateLimit { per_sec: 2.0, burst: 2 }));
+        assert_eq!(config.rate_limits.get("voice.start_recording"), Some(RateLimit { per_sec: 1.0, burst: 3 }));
+        assert_eq!(config.stall_timeouts.get(TaskKind::LlmStream), Some(Duration::from_secs(300)));
+        assert_eq!(config.stall_timeouts.get(TaskKind::RealtimeAsr), Some(Duration::from_secs(20)));
+        assert_eq!(config.stall_timeouts.get(TaskKind::PtyRead), None);
+
+        let cli = Cli::parse_from(["smart-workflow-server", "-s", "/tmp/sw.sock"]);
+        let config = Config::merge(FileConfig::default(), cli).unwrap();
+        assert_eq!(config.socket.as_deref(), Some("/tmp/sw.sock"));
+        assert!(!config.tls);
+        assert!(!config.fresh_start);
+    }
+
+    #[test]
+    fn test_disabled_modules() {
+        let file: FileConfig = toml::from_str(SAMPLE).unwrap();
+        let cli = Cli::parse_from(["smart-workflow-server", "--disable-module", "llm,VOICE", "--disable-module", "utils"]);
+        let config = Config::merge(file, cli).unwrap();
+        assert_eq!(config.disabled_modules, vec!["voice", "llm", "utils"]);
+
+        let file: FileConfig = toml::from_str(SAMPLE).unwrap();
+        let cli = Cli::parse_from(["smart-workflow-server", "--idle-exit-secs", "60"]);
+        assert_eq!(Config::merge(file, cli).unwrap().limits.idle_exit, Some(Duration::from_secs(60)));
+
+        assert!(Cli::try_parse_from(["smart-workflow-server", "--disable-module", "system"]).is_err());
+        assert!(Cli::try_parse_from(["smart-workflow-server", "--disable-module", ""]).is_err());
+        // 模块名称在启动时按已注册的模块校验
+        assert!(Cli::try_parse_from(["smart-workflow-server", "--disable-module", "video"]).is_ok());
+        let file: FileConfig = toml::from_str("[modules]\nsystem = false").unwrap();
+        assert!(Config::merge(file, Cli::default()).is_err());
+    }
+
+    #[test]
+    fn test_defaults_and_validation() {
+        let config = Config::merge(FileConfig::default(), Cli::default()).unwrap();
+        assert_eq!(config, Config::default());
+
+        let invalid = |toml_text: &str| {
+            let file: FileConfig = toml::from_str(toml_text).unwrap();
+            matches!(Config::merge(file, Cli::default()), Err(ConfigError::Invalid(_)))
+        };
+        assert!(invalid("[limits]\nheartbeat_timeout_secs = 5"));
+        assert!(invalid("[limits]\nmax_connections = 0"));
+        assert!(invalid("[limits]\noutbound_queue_size = 0"));
+        assert!(invalid("[limits]\nmax_message_size = 0"));
+        assert!(invalid("[limits]\nmax_llm_requests = 0"));
+        assert!(invalid("[workers]\nindexing = 0"));
+        assert!(invalid("[limits]\nmax_message_size = 2048\nmax_upload_size = 1024"));
+        assert!(invalid("proxy = \"not a url\""));
+        assert!(invalid("[network]\nbreaker_cooldown_secs = 0"));
+        assert!(toml::from_str::<FileConfig>("[network]\ndns = { \"api.example.com\" = \"not an ip\" }").is_err());
+        assert!(invalid("[batching]\naudio_level = 50"));
+        assert!(invalid("[rate_limits]\n\"pty.\" = { per_sec = 1 }"));
+        assert!(invalid("[rate_limits]\npty = { per_sec = -1 }"));
+        assert!(toml::from_str::<FileConfig>("[rate_limits]\npty = { rate = 1 }").is_err());
+        assert!(invalid("socket = \"/tmp/sw.sock\"\n[tls]\nenabled = true"));
+        assert!(invalid("host = \"0.0.0.0\""));
+        assert!(invalid("token = \"short\""));
+        assert!(invalid("[http]\nfiles = true"));
+        assert!(invalid("host = \"0.0.0.0\"\n[http]\nenabled = true\nfiles = true\n[tls]\nenabled = true"));
+        assert!(invalid("token = \"has spaces in the token\""));
+        assert!(Cli::try_parse_from(["smart-workflow-server", "--token", "vault-desktop-0123456789"]).is_err());
+
+        // 远程访问模式：非本机地址 + TLS
+        let cli = Cli::parse_from(["smart-workflow-server", "--host", "0.0.0.0", "--tls"]);
+        assert!(Config::merge(FileConfig::default(), cli).unwrap().is_remote());
+        assert!(Cli::try_parse_from(["smart-workflow-server", "--log-level", "loud"]).is_err());
+        assert!(Cli::try_parse_from(["smart-workflow-server", "--host", "example.com"]).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+// 文件监视模块
+// 监视 vault 目录中由外部程序 (同步工具、编辑器、下载等) 产生的变更，去抖动后以 file_created /
+// file_modified / file_deleted / file_renamed 事件推送。每个监视有独立的 session_id，
+// 可以通过 system/subscribe 订阅 "files:<session_id>" 接收其他连接发起的监视事件
+
+mod watcher;
+
+use watcher::{FileChange, VaultWatcher};
+
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use futures_util::SinkExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex as TokioMutex;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Files", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Files", format!($($arg)*));
+        }
+    };
+}
+
+/// 默认去抖动窗口 (毫秒)
+const DEFAULT_DEBOUNCE_MS: u64 = 500;
+
+/// 去抖动窗口的取值范围 (毫秒)
+const DEBOUNCE_RANGE_MS: std::ops::RangeInclusive<u64> = 50..=10_000;
+
+/// 同时存在的监视数上限
+const MAX_WATCHES: usize = 16;
+
+/// 默认忽略的名称 (版本库、Obsidian 配置目录和回收站)
+const DEFAULT_IGNORE: &[&str] = &[".git", ".obsidian", ".trash"];
+
+/// 创建文件监视模块错误
+fn files_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Files, code, message)
+}
+
+// ============================================================================
+// 请求和监视上下文
+// ============================================================================
+
+/// watch 请求
+#[derive(Debug, Deserialize)]
+struct WatchRequest {
+    /// 监视的目录 (递归)
+    paths: Vec<String>,
+    /// 去抖动窗口 (毫秒)
+    #[serde(default)]
+    debounce_ms: Option<u64>,
+    /// 忽略的文件或目录名称 (省略时使用默认列表)
+    #[serde(default)]
+    ignore: Option<Vec<String>>,
+}
+
+/// 监视的参数
+#[derive(Debug, Clone, Serialize)]
+struct WatchInfo {
+    session_id: String,
+    paths: Vec<String>,
+    debounce_ms: u64,
+    ignore: Vec<String>,
+}
+
+/// 单个监视的上下文 (监视器由事件转发任务持有，任务终止后随之销毁)
+struct WatchContext {
+    info: WatchInfo,
+    task: tokio::task::JoinHandle<()>,
+}
+
+// ============================================================================
+// 文件监视处理器
+// ============================================================================
+
+/// 文件监视模块处理器
+pub struct FilesHandler {
+    /// 监视: session_id → WatchContext (连接断开后由转发任务自身移除)
+    watches: Arc<TokioMutex<HashMap<String, WatchContext>>>,
+    /// WebSocket 发送器 (请求没有来源连接时用于广播事件)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl FilesHandler {
+    /// 创建新的文件监视处理器
+    pub fn new() -> Self {
+        Self {
+            watches: Arc::new(TokioMutex::new(HashMap::new())),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    /// 处理 watch 消息 - 开始监视一组目录
+    async fn handle_watch(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: WatchRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| files_error(ErrorCode::InvalidParams, format!("无效的 watch 请求: {}", e)))?;
+        if request.paths.is_empty() {
+            return Err(files_error(ErrorCode::InvalidParams, "paths 不能为空").into());
+        }
+        let debounce_ms = request.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS);
+        if !DEBOUNCE_RANGE_MS.contains(&debounce_ms) {
+            return Err(files_error(
+                ErrorCode::InvalidParams,
+                format!("debounce_ms 超出范围: {} (应为 {}-{})", debounce_ms, DEBOUNCE_RANGE_MS.start(), DEBOUNCE_RANGE_MS.end()),
+            ).into());
+        }
+        let ignore = request.ignore
+            .unwrap_or_else(|| DEFAULT_IGNORE.iter().map(|name| name.to_string()).collect());
+        let paths: Vec<PathBuf> = request.paths.iter().map(PathBuf::from).collect();
+        if let Some(path) = paths.iter().find(|path| !path.is_dir()) {
+            return Err(files_error(ErrorCode::NotFound, format!("目录不存在: {}", path.display())).into());
+        }
+
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+        let sender = sender.ok_or_else(|| files_error(ErrorCode::NotConnected, "WebSocket sender not set"))?;
+
+        let mut watches = self.watches.lock().await;
+        if watches.len() >= MAX_WATCHES {
+            return Err(files_error(ErrorCode::LimitExceeded, format!("监视过多 (最多 {} 个)", MAX_WATCHES)).into());
+        }
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let watcher = VaultWatcher::start(&paths, Duration::from_millis(debounce_ms), ignore.clone(), tx)
+            .map_err(|e| files_error(ErrorCode::IoError, format!("无法监视目录: {}", e)))?;
+
+        let session_id = Uuid::new_v4().to_string();
+        let info = WatchInfo {
+            session_id: session_id.clone(),
+            paths: request.paths,
+            debounce_ms,
+            ignore,
+        };
+        let watches_ref = Arc::clone(&self.watches);
+        let id = session_id.clone();
+        let task = tokio::spawn(async move {
+            forward_changes(&id, watcher, rx, sender).await;
+            watches_ref.lock().await.remove(&id);
+        });
+        log_info!("开始监视: session_id={}, paths={:?}", session_id, info.paths);
+        let response = ServerResponse::new(ModuleType::Files, "watching", serde_json::to_value(&info).unwrap_or_default());
+        watches.insert(session_id, WatchContext { info, task });
+        Ok(Some(response))
+    }
+
+    /// 处理 unwatch 消息 - 停止监视
+    async fn handle_unwatch(&self, session_id: &str) -> Result<Option<ServerResponse>, RouterError> {
+        let context = self.watches.lock().await.remove(session_id)
+            .ok_or_else(|| files_error(ErrorCode::SessionNotFound, format!("监视不存在: {}", session_id)))?;
+        context.task.abort();
+        log_info!("停止监视: session_id={}", session_id);
+        Ok(Some(ServerResponse::new(ModuleType::Files, "unwatched", serde_json::json!({ "session_id": session_id }))))
+    }
+
+    /// 处理 list 消息 - 列出当前的监视
+    async fn handle_list(&self) -> ServerResponse {
+        let watches = self.watches.lock().await;
+        let mut list: Vec<&WatchInfo> = watches.values().map(|context| &context.info).collect();
+        list.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        ServerResponse::new(ModuleType::Files, "watches", serde_json::json!({ "watches": list }))
+    }
+}
+
+impl Default for FilesHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 将监视器产生的变更转换为事件发送 (停止监视时被终止，连接断开后结束)
+async fn forward_changes(
+    session_id: &str,
+    watcher: VaultWatcher,
+    mut rx: tokio::sync::mpsc::UnboundedReceiver<Vec<FileChange>>,
+    sender: WsSender,
+) {
+    while let Some(changes) = rx.recv().await {
+        for change in changes {
+            let mut payload = serde_json::json!({
+                "session_id": session_id,
+                "path": change.path().to_string_lossy(),
+                "relative_path": watcher.relative_path(change.path()),
+            });
+            if let FileChange::Renamed { from, .. } = &change {
+                payload["old_path"] = from.to_string_lossy().into();
+                payload["old_relative_path"] = watcher.relative_path(from).into();
+            }
+            log_debug!("文件变更: {} {}", change.event_type(), change.path().display());
+            let event = ServerResponse::new(ModuleType::Files, change.event_type(), payload);
+            if sender.lock().await.send(Message::Text(event.to_json().into())).await.is_err() {
+                log_info!("连接已断开，停止转发监视事件: session_id={}", session_id);
+                return;
+            }
+        }
+    }
+}
+
+/// 文件监视模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("watch", &[
+        FieldSpec::required("paths", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("debounce_ms", FieldKind::Integer),
+        FieldSpec::optional("ignore", FieldKind::Array).items(FieldKind::String),
+    ]),
+    MessageSpec::new("unwatch", &[
+        FieldSpec::required("session_id", FieldKind::String),
+    ]),
+    MessageSpec::new("list", &[]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 文件监视模块
+    pub const Files: ModuleType = ModuleType::named("files");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for FilesHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Files
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    /// 停止所有监视 (连接关闭时调用)
+    async fn cleanup(&self) {
+        let mut watches = self.watches.lock().await;
+        for (session_id, context) in watches.drain() {
+            context.task.abort();
+            log_info!("停止监视: session_id={}", session_id);
+        }
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理文件监视消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "watch" => self.handle_watch(msg).await,
+            "unwatch" => {
+                let session_id: String = msg.get_field("session_id").ok_or_else(|| {
+                    files_error(ErrorCode::InvalidParams, "缺少 session_id 字段")
+                })?;
+                self.handle_unwatch(&session_id).await
+            }
+            "list" => Ok(Some(self.handle_list().await)),
+            _ => Err(files_error(ErrorCode::UnknownMessageType, format!("未知的文件监视消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+// vault 目录监视
+// 基于 notify 的去抖动监视器：同一文件在去抖动窗口内的多次变更合并，重命名的两端合并为一个事件，
+// 再转换为创建 / 修改 / 删除 / 重命名四种变更 (元数据和访问事件忽略)
+
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{self, EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// 文件变更
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    Created(PathBuf),
+    Modified(PathBuf),
+    Deleted(PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+impl FileChange {
+    /// 事件消息类型
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            FileChange::Created(_) => "file_created",
+            FileChange::Modified(_) => "file_modified",
+            FileChange::Deleted(_) => "file_deleted",
+            FileChange::Renamed { .. } => "file_renamed",
+        }
+    }
+
+    /// 变更后的路径 (重命名为新路径)
+    pub fn path(&self) -> &Path {
+        match self {
+            FileChange::Created(path) | FileChange::Modified(path) | FileChange::Deleted(path) => path,
+            FileChange::Renamed { to, .. } => to,
+        }
+    }
+}
+
+/// 监视的根目录
+#[derive(Debug, Clone)]
+struct Root {
+    path: PathBuf,
+    /// 规范化路径 (部分平台上报的事件路径经过规范化，如 macOS 的 /private/var)
+    canonical: PathBuf,
+}
+
+/// 监视的根目录和忽略的名称
+#[derive(Debug)]
+struct PathFilter {
+    roots: Vec<Root>,
+    ignore: Vec<String>,
+}
+
+impl PathFilter {
+    fn new(paths: &[PathBuf], ignore: Vec<String>) -> Self {
+        let roots = paths.iter()
+            .map(|path| Root {
+                path: path.clone(),
+                canonical: path.canonicalize().unwrap_or_else(|_| path.clone()),
+            })
+            .collect();
+        Self { roots, ignore }
+    }
+
+    /// 路径相对于所属根目录的部分 (不在任何根目录下时为 None)
+    fn relative<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        self.roots.iter()
+            .find_map(|root| path.strip_prefix(&root.path).or_else(|_| path.strip_prefix(&root.canonical)).ok())
+    }
+
+    /// 相对路径中含有忽略的名称
+    fn is_ignored(&self, path: &Path) -> bool {
+        let relative = self.relative(path).unwrap_or(path);
+        relative.components()
+            .any(|component| self.ignore.iter().any(|name| component.as_os_str() == name.as_str()))
+    }
+}
+
+/// 一组 vault 路径的监视器，变更批量发送到 `tx` (监视器销毁后停止)
+pub struct VaultWatcher {
+    filter: Arc<PathFilter>,
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+}
+
+impl VaultWatcher {
+    /// 递归监视 `paths`，相对路径中含有 `ignore` 中任一名称的变更被忽略
+    pub fn start(
+        paths: &[PathBuf],
+        debounce: Duration,
+        ignore: Vec<String>,
+        tx: UnboundedSender<Vec<FileChange>>,
+    ) -> notify::Result<Self> {
+        let filter = Arc::new(PathFilter::new(paths, ignore));
+        let handler_filter = Arc::clone(&filter);
+        let mut debouncer = new_debouncer(debounce, None, move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let changes = changes(events, &handler_filter);
+                if !changes.is_empty() {
+                    let _ = tx.send(changes);
+                }
+            }
+            Err(errors) => {
+                for error in errors {
+                    crate::logging::write(crate::logging::Level::Warn, "Files", format!("文件监视错误: {}", error));
+                }
+            }
+        })?;
+        for path in paths {
+            debouncer.watch(path, RecursiveMode::Recursive)?;
+        }
+        Ok(Self { filter, _debouncer: debouncer })
+    }
+
+    /// 路径相对于所属根目录的部分，使用 `/` 分隔 (与 Obsidian 的 vault 路径一致)
+    pub fn relative_path(&self, path: &Path) -> Option<String> {
+        let parts: Vec<String> = self.filter.relative(path)?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        Some(parts.join("/"))
+    }
+}
+
+/// 将去抖动后的事件转换为变更 (去掉被忽略的路径和重复的修改)
+fn changes(events: Vec<DebouncedEvent>, filter: &PathFilter) -> Vec<FileChange> {
+    let mut changes: Vec<FileChange> = Vec::new();
+    for event in events {
+        let paths = &event.event.paths;
+        let change = match event.event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                paths.first().cloned().map(FileChange::Created)
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                paths.first().cloned().map(FileChange::Deleted)
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => match paths.as_slice() {
+                [from, to] => Some(FileChange::Renamed { from: from.clone(), to: to.clone() }),
+                _ => None,
+            },
+            // 无法确定方向的重命名：按路径是否仍存在判断
+            EventKind::Modify(ModifyKind::Name(_)) => paths.first().map(|path| {
+                if path.exists() {
+                    FileChange::Created(path.clone())
+                } else {
+                    FileChange::Deleted(path.clone())
+                }
+            }),
+            EventKind::Modify(ModifyKind::Metadata(_)) => None,
+            EventKind::Modify(_) => paths.first().cloned().map(FileChange::Modified),
+            EventKind::Access(_) | EventKind::Any | EventKind::Other => None,
+        };
+        let change = match change {
+            // 从忽略的目录移入视为创建，移入忽略的目录视为删除
+            Some(FileChange::Renamed { from, to }) => match (filter.is_ignored(&from), filter.is_ignored(&to)) {
+                (false, false) => Some(FileChange::Renamed { from, to }),
+                (true, false) => Some(FileChange::Created(to)),
+                (false, true) => Some(FileChange::Deleted(from)),
+                (true, true) => None,
+            },
+            Some(change) if filter.is_ignored(change.path()) => None,
+            change => change,
+        };
+        if let Some(change) = change {
+            if !changes.contains(&change) {
+                changes.push(change);
+            }
+        }
+    }
+    changes
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_full::notify::event::{CreateKind, DataChange, MetadataKind, RemoveKind};
+    use notify_debouncer_full::notify::Event;
+    use std::time::Instant;
+
+    fn event(kind: EventKind, paths: &[&str]) -> DebouncedEvent {
+        let event = paths.iter().fold(Event::new(kind), |event, path| event.add_path(PathBuf::from(path)));
+        DebouncedEvent::new(event, Instant::now())
+    }
+
+    #[test]
+    fn test_changes() {
+        let filter = PathFilter {
+            roots: vec![Root { path: PathBuf::from("/vault"), canonical: PathBuf::from("/private/vault") }],
+            ignore: vec![".obsidian".to_string()],
+        };
+        assert_eq!(filter.relative(Path::new("/private/vault/dir/note.md")), Some(Path::new("dir/note.md")));
+        assert_eq!(filter.relative(Path::new("/elsewhere/note.md")), None);
+        let events = vec![
+            event(EventKind::Create(CreateKind::File), &["/vault/a.md"]),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &["/vault/b.md"]),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), &["/vault/b.md"]),
+            event(EventKind::Modify(ModifyKind::Metadata(MetadataKind::WriteTime)), &["/vault/c.md"]),
+            event(EventKind::Remove(RemoveKind::File), &["/private/vault/d.md"]),
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/vault/e.md", "/vault/dir/e.md"]),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), &["/vault/.obsidian/workspace.json"]),
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["/vault/.obsidian/tmp", "/vault/f.md"]),
+        ];
+        assert_eq!(changes(events, &filter), vec![
+            FileChange::Created(PathBuf::from("/vault/a.md")),
+            FileChange::Modified(PathBuf::from("/vault/b.md")),
+            FileChange::Deleted(PathBuf::from("/private/vault/d.md")),
+            FileChange::Renamed { from: PathBuf::from("/vault/e.md"), to: PathBuf::from("/vault/dir/e.md") },
+            FileChange::Created(PathBuf::from("/vault/f.md")),
+        ]);
+    }
+}
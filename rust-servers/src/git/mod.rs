@@ -0,0 +1,423 @@
+// Git 模块
+// vault 仓库的版本控制：状态、差异、暂存、提交、历史和推送 / 拉取，供 "AI 大幅修改后自动提交" 等检查点功能使用，
+// 无需通过 PTY 调用 git 命令。推送和拉取在后台执行：立即应答 push_started / pull_started，
+// 过程中发送 progress 事件，完成后发送 pushed / pulled 事件 (失败时为 error 事件)，可以用 system/cancel 取消
+
+mod remote;
+mod repo;
+
+use remote::Credentials;
+use repo::{Author, GitError};
+
+use crate::jobs::{self, Job, JobKind};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use crate::system::progress::ProgressReporter;
+use futures_util::SinkExt;
+use git2::Repository;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Mutex as TokioMutex;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Git", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Git", format!($($arg)*));
+        }
+    };
+}
+
+/// log 默认返回的提交数
+const DEFAULT_LOG_LIMIT: usize = 50;
+
+/// log 最多返回的提交数
+const MAX_LOG_LIMIT: usize = 1000;
+
+/// 同时进行的推送 / 拉取数上限
+const MAX_OPERATIONS: usize = 8;
+
+/// 创建 Git 模块错误
+fn git_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Git, code, message)
+}
+
+/// 在阻塞线程中打开仓库并执行操作
+async fn blocking<T: Send + 'static>(
+    path: PathBuf,
+    f: impl FnOnce(&Repository) -> Result<T, GitError> + Send + 'static,
+) -> Result<T, RouterError> {
+    let result = tokio::task::spawn_blocking(move || f(&repo::open(&path)?))
+        .await
+        .map_err(|e| git_error(ErrorCode::Internal, format!("Git 任务失败: {}", e)))?;
+    Ok(result.map_err(|e| ModuleError::from_error(ModuleType::Git, &e))?)
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// status / diff / stage / unstage / log 请求
+#[derive(Debug, Deserialize)]
+struct RepoRequest {
+    /// 仓库的工作目录
+    repo: String,
+    /// diff: 比较 HEAD 与暂存区 (默认比较暂存区与工作目录)
+    #[serde(default)]
+    staged: bool,
+    /// diff / stage / unstage: 限定的路径 (pathspec)，stage / unstage 省略时为全部
+    #[serde(default)]
+    paths: Vec<String>,
+    /// log: 返回的提交数
+    #[serde(default)]
+    limit: Option<usize>,
+    /// log: 只返回修改了该路径的提交
+    #[serde(default)]
+    path: Option<String>,
+}
+
+/// commit 请求
+#[derive(Debug, Deserialize)]
+struct CommitRequest {
+    repo: String,
+    message: String,
+    /// 提交前暂存全部变更 (含未跟踪和删除的文件)
+    #[serde(default)]
+    all: bool,
+    #[serde(default)]
+    author_name: Option<String>,
+    #[serde(default)]
+    author_email: Option<String>,
+}
+
+/// push / pull 请求
+#[derive(Debug, Deserialize)]
+struct RemoteRequest {
+    repo: String,
+    #[serde(default)]
+    remote: Option<String>,
+    #[serde(default)]
+    branch: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    /// pull: 合并提交的作者
+    #[serde(default)]
+    author_name: Option<String>,
+    #[serde(default)]
+    author_email: Option<String>,
+}
+
+fn parse<T: serde::de::DeserializeOwned>(msg: &ModuleMessage) -> Result<T, ModuleError> {
+    serde_json::from_value(msg.payload.clone())
+        .map_err(|e| git_error(ErrorCode::InvalidParams, format!("无效的 {} 请求: {}", msg.msg_type, e)))
+}
+
+/// 后台操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    Push,
+    Pull,
+}
+
+impl Operation {
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::Push => "push",
+            Operation::Pull => "pull",
+        }
+    }
+}
+
+// ============================================================================
+// Git 处理器
+// ============================================================================
+
+/// Git 模块处理器
+pub struct GitHandler {
+    /// 进行中的推送 / 拉取: request_id (没有时为生成的 ID) → 取消标志
+    operations: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    /// WebSocket 发送器 (请求没有来源连接时使用)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl GitHandler {
+    /// 创建新的 Git 处理器
+    pub fn new() -> Self {
+        Self {
+            operations: Arc::new(Mutex::new(HashMap::new())),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    /// 处理 status / diff / stage / unstage / log 消息
+    async fn handle_repo(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: RepoRequest = parse(msg)?;
+        let path = PathBuf::from(&request.repo);
+        let (msg_type, payload) = match msg.msg_type.as_str() {
+            "status" => ("status", blocking(path, repo::status).await.map(to_value)?),
+            "diff" => {
+                let diff = blocking(path, move |repo| repo::diff(repo, request.staged, &request.paths)).await?;
+                ("diff", to_value(diff))
+            }
+            "stage" => {
+                let status = blocking(path, move |repo| {
+                    repo::stage(repo, &request.paths)?;
+                    repo::status(repo)
+                }).await?;
+                ("staged", to_value(status))
+            }
+            "unstage" => {
+                let status = blocking(path, move |repo| {
+                    repo::unstage(repo, &request.paths)?;
+                    repo::status(repo)
+                }).await?;
+                ("unstaged", to_value(status))
+            }
+            _ => {
+                let limit = request.limit.unwrap_or(DEFAULT_LOG_LIMIT).min(MAX_LOG_LIMIT);
+                let commits = blocking(path, move |repo| repo::log(repo, limit, request.path.as_deref())).await?;
+                ("log", serde_json::json!({ "commits": commits }))
+            }
+        };
+        Ok(Some(ServerResponse::new(ModuleType::Git, msg_type, payload)))
+    }
+
+    /// 处理 commit 消息 - 提交暂存区 (all 时先暂存全部变更)
+    async fn handle_commit(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: CommitRequest = parse(msg)?;
+        if request.message.trim().is_empty() {
+            return Err(git_error(ErrorCode::InvalidParams, "message 不能为空").into());
+        }
+        let author = Author { name: request.author_name, email: request.author_email };
+        let message = request.message;
+        let all = request.all;
+        let commit = blocking(PathBuf::from(&request.repo), move |repo| {
+            if all {
+                repo::stage(repo, &[])?;
+            }
+            repo::commit(repo, &message, &author)
+        }).await?;
+        log_info!("已提交: {} {} ({})", request.repo, commit.id, commit.summary);
+        Ok(Some(ServerResponse::new(ModuleType::Git, "committed", to_value(commit))))
+    }
+
+    /// 处理 push / pull 消息 - 解析目标后在后台执行，立即应答
+    async fn handle_remote(&self, msg: &ModuleMessage, operation: Operation) -> Result<Option<ServerResponse>, RouterError> {
+        let request: RemoteRequest = parse(msg)?;
+        let path = PathBuf::from(&request.repo);
+        let (remote_name, branch) = (request.remote.clone(), request.branch.clone());
+        let destination = blocking(path.clone(), move |repo| remote::resolve(repo, remote_name, branch)).await?;
+
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+        let sender = sender.ok_or_else(|| git_error(ErrorCode::NotConnected, "WebSocket sender not set"))?;
+
+        let request_id = msg.request_id();
+        let key = request_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        let cancelled = Arc::new(AtomicBool::new(false));
+        {
+            let mut operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+            if operations.len() >= MAX_OPERATIONS {
+                return Err(git_error(ErrorCode::LimitExceeded, format!("进行中的推送 / 拉取过多 (最多 {} 个)", MAX_OPERATIONS)).into());
+            }
+            if operations.contains_key(&key) {
+                return Err(git_error(ErrorCode::InvalidParams, format!("请求已在进行中: {}", key)).into());
+            }
+            operations.insert(key.clone(), Arc::clone(&cancelled));
+        }
+        let method = match operation {
+            Operation::Push => "PUSH",
+            Operation::Pull => "FETCH",
+        };
+        crate::audit::record_network(ModuleType::Git, method, &destination.url);
+        log_info!("开始 {}: {} {}/{}", operation.name(), request.repo, destination.remote, destination.branch);
+
+        let started = ServerResponse::new(
+            ModuleType::Git,
+            &format!("{}_started", operation.name()),
+            to_value(&destination),
+        );
+        let credentials = Credentials { username: request.username, password: request.password };
+        let author = Author { name: request.author_name, email: request.author_email };
+        let progress = ProgressReporter::new(ModuleType::Git, request_id.clone(), Some(sender.clone()));
+        let operations = Arc::clone(&self.operations);
+        let job = jobs::register(
+            Job::new(JobKind::GitSync)
+                .with_request_id(request_id.clone())
+                .with_detail(format!("{} {}", operation.name(), request.repo))
+                .with_cancel_flag(Arc::clone(&cancelled)),
+        );
+        tokio::spawn(async move {
+            let _job = job;
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let task = tokio::task::spawn_blocking(move || {
+                let report = |stage, percent, detail| {
+                    let _ = tx.send((stage, percent, detail));
+                };
+                let repo = repo::open(&path)?;
+                match operation {
+                    Operation::Push => remote::push(&repo, &destination, &credentials, &cancelled, &report)
+                        .map(|result| ("pushed", to_value(result))),
+                    Operation::Pull => remote::pull(&repo, &destination, &credentials, &author, &cancelled, &report)
+                        .map(|result| {
+                            if result.result != "up_to_date" {
+                                crate::audit::record_file_write(ModuleType::Git, &path);
+                            }
+                            ("pulled", to_value(result))
+                        }),
+                }
+            });
+            while let Some((stage, percent, detail)) = rx.recv().await {
+                progress.report(stage, percent, detail).await;
+            }
+            let result = task.await;
+            operations.lock().unwrap_or_else(|e| e.into_inner()).remove(&key);
+
+            let event = match result {
+                Ok(Ok((msg_type, payload))) => {
+                    log_info!("{} 完成: {}", operation.name(), payload);
+                    ServerResponse::new(ModuleType::Git, msg_type, payload)
+                }
+                Ok(Err(e)) => {
+                    log_info!("{} 失败: {}", operation.name(), e);
+                    let error = ModuleError::from_error(ModuleType::Git, &e);
+                    ServerResponse::error(ModuleType::Git, error.code, &error.message)
+                }
+                Err(e) => ServerResponse::error(ModuleType::Git, ErrorCode::Internal, &format!("Git 任务失败: {}", e)),
+            };
+            let event = event.with_request_id(request_id.as_deref());
+            let _ = sender.lock().await.send(Message::Text(event.to_json().into())).await;
+        });
+        Ok(Some(started))
+    }
+}
+
+impl Default for GitHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn to_value(value: impl serde::Serialize) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+/// Git 模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("status", &[
+        FieldSpec::required("repo", FieldKind::String),
+    ]),
+    MessageSpec::new("diff", &[
+        FieldSpec::required("repo", FieldKind::String),
+        FieldSpec::optional("staged", FieldKind::Boolean),
+        FieldSpec::optional("paths", FieldKind::Array).items(FieldKind::String),
+    ]),
+    MessageSpec::new("stage", &[
+        FieldSpec::required("repo", FieldKind::String),
+        FieldSpec::optional("paths", FieldKind::Array).items(FieldKind::String),
+    ]),
+    MessageSpec::new("unstage", &[
+        FieldSpec::required("repo", FieldKind::String),
+        FieldSpec::optional("paths", FieldKind::Array).items(FieldKind::String),
+    ]),
+    MessageSpec::new("commit", &[
+        FieldSpec::required("repo", FieldKind::String),
+        FieldSpec::required("message", FieldKind::String),
+        FieldSpec::optional("all", FieldKind::Boolean),
+        FieldSpec::optional("author_name", FieldKind::String),
+        FieldSpec::optional("author_email", FieldKind::String),
+    ]),
+    MessageSpec::new("log", &[
+        FieldSpec::required("repo", FieldKind::String),
+        FieldSpec::optional("limit", FieldKind::Integer),
+        FieldSpec::optional("path", FieldKind::String),
+    ]),
+    MessageSpec::new("push", &[
+        FieldSpec::required("repo", FieldKind::String),
+        FieldSpec::optional("remote", FieldKind::String),
+        FieldSpec::optional("branch", FieldKind::String),
+        FieldSpec::optional("username", FieldKind::String),
+        FieldSpec::optional("password", FieldKind::String),
+    ]),
+    MessageSpec::new("pull", &[
+        FieldSpec::required("repo", FieldKind::String),
+        FieldSpec::optional("remote", FieldKind::String),
+        FieldSpec::optional("branch", FieldKind::String),
+        FieldSpec::optional("username", FieldKind::String),
+        FieldSpec::optional("password", FieldKind::String),
+        FieldSpec::optional("author_name", FieldKind::String),
+        FieldSpec::optional("author_email", FieldKind::String),
+    ]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// Git 模块
+    pub const Git: ModuleType = ModuleType::named("git");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for GitHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Git
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    /// 取消进行中的推送 / 拉取 (连接关闭时调用)
+    async fn cleanup(&self) {
+        let operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        for cancelled in operations.values() {
+            cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// 取消由 request_id 发起的推送 / 拉取
+    async fn cancel(&self, request_id: &str) -> bool {
+        let operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        match operations.get(request_id) {
+            Some(cancelled) if !cancelled.swap(true, Ordering::SeqCst) => {
+                log_info!("取消 Git 操作: request_id={}", request_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理 Git 消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "status" | "diff" | "stage" | "unstage" | "log" => self.handle_repo(msg).await,
+            "commit" => self.handle_commit(msg).await,
+            "push" => self.handle_remote(msg, Operation::Push).await,
+            "pull" => self.handle_remote(msg, Operation::Pull).await,
+            _ => Err(git_error(ErrorCode::UnknownMessageType, format!("未知的 Git 消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
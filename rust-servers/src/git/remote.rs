@@ -0,0 +1,278 @@
+// 推送和拉取
+// 认证依次尝试请求中的用户名 / 密码、Git 凭据助手和 ssh-agent；代理沿用出站网络设置。
+// 取消标志在传输回调中检查，推送只能在上传对象之前取消
+
+use git2::build::CheckoutBuilder;
+use git2::{Cred, CredentialType, FetchOptions, ProxyOptions, PushOptions, RemoteCallbacks, Repository};
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use super::repo::{self, Author, GitError};
+use crate::system::progress::percent_between;
+
+/// 认证回调的最大尝试次数 (凭据被拒绝后 libgit2 会反复调用回调)
+const MAX_CREDENTIAL_ATTEMPTS: u32 = 3;
+
+/// 进度回调的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 进度回调 (阶段、百分比、说明)
+pub type ProgressFn<'a> = &'a dyn Fn(&'static str, Option<u8>, Option<String>);
+
+/// HTTPS 认证 (省略时使用 Git 凭据助手)
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// 推送 / 拉取的目标
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Destination {
+    pub remote: String,
+    /// 分支名称 (本地和远程同名)
+    pub branch: String,
+    #[serde(skip)]
+    pub url: String,
+}
+
+/// 推送结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PushResult {
+    pub remote: String,
+    pub branch: String,
+    pub commit: String,
+}
+
+/// 拉取结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PullResult {
+    pub remote: String,
+    pub branch: String,
+    /// up_to_date / fast_forward / merged
+    pub result: &'static str,
+    /// 拉取后的 HEAD 提交
+    pub commit: String,
+}
+
+/// 取消时回调返回的错误
+fn cancelled_error() -> git2::Error {
+    git2::Error::new(git2::ErrorCode::User, git2::ErrorClass::Callback, "操作已取消")
+}
+
+/// 解析目标：远程省略时为分支的上游远程或 origin，分支省略时为当前分支
+pub fn resolve(repo: &Repository, remote: Option<String>, branch: Option<String>) -> Result<Destination, GitError> {
+    let head = repo.find_reference("HEAD")?;
+    let current = head.symbolic_target()?
+        .and_then(|name| name.strip_prefix("refs/heads/"))
+        .map(str::to_string);
+    let branch = branch.or(current).ok_or_else(|| {
+        git2::Error::new(git2::ErrorCode::Invalid, git2::ErrorClass::Reference, "HEAD 不在分支上，请指定 branch")
+    })?;
+    let remote = match remote {
+        Some(remote) => remote,
+        None => repo.branch_upstream_remote(&format!("refs/heads/{}", branch)).ok()
+            .and_then(|name| name.as_str().ok().map(str::to_string))
+            .unwrap_or_else(|| "origin".to_string()),
+    };
+    let url = repo.find_remote(&remote)?.url()?.to_string();
+    Ok(Destination { remote, branch, url })
+}
+
+/// 代理设置：沿用出站网络配置的代理，未配置时自动检测 (环境变量和 Git 配置)
+fn proxy_options() -> ProxyOptions<'static> {
+    let mut proxy = ProxyOptions::new();
+    match crate::network::proxy() {
+        Some(url) => proxy.url(&url),
+        None => proxy.auto(),
+    };
+    proxy
+}
+
+/// 节流：距离上次报告超过间隔或已完成时放行
+struct Throttle {
+    last: Cell<Option<Instant>>,
+}
+
+impl Throttle {
+    fn new() -> Self {
+        Self { last: Cell::new(None) }
+    }
+
+    fn ready(&self, done: bool) -> bool {
+        let now = Instant::now();
+        if done || self.last.get().is_none_or(|last| now.duration_since(last) >= PROGRESS_INTERVAL) {
+            self.last.set(Some(now));
+            return true;
+        }
+        false
+    }
+}
+
+/// 公共回调：认证和传输中的取消检查
+fn callbacks<'a>(repo: &Repository, credentials: &'a Credentials, cancelled: &'a AtomicBool) -> RemoteCallbacks<'a> {
+    let config = repo.config().ok();
+    let mut attempts = 0;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        if cancelled.load(Ordering::SeqCst) {
+            return Err(cancelled_error());
+        }
+        attempts += 1;
+        if attempts > MAX_CREDENTIAL_ATTEMPTS {
+            return Err(git2::Error::new(git2::ErrorCode::Auth, git2::ErrorClass::Callback, format!("认证失败: {}", url)));
+        }
+        let username = credentials.username.as_deref().or(username).unwrap_or("git");
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username);
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(password) = &credentials.password {
+                return Cred::userpass_plaintext(username, password);
+            }
+            if let Some(config) = &config {
+                return Cred::credential_helper(config, url, Some(username));
+            }
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            return Cred::ssh_key_from_agent(username);
+        }
+        if allowed.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+        Err(git2::Error::new(git2::ErrorCode::Auth, git2::ErrorClass::Callback, format!("没有可用的凭据: {}", url)))
+    });
+    callbacks.sideband_progress(move |_| !cancelled.load(Ordering::SeqCst));
+    callbacks
+}
+
+/// 操作失败时区分取消和其他错误
+fn map_cancelled(cancelled: &AtomicBool) -> impl Fn(git2::Error) -> GitError + '_ {
+    move |e| if cancelled.load(Ordering::SeqCst) { GitError::Cancelled } else { GitError::Git(e) }
+}
+
+/// 推送当前分支 (本地分支没有上游时设置为推送的远程分支)
+pub fn push(
+    repo: &Repository,
+    destination: &Destination,
+    credentials: &Credentials,
+    cancelled: &AtomicBool,
+    progress: ProgressFn<'_>,
+) -> Result<PushResult, GitError> {
+    let Destination { remote: remote_name, branch, .. } = destination.clone();
+    let commit = repo.find_reference(&format!("refs/heads/{}", branch))?.peel_to_commit()?.id();
+    let mut remote = repo.find_remote(&remote_name)?;
+
+    let rejected = RefCell::new(None);
+    let throttle = Throttle::new();
+    let mut callbacks = callbacks(repo, credentials, cancelled);
+    callbacks.push_negotiation(|_| if cancelled.load(Ordering::SeqCst) { Err(cancelled_error()) } else { Ok(()) });
+    callbacks.push_transfer_progress(|current, total, _bytes| {
+        if throttle.ready(current == total) {
+            progress("uploading", Some(percent_between(0, 100, current, total)), Some(format!("{}/{}", current, total)));
+        }
+    });
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(status) = status {
+            *rejected.borrow_mut() = Some(format!("{} ({})", refname, status));
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks).proxy_options(proxy_options());
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+    remote.push(&[refspec.as_str()], Some(&mut options)).map_err(map_cancelled(cancelled))?;
+    if let Some(reason) = rejected.take() {
+        return Err(GitError::Rejected(reason));
+    }
+
+    if let Ok(mut local) = repo.find_branch(&branch, git2::BranchType::Local) {
+        if local.upstream().is_err() {
+            let _ = local.set_upstream(Some(&format!("{}/{}", remote_name, branch)));
+        }
+    }
+    Ok(PushResult { remote: remote_name, branch, commit: commit.to_string() })
+}
+
+/// 检出树 (只更新未修改的文件，与本地修改冲突时失败)
+fn checkout(repo: &Repository, tree: &git2::Tree<'_>) -> Result<(), GitError> {
+    repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    Ok(())
+}
+
+/// 拉取远程分支并合并到当前分支 (快进或创建合并提交)，有冲突时不修改工作目录
+pub fn pull(
+    repo: &Repository,
+    destination: &Destination,
+    credentials: &Credentials,
+    author: &Author,
+    cancelled: &AtomicBool,
+    progress: ProgressFn<'_>,
+) -> Result<PullResult, GitError> {
+    repo::ensure_clean_state(repo)?;
+    let Destination { remote: remote_name, branch, .. } = destination.clone();
+    let mut remote = repo.find_remote(&remote_name)?;
+
+    let throttle = Throttle::new();
+    let mut callbacks = callbacks(repo, credentials, cancelled);
+    callbacks.transfer_progress(|stats| {
+        let (received, total) = (stats.received_objects(), stats.total_objects());
+        if throttle.ready(received == total) {
+            progress(
+                "receiving",
+                Some(percent_between(0, 90, received, total)),
+                Some(format!("{}/{} ({} KiB)", received, total, stats.received_bytes() / 1024)),
+            );
+        }
+        !cancelled.load(Ordering::SeqCst)
+    });
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks).proxy_options(proxy_options());
+    remote.fetch(&[branch.as_str()], Some(&mut options), None).map_err(map_cancelled(cancelled))?;
+    if cancelled.load(Ordering::SeqCst) {
+        return Err(GitError::Cancelled);
+    }
+
+    progress("merging", Some(95), None);
+    let fetch_head = repo.find_reference("FETCH_HEAD")?;
+    let theirs = repo.reference_to_annotated_commit(&fetch_head)?;
+    let their_commit = repo.find_commit(theirs.id())?;
+    let (analysis, _) = repo.merge_analysis(&[&theirs])?;
+    let local_ref = format!("refs/heads/{}", branch);
+    let result = |result, commit: git2::Oid| PullResult {
+        remote: remote_name.clone(),
+        branch: branch.clone(),
+        result,
+        commit: commit.to_string(),
+    };
+
+    if analysis.is_up_to_date() {
+        let head = repo.head()?.peel_to_commit()?.id();
+        return Ok(result("up_to_date", head));
+    }
+    if analysis.is_unborn() {
+        checkout(repo, &their_commit.tree()?)?;
+        repo.reference(&local_ref, theirs.id(), true, "pull: initial")?;
+        return Ok(result("fast_forward", theirs.id()));
+    }
+    if analysis.is_fast_forward() {
+        checkout(repo, &their_commit.tree()?)?;
+        repo.head()?.set_target(theirs.id(), "pull: fast-forward")?;
+        return Ok(result("fast_forward", theirs.id()));
+    }
+
+    // 在内存中合并，有冲突时直接返回，工作目录保持不变
+    let head_commit = repo.head()?.peel_to_commit()?;
+    let mut index = repo.merge_commits(&head_commit, &their_commit, None)?;
+    if index.has_conflicts() {
+        return Err(GitError::Conflicts(repo::conflicted_paths(&index)?));
+    }
+    let signature = repo::signature(repo, author)?;
+    let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+    checkout(repo, &tree)?;
+    let message = format!("Merge branch '{}' of {}", branch, remote_name);
+    let id = repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &[&head_commit, &their_commit])?;
+    Ok(result("merged", id))
+}
@@ -0,0 +1,470 @@
+// 本地仓库操作
+// 状态、差异、暂存、提交和历史，均为阻塞调用 (由处理器放到阻塞线程中执行)
+
+use git2::{
+    Delta, DiffFormat, DiffOptions, IndexAddOption, Repository, RepositoryState, Signature, Status, StatusOptions,
+};
+use serde::Serialize;
+use std::path::Path;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 差异文本的最大字节数 (超出部分截断)
+pub const MAX_DIFF_BYTES: usize = 1024 * 1024;
+
+/// 提交作者 (未指定时使用仓库配置的 user.name / user.email)
+#[derive(Debug, Clone, Default)]
+pub struct Author {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+// ============================================================================
+// 错误
+// ============================================================================
+
+/// Git 模块错误
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("不是 Git 仓库: {0}")]
+    NotRepository(String),
+
+    #[error("没有要提交的更改")]
+    NothingToCommit,
+
+    #[error("未配置提交作者 (user.name / user.email)")]
+    MissingAuthor,
+
+    #[error("存在冲突的文件: {}", .0.join(", "))]
+    Conflicts(Vec<String>),
+
+    #[error("推送被拒绝: {0}")]
+    Rejected(String),
+
+    #[error("仓库处于未完成的 {0} 状态")]
+    Unfinished(String),
+
+    #[error("操作已取消")]
+    Cancelled,
+
+    #[error("{}", .0.message())]
+    Git(#[from] git2::Error),
+}
+
+impl CodedError for GitError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            GitError::NotRepository(_) => ErrorCode::NotFound,
+            GitError::NothingToCommit => ErrorCode::InvalidParams,
+            GitError::MissingAuthor => ErrorCode::InvalidConfig,
+            GitError::Conflicts(_) | GitError::Rejected(_) | GitError::Unfinished(_) => ErrorCode::Conflict,
+            GitError::Cancelled => ErrorCode::Cancelled,
+            GitError::Git(e) => match (e.code(), e.class()) {
+                (git2::ErrorCode::NotFound, _) => ErrorCode::NotFound,
+                (git2::ErrorCode::Auth | git2::ErrorCode::Certificate, _) => ErrorCode::InvalidConfig,
+                (
+                    git2::ErrorCode::Conflict
+                    | git2::ErrorCode::MergeConflict
+                    | git2::ErrorCode::NotFastForward
+                    | git2::ErrorCode::Locked
+                    | git2::ErrorCode::Uncommitted,
+                    _,
+                ) => ErrorCode::Conflict,
+                (git2::ErrorCode::InvalidSpec | git2::ErrorCode::Invalid, _) => ErrorCode::InvalidParams,
+                (_, git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssh | git2::ErrorClass::Ssl) => {
+                    ErrorCode::NetworkError
+                }
+                (_, git2::ErrorClass::Os | git2::ErrorClass::Filesystem) => ErrorCode::IoError,
+                _ => ErrorCode::Internal,
+            },
+        }
+    }
+}
+
+/// 打开仓库 (路径为工作目录或 .git 目录，不向上查找)
+pub fn open(path: &Path) -> Result<Repository, GitError> {
+    Repository::open(path).map_err(|e| match e.code() {
+        git2::ErrorCode::NotFound => GitError::NotRepository(path.display().to_string()),
+        _ => GitError::Git(e),
+    })
+}
+
+/// 仓库不在普通状态 (合并、变基等未完成) 时返回错误
+pub fn ensure_clean_state(repo: &Repository) -> Result<(), GitError> {
+    match repo.state() {
+        RepositoryState::Clean => Ok(()),
+        state => Err(GitError::Unfinished(format!("{:?}", state).to_lowercase())),
+    }
+}
+
+/// 暂存区中有冲突的路径
+pub fn conflicted_paths(index: &git2::Index) -> Result<Vec<String>, GitError> {
+    Ok(index.conflicts()?
+        .filter_map(|conflict| conflict.ok())
+        .filter_map(|conflict| conflict.our.or(conflict.their).or(conflict.ancestor))
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .collect())
+}
+
+/// 签名：指定的作者优先，缺少的部分使用仓库配置
+pub fn signature(repo: &Repository, author: &Author) -> Result<Signature<'static>, GitError> {
+    let configured = repo.signature().ok();
+    let name = author.name.clone().or_else(|| configured.as_ref().and_then(|s| s.name().ok().map(str::to_string)));
+    let email = author.email.clone().or_else(|| configured.as_ref().and_then(|s| s.email().ok().map(str::to_string)));
+    match (name, email) {
+        (Some(name), Some(email)) if !name.is_empty() && !email.is_empty() => Ok(Signature::now(&name, &email)?),
+        _ => Err(GitError::MissingAuthor),
+    }
+}
+
+// ============================================================================
+// 状态
+// ============================================================================
+
+/// 文件的变更状态
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileStatus {
+    pub path: String,
+    /// 重命名前的路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    /// 已暂存的变更 (new / modified / deleted / renamed / typechange / conflicted)
+    pub staged: Option<&'static str>,
+    /// 未暂存的变更 (同上，未跟踪的文件为 new)
+    pub unstaged: Option<&'static str>,
+}
+
+/// 仓库状态
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RepoStatus {
+    /// 当前分支 (分离 HEAD 时为 None)
+    pub branch: Option<String>,
+    /// HEAD 提交 (尚无提交时为 None)
+    pub head: Option<String>,
+    /// 上游分支 (如 origin/main)
+    pub upstream: Option<String>,
+    /// 领先 / 落后上游的提交数
+    pub ahead: usize,
+    pub behind: usize,
+    /// 仓库状态 (clean / merge / rebase 等)
+    pub state: String,
+    pub files: Vec<FileStatus>,
+}
+
+fn staged_kind(status: Status) -> Option<&'static str> {
+    if status.is_conflicted() {
+        Some("conflicted")
+    } else if status.is_index_new() {
+        Some("new")
+    } else if status.is_index_modified() {
+        Some("modified")
+    } else if status.is_index_deleted() {
+        Some("deleted")
+    } else if status.is_index_renamed() {
+        Some("renamed")
+    } else if status.is_index_typechange() {
+        Some("typechange")
+    } else {
+        None
+    }
+}
+
+fn unstaged_kind(status: Status) -> Option<&'static str> {
+    if status.is_conflicted() {
+        Some("conflicted")
+    } else if status.is_wt_new() {
+        Some("new")
+    } else if status.is_wt_modified() {
+        Some("modified")
+    } else if status.is_wt_deleted() {
+        Some("deleted")
+    } else if status.is_wt_renamed() {
+        Some("renamed")
+    } else if status.is_wt_typechange() {
+        Some("typechange")
+    } else {
+        None
+    }
+}
+
+/// 读取仓库状态 (不含被忽略的文件)
+pub fn status(repo: &Repository) -> Result<RepoStatus, GitError> {
+    let head = repo.head().ok();
+    let branch = head.as_ref().filter(|head| head.is_branch()).and_then(|head| head.shorthand().ok().map(str::to_string));
+    let head_oid = head.as_ref().and_then(|head| head.target());
+
+    let mut upstream = None;
+    let (mut ahead, mut behind) = (0, 0);
+    if let Some(name) = &branch {
+        if let Ok(upstream_branch) = repo.find_branch(name, git2::BranchType::Local).and_then(|b| b.upstream()) {
+            upstream = upstream_branch.name().ok().flatten().map(str::to_string);
+            if let (Some(local), Some(remote)) = (head_oid, upstream_branch.get().target()) {
+                (ahead, behind) = repo.graph_ahead_behind(local, remote)?;
+            }
+        }
+    }
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    let statuses = repo.statuses(Some(&mut options))?;
+    let files = statuses.iter().filter_map(|entry| {
+        let status = entry.status();
+        // 重命名时 entry.path() 为原路径，取差异中的新路径
+        let renamed = entry.head_to_index().filter(|delta| delta.status() == Delta::Renamed);
+        let path = match renamed.as_ref().and_then(|delta| delta.new_file().path()) {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => entry.path().ok()?.to_string(),
+        };
+        let old_path = renamed.and_then(|delta| delta.old_file().path().map(|path| path.to_string_lossy().into_owned()));
+        Some(FileStatus {
+            path,
+            old_path,
+            staged: staged_kind(status),
+            unstaged: unstaged_kind(status),
+        })
+    }).collect();
+
+    Ok(RepoStatus {
+        branch,
+        head: head_oid.map(|oid| oid.to_string()),
+        upstream,
+        ahead,
+        behind,
+        state: match repo.state() {
+            RepositoryState::Clean => "clean".to_string(),
+            state => format!("{:?}", state).to_lowercase(),
+        },
+        files,
+    })
+}
+
+// ============================================================================
+// 差异
+// ============================================================================
+
+/// 差异
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RepoDiff {
+    /// 统一差异格式的补丁文本
+    pub patch: String,
+    /// 补丁文本是否因超过上限被截断
+    pub truncated: bool,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// 差异：staged 为 true 时比较 HEAD 与暂存区，否则比较暂存区与工作目录 (含未跟踪的文件)
+pub fn diff(repo: &Repository, staged: bool, paths: &[String]) -> Result<RepoDiff, GitError> {
+    let mut options = DiffOptions::new();
+    for path in paths {
+        options.pathspec(path);
+    }
+    let diff = if staged {
+        let head_tree = match repo.head() {
+            Ok(head) => Some(head.peel_to_tree()?),
+            Err(_) => None,
+        };
+        repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut options))?
+    } else {
+        options.include_untracked(true).recurse_untracked_dirs(true).show_untracked_content(true);
+        repo.diff_index_to_workdir(None, Some(&mut options))?
+    };
+
+    let stats = diff.stats()?;
+    let mut patch = String::new();
+    let mut truncated = false;
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        let content = String::from_utf8_lossy(line.content());
+        let prefix = match line.origin() {
+            origin @ ('+' | '-' | ' ') => Some(origin),
+            _ => None,
+        };
+        let added = prefix.map_or(0, |_| 1) + content.len();
+        if patch.len() + added > MAX_DIFF_BYTES {
+            truncated = true;
+            return false;
+        }
+        if let Some(prefix) = prefix {
+            patch.push(prefix);
+        }
+        patch.push_str(&content);
+        true
+    }).or_else(|e| if truncated { Ok(()) } else { Err(e) })?;
+
+    Ok(RepoDiff {
+        patch,
+        truncated,
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+// ============================================================================
+// 暂存
+// ============================================================================
+
+/// 暂存路径 (含删除)，paths 为空时暂存全部变更
+pub fn stage(repo: &Repository, paths: &[String]) -> Result<(), GitError> {
+    let all = ["*".to_string()];
+    let specs = if paths.is_empty() { &all[..] } else { paths };
+    let mut index = repo.index()?;
+    index.add_all(specs, IndexAddOption::DEFAULT, None)?;
+    index.update_all(specs, None)?;
+    index.write()?;
+    Ok(())
+}
+
+/// 取消暂存 (暂存区恢复为 HEAD 中的内容，工作目录不变)，paths 为空时取消全部
+pub fn unstage(repo: &Repository, paths: &[String]) -> Result<(), GitError> {
+    let all = ["*".to_string()];
+    let specs = if paths.is_empty() { &all[..] } else { paths };
+    match repo.head().ok().and_then(|head| head.peel_to_commit().ok()) {
+        Some(commit) => repo.reset_default(Some(commit.as_object()), specs)?,
+        // 尚无提交时从暂存区移除
+        None => {
+            let mut index = repo.index()?;
+            index.remove_all(specs, None)?;
+            index.write()?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// 提交和历史
+// ============================================================================
+
+/// 提交
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommitInfo {
+    pub id: String,
+    pub summary: String,
+    pub message: String,
+    pub author: String,
+    pub email: String,
+    /// 提交时间 (Unix 秒)
+    pub time: i64,
+    pub parents: Vec<String>,
+}
+
+impl CommitInfo {
+    fn new(commit: &git2::Commit<'_>) -> Self {
+        let author = commit.author();
+        Self {
+            id: commit.id().to_string(),
+            summary: commit.summary().ok().flatten().unwrap_or_default().to_string(),
+            message: commit.message().unwrap_or_default().to_string(),
+            author: author.name().unwrap_or_default().to_string(),
+            email: author.email().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+            parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        }
+    }
+}
+
+/// 提交暂存区 (暂存区与 HEAD 相同时返回 NothingToCommit)
+pub fn commit(repo: &Repository, message: &str, author: &Author) -> Result<CommitInfo, GitError> {
+    ensure_clean_state(repo)?;
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Err(GitError::Conflicts(conflicted_paths(&index)?));
+    }
+    let tree_id = index.write_tree()?;
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let unchanged = match &parent {
+        Some(parent) => parent.tree_id() == tree_id,
+        None => index.is_empty(),
+    };
+    if unchanged {
+        return Err(GitError::NothingToCommit);
+    }
+
+    let signature = signature(repo, author)?;
+    let tree = repo.find_tree(tree_id)?;
+    let parents: Vec<&git2::Commit<'_>> = parent.iter().collect();
+    let id = repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?;
+    Ok(CommitInfo::new(&repo.find_commit(id)?))
+}
+
+/// 从 HEAD 开始的提交历史 (最新的在前)，指定 path 时只包含修改了该路径的提交
+pub fn log(repo: &Repository, limit: usize, path: Option<&str>) -> Result<Vec<CommitInfo>, GitError> {
+    if repo.head().is_err() {
+        return Ok(Vec::new());
+    }
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    walk.set_sorting(git2::Sort::TIME)?;
+
+    let mut commits = Vec::new();
+    for id in walk {
+        if commits.len() >= limit {
+            break;
+        }
+        let commit = repo.find_commit(id?)?;
+        if let Some(path) = path {
+            if !touches(&commit, Path::new(path))? {
+                continue;
+            }
+        }
+        commits.push(CommitInfo::new(&commit));
+    }
+    Ok(commits)
+}
+
+/// 提交是否修改了路径 (与第一个父提交比较，根提交与空树比较)
+fn touches(commit: &git2::Commit<'_>, path: &Path) -> Result<bool, GitError> {
+    let entry_id = |tree: &git2::Tree<'_>| tree.get_path(path).ok().map(|entry| entry.id());
+    let current = entry_id(&commit.tree()?);
+    let previous = match commit.parents().next() {
+        Some(parent) => entry_id(&parent.tree()?),
+        None => None,
+    };
+    Ok(current != previous)
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_rename_and_unstage() {
+        let dir = std::env::temp_dir().join(format!("git-repo-test-{}", uuid::Uuid::new_v4()));
+        let repo = Repository::init(&dir).unwrap();
+        let author = Author { name: Some("Test".to_string()), email: Some("test@example.com".to_string()) };
+
+        // 尚无提交时取消暂存即从暂存区移除
+        std::fs::write(dir.join("a.md"), "a\n").unwrap();
+        stage(&repo, &[]).unwrap();
+        unstage(&repo, &[]).unwrap();
+        assert_eq!(status(&repo).unwrap().files[0].unstaged, Some("new"));
+        stage(&repo, &[]).unwrap();
+        commit(&repo, "init", &author).unwrap();
+        assert!(matches!(commit(&repo, "again", &author), Err(GitError::NothingToCommit)));
+
+        std::fs::rename(dir.join("a.md"), dir.join("b.md")).unwrap();
+        stage(&repo, &[]).unwrap();
+        let files = status(&repo).unwrap().files;
+        assert_eq!(files, vec![FileStatus {
+            path: "b.md".to_string(),
+            old_path: Some("a.md".to_string()),
+            staged: Some("renamed"),
+            unstaged: None,
+        }]);
+        unstage(&repo, &["b.md".to_string()]).unwrap();
+        let files = status(&repo).unwrap().files;
+        assert!(files.iter().any(|file| file.path == "b.md" && file.staged.is_none() && file.unstaged == Some("new")));
+        assert!(files.iter().any(|file| file.path == "a.md" && file.staged == Some("deleted")));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
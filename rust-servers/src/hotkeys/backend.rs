@@ -0,0 +1,173 @@
+// 全局快捷键注册
+// 系统后端通过 global-hotkey 注册 (Windows / X11)。管理器及其平台资源 (Windows 上的隐藏窗口、
+// X11 连接) 由进程内唯一的后台线程持有，注册和注销以命令的形式发送到该线程执行
+
+use global_hotkey::hotkey::HotKey;
+use std::sync::{Mutex, OnceLock};
+use thiserror::Error;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 快捷键错误
+#[derive(Debug, Error)]
+pub enum HotkeyError {
+    #[error("全局快捷键不可用: {0}")]
+    Unavailable(String),
+
+    #[error("无法注册快捷键 {0}: {1}")]
+    RegisterFailed(String, String),
+}
+
+impl CodedError for HotkeyError {
+    fn code(&self) -> ErrorCode {
+        ErrorCode::DeviceError
+    }
+}
+
+/// 快捷键按下或松开
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotkeyEvent {
+    /// 快捷键 id (HotKey::id)
+    pub id: u32,
+    pub pressed: bool,
+}
+
+/// 快捷键后端 (事件发送到打开后端时传入的通道)
+pub trait HotkeyBackend: Send {
+    fn register(&mut self, hotkey: HotKey) -> Result<(), HotkeyError>;
+    fn unregister(&mut self, hotkey: HotKey) -> Result<(), HotkeyError>;
+}
+
+// ============================================================================
+// 系统后端
+// ============================================================================
+
+/// 发送到后台线程的命令
+enum Command {
+    Register(HotKey, std::sync::mpsc::Sender<Result<(), String>>),
+    Unregister(HotKey, std::sync::mpsc::Sender<Result<(), String>>),
+}
+
+/// 当前接收事件的通道 (global-hotkey 的事件回调是进程级的，只能设置一次)
+static EVENTS: Mutex<Option<UnboundedSender<HotkeyEvent>>> = Mutex::new(None);
+
+/// 后台线程的命令通道 (首次使用时启动线程)
+static THREAD: OnceLock<Result<std::sync::mpsc::Sender<Command>, String>> = OnceLock::new();
+
+/// 系统快捷键 (后台线程的句柄)
+struct SystemHotkeys {
+    commands: std::sync::mpsc::Sender<Command>,
+}
+
+impl SystemHotkeys {
+    fn call(&self, command: impl FnOnce(std::sync::mpsc::Sender<Result<(), String>>) -> Command) -> Result<(), String> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.commands.send(command(tx)).map_err(|_| "快捷键线程已退出".to_string())?;
+        rx.recv().map_err(|_| "快捷键线程已退出".to_string())?
+    }
+}
+
+impl HotkeyBackend for SystemHotkeys {
+    fn register(&mut self, hotkey: HotKey) -> Result<(), HotkeyError> {
+        self.call(|tx| Command::Register(hotkey, tx))
+            .map_err(|e| HotkeyError::RegisterFailed(hotkey.into_string(), e))
+    }
+
+    fn unregister(&mut self, hotkey: HotKey) -> Result<(), HotkeyError> {
+        self.call(|tx| Command::Unregister(hotkey, tx))
+            .map_err(|e| HotkeyError::RegisterFailed(hotkey.into_string(), e))
+    }
+}
+
+/// 启动持有管理器的后台线程，管理器创建失败时返回错误
+#[cfg(not(target_os = "macos"))]
+fn spawn_thread() -> Result<std::sync::mpsc::Sender<Command>, String> {
+    // X11 连接在管理器自己的线程中建立，连接失败时注册不会报错，因此预先检查
+    #[cfg(target_os = "linux")]
+    if std::env::var_os("DISPLAY").is_none() {
+        return Err("未找到 X11 显示 (DISPLAY 未设置)".to_string());
+    }
+
+    global_hotkey::GlobalHotKeyEvent::set_event_handler(Some(|event: global_hotkey::GlobalHotKeyEvent| {
+        let pressed = event.state() == global_hotkey::HotKeyState::Pressed;
+        if let Some(tx) = &*EVENTS.lock().unwrap_or_else(|e| e.into_inner()) {
+            let _ = tx.send(HotkeyEvent { id: event.id(), pressed });
+        }
+    }));
+
+    let (commands_tx, commands_rx) = std::sync::mpsc::channel::<Command>();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("hotkeys".to_string())
+        .spawn(move || {
+            let manager = match global_hotkey::GlobalHotKeyManager::new() {
+                Ok(manager) => {
+                    let _ = ready_tx.send(Ok(()));
+                    manager
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            loop {
+                // Windows 上快捷键消息投递到创建管理器的线程，需要在此线程分发
+                #[cfg(windows)]
+                pump_messages();
+                match commands_rx.recv_timeout(std::time::Duration::from_millis(10)) {
+                    Ok(Command::Register(hotkey, reply)) => {
+                        let _ = reply.send(manager.register(hotkey).map_err(|e| e.to_string()));
+                    }
+                    Ok(Command::Unregister(hotkey, reply)) => {
+                        let _ = reply.send(manager.unregister(hotkey).map_err(|e| e.to_string()));
+                    }
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    ready_rx.recv().map_err(|_| "快捷键线程已退出".to_string())??;
+    Ok(commands_tx)
+}
+
+/// macOS 上快捷键需要在主线程的事件循环中注册，服务器没有事件循环
+#[cfg(target_os = "macos")]
+fn spawn_thread() -> Result<std::sync::mpsc::Sender<Command>, String> {
+    Err("macOS 上暂不支持".to_string())
+}
+
+/// 分发当前线程的窗口消息
+#[cfg(windows)]
+fn pump_messages() {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE};
+    unsafe {
+        let mut msg: MSG = std::mem::zeroed();
+        while PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+}
+
+fn open_system(events: UnboundedSender<HotkeyEvent>) -> Result<Box<dyn HotkeyBackend>, HotkeyError> {
+    let commands = THREAD.get_or_init(spawn_thread).clone().map_err(HotkeyError::Unavailable)?;
+    *EVENTS.lock().unwrap_or_else(|e| e.into_inner()) = Some(events);
+    Ok(Box::new(SystemHotkeys { commands }))
+}
+
+/// 打开系统快捷键后端
+#[cfg(not(any(test, feature = "test-support")))]
+pub fn open(events: UnboundedSender<HotkeyEvent>) -> Result<Box<dyn HotkeyBackend>, HotkeyError> {
+    open_system(events)
+}
+
+/// 打开快捷键后端 (安装了模拟快捷键时使用模拟快捷键)
+#[cfg(any(test, feature = "test-support"))]
+pub fn open(events: UnboundedSender<HotkeyEvent>) -> Result<Box<dyn HotkeyBackend>, HotkeyError> {
+    if crate::testing::hotkeys::is_installed() {
+        return Ok(Box::new(crate::testing::hotkeys::FakeHotkeys::new(events)));
+    }
+    open_system(events)
+}
@@ -0,0 +1,309 @@
+// 全局快捷键模块
+// 注册系统级快捷键 (如语音模块的按住说话)，Obsidian 不在前台时也能触发。快捷键按下和松开时
+// 向注册它的连接推送 hotkey_pressed / hotkey_released 事件，按住不放时不重复推送
+
+mod backend;
+
+pub use backend::{HotkeyBackend, HotkeyError, HotkeyEvent};
+
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use futures_util::SinkExt;
+use global_hotkey::hotkey::HotKey;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::Mutex as TokioMutex;
+use tokio_tungstenite::tungstenite::Message;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Hotkeys", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Hotkeys", format!($($arg)*));
+        }
+    };
+}
+
+/// 同时注册的快捷键数上限
+const MAX_HOTKEYS: usize = 32;
+
+/// 创建快捷键模块错误
+fn hotkeys_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Hotkeys, code, message)
+}
+
+/// 解析快捷键 (如 "ctrl+shift+Space"、"CmdOrCtrl+KeyR")
+fn parse_hotkey(hotkey: &str) -> Result<HotKey, ModuleError> {
+    hotkey.parse()
+        .map_err(|e| hotkeys_error(ErrorCode::InvalidParams, format!("无效的快捷键 '{}': {}", hotkey, e)))
+}
+
+// ============================================================================
+// 快捷键状态
+// ============================================================================
+
+/// 已注册快捷键的信息
+#[derive(Debug, Clone, Serialize)]
+struct HotkeyInfo {
+    id: u32,
+    /// 规范化后的快捷键 (如 "shift+control+Space")
+    hotkey: String,
+    name: Option<String>,
+}
+
+/// 已注册的快捷键
+struct Registration {
+    info: HotkeyInfo,
+    hotkey: HotKey,
+    /// 事件发送到注册该快捷键的连接
+    sender: WsSender,
+}
+
+/// 快捷键后端及已注册的快捷键 (后端在阻塞线程中访问)
+struct HotkeysState {
+    /// 首次注册时打开
+    backend: Mutex<Option<Box<dyn HotkeyBackend>>>,
+    /// 已注册的快捷键: id → Registration
+    registrations: Mutex<HashMap<u32, Registration>>,
+}
+
+impl HotkeysState {
+    fn registrations(&self) -> std::sync::MutexGuard<'_, HashMap<u32, Registration>> {
+        self.registrations.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn with_backend<T>(&self, f: impl FnOnce(&mut dyn HotkeyBackend) -> Result<T, HotkeyError>) -> Result<T, HotkeyError> {
+        let mut backend = self.backend.lock().unwrap_or_else(|e| e.into_inner());
+        let backend = backend.as_deref_mut()
+            .ok_or_else(|| HotkeyError::Unavailable("后端未打开".to_string()))?;
+        f(backend)
+    }
+
+    /// 注销快捷键并移除登记
+    fn remove(&self, id: u32) -> Result<Option<HotkeyInfo>, HotkeyError> {
+        let Some(registration) = self.registrations().remove(&id) else {
+            return Ok(None);
+        };
+        self.with_backend(|backend| backend.unregister(registration.hotkey))?;
+        Ok(Some(registration.info))
+    }
+}
+
+/// 在阻塞线程中访问快捷键后端
+async fn blocking<T: Send + 'static>(
+    state: &Arc<HotkeysState>,
+    f: impl FnOnce(&HotkeysState) -> Result<T, HotkeyError> + Send + 'static,
+) -> Result<T, RouterError> {
+    let state = Arc::clone(state);
+    tokio::task::spawn_blocking(move || f(&state))
+        .await
+        .map_err(|e| hotkeys_error(ErrorCode::Internal, format!("快捷键任务失败: {}", e)))?
+        .map_err(|e| ModuleError::from_error(ModuleType::Hotkeys, &e).into())
+}
+
+// ============================================================================
+// 快捷键处理器
+// ============================================================================
+
+/// 全局快捷键模块处理器
+pub struct HotkeysHandler {
+    state: Arc<HotkeysState>,
+    /// 事件分发任务 (与后端同时创建)
+    dispatch_task: TokioMutex<Option<tokio::task::JoinHandle<()>>>,
+    /// WebSocket 发送器 (请求没有来源连接时用于广播事件)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl HotkeysHandler {
+    /// 创建新的快捷键处理器
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(HotkeysState {
+                backend: Mutex::new(None),
+                registrations: Mutex::new(HashMap::new()),
+            }),
+            dispatch_task: TokioMutex::new(None),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    /// 打开后端并启动事件分发任务 (已打开时不做任何事)
+    async fn ensure_backend(&self) -> Result<(), RouterError> {
+        let mut dispatch_task = self.dispatch_task.lock().await;
+        if dispatch_task.is_some() {
+            return Ok(());
+        }
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        blocking(&self.state, move |state| {
+            *state.backend.lock().unwrap_or_else(|e| e.into_inner()) = Some(backend::open(tx)?);
+            Ok(())
+        }).await?;
+        *dispatch_task = Some(tokio::spawn(dispatch_events(Arc::clone(&self.state), rx)));
+        Ok(())
+    }
+
+    /// 处理 register 消息 - 注册全局快捷键
+    async fn handle_register(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let text: String = msg.get_field("hotkey")
+            .ok_or_else(|| hotkeys_error(ErrorCode::InvalidParams, "缺少 hotkey 字段"))?;
+        let name: Option<String> = msg.get_field("name");
+        let hotkey = parse_hotkey(&text)?;
+        {
+            let registrations = self.state.registrations();
+            if registrations.contains_key(&hotkey.id()) {
+                return Err(hotkeys_error(ErrorCode::InvalidParams, format!("快捷键已注册: {}", hotkey)).into());
+            }
+            if registrations.len() >= MAX_HOTKEYS {
+                return Err(hotkeys_error(ErrorCode::LimitExceeded, format!("快捷键过多 (最多 {} 个)", MAX_HOTKEYS)).into());
+            }
+        }
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+        let sender = sender.ok_or_else(|| hotkeys_error(ErrorCode::NotConnected, "WebSocket sender not set"))?;
+
+        self.ensure_backend().await?;
+        blocking(&self.state, move |state| state.with_backend(|backend| backend.register(hotkey))).await?;
+
+        let info = HotkeyInfo { id: hotkey.id(), hotkey: hotkey.into_string(), name };
+        log_info!("已注册快捷键: {} (name={:?})", info.hotkey, info.name);
+        let response = ServerResponse::new(ModuleType::Hotkeys, "registered", serde_json::to_value(&info).unwrap_or_default());
+        self.state.registrations().insert(info.id, Registration { info, hotkey, sender });
+        Ok(Some(response))
+    }
+
+    /// 处理 unregister 消息 - 注销快捷键
+    async fn handle_unregister(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let text: String = msg.get_field("hotkey")
+            .ok_or_else(|| hotkeys_error(ErrorCode::InvalidParams, "缺少 hotkey 字段"))?;
+        let id = parse_hotkey(&text)?.id();
+        let info = blocking(&self.state, move |state| state.remove(id)).await?
+            .ok_or_else(|| hotkeys_error(ErrorCode::NotFound, format!("快捷键未注册: {}", text)))?;
+        log_info!("已注销快捷键: {}", info.hotkey);
+        Ok(Some(ServerResponse::new(ModuleType::Hotkeys, "unregistered", serde_json::to_value(&info).unwrap_or_default())))
+    }
+
+    /// 处理 list 消息 - 列出已注册的快捷键
+    fn handle_list(&self) -> ServerResponse {
+        let registrations = self.state.registrations();
+        let mut list: Vec<&HotkeyInfo> = registrations.values().map(|registration| &registration.info).collect();
+        list.sort_by(|a, b| a.hotkey.cmp(&b.hotkey));
+        ServerResponse::new(ModuleType::Hotkeys, "hotkeys", serde_json::json!({ "hotkeys": list }))
+    }
+}
+
+impl Default for HotkeysHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 将快捷键事件发送到注册它的连接 (连接已断开时注销该快捷键)
+async fn dispatch_events(state: Arc<HotkeysState>, mut rx: UnboundedReceiver<HotkeyEvent>) {
+    while let Some(event) = rx.recv().await {
+        let target = state.registrations().get(&event.id)
+            .map(|registration| (registration.info.clone(), registration.sender.clone()));
+        let Some((info, sender)) = target else {
+            continue;
+        };
+        let event_type = if event.pressed { "hotkey_pressed" } else { "hotkey_released" };
+        log_debug!("{}: {}", event_type, info.hotkey);
+        let event = ServerResponse::new(ModuleType::Hotkeys, event_type, serde_json::to_value(&info).unwrap_or_default());
+        if sender.lock().await.send(Message::Text(event.to_json().into())).await.is_err() {
+            log_info!("连接已断开，注销快捷键: {}", info.hotkey);
+            let removed = Arc::clone(&state);
+            let _ = tokio::task::spawn_blocking(move || removed.remove(info.id)).await;
+        }
+    }
+}
+
+/// 快捷键模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("register", &[
+        FieldSpec::required("hotkey", FieldKind::String),
+        FieldSpec::optional("name", FieldKind::String),
+    ]),
+    MessageSpec::new("unregister", &[
+        FieldSpec::required("hotkey", FieldKind::String),
+    ]),
+    MessageSpec::new("list", &[]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 全局快捷键模块
+    pub const Hotkeys: ModuleType = ModuleType::named("hotkeys");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for HotkeysHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Hotkeys
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    /// 注销所有快捷键 (连接关闭时调用)
+    async fn cleanup(&self) {
+        let ids: Vec<u32> = self.state.registrations().keys().copied().collect();
+        for id in ids {
+            match blocking(&self.state, move |state| state.remove(id)).await {
+                Ok(Some(info)) => {
+                    log_info!("已注销快捷键: {}", info.hotkey);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log_info!("注销快捷键失败: {}", e);
+                }
+            }
+        }
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理快捷键消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "register" => self.handle_register(msg).await,
+            "unregister" => self.handle_unregister(msg).await,
+            "list" => Ok(Some(self.handle_list())),
+            _ => Err(hotkeys_error(ErrorCode::UnknownMessageType, format!("未知的快捷键消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hotkey() {
+        let hotkey = parse_hotkey("ctrl+shift+Space").unwrap();
+        assert_eq!(hotkey.into_string(), "shift+control+Space");
+        assert_eq!(parse_hotkey("Shift+Control+space").unwrap().id(), hotkey.id());
+        assert!(parse_hotkey("ctrl+").is_err());
+        assert!(parse_hotkey("ctrl+NoSuchKey").is_err());
+    }
+}
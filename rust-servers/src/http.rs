@@ -0,0 +1,659 @@
+// HTTP REST 桥接
+// 与 WebSocket 共用监听地址：不是 WebSocket 升级的 HTTP 请求由此处理，
+// 脚本和其他插件无需实现 WebSocket 协议即可调用服务器 (每个连接处理一个请求)
+//
+// GET  /health           健康检查 (无需令牌)
+// POST /detect-language  语言检测 (utils/detect_language)
+// POST /transcribe       转录 WAV 音频 (voice/transcribe)
+// POST /exec             执行任意协议消息，返回其响应
+// GET  /files/<path>     数据目录中生成的文件 (启用 http.files 时；支持 Range，插件可直接以 URL 嵌入)
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+
+use crate::router::{ErrorCode, MessageRouter, ModuleMessage, ModuleType, RouterError, ServerResponse};
+use crate::server::{token_from_query, tokens_match};
+use crate::system::locale::Locale;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Http", format!($($arg)*));
+    };
+}
+
+/// 请求头最大字节数
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// 请求体最大字节数 (base64 编码的音频)
+const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+/// 读取请求头的超时
+const HEAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 请求头最多解析的字段数
+const MAX_HEADERS: usize = 64;
+
+// ============================================================================
+// 请求识别
+// ============================================================================
+
+/// 已读取请求头的连接
+pub enum Accepted<S> {
+    /// WebSocket 升级请求 (已读取的字节会重新交给握手处理)
+    WebSocket(Prefixed<S>),
+    /// REST 请求
+    Rest(Request, S),
+}
+
+/// REST 请求
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// 获取请求头 (名称不区分大小写)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 请求携带的令牌 (`Authorization: Bearer <token>` 或 `?token=`)
+    fn token(&self) -> Option<String> {
+        self.header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string())
+            .or_else(|| self.query.as_deref().and_then(token_from_query))
+    }
+}
+
+/// 读取请求头，区分 WebSocket 升级请求和 REST 请求 (REST 请求同时读取请求体)
+pub async fn accept<S>(mut stream: S) -> io::Result<Accepted<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buf = Vec::new();
+    let (head_len, head) = tokio::time::timeout(HEAD_TIMEOUT, read_head(&mut stream, &mut buf))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "读取请求头超时"))??;
+
+    let upgrade = head.headers.iter()
+        .any(|(key, value)| key.eq_ignore_ascii_case("upgrade") && value.eq_ignore_ascii_case("websocket"));
+    if upgrade {
+        return Ok(Accepted::WebSocket(Prefixed::new(buf, stream)));
+    }
+
+    let content_length = head.header("content-length")
+        .map(|value| value.trim().parse::<usize>())
+        .transpose()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Content-Length 无效"))?
+        .unwrap_or(0);
+    if content_length > MAX_BODY_BYTES {
+        write_json(&mut stream, StatusCode::PAYLOAD_TOO_LARGE, &error_body(
+            ErrorCode::LimitExceeded,
+            &format!("请求体超过 {} 字节", MAX_BODY_BYTES),
+        )).await?;
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "请求体过大"));
+    }
+
+    let mut body = buf.split_off(head_len);
+    body.truncate(content_length);
+    let received = body.len();
+    body.resize(content_length, 0);
+    stream.read_exact(&mut body[received..]).await?;
+
+    Ok(Accepted::Rest(Request { body, ..head }, stream))
+}
+
+/// 读取到请求头结束，返回 (请求头长度, 请求头)
+async fn read_head<S>(stream: &mut S, buf: &mut Vec<u8>) -> io::Result<(usize, Request)>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+
+        let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut request = httparse::Request::new(&mut headers);
+        match request.parse(buf) {
+            Ok(httparse::Status::Complete(len)) => {
+                let target = request.path.unwrap_or("/");
+                let (path, query) = match target.split_once('?') {
+                    Some((path, query)) => (path, Some(query.to_string())),
+                    None => (target, None),
+                };
+                let head = Request {
+                    method: request.method.unwrap_or_default().to_string(),
+                    path: path.to_string(),
+                    query,
+                    headers: request.headers.iter()
+                        .map(|h| (h.name.to_string(), String::from_utf8_lossy(h.value).into_owned()))
+                        .collect(),
+                    body: Vec::new(),
+                };
+                return Ok((len, head));
+            }
+            Ok(httparse::Status::Partial) if buf.len() < MAX_HEAD_BYTES => {}
+            Ok(httparse::Status::Partial) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "请求头过大"));
+            }
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+/// 先返回已读取的字节，再从底层流读取
+pub struct Prefixed<S> {
+    prefix: Vec<u8>,
+    position: usize,
+    inner: S,
+}
+
+impl<S> Prefixed<S> {
+    fn new(prefix: Vec<u8>, inner: S) -> Self {
+        Self { prefix, position: 0, inner }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Prefixed<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        if self.position < self.prefix.len() {
+            let n = buf.remaining().min(self.prefix.len() - self.position);
+            let start = self.position;
+            buf.put_slice(&self.prefix[start..start + n]);
+            self.position += n;
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Prefixed<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+// ============================================================================
+// 请求处理
+// ============================================================================
+
+/// 处理 REST 请求并写回 JSON 响应
+///
+/// 设置 `files` 时 `/files/` 下的路径映射到该目录中的文件
+pub async fn serve<S>(
+    request: Request,
+    mut stream: S,
+    router: &MessageRouter,
+    token: &str,
+    files: Option<&Path>,
+) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let result = match (files, request.path.strip_prefix("/files/")) {
+        (Some(root), Some(relative)) => match open_file(&request, root, relative, token).await {
+            Ok(file) => {
+                log_info!("{} {} -> {}", request.method, request.path, file.status().as_u16());
+                return write_file(&mut stream, file, request.method == "HEAD").await;
+            }
+            Err(error) => error,
+        },
+        _ => handle(&request, router, token).await,
+    };
+    let (status, mut body) = result;
+    // 错误描述按 Accept-Language 翻译
+    if let Some(locale) = request.header("accept-language").map(Locale::from_tag) {
+        locale.localize_value(&mut body);
+    }
+    log_info!("{} {} -> {}", request.method, request.path, status.as_u16());
+    write_json(&mut stream, status, &body).await
+}
+
+async fn handle(request: &Request, router: &MessageRouter, token: &str) -> (StatusCode, serde_json::Value) {
+    if request.method == "GET" && request.path == "/health" {
+        return (StatusCode::OK, serde_json::json!({
+            "status": "ok",
+            "version": crate::SERVER_VERSION,
+            "modules": router.enabled_modules(),
+        }));
+    }
+
+    let endpoint = match request.path.as_str() {
+        "/detect-language" => Some((ModuleType::Utils, "detect_language")),
+        "/transcribe" => Some((ModuleType::Voice, "transcribe")),
+        "/exec" => None,
+        _ => return (StatusCode::NOT_FOUND, error_body(ErrorCode::NotFound, &format!("未知的路径: {}", request.path))),
+    };
+    if request.method != "POST" {
+        return (StatusCode::METHOD_NOT_ALLOWED, error_body(ErrorCode::InvalidMessage, "仅支持 POST 请求"));
+    }
+    if !request.token().is_some_and(|candidate| tokens_match(&candidate, token)) {
+        return (StatusCode::UNAUTHORIZED, error_body(ErrorCode::Unauthorized, "缺少或错误的令牌"));
+    }
+
+    // /exec 的请求体即消息 JSON；固定端点的请求体为消息负载，未提供 request_id 时自动生成
+    let text = String::from_utf8_lossy(&request.body);
+    let parsed = match endpoint {
+        Some((module, msg_type)) => serde_json::from_str::<serde_json::Value>(&text)
+            .map_err(RouterError::from)
+            .and_then(|payload| match payload {
+                serde_json::Value::Object(mut fields) => {
+                    fields.entry("request_id")
+                        .or_insert_with(|| format!("http-{}", uuid::Uuid::new_v4()).into());
+                    Ok(ModuleMessage {
+                        connection: None,
+                        module,
+                        msg_type: msg_type.to_string(),
+                        payload: serde_json::Value::Object(fields),
+                    })
+                }
+                _ => Err(RouterError::InvalidMessage("请求体必须是 JSON 对象".to_string())),
+            }),
+        None => router.parse_message(&text),
+    };
+    let msg = match parsed {
+        Ok(msg) => msg,
+        Err(e) => {
+            let module = endpoint.map(|(module, _)| module).unwrap_or(ModuleType::System);
+            let response = router.create_error_response(module, &e);
+            return (StatusCode::BAD_REQUEST, response_body(&response));
+        }
+    };
+
+    let module = msg.module;
+    let request_id = msg.request_id();
+    match router.route(msg).await {
+        Ok(Some(response)) => {
+            let response = response.with_request_id(request_id.as_deref());
+            (StatusCode::OK, response_body(&response))
+        }
+        // 没有直接响应的请求 (结果以事件形式发送给 WebSocket 连接)
+        Ok(None) => (StatusCode::ACCEPTED, serde_json::json!({ "accepted": true })),
+        Err(e) => {
+            let response = router.create_error_response(module, &e).with_request_id(request_id.as_deref());
+            let status = response.payload.get("code")
+                .and_then(|code| serde_json::from_value::<ErrorCode>(code.clone()).ok())
+                .map(status_for)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            (status, response_body(&response))
+        }
+    }
+}
+
+// ============================================================================
+// 文件
+// ============================================================================
+
+/// 待发送的文件
+struct ServedFile {
+    file: tokio::fs::File,
+    /// 文件总字节数
+    len: u64,
+    /// 请求的字节范围 (闭区间，None 为整个文件)
+    range: Option<(u64, u64)>,
+    content_type: &'static str,
+}
+
+impl ServedFile {
+    fn status(&self) -> StatusCode {
+        if self.range.is_some() { StatusCode::PARTIAL_CONTENT } else { StatusCode::OK }
+    }
+}
+
+/// 校验令牌并打开 root 下的文件
+async fn open_file(
+    request: &Request,
+    root: &Path,
+    relative: &str,
+    token: &str,
+) -> Result<ServedFile, (StatusCode, serde_json::Value)> {
+    if !matches!(request.method.as_str(), "GET" | "HEAD") {
+        return Err((StatusCode::METHOD_NOT_ALLOWED, error_body(ErrorCode::InvalidMessage, "仅支持 GET 请求")));
+    }
+    if !request.token().is_some_and(|candidate| tokens_match(&candidate, token)) {
+        return Err((StatusCode::UNAUTHORIZED, error_body(ErrorCode::Unauthorized, "缺少或错误的令牌")));
+    }
+
+    let not_found = || (StatusCode::NOT_FOUND, error_body(ErrorCode::NotFound, &format!("文件不存在: {}", relative)));
+    let path = resolve_file(root, relative).await.ok_or_else(not_found)?;
+    let file = tokio::fs::File::open(&path).await.map_err(|_| not_found())?;
+    let len = file.metadata().await.map_err(|_| not_found())?.len();
+
+    let range = match request.header("range").and_then(|value| parse_range(value, len)) {
+        None => None,
+        Some(Ok(range)) => Some(range),
+        Some(Err(())) => {
+            return Err((
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                error_body(ErrorCode::InvalidParams, &format!("无法满足的范围 (文件大小 {} 字节)", len)),
+            ));
+        }
+    };
+    Ok(ServedFile { file, len, range, content_type: content_type(&path) })
+}
+
+/// 将 URL 路径解析为 root 下的文件 (拒绝 `..`、绝对路径和指向 root 之外的符号链接)
+async fn resolve_file(root: &Path, relative: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(relative)?;
+    let mut path = root.to_path_buf();
+    for segment in decoded.split('/') {
+        let invalid = segment.is_empty()
+            || segment == "."
+            || segment == ".."
+            || segment.contains(['\\', ':', '\0']);
+        if invalid {
+            return None;
+        }
+        path.push(segment);
+    }
+    let root = tokio::fs::canonicalize(root).await.ok()?;
+    let path = tokio::fs::canonicalize(&path).await.ok()?;
+    let is_file = tokio::fs::metadata(&path).await.ok()?.is_file();
+    (is_file && path.starts_with(&root)).then_some(path)
+}
+
+/// 解码 URL 路径中的 %XX
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = bytes.get(index + 1..index + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// 解析单个字节范围 (`bytes=0-99`、`bytes=100-`、`bytes=-100`)，返回闭区间
+///
+/// 无法识别或包含多个范围时返回 None (发送整个文件)，范围超出文件时返回 Err
+fn parse_range(value: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    let range = if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(Err(()));
+        }
+        (len.saturating_sub(suffix), len - 1)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() { len.saturating_sub(1) } else { end.parse::<u64>().ok()?.min(len.saturating_sub(1)) };
+        if start >= len || end < start {
+            return Some(Err(()));
+        }
+        (start, end)
+    };
+    Some(Ok(range))
+}
+
+/// 按扩展名推断 Content-Type
+fn content_type(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match extension.as_str() {
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "ogg" | "opus" => "audio/ogg",
+        "flac" => "audio/flac",
+        "m4a" => "audio/mp4",
+        "webm" => "audio/webm",
+        "cast" => "application/x-asciicast",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "md" | "txt" | "log" => "text/plain; charset=utf-8",
+        "json" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 写回文件内容并关闭连接
+///
+/// 文件以沙箱方式提供 (CSP sandbox)，导出的 HTML 中的脚本无法借用页面 URL 中的令牌调用接口
+async fn write_file<S>(stream: &mut S, mut file: ServedFile, head_only: bool) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let status = file.status();
+    let (start, end) = file.range.unwrap_or((0, file.len.saturating_sub(1)));
+    let length = if file.len == 0 { 0 } else { end - start + 1 };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or(""),
+        file.content_type,
+        length,
+    );
+    if file.range.is_some() {
+        head.push_str(&format!("Content-Range: bytes {}-{}/{}\r\n", start, end, file.len));
+    }
+    head.push_str("Cache-Control: no-store\r\nContent-Security-Policy: sandbox\r\nX-Content-Type-Options: nosniff\r\nConnection: close\r\n\r\n");
+    stream.write_all(head.as_bytes()).await?;
+    if !head_only {
+        file.file.seek(io::SeekFrom::Start(start)).await?;
+        tokio::io::copy(&mut (&mut file.file).take(length), stream).await?;
+    }
+    stream.shutdown().await
+}
+
+/// 错误码对应的 HTTP 状态
+fn status_for(code: ErrorCode) -> StatusCode {
+    match code {
+        ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+        ErrorCode::ParseError
+        | ErrorCode::InvalidMessage
+        | ErrorCode::JsonError
+        | ErrorCode::UnknownMessageType
+        | ErrorCode::InvalidParams
+        | ErrorCode::InvalidConfig
+        | ErrorCode::IncompatibleProtocol => StatusCode::BAD_REQUEST,
+        ErrorCode::UnknownModule | ErrorCode::SessionNotFound | ErrorCode::NotFound => StatusCode::NOT_FOUND,
+        ErrorCode::ModuleDisabled => StatusCode::SERVICE_UNAVAILABLE,
+        ErrorCode::AlreadyRecording | ErrorCode::NotRecording | ErrorCode::Cancelled | ErrorCode::Conflict => StatusCode::CONFLICT,
+        ErrorCode::LimitExceeded => StatusCode::PAYLOAD_TOO_LARGE,
+        ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+        ErrorCode::NetworkError | ErrorCode::HttpError | ErrorCode::TranscriptionFailed => StatusCode::BAD_GATEWAY,
+        ErrorCode::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn response_body(response: &ServerResponse) -> serde_json::Value {
+    serde_json::to_value(response).unwrap_or_default()
+}
+
+fn error_body(code: ErrorCode, message: &str) -> serde_json::Value {
+    response_body(&ServerResponse::error(ModuleType::System, code, message))
+}
+
+/// 写回 JSON 响应并关闭连接
+async fn write_json<S>(stream: &mut S, status: StatusCode, body: &serde_json::Value) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let body = body.to_string();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or(""),
+        body.len(),
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 发送原始请求，返回 (状态码, JSON 响应体)
+    async fn request(router: &MessageRouter, raw: &str) -> (u16, serde_json::Value) {
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        client.write_all(raw.as_bytes()).await.unwrap();
+        match accept(server).await.unwrap() {
+            Accepted::Rest(request, stream) => serve(request, stream, router, "secret", None).await.unwrap(),
+            Accepted::WebSocket(_) => panic!("expected REST request"),
+        }
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head[9..12].parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    fn post(path: &str, token: &str, body: &str) -> String {
+        format!(
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\nContent-Length: {}\r\n\r\n{}",
+            path, token, body.len(), body
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rest_endpoints() {
+        let router = MessageRouter::new();
+
+        let (status, body) = request(&router, "GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["status"], "ok");
+
+        let (status, body) = request(&router, &post("/detect-language", "secret", r#"{"text": "这是一段用于检测语言的中文文本"}"#)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["language"], "zh");
+
+        let exec = r#"{"module": "utils", "type": "slugify", "text": "Hello World", "request_id": "req-1"}"#;
+        let (status, body) = request(&router, &post("/exec", "secret", exec)).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["request_id"], "req-1");
+
+        let (status, body) = request(&router, &post("/exec", "wrong", exec)).await;
+        assert_eq!(status, 401);
+        assert_eq!(body["code"], "UNAUTHORIZED");
+
+        let (status, body) = request(&router, &post("/exec", "secret", r#"{"module": "utils", "type": "nope"}"#)).await;
+        assert_eq!(status, 400);
+        assert_eq!(body["code"], "UNKNOWN_MESSAGE_TYPE");
+
+        let (status, _) = request(&router, &post("/missing", "secret", "{}")).await;
+        assert_eq!(status, 404);
+
+        let localized = "GET /missing HTTP/1.1\r\nHost: localhost\r\nAccept-Language: en-US,en;q=0.9\r\n\r\n";
+        let (status, body) = request(&router, localized).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["message"], "Unknown path: /missing");
+    }
+
+    #[tokio::test]
+    async fn test_serve_files() {
+        let router = MessageRouter::new();
+        // 根目录之外的文件放在同一个临时目录中，测试结束后一并删除
+        let outer = std::env::temp_dir().join(format!("http-files-test-{}", uuid::Uuid::new_v4()));
+        let root = outer.join("root");
+        std::fs::create_dir_all(root.join("casts")).unwrap();
+        std::fs::write(root.join("casts/会议 1.cast"), "0123456789").unwrap();
+        std::fs::write(outer.join("http-files-outside.txt"), "secret").unwrap();
+
+        // 返回 (状态码, 响应头, 响应体)
+        let get = |target: &str, extra: &str| {
+            let raw = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n", target, extra);
+            let root = root.clone();
+            let router = &router;
+            async move {
+                let (mut client, server) = tokio::io::duplex(64 * 1024);
+                client.write_all(raw.as_bytes()).await.unwrap();
+                match accept(server).await.unwrap() {
+                    Accepted::Rest(request, stream) => serve(request, stream, router, "secret", Some(&root)).await.unwrap(),
+                    Accepted::WebSocket(_) => panic!("expected REST request"),
+                }
+                let mut response = String::new();
+                client.read_to_string(&mut response).await.unwrap();
+                let (head, body) = response.split_once("\r\n\r\n").unwrap();
+                (head[9..12].parse::<u16>().unwrap(), head.to_string(), body.to_string())
+            }
+        };
+
+        let (status, head, body) = get("/files/casts/%E4%BC%9A%E8%AE%AE%201.cast?token=secret", "").await;
+        assert_eq!(status, 200);
+        assert!(head.contains("Content-Type: application/x-asciicast"));
+        assert_eq!(body, "0123456789");
+
+        let (status, head, body) = get("/files/casts/%E4%BC%9A%E8%AE%AE%201.cast", "Authorization: Bearer secret\r\nRange: bytes=2-4\r\n").await;
+        assert_eq!(status, 206);
+        assert!(head.contains("Content-Range: bytes 2-4/10"));
+        assert_eq!(body, "234");
+
+        let (status, _, _) = get("/files/casts/%E4%BC%9A%E8%AE%AE%201.cast?token=secret", "Range: bytes=20-\r\n").await;
+        assert_eq!(status, 416);
+        let (status, _, _) = get("/files/casts/%E4%BC%9A%E8%AE%AE%201.cast?token=wrong", "").await;
+        assert_eq!(status, 401);
+        let (status, _, _) = get("/files/../http-files-outside.txt?token=secret", "").await;
+        assert_eq!(status, 404);
+        let (status, _, _) = get("/files/casts/..%2F..%2Fhttp-files-outside.txt?token=secret", "").await;
+        assert_eq!(status, 404);
+        let (status, _, _) = get("/files/casts?token=secret", "").await;
+        assert_eq!(status, 404);
+
+        assert_eq!(parse_range("bytes=-3", 10), Some(Ok((7, 9))));
+        assert_eq!(parse_range("bytes=0-1,4-5", 10), None);
+        std::fs::remove_dir_all(&outer).ok();
+    }
+
+    #[tokio::test]
+    async fn test_websocket_upgrade_passthrough() {
+        let raw = "GET /?token=abc HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\nextra";
+        let (mut client, server) = tokio::io::duplex(1024);
+        client.write_all(raw.as_bytes()).await.unwrap();
+        drop(client);
+
+        let Accepted::WebSocket(mut stream) = accept(server).await.unwrap() else {
+            panic!("expected WebSocket upgrade");
+        };
+        let mut replayed = String::new();
+        stream.read_to_string(&mut replayed).await.unwrap();
+        assert_eq!(replayed, raw);
+    }
+}
@@ -0,0 +1,268 @@
+// 单实例锁
+// 在数据目录下写入锁文件 (内容与启动时输出到 stdout 的 JSON 相同)，同一数据目录 (即同一个 vault)
+// 只允许运行一个服务器实例。锁文件中的实例已退出时 (如插件崩溃后遗留) 由新实例接管
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+/// 锁文件名
+const LOCK_FILE_NAME: &str = "server.lock";
+
+/// 检查旧实例监听端口时的连接超时
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 单实例锁错误
+#[derive(Debug, Error)]
+pub enum LockError {
+    /// 已有实例在运行，`info` 为其锁文件内容 (启动信息)
+    #[error("已有服务器实例在运行 (pid {pid})，锁文件: {path}")]
+    AlreadyRunning { pid: u32, path: PathBuf, info: serde_json::Value },
+
+    #[error("无法创建锁文件 {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+}
+
+/// 单实例锁 (丢弃时删除锁文件)
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// 在数据目录下获取单实例锁
+    ///
+    /// 锁文件记录的实例仍在运行时返回 `AlreadyRunning`；进程已退出或不再监听记录的地址时接管锁文件
+    pub fn acquire(data_dir: &Path) -> Result<Self, LockError> {
+        let path = data_dir.join(LOCK_FILE_NAME);
+        let io_error = |source| LockError::Io { path: path.clone(), source };
+        fs::create_dir_all(data_dir).map_err(io_error)?;
+
+        // 先写入临时文件再硬链接到锁文件：创建是原子的，其他实例不会读到写了一半的内容
+        let initial = serde_json::json!({ "pid": std::process::id() });
+        let temp = temp_path(&path);
+        fs::write(&temp, initial.to_string()).map_err(io_error)?;
+        let result = Self::link(&temp, &path);
+        let _ = fs::remove_file(&temp);
+        result.map_err(|e| match e {
+            LinkError::Running(pid, info) => LockError::AlreadyRunning { pid, path: path.clone(), info },
+            LinkError::Io(source) => io_error(source),
+        })?;
+        Ok(Self { path })
+    }
+
+    fn link(temp: &Path, path: &Path) -> Result<(), LinkError> {
+        // 最多接管一次遗留的锁文件，接管后仍失败说明另一个实例同时启动
+        for attempt in 0..2 {
+            match create_with_content(temp, path) {
+                Ok(()) => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt == 0 => {
+                    let info = fs::read_to_string(path).ok()
+                        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok());
+                    let pid = info.as_ref()
+                        .and_then(|info| info.get("pid")?.as_u64())
+                        .map(|pid| pid as u32);
+                    if let (Some(info), Some(pid)) = (info, pid) {
+                        if is_running(pid, &info) {
+                            return Err(LinkError::Running(pid, info));
+                        }
+                        log_info!("锁文件记录的实例 (pid {}) 已退出，接管锁文件", pid);
+                    } else {
+                        log_info!("锁文件内容无效，接管锁文件");
+                    }
+                    match fs::remove_file(path) {
+                        Ok(()) => {}
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                        Err(e) => return Err(LinkError::Io(e)),
+                    }
+                }
+                Err(e) => return Err(LinkError::Io(e)),
+            }
+        }
+        Err(LinkError::Io(io::Error::from(io::ErrorKind::AlreadyExists)))
+    }
+
+    /// 锁文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 写入启动信息 (端口或套接字、pid、令牌)，供插件重新加载后发现已运行的实例
+    pub fn publish(&self, info: &serde_json::Value) -> io::Result<()> {
+        let temp = temp_path(&self.path);
+        fs::write(&temp, info.to_string())?;
+        fs::rename(&temp, &self.path)
+    }
+
+    /// 删除锁文件 (仅当锁文件仍属于当前进程时)
+    pub fn release(&self) {
+        let owned = fs::read_to_string(&self.path).ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+            .and_then(|info| info.get("pid")?.as_u64())
+            == Some(std::process::id() as u64);
+        if owned {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+enum LinkError {
+    Running(u32, serde_json::Value),
+    Io(io::Error),
+}
+
+/// 以 `temp` 的内容创建 `path`，`path` 已存在时返回 `AlreadyExists`
+fn create_with_content(temp: &Path, path: &Path) -> io::Result<()> {
+    match fs::hard_link(temp, path) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => {
+            // 不支持硬链接的文件系统 (如 exFAT) 退回到独占创建
+            let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+            file.write_all(&fs::read(temp)?)
+        }
+        result => result,
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    path.with_extension(format!("lock.{}.tmp", std::process::id()))
+}
+
+/// 锁文件记录的实例是否仍在运行
+///
+/// 进程存在且仍在监听记录的端口时视为运行中 (进程 ID 可能在重启后被其他进程复用)；
+/// 尚未写入监听地址时只检查进程
+fn is_running(pid: u32, info: &serde_json::Value) -> bool {
+    if pid == std::process::id() || !process_alive(pid) {
+        return false;
+    }
+    if let Some(port) = info.get("port").and_then(|v| v.as_u64()) {
+        // 监听所有地址 (0.0.0.0 / ::) 或未记录地址时连接本机
+        let host = info.get("host")
+            .and_then(|v| v.as_str())
+            .and_then(|host| host.parse::<IpAddr>().ok())
+            .filter(|host| !host.is_unspecified())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let addr = SocketAddr::new(host, port as u16);
+        return TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok();
+    }
+    #[cfg(unix)]
+    if let Some(socket) = info.get("socket").and_then(|v| v.as_str()) {
+        return std::os::unix::net::UnixStream::connect(socket).is_ok();
+    }
+    true
+}
+
+/// 进程是否存在
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // 非正数的 pid 表示进程组，不是有效的记录
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // 信号 0 只检查进程是否存在；EPERM 表示进程存在但属于其他用户
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// 进程是否存在
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+    use windows_sys::Win32::System::Threading::{GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return false;
+        }
+        let mut code = 0u32;
+        let queried = GetExitCodeProcess(handle, &mut code) != 0;
+        CloseHandle(handle);
+        queried && code == STILL_ACTIVE as u32
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("instance-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_single_instance() {
+        let dir = test_dir("single");
+        let lock = InstanceLock::acquire(&dir).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // 模拟另一个仍在监听的实例 (父进程一定存在)
+        let parent = std::os::unix::process::parent_id();
+        let info = serde_json::json!({ "pid": parent, "port": port, "token": "abc" });
+        fs::write(lock.path(), info.to_string()).unwrap();
+        match InstanceLock::acquire(&dir) {
+            Err(LockError::AlreadyRunning { pid, info, .. }) => {
+                assert_eq!(pid, parent);
+                assert_eq!(info["token"], "abc");
+            }
+            other => panic!("expected AlreadyRunning, got {:?}", other),
+        }
+
+        // 不属于当前进程的锁文件不会被删除
+        drop(lock);
+        assert!(dir.join(LOCK_FILE_NAME).exists());
+
+        // 旧实例不再监听时接管
+        drop(listener);
+        let lock = InstanceLock::acquire(&dir).unwrap();
+        lock.publish(&serde_json::json!({ "pid": std::process::id(), "port": 1 })).unwrap();
+        let published: serde_json::Value = serde_json::from_str(&fs::read_to_string(lock.path()).unwrap()).unwrap();
+        assert_eq!(published["port"], 1);
+        drop(lock);
+        assert!(!dir.join(LOCK_FILE_NAME).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stale_lock() {
+        let dir = test_dir("stale");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(LOCK_FILE_NAME), "not json").unwrap();
+        let lock = InstanceLock::acquire(&dir).unwrap();
+        drop(lock);
+
+        // 进程不存在 (超出系统 pid 上限) 时接管
+        fs::write(dir.join(LOCK_FILE_NAME), r#"{"pid": 2147483647}"#).unwrap();
+        assert!(InstanceLock::acquire(&dir).is_ok());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,339 @@
+// 后台任务登记
+// 各模块的长时间任务 (录音、转写、LLM 流、会议、工作流、Git 推送 / 拉取、备份、索引、下载、朗读) 开始时登记，
+// 结束时自动注销；system/list_jobs 列出所有任务，system/kill_job 终止其中之一
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::router::ModuleType;
+
+/// 任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// 录音 (含实时转录)
+    Recording,
+    /// 音频文件转写
+    Transcription,
+    /// LLM 流式响应
+    LlmStream,
+    /// 会议录制与纪要
+    Meeting,
+    /// 工作流
+    Workflow,
+    /// Git 推送 / 拉取
+    GitSync,
+    /// 备份
+    Backup,
+    /// 向量索引
+    Indexing,
+    /// 网页剪藏等下载
+    Download,
+    /// 朗读
+    Speech,
+}
+
+impl JobKind {
+    /// 任务所属模块
+    pub fn module(&self) -> ModuleType {
+        match self {
+            JobKind::Recording | JobKind::Transcription => ModuleType::Voice,
+            JobKind::LlmStream => ModuleType::Llm,
+            JobKind::Meeting => ModuleType::Meeting,
+            JobKind::Workflow => ModuleType::Workflow,
+            JobKind::GitSync => ModuleType::Git,
+            JobKind::Backup => ModuleType::Backup,
+            JobKind::Indexing => ModuleType::Vectors,
+            JobKind::Download => ModuleType::Utils,
+            JobKind::Speech => ModuleType::Tts,
+        }
+    }
+}
+
+/// 终止任务的方式
+enum Canceller {
+    Token(CancellationToken),
+    Flag(Arc<AtomicBool>),
+}
+
+/// 任务描述
+pub struct Job {
+    kind: JobKind,
+    request_id: Option<String>,
+    detail: Option<String>,
+    canceller: Option<Canceller>,
+}
+
+impl Job {
+    pub fn new(kind: JobKind) -> Self {
+        Self {
+            kind,
+            request_id: None,
+            detail: None,
+            canceller: None,
+        }
+    }
+
+    /// 发起任务的请求 ID (没有终止方式的任务按请求 ID 交给模块取消)
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// 任务说明 (如 URL、目录)
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// 终止时取消 `token`
+    pub fn with_cancel(mut self, token: CancellationToken) -> Self {
+        self.canceller = Some(Canceller::Token(token));
+        self
+    }
+
+    /// 终止时设置 `flag` (阻塞任务定期检查)
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.canceller = Some(Canceller::Flag(flag));
+        self
+    }
+}
+
+/// 任务登记凭证，被丢弃时自动注销
+pub struct JobGuard {
+    id: u64,
+    registry: &'static Registry,
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        if let Ok(mut jobs) = self.registry.jobs.lock() {
+            jobs.remove(&self.id);
+        }
+    }
+}
+
+/// 任务信息 (system/list_jobs 的列表项)
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: u64,
+    /// 任务所属模块
+    pub job_module: ModuleType,
+    pub kind: JobKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// 已运行的毫秒数
+    pub running_ms: u64,
+    /// 能否通过 kill_job 终止
+    pub killable: bool,
+}
+
+/// 终止任务的结果
+#[derive(Debug, PartialEq)]
+pub enum Kill {
+    /// 已通知任务终止
+    Killed(ModuleType),
+    /// 任务没有自己的终止方式，需要按请求 ID 交给模块取消
+    ByRequest(ModuleType, String),
+    /// 任务既没有终止方式也没有请求 ID
+    Unsupported(ModuleType),
+}
+
+struct Entry {
+    job: Job,
+    started: Instant,
+}
+
+/// 任务登记表
+struct Registry {
+    next_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, Entry>>,
+}
+
+impl Registry {
+    fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            jobs: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn register(&'static self, job: Job) -> JobGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.insert(id, Entry { job, started: Instant::now() });
+        }
+        JobGuard { id, registry: self }
+    }
+
+    /// 按登记顺序列出任务
+    fn list(&self) -> Vec<JobInfo> {
+        let Ok(jobs) = self.jobs.lock() else {
+            return Vec::new();
+        };
+        jobs.iter()
+            .map(|(id, entry)| JobInfo {
+                id: *id,
+                job_module: entry.job.kind.module(),
+                kind: entry.job.kind,
+                request_id: entry.job.request_id.clone(),
+                detail: entry.job.detail.clone(),
+                running_ms: entry.started.elapsed().as_millis() as u64,
+                killable: entry.job.canceller.is_some() || entry.job.request_id.is_some(),
+            })
+            .collect()
+    }
+
+    /// 终止任务；已通知终止的任务立即从列表中移除 (任务随后自行清理)，任务不存在时返回 None
+    fn kill(&self, id: u64) -> Option<Kill> {
+        let mut jobs = self.jobs.lock().ok()?;
+        let entry = jobs.get(&id)?;
+        let module = entry.job.kind.module();
+        match (&entry.job.canceller, &entry.job.request_id) {
+            (Some(canceller), _) => {
+                match canceller {
+                    Canceller::Token(token) => token.cancel(),
+                    Canceller::Flag(flag) => flag.store(true, Ordering::SeqCst),
+                }
+                jobs.remove(&id);
+                Some(Kill::Killed(module))
+            }
+            (None, Some(request_id)) => Some(Kill::ByRequest(module, request_id.clone())),
+            (None, None) => Some(Kill::Unsupported(module)),
+        }
+    }
+
+    /// 终止由 `request_id` 发起且有终止方式的任务，返回所属模块；没有这样的任务时返回 None
+    fn cancel_request(&self, request_id: &str) -> Option<ModuleType> {
+        let mut jobs = self.jobs.lock().ok()?;
+        let ids: Vec<u64> = jobs.iter()
+            .filter(|(_, entry)| entry.job.canceller.is_some() && entry.job.request_id.as_deref() == Some(request_id))
+            .map(|(id, _)| *id)
+            .collect();
+        let mut module = None;
+        for id in ids {
+            if let Some(entry) = jobs.remove(&id) {
+                match entry.job.canceller {
+                    Some(Canceller::Token(token)) => token.cancel(),
+                    Some(Canceller::Flag(flag)) => flag.store(true, Ordering::SeqCst),
+                    None => {}
+                }
+                module.get_or_insert(entry.job.kind.module());
+            }
+        }
+        module
+    }
+}
+
+/// 全局任务登记表
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Registry::new)
+}
+
+/// 登记任务
+pub fn register(job: Job) -> JobGuard {
+    registry().register(job)
+}
+
+/// 列出进行中的任务
+pub fn list() -> Vec<JobInfo> {
+    registry().list()
+}
+
+/// 终止任务 (任务不存在时返回 None)
+pub fn kill(id: u64) -> Option<Kill> {
+    registry().kill(id)
+}
+
+/// 按发起请求的 ID 终止任务 (system/cancel)，返回所属模块
+pub fn cancel_request(request_id: &str) -> Option<ModuleType> {
+    registry().cancel_request(request_id)
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_list() {
+        let registry: &'static Registry = Box::leak(Box::new(Registry::new()));
+        let stream = registry.register(
+            Job::new(JobKind::LlmStream)
+                .with_request_id(Some("req-1".to_string()))
+                .with_cancel(CancellationToken::new()),
+        );
+        let download = registry.register(Job::new(JobKind::Download).with_detail("https://example.com"));
+
+        let jobs = registry.list();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].id, stream.id);
+        let info = serde_json::to_value(&jobs[0]).unwrap();
+        assert_eq!(info["job_module"], "llm");
+        assert_eq!(info["kind"], "llm_stream");
+        assert_eq!(info["request_id"], "req-1");
+        assert_eq!(info["killable"], true);
+        assert!(info.get("detail").is_none());
+        assert_eq!(jobs[1].detail.as_deref(), Some("https://example.com"));
+        assert!(!jobs[1].killable);
+
+        // 凭证被丢弃时注销
+        drop(stream);
+        drop(download);
+        assert!(registry.list().is_empty());
+    }
+
+    #[test]
+    fn test_kill() {
+        let registry: &'static Registry = Box::leak(Box::new(Registry::new()));
+        let token = CancellationToken::new();
+        let flag = Arc::new(AtomicBool::new(false));
+        let stream = registry.register(Job::new(JobKind::LlmStream).with_cancel(token.clone()));
+        let backup = registry.register(Job::new(JobKind::Backup).with_cancel_flag(Arc::clone(&flag)));
+        let recording = registry.register(Job::new(JobKind::Recording).with_request_id(Some("rec".to_string())));
+        let indexing = registry.register(Job::new(JobKind::Indexing));
+
+        // 有终止方式的任务直接终止并移出列表
+        assert_eq!(registry.kill(stream.id), Some(Kill::Killed(ModuleType::Llm)));
+        assert!(token.is_cancelled());
+        assert_eq!(registry.kill(stream.id), None);
+        assert_eq!(registry.kill(backup.id), Some(Kill::Killed(ModuleType::Backup)));
+        assert!(flag.load(Ordering::SeqCst));
+
+        // 其余任务按请求 ID 交给模块取消，两者都没有时无法终止
+        assert_eq!(
+            registry.kill(recording.id),
+            Some(Kill::ByRequest(ModuleType::Voice, "rec".to_string())),
+        );
+        assert_eq!(registry.kill(indexing.id), Some(Kill::Unsupported(ModuleType::Vectors)));
+        assert_eq!(registry.list().len(), 2);
+        assert_eq!(registry.kill(999), None);
+    }
+
+    #[test]
+    fn test_cancel_request() {
+        let registry: &'static Registry = Box::leak(Box::new(Registry::new()));
+        let token = CancellationToken::new();
+        let _transcription = registry.register(
+            Job::new(JobKind::Transcription).with_request_id(Some("req-1".to_string())).with_cancel(token.clone()),
+        );
+        let _recording = registry.register(Job::new(JobKind::Recording).with_request_id(Some("req-2".to_string())));
+
+        assert_eq!(registry.cancel_request("req-1"), Some(ModuleType::Voice));
+        assert!(token.is_cancelled());
+        assert_eq!(registry.cancel_request("req-1"), None);
+        // 没有终止方式的任务由模块按请求 ID 取消
+        assert_eq!(registry.cancel_request("req-2"), None);
+        assert_eq!(registry.list().len(), 1);
+    }
+}
@@ -4,6 +4,8 @@
 pub mod sse_parser;
 pub mod thinking;
 pub mod response;
+pub mod queue;
+pub mod sink;
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -11,36 +13,49 @@ use tokio::sync::Mutex as TokioMutex;
 use tokio_util::sync::CancellationToken;
 use serde::{Deserialize, Serialize};
 
-use crate::router::{ModuleHandler, ModuleMessage, ModuleType, RouterError, ServerResponse};
+use crate::jobs::{self, Job, JobKind};
+use crate::router::{
+    CodedError, ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType,
+    RouterError, ServerResponse,
+};
 use crate::server::WsSender;
+use crate::watchdog::{self, Heartbeat, TaskKind, WatchedTask};
 
 use futures_util::SinkExt;
 
 use self::sse_parser::{SSEParser, SSEEvent};
 use self::thinking::StreamingThinkingFilter;
 use self::response::{ApiFormat, ResponseParser};
+use self::sink::Sinks;
+pub use self::queue::Priority;
+pub use self::sink::StreamSink;
 
 /// 日志宏
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] [LLM] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "LLM", format!($($arg)*));
     };
 }
 
 macro_rules! log_error {
     ($($arg:tt)*) => {
-        eprintln!("[ERROR] [LLM] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Error, "LLM", format!($($arg)*));
     };
 }
 
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] [LLM] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "LLM", format!($($arg)*));
         }
     };
 }
 
+/// 创建 LLM 模块错误
+fn llm_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Llm, code, message)
+}
+
 // ============================================================================
 // 配置和消息类型
 // ============================================================================
@@ -61,6 +76,12 @@ pub struct StreamConfig {
     /// 请求 ID（用于关联响应）
     #[serde(default)]
     pub request_id: Option<String>,
+    /// 优先级 (排队时交互请求先于后台任务)
+    #[serde(default)]
+    pub priority: Priority,
+    /// 附加的输出目标 (文件或 PTY 会话)，声明后客户端断开时流式请求继续执行
+    #[serde(default)]
+    pub sinks: Vec<StreamSink>,
 }
 
 /// LLM 模块错误
@@ -80,6 +101,29 @@ pub enum LLMError {
     
     #[error("HTTP error: {status} - {message}")]
     HttpError { status: u16, message: String },
+    
+    #[error("Stream stalled: {0}")]
+    Stalled(String),
+}
+
+impl CodedError for LLMError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            LLMError::NetworkError(_) => ErrorCode::NetworkError,
+            LLMError::ParseError(_) => ErrorCode::ParseError,
+            LLMError::Cancelled => ErrorCode::Cancelled,
+            LLMError::InvalidConfig(_) => ErrorCode::InvalidConfig,
+            LLMError::HttpError { .. } => ErrorCode::HttpError,
+            LLMError::Stalled(_) => ErrorCode::Timeout,
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        match self {
+            LLMError::HttpError { status, .. } => ErrorCode::is_retryable_status(*status),
+            _ => self.code().is_retryable(),
+        }
+    }
 }
 
 // ============================================================================
@@ -125,7 +169,8 @@ struct StreamErrorMessage {
     module: &'static str,
     #[serde(rename = "type")]
     msg_type: &'static str,
-    code: String,
+    code: ErrorCode,
+    retryable: bool,
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     request_id: Option<String>,
@@ -135,12 +180,83 @@ struct StreamErrorMessage {
 // LLM 处理器
 // ============================================================================
 
+/// 当前的流式请求
+struct ActiveStream {
+    /// 请求 ID
+    request_id: Option<String>,
+    /// 取消令牌 (流结束后同样被标记为取消)
+    cancel_token: CancellationToken,
+    /// 是否声明了附加的输出目标 (连接关闭时不取消)
+    has_sinks: bool,
+}
+
+/// 流式请求的输出：发送到客户端，内容同时写入附加的输出目标
+struct StreamOutput {
+    ws_sender: WsSender,
+    /// 客户端是否仍可接收 (发送失败后不再发送)
+    client_connected: bool,
+    sinks: Sinks,
+    request_id: Option<String>,
+}
+
+impl StreamOutput {
+    /// 客户端发送失败：没有可用的输出目标时中止流式请求，否则之后只写入输出目标
+    fn client_failed(&mut self, error: LLMError) -> Result<(), LLMError> {
+        if self.sinks.is_empty() {
+            return Err(error);
+        }
+        log_info!("客户端已断开，继续写入输出目标: {}", error);
+        self.client_connected = false;
+        Ok(())
+    }
+
+    /// 写入不单独发送给客户端的内容 (思考过滤器刷新出的剩余内容)
+    async fn write_sinks(&mut self, content: &str) -> Result<(), LLMError> {
+        self.sinks.write(content).await;
+        if !self.client_connected && self.sinks.is_empty() {
+            return Err(LLMError::NetworkError("客户端已断开且没有可用的输出目标".to_string()));
+        }
+        Ok(())
+    }
+
+    /// 输出一段内容
+    async fn chunk(&mut self, content: &str) -> Result<(), LLMError> {
+        self.write_sinks(content).await?;
+        if self.client_connected {
+            if let Err(e) = LLMHandler::send_chunk(&self.ws_sender, content, self.request_id.as_deref()).await {
+                return self.client_failed(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// 发送思考内容 (不写入输出目标)
+    async fn thinking(&mut self, content: &str) -> Result<(), LLMError> {
+        if self.client_connected {
+            if let Err(e) = LLMHandler::send_thinking(&self.ws_sender, content, self.request_id.as_deref()).await {
+                return self.client_failed(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// 发送完成消息
+    async fn complete(&mut self, full_content: &str) -> Result<(), LLMError> {
+        if self.client_connected {
+            if let Err(e) = LLMHandler::send_complete(&self.ws_sender, full_content, self.request_id.as_deref()).await {
+                return self.client_failed(e);
+            }
+        }
+        Ok(())
+    }
+}
+
 /// LLM 模块处理器
 pub struct LLMHandler {
     /// WebSocket 发送器
     ws_sender: Arc<TokioMutex<Option<WsSender>>>,
-    /// 当前请求的取消令牌
-    cancel_token: Arc<TokioMutex<Option<CancellationToken>>>,
+    /// 当前的流式请求
+    active_stream: Arc<TokioMutex<Option<ActiveStream>>>,
     /// HTTP 客户端
     http_client: reqwest::Client,
 }
@@ -150,32 +266,36 @@ impl LLMHandler {
     pub fn new() -> Self {
         Self {
             ws_sender: Arc::new(TokioMutex::new(None)),
-            cancel_token: Arc::new(TokioMutex::new(None)),
-            http_client: reqwest::Client::new(),
+            active_stream: Arc::new(TokioMutex::new(None)),
+            http_client: crate::network::client(),
         }
     }
     
-    /// 设置 WebSocket 发送器
-    pub async fn set_ws_sender(&self, sender: WsSender) {
-        let mut ws = self.ws_sender.lock().await;
-        *ws = Some(sender);
-    }
-    
     /// 开始流式请求
-    async fn start_stream(&self, config: StreamConfig) -> Result<(), LLMError> {
+    /// 
+    /// 流式事件发送到发起请求的连接 (`client`)，未知时使用默认发送器；内容同时写入声明的输出目标
+    async fn start_stream(&self, config: StreamConfig, client: Option<WsSender>) -> Result<(), LLMError> {
         log_info!("开始流式请求: endpoint={}", config.endpoint);
         
+        // 打开输出目标 (路径无效时不开始请求)
+        let sinks = Sinks::open(&config.sinks)?;
+        
         // 创建取消令牌
         let cancel_token = CancellationToken::new();
         {
-            let mut token = self.cancel_token.lock().await;
-            *token = Some(cancel_token.clone());
+            let mut active = self.active_stream.lock().await;
+            *active = Some(ActiveStream {
+                request_id: config.request_id.clone(),
+                cancel_token: cancel_token.clone(),
+                has_sinks: !sinks.is_empty(),
+            });
         }
         
         // 获取 WebSocket 发送器
-        let ws_sender = {
-            let ws = self.ws_sender.lock().await;
-            ws.clone().ok_or_else(|| LLMError::InvalidConfig("WebSocket not connected".to_string()))?
+        let ws_sender = match client {
+            Some(sender) => sender,
+            None => self.ws_sender.lock().await.clone()
+                .ok_or_else(|| LLMError::InvalidConfig("WebSocket not connected".to_string()))?,
         };
         
         // 克隆配置用于异步任务
@@ -184,27 +304,55 @@ impl LLMHandler {
         let body = config.body.clone();
         let api_format = config.api_format;
         let request_id = config.request_id.clone();
+        let priority = config.priority;
         let http_client = self.http_client.clone();
+        let finished = cancel_token.clone();
+        let mut output = StreamOutput {
+            ws_sender,
+            client_connected: true,
+            sinks,
+            request_id: request_id.clone(),
+        };
+        
+        let job = jobs::register(Job::new(JobKind::LlmStream).with_request_id(request_id.clone()).with_cancel(cancel_token.clone()));
         
-        // 在后台任务中执行流式请求
-        tokio::spawn(async move {
-            let result = Self::execute_stream(
-                http_client,
-                endpoint,
-                headers,
-                body,
-                api_format,
-                request_id.clone(),
-                ws_sender.clone(),
-                cancel_token,
-            ).await;
+        // 在后台任务中执行流式请求，上游长时间无数据时由看门狗中止
+        tokio::spawn(crate::audit::inherit(async move {
+            let _job = job;
+            // 在请求队列中等待名额 (等待期间可以取消)，开始请求后才由看门狗监视
+            let permit = tokio::select! {
+                permit = queue::queue().acquire(priority) => Some(permit),
+                _ = cancel_token.cancelled() => None,
+            };
+            let result = match permit {
+                Some(_permit) => {
+                    let heartbeat = watchdog::watch(WatchedTask::new(TaskKind::LlmStream).with_request_id(request_id.clone()));
+                    tokio::select! {
+                        result = Self::execute_stream(
+                            http_client,
+                            endpoint,
+                            headers,
+                            body,
+                            api_format,
+                            &mut output,
+                            cancel_token,
+                            &heartbeat,
+                        ) => result,
+                        _ = heartbeat.stalled() => Err(LLMError::Stalled("no data received from upstream".to_string())),
+                    }
+                }
+                None => Err(LLMError::Cancelled),
+            };
+            finished.cancel();
             
             if let Err(e) = result {
                 log_error!("流式请求失败: {}", e);
                 // 发送错误消息
-                let _ = Self::send_error(&ws_sender, &e, request_id.as_deref()).await;
+                if output.client_connected {
+                    let _ = Self::send_error(&output.ws_sender, &e, request_id.as_deref()).await;
+                }
             }
-        });
+        }));
         
         Ok(())
     }
@@ -216,9 +364,9 @@ impl LLMHandler {
         headers: HashMap<String, String>,
         body: String,
         api_format: ApiFormat,
-        request_id: Option<String>,
-        ws_sender: WsSender,
+        output: &mut StreamOutput,
         cancel_token: CancellationToken,
+        heartbeat: &Heartbeat,
     ) -> Result<(), LLMError> {
         // 构建请求
         let mut request = client.post(&endpoint)
@@ -231,8 +379,9 @@ impl LLMHandler {
         }
         
         // 发送请求
-        let response = request.body(body).send().await
+        let response = crate::network::send(ModuleType::Llm, request.body(body)).await
             .map_err(|e| LLMError::NetworkError(e.to_string()))?;
+        heartbeat.beat();
         
         // 检查响应状态
         let status = response.status();
@@ -248,9 +397,9 @@ impl LLMHandler {
         Self::process_stream(
             response,
             api_format,
-            request_id,
-            ws_sender,
+            output,
             cancel_token,
+            heartbeat,
         ).await
     }
     
@@ -258,9 +407,9 @@ impl LLMHandler {
     async fn process_stream(
         response: reqwest::Response,
         api_format: ApiFormat,
-        request_id: Option<String>,
-        ws_sender: WsSender,
+        output: &mut StreamOutput,
         cancel_token: CancellationToken,
+        heartbeat: &Heartbeat,
     ) -> Result<(), LLMError> {
         use futures_util::StreamExt;
         
@@ -281,6 +430,7 @@ impl LLMHandler {
                 chunk = stream.next() => {
                     match chunk {
                         Some(Ok(bytes)) => {
+                            heartbeat.beat();
                             let text = String::from_utf8_lossy(&bytes);
                             log_debug!("收到数据块: {} 字节", bytes.len());
                             
@@ -297,13 +447,14 @@ impl LLMHandler {
                                         let (remaining, thinking) = thinking_filter.flush();
                                         if !remaining.is_empty() {
                                             full_content.push_str(&remaining);
+                                            output.write_sinks(&remaining).await?;
                                         }
                                         if let Some(t) = thinking {
-                                            Self::send_thinking(&ws_sender, &t, request_id.as_deref()).await?;
+                                            output.thinking(&t).await?;
                                         }
                                         
                                         // 发送完成消息
-                                        Self::send_complete(&ws_sender, &full_content, request_id.as_deref()).await?;
+                                        output.complete(&full_content).await?;
                                         return Ok(());
                                     }
                                     SSEEvent::Data(data) => {
@@ -312,7 +463,7 @@ impl LLMHandler {
                                             Ok(extracted) => {
                                                 // 处理推理内容
                                                 if let Some(reasoning) = extracted.reasoning {
-                                                    Self::send_thinking(&ws_sender, &reasoning, request_id.as_deref()).await?;
+                                                    output.thinking(&reasoning).await?;
                                                 }
                                                 
                                                 // 处理主要内容
@@ -322,13 +473,13 @@ impl LLMHandler {
                                                     
                                                     // 发送思考内容
                                                     if let Some(t) = thinking {
-                                                        Self::send_thinking(&ws_sender, &t, request_id.as_deref()).await?;
+                                                        output.thinking(&t).await?;
                                                     }
                                                     
                                                     // 发送过滤后的内容
                                                     if !filtered.is_empty() {
                                                         full_content.push_str(&filtered);
-                                                        Self::send_chunk(&ws_sender, &filtered, request_id.as_deref()).await?;
+                                                        output.chunk(&filtered).await?;
                                                     }
                                                 }
                                                 
@@ -340,13 +491,14 @@ impl LLMHandler {
                                                     let (remaining, thinking) = thinking_filter.flush();
                                                     if !remaining.is_empty() {
                                                         full_content.push_str(&remaining);
+                                                        output.write_sinks(&remaining).await?;
                                                     }
                                                     if let Some(t) = thinking {
-                                                        Self::send_thinking(&ws_sender, &t, request_id.as_deref()).await?;
+                                                        output.thinking(&t).await?;
                                                     }
                                                     
                                                     // 发送完成消息
-                                                    Self::send_complete(&ws_sender, &full_content, request_id.as_deref()).await?;
+                                                    output.complete(&full_content).await?;
                                                     return Ok(());
                                                 }
                                             }
@@ -366,11 +518,11 @@ impl LLMHandler {
                                             if let Some(content) = extracted.content {
                                                 let (filtered, thinking) = thinking_filter.process_chunk(&content);
                                                 if let Some(t) = thinking {
-                                                    Self::send_thinking(&ws_sender, &t, request_id.as_deref()).await?;
+                                                    output.thinking(&t).await?;
                                                 }
                                                 if !filtered.is_empty() {
                                                     full_content.push_str(&filtered);
-                                                    Self::send_chunk(&ws_sender, &filtered, request_id.as_deref()).await?;
+                                                    output.chunk(&filtered).await?;
                                                 }
                                             }
                                         }
@@ -389,13 +541,14 @@ impl LLMHandler {
                             let (remaining, thinking) = thinking_filter.flush();
                             if !remaining.is_empty() {
                                 full_content.push_str(&remaining);
+                                output.write_sinks(&remaining).await?;
                             }
                             if let Some(t) = thinking {
-                                Self::send_thinking(&ws_sender, &t, request_id.as_deref()).await?;
+                                output.thinking(&t).await?;
                             }
                             
                             // 发送完成消息
-                            Self::send_complete(&ws_sender, &full_content, request_id.as_deref()).await?;
+                            output.complete(&full_content).await?;
                             return Ok(());
                         }
                     }
@@ -463,18 +616,20 @@ impl LLMHandler {
     
     /// 发送错误消息
     async fn send_error(ws_sender: &WsSender, error: &LLMError, request_id: Option<&str>) -> Result<(), LLMError> {
-        let (code, message) = match error {
-            LLMError::NetworkError(msg) => ("NETWORK_ERROR", msg.clone()),
-            LLMError::ParseError(msg) => ("PARSE_ERROR", msg.clone()),
-            LLMError::Cancelled => ("CANCELLED", "Request cancelled".to_string()),
-            LLMError::InvalidConfig(msg) => ("INVALID_CONFIG", msg.clone()),
-            LLMError::HttpError { status, message } => ("HTTP_ERROR", format!("{}: {}", status, message)),
+        let message = match error {
+            LLMError::NetworkError(msg) => msg.clone(),
+            LLMError::ParseError(msg) => msg.clone(),
+            LLMError::Cancelled => "Request cancelled".to_string(),
+            LLMError::InvalidConfig(msg) => msg.clone(),
+            LLMError::HttpError { status, message } => format!("{}: {}", status, message),
+            LLMError::Stalled(msg) => msg.clone(),
         };
         
         let msg = StreamErrorMessage {
             module: "llm",
             msg_type: "stream_error",
-            code: code.to_string(),
+            code: error.code(),
+            retryable: error.retryable(),
             message,
             request_id: request_id.map(|s| s.to_string()),
         };
@@ -493,19 +648,30 @@ impl LLMHandler {
     async fn cancel_stream(&self) -> Result<(), LLMError> {
         log_info!("取消流式请求");
         
-        let mut token = self.cancel_token.lock().await;
-        if let Some(cancel_token) = token.take() {
-            cancel_token.cancel();
+        let mut active = self.active_stream.lock().await;
+        if let Some(stream) = active.take() {
+            stream.cancel_token.cancel();
         }
         
         Ok(())
     }
     
-    /// 清理资源
-    pub async fn cleanup(&self) {
-        // 取消任何正在进行的请求
-        let _ = self.cancel_stream().await;
+    /// 取消指定请求的流式请求 (请求已结束时返回 false)
+    async fn cancel_request(&self, request_id: &str) -> bool {
+        let mut active = self.active_stream.lock().await;
+        let matched = active.as_ref().is_some_and(|stream| {
+            stream.request_id.as_deref() == Some(request_id) && !stream.cancel_token.is_cancelled()
+        });
+        if matched {
+            log_info!("取消流式请求: request_id={}", request_id);
+            if let Some(stream) = active.take() {
+                stream.cancel_token.cancel();
+            }
+        }
+        matched
     }
+    
+
 }
 
 impl Default for LLMHandler {
@@ -514,16 +680,148 @@ impl Default for LLMHandler {
     }
 }
 
+// ============================================================================
+// 服务端内部请求
+// ============================================================================
+
+/// 执行流式请求并返回完整内容 (不发送流式事件，思考内容被丢弃)
+///
+/// 供服务端内部的工作流使用 (如会议纪要)，按 `priority` 在请求队列中排队，上游长时间无数据时由看门狗中止
+pub async fn complete(config: StreamConfig) -> Result<String, LLMError> {
+    let _permit = queue::queue().acquire(config.priority).await;
+    let heartbeat = watchdog::watch(WatchedTask::new(TaskKind::LlmStream).with_request_id(config.request_id.clone()));
+    tokio::select! {
+        result = collect_stream(config, &heartbeat) => result,
+        _ = heartbeat.stalled() => Err(LLMError::Stalled("no data received from upstream".to_string())),
+    }
+}
+
+/// 构造单轮对话的请求体 (系统提示词 + 用户消息，开启流式输出)
+pub fn prompt_body(api_format: ApiFormat, model: &str, system: &str, user: &str) -> String {
+    let body = match api_format {
+        ApiFormat::ChatCompletions => serde_json::json!({
+            "model": model,
+            "stream": true,
+            "messages": [
+                { "role": "system", "content": system },
+                { "role": "user", "content": user },
+            ],
+        }),
+        ApiFormat::Responses => serde_json::json!({
+            "model": model,
+            "stream": true,
+            "instructions": system,
+            "input": user,
+        }),
+    };
+    body.to_string()
+}
+
+/// 发送请求并收集流式响应的内容
+async fn collect_stream(config: StreamConfig, heartbeat: &Heartbeat) -> Result<String, LLMError> {
+    use futures_util::StreamExt;
+
+    let mut request = crate::network::client().post(&config.endpoint)
+        .header("Content-Type", "application/json")
+        .header("Accept", "text/event-stream");
+    for (key, value) in &config.headers {
+        request = request.header(key, value);
+    }
+    let response = crate::network::send(ModuleType::Llm, request.body(config.body)).await
+        .map_err(|e| LLMError::NetworkError(e.to_string()))?;
+    heartbeat.beat();
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(LLMError::HttpError {
+            status: status.as_u16(),
+            message: error_text,
+        });
+    }
+
+    let mut sse_parser = SSEParser::new();
+    let mut thinking_filter = StreamingThinkingFilter::new();
+    let mut full_content = String::new();
+    let mut stream = response.bytes_stream();
+    'stream: while let Some(chunk) = stream.next().await {
+        let bytes = chunk.map_err(|e| LLMError::NetworkError(e.to_string()))?;
+        heartbeat.beat();
+        for event in sse_parser.parse_chunk(&String::from_utf8_lossy(&bytes)) {
+            let data = match event {
+                SSEEvent::Done => break 'stream,
+                SSEEvent::Data(data) | SSEEvent::Event { data, .. } => data,
+                SSEEvent::Comment(_) => continue,
+            };
+            let Ok(extracted) = ResponseParser::parse(&data, config.api_format) else {
+                continue;
+            };
+            if let Some(content) = extracted.content {
+                full_content.push_str(&thinking_filter.process_chunk(&content).0);
+            }
+            if extracted.is_done {
+                break 'stream;
+            }
+        }
+    }
+    full_content.push_str(&thinking_filter.flush().0);
+    Ok(full_content)
+}
+
 // ============================================================================
 // ModuleHandler 实现
 // ============================================================================
 
+/// LLM 模块支持的消息 (system/describe)
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("stream_start", &[
+        FieldSpec::required("endpoint", FieldKind::String),
+        FieldSpec::optional("headers", FieldKind::Object),
+        FieldSpec::required("body", FieldKind::String),
+        FieldSpec::optional("api_format", FieldKind::String).one_of(&["chat_completions", "responses"]),
+        FieldSpec::optional("priority", FieldKind::String).one_of(&["interactive", "background"]),
+        FieldSpec::optional("sinks", FieldKind::Array),
+    ]),
+    MessageSpec::new("stream_cancel", &[]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// LLM 流式处理模块
+    pub const Llm: ModuleType = ModuleType::named("llm");
+}
+
 #[async_trait::async_trait]
 impl ModuleHandler for LLMHandler {
     fn module_type(&self) -> ModuleType {
         ModuleType::Llm
     }
     
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+    
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        let mut ws = self.ws_sender.lock().await;
+        *ws = Some(sender);
+    }
+    
+    /// 清理资源
+    async fn cleanup(&self) {
+        // 取消正在进行的请求 (声明了输出目标的请求继续执行，结果写入输出目标)
+        let mut active = self.active_stream.lock().await;
+        if active.as_ref().is_some_and(|stream| !stream.has_sinks) {
+            if let Some(stream) = active.take() {
+                stream.cancel_token.cancel();
+            }
+        }
+    }
+    
+    async fn cancel(&self, request_id: &str) -> bool {
+        self.cancel_request(request_id).await
+    }
+    
     async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
         log_debug!("处理 LLM 消息: {}", msg.msg_type);
         
@@ -531,11 +829,11 @@ impl ModuleHandler for LLMHandler {
             "stream_start" => {
                 // 解析配置
                 let config: StreamConfig = serde_json::from_value(msg.payload.clone())
-                    .map_err(|e| RouterError::ModuleError(format!("Invalid stream config: {}", e)))?;
+                    .map_err(|e| llm_error(ErrorCode::InvalidParams, format!("Invalid stream config: {}", e)))?;
                 
                 // 开始流式请求
-                self.start_stream(config).await
-                    .map_err(|e| RouterError::ModuleError(e.to_string()))?;
+                self.start_stream(config, msg.sender()).await
+                    .map_err(|e| ModuleError::from_error(ModuleType::Llm, &e))?;
                 
                 // 返回确认消息
                 Ok(Some(ServerResponse::new(
@@ -547,7 +845,7 @@ impl ModuleHandler for LLMHandler {
             "stream_cancel" => {
                 // 取消流式请求
                 self.cancel_stream().await
-                    .map_err(|e| RouterError::ModuleError(e.to_string()))?;
+                    .map_err(|e| ModuleError::from_error(ModuleType::Llm, &e))?;
                 
                 Ok(Some(ServerResponse::new(
                     ModuleType::Llm,
@@ -556,7 +854,7 @@ impl ModuleHandler for LLMHandler {
                 )))
             }
             _ => {
-                Err(RouterError::ModuleError(format!("Unknown LLM message type: {}", msg.msg_type)))
+                Err(llm_error(ErrorCode::UnknownMessageType, format!("Unknown LLM message type: {}", msg.msg_type)).into())
             }
         }
     }
@@ -600,6 +898,7 @@ mod tests {
         assert_eq!(config.api_format, ApiFormat::ChatCompletions);
         assert!(config.headers.is_empty());
         assert!(config.request_id.is_none());
+        assert_eq!(config.priority, Priority::Interactive);
     }
     
     #[test]
@@ -607,4 +906,20 @@ mod tests {
         let handler = LLMHandler::new();
         assert_eq!(handler.module_type(), ModuleType::Llm);
     }
+    
+    #[tokio::test]
+    async fn test_cancel_request() {
+        let handler = LLMHandler::new();
+        let cancel_token = CancellationToken::new();
+        *handler.active_stream.lock().await = Some(ActiveStream {
+            request_id: Some("req-1".to_string()),
+            cancel_token: cancel_token.clone(),
+            has_sinks: false,
+        });
+        
+        assert!(!handler.cancel("req-2").await);
+        assert!(handler.cancel("req-1").await);
+        assert!(cancel_token.is_cancelled());
+        assert!(!handler.cancel("req-1").await);
+    }
 }
@@ -0,0 +1,210 @@
+// LLM 请求队列
+// 限制同时进行的 LLM 请求数，超出的请求排队等待；交互请求 (聊天、输入辅助) 优先于后台任务
+// (自动标签、批量摘要等)，且后台任务不会占满所有名额，交互请求总有一个名额可用
+
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::oneshot;
+
+/// 默认的最大并发请求数
+pub const DEFAULT_MAX_CONCURRENT: usize = 4;
+
+/// 请求优先级
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// 用户正在等待结果
+    #[default]
+    Interactive,
+    /// 后台任务，让位于交互请求
+    Background,
+}
+
+impl Priority {
+    fn index(self) -> usize {
+        match self {
+            Priority::Interactive => 0,
+            Priority::Background => 1,
+        }
+    }
+}
+
+struct QueueState {
+    max_concurrent: usize,
+    /// 各优先级正在进行的请求数
+    running: [usize; 2],
+    /// 各优先级排队的请求
+    waiting: [VecDeque<oneshot::Sender<Permit>>; 2],
+}
+
+impl QueueState {
+    fn can_start(&self, priority: Priority) -> bool {
+        let total = self.running[0] + self.running[1];
+        // 并发数大于 1 时为交互请求保留一个名额
+        let background_cap = self.max_concurrent.saturating_sub(1).max(1);
+        total < self.max_concurrent
+            && (priority == Priority::Interactive || self.running[1] < background_cap)
+    }
+}
+
+/// 请求队列
+#[derive(Clone)]
+pub struct RequestQueue {
+    state: Arc<Mutex<QueueState>>,
+}
+
+/// 请求名额 (丢弃时归还，交给排队中的下一个请求)
+pub struct Permit {
+    queue: RequestQueue,
+    priority: Priority,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.queue.lock();
+        state.running[self.priority.index()] -= 1;
+        let next = self.queue.dispatch(&mut state);
+        drop(state);
+        RequestQueue::hand_over(next);
+    }
+}
+
+impl RequestQueue {
+    /// 创建最多同时进行 `max_concurrent` 个请求的队列 (至少为 1)
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(QueueState {
+                max_concurrent: max_concurrent.max(1),
+                running: [0, 0],
+                waiting: [VecDeque::new(), VecDeque::new()],
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 等待名额；排队时交互请求先于后台请求，同一优先级按到达顺序
+    pub async fn acquire(&self, priority: Priority) -> Permit {
+        let receiver = {
+            let mut state = self.lock();
+            let overtaken = priority == Priority::Background && !state.waiting[0].is_empty();
+            if state.waiting[priority.index()].is_empty() && !overtaken && state.can_start(priority) {
+                state.running[priority.index()] += 1;
+                return Permit { queue: self.clone(), priority };
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.waiting[priority.index()].push_back(sender);
+            receiver
+        };
+        // 发送端只在交出名额时使用，不会在发送前被丢弃
+        receiver.await.expect("queued permit")
+    }
+
+    /// 为可以开始的排队请求分配名额
+    fn dispatch(&self, state: &mut QueueState) -> Vec<(oneshot::Sender<Permit>, Permit)> {
+        let mut next = Vec::new();
+        for priority in [Priority::Interactive, Priority::Background] {
+            while state.can_start(priority) {
+                let Some(sender) = state.waiting[priority.index()].pop_front() else {
+                    break;
+                };
+                state.running[priority.index()] += 1;
+                next.push((sender, Permit { queue: self.clone(), priority }));
+            }
+        }
+        next
+    }
+
+    /// 在释放锁之后交出名额；等待者已放弃 (如请求被取消) 时名额随之丢弃并归还
+    fn hand_over(next: Vec<(oneshot::Sender<Permit>, Permit)>) {
+        for (sender, permit) in next {
+            let _ = sender.send(permit);
+        }
+    }
+}
+
+static QUEUE: OnceLock<RequestQueue> = OnceLock::new();
+
+/// 按配置的并发数初始化全局队列，需在发起任何 LLM 请求之前调用 (之后的调用无效)
+pub fn init(max_concurrent: usize) {
+    let _ = QUEUE.set(RequestQueue::new(max_concurrent));
+}
+
+/// 全局请求队列
+pub fn queue() -> &'static RequestQueue {
+    QUEUE.get_or_init(|| RequestQueue::new(DEFAULT_MAX_CONCURRENT))
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 排队中的请求数 (交互, 后台)
+    fn waiting(queue: &RequestQueue) -> (usize, usize) {
+        let state = queue.lock();
+        (state.waiting[0].len(), state.waiting[1].len())
+    }
+
+    /// 确认 `acquire` 仍在等待
+    async fn pending(task: &tokio::task::JoinHandle<Permit>) {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!task.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_background_yields() {
+        let queue = RequestQueue::new(2);
+
+        // 后台任务最多占用 max - 1 个名额
+        let background = queue.acquire(Priority::Background).await;
+        let queued = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.acquire(Priority::Background).await })
+        };
+        pending(&queued).await;
+        let interactive = queue.acquire(Priority::Interactive).await;
+
+        // 名额已满：交互请求后到也先于排队中的后台请求
+        let chat = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.acquire(Priority::Interactive).await })
+        };
+        pending(&chat).await;
+        assert_eq!(waiting(&queue), (1, 1));
+        drop(background);
+        let chat = chat.await.unwrap();
+        pending(&queued).await;
+
+        drop(interactive);
+        drop(chat);
+        let _next = queued.await.unwrap();
+        assert_eq!(waiting(&queue), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waiter() {
+        let queue = RequestQueue::new(1);
+        let permit = queue.acquire(Priority::Interactive).await;
+
+        // 排队中的请求被取消后，名额交给下一个请求
+        let abandoned = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.acquire(Priority::Interactive).await })
+        };
+        pending(&abandoned).await;
+        abandoned.abort();
+        let _ = abandoned.await;
+        drop(permit);
+        let _permit = tokio::time::timeout(Duration::from_secs(1), queue.acquire(Priority::Background))
+            .await
+            .expect("permit returned");
+    }
+}
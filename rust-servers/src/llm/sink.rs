@@ -0,0 +1,179 @@
+// 流式输出的附加目标
+// 流式请求除发送到客户端外，可以同时追加到数据目录中的文件或写入 PTY 会话；
+// 客户端断开 (如 Obsidian 重新加载) 后流式请求继续执行，长文本的生成结果不会丢失
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::router::ModuleType;
+use crate::storage::{self, StorageArea};
+
+use super::LLMError;
+
+/// 日志宏
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Error, "LLM", format!($($arg)*));
+    };
+}
+
+/// 流式请求声明的输出目标
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamSink {
+    /// 追加到数据目录 artifacts 区域中的文件 (相对路径，可通过 HTTP 文件接口读取)
+    File { path: String },
+    /// 作为终端输入写入 PTY 会话
+    Pty { session_id: String },
+}
+
+/// `root` 下的相对路径；含空段、`.`、`..` 或 `\ : \0` 时返回 None
+fn resolve_path(root: &Path, relative: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for segment in relative.split('/') {
+        let invalid = segment.is_empty()
+            || segment == "."
+            || segment == ".."
+            || segment.contains(['\\', ':', '\0']);
+        if invalid {
+            return None;
+        }
+        path.push(segment);
+    }
+    Some(path)
+}
+
+/// 在 `root` 下以追加方式打开文件 (按需创建上级目录)
+fn open_file(root: &Path, relative: &str) -> Result<(PathBuf, File), LLMError> {
+    let path = resolve_path(root, relative)
+        .ok_or_else(|| LLMError::InvalidConfig(format!("无效的输出文件路径: {}", relative)))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| LLMError::InvalidConfig(format!("无法创建目录 {}: {}", parent.display(), e)))?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(&path)
+        .map_err(|e| LLMError::InvalidConfig(format!("无法打开输出文件 {}: {}", path.display(), e)))?;
+    Ok((path, file))
+}
+
+/// 已打开的输出目标
+enum OpenSink {
+    File { path: PathBuf, file: File },
+    Pty { session_id: String },
+}
+
+/// 流式请求的附加输出目标 (写入失败的目标记录错误后不再写入)
+pub(super) struct Sinks {
+    sinks: Vec<OpenSink>,
+}
+
+impl Sinks {
+    /// 打开声明的输出目标；文件路径无效或无法打开时返回 Err
+    pub(super) fn open(sinks: &[StreamSink]) -> Result<Self, LLMError> {
+        let mut root = None;
+        let sinks = sinks.iter().map(|sink| match sink {
+            StreamSink::File { path } => {
+                let root = match &root {
+                    Some(root) => root,
+                    None => root.insert(artifacts_dir()?),
+                };
+                let (path, file) = open_file(root, path)?;
+                crate::audit::record_file_write(ModuleType::Llm, &path);
+                Ok(OpenSink::File { path, file })
+            }
+            StreamSink::Pty { session_id } => Ok(OpenSink::Pty { session_id: session_id.clone() }),
+        }).collect::<Result<_, LLMError>>()?;
+        Ok(Self { sinks })
+    }
+
+    /// 是否声明了输出目标
+    pub(super) fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// 写入一段内容
+    pub(super) async fn write(&mut self, content: &str) {
+        let mut failed = Vec::new();
+        for (index, sink) in self.sinks.iter_mut().enumerate() {
+            let result = match sink {
+                OpenSink::File { path, file } => file.write_all(content.as_bytes())
+                    .map_err(|e| format!("写入 {} 失败: {}", path.display(), e)),
+                OpenSink::Pty { session_id } => crate::pty::write_session(session_id, content.as_bytes()).await
+                    .map_err(|e| format!("写入 PTY 会话 {} 失败: {}", session_id, e)),
+            };
+            if let Err(e) = result {
+                log_error!("流式输出目标不可用: {}", e);
+                failed.push(index);
+            }
+        }
+        for index in failed.into_iter().rev() {
+            self.sinks.remove(index);
+        }
+    }
+}
+
+/// artifacts 区域目录
+fn artifacts_dir() -> Result<PathBuf, LLMError> {
+    let dirs = storage::data_dirs()
+        .ok_or_else(|| LLMError::InvalidConfig("无法确定数据目录".to_string()))?;
+    dirs.ensure(StorageArea::Artifacts)
+        .map_err(|e| LLMError::InvalidConfig(format!("无法创建文件目录: {}", e)))
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sink_deserialize() {
+        let sinks: Vec<StreamSink> = serde_json::from_str(
+            r#"[{"type": "file", "path": "drafts/out.md"}, {"type": "pty", "session_id": "abc"}]"#,
+        ).unwrap();
+        assert_eq!(sinks, vec![
+            StreamSink::File { path: "drafts/out.md".to_string() },
+            StreamSink::Pty { session_id: "abc".to_string() },
+        ]);
+        assert!(serde_json::from_str::<StreamSink>(r#"{"type": "socket"}"#).is_err());
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let root = Path::new("/data/artifacts");
+        assert_eq!(resolve_path(root, "a/b.md"), Some(root.join("a").join("b.md")));
+        for invalid in ["", "/etc/passwd", "../x", "a/./b", "a//b", "a\\b", "c:x"] {
+            assert_eq!(resolve_path(root, invalid), None, "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_append() {
+        let dir = std::env::temp_dir().join(format!("llm-sink-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let (path, file) = open_file(&dir, "drafts/out.md").unwrap();
+        let mut sinks = Sinks { sinks: vec![OpenSink::File { path: path.clone(), file }] };
+        sinks.write("第一段").await;
+        sinks.write("，第二段").await;
+        // 再次打开时追加到已有内容之后
+        let (_, mut file) = open_file(&dir, "drafts/out.md").unwrap();
+        file.write_all(b"\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "第一段，第二段\n");
+
+        assert!(open_file(&dir, "../out.md").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_failed_sink_removed() {
+        let mut sinks = Sinks { sinks: vec![OpenSink::Pty { session_id: "missing".to_string() }] };
+        assert!(!sinks.is_empty());
+        sinks.write("ls\n").await;
+        assert!(sinks.is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+// 日志模块
+// 统一处理各模块的日志输出：写入 stderr 和按大小轮转的日志文件，
+// 并在内存中保留最近的日志记录供客户端查询，同时向实时订阅者推送
+
+use chrono::{Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::broadcast;
+
+/// 日志文件名 (轮转后的历史文件为 server.log.1 ... server.log.N)
+const LOG_FILE_NAME: &str = "server.log";
+
+/// 单个日志文件的最大字节数，超过后轮转
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// 保留的历史日志文件数
+const MAX_BACKUP_FILES: usize = 3;
+
+/// 内存中保留的最近日志记录数
+const MAX_RECENT_RECORDS: usize = 1000;
+
+/// 实时日志通道容量 (订阅者落后过多时丢弃旧记录)
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
+// ============================================================================
+// 日志记录
+// ============================================================================
+
+/// 日志级别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Level {
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+/// 最低输出级别 (低于此级别的日志直接丢弃)，默认输出所有级别
+static MIN_LEVEL: AtomicU8 = AtomicU8::new(Level::Debug as u8);
+
+/// 设置最低输出级别
+pub fn set_level(level: Level) {
+    MIN_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+impl Level {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+/// 单条日志记录
+#[derive(Debug, Clone, Serialize)]
+pub struct LogRecord {
+    /// 本地时间 (RFC 3339，毫秒精度)
+    pub timestamp: String,
+    /// 日志级别
+    pub level: Level,
+    /// 来源模块标签 (如 "PTY"、"Voice")，未标注时为空
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub target: String,
+    /// 日志内容
+    pub message: String,
+}
+
+impl LogRecord {
+    /// 与 stderr 输出一致的文本格式: `[LEVEL] [target] message`
+    fn to_line(&self) -> String {
+        if self.target.is_empty() {
+            format!("[{}] {}", self.level.as_str(), self.message)
+        } else {
+            format!("[{}] [{}] {}", self.level.as_str(), self.target, self.message)
+        }
+    }
+}
+
+// ============================================================================
+// 轮转日志文件
+// ============================================================================
+
+/// 按大小轮转的日志文件
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+}
+
+impl RotatingFile {
+    /// 在目录下打开 (或创建) 日志文件，追加写入
+    fn open(dir: &Path, max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_FILE_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size, max_bytes })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    /// server.log.N-1 -> server.log.N, ..., server.log -> server.log.1
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..MAX_BACKUP_FILES).rev() {
+            let from = self.backup_path(index);
+            if from.exists() {
+                fs::rename(from, self.backup_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.backup_path(1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn backup_path(&self, index: usize) -> PathBuf {
+        self.path.with_file_name(format!("{}.{}", LOG_FILE_NAME, index))
+    }
+}
+
+// ============================================================================
+// 全局日志器
+// ============================================================================
+
+struct Logger {
+    file: Mutex<Option<RotatingFile>>,
+    recent: Mutex<VecDeque<LogRecord>>,
+    live: broadcast::Sender<LogRecord>,
+}
+
+fn logger() -> &'static Logger {
+    static LOGGER: OnceLock<Logger> = OnceLock::new();
+    LOGGER.get_or_init(|| Logger {
+        file: Mutex::new(None),
+        recent: Mutex::new(VecDeque::with_capacity(MAX_RECENT_RECORDS)),
+        live: broadcast::channel(LIVE_CHANNEL_CAPACITY).0,
+    })
+}
+
+/// 启用文件输出，返回日志文件路径
+pub fn init_file(dir: &Path) -> io::Result<PathBuf> {
+    let file = RotatingFile::open(dir, MAX_FILE_BYTES)?;
+    let path = file.path.clone();
+    if let Ok(mut guard) = logger().file.lock() {
+        *guard = Some(file);
+    }
+    Ok(path)
+}
+
+/// 当前日志文件路径 (未启用文件输出时为 None)
+pub fn file_path() -> Option<PathBuf> {
+    logger().file.lock().ok()?.as_ref().map(|file| file.path.clone())
+}
+
+/// 写入一条日志
+///
+/// 各模块的 log_* 宏都通过此函数输出
+pub fn write(level: Level, target: &str, message: String) {
+    if (level as u8) < MIN_LEVEL.load(Ordering::Relaxed) {
+        return;
+    }
+    let record = LogRecord {
+        timestamp: Local::now().to_rfc3339_opts(SecondsFormat::Millis, false),
+        level,
+        target: target.to_string(),
+        message,
+    };
+    let line = record.to_line();
+    eprintln!("{}", line);
+
+    let logger = logger();
+    if let Ok(mut file) = logger.file.lock() {
+        if let Some(file) = file.as_mut() {
+            if let Err(e) = file.write_line(&format!("{} {}", record.timestamp, line)) {
+                eprintln!("[ERROR] 写入日志文件失败: {}", e);
+            }
+        }
+    }
+    if let Ok(mut recent) = logger.recent.lock() {
+        if recent.len() == MAX_RECENT_RECORDS {
+            recent.pop_front();
+        }
+        recent.push_back(record.clone());
+    }
+    // 没有实时订阅者时发送失败，忽略
+    let _ = logger.live.send(record);
+}
+
+/// 最近的日志记录 (按时间升序，最多 `limit` 条，只包含不低于 `min_level` 的记录)
+pub fn recent(limit: usize, min_level: Level) -> Vec<LogRecord> {
+    let Ok(recent) = logger().recent.lock() else {
+        return Vec::new();
+    };
+    let mut records: Vec<LogRecord> = recent
+        .iter()
+        .rev()
+        .filter(|record| record.level >= min_level)
+        .take(limit)
+        .cloned()
+        .collect();
+    records.reverse();
+    records
+}
+
+/// 订阅实时日志
+pub fn subscribe() -> broadcast::Receiver<LogRecord> {
+    logger().live.subscribe()
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_and_live() {
+        let mut live = subscribe();
+        write(Level::Debug, "Test", "recent-debug-marker".to_string());
+        write(Level::Warn, "Test", "recent-warn-marker".to_string());
+
+        let records = recent(MAX_RECENT_RECORDS, Level::Info);
+        assert!(records.iter().any(|r| r.message == "recent-warn-marker"));
+        assert!(!records.iter().any(|r| r.message == "recent-debug-marker"));
+        assert!(records.iter().all(|r| r.level >= Level::Info));
+        assert!(recent(1, Level::Debug).len() <= 1);
+
+        // 其他测试可能并发写入日志，只检查本测试的记录按顺序出现
+        let mut received = Vec::new();
+        while let Ok(record) = live.try_recv() {
+            if record.message.starts_with("recent-") {
+                received.push(record.message);
+            }
+        }
+        assert_eq!(received, vec!["recent-debug-marker", "recent-warn-marker"]);
+    }
+
+    #[test]
+    fn test_record_line() {
+        let record = |target: &str| LogRecord {
+            timestamp: String::new(),
+            level: Level::Error,
+            target: target.to_string(),
+            message: "boom".to_string(),
+        };
+        assert_eq!(record("PTY").to_line(), "[ERROR] [PTY] boom");
+        assert_eq!(record("").to_line(), "[ERROR] boom");
+    }
+
+    #[test]
+    fn test_rotating_file() {
+        let dir = std::env::temp_dir().join(format!("logging-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut file = RotatingFile::open(&dir, 64).unwrap();
+        for i in 0..(MAX_BACKUP_FILES + 3) {
+            file.write_line(&format!("line {:02} {}", i, "x".repeat(40))).unwrap();
+        }
+
+        let current = fs::read_to_string(dir.join(LOG_FILE_NAME)).unwrap();
+        assert_eq!(current.lines().count(), 1);
+        assert!(current.contains(&format!("line {:02}", MAX_BACKUP_FILES + 2)));
+        assert!(fs::read_to_string(file.backup_path(1)).unwrap().contains(&format!("line {:02}", MAX_BACKUP_FILES + 1)));
+        assert!(file.backup_path(MAX_BACKUP_FILES).exists());
+        assert!(!file.backup_path(MAX_BACKUP_FILES + 1).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -3,15 +3,51 @@
 
 mod server;
 mod router;
+mod logging;
+mod audit;
+mod config;
+mod middleware;
+mod outbound;
+mod network;
+mod watchdog;
+mod jobs;
+mod workers;
+mod instance;
+mod storage;
+mod snapshot;
+mod tls;
+mod http;
 
 // 功能模块
 pub mod pty;
 pub mod voice;
 pub mod llm;
 pub mod utils;
+pub mod files;
+pub mod clipboard;
+pub mod hotkeys;
+pub mod capture;
+pub mod git;
+pub mod scheduler;
+pub mod notify;
+pub mod playback;
+pub mod backup;
+pub mod system_info;
+pub mod meeting;
+pub mod workflow;
+pub mod vectors;
+pub mod tts;
+pub mod system;
 
-use server::{Server, ServerConfig};
-use std::env;
+// 集成测试支持
+#[cfg(any(test, feature = "test-support"))]
+pub mod testing;
+
+use clap::Parser;
+use config::{Cli, Config};
+use instance::{InstanceLock, LockError};
+use server::Server;
+use tls::TlsIdentity;
 
 const SERVER_VERSION: &str = match option_env!("SW_SERVER_VERSION") {
     Some(version) => version,
@@ -21,75 +57,174 @@ const SERVER_VERSION: &str = match option_env!("SW_SERVER_VERSION") {
 /// 日志宏
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Error, "", format!($($arg)*));
     };
 }
 
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
         }
     };
 }
 
-/// 解析命令行参数
-fn parse_args() -> u16 {
-    let args: Vec<String> = env::args().collect();
-    let mut port: u16 = 0;
-    
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-p" | "--port" => {
-                if i + 1 < args.len() {
-                    port = args[i + 1].parse().unwrap_or(0);
-                    i += 1;
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 加载配置文件并应用命令行覆盖
+    let config = match Config::load(Cli::parse()) {
+        Ok(config) => config,
+        Err(e) => {
+            log_error!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(level) = config.log_level {
+        logging::set_level(level);
+    }
+    network::init(config.network.clone());
+    llm::queue::init(config.limits.max_llm_requests);
+    workers::init(config.workers);
+
+    log_debug!("启动配置: {:?}", config);
+
+    // 数据目录 (未指定时使用平台默认目录)，并启用日志文件输出
+    match storage::DataDirs::resolve(config.data_dir.as_deref()) {
+        Some(dirs) => {
+            match dirs.ensure(storage::StorageArea::Logs).and_then(|dir| logging::init_file(&dir)) {
+                Ok(path) => {
+                    log_info!("日志文件: {}", path.display());
+                }
+                Err(e) => {
+                    log_error!("无法打开日志文件，仅输出到控制台: {}", e);
                 }
             }
-            arg if arg.starts_with("--port=") => {
-                port = arg.trim_start_matches("--port=").parse().unwrap_or(0);
+            match dirs.ensure(storage::StorageArea::Audit).and_then(|dir| audit::init_file(&dir)) {
+                Ok(path) => {
+                    log_info!("审计日志: {}", path.display());
+                }
+                Err(e) => {
+                    log_error!("无法打开审计日志文件，审计记录仅保留在内存中: {}", e);
+                }
             }
-            "-h" | "--help" => {
-                eprintln!("Usage: smart-workflow-server [OPTIONS]");
-                eprintln!("Options:");
-                eprintln!("  -p, --port <PORT>  监听端口 (0 表示随机端口) [默认: 0]");
-                eprintln!("  -h, --help         显示帮助信息");
-                eprintln!("  -V, --version      显示版本信息");
-                std::process::exit(0);
+            match dirs.ensure(storage::StorageArea::Schedules) {
+                Ok(dir) => scheduler::init_dir(dir),
+                Err(e) => {
+                    log_error!("无法创建计划任务目录，任务仅保存在内存中: {}", e);
+                }
             }
-            "-V" | "--version" => {
-                println!("{}", SERVER_VERSION);
-                std::process::exit(0);
+            match dirs.ensure(storage::StorageArea::Backups) {
+                Ok(dir) => backup::init_dir(dir),
+                Err(e) => {
+                    log_error!("无法创建备份目录，备份请求需要指定 dest: {}", e);
+                }
             }
-            _ => {}
+            match dirs.ensure(storage::StorageArea::Vectors) {
+                Ok(dir) => vectors::init_dir(dir),
+                Err(e) => {
+                    log_error!("无法创建向量目录，索引仅保存在内存中: {}", e);
+                }
+            }
+            log_info!("数据目录: {}", dirs.root().display());
+            storage::init(dirs);
+        }
+        None => {
+            log_error!("无法确定数据目录，仅输出到控制台");
         }
-        i += 1;
     }
-    
-    port
-}
 
-#[tokio::main(flavor = "current_thread")]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // 解析命令行参数
-    let port = parse_args();
+    if !config.disabled_modules.is_empty() {
+        log_info!("已禁用模块: {}", config.disabled_modules.join(", "));
+    }
 
-    log_debug!("启动参数: port={}", port);
+    // 启用 TLS 时加载数据目录中的证书 (首次启动时生成自签名证书)，失败时不退回明文监听
+    let tls = if config.tls {
+        let identity = storage::data_dirs()
+            .ok_or_else(|| "无法确定数据目录".to_string())
+            .and_then(|dirs| dirs.ensure(storage::StorageArea::Tls).map_err(|e| e.to_string()))
+            .and_then(|dir| TlsIdentity::load_or_generate(&dir).map_err(|e| e.to_string()));
+        match identity {
+            Ok(identity) => {
+                log_info!("TLS 证书指纹 (SHA-256): {}", identity.fingerprint());
+                Some(identity)
+            }
+            Err(e) => {
+                log_error!("无法启用 TLS: {}", e);
+                std::process::exit(2);
+            }
+        }
+    } else {
+        None
+    };
 
-    // 创建服务器配置
-    let config = ServerConfig { port };
+    // 同一数据目录 (含未指定时的平台默认目录) 只运行一个实例：已有实例时输出其启动信息供插件连接，并以退出码 3 退出
+    let instance_lock = match storage::data_dirs().map(|dirs| InstanceLock::acquire(dirs.root())).transpose() {
+        Ok(lock) => lock,
+        Err(LockError::AlreadyRunning { pid, path, info }) => {
+            log_error!("已有服务器实例在运行 (pid {})，锁文件: {}", pid, path.display());
+            println!("{}", info);
+            std::process::exit(3);
+        }
+        Err(e) => {
+            log_error!("{}，不启用单实例检查", e);
+            None
+        }
+    };
 
     // 创建并启动服务器
-    let server = Server::new(config);
-    let port = server.start().await?;
+    let http_files = config.http_files;
+    let mut server = Server::new(config);
+    if let Some(lock) = instance_lock {
+        server = server.with_instance_lock(lock);
+    }
+    if let Some(tls) = tls {
+        server = server.with_tls(tls);
+    }
+    // 状态快照保存在数据目录中 (无法确定数据目录时不保存也不恢复)
+    match storage::data_dirs().map(|dirs| dirs.ensure(storage::StorageArea::State)) {
+        Some(Ok(dir)) => server = server.with_state_dir(dir),
+        Some(Err(e)) => {
+            log_error!("无法创建状态目录，不保存状态快照: {}", e);
+        }
+        None => {}
+    }
+    // HTTP 文件接口提供数据目录中生成的文件
+    if http_files {
+        match storage::data_dirs().map(|dirs| dirs.ensure(storage::StorageArea::Artifacts)) {
+            Some(Ok(dir)) => {
+                log_info!("HTTP 文件接口: {}", dir.display());
+                server = server.with_files_dir(dir);
+            }
+            Some(Err(e)) => {
+                log_error!("无法创建文件目录，不提供 HTTP 文件接口: {}", e);
+            }
+            None => {
+                log_error!("无法确定数据目录，不提供 HTTP 文件接口");
+            }
+        }
+    }
+    let address = server.start().await?;
 
     // 保持主线程运行
-    log_info!("Smart Workflow Server 已启动，监听端口: {}", port);
+    log_info!("Smart Workflow Server 已启动，监听地址: {}", address);
     
-    // 等待 Ctrl+C 信号
-    tokio::signal::ctrl_c().await?;
-    log_info!("收到退出信号，正在关闭服务器...");
+    // 等待 Ctrl+C 信号，或最后一个连接断开后空闲超时 (插件崩溃后不遗留孤儿进程)
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            log_info!("收到退出信号，正在关闭服务器...");
+        }
+        _ = server.idle_exit() => {
+            log_info!("所有连接已断开且空闲超时，正在关闭服务器...");
+        }
+    }
+    server.shutdown().await;
 
     Ok(())
 }
@@ -0,0 +1,226 @@
+// 会议录音
+// 同时采集麦克风和系统音频 (对方的声音)，每个来源由采集线程追加单声道样本到各自的缓冲区，
+// 会议流水线定时取走样本。cpal 的输入流不能跨线程传递，因此在采集线程中打开并持有，
+// InputCapture 丢弃时通知线程退出并关闭输入流。
+//
+// 系统音频: Windows 上可以直接选择输出设备 (WASAPI 回环)，未指定设备时使用默认输出设备；
+// 其他平台需要回环 / 监听输入设备 (如 PulseAudio 的 Monitor、macOS 的 BlackHole)，必须指定设备名称
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+use crate::voice::audio::recorder::to_mono;
+use crate::voice::audio::{select_input_device, RecordingError};
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Error, "Meeting", format!($($arg)*));
+    };
+}
+
+/// 音频来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// 麦克风 (本地发言人)
+    Mic,
+    /// 系统音频 (远端参会者)
+    System,
+}
+
+/// 要采集的来源
+#[derive(Debug, Clone)]
+pub struct SourceSpec {
+    pub kind: SourceKind,
+    /// 设备名称 (None 为默认设备)
+    pub device: Option<String>,
+}
+
+/// 一个来源的样本缓冲区 (单声道，设备采样率)
+#[derive(Clone)]
+pub struct SourceBuffer {
+    pub kind: SourceKind,
+    pub sample_rate: u32,
+    samples: Arc<Mutex<Vec<f32>>>,
+}
+
+impl SourceBuffer {
+    pub fn new(kind: SourceKind, sample_rate: u32) -> Self {
+        Self { kind, sample_rate, samples: Arc::new(Mutex::new(Vec::new())) }
+    }
+
+    /// 追加样本 (采集线程调用)
+    pub fn push(&self, samples: &[f32]) {
+        self.samples.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(samples);
+    }
+
+    /// 取走已采集的样本
+    pub fn drain(&self) -> Vec<f32> {
+        std::mem::take(&mut *self.samples.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// 进行中的采集 (丢弃时停止)
+pub struct InputCapture {
+    pub buffers: Vec<SourceBuffer>,
+    /// 丢弃时通知采集线程关闭输入流
+    _stop: std::sync::mpsc::Sender<()>,
+}
+
+impl InputCapture {
+    pub fn new(buffers: Vec<SourceBuffer>, stop: std::sync::mpsc::Sender<()>) -> Self {
+        Self { buffers, _stop: stop }
+    }
+
+    /// 停止采集，返回缓冲区 (用于取走最后的样本)
+    pub fn stop(self) -> Vec<SourceBuffer> {
+        self.buffers
+    }
+}
+
+/// 音频输入后端
+pub trait InputBackend: Send + Sync {
+    /// 开始采集指定的来源 (任一来源无法打开时失败)
+    fn open(&self, sources: &[SourceSpec]) -> Result<InputCapture, RecordingError>;
+}
+
+// ============================================================================
+// 系统后端
+// ============================================================================
+
+/// 系统音频输入
+struct SystemInput;
+
+impl InputBackend for SystemInput {
+    fn open(&self, sources: &[SourceSpec]) -> Result<InputCapture, RecordingError> {
+        let sources = sources.to_vec();
+        let (opened_tx, opened_rx) = std::sync::mpsc::channel();
+        let (stop_tx, stop_rx) = std::sync::mpsc::channel::<()>();
+
+        std::thread::Builder::new()
+            .name("meeting-capture".to_string())
+            .spawn(move || {
+                let mut streams = Vec::new();
+                let mut buffers = Vec::new();
+                for source in &sources {
+                    match open_stream(source) {
+                        Ok((stream, buffer)) => {
+                            streams.push(stream);
+                            buffers.push(buffer);
+                        }
+                        Err(e) => {
+                            let _ = opened_tx.send(Err(e));
+                            return;
+                        }
+                    }
+                }
+                if opened_tx.send(Ok(buffers)).is_err() {
+                    return;
+                }
+                let _ = stop_rx.recv();
+                drop(streams);
+            })
+            .map_err(|e| RecordingError::DeviceError(format!("无法启动采集线程: {}", e)))?;
+
+        let buffers = opened_rx
+            .recv()
+            .map_err(|_| RecordingError::DeviceError("采集线程意外退出".to_string()))??;
+        Ok(InputCapture::new(buffers, stop_tx))
+    }
+}
+
+/// 打开一个来源的输入流并开始采集
+fn open_stream(source: &SourceSpec) -> Result<(cpal::Stream, SourceBuffer), RecordingError> {
+    let device = match source.kind {
+        SourceKind::Mic => select_input_device(source.device.as_deref())?,
+        SourceKind::System => select_loopback_device(source.device.as_deref())?,
+    };
+    // 输出设备 (WASAPI 回环) 没有输入配置，使用其输出配置
+    let supported = match (device.default_input_config(), source.kind) {
+        (Ok(config), _) => Ok(config),
+        (Err(_), SourceKind::System) => device.default_output_config(),
+        (Err(e), SourceKind::Mic) => Err(e),
+    }
+    .map_err(|e| RecordingError::DeviceError(format!("无法获取默认音频配置: {}", e)))?;
+    let config = supported.config();
+    let buffer = SourceBuffer::new(source.kind, config.sample_rate.0);
+    let stream = match supported.sample_format() {
+        cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, buffer.clone())?,
+        cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, buffer.clone())?,
+        cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, buffer.clone())?,
+        format => return Err(RecordingError::UnsupportedSampleFormat(format!("{:?}", format))),
+    };
+    stream.play().map_err(|e| RecordingError::DeviceError(e.to_string()))?;
+    Ok((stream, buffer))
+}
+
+/// 创建输入流：样本转为 f32 单声道后追加到缓冲区
+fn build_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig, buffer: SourceBuffer) -> Result<cpal::Stream, RecordingError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels;
+    let kind = buffer.kind;
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let samples: Vec<f32> = data.iter().map(|&sample| sample.to_sample::<f32>()).collect();
+                buffer.push(&to_mono(&samples, channels));
+            },
+            move |err| {
+                log_error!("会议录音流错误 ({:?}): {}", kind, err);
+            },
+            None,
+        )
+        .map_err(|e| RecordingError::DeviceError(e.to_string()))
+}
+
+/// 选择系统音频设备
+///
+/// 优先按名称匹配输入设备 (回环 / 监听设备)；Windows 上再匹配输出设备，未指定名称时使用默认输出设备
+fn select_loopback_device(name: Option<&str>) -> Result<cpal::Device, RecordingError> {
+    let host = cpal::default_host();
+    if let Some(name) = name {
+        let inputs = host
+            .input_devices()
+            .map_err(|e| RecordingError::DeviceError(format!("无法获取输入设备列表: {}", e)))?;
+        if let Some(device) = inputs.into_iter().find(|device| device.name().is_ok_and(|n| n == name)) {
+            return Ok(device);
+        }
+    }
+    if cfg!(windows) {
+        let device = match name {
+            Some(name) => host
+                .output_devices()
+                .map_err(|e| RecordingError::DeviceError(format!("无法获取输出设备列表: {}", e)))?
+                .find(|device| device.name().is_ok_and(|n| n == name)),
+            None => host.default_output_device(),
+        };
+        if let Some(device) = device {
+            return Ok(device);
+        }
+    }
+    Err(RecordingError::MicrophoneUnavailable(match name {
+        Some(name) => format!("未找到系统音频设备: {}", name),
+        None => "未指定系统音频设备 (需要回环或监听输入设备)".to_string(),
+    }))
+}
+
+/// 打开系统音频输入
+#[cfg(not(any(test, feature = "test-support")))]
+pub fn open() -> Box<dyn InputBackend> {
+    Box::new(SystemInput)
+}
+
+/// 打开音频输入 (安装了模拟输入时使用模拟输入)
+#[cfg(any(test, feature = "test-support"))]
+pub fn open() -> Box<dyn InputBackend> {
+    if crate::testing::audio_input::is_installed() {
+        return Box::new(crate::testing::audio_input::FakeInput);
+    }
+    Box::new(SystemInput)
+}
@@ -0,0 +1,756 @@
+// 会议模块
+// 会议模式同时录制麦克风和系统音频，按分段 (默认 30 秒，在停顿处切开) 边录边转写，每段转写完成后推送 segment 事件；
+// stop 后转写剩余的音频，再调用 LLM 生成摘要、待办和决定，最后推送 completed 事件 (结构化结果，由插件写入会议笔记)。
+// 开启 diarize 时麦克风和系统音频分别转写，片段按来源标注发言人 local / remote。
+// process 消息对已有的录音 (base64 WAV) 执行同样的流程。各阶段通过 progress 事件报告，可以用 cancel 或 system/cancel 取消
+
+mod capture;
+mod summary;
+mod transcript;
+
+pub use capture::{open as open_input, InputBackend, InputCapture, SourceBuffer, SourceKind, SourceSpec};
+pub use summary::{ActionItem, Summary, SummaryConfig};
+pub use transcript::{Segment, Speaker};
+
+use transcript::{samples_to_ms, Chunk, Chunker};
+
+use crate::jobs::{self, Job, JobKind};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use crate::system::progress::{percent_between, ProgressReporter};
+use crate::voice::asr::WordTiming;
+use crate::voice::audio::recorder::{resample, to_mono};
+use crate::voice::audio::{decode_wav, utils, AudioData, TARGET_SAMPLE_RATE};
+use crate::voice::config::{ASRConfig, ASRMode};
+use base64::{Engine as _, engine::general_purpose};
+use futures_util::SinkExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex as TokioMutex};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Meeting", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Meeting", format!($($arg)*));
+        }
+    };
+}
+
+/// 默认分段时长 (秒)
+const DEFAULT_CHUNK_SECS: u32 = 30;
+
+/// 分段时长的范围 (秒)
+const CHUNK_SECS_RANGE: std::ops::RangeInclusive<u32> = 10..=120;
+
+/// 录音期间取走采集样本的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 同时进行的会议 (含 process) 上限
+const MAX_SESSIONS: usize = 4;
+
+/// AGC 按块处理的样本数 (0.2 秒 @ 16kHz)
+const AGC_CHUNK_SAMPLES: usize = 3200;
+
+/// 创建会议模块错误
+fn meeting_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Meeting, code, message)
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// start 和 process 共用的选项
+#[derive(Debug, Deserialize)]
+struct Options {
+    asr_config: ASRConfig,
+    /// 按来源分别转写并标注发言人
+    #[serde(default)]
+    diarize: bool,
+    /// 分段时长 (秒)
+    #[serde(default)]
+    chunk_seconds: Option<u32>,
+    /// 生成纪要的 LLM 配置 (省略时只转写)
+    #[serde(default)]
+    summary: Option<SummaryConfig>,
+}
+
+impl Options {
+    fn validate(&self) -> Result<(), ModuleError> {
+        if let Some(secs) = self.chunk_seconds.filter(|secs| !CHUNK_SECS_RANGE.contains(secs)) {
+            return Err(meeting_error(
+                ErrorCode::InvalidParams,
+                format!("chunk_seconds 超出范围: {} (应为 {}-{})", secs, CHUNK_SECS_RANGE.start(), CHUNK_SECS_RANGE.end()),
+            ));
+        }
+        if self.summary.as_ref().is_some_and(|summary| summary.endpoint.is_empty()) {
+            return Err(meeting_error(ErrorCode::InvalidParams, "summary.endpoint 不能为空"));
+        }
+        self.asr_config.validate().map_err(|e| meeting_error(ErrorCode::InvalidConfig, e.to_string()))
+    }
+
+    fn chunk_secs(&self) -> u32 {
+        self.chunk_seconds.unwrap_or(DEFAULT_CHUNK_SECS)
+    }
+
+    /// 分段转写使用 HTTP 模式 (实时模式的配置同样适用)
+    fn http_asr_config(&self) -> ASRConfig {
+        let mut config = self.asr_config.clone();
+        config.primary.mode = ASRMode::Http;
+        if let Some(fallback) = config.fallback.as_mut() {
+            fallback.mode = ASRMode::Http;
+        }
+        config
+    }
+}
+
+/// start 请求
+#[derive(Debug, Deserialize)]
+struct StartRequest {
+    #[serde(flatten)]
+    options: Options,
+    /// 是否录制麦克风
+    #[serde(default = "default_true")]
+    mic: bool,
+    #[serde(default)]
+    mic_device: Option<String>,
+    /// 是否录制系统音频
+    #[serde(default)]
+    system: bool,
+    #[serde(default)]
+    system_device: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// process 请求
+#[derive(Debug, Deserialize)]
+struct ProcessRequest {
+    #[serde(flatten)]
+    options: Options,
+    /// 麦克风或混合后的录音 (base64 WAV)
+    audio: String,
+    /// 单独录制的系统音频 (base64 WAV，用于区分发言人)
+    #[serde(default)]
+    system_audio: Option<String>,
+}
+
+/// stop / cancel 请求
+#[derive(Debug, Default, Deserialize)]
+struct SessionRequest {
+    /// 会议 ID (省略时为正在录音的会议)
+    #[serde(default)]
+    id: Option<String>,
+}
+
+fn parse<T: serde::de::DeserializeOwned>(msg: &ModuleMessage) -> Result<T, ModuleError> {
+    serde_json::from_value(msg.payload.clone())
+        .map_err(|e| meeting_error(ErrorCode::InvalidParams, format!("无效的 {} 请求: {}", msg.msg_type, e)))
+}
+
+/// 解码 base64 WAV 为 16 kHz 单声道样本
+fn decode_track(field: &str, data: &str) -> Result<Vec<f32>, ModuleError> {
+    let wav = general_purpose::STANDARD
+        .decode(data.as_bytes())
+        .map_err(|e| meeting_error(ErrorCode::InvalidParams, format!("{} 不是有效的 base64: {}", field, e)))?;
+    let audio = decode_wav(&wav)
+        .map_err(|e| meeting_error(ErrorCode::InvalidParams, format!("无法解码 WAV 音频: {}", e)))?;
+    Ok(resample(&to_mono(&audio.samples, audio.channels), audio.sample_rate, TARGET_SAMPLE_RATE))
+}
+
+// ============================================================================
+// 会议状态和结果
+// ============================================================================
+
+/// 会议状态 (status 消息返回)
+#[derive(Debug, Clone, Serialize)]
+struct Status {
+    id: String,
+    /// 是否为实时录音 (process 为 false)
+    live: bool,
+    /// recording / transcribing / summarizing
+    stage: &'static str,
+    /// 已录制或已读取的时长 (毫秒)
+    position_ms: u64,
+    /// 已切出的分段数
+    chunks: usize,
+    /// 已转写的片段数
+    segments: usize,
+}
+
+/// 进行中的会议
+struct Session {
+    request_id: Option<String>,
+    /// 停止录音 (仅实时会议；stop 后为 None)
+    stop: Option<oneshot::Sender<()>>,
+    cancel: CancellationToken,
+    status: Arc<Mutex<Status>>,
+}
+
+/// 会议结果 (completed 事件)
+#[derive(Debug, Clone, Serialize)]
+struct MeetingResult {
+    id: String,
+    duration_ms: u64,
+    segments: Vec<Segment>,
+    /// 纯文本转写稿 (每个片段一行)
+    transcript: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<Summary>,
+    /// 生成纪要失败的原因 (转写结果仍然返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary_error: Option<String>,
+    /// 转写失败的片段数
+    failed_segments: usize,
+}
+
+/// 音频输入
+enum Input {
+    /// 实时录音，收到停止信号后结束
+    Live { capture: InputCapture, stop: oneshot::Receiver<()> },
+    /// 已有的录音 (16 kHz 单声道)
+    Recorded(Vec<(SourceKind, Vec<f32>)>),
+}
+
+/// 会议事件的发送目标
+#[derive(Clone)]
+struct Events {
+    sender: WsSender,
+    request_id: Option<String>,
+}
+
+impl Events {
+    async fn send(&self, event: ServerResponse) {
+        let event = event.with_request_id(self.request_id.as_deref());
+        let _ = self.sender.lock().await.send(Message::Text(event.to_json().into())).await;
+    }
+}
+
+// ============================================================================
+// 流水线
+// ============================================================================
+
+/// 一次会议的处理流程
+struct Pipeline {
+    id: String,
+    options: Options,
+    events: Events,
+    progress: ProgressReporter,
+    status: Arc<Mutex<Status>>,
+}
+
+impl Pipeline {
+    fn status(&self) -> std::sync::MutexGuard<'_, Status> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 录音 (或读取录音) 的同时转写分段，全部转写完成后生成纪要
+    async fn run(self, input: Input, kinds: Vec<SourceKind>) -> MeetingResult {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let chunker = Chunker::new(&kinds, self.options.chunk_secs());
+        let (duration_ms, mut segments) = tokio::join!(self.produce(input, chunker, tx), self.transcribe(rx));
+        segments.sort_by_key(|segment| (segment.chunk, segment.start_ms));
+
+        let transcript = transcript::format_transcript(&segments);
+        let (summary, summary_error) = match &self.options.summary {
+            Some(config) if !transcript.is_empty() => {
+                self.status().stage = "summarizing";
+                self.progress.report("summarizing", None, None).await;
+                match summary::summarize(config, &transcript, self.events.request_id.clone()).await {
+                    Ok(summary) => (Some(summary), None),
+                    Err(e) => {
+                        log_info!("生成会议纪要失败: {}", e);
+                        (None, Some(e.to_string()))
+                    }
+                }
+            }
+            _ => (None, None),
+        };
+        MeetingResult {
+            id: self.id,
+            duration_ms,
+            failed_segments: segments.iter().filter(|segment| segment.error.is_some()).count(),
+            segments,
+            transcript,
+            summary,
+            summary_error,
+        }
+    }
+
+    /// 切出分段并送去转写，返回总时长
+    async fn produce(&self, input: Input, mut chunker: Chunker, tx: mpsc::UnboundedSender<Chunk>) -> u64 {
+        let queue = |chunk: Chunk| {
+            self.status().chunks += 1;
+            let _ = tx.send(chunk);
+        };
+        match input {
+            Input::Live { capture, mut stop } => {
+                self.progress.report("recording", None, None).await;
+                let mut ticker = tokio::time::interval(POLL_INTERVAL);
+                let mut capture = Some(capture);
+                while let Some(live) = capture.take() {
+                    let stopping = tokio::select! {
+                        _ = ticker.tick() => false,
+                        _ = &mut stop => true,
+                    };
+                    // 停止时先关闭输入流，再取走最后的样本
+                    let buffers = if stopping {
+                        live.stop()
+                    } else {
+                        let buffers = live.buffers.clone();
+                        capture = Some(live);
+                        buffers
+                    };
+                    for buffer in &buffers {
+                        chunker.push(buffer.kind, &resample(&buffer.drain(), buffer.sample_rate, TARGET_SAMPLE_RATE));
+                    }
+                    self.status().position_ms = chunker.position_ms();
+                    while let Some(chunk) = chunker.next_chunk() {
+                        queue(chunk);
+                        let detail = transcript::format_timestamp(chunker.position_ms());
+                        self.progress.report("recording", None, Some(detail)).await;
+                    }
+                }
+                log_info!("会议录音结束: {} ({}ms)", self.id, chunker.position_ms());
+            }
+            Input::Recorded(tracks) => {
+                for (kind, samples) in &tracks {
+                    chunker.push(*kind, samples);
+                }
+                self.status().position_ms = chunker.position_ms();
+                while let Some(chunk) = chunker.next_chunk() {
+                    queue(chunk);
+                }
+            }
+        }
+        if let Some(chunk) = chunker.finish() {
+            queue(chunk);
+        }
+        let (chunks, segments) = {
+            let mut status = self.status();
+            status.stage = "transcribing";
+            (status.chunks, status.segments)
+        };
+        let done = segments.min(chunks);
+        self.progress.report("transcribing", Some(percent_between(0, 100, done, chunks)), None).await;
+        chunker.position_ms()
+    }
+
+    /// 依次转写分段，推送 segment 事件
+    async fn transcribe(&self, mut rx: mpsc::UnboundedReceiver<Chunk>) -> Vec<Segment> {
+        let asr_config = self.options.http_asr_config();
+        let mut segments = Vec::new();
+        let mut done = 0;
+        // 已转写文本的末尾，作为下一段的提示
+        let mut context = String::new();
+        while let Some(chunk) = rx.recv().await {
+            log_debug!("转写分段 {} ({}ms)", chunk.index, chunk.duration_ms());
+            let prompt = Some(context.as_str()).filter(|context| !context.is_empty());
+            let chunk_segments = transcribe_chunk(&chunk, &asr_config, self.options.diarize, prompt).await;
+            for segment in &chunk_segments {
+                if !segment.text.is_empty() {
+                    if !context.is_empty() {
+                        context.push(' ');
+                    }
+                    context.push_str(&segment.text);
+                }
+            }
+            context = transcript::context_tail(&context).to_string();
+            for segment in chunk_segments {
+                let payload = serde_json::to_value(&segment).unwrap_or_default();
+                self.events.send(ServerResponse::new(ModuleType::Meeting, "segment", payload)).await;
+                segments.push(segment);
+            }
+            done += 1;
+            let (stage, chunks) = {
+                let mut status = self.status();
+                status.segments = segments.len();
+                (status.stage, status.chunks)
+            };
+            if stage == "transcribing" {
+                let detail = format!("{}/{}", done, chunks);
+                self.progress.report("transcribing", Some(percent_between(0, 100, done, chunks)), Some(detail)).await;
+            }
+        }
+        segments
+    }
+}
+
+/// 转写一个分段：开启 diarize 时各来源分别转写并标注发言人，否则混合后转写。整段静音的来源不转写。
+/// `prompt` 为上一段的末尾文本，支持提示的引擎 (whisper、openai) 用它衔接上下文
+async fn transcribe_chunk(chunk: &Chunk, asr_config: &ASRConfig, diarize: bool, prompt: Option<&str>) -> Vec<Segment> {
+    let tracks: Vec<(Option<Speaker>, Vec<f32>)> = if diarize {
+        chunk.tracks.iter().map(|(kind, samples)| (Some(Speaker::from(*kind)), samples.clone())).collect()
+    } else {
+        vec![(None, chunk.mixed())]
+    };
+
+    let mut segments = Vec::new();
+    for (speaker, samples) in tracks {
+        let Some((start, end)) = transcript::voiced_range(&samples) else {
+            continue;
+        };
+        let mut voiced = samples[start..end].to_vec();
+        let mut gain = 1.0;
+        for block in voiced.chunks_mut(AGC_CHUNK_SAMPLES) {
+            utils::apply_agc(block, &mut gain);
+        }
+        let audio = AudioData::new(voiced, TARGET_SAMPLE_RATE, 1);
+        let mut segment = Segment {
+            chunk: chunk.index,
+            start_ms: chunk.start_ms + samples_to_ms(start),
+            end_ms: chunk.start_ms + samples_to_ms(end),
+            speaker,
+            text: String::new(),
+            engine: None,
+            error: None,
+            words: None,
+        };
+        match crate::voice::perform_transcription_with_prompt(&audio, asr_config, prompt).await {
+            Ok(result) => {
+                segment.text = crate::voice::post_process_text(result.text.trim().to_string(), asr_config);
+                segment.engine = Some(result.engine);
+                // 引擎的时间相对转写的音频 (有语音的范围)，换算为相对会议开始
+                segment.words = result.words.filter(|_| asr_config.word_timestamps).map(|words| {
+                    words.into_iter()
+                        .map(|word| WordTiming {
+                            start_ms: word.start_ms + segment.start_ms,
+                            end_ms: word.end_ms + segment.start_ms,
+                            ..word
+                        })
+                        .collect()
+                });
+            }
+            Err(e) => {
+                log_info!("分段 {} 转写失败: {}", chunk.index, e);
+                segment.error = Some(e.to_string());
+            }
+        }
+        if !segment.text.is_empty() || segment.error.is_some() {
+            segments.push(segment);
+        }
+    }
+    segments.sort_by_key(|segment| segment.start_ms);
+    segments
+}
+
+// ============================================================================
+// 会议处理器
+// ============================================================================
+
+/// 会议模块处理器
+pub struct MeetingHandler {
+    /// 进行中的会议: 会议 ID → 会议
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+    /// WebSocket 发送器 (请求没有来源连接时使用)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl MeetingHandler {
+    /// 创建新的会议处理器
+    pub fn new() -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    fn sessions(&self) -> std::sync::MutexGuard<'_, HashMap<String, Session>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 事件发送目标：优先使用发起请求的连接
+    async fn events(&self, msg: &ModuleMessage) -> Result<Events, ModuleError> {
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+        let sender = sender.ok_or_else(|| meeting_error(ErrorCode::NotConnected, "WebSocket sender not set"))?;
+        Ok(Events { sender, request_id: msg.request_id() })
+    }
+
+    /// 登记会议并在后台运行流水线，结束后推送 completed 事件
+    fn launch(&self, options: Options, events: Events, input: Input, kinds: Vec<SourceKind>) -> Result<String, ModuleError> {
+        let id = Uuid::new_v4().to_string();
+        let (stop, input) = match input {
+            Input::Live { capture, .. } => {
+                let (stop_tx, stop_rx) = oneshot::channel();
+                (Some(stop_tx), Input::Live { capture, stop: stop_rx })
+            }
+            input => (None, input),
+        };
+        let status = Arc::new(Mutex::new(Status {
+            id: id.clone(),
+            live: stop.is_some(),
+            stage: if stop.is_some() { "recording" } else { "transcribing" },
+            position_ms: 0,
+            chunks: 0,
+            segments: 0,
+        }));
+        let cancel = CancellationToken::new();
+        {
+            let mut sessions = self.sessions();
+            if sessions.len() >= MAX_SESSIONS {
+                return Err(meeting_error(ErrorCode::LimitExceeded, format!("进行中的会议过多 (最多 {} 个)", MAX_SESSIONS)));
+            }
+            if stop.is_some() && sessions.values().any(|session| session.stop.is_some()) {
+                return Err(meeting_error(ErrorCode::AlreadyRecording, "已有会议在录音中"));
+            }
+            sessions.insert(id.clone(), Session {
+                request_id: events.request_id.clone(),
+                stop,
+                cancel: cancel.clone(),
+                status: Arc::clone(&status),
+            });
+        }
+
+        let pipeline = Pipeline {
+            id: id.clone(),
+            options,
+            progress: ProgressReporter::new(ModuleType::Meeting, events.request_id.clone(), Some(events.sender.clone())),
+            events: events.clone(),
+            status,
+        };
+        let sessions = Arc::clone(&self.sessions);
+        let session_id = id.clone();
+        let job = jobs::register(
+            Job::new(JobKind::Meeting).with_request_id(events.request_id.clone()).with_detail(id.clone()).with_cancel(cancel.clone()),
+        );
+        tokio::spawn(crate::audit::inherit(async move {
+            let _job = job;
+            let result = tokio::select! {
+                result = pipeline.run(input, kinds) => Some(result),
+                _ = cancel.cancelled() => None,
+            };
+            sessions.lock().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+            match result {
+                Some(result) => {
+                    log_info!("会议完成: {} ({} 个片段)", session_id, result.segments.len());
+                    let payload = serde_json::to_value(&result).unwrap_or_default();
+                    events.send(ServerResponse::new(ModuleType::Meeting, "completed", payload)).await;
+                }
+                None => {
+                    log_info!("会议已取消: {}", session_id);
+                }
+            }
+        }));
+        Ok(id)
+    }
+
+    /// 处理 start 消息 - 开始录制会议
+    async fn handle_start(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: StartRequest = parse(msg)?;
+        request.options.validate()?;
+        let mut sources = Vec::new();
+        if request.mic {
+            sources.push(SourceSpec { kind: SourceKind::Mic, device: request.mic_device.clone() });
+        }
+        if request.system {
+            sources.push(SourceSpec { kind: SourceKind::System, device: request.system_device.clone() });
+        }
+        if sources.is_empty() {
+            return Err(meeting_error(ErrorCode::InvalidParams, "mic 和 system 至少启用一个").into());
+        }
+        if self.sessions().values().any(|session| session.stop.is_some()) {
+            return Err(meeting_error(ErrorCode::AlreadyRecording, "已有会议在录音中").into());
+        }
+        let events = self.events(msg).await?;
+
+        let specs = sources.clone();
+        let capture = tokio::task::spawn_blocking(move || capture::open().open(&specs))
+            .await
+            .map_err(|e| meeting_error(ErrorCode::Internal, format!("打开录音设备失败: {}", e)))?
+            .map_err(|e| meeting_error(ErrorCode::DeviceError, format!("打开录音设备失败: {}", e)))?;
+        let kinds: Vec<SourceKind> = sources.iter().map(|source| source.kind).collect();
+        let chunk_seconds = request.options.chunk_secs();
+        let (_, placeholder) = oneshot::channel();
+        let id = self.launch(request.options, events, Input::Live { capture, stop: placeholder }, kinds.clone())?;
+        log_info!("开始会议录音: {} {:?}", id, kinds);
+
+        Ok(Some(ServerResponse::new(ModuleType::Meeting, "started", serde_json::json!({
+            "id": id,
+            "sources": kinds,
+            "chunk_seconds": chunk_seconds,
+        }))))
+    }
+
+    /// 处理 process 消息 - 对已有录音执行会议流程
+    async fn handle_process(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: ProcessRequest = parse(msg)?;
+        request.options.validate()?;
+        let mut tracks = vec![(SourceKind::Mic, decode_track("audio", &request.audio)?)];
+        if let Some(system_audio) = &request.system_audio {
+            tracks.push((SourceKind::System, decode_track("system_audio", system_audio)?));
+        }
+        let duration_ms = tracks.iter().map(|(_, samples)| samples_to_ms(samples.len())).max().unwrap_or(0);
+        let events = self.events(msg).await?;
+        let kinds = tracks.iter().map(|(kind, _)| *kind).collect();
+        let id = self.launch(request.options, events, Input::Recorded(tracks), kinds)?;
+        log_info!("处理会议录音: {} ({}ms)", id, duration_ms);
+
+        Ok(Some(ServerResponse::new(ModuleType::Meeting, "process_started", serde_json::json!({
+            "id": id,
+            "duration_ms": duration_ms,
+        }))))
+    }
+
+    /// 查找会议：指定 ID 时按 ID 查找，否则为正在录音的会议
+    fn find(sessions: &HashMap<String, Session>, id: Option<&str>) -> Option<String> {
+        match id {
+            Some(id) => sessions.contains_key(id).then(|| id.to_string()),
+            None => sessions.iter().find(|(_, session)| session.stop.is_some()).map(|(id, _)| id.clone()),
+        }
+    }
+
+    /// 处理 stop 消息 - 停止录音，转写剩余音频并生成纪要
+    async fn handle_stop(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SessionRequest = parse(msg)?;
+        let mut sessions = self.sessions();
+        let stop = Self::find(&sessions, request.id.as_deref())
+            .and_then(|id| sessions.get_mut(&id).and_then(|session| session.stop.take()).map(|stop| (id, stop)));
+        let Some((id, stop)) = stop else {
+            return Err(meeting_error(ErrorCode::NotRecording, "没有正在录音的会议").into());
+        };
+        drop(sessions);
+        let _ = stop.send(());
+        log_info!("停止会议录音: {}", id);
+        Ok(Some(ServerResponse::new(ModuleType::Meeting, "stopping", serde_json::json!({ "id": id }))))
+    }
+
+    /// 处理 cancel 消息 - 放弃会议 (不推送 completed 事件)
+    async fn handle_cancel(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SessionRequest = parse(msg)?;
+        let mut sessions = self.sessions();
+        let session = Self::find(&sessions, request.id.as_deref()).and_then(|id| sessions.remove(&id).map(|s| (id, s)));
+        let Some((id, session)) = session else {
+            return Err(meeting_error(ErrorCode::NotFound, "会议不存在").into());
+        };
+        session.cancel.cancel();
+        log_info!("取消会议: {}", id);
+        Ok(Some(ServerResponse::new(ModuleType::Meeting, "cancelled", serde_json::json!({ "id": id }))))
+    }
+
+    /// 处理 status 消息 - 列出进行中的会议
+    async fn handle_status(&self) -> Result<Option<ServerResponse>, RouterError> {
+        let meetings: Vec<Status> = self.sessions()
+            .values()
+            .map(|session| session.status.lock().unwrap_or_else(|e| e.into_inner()).clone())
+            .collect();
+        Ok(Some(ServerResponse::new(ModuleType::Meeting, "status", serde_json::json!({ "meetings": meetings }))))
+    }
+}
+
+impl Default for MeetingHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 纪要的 LLM 配置字段 (对应 SummaryConfig)
+const SUMMARY_FIELDS: &[FieldSpec] = &[
+    FieldSpec::required("endpoint", FieldKind::String),
+    FieldSpec::optional("headers", FieldKind::Object),
+    FieldSpec::required("model", FieldKind::String),
+    FieldSpec::optional("api_format", FieldKind::String).one_of(&["chat_completions", "responses"]),
+    FieldSpec::optional("prompt", FieldKind::String),
+];
+
+/// 会议模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("start", &[
+        FieldSpec::required("asr_config", FieldKind::Object).with_fields(crate::voice::ASR_CONFIG_FIELDS),
+        FieldSpec::optional("mic", FieldKind::Boolean),
+        FieldSpec::optional("mic_device", FieldKind::String),
+        FieldSpec::optional("system", FieldKind::Boolean),
+        FieldSpec::optional("system_device", FieldKind::String),
+        FieldSpec::optional("diarize", FieldKind::Boolean),
+        FieldSpec::optional("chunk_seconds", FieldKind::Integer),
+        FieldSpec::optional("summary", FieldKind::Object).with_fields(SUMMARY_FIELDS),
+    ]),
+    MessageSpec::new("stop", &[FieldSpec::optional("id", FieldKind::String)]),
+    MessageSpec::new("cancel", &[FieldSpec::optional("id", FieldKind::String)]),
+    MessageSpec::new("status", &[]),
+    MessageSpec::new("process", &[
+        FieldSpec::required("asr_config", FieldKind::Object).with_fields(crate::voice::ASR_CONFIG_FIELDS),
+        FieldSpec::required("audio", FieldKind::String),
+        FieldSpec::optional("system_audio", FieldKind::String),
+        FieldSpec::optional("diarize", FieldKind::Boolean),
+        FieldSpec::optional("chunk_seconds", FieldKind::Integer),
+        FieldSpec::optional("summary", FieldKind::Object).with_fields(SUMMARY_FIELDS),
+    ]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 会议模块
+    pub const Meeting: ModuleType = ModuleType::named("meeting");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for MeetingHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Meeting
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    /// 取消进行中的会议 (连接关闭时调用)
+    async fn cleanup(&self) {
+        for (_, session) in self.sessions().drain() {
+            session.cancel.cancel();
+        }
+    }
+
+    /// 取消由 request_id 发起的会议
+    async fn cancel(&self, request_id: &str) -> bool {
+        let mut sessions = self.sessions();
+        let id = sessions.iter()
+            .find(|(_, session)| session.request_id.as_deref() == Some(request_id))
+            .map(|(id, _)| id.clone());
+        match id.and_then(|id| sessions.remove(&id)) {
+            Some(session) => {
+                log_info!("取消会议: request_id={}", request_id);
+                session.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理会议消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "start" => self.handle_start(msg).await,
+            "stop" => self.handle_stop(msg).await,
+            "cancel" => self.handle_cancel(msg).await,
+            "status" => self.handle_status().await,
+            "process" => self.handle_process(msg).await,
+            _ => Err(meeting_error(ErrorCode::UnknownMessageType, format!("未知的会议消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// 会议纪要
+// 把转写稿发送给客户端配置的 LLM 端点 (Chat Completions 或 Responses 格式)，要求以 JSON 返回摘要、
+// 待办事项和决定。模型没有按要求返回 JSON 时，把全文作为摘要并从 Markdown 任务列表中提取待办
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::llm::response::ApiFormat;
+use crate::llm::{LLMError, Priority, StreamConfig};
+
+/// 默认的系统提示词
+const DEFAULT_PROMPT: &str = "You are a meeting assistant. Summarize the meeting transcript provided by the user. \
+Each line is `[time] speaker: text`; speaker `local` is the person who recorded the meeting and `remote` are the other \
+participants (lines without a speaker were not separated). Reply in the language of the transcript with only a JSON \
+object of the form {\"summary\": \"...\", \"action_items\": [{\"task\": \"...\", \"owner\": \"...\", \"due\": \"...\"}], \
+\"decisions\": [\"...\"]}. Omit owner or due when the transcript does not mention them.";
+
+/// 纪要的 LLM 配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct SummaryConfig {
+    /// API 端点
+    pub endpoint: String,
+    /// 请求头 (如 Authorization)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 模型名称
+    pub model: String,
+    #[serde(default)]
+    pub api_format: ApiFormat,
+    /// 替换默认的系统提示词
+    #[serde(default)]
+    pub prompt: Option<String>,
+}
+
+impl SummaryConfig {
+    /// 构造流式请求
+    fn request(&self, transcript: &str, request_id: Option<String>) -> StreamConfig {
+        let prompt = self.prompt.as_deref().unwrap_or(DEFAULT_PROMPT);
+        StreamConfig {
+            endpoint: self.endpoint.clone(),
+            headers: self.headers.clone(),
+            body: crate::llm::prompt_body(self.api_format, &self.model, prompt, transcript),
+            api_format: self.api_format,
+            request_id,
+            priority: Priority::Background,
+            sinks: Vec::new(),
+        }
+    }
+}
+
+/// 待办事项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionItem {
+    pub task: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+}
+
+/// 会议纪要
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub summary: String,
+    #[serde(default)]
+    pub action_items: Vec<ActionItem>,
+    #[serde(default)]
+    pub decisions: Vec<String>,
+}
+
+/// 生成会议纪要
+pub async fn summarize(config: &SummaryConfig, transcript: &str, request_id: Option<String>) -> Result<Summary, LLMError> {
+    let content = crate::llm::complete(config.request(transcript, request_id)).await?;
+    Ok(parse(&content))
+}
+
+/// 解析模型的回复 (允许 JSON 外有代码块标记或说明文字)
+pub fn parse(content: &str) -> Summary {
+    let json = content
+        .find('{')
+        .zip(content.rfind('}'))
+        .and_then(|(start, end)| content.get(start..=end));
+    if let Some(summary) = json.and_then(|json| serde_json::from_str::<Summary>(json).ok()) {
+        return summary;
+    }
+    let action_items = content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            line.strip_prefix("- [ ]").or_else(|| line.strip_prefix("* [ ]"))
+        })
+        .map(|task| ActionItem { task: task.trim().to_string(), owner: None, due: None })
+        .filter(|item| !item.task.is_empty())
+        .collect();
+    Summary {
+        summary: content.trim().to_string(),
+        action_items,
+        decisions: Vec::new(),
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json() {
+        let content = "```json\n{\"summary\": \"讨论了发布计划\", \"action_items\": [{\"task\": \"更新文档\", \"owner\": \"小王\"}]}\n```";
+        assert_eq!(parse(content), Summary {
+            summary: "讨论了发布计划".to_string(),
+            action_items: vec![ActionItem { task: "更新文档".to_string(), owner: Some("小王".to_string()), due: None }],
+            decisions: Vec::new(),
+        });
+    }
+
+    #[test]
+    fn test_parse_fallback() {
+        let content = "## 摘要\n确定了上线时间。\n\n- [ ] 准备发布说明\n- [x] 已完成的事\n  * [ ] 通知用户\n";
+        let summary = parse(content);
+        assert_eq!(summary.summary, content.trim());
+        let tasks: Vec<&str> = summary.action_items.iter().map(|item| item.task.as_str()).collect();
+        assert_eq!(tasks, vec!["准备发布说明", "通知用户"]);
+    }
+
+    #[test]
+    fn test_request_body() {
+        let config: SummaryConfig = serde_json::from_value(serde_json::json!({
+            "endpoint": "https://api.example.com/v1/responses",
+            "model": "gpt-test",
+            "api_format": "responses",
+        }))
+        .unwrap();
+        let request = config.request("[00:01] 你好", None);
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["input"], "[00:01] 你好");
+        assert_eq!(body["instructions"], DEFAULT_PROMPT);
+        assert_eq!(request.api_format, ApiFormat::Responses);
+    }
+}
@@ -0,0 +1,339 @@
+// 分段和转写稿
+// 各来源的样本 (16 kHz 单声道) 按时间对齐累积，达到分段时长后在末尾几秒内最安静的位置切开，
+// 避免把一句话切成两半。每段按来源分别或混合后转写，得到带时间戳 (相对会议开始) 的片段
+
+use serde::Serialize;
+
+use super::capture::SourceKind;
+use crate::voice::asr::WordTiming;
+use crate::voice::audio::utils::is_voice_active;
+use crate::voice::audio::TARGET_SAMPLE_RATE;
+
+/// 分析音量的帧长 (100 毫秒)
+const FRAME_SAMPLES: usize = TARGET_SAMPLE_RATE as usize / 10;
+
+/// 在分段末尾的这段时间内寻找切分点 (秒)
+const CUT_SEARCH_SECS: usize = 5;
+
+/// 样本数换算为毫秒
+pub fn samples_to_ms(samples: usize) -> u64 {
+    samples as u64 * 1000 / TARGET_SAMPLE_RATE as u64
+}
+
+/// 一段待转写的音频
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    /// 分段序号 (从 0 开始)
+    pub index: usize,
+    /// 相对会议开始的起始时间 (毫秒)
+    pub start_ms: u64,
+    /// 各来源的样本 (长度相同，较短的来源补静音)
+    pub tracks: Vec<(SourceKind, Vec<f32>)>,
+}
+
+impl Chunk {
+    /// 样本数
+    pub fn len(&self) -> usize {
+        self.tracks.first().map_or(0, |(_, samples)| samples.len())
+    }
+
+    /// 时长 (毫秒)
+    pub fn duration_ms(&self) -> u64 {
+        samples_to_ms(self.len())
+    }
+
+    /// 混合所有来源 (求和后限幅)
+    pub fn mixed(&self) -> Vec<f32> {
+        let mut mixed = vec![0.0f32; self.len()];
+        for (_, samples) in &self.tracks {
+            for (out, sample) in mixed.iter_mut().zip(samples) {
+                *out += sample;
+            }
+        }
+        for sample in &mut mixed {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+        mixed
+    }
+}
+
+/// 分段器
+pub struct Chunker {
+    /// 各来源尚未切出的样本
+    pending: Vec<(SourceKind, Vec<f32>)>,
+    /// 已切出的样本数 (即下一段的起始位置)
+    consumed: usize,
+    /// 下一段的序号
+    next_index: usize,
+    /// 分段长度 (样本数)
+    chunk_samples: usize,
+}
+
+impl Chunker {
+    pub fn new(kinds: &[SourceKind], chunk_secs: u32) -> Self {
+        Self {
+            pending: kinds.iter().map(|kind| (*kind, Vec::new())).collect(),
+            consumed: 0,
+            next_index: 0,
+            chunk_samples: (chunk_secs * TARGET_SAMPLE_RATE) as usize,
+        }
+    }
+
+    /// 追加一个来源的样本 (16 kHz 单声道)
+    pub fn push(&mut self, kind: SourceKind, samples: &[f32]) {
+        if let Some((_, pending)) = self.pending.iter_mut().find(|(k, _)| *k == kind) {
+            pending.extend_from_slice(samples);
+        }
+    }
+
+    /// 已累积的时长 (毫秒，以最长的来源为准)
+    pub fn position_ms(&self) -> u64 {
+        samples_to_ms(self.consumed + self.pending_len())
+    }
+
+    fn pending_len(&self) -> usize {
+        self.pending.iter().map(|(_, samples)| samples.len()).max().unwrap_or(0)
+    }
+
+    /// 累积到分段长度时切出一段
+    pub fn next_chunk(&mut self) -> Option<Chunk> {
+        if self.pending_len() < self.chunk_samples {
+            return None;
+        }
+        let cut = quietest_cut(&self.pending, self.chunk_samples);
+        Some(self.take(cut))
+    }
+
+    /// 切出剩余的全部样本 (没有剩余时返回 None)
+    pub fn finish(&mut self) -> Option<Chunk> {
+        let len = self.pending_len();
+        (len > 0).then(|| self.take(len))
+    }
+
+    fn take(&mut self, len: usize) -> Chunk {
+        let tracks = self.pending
+            .iter_mut()
+            .map(|(kind, pending)| {
+                let mut samples: Vec<f32> = pending.drain(..len.min(pending.len())).collect();
+                samples.resize(len, 0.0);
+                (*kind, samples)
+            })
+            .collect();
+        let chunk = Chunk {
+            index: self.next_index,
+            start_ms: samples_to_ms(self.consumed),
+            tracks,
+        };
+        self.consumed += len;
+        self.next_index += 1;
+        chunk
+    }
+}
+
+/// 在 `[end - 搜索范围, end]` 内找到所有来源都最安静的帧，返回该帧中点作为切分位置
+fn quietest_cut(tracks: &[(SourceKind, Vec<f32>)], end: usize) -> usize {
+    let search = (CUT_SEARCH_SECS * TARGET_SAMPLE_RATE as usize).min(end / 2);
+    let loudness = |start: usize| {
+        tracks
+            .iter()
+            .filter_map(|(_, samples)| samples.get(start..(start + FRAME_SAMPLES).min(samples.len())))
+            .map(crate::voice::audio::utils::calculate_rms)
+            .fold(0.0f32, f32::max)
+    };
+    (end.saturating_sub(search)..end.saturating_sub(FRAME_SAMPLES).max(1))
+        .step_by(FRAME_SAMPLES)
+        .min_by(|a, b| loudness(*a).total_cmp(&loudness(*b)))
+        .map_or(end, |start| start + FRAME_SAMPLES / 2)
+}
+
+/// 有语音的范围 (样本下标，按帧判断)，整段静音时返回 None
+pub fn voiced_range(samples: &[f32]) -> Option<(usize, usize)> {
+    let mut frames = samples.chunks(FRAME_SAMPLES).enumerate().filter(|(_, frame)| is_voice_active(frame));
+    let (first, _) = frames.next()?;
+    let last = frames.next_back().map_or(first, |(index, _)| index);
+    Some((first * FRAME_SAMPLES, ((last + 1) * FRAME_SAMPLES).min(samples.len())))
+}
+
+// ============================================================================
+// 片段
+// ============================================================================
+
+/// 发言人 (按来源区分: 麦克风为本地，系统音频为远端)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Speaker {
+    Local,
+    Remote,
+}
+
+impl From<SourceKind> for Speaker {
+    fn from(kind: SourceKind) -> Self {
+        match kind {
+            SourceKind::Mic => Speaker::Local,
+            SourceKind::System => Speaker::Remote,
+        }
+    }
+}
+
+impl Speaker {
+    fn label(self) -> &'static str {
+        match self {
+            Speaker::Local => "local",
+            Speaker::Remote => "remote",
+        }
+    }
+}
+
+/// 转写片段
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Segment {
+    /// 所属分段的序号
+    pub chunk: usize,
+    /// 相对会议开始的时间 (毫秒)
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// 发言人 (未开启分离时省略)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<Speaker>,
+    pub text: String,
+    /// 使用的 ASR 引擎 (转写失败时省略)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// 转写失败的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 词级时间戳 (相对会议开始，启用 word_timestamps 且引擎提供时间信息时)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub words: Option<Vec<WordTiming>>,
+}
+
+/// 格式化时间戳 (mm:ss，超过一小时为 h:mm:ss)
+pub fn format_timestamp(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// 生成纯文本转写稿，每个片段一行: `[mm:ss] local: 文本` (没有发言人时省略)
+pub fn format_transcript(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .filter(|segment| !segment.text.is_empty())
+        .map(|segment| match segment.speaker {
+            Some(speaker) => format!("[{}] {}: {}", format_timestamp(segment.start_ms), speaker.label(), segment.text),
+            None => format!("[{}] {}", format_timestamp(segment.start_ms), segment.text),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 作为下一段转写提示的上文长度上限 (字符)
+const CONTEXT_CHARS: usize = 200;
+
+/// 转写稿末尾的一段上文，用作下一段的转写提示，让人名和术语在分段边界前后保持一致。
+/// 超出长度时从句子或词语边界开始，避免提示以半个词开头
+pub fn context_tail(text: &str) -> &str {
+    let text = text.trim();
+    let Some((start, _)) = text.char_indices().rev().nth(CONTEXT_CHARS - 1) else {
+        return text;
+    };
+    if start == 0 {
+        return text;
+    }
+    let tail = &text[start..];
+    let boundary = tail
+        .char_indices()
+        .take(CONTEXT_CHARS / 2)
+        .find(|(_, c)| c.is_whitespace() || matches!(c, '。' | '！' | '？' | '，' | '.' | '!' | '?' | ','));
+    match boundary {
+        Some((i, c)) => tail[i + c.len_utf8()..].trim_start(),
+        None => tail,
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(samples: usize) -> Vec<f32> {
+        (0..samples).map(|i| (i as f32 * 0.1).sin() * 0.3).collect()
+    }
+
+    #[test]
+    fn test_chunker_cuts_at_quiet_frame() {
+        let rate = TARGET_SAMPLE_RATE as usize;
+        let mut chunker = Chunker::new(&[SourceKind::Mic, SourceKind::System], 10);
+        // 8 秒处有 0.5 秒停顿，之后继续说话
+        let mut mic = tone(8 * rate);
+        mic.extend(vec![0.0; rate / 2]);
+        mic.extend(tone(3 * rate));
+        chunker.push(SourceKind::Mic, &mic);
+        chunker.push(SourceKind::System, &vec![0.01; 4 * rate]);
+        assert_eq!(chunker.position_ms(), 11_500);
+
+        let chunk = chunker.next_chunk().unwrap();
+        assert_eq!((chunk.index, chunk.start_ms), (0, 0));
+        assert!((8 * rate..8 * rate + rate / 2).contains(&chunk.len()), "cut at {}", chunk.len());
+        // 较短的来源补静音
+        assert_eq!(chunk.tracks[1].1.len(), chunk.len());
+        assert!(chunker.next_chunk().is_none());
+
+        let rest = chunker.finish().unwrap();
+        assert_eq!(rest.index, 1);
+        assert_eq!(rest.start_ms, chunk.duration_ms());
+        assert_eq!(chunk.len() + rest.len(), mic.len());
+        assert!(chunker.finish().is_none());
+    }
+
+    #[test]
+    fn test_voiced_range() {
+        let mut samples = vec![0.0; FRAME_SAMPLES * 3];
+        samples.extend(tone(FRAME_SAMPLES * 2));
+        samples.extend(vec![0.0; FRAME_SAMPLES]);
+        assert_eq!(voiced_range(&samples), Some((FRAME_SAMPLES * 3, FRAME_SAMPLES * 5)));
+        assert_eq!(voiced_range(&vec![0.0; FRAME_SAMPLES * 4]), None);
+    }
+
+    #[test]
+    fn test_format_transcript() {
+        let segment = |start_ms, speaker, text: &str| Segment {
+            chunk: 0,
+            start_ms,
+            end_ms: start_ms + 1000,
+            speaker,
+            text: text.to_string(),
+            engine: None,
+            error: None,
+            words: None,
+        };
+        let segments = vec![
+            segment(5_000, Some(Speaker::Local), "开始吧"),
+            segment(65_000, Some(Speaker::Remote), "好的"),
+            segment(3_725_000, None, "结束"),
+            segment(3_726_000, None, ""),
+        ];
+        assert_eq!(format_transcript(&segments), "[00:05] local: 开始吧\n[01:05] remote: 好的\n[1:02:05] 结束");
+    }
+
+    #[test]
+    fn test_context_tail() {
+        assert_eq!(context_tail(" 短句。 "), "短句。");
+        // 从第一个词语边界开始
+        let words = "alpha ".repeat(50);
+        let tail = context_tail(&words);
+        assert!(tail.len() < CONTEXT_CHARS && tail.starts_with("alpha"), "{}", tail);
+        let chinese = "讨论排期。".repeat(42);
+        let tail = context_tail(&chinese);
+        assert!(tail.starts_with("讨论排期。") && tail.ends_with("排期。"), "{}", tail);
+        assert_eq!(tail.chars().count(), 195);
+        // 没有边界时按长度截取
+        assert_eq!(context_tail(&"字".repeat(300)).chars().count(), CONTEXT_CHARS);
+    }
+}
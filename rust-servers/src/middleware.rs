@@ -0,0 +1,454 @@
+// 路由中间件
+// 包裹 MessageRouter::route 的横切逻辑 (请求追踪、耗时统计、速率限制等)，
+// 按注册顺序由外到内执行，最内层为模块分发
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::{RateLimit, RateLimits};
+use crate::router::{ErrorCode, MessageRouter, ModuleError, ModuleMessage, ModuleType, RouterError, ServerResponse};
+use crate::server::ConnectionId;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Error, "", format!($($arg)*));
+    };
+}
+
+/// 令牌桶数量超过该值时清理已补满的桶 (已断开连接的桶补满后与不存在等价)
+const RATE_BUCKET_PRUNE_THRESHOLD: usize = 256;
+
+/// 路由结果
+pub type RouteResult = Result<Option<ServerResponse>, RouterError>;
+
+// ============================================================================
+// 中间件链
+// ============================================================================
+
+/// 路由中间件
+#[async_trait::async_trait]
+pub trait Middleware: Send + Sync {
+    /// 处理消息
+    ///
+    /// 调用 `next.run(msg)` 将消息交给下一层；不调用时直接以返回值应答
+    async fn handle(&self, msg: ModuleMessage, next: Next<'_>) -> RouteResult;
+}
+
+/// 中间件链中剩余的部分
+pub struct Next<'a> {
+    router: &'a MessageRouter,
+    chain: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(router: &'a MessageRouter, chain: &'a [Arc<dyn Middleware>]) -> Self {
+        Self { router, chain }
+    }
+
+    /// 交给下一个中间件，链尾分发到模块
+    pub async fn run(self, msg: ModuleMessage) -> RouteResult {
+        match self.chain.split_first() {
+            Some((middleware, rest)) => middleware.handle(msg, Next::new(self.router, rest)).await,
+            None => self.router.dispatch(msg).await,
+        }
+    }
+}
+
+// ============================================================================
+// 请求追踪
+// ============================================================================
+
+/// 记录每条消息的来源连接、请求 ID、处理结果和耗时
+pub struct RequestTracing;
+
+#[async_trait::async_trait]
+impl Middleware for RequestTracing {
+    async fn handle(&self, msg: ModuleMessage, next: Next<'_>) -> RouteResult {
+        let name = format!("{}.{}", msg.module, msg.msg_type);
+        let connection = msg.connection.as_ref().map(|c| c.id);
+        let request_id = msg.request_id();
+        let start = Instant::now();
+
+        let result = next.run(msg).await;
+
+        let elapsed = start.elapsed().as_millis();
+        match &result {
+            Ok(_) => {
+                log_info!(
+                    "{} 完成: connection={:?}, request_id={:?}, 耗时 {} ms",
+                    name, connection, request_id, elapsed
+                );
+            }
+            Err(e) => {
+                log_error!(
+                    "{} 失败 [{}]: {} (connection={:?}, request_id={:?}, 耗时 {} ms)",
+                    name, e.code(), e, connection, request_id, elapsed
+                );
+            }
+        }
+        result
+    }
+}
+
+// ============================================================================
+// 耗时统计
+// ============================================================================
+
+/// 单个消息类型的累计统计
+#[derive(Debug, Default, Clone)]
+struct Stat {
+    count: u64,
+    errors: u64,
+    total: Duration,
+    max: Duration,
+}
+
+/// 单个消息类型的统计结果
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageMetrics {
+    pub module: ModuleType,
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    /// 处理次数
+    pub count: u64,
+    /// 失败次数
+    pub errors: u64,
+    /// 平均耗时 (毫秒)
+    pub avg_ms: f64,
+    /// 最大耗时 (毫秒)
+    pub max_ms: f64,
+}
+
+/// 统计查询响应 (metrics)
+#[derive(Debug, Serialize)]
+pub struct MetricsResponse {
+    /// 开始统计以来的秒数
+    pub uptime_secs: u64,
+    /// 按模块和消息类型排序的统计
+    pub messages: Vec<MessageMetrics>,
+}
+
+/// 按模块和消息类型统计处理次数、失败次数和耗时，并应答 `system`/`get_metrics`
+pub struct Metrics {
+    started: Instant,
+    stats: Mutex<HashMap<(ModuleType, String), Stat>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            stats: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn record(&self, module: ModuleType, msg_type: String, elapsed: Duration, ok: bool) {
+        let Ok(mut stats) = self.stats.lock() else {
+            return;
+        };
+        let stat = stats.entry((module, msg_type)).or_default();
+        stat.count += 1;
+        if !ok {
+            stat.errors += 1;
+        }
+        stat.total += elapsed;
+        stat.max = stat.max.max(elapsed);
+    }
+
+    /// 当前统计快照
+    pub fn snapshot(&self) -> MetricsResponse {
+        let mut messages: Vec<MessageMetrics> = self.stats.lock()
+            .map(|stats| {
+                stats.iter()
+                    .map(|((module, msg_type), stat)| MessageMetrics {
+                        module: *module,
+                        msg_type: msg_type.clone(),
+                        count: stat.count,
+                        errors: stat.errors,
+                        avg_ms: stat.total.as_secs_f64() * 1000.0 / stat.count as f64,
+                        max_ms: stat.max.as_secs_f64() * 1000.0,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        messages.sort_by(|a, b| (a.module.to_string(), &a.msg_type).cmp(&(b.module.to_string(), &b.msg_type)));
+
+        MetricsResponse {
+            uptime_secs: self.started.elapsed().as_secs(),
+            messages,
+        }
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for Metrics {
+    async fn handle(&self, msg: ModuleMessage, next: Next<'_>) -> RouteResult {
+        if msg.module == ModuleType::System && msg.msg_type == "get_metrics" {
+            let payload = serde_json::to_value(self.snapshot())?;
+            return Ok(Some(ServerResponse::new(ModuleType::System, "metrics", payload)));
+        }
+
+        let (module, msg_type) = (msg.module, msg.msg_type.clone());
+        let start = Instant::now();
+        let result = next.run(msg).await;
+
+        // 未知消息类型不计入统计，避免任意类型名使统计表无限增长
+        let unknown = matches!(&result, Err(e) if e.code() == ErrorCode::UnknownMessageType);
+        if !unknown {
+            self.record(module, msg_type, start.elapsed(), result.is_ok());
+        }
+        result
+    }
+}
+
+// ============================================================================
+// 速率限制
+// ============================================================================
+
+/// 令牌桶
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// 本轮限流中已拒绝的消息数 (只在开始和结束限流时记录日志，避免消息风暴刷屏)
+    rejected: u64,
+}
+
+impl Bucket {
+    /// 按经过的时间补充令牌
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_sec).min(limit.burst as f64);
+        self.updated = now;
+    }
+}
+
+/// 按连接限制各模块和消息类型的消息速率 (令牌桶)
+///
+/// 防止插件缺陷导致的消息循环反复申请设备或 PTY (如 `start_recording` / `init` 风暴)。
+/// 超出限制的消息不会到达模块，直接以可重试的 `RATE_LIMITED` 错误应答
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<HashMap<(Option<ConnectionId>, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 尝试为消息消耗令牌，超出限制时返回受限的项和需等待的时间
+    fn acquire(&self, connection: Option<ConnectionId>, module: ModuleType, msg_type: &str) -> Result<(), (String, Duration)> {
+        let keys = [module.to_string(), format!("{}.{}", module, msg_type)];
+        let limited: Vec<(&String, RateLimit)> = keys.iter()
+            .filter_map(|key| self.limits.get(key).map(|limit| (key, limit)))
+            .collect();
+        if limited.is_empty() {
+            return Ok(());
+        }
+
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if buckets.len() > RATE_BUCKET_PRUNE_THRESHOLD {
+            let limits = &self.limits;
+            buckets.retain(|(_, key), bucket| {
+                let Some(limit) = limits.get(key) else {
+                    return false;
+                };
+                bucket.refill(limit, now);
+                bucket.tokens < limit.burst as f64
+            });
+        }
+
+        // 所有项都有令牌时才消耗，被拒绝的消息不占用其他项的额度
+        for (key, limit) in &limited {
+            let bucket = buckets.entry((connection, key.to_string())).or_insert_with(|| Bucket {
+                tokens: limit.burst as f64,
+                updated: now,
+                rejected: 0,
+            });
+            bucket.refill(*limit, now);
+            if bucket.tokens < 1.0 {
+                if bucket.rejected == 0 {
+                    log_warn!(
+                        "{} 超出速率限制 (每秒 {} 条，突发 {} 条)，connection={:?}",
+                        key, limit.per_sec, limit.burst, connection
+                    );
+                }
+                bucket.rejected += 1;
+                let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / limit.per_sec);
+                return Err((key.to_string(), wait));
+            }
+        }
+        for (key, _) in &limited {
+            if let Some(bucket) = buckets.get_mut(&(connection, key.to_string())) {
+                bucket.tokens -= 1.0;
+                if bucket.rejected > 0 {
+                    log_info!(
+                        "{} 恢复正常，限流期间拒绝 {} 条消息，connection={:?}",
+                        key, bucket.rejected, connection
+                    );
+                    bucket.rejected = 0;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RateLimiter {
+    async fn handle(&self, msg: ModuleMessage, next: Next<'_>) -> RouteResult {
+        let connection = msg.connection.as_ref().map(|c| c.id);
+        if let Err((key, wait)) = self.acquire(connection, msg.module, &msg.msg_type) {
+            return Err(ModuleError::new(
+                msg.module,
+                ErrorCode::RateLimited,
+                format!("{} 消息过于频繁，请 {} ms 后重试", key, wait.as_millis().max(1)),
+            ).into());
+        }
+        next.run(msg).await
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 记录经过顺序的测试中间件，`reply` 为 true 时直接应答
+    struct Probe {
+        name: &'static str,
+        trail: Arc<Mutex<Vec<&'static str>>>,
+        reply: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Middleware for Probe {
+        async fn handle(&self, msg: ModuleMessage, next: Next<'_>) -> RouteResult {
+            self.trail.lock().unwrap().push(self.name);
+            if self.reply {
+                return Ok(Some(ServerResponse::new(msg.module, self.name, serde_json::json!({}))));
+            }
+            next.run(msg).await
+        }
+    }
+
+    fn message(router: &MessageRouter, json: &str) -> ModuleMessage {
+        router.parse_message(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chain_order_and_short_circuit() {
+        let trail = Arc::new(Mutex::new(Vec::new()));
+        let probe = |name, reply| Probe { name, trail: Arc::clone(&trail), reply };
+        let router = MessageRouter::new()
+            .with_middleware(probe("outer", false))
+            .with_middleware(probe("inner", true))
+            .with_middleware(probe("unreachable", false));
+
+        let response = router.route(message(&router, r#"{"module": "system", "type": "describe"}"#))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.msg_type, "inner");
+        assert_eq!(*trail.lock().unwrap(), vec!["outer", "inner"]);
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let router = MessageRouter::new().with_middleware(Metrics::new());
+
+        for json in [
+            r#"{"module": "system", "type": "describe"}"#,
+            r#"{"module": "system", "type": "describe"}"#,
+            r#"{"module": "pty", "type": "resize", "session_id": "missing"}"#,
+            r#"{"module": "pty", "type": "no_such_type"}"#,
+        ] {
+            let _ = router.route(message(&router, json)).await;
+        }
+
+        let response = router.route(message(&router, r#"{"module": "system", "type": "get_metrics"}"#))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.msg_type, "metrics");
+
+        let messages = response.payload["messages"].as_array().unwrap();
+        let summary: Vec<(String, u64, u64)> = messages.iter()
+            .map(|m| (
+                format!("{}.{}", m["module"].as_str().unwrap(), m["type"].as_str().unwrap()),
+                m["count"].as_u64().unwrap(),
+                m["errors"].as_u64().unwrap(),
+            ))
+            .collect();
+        assert_eq!(summary, vec![
+            ("pty.resize".to_string(), 1, 1),
+            ("system.describe".to_string(), 2, 0),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter() {
+        let slow = |burst| RateLimit { per_sec: 0.001, burst };
+        let router = MessageRouter::new()
+            .with_middleware(RateLimiter::new(RateLimits::none().with("system.describe", slow(2))));
+
+        let describe = || message(&router, r#"{"module": "system", "type": "describe"}"#);
+        assert!(router.route(describe()).await.is_ok());
+        assert!(router.route(describe()).await.is_ok());
+        let error = router.route(describe()).await.unwrap_err();
+        assert_eq!(error.code(), ErrorCode::RateLimited);
+        assert!(matches!(&error, RouterError::Module(e) if e.retryable));
+
+        // 消息需同时满足模块和消息类型的限制，被拒绝的消息不消耗模块额度
+        let limiter = RateLimiter::new(RateLimits::none().with("pty", slow(3)).with("pty.init", slow(1)));
+        assert!(limiter.acquire(Some(1), ModuleType::Pty, "init").is_ok());
+        let (key, wait) = limiter.acquire(Some(1), ModuleType::Pty, "init").unwrap_err();
+        assert_eq!(key, "pty.init");
+        assert!(wait > Duration::from_secs(100));
+        assert!(limiter.acquire(Some(1), ModuleType::Pty, "resize").is_ok());
+        assert!(limiter.acquire(Some(1), ModuleType::Pty, "input").is_ok());
+        assert_eq!(limiter.acquire(Some(1), ModuleType::Pty, "input").unwrap_err().0, "pty");
+
+        // 各连接单独计数，未限制的模块不受影响
+        assert!(limiter.acquire(Some(2), ModuleType::Pty, "init").is_ok());
+        assert!(limiter.acquire(Some(1), ModuleType::Llm, "stream_start").is_ok());
+
+        // 令牌按时间补充
+        let limiter = RateLimiter::new(RateLimits::none().with("voice", RateLimit { per_sec: 100.0, burst: 1 }));
+        assert!(limiter.acquire(None, ModuleType::Voice, "start_recording").is_ok());
+        assert!(limiter.acquire(None, ModuleType::Voice, "start_recording").is_err());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(limiter.acquire(None, ModuleType::Voice, "start_recording").is_ok());
+    }
+}
@@ -0,0 +1,361 @@
+// 出站网络
+// 所有出站 HTTP / WebSocket 连接 (ASR、LLM、网页剪藏、诊断) 共用一个连接管理器：统一的代理和 DNS 覆盖，
+// HTTP 请求共用一个连接池 (keep-alive)，并按主机熔断：同一主机连续失败达到阈值后在冷却时间内直接拒绝请求，
+// 冷却结束后放行请求，成功则恢复，再次失败立即重新熔断且冷却时间加倍 (不超过上限)。
+// 每次请求都记入审计日志
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::config::NetworkSettings;
+use crate::router::ModuleType;
+
+/// 日志宏
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "Network", format!($($arg)*));
+    };
+}
+
+/// 熔断冷却时间的上限
+const MAX_BREAKER_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// 代理 CONNECT 响应头的最大字节数
+const MAX_CONNECT_RESPONSE_BYTES: usize = 8 * 1024;
+
+/// 出站 WebSocket 连接
+pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 出站网络错误
+#[derive(Debug, Error)]
+pub enum NetworkError {
+    #[error("{host} 连续请求失败，已暂停请求 ({} 秒后重试)", retry_after.as_secs().max(1))]
+    CircuitOpen { host: String, retry_after: Duration },
+
+    #[error("{0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("{0}")]
+    WebSocket(#[from] tungstenite::Error),
+
+    #[error("连接失败 {host}: {source}")]
+    Connect { host: String, source: std::io::Error },
+
+    #[error("代理连接失败: {0}")]
+    Proxy(String),
+}
+
+impl NetworkError {
+    /// 是否为请求超时
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, NetworkError::Http(e) if e.is_timeout())
+    }
+}
+
+// ============================================================================
+// 熔断器
+// ============================================================================
+
+/// 单个主机的熔断状态
+#[derive(Debug, Default)]
+struct Breaker {
+    /// 连续失败次数
+    failures: u32,
+    /// 连续熔断次数 (决定冷却时间)
+    trips: u32,
+    /// 熔断截止时间 (过后放行请求，再次失败立即重新熔断)
+    open_until: Option<Instant>,
+}
+
+impl Breaker {
+    /// 检查是否放行请求，熔断中时返回剩余冷却时间
+    fn check(&self, now: Instant) -> Result<(), Duration> {
+        match self.open_until {
+            Some(until) if now < until => Err(until - now),
+            _ => Ok(()),
+        }
+    }
+
+    /// 记录一次失败，达到阈值 (或冷却后的首次失败) 时熔断，返回冷却时间
+    fn failure(&mut self, now: Instant, threshold: u32, cooldown: Duration) -> Option<Duration> {
+        self.failures += 1;
+        if self.failures < threshold && self.open_until.is_none() {
+            return None;
+        }
+        let duration = cooldown
+            .saturating_mul(1 << self.trips.min(16))
+            .min(MAX_BREAKER_COOLDOWN);
+        self.trips += 1;
+        self.failures = 0;
+        self.open_until = Some(now + duration);
+        Some(duration)
+    }
+}
+
+// ============================================================================
+// 连接管理器
+// ============================================================================
+
+struct NetworkManager {
+    settings: NetworkSettings,
+    /// 共用的 HTTP 客户端 (内部为连接池，克隆开销很小)
+    client: reqwest::Client,
+    breakers: Mutex<HashMap<String, Breaker>>,
+}
+
+static MANAGER: OnceLock<NetworkManager> = OnceLock::new();
+
+/// 按设置初始化连接管理器，需在发起任何出站请求之前调用 (之后的调用无效)
+pub fn init(settings: NetworkSettings) {
+    let _ = MANAGER.set(NetworkManager::new(settings));
+}
+
+fn manager() -> &'static NetworkManager {
+    MANAGER.get_or_init(|| NetworkManager::new(NetworkSettings::default()))
+}
+
+impl NetworkManager {
+    fn new(settings: NetworkSettings) -> Self {
+        let mut builder = reqwest::Client::builder()
+            .pool_max_idle_per_host(settings.pool_max_idle_per_host)
+            .pool_idle_timeout(settings.pool_idle_timeout);
+        // 未配置代理时沿用 reqwest 默认行为 (读取 HTTP_PROXY / HTTPS_PROXY 环境变量)
+        if let Some(Ok(proxy)) = settings.proxy.as_deref().map(reqwest::Proxy::all) {
+            builder = builder.proxy(proxy);
+        }
+        for (host, ip) in &settings.dns {
+            // 端口沿用 URL 中的端口
+            builder = builder.resolve(host, SocketAddr::new(*ip, 0));
+        }
+        Self {
+            client: builder.build().unwrap_or_default(),
+            settings,
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn check(&self, host: &str) -> Result<(), NetworkError> {
+        if self.settings.breaker_threshold == 0 {
+            return Ok(());
+        }
+        let breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        match breakers.get(host).map(|breaker| breaker.check(Instant::now())) {
+            Some(Err(retry_after)) => Err(NetworkError::CircuitOpen { host: host.to_string(), retry_after }),
+            _ => Ok(()),
+        }
+    }
+
+    fn record(&self, host: &str, success: bool) {
+        if self.settings.breaker_threshold == 0 {
+            return;
+        }
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        // 成功后清除该主机的熔断状态
+        if success {
+            breakers.remove(host);
+            return;
+        }
+        let breaker = breakers.entry(host.to_string()).or_default();
+        let tripped = breaker.failure(Instant::now(), self.settings.breaker_threshold, self.settings.breaker_cooldown);
+        if let Some(cooldown) = tripped {
+            log_warn!("{} 连续请求失败，暂停请求 {} 秒", host, cooldown.as_secs());
+        }
+    }
+}
+
+/// 共用的 HTTP 客户端，用于构建请求 (超时等按请求设置)，请求需通过 [`send`] 发送
+pub fn client() -> reqwest::Client {
+    manager().client.clone()
+}
+
+/// 配置的 HTTP 代理 (不经过共用客户端的连接，如 Git 推送 / 拉取，使用同一代理)
+pub fn proxy() -> Option<String> {
+    manager().settings.proxy.clone()
+}
+
+/// 发送 HTTP 请求：记入审计日志并经过熔断检查
+///
+/// 连接失败、超时、HTTP 429 和 5xx 计为失败
+pub async fn send(module: ModuleType, request: reqwest::RequestBuilder) -> Result<reqwest::Response, NetworkError> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let host = host_key(request.url().host_str().unwrap_or_default(), request.url().port_or_known_default());
+    crate::audit::record_network(module, request.method().as_str(), request.url().as_str());
+
+    let manager = manager();
+    manager.check(&host)?;
+    let result = client.execute(request).await;
+    let success = match &result {
+        Ok(response) => {
+            let status = response.status();
+            !(status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+        }
+        Err(_) => false,
+    };
+    manager.record(&host, success);
+    Ok(result?)
+}
+
+/// 建立 WebSocket 连接：应用 DNS 覆盖和 HTTP 代理 (CONNECT 隧道)，记入审计日志并经过熔断检查
+///
+/// 只支持 http:// 代理，其他类型的代理不用于 WebSocket 连接
+pub async fn connect_websocket(
+    module: ModuleType,
+    request: impl IntoClientRequest + Unpin,
+) -> Result<(WsStream, tungstenite::handshake::client::Response), NetworkError> {
+    let request = request.into_client_request()?;
+    let uri = request.uri().clone();
+    let hostname = uri.host().unwrap_or_default().to_string();
+    let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("wss") { 443 } else { 80 });
+    let host = host_key(&hostname, Some(port));
+    crate::audit::record_network(module, "GET", &uri.to_string());
+
+    let manager = manager();
+    manager.check(&host)?;
+    let result = async {
+        let stream = open_stream(&manager.settings, &hostname, port).await?;
+        Ok(tokio_tungstenite::client_async_tls(request, stream).await?)
+    }.await;
+    manager.record(&host, result.is_ok());
+    result
+}
+
+/// 建立到目标主机的 TCP 连接 (经由代理或直连)
+async fn open_stream(settings: &NetworkSettings, hostname: &str, port: u16) -> Result<TcpStream, NetworkError> {
+    let target = match settings.dns.get(hostname) {
+        Some(ip) => SocketAddr::new(*ip, port).to_string(),
+        None => format!("{}:{}", hostname, port),
+    };
+    let proxy = settings.proxy.as_deref()
+        .and_then(|proxy| reqwest::Url::parse(proxy).ok())
+        .filter(|proxy| proxy.scheme() == "http");
+    let Some(proxy) = proxy else {
+        return TcpStream::connect(&target).await
+            .map_err(|source| NetworkError::Connect { host: target, source });
+    };
+
+    let proxy_host = host_key(proxy.host_str().unwrap_or_default(), proxy.port_or_known_default());
+    let mut stream = TcpStream::connect(&proxy_host).await
+        .map_err(|source| NetworkError::Connect { host: proxy_host, source })?;
+    // 隧道目标使用主机名，由代理解析 (DNS 覆盖的地址同样可以直接作为目标)
+    let request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n\r\n");
+    stream.write_all(request.as_bytes()).await.map_err(|e| NetworkError::Proxy(e.to_string()))?;
+
+    // 读取响应头 (逐字节读取，不多读隧道中的数据)
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_CONNECT_RESPONSE_BYTES {
+            return Err(NetworkError::Proxy("响应头过长".to_string()));
+        }
+        let byte = stream.read_u8().await.map_err(|e| NetworkError::Proxy(e.to_string()))?;
+        response.push(byte);
+    }
+    let status_line = String::from_utf8_lossy(&response);
+    let status_line = status_line.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(NetworkError::Proxy(format!("代理拒绝连接 {}: {}", target, status_line)));
+    }
+    Ok(stream)
+}
+
+/// 熔断按 `主机:端口` 区分
+fn host_key(host: &str, port: Option<u16>) -> String {
+    match port {
+        Some(port) => format!("{}:{}", host.to_ascii_lowercase(), port),
+        None => host.to_ascii_lowercase(),
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_breaker_backoff() {
+        let cooldown = Duration::from_secs(10);
+        let start = Instant::now();
+        let mut breaker = Breaker::default();
+
+        assert_eq!(breaker.failure(start, 3, cooldown), None);
+        assert_eq!(breaker.failure(start, 3, cooldown), None);
+        assert!(breaker.check(start).is_ok());
+        assert_eq!(breaker.failure(start, 3, cooldown), Some(cooldown));
+        assert_eq!(breaker.check(start + Duration::from_secs(4)), Err(Duration::from_secs(6)));
+
+        // 冷却结束后放行，再次失败立即熔断，冷却时间加倍
+        let later = start + cooldown;
+        assert!(breaker.check(later).is_ok());
+        assert_eq!(breaker.failure(later, 3, cooldown), Some(cooldown * 2));
+        assert!(breaker.check(later + cooldown).is_err());
+
+        // 冷却时间不超过上限
+        for _ in 0..10 {
+            breaker.failure(later, 3, cooldown);
+        }
+        assert!(breaker.check(later + MAX_BREAKER_COOLDOWN).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_websocket_via_proxy_and_dns_override() {
+        // 只接受 CONNECT 隧道的 HTTP 代理，隧道目标为 DNS 覆盖后的地址
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = upstream.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = upstream.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            use futures_util::SinkExt;
+            ws.send(tungstenite::Message::text("hello")).await.unwrap();
+        });
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_addr = proxy.local_addr().unwrap();
+        let expected = format!("CONNECT {} HTTP/1.1", upstream_addr);
+        tokio::spawn(async move {
+            let (mut client, _) = proxy.accept().await.unwrap();
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(client.read_u8().await.unwrap());
+            }
+            assert!(String::from_utf8_lossy(&head).starts_with(&expected));
+            let mut upstream = TcpStream::connect(upstream_addr).await.unwrap();
+            client.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await.unwrap();
+            let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+        });
+
+        let settings = NetworkSettings {
+            proxy: Some(format!("http://{}", proxy_addr)),
+            dns: [("asr.invalid".to_string(), upstream_addr.ip())].into(),
+            ..Default::default()
+        };
+        let stream = open_stream(&settings, "asr.invalid", upstream_addr.port()).await.unwrap();
+        let url = format!("ws://asr.invalid:{}/", upstream_addr.port());
+        let (mut ws, _) = tokio_tungstenite::client_async_tls(url, stream).await.unwrap();
+        use futures_util::StreamExt;
+        assert_eq!(ws.next().await.unwrap().unwrap(), tungstenite::Message::text("hello"));
+    }
+
+    #[tokio::test]
+    async fn test_circuit_open() {
+        // 监听后立即关闭的端口不可达
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let url = format!("http://{}/", closed);
+        let threshold = manager().settings.breaker_threshold;
+        for _ in 0..threshold {
+            let result = send(ModuleType::System, client().get(&url)).await;
+            assert!(matches!(result, Err(NetworkError::Http(_))));
+        }
+        let result = send(ModuleType::System, client().get(&url)).await;
+        assert!(matches!(result, Err(NetworkError::CircuitOpen { .. })), "{:?}", result);
+    }
+}
@@ -0,0 +1,156 @@
+// 系统通知
+// 通过 notify-rust 显示 (Linux 上经 D-Bus 发送给通知服务，Windows / macOS 使用系统通知中心)。
+// 只有 Linux 的通知服务会报告用户点击了通知或按钮，其他平台上通知只负责显示
+
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 通知中显示的应用名称
+const APP_NAME: &str = "Smart Workflow";
+
+/// 通知错误
+#[derive(Debug, Error)]
+pub enum NotifyError {
+    #[error("系统通知不可用: {0}")]
+    Unavailable(String),
+}
+
+impl CodedError for NotifyError {
+    fn code(&self) -> ErrorCode {
+        ErrorCode::DeviceError
+    }
+}
+
+/// 紧急程度 (只有 Linux 的通知服务区分)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// 通知按钮
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct Action {
+    pub id: String,
+    pub label: String,
+}
+
+/// 要显示的通知
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub body: Option<String>,
+    pub actions: Vec<Action>,
+    pub urgency: Urgency,
+    /// 自动消失的时间 (None 时由系统决定，零表示不自动消失)
+    pub timeout: Option<Duration>,
+}
+
+/// 用户对通知的操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserAction {
+    /// 点击了通知本身
+    Clicked,
+    /// 点击了按钮 (按钮 id)
+    Action(String),
+    /// 通知被关闭或过期
+    Closed,
+}
+
+/// 通知后端
+pub trait NotifyBackend: Send {
+    /// 显示通知，返回是否会报告用户操作
+    ///
+    /// 会报告时，用户操作通知后 (或通知关闭后) 向 `respond` 发送一次操作；不会报告时丢弃 `respond`
+    fn show(&mut self, notification: &Notification, respond: oneshot::Sender<UserAction>) -> Result<bool, NotifyError>;
+}
+
+// ============================================================================
+// 系统后端
+// ============================================================================
+
+/// 系统通知
+struct SystemNotifier;
+
+impl SystemNotifier {
+    fn build(notification: &Notification) -> notify_rust::Notification {
+        let mut builder = notify_rust::Notification::new();
+        builder.appname(APP_NAME).summary(&notification.title);
+        if let Some(body) = &notification.body {
+            builder.body(body);
+        }
+        match notification.timeout {
+            Some(timeout) if timeout.is_zero() => {
+                builder.timeout(notify_rust::Timeout::Never);
+            }
+            Some(timeout) => {
+                builder.timeout(notify_rust::Timeout::Milliseconds(timeout.as_millis().min(u32::MAX as u128) as u32));
+            }
+            None => {}
+        }
+        builder
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl NotifyBackend for SystemNotifier {
+    fn show(&mut self, notification: &Notification, respond: oneshot::Sender<UserAction>) -> Result<bool, NotifyError> {
+        let mut builder = Self::build(notification);
+        builder.urgency(match notification.urgency {
+            Urgency::Low => notify_rust::Urgency::Low,
+            Urgency::Normal => notify_rust::Urgency::Normal,
+            Urgency::Critical => notify_rust::Urgency::Critical,
+        });
+        // "default" 为点击通知本身时报告的操作
+        builder.action("default", "");
+        for action in &notification.actions {
+            builder.action(&action.id, &action.label);
+        }
+        let handle = builder.show().map_err(|e| NotifyError::Unavailable(e.to_string()))?;
+
+        // 等待用户操作会阻塞到通知关闭，在单独的线程中等待
+        std::thread::Builder::new()
+            .name("notify-wait".to_string())
+            .spawn(move || {
+                handle.wait_for_action(|action| {
+                    let action = match action {
+                        "default" => UserAction::Clicked,
+                        "__closed" => UserAction::Closed,
+                        id => UserAction::Action(id.to_string()),
+                    };
+                    let _ = respond.send(action);
+                });
+            })
+            .map(|_| true)
+            .or(Ok(false))
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+impl NotifyBackend for SystemNotifier {
+    fn show(&mut self, notification: &Notification, _respond: oneshot::Sender<UserAction>) -> Result<bool, NotifyError> {
+        Self::build(notification).show().map_err(|e| NotifyError::Unavailable(e.to_string()))?;
+        Ok(false)
+    }
+}
+
+/// 打开系统通知后端
+#[cfg(not(any(test, feature = "test-support")))]
+pub fn open() -> Box<dyn NotifyBackend> {
+    Box::new(SystemNotifier)
+}
+
+/// 打开通知后端 (安装了模拟通知时使用模拟通知)
+#[cfg(any(test, feature = "test-support"))]
+pub fn open() -> Box<dyn NotifyBackend> {
+    if crate::testing::notify::is_installed() {
+        return Box::new(crate::testing::notify::FakeNotifier);
+    }
+    Box::new(SystemNotifier)
+}
@@ -0,0 +1,265 @@
+// 系统通知模块
+// 显示操作系统级别的通知 (标题、正文、可选按钮)，Obsidian 最小化时也能提醒用户
+// 长时间任务的结果 (如 PTY 中的构建完成、长音频转写完成)。
+// 支持的平台上，用户点击通知或按钮时向发起请求的连接推送事件
+
+mod backend;
+
+pub use backend::{Action, Notification, NotifyBackend, NotifyError, Urgency, UserAction};
+
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use futures_util::SinkExt;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex as TokioMutex, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Notify", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Notify", format!($($arg)*));
+        }
+    };
+}
+
+/// 每条通知最多的按钮数 (多数通知中心最多显示 3 个)
+const MAX_ACTIONS: usize = 3;
+
+/// 同时等待用户操作的通知数上限 (每条都占用一个等待线程)
+const MAX_PENDING: usize = 32;
+
+/// 标题和正文的最大长度 (字符)
+const MAX_TEXT_CHARS: usize = 1000;
+
+/// 系统保留的按钮 id (点击通知本身、通知关闭)
+const RESERVED_ACTION_IDS: &[&str] = &["default", "__closed"];
+
+/// 创建通知模块错误
+fn notify_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Notify, code, message)
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// show 请求
+#[derive(Debug, Deserialize)]
+struct ShowRequest {
+    /// 通知 id (事件中原样返回)，省略时自动生成
+    #[serde(default)]
+    id: Option<String>,
+    title: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    actions: Vec<Action>,
+    #[serde(default)]
+    urgency: Urgency,
+    /// 自动消失的时间 (毫秒)，0 表示不自动消失，省略时由系统决定
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
+impl ShowRequest {
+    fn validate(&self) -> Result<(), ModuleError> {
+        if self.title.trim().is_empty() {
+            return Err(notify_error(ErrorCode::InvalidParams, "标题不能为空"));
+        }
+        let too_long = |text: &str| text.chars().count() > MAX_TEXT_CHARS;
+        if too_long(&self.title) || self.body.as_deref().is_some_and(too_long) {
+            return Err(notify_error(ErrorCode::LimitExceeded, format!("标题或正文过长 (最多 {} 个字符)", MAX_TEXT_CHARS)));
+        }
+        if self.actions.len() > MAX_ACTIONS {
+            return Err(notify_error(ErrorCode::InvalidParams, format!("按钮过多 (最多 {} 个)", MAX_ACTIONS)));
+        }
+        for (index, action) in self.actions.iter().enumerate() {
+            if action.id.is_empty() || RESERVED_ACTION_IDS.contains(&action.id.as_str()) {
+                return Err(notify_error(ErrorCode::InvalidParams, format!("无效的按钮 id: '{}'", action.id)));
+            }
+            if self.actions[..index].iter().any(|other| other.id == action.id) {
+                return Err(notify_error(ErrorCode::InvalidParams, format!("重复的按钮 id: '{}'", action.id)));
+            }
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// 通知处理器
+// ============================================================================
+
+/// 系统通知模块处理器
+pub struct NotifyHandler {
+    /// 正在等待用户操作的通知数
+    pending: Arc<AtomicUsize>,
+    /// WebSocket 发送器 (请求没有来源连接时用于广播事件)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl NotifyHandler {
+    /// 创建新的通知处理器
+    pub fn new() -> Self {
+        Self {
+            pending: Arc::new(AtomicUsize::new(0)),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    /// 处理 show 消息 - 显示通知
+    async fn handle_show(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: ShowRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| notify_error(ErrorCode::InvalidParams, format!("无效的 show 请求: {}", e)))?;
+        request.validate()?;
+        if self.pending.load(Ordering::SeqCst) >= MAX_PENDING {
+            return Err(notify_error(
+                ErrorCode::LimitExceeded,
+                format!("等待用户操作的通知过多 (最多 {} 条)", MAX_PENDING),
+            ).into());
+        }
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+
+        let id = request.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let notification = Notification {
+            title: request.title,
+            body: request.body,
+            actions: request.actions,
+            urgency: request.urgency,
+            timeout: request.timeout_ms.map(Duration::from_millis),
+        };
+        let (respond, response) = oneshot::channel();
+        let interactive = tokio::task::spawn_blocking(move || backend::open().show(&notification, respond))
+            .await
+            .map_err(|e| notify_error(ErrorCode::Internal, format!("通知任务失败: {}", e)))?
+            .map_err(|e| ModuleError::from_error(ModuleType::Notify, &e))?;
+        log_info!("已显示通知: id={}, interactive={}", id, interactive);
+
+        if let (true, Some(sender)) = (interactive, sender) {
+            self.pending.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(forward_action(id.clone(), response, sender, Arc::clone(&self.pending)));
+        }
+        Ok(Some(ServerResponse::new(
+            ModuleType::Notify,
+            "shown",
+            serde_json::json!({ "id": id, "interactive": interactive }),
+        )))
+    }
+}
+
+impl Default for NotifyHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 等待用户操作通知，推送对应的事件 (通知关闭前一直等待)
+async fn forward_action(id: String, response: oneshot::Receiver<UserAction>, sender: WsSender, pending: Arc<AtomicUsize>) {
+    let action = response.await;
+    pending.fetch_sub(1, Ordering::SeqCst);
+    let event = match action {
+        Ok(UserAction::Clicked) => ServerResponse::new(ModuleType::Notify, "clicked", serde_json::json!({ "id": id })),
+        Ok(UserAction::Action(action)) => {
+            ServerResponse::new(ModuleType::Notify, "action", serde_json::json!({ "id": id, "action": action }))
+        }
+        Ok(UserAction::Closed) => ServerResponse::new(ModuleType::Notify, "closed", serde_json::json!({ "id": id })),
+        // 后端没有报告操作 (如通知服务断开)
+        Err(_) => return,
+    };
+    log_debug!("通知事件: id={}, type={}", id, event.msg_type);
+    if sender.lock().await.send(Message::Text(event.to_json().into())).await.is_err() {
+        log_debug!("连接已断开，丢弃通知事件: id={}", id);
+    }
+}
+
+/// 通知模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("show", &[
+        FieldSpec::optional("id", FieldKind::String),
+        FieldSpec::required("title", FieldKind::String),
+        FieldSpec::optional("body", FieldKind::String),
+        FieldSpec::optional("actions", FieldKind::Array),
+        FieldSpec::optional("urgency", FieldKind::String).one_of(&["low", "normal", "critical"]),
+        FieldSpec::optional("timeout_ms", FieldKind::Integer),
+    ]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 系统通知模块
+    pub const Notify: ModuleType = ModuleType::named("notify");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for NotifyHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Notify
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理通知消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "show" => self.handle_show(msg).await,
+            _ => Err(notify_error(ErrorCode::UnknownMessageType, format!("未知的通知消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(value: serde_json::Value) -> ShowRequest {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let valid = request(serde_json::json!({
+            "title": "构建完成", "body": "cargo build 用时 42s",
+            "actions": [{ "id": "open", "label": "打开终端" }], "urgency": "critical",
+        }));
+        assert!(valid.validate().is_ok());
+        assert_eq!(valid.urgency, Urgency::Critical);
+
+        let invalid = [
+            serde_json::json!({ "title": "  " }),
+            serde_json::json!({ "title": "x", "actions": [{ "id": "default", "label": "打开" }] }),
+            serde_json::json!({ "title": "x", "actions": [{ "id": "a", "label": "A" }, { "id": "a", "label": "B" }] }),
+            serde_json::json!({ "title": "x", "actions": [
+                { "id": "a", "label": "A" }, { "id": "b", "label": "B" }, { "id": "c", "label": "C" }, { "id": "d", "label": "D" },
+            ] }),
+        ];
+        for value in invalid {
+            assert_eq!(request(value.clone()).validate().unwrap_err().code, ErrorCode::InvalidParams, "{}", value);
+        }
+        let long = request(serde_json::json!({ "title": "x", "body": "长".repeat(MAX_TEXT_CHARS + 1) }));
+        assert_eq!(long.validate().unwrap_err().code, ErrorCode::LimitExceeded);
+    }
+}
@@ -0,0 +1,704 @@
+// 出站消息队列
+// 每个连接一个有界队列，由独立的写任务发送到底层连接，慢客户端不再阻塞 PTY 读取和音量转发。
+// 可丢弃事件 (如 audio_level) 在队列积压时合并或丢弃，响应、转录结果和 PTY 数据不会丢失。
+// 配置了合并窗口的高频事件在窗口内连续到达时合并为一条消息发送
+
+use futures_util::{Sink, SinkExt};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::sync::{Mutex as TokioMutex, Notify};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+
+use crate::config::BatchWindows;
+use crate::router::ModuleType;
+use crate::server::WsSink;
+use crate::system::codec::{self, Encoding};
+use crate::system::locale::Locale;
+
+/// 日志宏
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
+        }
+    };
+}
+
+/// 可丢弃的事件 (模块, 消息类型)：队列积压时只保留同一会话 (或请求) 的最新一条
+const LOSSY_EVENTS: &[(&str, &str)] = &[
+    ("voice", "audio_level"),
+    ("utils", "progress"),
+];
+
+/// 合并后的 JSON 事件最多包含的事件数
+const MAX_BATCH_EVENTS: usize = 256;
+
+/// 合并后的 PTY 输出帧最大字节数
+const MAX_BATCH_FRAME_BYTES: usize = 64 * 1024;
+
+/// 合并事件时保留在外层的字段 (同一批事件的这些字段相同)
+const BATCH_HEAD_FIELDS: &[&str] = &["module", "type", "session_id", "request_id"];
+
+// ============================================================================
+// 消息分类
+// ============================================================================
+
+/// 出站消息优先级
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Priority {
+    /// 不可丢弃，按入队顺序发送 (队列满时等待)
+    Normal,
+    /// 可丢弃，同一键的消息合并为最新一条，队列满时丢弃；在普通消息之后发送
+    Lossy(String),
+}
+
+/// 事件标识 (模块、类型、会话和同类事件的分组键)
+#[derive(Debug, Clone)]
+struct EventId {
+    module: String,
+    msg_type: String,
+    session_id: Option<String>,
+    key: String,
+}
+
+/// 已分类的出站消息
+///
+/// 创建时解析一次，优先级、事件标识和解析后的 JSON 随消息一起投递和入队，
+/// 订阅匹配、错误翻译和事件合并都不再重复解析。
+/// 二进制消息为 PTY 输出帧: [session_id_length: u8][session_id: bytes][data: bytes]，视为 pty.output 事件
+#[derive(Debug, Clone)]
+pub struct Outbound {
+    msg: Message,
+    /// 事件标识 (无法识别的消息为 None)
+    id: Option<EventId>,
+    priority: Priority,
+    /// 文本消息解析后的 JSON 对象
+    json: Option<Arc<serde_json::Map<String, serde_json::Value>>>,
+}
+
+impl Outbound {
+    /// 分类消息 (二进制帧和控制帧均不可丢弃)
+    pub fn new(msg: Message) -> Outbound {
+        let mut json = None;
+        let id = match &msg {
+            Message::Text(text) => match serde_json::from_str(text.as_str()) {
+                Ok(serde_json::Value::Object(object)) => {
+                    let field = |name: &str| object.get(name).and_then(|v| v.as_str()).map(str::to_string);
+                    let id = EventId::new(
+                        field("module").unwrap_or_default(),
+                        field("type").unwrap_or_default(),
+                        field("session_id"),
+                        field("request_id"),
+                    );
+                    json = Some(Arc::new(object));
+                    Some(id)
+                }
+                _ => None,
+            },
+            Message::Binary(data) => data.first()
+                .and_then(|len| data.get(1..1 + *len as usize))
+                .and_then(|session_id| std::str::from_utf8(session_id).ok())
+                .map(|session_id| EventId::new("pty".to_string(), "output".to_string(), Some(session_id.to_string()), None)),
+            _ => None,
+        };
+        let priority = match &id {
+            Some(id) if LOSSY_EVENTS.contains(&(id.module.as_str(), id.msg_type.as_str())) => Priority::Lossy(id.key.clone()),
+            _ => Priority::Normal,
+        };
+        Outbound { msg, id, priority, json }
+    }
+
+    /// 优先级
+    pub fn priority(&self) -> &Priority {
+        &self.priority
+    }
+
+    /// 事件所属模块和会话 ID (用于匹配订阅，模块未注册时为 None)
+    pub fn topic(&self) -> Option<(ModuleType, Option<&str>)> {
+        let id = self.id.as_ref()?;
+        Some((ModuleType::from_name(&id.module)?, id.session_id.as_deref()))
+    }
+
+    /// 按连接语言翻译错误描述 (错误的 `message`、诊断结果和 `error` 字段等，见 locale.rs)，没有需要翻译的内容时原样返回
+    fn localized(self, locale: Locale) -> Outbound {
+        let Some(json) = &self.json else {
+            return self;
+        };
+        if !locale.affects(json) {
+            return self;
+        }
+        let mut value = serde_json::Value::Object(json.as_ref().clone());
+        locale.localize_value(&mut value);
+        let serde_json::Value::Object(object) = value else {
+            return self;
+        };
+        let msg = Message::Text(serde_json::to_string(&object).unwrap_or_default().into());
+        Outbound { msg, json: Some(Arc::new(object)), ..self }
+    }
+}
+
+impl EventId {
+    fn new(module: String, msg_type: String, session_id: Option<String>, request_id: Option<String>) -> EventId {
+        let key = format!(
+            "{}.{}:{}:{}",
+            module, msg_type, session_id.as_deref().unwrap_or_default(), request_id.as_deref().unwrap_or_default(),
+        );
+        EventId { module, msg_type, session_id, key }
+    }
+}
+
+// ============================================================================
+// 队列条目
+// ============================================================================
+
+/// 条目内容
+enum Body {
+    /// 原样发送的消息
+    Message(Message),
+    /// 同类 JSON 事件：公共字段 + 各事件的其余字段
+    Events {
+        head: serde_json::Map<String, serde_json::Value>,
+        items: Vec<serde_json::Value>,
+    },
+    /// 同一会话的 PTY 输出帧 (后续帧的数据追加到第一帧)
+    Frame(Vec<u8>),
+}
+
+/// 队列中的一条待发送消息 (可能由多条事件合并而成)
+struct Entry {
+    body: Body,
+    /// 入队时的连接编码 (发送时按此编码转换)
+    encoding: Encoding,
+    /// 同类事件的分组键
+    key: Option<String>,
+    /// 合并窗口的截止时间 (不可合并的消息为 None)
+    deadline: Option<Instant>,
+}
+
+impl Entry {
+    /// 创建条目，`window` 为该事件的合并窗口
+    fn new(item: Outbound, encoding: Encoding, window: Option<std::time::Duration>) -> Entry {
+        let deadline = window.map(|window| Instant::now() + window);
+        let key = item.id.map(|id| id.key);
+        let body = match (deadline, item.msg, item.json) {
+            (Some(_), Message::Binary(data), _) => Body::Frame(data.to_vec()),
+            (Some(_), Message::Text(_), Some(json)) => {
+                let mut item = Arc::unwrap_or_clone(json);
+                let head = BATCH_HEAD_FIELDS.iter()
+                    .filter_map(|field| item.remove_entry(*field))
+                    .collect();
+                Body::Events { head, items: vec![serde_json::Value::Object(item)] }
+            }
+            (_, msg, _) => Body::Message(msg),
+        };
+        Entry { body, encoding, key, deadline }
+    }
+
+    /// 是否已达到合并上限
+    fn is_full(&self) -> bool {
+        match &self.body {
+            Body::Message(_) => true,
+            Body::Events { items, .. } => items.len() >= MAX_BATCH_EVENTS,
+            Body::Frame(frame) => frame.len() >= MAX_BATCH_FRAME_BYTES,
+        }
+    }
+
+    /// 尝试把同类事件合并到本条目，无法合并时原样返回
+    fn merge(&mut self, other: Entry) -> Result<(), Entry> {
+        if self.deadline.is_none() || self.key != other.key || self.encoding != other.encoding || self.is_full() {
+            return Err(other);
+        }
+        match (&mut self.body, other.body) {
+            (Body::Events { items, .. }, Body::Events { items: mut more, .. }) => {
+                items.append(&mut more);
+                Ok(())
+            }
+            (Body::Frame(frame), Body::Frame(more)) => {
+                let header = 1 + more[0] as usize;
+                frame.extend_from_slice(&more[header..]);
+                Ok(())
+            }
+            (_, body) => Err(Entry { body, ..other }),
+        }
+    }
+
+    /// 是否可以发送：合并窗口已过、已满，或后面已有其他消息 (不能再合并新事件)
+    fn is_ready(&self, now: Instant, is_last: bool) -> bool {
+        match self.deadline {
+            Some(deadline) => !is_last || now >= deadline || self.is_full(),
+            None => true,
+        }
+    }
+
+    /// 转换为发送的消息：单条事件保持原格式，多条事件放在 `batch` 数组中
+    fn into_message(self) -> Message {
+        let msg = match self.body {
+            Body::Message(msg) => msg,
+            Body::Frame(frame) => Message::Binary(frame.into()),
+            Body::Events { mut head, mut items } => {
+                if items.len() == 1 {
+                    if let Some(serde_json::Value::Object(item)) = items.pop() {
+                        head.extend(item);
+                    }
+                } else {
+                    head.insert("batch".to_string(), serde_json::Value::Array(items));
+                }
+                Message::Text(serde_json::Value::Object(head).to_string().into())
+            }
+        };
+        codec::encode_message(msg, self.encoding)
+    }
+}
+
+// ============================================================================
+// 出站队列
+// ============================================================================
+
+#[derive(Default)]
+struct QueueState {
+    normal: VecDeque<Entry>,
+    lossy: VecDeque<Entry>,
+    /// 写任务正在发送一条消息
+    sending: bool,
+    closed: bool,
+}
+
+impl QueueState {
+    fn len(&self) -> usize {
+        self.normal.len() + self.lossy.len()
+    }
+
+    /// 取出下一条可发送的条目 (普通消息优先)，关闭后不再等待合并窗口
+    fn take_ready(&mut self, now: Instant) -> Option<Entry> {
+        let closed = self.closed;
+        let normal_ready = self.normal.front()
+            .is_some_and(|entry| closed || entry.is_ready(now, self.normal.len() == 1));
+        if normal_ready {
+            return self.normal.pop_front();
+        }
+        let index = self.lossy.iter().position(|entry| closed || entry.is_ready(now, true))?;
+        self.lossy.remove(index)
+    }
+
+    /// 最早到期的合并窗口
+    fn next_deadline(&self) -> Option<Instant> {
+        self.normal.front().into_iter()
+            .chain(self.lossy.iter())
+            .filter_map(|entry| entry.deadline)
+            .min()
+    }
+}
+
+/// 单个连接的有界出站队列
+pub struct OutboundQueue {
+    capacity: usize,
+    /// 事件合并窗口
+    windows: BatchWindows,
+    /// 客户端是否接受合并后的 JSON 事件 (握手时协商；PTY 输出帧合并对客户端透明，不受此限制)
+    batching: AtomicBool,
+    state: Mutex<QueueState>,
+    /// 有新消息或队列关闭 (唤醒写任务)
+    ready: Notify,
+    /// 队列有空位、已清空或已关闭 (唤醒等待的发送方)
+    space: Notify,
+    /// 被丢弃的可丢弃事件数
+    dropped: AtomicU64,
+}
+
+impl OutboundQueue {
+    /// 创建队列并启动写任务，队列中的消息依次写入 `sink`
+    pub fn spawn(capacity: usize, windows: BatchWindows, mut sink: WsSink) -> Arc<Self> {
+        let queue = Arc::new(Self {
+            capacity: capacity.max(1),
+            windows,
+            batching: AtomicBool::new(false),
+            state: Mutex::new(QueueState::default()),
+            ready: Notify::new(),
+            space: Notify::new(),
+            dropped: AtomicU64::new(0),
+        });
+        let writer = Arc::clone(&queue);
+        tokio::spawn(async move {
+            while let Some(msg) = writer.next().await {
+                let result = sink.send(msg).await;
+                writer.sent();
+                if let Err(e) = result {
+                    log_debug!("出站消息发送失败，关闭队列: {}", e);
+                    writer.close();
+                    break;
+                }
+            }
+            let _ = sink.close().await;
+        });
+        queue
+    }
+
+    /// 设置客户端是否接受合并后的 JSON 事件
+    pub fn set_batching(&self, enabled: bool) {
+        self.batching.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 消息入队，发送时按 `encoding` 转换
+    ///
+    /// 可丢弃事件立即返回 (合并或丢弃)，其他消息在队列满时等待写任务腾出空位。
+    /// 队列已关闭时返回 `ConnectionClosed`
+    pub async fn push(&self, item: Outbound, encoding: Encoding) -> Result<(), WsError> {
+        let priority = item.priority.clone();
+        let window = item.id.as_ref()
+            .filter(|_| matches!(item.msg, Message::Binary(_)) || self.batching.load(Ordering::Relaxed))
+            .and_then(|id| self.windows.get(&id.module, &id.msg_type));
+        let mut entry = Entry::new(item, encoding, window);
+        loop {
+            let space = self.space.notified();
+            {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                if state.closed {
+                    return Err(WsError::ConnectionClosed);
+                }
+                match &priority {
+                    Priority::Lossy(key) => {
+                        if let Some(slot) = state.lossy.iter_mut().find(|e| e.key.as_ref() == Some(key)) {
+                            // 能合并时保留窗口内的全部事件，否则只保留最新一条
+                            if let Err(entry) = slot.merge(entry) {
+                                *slot = entry;
+                            }
+                        } else if state.len() < self.capacity {
+                            state.lossy.push_back(entry);
+                        } else {
+                            self.dropped.fetch_add(1, Ordering::Relaxed);
+                            return Ok(());
+                        }
+                        self.ready.notify_one();
+                        return Ok(());
+                    }
+                    Priority::Normal => {
+                        entry = match state.normal.back_mut() {
+                            Some(last) => match last.merge(entry) {
+                                Ok(()) => return Ok(()),
+                                Err(entry) => entry,
+                            },
+                            None => entry,
+                        };
+                        if state.len() < self.capacity {
+                            state.normal.push_back(entry);
+                            self.ready.notify_one();
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+            space.await;
+        }
+    }
+
+    /// 取出下一条待发送的消息 (普通消息优先)，队列关闭且已清空时返回 None
+    ///
+    /// 合并窗口未到期的事件留在队列中继续合并，直到窗口到期或后面有其他消息
+    async fn next(&self) -> Option<Message> {
+        loop {
+            let ready = self.ready.notified();
+            let deadline = {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(entry) = state.take_ready(Instant::now()) {
+                    state.sending = true;
+                    return Some(entry.into_message());
+                }
+                if state.closed {
+                    return None;
+                }
+                state.next_deadline()
+            };
+            match deadline {
+                Some(deadline) => {
+                    tokio::select! {
+                        _ = ready => {}
+                        _ = tokio::time::sleep_until(deadline.into()) => {}
+                    }
+                }
+                None => ready.await,
+            }
+        }
+    }
+
+    /// 一条消息发送完成
+    fn sent(&self) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).sending = false;
+        self.space.notify_waiters();
+    }
+
+    /// 关闭队列：不再接受新消息，写任务发送完剩余消息后退出
+    pub fn close(&self) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).closed = true;
+        self.ready.notify_one();
+        self.space.notify_waiters();
+    }
+
+    /// 等待队列中的消息全部写入底层连接 (或队列关闭)
+    #[allow(dead_code)]
+    pub async fn flush(&self) {
+        loop {
+            let space = self.space.notified();
+            {
+                let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                if state.closed || (state.len() == 0 && !state.sending) {
+                    return;
+                }
+            }
+            space.await;
+        }
+    }
+
+    /// 被丢弃的可丢弃事件数
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+// ============================================================================
+// 入队发送端
+// ============================================================================
+
+type PushFuture = Pin<Box<dyn Future<Output = Result<(), WsError>> + Send>>;
+
+/// 连接的出站端口：按连接协商的语言翻译错误描述，记录连接当前的编码后入队
+#[derive(Clone)]
+pub struct OutboundPort {
+    queue: Arc<OutboundQueue>,
+    encoding: Arc<TokioMutex<Encoding>>,
+    locale: Arc<TokioMutex<Option<Locale>>>,
+}
+
+impl OutboundPort {
+    pub fn new(
+        queue: Arc<OutboundQueue>,
+        encoding: Arc<TokioMutex<Encoding>>,
+        locale: Arc<TokioMutex<Option<Locale>>>,
+    ) -> Self {
+        Self { queue, encoding, locale }
+    }
+
+    /// 发送已分类的消息，队列关闭后返回 `ConnectionClosed`
+    pub async fn send(&self, item: Outbound) -> Result<(), WsError> {
+        // 入队时即确定编码 (之后切换编码不影响已入队的消息)
+        let encoding = *self.encoding.lock().await;
+        let locale = *self.locale.lock().await;
+        let item = match locale {
+            Some(locale) => item.localized(locale),
+            None => item,
+        };
+        self.queue.push(item, encoding).await
+    }
+}
+
+/// 写入出站端口的发送端：每条消息在这里分类一次
+///
+/// 队列关闭后每次发送都返回 `ConnectionClosed`
+pub struct QueueSink {
+    port: OutboundPort,
+    pending: Option<PushFuture>,
+}
+
+impl QueueSink {
+    pub fn new(port: OutboundPort) -> Self {
+        Self { port, pending: None }
+    }
+}
+
+impl Sink<Message> for QueueSink {
+    type Error = WsError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), WsError>> {
+        self.poll_flush(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, msg: Message) -> Result<(), WsError> {
+        let port = self.port.clone();
+        self.pending = Some(Box::pin(async move { port.send(Outbound::new(msg)).await }));
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), WsError>> {
+        let Some(pending) = self.pending.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+        let result = std::task::ready!(pending.as_mut().poll(cx));
+        self.pending = None;
+        Poll::Ready(result)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), WsError>> {
+        self.poll_flush(cx)
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    fn event(msg_type: &str, n: u32) -> Message {
+        Message::Text(serde_json::json!({ "module": "voice", "type": msg_type, "n": n }).to_string().into())
+    }
+
+    async fn push(queue: &OutboundQueue, msg: Message) -> Result<(), WsError> {
+        queue.push(Outbound::new(msg), Encoding::Json).await
+    }
+
+    fn texts(received: &Mutex<Vec<Message>>) -> Vec<String> {
+        received.lock().unwrap().iter()
+            .map(|m| {
+                let value: serde_json::Value = serde_json::from_str(m.to_text().unwrap()).unwrap();
+                format!("{}#{}", value["type"].as_str().unwrap(), value["n"])
+            })
+            .collect()
+    }
+
+    /// 创建把消息记录到列表中的发送端
+    fn recording_sink() -> (WsSink, Arc<Mutex<Vec<Message>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = futures_util::sink::unfold(Arc::clone(&received), |received, msg: Message| async move {
+            received.lock().unwrap().push(msg);
+            Ok::<_, WsError>(received)
+        });
+        (Box::pin(sink), received)
+    }
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(Outbound::new(event("transcription_complete", 0)).priority(), &Priority::Normal);
+        assert!(matches!(Outbound::new(event("audio_level", 0)).priority(), Priority::Lossy(_)));
+
+        // 二进制帧视为 pty.output 事件，不可丢弃
+        let frame = Outbound::new(Message::Binary(vec![1, b'a', b'x'].into()));
+        assert_eq!(frame.priority(), &Priority::Normal);
+        assert_eq!(frame.id.as_ref().and_then(|id| id.session_id.as_deref()), Some("a"));
+        assert_eq!(Outbound::new(Message::Text("not json".into())).topic(), None);
+    }
+
+    #[test]
+    fn test_localized() {
+        let error = Message::Text(serde_json::json!({
+            "module": "voice", "type": "error", "code": "NOT_RECORDING", "message": "未在录音中", "request_id": "req-1",
+        }).to_string().into());
+        let localized = Outbound::new(error).localized(Locale::En);
+        let value: serde_json::Value = serde_json::from_str(localized.msg.to_text().unwrap()).unwrap();
+        assert_eq!(value["message"], "Not recording");
+        assert_eq!(value["code"], "NOT_RECORDING");
+        assert_eq!(value["request_id"], "req-1");
+        assert_eq!(localized.json.unwrap()["message"], "Not recording");
+
+        // 不是错误的消息原样发送
+        let event = Message::Text(r#"{"module":"voice","type":"transcription_complete","message":"未在录音中"}"#.into());
+        assert_eq!(Outbound::new(event.clone()).localized(Locale::En).msg, event);
+
+        // 诊断结果的 message 和嵌套的 error 字段一并翻译
+        let report = Message::Text(serde_json::json!({
+            "module": "system", "type": "diagnosis",
+            "checks": [{ "check": "disk", "status": "fail", "message": "无法获取磁盘空间: denied" }],
+            "endpoints": [{ "name": "qwen", "reachable": false, "error": "URL 无效: empty host" }],
+        }).to_string().into());
+        let value = Outbound::new(report).localized(Locale::En).json.unwrap();
+        assert_eq!(value["checks"][0]["message"], "Cannot get disk space: denied");
+        assert_eq!(value["endpoints"][0]["error"], "Invalid URL: empty host");
+    }
+
+    #[tokio::test]
+    async fn test_lossy_events_coalesce_under_pressure() {
+        // 写任务每发送一条消息需要一个许可，模拟慢客户端
+        let permits = Arc::new(Semaphore::new(0));
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = futures_util::sink::unfold(
+            (Arc::clone(&permits), Arc::clone(&received)),
+            |(permits, received), msg: Message| async move {
+                permits.acquire().await.unwrap().forget();
+                received.lock().unwrap().push(msg);
+                Ok::<_, WsError>((permits, received))
+            },
+        );
+        let queue = OutboundQueue::spawn(3, BatchWindows::none(), Box::pin(sink));
+
+        push(&queue, event("transcription_progress", 0)).await.unwrap();
+        tokio::task::yield_now().await;
+        for n in 1..=5 {
+            push(&queue, event("audio_level", n)).await.unwrap();
+        }
+        push(&queue, event("transcription_complete", 6)).await.unwrap();
+        push(&queue, event("transcription_complete", 7)).await.unwrap();
+
+        // 队列已满：可丢弃事件被丢弃，普通消息等待空位
+        push(&queue, Message::Text(r#"{"module":"voice","type":"audio_level","session_id":"x","n":8}"#.into())).await.unwrap();
+        assert_eq!(queue.dropped(), 1);
+        let blocked = tokio::time::timeout(Duration::from_millis(50), push(&queue, event("transcription_complete", 9))).await;
+        assert!(blocked.is_err());
+
+        // 普通消息先于可丢弃事件发送，连续的音量事件只保留最新一条
+        permits.add_permits(10);
+        queue.flush().await;
+        assert_eq!(texts(&received), vec![
+            "transcription_progress#0",
+            "transcription_complete#6",
+            "transcription_complete#7",
+            "audio_level#5",
+        ]);
+
+        queue.close();
+        assert!(push(&queue, event("transcription_complete", 10)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_batching() {
+        let windows = BatchWindows::none()
+            .with("llm.stream_chunk", Duration::from_millis(20))
+            .with("pty.output", Duration::from_millis(20));
+        let (sink, received) = recording_sink();
+        let queue = OutboundQueue::spawn(16, windows, sink);
+        let chunk = |content: &str| Message::Text(
+            serde_json::json!({ "module": "llm", "type": "stream_chunk", "content": content, "request_id": "r1" }).to_string().into()
+        );
+        let frame = |data: &[u8]| {
+            let mut frame = vec![3u8];
+            frame.extend_from_slice(b"abc");
+            frame.extend_from_slice(data);
+            Message::Binary(frame.into())
+        };
+
+        // 客户端未接受合并时 JSON 事件逐条发送，PTY 输出帧仍然合并
+        push(&queue, chunk("a")).await.unwrap();
+        push(&queue, chunk("b")).await.unwrap();
+        push(&queue, frame(b"12")).await.unwrap();
+        push(&queue, frame(b"34")).await.unwrap();
+        queue.flush().await;
+        {
+            let received = received.lock().unwrap();
+            assert_eq!(received.len(), 3);
+            assert_eq!(received[2], frame(b"1234"));
+        }
+
+        // 接受合并后窗口内连续的同类事件合并为 batch，其他消息结束当前批次
+        queue.set_batching(true);
+        push(&queue, chunk("c")).await.unwrap();
+        push(&queue, chunk("d")).await.unwrap();
+        push(&queue, event("transcription_complete", 1)).await.unwrap();
+        push(&queue, chunk("e")).await.unwrap();
+        queue.flush().await;
+        let received = received.lock().unwrap();
+        let values: Vec<serde_json::Value> = received[3..].iter()
+            .map(|m| serde_json::from_str(m.to_text().unwrap()).unwrap())
+            .collect();
+        assert_eq!(values, vec![
+            serde_json::json!({ "module": "llm", "type": "stream_chunk", "request_id": "r1", "batch": [{ "content": "c" }, { "content": "d" }] }),
+            serde_json::json!({ "module": "voice", "type": "transcription_complete", "n": 1 }),
+            serde_json::json!({ "module": "llm", "type": "stream_chunk", "request_id": "r1", "content": "e" }),
+        ]);
+    }
+}
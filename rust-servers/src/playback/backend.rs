@@ -0,0 +1,367 @@
+// 音频输出
+// 解码后的音频通过 rodio 播放到系统输出设备。rodio 的输出流不能跨线程移动，
+// 每次播放在单独的线程中打开输出流并持有到播放结束，控制通过可共享的 Sink 完成
+
+use rodio::cpal::traits::HostTrait;
+use rodio::{Decoder, DeviceTrait, OutputStreamBuilder, Sink, Source};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 播放错误
+#[derive(Debug, Error)]
+pub enum PlaybackError {
+    #[error("无法打开音频文件: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("无法解码音频: {0}")]
+    Decode(String),
+    #[error("未找到指定播放设备: {0}")]
+    DeviceNotFound(String),
+    #[error("音频输出不可用: {0}")]
+    Unavailable(String),
+    #[error("无法跳转: {0}")]
+    Seek(String),
+}
+
+impl CodedError for PlaybackError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            PlaybackError::Io(e) if e.kind() == std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+            PlaybackError::Io(_) => ErrorCode::IoError,
+            PlaybackError::Decode(_) | PlaybackError::Seek(_) => ErrorCode::InvalidParams,
+            PlaybackError::DeviceNotFound(_) => ErrorCode::NotFound,
+            PlaybackError::Unavailable(_) => ErrorCode::DeviceError,
+        }
+    }
+}
+
+/// 输出设备信息
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct OutputDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+}
+
+/// 待播放的音频 (打开时已解码头部，得到时长)
+pub struct Track {
+    source: Box<dyn Source + Send>,
+    /// 总时长 (格式未记录时长时为 None)
+    pub duration: Option<Duration>,
+    /// 边写入边播放的音频流
+    stream: Option<PcmStream>,
+}
+
+impl Track {
+    /// 打开音频文件 (边读边解码，不整体载入内存)
+    pub fn open(path: &Path) -> Result<Self, PlaybackError> {
+        let decoder = Decoder::try_from(File::open(path)?).map_err(|e| PlaybackError::Decode(e.to_string()))?;
+        Ok(Self::from_decoder(decoder))
+    }
+
+    /// 内存中的音频数据 (如 TTS 输出)
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, PlaybackError> {
+        let decoder = Decoder::try_from(Cursor::new(bytes)).map_err(|e| PlaybackError::Decode(e.to_string()))?;
+        Ok(Self::from_decoder(decoder))
+    }
+
+    /// 边写入边播放的 PCM 音频 (如流式 TTS 输出)，数据通过返回的 PcmStream 陆续写入
+    pub fn stream(sample_rate: u32, channels: u16) -> (Self, PcmStream) {
+        let stream = PcmStream::new(sample_rate, channels);
+        let track = Self {
+            source: Box::new(PcmSource { stream: stream.clone(), buffer: VecDeque::new() }),
+            duration: None,
+            stream: Some(stream.clone()),
+        };
+        (track, stream)
+    }
+
+    fn from_decoder<R: Read + Seek + Send + Sync + 'static>(decoder: Decoder<R>) -> Self {
+        Self {
+            duration: decoder.total_duration(),
+            source: Box::new(decoder),
+            stream: None,
+        }
+    }
+
+    /// 边写入边播放时的音频流
+    pub fn pcm_stream(&self) -> Option<&PcmStream> {
+        self.stream.as_ref()
+    }
+
+    /// 解码后的音频源
+    pub fn into_source(self) -> Box<dyn Source + Send> {
+        self.source
+    }
+}
+
+// ============================================================================
+// 流式音频
+// ============================================================================
+
+/// 写入中的 PCM 数据
+#[derive(Default)]
+struct PcmBuffer {
+    samples: VecDeque<f32>,
+    /// 上次写入末尾不足一个采样的字节
+    odd_byte: Option<u8>,
+}
+
+/// 边写入边播放的 PCM 音频流 (16 位有符号小端)，克隆后共享同一缓冲区
+#[derive(Clone)]
+pub struct PcmStream {
+    sample_rate: u32,
+    channels: u16,
+    buffer: Arc<Mutex<PcmBuffer>>,
+    /// 已写入的采样数 (各声道合计)
+    written: Arc<AtomicU64>,
+    finished: Arc<AtomicBool>,
+}
+
+impl PcmStream {
+    fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate,
+            channels: channels.max(1),
+            buffer: Arc::new(Mutex::new(PcmBuffer::default())),
+            written: Arc::new(AtomicU64::new(0)),
+            finished: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn buffer(&self) -> std::sync::MutexGuard<'_, PcmBuffer> {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 追加 PCM 数据 (可以在采样中间切开)
+    pub fn write(&self, pcm: &[u8]) {
+        let mut buffer = self.buffer();
+        let mut bytes = pcm;
+        if let Some(low) = buffer.odd_byte.take() {
+            let Some((&high, rest)) = bytes.split_first() else {
+                buffer.odd_byte = Some(low);
+                return;
+            };
+            buffer.samples.push_back(i16::from_le_bytes([low, high]) as f32 / 32768.0);
+            self.written.fetch_add(1, Ordering::Relaxed);
+            bytes = rest;
+        }
+        let pairs = bytes.chunks_exact(2);
+        buffer.odd_byte = pairs.remainder().first().copied();
+        let count = pairs.len() as u64;
+        buffer.samples.extend(pairs.map(|pair| i16::from_le_bytes([pair[0], pair[1]]) as f32 / 32768.0));
+        self.written.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// 数据已全部写入，播放完剩余数据后结束
+    pub fn finish(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+    }
+
+    /// 是否已全部写入
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    /// 已写入音频的时长
+    pub fn written(&self) -> Duration {
+        let frames = self.written.load(Ordering::Relaxed) / self.channels as u64;
+        Duration::from_micros(frames * 1_000_000 / self.sample_rate.max(1) as u64)
+    }
+}
+
+/// 读取 PcmStream 的音频源，数据暂时不足时输出静音
+struct PcmSource {
+    stream: PcmStream,
+    /// 从共享缓冲区取出、待输出的采样
+    buffer: VecDeque<f32>,
+}
+
+impl Iterator for PcmSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(sample) = self.buffer.pop_front() {
+            return Some(sample);
+        }
+        // 先读取结束标记再取数据，避免漏掉结束前最后写入的数据
+        let finished = self.stream.is_finished();
+        std::mem::swap(&mut self.buffer, &mut self.stream.buffer().samples);
+        match self.buffer.pop_front() {
+            Some(sample) => Some(sample),
+            None if finished => None,
+            None => Some(0.0),
+        }
+    }
+}
+
+impl Source for PcmSource {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.stream.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.stream.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// 正在播放的音频 (丢弃时停止播放)
+pub trait PlaybackOutput: Send + Sync {
+    fn pause(&self);
+    fn resume(&self);
+    fn is_paused(&self) -> bool;
+    fn seek(&self, position: Duration) -> Result<(), PlaybackError>;
+    /// 当前播放位置
+    fn position(&self) -> Duration;
+    /// 是否已播放完毕
+    fn finished(&self) -> bool;
+}
+
+/// 音频输出后端
+pub trait OutputBackend {
+    /// 输出设备列表
+    fn devices(&self) -> Result<Vec<OutputDeviceInfo>, PlaybackError>;
+
+    /// 在指定设备 (None 为默认设备) 上开始播放
+    fn play(&self, track: Track, device: Option<&str>, volume: f32) -> Result<Arc<dyn PlaybackOutput>, PlaybackError>;
+}
+
+// ============================================================================
+// 系统后端
+// ============================================================================
+
+/// 系统音频输出
+struct SystemBackend;
+
+impl SystemBackend {
+    fn select_device(name: &str) -> Result<rodio::Device, PlaybackError> {
+        let host = rodio::cpal::default_host();
+        let devices = host
+            .output_devices()
+            .map_err(|e| PlaybackError::Unavailable(format!("无法获取输出设备列表: {}", e)))?;
+        for device in devices {
+            if device.name().is_ok_and(|device_name| device_name == name) {
+                return Ok(device);
+            }
+        }
+        Err(PlaybackError::DeviceNotFound(name.to_string()))
+    }
+}
+
+impl OutputBackend for SystemBackend {
+    fn devices(&self) -> Result<Vec<OutputDeviceInfo>, PlaybackError> {
+        let host = rodio::cpal::default_host();
+        let default_name = host.default_output_device().and_then(|device| device.name().ok());
+        let devices = host
+            .output_devices()
+            .map_err(|e| PlaybackError::Unavailable(format!("无法获取输出设备列表: {}", e)))?;
+        Ok(devices
+            .filter_map(|device| device.name().ok())
+            .map(|name| OutputDeviceInfo {
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+            })
+            .collect())
+    }
+
+    fn play(&self, track: Track, device: Option<&str>, volume: f32) -> Result<Arc<dyn PlaybackOutput>, PlaybackError> {
+        let device = device.map(Self::select_device).transpose()?;
+        let (opened_tx, opened_rx) = std::sync::mpsc::channel();
+        let (stop_tx, stop_rx) = std::sync::mpsc::channel::<()>();
+
+        // 输出流在播放线程中打开并持有，SystemOutput 丢弃时 stop_tx 随之关闭，线程退出并关闭输出流
+        std::thread::Builder::new()
+            .name("playback-output".to_string())
+            .spawn(move || {
+                let builder = match device {
+                    Some(device) => OutputStreamBuilder::from_device(device),
+                    None => OutputStreamBuilder::from_default_device(),
+                };
+                let stream = match builder.and_then(|builder| builder.open_stream_or_fallback()) {
+                    Ok(mut stream) => {
+                        stream.log_on_drop(false);
+                        stream
+                    }
+                    Err(e) => {
+                        let _ = opened_tx.send(Err(PlaybackError::Unavailable(e.to_string())));
+                        return;
+                    }
+                };
+                let sink = Arc::new(Sink::connect_new(stream.mixer()));
+                sink.set_volume(volume);
+                sink.append(track.into_source());
+                if opened_tx.send(Ok(Arc::clone(&sink))).is_err() {
+                    return;
+                }
+                let _ = stop_rx.recv();
+                sink.stop();
+            })?;
+
+        let sink = opened_rx
+            .recv()
+            .map_err(|_| PlaybackError::Unavailable("播放线程意外退出".to_string()))??;
+        Ok(Arc::new(SystemOutput { sink, _stop: stop_tx }))
+    }
+}
+
+/// 系统输出设备上的播放
+struct SystemOutput {
+    sink: Arc<Sink>,
+    /// 丢弃时通知播放线程关闭输出流
+    _stop: std::sync::mpsc::Sender<()>,
+}
+
+impl PlaybackOutput for SystemOutput {
+    fn pause(&self) {
+        self.sink.pause();
+    }
+
+    fn resume(&self) {
+        self.sink.play();
+    }
+
+    fn is_paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn seek(&self, position: Duration) -> Result<(), PlaybackError> {
+        self.sink.try_seek(position).map_err(|e| PlaybackError::Seek(e.to_string()))
+    }
+
+    fn position(&self) -> Duration {
+        self.sink.get_pos()
+    }
+
+    fn finished(&self) -> bool {
+        self.sink.empty()
+    }
+}
+
+/// 打开系统音频输出
+#[cfg(not(any(test, feature = "test-support")))]
+pub fn open() -> Box<dyn OutputBackend> {
+    Box::new(SystemBackend)
+}
+
+/// 打开音频输出 (安装了模拟输出时使用模拟输出)
+#[cfg(any(test, feature = "test-support"))]
+pub fn open() -> Box<dyn OutputBackend> {
+    if crate::testing::playback::is_installed() {
+        return Box::new(crate::testing::playback::FakeOutput);
+    }
+    Box::new(SystemBackend)
+}
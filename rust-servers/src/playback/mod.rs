@@ -0,0 +1,507 @@
+// 音频播放模块
+// 播放本地音频文件和内存中的音频 (如 TTS 输出)，支持暂停、跳转、停止和选择输出设备。
+// 同一时间只播放一段音频，新的 play 请求替换正在播放的音频；
+// 播放期间定时推送 position 事件，播放完毕时推送 ended 事件
+
+mod backend;
+
+pub use backend::{open as open_output, OutputBackend, OutputDeviceInfo, PcmStream, PlaybackError, PlaybackOutput, Track};
+
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use base64::{Engine as _, engine::general_purpose};
+use futures_util::SinkExt;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Mutex as TokioMutex;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Playback", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Playback", format!($($arg)*));
+        }
+    };
+}
+
+/// 默认的 position 事件间隔 (毫秒)
+const DEFAULT_POSITION_INTERVAL_MS: u64 = 1000;
+
+/// position 事件间隔的取值范围 (毫秒，0 表示不推送)
+const POSITION_INTERVAL_RANGE_MS: std::ops::RangeInclusive<u64> = 100..=10_000;
+
+/// 检查播放是否结束的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// PCM 数据的默认采样率 (常见 TTS 接口的 PCM 输出)
+const DEFAULT_PCM_SAMPLE_RATE: u32 = 24_000;
+
+/// 创建播放模块错误
+fn playback_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Playback, code, message)
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// play 请求 (path 和 data 二选一)
+#[derive(Debug, Deserialize)]
+struct PlayRequest {
+    /// 播放 id (事件中原样返回)，省略时自动生成
+    #[serde(default)]
+    id: Option<String>,
+    /// 本地音频文件
+    #[serde(default)]
+    path: Option<String>,
+    /// base64 编码的音频数据
+    #[serde(default)]
+    data: Option<String>,
+    /// data 的格式: wav (默认) / pcm_s16le
+    #[serde(default)]
+    format: Option<String>,
+    /// PCM 数据的采样率
+    #[serde(default)]
+    sample_rate: Option<u32>,
+    /// PCM 数据的声道数
+    #[serde(default)]
+    channels: Option<u16>,
+    /// 输出设备名称，省略时使用默认设备
+    #[serde(default)]
+    device: Option<String>,
+    /// 音量 (0.0 - 1.0)
+    #[serde(default)]
+    volume: Option<f32>,
+    /// 开始位置 (毫秒)
+    #[serde(default)]
+    start_ms: Option<u64>,
+    #[serde(default)]
+    position_interval_ms: Option<u64>,
+}
+
+/// 音频来源
+enum Input {
+    File(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl PlayRequest {
+    /// 解析音频来源 (PCM 数据转为 WAV)
+    fn input(&self) -> Result<Input, ModuleError> {
+        let data = match (&self.path, &self.data) {
+            (Some(path), None) => return Ok(Input::File(PathBuf::from(path))),
+            (None, Some(data)) => general_purpose::STANDARD.decode(data.as_bytes())
+                .map_err(|e| playback_error(ErrorCode::InvalidParams, format!("无效的 base64 数据: {}", e)))?,
+            _ => return Err(playback_error(ErrorCode::InvalidParams, "必须提供 'path' 或 'data' 之一")),
+        };
+        match self.format.as_deref() {
+            None | Some("wav") => Ok(Input::Bytes(data)),
+            Some("pcm_s16le") => {
+                let channels = self.channels.unwrap_or(1);
+                let sample_rate = self.sample_rate.unwrap_or(DEFAULT_PCM_SAMPLE_RATE);
+                if channels == 0 || sample_rate == 0 || data.len() % (2 * channels as usize) != 0 {
+                    return Err(playback_error(ErrorCode::InvalidParams, "PCM 数据长度与声道数不符"));
+                }
+                let samples: Vec<i16> = data.chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect();
+                let wav = crate::voice::audio::encode_i16_to_wav(&samples, sample_rate, channels)
+                    .map_err(|e| playback_error(ErrorCode::Internal, format!("PCM 转换失败: {}", e)))?;
+                Ok(Input::Bytes(wav))
+            }
+            Some(format) => Err(playback_error(ErrorCode::InvalidParams, format!("不支持的音频格式: {}", format))),
+        }
+    }
+
+    fn volume(&self) -> Result<f32, ModuleError> {
+        let volume = self.volume.unwrap_or(1.0);
+        if !(0.0..=1.0).contains(&volume) {
+            return Err(playback_error(ErrorCode::InvalidParams, format!("volume 超出范围: {} (应为 0-1)", volume)));
+        }
+        Ok(volume)
+    }
+
+    fn position_interval(&self) -> Result<Option<Duration>, ModuleError> {
+        match self.position_interval_ms.unwrap_or(DEFAULT_POSITION_INTERVAL_MS) {
+            0 => Ok(None),
+            ms if POSITION_INTERVAL_RANGE_MS.contains(&ms) => Ok(Some(Duration::from_millis(ms))),
+            ms => Err(playback_error(
+                ErrorCode::InvalidParams,
+                format!(
+                    "position_interval_ms 超出范围: {} (应为 0 或 {}-{})",
+                    ms, POSITION_INTERVAL_RANGE_MS.start(), POSITION_INTERVAL_RANGE_MS.end()
+                ),
+            )),
+        }
+    }
+}
+
+// ============================================================================
+// 播放状态
+// ============================================================================
+
+/// 正在播放的音频 (丢弃时停止播放和事件推送)
+struct Playback {
+    id: String,
+    output: Arc<dyn PlaybackOutput>,
+    duration: Option<Duration>,
+    /// 推送 position / ended 事件的任务
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl Playback {
+    fn payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "state": if self.output.is_paused() { "paused" } else { "playing" },
+            "position_ms": millis(self.output.position()),
+            "duration_ms": self.duration.map(millis),
+        })
+    }
+}
+
+impl Drop for Playback {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+type Current = Arc<Mutex<Option<Playback>>>;
+
+fn lock(current: &Current) -> std::sync::MutexGuard<'_, Option<Playback>> {
+    current.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 推送播放位置，播放完毕时推送 ended 事件并清除当前播放
+async fn report_progress(current: Current, id: String, interval: Option<Duration>, sender: Option<WsSender>) {
+    let mut last_report = tokio::time::Instant::now();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let (event, finished) = {
+            let guard = lock(&current);
+            let Some(playback) = guard.as_ref().filter(|playback| playback.id == id) else {
+                return;
+            };
+            if playback.output.finished() {
+                let event = ServerResponse::new(
+                    ModuleType::Playback,
+                    "ended",
+                    serde_json::json!({ "id": id, "duration_ms": playback.duration.map(millis) }),
+                );
+                (Some(event), true)
+            } else if interval.is_some_and(|interval| last_report.elapsed() >= interval) && !playback.output.is_paused() {
+                last_report = tokio::time::Instant::now();
+                (Some(ServerResponse::new(ModuleType::Playback, "position", playback.payload())), false)
+            } else {
+                (None, false)
+            }
+        };
+        if let (Some(event), Some(sender)) = (event, &sender) {
+            let _ = sender.lock().await.send(Message::Text(event.to_json().into())).await;
+        }
+        if finished {
+            log_info!("播放结束: id={}", id);
+            let mut guard = lock(&current);
+            if guard.as_ref().is_some_and(|playback| playback.id == id) {
+                // 当前任务即将结束，不需要再中止
+                if let Some(mut playback) = guard.take() {
+                    playback.task = None;
+                }
+            }
+            return;
+        }
+    }
+}
+
+// ============================================================================
+// 播放处理器
+// ============================================================================
+
+/// 音频播放模块处理器
+pub struct PlaybackHandler {
+    current: Current,
+    /// WebSocket 发送器 (请求没有来源连接时用于广播事件)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl PlaybackHandler {
+    /// 创建新的播放处理器
+    pub fn new() -> Self {
+        Self {
+            current: Arc::new(Mutex::new(None)),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    /// 处理 play 消息 - 开始播放 (替换正在播放的音频)
+    async fn handle_play(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: PlayRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| playback_error(ErrorCode::InvalidParams, format!("无效的 play 请求: {}", e)))?;
+        let input = request.input()?;
+        let volume = request.volume()?;
+        let interval = request.position_interval()?;
+        let start = Duration::from_millis(request.start_ms.unwrap_or(0));
+        let device = request.device.clone();
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+
+        let (output, duration) = tokio::task::spawn_blocking(move || {
+            let track = match input {
+                Input::File(path) => Track::open(&path)?,
+                Input::Bytes(bytes) => Track::from_bytes(bytes)?,
+            };
+            let duration = track.duration;
+            if duration.is_some_and(|duration| start > duration) {
+                return Err(PlaybackError::Seek(format!("开始位置超出音频时长: {}ms", millis(start))));
+            }
+            let output = backend::open().play(track, device.as_deref(), volume)?;
+            if !start.is_zero() {
+                output.seek(start)?;
+            }
+            Ok((output, duration))
+        })
+        .await
+        .map_err(|e| playback_error(ErrorCode::Internal, format!("播放任务失败: {}", e)))?
+        .map_err(|e| ModuleError::from_error(ModuleType::Playback, &e))?;
+
+        let id = request.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let task = tokio::spawn(report_progress(Arc::clone(&self.current), id.clone(), interval, sender));
+        let playback = Playback { id: id.clone(), output, duration, task: Some(task) };
+        let mut payload = playback.payload();
+        payload["device"] = request.device.into();
+        let previous = lock(&self.current).replace(playback);
+        if let Some(previous) = previous {
+            log_info!("替换正在播放的音频: id={}", previous.id);
+        }
+        log_info!("开始播放: id={}, duration_ms={:?}", id, duration.map(millis));
+        Ok(Some(ServerResponse::new(ModuleType::Playback, "playing", payload)))
+    }
+
+    /// 对当前播放执行操作 (指定 id 时必须与当前播放一致)
+    fn with_current<T>(
+        &self,
+        msg: &ModuleMessage,
+        f: impl FnOnce(&Playback) -> Result<T, ModuleError>,
+    ) -> Result<T, ModuleError> {
+        let id: Option<String> = msg.get_field("id");
+        let guard = lock(&self.current);
+        match guard.as_ref() {
+            Some(playback) if id.as_ref().is_none_or(|id| *id == playback.id) => f(playback),
+            Some(_) | None => Err(playback_error(
+                ErrorCode::NotFound,
+                format!("没有正在播放的音频: {}", id.as_deref().unwrap_or("-")),
+            )),
+        }
+    }
+
+    /// 处理 pause / resume 消息
+    fn handle_pause(&self, msg: &ModuleMessage, pause: bool) -> Result<Option<ServerResponse>, RouterError> {
+        let payload = self.with_current(msg, |playback| {
+            if pause {
+                playback.output.pause();
+            } else {
+                playback.output.resume();
+            }
+            Ok(playback.payload())
+        })?;
+        log_debug!("{}: id={}", if pause { "暂停播放" } else { "继续播放" }, payload["id"]);
+        Ok(Some(ServerResponse::new(ModuleType::Playback, if pause { "paused" } else { "resumed" }, payload)))
+    }
+
+    /// 处理 seek 消息 - 跳转到指定位置
+    fn handle_seek(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let position_ms: u64 = msg.get_field("position_ms")
+            .ok_or_else(|| playback_error(ErrorCode::InvalidParams, "缺少 position_ms 字段"))?;
+        let position = Duration::from_millis(position_ms);
+        let payload = self.with_current(msg, |playback| {
+            if playback.duration.is_some_and(|duration| position > duration) {
+                return Err(playback_error(ErrorCode::InvalidParams, format!("跳转位置超出音频时长: {}ms", position_ms)));
+            }
+            playback.output.seek(position).map_err(|e| ModuleError::from_error(ModuleType::Playback, &e))?;
+            Ok(playback.payload())
+        })?;
+        log_debug!("跳转: id={}, position_ms={}", payload["id"], position_ms);
+        Ok(Some(ServerResponse::new(ModuleType::Playback, "seeked", payload)))
+    }
+
+    /// 处理 stop 消息 - 停止播放
+    fn handle_stop(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let payload = self.with_current(msg, |playback| Ok(playback.payload()))?;
+        lock(&self.current).take();
+        log_info!("停止播放: id={}", payload["id"]);
+        Ok(Some(ServerResponse::new(
+            ModuleType::Playback,
+            "stopped",
+            serde_json::json!({ "id": payload["id"], "position_ms": payload["position_ms"] }),
+        )))
+    }
+
+    /// 处理 status 消息 - 当前播放状态
+    fn handle_status(&self) -> ServerResponse {
+        let payload = lock(&self.current)
+            .as_ref()
+            .map(Playback::payload)
+            .unwrap_or_else(|| serde_json::json!({ "state": "idle" }));
+        ServerResponse::new(ModuleType::Playback, "status", payload)
+    }
+
+    /// 处理 list_devices 消息 - 输出设备列表
+    async fn handle_list_devices(&self) -> Result<Option<ServerResponse>, RouterError> {
+        let devices = tokio::task::spawn_blocking(|| backend::open().devices())
+            .await
+            .map_err(|e| playback_error(ErrorCode::Internal, format!("播放任务失败: {}", e)))?
+            .map_err(|e| ModuleError::from_error(ModuleType::Playback, &e))?;
+        Ok(Some(ServerResponse::new(ModuleType::Playback, "devices", serde_json::json!({ "devices": devices }))))
+    }
+}
+
+impl Default for PlaybackHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 播放模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("play", &[
+        FieldSpec::optional("id", FieldKind::String),
+        FieldSpec::optional("path", FieldKind::String),
+        FieldSpec::optional("data", FieldKind::String),
+        FieldSpec::optional("format", FieldKind::String).one_of(&["wav", "pcm_s16le"]),
+        FieldSpec::optional("sample_rate", FieldKind::Integer),
+        FieldSpec::optional("channels", FieldKind::Integer),
+        FieldSpec::optional("device", FieldKind::String),
+        FieldSpec::optional("volume", FieldKind::Number),
+        FieldSpec::optional("start_ms", FieldKind::Integer),
+        FieldSpec::optional("position_interval_ms", FieldKind::Integer),
+    ]),
+    MessageSpec::new("pause", &[FieldSpec::optional("id", FieldKind::String)]),
+    MessageSpec::new("resume", &[FieldSpec::optional("id", FieldKind::String)]),
+    MessageSpec::new("seek", &[
+        FieldSpec::optional("id", FieldKind::String),
+        FieldSpec::required("position_ms", FieldKind::Integer),
+    ]),
+    MessageSpec::new("stop", &[FieldSpec::optional("id", FieldKind::String)]),
+    MessageSpec::new("status", &[]),
+    MessageSpec::new("list_devices", &[]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 音频播放模块
+    pub const Playback: ModuleType = ModuleType::named("playback");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for PlaybackHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Playback
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    /// 停止播放 (连接关闭时调用)
+    async fn cleanup(&self) {
+        if let Some(playback) = lock(&self.current).take() {
+            log_info!("连接已关闭，停止播放: id={}", playback.id);
+        }
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理播放消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "play" => self.handle_play(msg).await,
+            "pause" => self.handle_pause(msg, true),
+            "resume" => self.handle_pause(msg, false),
+            "seek" => self.handle_seek(msg),
+            "stop" => self.handle_stop(msg),
+            "status" => Ok(Some(self.handle_status())),
+            "list_devices" => self.handle_list_devices().await,
+            _ => Err(playback_error(ErrorCode::UnknownMessageType, format!("未知的播放消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(value: serde_json::Value) -> PlayRequest {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_pcm_input() {
+        // 2 个声道各 2 个采样
+        let pcm: Vec<u8> = [1i16, -1, 300, -300].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let data = general_purpose::STANDARD.encode(&pcm);
+        let input = request(serde_json::json!({ "data": data, "format": "pcm_s16le", "sample_rate": 8000, "channels": 2 }))
+            .input()
+            .unwrap();
+        let Input::Bytes(wav) = input else { panic!("expected in-memory audio") };
+        let track = Track::from_bytes(wav).unwrap();
+        assert_eq!(track.duration, Some(Duration::from_micros(250)));
+
+        let odd = request(serde_json::json!({ "data": data, "format": "pcm_s16le", "channels": 3 }));
+        assert_eq!(odd.input().err().unwrap().code, ErrorCode::InvalidParams);
+        let both = request(serde_json::json!({ "data": data, "path": "/tmp/a.wav" }));
+        assert_eq!(both.input().err().unwrap().code, ErrorCode::InvalidParams);
+        let undecodable = request(serde_json::json!({ "data": general_purpose::STANDARD.encode(b"not audio") }));
+        let Input::Bytes(bytes) = undecodable.input().unwrap() else { panic!("expected in-memory audio") };
+        assert!(matches!(Track::from_bytes(bytes), Err(PlaybackError::Decode(_))));
+    }
+
+    #[test]
+    fn test_pcm_stream() {
+        let (track, stream) = Track::stream(1000, 1);
+        assert!(track.duration.is_none());
+        let mut source = track.into_source();
+        // 在采样中间切开的写入
+        stream.write(&[0x00, 0x40, 0x00]);
+        stream.write(&[0xc0]);
+        assert_eq!(stream.written(), Duration::from_millis(2));
+        assert_eq!(source.next(), Some(0.5));
+        assert_eq!(source.next(), Some(-0.5));
+        // 数据不足时输出静音，写入结束并播放完剩余数据后结束
+        assert_eq!(source.next(), Some(0.0));
+        stream.write(&[0x00, 0x20]);
+        stream.finish();
+        assert_eq!(source.next(), Some(0.25));
+        assert_eq!(source.next(), None);
+    }
+
+    #[test]
+    fn test_position_interval() {
+        assert_eq!(request(serde_json::json!({})).position_interval().unwrap(), Some(Duration::from_secs(1)));
+        assert_eq!(request(serde_json::json!({ "position_interval_ms": 0 })).position_interval().unwrap(), None);
+        assert!(request(serde_json::json!({ "position_interval_ms": 50 })).position_interval().is_err());
+        assert!(request(serde_json::json!({ "volume": 1.5 })).volume().is_err());
+    }
+}
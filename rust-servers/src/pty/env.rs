@@ -0,0 +1,229 @@
+// Shell 环境快照
+// get_env 在提示符处执行 Shell Integration 定义的 __sw_env 函数，shell 把当前的环境变量 (base64 编码)
+// 连同本次请求的随机 nonce 包在私有 OSC 序列中输出。序列在发送给客户端和记入回滚缓冲之前从输出中去掉，
+// 环境变量不会显示或随状态快照保存；nonce 不符的序列 (如 cat 一个包含该序列的文件) 被丢弃。
+// 名称像密钥的变量只报告名称，用于排查“在系统终端中可以运行、在 Obsidian 中不行”之类的环境差异
+
+use base64::{Engine as _, engine::general_purpose};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tokio::sync::oneshot;
+
+/// 环境变量序列的前缀 (`ESC ] 7777 ; env ; nonce ; base64 BEL`)
+const ENV_OSC_PREFIX: &[u8] = b"\x1b]7777;env;";
+
+/// 序列结束符 (BEL)
+const ENV_OSC_END: u8 = 0x07;
+
+/// 环境变量序列的最大长度，超出时丢弃 (防止没有结束符的序列无限增长)
+const MAX_PAYLOAD_BYTES: usize = 1024 * 1024;
+
+/// 名称中含有这些片段 (不区分大小写) 的变量视为密钥，值以 `REDACTED` 代替
+const SECRET_MARKERS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASS", "CREDENTIAL", "AUTH", "COOKIE", "PRIVATE"];
+
+/// 密钥变量报告的值
+const REDACTED: &str = "<redacted>";
+
+/// 在 shell 中执行的命令 (空格前缀不进入历史记录)，shell 把 `nonce` 原样写入应答序列
+pub fn env_command(nonce: &str) -> String {
+    format!(" __sw_env {}\n", nonce)
+}
+
+/// 环境变量快照
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EnvSnapshot {
+    /// 全部环境变量 (按名称排序，密钥变量的值以 `<redacted>` 代替)
+    pub env: BTreeMap<String, String>,
+    /// PATH 中的目录 (按顺序)
+    pub path: Vec<String>,
+}
+
+impl EnvSnapshot {
+    /// 解析 `env` 命令的输出 (值中的换行使后续行不以 `名称=` 开头，这些行并入上一个变量)
+    fn parse(output: &str) -> Self {
+        let mut env = BTreeMap::new();
+        let mut last: Option<String> = None;
+        for line in output.lines() {
+            match line.split_once('=').filter(|(name, _)| is_name(name)) {
+                Some((name, value)) => {
+                    env.insert(name.to_string(), value.to_string());
+                    last = Some(name.to_string());
+                }
+                None => {
+                    if let Some(value) = last.as_ref().and_then(|name| env.get_mut(name)) {
+                        value.push('\n');
+                        value.push_str(line);
+                    }
+                }
+            }
+        }
+        let path = env.get("PATH")
+            .map(|path| path.split(':').filter(|dir| !dir.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default();
+        for (name, value) in env.iter_mut() {
+            if is_secret(name) {
+                *value = REDACTED.to_string();
+            }
+        }
+        Self { env, path }
+    }
+}
+
+/// 名称是否像密钥 (API Key、令牌、密码等)
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+/// 环境变量名 (字母或下划线开头，只含字母、数字和下划线)
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// 从 PTY 输出中取出环境变量序列并交给等待者
+#[derive(Default)]
+pub struct EnvCapture {
+    /// 上一块输出末尾可能是序列前缀的部分，等下一块输出再决定
+    held: Vec<u8>,
+    /// 正在收集的序列内容
+    payload: Option<Vec<u8>>,
+    /// 等待应答的请求 (nonce, 接收方)
+    waiters: Vec<(String, oneshot::Sender<EnvSnapshot>)>,
+}
+
+impl EnvCapture {
+    /// 为一次查询生成 nonce 并等待带有该 nonce 的环境变量序列
+    pub fn wait(&mut self) -> (String, oneshot::Receiver<EnvSnapshot>) {
+        // 已超时放弃的查询不再等待
+        self.waiters.retain(|(_, sender)| !sender.is_closed());
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        let (sender, receiver) = oneshot::channel();
+        self.waiters.push((nonce.clone(), sender));
+        (nonce, receiver)
+    }
+
+    /// 返回去掉环境变量序列后的输出
+    pub fn filter(&mut self, output: &[u8]) -> Vec<u8> {
+        let mut data = std::mem::take(&mut self.held);
+        data.extend_from_slice(output);
+        let mut visible = Vec::with_capacity(data.len());
+        let mut rest = &data[..];
+        loop {
+            if let Some(mut payload) = self.payload.take() {
+                let Some(end) = rest.iter().position(|&b| b == ENV_OSC_END) else {
+                    payload.extend_from_slice(rest);
+                    if payload.len() <= MAX_PAYLOAD_BYTES {
+                        self.payload = Some(payload);
+                    }
+                    return visible;
+                };
+                payload.extend_from_slice(&rest[..end]);
+                self.finish(&payload);
+                rest = &rest[end + 1..];
+            } else if let Some(start) = rest.windows(ENV_OSC_PREFIX.len()).position(|window| window == ENV_OSC_PREFIX) {
+                visible.extend_from_slice(&rest[..start]);
+                self.payload = Some(Vec::new());
+                rest = &rest[start + ENV_OSC_PREFIX.len()..];
+            } else {
+                let keep = partial_prefix_len(rest);
+                visible.extend_from_slice(&rest[..rest.len() - keep]);
+                self.held = rest[rest.len() - keep..].to_vec();
+                return visible;
+            }
+        }
+    }
+
+    /// 序列内容为 `nonce;base64`，只交给发出该 nonce 的查询
+    fn finish(&mut self, payload: &[u8]) {
+        let Some(split) = payload.iter().position(|&b| b == b';') else {
+            return;
+        };
+        let (nonce, data) = (&payload[..split], &payload[split + 1..]);
+        let Some(index) = self.waiters.iter().position(|(expected, _)| expected.as_bytes() == nonce) else {
+            return;
+        };
+        let (_, waiter) = self.waiters.remove(index);
+        let decoded = general_purpose::STANDARD.decode(data).unwrap_or_default();
+        let _ = waiter.send(EnvSnapshot::parse(&String::from_utf8_lossy(&decoded)));
+    }
+}
+
+/// 输出末尾与序列前缀开头相同的字节数
+fn partial_prefix_len(output: &[u8]) -> usize {
+    (1..ENV_OSC_PREFIX.len().min(output.len() + 1))
+        .rev()
+        .find(|&len| output.ends_with(&ENV_OSC_PREFIX[..len]))
+        .unwrap_or(0)
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(nonce: &str, env: &str) -> Vec<u8> {
+        let mut bytes = ENV_OSC_PREFIX.to_vec();
+        bytes.extend_from_slice(nonce.as_bytes());
+        bytes.push(b';');
+        bytes.extend_from_slice(general_purpose::STANDARD.encode(env).as_bytes());
+        bytes.push(ENV_OSC_END);
+        bytes
+    }
+
+    #[test]
+    fn test_parse() {
+        let snapshot = EnvSnapshot::parse("HOME=/home/me\nPATH=/usr/local/bin:/usr/bin::/bin\nEMPTY=\n");
+        assert_eq!(snapshot.env["HOME"], "/home/me");
+        assert_eq!(snapshot.env["EMPTY"], "");
+        assert_eq!(snapshot.path, vec!["/usr/local/bin", "/usr/bin", "/bin"]);
+
+        // 多行的值
+        let snapshot = EnvSnapshot::parse("A=1\n  continued=2\n=x\nB=3\n");
+        assert_eq!(snapshot.env["A"], "1\n  continued=2\n=x");
+        assert_eq!(snapshot.env["B"], "3");
+        assert_eq!(snapshot.env.len(), 2);
+
+        // 密钥变量只报告名称
+        let snapshot = EnvSnapshot::parse("OPENAI_API_KEY=sk-1\nGITHUB_TOKEN=ghp\nPGPASSWORD=pw\nEDITOR=vim\n");
+        assert_eq!(snapshot.env["OPENAI_API_KEY"], REDACTED);
+        assert_eq!(snapshot.env["GITHUB_TOKEN"], REDACTED);
+        assert_eq!(snapshot.env["PGPASSWORD"], REDACTED);
+        assert_eq!(snapshot.env["EDITOR"], "vim");
+    }
+
+    #[tokio::test]
+    async fn test_filter() {
+        let mut capture = EnvCapture::default();
+        let (nonce, mut receiver) = capture.wait();
+
+        // nonce 不符的序列 (如输出中伪造的应答) 被去掉但不作为应答
+        assert_eq!(capture.filter(&sequence("forged", "EDITOR=evil\n")), b"");
+        assert!(receiver.try_recv().is_err());
+
+        // 序列跨越多块输出，前后的输出照常显示
+        let mut output = b" __sw_env\r\n".to_vec();
+        output.extend(sequence(&nonce, "PATH=/bin:/usr/bin\nEDITOR=vim\n"));
+        output.extend_from_slice(b"\x1b[1A\x1b[2K$ ");
+        let mut visible = Vec::new();
+        for chunk in output.chunks(5) {
+            visible.extend(capture.filter(chunk));
+        }
+        assert_eq!(visible, b" __sw_env\r\n\x1b[1A\x1b[2K$ ");
+
+        let snapshot = receiver.await.unwrap();
+        assert_eq!(snapshot.env["EDITOR"], "vim");
+        assert_eq!(snapshot.path, vec!["/bin", "/usr/bin"]);
+
+        // 没有等待者时同样去掉序列；其他 OSC 序列不受影响
+        let mut output = b"\x1b]7;file://host/tmp\x07".to_vec();
+        output.extend(sequence(&nonce, "A=1"));
+        assert_eq!(capture.filter(&output), b"\x1b]7;file://host/tmp\x07");
+        assert_eq!(capture.filter(b"\x1b]"), b"");
+        assert_eq!(capture.filter(b"0;title\x07"), b"\x1b]0;title\x07");
+    }
+}
@@ -1,17 +1,31 @@
 // PTY 模块
-// 提供终端会话管理功能
+// 提供终端会话管理功能；会话的启动参数、工作目录和最近的输出随状态快照保存，重启后重新启动 shell 恢复
 
+mod env;
+mod screen;
+mod scrollback;
 mod session;
 mod shell;
 
+pub use screen::Screen;
+use env::EnvCapture;
+pub use scrollback::{Scrollback, SCROLLBACK_CAPACITY};
 pub use session::{PtySession, PtyReader, PtyWriter};
 pub use shell::{get_shell_by_type, get_shell_integration_script, get_default_shell};
 
-use crate::router::{ModuleHandler, ModuleMessage, ModuleType, RouterError, ServerResponse};
+use crate::audit::{self, AuditAction};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
 use crate::server::WsSender;
+use crate::watchdog::{self, TaskKind, WatchedTask};
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use tokio::sync::Mutex as TokioMutex;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::{Mutex as TokioMutex, Notify};
 use tokio_tungstenite::tungstenite::Message;
 use futures_util::SinkExt;
 use uuid::Uuid;
@@ -19,28 +33,62 @@ use uuid::Uuid;
 /// 日志宏
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] [PTY] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "PTY", format!($($arg)*));
     };
 }
 
 macro_rules! log_error {
     ($($arg:tt)*) => {
-        eprintln!("[ERROR] [PTY] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Error, "PTY", format!($($arg)*));
     };
 }
 
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] [PTY] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "PTY", format!($($arg)*));
         }
     };
 }
 
+/// get_env 等待 shell 输出环境变量的时间
+const GET_ENV_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 创建 PTY 模块错误
+fn pty_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Pty, code, message)
+}
+
 // ============================================================================
 // PTY 会话上下文
 // ============================================================================
 
+/// 会话的启动参数和当前尺寸
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionInfo {
+    shell_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shell_args: Option<Vec<String>>,
+    cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    env: Option<HashMap<String, String>>,
+    cols: u16,
+    rows: u16,
+    /// 输出停止多久后发送 output_idle 事件 (毫秒)，未设置时不发送
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    idle_notify_ms: Option<u64>,
+}
+
+/// 状态快照中保存的会话
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedSession {
+    session_id: String,
+    #[serde(flatten)]
+    info: SessionInfo,
+    /// 回滚缓冲 (base64)
+    scrollback: String,
+}
+
 /// 单个 PTY 会话的上下文
 ///
 /// 包含一个 PTY 会话所需的所有资源
@@ -51,19 +99,36 @@ struct PtySessionContext {
     writer: Arc<Mutex<PtyWriter>>,
     /// 读取任务句柄
     read_task: Option<tokio::task::JoinHandle<()>>,
+    /// 启动参数和当前尺寸
+    info: SessionInfo,
+    /// 最近的输出和工作目录
+    scrollback: Arc<Mutex<Scrollback>>,
+    /// 当前屏幕内容
+    screen: Arc<Mutex<Screen>>,
+    /// get_env 的环境变量序列
+    env_capture: Arc<Mutex<EnvCapture>>,
+    /// shell 进程是否仍在运行
+    running: Box<dyn Fn() -> bool + Send + Sync>,
+    /// 是否由状态快照恢复
+    restored: bool,
+    /// 创建会话的连接声明的 vault (会话列表和状态快照按 vault 分开)
+    vault: Option<String>,
 }
 
 impl PtySessionContext {
-    /// 创建新的会话上下文
-    fn new(
-        session: Arc<TokioMutex<PtySession>>,
-        writer: Arc<Mutex<PtyWriter>>,
-    ) -> Self {
-        Self {
-            session,
-            writer,
-            read_task: None,
-        }
+    /// 当前工作目录 (Shell Integration 报告的目录，未报告时为启动时的目录)
+    fn cwd(&self) -> Option<String> {
+        let scrollback = self.scrollback.lock().unwrap_or_else(|e| e.into_inner());
+        scrollback.cwd().map(str::to_string).or_else(|| self.info.cwd.clone())
+    }
+
+    /// 写入终端输入并记入回滚缓冲
+    fn write(&self, data: &[u8]) -> Result<(), RouterError> {
+        let mut w = self.writer.lock().unwrap();
+        w.write(data)
+            .map_err(|e| pty_error(ErrorCode::IoError, format!("写入 PTY 失败: {}", e)))?;
+        self.scrollback.lock().unwrap_or_else(|e| e.into_inner()).input(data);
+        Ok(())
     }
 }
 
@@ -71,12 +136,33 @@ impl PtySessionContext {
 // PTY 处理器
 // ============================================================================
 
+/// 会话表: session_id → PtySessionContext
+type Sessions = TokioMutex<HashMap<String, PtySessionContext>>;
+
+/// 所有 PTY 处理器的会话表 (模块崩溃重建后旧的表随处理器释放)，供其他模块按 session_id 写入会话
+static SESSION_TABLES: Mutex<Vec<Weak<Sessions>>> = Mutex::new(Vec::new());
+
+/// 向指定会话写入数据 (作为终端输入)，供其他模块使用 (如 LLM 的 pty 输出目标)
+pub async fn write_session(session_id: &str, data: &[u8]) -> Result<(), RouterError> {
+    let tables: Vec<Arc<Sessions>> = {
+        let mut tables = SESSION_TABLES.lock().unwrap_or_else(|e| e.into_inner());
+        tables.retain(|table| table.strong_count() > 0);
+        tables.iter().filter_map(Weak::upgrade).collect()
+    };
+    for table in tables {
+        if let Some(context) = table.lock().await.get(session_id) {
+            return context.write(data);
+        }
+    }
+    Err(pty_error(ErrorCode::SessionNotFound, format!("PTY 会话不存在: {}", session_id)).into())
+}
+
 /// PTY 模块处理器
 /// 
 /// 管理多个 PTY 会话的生命周期，处理终端相关的消息
 pub struct PtyHandler {
-    /// 会话管理器: session_id → PtySessionContext
-    sessions: TokioMutex<HashMap<String, PtySessionContext>>,
+    /// 会话管理器: session_id → PtySessionContext (读取任务停滞时由任务自身移除)
+    sessions: Arc<Sessions>,
     /// WebSocket 发送器 (用于发送 PTY 输出)
     ws_sender: TokioMutex<Option<WsSender>>,
 }
@@ -84,60 +170,22 @@ pub struct PtyHandler {
 impl PtyHandler {
     /// 创建新的 PTY 处理器
     pub fn new() -> Self {
+        let sessions = Arc::new(TokioMutex::new(HashMap::new()));
+        SESSION_TABLES.lock().unwrap_or_else(|e| e.into_inner()).push(Arc::downgrade(&sessions));
         Self {
-            sessions: TokioMutex::new(HashMap::new()),
+            sessions,
             ws_sender: TokioMutex::new(None),
         }
     }
     
-    /// 设置 WebSocket 发送器
-    pub async fn set_ws_sender(&self, sender: WsSender) {
-        let mut ws_sender = self.ws_sender.lock().await;
-        *ws_sender = Some(sender);
-    }
-    
     /// 处理 init 消息 - 创建 PTY 会话
-    async fn handle_init(
-        &self,
-        shell_type: Option<String>,
-        shell_args: Option<Vec<String>>,
-        cwd: Option<String>,
-        env: Option<HashMap<String, String>>,
-    ) -> Result<Option<ServerResponse>, RouterError> {
+    async fn handle_init(&self, info: SessionInfo, client: Option<WsSender>) -> Result<Option<ServerResponse>, RouterError> {
         // 生成唯一的 session_id
         let session_id = Uuid::new_v4().to_string();
         
-        log_info!("初始化 PTY 会话: session_id={}, shell_type={:?}, cwd={:?}", session_id, shell_type, cwd);
-        
-        // 创建 PTY 会话
-        let (pty_session, pty_reader, pty_writer) = PtySession::new(
-            80,
-            24,
-            shell_type.as_deref(),
-            shell_args.as_ref().map(|v| v.as_slice()),
-            cwd.as_deref(),
-            env.as_ref(),
-        ).map_err(|e| RouterError::ModuleError(format!("创建 PTY 会话失败: {}", e)))?;
-        
-        // 创建会话上下文
-        let pty_session = Arc::new(TokioMutex::new(pty_session));
-        let pty_reader = Arc::new(Mutex::new(pty_reader));
-        let pty_writer = Arc::new(Mutex::new(pty_writer));
-
-        let mut context = PtySessionContext::new(
-            Arc::clone(&pty_session),
-            Arc::clone(&pty_writer),
-        );
-        
-        // 启动 PTY 输出读取任务
-        let read_task = self.start_read_task(session_id.clone(), pty_reader, pty_writer, shell_type).await?;
-        context.read_task = Some(read_task);
+        log_info!("初始化 PTY 会话: session_id={}, shell_type={:?}, cwd={:?}", session_id, info.shell_type, info.cwd);
         
-        // 存储会话上下文
-        {
-            let mut sessions = self.sessions.lock().await;
-            sessions.insert(session_id.clone(), context);
-        }
+        self.spawn_session(session_id.clone(), info, Scrollback::new(SCROLLBACK_CAPACITY), false, client).await?;
         
         log_info!("PTY 会话创建成功: session_id={}", session_id);
         
@@ -152,103 +200,252 @@ impl PtyHandler {
         )))
     }
     
+    /// 启动 shell 进程和输出读取任务，并登记会话
+    async fn spawn_session(
+        &self,
+        session_id: String,
+        info: SessionInfo,
+        scrollback: Scrollback,
+        restored: bool,
+        client: Option<WsSender>,
+    ) -> Result<(), RouterError> {
+        // 创建 PTY 会话
+        let (pty_session, pty_reader, pty_writer) = PtySession::new(
+            info.cols,
+            info.rows,
+            info.shell_type.as_deref(),
+            info.shell_args.as_deref(),
+            info.cwd.as_deref(),
+            info.env.as_ref(),
+        ).map_err(|e| pty_error(ErrorCode::Internal, format!("创建 PTY 会话失败: {}", e)))?;
+        
+        let program = get_shell_by_type(info.shell_type.as_deref())
+            .get_argv()
+            .first()
+            .map(|program| program.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        audit::record(AuditAction::PtyExec, ModuleType::Pty, program, None, serde_json::json!({
+            "session_id": session_id,
+            "shell_type": info.shell_type,
+            "shell_args": info.shell_args,
+            "cwd": info.cwd,
+            "restored": restored,
+        }));
+        
+        let running = pty_session.running_probe();
+        let probe = pty_session.running_probe();
+        let pty_session = Arc::new(TokioMutex::new(pty_session));
+        let pty_reader = Arc::new(Mutex::new(pty_reader));
+        let pty_writer = Arc::new(Mutex::new(pty_writer));
+        // 恢复的会话用回滚缓冲重建屏幕
+        let mut screen = Screen::new(info.cols, info.rows);
+        screen.push(&scrollback.contents());
+        let scrollback = Arc::new(Mutex::new(scrollback));
+        let screen = Arc::new(Mutex::new(screen));
+        let env_capture = Arc::new(Mutex::new(EnvCapture::default()));
+        
+        // 启动 PTY 输出读取任务
+        let read_task = self.start_read_task(
+            session_id.clone(),
+            pty_reader,
+            Arc::clone(&pty_writer),
+            info.shell_type.clone(),
+            info.idle_notify_ms.filter(|&ms| ms > 0).map(Duration::from_millis),
+            running,
+            Arc::clone(&scrollback),
+            Arc::clone(&screen),
+            Arc::clone(&env_capture),
+            client,
+        ).await?;
+        
+        // 存储会话上下文
+        let context = PtySessionContext {
+            session: pty_session,
+            writer: pty_writer,
+            read_task: Some(read_task),
+            info,
+            scrollback,
+            screen,
+            env_capture,
+            running: Box::new(probe),
+            restored,
+            vault: crate::storage::current_vault(),
+        };
+        self.sessions.lock().await.insert(session_id, context);
+        Ok(())
+    }
+    
     /// 启动 PTY 输出读取任务
     /// 
+    /// 输出去掉 get_env 的环境变量序列后记入回滚缓冲和屏幕，并发送到创建会话的连接 (`client`)，未知时使用默认发送器。
+    /// 设置了 `idle_notify` 时，输出停止该时长后发送一次 output_idle 事件，再次有输出后重新计时。
+    /// shell 进程 (`running`) 退出后迟迟读不到 EOF 时由看门狗中止，并按进程退出处理。
     /// 返回任务句柄，由调用者负责存储
+    #[allow(clippy::too_many_arguments)]
     async fn start_read_task(
         &self,
         session_id: String,
         reader: Arc<Mutex<PtyReader>>,
         writer: Arc<Mutex<PtyWriter>>,
         shell_type: Option<String>,
+        idle_notify: Option<Duration>,
+        running: impl Fn() -> bool + Send + Sync + 'static,
+        scrollback: Arc<Mutex<Scrollback>>,
+        screen: Arc<Mutex<Screen>>,
+        env_capture: Arc<Mutex<EnvCapture>>,
+        client: Option<WsSender>,
     ) -> Result<tokio::task::JoinHandle<()>, RouterError> {
-        let ws_sender = {
-            let ws_sender_guard = self.ws_sender.lock().await;
-            ws_sender_guard.clone()
+        let ws_sender = match client {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
         };
         
-        let ws_sender = ws_sender.ok_or_else(|| RouterError::ModuleError("WebSocket sender not set".to_string()))?;
+        let ws_sender = ws_sender.ok_or_else(|| pty_error(ErrorCode::NotConnected, "WebSocket sender not set"))?;
         
         // 启动读取任务
+        let sessions = Arc::clone(&self.sessions);
         let task = tokio::spawn(async move {
+            let heartbeat = watchdog::watch(
+                WatchedTask::new(TaskKind::PtyRead)
+                    .with_session_id(session_id.clone())
+                    .with_idle_probe(running),
+            );
             let mut first_output = true;
+            let output = Notify::new();
             
-            loop {
-                // 在阻塞任务中读取 PTY 输出
-                let reader_clone = Arc::clone(&reader);
-                let result = tokio::task::spawn_blocking(move || -> Result<(Vec<u8>, usize), String> {
-                    let mut reader = reader_clone.lock().unwrap();
-                    let mut local_buf = vec![0u8; 8192];
-                    match reader.read(&mut local_buf) {
-                        Ok(n) => Ok((local_buf, n)),
-                        Err(e) => Err(e.to_string()),
-                    }
-                }).await;
-                
-                match result {
-                    Ok(Ok((data, n))) if n > 0 => {
-                        log_debug!("读取 PTY 输出: session_id={}, {} 字节", session_id, n);
-                        
-                        // 构建带 session_id 前缀的二进制帧
-                        // 格式: [session_id_length: u8][session_id: bytes][data: bytes]
-                        let session_id_bytes = session_id.as_bytes();
-                        let session_id_len = session_id_bytes.len() as u8;
-                        
-                        let mut frame = Vec::with_capacity(1 + session_id_bytes.len() + n);
-                        frame.push(session_id_len);
-                        frame.extend_from_slice(session_id_bytes);
-                        frame.extend_from_slice(&data[..n]);
-                        
-                        let mut sender = ws_sender.lock().await;
-                        if let Err(e) = sender.send(Message::Binary(frame.into())).await {
-                            log_error!("发送 PTY 输出失败: session_id={}, {}", session_id, e);
-                            break;
+            let read_loop = async {
+                loop {
+                    // 在阻塞任务中读取 PTY 输出
+                    let reader_clone = Arc::clone(&reader);
+                    let result = tokio::task::spawn_blocking(move || -> Result<(Vec<u8>, usize), String> {
+                        let mut reader = reader_clone.lock().unwrap();
+                        let mut local_buf = vec![0u8; 8192];
+                        match reader.read(&mut local_buf) {
+                            Ok(n) => Ok((local_buf, n)),
+                            Err(e) => Err(e.to_string()),
                         }
-                        drop(sender);
-                        
-                        // 首次输出后注入 Shell Integration 脚本
-                        if first_output {
-                            first_output = false;
-                            if let Some(ref st) = shell_type {
-                                if let Some(script) = get_shell_integration_script(st) {
-                                    let mut w = writer.lock().unwrap();
-                                    if let Err(e) = w.write(script.as_bytes()) {
-                                        log_error!("发送 Shell Integration 脚本失败: session_id={}, {}", session_id, e);
-                                    } else {
-                                        log_debug!("Shell Integration 脚本已发送: session_id={}", session_id);
+                    }).await;
+                    
+                    match result {
+                        Ok(Ok((data, n))) if n > 0 => {
+                            heartbeat.beat();
+                            output.notify_one();
+                            log_debug!("读取 PTY 输出: session_id={}, {} 字节", session_id, n);
+                            let visible = env_capture.lock().unwrap_or_else(|e| e.into_inner()).filter(&data[..n]);
+                            if !visible.is_empty() {
+                                scrollback.lock().unwrap_or_else(|e| e.into_inner()).push(&visible);
+                                screen.lock().unwrap_or_else(|e| e.into_inner()).push(&visible);
+                                
+                                // 构建带 session_id 前缀的二进制帧
+                                // 格式: [session_id_length: u8][session_id: bytes][data: bytes]
+                                let session_id_bytes = session_id.as_bytes();
+                                let session_id_len = session_id_bytes.len() as u8;
+                                
+                                let mut frame = Vec::with_capacity(1 + session_id_bytes.len() + visible.len());
+                                frame.push(session_id_len);
+                                frame.extend_from_slice(session_id_bytes);
+                                frame.extend_from_slice(&visible);
+                                
+                                let mut sender = ws_sender.lock().await;
+                                if let Err(e) = sender.send(Message::Binary(frame.into())).await {
+                                    log_error!("发送 PTY 输出失败: session_id={}, {}", session_id, e);
+                                    break;
+                                }
+                                drop(sender);
+                            }
+                            
+                            // 首次输出后注入 Shell Integration 脚本
+                            if first_output {
+                                first_output = false;
+                                if let Some(ref st) = shell_type {
+                                    if let Some(script) = get_shell_integration_script(st) {
+                                        let mut w = writer.lock().unwrap();
+                                        if let Err(e) = w.write(script.as_bytes()) {
+                                            log_error!("发送 Shell Integration 脚本失败: session_id={}, {}", session_id, e);
+                                        } else {
+                                            log_debug!("Shell Integration 脚本已发送: session_id={}", session_id);
+                                        }
                                     }
                                 }
                             }
                         }
-                    }
-                    Ok(Ok(_)) => {
-                        // EOF - 进程退出
-                        log_info!("PTY 输出结束: session_id={}", session_id);
-                        
-                        // 发送 exit 事件
-                        let exit_response = ServerResponse::new(
-                            ModuleType::Pty,
-                            "exit",
-                            serde_json::json!({
-                                "session_id": session_id,
-                                "code": 0
-                            }),
-                        );
-                        let mut sender = ws_sender.lock().await;
-                        if let Err(e) = sender.send(Message::Text(exit_response.to_json().into())).await {
-                            log_error!("发送 exit 事件失败: session_id={}, {}", session_id, e);
+                        Ok(Ok(_)) => {
+                            // EOF - 进程退出
+                            log_info!("PTY 输出结束: session_id={}", session_id);
+                            
+                            // 发送 exit 事件
+                            let exit_response = ServerResponse::new(
+                                ModuleType::Pty,
+                                "exit",
+                                serde_json::json!({
+                                    "session_id": session_id,
+                                    "code": 0
+                                }),
+                            );
+                            let mut sender = ws_sender.lock().await;
+                            if let Err(e) = sender.send(Message::Text(exit_response.to_json().into())).await {
+                                log_error!("发送 exit 事件失败: session_id={}, {}", session_id, e);
+                            }
+                            break;
+                        }
+                        Ok(Err(e)) => {
+                            log_error!("PTY 输出读取错误: session_id={}, {}", session_id, e);
+                            break;
+                        }
+                        Err(e) => {
+                            log_error!("PTY 读取任务错误: session_id={}, {}", session_id, e);
+                            break;
                         }
-                        break;
-                    }
-                    Ok(Err(e)) => {
-                        log_error!("PTY 输出读取错误: session_id={}, {}", session_id, e);
-                        break;
                     }
-                    Err(e) => {
-                        log_error!("PTY 读取任务错误: session_id={}, {}", session_id, e);
-                        break;
+                }
+            };
+            
+            // 不会结束，随读取任务一起停止
+            let idle_watch = async {
+                let Some(idle) = idle_notify else {
+                    return std::future::pending::<()>().await;
+                };
+                loop {
+                    output.notified().await;
+                    while tokio::time::timeout(idle, output.notified()).await.is_ok() {}
+                    log_debug!("PTY 输出空闲: session_id={}, {}ms", session_id, idle.as_millis());
+                    let idle_response = ServerResponse::new(
+                        ModuleType::Pty,
+                        "output_idle",
+                        serde_json::json!({
+                            "session_id": session_id,
+                            "idle_ms": idle.as_millis() as u64,
+                        }),
+                    );
+                    let mut sender = ws_sender.lock().await;
+                    if let Err(e) = sender.send(Message::Text(idle_response.to_json().into())).await {
+                        log_error!("发送 output_idle 事件失败: session_id={}, {}", session_id, e);
                     }
                 }
+            };
+            
+            tokio::select! {
+                _ = read_loop => {}
+                _ = idle_watch => {}
+                _ = heartbeat.stalled() => {
+                    // 阻塞的读取线程无法中断，放弃等待并按进程退出处理
+                    log_error!("PTY 进程已退出但输出未结束，关闭会话: session_id={}", session_id);
+                    let exit_response = ServerResponse::new(
+                        ModuleType::Pty,
+                        "exit",
+                        serde_json::json!({
+                            "session_id": session_id,
+                            "code": 0
+                        }),
+                    );
+                    let _ = ws_sender.lock().await.send(Message::Text(exit_response.to_json().into())).await;
+                    
+                    // destroy / cleanup 可能正持有会话表并等待本任务结束，在单独的任务中移除会话
+                    let session_id = session_id.clone();
+                    tokio::spawn(async move {
+                        sessions.lock().await.remove(&session_id);
+                    });
+                }
             }
         });
         
@@ -259,13 +456,17 @@ impl PtyHandler {
     async fn handle_resize(&self, session_id: &str, cols: u16, rows: u16) -> Result<Option<ServerResponse>, RouterError> {
         log_info!("调整终端尺寸: session_id={}, {}x{}", session_id, cols, rows);
         
-        let sessions = self.sessions.lock().await;
-        let context = sessions.get(session_id)
-            .ok_or_else(|| RouterError::ModuleError(format!("SESSION_NOT_FOUND: {}", session_id)))?;
+        let mut sessions = self.sessions.lock().await;
+        let context = sessions.get_mut(session_id)
+            .ok_or_else(|| pty_error(ErrorCode::SessionNotFound, format!("PTY 会话不存在: {}", session_id)))?;
         
         let mut pty = context.session.lock().await;
         pty.resize(cols, rows)
-            .map_err(|e| RouterError::ModuleError(format!("调整终端尺寸失败: {}", e)))?;
+            .map_err(|e| pty_error(ErrorCode::IoError, format!("调整终端尺寸失败: {}", e)))?;
+        drop(pty);
+        context.screen.lock().unwrap_or_else(|e| e.into_inner()).resize(cols, rows);
+        context.info.cols = cols;
+        context.info.rows = rows;
         
         Ok(None) // resize 不需要响应
     }
@@ -274,13 +475,8 @@ impl PtyHandler {
     pub async fn write_data(&self, session_id: &str, data: &[u8]) -> Result<(), RouterError> {
         let sessions = self.sessions.lock().await;
         let context = sessions.get(session_id)
-            .ok_or_else(|| RouterError::ModuleError(format!("SESSION_NOT_FOUND: {}", session_id)))?;
-        
-        let mut w = context.writer.lock().unwrap();
-        w.write(data)
-            .map_err(|e| RouterError::ModuleError(format!("写入 PTY 失败: {}", e)))?;
-        
-        Ok(())
+            .ok_or_else(|| pty_error(ErrorCode::SessionNotFound, format!("PTY 会话不存在: {}", session_id)))?;
+        context.write(data)
     }
     
     /// 销毁指定会话
@@ -298,16 +494,193 @@ impl PtyHandler {
             if let Some(task) = context.read_task.take() {
                 let _ = task.await;
             }
+            audit::record(AuditAction::PtyDestroy, ModuleType::Pty, session_id, None, serde_json::Value::Null);
             
             log_info!("PTY 会话已销毁: session_id={}", session_id);
             Ok(())
         } else {
-            Err(RouterError::ModuleError(format!("SESSION_NOT_FOUND: {}", session_id)))
+            Err(pty_error(ErrorCode::SessionNotFound, format!("PTY 会话不存在: {}", session_id)).into())
         }
     }
     
+    /// 处理 list 消息 - 列出所有会话 (包括由状态快照恢复的会话)
+    async fn handle_list(&self) -> ServerResponse {
+        let vault = crate::storage::current_vault();
+        let sessions = self.sessions.lock().await;
+        let mut list: Vec<serde_json::Value> = sessions.iter()
+            .filter(|(_, context)| context.vault == vault)
+            .map(|(session_id, context)| serde_json::json!({
+                "session_id": session_id,
+                "shell_type": context.info.shell_type,
+                "cwd": context.cwd(),
+                "cols": context.info.cols,
+                "rows": context.info.rows,
+                "running": (context.running)(),
+                "restored": context.restored,
+            }))
+            .collect();
+        list.sort_by(|a, b| a["session_id"].as_str().cmp(&b["session_id"].as_str()));
+        ServerResponse::new(ModuleType::Pty, "sessions", serde_json::json!({ "sessions": list }))
+    }
+    
+    /// 处理 scrollback 消息 - 返回会话最近的输出 (base64)，用于重新连接后回放
+    async fn handle_scrollback(&self, session_id: &str) -> Result<Option<ServerResponse>, RouterError> {
+        let sessions = self.sessions.lock().await;
+        let context = sessions.get(session_id)
+            .ok_or_else(|| pty_error(ErrorCode::SessionNotFound, format!("PTY 会话不存在: {}", session_id)))?;
+        let data = context.scrollback.lock().unwrap_or_else(|e| e.into_inner()).contents();
+        Ok(Some(ServerResponse::new(
+            ModuleType::Pty,
+            "scrollback",
+            serde_json::json!({
+                "session_id": session_id,
+                "data": general_purpose::STANDARD.encode(data),
+            }),
+        )))
+    }
+    
+    /// 处理 get_screen 消息 - 返回会话当前屏幕的文本快照，用于后台终端的预览
+    ///
+    /// `start_row` / `end_row` (不含) 只返回其中的行，默认为整个屏幕
+    async fn handle_get_screen(
+        &self,
+        session_id: &str,
+        start_row: Option<usize>,
+        end_row: Option<usize>,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let sessions = self.sessions.lock().await;
+        let context = sessions.get(session_id)
+            .ok_or_else(|| pty_error(ErrorCode::SessionNotFound, format!("PTY 会话不存在: {}", session_id)))?;
+        let screen = context.screen.lock().unwrap_or_else(|e| e.into_inner());
+        let (cols, rows) = screen.size();
+        let (cursor_row, cursor_col) = screen.cursor();
+        let end_row = end_row.unwrap_or(rows).min(rows);
+        let start_row = start_row.unwrap_or(0).min(end_row);
+        let lines = &screen.lines()[start_row..end_row];
+        Ok(Some(ServerResponse::new(
+            ModuleType::Pty,
+            "screen",
+            serde_json::json!({
+                "session_id": session_id,
+                "cols": cols,
+                "rows": rows,
+                "cursor": { "row": cursor_row, "col": cursor_col },
+                "alternate": screen.is_alternate(),
+                "start_row": start_row,
+                "lines": lines,
+            }),
+        )))
+    }
+    
+    /// 处理 get_env 消息 - 在提示符处执行 Shell Integration 的 __sw_env，返回 shell 当前的环境变量和 PATH
+    ///
+    /// 只在 shell 停在空的提示符时执行，避免命令被正在运行的程序读取
+    async fn handle_get_env(&self, session_id: &str) -> Result<Option<ServerResponse>, RouterError> {
+        let receiver = {
+            let sessions = self.sessions.lock().await;
+            let context = sessions.get(session_id)
+                .ok_or_else(|| pty_error(ErrorCode::SessionNotFound, format!("PTY 会话不存在: {}", session_id)))?;
+            if context.info.shell_type.as_deref().and_then(get_shell_integration_script).is_none() {
+                return Err(pty_error(ErrorCode::InvalidParams, "get_env 需要 Shell Integration (shell_type 为 bash、zsh 或 fish)").into());
+            }
+            let mut scrollback = context.scrollback.lock().unwrap_or_else(|e| e.into_inner());
+            if !scrollback.at_prompt() || context.screen.lock().unwrap_or_else(|e| e.into_inner()).is_alternate() {
+                return Err(pty_error(ErrorCode::Conflict, "shell 不在空的提示符 (正在运行命令或已键入内容)").into());
+            }
+            let (nonce, receiver) = context.env_capture.lock().unwrap_or_else(|e| e.into_inner()).wait();
+            let command = env::env_command(&nonce);
+            context.writer.lock().unwrap_or_else(|e| e.into_inner()).write(command.as_bytes())
+                .map_err(|e| pty_error(ErrorCode::IoError, format!("写入 PTY 失败: {}", e)))?;
+            scrollback.input(command.as_bytes());
+            receiver
+        };
+        log_info!("查询 shell 环境变量: session_id={}", session_id);
+        let snapshot = tokio::time::timeout(GET_ENV_TIMEOUT, receiver).await
+            .map_err(|_| pty_error(ErrorCode::Timeout, format!("shell 未在 {} 秒内返回环境变量", GET_ENV_TIMEOUT.as_secs())))?
+            .map_err(|_| pty_error(ErrorCode::SessionNotFound, format!("PTY 会话已结束: {}", session_id)))?;
+        Ok(Some(ServerResponse::new(
+            ModuleType::Pty,
+            "env_snapshot",
+            serde_json::json!({
+                "session_id": session_id,
+                "env": snapshot.env,
+                "path": snapshot.path,
+            }),
+        )))
+    }
+    
+    /// 检查是否有活跃会话
+    pub async fn has_sessions(&self) -> bool {
+        let sessions = self.sessions.lock().await;
+        !sessions.is_empty()
+    }
+}
+
+impl Default for PtyHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// PTY 模块支持的消息 (system/describe)
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("init", &[
+        FieldSpec::optional("shell_type", FieldKind::String),
+        FieldSpec::optional("shell_args", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("cwd", FieldKind::String),
+        FieldSpec::optional("env", FieldKind::Object),
+        FieldSpec::optional("idle_notify_ms", FieldKind::Integer),
+    ]),
+    MessageSpec::new("resize", &[
+        FieldSpec::required("session_id", FieldKind::String),
+        FieldSpec::optional("cols", FieldKind::Integer),
+        FieldSpec::optional("rows", FieldKind::Integer),
+    ]),
+    MessageSpec::new("destroy", &[
+        FieldSpec::required("session_id", FieldKind::String),
+    ]),
+    MessageSpec::new("env", &[
+        FieldSpec::optional("cwd", FieldKind::String),
+        FieldSpec::optional("env", FieldKind::Object),
+    ]),
+    MessageSpec::new("list", &[]),
+    MessageSpec::new("scrollback", &[
+        FieldSpec::required("session_id", FieldKind::String),
+    ]),
+    MessageSpec::new("get_screen", &[
+        FieldSpec::required("session_id", FieldKind::String),
+        FieldSpec::optional("start_row", FieldKind::Integer),
+        FieldSpec::optional("end_row", FieldKind::Integer),
+    ]),
+    MessageSpec::new("get_env", &[
+        FieldSpec::required("session_id", FieldKind::String),
+    ]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// PTY 终端模块
+    pub const Pty: ModuleType = ModuleType::named("pty");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for PtyHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Pty
+    }
+    
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+    
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        let mut ws_sender = self.ws_sender.lock().await;
+        *ws_sender = Some(sender);
+    }
+    
     /// 清理所有会话 (连接关闭时调用)
-    pub async fn cleanup_all(&self) {
+    async fn cleanup(&self) {
         log_info!("清理所有 PTY 会话");
         
         let mut sessions = self.sessions.lock().await;
@@ -323,28 +696,53 @@ impl PtyHandler {
             if let Some(task) = context.read_task.take() {
                 let _ = task.await;
             }
+            audit::record(AuditAction::PtyDestroy, ModuleType::Pty, session_id, None, serde_json::json!({ "reason": "cleanup" }));
         }
         
         log_info!("所有 PTY 会话已清理");
     }
     
-    /// 检查是否有活跃会话
-    pub async fn has_sessions(&self) -> bool {
+    /// 保存当前 vault 中仍在运行的会话 (启动参数、工作目录、尺寸和回滚缓冲)
+    async fn snapshot(&self) -> Option<serde_json::Value> {
+        let vault = crate::storage::current_vault();
         let sessions = self.sessions.lock().await;
-        !sessions.is_empty()
-    }
-}
-
-impl Default for PtyHandler {
-    fn default() -> Self {
-        Self::new()
+        let saved: Vec<SavedSession> = sessions.iter()
+            .filter(|(_, context)| context.vault == vault && (context.running)())
+            .map(|(session_id, context)| {
+                let info = SessionInfo { cwd: context.cwd(), ..context.info.clone() };
+                let scrollback = context.scrollback.lock().unwrap_or_else(|e| e.into_inner()).contents();
+                SavedSession {
+                    session_id: session_id.clone(),
+                    info,
+                    scrollback: general_purpose::STANDARD.encode(scrollback),
+                }
+            })
+            .collect();
+        Some(serde_json::json!({ "sessions": saved }))
     }
-}
-
-#[async_trait::async_trait]
-impl ModuleHandler for PtyHandler {
-    fn module_type(&self) -> ModuleType {
-        ModuleType::Pty
+    
+    /// 以相同的会话 ID、shell、工作目录和尺寸重新启动会话，回滚缓冲保留上次的输出
+    async fn restore(&self, state: serde_json::Value) {
+        let saved: Vec<SavedSession> = match serde_json::from_value(state["sessions"].clone()) {
+            Ok(saved) => saved,
+            Err(e) => {
+                log_error!("无法解析 PTY 会话快照: {}", e);
+                return;
+            }
+        };
+        for session in saved {
+            let mut scrollback = Scrollback::new(SCROLLBACK_CAPACITY);
+            scrollback.push(&general_purpose::STANDARD.decode(&session.scrollback).unwrap_or_default());
+            let session_id = session.session_id;
+            match self.spawn_session(session_id.clone(), session.info, scrollback, true, None).await {
+                Ok(()) => {
+                    log_info!("已恢复 PTY 会话: session_id={}", session_id);
+                }
+                Err(e) => {
+                    log_error!("恢复 PTY 会话失败: session_id={}, {}", session_id, e);
+                }
+            }
+        }
     }
     
     async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
@@ -352,18 +750,22 @@ impl ModuleHandler for PtyHandler {
         
         match msg.msg_type.as_str() {
             "init" => {
-                let shell_type: Option<String> = msg.get_field("shell_type");
-                let shell_args: Option<Vec<String>> = msg.get_field("shell_args");
-                let cwd: Option<String> = msg.get_field("cwd");
-                let env: Option<HashMap<String, String>> = msg.get_field("env");
-                
-                self.handle_init(shell_type, shell_args, cwd, env).await
+                let info = SessionInfo {
+                    shell_type: msg.get_field("shell_type"),
+                    shell_args: msg.get_field("shell_args"),
+                    cwd: msg.get_field("cwd"),
+                    env: msg.get_field("env"),
+                    cols: 80,
+                    rows: 24,
+                    idle_notify_ms: msg.get_field("idle_notify_ms"),
+                };
+                self.handle_init(info, msg.sender()).await
             }
             "resize" => {
                 // resize 需要 session_id
                 let session_id: Option<String> = msg.get_field("session_id");
                 let session_id = session_id.ok_or_else(|| {
-                    RouterError::ModuleError("SESSION_ID_REQUIRED".to_string())
+                    pty_error(ErrorCode::InvalidParams, "缺少 session_id 字段")
                 })?;
                 
                 let cols: u16 = msg.get_field("cols").unwrap_or(80);
@@ -375,12 +777,31 @@ impl ModuleHandler for PtyHandler {
                 // destroy 需要 session_id
                 let session_id: Option<String> = msg.get_field("session_id");
                 let session_id = session_id.ok_or_else(|| {
-                    RouterError::ModuleError("SESSION_ID_REQUIRED".to_string())
+                    pty_error(ErrorCode::InvalidParams, "缺少 session_id 字段")
                 })?;
                 
                 self.handle_destroy(&session_id).await?;
                 Ok(None)
             }
+            "list" => Ok(Some(self.handle_list().await)),
+            "scrollback" => {
+                let session_id: String = msg.get_field("session_id").ok_or_else(|| {
+                    pty_error(ErrorCode::InvalidParams, "缺少 session_id 字段")
+                })?;
+                self.handle_scrollback(&session_id).await
+            }
+            "get_screen" => {
+                let session_id: String = msg.get_field("session_id").ok_or_else(|| {
+                    pty_error(ErrorCode::InvalidParams, "缺少 session_id 字段")
+                })?;
+                self.handle_get_screen(&session_id, msg.get_field("start_row"), msg.get_field("end_row")).await
+            }
+            "get_env" => {
+                let session_id: String = msg.get_field("session_id").ok_or_else(|| {
+                    pty_error(ErrorCode::InvalidParams, "缺少 session_id 字段")
+                })?;
+                self.handle_get_env(&session_id).await
+            }
             "env" => {
                 // env 命令在原实现中只是记录日志，实际环境变量在 init 时设置
                 let cwd: Option<String> = msg.get_field("cwd");
@@ -390,7 +811,7 @@ impl ModuleHandler for PtyHandler {
             }
             _ => {
                 log_debug!("未知的 PTY 消息类型: {}", msg.msg_type);
-                Err(RouterError::ModuleError(format!("未知的 PTY 消息类型: {}", msg.msg_type)))
+                Err(pty_error(ErrorCode::UnknownMessageType, format!("未知的 PTY 消息类型: {}", msg.msg_type)).into())
             }
         }
     }
@@ -0,0 +1,517 @@
+// PTY 屏幕模型
+// 轻量的 VT 解析器：按终端输出维护当前屏幕的字符网格 (光标移动、擦除、滚动区域、备用屏幕)，
+// 忽略颜色等属性，用于生成后台终端的预览快照
+
+use unicode_width::UnicodeWidthChar;
+
+/// 宽字符 (中文等) 第二列的占位
+const WIDE_TAIL: char = '\0';
+
+/// Tab 停止位间隔
+const TAB_WIDTH: usize = 8;
+
+/// 解析状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    /// `ESC (` 等带中间字节的序列，再消耗一个字节
+    EscapeIntermediate,
+    Csi,
+    /// OSC / DCS 等字符串序列，直到 BEL 或 ST
+    String,
+    StringEscape,
+}
+
+/// 终端屏幕
+#[derive(Debug, Clone)]
+pub struct Screen {
+    cols: usize,
+    rows: usize,
+    grid: Vec<Vec<char>>,
+    /// 切换到备用屏幕时保存的主屏幕
+    main_grid: Option<Vec<Vec<char>>>,
+    row: usize,
+    col: usize,
+    saved_cursor: (usize, usize),
+    /// 光标位于最后一列且已写入字符，下一个字符换行后写入
+    wrap_pending: bool,
+    /// 滚动区域 (首行和末行，含)
+    scroll_top: usize,
+    scroll_bottom: usize,
+    state: State,
+    params: Vec<u16>,
+    private: bool,
+    utf8: Vec<u8>,
+}
+
+impl Screen {
+    /// 创建指定尺寸的空白屏幕
+    pub fn new(cols: u16, rows: u16) -> Self {
+        let (cols, rows) = (usize::from(cols.max(1)), usize::from(rows.max(1)));
+        Self {
+            cols,
+            rows,
+            grid: vec![vec![' '; cols]; rows],
+            main_grid: None,
+            row: 0,
+            col: 0,
+            saved_cursor: (0, 0),
+            wrap_pending: false,
+            scroll_top: 0,
+            scroll_bottom: rows - 1,
+            state: State::Ground,
+            params: Vec::new(),
+            private: false,
+            utf8: Vec::new(),
+        }
+    }
+
+    /// 处理一段终端输出
+    pub fn push(&mut self, output: &[u8]) {
+        for &byte in output {
+            self.advance(byte);
+        }
+    }
+
+    /// 调整尺寸：行尾截断或补空白，行数减少时丢弃顶部的行 (保持光标所在行可见)
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        let (cols, rows) = (usize::from(cols.max(1)), usize::from(rows.max(1)));
+        let dropped = (self.row + 1).saturating_sub(rows);
+        for grid in std::iter::once(&mut self.grid).chain(self.main_grid.as_mut()) {
+            grid.drain(..dropped.min(grid.len()));
+            grid.resize(rows, vec![' '; cols]);
+            for line in grid.iter_mut() {
+                line.resize(cols, ' ');
+            }
+        }
+        self.cols = cols;
+        self.rows = rows;
+        self.row = (self.row - dropped).min(rows - 1);
+        self.col = self.col.min(cols - 1);
+        self.wrap_pending = false;
+        self.scroll_top = 0;
+        self.scroll_bottom = rows - 1;
+    }
+
+    /// 屏幕尺寸 (列数, 行数)
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    /// 光标位置 (行, 列)，从 0 开始
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// 是否处于备用屏幕 (vim、less 等全屏程序)
+    pub fn is_alternate(&self) -> bool {
+        self.main_grid.is_some()
+    }
+
+    /// 每行的文本 (去掉行尾空白)
+    pub fn lines(&self) -> Vec<String> {
+        self.grid
+            .iter()
+            .map(|line| {
+                let text: String = line.iter().filter(|&&c| c != WIDE_TAIL).collect();
+                text.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    fn advance(&mut self, byte: u8) {
+        match self.state {
+            State::Ground => self.ground(byte),
+            State::Escape => self.escape(byte),
+            State::EscapeIntermediate => self.state = State::Ground,
+            State::Csi => self.csi(byte),
+            State::String => match byte {
+                0x07 => self.state = State::Ground,
+                0x1b => self.state = State::StringEscape,
+                _ => {}
+            },
+            // ST (`ESC \`)，其他字节同样结束字符串
+            State::StringEscape => self.state = State::Ground,
+        }
+    }
+
+    fn ground(&mut self, byte: u8) {
+        if byte >= 0x80 {
+            self.utf8.push(byte);
+            match std::str::from_utf8(&self.utf8) {
+                Ok(text) => {
+                    let c = text.chars().next().unwrap_or(char::REPLACEMENT_CHARACTER);
+                    self.utf8.clear();
+                    self.print(c);
+                }
+                // 不完整的字符等待后续字节
+                Err(e) if e.error_len().is_none() => {}
+                Err(_) => {
+                    self.utf8.clear();
+                    self.print(char::REPLACEMENT_CHARACTER);
+                }
+            }
+            return;
+        }
+        self.utf8.clear();
+        match byte {
+            0x1b => self.state = State::Escape,
+            0x20..=0x7e => self.print(byte as char),
+            _ => self.control(byte),
+        }
+    }
+
+    fn control(&mut self, byte: u8) {
+        match byte {
+            0x08 => {
+                self.col = self.col.saturating_sub(1);
+                self.wrap_pending = false;
+            }
+            0x09 => {
+                self.col = ((self.col / TAB_WIDTH + 1) * TAB_WIDTH).min(self.cols - 1);
+                self.wrap_pending = false;
+            }
+            0x0a..=0x0c => self.linefeed(),
+            0x0d => {
+                self.col = 0;
+                self.wrap_pending = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn escape(&mut self, byte: u8) {
+        self.state = State::Ground;
+        match byte {
+            b'[' => {
+                self.params.clear();
+                self.params.push(0);
+                self.private = false;
+                self.state = State::Csi;
+            }
+            b']' | b'P' | b'X' | b'^' | b'_' => self.state = State::String,
+            0x20..=0x2f => self.state = State::EscapeIntermediate,
+            b'7' => self.saved_cursor = (self.row, self.col),
+            b'8' => self.restore_cursor(),
+            b'D' => self.linefeed(),
+            b'E' => {
+                self.linefeed();
+                self.col = 0;
+            }
+            b'M' => self.reverse_index(),
+            b'c' => *self = Self::new(self.cols as u16, self.rows as u16),
+            _ => {}
+        }
+    }
+
+    fn csi(&mut self, byte: u8) {
+        match byte {
+            b'0'..=b'9' => {
+                if let Some(param) = self.params.last_mut() {
+                    *param = param.saturating_mul(10).saturating_add(u16::from(byte - b'0'));
+                }
+            }
+            b';' | b':' => self.params.push(0),
+            b'?' | b'>' | b'=' | b'<' => self.private = true,
+            0x20..=0x2f => {}
+            0x40..=0x7e => {
+                self.state = State::Ground;
+                self.dispatch_csi(byte);
+            }
+            0x1b => self.state = State::Escape,
+            _ => self.control(byte),
+        }
+    }
+
+    /// 第 `index` 个参数，缺省或为 0 时取 `default`
+    fn param(&self, index: usize, default: usize) -> usize {
+        match self.params.get(index) {
+            Some(&value) if value > 0 => usize::from(value),
+            _ => default,
+        }
+    }
+
+    fn dispatch_csi(&mut self, action: u8) {
+        if self.private {
+            if matches!(action, b'h' | b'l') {
+                let enable = action == b'h';
+                for mode in self.params.clone() {
+                    if matches!(mode, 47 | 1047 | 1049) {
+                        self.set_alternate(enable, mode == 1049);
+                    }
+                }
+            }
+            return;
+        }
+        if action == b'm' {
+            return;
+        }
+        self.wrap_pending = false;
+        let n = self.param(0, 1);
+        match action {
+            b'A' => self.row = self.row.saturating_sub(n),
+            b'B' | b'e' => self.row = (self.row + n).min(self.rows - 1),
+            b'C' | b'a' => self.col = (self.col + n).min(self.cols - 1),
+            b'D' => self.col = self.col.saturating_sub(n),
+            b'E' => {
+                self.row = (self.row + n).min(self.rows - 1);
+                self.col = 0;
+            }
+            b'F' => {
+                self.row = self.row.saturating_sub(n);
+                self.col = 0;
+            }
+            b'G' | b'`' => self.col = (n - 1).min(self.cols - 1),
+            b'd' => self.row = (n - 1).min(self.rows - 1),
+            b'H' | b'f' => {
+                self.row = (n - 1).min(self.rows - 1);
+                self.col = (self.param(1, 1) - 1).min(self.cols - 1);
+            }
+            b'J' => match self.param(0, 0) {
+                0 => {
+                    self.clear_line(self.row, self.col, self.cols);
+                    for row in self.row + 1..self.rows {
+                        self.clear_line(row, 0, self.cols);
+                    }
+                }
+                1 => {
+                    for row in 0..self.row {
+                        self.clear_line(row, 0, self.cols);
+                    }
+                    self.clear_line(self.row, 0, self.col + 1);
+                }
+                _ => {
+                    for row in 0..self.rows {
+                        self.clear_line(row, 0, self.cols);
+                    }
+                }
+            },
+            b'K' => match self.param(0, 0) {
+                0 => self.clear_line(self.row, self.col, self.cols),
+                1 => self.clear_line(self.row, 0, self.col + 1),
+                _ => self.clear_line(self.row, 0, self.cols),
+            },
+            b'L' if (self.scroll_top..=self.scroll_bottom).contains(&self.row) => {
+                self.scroll_down_from(self.row, n);
+                self.col = 0;
+            }
+            b'M' if (self.scroll_top..=self.scroll_bottom).contains(&self.row) => {
+                self.scroll_up_from(self.row, n);
+                self.col = 0;
+            }
+            b'@' => {
+                let line = &mut self.grid[self.row];
+                let n = n.min(self.cols - self.col);
+                line.truncate(self.cols - n);
+                line.splice(self.col..self.col, std::iter::repeat_n(' ', n));
+            }
+            b'P' => {
+                let line = &mut self.grid[self.row];
+                let n = n.min(self.cols - self.col);
+                line.drain(self.col..self.col + n);
+                line.resize(self.cols, ' ');
+            }
+            b'X' => self.clear_line(self.row, self.col, (self.col + n).min(self.cols)),
+            b'S' => self.scroll_up_from(self.scroll_top, n),
+            b'T' => self.scroll_down_from(self.scroll_top, n),
+            b'r' => {
+                let top = self.param(0, 1) - 1;
+                let bottom = self.param(1, self.rows).min(self.rows) - 1;
+                if top < bottom {
+                    self.scroll_top = top;
+                    self.scroll_bottom = bottom;
+                    self.row = 0;
+                    self.col = 0;
+                }
+            }
+            b's' => self.saved_cursor = (self.row, self.col),
+            b'u' => self.restore_cursor(),
+            _ => {}
+        }
+    }
+
+    /// 在光标处写入字符，到达行尾时自动换行
+    fn print(&mut self, c: char) {
+        let width = c.width().unwrap_or(0);
+        // 组合字符等零宽字符不占列
+        if width == 0 || width > self.cols {
+            return;
+        }
+        if self.wrap_pending || self.col + width > self.cols {
+            self.col = 0;
+            self.linefeed();
+        }
+        self.put(self.col, c);
+        if width == 2 {
+            self.put(self.col + 1, WIDE_TAIL);
+        }
+        self.col += width;
+        if self.col >= self.cols {
+            self.col = self.cols - 1;
+            self.wrap_pending = true;
+        }
+    }
+
+    /// 写入单元格，被覆盖一半的宽字符清为空白
+    fn put(&mut self, col: usize, c: char) {
+        let line = &mut self.grid[self.row];
+        if line[col] == WIDE_TAIL && col > 0 && c != WIDE_TAIL {
+            line[col - 1] = ' ';
+        }
+        if line.get(col + 1) == Some(&WIDE_TAIL) {
+            line[col + 1] = ' ';
+        }
+        line[col] = c;
+    }
+
+    fn clear_line(&mut self, row: usize, from: usize, to: usize) {
+        for cell in &mut self.grid[row][from..to] {
+            *cell = ' ';
+        }
+    }
+
+    fn linefeed(&mut self) {
+        self.wrap_pending = false;
+        if self.row == self.scroll_bottom {
+            self.scroll_up_from(self.scroll_top, 1);
+        } else if self.row < self.rows - 1 {
+            self.row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.wrap_pending = false;
+        if self.row == self.scroll_top {
+            self.scroll_down_from(self.scroll_top, 1);
+        } else {
+            self.row = self.row.saturating_sub(1);
+        }
+    }
+
+    /// 从 `top` 行到滚动区域底部上移 `n` 行，底部补空行
+    fn scroll_up_from(&mut self, top: usize, n: usize) {
+        let n = n.min(self.scroll_bottom + 1 - top);
+        self.grid.drain(top..top + n);
+        let blank = vec![' '; self.cols];
+        self.grid.splice(self.scroll_bottom + 1 - n..self.scroll_bottom + 1 - n, std::iter::repeat_n(blank, n));
+    }
+
+    /// 从 `top` 行到滚动区域底部下移 `n` 行，`top` 处补空行
+    fn scroll_down_from(&mut self, top: usize, n: usize) {
+        let n = n.min(self.scroll_bottom + 1 - top);
+        self.grid.drain(self.scroll_bottom + 1 - n..self.scroll_bottom + 1);
+        let blank = vec![' '; self.cols];
+        self.grid.splice(top..top, std::iter::repeat_n(blank, n));
+    }
+
+    fn restore_cursor(&mut self) {
+        let (row, col) = self.saved_cursor;
+        self.row = row.min(self.rows - 1);
+        self.col = col.min(self.cols - 1);
+        self.wrap_pending = false;
+    }
+
+    /// 切换备用屏幕 (1049 同时保存和恢复光标)
+    fn set_alternate(&mut self, enable: bool, save_cursor: bool) {
+        if enable == self.is_alternate() {
+            return;
+        }
+        if enable {
+            if save_cursor {
+                self.saved_cursor = (self.row, self.col);
+            }
+            let blank = vec![vec![' '; self.cols]; self.rows];
+            self.main_grid = Some(std::mem::replace(&mut self.grid, blank));
+        } else {
+            if let Some(main) = self.main_grid.take() {
+                self.grid = main;
+            }
+            if save_cursor {
+                self.restore_cursor();
+            }
+        }
+        self.scroll_top = 0;
+        self.scroll_bottom = self.rows - 1;
+        self.wrap_pending = false;
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(cols: u16, rows: u16, output: &[u8]) -> Screen {
+        let mut screen = Screen::new(cols, rows);
+        screen.push(output);
+        screen
+    }
+
+    #[test]
+    fn test_print_and_wrap() {
+        let screen = screen(5, 3, b"hello world\r\n$ ");
+        assert_eq!(screen.lines(), [" worl", "d", "$"]);
+        // 最后一行换行后向上滚动
+        let screen = self::screen(5, 3, b"a\r\nb\r\nc\r\nd");
+        assert_eq!(screen.lines(), ["b", "c", "d"]);
+        assert_eq!(screen.cursor(), (2, 1));
+
+        // 宽字符占两列，放不下时换到下一行；多字节字符可以分段到达
+        let mut screen = Screen::new(5, 2);
+        let text = "你好吗".as_bytes();
+        screen.push(&text[..4]);
+        screen.push(&text[4..]);
+        assert_eq!(screen.lines(), ["你好", "吗"]);
+    }
+
+    #[test]
+    fn test_cursor_and_erase() {
+        let screen = screen(10, 3, b"abcdef\x1b[3D\x1b[K\x1b[2;3Hxy\x1b[1;2H\x1b[P");
+        assert_eq!(screen.lines(), ["ac", "  xy", ""]);
+        assert_eq!(screen.cursor(), (0, 1));
+
+        // 清屏并移回左上角；颜色和 OSC 序列不影响内容
+        let screen = self::screen(10, 2, b"old\r\ntext\x1b[2J\x1b[H\x1b[1;32mnew\x1b[0m\x1b]0;title\x07!");
+        assert_eq!(screen.lines(), ["new!", ""]);
+    }
+
+    #[test]
+    fn test_scroll_region() {
+        // 第 1 行是固定的标题，滚动区域为第 2-3 行
+        let screen = screen(10, 4, b"title\x1b[2;3r\x1b[2;1Ha\r\nb\r\nc\x1b[4;1Hstatus");
+        assert_eq!(screen.lines(), ["title", "b", "c", "status"]);
+
+        // 反向换行在区域顶部插入空行
+        let screen = self::screen(10, 3, b"a\r\nb\r\nc\x1b[2;3r\x1b[2;1H\x1bMx");
+        assert_eq!(screen.lines(), ["a", "x", "b"]);
+    }
+
+    #[test]
+    fn test_alternate_screen() {
+        let mut screen = screen(10, 2, b"$ vim");
+        screen.push(b"\x1b[?1049h\x1b[Hediting");
+        assert!(screen.is_alternate());
+        assert_eq!(screen.lines(), ["editing", ""]);
+
+        // 退出后恢复主屏幕和光标
+        screen.push(b"\x1b[?1049l");
+        assert!(!screen.is_alternate());
+        assert_eq!(screen.lines(), ["$ vim", ""]);
+        assert_eq!(screen.cursor(), (0, 5));
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut screen = screen(6, 3, b"one\r\ntwo\r\nthree");
+        screen.resize(4, 2);
+        assert_eq!(screen.size(), (4, 2));
+        assert_eq!(screen.lines(), ["two", "thre"]);
+        assert_eq!(screen.cursor(), (1, 3));
+        screen.resize(8, 3);
+        assert_eq!(screen.lines(), ["two", "thre", ""]);
+    }
+}
@@ -0,0 +1,131 @@
+// PTY 回滚缓冲
+// 保留每个会话最近的输出 (超出容量时丢弃最早的部分)，并从 Shell Integration 输出的 OSC 7 序列中
+// 记录当前工作目录，用于重启后恢复会话和客户端重新连接时回放输出。
+// Shell Integration 在每个提示符前输出 OSC 7，之后没有输入时认为 shell 停在空的提示符 (get_env 据此判断能否执行命令)
+
+use std::collections::VecDeque;
+
+/// 每个会话保留的输出字节数
+pub const SCROLLBACK_CAPACITY: usize = 64 * 1024;
+
+/// OSC 7 (当前工作目录) 序列的前缀
+const OSC7_PREFIX: &[u8] = b"\x1b]7;file://";
+
+/// 会话输出的回滚缓冲
+#[derive(Debug, Clone)]
+pub struct Scrollback {
+    data: VecDeque<u8>,
+    capacity: usize,
+    cwd: Option<String>,
+    at_prompt: bool,
+}
+
+impl Scrollback {
+    /// 创建指定容量的缓冲
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            data: VecDeque::with_capacity(capacity.min(SCROLLBACK_CAPACITY)),
+            capacity,
+            cwd: None,
+            at_prompt: false,
+        }
+    }
+
+    /// 追加输出，超出容量时丢弃最早的字节
+    pub fn push(&mut self, output: &[u8]) {
+        if let Some(cwd) = last_osc7_cwd(output) {
+            self.cwd = Some(cwd);
+            self.at_prompt = true;
+        }
+        let output = &output[output.len().saturating_sub(self.capacity)..];
+        let overflow = (self.data.len() + output.len()).saturating_sub(self.capacity);
+        self.data.drain(..overflow);
+        self.data.extend(output);
+    }
+
+    /// 缓冲中的全部输出
+    pub fn contents(&self) -> Vec<u8> {
+        self.data.iter().copied().collect()
+    }
+
+    /// 最近一次报告的工作目录
+    pub fn cwd(&self) -> Option<&str> {
+        self.cwd.as_deref()
+    }
+
+    /// 记录写入 shell 的输入 (提示符处键入内容或执行命令)
+    pub fn input(&mut self, data: &[u8]) {
+        if !data.is_empty() {
+            self.at_prompt = false;
+        }
+    }
+
+    /// shell 是否停在空的提示符
+    pub fn at_prompt(&self) -> bool {
+        self.at_prompt
+    }
+}
+
+/// 提取输出中最后一个完整 OSC 7 序列的路径 (`ESC ] 7 ; file://主机/路径` 以 BEL 或 ST 结束)
+fn last_osc7_cwd(output: &[u8]) -> Option<String> {
+    let start = output.windows(OSC7_PREFIX.len()).rposition(|window| window == OSC7_PREFIX)? + OSC7_PREFIX.len();
+    let rest = &output[start..];
+    let end = rest.iter().position(|&b| b == 0x07 || b == 0x1b)?;
+    let url = std::str::from_utf8(&rest[..end]).ok()?;
+    // 跳过主机名，保留以 / 开头的路径
+    let path = &url[url.find('/')?..];
+    Some(path.to_string())
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity() {
+        let mut scrollback = Scrollback::new(8);
+        scrollback.push(b"hello ");
+        scrollback.push(b"world");
+        assert_eq!(scrollback.contents(), b"lo world");
+
+        scrollback.push(b"0123456789");
+        assert_eq!(scrollback.contents(), b"23456789");
+    }
+
+    #[test]
+    fn test_osc7_cwd() {
+        let mut scrollback = Scrollback::new(SCROLLBACK_CAPACITY);
+        assert_eq!(scrollback.cwd(), None);
+
+        scrollback.push(b"\x1b]7;file://host/home/me\x1b\\$ ");
+        assert_eq!(scrollback.cwd(), Some("/home/me"));
+
+        scrollback.push(b"cd /tmp\r\n\x1b]7;file://host/a\x07\x1b]7;file://host/tmp/x y\x07");
+        assert_eq!(scrollback.cwd(), Some("/tmp/x y"));
+
+        // 不完整的序列不更新
+        scrollback.push(b"\x1b]7;file://host/var");
+        assert_eq!(scrollback.cwd(), Some("/tmp/x y"));
+    }
+
+    #[test]
+    fn test_at_prompt() {
+        let mut scrollback = Scrollback::new(SCROLLBACK_CAPACITY);
+        assert!(!scrollback.at_prompt());
+
+        scrollback.push(b"\x1b]7;file://host/home/me\x07$ ");
+        assert!(scrollback.at_prompt());
+
+        // 键入内容后直到下一个提示符
+        scrollback.input(b"ls");
+        assert!(!scrollback.at_prompt());
+        scrollback.push(b"ls\r\nnotes\r\n");
+        assert!(!scrollback.at_prompt());
+        scrollback.push(b"\x1b]7;file://host/home/me\x07$ ");
+        assert!(scrollback.at_prompt());
+    }
+}
@@ -1,6 +1,6 @@
 // PTY 会话管理
 
-use portable_pty::{native_pty_system, Child, MasterPty, PtySize};
+use portable_pty::{native_pty_system, Child, MasterPty, PtySize, PtySystem};
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
 
@@ -39,7 +39,7 @@ impl PtySession {
         env: Option<&std::collections::HashMap<String, String>>
     ) -> Result<(Self, PtyReader, PtyWriter), Box<dyn std::error::Error>> {
         // 获取 PTY 系统
-        let pty_system = native_pty_system();
+        let pty_system = pty_system(shell_type);
         
         // 创建 PTY 对
         let pair = pty_system.openpty(PtySize {
@@ -129,6 +129,15 @@ impl PtySession {
         Ok(())
     }
     
+    /// 创建检查子进程是否仍在运行的探测函数 (可在其他线程中调用)
+    pub fn running_probe(&self) -> impl Fn() -> bool + Send + Sync + 'static {
+        let child = Arc::clone(&self.child);
+        move || match child.lock() {
+            Ok(mut child) => matches!(child.try_wait(), Ok(None)),
+            Err(_) => false,
+        }
+    }
+    
     /// 终止子进程
     pub fn kill(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         if let Ok(mut child) = self.child.lock() {
@@ -138,6 +147,21 @@ impl PtySession {
     }
 }
 
+/// 选择 PTY 系统
+#[cfg(not(any(test, feature = "test-support")))]
+fn pty_system(_shell_type: Option<&str>) -> Box<dyn PtySystem + Send> {
+    native_pty_system()
+}
+
+/// 选择 PTY 系统 (shell_type 为 `mock` 时使用内存中的假 PTY)
+#[cfg(any(test, feature = "test-support"))]
+fn pty_system(shell_type: Option<&str>) -> Box<dyn PtySystem + Send> {
+    if shell_type == Some(crate::testing::pty::MOCK_SHELL) {
+        return Box::new(crate::testing::pty::FakePtySystem);
+    }
+    native_pty_system()
+}
+
 impl PtyReader {
     /// 从 PTY 读取数据
     pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, Box<dyn std::error::Error>> {
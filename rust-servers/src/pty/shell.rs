@@ -6,18 +6,19 @@ use portable_pty::CommandBuilder;
 /// 使用空格前缀防止命令进入历史记录，使用重定向隐藏输出
 /// 注意: bash/zsh 默认配置不记录以空格开头的命令
 /// 仅在 Unix 平台使用，Windows 依赖前端 prompt 解析
+/// __sw_cwd 在每个提示符前用 OSC 7 报告工作目录；__sw_env 由 get_env 调用，输出环境变量后清除回显的命令行 (见 env.rs)
 
 // Bash: 定义函数并设置 PROMPT_COMMAND，静默执行
 #[cfg(not(windows))]
-const SHELL_INTEGRATION_BASH: &str = " eval '__sw_cwd(){ printf \"\\e]7;file://%s%s\\e\\\\\" \"${HOSTNAME:-localhost}\" \"$PWD\";};PROMPT_COMMAND=\"__sw_cwd${PROMPT_COMMAND:+;$PROMPT_COMMAND}\"' 2>/dev/null;__sw_cwd;printf '\\ec'\n";
+const SHELL_INTEGRATION_BASH: &str = " eval '__sw_cwd(){ printf \"\\e]7;file://%s%s\\e\\\\\" \"${HOSTNAME:-localhost}\" \"$PWD\";};__sw_env(){ printf \"\\e]7777;env;%s;%s\\a\\e[1A\\e[2K\" \"$1\" \"$(env|base64|tr -d \"\\n\")\";};PROMPT_COMMAND=\"__sw_cwd${PROMPT_COMMAND:+;$PROMPT_COMMAND}\"' 2>/dev/null;__sw_cwd;printf '\\ec'\n";
 
 // Zsh: 使用 precmd hook，静默执行
 #[cfg(not(windows))]
-const SHELL_INTEGRATION_ZSH: &str = " eval '__sw_cwd(){ printf \"\\e]7;file://%s%s\\e\\\\\" \"${HOST:-localhost}\" \"$PWD\";};autoload -Uz add-zsh-hook;add-zsh-hook precmd __sw_cwd;add-zsh-hook chpwd __sw_cwd' 2>/dev/null;__sw_cwd;printf '\\ec'\n";
+const SHELL_INTEGRATION_ZSH: &str = " eval '__sw_cwd(){ printf \"\\e]7;file://%s%s\\e\\\\\" \"${HOST:-localhost}\" \"$PWD\";};__sw_env(){ printf \"\\e]7777;env;%s;%s\\a\\e[1A\\e[2K\" \"$1\" \"$(env|base64|tr -d \"\\n\")\";};autoload -Uz add-zsh-hook;add-zsh-hook precmd __sw_cwd;add-zsh-hook chpwd __sw_cwd' 2>/dev/null;__sw_cwd;printf '\\ec'\n";
 
-// Fish: 使用事件监听器
+// Fish: 使用事件监听器 (目录变化和每个提示符)
 #[cfg(not(windows))]
-const SHELL_INTEGRATION_FISH: &str = " eval 'function __sw_cwd --on-variable PWD; printf \"\\e]7;file://%s%s\\e\\\\\" (hostname) $PWD; end' 2>/dev/null;__sw_cwd;printf '\\ec'\n";
+const SHELL_INTEGRATION_FISH: &str = " eval 'function __sw_cwd --on-variable PWD --on-event fish_prompt; printf \"\\e]7;file://%s%s\\e\\\\\" (hostname) $PWD; end;function __sw_env; printf \"\\e]7777;env;%s;%s\\a\\e[1A\\e[2K\" $argv[1] (env|base64|tr -d \"\\n\"); end' 2>/dev/null;__sw_cwd;printf '\\ec'\n";
 
 /// 获取 Shell Integration 脚本
 /// 
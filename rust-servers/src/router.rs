@@ -2,27 +2,37 @@
 // 根据 module 字段将消息分发到对应的功能模块
 
 use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use futures_util::{FutureExt, SinkExt};
+use std::cell::RefCell;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Once, RwLock};
 use thiserror::Error;
-use crate::server::WsSender;
+use tokio_tungstenite::tungstenite::Message;
+use crate::middleware::{Middleware, Next, RouteResult};
+use crate::server::{Connection, WsSender};
 
 /// 日志宏
+#[allow(unused_macros)]
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
     };
 }
 
 #[allow(unused_macros)]
 macro_rules! log_error {
     ($($arg:tt)*) => {
-        eprintln!("[ERROR] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Error, "", format!($($arg)*));
     };
 }
 
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
         }
     };
 }
@@ -32,35 +42,71 @@ macro_rules! log_debug {
 // ============================================================================
 
 /// 模块类型
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum ModuleType {
-    /// PTY 终端模块
-    Pty,
-    /// 语音模块
-    Voice,
-    /// LLM 流式处理模块
-    Llm,
-    /// 工具模块
-    Utils,
+/// 
+/// 以模块名称区分，各模块在自己的文件中声明对应的常量 (如 `ModuleType::Pty`)；
+/// 名称需通过 `MessageRouter::with_module` 注册后才能被解析
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModuleType(&'static str);
+
+/// 已注册的模块名称 (按注册顺序，消息反序列化时据此识别模块)
+static MODULE_NAMES: RwLock<Vec<&'static str>> = RwLock::new(Vec::new());
+
+impl ModuleType {
+    /// 以名称声明模块类型
+    pub const fn named(name: &'static str) -> Self {
+        Self(name)
+    }
+    
+    /// 模块名称
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+    
+    /// 按名称查找已注册的模块
+    pub fn from_name(name: &str) -> Option<ModuleType> {
+        let names = MODULE_NAMES.read().ok()?;
+        names.iter().find(|n| **n == name).map(|n| ModuleType(n))
+    }
+    
+    /// 注册模块名称
+    fn register(self) {
+        if let Ok(mut names) = MODULE_NAMES.write() {
+            if !names.contains(&self.0) {
+                names.push(self.0);
+            }
+        }
+    }
 }
 
 impl std::fmt::Display for ModuleType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ModuleType::Pty => write!(f, "pty"),
-            ModuleType::Voice => write!(f, "voice"),
-            ModuleType::Llm => write!(f, "llm"),
-            ModuleType::Utils => write!(f, "utils"),
-        }
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for ModuleType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for ModuleType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = std::borrow::Cow::<'de, str>::deserialize(deserializer)?;
+        ModuleType::from_name(&name)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown module `{}`", name)))
     }
 }
 
 /// 统一消息格式
 /// 
-/// 所有客户端消息必须包含 `module` 字段来指定目标模块
+/// 所有客户端消息必须包含 `module` 字段来指定目标模块，
+/// 可选的顶层 `request_id` 会回显到对应的响应和由该请求产生的异步事件上
 #[derive(Debug, Deserialize)]
 pub struct ModuleMessage {
+    /// 消息来源连接 (由服务器在解析后设置，请求产生的异步事件投递到该连接)
+    #[serde(skip)]
+    pub connection: Option<Connection>,
     /// 目标模块
     pub module: ModuleType,
     /// 消息类型
@@ -78,6 +124,16 @@ impl ModuleMessage {
         &self.payload
     }
     
+    /// 获取来源连接的事件发送器 (事件同时抄送给订阅了该事件的其他连接)
+    pub fn sender(&self) -> Option<WsSender> {
+        self.connection.as_ref().map(|c| std::sync::Arc::clone(&c.events))
+    }
+    
+    /// 获取请求 ID (顶层可选字段，保留在负载中以便各模块解析)
+    pub fn request_id(&self) -> Option<String> {
+        self.payload.get("request_id").and_then(|v| v.as_str()).map(str::to_string)
+    }
+    
     /// 获取负载中的字段值
     pub fn get_field<T: serde::de::DeserializeOwned>(&self, field: &str) -> Option<T> {
         self.payload.get(field).and_then(|v| serde_json::from_value(v.clone()).ok())
@@ -85,7 +141,7 @@ impl ModuleMessage {
 }
 
 /// 服务器响应消息
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ServerResponse {
     /// 来源模块
     pub module: ModuleType,
@@ -108,24 +164,175 @@ impl ServerResponse {
         }
     }
     
-    /// 创建错误响应
-    pub fn error(module: ModuleType, code: &str, message: &str) -> Self {
+    /// 创建错误响应 (是否可重试由错误码决定)
+    pub fn error(module: ModuleType, code: ErrorCode, message: &str) -> Self {
         Self {
             module,
             msg_type: "error".to_string(),
             payload: serde_json::json!({
                 "code": code,
-                "message": message
+                "message": message,
+                "retryable": code.is_retryable(),
             }),
         }
     }
     
+    /// 附加请求 ID (模块已在负载中设置时保持不变)
+    pub fn with_request_id(mut self, request_id: Option<&str>) -> Self {
+        if let (Some(id), serde_json::Value::Object(payload)) = (request_id, &mut self.payload) {
+            payload
+                .entry("request_id")
+                .or_insert_with(|| serde_json::Value::String(id.to_string()));
+        }
+        self
+    }
+    
     /// 转换为 JSON 字符串
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
     }
 }
 
+// ============================================================================
+// 错误码
+// ============================================================================
+
+/// 错误码
+/// 
+/// 错误响应和异步错误事件中的 `code` 字段，客户端应依据错误码而非错误文本决定处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    // 协议层
+    /// 连接未认证
+    Unauthorized,
+    /// 消息无法解析 (LLM 流中表示上游响应无法解析)
+    ParseError,
+    /// 未知模块
+    UnknownModule,
+    /// 消息负载不符合消息描述
+    InvalidMessage,
+    /// 模块已禁用
+    ModuleDisabled,
+    /// 协议版本不兼容
+    IncompatibleProtocol,
+    /// JSON 序列化/反序列化错误
+    JsonError,
+    
+    // 模块层
+    /// 模块不支持该消息类型
+    UnknownMessageType,
+    /// 请求参数无效
+    InvalidParams,
+    /// 配置缺失或无效 (如 ASR / LLM 配置)
+    InvalidConfig,
+    /// 缺少可用的客户端连接
+    NotConnected,
+    /// 会话不存在
+    SessionNotFound,
+    /// 已在录音中
+    AlreadyRecording,
+    /// 未在录音中
+    NotRecording,
+    /// 设备错误 (音频设备、剪贴板、全局快捷键、截图、系统通知)
+    DeviceError,
+    /// 资源不存在 (如词典)
+    NotFound,
+    /// 文件读写错误
+    IoError,
+    /// 超出服务器限制 (大小、长度、数量或耗时)
+    LimitExceeded,
+    /// 消息过于频繁 (超出速率限制)
+    RateLimited,
+    /// 网络错误
+    NetworkError,
+    /// 上游服务返回 HTTP 错误状态
+    HttpError,
+    /// 请求超时
+    Timeout,
+    /// 请求已取消
+    Cancelled,
+    /// 与资源的当前状态冲突 (如 Git 合并冲突、推送被拒绝)
+    Conflict,
+    /// 语音转写失败
+    TranscriptionFailed,
+    /// 服务器内部错误
+    Internal,
+    /// 模块处理消息时崩溃 (已重新初始化)
+    ModuleCrashed,
+}
+
+impl ErrorCode {
+    /// 默认是否可重试 (原样重发请求可能成功)
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::NetworkError | ErrorCode::Timeout | ErrorCode::TranscriptionFailed | ErrorCode::RateLimited
+        )
+    }
+    
+    /// 上游 HTTP 状态码是否可重试 (限流或服务端错误)
+    pub fn is_retryable_status(status: u16) -> bool {
+        status == 429 || status >= 500
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(code)) => f.write_str(&code),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// 可映射为错误码的模块内部错误
+pub trait CodedError: std::fmt::Display {
+    /// 错误码
+    fn code(&self) -> ErrorCode;
+    
+    /// 是否可重试
+    fn retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+}
+
+/// 模块处理错误
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("[{code}] {message}")]
+pub struct ModuleError {
+    /// 出错的模块
+    pub module: ModuleType,
+    /// 错误码
+    pub code: ErrorCode,
+    /// 是否可重试
+    pub retryable: bool,
+    /// 错误描述 (仅供展示)
+    pub message: String,
+}
+
+impl ModuleError {
+    /// 创建模块错误，是否可重试由错误码决定
+    pub fn new(module: ModuleType, code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            module,
+            code,
+            retryable: code.is_retryable(),
+            message: message.into(),
+        }
+    }
+    
+    /// 从模块内部错误创建
+    pub fn from_error(module: ModuleType, error: &impl CodedError) -> Self {
+        Self {
+            module,
+            code: error.code(),
+            retryable: error.retryable(),
+            message: error.to_string(),
+        }
+    }
+}
+
 // ============================================================================
 // 路由器错误
 // ============================================================================
@@ -145,13 +352,201 @@ pub enum RouterError {
     
     /// 模块处理错误
     #[error("Module error: {0}")]
-    ModuleError(String),
+    Module(#[from] ModuleError),
+    
+    /// 模块已在启动配置中禁用
+    #[error("Module disabled: {0}")]
+    ModuleDisabled(ModuleType),
+    
+    /// 协议版本不兼容 (发送错误后关闭连接)
+    #[error("Incompatible protocol: {0}")]
+    IncompatibleProtocol(String),
     
     /// JSON 序列化/反序列化错误
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 }
 
+impl RouterError {
+    /// 对应的错误码
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            RouterError::UnknownModule(_) => ErrorCode::UnknownModule,
+            RouterError::InvalidMessage(_) => ErrorCode::InvalidMessage,
+            RouterError::Module(e) => e.code,
+            RouterError::ModuleDisabled(_) => ErrorCode::ModuleDisabled,
+            RouterError::IncompatibleProtocol(_) => ErrorCode::IncompatibleProtocol,
+            RouterError::JsonError(_) => ErrorCode::JsonError,
+        }
+    }
+}
+
+// ============================================================================
+// 消息描述 (system/describe)
+// ============================================================================
+
+/// 字段类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    String,
+    Integer,
+    /// 非负整数 (如任务 ID)
+    Unsigned,
+    Number,
+    Boolean,
+    Array,
+    Object,
+    /// 多种类型均可 (如文本或向量)
+    Any,
+}
+
+/// 消息字段描述
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub kind: FieldKind,
+    pub required: bool,
+    /// 字符串字段的可选值 (为空时不限制)
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub values: &'static [&'static str],
+    /// 数组元素类型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<FieldKind>,
+    /// 对象字段的子字段 (为空时不检查)
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub fields: &'static [FieldSpec],
+}
+
+impl FieldSpec {
+    const fn with(name: &'static str, kind: FieldKind, required: bool) -> Self {
+        Self { name, kind, required, values: &[], items: None, fields: &[] }
+    }
+
+    /// 必填字段
+    pub const fn required(name: &'static str, kind: FieldKind) -> Self {
+        Self::with(name, kind, true)
+    }
+
+    /// 可选字段
+    pub const fn optional(name: &'static str, kind: FieldKind) -> Self {
+        Self::with(name, kind, false)
+    }
+
+    /// 限定字符串的取值
+    pub const fn one_of(mut self, values: &'static [&'static str]) -> Self {
+        self.values = values;
+        self
+    }
+
+    /// 指定数组元素类型
+    pub const fn items(mut self, kind: FieldKind) -> Self {
+        self.items = Some(kind);
+        self
+    }
+
+    /// 指定对象的子字段
+    pub const fn with_fields(mut self, fields: &'static [FieldSpec]) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// 校验字段值，`path` 为字段在负载中的完整路径 (如 `asr_config.primary`)
+    fn validate(&self, value: Option<&serde_json::Value>, path: &str) -> Result<(), String> {
+        let value = match value {
+            Some(value) if !value.is_null() => value,
+            _ if self.required => return Err(format!("{} is required", path)),
+            _ => return Ok(()),
+        };
+        if !self.kind.matches(value) {
+            return Err(format!("{} must be {}", path, self.kind.describe()));
+        }
+        if let (false, Some(text)) = (self.values.is_empty(), value.as_str()) {
+            if !self.values.contains(&text) {
+                return Err(format!("{} must be one of {}", path, self.values.join(", ")));
+            }
+        }
+        if let (Some(kind), Some(items)) = (self.items, value.as_array()) {
+            if let Some(index) = items.iter().position(|item| !kind.matches(item)) {
+                return Err(format!("{}[{}] must be {}", path, index, kind.describe()));
+            }
+        }
+        validate_fields(self.fields, value, &format!("{}.", path))
+    }
+}
+
+/// 按字段描述校验对象，`prefix` 为字段路径前缀
+fn validate_fields(fields: &[FieldSpec], value: &serde_json::Value, prefix: &str) -> Result<(), String> {
+    for field in fields {
+        field.validate(value.get(field.name), &format!("{}{}", prefix, field.name))?;
+    }
+    Ok(())
+}
+
+impl FieldKind {
+    fn matches(&self, value: &serde_json::Value) -> bool {
+        match self {
+            FieldKind::String => value.is_string(),
+            FieldKind::Integer => value.is_i64() || value.is_u64(),
+            FieldKind::Unsigned => value.is_u64(),
+            FieldKind::Number => value.is_number(),
+            FieldKind::Boolean => value.is_boolean(),
+            FieldKind::Array => value.is_array(),
+            FieldKind::Object => value.is_object(),
+            FieldKind::Any => true,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            FieldKind::String => "a string",
+            FieldKind::Integer => "an integer",
+            FieldKind::Unsigned => "a non-negative integer",
+            FieldKind::Number => "a number",
+            FieldKind::Boolean => "a boolean",
+            FieldKind::Array => "an array",
+            FieldKind::Object => "an object",
+            FieldKind::Any => "any value",
+        }
+    }
+}
+
+/// 模块支持的消息类型及其字段
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MessageSpec {
+    #[serde(rename = "type")]
+    pub msg_type: &'static str,
+    pub fields: &'static [FieldSpec],
+    /// 是否在连接任务之外处理 (长时间任务，处理期间同一连接上的其他消息照常处理)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub concurrent: bool,
+}
+
+impl MessageSpec {
+    pub const fn new(msg_type: &'static str, fields: &'static [FieldSpec]) -> Self {
+        Self { msg_type, fields, concurrent: false }
+    }
+    
+    /// 在连接任务之外处理：处理期间同一连接上的 cancel、kill_job 和心跳仍能送达
+    pub const fn concurrent(mut self) -> Self {
+        self.concurrent = true;
+        self
+    }
+    
+    /// 校验消息负载 (未描述的字段不检查)
+    pub fn validate(&self, payload: &serde_json::Value) -> Result<(), String> {
+        validate_fields(self.fields, payload, "")
+    }
+}
+
+/// 单个模块的描述
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleDescription {
+    pub module: ModuleType,
+    pub messages: &'static [MessageSpec],
+}
+
 // ============================================================================
 // 模块处理器 trait
 // ============================================================================
@@ -160,11 +555,40 @@ pub enum RouterError {
 /// 
 /// 各功能模块需要实现此 trait 来处理消息
 #[async_trait::async_trait]
-pub trait ModuleHandler: Send + Sync {
-    /// 获取模块类型
-    #[allow(dead_code)]
+pub trait ModuleHandler: Any + Send + Sync {
+    /// 获取模块类型 (路由器以此作为注册键)
     fn module_type(&self) -> ModuleType;
     
+    /// 模块支持的消息类型及字段 (用于 system/describe)
+    fn messages(&self) -> &'static [MessageSpec];
+    
+    /// 设置默认 WebSocket 发送器 (不推送异步事件的模块无需实现)
+    async fn set_ws_sender(&self, _sender: WsSender) {}
+    
+    /// 新连接完成握手后调用 (需要知道是否有客户端在线的模块实现，如补发离线期间的事件)
+    async fn connected(&self) {}
+    
+    /// 最后一个连接关闭时清理模块资源
+    async fn cleanup(&self) {}
+    
+    /// 取消由 `request_id` 发起的进行中的任务 (system/cancel)
+    /// 
+    /// 返回 false 表示模块没有该请求的任务 (没有长时间任务的模块无需实现)
+    async fn cancel(&self, _request_id: &str) -> bool {
+        false
+    }
+    
+    /// 导出需要跨重启保留的轻量状态 (写入状态快照)
+    /// 
+    /// 每个 vault 调用一次 (`storage::current_vault()` 为要保存的 vault)，只导出属于该 vault 的状态；
+    /// 恢复时 `restore` 同样在快照所属的 vault 中调用。返回 None 表示模块没有需要保留的状态
+    async fn snapshot(&self) -> Option<serde_json::Value> {
+        None
+    }
+    
+    /// 启动时恢复上次 `snapshot` 导出的状态
+    async fn restore(&self, _state: serde_json::Value) {}
+    
     /// 处理消息
     /// 
     /// 返回 Some(response) 表示需要发送响应
@@ -180,53 +604,262 @@ pub trait ModuleHandler: Send + Sync {
 /// 
 /// 负责将消息路由到对应的功能模块
 pub struct MessageRouter {
-    // PTY 模块处理器
-    pty_handler: crate::pty::PtyHandler,
-    // Voice 模块处理器
-    voice_handler: crate::voice::VoiceHandler,
-    // LLM 模块处理器
-    llm_handler: crate::llm::LLMHandler,
-    // Utils 模块处理器
-    utils_handler: crate::utils::UtilsHandler,
+    // 已注册的模块
+    modules: HashMap<ModuleType, ModuleSlot>,
+    // 模块注册顺序
+    order: Vec<ModuleType>,
+    // 启动时禁用的模块
+    disabled_modules: HashSet<ModuleType>,
+    // 中间件 (按注册顺序由外到内)
+    middlewares: Vec<Arc<dyn Middleware>>,
+    // 默认 WebSocket 发送器 (模块重新初始化后重新设置)
+    default_sender: RwLock<Option<WsSender>>,
+}
+
+/// 已注册的模块：当前处理器实例及其构造函数 (崩溃后用于重新初始化)
+struct ModuleSlot {
+    handler: RwLock<Arc<dyn ModuleHandler>>,
+    factory: Box<dyn Fn() -> Arc<dyn ModuleHandler> + Send + Sync>,
+    crashes: AtomicU32,
+}
+
+impl ModuleSlot {
+    fn current(&self) -> Arc<dyn ModuleHandler> {
+        Arc::clone(&self.handler.read().unwrap_or_else(|e| e.into_inner()))
+    }
 }
 
 impl MessageRouter {
-    /// 创建新的消息路由器
+    /// 创建新的消息路由器并注册内置模块
     pub fn new() -> Self {
+        Self::empty()
+            .with_module(crate::pty::PtyHandler::new)
+            .with_module(crate::voice::VoiceHandler::new)
+            .with_module(crate::llm::LLMHandler::new)
+            .with_module(crate::utils::UtilsHandler::new)
+            .with_module(crate::files::FilesHandler::new)
+            .with_module(crate::clipboard::ClipboardHandler::new)
+            .with_module(crate::hotkeys::HotkeysHandler::new)
+            .with_module(crate::capture::CaptureHandler::new)
+            .with_module(crate::git::GitHandler::new)
+            .with_module(crate::scheduler::SchedulerHandler::new)
+            .with_module(crate::notify::NotifyHandler::new)
+            .with_module(crate::playback::PlaybackHandler::new)
+            .with_module(crate::backup::BackupHandler::new)
+            .with_module(crate::system_info::SystemInfoHandler::new)
+            .with_module(crate::meeting::MeetingHandler::new)
+            .with_module(crate::workflow::WorkflowHandler::new)
+            .with_module(crate::vectors::VectorsHandler::new)
+            .with_module(crate::tts::TtsHandler::new)
+            .with_module(crate::system::SystemHandler::new)
+    }
+    
+    /// 创建未注册任何模块的路由器
+    pub fn empty() -> Self {
+        install_panic_hook();
         Self {
-            pty_handler: crate::pty::PtyHandler::new(),
-            voice_handler: crate::voice::VoiceHandler::new(),
-            llm_handler: crate::llm::LLMHandler::new(),
-            utils_handler: crate::utils::UtilsHandler::new(),
+            modules: HashMap::new(),
+            order: Vec::new(),
+            disabled_modules: HashSet::new(),
+            middlewares: Vec::new(),
+            default_sender: RwLock::new(None),
+        }
+    }
+    
+    /// 注册模块，`factory` 创建处理器实例 (模块崩溃后再次调用以重新初始化)
+    /// 
+    /// 模块以 `module_type()` 的名称注册，客户端以该名称发送消息；同一模块重复注册时替换原模块
+    pub fn with_module<H, F>(mut self, factory: F) -> Self
+    where
+        H: ModuleHandler,
+        F: Fn() -> H + Send + Sync + 'static,
+    {
+        let handler: Arc<dyn ModuleHandler> = Arc::new(factory());
+        let module = handler.module_type();
+        module.register();
+        if !self.order.contains(&module) {
+            self.order.push(module);
         }
+        self.modules.insert(module, ModuleSlot {
+            handler: RwLock::new(handler),
+            factory: Box::new(move || Arc::new(factory())),
+            crashes: AtomicU32::new(0),
+        });
+        self
+    }
+    
+    /// 添加中间件，先添加的位于外层
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+    
+    /// 按名称禁用模块 (System 模块不可禁用，未注册的名称被忽略)
+    /// 
+    /// 发往禁用模块的请求返回 MODULE_DISABLED 错误，握手消息中也不再列出这些模块
+    pub fn with_disabled_modules<S: AsRef<str>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.disabled_modules = names
+            .into_iter()
+            .filter_map(|name| self.order.iter().copied().find(|module| module.name() == name.as_ref()))
+            .filter(|module| *module != ModuleType::System)
+            .collect();
+        self
+    }
+    
+    /// 是否注册了该名称的模块
+    pub fn has_module(&self, name: &str) -> bool {
+        self.order.iter().any(|module| module.name() == name)
     }
     
-    /// 设置 WebSocket 发送器 (用于 PTY 输出、Voice 消息、LLM 流式响应等)
+    /// 检查模块是否可用 (已实现且未被禁用)
+    pub fn is_module_enabled(&self, module: ModuleType) -> bool {
+        self.is_module_implemented(module) && !self.disabled_modules.contains(&module)
+    }
+    
+    /// 设置默认 WebSocket 发送器 (用于 PTY 输出、Voice 消息、LLM 流式响应等)
+    /// 
+    /// 消息带有来源连接时，模块优先将事件投递到来源连接
     pub async fn set_ws_sender(&self, sender: WsSender) {
-        self.pty_handler.set_ws_sender(sender.clone()).await;
-        self.voice_handler.set_ws_sender(sender.clone()).await;
-        self.llm_handler.set_ws_sender(sender.clone()).await;
-        self.utils_handler.set_ws_sender(sender).await;
+        *self.default_sender.write().unwrap_or_else(|e| e.into_inner()) = Some(sender.clone());
+        for slot in self.modules.values() {
+            slot.current().set_ws_sender(sender.clone()).await;
+        }
+    }
+    
+    /// 通知各模块有新连接
+    pub async fn connected(&self) {
+        for module in self.registered_modules() {
+            if let Some(handler) = self.handler(module) {
+                handler.connected().await;
+            }
+        }
+    }
+    
+    /// 清理所有模块资源
+    pub async fn cleanup(&self) {
+        for module in self.registered_modules() {
+            if let Some(handler) = self.handler(module) {
+                handler.cleanup().await;
+            }
+        }
+    }
+    
+    /// 导出各启用模块的状态，以模块名为键
+    pub async fn snapshot(&self) -> BTreeMap<String, serde_json::Value> {
+        let mut states = BTreeMap::new();
+        for module in self.registered_modules() {
+            if !self.is_module_enabled(module) {
+                continue;
+            }
+            if let Some(handler) = self.handler(module) {
+                if let Some(state) = handler.snapshot().await {
+                    states.insert(module.name().to_string(), state);
+                }
+            }
+        }
+        states
+    }
+    
+    /// 将快照中的状态交给对应模块恢复 (未知或已禁用的模块忽略)
+    pub async fn restore(&self, states: BTreeMap<String, serde_json::Value>) {
+        for (name, state) in states {
+            let Some(module) = ModuleType::from_name(&name) else {
+                continue;
+            };
+            if !self.is_module_enabled(module) {
+                continue;
+            }
+            if let Some(handler) = self.handler(module) {
+                handler.restore(state).await;
+            }
+        }
+    }
+    
+    /// 取消由 `request_id` 发起的任务，返回任务所属的模块
+    pub async fn cancel(&self, request_id: &str) -> Option<ModuleType> {
+        for module in self.registered_modules() {
+            if !self.is_module_enabled(module) {
+                continue;
+            }
+            if let Some(handler) = self.handler(module) {
+                if handler.cancel(request_id).await {
+                    return Some(module);
+                }
+            }
+        }
+        // 模块没有自行跟踪的任务 (如文件转写、网页剪藏) 按任务登记中的终止方式取消
+        crate::jobs::cancel_request(request_id)
+    }
+    
+    /// 消息是否在连接任务之外处理 (见 `MessageSpec::concurrent`)
+    pub fn is_concurrent(&self, msg: &ModuleMessage) -> bool {
+        self.handler(msg.module).is_some_and(|handler| {
+            handler.messages().iter().any(|spec| spec.msg_type == msg.msg_type && spec.concurrent)
+        })
+    }
+    
+    /// 终止 `system/list_jobs` 列出的任务
+    async fn kill_job(&self, id: u64) -> Result<ServerResponse, RouterError> {
+        let not_found = || ModuleError::new(ModuleType::System, ErrorCode::NotFound, format!("没有进行中的任务: {}", id));
+        let module = match crate::jobs::kill(id).ok_or_else(not_found)? {
+            crate::jobs::Kill::Killed(module) => module,
+            crate::jobs::Kill::ByRequest(module, request_id) => {
+                self.cancel(&request_id).await.ok_or_else(not_found)?;
+                module
+            }
+            crate::jobs::Kill::Unsupported(module) => {
+                return Err(ModuleError::new(
+                    ModuleType::System,
+                    ErrorCode::InvalidParams,
+                    format!("任务 {} 不支持终止，请使用 {} 模块自身的取消消息", id, module),
+                ).into());
+            }
+        };
+        Ok(ServerResponse::new(
+            ModuleType::System,
+            "job_killed",
+            serde_json::json!({ "id": id, "job_module": module }),
+        ))
+    }
+    
+    /// 获取指定类型的模块处理器 (如写入 PTY 数据)
+    /// 
+    /// 模块未注册或处理器类型不符时返回 None
+    pub fn module<H: ModuleHandler>(&self, module: ModuleType) -> Option<Arc<H>> {
+        let handler: Arc<dyn Any + Send + Sync> = self.handler(module)?;
+        handler.downcast().ok()
     }
     
-    /// 获取 PTY 处理器引用 (用于写入数据)
-    pub fn pty_handler(&self) -> &crate::pty::PtyHandler {
-        &self.pty_handler
+    /// 获取模块处理器
+    fn handler(&self, module: ModuleType) -> Option<Arc<dyn ModuleHandler>> {
+        self.modules.get(&module).map(ModuleSlot::current)
     }
     
-    /// 获取 Voice 处理器引用
-    pub fn voice_handler(&self) -> &crate::voice::VoiceHandler {
-        &self.voice_handler
+    /// 已注册的模块 (按注册顺序)
+    pub fn registered_modules(&self) -> Vec<ModuleType> {
+        self.order.clone()
     }
     
-    /// 获取 LLM 处理器引用
-    pub fn llm_handler(&self) -> &crate::llm::LLMHandler {
-        &self.llm_handler
+    /// 各已启用模块支持的消息 (System 模块始终包含在内)
+    pub fn describe(&self) -> Vec<ModuleDescription> {
+        self.enabled_modules()
+            .into_iter()
+            .chain(std::iter::once(ModuleType::System))
+            .filter_map(|module| {
+                Some(ModuleDescription {
+                    module,
+                    messages: self.handler(module)?.messages(),
+                })
+            })
+            .collect()
     }
     
-    /// 获取 Utils 处理器引用
-    pub fn utils_handler(&self) -> &crate::utils::UtilsHandler {
-        &self.utils_handler
+    /// 已启用的功能模块 (用于握手消息)
+    pub fn enabled_modules(&self) -> Vec<ModuleType> {
+        self.registered_modules()
+            .into_iter()
+            .filter(|module| *module != ModuleType::System && self.is_module_enabled(*module))
+            .collect()
     }
     
     /// 解析消息并提取模块类型
@@ -246,76 +879,170 @@ impl MessageRouter {
     /// 用于在消息解析失败时提取模块信息以便返回正确的错误响应
     #[allow(dead_code)]
     pub fn try_parse_module(&self, text: &str) -> Option<ModuleType> {
-        if let Ok(value) = serde_json::from_str::<serde_json::Value>(text) {
-            if let Some(module_str) = value.get("module").and_then(|v| v.as_str()) {
-                return match module_str {
-                    "pty" => Some(ModuleType::Pty),
-                    "voice" => Some(ModuleType::Voice),
-                    "llm" => Some(ModuleType::Llm),
-                    "utils" => Some(ModuleType::Utils),
-                    _ => None,
-                };
-            }
-        }
-        None
+        let value = serde_json::from_str::<serde_json::Value>(text).ok()?;
+        ModuleType::from_name(value.get("module")?.as_str()?)
     }
     
-    /// 路由消息到对应模块
+    /// 路由消息：依次经过中间件后分发到对应模块
     /// 
     /// 返回模块处理结果或错误响应
-    /// 
-    pub async fn route(&self, msg: ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
-        log_info!("路由消息到模块: {}, 类型: {}", msg.module, msg.msg_type);
-        
-        match msg.module {
-            ModuleType::Pty => {
-                // PTY 模块处理
-                log_debug!("PTY 模块消息: {}", msg.msg_type);
-                self.pty_handler.handle(&msg).await
-            }
-            ModuleType::Voice => {
-                // Voice 模块处理
-                log_debug!("Voice 模块消息: {}", msg.msg_type);
-                self.voice_handler.handle(&msg).await
-            }
-            ModuleType::Llm => {
-                // LLM 模块处理
-                log_debug!("LLM 模块消息: {}", msg.msg_type);
-                self.llm_handler.handle(&msg).await
-            }
-            ModuleType::Utils => {
-                // Utils 模块处理
-                log_debug!("Utils 模块消息: {}", msg.msg_type);
-                self.utils_handler.handle(&msg).await
-            }
+    pub async fn route(&self, msg: ModuleMessage) -> RouteResult {
+        Next::new(self, &self.middlewares).run(msg).await
+    }
+    
+    /// 分发消息到对应模块 (中间件链的最内层)
+    pub(crate) async fn dispatch(&self, msg: ModuleMessage) -> RouteResult {
+        let Some(handler) = self.handler(msg.module) else {
+            return Err(RouterError::UnknownModule(msg.module.to_string()));
+        };
+        if self.disabled_modules.contains(&msg.module) {
+            return Err(RouterError::ModuleDisabled(msg.module));
+        }
+        
+        // 分发前按消息描述校验负载 (未描述的消息类型交由模块返回错误)
+        let spec = handler
+            .messages()
+            .iter()
+            .find(|spec| spec.msg_type == msg.msg_type);
+        if let Some(spec) = spec {
+            spec.validate(&msg.payload)
+                .map_err(|e| RouterError::InvalidMessage(format!("{}.{}: {}", msg.module, msg.msg_type, e)))?;
+        }
+        
+        if msg.module == ModuleType::System && msg.msg_type == "describe" {
+            // describe 需要汇总所有模块，由路由器直接应答
+            return Ok(Some(crate::system::DescribeResponse::new(self.describe()).into_response()));
+        }
+        
+        if msg.module == ModuleType::System && msg.msg_type == "cancel" {
+            // 任务可能属于任一模块，由路由器查找所属模块并应答
+            let request_id = msg.request_id().unwrap_or_default();
+            return match self.cancel(&request_id).await {
+                Some(module) => Ok(Some(ServerResponse::new(
+                    ModuleType::System,
+                    "cancelled",
+                    serde_json::json!({ "cancelled_module": module }),
+                ))),
+                None => Err(ModuleError::new(
+                    ModuleType::System,
+                    ErrorCode::NotFound,
+                    format!("没有进行中的请求: {}", request_id),
+                ).into()),
+            };
+        }
+        
+        if msg.module == ModuleType::System && msg.msg_type == "kill_job" {
+            // 没有自己终止方式的任务按请求 ID 交给所属模块取消，由路由器应答 (id 已按消息描述校验)
+            let id: u64 = msg.get_field("id")
+                .ok_or_else(|| RouterError::InvalidMessage("system.kill_job: id must be a non-negative integer".to_string()))?;
+            return self.kill_job(id).await.map(Some);
+        }
+        
+        // 模块处理中的 panic 不影响连接任务：记录崩溃、重新初始化模块并返回 MODULE_CRASHED。
+        // 处理期间记录的审计操作归属于该消息的 request_id，按 vault 保存的数据使用来源连接声明的 vault
+        let vault = match &msg.connection {
+            Some(connection) => connection.vault.lock().await.clone(),
+            None => None,
+        };
+        let handled = crate::storage::vault_scope(vault, crate::audit::scope(msg.request_id(), handler.handle(&msg)));
+        match AssertUnwindSafe(handled).catch_unwind().await {
+            Ok(result) => result,
+            Err(payload) => Err(self.recover(&msg, panic_message(payload.as_ref())).await.into()),
+        }
+    }
+    
+    /// 处理模块崩溃：记录 panic 信息和调用栈，通知所有客户端，并用新实例替换崩溃的处理器
+    async fn recover(&self, msg: &ModuleMessage, reason: String) -> ModuleError {
+        let module = msg.module;
+        let crash_id = format!("crash-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        let backtrace = LAST_PANIC.with(|last| last.borrow_mut().take()).unwrap_or_default();
+        log_error!("[{}] 模块 {} 处理 {} 时崩溃: {}\n{}", crash_id, module, msg.msg_type, reason, backtrace);
+        
+        let Some(slot) = self.modules.get(&module) else {
+            return ModuleError::new(module, ErrorCode::ModuleCrashed, format!("模块崩溃 ({})", crash_id));
+        };
+        let crashes = slot.crashes.fetch_add(1, Ordering::Relaxed) + 1;
+        
+        // 尽力清理旧实例 (状态可能已损坏，清理时再次 panic 则忽略)
+        let old = slot.current();
+        let _ = AssertUnwindSafe(old.cleanup()).catch_unwind().await;
+        LAST_PANIC.with(|last| last.borrow_mut().take());
+        
+        let handler = (slot.factory)();
+        let sender = self.default_sender.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(sender) = sender {
+            // 通知所有客户端该模块已重置 (进行中的会话、录音和流已丢失)
+            let event = ServerResponse::new(ModuleType::System, "module_crashed", serde_json::json!({
+                "crashed_module": module,
+                "crash_id": crash_id,
+                "crashes": crashes,
+            }));
+            let _ = sender.lock().await.send(Message::Text(event.to_json().into())).await;
+            handler.set_ws_sender(sender).await;
         }
+        *slot.handler.write().unwrap_or_else(|e| e.into_inner()) = handler;
+        log_info!("[{}] 模块 {} 已重新初始化 (累计崩溃 {} 次)", crash_id, module, crashes);
+        
+        ModuleError::new(module, ErrorCode::ModuleCrashed, format!("模块崩溃，已重新初始化 (日志引用: {})", crash_id))
     }
     
     /// 创建错误响应
     /// 
     pub fn create_error_response(&self, module: ModuleType, error: &RouterError) -> ServerResponse {
-        let (code, message) = match error {
-            RouterError::UnknownModule(m) => ("UNKNOWN_MODULE", format!("未知模块: {}", m)),
-            RouterError::InvalidMessage(m) => ("INVALID_MESSAGE", format!("无效消息: {}", m)),
-            RouterError::ModuleError(m) => ("MODULE_ERROR", m.clone()),
-            RouterError::JsonError(e) => ("JSON_ERROR", format!("JSON 错误: {}", e)),
+        let message = match error {
+            RouterError::UnknownModule(m) => format!("未知模块: {}", m),
+            RouterError::InvalidMessage(m) => format!("无效消息: {}", m),
+            RouterError::Module(e) => {
+                let mut response = ServerResponse::error(e.module, e.code, &e.message);
+                response.payload["retryable"] = e.retryable.into();
+                return response;
+            }
+            RouterError::ModuleDisabled(m) => format!("模块已禁用: {}", m),
+            RouterError::IncompatibleProtocol(m) => format!("协议版本不兼容: {}", m),
+            RouterError::JsonError(e) => format!("JSON 错误: {}", e),
         };
         
-        ServerResponse::error(module, code, &message)
+        let mut response = ServerResponse::error(module, error.code(), &message);
+        if let RouterError::IncompatibleProtocol(_) = error {
+            // 附带服务器支持的协议版本区间，便于客户端提示升级
+            response.payload["protocol_version"] = crate::system::PROTOCOL_VERSION.into();
+            response.payload["min_protocol_version"] = crate::system::MIN_PROTOCOL_VERSION.into();
+        }
+        response
     }
     
     /// 检查模块是否已实现
-    #[allow(dead_code)]
     pub fn is_module_implemented(&self, module: ModuleType) -> bool {
-        match module {
-            ModuleType::Pty => true,    // PTY 模块已实现
-            ModuleType::Voice => true,  // Voice 模块已实现
-            ModuleType::Llm => true,    // LLM 模块已实现
-            ModuleType::Utils => true,  // Utils 模块已实现
-        }
+        self.modules.contains_key(&module)
     }
 }
 
+thread_local! {
+    /// 当前线程最近一次 panic 的位置和调用栈 (由 panic hook 记录，模块崩溃时取出写入日志)
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// 提取 panic 信息
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// 安装 panic hook：在保留默认输出的同时记录 panic 位置和调用栈
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let location = info.location().map(|l| l.to_string()).unwrap_or_default();
+            let backtrace = std::backtrace::Backtrace::force_capture();
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(format!("at {}\n{}", location, backtrace)));
+            previous(info);
+        }));
+    });
+}
+
 impl Default for MessageRouter {
     fn default() -> Self {
         Self::new()
@@ -400,6 +1127,7 @@ mod tests {
         assert_eq!(router.try_parse_module(r#"{"module": "voice"}"#), Some(ModuleType::Voice));
         assert_eq!(router.try_parse_module(r#"{"module": "llm"}"#), Some(ModuleType::Llm));
         assert_eq!(router.try_parse_module(r#"{"module": "utils"}"#), Some(ModuleType::Utils));
+        assert_eq!(router.try_parse_module(r#"{"module": "system"}"#), Some(ModuleType::System));
     }
     
     #[test]
@@ -418,14 +1146,15 @@ mod tests {
     
     #[test]
     fn test_server_response_error() {
-        let response = ServerResponse::error(ModuleType::Pty, "TEST_ERROR", "Test error message");
+        let response = ServerResponse::error(ModuleType::Pty, ErrorCode::SessionNotFound, "Test error message");
         
         assert_eq!(response.module, ModuleType::Pty);
         assert_eq!(response.msg_type, "error");
         
         let payload = response.payload.as_object().unwrap();
-        assert_eq!(payload.get("code").unwrap().as_str().unwrap(), "TEST_ERROR");
+        assert_eq!(payload.get("code").unwrap().as_str().unwrap(), "SESSION_NOT_FOUND");
         assert_eq!(payload.get("message").unwrap().as_str().unwrap(), "Test error message");
+        assert_eq!(payload.get("retryable").unwrap().as_bool(), Some(false));
     }
     
     #[test]
@@ -444,6 +1173,7 @@ mod tests {
         assert_eq!(format!("{}", ModuleType::Voice), "voice");
         assert_eq!(format!("{}", ModuleType::Llm), "llm");
         assert_eq!(format!("{}", ModuleType::Utils), "utils");
+        assert_eq!(format!("{}", ModuleType::System), "system");
     }
     
     #[test]
@@ -463,15 +1193,272 @@ mod tests {
     #[test]
     fn test_create_error_response_module_error() {
         let router = MessageRouter::new();
-        let error = RouterError::ModuleError("Something went wrong".to_string());
-        let response = router.create_error_response(ModuleType::Llm, &error);
+        let error = RouterError::from(ModuleError::new(ModuleType::Llm, ErrorCode::NetworkError, "Something went wrong"));
+        let response = router.create_error_response(ModuleType::Utils, &error);
         
+        // 错误响应的模块取自模块错误本身
         assert_eq!(response.module, ModuleType::Llm);
         assert_eq!(response.msg_type, "error");
         
         let payload = response.payload.as_object().unwrap();
-        assert_eq!(payload.get("code").unwrap().as_str().unwrap(), "MODULE_ERROR");
+        assert_eq!(payload.get("code").unwrap().as_str().unwrap(), "NETWORK_ERROR");
         assert_eq!(payload.get("message").unwrap().as_str().unwrap(), "Something went wrong");
+        assert_eq!(payload.get("retryable").unwrap().as_bool(), Some(true));
+        
+        // 可重试标志可由具体错误覆盖
+        let mut error = ModuleError::new(ModuleType::Utils, ErrorCode::HttpError, "Server returned HTTP 404");
+        error.retryable = ErrorCode::is_retryable_status(404);
+        let response = router.create_error_response(ModuleType::Utils, &RouterError::from(error));
+        assert_eq!(response.payload["code"], "HTTP_ERROR");
+        assert_eq!(response.payload["retryable"], false);
+    }
+    
+    #[test]
+    fn test_create_error_response_incompatible_protocol() {
+        let router = MessageRouter::new();
+        let error = RouterError::IncompatibleProtocol("client supports 2..=2".to_string());
+        let response = router.create_error_response(ModuleType::System, &error);
+        
+        assert_eq!(response.payload["code"], "INCOMPATIBLE_PROTOCOL");
+        assert_eq!(response.payload["protocol_version"], crate::system::PROTOCOL_VERSION);
+        assert_eq!(response.payload["min_protocol_version"], crate::system::MIN_PROTOCOL_VERSION);
+    }
+    
+    #[test]
+    fn test_enabled_modules() {
+        let router = MessageRouter::new();
+        let registered = router.registered_modules();
+        assert!(registered.contains(&ModuleType::System));
+        assert_eq!(
+            router.enabled_modules(),
+            registered.iter().copied().filter(|module| *module != ModuleType::System).collect::<Vec<_>>()
+        );
+        // 注册过的模块都能按名称解析
+        assert!(registered.iter().all(|module| ModuleType::from_name(module.name()) == Some(*module)));
+    }
+    
+    #[tokio::test]
+    async fn test_disabled_modules() {
+        let router = MessageRouter::new()
+            .with_disabled_modules(["voice", "llm", "system", "unregistered_test"]);
+        assert_eq!(
+            router.enabled_modules(),
+            router.registered_modules().into_iter()
+                .filter(|module| ![ModuleType::Voice, ModuleType::Llm, ModuleType::System].contains(module))
+                .collect::<Vec<_>>()
+        );
+        assert!(router.is_module_enabled(ModuleType::System));
+        
+        let msg = router.parse_message(r#"{"module": "voice", "type": "cancel_recording", "request_id": "req-1"}"#).unwrap();
+        let error = router.route(msg).await.unwrap_err();
+        assert!(matches!(error, RouterError::ModuleDisabled(ModuleType::Voice)));
+        
+        let response = router.create_error_response(ModuleType::Voice, &error);
+        assert_eq!(response.msg_type, "error");
+        assert_eq!(response.payload["code"], "MODULE_DISABLED");
+    }
+    
+    #[tokio::test]
+    async fn test_extension_module() {
+        struct EchoHandler;
+        
+        const ECHO_MESSAGES: &[MessageSpec] = &[
+            MessageSpec::new("ping", &[FieldSpec::required("text", FieldKind::String)]),
+        ];
+        
+        #[async_trait::async_trait]
+        impl ModuleHandler for EchoHandler {
+            fn module_type(&self) -> ModuleType {
+                ModuleType::named("echo_test")
+            }
+            
+            fn messages(&self) -> &'static [MessageSpec] {
+                ECHO_MESSAGES
+            }
+            
+            async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+                Ok(Some(ServerResponse::new(self.module_type(), "pong", msg.payload.clone())))
+            }
+        }
+        
+        assert!(MessageRouter::new().parse_message(r#"{"module": "unregistered_test", "type": "ping"}"#).is_err());
+        
+        let router = MessageRouter::new().with_module(|| EchoHandler);
+        assert!(router.has_module("echo_test"));
+        assert_eq!(router.registered_modules().last(), Some(&ModuleType::named("echo_test")));
+        assert_eq!(router.enabled_modules().last(), Some(&ModuleType::named("echo_test")));
+        assert!(router.describe().iter().any(|d| d.module == ModuleType::named("echo_test")));
+        
+        let msg = router.parse_message(r#"{"module": "echo_test", "type": "ping", "text": "hi"}"#).unwrap();
+        let response = router.route(msg).await.unwrap().unwrap();
+        assert_eq!(serde_json::to_value(&response).unwrap()["module"], "echo_test");
+        assert_eq!(response.payload["text"], "hi");
+        
+        assert!(router.module::<crate::pty::PtyHandler>(ModuleType::Pty).is_some());
+        assert!(router.module::<crate::pty::PtyHandler>(ModuleType::Voice).is_none());
+        
+        // 未注册的模块即使名称可解析也返回 UNKNOWN_MODULE
+        let router = MessageRouter::empty().with_module(crate::system::SystemHandler::new);
+        assert_eq!(router.enabled_modules(), vec![]);
+        let msg = router.parse_message(r#"{"module": "pty", "type": "resize", "session_id": "abc"}"#).unwrap();
+        assert!(matches!(router.route(msg).await, Err(RouterError::UnknownModule(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_cancel() {
+        struct JobHandler;
+        
+        #[async_trait::async_trait]
+        impl ModuleHandler for JobHandler {
+            fn module_type(&self) -> ModuleType {
+                ModuleType::named("cancel_test")
+            }
+            
+            fn messages(&self) -> &'static [MessageSpec] {
+                &[]
+            }
+            
+            async fn cancel(&self, request_id: &str) -> bool {
+                request_id == "req-1"
+            }
+            
+            async fn handle(&self, _msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+                Ok(None)
+            }
+        }
+        
+        let router = MessageRouter::new().with_module(|| JobHandler);
+        let msg = router.parse_message(r#"{"module": "system", "type": "cancel", "request_id": "req-1"}"#).unwrap();
+        let response = router.route(msg).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "cancelled");
+        assert_eq!(response.payload["cancelled_module"], "cancel_test");
+        
+        // 没有进行中的任务时返回 NOT_FOUND，缺少 request_id 时校验失败
+        let msg = router.parse_message(r#"{"module": "system", "type": "cancel", "request_id": "req-2"}"#).unwrap();
+        let error = router.route(msg).await.unwrap_err();
+        assert_eq!(router.create_error_response(ModuleType::System, &error).payload["code"], "NOT_FOUND");
+        let msg = router.parse_message(r#"{"module": "system", "type": "cancel"}"#).unwrap();
+        assert!(matches!(router.route(msg).await, Err(RouterError::InvalidMessage(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_module_crash_recovery() {
+        use std::sync::atomic::AtomicUsize;
+        
+        static INSTANCES: AtomicUsize = AtomicUsize::new(0);
+        
+        struct CrashHandler {
+            generation: usize,
+        }
+        
+        impl CrashHandler {
+            fn new() -> Self {
+                Self { generation: INSTANCES.fetch_add(1, Ordering::SeqCst) + 1 }
+            }
+        }
+        
+        #[async_trait::async_trait]
+        impl ModuleHandler for CrashHandler {
+            fn module_type(&self) -> ModuleType {
+                ModuleType::named("crash_test")
+            }
+            
+            fn messages(&self) -> &'static [MessageSpec] {
+                &[]
+            }
+            
+            async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+                if msg.msg_type == "boom" {
+                    panic!("boom in generation {}", self.generation);
+                }
+                Ok(Some(ServerResponse::new(self.module_type(), "generation", serde_json::json!({ "generation": self.generation }))))
+            }
+        }
+        
+        let router = MessageRouter::empty().with_module(CrashHandler::new);
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = futures_util::sink::unfold(Arc::clone(&received), |received, msg: Message| async move {
+            received.lock().unwrap().push(msg);
+            Ok::<_, tokio_tungstenite::tungstenite::Error>(received)
+        });
+        router.set_ws_sender(Arc::new(tokio::sync::Mutex::new(Box::pin(sink)))).await;
+        let send = |msg_type: &str| router.parse_message(&format!(r#"{{"module": "crash_test", "type": "{}"}}"#, msg_type)).unwrap();
+        
+        let error = router.route(send("boom")).await.unwrap_err();
+        assert_eq!(error.code(), ErrorCode::ModuleCrashed);
+        let response = router.create_error_response(ModuleType::named("crash_test"), &error);
+        assert_eq!(response.payload["code"], "MODULE_CRASHED");
+        assert!(response.payload["message"].as_str().unwrap().contains("crash-"));
+        
+        // 模块已用新实例替换，后续消息正常处理
+        let response = router.route(send("ping")).await.unwrap().unwrap();
+        assert_eq!(response.payload["generation"], 2);
+        
+        let received = received.lock().unwrap();
+        let event: serde_json::Value = serde_json::from_str(received[0].to_text().unwrap()).unwrap();
+        assert_eq!(event["type"], "module_crashed");
+        assert_eq!(event["crashed_module"], "crash_test");
+        assert_eq!(event["crashes"], 1);
+    }
+    
+    #[tokio::test]
+    async fn test_validate_payload() {
+        async fn invalid(router: &MessageRouter, json: &str) -> String {
+            match router.route(router.parse_message(json).unwrap()).await {
+                Err(RouterError::InvalidMessage(e)) => e,
+                other => panic!("expected InvalidMessage, got {:?}", other.map(|r| r.map(|r| r.msg_type))),
+            }
+        }
+        let router = MessageRouter::new();
+        
+        let asr = r#""asr_config": {"primary": {"provider": "azure", "mode": "http"}, "enable_fallback": false}"#;
+        assert_eq!(
+            invalid(&router, &format!(r#"{{"module": "voice", "type": "start_recording", "mode": "press", {}}}"#, asr)).await,
+            "voice.start_recording: asr_config.primary.provider must be one of qwen, doubao, sensevoice, whisper, openai, vosk"
+        );
+        assert_eq!(
+            invalid(&router, r#"{"module": "voice", "type": "start_recording", "mode": "press"}"#).await,
+            "voice.start_recording: asr_config is required"
+        );
+        assert_eq!(
+            invalid(&router, r#"{"module": "pty", "type": "resize", "session_id": "abc", "cols": "80"}"#).await,
+            "pty.resize: cols must be an integer"
+        );
+        assert_eq!(
+            invalid(&router, r#"{"module": "utils", "type": "hash", "texts": ["a", 1], "request_id": "req-1"}"#).await,
+            "utils.hash: texts[1] must be a string"
+        );
+        
+        // 未描述的字段不检查，null 视为未设置
+        let msg = router.parse_message(r#"{"module": "system", "type": "get_logs", "limit": null, "extra": 1}"#).unwrap();
+        assert!(router.route(msg).await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_describe() {
+        let router = MessageRouter::new().with_disabled_modules(["voice"]);
+        let modules: Vec<ModuleType> = router.describe().iter().map(|d| d.module).collect();
+        assert_eq!(modules, vec![ModuleType::Pty, ModuleType::Llm, ModuleType::Utils, ModuleType::Files, ModuleType::Clipboard, ModuleType::Hotkeys, ModuleType::Capture, ModuleType::Git, ModuleType::Scheduler, ModuleType::Notify, ModuleType::Playback, ModuleType::Backup, ModuleType::SystemInfo, ModuleType::Meeting, ModuleType::Workflow, ModuleType::Vectors, ModuleType::Tts, ModuleType::System]);
+        
+        for description in router.describe() {
+            let mut types: Vec<&str> = description.messages.iter().map(|m| m.msg_type).collect();
+            assert!(!types.is_empty());
+            types.sort_unstable();
+            types.dedup();
+            assert_eq!(types.len(), description.messages.len(), "{} 中有重复的消息类型", description.module);
+        }
+        
+        let msg = router.parse_message(r#"{"module": "system", "type": "describe", "request_id": "req-1"}"#).unwrap();
+        let response = router.route(msg).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "description");
+        let payload = &response.payload;
+        assert_eq!(payload["protocol_version"], crate::system::PROTOCOL_VERSION);
+        assert_eq!(payload["modules"][0]["module"], "pty");
+        let resize = payload["modules"][0]["messages"].as_array().unwrap()
+            .iter()
+            .find(|m| m["type"] == "resize")
+            .unwrap();
+        assert_eq!(resize["fields"][0], serde_json::json!({"name": "session_id", "type": "string", "required": true}));
     }
     
     #[tokio::test]
@@ -520,6 +1507,27 @@ mod tests {
         assert_eq!(missing, None);
     }
     
+    #[test]
+    fn test_request_id_echo() {
+        let router = MessageRouter::new();
+        let json = r#"{"module": "pty", "type": "resize", "request_id": "req-1", "cols": 80}"#;
+        
+        let msg = router.parse_message(json).unwrap();
+        assert_eq!(msg.request_id(), Some("req-1".to_string()));
+        
+        let response = ServerResponse::error(ModuleType::Pty, ErrorCode::Internal, "failed")
+            .with_request_id(msg.request_id().as_deref());
+        assert_eq!(response.payload["request_id"], "req-1");
+        
+        // 模块自行设置的 request_id 不会被覆盖
+        let response = ServerResponse::new(ModuleType::Utils, "done", serde_json::json!({"request_id": "own"}))
+            .with_request_id(Some("req-1"));
+        assert_eq!(response.payload["request_id"], "own");
+        
+        let msg = router.parse_message(r#"{"module": "voice", "type": "stop_recording"}"#).unwrap();
+        assert_eq!(msg.request_id(), None);
+    }
+    
     #[test]
     fn test_module_type_serialization() {
         // 测试序列化
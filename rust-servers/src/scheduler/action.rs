@@ -0,0 +1,23 @@
+// 任务动作
+// 任务可以指定服务器内置的动作 (action)，到期时由服务器直接执行，不需要客户端处理 fired 事件。
+// 动作以任务的 data 为参数，登记任务时先校验；执行结果以 action_completed / action_failed 事件广播
+
+use crate::router::ModuleError;
+
+/// 服务器内置的任务动作
+#[async_trait::async_trait]
+pub trait JobAction: Send + Sync {
+    /// 校验任务的 data (登记任务时调用)
+    fn validate(&self, data: &serde_json::Value) -> Result<(), ModuleError>;
+
+    /// 执行动作，返回 action_completed 事件的 result
+    async fn run(&self, data: serde_json::Value) -> Result<serde_json::Value, ModuleError>;
+}
+
+/// 按名称查找内置动作
+pub fn lookup(name: &str) -> Option<&'static dyn JobAction> {
+    match name {
+        "backup" => Some(&crate::backup::ScheduledBackup),
+        _ => None,
+    }
+}
@@ -0,0 +1,352 @@
+// 计划任务定义与持久化
+// 任务按 cron 表达式 (本地时间，5 段为分钟精度，6 段时第一段为秒) 或一次性的时间点触发。
+// 到期计算以调用方传入的当前时间为准，不直接读取时钟
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 任务文件名
+const JOBS_FILE_NAME: &str = "jobs.json";
+
+/// 任务文件格式版本
+const JOBS_VERSION: u32 = 1;
+
+/// 统计错过次数的上限 (如每秒触发的任务离线一周时不逐一计数)
+pub const MAX_MISSED_COUNT: u32 = 1000;
+
+/// 超过到期时间多久才触发视为错过 (秒)，短暂的计时误差不算错过
+const MISSED_GRACE_SECS: i64 = 60;
+
+/// 解析 cron 表达式 (支持可选的秒字段和 @daily 等别名)
+pub fn parse_cron(expr: &str) -> Result<Cron, String> {
+    Cron::new(expr)
+        .with_seconds_optional()
+        .parse()
+        .map_err(|e| format!("无效的 cron 表达式 '{}': {}", expr, e))
+}
+
+/// 解析时间点：RFC 3339，或不带时区的本地时间 (如 "2026-10-16T09:00")
+pub fn parse_time(text: &str) -> Result<DateTime<Local>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Local));
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .ok_or_else(|| format!("无效的时间: {}", text))
+}
+
+// ============================================================================
+// 任务
+// ============================================================================
+
+/// 触发时间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    /// cron 表达式，重复触发
+    Cron(String),
+    /// 一次性时间点，触发后删除任务
+    At(DateTime<Local>),
+}
+
+/// 离线期间错过的触发如何处理
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedPolicy {
+    /// 客户端重新连接后补发一次
+    #[default]
+    Fire,
+    /// 跳过，等待下一次触发
+    Skip,
+}
+
+/// 计划任务
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    /// 触发时原样返回给客户端的数据 (指定 action 时为动作的参数)
+    #[serde(default)]
+    pub data: serde_json::Value,
+    /// 到期时由服务器执行的内置动作
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub missed: MissedPolicy,
+    #[serde(default)]
+    pub paused: bool,
+    pub created_at: DateTime<Local>,
+    /// 下一次触发时间 (暂停时为空)
+    #[serde(default)]
+    pub next_run: Option<DateTime<Local>>,
+    /// 上一次触发时间
+    #[serde(default)]
+    pub last_run: Option<DateTime<Local>>,
+    /// 登记任务的连接声明的 vault (只有同一 vault 的连接能看到和修改该任务)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault: Option<String>,
+}
+
+/// 一次到期
+#[derive(Debug, Clone, PartialEq)]
+pub struct Due {
+    /// 本次触发对应的计划时间 (错过多次时为最近的一次)
+    pub scheduled_at: DateTime<Local>,
+    /// 未能按时触发的次数 (按时触发时为 0，最多统计 MAX_MISSED_COUNT 次)
+    pub missed_count: u32,
+}
+
+impl Job {
+    /// 创建任务并计算首次触发时间，任务不会再触发时 (一次性时间点已过去、cron 没有下一次) 返回错误
+    pub fn new(
+        id: String,
+        name: Option<String>,
+        schedule: Schedule,
+        data: serde_json::Value,
+        missed: MissedPolicy,
+        now: DateTime<Local>,
+    ) -> Result<Self, String> {
+        let mut job = Self {
+            id,
+            name,
+            schedule,
+            data,
+            action: None,
+            missed,
+            paused: false,
+            created_at: now,
+            next_run: None,
+            last_run: None,
+            vault: None,
+        };
+        job.next_run = match &job.schedule {
+            Schedule::At(at) if *at <= now => return Err(format!("时间已过去: {}", at.to_rfc3339())),
+            Schedule::At(at) => Some(*at),
+            Schedule::Cron(expr) => Some(next_occurrence(&parse_cron(expr)?, now)
+                .ok_or_else(|| format!("cron 表达式没有下一次触发时间: {}", expr))?),
+        };
+        Ok(job)
+    }
+
+    /// 任务表中的键 (不同 vault 可以使用相同的任务 ID)
+    pub fn key(&self) -> (Option<String>, String) {
+        (self.vault.clone(), self.id.clone())
+    }
+
+    /// 一次性任务已触发 (或已跳过)，可以删除
+    pub fn finished(&self) -> bool {
+        matches!(self.schedule, Schedule::At(_)) && !self.paused && self.next_run.is_none()
+    }
+
+    /// 暂停：不再触发，恢复前错过的触发不补发
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.next_run = None;
+    }
+
+    /// 恢复：cron 任务从现在起计算下一次触发；一次性任务的时间点已过去时按错过处理
+    pub fn resume(&mut self, now: DateTime<Local>) {
+        self.paused = false;
+        self.next_run = match &self.schedule {
+            Schedule::At(at) => Some(*at),
+            Schedule::Cron(expr) => parse_cron(expr).ok().and_then(|cron| next_occurrence(&cron, now)),
+        };
+    }
+
+    /// 取出到期的触发并推进下一次触发时间，未到期时返回 None
+    ///
+    /// `catch_up` 为 true 时 (客户端离线期间到期) 即使刚到期也算错过
+    pub fn take_due(&mut self, now: DateTime<Local>, catch_up: bool) -> Option<Due> {
+        let first = self.next_run.filter(|next| *next <= now)?;
+        let (scheduled_at, count, next_run) = match &self.schedule {
+            Schedule::At(at) => (*at, 1, None),
+            Schedule::Cron(expr) => match parse_cron(expr) {
+                Ok(cron) => {
+                    let mut latest = first;
+                    let mut count = 1;
+                    for time in cron.iter_after(first) {
+                        if time > now || count >= MAX_MISSED_COUNT {
+                            break;
+                        }
+                        latest = time;
+                        count += 1;
+                    }
+                    (latest, count, next_occurrence(&cron, now))
+                }
+                Err(_) => (first, 1, None),
+            },
+        };
+        self.next_run = next_run;
+        let late = catch_up || (now - scheduled_at).num_seconds() > MISSED_GRACE_SECS;
+        Some(Due {
+            scheduled_at,
+            missed_count: if late { count } else { count - 1 },
+        })
+    }
+}
+
+/// `time` 之后 (不含) 的下一次触发时间
+fn next_occurrence(cron: &Cron, time: DateTime<Local>) -> Option<DateTime<Local>> {
+    cron.find_next_occurrence(&time, false).ok()
+}
+
+// ============================================================================
+// 持久化
+// ============================================================================
+
+/// 任务文件
+#[derive(Debug, Serialize, Deserialize)]
+struct JobsFile {
+    version: u32,
+    jobs: Vec<Job>,
+}
+
+/// 写入 `dir` 下的任务文件 (先写临时文件再替换；任务数据可能包含提醒内容，文件仅当前用户可读)
+pub fn save(dir: &Path, jobs: &[Job]) -> io::Result<()> {
+    let path = dir.join(JOBS_FILE_NAME);
+    let temp = dir.join(format!("{}.tmp", JOBS_FILE_NAME));
+    let file = JobsFile { version: JOBS_VERSION, jobs: jobs.to_vec() };
+    let contents = serde_json::to_vec_pretty(&file).map_err(io::Error::other)?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    io::Write::write_all(&mut options.open(&temp)?, &contents)?;
+    fs::rename(&temp, &path)
+}
+
+/// 读取 `dir` 下的任务文件 (不存在时为空)，cron 表达式已无法解析的任务被丢弃
+pub fn load(dir: &Path) -> io::Result<Vec<Job>> {
+    let text = match fs::read_to_string(dir.join(JOBS_FILE_NAME)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let file: JobsFile = serde_json::from_str(&text)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if file.version != JOBS_VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("不支持的任务文件版本: {}", file.version)));
+    }
+    Ok(file.jobs.into_iter()
+        .filter(|job| match &job.schedule {
+            Schedule::Cron(expr) => parse_cron(expr).is_ok(),
+            Schedule::At(_) => true,
+        })
+        .collect())
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, 16, hour, minute, 0).unwrap()
+    }
+
+    fn hourly(now: DateTime<Local>) -> Job {
+        Job::new("hourly".into(), None, Schedule::Cron("0 * * * *".into()), serde_json::Value::Null, MissedPolicy::Fire, now).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(parse_cron("*/5 * * * *").is_ok());
+        assert!(parse_cron("30 0 3 * * *").is_ok());
+        assert!(parse_cron("@daily").is_ok());
+        assert!(parse_cron("61 * * * *").is_err());
+
+        assert_eq!(parse_time("2026-10-16T09:30").unwrap(), time(9, 30));
+        assert_eq!(parse_time("2026-10-16T01:00:00Z").unwrap(), chrono::Utc.with_ymd_and_hms(2026, 10, 16, 1, 0, 0).unwrap());
+        assert!(parse_time("tomorrow").is_err());
+    }
+
+    #[test]
+    fn test_take_due() {
+        let mut job = hourly(time(9, 30));
+        assert_eq!(job.next_run, Some(time(10, 0)));
+        assert_eq!(job.take_due(time(9, 59), false), None);
+
+        // 按时触发
+        let due = job.take_due(time(10, 0) + chrono::Duration::seconds(2), false).unwrap();
+        assert_eq!(due, Due { scheduled_at: time(10, 0), missed_count: 0 });
+        assert_eq!(job.next_run, Some(time(11, 0)));
+
+        // 11:00、12:00、13:00 都已过去，补发最近的一次
+        let due = job.take_due(time(13, 30), false).unwrap();
+        assert_eq!(due, Due { scheduled_at: time(13, 0), missed_count: 3 });
+        assert_eq!(job.next_run, Some(time(14, 0)));
+
+        // 客户端离线期间到期的，即使刚到期也算错过
+        let due = job.take_due(time(14, 0), true).unwrap();
+        assert_eq!(due.missed_count, 1);
+
+        // 暂停期间的触发不补发
+        job.pause();
+        assert_eq!(job.take_due(time(18, 0), false), None);
+        job.resume(time(18, 30));
+        assert_eq!(job.next_run, Some(time(19, 0)));
+        assert!(!job.finished());
+    }
+
+    #[test]
+    fn test_one_shot() {
+        let at = Schedule::At(time(9, 0));
+        assert!(Job::new("late".into(), None, at.clone(), serde_json::Value::Null, MissedPolicy::Fire, time(9, 0)).is_err());
+        assert!(Job::new("never".into(), None, Schedule::Cron("0 0 30 2 *".into()), serde_json::Value::Null, MissedPolicy::Fire, time(9, 0)).is_err());
+
+        let mut job = Job::new("once".into(), None, at, serde_json::Value::Null, MissedPolicy::Skip, time(8, 0)).unwrap();
+        job.pause();
+        assert!(!job.finished());
+        // 暂停期间时间点已过去，恢复后立即按错过处理
+        job.resume(time(10, 0));
+        let due = job.take_due(time(10, 0), false).unwrap();
+        assert_eq!(due, Due { scheduled_at: time(9, 0), missed_count: 1 });
+        assert!(job.finished());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("scheduler-job-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        assert!(load(&dir).unwrap().is_empty());
+
+        let mut job = hourly(time(9, 30));
+        job.name = Some("整点".into());
+        job.data = serde_json::json!({ "action": "index" });
+        save(&dir, &[job.clone()]).unwrap();
+        assert_eq!(load(&dir).unwrap(), vec![job.clone()]);
+
+        // 调度方式与其他字段平铺在同一对象中
+        let text = fs::read_to_string(dir.join(JOBS_FILE_NAME)).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["jobs"][0]["cron"], "0 * * * *");
+        assert_eq!(value["jobs"][0]["missed"], "fire");
+
+        // 无法解析的 cron 表达式丢弃
+        job.schedule = Schedule::Cron("bad".into());
+        save(&dir, &[job]).unwrap();
+        assert!(load(&dir).unwrap().is_empty());
+
+        fs::write(dir.join(JOBS_FILE_NAME), "{").unwrap();
+        assert!(load(&dir).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
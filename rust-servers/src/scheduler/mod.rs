@@ -0,0 +1,561 @@
+// 计划任务模块
+// 客户端登记 cron 任务或一次性任务 (提醒、夜间索引 vault、定时 Git 提交等)，任务保存在数据目录的 schedules 区域，
+// 服务器重启后继续生效。任务到期时广播 fired 事件，由客户端执行实际操作。
+// 没有客户端连接期间 (包括服务器未运行时) 到期的任务在下一个客户端连接后补发一次 (missed: true)，或按任务设置跳过。
+// 指定 action 的任务到期时还由服务器执行内置动作 (如 backup)。
+// 任务属于登记它的连接声明的 vault：list / remove 等只作用于同一 vault 的任务，事件带有任务的 vault 供客户端过滤
+
+mod action;
+mod job;
+
+pub use action::JobAction;
+use job::{Due, Job, MissedPolicy, Schedule};
+
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use chrono::{DateTime, Local};
+use futures_util::SinkExt;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Scheduler", format!($($arg)*));
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Error, "Scheduler", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Scheduler", format!($($arg)*));
+        }
+    };
+}
+
+/// 任务数上限
+const MAX_JOBS: usize = 256;
+
+/// 计时器的最长等待时间 (系统休眠或调整时钟后，最多延迟这么久重新按墙上时间计算)
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// 任务文件所在目录 (启动时设置)
+static STORE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 设置任务文件所在目录 (启动时调用一次；未设置时任务只保存在内存中)
+pub fn init_dir(dir: PathBuf) {
+    let _ = STORE_DIR.set(dir);
+}
+
+/// 创建计划任务模块错误
+fn scheduler_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Scheduler, code, message)
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// add 请求 (cron 和 at 二选一)
+#[derive(Debug, Deserialize)]
+struct AddRequest {
+    /// 任务 ID (省略时生成；已存在时替换原任务)
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    cron: Option<String>,
+    #[serde(default)]
+    at: Option<String>,
+    #[serde(default)]
+    data: serde_json::Value,
+    /// 到期时执行的内置动作
+    #[serde(default)]
+    action: Option<String>,
+    #[serde(default)]
+    missed: MissedPolicy,
+}
+
+// ============================================================================
+// 任务状态
+// ============================================================================
+
+/// 任务及在线状态 (处理器和计时器共享)
+struct SchedulerState {
+    /// 任务: (vault, id) → Job
+    jobs: Mutex<BTreeMap<(Option<String>, String), Job>>,
+    /// 任务文件所在目录
+    dir: Option<PathBuf>,
+    /// 是否有客户端连接
+    online: AtomicBool,
+    /// 上线后第一次检查时，离线期间到期的任务都按错过处理
+    catching_up: AtomicBool,
+    /// 任务变化或上线时唤醒计时器
+    wake: Notify,
+}
+
+impl SchedulerState {
+    /// 从任务文件加载任务
+    fn load(dir: Option<PathBuf>) -> Self {
+        let jobs = match dir.as_deref().map(job::load) {
+            Some(Ok(jobs)) => {
+                if !jobs.is_empty() {
+                    log_info!("已加载 {} 个计划任务", jobs.len());
+                }
+                jobs
+            }
+            Some(Err(e)) => {
+                log_error!("无法读取计划任务，忽略: {}", e);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        Self {
+            jobs: Mutex::new(jobs.into_iter().map(|job| (job.key(), job)).collect()),
+            dir,
+            online: AtomicBool::new(false),
+            catching_up: AtomicBool::new(true),
+            wake: Notify::new(),
+        }
+    }
+
+    fn jobs(&self) -> std::sync::MutexGuard<'_, BTreeMap<(Option<String>, String), Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 写入任务文件 (失败时只记录日志，任务仍在内存中生效)
+    fn persist(&self, jobs: &BTreeMap<(Option<String>, String), Job>) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let jobs: Vec<Job> = jobs.values().cloned().collect();
+        if let Err(e) = job::save(dir, &jobs) {
+            log_error!("保存计划任务失败 {}: {}", dir.display(), e);
+        }
+    }
+
+    /// 修改任务后保存并唤醒计时器
+    fn update<T>(&self, f: impl FnOnce(&mut BTreeMap<(Option<String>, String), Job>) -> Result<T, ModuleError>) -> Result<T, ModuleError> {
+        let mut jobs = self.jobs();
+        let result = f(&mut jobs)?;
+        self.persist(&jobs);
+        drop(jobs);
+        self.wake.notify_one();
+        Ok(result)
+    }
+
+    /// 处理到期的任务，返回要发送的事件、要执行的动作和距下一次到期的等待时间
+    ///
+    /// 没有客户端连接时不处理，到期的任务保留到上线后补发
+    fn process(&self, now: DateTime<Local>) -> (Vec<ServerResponse>, Vec<ActionRun>, Duration) {
+        if !self.online.load(Ordering::SeqCst) {
+            return (Vec::new(), Vec::new(), MAX_WAIT);
+        }
+        let catch_up = self.catching_up.swap(false, Ordering::SeqCst);
+        let mut events = Vec::new();
+        let mut runs = Vec::new();
+        let mut jobs = self.jobs();
+        let mut changed = false;
+        for job in jobs.values_mut() {
+            let Some(due) = job.take_due(now, catch_up) else {
+                continue;
+            };
+            changed = true;
+            if due.missed_count > 0 && job.missed == MissedPolicy::Skip {
+                log_info!("跳过错过的计划任务: {} (错过 {} 次)", job.id, due.missed_count);
+                continue;
+            }
+            job.last_run = Some(now);
+            log_debug!("计划任务触发: {} (计划时间 {})", job.id, due.scheduled_at.to_rfc3339());
+            events.push(fired_event(job, &due, now));
+            if let Some(action) = &job.action {
+                runs.push(ActionRun {
+                    job_id: job.id.clone(),
+                    action: action.clone(),
+                    data: job.data.clone(),
+                    vault: job.vault.clone(),
+                });
+            }
+        }
+        jobs.retain(|_, job| !job.finished());
+        if changed {
+            self.persist(&jobs);
+        }
+        let wait = jobs.values()
+            .filter_map(|job| job.next_run)
+            .min()
+            .map(|next| (next - now).to_std().unwrap_or_default().min(MAX_WAIT))
+            .unwrap_or(MAX_WAIT);
+        (events, runs, wait)
+    }
+}
+
+/// 一次动作执行
+struct ActionRun {
+    job_id: String,
+    action: String,
+    data: serde_json::Value,
+    /// 在登记任务的 vault 中执行
+    vault: Option<String>,
+}
+
+/// 执行任务动作，广播 action_completed / action_failed 事件
+async fn run_action(run: ActionRun, sender: WsSender) {
+    let Some(action) = action::lookup(&run.action) else {
+        return;
+    };
+    let event = match crate::storage::vault_scope(run.vault.clone(), action.run(run.data)).await {
+        Ok(result) => {
+            log_info!("计划任务动作完成: {} ({})", run.job_id, run.action);
+            ServerResponse::new(ModuleType::Scheduler, "action_completed", serde_json::json!({
+                "id": run.job_id,
+                "action": run.action,
+                "vault": run.vault,
+                "result": result,
+            }))
+        }
+        Err(e) => {
+            log_error!("计划任务动作失败: {} ({}): {}", run.job_id, run.action, e);
+            ServerResponse::new(ModuleType::Scheduler, "action_failed", serde_json::json!({
+                "id": run.job_id,
+                "action": run.action,
+                "vault": run.vault,
+                "code": e.code,
+                "message": e.message,
+            }))
+        }
+    };
+    let _ = sender.lock().await.send(Message::Text(event.to_json().into())).await;
+}
+
+/// fired 事件
+fn fired_event(job: &Job, due: &Due, now: DateTime<Local>) -> ServerResponse {
+    ServerResponse::new(ModuleType::Scheduler, "fired", serde_json::json!({
+        "id": job.id,
+        "name": job.name,
+        "data": job.data,
+        "action": job.action,
+        "vault": job.vault,
+        "scheduled_at": due.scheduled_at,
+        "fired_at": now,
+        "missed": due.missed_count > 0,
+        "missed_count": due.missed_count,
+    }))
+}
+
+/// 计时器：等到最近的任务到期 (或任务变化、客户端上线) 后处理到期的任务，广播 fired 事件并在后台执行动作
+async fn run_timer(state: Arc<SchedulerState>, sender: WsSender) {
+    loop {
+        let (events, runs, wait) = state.process(Local::now());
+        for event in events {
+            let _ = sender.lock().await.send(Message::Text(event.to_json().into())).await;
+        }
+        for run in runs {
+            tokio::spawn(run_action(run, sender.clone()));
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = state.wake.notified() => {}
+        }
+    }
+}
+
+// ============================================================================
+// 计划任务处理器
+// ============================================================================
+
+/// 计划任务模块处理器
+pub struct SchedulerHandler {
+    state: Arc<SchedulerState>,
+    /// 计时器任务 (设置 WebSocket 发送器时启动，处理器释放时停止)
+    timer: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+impl SchedulerHandler {
+    /// 创建新的计划任务处理器，加载已保存的任务
+    pub fn new() -> Self {
+        Self::with_dir(STORE_DIR.get().cloned())
+    }
+
+    /// 使用指定的任务文件目录 (None 时任务只保存在内存中)
+    fn with_dir(dir: Option<PathBuf>) -> Self {
+        Self {
+            state: Arc::new(SchedulerState::load(dir)),
+            timer: Mutex::new(None),
+        }
+    }
+
+    /// 处理 add 消息 - 添加任务 (ID 已存在时替换)
+    fn handle_add(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: AddRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| scheduler_error(ErrorCode::InvalidParams, format!("无效的 add 请求: {}", e)))?;
+        let schedule = match (request.cron, request.at) {
+            (Some(expr), None) => Schedule::Cron(expr),
+            (None, Some(at)) => Schedule::At(job::parse_time(&at).map_err(|e| scheduler_error(ErrorCode::InvalidParams, e))?),
+            _ => return Err(scheduler_error(ErrorCode::InvalidParams, "cron 和 at 必须指定其中一个").into()),
+        };
+        let id = request.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        if id.is_empty() {
+            return Err(scheduler_error(ErrorCode::InvalidParams, "id 不能为空").into());
+        }
+        if let Some(name) = &request.action {
+            let action = action::lookup(name)
+                .ok_or_else(|| scheduler_error(ErrorCode::InvalidParams, format!("未知的任务动作: {} (可用: backup)", name)))?;
+            action.validate(&request.data)?;
+        }
+        let mut job = Job::new(id, request.name, schedule, request.data, request.missed, Local::now())
+            .map_err(|e| scheduler_error(ErrorCode::InvalidParams, e))?;
+        job.action = request.action;
+        job.vault = crate::storage::current_vault();
+
+        let replaced = self.state.update(|jobs| {
+            if !jobs.contains_key(&job.key()) && jobs.len() >= MAX_JOBS {
+                return Err(scheduler_error(ErrorCode::LimitExceeded, format!("计划任务过多 (最多 {} 个)", MAX_JOBS)));
+            }
+            Ok(jobs.insert(job.key(), job.clone()).is_some())
+        })?;
+        log_info!("已{}计划任务: {} (下次触发 {:?})", if replaced { "替换" } else { "添加" }, job.id, job.next_run.map(|t| t.to_rfc3339()));
+        let mut payload = to_value(&job);
+        payload["replaced"] = replaced.into();
+        Ok(Some(ServerResponse::new(ModuleType::Scheduler, "added", payload)))
+    }
+
+    /// 处理 remove / pause / resume 消息
+    fn handle_job(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let id: String = msg.get_field("id")
+            .ok_or_else(|| scheduler_error(ErrorCode::InvalidParams, "缺少 id 字段"))?;
+        let not_found = || scheduler_error(ErrorCode::NotFound, format!("计划任务不存在: {}", id));
+        let key = (crate::storage::current_vault(), id.clone());
+        let (msg_type, payload) = self.state.update(|jobs| match msg.msg_type.as_str() {
+            "remove" => {
+                jobs.remove(&key).ok_or_else(not_found)?;
+                Ok(("removed", serde_json::json!({ "id": id })))
+            }
+            "pause" => {
+                let job = jobs.get_mut(&key).ok_or_else(not_found)?;
+                job.pause();
+                Ok(("paused", to_value(&*job)))
+            }
+            _ => {
+                let job = jobs.get_mut(&key).ok_or_else(not_found)?;
+                if job.paused {
+                    job.resume(Local::now());
+                }
+                Ok(("resumed", to_value(&*job)))
+            }
+        })?;
+        log_info!("计划任务 {}: {}", msg_type, id);
+        Ok(Some(ServerResponse::new(ModuleType::Scheduler, msg_type, payload)))
+    }
+
+    /// 处理 list 消息 - 按下次触发时间列出当前 vault 的任务 (暂停的任务在最后)
+    fn handle_list(&self) -> ServerResponse {
+        let vault = crate::storage::current_vault();
+        let jobs = self.state.jobs();
+        let mut list: Vec<&Job> = jobs.values().filter(|job| job.vault == vault).collect();
+        list.sort_by_key(|job| (job.next_run.is_none(), job.next_run));
+        ServerResponse::new(ModuleType::Scheduler, "jobs", serde_json::json!({ "jobs": list }))
+    }
+}
+
+impl Default for SchedulerHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SchedulerHandler {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            timer.abort();
+        }
+    }
+}
+
+fn to_value(value: impl serde::Serialize) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+/// 计划任务模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("add", &[
+        FieldSpec::optional("id", FieldKind::String),
+        FieldSpec::optional("name", FieldKind::String),
+        FieldSpec::optional("cron", FieldKind::String),
+        FieldSpec::optional("at", FieldKind::String),
+        FieldSpec::optional("data", FieldKind::Any),
+        FieldSpec::optional("action", FieldKind::String).one_of(&["backup"]),
+        FieldSpec::optional("missed", FieldKind::String).one_of(&["fire", "skip"]),
+    ]),
+    MessageSpec::new("remove", &[
+        FieldSpec::required("id", FieldKind::String),
+    ]),
+    MessageSpec::new("pause", &[
+        FieldSpec::required("id", FieldKind::String),
+    ]),
+    MessageSpec::new("resume", &[
+        FieldSpec::required("id", FieldKind::String),
+    ]),
+    MessageSpec::new("list", &[]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 计划任务模块
+    pub const Scheduler: ModuleType = ModuleType::named("scheduler");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for SchedulerHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Scheduler
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器并启动计时器 (fired 事件广播给所有连接)
+    async fn set_ws_sender(&self, sender: WsSender) {
+        let timer = tokio::spawn(run_timer(Arc::clone(&self.state), sender));
+        if let Some(old) = self.timer.lock().unwrap_or_else(|e| e.into_inner()).replace(timer) {
+            old.abort();
+        }
+    }
+
+    /// 客户端上线，补发离线期间到期的任务
+    async fn connected(&self) {
+        if !self.state.online.swap(true, Ordering::SeqCst) {
+            self.state.catching_up.store(true, Ordering::SeqCst);
+            self.state.wake.notify_one();
+        }
+    }
+
+    /// 最后一个连接关闭后，到期的任务保留到下一个客户端连接 (任务本身不删除)
+    async fn cleanup(&self) {
+        self.state.online.store(false, Ordering::SeqCst);
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理计划任务消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "add" => self.handle_add(msg),
+            "remove" | "pause" | "resume" => self.handle_job(msg),
+            "list" => Ok(Some(self.handle_list())),
+            _ => Err(scheduler_error(ErrorCode::UnknownMessageType, format!("未知的计划任务消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_catch_up_after_restart() {
+        let dir = std::env::temp_dir().join(format!("scheduler-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // 上次运行时登记的任务，在服务器未运行期间到期
+        let now = Local::now();
+        let mut reminder = Job::new(
+            "reminder".into(), Some("喝水".into()), Schedule::At(now + chrono::Duration::seconds(1)),
+            serde_json::json!({ "text": "喝水" }), MissedPolicy::Fire, now,
+        ).unwrap();
+        reminder.next_run = Some(now - chrono::Duration::hours(1));
+        let mut skipped = Job::new(
+            "skipped".into(), None, Schedule::Cron("0 * * * *".into()), serde_json::Value::Null, MissedPolicy::Skip, now,
+        ).unwrap();
+        skipped.next_run = Some(now - chrono::Duration::hours(3));
+        job::save(&dir, &[reminder, skipped]).unwrap();
+
+        let handler = SchedulerHandler::with_dir(Some(dir.clone()));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let sink = futures_util::sink::unfold(tx, |tx, msg: Message| async move {
+            let _ = tx.send(msg);
+            Ok::<_, tokio_tungstenite::tungstenite::Error>(tx)
+        });
+        handler.set_ws_sender(Arc::new(tokio::sync::Mutex::new(Box::pin(sink)))).await;
+
+        // 没有客户端时不触发
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rx.try_recv().is_err());
+
+        handler.connected().await;
+        let message = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        let event: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+        assert_eq!(event["type"], "fired");
+        assert_eq!(event["id"], "reminder");
+        assert_eq!(event["data"]["text"], "喝水");
+        assert_eq!(event["missed"], true);
+        assert_eq!(event["missed_count"], 1);
+
+        // 跳过的任务不发送事件，推进到下一次触发；已触发的一次性任务从文件中删除
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rx.try_recv().is_err());
+        let jobs = job::load(&dir).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, "skipped");
+        assert!(jobs[0].next_run.unwrap() > now);
+        assert!(jobs[0].last_run.is_none());
+
+        drop(handler);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_vault_jobs() {
+        use crate::storage::vault_scope;
+
+        let handler = SchedulerHandler::with_dir(None);
+        let message = |msg_type: &str, payload: serde_json::Value| ModuleMessage {
+            module: ModuleType::Scheduler,
+            msg_type: msg_type.to_string(),
+            payload,
+            connection: None,
+        };
+        let add = message("add", serde_json::json!({ "id": "nightly", "cron": "0 3 * * *" }));
+        let list = message("list", serde_json::json!({}));
+        let vaults = |response: ServerResponse| -> Vec<serde_json::Value> {
+            response.payload["jobs"].as_array().unwrap().iter().map(|job| job["vault"].clone()).collect()
+        };
+
+        // 不同 vault 的同名任务互不替换，各自只看到自己的任务
+        for vault in ["work", "home"] {
+            let response = vault_scope(Some(vault.to_string()), handler.handle(&add)).await.unwrap().unwrap();
+            assert_eq!(response.payload["replaced"], false);
+            assert_eq!(response.payload["vault"], vault);
+        }
+        let listed = vault_scope(Some("work".to_string()), handler.handle(&list)).await.unwrap().unwrap();
+        assert_eq!(vaults(listed), vec![serde_json::json!("work")]);
+        assert!(vaults(handler.handle(&list).await.unwrap().unwrap()).is_empty());
+
+        let remove = message("remove", serde_json::json!({ "id": "nightly" }));
+        assert!(handler.handle(&remove).await.is_err());
+        vault_scope(Some("home".to_string()), handler.handle(&remove)).await.unwrap();
+        assert_eq!(handler.state.jobs().len(), 1);
+    }
+}
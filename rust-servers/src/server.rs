@@ -1,31 +1,66 @@
 // WebSocket 服务器实现
 // 统一的 WebSocket 服务器，处理所有模块的消息
 
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
-use tokio_tungstenite::{accept_async, tungstenite::Message};
-use futures_util::{StreamExt, SinkExt};
-use std::sync::Arc;
-use tokio::sync::Mutex as TokioMutex;
+use tokio_tungstenite::accept_hdr_async_with_config;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Message;
+use futures_util::{Sink, StreamExt, SinkExt};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{ready, Context, Poll};
+use tokio::sync::{broadcast, watch, Mutex as TokioMutex, RwLock};
+use tokio::time::Instant;
 
-use crate::router::{MessageRouter, ModuleType, RouterError, ServerResponse};
+use crate::config::{BatchWindows, Config, Limits};
+use crate::http;
+use crate::instance::InstanceLock;
+use crate::middleware::{Metrics, RateLimiter, RequestTracing};
+use crate::outbound::{Outbound, OutboundPort, OutboundQueue, QueueSink};
+use crate::pty::PtyHandler;
+use crate::router::{ErrorCode, MessageRouter, ModuleError, ModuleMessage, ModuleType, RouterError, ServerResponse};
+use crate::snapshot::Snapshot;
+use crate::system::codec::{self, Encoding};
+use crate::system::locale::Locale;
+use crate::system::subscription::Subscriptions;
+use crate::system::upload::{self, UploadOutcome, Uploads};
+use crate::system::{ServerHello, LOG_SESSION};
+use crate::tls::TlsIdentity;
+use crate::logging;
+use crate::watchdog;
 
 /// 日志宏
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
     };
 }
 
 macro_rules! log_error {
     ($($arg:tt)*) => {
-        eprintln!("[ERROR] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Error, "", format!($($arg)*));
     };
 }
 
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
         }
     };
 }
@@ -34,52 +69,360 @@ macro_rules! log_debug {
 // 服务器配置和实现
 // ============================================================================
 
-/// WebSocket 服务器配置
-pub struct ServerConfig {
-    pub port: u16,
+/// TLS 握手超时 (超时未完成握手的连接直接关闭)
+const TLS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// 所有连接共享的服务器状态
+struct ServerState {
+    /// 认证令牌 (配置文件指定，否则每次启动随机生成；随端口信息输出到 stdout)
+    token: String,
+    /// 消息路由器 (所有连接共享同一组模块和会话)
+    router: MessageRouter,
+    /// 已建立的连接
+    connections: Arc<ConnectionRegistry>,
+    /// 资源限制
+    limits: Limits,
+    /// 是否在同一监听地址上提供 HTTP REST 接口
+    http_api: bool,
+    /// 状态快照目录 (未设置时不保存也不恢复状态)
+    state_dir: OnceLock<PathBuf>,
+    /// 连接声明过或快照中恢复的 vault (None 为未声明 vault 的连接)，每个 vault 的状态单独保存
+    vaults: Mutex<BTreeSet<Option<String>>>,
+    /// HTTP 接口提供文件的目录 (未设置时不提供 /files/)
+    files_dir: OnceLock<PathBuf>,
+}
+
+impl ServerState {
+    /// 保存各模块的状态快照 (需在清理模块资源之前调用)
+    ///
+    /// 每个 vault 的状态写入状态目录中该 vault 的子目录，未声明 vault 的连接的状态写入状态目录本身
+    async fn save_snapshot(&self) {
+        let Some(dir) = self.state_dir.get() else {
+            return;
+        };
+        let vaults = self.vaults.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        for vault in vaults {
+            let modules = crate::storage::vault_scope(vault.clone(), self.router.snapshot()).await;
+            let dir = crate::storage::vault_path(dir, vault.as_deref());
+            let snapshot = Snapshot::new(vault, modules);
+            match std::fs::create_dir_all(&dir).and_then(|_| snapshot.save(&dir)) {
+                Ok(path) => {
+                    log_info!("已保存状态快照: {}", path.display());
+                }
+                Err(e) => {
+                    log_error!("保存状态快照失败: {}", e);
+                }
+            }
+        }
+    }
+
+    /// 恢复上次保存的状态快照 (各 vault 的状态在该 vault 的上下文中恢复)
+    async fn restore_snapshot(&self) {
+        let Some(dir) = self.state_dir.get() else {
+            return;
+        };
+        let dirs = std::iter::once(dir.clone()).chain(crate::storage::vault_dirs(dir));
+        for dir in dirs {
+            match Snapshot::load(&dir) {
+                Ok(Some(snapshot)) => {
+                    log_info!("恢复状态快照 (保存于 {}，服务器版本 {}，vault={:?})",
+                        snapshot.saved_at, snapshot.server_version, snapshot.vault);
+                    self.vaults.lock().unwrap_or_else(|e| e.into_inner()).insert(snapshot.vault.clone());
+                    crate::storage::vault_scope(snapshot.vault, self.router.restore(snapshot.modules)).await;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log_warn!("无法读取状态快照 {}，忽略: {}", dir.display(), e);
+                }
+            }
+        }
+    }
 }
 
 /// WebSocket 服务器
 pub struct Server {
-    config: ServerConfig,
+    config: Config,
+    state: Arc<ServerState>,
+    /// 数据目录下的单实例锁 (启动信息同时写入锁文件)
+    instance_lock: Option<InstanceLock>,
+    /// TLS 证书 (设置时 TCP 监听使用 wss://)
+    tls: Option<TlsIdentity>,
 }
 
 impl Server {
-    pub fn new(config: ServerConfig) -> Self {
-        Self { config }
+    pub fn new(config: Config) -> Self {
+        Self {
+            state: Arc::new(ServerState {
+                token: config.token.clone().unwrap_or_else(generate_token),
+                router: MessageRouter::new()
+                    .with_disabled_modules(&config.disabled_modules)
+                    .with_middleware(RequestTracing)
+                    .with_middleware(Metrics::new())
+                    .with_middleware(RateLimiter::new(config.rate_limits.clone())),
+                connections: Arc::new(
+                    ConnectionRegistry::new()
+                        .with_queue_capacity(config.limits.outbound_queue_size)
+                        .with_batch_windows(config.batching.clone()),
+                ),
+                limits: config.limits.clone(),
+                http_api: config.http_api,
+                state_dir: OnceLock::new(),
+                vaults: Mutex::new(BTreeSet::from([None])),
+                files_dir: OnceLock::new(),
+            }),
+            config,
+            instance_lock: None,
+            tls: None,
+        }
     }
 
-    /// 启动服务器
-    pub async fn start(&self) -> Result<u16, Box<dyn std::error::Error>> {
-        let addr = format!("127.0.0.1:{}", self.config.port);
-        let listener = TcpListener::bind(&addr).await?;
+    /// 设置单实例锁，启动信息会同时写入锁文件
+    pub fn with_instance_lock(mut self, lock: InstanceLock) -> Self {
+        self.instance_lock = Some(lock);
+        self
+    }
+
+    /// 设置 TLS 证书，TCP 监听改为 wss:// 并在启动信息中输出证书指纹
+    pub fn with_tls(mut self, tls: TlsIdentity) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// 设置状态快照目录：退出前保存模块状态，启动时恢复 (除非配置了 fresh_start)
+    pub fn with_state_dir(self, dir: PathBuf) -> Self {
+        let _ = self.state.state_dir.set(dir);
+        self
+    }
+
+    /// 设置 HTTP 接口提供文件的目录 (GET /files/<path>，需要同时启用 HTTP 接口)
+    pub fn with_files_dir(self, dir: PathBuf) -> Self {
+        let _ = self.state.files_dir.set(dir);
+        self
+    }
+
+    /// 启动服务器，返回监听地址
+    pub async fn start(&self) -> Result<String, Box<dyn std::error::Error>> {
+        // 配置中禁用的模块必须是已注册的模块
+        if let Some(name) = self.config.disabled_modules.iter().find(|name| !self.state.router.has_module(name)) {
+            return Err(format!("未知的模块: {}", name).into());
+        }
+        // 不属于特定请求的模块事件广播给所有连接
+        self.state.router.set_ws_sender(self.state.connections.broadcast_sender()).await;
+        if self.config.fresh_start {
+            log_info!("不恢复上次保存的状态");
+        } else {
+            self.state.restore_snapshot().await;
+        }
+        tokio::spawn(forward_logs(Arc::clone(&self.state.connections)));
+        tokio::spawn(watchdog::run(self.config.stall_timeouts.clone(), self.state.connections.broadcast_sender()));
+
+        match &self.config.socket {
+            Some(socket) => self.start_local(socket).await,
+            None => self.start_tcp().await,
+        }
+    }
+
+    /// 在 TCP 端口上监听 (默认仅本机)
+    async fn start_tcp(&self) -> Result<String, Box<dyn std::error::Error>> {
+        // 远程访问模式下令牌和消息会经过网络，不允许明文监听
+        if self.config.is_remote() && self.tls.is_none() {
+            return Err("远程访问模式需要 TLS 证书".into());
+        }
+        let host = self.config.host.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let listener = TcpListener::bind(SocketAddr::new(host, self.config.port)).await?;
         let local_addr = listener.local_addr()?;
-        let port = local_addr.port();
 
-        log_info!("服务器绑定到 {}", local_addr);
+        match &self.tls {
+            Some(tls) => {
+                log_info!("服务器绑定到 {} (TLS，证书: {})", local_addr, tls.cert_path().display());
+            }
+            None => {
+                log_info!("服务器绑定到 {}", local_addr);
+            }
+        }
+        if self.config.is_remote() {
+            log_info!("远程访问模式：其他机器可以通过 TLS 连接，连接需要认证令牌");
+        }
 
-        // 输出端口信息到 stdout (JSON 格式)
-        // TypeScript 端会解析这个 JSON 来获取端口号
-        println!(
-            r#"{{"port": {}, "pid": {}}}"#,
-            port,
-            std::process::id()
-        );
+        // TypeScript 端会解析这个 JSON 来获取端口号和认证令牌
+        self.print_startup_info("port", local_addr.port().into());
 
         // 主循环：接受 WebSocket 连接
+        let state = Arc::clone(&self.state);
+        let acceptor = self.tls.as_ref().map(|tls| tls.acceptor().clone());
         tokio::spawn(async move {
             log_info!("正在监听 WebSocket 连接...");
             while let Ok((stream, addr)) = listener.accept().await {
                 log_debug!("接受来自 {} 的连接", addr);
+                let Some(acceptor) = &acceptor else {
+                    spawn_connection(stream, Arc::clone(&state));
+                    continue;
+                };
+                // TLS 握手在单独的任务中进行，不阻塞接受其他连接
+                let (acceptor, state) = (acceptor.clone(), Arc::clone(&state));
                 tokio::spawn(async move {
-                    if let Err(e) = handle_connection(stream).await {
-                        log_error!("连接处理错误: {}", e);
+                    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => spawn_connection(stream, state),
+                        Ok(Err(e)) => {
+                            log_warn!("来自 {} 的 TLS 握手失败: {}", addr, e);
+                        }
+                        Err(_) => {
+                            log_warn!("来自 {} 的 TLS 握手超时", addr);
+                        }
                     }
                 });
             }
         });
 
-        Ok(port)
+        Ok(local_addr.to_string())
+    }
+
+    /// 在 Unix 域套接字上监听 (仅当前用户可访问)
+    #[cfg(unix)]
+    async fn start_local(&self, path: &str) -> Result<String, Box<dyn std::error::Error>> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+        // 清理上次异常退出遗留的套接字文件，其他类型的文件不覆盖
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(format!("路径已存在且不是套接字: {}", path).into());
+            }
+            std::fs::remove_file(path)?;
+        }
+
+        let listener = tokio::net::UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+        log_info!("服务器绑定到 Unix 域套接字 {}", path);
+        self.print_startup_info("socket", path.into());
+
+        let state = Arc::clone(&self.state);
+        tokio::spawn(async move {
+            log_info!("正在监听 WebSocket 连接...");
+            while let Ok((stream, _)) = listener.accept().await {
+                log_debug!("接受 Unix 域套接字连接");
+                spawn_connection(stream, Arc::clone(&state));
+            }
+        });
+
+        Ok(path.to_string())
+    }
+
+    /// 在命名管道上监听 (拒绝远程客户端)
+    #[cfg(windows)]
+    async fn start_local(&self, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let pipe_name = if name.starts_with(r"\\.\pipe\") {
+            name.to_string()
+        } else {
+            format!(r"\\.\pipe\{}", name)
+        };
+
+        // 每个连接占用一个管道实例，连接建立后立即创建下一个实例
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .reject_remote_clients(true)
+            .create(&pipe_name)?;
+
+        log_info!("服务器绑定到命名管道 {}", pipe_name);
+        self.print_startup_info("socket", pipe_name.clone().into());
+
+        let state = Arc::clone(&self.state);
+        let listen_name = pipe_name.clone();
+        tokio::spawn(async move {
+            log_info!("正在监听 WebSocket 连接...");
+            loop {
+                if let Err(e) = server.connect().await {
+                    log_error!("命名管道连接失败: {}", e);
+                    break;
+                }
+                let next = match ServerOptions::new().reject_remote_clients(true).create(&listen_name) {
+                    Ok(next) => next,
+                    Err(e) => {
+                        log_error!("创建命名管道实例失败: {}", e);
+                        break;
+                    }
+                };
+                log_debug!("接受命名管道连接");
+                spawn_connection(std::mem::replace(&mut server, next), Arc::clone(&state));
+            }
+        });
+
+        Ok(pipe_name)
+    }
+
+    /// 输出启动信息到 stdout (JSON 格式)
+    fn print_startup_info(&self, address_key: &str, address: serde_json::Value) {
+        let mut info = serde_json::json!({
+            "pid": std::process::id(),
+            "token": self.state.token,
+        });
+        info[address_key] = address;
+        if let (Some(host), None) = (self.config.host, &self.config.socket) {
+            info["host"] = host.to_string().into();
+        }
+        if let Some(tls) = &self.tls {
+            info["tls_fingerprint"] = tls.fingerprint().into();
+        }
+        println!("{}", info);
+        if let Some(lock) = &self.instance_lock {
+            if let Err(e) = lock.publish(&info) {
+                log_error!("写入锁文件失败 {}: {}", lock.path().display(), e);
+            }
+        }
+    }
+
+    /// 认证令牌
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn token(&self) -> &str {
+        &self.state.token
+    }
+
+    /// 等待空闲退出：最后一个连接断开后宽限期内没有新连接时返回
+    ///
+    /// 未配置空闲退出时永不返回
+    pub async fn idle_exit(&self) {
+        let Some(grace) = self.config.limits.idle_exit else {
+            return std::future::pending().await;
+        };
+        let mut idle_since = self.state.connections.idle_since();
+        loop {
+            let Ok(since) = idle_since.wait_for(Option::is_some).await.map(|since| *since) else {
+                return std::future::pending().await;
+            };
+            // 宽限期内有新连接 (或又一次断开) 时重新计时
+            tokio::select! {
+                _ = tokio::time::sleep_until(since.unwrap_or_else(Instant::now) + grace) => {
+                    if *idle_since.borrow() == since {
+                        return;
+                    }
+                }
+                _ = idle_since.changed() => {}
+            }
+        }
+    }
+
+    /// 关闭服务器：保存状态快照，清理所有模块资源 (PTY 会话、录音、LLM 流等) 和监听资源
+    /// 
+    /// 没有连接时模块资源已在最后一个连接断开时保存并清理，不再覆盖快照
+    pub async fn shutdown(&self) {
+        if self.state.connections.len().await > 0 {
+            self.state.save_snapshot().await;
+        }
+        self.state.router.cleanup().await;
+        self.cleanup();
+    }
+
+    /// 清理监听资源 (删除 Unix 域套接字文件和锁文件)
+    fn cleanup(&self) {
+        #[cfg(unix)]
+        if let Some(path) = &self.config.socket {
+            let _ = std::fs::remove_file(path);
+        }
+        if let Some(lock) = &self.instance_lock {
+            lock.release();
+        }
     }
 }
 
@@ -87,46 +430,521 @@ impl Server {
 // 连接处理
 // ============================================================================
 
+/// WebSocket 发送端 (与底层传输无关)
+pub type WsSink = Pin<Box<dyn Sink<Message, Error = tokio_tungstenite::tungstenite::Error> + Send>>;
+
 /// WebSocket 发送器类型别名
-pub type WsSender = Arc<TokioMutex<futures_util::stream::SplitSink<
-    tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
-    Message
->>>;
+pub type WsSender = Arc<TokioMutex<WsSink>>;
+
+/// 连接 ID (进程内递增)
+pub type ConnectionId = u64;
+
+/// 客户端连接 (消息来源)
+#[derive(Clone)]
+pub struct Connection {
+    /// 连接 ID
+    pub id: ConnectionId,
+    /// 该连接的发送器 (仅发送给该连接，用于响应)
+    pub sender: WsSender,
+    /// 该连接的事件发送器 (发送给该连接，并抄送给订阅了该事件的其他连接)
+    pub events: WsSender,
+    /// 该连接的事件订阅
+    pub subscriptions: Arc<TokioMutex<Subscriptions>>,
+    /// 该连接协商后的消息编码
+    pub encoding: Arc<TokioMutex<Encoding>>,
+    /// 该连接协商后的错误消息语言 (None 表示不翻译)
+    pub locale: Arc<TokioMutex<Option<Locale>>>,
+    /// 该连接在 hello 中声明的 vault 标识 (按 vault 保存的数据据此隔离)
+    pub vault: Arc<TokioMutex<Option<String>>>,
+    /// 该连接的出站队列
+    pub outbound: Arc<OutboundQueue>,
+    /// 该连接的出站端口 (投递已分类的消息)
+    pub port: OutboundPort,
+}
+
+impl std::fmt::Debug for Connection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Connection").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+/// 连接注册表
+/// 
+/// 记录所有已建立的连接，按订阅向连接投递事件
+pub struct ConnectionRegistry {
+    next_id: AtomicU64,
+    connections: TokioMutex<HashMap<ConnectionId, Connection>>,
+    /// 每个连接出站队列的容量
+    queue_capacity: usize,
+    /// 事件合并窗口
+    batch_windows: BatchWindows,
+    /// 最后一个连接断开的时间 (有连接或从未有过连接时为 None)
+    idle_since: watch::Sender<Option<Instant>>,
+    /// 模块清理期间阻止新连接注册 (写锁由清理持有)
+    cleanup_gate: RwLock<()>,
+}
+
+impl ConnectionRegistry {
+    /// 创建空的注册表
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            connections: TokioMutex::new(HashMap::new()),
+            queue_capacity: Limits::default().outbound_queue_size,
+            batch_windows: BatchWindows::none(),
+            idle_since: watch::Sender::new(None),
+            cleanup_gate: RwLock::new(()),
+        }
+    }
+
+    /// 设置每个连接出站队列的容量
+    pub fn with_queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity;
+        self
+    }
+
+    /// 设置事件合并窗口
+    pub fn with_batch_windows(mut self, windows: BatchWindows) -> Self {
+        self.batch_windows = windows;
+        self
+    }
+
+    /// 注册连接
+    /// 
+    /// 发往该连接的消息按连接协商的编码转换后进入出站队列，由写任务写入 `sink`
+    pub async fn register(self: &Arc<Self>, sink: WsSink) -> Connection {
+        let _gate = self.cleanup_gate.read().await;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let encoding = Arc::new(TokioMutex::new(Encoding::default()));
+        let locale = Arc::new(TokioMutex::new(None));
+        let outbound = OutboundQueue::spawn(self.queue_capacity, self.batch_windows.clone(), sink);
+        let port = OutboundPort::new(Arc::clone(&outbound), Arc::clone(&encoding), Arc::clone(&locale));
+        let sender: WsSender = Arc::new(TokioMutex::new(Box::pin(QueueSink::new(port.clone()))));
+        let connection = Connection {
+            id,
+            events: self.event_sender(id, port.clone()),
+            sender,
+            port,
+            subscriptions: Arc::new(TokioMutex::new(Subscriptions::default())),
+            encoding,
+            locale,
+            vault: Arc::new(TokioMutex::new(None)),
+            outbound,
+        };
+        self.connections.lock().await.insert(id, connection.clone());
+        self.idle_since.send_replace(None);
+        connection
+    }
+
+    /// 当前连接数
+    pub async fn len(&self) -> usize {
+        self.connections.lock().await.len()
+    }
+
+    /// 注销连接并关闭其出站队列 (已入队的消息仍会发出)，返回剩余连接数
+    pub async fn unregister(&self, id: ConnectionId) -> usize {
+        let mut connections = self.connections.lock().await;
+        if let Some(connection) = connections.remove(&id) {
+            let dropped = connection.outbound.dropped();
+            if dropped > 0 {
+                log_debug!("连接 {} 共丢弃 {} 条可丢弃事件", id, dropped);
+            }
+            connection.outbound.close();
+            if connections.is_empty() {
+                self.idle_since.send_replace(Some(Instant::now()));
+            }
+        }
+        connections.len()
+    }
+
+    /// 最后一个连接断开后运行模块清理，返回是否已清理
+    /// 
+    /// 清理期间新连接等待清理完成后再注册；开始清理前已有新连接 (如客户端立即重连) 时跳过，
+    /// 不会清理新连接正在使用的会话
+    pub async fn cleanup_when_idle(&self, cleanup: impl Future<Output = ()>) -> bool {
+        let _gate = self.cleanup_gate.write().await;
+        if !self.connections.lock().await.is_empty() {
+            return false;
+        }
+        cleanup.await;
+        true
+    }
+
+    /// 订阅最后一个连接断开的时间
+    pub fn idle_since(&self) -> watch::Receiver<Option<Instant>> {
+        self.idle_since.subscribe()
+    }
+
+    /// 创建向所有连接广播的发送器
+    /// 
+    /// 没有订阅的连接接收全部事件，有订阅的连接只接收匹配的事件。
+    /// 单个连接发送失败 (如已断开) 不影响其他连接
+    pub fn broadcast_sender(self: &Arc<Self>) -> WsSender {
+        let sink = futures_util::sink::unfold(Arc::downgrade(self), |registry, msg: Message| async move {
+            if let Some(registry) = registry.upgrade() {
+                registry.deliver(&Outbound::new(msg), None).await;
+            }
+            Ok::<_, tokio_tungstenite::tungstenite::Error>(registry)
+        });
+        Arc::new(TokioMutex::new(Box::pin(sink)))
+    }
+
+    /// 创建连接的事件发送器：发送给该连接，并抄送给订阅了该事件的其他连接
+    /// 
+    /// 只有发送给来源连接失败时才返回错误
+    fn event_sender(self: &Arc<Self>, origin: ConnectionId, port: OutboundPort) -> WsSender {
+        let state = (Arc::downgrade(self), port);
+        let sink = futures_util::sink::unfold(state, move |(registry, port), msg: Message| async move {
+            // 消息只分类一次，发送给来源连接和抄送给其他连接时共用
+            let item = Outbound::new(msg);
+            let result = port.send(item.clone()).await;
+            if let Some(registry) = registry.upgrade() {
+                registry.deliver(&item, Some(origin)).await;
+            }
+            result.map(|()| (registry, port))
+        });
+        Arc::new(TokioMutex::new(Box::pin(FailFast::new(sink))))
+    }
+
+    /// 按订阅投递事件 (`origin` 为发起该事件的连接，不会重复发送给它)
+    async fn deliver(&self, item: &Outbound, origin: Option<ConnectionId>) {
+        let topic = item.topic();
+        let mut targets = Vec::new();
+        for connection in self.connections.lock().await.values() {
+            if Some(connection.id) == origin {
+                continue;
+            }
+            let subscriptions = connection.subscriptions.lock().await;
+            let wanted = match &topic {
+                Some((module, session_id)) => subscriptions.matches(*module, *session_id),
+                None => false,
+            };
+            if wanted || (origin.is_none() && subscriptions.is_empty()) {
+                targets.push(connection.port.clone());
+            }
+        }
+
+        for port in targets {
+            let _ = port.send(item.clone()).await;
+        }
+    }
+
+    /// 只向订阅了该主题的连接发布消息 (没有订阅的连接不会收到)
+    pub async fn publish(&self, module: ModuleType, session_id: Option<&str>, msg: Message) {
+        let mut targets = Vec::new();
+        for connection in self.connections.lock().await.values() {
+            if connection.subscriptions.lock().await.matches(module, session_id) {
+                targets.push(connection.port.clone());
+            }
+        }
+
+        let item = Outbound::new(msg);
+        for port in targets {
+            let _ = port.send(item.clone()).await;
+        }
+    }
+}
+
+/// 将实时日志推送给订阅了 `system:logs` 的连接
+async fn forward_logs(registry: Arc<ConnectionRegistry>) {
+    let mut records = logging::subscribe();
+    loop {
+        match records.recv().await {
+            Ok(record) => {
+                let payload = serde_json::to_value(&record).unwrap_or_default();
+                let event = ServerResponse::new(ModuleType::System, "log", payload);
+                registry.publish(ModuleType::System, Some(LOG_SESSION), Message::Text(event.to_json().into())).await;
+            }
+            // 订阅者处理过慢时跳过丢失的记录
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// 发送失败后拒绝后续发送的 sink
+/// 
+/// unfold sink 出错后再次发送会 panic，而模块 (如 LLM 流) 可能在发送失败后继续发送错误消息
+struct FailFast<S> {
+    inner: Pin<Box<S>>,
+    failed: bool,
+}
+
+impl<S> FailFast<S> {
+    fn new(inner: S) -> Self {
+        Self { inner: Box::pin(inner), failed: false }
+    }
+
+    fn check<T>(&mut self, result: Result<T, tokio_tungstenite::tungstenite::Error>) -> Result<T, tokio_tungstenite::tungstenite::Error> {
+        self.failed |= result.is_err();
+        result
+    }
+}
+
+impl<S> Sink<Message> for FailFast<S>
+where
+    S: Sink<Message, Error = tokio_tungstenite::tungstenite::Error>,
+{
+    type Error = tokio_tungstenite::tungstenite::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.failed {
+            return Poll::Ready(Err(tokio_tungstenite::tungstenite::Error::AlreadyClosed));
+        }
+        let result = ready!(self.inner.as_mut().poll_ready(cx));
+        Poll::Ready(self.check(result))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        if self.failed {
+            return Err(tokio_tungstenite::tungstenite::Error::AlreadyClosed);
+        }
+        let result = self.inner.as_mut().start_send(item);
+        self.check(result)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.failed {
+            return Poll::Ready(Err(tokio_tungstenite::tungstenite::Error::AlreadyClosed));
+        }
+        let result = ready!(self.inner.as_mut().poll_flush(cx));
+        Poll::Ready(self.check(result))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.failed {
+            return Poll::Ready(Ok(()));
+        }
+        let result = ready!(self.inner.as_mut().poll_close(cx));
+        Poll::Ready(self.check(result))
+    }
+}
+
+impl Default for ConnectionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 在新任务中处理连接
+fn spawn_connection<S>(stream: S, state: Arc<ServerState>)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        // 启用 REST 接口时先读取请求头，WebSocket 升级请求之外的 HTTP 请求交给 REST 桥接
+        let result = if state.http_api {
+            match http::accept(stream).await {
+                Ok(http::Accepted::WebSocket(stream)) => handle_connection(stream, state).await,
+                Ok(http::Accepted::Rest(request, stream)) => {
+                    let files = state.files_dir.get().map(PathBuf::as_path);
+                    http::serve(request, stream, &state.router, &state.token, files).await.map_err(Into::into)
+                }
+                Err(e) => Err(e.into()),
+            }
+        } else {
+            handle_connection(stream, state).await
+        };
+        if let Err(e) = result {
+            log_error!("连接处理错误: {}", e);
+        }
+    });
+}
+
+/// WebSocket 协议层的帧大小上限 (超过时直接断开连接)
+/// 
+/// 明显高于 `max_message_size`，使略超上限的消息能收到 LIMIT_EXCEEDED 错误而不是被断开
+const WS_HARD_LIMIT: usize = 64 * 1024 * 1024;
+
+fn websocket_config(limits: &Limits) -> WebSocketConfig {
+    let hard_limit = limits.max_message_size.saturating_mul(2).max(WS_HARD_LIMIT);
+    WebSocketConfig::default()
+        .max_message_size(Some(hard_limit))
+        .max_frame_size(Some(hard_limit))
+}
 
 /// 处理单个 WebSocket 连接
-async fn handle_connection(
-    stream: tokio::net::TcpStream,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // 升级到 WebSocket
-    let ws_stream = accept_async(stream).await?;
+/// 
+/// 连接必须通过 `?token=` 查询参数或第一条消息 (system/auth 或带 token 的 system/hello) 完成认证，
+/// 认证前不会路由任何消息
+// 握手回调的错误类型由 tungstenite 决定
+#[allow(clippy::result_large_err)]
+async fn handle_connection<S>(
+    stream: S,
+    state: Arc<ServerState>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let token = state.token.as_str();
+    let router = &state.router;
+    
+    // 升级到 WebSocket，查询参数中的令牌错误时直接以 401 拒绝，连接数已满时以 503 拒绝
+    let mut authenticated = false;
+    let full = state.connections.len().await >= state.limits.max_connections;
+    // 错误响应的类型由 tungstenite 决定
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, response: Response| {
+        if full {
+            let mut error = ErrorResponse::new(Some("Too many connections".to_string()));
+            *error.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            return Err(error);
+        }
+        match request.uri().query().and_then(token_from_query) {
+            Some(candidate) if tokens_match(&candidate, token) => {
+                authenticated = true;
+                Ok(response)
+            }
+            Some(_) => {
+                let mut error = ErrorResponse::new(Some("Invalid token".to_string()));
+                *error.status_mut() = StatusCode::UNAUTHORIZED;
+                Err(error)
+            }
+            None => Ok(response),
+        }
+    };
+    let ws_stream = accept_hdr_async_with_config(stream, callback, Some(websocket_config(&state.limits))).await?;
     
-    log_info!("WebSocket 连接已建立");
+    log_info!("WebSocket 连接已建立 (authenticated={})", authenticated);
     
     // 分离读写流
     let (ws_sender, mut ws_receiver) = ws_stream.split();
-    let ws_sender: WsSender = Arc::new(TokioMutex::new(ws_sender));
     
-    // 创建消息路由器
-    let router = Arc::new(MessageRouter::new());
+    // 注册连接，该连接发起的请求产生的事件会投递到这里
+    let connection = state.connections.register(Box::pin(ws_sender)).await;
+    let ws_sender = Arc::clone(&connection.sender);
+    log_debug!("连接已注册: id={}", connection.id);
     
-    // 设置 WebSocket 发送器 (用于 PTY 输出)
-    router.set_ws_sender(Arc::clone(&ws_sender)).await;
+    // 发送握手消息 (协议版本、服务器版本和已启用模块)
+    let hello = ServerHello::new(router.enabled_modules())
+        .with_max_message_size(state.limits.max_message_size)
+        .into_response();
+    if let Err(e) = send_response(&ws_sender, &hello).await {
+        state.connections.unregister(connection.id).await;
+        return Err(e);
+    }
+    router.connected().await;
     
     // 消息处理循环
-    while let Some(msg_result) = ws_receiver.next().await {
+    // 心跳：定期发送 Ping，超时未收到任何帧时按连接关闭处理 (客户端被强制结束时不会发送 Close)
+    let mut heartbeat = tokio::time::interval(state.limits.heartbeat_interval);
+    heartbeat.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_seen = tokio::time::Instant::now();
+    
+    // 该连接上进行中的分块上传 (连接关闭时丢弃)
+    let mut uploads = Uploads::new(state.limits.max_message_size, state.limits.max_upload_size);
+    
+    loop {
+        // 优先读取已到达的帧：处理器长时间占用循环后，排队的帧 (含 Pong) 先于过期的心跳检查处理
+        let msg_result = tokio::select! {
+            biased;
+            msg_result = ws_receiver.next() => match msg_result {
+                Some(msg_result) => msg_result,
+                None => break,
+            },
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > state.limits.heartbeat_timeout {
+                    log_info!("心跳超时 ({} 秒未收到数据)，关闭连接", last_seen.elapsed().as_secs());
+                    break;
+                }
+                let mut sender = ws_sender.lock().await;
+                if let Err(e) = sender.send(Message::Ping(Default::default())).await {
+                    log_error!("发送心跳失败: {}", e);
+                    break;
+                }
+                continue;
+            }
+        };
+        last_seen = tokio::time::Instant::now();
+        
         match msg_result {
             Ok(msg) => {
                 log_debug!("收到消息类型: {:?}", std::mem::discriminant(&msg));
                 
+                // 未认证连接只接受认证消息
+                if !authenticated && !matches!(msg, Message::Ping(_) | Message::Pong(_) | Message::Close(_)) {
+                    let credential = match &msg {
+                        Message::Text(text) => auth_message_token(text).map(|c| (c, text.as_str())),
+                        _ => None,
+                    };
+                    match credential {
+                        Some(((candidate, msg_type), text)) if tokens_match(&candidate, token) => {
+                            authenticated = true;
+                            log_info!("连接认证成功");
+                            // system/hello 继续走正常路由以完成握手
+                            if msg_type == "auth" {
+                                let response = ServerResponse::new(ModuleType::System, "auth_ok", serde_json::json!({}))
+                                    .with_request_id(extract_request_id_from_json(text).as_deref());
+                                if let Err(e) = send_response(&ws_sender, &response).await {
+                                    log_error!("发送响应失败: {}", e);
+                                    break;
+                                }
+                                continue;
+                            }
+                        }
+                        _ => {
+                            log_error!("未认证的连接发送了消息，关闭连接");
+                            let error_response = ServerResponse::error(
+                                ModuleType::System,
+                                ErrorCode::Unauthorized,
+                                "连接未认证: 请在第一条消息中提供 token",
+                            );
+                            let _ = send_response(&ws_sender, &error_response).await;
+                            let mut sender = ws_sender.lock().await;
+                            let _ = sender.send(Message::Close(None)).await;
+                            break;
+                        }
+                    }
+                }
+                
                 match msg {
                     Message::Text(text) => {
                         // 处理文本消息
-                        if let Err(e) = handle_text_message(
+                        match handle_text_message(
                             &text,
-                            &router,
-                            &ws_sender
+                            &state,
+                            &connection,
+                            &mut uploads,
                         ).await {
-                            log_error!("消息处理错误: {}", e);
+                            Ok(true) => {}
+                            Ok(false) => {
+                                log_info!("客户端协议版本不兼容，关闭连接");
+                                let mut sender = ws_sender.lock().await;
+                                let _ = sender.send(Message::Close(None)).await;
+                                break;
+                            }
+                            Err(e) => {
+                                log_error!("消息处理错误: {}", e);
+                            }
+                        }
+                    }
+                    Message::Binary(data) if codec::is_msgpack_frame(&data)
+                        && *connection.encoding.lock().await == Encoding::Msgpack =>
+                    {
+                        // MessagePack 编码的模块消息，转换为 JSON 后按文本消息处理
+                        let text = match codec::msgpack_to_json(&data) {
+                            Ok(text) => text,
+                            Err(e) => {
+                                log_error!("消息解析错误: {}", e);
+                                let error_response = create_parse_error_response(ModuleType::Utils, &e);
+                                if let Err(e) = send_response(&ws_sender, &error_response).await {
+                                    log_error!("发送响应失败: {}", e);
+                                    break;
+                                }
+                                continue;
+                            }
+                        };
+                        match handle_text_message(&text, &state, &connection, &mut uploads).await {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                log_info!("客户端协议版本不兼容，关闭连接");
+                                let mut sender = ws_sender.lock().await;
+                                let _ = sender.send(Message::Close(None)).await;
+                                break;
+                            }
+                            Err(e) => {
+                                log_error!("消息处理错误: {}", e);
+                            }
                         }
                     }
                     Message::Binary(data) => {
@@ -134,6 +952,13 @@ async fn handle_connection(
                         // 格式: [session_id_length: u8][session_id: bytes][data: bytes]
                         log_debug!("收到二进制数据: {} 字节", data.len());
                         
+                        let pty = router.module::<PtyHandler>(ModuleType::Pty)
+                            .filter(|_| router.is_module_enabled(ModuleType::Pty));
+                        let Some(pty) = pty else {
+                            log_error!("PTY 模块不可用，忽略二进制数据");
+                            continue;
+                        };
+                        
                         if data.len() < 2 {
                             log_error!("二进制数据格式错误: 数据太短");
                             continue;
@@ -156,7 +981,7 @@ async fn handle_connection(
                         let pty_data = &data[1 + session_id_len..];
                         log_debug!("写入 PTY: session_id={}, {} 字节", session_id, pty_data.len());
                         
-                        if let Err(e) = router.pty_handler().write_data(session_id, pty_data).await {
+                        if let Err(e) = pty.write_data(session_id, pty_data).await {
                             log_error!("写入 PTY 失败: session_id={}, {}", session_id, e);
                         }
                     }
@@ -167,10 +992,13 @@ async fn handle_connection(
                     Message::Ping(data) => {
                         // 响应 Ping
                         let mut sender = ws_sender.lock().await;
-                        sender.send(Message::Pong(data)).await?;
+                        if let Err(e) = sender.send(Message::Pong(data)).await {
+                            log_error!("响应 Ping 失败: {}", e);
+                            break;
+                        }
                     }
                     Message::Pong(_) => {
-                        // 忽略 Pong
+                        // 心跳响应，已在收到时刷新活跃时间
                     }
                     _ => {
                         log_debug!("忽略的消息类型");
@@ -182,80 +1010,220 @@ async fn handle_connection(
                 break;
             }
         }
+        // 处理期间没有读取新的帧，不应计入心跳超时
+        last_seen = tokio::time::Instant::now();
     }
     
-    log_info!("WebSocket 连接已关闭");
-    
-    // 清理所有 PTY 会话
-    router.pty_handler().cleanup_all().await;
-    
-    // 清理 Voice 模块资源
-    router.voice_handler().cleanup().await;
-    
-    // 清理 LLM 模块资源
-    router.llm_handler().cleanup().await;
+    // 注销连接 (正常关闭、接收错误、发送失败和心跳超时都会走到这里)，仍有其他连接时保留共享的会话和状态
+    let remaining = state.connections.unregister(connection.id).await;
+    log_info!("WebSocket 连接已关闭 (剩余连接: {})", remaining);
+    if remaining > 0 {
+        return Ok(());
+    }
     
-    // 清理 Utils 模块资源
-    router.utils_handler().cleanup().await;
+    // 保存状态快照后清理所有模块资源 (PTY 会话、录音、LLM 流等)
+    let cleanup = async {
+        state.save_snapshot().await;
+        router.cleanup().await;
+    };
+    if !state.connections.cleanup_when_idle(cleanup).await {
+        log_info!("已有新连接，保留模块资源");
+    }
     
     Ok(())
 }
 
 /// 处理文本消息
+/// 
+/// 超过大小上限的消息直接以错误应答；分块上传消息在连接内处理，上传完成后拼接的消息按直接发送的消息处理。
+/// 返回 Ok(false) 表示需要关闭连接 (握手时协议版本不兼容)
 async fn handle_text_message(
     text: &str,
-    router: &Arc<MessageRouter>,
-    ws_sender: &WsSender,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    state: &Arc<ServerState>,
+    connection: &Connection,
+    uploads: &mut Uploads,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let ws_sender = &connection.sender;
+    let router = &state.router;
+    let limits = &state.limits;
+    
+    if text.len() > limits.max_message_size {
+        log_error!("消息过大: {} 字节 (上限 {} 字节)", text.len(), limits.max_message_size);
+        let envelope = MessageEnvelope::peek(text);
+        let error_response = ServerResponse::error(
+            envelope.module.as_deref().and_then(ModuleType::from_name).unwrap_or(ModuleType::System),
+            ErrorCode::LimitExceeded,
+            &format!(
+                "消息大小 {} 字节超过 {} 字节的上限，请使用分块上传 (upload_begin / upload_append / upload_commit)",
+                text.len(), limits.max_message_size
+            ),
+        ).with_request_id(envelope.request_id.as_deref());
+        send_response(ws_sender, &error_response).await?;
+        return Ok(true);
+    }
+    
     // 解析消息
-    match router.parse_message(text) {
-        Ok(msg) => {
-            let module = msg.module;
+    let msg = match router.parse_message(text) {
+        Ok(msg) => msg,
+        Err(e) => {
+            send_parse_error(ws_sender, text, &e).await?;
+            return Ok(true);
+        }
+    };
+    if !upload::is_upload_message(&msg) {
+        return dispatch_message(msg, state, connection).await;
+    }
+    
+    // 分块上传
+    let request_id = msg.request_id();
+    let text = match uploads.handle(&msg) {
+        Ok(UploadOutcome::Commit(text)) => text,
+        Ok(UploadOutcome::Reply(response)) => {
+            send_response(ws_sender, &response.with_request_id(request_id.as_deref())).await?;
+            return Ok(true);
+        }
+        Err(e) => {
+            log_error!("分块上传失败: {}", e);
+            let error = RouterError::Module(ModuleError::from_error(ModuleType::System, &e));
+            let error_response = router.create_error_response(ModuleType::System, &error)
+                .with_request_id(request_id.as_deref());
+            send_response(ws_sender, &error_response).await?;
+            return Ok(true);
+        }
+    };
+    log_debug!("分块上传完成: {} 字节", text.len());
+    match router.parse_message(&text) {
+        Ok(msg) if !upload::is_upload_message(&msg) => dispatch_message(msg, state, connection).await,
+        Ok(_) => {
+            let error_response = ServerResponse::error(
+                ModuleType::System,
+                ErrorCode::InvalidParams,
+                "上传的内容不能是分块上传消息",
+            ).with_request_id(request_id.as_deref());
+            send_response(ws_sender, &error_response).await?;
+            Ok(true)
+        }
+        Err(e) => {
+            send_parse_error(ws_sender, &text, &e).await?;
+            Ok(true)
+        }
+    }
+}
+
+/// 处理一条模块消息：长时间任务 (`MessageSpec::concurrent`) 在单独的任务中处理，
+/// 使连接循环在处理期间仍能读取 cancel、kill_job 和心跳；其余消息按到达顺序依次处理
+/// 
+/// 返回 Ok(false) 表示需要关闭连接 (握手时协议版本不兼容)
+async fn dispatch_message(
+    msg: ModuleMessage,
+    state: &Arc<ServerState>,
+    connection: &Connection,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    if !state.router.is_concurrent(&msg) {
+        return route_message(msg, state, connection).await;
+    }
+    let state = Arc::clone(state);
+    let connection = connection.clone();
+    tokio::spawn(async move {
+        if let Err(e) = route_message(msg, &state, &connection).await {
+            log_error!("发送响应失败: {}", e);
+        }
+    });
+    Ok(true)
+}
+
+/// 将消息路由到模块并发送响应
+/// 
+/// 返回 Ok(false) 表示需要关闭连接 (握手时协议版本不兼容)
+async fn route_message(
+    mut msg: ModuleMessage,
+    state: &ServerState,
+    connection: &Connection,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let ws_sender = &connection.sender;
+    msg.connection = Some(connection.clone());
+    let module = msg.module;
+    let request_id = msg.request_id();
+    
+    // 路由消息到对应模块
+    match state.router.route(msg).await {
+        Ok(Some(response)) => {
+            // 发送响应 (回显请求 ID)
+            let response = response.with_request_id(request_id.as_deref());
+            send_response(ws_sender, &response).await?;
             
-            // 路由消息到对应模块
-            match router.route(msg).await {
-                Ok(Some(response)) => {
-                    // 发送响应
-                    send_response(ws_sender, &response).await?;
-                }
-                Ok(None) => {
-                    // 模块处理成功但无需响应
-                    log_debug!("模块处理完成，无响应");
-                }
-                Err(e) => {
-                    // 模块处理错误，发送错误响应
-                    log_error!("模块处理错误: {}", e);
-                    let error_response = router.create_error_response(module, &e);
-                    send_response(ws_sender, &error_response).await?;
+            // hello_ack 以原编码发送，之后的消息切换到协商的编码、语言和事件合并设置，并在声明的 vault 中处理
+            if response.module == ModuleType::System && response.msg_type == "hello_ack" {
+                if let Some(encoding) = response.payload.get("encoding")
+                    .and_then(|v| serde_json::from_value::<Encoding>(v.clone()).ok())
+                {
+                    *connection.encoding.lock().await = encoding;
                 }
+                *connection.locale.lock().await = response.payload.get("locale")
+                    .and_then(|v| serde_json::from_value::<Locale>(v.clone()).ok());
+                let vault = response.payload.get("vault")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string);
+                state.vaults.lock().unwrap_or_else(|e| e.into_inner()).insert(vault.clone());
+                *connection.vault.lock().await = vault;
+                let batching = response.payload.get("batching").and_then(|v| v.as_bool());
+                connection.outbound.set_batching(batching.unwrap_or(false));
             }
         }
+        Ok(None) => {
+            // 模块处理成功但无需响应
+            log_debug!("模块处理完成，无响应");
+        }
         Err(e) => {
-            // 消息解析错误
-            log_error!("消息解析错误: {}", e);
-            
-            // 尝试从原始 JSON 中提取 module 字段用于错误响应
-            let module = extract_module_from_json(text);
-            let error_response = create_parse_error_response(module, &e);
+            // 模块处理错误，发送错误响应 (日志由请求追踪中间件输出)
+            let error_response = state.router.create_error_response(module, &e)
+                .with_request_id(request_id.as_deref());
             send_response(ws_sender, &error_response).await?;
+            if let RouterError::IncompatibleProtocol(_) = e {
+                return Ok(false);
+            }
         }
     }
     
-    Ok(())
+    Ok(true)
+}
+
+/// 发送消息解析错误
+async fn send_parse_error(
+    ws_sender: &WsSender,
+    text: &str,
+    error: &RouterError,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    log_error!("消息解析错误: {}", error);
+    
+    // 尝试从原始 JSON 中提取 module 字段用于错误响应
+    let module = extract_module_from_json(text);
+    let error_response = create_parse_error_response(module, error)
+        .with_request_id(extract_request_id_from_json(text).as_deref());
+    send_response(ws_sender, &error_response).await
+}
+
+/// 消息的 module 和 request_id (用于应答无法完整处理的消息，其余字段解析时跳过)
+#[derive(Debug, Default, Deserialize)]
+struct MessageEnvelope {
+    #[serde(default)]
+    module: Option<String>,
+    #[serde(default)]
+    request_id: Option<String>,
+}
+
+impl MessageEnvelope {
+    fn peek(text: &str) -> Self {
+        serde_json::from_str(text).unwrap_or_default()
+    }
 }
 
 /// 从 JSON 中提取 module 字段
 fn extract_module_from_json(text: &str) -> ModuleType {
     // 尝试解析 JSON 并提取 module 字段
     if let Ok(value) = serde_json::from_str::<serde_json::Value>(text) {
-        if let Some(module_str) = value.get("module").and_then(|v| v.as_str()) {
-            match module_str {
-                "pty" => return ModuleType::Pty,
-                "voice" => return ModuleType::Voice,
-                "llm" => return ModuleType::Llm,
-                "utils" => return ModuleType::Utils,
-                _ => {}
-            }
+        if let Some(module) = value.get("module").and_then(|v| v.as_str()).and_then(ModuleType::from_name) {
+            return module;
         }
     }
     
@@ -263,11 +1231,57 @@ fn extract_module_from_json(text: &str) -> ModuleType {
     ModuleType::Utils
 }
 
+// ============================================================================
+// 认证
+// ============================================================================
+
+/// 生成认证令牌 (128 位随机数的十六进制表示)
+fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// 从查询字符串中提取 token 参数
+pub(crate) fn token_from_query(query: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "token")
+        .map(|(_, value)| value.to_string())
+}
+
+/// 从认证消息中提取令牌和消息类型
+/// 
+/// 仅接受 system/auth 和 system/hello 两种消息
+fn auth_message_token(text: &str) -> Option<(String, String)> {
+    let value = serde_json::from_str::<serde_json::Value>(text).ok()?;
+    if value.get("module")?.as_str()? != "system" {
+        return None;
+    }
+    let msg_type = value.get("type")?.as_str()?;
+    if msg_type != "auth" && msg_type != "hello" {
+        return None;
+    }
+    let token = value.get("token")?.as_str()?;
+    Some((token.to_string(), msg_type.to_string()))
+}
+
+/// 常量时间比较令牌，避免通过响应时间推测令牌
+pub(crate) fn tokens_match(candidate: &str, token: &str) -> bool {
+    let (a, b) = (candidate.as_bytes(), token.as_bytes());
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 从 JSON 中提取 request_id 字段
+fn extract_request_id_from_json(text: &str) -> Option<String> {
+    let value = serde_json::from_str::<serde_json::Value>(text).ok()?;
+    value.get("request_id")?.as_str().map(str::to_string)
+}
+
 /// 创建解析错误响应
 fn create_parse_error_response(module: ModuleType, error: &RouterError) -> ServerResponse {
     ServerResponse::error(
         module,
-        "PARSE_ERROR",
+        ErrorCode::ParseError,
         &format!("消息解析失败: {}", error)
     )
 }
@@ -304,3 +1318,167 @@ pub async fn send_binary(
     sender.send(Message::Binary(data.into())).await?;
     Ok(())
 }
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::subscription::Topic;
+
+    #[test]
+    fn test_generate_token() {
+        let token = generate_token();
+        assert_eq!(token.len(), 32);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, generate_token());
+    }
+
+    #[test]
+    fn test_token_from_query() {
+        assert_eq!(token_from_query("token=abc"), Some("abc".to_string()));
+        assert_eq!(token_from_query("v=1&token=abc"), Some("abc".to_string()));
+        assert_eq!(token_from_query("tokens=abc"), None);
+        assert_eq!(token_from_query(""), None);
+    }
+
+    #[test]
+    fn test_auth_message_token() {
+        assert_eq!(
+            auth_message_token(r#"{"module": "system", "type": "auth", "token": "abc"}"#),
+            Some(("abc".to_string(), "auth".to_string()))
+        );
+        assert_eq!(
+            auth_message_token(r#"{"module": "system", "type": "hello", "protocol_version": 1, "token": "abc"}"#),
+            Some(("abc".to_string(), "hello".to_string()))
+        );
+        assert_eq!(auth_message_token(r#"{"module": "pty", "type": "init", "token": "abc"}"#), None);
+        assert_eq!(auth_message_token(r#"{"module": "system", "type": "hello", "protocol_version": 1}"#), None);
+        assert_eq!(auth_message_token("not json"), None);
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("abc", "abc"));
+        assert!(!tokens_match("abd", "abc"));
+        assert!(!tokens_match("ab", "abc"));
+        assert!(!tokens_match("", "abc"));
+    }
+
+    /// 创建把消息记录到列表中的发送器
+    fn recording_sender() -> (WsSink, Arc<std::sync::Mutex<Vec<Message>>>) {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = futures_util::sink::unfold(Arc::clone(&received), |received, msg: Message| async move {
+            received.lock().unwrap().push(msg);
+            Ok::<_, tokio_tungstenite::tungstenite::Error>(received)
+        });
+        (Box::pin(sink), received)
+    }
+
+    /// 等待连接的出站队列全部写出
+    async fn flush(connections: &[&Connection]) {
+        for connection in connections {
+            connection.outbound.flush().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_registry() {
+        let registry = Arc::new(ConnectionRegistry::new());
+        let (first_sender, first) = recording_sender();
+        let (second_sender, second) = recording_sender();
+        let a = registry.register(first_sender).await;
+        let b = registry.register(second_sender).await;
+        assert_ne!(a.id, b.id);
+
+        let broadcast = registry.broadcast_sender();
+        broadcast.lock().await.send(Message::Text("event".into())).await.unwrap();
+        flush(&[&a, &b]).await;
+        assert_eq!(first.lock().unwrap().len(), 1);
+        assert_eq!(second.lock().unwrap().len(), 1);
+
+        assert_eq!(registry.unregister(a.id).await, 1);
+        broadcast.lock().await.send(Message::Text("event".into())).await.unwrap();
+        flush(&[&b]).await;
+        assert_eq!(first.lock().unwrap().len(), 1);
+        assert_eq!(second.lock().unwrap().len(), 2);
+
+        // 最后一个连接断开时开始计算空闲时间，新连接建立后清除
+        let idle_since = registry.idle_since();
+        assert_eq!(*idle_since.borrow(), None);
+        assert_eq!(registry.unregister(b.id).await, 0);
+        assert!(idle_since.borrow().is_some());
+        let (sender, _) = recording_sender();
+        registry.register(sender).await;
+        assert_eq!(*idle_since.borrow(), None);
+    }
+
+    #[tokio::test]
+    async fn test_connection_encoding() {
+        let registry = Arc::new(ConnectionRegistry::new());
+        let (sink, received) = recording_sender();
+        let connection = registry.register(sink).await;
+        let event = || Message::Text(r#"{"module":"llm","type":"stream_chunk","content":"hi"}"#.into());
+
+        connection.sender.lock().await.send(event()).await.unwrap();
+        *connection.encoding.lock().await = Encoding::Msgpack;
+        connection.events.lock().await.send(event()).await.unwrap();
+        flush(&[&connection]).await;
+
+        let received = received.lock().unwrap();
+        assert!(matches!(received[0], Message::Text(_)));
+        let Message::Binary(data) = &received[1] else {
+            panic!("expected MessagePack frame");
+        };
+        let decoded: serde_json::Value = serde_json::from_str(&codec::msgpack_to_json(data).unwrap()).unwrap();
+        assert_eq!(decoded["content"], "hi");
+    }
+
+    #[tokio::test]
+    async fn test_subscription_delivery() {
+        let registry = Arc::new(ConnectionRegistry::new());
+        let (observer_sender, observer) = recording_sender();
+        let (origin_sender, origin) = recording_sender();
+        let (idle_sender, idle) = recording_sender();
+        let observer_conn = registry.register(observer_sender).await;
+        let origin_conn = registry.register(origin_sender).await;
+        let idle_conn = registry.register(idle_sender).await;
+        let all = [&observer_conn, &origin_conn, &idle_conn];
+        observer_conn.subscriptions.lock().await
+            .subscribe([Topic::parse("pty:abc").unwrap()]);
+
+        let event = |session_id: &str| Message::Text(
+            serde_json::json!({ "module": "pty", "type": "output", "session_id": session_id }).to_string().into()
+        );
+
+        // 发起连接总能收到自己的事件，订阅者收到匹配的抄送，未订阅的连接不会收到
+        origin_conn.events.lock().await.send(event("abc")).await.unwrap();
+        origin_conn.events.lock().await.send(event("xyz")).await.unwrap();
+        flush(&all).await;
+        assert_eq!(origin.lock().unwrap().len(), 2);
+        assert_eq!(observer.lock().unwrap().len(), 1);
+        assert_eq!(idle.lock().unwrap().len(), 0);
+
+        // PTY 输出二进制帧按帧头中的会话 ID 匹配
+        let mut frame = vec![3u8];
+        frame.extend_from_slice(b"abcoutput");
+        origin_conn.events.lock().await.send(Message::Binary(frame.into())).await.unwrap();
+        flush(&all).await;
+        assert_eq!(observer.lock().unwrap().len(), 2);
+
+        // 发布只发给匹配的订阅者
+        registry.publish(ModuleType::Pty, Some("abc"), event("abc")).await;
+        flush(&all).await;
+        assert_eq!(observer.lock().unwrap().len(), 3);
+        assert_eq!(idle.lock().unwrap().len(), 0);
+
+        // 广播只发给匹配的订阅者和没有订阅的连接
+        registry.broadcast_sender().lock().await.send(event("xyz")).await.unwrap();
+        flush(&all).await;
+        assert_eq!(observer.lock().unwrap().len(), 3);
+        assert_eq!(origin.lock().unwrap().len(), 4);
+        assert_eq!(idle.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,125 @@
+// 状态快照
+// 最后一个连接断开或服务器退出前，各模块导出需要跨重启保留的轻量状态 (目前为 PTY 会话的 shell、
+// 工作目录、尺寸和回滚缓冲)，写入数据目录的 state/snapshot.json；下次启动时交给对应模块恢复。
+// 声明了 vault 的连接的状态按 vault 分别写入 state/vaults/<名称>/snapshot.json。
+// 快照可能包含终端输出，文件仅当前用户可读
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 快照文件名
+const SNAPSHOT_FILE_NAME: &str = "snapshot.json";
+
+/// 快照格式版本 (格式不兼容时递增，旧版本的快照不再恢复)
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// 状态快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    /// 保存时间 (RFC 3339)
+    pub saved_at: String,
+    /// 写入快照的服务器版本
+    pub server_version: String,
+    /// 快照所属的 vault (未声明 vault 的连接的状态为 None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault: Option<String>,
+    /// 各模块的状态，以模块名为键
+    pub modules: BTreeMap<String, serde_json::Value>,
+}
+
+impl Snapshot {
+    /// 以当前时间和服务器版本创建 `vault` 的快照
+    pub fn new(vault: Option<String>, modules: BTreeMap<String, serde_json::Value>) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            saved_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            server_version: env!("CARGO_PKG_VERSION").to_string(),
+            vault,
+            modules,
+        }
+    }
+
+    /// 写入 `dir` 下的快照文件 (先写临时文件再替换，不会留下写了一半的快照)
+    pub fn save(&self, dir: &Path) -> io::Result<PathBuf> {
+        let path = dir.join(SNAPSHOT_FILE_NAME);
+        let temp = dir.join(format!("{}.tmp", SNAPSHOT_FILE_NAME));
+        let contents = serde_json::to_vec(self).map_err(io::Error::other)?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        io::Write::write_all(&mut options.open(&temp)?, &contents)?;
+        fs::rename(&temp, &path)?;
+        Ok(path)
+    }
+
+    /// 读取 `dir` 下的快照，没有快照或版本不兼容时返回 None
+    pub fn load(dir: &Path) -> io::Result<Option<Self>> {
+        let text = match fs::read_to_string(dir.join(SNAPSHOT_FILE_NAME)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let snapshot: Snapshot = serde_json::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok((snapshot.version == SNAPSHOT_VERSION).then_some(snapshot))
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("snapshot-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = test_dir("roundtrip");
+        assert!(Snapshot::load(&dir).unwrap().is_none());
+
+        let mut modules = BTreeMap::new();
+        modules.insert("pty".to_string(), serde_json::json!({ "sessions": [] }));
+        let path = Snapshot::new(None, modules).save(&dir).unwrap();
+        assert_eq!(path, dir.join(SNAPSHOT_FILE_NAME));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        let loaded = Snapshot::load(&dir).unwrap().unwrap();
+        assert_eq!(loaded.modules["pty"], serde_json::json!({ "sessions": [] }));
+        assert_eq!(loaded.server_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(loaded.vault, None);
+
+        // 版本不兼容的快照不恢复
+        let mut old = loaded;
+        old.version = 0;
+        old.save(&dir).unwrap();
+        assert!(Snapshot::load(&dir).unwrap().is_none());
+
+        // 内容损坏时报告错误
+        fs::write(&path, "{").unwrap();
+        assert!(Snapshot::load(&dir).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
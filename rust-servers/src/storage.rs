@@ -0,0 +1,509 @@
+// 数据目录
+// 统一管理服务器写入磁盘的数据：日志、缓存、转写历史、用量记录、向量库、TLS 证书、状态快照、审计日志、计划任务、备份快照和生成的文件各占一个子目录。
+// 指定 --data-dir 时全部位于该目录下；否则使用平台数据目录
+// (Linux 为 XDG 目录，macOS 为 ~/Library，Windows 为 AppData)，缓存放在平台缓存目录。
+// 多个 vault 共用一个服务器时，客户端在 hello 中声明 vault 标识，按 vault 保存的数据 (向量索引、备份快照等)
+// 位于区域下的 vaults/<名称> 子目录，互不可见
+
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 平台目录下的应用目录名
+const APP_DIR_NAME: &str = "smart-workflow";
+
+/// 区域下按 vault 划分的子目录
+const VAULTS_DIR_NAME: &str = "vaults";
+
+/// vault 目录名中保留的标识前缀长度 (字符)
+const VAULT_PREFIX_CHARS: usize = 32;
+
+tokio::task_local! {
+    /// 当前消息来源连接声明的 vault
+    static VAULT: Option<String>;
+}
+
+// ============================================================================
+// 数据区域
+// ============================================================================
+
+/// 数据目录下的区域 (各占一个子目录)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageArea {
+    /// 日志文件
+    Logs,
+    /// 可随时删除的缓存 (clear_cache 清空)
+    Cache,
+    /// 转写历史
+    History,
+    /// 用量记录
+    Usage,
+    /// 向量库
+    Vectors,
+    /// TLS 证书和私钥
+    Tls,
+    /// 重启时恢复的状态快照
+    State,
+    /// 特权操作的审计日志
+    Audit,
+    /// 计划任务
+    Schedules,
+    /// 备份快照
+    Backups,
+    /// 生成的文件 (录音、终端录制、导出的 HTML 等)，可通过 HTTP 文件接口访问
+    Artifacts,
+    /// 本地模型文件 (如 Whisper 模型)
+    Models,
+}
+
+impl StorageArea {
+    /// 全部区域
+    pub const ALL: [StorageArea; 12] = [
+        StorageArea::Logs,
+        StorageArea::Cache,
+        StorageArea::History,
+        StorageArea::Usage,
+        StorageArea::Vectors,
+        StorageArea::Tls,
+        StorageArea::State,
+        StorageArea::Audit,
+        StorageArea::Schedules,
+        StorageArea::Backups,
+        StorageArea::Artifacts,
+        StorageArea::Models,
+    ];
+
+    /// 子目录名
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            StorageArea::Logs => "logs",
+            StorageArea::Cache => "cache",
+            StorageArea::History => "history",
+            StorageArea::Usage => "usage",
+            StorageArea::Vectors => "vectors",
+            StorageArea::Tls => "tls",
+            StorageArea::State => "state",
+            StorageArea::Audit => "audit",
+            StorageArea::Schedules => "schedules",
+            StorageArea::Backups => "backups",
+            StorageArea::Artifacts => "artifacts",
+            StorageArea::Models => "models",
+        }
+    }
+}
+
+/// 区域占用情况
+#[derive(Debug, Clone, Serialize)]
+pub struct AreaUsage {
+    pub area: StorageArea,
+    pub path: String,
+    /// 文件总字节数 (目录不存在时为 0)
+    pub bytes: u64,
+    /// 文件数
+    pub files: u64,
+}
+
+/// 数据目录占用情况
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageInfo {
+    /// 数据目录
+    pub root: String,
+    /// 是否由 --data-dir 或配置文件指定 (否则为平台默认目录)
+    pub explicit: bool,
+    pub areas: Vec<AreaUsage>,
+    pub total_bytes: u64,
+}
+
+/// 磁盘空间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DiskSpace {
+    /// 当前用户可用的字节数
+    pub available_bytes: u64,
+    /// 文件系统总字节数
+    pub total_bytes: u64,
+}
+
+/// 清理结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Cleared {
+    /// 释放的字节数
+    pub freed_bytes: u64,
+    /// 删除的文件数
+    pub files: u64,
+}
+
+// ============================================================================
+// 数据目录
+// ============================================================================
+
+/// 服务器数据目录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDirs {
+    root: PathBuf,
+    cache: PathBuf,
+    explicit: bool,
+}
+
+impl DataDirs {
+    /// 使用指定的数据目录 (缓存也放在其中)
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        Self {
+            cache: root.join(StorageArea::Cache.dir_name()),
+            root,
+            explicit: true,
+        }
+    }
+
+    /// 解析数据目录：优先使用指定的目录，否则使用平台默认目录 (无法确定用户目录时返回 None)
+    pub fn resolve(explicit: Option<&Path>) -> Option<Self> {
+        match explicit {
+            Some(root) => Some(Self::at(root)),
+            None => platform_dirs(|key| std::env::var_os(key)),
+        }
+    }
+
+    /// 数据目录
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 区域路径 (不保证已存在)
+    pub fn path(&self, area: StorageArea) -> PathBuf {
+        match area {
+            StorageArea::Cache => self.cache.clone(),
+            area => self.root.join(area.dir_name()),
+        }
+    }
+
+    /// 区域路径，不存在时创建
+    pub fn ensure(&self, area: StorageArea) -> io::Result<PathBuf> {
+        let path = self.path(area);
+        fs::create_dir_all(&path)?;
+        Ok(path)
+    }
+
+    /// 统计各区域的占用 (遍历目录，应在阻塞线程中调用)
+    pub fn info(&self) -> StorageInfo {
+        let areas: Vec<AreaUsage> = StorageArea::ALL.iter()
+            .map(|&area| {
+                let path = self.path(area);
+                let (bytes, files) = dir_usage(&path);
+                AreaUsage {
+                    area,
+                    path: path.display().to_string(),
+                    bytes,
+                    files,
+                }
+            })
+            .collect();
+        StorageInfo {
+            root: self.root.display().to_string(),
+            explicit: self.explicit,
+            total_bytes: areas.iter().map(|area| area.bytes).sum(),
+            areas,
+        }
+    }
+
+    /// 数据目录所在磁盘的可用空间 (字节)
+    ///
+    /// 目录尚未创建时按最近的已存在上级目录计算
+    pub fn available_space(&self) -> io::Result<u64> {
+        disk_space(&self.root).map(|space| space.available_bytes)
+    }
+
+    /// 清空缓存目录 (保留目录本身)
+    ///
+    /// 单个文件删除失败 (如被占用) 时跳过，结果只统计实际删除的文件
+    pub fn clear_cache(&self) -> io::Result<Cleared> {
+        let mut cleared = Cleared::default();
+        let entries = match fs::read_dir(&self.cache) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(cleared),
+            Err(e) => return Err(e),
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                let (bytes, files) = dir_usage(&path);
+                if fs::remove_dir_all(&path).is_ok() {
+                    cleared.freed_bytes += bytes;
+                    cleared.files += files;
+                }
+            } else if fs::remove_file(&path).is_ok() {
+                cleared.freed_bytes += metadata.len();
+                cleared.files += 1;
+            }
+        }
+        Ok(cleared)
+    }
+}
+
+/// 平台默认目录
+///
+/// - Linux 等: `$XDG_DATA_HOME` (默认 `~/.local/share`)，缓存 `$XDG_CACHE_HOME` (默认 `~/.cache`)
+/// - macOS: `~/Library/Application Support`，缓存 `~/Library/Caches`
+/// - Windows: `%APPDATA%`，缓存 `%LOCALAPPDATA%`
+fn platform_dirs(env: impl Fn(&str) -> Option<OsString>) -> Option<DataDirs> {
+    // 只接受绝对路径，相对路径会随工作目录变化
+    let var = |key: &str| env(key).map(PathBuf::from).filter(|path| path.is_absolute());
+
+    let (data, cache) = if cfg!(windows) {
+        let data = var("APPDATA").or_else(|| var("USERPROFILE").map(|home| home.join("AppData").join("Roaming")))?;
+        let cache = var("LOCALAPPDATA").unwrap_or_else(|| data.clone());
+        (data.join(APP_DIR_NAME), cache.join(APP_DIR_NAME).join(StorageArea::Cache.dir_name()))
+    } else if cfg!(target_os = "macos") {
+        let library = var("HOME")?.join("Library");
+        (library.join("Application Support").join(APP_DIR_NAME), library.join("Caches").join(APP_DIR_NAME))
+    } else {
+        let home = var("HOME");
+        let data = var("XDG_DATA_HOME").or_else(|| home.as_ref().map(|home| home.join(".local").join("share")))?;
+        let cache = var("XDG_CACHE_HOME").or_else(|| home.map(|home| home.join(".cache")))
+            .map(|cache| cache.join(APP_DIR_NAME))
+            .unwrap_or_else(|| data.join(APP_DIR_NAME).join(StorageArea::Cache.dir_name()));
+        (data.join(APP_DIR_NAME), cache)
+    };
+    Some(DataDirs { root: data, cache, explicit: false })
+}
+
+/// 路径所在磁盘的空间
+///
+/// 路径尚未创建时按最近的已存在上级目录计算
+pub fn disk_space(path: &Path) -> io::Result<DiskSpace> {
+    let existing = path.ancestors()
+        .find(|path| path.exists())
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+    filesystem_space(existing)
+}
+
+/// 路径所在文件系统的空间
+#[cfg(unix)]
+fn filesystem_space(path: &Path) -> io::Result<DiskSpace> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // 字段类型因平台而异 (macOS 上 f_bavail 为 u32)
+    #[allow(clippy::unnecessary_cast)]
+    Ok(DiskSpace {
+        available_bytes: stat.f_bavail as u64 * stat.f_frsize as u64,
+        total_bytes: stat.f_blocks as u64 * stat.f_frsize as u64,
+    })
+}
+
+/// 路径所在卷的空间
+#[cfg(windows)]
+fn filesystem_space(path: &Path) -> io::Result<DiskSpace> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let (mut available, mut total) = (0u64, 0u64);
+    let ok = unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, &mut total, std::ptr::null_mut()) };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(DiskSpace { available_bytes: available, total_bytes: total })
+}
+
+/// 统计目录下的总字节数和文件数 (不跟随符号链接，无法读取的条目跳过)
+fn dir_usage(dir: &Path) -> (u64, u64) {
+    let (mut bytes, mut files) = (0, 0);
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = fs::symlink_metadata(entry.path()) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else {
+                bytes += metadata.len();
+                files += 1;
+            }
+        }
+    }
+    (bytes, files)
+}
+
+// ============================================================================
+// vault 命名空间
+// ============================================================================
+
+/// vault 的目录名：标识中的字母数字 (其余字符替换为 `_`，最多 32 个) 加标识的哈希前缀，
+/// 不同标识不会落到同一目录，目录名也不含路径分隔符
+pub fn vault_dir_name(vault: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let prefix: String = vault.chars()
+        .take(VAULT_PREFIX_CHARS)
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let hash: String = Sha256::digest(vault.as_bytes())[..6].iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}-{}", prefix, hash)
+}
+
+/// 区域目录 `dir` 中属于 `vault` 的子目录 (未声明 vault 时为 `dir` 本身)
+pub fn vault_path(dir: &Path, vault: Option<&str>) -> PathBuf {
+    match vault {
+        Some(vault) => dir.join(VAULTS_DIR_NAME).join(vault_dir_name(vault)),
+        None => dir.to_path_buf(),
+    }
+}
+
+/// 区域目录 `dir` 中各 vault 的子目录
+pub fn vault_dirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir.join(VAULTS_DIR_NAME)) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// 在 `vault` 的上下文中运行 `future`，期间按 vault 保存的数据使用该 vault 的目录
+pub async fn vault_scope<F: Future>(vault: Option<String>, future: F) -> F::Output {
+    VAULT.scope(vault, future).await
+}
+
+/// 将当前 vault 传递给要在后台任务中运行的 `future`
+pub fn inherit_vault<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    VAULT.scope(current_vault(), future)
+}
+
+/// 当前 vault (不在 vault 上下文中或客户端未声明时为 None)
+pub fn current_vault() -> Option<String> {
+    VAULT.try_with(|vault| vault.clone()).ok().flatten()
+}
+
+// ============================================================================
+// 全局数据目录
+// ============================================================================
+
+static DATA_DIRS: OnceLock<DataDirs> = OnceLock::new();
+
+/// 设置全局数据目录 (启动时调用一次，重复调用时忽略)
+pub fn init(dirs: DataDirs) {
+    let _ = DATA_DIRS.set(dirs);
+}
+
+/// 全局数据目录 (未设置时为 None)
+pub fn data_dirs() -> Option<&'static DataDirs> {
+    DATA_DIRS.get()
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("storage-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_platform_dirs() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |key: &str| vars.iter().find(|(k, _)| *k == key).map(|(_, v)| OsString::from(v))
+        };
+
+        if cfg!(windows) {
+            let dirs = platform_dirs(env(&[("APPDATA", r"C:\Users\me\AppData\Roaming"), ("LOCALAPPDATA", r"C:\Users\me\AppData\Local")])).unwrap();
+            assert_eq!(dirs.root(), Path::new(r"C:\Users\me\AppData\Roaming\smart-workflow"));
+            assert_eq!(dirs.path(StorageArea::Cache), Path::new(r"C:\Users\me\AppData\Local\smart-workflow\cache"));
+        } else if cfg!(target_os = "macos") {
+            let dirs = platform_dirs(env(&[("HOME", "/Users/me")])).unwrap();
+            assert_eq!(dirs.root(), Path::new("/Users/me/Library/Application Support/smart-workflow"));
+            assert_eq!(dirs.path(StorageArea::Cache), Path::new("/Users/me/Library/Caches/smart-workflow"));
+        } else {
+            let dirs = platform_dirs(env(&[("HOME", "/home/me")])).unwrap();
+            assert_eq!(dirs.root(), Path::new("/home/me/.local/share/smart-workflow"));
+            assert_eq!(dirs.path(StorageArea::Cache), Path::new("/home/me/.cache/smart-workflow"));
+            assert_eq!(dirs.path(StorageArea::History), Path::new("/home/me/.local/share/smart-workflow/history"));
+            assert!(!dirs.explicit);
+
+            // XDG 变量优先，相对路径忽略
+            let dirs = platform_dirs(env(&[("HOME", "/home/me"), ("XDG_DATA_HOME", "/data"), ("XDG_CACHE_HOME", "relative")])).unwrap();
+            assert_eq!(dirs.root(), Path::new("/data/smart-workflow"));
+            assert_eq!(dirs.path(StorageArea::Cache), Path::new("/home/me/.cache/smart-workflow"));
+        }
+        assert!(platform_dirs(env(&[])).is_none());
+    }
+
+    #[test]
+    fn test_info_and_clear_cache() {
+        let dir = test_dir("info");
+        let dirs = DataDirs::resolve(Some(&dir)).unwrap();
+        assert!(dirs.explicit);
+        assert_eq!(dirs.path(StorageArea::Cache), dir.join("cache"));
+
+        // 目录尚不存在时各区域为空
+        let info = dirs.info();
+        assert_eq!(info.total_bytes, 0);
+        assert_eq!(info.areas.len(), StorageArea::ALL.len());
+        assert_eq!(dirs.clear_cache().unwrap(), Cleared::default());
+
+        let cache = dirs.ensure(StorageArea::Cache).unwrap();
+        fs::create_dir_all(cache.join("images")).unwrap();
+        fs::write(cache.join("a.bin"), [0u8; 10]).unwrap();
+        fs::write(cache.join("images").join("b.png"), [0u8; 5]).unwrap();
+        fs::write(dirs.ensure(StorageArea::History).unwrap().join("h.jsonl"), "{}\n").unwrap();
+
+        let info = dirs.info();
+        let usage = |area| info.areas.iter().find(|usage| usage.area == area).unwrap();
+        assert_eq!((usage(StorageArea::Cache).bytes, usage(StorageArea::Cache).files), (15, 2));
+        assert_eq!(usage(StorageArea::History).bytes, 3);
+        assert_eq!(info.total_bytes, 18);
+
+        // 只清空缓存，其他区域保留
+        assert_eq!(dirs.clear_cache().unwrap(), Cleared { freed_bytes: 15, files: 2 });
+        assert!(cache.exists());
+        assert_eq!(dirs.info().total_bytes, 3);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_vault_path() {
+        let name = vault_dir_name("Work Notes/../2024");
+        assert!(name.starts_with("Work_Notes____2024-"));
+        assert_eq!(name.len(), "Work_Notes____2024-".len() + 12);
+        // 替换字符后前缀相同的标识仍然分开
+        assert_ne!(vault_dir_name("a/b"), vault_dir_name("a_b"));
+        assert_eq!(vault_dir_name(&"x".repeat(100)).len(), 32 + 1 + 12);
+
+        let dir = Path::new("/data/vectors");
+        assert_eq!(vault_path(dir, None), dir);
+        assert_eq!(vault_path(dir, Some("a_b")), dir.join("vaults").join(vault_dir_name("a_b")));
+
+        assert_eq!(current_vault(), None);
+        vault_scope(Some("work".to_string()), async {
+            assert_eq!(current_vault().as_deref(), Some("work"));
+            let inherited = tokio::spawn(inherit_vault(async { current_vault() })).await.unwrap();
+            assert_eq!(inherited.as_deref(), Some("work"));
+        }).await;
+    }
+}
@@ -0,0 +1,105 @@
+// 消息编码
+// 握手时协商的消息编码：默认 JSON 文本帧，可选 MessagePack 二进制帧
+//
+// MessagePack 消息以二进制帧发送，与 PTY 二进制帧 ([session_id_length: u8][session_id][data])
+// 通过首字节区分：MessagePack 消息总是 map，首字节为 0x80-0x8F、0xDE 或 0xDF，
+// 而 PTY 会话 ID 长度不超过 127 字节
+
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::router::RouterError;
+
+/// 消息编码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// JSON 文本帧 (默认)
+    #[default]
+    Json,
+    /// MessagePack 二进制帧
+    Msgpack,
+}
+
+/// 服务器支持的编码
+pub const SUPPORTED_ENCODINGS: [Encoding; 2] = [Encoding::Json, Encoding::Msgpack];
+
+/// 协商编码：取客户端偏好列表中第一个服务器支持的编码，均不支持时使用 JSON
+pub fn negotiate_encoding(preferred: &[String]) -> Encoding {
+    preferred
+        .iter()
+        .find_map(|name| serde_json::from_value(serde_json::Value::String(name.clone())).ok())
+        .unwrap_or_default()
+}
+
+/// 判断二进制帧是否为 MessagePack 消息
+pub fn is_msgpack_frame(data: &[u8]) -> bool {
+    matches!(data.first(), Some(0x80..=0x8F | 0xDE | 0xDF))
+}
+
+/// 将 MessagePack 消息解码为 JSON 文本
+pub fn msgpack_to_json(data: &[u8]) -> Result<String, RouterError> {
+    let value: serde_json::Value = rmp_serde::from_slice(data)
+        .map_err(|e| RouterError::InvalidMessage(format!("Invalid MessagePack message: {}", e)))?;
+    Ok(value.to_string())
+}
+
+/// 按编码转换发出的消息
+///
+/// 使用 MessagePack 时 JSON 文本帧转换为 MessagePack 二进制帧，其余帧 (PTY 输出、Close 等) 原样发送
+pub fn encode_message(msg: Message, encoding: Encoding) -> Message {
+    let Message::Text(text) = &msg else {
+        return msg;
+    };
+    if encoding == Encoding::Json {
+        return msg;
+    }
+    let encoded = serde_json::from_str::<serde_json::Value>(text.as_str())
+        .ok()
+        .and_then(|value| rmp_serde::to_vec_named(&value).ok());
+    match encoded {
+        Some(bytes) => Message::Binary(bytes.into()),
+        None => msg,
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_encoding() {
+        assert_eq!(negotiate_encoding(&[]), Encoding::Json);
+        assert_eq!(negotiate_encoding(&["cbor".to_string(), "msgpack".to_string()]), Encoding::Msgpack);
+        assert_eq!(negotiate_encoding(&["json".to_string(), "msgpack".to_string()]), Encoding::Json);
+    }
+
+    #[test]
+    fn test_msgpack_roundtrip() {
+        let json = r#"{"module":"voice","type":"audio_level","level":0.5,"waveform":[0.1,0.2]}"#;
+        let encoded = encode_message(Message::Text(json.into()), Encoding::Msgpack);
+        let Message::Binary(data) = encoded else {
+            panic!("expected binary frame");
+        };
+        assert!(is_msgpack_frame(&data));
+
+        let decoded: serde_json::Value = serde_json::from_str(&msgpack_to_json(&data).unwrap()).unwrap();
+        assert_eq!(decoded, serde_json::from_str::<serde_json::Value>(json).unwrap());
+    }
+
+    #[test]
+    fn test_encode_passthrough() {
+        let text = Message::Text(r#"{"module":"pty"}"#.into());
+        assert_eq!(encode_message(text.clone(), Encoding::Json), text);
+
+        // PTY 输出帧不转换，且不会被识别为 MessagePack
+        let frame = Message::Binary(vec![3, b'a', b'b', b'c', b'x'].into());
+        assert_eq!(encode_message(frame.clone(), Encoding::Msgpack), frame);
+        assert!(!is_msgpack_frame(&[36, b'a']));
+        assert!(msgpack_to_json(&[0x81]).is_err());
+    }
+}
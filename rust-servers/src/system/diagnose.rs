@@ -0,0 +1,321 @@
+// 自我诊断
+// system/diagnose 并行运行一组快速检查：默认 (或指定的) 麦克风能否打开、默认 shell 能否在 PTY 中启动、
+// ASR / LLM 端点能否连通、数据目录所在磁盘的剩余空间。每项检查都有超时，结果汇总为结构化报告，
+// 供插件的故障排查面板显示。ASR / LLM 配置由插件保存，端点随请求传入
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::audit::{self, AuditAction};
+use crate::network::NetworkError;
+use crate::router::ModuleType;
+use crate::storage;
+use crate::voice::config::ASRConfig;
+
+/// 单项检查的超时
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 可用空间低于此值时提示 (字节)
+const LOW_DISK_SPACE: u64 = 100 * 1024 * 1024;
+
+/// 诊断项名称 (checks 过滤使用)
+pub const CHECK_NAMES: &[&str] = &["microphone", "shell", "network", "disk"];
+
+/// 检查结果状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// 可以工作但需要注意 (如磁盘空间不足)
+    Warn,
+    Fail,
+}
+
+/// 单项检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    /// 诊断项 (microphone / shell / network / disk)
+    pub check: &'static str,
+    /// 检查对象 (network 为端点名称)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub status: CheckStatus,
+    pub message: String,
+    pub duration_ms: u64,
+    /// 检查相关的细节 (设备名、shell 路径、HTTP 状态码、可用空间等)
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub details: serde_json::Value,
+}
+
+/// 诊断报告
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosisReport {
+    /// 没有失败的检查项
+    pub ok: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+/// diagnose 请求
+#[derive(Debug, Default, Deserialize)]
+pub struct DiagnoseRequest {
+    /// 只运行指定的诊断项 (省略时全部运行)
+    #[serde(default)]
+    pub checks: Option<Vec<String>>,
+    /// 当前 ASR 配置：检查主备引擎的端点，并使用其中的录音设备
+    #[serde(default)]
+    pub asr_config: Option<ASRConfig>,
+    /// 其他需要检查连通性的端点 (名称 -> URL，如 LLM 端点)
+    #[serde(default)]
+    pub endpoints: BTreeMap<String, String>,
+}
+
+impl DiagnoseRequest {
+    fn wants(&self, check: &str) -> bool {
+        self.checks.as_ref().is_none_or(|checks| checks.iter().any(|c| c == check))
+    }
+}
+
+/// 检查各项的中间结果：状态、说明和细节
+type Outcome = (CheckStatus, String, serde_json::Value);
+
+/// 运行诊断
+pub async fn run(request: DiagnoseRequest) -> DiagnosisReport {
+    let mut endpoints: Vec<(String, String)> = Vec::new();
+    if let Some(asr) = &request.asr_config {
+        if let Some(url) = crate::voice::asr::endpoint_url(&asr.primary) {
+            endpoints.push(("asr.primary".to_string(), url));
+        }
+        if let Some(url) = asr.fallback.as_ref().and_then(crate::voice::asr::endpoint_url) {
+            endpoints.push(("asr.fallback".to_string(), url));
+        }
+    }
+    endpoints.extend(request.endpoints.clone());
+
+    let device = request.asr_config.as_ref().and_then(|asr| asr.recording_device.clone());
+    let microphone = async {
+        if request.wants("microphone") {
+            Some(timed("microphone", None, blocking(move || check_microphone(device.as_deref()))).await)
+        } else {
+            None
+        }
+    };
+    let shell = async {
+        if request.wants("shell") {
+            // 阻塞线程中没有请求上下文，审计记录的请求 ID 需要显式传入
+            let request_id = audit::current_request_id();
+            Some(timed("shell", None, blocking(move || check_shell(request_id))).await)
+        } else {
+            None
+        }
+    };
+    let network = async {
+        if request.wants("network") {
+            let checks = endpoints.into_iter()
+                .map(|(name, url)| timed("network", Some(name), check_endpoint(url)));
+            futures_util::future::join_all(checks).await
+        } else {
+            Vec::new()
+        }
+    };
+    let disk = async {
+        if request.wants("disk") {
+            Some(timed("disk", None, blocking(check_disk)).await)
+        } else {
+            None
+        }
+    };
+    let (microphone, shell, network, disk) = tokio::join!(microphone, shell, network, disk);
+
+    let checks: Vec<CheckResult> = microphone.into_iter()
+        .chain(shell)
+        .chain(network)
+        .chain(disk)
+        .collect();
+    DiagnosisReport {
+        ok: checks.iter().all(|check| check.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+/// 运行一项检查并计时，超时记为失败
+async fn timed(check: &'static str, target: Option<String>, future: impl Future<Output = Outcome>) -> CheckResult {
+    let started = Instant::now();
+    let (status, message, details) = tokio::time::timeout(CHECK_TIMEOUT, future)
+        .await
+        .unwrap_or_else(|_| (CheckStatus::Fail, format!("检查超时 ({} 秒)", CHECK_TIMEOUT.as_secs()), serde_json::Value::Null));
+    CheckResult {
+        check,
+        target,
+        status,
+        message,
+        duration_ms: started.elapsed().as_millis() as u64,
+        details,
+    }
+}
+
+/// 在阻塞线程中运行检查
+async fn blocking(check: impl FnOnce() -> Outcome + Send + 'static) -> Outcome {
+    tokio::task::spawn_blocking(check)
+        .await
+        .unwrap_or_else(|e| (CheckStatus::Fail, format!("检查异常终止: {}", e), serde_json::Value::Null))
+}
+
+/// 打开录音设备的输入流 (不开始录音)
+fn check_microphone(device_name: Option<&str>) -> Outcome {
+    use cpal::traits::DeviceTrait;
+
+    let device = match crate::voice::audio::select_input_device(device_name) {
+        Ok(device) => device,
+        Err(e) => return (CheckStatus::Fail, e.to_string(), serde_json::Value::Null),
+    };
+    let name = device.name().unwrap_or_default();
+    let config = match device.default_input_config() {
+        Ok(config) => config,
+        Err(e) => return (CheckStatus::Fail, format!("无法获取输入格式: {}", e), serde_json::json!({ "device": name })),
+    };
+    let details = serde_json::json!({
+        "device": name,
+        "sample_rate": config.sample_rate().0,
+        "channels": config.channels(),
+    });
+    let stream = device.build_input_stream_raw(
+        &config.config(),
+        config.sample_format(),
+        |_: &cpal::Data, _: &cpal::InputCallbackInfo| {},
+        |_| {},
+        Some(CHECK_TIMEOUT),
+    );
+    match stream {
+        Ok(_) => (CheckStatus::Ok, format!("可以打开录音设备: {}", name), details),
+        Err(e) => (CheckStatus::Fail, format!("无法打开录音设备: {}", e), details),
+    }
+}
+
+/// 在 PTY 中启动默认 shell 后立即终止
+fn check_shell(request_id: Option<String>) -> Outcome {
+    let shell = crate::pty::get_default_shell()
+        .get_argv()
+        .first()
+        .map(|program| program.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let details = serde_json::json!({ "shell": shell });
+    match crate::pty::PtySession::new(80, 24, None, None, None, None) {
+        Ok((mut session, _, _)) => {
+            audit::record(AuditAction::PtyExec, ModuleType::System, shell.clone(), request_id, serde_json::json!({ "purpose": "diagnose" }));
+            let running = session.running_probe();
+            let _ = session.kill();
+            // 回收子进程
+            for _ in 0..10 {
+                if !running() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            (CheckStatus::Ok, format!("可以启动默认 shell: {}", shell), details)
+        }
+        Err(e) => (CheckStatus::Fail, format!("无法启动默认 shell: {}", e), details),
+    }
+}
+
+/// 探测端点时使用的 HTTP 地址 (WebSocket 端点改用对应的 HTTP 地址)
+pub fn probe_url(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("wss://") {
+        format!("https://{}", rest)
+    } else if let Some(rest) = url.strip_prefix("ws://") {
+        format!("http://{}", rest)
+    } else {
+        url.to_string()
+    }
+}
+
+/// 检查端点能否连通 (收到任何 HTTP 响应即视为可达，不校验认证)
+async fn check_endpoint(url: String) -> Outcome {
+    let request = crate::network::client().head(probe_url(&url)).timeout(CHECK_TIMEOUT);
+    match crate::network::send(ModuleType::System, request).await {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            (CheckStatus::Ok, format!("端点可达 (HTTP {})", status), serde_json::json!({ "url": url, "http_status": status }))
+        }
+        Err(e) => {
+            let reason = match &e {
+                NetworkError::Http(e) if e.is_builder() => "URL 无效",
+                e if e.is_timeout() => "连接超时",
+                _ => "无法连接",
+            };
+            (CheckStatus::Fail, format!("{}: {}", reason, e), serde_json::json!({ "url": url }))
+        }
+    }
+}
+
+/// 检查数据目录所在磁盘的可用空间
+fn check_disk() -> Outcome {
+    let Some(dirs) = storage::data_dirs() else {
+        return (CheckStatus::Fail, "未配置数据目录".to_string(), serde_json::Value::Null);
+    };
+    let path = dirs.root().display().to_string();
+    match dirs.available_space() {
+        Ok(available) => {
+            let details = serde_json::json!({ "path": path, "available_bytes": available });
+            let available_mib = available / (1024 * 1024);
+            if available < LOW_DISK_SPACE {
+                (CheckStatus::Warn, format!("数据目录所在磁盘空间不足: 剩余 {} MiB", available_mib), details)
+            } else {
+                (CheckStatus::Ok, format!("剩余 {} MiB", available_mib), details)
+            }
+        }
+        Err(e) => (CheckStatus::Fail, format!("无法获取磁盘空间: {}", e), serde_json::json!({ "path": path })),
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_selected_checks() {
+        let dir = std::env::temp_dir().join(format!("diagnose-test-{}", std::process::id()));
+        storage::init(storage::DataDirs::at(&dir));
+
+        // 监听后立即关闭的端口不可达
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let upstream = crate::testing::MockLlm::streaming(&["ok"]).await;
+        let request = DiagnoseRequest {
+            checks: Some(vec!["shell".to_string(), "network".to_string(), "disk".to_string()]),
+            asr_config: None,
+            endpoints: BTreeMap::from([
+                ("llm".to_string(), upstream.endpoint().to_string()),
+                ("offline".to_string(), format!("ws://{}/", closed)),
+            ]),
+        };
+        let report = run(request).await;
+
+        let checks: Vec<(&str, Option<&str>)> = report.checks.iter()
+            .map(|check| (check.check, check.target.as_deref()))
+            .collect();
+        assert_eq!(checks, vec![("shell", None), ("network", Some("llm")), ("network", Some("offline")), ("disk", None)]);
+        assert!(!report.checks[0].details["shell"].as_str().unwrap().is_empty());
+        #[cfg(unix)]
+        assert_eq!(report.checks[0].status, CheckStatus::Ok, "{}", report.checks[0].message);
+        assert_eq!(report.checks[1].status, CheckStatus::Ok);
+        assert!(report.checks[1].details["http_status"].is_u64());
+        assert_eq!(report.checks[2].status, CheckStatus::Fail);
+        assert_eq!(report.checks[2].details["url"], format!("ws://{}/", closed));
+        assert_ne!(report.checks[3].status, CheckStatus::Fail);
+        assert!(report.checks[3].details["available_bytes"].is_u64());
+        assert!(!report.ok);
+    }
+
+    #[test]
+    fn test_request_filter() {
+        let request: DiagnoseRequest = serde_json::from_value(serde_json::json!({ "checks": ["shell"] })).unwrap();
+        assert!(request.wants("shell"));
+        assert!(!request.wants("microphone"));
+        assert!(DiagnoseRequest::default().wants("microphone"));
+    }
+}
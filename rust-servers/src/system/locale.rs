@@ -0,0 +1,762 @@
+// 错误消息本地化
+// 客户端在 hello 中声明语言后，发往该连接的错误 (带有 code 和 message 的消息) 按语言翻译 message，错误码不变；
+// 诊断结果、系统信息提示的 message 以及各处的 error 字段 (如端点探测失败的原因) 同样翻译。
+// 翻译以消息模板为单位：模板与代码中的格式字符串一致，`{}` 处的参数原样保留 (参数本身也是已知消息时一并翻译)，
+// 未收录的消息 (如上游服务返回的原文) 保持不变
+
+use serde::{Deserialize, Serialize};
+
+/// 参数中嵌套消息的最大翻译深度
+const MAX_DEPTH: usize = 4;
+
+/// 错误消息语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    En,
+    Zh,
+}
+
+impl Locale {
+    /// 从语言标签解析 (如 `zh-CN`、`en-US`，或 HTTP Accept-Language 的首选项)，非中文一律使用英文
+    pub fn from_tag(tag: &str) -> Self {
+        let primary = tag.split([',', ';']).next().unwrap_or_default().trim().to_ascii_lowercase();
+        if primary.starts_with("zh") {
+            Locale::Zh
+        } else {
+            Locale::En
+        }
+    }
+
+    /// 翻译消息文本
+    pub fn translate(&self, message: &str) -> String {
+        translate(message, *self, 0)
+    }
+
+    /// 翻译 JSON 消息中的错误描述 (见 `is_description`)，任意层级
+    pub fn localize_value(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(object) => {
+                let described = is_described(object);
+                for (key, field) in object.iter_mut() {
+                    match field {
+                        serde_json::Value::String(text) if is_description(key, described) => *text = self.translate(text),
+                        _ => self.localize_value(field),
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|item| self.localize_value(item)),
+            _ => {}
+        }
+    }
+
+    /// JSON 对象中是否有会被翻译的错误描述 (没有时不必复制消息)
+    pub fn affects(&self, object: &serde_json::Map<String, serde_json::Value>) -> bool {
+        let described = is_described(object);
+        object.iter().any(|(key, field)| match field {
+            serde_json::Value::String(text) if is_description(key, described) => self.translate(text) != *text,
+            serde_json::Value::Object(object) => self.affects(object),
+            serde_json::Value::Array(items) => items.iter().any(|item| item.as_object().is_some_and(|object| self.affects(object))),
+            _ => false,
+        })
+    }
+}
+
+/// 对象是否带有字符串的 `code` (错误)、`check` (诊断结果) 或 `kind` (系统信息提示)，其 `message` 是错误描述
+fn is_described(object: &serde_json::Map<String, serde_json::Value>) -> bool {
+    ["code", "check", "kind"].iter().any(|key| object.get(*key).is_some_and(|value| value.is_string()))
+}
+
+/// 需要翻译的字段：上述对象的 `message`，以及任意对象中的 `error` 和 `xxx_error` (如端点探测、备用引擎的失败原因)
+fn is_description(key: &str, described: bool) -> bool {
+    (described && key == "message") || key == "error" || key.ends_with("_error")
+}
+
+fn translate(message: &str, locale: Locale, depth: usize) -> String {
+    if depth >= MAX_DEPTH {
+        return message.to_string();
+    }
+    for (zh, en) in CATALOG {
+        let (from, to) = match locale {
+            Locale::En => (zh, en),
+            Locale::Zh => (en, zh),
+        };
+        if let Some(args) = match_template(from, message) {
+            let mut pieces = to.split("{}");
+            let mut result = pieces.next().unwrap_or_default().to_string();
+            for (arg, piece) in args.iter().zip(pieces) {
+                result.push_str(&translate(arg, locale, depth + 1));
+                result.push_str(piece);
+            }
+            return result;
+        }
+    }
+    message.to_string()
+}
+
+/// 按模板匹配消息，返回 `{}` 处的参数
+fn match_template<'a>(template: &str, message: &'a str) -> Option<Vec<&'a str>> {
+    let mut pieces = template.split("{}");
+    let mut rest = message.strip_prefix(pieces.next()?)?;
+    let pieces: Vec<&str> = pieces.collect();
+    let mut args = Vec::with_capacity(pieces.len());
+    for (i, piece) in pieces.iter().enumerate() {
+        if i + 1 == pieces.len() {
+            args.push(rest.strip_suffix(piece)?);
+            rest = "";
+        } else {
+            let end = rest.find(piece)?;
+            args.push(&rest[..end]);
+            rest = &rest[end + piece.len()..];
+        }
+    }
+    rest.is_empty().then_some(args)
+}
+
+/// 消息模板 (中文, 英文)，两种语言中参数的顺序相同
+///
+/// 新增发给客户端的错误消息时在此补充对应的模板
+const CATALOG: &[(&str, &str)] = &[
+    // 协议与路由
+    ("未知模块: {}", "Unknown module: {}"),
+    ("无效消息: {}", "Invalid message: {}"),
+    ("模块已禁用: {}", "Module disabled: {}"),
+    ("协议版本不兼容: {}", "Incompatible protocol: {}"),
+    ("客户端支持 {}..={}，服务器支持 {}..={}", "client supports {}..={}, server supports {}..={}"),
+    ("JSON 错误: {}", "JSON error: {}"),
+    ("消息解析失败: {}", "Failed to parse message: {}"),
+    ("连接未认证: 请在第一条消息中提供 token", "Connection not authenticated: send the token in the first message"),
+    ("没有进行中的请求: {}", "No request in progress: {}"),
+    ("没有进行中的任务: {}", "No job in progress: {}"),
+    ("任务 {} 不支持终止，请使用 {} 模块自身的取消消息", "Job {} cannot be killed; use the {} module's own cancel message"),
+    ("模块崩溃，已重新初始化 (日志引用: {})", "Module crashed and was reinitialized (log reference: {})"),
+    ("模块崩溃 ({})", "Module crashed ({})"),
+    ("{} 消息过于频繁，请 {} ms 后重试", "Too many {} messages, retry in {} ms"),
+    (
+        "消息大小 {} 字节超过 {} 字节的上限，请使用分块上传 (upload_begin / upload_append / upload_commit)",
+        "Message of {} bytes exceeds the {}-byte limit; use a chunked upload (upload_begin / upload_append / upload_commit)",
+    ),
+    // System
+    ("未知的 System 消息类型: {}", "Unknown System message type: {}"),
+    ("无效的 hello: {}", "Invalid hello: {}"),
+    ("无效的 MessagePack 消息: {}", "Invalid MessagePack message: {}"),
+    ("订阅需要客户端连接", "Subscriptions require a client connection"),
+    ("未知的订阅模块: {}", "Unknown topic module: {}"),
+    ("订阅主题的会话 ID 为空: {}", "Empty session id in topic: {}"),
+    ("分块上传仅支持 WebSocket 连接", "Chunked uploads require a WebSocket connection"),
+    ("上传的内容不能是分块上传消息", "An uploaded message cannot be a chunked upload message"),
+    ("上传不存在: {}", "Upload not found: {}"),
+    ("上传内容超过 {} 字节的限制", "Upload exceeds the {}-byte limit"),
+    ("进行中的上传过多 (每个连接最多 {} 个)", "Too many uploads in progress (at most {} per connection)"),
+    ("分块偏移不连续: 期望 {}，实际 {}", "Chunk offset mismatch: expected {}, got {}"),
+    ("无效的上传消息: {}", "Invalid upload message: {}"),
+    ("未配置数据目录", "No data directory configured"),
+    ("统计数据目录失败: {}", "Failed to measure the data directory: {}"),
+    ("清理缓存失败: {}", "Failed to clear the cache: {}"),
+    ("vault 不能为空", "'vault' must not be empty"),
+    ("读取审计日志失败: {}", "Failed to read the audit log: {}"),
+    ("并发上限必须大于 0", "The concurrency limit must be greater than 0"),
+    ("检查超时 ({} 秒)", "Check timed out ({} seconds)"),
+    ("检查异常终止: {}", "Check aborted: {}"),
+    ("无法获取输入格式: {}", "Cannot get the input format: {}"),
+    ("可以打开录音设备: {}", "Recording device can be opened: {}"),
+    ("无法打开录音设备: {}", "Cannot open the recording device: {}"),
+    ("可以启动默认 shell: {}", "Default shell can be started: {}"),
+    ("无法启动默认 shell: {}", "Cannot start the default shell: {}"),
+    ("端点可达 (HTTP {})", "Endpoint reachable (HTTP {})"),
+    ("数据目录所在磁盘空间不足: 剩余 {} MiB", "Low disk space for the data directory: {} MiB left"),
+    ("剩余 {} MiB", "{} MiB left"),
+    ("无法获取磁盘空间: {}", "Cannot get disk space: {}"),
+    ("URL 无效: {}", "Invalid URL: {}"),
+    // 配置与启动
+    ("无法读取配置文件 {}: {}", "Cannot read config file {}: {}"),
+    ("配置文件格式错误 {}: {}", "Malformed config file {}: {}"),
+    ("配置无效: {}", "Invalid configuration: {}"),
+    ("batching 的键应为 module.type: {}", "'batching' keys must be module.type: {}"),
+    ("rate_limits 的键应为 module 或 module.type: {}", "'rate_limits' keys must be module or module.type: {}"),
+    ("rate_limits.{} 的 per_sec 不能为负数", "'per_sec' of rate_limits.{} must not be negative"),
+    ("system 模块不能被禁用", "The system module cannot be disabled"),
+    ("limits.heartbeat_interval_secs 必须大于 0", "'limits.heartbeat_interval_secs' must be greater than 0"),
+    ("limits.heartbeat_timeout_secs 必须大于 heartbeat_interval_secs", "'limits.heartbeat_timeout_secs' must be greater than 'heartbeat_interval_secs'"),
+    ("limits.max_connections 必须大于 0", "'limits.max_connections' must be greater than 0"),
+    ("limits.outbound_queue_size 必须大于 0", "'limits.outbound_queue_size' must be greater than 0"),
+    ("limits.max_message_size 必须大于 0", "'limits.max_message_size' must be greater than 0"),
+    ("limits.max_llm_requests 必须大于 0", "'limits.max_llm_requests' must be greater than 0"),
+    ("workers.{} 必须大于 0", "'workers.{}' must be greater than 0"),
+    ("limits.max_upload_size 不能小于 max_message_size", "'limits.max_upload_size' must not be less than 'max_message_size'"),
+    ("tls 仅用于 TCP 监听，不能与 socket 同时使用", "'tls' only applies to TCP listening and cannot be used with 'socket'"),
+    ("host 为非本机地址 (远程访问模式) 时必须启用 tls", "'tls' must be enabled when 'host' is not a local address (remote access mode)"),
+    ("http.files 需要启用 HTTP 接口 (http.enabled 或 --http-api)", "'http.files' needs the HTTP API ('http.enabled' or --http-api)"),
+    ("http.files 仅用于本机监听，不能在远程访问模式下启用", "'http.files' is only for local listening and cannot be enabled in remote access mode"),
+    ("token 至少需要 {} 个字符，且只能包含字母、数字、- 和 _", "'token' needs at least {} characters and may only contain letters, digits, - and _"),
+    ("proxy 地址无效 ({}): {}", "Invalid proxy address ({}): {}"),
+    ("network.breaker_cooldown_secs 必须大于 0", "'network.breaker_cooldown_secs' must be greater than 0"),
+    ("未知的模块: {}", "Unknown module: {}"),
+    ("远程访问模式需要 TLS 证书", "Remote access mode needs a TLS certificate"),
+    ("路径已存在且不是套接字: {}", "Path exists and is not a socket: {}"),
+    ("已有服务器实例在运行 (pid {})，锁文件: {}", "A server instance is already running (pid {}), lock file: {}"),
+    ("无法创建锁文件 {}: {}", "Cannot create lock file {}: {}"),
+    ("无法读写证书文件 {}: {}", "Cannot read or write certificate file {}: {}"),
+    ("生成自签名证书失败: {}", "Failed to generate a self-signed certificate: {}"),
+    ("证书文件无效 {}: {}", "Invalid certificate file {}: {}"),
+    // 网络
+    ("{} 连续请求失败，已暂停请求 ({} 秒后重试)", "Repeated requests to {} failed, requests paused (retry in {} seconds)"),
+    ("连接失败 {}: {}", "Connection to {} failed: {}"),
+    ("代理连接失败: {}", "Proxy connection failed: {}"),
+    ("响应头过长", "Response headers too long"),
+    ("代理拒绝连接 {}: {}", "Proxy refused the connection to {}: {}"),
+    // HTTP 接口
+    ("未知的路径: {}", "Unknown path: {}"),
+    ("仅支持 POST 请求", "Only POST requests are supported"),
+    ("缺少或错误的令牌", "Missing or invalid token"),
+    ("请求体超过 {} 字节", "Request body exceeds {} bytes"),
+    ("仅支持 GET 请求", "Only GET requests are supported"),
+    ("文件不存在: {}", "File not found: {}"),
+    ("无法满足的范围 (文件大小 {} 字节)", "Range not satisfiable (file is {} bytes)"),
+    ("请求体必须是 JSON 对象", "The request body must be a JSON object"),
+    // PTY
+    ("未知的 PTY 消息类型: {}", "Unknown PTY message type: {}"),
+    ("PTY 会话不存在: {}", "PTY session not found: {}"),
+    ("创建 PTY 会话失败: {}", "Failed to create PTY session: {}"),
+    ("写入 PTY 失败: {}", "Failed to write to PTY: {}"),
+    ("调整终端尺寸失败: {}", "Failed to resize terminal: {}"),
+    ("缺少 session_id 字段", "Missing session_id field"),
+    ("get_env 需要 Shell Integration (shell_type 为 bash、zsh 或 fish)", "get_env requires shell integration (shell_type bash, zsh or fish)"),
+    ("shell 不在空的提示符 (正在运行命令或已键入内容)", "The shell is not at an empty prompt (a command is running or text has been typed)"),
+    ("shell 未在 {} 秒内返回环境变量", "The shell did not report its environment within {} seconds"),
+    ("PTY 会话已结束: {}", "PTY session has ended: {}"),
+    // Voice
+    ("未知的 Voice 消息类型: {}", "Unknown Voice message type: {}"),
+    ("ASR 配置未设置", "ASR configuration not set"),
+    ("缺少 asr_config 字段", "Missing asr_config field"),
+    ("缺少 audio 字段", "Missing audio field"),
+    ("缺少 mode 字段", "Missing mode field"),
+    ("audio 不是有效的 base64: {}", "audio is not valid base64: {}"),
+    ("无法解码 WAV 音频: {}", "Failed to decode WAV audio: {}"),
+    ("已在录音中", "Already recording"),
+    ("未在录音中", "Not recording"),
+    ("录音器未初始化", "Recorder not initialized"),
+    ("流式录音器未初始化", "Streaming recorder not initialized"),
+    ("创建录音器失败: {}", "Failed to create recorder: {}"),
+    ("创建流式录音器失败: {}", "Failed to create streaming recorder: {}"),
+    ("启动录音失败: {}", "Failed to start recording: {}"),
+    ("启动流式录音失败: {}", "Failed to start streaming recording: {}"),
+    ("停止录音失败: {}", "Failed to stop recording: {}"),
+    ("停止流式录音失败: {}", "Failed to stop streaming recording: {}"),
+    ("获取录音设备失败: {}", "Failed to list recording devices: {}"),
+    ("发送消息失败: {}", "Failed to send message: {}"),
+    ("JSON 序列化失败: {}", "JSON serialization failed: {}"),
+    ("实时转录失败: {}; HTTP 回退也失败: {}", "Realtime transcription failed: {}; HTTP fallback also failed: {}"),
+    ("实时转录任务异常; HTTP 回退也失败: {}", "Realtime transcription task failed; HTTP fallback also failed: {}"),
+    ("实时转录长时间无进度，录音已取消", "Realtime transcription stopped making progress; recording cancelled"),
+    ("所有 ASR 引擎失败: 主引擎={}, 备用引擎={}", "All ASR engines failed: primary={}, fallback={}"),
+    ("WebSocket 错误: {}", "WebSocket error: {}"),
+    ("不支持的操作: {}", "Unsupported operation: {}"),
+    ("内部错误: {}", "Internal error: {}"),
+    ("引擎未初始化", "Engine not initialized"),
+    ("无效的音频格式: {}", "Invalid audio format: {}"),
+    ("认证失败 ({}): {}", "Authentication failed ({}): {}"),
+    ("请求超时 ({}ms)", "Request timed out ({}ms)"),
+    ("配置错误: {}", "Configuration error: {}"),
+    ("配额超限 ({})", "Quota exceeded ({})"),
+    ("IO 错误: {}", "I/O error: {}"),
+    ("WAV 编码错误: {}", "WAV encoding error: {}"),
+    ("无效的音频数据", "Invalid audio data"),
+    ("不支持的采样格式: {}", "Unsupported sample format: {}"),
+    ("音频录制权限被拒绝", "Audio recording permission denied"),
+    ("音频编码错误: {}", "Audio encoding error: {}"),
+    ("音频设备错误: {}", "Audio device error: {}"),
+    ("麦克风不可用: {}", "Microphone unavailable: {}"),
+    ("供应商 {} 不支持 {} 模式", "Provider {} does not support {} mode"),
+    ("缺少必需的 API Key: {}", "Missing required API key: {}"),
+    ("无效的时间格式: {}", "Invalid time format: {}"),
+    ("keywords 超出范围: {} (最多 {})", "'keywords' out of range: {} (max {})"),
+    ("mood.endpoint 不能为空", "'mood.endpoint' must not be empty"),
+    ("编码任务失败: {}", "Encoding task failed: {}"),
+    ("编码录音失败: {}", "Failed to encode recording: {}"),
+    ("无法创建目录 {}: {}", "Cannot create directory {}: {}"),
+    ("无法写入 {}: {}", "Cannot write {}: {}"),
+    ("{} 中同名录音过多", "Too many recordings with the same name in {}"),
+    ("实时转录长时间无进度，已中止", "Realtime transcription stopped making progress and was aborted"),
+    ("转录已终止", "Transcription cancelled"),
+    ("连续 {} 次发送失败: {}", "Sending failed {} times in a row: {}"),
+    ("连续 {} 次发送失败，备用引擎也不可用: {}", "Sending failed {} times in a row and the fallback engine is unavailable: {}"),
+    ("缺少 dashscope_api_key", "Missing 'dashscope_api_key'"),
+    ("缺少 app_id", "Missing 'app_id'"),
+    ("缺少 access_token", "Missing 'access_token'"),
+    ("缺少 siliconflow_api_key", "Missing 'siliconflow_api_key'"),
+    ("缺少 API Key", "Missing API key"),
+    ("{} 需要模型配置，请使用 create_engine", "{} needs a model config, use create_engine"),
+    ("音频数据为空", "Audio data is empty"),
+    ("转录失败，未知错误", "Transcription failed with an unknown error"),
+    ("解析响应失败: {}", "Failed to parse the response: {}"),
+    ("API 请求失败 ({}): {}", "API request failed ({}): {}"),
+    ("API 错误: {}", "API error: {}"),
+    ("创建文件部分失败: {}", "Failed to create the file part: {}"),
+    ("接口或模型不存在 ({}): {}", "Endpoint or model not found ({}): {}"),
+    ("模型不存在或服务不可用: {}", "Model not found or service unavailable: {}"),
+    ("服务暂时不可用 ({}): {}", "Service temporarily unavailable ({}): {}"),
+    ("豆包 ASR 失败 ({}): {}", "Doubao ASR failed ({}): {}"),
+    ("无法解析豆包转录结果，响应格式: {}", "Cannot parse the Doubao transcription, response: {}"),
+    ("无法解析转录结果，响应格式: {}", "Cannot parse the transcription, response: {}"),
+    ("DoubaoHttpEngine 不支持 Realtime 模式，请使用 DoubaoRealtimeEngine", "DoubaoHttpEngine does not support realtime mode, use DoubaoRealtimeEngine"),
+    ("QwenHttpEngine 不支持 Realtime 模式，请使用 QwenRealtimeEngine", "QwenHttpEngine does not support realtime mode, use QwenRealtimeEngine"),
+    ("OpenAI 兼容接口不支持 Realtime 模式，仅支持 HTTP 模式", "OpenAI-compatible APIs do not support realtime mode, only HTTP mode"),
+    ("SenseVoice 不支持 Realtime 模式，仅支持 HTTP 模式", "SenseVoice does not support realtime mode, only HTTP mode"),
+    ("Whisper 不支持 Realtime 模式，仅支持 HTTP 模式", "Whisper does not support realtime mode, only HTTP mode"),
+    ("DoubaoRealtimeEngine 不支持 HTTP 模式，请使用 DoubaoHttpEngine 或创建 Realtime 会话", "DoubaoRealtimeEngine does not support HTTP mode, use DoubaoHttpEngine or create a realtime session"),
+    ("QwenRealtimeEngine 不支持 HTTP 模式，请使用 QwenHttpEngine 或创建 Realtime 会话", "QwenRealtimeEngine does not support HTTP mode, use QwenHttpEngine or create a realtime session"),
+    ("构建请求失败: {}", "Failed to build the request: {}"),
+    ("WebSocket 连接失败: {}", "WebSocket connection failed: {}"),
+    ("WebSocket 连接被关闭", "WebSocket connection closed"),
+    ("WebSocket 连接结束，无转录结果", "WebSocket connection ended without a transcription"),
+    ("序列化配置失败: {}", "Failed to serialize the config: {}"),
+    ("发送 Full Client Request 失败: {}", "Failed to send the Full Client Request: {}"),
+    ("豆包 Full Client Request 响应错误: {}", "Doubao Full Client Request error: {}"),
+    ("发送 session.update 失败: {}", "Failed to send session.update: {}"),
+    ("未收到转录结果", "No transcription received"),
+    ("发送音频块失败：通道已关闭", "Failed to send audio chunk: channel closed"),
+    ("提交音频失败：通道已关闭", "Failed to commit audio: channel closed"),
+    ("会话已关闭", "Session closed"),
+    ("结果通道已关闭", "Result channel closed"),
+    ("Gzip 压缩失败: {}", "Gzip compression failed: {}"),
+    ("Gzip 完成失败: {}", "Failed to finish gzip: {}"),
+    ("Gzip 解压失败: {}", "Gzip decompression failed: {}"),
+    ("响应太短: {} bytes", "Response too short: {} bytes"),
+    ("响应不完整: {} bytes", "Incomplete response: {} bytes"),
+    ("服务器返回错误: code={}", "Server returned an error: code={}"),
+    ("数据不足以包含 sequence", "Data too short to contain the sequence"),
+    ("数据不足以包含 payload size", "Data too short to contain the payload size"),
+    ("数据不完整: 需要 {} bytes，实际 {} bytes", "Incomplete data: need {} bytes, got {} bytes"),
+    ("UTF-8 解码失败: {}", "UTF-8 decoding failed: {}"),
+    ("JSON 解析失败: {}", "JSON parsing failed: {}"),
+    ("中间响应，等待更多数据", "Intermediate response, waiting for more data"),
+    ("加载 Vosk 模型任务失败: {}", "Vosk model loading task failed: {}"),
+    ("vosk 需要 model_path", "vosk needs 'model_path'"),
+    ("Vosk 模型目录不存在: {}", "Vosk model directory not found: {}"),
+    ("Vosk 识别任务失败: {}", "Vosk recognition task failed: {}"),
+    ("未找到 Vosk 库 {}，请放入数据目录的 models 区域或安装到系统库路径 ({})", "Vosk library {} not found, put it in the models area of the data directory or install it on the system library path ({})"),
+    ("未找到 {} 库 {}，请放入数据目录的 models 区域或安装到系统库路径 ({})", "{} library {} not found, put it in the models area of the data directory or install it on the system library path ({})"),
+    ("无效的模型路径: {}", "Invalid model path: {}"),
+    ("加载 Vosk 模型失败: {}", "Failed to load the Vosk model: {}"),
+    ("创建 Vosk 识别器失败", "Failed to create the Vosk recognizer"),
+    ("Vosk 识别失败", "Vosk recognition failed"),
+    ("此版本未包含本地 Whisper，需要启用 local-whisper 特性重新编译", "This build does not include local Whisper, rebuild with the local-whisper feature"),
+    ("此版本未包含本地 Whisper", "This build does not include local Whisper"),
+    ("未配置数据目录，无法按 model_size 查找模型", "No data directory configured, cannot find the model by 'model_size'"),
+    ("whisper 需要 model_path 或 model_size", "whisper needs 'model_path' or 'model_size'"),
+    ("Whisper 模型文件不存在: {}", "Whisper model file not found: {}"),
+    ("Whisper 推理任务失败: {}", "Whisper inference task failed: {}"),
+    ("模型路径不是有效的 UTF-8: {}", "Model path is not valid UTF-8: {}"),
+    ("加载 Whisper 模型失败 ({}): {}", "Failed to load the Whisper model ({}): {}"),
+    ("创建 Whisper 状态失败: {}", "Failed to create the Whisper state: {}"),
+    ("Whisper 推理失败: {}", "Whisper inference failed: {}"),
+    ("读取 Whisper 结果失败: {}", "Failed to read the Whisper result: {}"),
+    ("FLAC 不支持 {} 个声道", "FLAC does not support {} channels"),
+    ("FLAC 不支持采样率 {} Hz", "FLAC does not support a sample rate of {} Hz"),
+    ("创建 Opus 编码器失败 (错误码 {})", "Failed to create the Opus encoder (error code {})"),
+    ("Opus 编码失败 (错误码 {})", "Opus encoding failed (error code {})"),
+    ("无法启动录音线程: {}", "Cannot start the recording thread: {}"),
+    ("录音线程意外退出", "The recording thread exited unexpectedly"),
+    ("未找到指定录音设备: {}", "Recording device not found: {}"),
+    ("没有找到默认音频输入设备", "No default audio input device found"),
+    ("无法获取音频输出流: {}", "Cannot get the audio output stream: {}"),
+    ("无法创建音频 Sink: {}", "Cannot create the audio sink: {}"),
+    ("base_url 必须以 http:// 或 https:// 开头: {}", "'base_url' must start with http:// or https://: {}"),
+    ("model 不能为空", "'model' must not be empty"),
+    ("{} 不支持上传格式 {}", "{} does not support upload format {}"),
+    ("chunk_ms 超出范围: {} ({}-{})", "'chunk_ms' out of range: {} ({}-{})"),
+    ("chunk_overlap_ms 不能超过块时长的一半: {} (块时长 {})", "'chunk_overlap_ms' must not exceed half the chunk length: {} (chunk length {})"),
+    ("无效的 expected_language: {}", "Invalid 'expected_language': {}"),
+    ("vad_aggressiveness 超出范围: {} (应为 0-{})", "'vad_aggressiveness' out of range: {} (expected 0-{})"),
+    ("auto_stop_silence_ms 过短: {} (至少 {}，0 为不自动停止)", "'auto_stop_silence_ms' too short: {} (at least {}, 0 disables auto stop)"),
+    ("后台任务失败: {}", "Background task failed: {}"),
+    // LLM
+    ("未知的 LLM 消息类型: {}", "Unknown LLM message type: {}"),
+    ("无效的流配置: {}", "Invalid stream config: {}"),
+    ("无效的配置: {}", "Invalid configuration: {}"),
+    ("网络错误: {}", "Network error: {}"),
+    ("HTTP 错误: {} - {}", "HTTP error: {} - {}"),
+    ("JSON 解析错误: {}", "JSON parse error: {}"),
+    ("解析错误: {}", "Parse error: {}"),
+    ("请求已取消", "Request cancelled"),
+    ("流已停滞: {}", "Stream stalled: {}"),
+    ("未知格式", "Unknown format"),
+    ("未设置 WebSocket 发送器", "WebSocket sender not set"),
+    ("无效的输出文件路径: {}", "Invalid output file path: {}"),
+    ("无法打开输出文件 {}: {}", "Cannot open output file {}: {}"),
+    ("无法确定数据目录", "Cannot determine the data directory"),
+    ("无法创建文件目录: {}", "Cannot create the files directory: {}"),
+    ("写入 PTY 会话 {} 失败: {}", "Failed to write to PTY session {}: {}"),
+    ("写入 {} 失败: {}", "Failed to write {}: {}"),
+    ("客户端已断开且没有可用的输出目标", "Client disconnected and no output sink is available"),
+    // Utils
+    ("未知的 Utils 消息类型: {}", "Unknown Utils message type: {}"),
+    ("无效的 {} 请求: {}", "Invalid {} request: {}"),
+    ("{} 任务失败: {}", "{} task failed: {}"),
+    ("日历任务失败: {}", "Calendar task failed: {}"),
+    ("相似度计算任务失败: {}", "Similarity task failed: {}"),
+    ("转码任务失败: {}", "Transcode task failed: {}"),
+    ("哈希任务失败: {}", "Hash task failed: {}"),
+    ("重复检测任务失败: {}", "Duplicate detection task failed: {}"),
+    ("拼写检查任务失败: {}", "Spellcheck task failed: {}"),
+    ("词典加载任务失败: {}", "Dictionary load task failed: {}"),
+    ("网页剪藏已终止", "Web clip cancelled"),
+    ("响应序列化失败: {}", "Failed to serialize response: {}"),
+    ("必须提供 'texts' 或 'paths'", "Either 'texts' or 'paths' is required"),
+    ("语言检测流不存在: {}", "Language stream not found: {}"),
+    ("语言检测流过多 (最多 {} 个)", "Too many language detection streams (max {})"),
+    ("无法提取正文: {}", "Failed to extract article: {}"),
+    ("无效的 URL: {}", "Invalid URL: {}"),
+    ("页面超过 {} 字节", "Page exceeds {} bytes"),
+    ("请求失败: {}", "Request failed: {}"),
+    ("服务器返回 HTTP {}", "Server returned HTTP {}"),
+    ("不支持的内容类型: {}", "Unsupported content type: {}"),
+    ("无效的参考时间: {}", "Invalid reference time: {}"),
+    ("无法序列化键 '{}' 的值: {}", "Failed to serialize value for key '{}': {}"),
+    ("Frontmatter 必须是映射", "Frontmatter must be a mapping"),
+    ("无效的 YAML frontmatter: {}", "Invalid YAML frontmatter: {}"),
+    ("无效的正则表达式: {}", "Invalid regex pattern: {}"),
+    ("正则求值失败: {}", "Regex evaluation failed: {}"),
+    ("正则求值超时 ({} ms)", "Regex evaluation timed out after {} ms"),
+    ("替换操作需要 'replacement'", "Replace operation requires 'replacement'"),
+    ("不支持的正则标志: '{}'", "Unsupported regex flag: '{}'"),
+    ("向量维度不一致: {} 与 {}", "Embedding dimensions differ: {} vs {}"),
+    ("'cosine' 方法需要两个输入的向量", "Method 'cosine' requires embedding vectors for both inputs"),
+    ("'{}' 方法需要文本输入", "Method '{}' requires text inputs"),
+    ("文本过长，无法计算 levenshtein: {} 个字符 (最多 {})", "Text too long for levenshtein: {} characters (max {})"),
+    ("找不到 '{}' 的词典", "Dictionary not found for '{}'"),
+    ("无法解析词典 '{}': {}", "Failed to parse dictionary '{}': {}"),
+    ("无法读取词典: {}", "Failed to read dictionary: {}"),
+    ("无效的语言代码: '{}'", "Invalid language code: '{}'"),
+    ("必须提供 'bytes' 或 'path'", "Either 'bytes' or 'path' is required"),
+    ("文件错误: {}", "File error: {}"),
+    ("输入过大: {} 字节 (最多 {})", "Input too large: {} bytes (max {})"),
+    ("无效的 base64 数据: {}", "Invalid base64 data: {}"),
+    ("未知的编码标签: '{}'", "Unknown encoding label: '{}'"),
+    ("不支持的目标编码: '{}' (仅支持 utf8)", "Unsupported target encoding: '{}' (only utf8 is supported)"),
+    ("必须提供 'data' 或 'url'", "Either 'data' or 'url' is required"),
+    ("必须提供 'events' 或 'text'", "Either 'events' or 'text' is required"),
+    ("日历超过 {} 字节", "Calendar exceeds {} bytes"),
+    ("无效的 ICS 数据: {}", "Invalid ICS data: {}"),
+    ("缺少 BEGIN:VCALENDAR", "missing BEGIN:VCALENDAR"),
+    ("不支持的协议 '{}'", "unsupported scheme '{}'"),
+    ("无效的事件时间: '{}'", "Invalid event time: '{}'"),
+    ("文本中没有找到日期: {}", "No date found in text: {}"),
+    // Files
+    ("未知的文件监视消息类型: {}", "Unknown Files message type: {}"),
+    ("无效的 watch 请求: {}", "Invalid watch request: {}"),
+    ("paths 不能为空", "'paths' must not be empty"),
+    ("debounce_ms 超出范围: {} (应为 {}-{})", "debounce_ms out of range: {} (expected {}-{})"),
+    ("目录不存在: {}", "Directory not found: {}"),
+    ("监视过多 (最多 {} 个)", "Too many watches (max {})"),
+    ("无法监视目录: {}", "Failed to watch directory: {}"),
+    ("监视不存在: {}", "Watch not found: {}"),
+    // Clipboard
+    ("未知的剪贴板消息类型: {}", "Unknown Clipboard message type: {}"),
+    ("剪贴板不可用: {}", "Clipboard unavailable: {}"),
+    ("无效的 PNG 图片: {}", "Invalid PNG image: {}"),
+    ("像素数据与尺寸不符", "Pixel data does not match the image size"),
+    ("剪贴板任务失败: {}", "Clipboard task failed: {}"),
+    ("无效的 read 请求: {}", "Invalid read request: {}"),
+    ("无效的 write 请求: {}", "Invalid write request: {}"),
+    ("必须提供 'text' 或 'image' 之一", "Exactly one of 'text' or 'image' is required"),
+    ("interval_ms 超出范围: {} (应为 {}-{})", "interval_ms out of range: {} (expected {}-{})"),
+    // Hotkeys
+    ("未知的快捷键消息类型: {}", "Unknown Hotkeys message type: {}"),
+    ("全局快捷键不可用: {}", "Global hotkeys unavailable: {}"),
+    ("无法注册快捷键 {}: {}", "Failed to register hotkey {}: {}"),
+    ("无效的快捷键 '{}': {}", "Invalid hotkey '{}': {}"),
+    ("缺少 hotkey 字段", "Missing 'hotkey' field"),
+    ("快捷键已注册: {}", "Hotkey already registered: {}"),
+    ("快捷键过多 (最多 {} 个)", "Too many hotkeys (max {})"),
+    ("快捷键未注册: {}", "Hotkey not registered: {}"),
+    ("快捷键任务失败: {}", "Hotkey task failed: {}"),
+    ("快捷键线程已退出", "The hotkey thread has exited"),
+    ("未找到 X11 显示 (DISPLAY 未设置)", "No X11 display found (DISPLAY is not set)"),
+    ("macOS 上暂不支持", "Not supported on macOS yet"),
+    ("后端未打开", "Backend not opened"),
+    // Capture
+    ("未知的截图消息类型: {}", "Unknown Capture message type: {}"),
+    ("截图不可用: {}", "Screen capture unavailable: {}"),
+    ("显示器不存在: {}", "Monitor not found: {}"),
+    ("窗口不存在: {}", "Window not found: {}"),
+    ("截图失败: {}", "Screen capture failed: {}"),
+    ("截图任务失败: {}", "Screen capture task failed: {}"),
+    ("无效的 capture 请求: {}", "Invalid capture request: {}"),
+    ("region 需要 x、y 和正的 width、height", "region requires x, y and a positive width and height"),
+    ("window 需要 window_id", "window requires window_id"),
+    ("未知的截图目标: {}", "Unknown capture target: {}"),
+    ("区域不在任何显示器内", "The region is not on any monitor"),
+    ("没有可用的显示器", "No monitor available"),
+    ("窗口不可见或已最小化", "The window is hidden or minimized"),
+    ("PNG 编码失败: {}", "PNG encoding failed: {}"),
+    ("无法保存截图: {}", "Failed to save screenshot: {}"),
+    ("无法连接 X11 显示: {}", "Failed to connect to the X11 display: {}"),
+    ("不支持的像素格式 (色深 {})", "Unsupported pixel format (depth {})"),
+    ("像素数据不完整", "Incomplete pixel data"),
+    ("当前平台不支持", "Not supported on this platform"),
+    // Git
+    ("未知的 Git 消息类型: {}", "Unknown Git message type: {}"),
+    ("Git 任务失败: {}", "Git task failed: {}"),
+    ("message 不能为空", "'message' must not be empty"),
+    ("进行中的推送 / 拉取过多 (最多 {} 个)", "Too many pushes / pulls in progress (max {})"),
+    ("请求已在进行中: {}", "Request already in progress: {}"),
+    ("不是 Git 仓库: {}", "Not a Git repository: {}"),
+    ("没有要提交的更改", "Nothing to commit"),
+    ("未配置提交作者 (user.name / user.email)", "No commit author configured (user.name / user.email)"),
+    ("存在冲突的文件: {}", "Conflicting files: {}"),
+    ("推送被拒绝: {}", "Push rejected: {}"),
+    ("仓库处于未完成的 {} 状态", "The repository has an unfinished {}"),
+    ("操作已取消", "Operation cancelled"),
+    ("HEAD 不在分支上，请指定 branch", "HEAD is not on a branch; specify 'branch'"),
+    ("认证失败: {}", "Authentication failed: {}"),
+    ("没有可用的凭据: {}", "No usable credentials: {}"),
+    // 计划任务
+    ("未知的计划任务消息类型: {}", "Unknown scheduler message type: {}"),
+    ("无效的 add 请求: {}", "Invalid add request: {}"),
+    ("缺少 id 字段", "Missing 'id' field"),
+    ("cron 和 at 必须指定其中一个", "Exactly one of 'cron' and 'at' is required"),
+    ("id 不能为空", "'id' must not be empty"),
+    ("计划任务过多 (最多 {} 个)", "Too many scheduled jobs (max {})"),
+    ("计划任务不存在: {}", "Scheduled job not found: {}"),
+    ("无效的 cron 表达式 '{}': {}", "Invalid cron expression '{}': {}"),
+    ("cron 表达式没有下一次触发时间: {}", "Cron expression never fires again: {}"),
+    ("无效的时间: {}", "Invalid time: {}"),
+    ("时间已过去: {}", "Time is in the past: {}"),
+    // 系统通知
+    ("未知的通知消息类型: {}", "Unknown notify message type: {}"),
+    ("无效的 show 请求: {}", "Invalid show request: {}"),
+    ("标题不能为空", "Title must not be empty"),
+    ("标题或正文过长 (最多 {} 个字符)", "Title or body too long (max {} characters)"),
+    ("按钮过多 (最多 {} 个)", "Too many actions (max {})"),
+    ("无效的按钮 id: '{}'", "Invalid action id: '{}'"),
+    ("重复的按钮 id: '{}'", "Duplicate action id: '{}'"),
+    ("等待用户操作的通知过多 (最多 {} 条)", "Too many notifications awaiting user action (max {})"),
+    ("通知任务失败: {}", "Notification task failed: {}"),
+    ("系统通知不可用: {}", "System notifications unavailable: {}"),
+    // 音频播放
+    ("未知的播放消息类型: {}", "Unknown playback message type: {}"),
+    ("无效的 play 请求: {}", "Invalid play request: {}"),
+    ("必须提供 'path' 或 'data' 之一", "Exactly one of 'path' or 'data' is required"),
+    ("PCM 数据长度与声道数不符", "PCM data length does not match the channel count"),
+    ("PCM 转换失败: {}", "PCM conversion failed: {}"),
+    ("不支持的音频格式: {}", "Unsupported audio format: {}"),
+    ("volume 超出范围: {} (应为 0-1)", "volume out of range: {} (expected 0-1)"),
+    ("position_interval_ms 超出范围: {} (应为 0 或 {}-{})", "position_interval_ms out of range: {} (expected 0 or {}-{})"),
+    ("没有正在播放的音频: {}", "Nothing is playing: {}"),
+    ("缺少 position_ms 字段", "Missing 'position_ms' field"),
+    ("跳转位置超出音频时长: {}ms", "Seek position is beyond the end of the audio: {}ms"),
+    ("开始位置超出音频时长: {}ms", "Start position is beyond the end of the audio: {}ms"),
+    ("播放任务失败: {}", "Playback task failed: {}"),
+    ("无法打开音频文件: {}", "Cannot open audio file: {}"),
+    ("无法解码音频: {}", "Cannot decode audio: {}"),
+    ("未找到指定播放设备: {}", "Output device not found: {}"),
+    ("音频输出不可用: {}", "Audio output unavailable: {}"),
+    ("无法跳转: {}", "Cannot seek: {}"),
+    ("无法获取输出设备列表: {}", "Cannot list output devices: {}"),
+    ("播放线程意外退出", "Playback thread exited unexpectedly"),
+    // 备份
+    ("未知的备份消息类型: {}", "Unknown backup message type: {}"),
+    ("folders 不能为空", "'folders' must not be empty"),
+    ("dest 不能为空", "'dest' must not be empty"),
+    ("未配置数据目录，请指定 dest", "No data directory configured, specify 'dest'"),
+    ("进行中的备份过多 (最多 {} 个)", "Too many backups in progress (max {})"),
+    ("keep_last 和 keep_days 至少指定一个", "At least one of 'keep_last' or 'keep_days' is required"),
+    ("备份任务失败: {}", "Backup task failed: {}"),
+    ("文件读写失败: {}", "File read/write failed: {}"),
+    ("快照不存在: {}", "Snapshot not found: {}"),
+    ("无效的快照文件 {}: {}", "Invalid snapshot file {}: {}"),
+    ("无效的快照名称: '{}' (只能包含字母、数字、- 和 _，最多 64 个字符)", "Invalid snapshot name: '{}' (letters, digits, - and _ only, max 64 characters)"),
+    ("备份已取消", "Backup cancelled"),
+    ("未知的任务动作: {} (可用: backup)", "Unknown job action: {} (available: backup)"),
+    // 系统信息
+    ("未知的系统信息消息类型: {}", "Unknown system_info message type: {}"),
+    ("未知的信息分类: {} (可用: {})", "Unknown section: {} (available: {})"),
+    ("端点过多 (最多 {} 个)", "Too many endpoints (max {})"),
+    ("系统信息采集失败: {}", "Failed to collect system info: {}"),
+    ("CPU 使用率过高: {}%", "High CPU usage: {}%"),
+    ("{} 所在磁盘空间不足: 剩余 {} MiB", "Low disk space for {}: {} MiB left"),
+    ("可用内存不足: {} MiB", "Low available memory: {} MiB"),
+    ("电量不足: {}%，未接通电源", "Low battery: {}%, not plugged in"),
+    ("端点不可达: {}", "Endpoint unreachable: {}"),
+    // 会议
+    ("未知的会议消息类型: {}", "Unknown meeting message type: {}"),
+    ("chunk_seconds 超出范围: {} (应为 {}-{})", "chunk_seconds out of range: {} (expected {}-{})"),
+    ("summary.endpoint 不能为空", "'summary.endpoint' must not be empty"),
+    ("system_audio 不是有效的 base64: {}", "system_audio is not valid base64: {}"),
+    ("mic 和 system 至少启用一个", "At least one of 'mic' or 'system' must be enabled"),
+    ("已有会议在录音中", "A meeting is already being recorded"),
+    ("进行中的会议过多 (最多 {} 个)", "Too many meetings in progress (max {})"),
+    ("打开录音设备失败: {}", "Failed to open recording device: {}"),
+    ("没有正在录音的会议", "No meeting is being recorded"),
+    ("会议不存在", "Meeting not found"),
+    ("未找到系统音频设备: {}", "System audio device not found: {}"),
+    ("未指定系统音频设备 (需要回环或监听输入设备)", "No system audio device specified (a loopback or monitor input device is required)"),
+    ("无法获取默认音频配置: {}", "Cannot get default audio config: {}"),
+    ("无法获取输入设备列表: {}", "Cannot list input devices: {}"),
+    ("无法启动采集线程: {}", "Cannot start capture thread: {}"),
+    ("采集线程意外退出", "Capture thread exited unexpectedly"),
+    ("{} 不是有效的 base64: {}", "{} is not valid base64: {}"),
+    // 工作流
+    ("未知的工作流消息类型: {}", "Unknown workflow message type: {}"),
+    ("steps 不能为空", "'steps' must not be empty"),
+    ("步骤过多 (最多 {} 个)", "Too many steps (max {})"),
+    ("无效的步骤 ID: '{}' (只能包含字母、数字、- 和 _，最多 {} 个字符)", "Invalid step id: '{}' (letters, digits, - and _ only, max {} characters)"),
+    ("步骤 ID 重复: {}", "Duplicate step id: {}"),
+    ("步骤 {} 的 retries 超出范围: {} (应为 0-{})", "retries of step {} out of range: {} (expected 0-{})"),
+    ("步骤 {} 的 retry_delay_ms 超出范围: {} (应为 0-{})", "retry_delay_ms of step {} out of range: {} (expected 0-{})"),
+    ("步骤 {} 的条件引用了未知或之后的步骤: {}", "Condition of step {} refers to an unknown or later step: {}"),
+    ("步骤 {} 的模板引用了未知或之后的步骤: {}", "Template of step {} refers to an unknown or later step: {}"),
+    ("步骤 {} 的 max_seconds 超出范围: {} (应为 {}-{})", "max_seconds of step {} out of range: {} (expected {}-{})"),
+    ("步骤 {} 的 asr_config 无效: {}", "Invalid asr_config in step {}: {}"),
+    ("步骤 {} 的 endpoint 不能为空", "endpoint of step {} must not be empty"),
+    ("步骤 {} 的 path 必须是绝对路径: {}", "path of step {} must be absolute: {}"),
+    ("没有可转写的音频 (需要之前的 record 步骤或 audio 字段)", "No audio to transcribe (needs an earlier record step or an 'audio' field)"),
+    ("path 必须是绝对路径: {}", "path must be absolute: {}"),
+    ("文件已存在: {}", "File already exists: {}"),
+    ("无法写入文件: {}", "Cannot write file: {}"),
+    ("写入任务失败: {}", "Write task failed: {}"),
+    ("运行中的工作流过多 (最多 {} 个)", "Too many workflows running (max {})"),
+    ("没有正在录音的工作流", "No workflow is recording"),
+    ("工作流不存在", "Workflow not found"),
+    // 向量索引
+    ("未知的向量消息类型: {}", "Unknown vectors message type: {}"),
+    ("path 不能为空", "'path' must not be empty"),
+    ("embedding.endpoint 不能为空", "'embedding.endpoint' must not be empty"),
+    ("embedding.model 不能为空", "'embedding.model' must not be empty"),
+    ("limit 必须在 1 到 {} 之间", "'limit' must be between 1 and {}"),
+    ("向量索引任务失败: {}", "Vector index task failed: {}"),
+    ("索引文件读写失败: {}", "Failed to read or write the index file: {}"),
+    ("无效的索引文件: {}", "Invalid index file: {}"),
+    ("不支持的索引版本: {}", "Unsupported index version: {}"),
+    ("索引使用模型 {} 生成，与请求的模型 {} 不同 (先 clear 再重新索引)", "The index was built with model {}, not the requested model {} (clear it and re-index first)"),
+    ("向量维度不一致: 索引为 {}，收到 {}", "Embedding dimensions differ: index has {}, got {}"),
+    ("无法解析嵌入响应: {}", "Cannot parse embedding response: {}"),
+    ("嵌入向量个数不符: 需要 {}，收到 {}", "Embedding count mismatch: expected {}, got {}"),
+    ("chunking.size 必须在 {} 到 {} 之间", "'chunking.size' must be between {} and {}"),
+    ("chunking.overlap 不能超过 size 的一半", "'chunking.overlap' cannot exceed half of 'chunking.size'"),
+    ("chunking.overlap 不能超过 {} 句", "'chunking.overlap' cannot exceed {} sentences"),
+    ("chunking.overlap 只适用于 sentence 和 tokens 策略", "'chunking.overlap' only applies to the sentence and tokens strategies"),
+    ("缺少 embedding (只有 dry_run 可以省略)", "Missing 'embedding' (only dry_run may omit it)"),
+    ("root 不是目录: {}", "'root' is not a directory: {}"),
+    ("扫描笔记任务失败: {}", "Note scanning task failed: {}"),
+    ("读取笔记失败: {}", "Failed to read note: {}"),
+    ("重新索引已终止", "Reindexing cancelled"),
+    // 语音合成
+    ("未知的语音合成消息类型: {}", "Unknown tts message type: {}"),
+    ("无效的 speak 请求: {}", "Invalid speak request: {}"),
+    ("缺少 doubao 配置", "Missing 'doubao' config"),
+    ("朗读文本过长 (最多 {} 个字符)", "Text is too long to read aloud (max {} characters)"),
+    ("text 不能为空", "'text' must not be empty"),
+    ("没有正在进行的朗读: {}", "Nothing is being read aloud: {}"),
+    ("TTS 配置无效: {}", "Invalid TTS config: {}"),
+    ("app_id 不能为空", "'app_id' must not be empty"),
+    ("access_token 不能为空", "'access_token' must not be empty"),
+    ("access_token 含有无效字符", "'access_token' contains invalid characters"),
+    ("speed_ratio 超出范围: {} (应为 {}-{})", "'speed_ratio' out of range: {} (expected {}-{})"),
+    ("无效的 endpoint: {}", "Invalid endpoint: {}"),
+    ("TTS 连接失败: {}", "TTS connection failed: {}"),
+    ("TTS 服务返回错误: code={}, {}", "TTS service error: code={}, {}"),
+    ("TTS 响应无法解析: {}", "Cannot parse TTS response: {}"),
+    ("等待 TTS 音频超时", "Timed out waiting for TTS audio"),
+    ("连接在合成结束前关闭", "Connection closed before synthesis finished"),
+    ("音频包不完整: 需要 {} bytes", "Incomplete audio packet: need {} bytes"),
+];
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(Locale::from_tag("zh-CN"), Locale::Zh);
+        assert_eq!(Locale::from_tag("zh-Hant-TW"), Locale::Zh);
+        assert_eq!(Locale::from_tag("en-US,en;q=0.9"), Locale::En);
+        assert_eq!(Locale::from_tag("de"), Locale::En);
+        assert_eq!(Locale::from_tag(" ZH ;q=1"), Locale::Zh);
+    }
+
+    #[test]
+    fn test_translate() {
+        // 参数原样保留，嵌套的已知消息一并翻译
+        assert_eq!(Locale::En.translate("PTY 会话不存在: abc"), "PTY session not found: abc");
+        assert_eq!(
+            Locale::En.translate("启动录音失败: 音频设备错误: no device"),
+            "Failed to start recording: Audio device error: no device"
+        );
+        assert_eq!(
+            Locale::En.translate("voice.start_recording 消息过于频繁，请 850 ms 后重试"),
+            "Too many voice.start_recording messages, retry in 850 ms"
+        );
+        assert_eq!(Locale::Zh.translate("Invalid clip_url request: missing field `url`"), "无效的 clip_url 请求: missing field `url`");
+        assert_eq!(Locale::Zh.translate("Network error: connection refused"), "网络错误: connection refused");
+
+        // 未收录的消息和已是目标语言的消息不变
+        assert_eq!(Locale::En.translate("upstream says no"), "upstream says no");
+        assert_eq!(Locale::Zh.translate("已在录音中"), "已在录音中");
+        assert_eq!(Locale::En.translate("已在录音中 (2)"), "已在录音中 (2)");
+    }
+
+    #[test]
+    fn test_catalog() {
+        // 两种语言的参数个数必须相同
+        for (zh, en) in CATALOG {
+            assert_eq!(zh.matches("{}").count(), en.matches("{}").count(), "{} / {}", zh, en);
+            assert!(!zh.contains("{}{}") && !en.contains("{}{}"), "{} / {}", zh, en);
+        }
+    }
+
+    /// 是否含有汉字
+    fn has_han(text: &str) -> bool {
+        text.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c))
+    }
+
+    /// 目录下的全部 .rs 文件
+    fn source_files(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                source_files(&path, files);
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                files.push(path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_catalog_covers_client_errors() {
+        // 错误构造 (xxx_error(ErrorCode, ..)、XxxError::Variant(..)、#[error(..)]、Err(..))、诊断结果、系统信息提示和
+        // error 字段中的中文字面量都会发给客户端，必须能在 CATALOG 中找到模板；格式参数按 `{}` 处理
+        let literal = regex::Regex::new(
+            r#"(?:_error\(\s*ErrorCode::\w+,|\b[A-Z]\w*Error::\w+\(|#\[error\(|\bErr\(|CheckStatus::\w+,|\bwarn\("\w+",|\berror: Some\(|_error: Some\()\s*(?:&?format!\(\s*)?"((?:[^"\\]|\\.)*)""#,
+        ).unwrap();
+        let placeholder = regex::Regex::new(r"\{[^{}]*\}").unwrap();
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut files = Vec::new();
+        source_files(&src, &mut files);
+        files.sort();
+
+        let mut missing = Vec::new();
+        for path in files.iter().filter(|path| !path.starts_with(src.join("testing"))) {
+            let source = std::fs::read_to_string(path).unwrap();
+            let code = source.split("#[cfg(test)]").next().unwrap_or_default();
+            for capture in literal.captures_iter(code) {
+                if !has_han(&capture[1]) {
+                    continue;
+                }
+                let template = placeholder.replace_all(&capture[1], "{}").replace("\\\"", "\"");
+                if has_han(&Locale::En.translate(&template)) {
+                    missing.push(format!("{}: {}", path.strip_prefix(&src).unwrap().display(), template));
+                }
+            }
+        }
+        missing.sort();
+        missing.dedup();
+        assert!(missing.is_empty(), "CATALOG 缺少 {} 条错误消息:\n{}", missing.len(), missing.join("\n"));
+    }
+}
@@ -0,0 +1,763 @@
+// System 模块
+// 处理连接级别的协议消息：握手、协议版本、编码与语言协商、事件订阅、日志查询、能力描述、分块上传、数据目录维护、本地任务并发调整、自我诊断
+
+pub mod codec;
+pub mod diagnose;
+pub mod locale;
+pub mod progress;
+pub mod subscription;
+pub mod upload;
+
+use serde::{Deserialize, Serialize};
+
+use codec::Encoding;
+use locale::Locale;
+use subscription::Topic;
+
+use crate::audit::{self, AuditAction, AuditQuery, AuditRecord};
+use crate::logging::{self, Level, LogRecord};
+use crate::storage::{self, DataDirs};
+use crate::workers::{KindUsage, WorkerLimits};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleDescription, ModuleError, ModuleHandler, ModuleMessage,
+    ModuleType, RouterError, ServerResponse,
+};
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "System", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "System", format!($($arg)*));
+        }
+    };
+}
+
+/// 创建 System 模块错误
+fn system_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::System, code, message)
+}
+
+/// 当前协议版本
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// 服务器仍兼容的最低协议版本
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// 实时日志的订阅主题会话名 (订阅 `system:logs` 接收 `log` 事件)
+pub const LOG_SESSION: &str = "logs";
+
+/// get_logs 默认返回的日志条数
+const DEFAULT_LOG_LIMIT: usize = 200;
+
+// ============================================================================
+// 消息类型定义
+// ============================================================================
+
+/// 服务器 hello (连接建立后立即发送)
+#[derive(Debug, Serialize)]
+pub struct ServerHello {
+    /// 当前协议版本
+    pub protocol_version: u32,
+    /// 兼容的最低协议版本
+    pub min_protocol_version: u32,
+    /// 服务器版本
+    pub server_version: String,
+    /// 已启用的模块
+    pub modules: Vec<ModuleType>,
+    /// 支持的消息编码
+    pub encodings: Vec<Encoding>,
+    /// 单条消息的大小上限 (字节)，更大的消息需分块上传
+    pub max_message_size: usize,
+}
+
+impl ServerHello {
+    /// 创建服务器 hello 消息
+    pub fn new(modules: Vec<ModuleType>) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            min_protocol_version: MIN_PROTOCOL_VERSION,
+            server_version: crate::SERVER_VERSION.to_string(),
+            modules,
+            encodings: codec::SUPPORTED_ENCODINGS.to_vec(),
+            max_message_size: crate::config::Limits::default().max_message_size,
+        }
+    }
+
+    /// 设置单条消息的大小上限
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// 转换为服务器响应
+    pub fn into_response(self) -> ServerResponse {
+        let payload = serde_json::to_value(&self).unwrap_or_default();
+        ServerResponse::new(ModuleType::System, "hello", payload)
+    }
+}
+
+/// 客户端 hello
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClientHello {
+    /// 客户端支持的最高协议版本
+    pub protocol_version: u32,
+    /// 客户端支持的最低协议版本 (默认与 protocol_version 相同)
+    #[serde(default)]
+    pub min_protocol_version: Option<u32>,
+    /// 客户端版本 (仅用于日志)
+    #[serde(default)]
+    pub client_version: Option<String>,
+    /// 客户端支持的消息编码 (按偏好排序，省略时使用 JSON)
+    #[serde(default)]
+    pub encodings: Vec<String>,
+    /// 客户端是否接受合并后的事件 (`batch` 数组)
+    #[serde(default)]
+    pub batching: bool,
+    /// 错误消息的语言标签 (如 `en-US`、`zh-CN`，省略时不翻译)
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// vault 标识 (如 vault 路径)，按 vault 保存的数据只对声明同一标识的连接可见
+    #[serde(default)]
+    pub vault: Option<String>,
+}
+
+/// 握手确认响应
+#[derive(Debug, Serialize)]
+pub struct HelloAckResponse {
+    /// 协商后的协议版本
+    pub protocol_version: u32,
+    /// 服务器版本
+    pub server_version: String,
+    /// 协商后的消息编码 (hello_ack 之后的消息使用该编码)
+    pub encoding: Encoding,
+    /// 是否合并高频事件 (hello_ack 之后生效)
+    pub batching: bool,
+    /// 协商后的错误消息语言 (客户端未声明时省略)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<Locale>,
+    /// 该连接的 vault 标识 (客户端未声明时省略)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault: Option<String>,
+}
+
+/// 订阅请求 (subscribe / unsubscribe)
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscriptionRequest {
+    /// 主题列表；unsubscribe 省略时取消全部订阅
+    #[serde(default)]
+    pub topics: Option<Vec<String>>,
+}
+
+/// 订阅结果响应
+#[derive(Debug, Serialize)]
+pub struct SubscriptionResponse {
+    /// 当前订阅的全部主题
+    pub topics: Vec<String>,
+}
+
+/// 日志查询请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetLogsRequest {
+    /// 最多返回的条数
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// 最低日志级别 (默认 info)
+    #[serde(default)]
+    pub level: Level,
+}
+
+/// 日志查询响应
+#[derive(Debug, Serialize)]
+pub struct LogsResponse {
+    /// 最近的日志记录 (按时间升序)
+    pub records: Vec<LogRecord>,
+    /// 日志文件路径 (未启用文件输出时为 null)
+    pub file: Option<String>,
+}
+
+/// set_limits 请求 (省略的项保持不变)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SetLimitsRequest {
+    /// 同时运行的 CPU 密集任务总数
+    #[serde(default)]
+    pub threads: Option<usize>,
+    /// 同时运行的本地语音识别任务数
+    #[serde(default)]
+    pub local_asr: Option<usize>,
+    /// 同时运行的索引任务数
+    #[serde(default)]
+    pub indexing: Option<usize>,
+}
+
+/// 本地任务并发响应
+#[derive(Debug, Serialize)]
+pub struct LimitsResponse {
+    /// 调整后的上限
+    #[serde(flatten)]
+    pub limits: WorkerLimits,
+    /// 各任务类型正在运行和排队的任务数
+    pub tasks: Vec<KindUsage>,
+}
+
+/// 审计日志查询响应
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    /// 最近的审计记录 (按时间升序)
+    pub records: Vec<AuditRecord>,
+    /// 审计日志文件路径 (未启用文件输出时为 null，记录仅保留在内存中)
+    pub file: Option<String>,
+}
+
+/// 能力描述响应 (describe)
+#[derive(Debug, Serialize)]
+pub struct DescribeResponse {
+    /// 当前协议版本
+    pub protocol_version: u32,
+    /// 服务器版本
+    pub server_version: String,
+    /// 各已启用模块支持的消息类型及字段
+    pub modules: Vec<ModuleDescription>,
+}
+
+impl DescribeResponse {
+    /// 创建能力描述响应
+    pub fn new(modules: Vec<ModuleDescription>) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            server_version: crate::SERVER_VERSION.to_string(),
+            modules,
+        }
+    }
+
+    /// 转换为服务器响应
+    pub fn into_response(self) -> ServerResponse {
+        let payload = serde_json::to_value(&self).unwrap_or_default();
+        ServerResponse::new(ModuleType::System, "description", payload)
+    }
+}
+
+/// 协商协议版本
+///
+/// 双方支持的版本区间有交集时返回交集中的最高版本
+pub fn negotiate(hello: &ClientHello) -> Result<u32, RouterError> {
+    let client_min = hello.min_protocol_version.unwrap_or(hello.protocol_version);
+    let version = hello.protocol_version.min(PROTOCOL_VERSION);
+    if version < client_min.max(MIN_PROTOCOL_VERSION) {
+        return Err(RouterError::IncompatibleProtocol(format!(
+            "client supports {}..={}, server supports {}..={}",
+            client_min, hello.protocol_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        )));
+    }
+    Ok(version)
+}
+
+// ============================================================================
+// System 处理器
+// ============================================================================
+
+/// System 模块处理器
+///
+/// 由所有连接共享，不保存连接级状态；协商结果通过 hello_ack 返回给客户端
+pub struct SystemHandler;
+
+impl SystemHandler {
+    /// 创建新的 System 处理器
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 处理客户端 hello
+    async fn handle_hello(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let hello: ClientHello = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| RouterError::InvalidMessage(format!("Invalid hello: {}", e)))?;
+
+        let version = negotiate(&hello)?;
+        let encoding = codec::negotiate_encoding(&hello.encodings);
+        let locale = hello.locale.as_deref().map(Locale::from_tag);
+        if hello.vault.as_deref().is_some_and(|vault| vault.trim().is_empty()) {
+            return Err(system_error(ErrorCode::InvalidParams, "vault 不能为空").into());
+        }
+
+        log_info!("握手完成: client_version={:?}, protocol_version={}, encoding={:?}, locale={:?}, vault={:?}",
+            hello.client_version, version, encoding, locale, hello.vault);
+
+        let response = HelloAckResponse {
+            protocol_version: version,
+            server_version: crate::SERVER_VERSION.to_string(),
+            encoding,
+            batching: hello.batching,
+            locale,
+            vault: hello.vault,
+        };
+        let payload = serde_json::to_value(&response)?;
+
+        Ok(Some(ServerResponse::new(ModuleType::System, "hello_ack", payload)))
+    }
+
+    /// 处理订阅和取消订阅
+    ///
+    /// 订阅保存在来源连接上，由服务器在投递事件时过滤
+    async fn handle_subscription(&self, msg: &ModuleMessage, subscribe: bool) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SubscriptionRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| RouterError::InvalidMessage(format!("Invalid subscription request: {}", e)))?;
+        let connection = msg.connection.as_ref()
+            .ok_or_else(|| system_error(ErrorCode::NotConnected, "订阅需要客户端连接"))?;
+
+        let topics = request.topics
+            .map(|topics| topics.iter().map(|t| Topic::parse(t)).collect::<Result<Vec<_>, _>>())
+            .transpose()?;
+
+        let mut subscriptions = connection.subscriptions.lock().await;
+        let msg_type = match (subscribe, topics) {
+            (true, Some(topics)) => {
+                subscriptions.subscribe(topics);
+                "subscribed"
+            }
+            (true, None) => {
+                return Err(RouterError::InvalidMessage("Invalid subscription request: missing field `topics`".to_string()));
+            }
+            (false, Some(topics)) => {
+                subscriptions.unsubscribe(&topics);
+                "unsubscribed"
+            }
+            (false, None) => {
+                subscriptions.clear();
+                "unsubscribed"
+            }
+        };
+
+        log_info!("连接 {} 订阅更新: {:?}", connection.id, subscriptions.topics());
+
+        let response = SubscriptionResponse {
+            topics: subscriptions.topics(),
+        };
+        let payload = serde_json::to_value(&response)?;
+
+        Ok(Some(ServerResponse::new(ModuleType::System, msg_type, payload)))
+    }
+}
+
+impl SystemHandler {
+    /// 处理日志查询
+    fn handle_get_logs(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: GetLogsRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| RouterError::InvalidMessage(format!("Invalid get_logs request: {}", e)))?;
+
+        let response = LogsResponse {
+            records: logging::recent(request.limit.unwrap_or(DEFAULT_LOG_LIMIT), request.level),
+            file: logging::file_path().map(|path| path.display().to_string()),
+        };
+        let payload = serde_json::to_value(&response)?;
+
+        Ok(Some(ServerResponse::new(ModuleType::System, "logs", payload)))
+    }
+
+    /// 处理审计日志查询
+    async fn handle_get_audit_log(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let query: AuditQuery = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| RouterError::InvalidMessage(format!("Invalid get_audit_log request: {}", e)))?;
+
+        // 审计日志文件只追加不截断，可能较大，在阻塞线程中读取
+        let records = tokio::task::spawn_blocking(move || audit::query(&query))
+            .await
+            .map_err(|e| system_error(ErrorCode::Internal, format!("读取审计日志失败: {}", e)))?
+            .map_err(|e| system_error(ErrorCode::IoError, format!("读取审计日志失败: {}", e)))?;
+        let response = AuditLogResponse {
+            records,
+            file: audit::file_path().map(|path| path.display().to_string()),
+        };
+        let payload = serde_json::to_value(&response)?;
+
+        Ok(Some(ServerResponse::new(ModuleType::System, "audit_log", payload)))
+    }
+}
+
+impl SystemHandler {
+    /// 查询数据目录各区域的占用
+    async fn handle_get_storage_info(&self) -> Result<Option<ServerResponse>, RouterError> {
+        let dirs = Self::data_dirs()?;
+        let info = tokio::task::spawn_blocking(move || dirs.info())
+            .await
+            .map_err(|e| system_error(ErrorCode::Internal, format!("统计数据目录失败: {}", e)))?;
+        let payload = serde_json::to_value(&info)?;
+
+        Ok(Some(ServerResponse::new(ModuleType::System, "storage_info", payload)))
+    }
+
+    /// 清空缓存目录
+    async fn handle_clear_cache(&self) -> Result<Option<ServerResponse>, RouterError> {
+        let dirs = Self::data_dirs()?;
+        let cleared = tokio::task::spawn_blocking(move || dirs.clear_cache())
+            .await
+            .map_err(|e| system_error(ErrorCode::Internal, format!("清理缓存失败: {}", e)))?
+            .map_err(|e| system_error(ErrorCode::IoError, format!("清理缓存失败: {}", e)))?;
+
+        log_info!("已清理缓存: {} 个文件, {} 字节", cleared.files, cleared.freed_bytes);
+
+        let payload = serde_json::to_value(&cleared)?;
+        Ok(Some(ServerResponse::new(ModuleType::System, "cache_cleared", payload)))
+    }
+
+    /// 调整 CPU 密集的本地任务并发上限，返回调整后的上限和各类型的任务数
+    fn handle_set_limits(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SetLimitsRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| RouterError::InvalidMessage(format!("Invalid set_limits request: {}", e)))?;
+        let pool = crate::workers::pool();
+        let current = pool.limits();
+        let limits = WorkerLimits {
+            threads: request.threads.unwrap_or(current.threads),
+            local_asr: request.local_asr.unwrap_or(current.local_asr),
+            indexing: request.indexing.unwrap_or(current.indexing),
+        };
+        if limits.threads == 0 || limits.local_asr == 0 || limits.indexing == 0 {
+            return Err(system_error(ErrorCode::InvalidParams, "并发上限必须大于 0").into());
+        }
+        if limits != current {
+            pool.set_limits(limits);
+            log_info!("本地任务并发上限已调整: {:?}", limits);
+        }
+
+        let payload = serde_json::to_value(LimitsResponse { limits, tasks: pool.usage() })?;
+        Ok(Some(ServerResponse::new(ModuleType::System, "limits", payload)))
+    }
+
+    /// 运行自我诊断
+    async fn handle_diagnose(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: diagnose::DiagnoseRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| RouterError::InvalidMessage(format!("Invalid diagnose request: {}", e)))?;
+        if let Some(unknown) = request.checks.iter().flatten().find(|c| !diagnose::CHECK_NAMES.contains(&c.as_str())) {
+            return Err(RouterError::InvalidMessage(format!("Invalid diagnose request: unknown check `{}`", unknown)));
+        }
+
+        let report = diagnose::run(request).await;
+        let failed: Vec<&str> = report.checks.iter()
+            .filter(|check| check.status == diagnose::CheckStatus::Fail)
+            .map(|check| check.target.as_deref().unwrap_or(check.check))
+            .collect();
+        log_info!("自我诊断完成: {} 项检查, 失败: {:?}", report.checks.len(), failed);
+
+        let payload = serde_json::to_value(&report)?;
+        Ok(Some(ServerResponse::new(ModuleType::System, "diagnosis", payload)))
+    }
+
+    fn data_dirs() -> Result<&'static DataDirs, ModuleError> {
+        storage::data_dirs().ok_or_else(|| system_error(ErrorCode::InvalidConfig, "未配置数据目录"))
+    }
+}
+
+impl Default for SystemHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// System 模块支持的消息 (describe、cancel 和 kill_job 需要涉及所有模块，由路由器应答)
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("hello", &[
+        FieldSpec::required("protocol_version", FieldKind::Integer),
+        FieldSpec::optional("min_protocol_version", FieldKind::Integer),
+        FieldSpec::optional("client_version", FieldKind::String),
+        FieldSpec::optional("encodings", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("batching", FieldKind::Boolean),
+        FieldSpec::optional("locale", FieldKind::String),
+        FieldSpec::optional("vault", FieldKind::String),
+    ]),
+    MessageSpec::new("subscribe", &[
+        FieldSpec::required("topics", FieldKind::Array).items(FieldKind::String),
+    ]),
+    MessageSpec::new("unsubscribe", &[
+        FieldSpec::optional("topics", FieldKind::Array).items(FieldKind::String),
+    ]),
+    MessageSpec::new("get_logs", &[
+        FieldSpec::optional("limit", FieldKind::Integer),
+        FieldSpec::optional("level", FieldKind::String).one_of(&["debug", "info", "warn", "error"]),
+    ]),
+    MessageSpec::new("get_audit_log", &[
+        FieldSpec::optional("limit", FieldKind::Integer),
+        FieldSpec::optional("action", FieldKind::String).one_of(AuditAction::NAMES),
+        FieldSpec::optional("origin_request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("describe", &[]),
+    // 由路由器查找任务所属模块并应答
+    MessageSpec::new("cancel", &[
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("list_jobs", &[]),
+    // 由路由器终止任务 (必要时交给所属模块取消) 并应答
+    MessageSpec::new("kill_job", &[
+        FieldSpec::required("id", FieldKind::Unsigned),
+    ]),
+    // 由耗时统计中间件应答
+    MessageSpec::new("get_metrics", &[]),
+    MessageSpec::new("get_storage_info", &[]),
+    MessageSpec::new("clear_cache", &[]),
+    MessageSpec::new("set_limits", &[
+        FieldSpec::optional("threads", FieldKind::Integer),
+        FieldSpec::optional("local_asr", FieldKind::Integer),
+        FieldSpec::optional("indexing", FieldKind::Integer),
+    ]),
+    MessageSpec::new("diagnose", &[
+        FieldSpec::optional("checks", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("asr_config", FieldKind::Object),
+        FieldSpec::optional("endpoints", FieldKind::Object),
+    ]),
+    // 分块上传，由 WebSocket 连接直接处理
+    MessageSpec::new("upload_begin", &[
+        FieldSpec::optional("size", FieldKind::Integer),
+    ]),
+    MessageSpec::new("upload_append", &[
+        FieldSpec::required("upload_id", FieldKind::String),
+        FieldSpec::required("data", FieldKind::String),
+        FieldSpec::optional("offset", FieldKind::Integer),
+    ]),
+    MessageSpec::new("upload_commit", &[
+        FieldSpec::required("upload_id", FieldKind::String),
+    ]),
+    MessageSpec::new("upload_abort", &[
+        FieldSpec::required("upload_id", FieldKind::String),
+    ]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 系统模块 (握手等连接级消息)
+    pub const System: ModuleType = ModuleType::named("system");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for SystemHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::System
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理 System 消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "hello" => self.handle_hello(msg).await,
+            "subscribe" => self.handle_subscription(msg, true).await,
+            "unsubscribe" => self.handle_subscription(msg, false).await,
+            "get_logs" => self.handle_get_logs(msg),
+            "get_audit_log" => self.handle_get_audit_log(msg).await,
+            "get_storage_info" => self.handle_get_storage_info().await,
+            "clear_cache" => self.handle_clear_cache().await,
+            "set_limits" => self.handle_set_limits(msg),
+            "diagnose" => self.handle_diagnose(msg).await,
+            "list_jobs" => Ok(Some(ServerResponse::new(ModuleType::System, "jobs", serde_json::json!({
+                "jobs": crate::jobs::list(),
+            })))),
+            // 连接之外 (如 HTTP 接口) 没有保存上传状态的地方
+            msg_type if upload::UPLOAD_MESSAGES.contains(&msg_type) => {
+                Err(system_error(ErrorCode::NotConnected, "分块上传仅支持 WebSocket 连接").into())
+            }
+            _ => Err(system_error(ErrorCode::UnknownMessageType, format!("未知的 System 消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(payload: serde_json::Value) -> ModuleMessage {
+        ModuleMessage {
+            module: ModuleType::System,
+            msg_type: "hello".to_string(),
+            payload,
+            connection: None,
+        }
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let client = |max: u32, min: Option<u32>| ClientHello {
+            protocol_version: max,
+            min_protocol_version: min,
+            client_version: None,
+            encodings: Vec::new(),
+            batching: false,
+            locale: None,
+            vault: None,
+        };
+
+        assert_eq!(negotiate(&client(PROTOCOL_VERSION, None)).unwrap(), PROTOCOL_VERSION);
+        // 更新的客户端降级到服务器版本
+        assert_eq!(negotiate(&client(PROTOCOL_VERSION + 3, Some(1))).unwrap(), PROTOCOL_VERSION);
+        // 客户端只支持更新的协议
+        assert!(matches!(
+            negotiate(&client(PROTOCOL_VERSION + 2, Some(PROTOCOL_VERSION + 1))),
+            Err(RouterError::IncompatibleProtocol(_))
+        ));
+        // 客户端过旧
+        assert!(negotiate(&client(MIN_PROTOCOL_VERSION - 1, None)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_hello() {
+        let handler = SystemHandler::new();
+
+        let msg = hello(serde_json::json!({ "protocol_version": PROTOCOL_VERSION, "client_version": "1.0.0" }));
+        let response = handler.handle(&msg).await.unwrap().unwrap();
+        assert_eq!(response.module, ModuleType::System);
+        assert_eq!(response.msg_type, "hello_ack");
+        assert_eq!(response.payload["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(response.payload["encoding"], "json");
+        assert_eq!(response.payload["batching"], false);
+        assert!(response.payload.get("locale").is_none());
+
+        let msg = hello(serde_json::json!({ "protocol_version": PROTOCOL_VERSION, "encodings": ["msgpack", "json"], "batching": true }));
+        let response = handler.handle(&msg).await.unwrap().unwrap();
+        assert_eq!(response.payload["encoding"], "msgpack");
+        assert_eq!(response.payload["batching"], true);
+
+        let msg = hello(serde_json::json!({ "protocol_version": PROTOCOL_VERSION, "locale": "en-GB" }));
+        let response = handler.handle(&msg).await.unwrap().unwrap();
+        assert_eq!(response.payload["locale"], "en");
+        assert!(response.payload.get("vault").is_none());
+
+        let msg = hello(serde_json::json!({ "protocol_version": PROTOCOL_VERSION, "vault": "/home/me/Notes" }));
+        let response = handler.handle(&msg).await.unwrap().unwrap();
+        assert_eq!(response.payload["vault"], "/home/me/Notes");
+        let result = handler.handle(&hello(serde_json::json!({ "protocol_version": PROTOCOL_VERSION, "vault": " " }))).await;
+        assert!(result.is_err());
+
+        let result = handler.handle(&hello(serde_json::json!({}))).await;
+        assert!(matches!(result, Err(RouterError::InvalidMessage(_))));
+    }
+
+    #[tokio::test]
+    async fn test_set_limits() {
+        let handler = SystemHandler::new();
+        let message = |payload: serde_json::Value| ModuleMessage {
+            module: ModuleType::System,
+            msg_type: "set_limits".to_string(),
+            payload,
+            connection: None,
+        };
+
+        // 省略所有项时只返回当前上限
+        let current = crate::workers::pool().limits();
+        let response = handler.handle(&message(serde_json::json!({}))).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "limits");
+        assert_eq!(response.payload["threads"], current.threads);
+        assert_eq!(response.payload["tasks"][0]["kind"], "local_asr");
+
+        let result = handler.handle(&message(serde_json::json!({ "indexing": 0 }))).await;
+        assert!(result.is_err());
+        assert_eq!(crate::workers::pool().limits(), current);
+    }
+
+    #[tokio::test]
+    async fn test_handle_subscription() {
+        use futures_util::SinkExt;
+
+        let registry = std::sync::Arc::new(crate::server::ConnectionRegistry::new());
+        let sink = futures_util::sink::drain().sink_map_err(|e| match e {});
+        let connection = registry.register(Box::pin(sink)).await;
+        let handler = SystemHandler::new();
+        let message = |msg_type: &str, payload: serde_json::Value| ModuleMessage {
+            module: ModuleType::System,
+            msg_type: msg_type.to_string(),
+            payload,
+            connection: Some(connection.clone()),
+        };
+
+        let response = handler.handle(&message("subscribe", serde_json::json!({ "topics": ["voice", "pty:abc"] })))
+            .await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "subscribed");
+        assert_eq!(response.payload["topics"], serde_json::json!(["voice", "pty:abc"]));
+
+        let response = handler.handle(&message("unsubscribe", serde_json::json!({ "topics": ["voice"] })))
+            .await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "unsubscribed");
+        assert_eq!(response.payload["topics"], serde_json::json!(["pty:abc"]));
+
+        let response = handler.handle(&message("unsubscribe", serde_json::json!({}))).await.unwrap().unwrap();
+        assert_eq!(response.payload["topics"], serde_json::json!([]));
+
+        let result = handler.handle(&message("subscribe", serde_json::json!({ "topics": ["bogus"] }))).await;
+        assert!(matches!(result, Err(RouterError::InvalidMessage(_))));
+        let result = handler.handle(&message("subscribe", serde_json::json!({}))).await;
+        assert!(matches!(result, Err(RouterError::InvalidMessage(_))));
+    }
+
+    #[tokio::test]
+    async fn test_handle_get_logs() {
+        crate::logging::write(Level::Error, "System", "get-logs-marker".to_string());
+        let msg = ModuleMessage {
+            module: ModuleType::System,
+            msg_type: "get_logs".to_string(),
+            payload: serde_json::json!({ "level": "error" }),
+            connection: None,
+        };
+        let response = SystemHandler::new().handle(&msg).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "logs");
+        let records = response.payload["records"].as_array().unwrap();
+        assert!(records.iter().all(|r| r["level"] == "error"));
+        assert!(records.iter().any(|r| r["message"] == "get-logs-marker" && r["target"] == "System"));
+
+        let msg = ModuleMessage {
+            payload: serde_json::json!({ "level": "verbose" }),
+            ..msg
+        };
+        assert!(matches!(SystemHandler::new().handle(&msg).await, Err(RouterError::InvalidMessage(_))));
+    }
+
+    #[tokio::test]
+    async fn test_handle_storage() {
+        let dir = std::env::temp_dir().join(format!("system-storage-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        storage::init(DataDirs::at(&dir));
+        let dirs = storage::data_dirs().unwrap();
+        let cache = dirs.ensure(storage::StorageArea::Cache).unwrap();
+        std::fs::write(cache.join("page.html"), "cached").unwrap();
+
+        let message = |msg_type: &str| ModuleMessage {
+            module: ModuleType::System,
+            msg_type: msg_type.to_string(),
+            payload: serde_json::json!({}),
+            connection: None,
+        };
+        let handler = SystemHandler::new();
+
+        let response = handler.handle(&message("get_storage_info")).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "storage_info");
+        assert_eq!(response.payload["root"], dirs.root().display().to_string());
+        assert_eq!(response.payload["areas"][1]["area"], "cache");
+        assert_eq!(response.payload["areas"][1]["bytes"], 6);
+
+        let response = handler.handle(&message("clear_cache")).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "cache_cleared");
+        assert_eq!(response.payload, serde_json::json!({ "freed_bytes": 6, "files": 1 }));
+        assert!(!cache.join("page.html").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_server_hello() {
+        let response = ServerHello::new(vec![ModuleType::Pty, ModuleType::Utils])
+            .with_max_message_size(1024)
+            .into_response();
+        assert_eq!(response.msg_type, "hello");
+        assert_eq!(response.payload["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(response.payload["modules"], serde_json::json!(["pty", "utils"]));
+        assert!(response.payload["server_version"].is_string());
+        assert_eq!(response.payload["encodings"], serde_json::json!(["json", "msgpack"]));
+        assert_eq!(response.payload["max_message_size"], 1024);
+    }
+}
@@ -0,0 +1,113 @@
+// 进度事件
+// 耗时较长的请求在处理过程中向发起请求的连接发送统一格式的 progress 事件，客户端可以用同一个进度界面展示:
+// { "module": "<模块>", "type": "progress", "request_id": "...", "stage": "...", "percent": 40, "detail": "..." }
+
+use futures_util::SinkExt;
+use serde::Serialize;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::router::{ModuleType, ServerResponse};
+use crate::server::WsSender;
+
+/// 进度
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Progress {
+    /// 当前阶段 (由各请求定义，如 fetching / extracting)
+    pub stage: &'static str,
+    /// 完成百分比 (0-100，无法估计时省略)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent: Option<u8>,
+    /// 阶段说明 (如 "3/10")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// 进度报告器
+///
+/// 事件发送到发起请求的连接 (并抄送给订阅了该模块事件的连接)；没有来源连接时不发送
+#[derive(Clone)]
+pub struct ProgressReporter {
+    module: ModuleType,
+    request_id: Option<String>,
+    sender: Option<WsSender>,
+}
+
+impl ProgressReporter {
+    pub fn new(module: ModuleType, request_id: Option<String>, sender: Option<WsSender>) -> Self {
+        Self { module, request_id, sender }
+    }
+
+    /// 不发送事件的报告器
+    pub fn none(module: ModuleType) -> Self {
+        Self::new(module, None, None)
+    }
+
+    /// 报告进度 (发送失败时忽略，不影响请求本身)
+    pub async fn report(&self, stage: &'static str, percent: Option<u8>, detail: Option<String>) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        let progress = Progress {
+            stage,
+            percent: percent.map(|percent| percent.min(100)),
+            detail,
+        };
+        let payload = serde_json::to_value(&progress).unwrap_or_default();
+        let event = ServerResponse::new(self.module, "progress", payload)
+            .with_request_id(self.request_id.as_deref());
+        let _ = sender.lock().await.send(Message::Text(event.to_json().into())).await;
+    }
+}
+
+/// 按已完成数量计算区间 `[start, end]` 内的百分比
+pub fn percent_between(start: u8, end: u8, done: usize, total: usize) -> u8 {
+    if total == 0 {
+        return end;
+    }
+    let span = end.saturating_sub(start) as usize;
+    start + (span * done.min(total) / total) as u8
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::Mutex as TokioMutex;
+
+    #[tokio::test]
+    async fn test_report() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = futures_util::sink::unfold(Arc::clone(&received), |received, msg: Message| async move {
+            received.lock().unwrap().push(msg);
+            Ok::<_, tokio_tungstenite::tungstenite::Error>(received)
+        });
+        let sender: WsSender = Arc::new(TokioMutex::new(Box::pin(sink)));
+        let progress = ProgressReporter::new(ModuleType::Utils, Some("req-1".to_string()), Some(sender));
+
+        progress.report("fetching", Some(0), None).await;
+        progress.report("downloading_images", Some(120), Some("1/2".to_string())).await;
+        ProgressReporter::none(ModuleType::Utils).report("fetching", None, None).await;
+
+        let events: Vec<serde_json::Value> = received.lock().unwrap().iter()
+            .map(|msg| serde_json::from_str(msg.to_text().unwrap()).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], serde_json::json!({
+            "module": "utils", "type": "progress", "request_id": "req-1", "stage": "fetching", "percent": 0,
+        }));
+        assert_eq!(events[1]["percent"], 100);
+        assert_eq!(events[1]["detail"], "1/2");
+    }
+
+    #[test]
+    fn test_percent_between() {
+        assert_eq!(percent_between(50, 100, 0, 4), 50);
+        assert_eq!(percent_between(50, 100, 1, 4), 62);
+        assert_eq!(percent_between(50, 100, 4, 4), 100);
+        assert_eq!(percent_between(50, 100, 0, 0), 100);
+    }
+}
@@ -0,0 +1,151 @@
+// 事件订阅
+// 客户端通过 system/subscribe 选择接收哪些事件流
+//
+// 主题格式: "*" (所有事件)、"<module>" (模块全部事件)、"<module>:<session_id>" (指定会话)
+
+use std::fmt;
+
+use crate::router::{ModuleType, RouterError};
+
+/// 订阅主题
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Topic {
+    /// 所有事件
+    All,
+    /// 模块的全部事件
+    Module(ModuleType),
+    /// 模块中指定会话的事件
+    Session(ModuleType, String),
+}
+
+impl Topic {
+    /// 解析主题字符串
+    pub fn parse(text: &str) -> Result<Self, RouterError> {
+        let text = text.trim();
+        if text == "*" {
+            return Ok(Topic::All);
+        }
+
+        let (module, session_id) = match text.split_once(':') {
+            Some((module, session_id)) => (module, Some(session_id)),
+            None => (text, None),
+        };
+        let module: ModuleType = serde_json::from_value(serde_json::Value::String(module.to_string()))
+            .map_err(|_| RouterError::InvalidMessage(format!("Unknown topic module: {}", module)))?;
+
+        match session_id {
+            Some("") => Err(RouterError::InvalidMessage(format!("Empty session id in topic: {}", text))),
+            Some(session_id) => Ok(Topic::Session(module, session_id.to_string())),
+            None => Ok(Topic::Module(module)),
+        }
+    }
+
+    /// 判断事件是否属于该主题
+    pub fn matches(&self, module: ModuleType, session_id: Option<&str>) -> bool {
+        match self {
+            Topic::All => true,
+            Topic::Module(m) => *m == module,
+            Topic::Session(m, id) => *m == module && session_id == Some(id.as_str()),
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topic::All => write!(f, "*"),
+            Topic::Module(module) => write!(f, "{}", module),
+            Topic::Session(module, session_id) => write!(f, "{}:{}", module, session_id),
+        }
+    }
+}
+
+/// 单个连接的订阅集合
+///
+/// 没有任何订阅时连接接收所有广播事件，但不会收到其他连接发起的事件；
+/// 订阅后只接收匹配的广播事件，并同时接收其他连接发起的匹配事件
+#[derive(Debug, Clone, Default)]
+pub struct Subscriptions {
+    topics: Vec<Topic>,
+}
+
+impl Subscriptions {
+    /// 添加订阅 (重复的主题被忽略)
+    pub fn subscribe(&mut self, topics: impl IntoIterator<Item = Topic>) {
+        for topic in topics {
+            if !self.topics.contains(&topic) {
+                self.topics.push(topic);
+            }
+        }
+    }
+
+    /// 取消订阅
+    pub fn unsubscribe(&mut self, topics: &[Topic]) {
+        self.topics.retain(|topic| !topics.contains(topic));
+    }
+
+    /// 取消全部订阅
+    pub fn clear(&mut self) {
+        self.topics.clear();
+    }
+
+    /// 是否没有任何订阅
+    pub fn is_empty(&self) -> bool {
+        self.topics.is_empty()
+    }
+
+    /// 是否订阅了该事件
+    pub fn matches(&self, module: ModuleType, session_id: Option<&str>) -> bool {
+        self.topics.iter().any(|topic| topic.matches(module, session_id))
+    }
+
+    /// 当前订阅的主题字符串
+    pub fn topics(&self) -> Vec<String> {
+        self.topics.iter().map(Topic::to_string).collect()
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_topic() {
+        assert_eq!(Topic::parse("*").unwrap(), Topic::All);
+        assert_eq!(Topic::parse("voice").unwrap(), Topic::Module(ModuleType::Voice));
+        assert_eq!(
+            Topic::parse("pty:abc").unwrap(),
+            Topic::Session(ModuleType::Pty, "abc".to_string())
+        );
+        assert_eq!(Topic::parse("pty:abc").unwrap().to_string(), "pty:abc");
+        assert!(Topic::parse("unknown").is_err());
+        assert!(Topic::parse("pty:").is_err());
+    }
+
+    #[test]
+    fn test_subscriptions_match() {
+        let mut subscriptions = Subscriptions::default();
+        assert!(subscriptions.is_empty());
+        assert!(!subscriptions.matches(ModuleType::Pty, Some("abc")));
+
+        subscriptions.subscribe([
+            Topic::Session(ModuleType::Pty, "abc".to_string()),
+            Topic::Module(ModuleType::Voice),
+            Topic::Module(ModuleType::Voice),
+        ]);
+        assert_eq!(subscriptions.topics(), vec!["pty:abc", "voice"]);
+        assert!(subscriptions.matches(ModuleType::Pty, Some("abc")));
+        assert!(!subscriptions.matches(ModuleType::Pty, Some("xyz")));
+        assert!(!subscriptions.matches(ModuleType::Pty, None));
+        assert!(subscriptions.matches(ModuleType::Voice, None));
+
+        subscriptions.unsubscribe(&[Topic::Module(ModuleType::Voice)]);
+        assert!(!subscriptions.matches(ModuleType::Voice, None));
+        subscriptions.clear();
+        assert!(subscriptions.is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+// 分块上传
+// 超过单条消息大小限制的合法请求 (如 base64 音频文件、长文档) 可拆成多块发送：
+// upload_begin 开始上传，upload_append 依次追加完整消息 JSON 文本的片段，
+// upload_commit 将拼接后的消息当作直接发送的消息处理。上传状态属于连接，连接关闭时丢弃
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode, ModuleMessage, ModuleType, ServerResponse};
+
+/// 每个连接同时进行的上传数上限
+pub const MAX_PENDING_UPLOADS: usize = 4;
+
+/// 分块上传消息类型
+pub const UPLOAD_MESSAGES: &[&str] = &["upload_begin", "upload_append", "upload_commit", "upload_abort"];
+
+/// 是否为分块上传消息
+pub fn is_upload_message(msg: &ModuleMessage) -> bool {
+    msg.module == ModuleType::System && UPLOAD_MESSAGES.contains(&msg.msg_type.as_str())
+}
+
+/// 分块上传错误
+#[derive(Debug, Error)]
+pub enum UploadError {
+    #[error("上传不存在: {0}")]
+    NotFound(String),
+
+    #[error("上传内容超过 {0} 字节的限制")]
+    TooLarge(usize),
+
+    #[error("进行中的上传过多 (每个连接最多 {0} 个)")]
+    TooMany(usize),
+
+    #[error("分块偏移不连续: 期望 {expected}，实际 {actual}")]
+    OffsetMismatch { expected: usize, actual: usize },
+
+    #[error("无效的上传消息: {0}")]
+    InvalidMessage(String),
+}
+
+impl CodedError for UploadError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            UploadError::NotFound(_) => ErrorCode::NotFound,
+            UploadError::TooLarge(_) | UploadError::TooMany(_) => ErrorCode::LimitExceeded,
+            UploadError::OffsetMismatch { .. } | UploadError::InvalidMessage(_) => ErrorCode::InvalidParams,
+        }
+    }
+}
+
+/// 分块上传请求
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum UploadRequest {
+    #[serde(rename = "upload_begin")]
+    Begin {
+        /// 预计的总字节数 (可选，超出限制时立即拒绝)
+        #[serde(default)]
+        size: Option<usize>,
+    },
+    #[serde(rename = "upload_append")]
+    Append {
+        upload_id: String,
+        data: String,
+        /// 该片段在完整消息中的字节偏移 (可选，用于发现丢失或重复的片段)
+        #[serde(default)]
+        offset: Option<usize>,
+    },
+    #[serde(rename = "upload_commit")]
+    Commit {
+        upload_id: String,
+    },
+    #[serde(rename = "upload_abort")]
+    Abort {
+        upload_id: String,
+    },
+}
+
+/// 开始上传的响应 (upload_ready)
+#[derive(Debug, Serialize)]
+struct UploadReady {
+    upload_id: String,
+    /// 单条消息的大小上限 (每个片段连同消息外壳不能超过该值)
+    max_message_size: usize,
+    /// 完整消息的大小上限
+    max_upload_size: usize,
+}
+
+/// 上传消息的处理结果
+#[derive(Debug)]
+pub enum UploadOutcome {
+    /// 直接应答
+    Reply(ServerResponse),
+    /// 上传完成，拼接后的消息文本需按普通消息处理
+    Commit(String),
+}
+
+/// 一个连接上进行中的上传
+#[derive(Debug)]
+pub struct Uploads {
+    max_message_size: usize,
+    max_upload_size: usize,
+    next_id: u64,
+    pending: HashMap<String, String>,
+}
+
+impl Uploads {
+    pub fn new(max_message_size: usize, max_upload_size: usize) -> Self {
+        Self {
+            max_message_size,
+            max_upload_size,
+            next_id: 1,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// 处理分块上传消息
+    pub fn handle(&mut self, msg: &ModuleMessage) -> Result<UploadOutcome, UploadError> {
+        let mut payload = msg.payload.clone();
+        if let serde_json::Value::Object(fields) = &mut payload {
+            fields.insert("type".to_string(), serde_json::Value::String(msg.msg_type.clone()));
+        }
+        let request: UploadRequest = serde_json::from_value(payload)
+            .map_err(|e| UploadError::InvalidMessage(format!("{}: {}", msg.msg_type, e)))?;
+
+        match request {
+            UploadRequest::Begin { size } => {
+                if size.is_some_and(|size| size > self.max_upload_size) {
+                    return Err(UploadError::TooLarge(self.max_upload_size));
+                }
+                if self.pending.len() >= MAX_PENDING_UPLOADS {
+                    return Err(UploadError::TooMany(MAX_PENDING_UPLOADS));
+                }
+                let upload_id = format!("upload-{}", self.next_id);
+                self.next_id += 1;
+                self.pending.insert(upload_id.clone(), String::with_capacity(size.unwrap_or(0)));
+                let ready = UploadReady {
+                    upload_id,
+                    max_message_size: self.max_message_size,
+                    max_upload_size: self.max_upload_size,
+                };
+                Ok(UploadOutcome::Reply(reply("upload_ready", &ready)))
+            }
+            UploadRequest::Append { upload_id, data, offset } => {
+                let text = self.pending.get_mut(&upload_id).ok_or_else(|| UploadError::NotFound(upload_id.clone()))?;
+                if let Some(offset) = offset.filter(|offset| *offset != text.len()) {
+                    return Err(UploadError::OffsetMismatch { expected: text.len(), actual: offset });
+                }
+                if text.len() + data.len() > self.max_upload_size {
+                    // 超限的上传无法完成，直接丢弃
+                    self.pending.remove(&upload_id);
+                    return Err(UploadError::TooLarge(self.max_upload_size));
+                }
+                text.push_str(&data);
+                let received = text.len();
+                Ok(UploadOutcome::Reply(reply(
+                    "upload_appended",
+                    &serde_json::json!({ "upload_id": upload_id, "received": received }),
+                )))
+            }
+            UploadRequest::Commit { upload_id } => {
+                let text = self.pending.remove(&upload_id).ok_or(UploadError::NotFound(upload_id))?;
+                Ok(UploadOutcome::Commit(text))
+            }
+            UploadRequest::Abort { upload_id } => {
+                self.pending.remove(&upload_id).ok_or_else(|| UploadError::NotFound(upload_id.clone()))?;
+                Ok(UploadOutcome::Reply(reply(
+                    "upload_aborted",
+                    &serde_json::json!({ "upload_id": upload_id }),
+                )))
+            }
+        }
+    }
+}
+
+fn reply(msg_type: &str, payload: &impl Serialize) -> ServerResponse {
+    ServerResponse::new(ModuleType::System, msg_type, serde_json::to_value(payload).unwrap_or_default())
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(msg_type: &str, payload: serde_json::Value) -> ModuleMessage {
+        ModuleMessage {
+            module: ModuleType::System,
+            msg_type: msg_type.to_string(),
+            payload,
+            connection: None,
+        }
+    }
+
+    fn reply_of(outcome: UploadOutcome) -> ServerResponse {
+        match outcome {
+            UploadOutcome::Reply(response) => response,
+            UploadOutcome::Commit(text) => panic!("unexpected commit: {}", text),
+        }
+    }
+
+    #[test]
+    fn test_upload() {
+        let mut uploads = Uploads::new(16, 64);
+        let full = r#"{"module":"system","type":"describe"}"#;
+
+        let ready = reply_of(uploads.handle(&message("upload_begin", serde_json::json!({ "size": full.len() }))).unwrap());
+        assert_eq!(ready.msg_type, "upload_ready");
+        assert_eq!(ready.payload["max_upload_size"], 64);
+        let upload_id = ready.payload["upload_id"].as_str().unwrap().to_string();
+
+        for (offset, chunk) in [(0, &full[..20]), (20, &full[20..])] {
+            let appended = reply_of(uploads.handle(&message("upload_append", serde_json::json!({
+                "upload_id": upload_id, "offset": offset, "data": chunk,
+            }))).unwrap());
+            assert_eq!(appended.payload["received"], offset + chunk.len());
+        }
+
+        // 重复发送的片段被拒绝
+        let error = uploads.handle(&message("upload_append", serde_json::json!({
+            "upload_id": upload_id, "offset": 0, "data": "x",
+        }))).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidParams);
+
+        match uploads.handle(&message("upload_commit", serde_json::json!({ "upload_id": upload_id }))).unwrap() {
+            UploadOutcome::Commit(text) => assert_eq!(text, full),
+            UploadOutcome::Reply(response) => panic!("unexpected reply: {:?}", response),
+        }
+        let error = uploads.handle(&message("upload_commit", serde_json::json!({ "upload_id": upload_id }))).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::NotFound);
+    }
+
+    #[test]
+    fn test_upload_limits() {
+        let mut uploads = Uploads::new(16, 8);
+        let error = uploads.handle(&message("upload_begin", serde_json::json!({ "size": 9 }))).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::LimitExceeded);
+
+        // 超出总大小的上传被丢弃
+        let ready = reply_of(uploads.handle(&message("upload_begin", serde_json::json!({}))).unwrap());
+        let upload_id = ready.payload["upload_id"].as_str().unwrap().to_string();
+        let append = |data: &str| message("upload_append", serde_json::json!({ "upload_id": upload_id, "data": data }));
+        assert!(uploads.handle(&append("12345")).is_ok());
+        assert_eq!(uploads.handle(&append("6789")).unwrap_err().code(), ErrorCode::LimitExceeded);
+        assert_eq!(uploads.handle(&append("6")).unwrap_err().code(), ErrorCode::NotFound);
+
+        for _ in 0..MAX_PENDING_UPLOADS {
+            assert!(uploads.handle(&message("upload_begin", serde_json::json!({}))).is_ok());
+        }
+        let error = uploads.handle(&message("upload_begin", serde_json::json!({}))).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::LimitExceeded);
+
+        let aborted = reply_of(uploads.handle(&message("upload_abort", serde_json::json!({ "upload_id": "upload-2" }))).unwrap());
+        assert_eq!(aborted.msg_type, "upload_aborted");
+        assert!(uploads.handle(&message("upload_begin", serde_json::json!({}))).is_ok());
+
+        let error = uploads.handle(&message("upload_append", serde_json::json!({ "upload_id": "upload-3" }))).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidParams);
+    }
+}
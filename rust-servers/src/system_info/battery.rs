@@ -0,0 +1,214 @@
+// 电池状态
+// Linux 读取 /sys/class/power_supply，Windows 调用 GetSystemPowerStatus，macOS 解析 `pmset -g batt` 的输出。
+// 台式机等没有电池的设备返回 None
+
+use serde::Serialize;
+
+/// 充电状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChargeState {
+    Charging,
+    Discharging,
+    Full,
+    /// 接通电源但未充电 (如达到充电上限)
+    NotCharging,
+    Unknown,
+}
+
+/// 电池状态
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Battery {
+    /// 剩余电量 (0-100)
+    pub percent: u8,
+    pub state: ChargeState,
+    /// 是否接通外部电源
+    pub on_ac: bool,
+    /// 预计剩余使用时间 (秒，放电时且系统能估算时提供)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_remaining_secs: Option<u64>,
+}
+
+/// 读取电池状态 (没有电池或无法读取时返回 None)
+#[cfg(target_os = "linux")]
+pub fn read() -> Option<Battery> {
+    read_sysfs(std::path::Path::new("/sys/class/power_supply"))
+}
+
+/// 从 power_supply 目录读取第一块电池 (多块电池时按名称排序)
+#[cfg(any(target_os = "linux", test))]
+fn read_sysfs(root: &std::path::Path) -> Option<Battery> {
+    let read = |path: std::path::PathBuf| std::fs::read_to_string(path).ok().map(|value| value.trim().to_string());
+    let mut supplies: Vec<std::path::PathBuf> = std::fs::read_dir(root).ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    supplies.sort();
+
+    // 任一外部电源在线即为接通电源
+    let on_ac = supplies.iter()
+        .filter(|path| read(path.join("type")).as_deref() == Some("Mains"))
+        .any(|path| read(path.join("online")).as_deref() == Some("1"));
+    let battery = supplies.iter().find(|path| {
+        read(path.join("type")).as_deref() == Some("Battery") && read(path.join("present")).as_deref() != Some("0")
+    })?;
+
+    let percent: u8 = read(battery.join("capacity"))?.parse().ok()?;
+    let state = match read(battery.join("status")).as_deref() {
+        Some("Charging") => ChargeState::Charging,
+        Some("Discharging") => ChargeState::Discharging,
+        Some("Full") => ChargeState::Full,
+        Some("Not charging") => ChargeState::NotCharging,
+        _ => ChargeState::Unknown,
+    };
+    // 剩余时间 = 剩余电量 / 当前功耗 (能量以 µWh / µW 或电荷以 µAh / µA 报告)
+    let number = |name: &str| read(battery.join(name)).and_then(|value| value.parse::<u64>().ok());
+    let time_remaining_secs = if state == ChargeState::Discharging {
+        let remaining = number("energy_now").zip(number("power_now"))
+            .or_else(|| number("charge_now").zip(number("current_now")));
+        remaining.filter(|(_, rate)| *rate > 0).map(|(now, rate)| now * 3600 / rate)
+    } else {
+        None
+    };
+    Some(Battery {
+        percent: percent.min(100),
+        state,
+        on_ac: on_ac || matches!(state, ChargeState::Charging | ChargeState::Full | ChargeState::NotCharging),
+        time_remaining_secs,
+    })
+}
+
+/// 读取电池状态 (没有电池或无法读取时返回 None)
+#[cfg(windows)]
+pub fn read() -> Option<Battery> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    // BatteryFlag: 128 = 没有电池，255 = 未知；BatteryLifePercent 255 = 未知
+    if status.BatteryFlag & 128 != 0 || status.BatteryFlag == 255 || status.BatteryLifePercent > 100 {
+        return None;
+    }
+    let on_ac = status.ACLineStatus == 1;
+    let state = if status.BatteryFlag & 8 != 0 {
+        ChargeState::Charging
+    } else if !on_ac {
+        ChargeState::Discharging
+    } else if status.BatteryLifePercent == 100 {
+        ChargeState::Full
+    } else {
+        ChargeState::NotCharging
+    };
+    Some(Battery {
+        percent: status.BatteryLifePercent,
+        state,
+        on_ac,
+        // BatteryLifeTime 为 u32::MAX 时表示未知
+        time_remaining_secs: (state == ChargeState::Discharging && status.BatteryLifeTime != u32::MAX)
+            .then_some(status.BatteryLifeTime as u64),
+    })
+}
+
+/// 读取电池状态 (没有电池或无法读取时返回 None)
+#[cfg(target_os = "macos")]
+pub fn read() -> Option<Battery> {
+    let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_pmset(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// 解析 `pmset -g batt` 的输出
+///
+/// ```text
+/// Now drawing from 'Battery Power'
+///  -InternalBattery-0 (id=1234567)    85%; discharging; 4:12 remaining present: true
+/// ```
+#[cfg(any(target_os = "macos", test))]
+fn parse_pmset(output: &str) -> Option<Battery> {
+    let on_ac = output.lines().next()?.contains("AC Power");
+    let line = output.lines().find(|line| line.contains("InternalBattery"))?;
+    let fields: Vec<&str> = line.split('\t').nth(1)?.split(';').map(str::trim).collect();
+    let percent: u8 = fields.first()?.strip_suffix('%')?.parse().ok()?;
+    let state = match fields.get(1).copied() {
+        Some("charging") => ChargeState::Charging,
+        Some("discharging") => ChargeState::Discharging,
+        Some("charged") => ChargeState::Full,
+        Some("AC attached") | Some("finishing charge") => ChargeState::NotCharging,
+        _ => ChargeState::Unknown,
+    };
+    let time_remaining_secs = fields.get(2)
+        .filter(|_| state == ChargeState::Discharging)
+        .and_then(|field| field.split_whitespace().next())
+        .and_then(|time| time.split_once(':'))
+        .and_then(|(hours, minutes)| Some(hours.parse::<u64>().ok()? * 3600 + minutes.parse::<u64>().ok()? * 60));
+    Some(Battery { percent: percent.min(100), state, on_ac, time_remaining_secs })
+}
+
+/// 读取电池状态 (不支持的平台)
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn read() -> Option<Battery> {
+    None
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_read_sysfs() {
+        let root = std::env::temp_dir().join(format!("battery-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let write = |supply: &str, files: &[(&str, &str)]| {
+            fs::create_dir_all(root.join(supply)).unwrap();
+            for (name, value) in files {
+                fs::write(root.join(supply).join(name), format!("{}\n", value)).unwrap();
+            }
+        };
+        // 没有电池的台式机
+        write("AC", &[("type", "Mains"), ("online", "1")]);
+        assert_eq!(read_sysfs(&root), None);
+
+        write("AC", &[("type", "Mains"), ("online", "0")]);
+        write("BAT0", &[
+            ("type", "Battery"), ("present", "1"), ("capacity", "15"), ("status", "Discharging"),
+            ("energy_now", "6000000"), ("power_now", "12000000"),
+        ]);
+        assert_eq!(read_sysfs(&root), Some(Battery {
+            percent: 15,
+            state: ChargeState::Discharging,
+            on_ac: false,
+            time_remaining_secs: Some(1800),
+        }));
+
+        write("AC", &[("online", "1")]);
+        write("BAT0", &[("status", "Charging"), ("capacity", "16")]);
+        let battery = read_sysfs(&root).unwrap();
+        assert_eq!((battery.state, battery.on_ac, battery.time_remaining_secs), (ChargeState::Charging, true, None));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_parse_pmset() {
+        let output = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t85%; discharging; 4:12 remaining present: true\n";
+        assert_eq!(parse_pmset(output), Some(Battery {
+            percent: 85,
+            state: ChargeState::Discharging,
+            on_ac: false,
+            time_remaining_secs: Some(4 * 3600 + 12 * 60),
+        }));
+        let output = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=4653155)\t100%; charged; 0:00 remaining present: true\n";
+        let battery = parse_pmset(output).unwrap();
+        assert_eq!((battery.state, battery.on_ac, battery.time_remaining_secs), (ChargeState::Full, true, None));
+        // 没有电池的 Mac
+        assert_eq!(parse_pmset("Now drawing from 'AC Power'\n"), None);
+    }
+}
@@ -0,0 +1,200 @@
+// 系统信息模块
+// 报告 CPU 负载、内存、数据目录和 vault 所在磁盘的剩余空间、电池状态和端点可达性，
+// 并按阈值给出警告，供插件在笔记本电量不足时启动本地推理等重负载任务前提醒用户
+
+mod battery;
+mod probe;
+
+pub use battery::{Battery, ChargeState};
+pub use probe::{CpuInfo, DiskInfo, EndpointInfo, MemoryInfo, Warning};
+
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// 日志宏
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "SystemInfo", format!($($arg)*));
+        }
+    };
+}
+
+/// 信息分类 (sections 过滤使用)
+const SECTIONS: &[&str] = &["cpu", "memory", "disk", "battery", "network"];
+
+/// 一次请求最多探测的端点数
+const MAX_ENDPOINTS: usize = 16;
+
+/// 创建系统信息模块错误
+fn system_info_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::SystemInfo, code, message)
+}
+
+// ============================================================================
+// 请求和结果
+// ============================================================================
+
+/// get 请求
+#[derive(Debug, Default, Deserialize)]
+struct GetRequest {
+    /// 只返回指定的分类 (省略时全部返回)
+    #[serde(default)]
+    sections: Option<Vec<String>>,
+    /// 除数据目录外还要统计磁盘空间的路径 (名称 -> 路径，如 vault)
+    #[serde(default)]
+    paths: BTreeMap<String, String>,
+    /// 需要检查可达性的端点 (名称 -> URL，如 ASR / LLM 端点)
+    #[serde(default)]
+    endpoints: BTreeMap<String, String>,
+}
+
+impl GetRequest {
+    fn wants(&self, section: &str) -> bool {
+        self.sections.as_ref().is_none_or(|sections| sections.iter().any(|s| s == section))
+    }
+}
+
+/// info 响应
+#[derive(Debug, Clone, Serialize)]
+struct Report {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu: Option<CpuInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<MemoryInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disks: Option<Vec<DiskInfo>>,
+    /// 请求了 battery 时总是包含，没有电池时为 null
+    #[serde(skip_serializing_if = "Option::is_none")]
+    battery: Option<Option<Battery>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    endpoints: Option<Vec<EndpointInfo>>,
+    warnings: Vec<Warning>,
+}
+
+/// 采集请求的各项信息 (CPU、内存、磁盘和电池在阻塞线程中读取，同时探测端点)
+async fn collect(request: GetRequest) -> Result<Report, ModuleError> {
+    let (want_cpu, want_memory) = (request.wants("cpu"), request.wants("memory"));
+    let want_disk = request.wants("disk");
+    let want_battery = request.wants("battery");
+    let want_network = request.wants("network");
+    let GetRequest { paths: extra_paths, endpoints, .. } = request;
+    let mut paths: Vec<(String, PathBuf)> = Vec::new();
+    if want_disk {
+        if let Some(dirs) = crate::storage::data_dirs() {
+            paths.push(("data_dir".to_string(), dirs.root().to_path_buf()));
+        }
+        paths.extend(extra_paths.into_iter().map(|(name, path)| (name, PathBuf::from(path))));
+    }
+
+    let local = tokio::task::spawn_blocking(move || {
+        let (cpu, memory) = if want_cpu || want_memory {
+            let (cpu, memory) = probe::sample_cpu_memory();
+            (want_cpu.then_some(cpu), want_memory.then_some(memory))
+        } else {
+            (None, None)
+        };
+        let disks = want_disk.then(|| paths.into_iter().map(|(name, path)| probe::disk(name, &path)).collect());
+        let battery = want_battery.then(battery::read);
+        (cpu, memory, disks, battery)
+    });
+    let endpoints = async {
+        if want_network {
+            let probes = endpoints.into_iter().map(|(name, url)| probe::probe(name, url));
+            Some(futures_util::future::join_all(probes).await)
+        } else {
+            None
+        }
+    };
+    let (local, endpoints) = tokio::join!(local, endpoints);
+    let (cpu, memory, disks, battery) = local
+        .map_err(|e| system_info_error(ErrorCode::Internal, format!("系统信息采集失败: {}", e)))?;
+
+    let warnings = probe::warnings(
+        cpu.as_ref(),
+        memory.as_ref(),
+        disks.as_deref().unwrap_or_default(),
+        battery.as_ref().and_then(Option::as_ref),
+        endpoints.as_deref().unwrap_or_default(),
+    );
+    Ok(Report { cpu, memory, disks, battery, endpoints, warnings })
+}
+
+// ============================================================================
+// 系统信息处理器
+// ============================================================================
+
+/// 系统信息模块处理器
+pub struct SystemInfoHandler;
+
+impl SystemInfoHandler {
+    /// 创建新的系统信息处理器
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 处理 get 消息 - 采集系统信息
+    async fn handle_get(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: GetRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| system_info_error(ErrorCode::InvalidParams, format!("无效的 get 请求: {}", e)))?;
+        if let Some(unknown) = request.sections.iter().flatten().find(|s| !SECTIONS.contains(&s.as_str())) {
+            return Err(system_info_error(
+                ErrorCode::InvalidParams,
+                format!("未知的信息分类: {} (可用: {})", unknown, SECTIONS.join(", ")),
+            ).into());
+        }
+        if request.endpoints.len() > MAX_ENDPOINTS {
+            return Err(system_info_error(ErrorCode::LimitExceeded, format!("端点过多 (最多 {} 个)", MAX_ENDPOINTS)).into());
+        }
+        let report = collect(request).await?;
+        log_debug!("系统信息: {} 条警告", report.warnings.len());
+        let payload = serde_json::to_value(&report).unwrap_or_default();
+        Ok(Some(ServerResponse::new(ModuleType::SystemInfo, "info", payload)))
+    }
+}
+
+impl Default for SystemInfoHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 系统信息模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("get", &[
+        FieldSpec::optional("sections", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("paths", FieldKind::Object),
+        FieldSpec::optional("endpoints", FieldKind::Object),
+    ]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 系统信息模块
+    pub const SystemInfo: ModuleType = ModuleType::named("system_info");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for SystemInfoHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::SystemInfo
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理系统信息消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "get" => self.handle_get(msg).await,
+            _ => Err(system_info_error(ErrorCode::UnknownMessageType, format!("未知的系统信息消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+// 资源采样
+// CPU 和内存通过 sysinfo 读取，磁盘空间按路径所在的文件系统统计，网络可达性对客户端给出的端点发送 HEAD 请求。
+// 采样结果按阈值生成警告，供插件在启动本地推理等重负载任务前提示用户
+
+use serde::Serialize;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::battery::Battery;
+use crate::network::NetworkError;
+use crate::router::ModuleType;
+use crate::storage;
+
+/// 单个端点探测的超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 电量低于此值且未接通电源时警告 (百分比)
+const LOW_BATTERY_PERCENT: u8 = 20;
+
+/// CPU 使用率高于此值时警告 (百分比)
+const HIGH_CPU_PERCENT: f32 = 90.0;
+
+/// 可用内存低于此值时警告 (字节)
+const LOW_MEMORY: u64 = 1024 * 1024 * 1024;
+
+/// 可用磁盘空间低于此值时警告 (字节)
+const LOW_DISK_SPACE: u64 = 1024 * 1024 * 1024;
+
+/// CPU 状态
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuInfo {
+    /// 逻辑核心数
+    pub cores: usize,
+    /// 物理核心数 (无法获取时省略)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub physical_cores: Option<usize>,
+    /// 所有核心的平均使用率 (0-100)
+    pub usage_percent: f32,
+    /// 1、5、15 分钟平均负载 (Windows 上没有)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_average: Option<[f64; 3]>,
+}
+
+/// 内存状态 (字节)
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryInfo {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_used_bytes: u64,
+}
+
+/// 磁盘空间
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskInfo {
+    /// 路径的来源 (data_dir 或请求中 paths 的键，如 vault)
+    pub name: String,
+    pub path: String,
+    #[serde(flatten)]
+    pub space: Option<storage::DiskSpace>,
+    /// 无法获取空间时的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 端点可达性
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointInfo {
+    pub name: String,
+    pub url: String,
+    /// 收到任何 HTTP 响应即为可达
+    pub reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 资源警告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Warning {
+    /// low_battery / high_cpu / low_memory / low_disk / unreachable
+    pub kind: &'static str,
+    pub message: String,
+}
+
+/// 采样 CPU 和内存 (阻塞约 200 毫秒以计算 CPU 使用率，应在阻塞线程中调用)
+pub fn sample_cpu_memory() -> (CpuInfo, MemoryInfo) {
+    let mut system = sysinfo::System::new();
+    system.refresh_cpu_usage();
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_cpu_usage();
+    system.refresh_memory();
+
+    let load = sysinfo::System::load_average();
+    let cpu = CpuInfo {
+        cores: system.cpus().len(),
+        physical_cores: sysinfo::System::physical_core_count(),
+        usage_percent: (system.global_cpu_usage() * 10.0).round() / 10.0,
+        load_average: (!cfg!(windows)).then_some([load.one, load.five, load.fifteen]),
+    };
+    let memory = MemoryInfo {
+        total_bytes: system.total_memory(),
+        available_bytes: system.available_memory(),
+        swap_total_bytes: system.total_swap(),
+        swap_used_bytes: system.used_swap(),
+    };
+    (cpu, memory)
+}
+
+/// 统计路径所在磁盘的空间 (路径尚未创建时按最近的已存在上级目录计算)
+pub fn disk(name: String, path: &Path) -> DiskInfo {
+    let (space, error) = match storage::disk_space(path) {
+        Ok(space) => (Some(space), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    DiskInfo { name, path: path.display().to_string(), space, error }
+}
+
+/// 探测端点能否连通 (不校验认证，WebSocket 端点改用对应的 HTTP 地址)
+pub async fn probe(name: String, url: String) -> EndpointInfo {
+    let started = Instant::now();
+    let request = crate::network::client().head(crate::system::diagnose::probe_url(&url)).timeout(PROBE_TIMEOUT);
+    let (reachable, http_status, error) = match crate::network::send(ModuleType::SystemInfo, request).await {
+        Ok(response) => (true, Some(response.status().as_u16()), None),
+        Err(NetworkError::Http(e)) if e.is_builder() => (false, None, Some(format!("URL 无效: {}", e))),
+        Err(e) => (false, None, Some(e.to_string())),
+    };
+    EndpointInfo {
+        name,
+        url,
+        reachable,
+        http_status,
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
+/// 按阈值生成警告
+pub fn warnings(
+    cpu: Option<&CpuInfo>,
+    memory: Option<&MemoryInfo>,
+    disks: &[DiskInfo],
+    battery: Option<&Battery>,
+    endpoints: &[EndpointInfo],
+) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let mut warn = |kind: &'static str, message: String| warnings.push(Warning { kind, message });
+    if let Some(battery) = battery.filter(|battery| !battery.on_ac && battery.percent < LOW_BATTERY_PERCENT) {
+        warn("low_battery", format!("电量不足: {}%，未接通电源", battery.percent));
+    }
+    if let Some(cpu) = cpu.filter(|cpu| cpu.usage_percent > HIGH_CPU_PERCENT) {
+        warn("high_cpu", format!("CPU 使用率过高: {:.0}%", cpu.usage_percent));
+    }
+    if let Some(memory) = memory.filter(|memory| memory.available_bytes < LOW_MEMORY) {
+        warn("low_memory", format!("可用内存不足: {} MiB", memory.available_bytes / (1024 * 1024)));
+    }
+    for disk in disks {
+        if let Some(space) = disk.space.filter(|space| space.available_bytes < LOW_DISK_SPACE) {
+            warn("low_disk", format!("{} 所在磁盘空间不足: 剩余 {} MiB", disk.path, space.available_bytes / (1024 * 1024)));
+        }
+    }
+    for endpoint in endpoints.iter().filter(|endpoint| !endpoint.reachable) {
+        warn("unreachable", format!("端点不可达: {}", endpoint.name));
+    }
+    warnings
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system_info::battery::ChargeState;
+
+    #[test]
+    fn test_warnings() {
+        let cpu = CpuInfo { cores: 8, physical_cores: Some(4), usage_percent: 95.5, load_average: None };
+        let memory = MemoryInfo { total_bytes: 16 << 30, available_bytes: 8 << 30, swap_total_bytes: 0, swap_used_bytes: 0 };
+        let disks = vec![
+            DiskInfo { name: "data_dir".to_string(), path: "/data".to_string(), space: Some(storage::DiskSpace { available_bytes: 10 << 20, total_bytes: 1 << 40 }), error: None },
+            DiskInfo { name: "vault".to_string(), path: "/missing".to_string(), space: None, error: Some("not found".to_string()) },
+        ];
+        let battery = Battery { percent: 12, state: ChargeState::Discharging, on_ac: false, time_remaining_secs: None };
+        let kinds: Vec<&str> = warnings(Some(&cpu), Some(&memory), &disks, Some(&battery), &[])
+            .into_iter()
+            .map(|warning| warning.kind)
+            .collect();
+        assert_eq!(kinds, vec!["low_battery", "high_cpu", "low_disk"]);
+
+        // 接通电源时电量低不警告
+        let charging = Battery { on_ac: true, state: ChargeState::Charging, ..battery };
+        assert!(warnings(None, None, &[], Some(&charging), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_sample() {
+        let (cpu, memory) = sample_cpu_memory();
+        assert!(cpu.cores > 0);
+        assert!((0.0..=100.0).contains(&cpu.usage_percent));
+        assert!(memory.total_bytes >= memory.available_bytes);
+
+        let disk = disk("tmp".to_string(), &std::env::temp_dir().join("system-info-missing").join("child"));
+        assert!(disk.space.is_some_and(|space| space.total_bytes > 0), "{:?}", disk.error);
+    }
+}
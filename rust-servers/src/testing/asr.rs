@@ -0,0 +1,221 @@
+// 模拟 ASR 引擎
+// 注册后通过 API Key `mock:<名称>` 选用 (供应商和模式不限)，按预设返回转写文本或失败，
+// 用于覆盖主备引擎的兜底路径而不访问真实的 ASR 服务
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::voice::asr::{ASREngine, ASRError, ASRMode, PartialResultCallback, RealtimeSession};
+use crate::voice::audio::AudioData;
+use crate::voice::config::ASRProviderConfig;
+
+/// 模拟引擎 API Key 的前缀
+pub const MOCK_KEY_PREFIX: &str = "mock:";
+
+/// 模拟 ASR 引擎 (克隆后共享调用计数)
+#[derive(Debug, Clone)]
+pub struct MockAsrEngine {
+    name: String,
+    reply: Result<String, String>,
+    delay: Duration,
+    calls: Arc<AtomicUsize>,
+    /// 实时会话依次推送的部分结果 (None 为仅支持 HTTP 模式)
+    partials: Option<Vec<String>>,
+    /// 实时会话的保活间隔
+    keep_alive: Option<Duration>,
+    /// 实时会话收到的保活帧 (全零的音频块) 数
+    keep_alives: Arc<AtomicUsize>,
+}
+
+impl MockAsrEngine {
+    /// 创建总是返回 `text` 的引擎
+    pub fn replying(name: &str, text: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            reply: Ok(text.to_string()),
+            delay: Duration::ZERO,
+            calls: Arc::new(AtomicUsize::new(0)),
+            partials: None,
+            keep_alive: None,
+            keep_alives: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// 创建实时引擎：每收到一个音频块推送下一条部分结果 (用完后重复最后一条)，关闭会话时返回 `text`
+    pub fn realtime(name: &str, partials: &[&str], text: &str) -> Self {
+        Self {
+            partials: Some(partials.iter().map(|p| p.to_string()).collect()),
+            ..Self::replying(name, text)
+        }
+    }
+
+    /// 创建总是失败 (网络错误) 的引擎
+    pub fn failing(name: &str, message: &str) -> Self {
+        Self {
+            reply: Err(message.to_string()),
+            ..Self::replying(name, "")
+        }
+    }
+
+    /// 每次转写前等待
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// 实时会话停顿 `interval` 后需要保活
+    pub fn with_keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    /// 注册到全局表，返回选用该引擎的供应商配置 (可直接放入 asr_config)
+    pub fn register(&self) -> serde_json::Value {
+        registry().lock().unwrap_or_else(|e| e.into_inner()).insert(self.name.clone(), self.clone());
+        serde_json::json!({
+            "provider": "qwen",
+            "mode": if self.partials.is_some() { "realtime" } else { "http" },
+            "dashscope_api_key": format!("{}{}", MOCK_KEY_PREFIX, self.name),
+        })
+    }
+
+    /// 已执行的转写次数
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// 实时会话收到的保活帧数
+    pub fn keep_alives(&self) -> usize {
+        self.keep_alives.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl ASREngine for MockAsrEngine {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supported_modes(&self) -> Vec<ASRMode> {
+        match self.partials {
+            Some(_) => vec![ASRMode::Http, ASRMode::Realtime],
+            None => vec![ASRMode::Http],
+        }
+    }
+
+    async fn transcribe(&self, _audio: &AudioData) -> Result<String, ASRError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        self.reply.clone().map_err(ASRError::NetworkError)
+    }
+
+    async fn create_realtime_session(&self) -> Result<Box<dyn RealtimeSession>, ASRError> {
+        let Some(partials) = self.partials.clone() else {
+            return Err(ASRError::UnsupportedOperation("模拟引擎不支持实时模式".to_string()));
+        };
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(Box::new(MockRealtimeSession {
+            partials,
+            chunks: 0,
+            reply: self.reply.clone(),
+            callback: None,
+            keep_alive: self.keep_alive,
+            keep_alives: Arc::clone(&self.keep_alives),
+        }))
+    }
+}
+
+/// 模拟实时会话
+struct MockRealtimeSession {
+    partials: Vec<String>,
+    chunks: usize,
+    reply: Result<String, String>,
+    callback: Option<PartialResultCallback>,
+    keep_alive: Option<Duration>,
+    keep_alives: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl RealtimeSession for MockRealtimeSession {
+    async fn send_chunk(&mut self, chunk: &[u8]) -> Result<(), ASRError> {
+        self.reply.as_ref().map_err(|e| ASRError::WebSocketError(e.clone()))?;
+        if chunk.iter().all(|&byte| byte == 0) {
+            self.keep_alives.fetch_add(1, Ordering::SeqCst);
+            return Ok(());
+        }
+        let partial = self.partials.get(self.chunks).or(self.partials.last());
+        if let (Some(partial), Some(callback)) = (partial, &self.callback) {
+            callback(partial);
+        }
+        self.chunks += 1;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<String, ASRError> {
+        self.reply.clone().map_err(ASRError::WebSocketError)
+    }
+
+    fn keep_alive_interval(&self) -> Option<Duration> {
+        self.keep_alive
+    }
+
+    fn set_partial_callback(&mut self, callback: Box<dyn Fn(&str) + Send + 'static>) {
+        self.callback = Some(callback);
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<String, MockAsrEngine>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, MockAsrEngine>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 按供应商配置中的 `mock:<名称>` API Key 查找模拟引擎 (不是模拟配置时返回 None)
+pub fn engine_for(config: &ASRProviderConfig) -> Option<Result<Box<dyn ASREngine>, ASRError>> {
+    let name = [&config.dashscope_api_key, &config.access_token, &config.siliconflow_api_key, &config.api_key]
+        .into_iter()
+        .flatten()
+        .find_map(|key| key.strip_prefix(MOCK_KEY_PREFIX))?;
+    let engine = registry().lock().unwrap_or_else(|e| e.into_inner()).get(name).cloned();
+    Some(match engine {
+        Some(engine) => Ok(Box::new(engine)),
+        None => Err(ASRError::ConfigError(format!("未注册的模拟引擎: {}", name))),
+    })
+}
+
+/// 生成指定时长的静音 WAV (16 kHz 单声道)，用于 voice/transcribe 的 audio 字段
+pub fn silent_wav(duration_ms: u32) -> Vec<u8> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 16000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut buffer, spec).expect("create wav writer");
+    for _ in 0..spec.sample_rate * duration_ms / 1000 {
+        writer.write_sample(0i16).expect("write wav sample");
+    }
+    writer.finalize().expect("finalize wav");
+    buffer.into_inner()
+}
+
+/// 生成指定时长的正弦波 WAV (16 kHz 单声道)，可以通过语音活动检测
+pub fn tone_wav(duration_ms: u32) -> Vec<u8> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 16000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut buffer, spec).expect("create wav writer");
+    for i in 0..spec.sample_rate * duration_ms / 1000 {
+        let sample = (i as f32 * 0.1).sin() * 0.3;
+        writer.write_sample((sample * i16::MAX as f32) as i16).expect("write wav sample");
+    }
+    writer.finalize().expect("finalize wav");
+    buffer.into_inner()
+}
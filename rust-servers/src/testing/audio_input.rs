@@ -0,0 +1,55 @@
+// 模拟音频输入
+// 调用 install 后会议模块改用模拟输入 (测试环境通常没有声卡)：每个来源按真实时间产生正弦波样本，
+// 采集停止 (InputCapture 丢弃) 后线程退出
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use crate::meeting::{InputBackend, InputCapture, SourceBuffer, SourceSpec};
+use crate::voice::audio::RecordingError;
+
+/// 是否已安装模拟输入
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// 模拟输入的采样率
+pub const SAMPLE_RATE: u32 = 16000;
+
+/// 产生样本的间隔
+const TICK: Duration = Duration::from_millis(50);
+
+/// 安装模拟输入 (此后的采集都使用模拟输入)
+pub fn install() {
+    INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// 是否已安装模拟输入
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::SeqCst)
+}
+
+/// 模拟输入
+pub struct FakeInput;
+
+impl InputBackend for FakeInput {
+    fn open(&self, sources: &[SourceSpec]) -> Result<InputCapture, RecordingError> {
+        if let Some(device) = sources.iter().find_map(|source| source.device.as_deref()) {
+            return Err(RecordingError::MicrophoneUnavailable(format!("未找到设备: {}", device)));
+        }
+        let buffers: Vec<SourceBuffer> = sources.iter().map(|source| SourceBuffer::new(source.kind, SAMPLE_RATE)).collect();
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let producers = buffers.clone();
+        std::thread::spawn(move || {
+            let samples_per_tick = (SAMPLE_RATE as u128 * TICK.as_millis() / 1000) as usize;
+            let mut phase = 0usize;
+            while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(TICK) {
+                let tone: Vec<f32> = (phase..phase + samples_per_tick).map(|i| (i as f32 * 0.1).sin() * 0.3).collect();
+                phase += samples_per_tick;
+                for buffer in &producers {
+                    buffer.push(&tone);
+                }
+            }
+        });
+        Ok(InputCapture::new(buffers, stop_tx))
+    }
+}
@@ -0,0 +1,70 @@
+// 模拟屏幕
+// 调用 install 后截图模块改用进程内的模拟屏幕 (测试环境通常没有图形界面)。
+// 模拟屏幕有两台显示器和一个窗口，显示器上每个像素的 R / G 为其虚拟桌面坐标、B 为显示器 id，
+// 据此可以检查区域裁剪是否正确
+
+use image::{Rgba, RgbaImage};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::capture::{CaptureBackend, CaptureError, MonitorInfo, WindowInfo};
+
+/// 是否已安装模拟屏幕
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// 窗口的颜色
+pub const WINDOW_COLOR: [u8; 4] = [200, 100, 50, 255];
+
+/// 安装模拟屏幕 (此后打开的截图后端都是模拟屏幕)
+pub fn install() {
+    INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// 是否已安装模拟屏幕
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::SeqCst)
+}
+
+/// 显示器 1 (64x48，主显示器) 和右侧的显示器 2 (32x24，缩放比例 2)
+fn monitors() -> Vec<(MonitorInfo, u32)> {
+    vec![
+        (MonitorInfo { id: 1, name: "FAKE-1".to_string(), x: 0, y: 0, width: 64, height: 48, primary: true }, 1),
+        (MonitorInfo { id: 2, name: "FAKE-2".to_string(), x: 64, y: 0, width: 32, height: 24, primary: false }, 2),
+    ]
+}
+
+/// 模拟屏幕
+pub struct FakeScreen;
+
+impl CaptureBackend for FakeScreen {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, CaptureError> {
+        Ok(monitors().into_iter().map(|(monitor, _)| monitor).collect())
+    }
+
+    fn windows(&self) -> Result<Vec<WindowInfo>, CaptureError> {
+        Ok(vec![WindowInfo {
+            id: 100,
+            title: "Daily note - Obsidian".to_string(),
+            app_name: "obsidian".to_string(),
+            x: 8,
+            y: 8,
+            width: 16,
+            height: 12,
+        }])
+    }
+
+    fn capture_monitor(&self, id: u32) -> Result<RgbaImage, CaptureError> {
+        let (monitor, scale) = monitors().into_iter().find(|(monitor, _)| monitor.id == id)
+            .ok_or(CaptureError::MonitorNotFound(id))?;
+        Ok(RgbaImage::from_fn(monitor.width * scale, monitor.height * scale, |x, y| {
+            let global_x = monitor.x as u32 + x / scale;
+            let global_y = monitor.y as u32 + y / scale;
+            Rgba([global_x as u8, global_y as u8, monitor.id as u8, 255])
+        }))
+    }
+
+    fn capture_window(&self, id: u32) -> Result<RgbaImage, CaptureError> {
+        let window = self.windows()?.into_iter().find(|window| window.id == id)
+            .ok_or(CaptureError::WindowNotFound(id))?;
+        Ok(RgbaImage::from_pixel(window.width, window.height, Rgba(WINDOW_COLOR)))
+    }
+}
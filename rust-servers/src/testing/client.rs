@@ -0,0 +1,174 @@
+// 测试客户端
+// 通过真实的 WebSocket 连接收发消息，响应解析为 ServerResponse；
+// 等待某条消息时先收到的其他消息保留在队列中，后续仍可按顺序取出
+
+use futures_util::{SinkExt, StreamExt};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::router::{ModuleType, ServerResponse};
+
+/// 等待消息的默认超时
+pub const RECV_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// WebSocket 测试客户端
+///
+/// 等待超时或连接异常时直接 panic，便于在测试中使用
+pub struct TestClient {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    next_id: u64,
+    /// 尚未取出的文本消息
+    messages: VecDeque<ServerResponse>,
+    /// 尚未取出的二进制帧 (PTY 输出)
+    frames: VecDeque<Vec<u8>>,
+}
+
+impl TestClient {
+    /// 连接到服务器并通过查询参数认证
+    pub async fn connect(address: &str, token: &str) -> Result<Self, tokio_tungstenite::tungstenite::Error> {
+        let (stream, _) = tokio_tungstenite::connect_async(format!("ws://{}/?token={}", address, token)).await?;
+        Ok(Self {
+            stream,
+            next_id: 1,
+            messages: VecDeque::new(),
+            frames: VecDeque::new(),
+        })
+    }
+
+    /// 发送消息，返回 request_id (负载中没有时自动生成)
+    pub async fn send(&mut self, module: ModuleType, msg_type: &str, payload: serde_json::Value) -> String {
+        let request_id = format!("test-{}", self.next_id);
+        self.next_id += 1;
+        let mut message = match payload {
+            serde_json::Value::Object(fields) => fields,
+            serde_json::Value::Null => serde_json::Map::new(),
+            other => panic!("payload must be an object: {}", other),
+        };
+        message.insert("module".to_string(), serde_json::to_value(module).unwrap_or_default());
+        message.insert("type".to_string(), msg_type.into());
+        message.entry("request_id").or_insert_with(|| request_id.clone().into());
+        let request_id = message["request_id"].as_str().unwrap_or_default().to_string();
+        self.send_raw(Message::Text(serde_json::Value::Object(message).to_string().into())).await;
+        request_id
+    }
+
+    /// 发送消息并等待同一 request_id 的第一条响应 (可能是 error)
+    pub async fn request(&mut self, module: ModuleType, msg_type: &str, payload: serde_json::Value) -> ServerResponse {
+        let request_id = self.send(module, msg_type, payload).await;
+        self.recv_matching(|response| request_id_of(response) == Some(request_id.as_str())).await
+    }
+
+    /// 发送 PTY 输入 (二进制帧)
+    pub async fn send_pty_input(&mut self, session_id: &str, data: &[u8]) {
+        let mut frame = Vec::with_capacity(1 + session_id.len() + data.len());
+        frame.push(session_id.len() as u8);
+        frame.extend_from_slice(session_id.as_bytes());
+        frame.extend_from_slice(data);
+        self.send_raw(Message::Binary(frame.into())).await;
+    }
+
+    /// 发送原始帧
+    pub async fn send_raw(&mut self, message: Message) {
+        self.stream.send(message).await.expect("send message");
+    }
+
+    /// 取出下一条文本消息
+    pub async fn recv(&mut self) -> ServerResponse {
+        self.recv_matching(|_| true).await
+    }
+
+    /// 取出下一条指定模块和类型的消息
+    pub async fn expect(&mut self, module: ModuleType, msg_type: &str) -> ServerResponse {
+        self.recv_matching(|response| response.module == module && response.msg_type == msg_type).await
+    }
+
+    /// 取出下一条满足条件的消息，之前收到的其他消息留在队列中
+    pub async fn recv_matching(&mut self, matches: impl Fn(&ServerResponse) -> bool) -> ServerResponse {
+        let deadline = tokio::time::Instant::now() + RECV_TIMEOUT;
+        let mut checked = 0;
+        loop {
+            if let Some(index) = self.messages.iter().skip(checked).position(&matches) {
+                return self.messages.remove(checked + index).expect("queued message");
+            }
+            checked = self.messages.len();
+            self.receive_until(deadline).await;
+        }
+    }
+
+    /// 取出 PTY 输出，直到累计内容包含 `needle`，返回会话 ID 和累计内容
+    pub async fn recv_pty_output(&mut self, needle: &str) -> (String, String) {
+        let deadline = tokio::time::Instant::now() + RECV_TIMEOUT;
+        let mut output = String::new();
+        loop {
+            while let Some(frame) = self.frames.pop_front() {
+                let len = frame[0] as usize;
+                output.push_str(&String::from_utf8_lossy(&frame[1 + len..]));
+                if output.contains(needle) {
+                    return (String::from_utf8_lossy(&frame[1..1 + len]).into_owned(), output);
+                }
+            }
+            self.receive_until(deadline).await;
+        }
+    }
+
+    /// 确认在 `wait` 内没有收到满足条件的消息
+    pub async fn assert_no_message(&mut self, wait: Duration, matches: impl Fn(&ServerResponse) -> bool) {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            if let Some(message) = self.messages.iter().find(|message| matches(message)) {
+                panic!("unexpected message: {:?}", message);
+            }
+            match tokio::time::timeout_at(deadline, self.stream.next()).await {
+                Ok(Some(Ok(message))) => self.queue(message),
+                Ok(_) | Err(_) => return,
+            }
+        }
+    }
+
+    /// 关闭连接
+    pub async fn close(mut self) {
+        let _ = self.stream.close(None).await;
+    }
+
+    /// 接收一帧放入队列，超过截止时间时 panic
+    async fn receive_until(&mut self, deadline: tokio::time::Instant) {
+        match tokio::time::timeout_at(deadline, self.stream.next()).await {
+            Ok(Some(Ok(message))) => self.queue(message),
+            Ok(Some(Err(e))) => panic!("connection error: {}", e),
+            Ok(None) => panic!("connection closed, queued: {:?}", self.messages),
+            Err(_) => panic!("timed out waiting for message, queued: {:?}", self.messages),
+        }
+    }
+
+    fn queue(&mut self, message: Message) {
+        match message {
+            Message::Text(text) => {
+                let value: serde_json::Value = serde_json::from_str(text.as_str()).expect("server sent invalid JSON");
+                // 合并后的事件展开为单独的消息
+                if let Some(batch) = value.get("batch").and_then(|batch| batch.as_array()) {
+                    for item in batch {
+                        let mut item = item.clone();
+                        for key in ["module", "type", "request_id", "session_id"] {
+                            if let (Some(field), Some(fields)) = (value.get(key), item.as_object_mut()) {
+                                fields.entry(key).or_insert_with(|| field.clone());
+                            }
+                        }
+                        self.messages.push_back(serde_json::from_value(item).expect("invalid batched message"));
+                    }
+                } else {
+                    self.messages.push_back(serde_json::from_value(value).expect("invalid server message"));
+                }
+            }
+            Message::Binary(data) => self.frames.push_back(data.to_vec()),
+            _ => {}
+        }
+    }
+}
+
+/// 响应的 request_id
+pub fn request_id_of(response: &ServerResponse) -> Option<&str> {
+    response.payload.get("request_id").and_then(|id| id.as_str())
+}
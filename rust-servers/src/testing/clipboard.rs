@@ -0,0 +1,73 @@
+// 内存剪贴板
+// 调用 install 后剪贴板模块改用进程内的内存剪贴板 (测试环境通常没有图形界面)，
+// 测试可以直接修改其内容模拟其他程序复制
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::clipboard::{ClipboardBackend, ClipboardError, Image};
+
+/// 是否已安装内存剪贴板
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// 剪贴板内容 (同一时间只有一种格式)
+#[derive(Debug, Clone)]
+enum Content {
+    Text(String),
+    Image(Image),
+}
+
+static CONTENT: Mutex<Option<Content>> = Mutex::new(None);
+
+fn content() -> std::sync::MutexGuard<'static, Option<Content>> {
+    CONTENT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 安装内存剪贴板 (此后打开的剪贴板都是内存剪贴板)
+pub fn install() {
+    INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// 是否已安装内存剪贴板
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::SeqCst)
+}
+
+/// 模拟其他程序复制文本
+pub fn set_text(text: &str) {
+    *content() = Some(Content::Text(text.to_string()));
+}
+
+/// 当前的文本内容
+pub fn text() -> Option<String> {
+    match &*content() {
+        Some(Content::Text(text)) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+/// 内存剪贴板
+pub struct MemoryClipboard;
+
+impl ClipboardBackend for MemoryClipboard {
+    fn text(&mut self) -> Result<Option<String>, ClipboardError> {
+        Ok(text())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        set_text(text);
+        Ok(())
+    }
+
+    fn image(&mut self) -> Result<Option<Image>, ClipboardError> {
+        match &*content() {
+            Some(Content::Image(image)) => Ok(Some(image.clone())),
+            _ => Ok(None),
+        }
+    }
+
+    fn set_image(&mut self, image: &Image) -> Result<(), ClipboardError> {
+        *content() = Some(Content::Image(image.clone()));
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+// 模拟全局快捷键
+// 调用 install 后快捷键模块改用进程内的模拟后端 (测试环境通常没有图形界面)，
+// 测试通过 press / release 模拟用户按下和松开已注册的快捷键
+
+use global_hotkey::hotkey::HotKey;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::hotkeys::{HotkeyBackend, HotkeyError, HotkeyEvent};
+
+/// 是否已安装模拟快捷键
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// 已注册的快捷键: id → 事件通道
+static REGISTERED: Mutex<Option<HashMap<u32, UnboundedSender<HotkeyEvent>>>> = Mutex::new(None);
+
+/// 被其他程序占用的快捷键 id
+static OCCUPIED: Mutex<Option<HashSet<u32>>> = Mutex::new(None);
+
+fn parse(hotkey: &str) -> HotKey {
+    hotkey.parse().expect("valid hotkey")
+}
+
+/// 安装模拟快捷键 (此后打开的快捷键后端都是模拟后端)
+pub fn install() {
+    INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// 是否已安装模拟快捷键
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::SeqCst)
+}
+
+/// 模拟其他程序占用快捷键 (之后注册该快捷键失败)
+pub fn occupy(hotkey: &str) {
+    OCCUPIED.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert_with(HashSet::new).insert(parse(hotkey).id());
+}
+
+/// 发送事件，快捷键未注册时返回 false
+fn emit(hotkey: &str, pressed: bool) -> bool {
+    let id = parse(hotkey).id();
+    let registered = REGISTERED.lock().unwrap_or_else(|e| e.into_inner());
+    match registered.as_ref().and_then(|registered| registered.get(&id)) {
+        Some(tx) => tx.send(HotkeyEvent { id, pressed }).is_ok(),
+        None => false,
+    }
+}
+
+/// 模拟按下快捷键
+pub fn press(hotkey: &str) -> bool {
+    emit(hotkey, true)
+}
+
+/// 模拟松开快捷键
+pub fn release(hotkey: &str) -> bool {
+    emit(hotkey, false)
+}
+
+/// 模拟快捷键后端
+pub struct FakeHotkeys {
+    events: UnboundedSender<HotkeyEvent>,
+}
+
+impl FakeHotkeys {
+    pub fn new(events: UnboundedSender<HotkeyEvent>) -> Self {
+        Self { events }
+    }
+}
+
+impl HotkeyBackend for FakeHotkeys {
+    fn register(&mut self, hotkey: HotKey) -> Result<(), HotkeyError> {
+        let occupied = OCCUPIED.lock().unwrap_or_else(|e| e.into_inner());
+        if occupied.as_ref().is_some_and(|occupied| occupied.contains(&hotkey.id())) {
+            return Err(HotkeyError::RegisterFailed(hotkey.into_string(), "已被其他程序占用".to_string()));
+        }
+        REGISTERED.lock().unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(HashMap::new)
+            .insert(hotkey.id(), self.events.clone());
+        Ok(())
+    }
+
+    fn unregister(&mut self, hotkey: HotKey) -> Result<(), HotkeyError> {
+        if let Some(registered) = REGISTERED.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            registered.remove(&hotkey.id());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+// 模拟 LLM 上游
+// 在本机随机端口上提供 Chat Completions 格式的 SSE 流，stream_start 的 endpoint 指向它即可，
+// 不需要修改 LLM 模块。可设置分块间隔 (用于测试取消和停滞) 或返回 HTTP 错误
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// 请求头的大小上限
+const MAX_REQUEST_HEAD: usize = 64 * 1024;
+
+/// 模拟上游的响应
+#[derive(Debug, Clone)]
+enum Reply {
+    /// 依次发送的内容分块 (最后发送 `[DONE]`)
+    Stream { chunks: Vec<String>, interval: Duration },
+    /// HTTP 错误状态和响应体
+    Status(u16, String),
+}
+
+/// 模拟 LLM 上游 (丢弃时停止监听)
+pub struct MockLlm {
+    endpoint: String,
+    requests: Arc<AtomicUsize>,
+    /// 最近一个请求的请求体
+    last_body: Arc<Mutex<Vec<u8>>>,
+    task: JoinHandle<()>,
+}
+
+impl MockLlm {
+    /// 启动依次返回 `chunks` 的上游
+    pub async fn streaming(chunks: &[&str]) -> Self {
+        Self::streaming_with_interval(chunks, Duration::ZERO).await
+    }
+
+    /// 启动依次返回 `chunks` 的上游，每个分块之前等待 `interval`
+    pub async fn streaming_with_interval(chunks: &[&str], interval: Duration) -> Self {
+        let chunks = chunks.iter().map(|chunk| chunk.to_string()).collect();
+        Self::start(Reply::Stream { chunks, interval }).await
+    }
+
+    /// 启动总是返回 HTTP 错误的上游
+    pub async fn failing(status: u16, body: &str) -> Self {
+        Self::replying(status, body).await
+    }
+
+    /// 启动总是返回指定状态和 JSON 响应体的上游 (也用于模拟非流式接口)
+    pub async fn replying(status: u16, body: &str) -> Self {
+        Self::start(Reply::Status(status, body.to_string())).await
+    }
+
+    async fn start(reply: Reply) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock llm");
+        let endpoint = format!("http://{}/v1/chat/completions", listener.local_addr().expect("mock llm address"));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        let last_body = Arc::new(Mutex::new(Vec::new()));
+        let body = Arc::clone(&last_body);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(serve(stream, reply.clone(), Arc::clone(&body)));
+            }
+        });
+        Self { endpoint, requests, last_body, task }
+    }
+
+    /// stream_start 使用的 endpoint
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// 收到的请求数
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// 最近一个请求的请求体 (按 UTF-8 宽松解码)
+    pub fn last_body(&self) -> String {
+        String::from_utf8_lossy(&self.last_body.lock().unwrap()).into_owned()
+    }
+
+    /// stream_start 的负载 (Chat Completions 格式)
+    pub fn stream_config(&self) -> serde_json::Value {
+        serde_json::json!({
+            "endpoint": self.endpoint,
+            "body": r#"{"model":"mock","stream":true,"messages":[]}"#,
+        })
+    }
+}
+
+impl Drop for MockLlm {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 处理一个请求：读完请求体后按预设响应 (响应结束时关闭连接)
+async fn serve(mut stream: TcpStream, reply: Reply, last_body: Arc<Mutex<Vec<u8>>>) {
+    match read_request(&mut stream).await {
+        Ok(body) => *last_body.lock().unwrap() = body,
+        Err(_) => return,
+    }
+    let _ = match reply {
+        Reply::Status(status, body) => {
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status, body.len(), body
+            );
+            stream.write_all(response.as_bytes()).await
+        }
+        Reply::Stream { chunks, interval } => send_stream(&mut stream, &chunks, interval).await,
+    };
+    let _ = stream.shutdown().await;
+}
+
+async fn send_stream(stream: &mut TcpStream, chunks: &[String], interval: Duration) -> std::io::Result<()> {
+    stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n").await?;
+    for chunk in chunks {
+        tokio::time::sleep(interval).await;
+        let data = serde_json::json!({ "choices": [{ "index": 0, "delta": { "content": chunk } }] });
+        stream.write_all(format!("data: {}\n\n", data).as_bytes()).await?;
+        stream.flush().await?;
+    }
+    stream.write_all(b"data: [DONE]\n\n").await
+}
+
+/// 读取请求头和请求体 (按 Content-Length)，返回请求体
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buffer.len() > MAX_REQUEST_HEAD {
+            return Err(std::io::ErrorKind::InvalidData.into());
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    };
+    let head = String::from_utf8_lossy(&buffer[..head_end]).to_ascii_lowercase();
+    let content_length = head.lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = buffer.split_off(head_end);
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    Ok(body)
+}
@@ -0,0 +1,1643 @@
+// 集成测试支持
+// 在随机端口上启动完整的服务器 (中间件、出站队列、认证都与正式运行相同)，配合测试客户端、
+// 模拟 ASR 引擎、模拟 LLM 上游和假 PTY 覆盖模块行为 (兜底路径、取消、重连等)。
+// `cargo test` 时总是编译；启用 `test-support` feature 时也编译进普通构建
+#![cfg_attr(not(test), allow(dead_code))]
+
+pub mod asr;
+pub mod audio_input;
+pub mod capture;
+pub mod client;
+pub mod clipboard;
+pub mod hotkeys;
+pub mod llm;
+pub mod notify;
+pub mod playback;
+pub mod pty;
+pub mod tts;
+
+pub use asr::MockAsrEngine;
+pub use client::TestClient;
+pub use llm::MockLlm;
+
+use crate::config::Config;
+use crate::server::Server;
+use crate::tls::TlsIdentity;
+
+/// 在随机端口上运行的测试服务器 (丢弃前应调用 shutdown 清理模块资源)
+pub struct TestServer {
+    server: Server,
+    address: String,
+}
+
+impl TestServer {
+    /// 使用默认配置启动
+    pub async fn start() -> Self {
+        Self::start_with(Config::default()).await
+    }
+
+    /// 使用指定配置启动 (总是监听本机随机 TCP 端口)
+    pub async fn start_with(config: Config) -> Self {
+        let config = Config {
+            port: 0,
+            socket: None,
+            ..config
+        };
+        Self::launch(Server::new(config)).await
+    }
+
+    /// 使用指定配置和证书启动 TLS (wss://) 服务器
+    pub async fn start_tls(config: Config, tls: TlsIdentity) -> Self {
+        let config = Config {
+            port: 0,
+            socket: None,
+            tls: true,
+            ..config
+        };
+        Self::launch(Server::new(config).with_tls(tls)).await
+    }
+
+    /// 使用指定配置启动，状态快照保存在 `state_dir` 中
+    pub async fn start_with_state_dir(config: Config, state_dir: std::path::PathBuf) -> Self {
+        let config = Config {
+            port: 0,
+            socket: None,
+            ..config
+        };
+        Self::launch(Server::new(config).with_state_dir(state_dir)).await
+    }
+
+    async fn launch(server: Server) -> Self {
+        let address = server.start().await.expect("start test server");
+        Self { server, address }
+    }
+
+    /// 监听地址 (`127.0.0.1:<端口>`)
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// 认证令牌
+    pub fn token(&self) -> &str {
+        self.server.token()
+    }
+
+    /// 建立已认证的连接
+    pub async fn connect(&self) -> TestClient {
+        TestClient::connect(&self.address, self.token()).await.expect("connect to test server")
+    }
+
+    /// 清理所有模块资源
+    pub async fn shutdown(self) {
+        self.server.shutdown().await;
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::ModuleType;
+    use base64::Engine;
+    use std::time::Duration;
+
+    fn transcribe_payload(primary: serde_json::Value, fallback: Option<serde_json::Value>) -> serde_json::Value {
+        let wav = base64::engine::general_purpose::STANDARD.encode(asr::silent_wav(200));
+        serde_json::json!({
+            "audio": wav,
+            "asr_config": {
+                "primary": primary,
+                "fallback": fallback,
+                "enable_fallback": fallback.is_some(),
+                "enable_audio_feedback": false,
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_connect_and_describe() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        let hello = client.expect(ModuleType::System, "hello").await;
+        assert!(hello.payload["modules"].as_array().unwrap().len() >= 4);
+
+        let description = client.request(ModuleType::System, "describe", serde_json::json!({})).await;
+        assert_eq!(description.msg_type, "description");
+
+        // 令牌错误时拒绝连接
+        assert!(TestClient::connect(server.address(), "wrong").await.is_err());
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_asr_fallback() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        // 主引擎成功
+        let primary = MockAsrEngine::replying("harness-primary-ok", "主引擎结果");
+        let response = client.request(ModuleType::Voice, "transcribe", transcribe_payload(primary.register(), None)).await;
+        assert_eq!(response.msg_type, "transcription_complete");
+        assert_eq!(response.payload["text"], "主引擎结果");
+        assert_eq!(response.payload["engine"], "harness-primary-ok");
+        assert_eq!(response.payload["used_fallback"], false);
+        assert_eq!(primary.calls(), 1);
+        // 静音 WAV 的质量报告
+        assert_eq!(response.payload["quality"]["issues"], serde_json::json!(["no_speech"]));
+        assert_eq!(response.payload["quality"]["silence_percent"], 100.0);
+
+        // 主引擎失败，使用备用引擎的结果
+        let primary = MockAsrEngine::failing("harness-primary-down", "connection refused");
+        let fallback = MockAsrEngine::replying("harness-fallback", "备用引擎结果");
+        let payload = transcribe_payload(primary.register(), Some(fallback.register()));
+        let response = client.request(ModuleType::Voice, "transcribe", payload).await;
+        assert_eq!(response.msg_type, "transcription_complete");
+        assert_eq!(response.payload["text"], "备用引擎结果");
+        assert_eq!(response.payload["used_fallback"], true);
+        assert!(primary.calls() >= 1);
+        assert_eq!(fallback.calls(), 1);
+
+        // 全部失败
+        let broken = MockAsrEngine::failing("harness-broken", "connection refused");
+        let payload = transcribe_payload(broken.register(), Some(broken.register()));
+        let response = client.request(ModuleType::Voice, "transcribe", payload).await;
+        assert_eq!(response.msg_type, "error");
+        assert_eq!(response.payload["code"], "TRANSCRIPTION_FAILED");
+
+        // HTTP 模式不记录部分结果延迟
+        let metrics = client.request(ModuleType::Voice, "get_metrics", serde_json::json!({})).await;
+        assert_eq!(metrics.msg_type, "metrics");
+        assert!(metrics.payload["partial_latency"].as_array().unwrap().iter().all(|m| !m["engine"].as_str().unwrap().starts_with("harness-")));
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_with_slow_handler() {
+        let mut config = Config::default();
+        config.limits.heartbeat_interval = Duration::from_secs(1);
+        config.limits.heartbeat_timeout = Duration::from_secs(2);
+        let server = TestServer::start_with(config).await;
+        let mut client = server.connect().await;
+
+        // 处理时间超过心跳超时，连接仍然保持
+        let slow = MockAsrEngine::replying("harness-heartbeat-slow", "慢").with_delay(Duration::from_secs(3));
+        let response = client.request(ModuleType::Voice, "transcribe", transcribe_payload(slow.register(), None)).await;
+        assert_eq!(response.msg_type, "transcription_complete");
+        for _ in 0..3 {
+            let response = client.request(ModuleType::Voice, "get_metrics", serde_json::json!({})).await;
+            assert_eq!(response.msg_type, "metrics");
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_cancel_transcription() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        // 转写在连接任务之外进行，同一连接上的 cancel 立即生效
+        let slow = MockAsrEngine::replying("harness-cancel-slow", "很慢").with_delay(Duration::from_secs(5));
+        let request_id = client.send(ModuleType::Voice, "transcribe", transcribe_payload(slow.register(), None)).await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let started = tokio::time::Instant::now();
+        client.send(ModuleType::System, "cancel", serde_json::json!({ "request_id": request_id })).await;
+        let cancelled = client.expect(ModuleType::System, "cancelled").await;
+        assert_eq!(cancelled.payload["cancelled_module"], "voice");
+        let error = client.expect(ModuleType::Voice, "error").await;
+        assert_eq!(error.payload["code"], "CANCELLED");
+        assert_eq!(error.payload["request_id"], request_id.as_str());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // 已结束的请求
+        let error = client.request(ModuleType::System, "cancel", serde_json::json!({ "request_id": request_id })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_provider_capabilities() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        // 不带配置时列出全部供应商
+        let response = client.request(ModuleType::Voice, "get_provider_capabilities", serde_json::json!({})).await;
+        assert_eq!(response.msg_type, "provider_capabilities");
+        let providers = response.payload["providers"].as_array().unwrap();
+        assert_eq!(providers.len(), 6);
+        let sensevoice = providers.iter().find(|p| p["provider"] == "sensevoice").unwrap();
+        assert_eq!(sensevoice["modes"], serde_json::json!(["http"]));
+        assert_eq!(sensevoice["diarization"], false);
+        // 降噪和 VAD 内置，总是可用
+        assert_eq!(response.payload["audio_processing"], serde_json::json!({ "noise_suppression": true, "vad": true }));
+
+        // 带配置时只列出主供应商和备用供应商
+        let response = client.request(ModuleType::Voice, "get_provider_capabilities", serde_json::json!({
+            "asr_config": {
+                "primary": { "provider": "doubao", "mode": "realtime", "app_id": "app", "access_token": "token" },
+                "fallback": { "provider": "openai", "mode": "http", "api_key": "key", "model": "gpt-4o-transcribe", "upload_format": "opus" },
+                "enable_fallback": true,
+            },
+        })).await;
+        let providers = response.payload["providers"].as_array().unwrap();
+        assert_eq!(providers.len(), 2);
+        assert_eq!(providers[0]["role"], "primary");
+        assert_eq!(providers[0]["modes"], serde_json::json!(["http", "realtime"]));
+        assert_eq!(providers[0]["word_timestamps"], true);
+        assert_eq!(providers[1]["role"], "fallback");
+        assert_eq!(providers[1]["word_timestamps"], false);
+        assert_eq!(providers[1]["hotwords"], true);
+
+        let invalid = client.request(ModuleType::Voice, "get_provider_capabilities", serde_json::json!({
+            "asr_config": { "primary": { "provider": "unknown", "mode": "http" }, "enable_fallback": false },
+        })).await;
+        assert_eq!(invalid.msg_type, "error");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_pause_recording() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        // 测试环境没有录音设备，只检查未录音时的应答
+        for msg_type in ["pause_recording", "resume_recording"] {
+            let error = client.request(ModuleType::Voice, msg_type, serde_json::json!({})).await;
+            assert_eq!(error.msg_type, "error");
+            assert_eq!(error.payload["code"], "NOT_RECORDING");
+        }
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_language_mismatch() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        // 预期中文，引擎返回英文
+        let engine = MockAsrEngine::replying("harness-english", "The meeting is moved to three o'clock tomorrow");
+        let mut payload = transcribe_payload(engine.register(), None);
+        payload["asr_config"]["expected_language"] = "zh".into();
+        let response = client.request(ModuleType::Voice, "transcribe", payload).await;
+        assert_eq!(response.msg_type, "transcription_complete");
+        let warning = &response.payload["language_mismatch"];
+        assert_eq!(warning["expected"], "zh");
+        assert_eq!(warning["detected"], "en");
+        assert_eq!(warning["engine"], "harness-english");
+
+        // 与预期一致
+        let mut payload = transcribe_payload(engine.register(), None);
+        payload["asr_config"]["expected_language"] = "en-US".into();
+        let response = client.request(ModuleType::Voice, "transcribe", payload).await;
+        assert!(response.payload.get("language_mismatch").is_none());
+
+        // 无效的语言代码
+        let mut payload = transcribe_payload(engine.register(), None);
+        payload["asr_config"]["expected_language"] = "chinese".into();
+        let response = client.request(ModuleType::Voice, "transcribe", payload).await;
+        assert_eq!(response.msg_type, "error");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_journal_entry() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+        let mood = MockLlm::streaming(&["😌 ", "平静"]).await;
+
+        // 转写 audio 中的录音，按 recorded_at 排版并判断心情
+        let engine = MockAsrEngine::replying("harness-journal", "晚上散步想清楚了发布计划");
+        let mut payload = transcribe_payload(engine.register(), None);
+        payload["recorded_at"] = "2026-10-16T21:05:00+08:00".into();
+        payload["time_format"] = "%H:%M".into();
+        payload["mood"] = serde_json::json!({ "endpoint": mood.endpoint(), "model": "mock" });
+        payload["template"] = "- {{date}} {{text}} ({{mood}})".into();
+        let entry = client.request(ModuleType::Voice, "journal_entry", payload).await;
+        assert_eq!(entry.msg_type, "journal_entry");
+        assert_eq!(entry.payload["text"], "晚上散步想清楚了发布计划");
+        assert_eq!(entry.payload["mood"], "😌 平静");
+        let expected = format!("- {} 晚上散步想清楚了发布计划 (😌 平静)", entry.payload["date"].as_str().unwrap());
+        assert_eq!(entry.payload["markdown"], expected.as_str());
+
+        // 转写为空时不生成段落
+        let engine = MockAsrEngine::replying("harness-journal-empty", "");
+        let entry = client.request(ModuleType::Voice, "journal_entry", transcribe_payload(engine.register(), None)).await;
+        assert_eq!(entry.payload["markdown"], "");
+
+        let invalid = client.request(ModuleType::Voice, "journal_entry", serde_json::json!({
+            "asr_config": { "primary": engine.register(), "enable_fallback": false }, "keywords": 100,
+        })).await;
+        assert_eq!(invalid.payload["code"], "INVALID_PARAMS");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_llm_stream_and_cancel() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        let upstream = MockLlm::streaming(&["Hel", "lo"]).await;
+        let request_id = client.send(ModuleType::Llm, "stream_start", upstream.stream_config()).await;
+        client.expect(ModuleType::Llm, "stream_started").await;
+        let complete = client.expect(ModuleType::Llm, "stream_complete").await;
+        assert_eq!(complete.payload["full_content"], "Hello");
+        assert_eq!(complete.payload["request_id"], request_id.as_str());
+
+        // 按 request_id 取消进行中的流
+        let slow = MockLlm::streaming_with_interval(&["a"; 50], Duration::from_millis(100)).await;
+        let request_id = client.send(ModuleType::Llm, "stream_start", slow.stream_config()).await;
+        client.expect(ModuleType::Llm, "stream_chunk").await;
+        // cancel 的 request_id 即要取消的请求
+        client.send(ModuleType::System, "cancel", serde_json::json!({ "request_id": request_id })).await;
+        let cancelled = client.expect(ModuleType::System, "cancelled").await;
+        assert_eq!(cancelled.msg_type, "cancelled");
+        assert_eq!(cancelled.payload["cancelled_module"], "llm");
+        let error = client.expect(ModuleType::Llm, "stream_error").await;
+        assert_eq!(error.payload["code"], "CANCELLED");
+        client.assert_no_message(Duration::from_millis(300), |m| m.msg_type == "stream_complete").await;
+
+        // 上游返回 HTTP 错误
+        let failing = MockLlm::failing(503, "overloaded").await;
+        client.send(ModuleType::Llm, "stream_start", failing.stream_config()).await;
+        let error = client.expect(ModuleType::Llm, "stream_error").await;
+        assert_eq!(error.payload["code"], "HTTP_ERROR");
+        assert_eq!(error.payload["retryable"], true);
+        assert_eq!(failing.requests(), 1);
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_llm_stream_sinks() {
+        let dir = std::env::temp_dir().join(format!("llm-sinks-test-{}", std::process::id()));
+        crate::storage::init(crate::storage::DataDirs::at(&dir));
+        let artifacts = crate::storage::data_dirs().unwrap().path(crate::storage::StorageArea::Artifacts);
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        // 内容同时写入 PTY 会话
+        let init = client.request(ModuleType::Pty, "init", serde_json::json!({ "shell_type": pty::MOCK_SHELL })).await;
+        let session_id = init.payload["session_id"].as_str().unwrap().to_string();
+        client.recv_pty_output(pty::MOCK_PROMPT).await;
+        let upstream = MockLlm::streaming(&["echo ", "from-llm\r"]).await;
+        let mut config = upstream.stream_config();
+        config["sinks"] = serde_json::json!([{ "type": "pty", "session_id": session_id }]);
+        client.send(ModuleType::Llm, "stream_start", config).await;
+        client.expect(ModuleType::Llm, "stream_complete").await;
+        let (_, output) = client.recv_pty_output("from-llm").await;
+        assert!(output.contains("echo from-llm"));
+
+        // 无效的文件路径
+        let mut config = upstream.stream_config();
+        config["sinks"] = serde_json::json!([{ "type": "file", "path": "../escape.md" }]);
+        let invalid = client.request(ModuleType::Llm, "stream_start", config).await;
+        assert_eq!(invalid.payload["code"], "INVALID_CONFIG");
+
+        // 客户端断开后继续写入文件
+        let path = format!("llm/{}.md", uuid::Uuid::new_v4());
+        let slow = MockLlm::streaming_with_interval(&["a"; 10], Duration::from_millis(50)).await;
+        let mut config = slow.stream_config();
+        config["sinks"] = serde_json::json!([{ "type": "file", "path": path }]);
+        client.send(ModuleType::Llm, "stream_start", config).await;
+        client.expect(ModuleType::Llm, "stream_chunk").await;
+        client.close().await;
+
+        let file = artifacts.join(&path);
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while std::fs::read_to_string(&file).unwrap_or_default() != "a".repeat(10) {
+            assert!(tokio::time::Instant::now() < deadline, "文件内容不完整");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        server.shutdown().await;
+        let _ = std::fs::remove_file(&file);
+    }
+
+    #[tokio::test]
+    async fn test_list_and_kill_jobs() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        let slow = MockLlm::streaming_with_interval(&["a"; 50], Duration::from_millis(100)).await;
+        let request_id = client.send(ModuleType::Llm, "stream_start", slow.stream_config()).await;
+        client.expect(ModuleType::Llm, "stream_chunk").await;
+
+        // 任务列表是全局的 (其他测试的任务也在其中)，按 request_id 找到这次的流
+        let jobs = client.request(ModuleType::System, "list_jobs", serde_json::json!({})).await;
+        assert_eq!(jobs.msg_type, "jobs");
+        let job = jobs.payload["jobs"].as_array().unwrap().iter()
+            .find(|job| job["request_id"] == request_id.as_str())
+            .cloned()
+            .expect("stream job listed");
+        assert_eq!(job["job_module"], "llm");
+        assert_eq!(job["kind"], "llm_stream");
+        assert_eq!(job["killable"], true);
+
+        let killed = client.request(ModuleType::System, "kill_job", serde_json::json!({ "id": job["id"] })).await;
+        assert_eq!(killed.msg_type, "job_killed");
+        assert_eq!(killed.payload["job_module"], "llm");
+        let error = client.expect(ModuleType::Llm, "stream_error").await;
+        assert_eq!(error.payload["code"], "CANCELLED");
+
+        // 已终止的任务不再列出，再次终止返回 NOT_FOUND
+        let jobs = client.request(ModuleType::System, "list_jobs", serde_json::json!({})).await;
+        assert!(jobs.payload["jobs"].as_array().unwrap().iter().all(|listed| listed["id"] != job["id"]));
+        let error = client.request(ModuleType::System, "kill_job", serde_json::json!({ "id": job["id"] })).await;
+        assert_eq!(error.msg_type, "error");
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+
+        // id 缺失或为负数时校验失败，而不是当作任务 0 报告 NOT_FOUND
+        for payload in [serde_json::json!({}), serde_json::json!({ "id": -1 })] {
+            let error = client.request(ModuleType::System, "kill_job", payload).await;
+            assert_eq!(error.payload["code"], "INVALID_MESSAGE", "{:?}", error.payload);
+        }
+
+        // 文件转写在后台进行，同一连接可以列出并终止它
+        let slow = MockAsrEngine::replying("harness-kill-slow", "很慢").with_delay(Duration::from_secs(5));
+        let request_id = client.send(ModuleType::Voice, "transcribe", transcribe_payload(slow.register(), None)).await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let jobs = client.request(ModuleType::System, "list_jobs", serde_json::json!({})).await;
+        let job = jobs.payload["jobs"].as_array().unwrap().iter()
+            .find(|job| job["request_id"] == request_id.as_str())
+            .cloned()
+            .expect("transcription job listed");
+        assert_eq!(job["kind"], "transcription");
+        assert_eq!(job["killable"], true);
+        let started = tokio::time::Instant::now();
+        let killed = client.request(ModuleType::System, "kill_job", serde_json::json!({ "id": job["id"] })).await;
+        assert_eq!(killed.msg_type, "job_killed");
+        assert_eq!(killed.payload["job_module"], "voice");
+        let error = client.expect(ModuleType::Voice, "error").await;
+        assert_eq!(error.payload["code"], "CANCELLED");
+        assert_eq!(error.payload["request_id"], request_id.as_str());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_fake_pty() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        let init = client.request(ModuleType::Pty, "init", serde_json::json!({ "shell_type": pty::MOCK_SHELL })).await;
+        assert_eq!(init.msg_type, "init_complete");
+        let session_id = init.payload["session_id"].as_str().unwrap().to_string();
+
+        let (output_session, _) = client.recv_pty_output(pty::MOCK_PROMPT).await;
+        assert_eq!(output_session, session_id);
+
+        client.send_pty_input(&session_id, b"echo hi\r").await;
+        let (_, output) = client.recv_pty_output("echo hi").await;
+        assert!(output.contains("echo hi"));
+
+        let screen = client.request(ModuleType::Pty, "get_screen", serde_json::json!({ "session_id": session_id })).await;
+        assert_eq!(screen.msg_type, "screen");
+        assert_eq!(screen.payload["rows"], 24);
+        assert_eq!(screen.payload["lines"][0], "mock$ echo hi");
+        assert_eq!(screen.payload["cursor"], serde_json::json!({ "row": 0, "col": 0 }));
+        let region = serde_json::json!({ "session_id": session_id, "start_row": 1, "end_row": 3 });
+        let screen = client.request(ModuleType::Pty, "get_screen", region).await;
+        assert_eq!(screen.payload["lines"], serde_json::json!(["", ""]));
+
+        // 假 shell 没有 Shell Integration
+        let env = client.request(ModuleType::Pty, "get_env", serde_json::json!({ "session_id": session_id })).await;
+        assert_eq!(env.payload["code"], "INVALID_PARAMS");
+
+        client.send_pty_input(&session_id, b"exit\r").await;
+        let exit = client.recv_matching(|m| m.module == ModuleType::Pty && m.msg_type == "exit").await;
+        assert_eq!(exit.payload["session_id"], session_id.as_str());
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_pty_output_idle() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+        let is_idle = |m: &crate::router::ServerResponse| m.module == ModuleType::Pty && m.msg_type == "output_idle";
+
+        // 未设置 idle_notify_ms 时不发送
+        client.request(ModuleType::Pty, "init", serde_json::json!({ "shell_type": pty::MOCK_SHELL })).await;
+        client.recv_pty_output(pty::MOCK_PROMPT).await;
+        client.assert_no_message(Duration::from_millis(300), is_idle).await;
+
+        let payload = serde_json::json!({ "shell_type": pty::MOCK_SHELL, "idle_notify_ms": 100 });
+        let init = client.request(ModuleType::Pty, "init", payload).await;
+        let session_id = init.payload["session_id"].as_str().unwrap().to_string();
+        let idle = client.recv_matching(is_idle).await;
+        assert_eq!(idle.payload["session_id"], session_id.as_str());
+        assert_eq!(idle.payload["idle_ms"], 100);
+        // 输出停止后只通知一次，再次有输出后重新计时
+        client.assert_no_message(Duration::from_millis(300), is_idle).await;
+        client.send_pty_input(&session_id, b"echo hi\r").await;
+        let idle = client.recv_matching(is_idle).await;
+        assert_eq!(idle.payload["session_id"], session_id.as_str());
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        let payload = serde_json::json!({ "shell_type": pty::MOCK_SHELL, "request_id": "audit-pty-init" });
+        let init = client.request(ModuleType::Pty, "init", payload).await;
+        let session_id = init.payload["session_id"].as_str().unwrap().to_string();
+        client.send(ModuleType::Pty, "destroy", serde_json::json!({ "session_id": session_id, "request_id": "audit-pty-destroy" })).await;
+
+        // 后台流式任务中的网络请求归属于发起请求
+        let upstream = MockLlm::streaming(&["ok"]).await;
+        let mut config = upstream.stream_config();
+        config["request_id"] = "audit-llm".into();
+        client.send(ModuleType::Llm, "stream_start", config).await;
+        client.expect(ModuleType::Llm, "stream_complete").await;
+
+        let query = |request_id: &str| serde_json::json!({ "origin_request_id": request_id });
+        let log = client.request(ModuleType::System, "get_audit_log", query("audit-pty-init")).await;
+        assert_eq!(log.msg_type, "audit_log");
+        let records = log.payload["records"].as_array().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["action"], "pty_exec");
+        assert_eq!(records[0]["module"], "pty");
+        assert_eq!(records[0]["details"]["session_id"], session_id.as_str());
+
+        let log = client.request(ModuleType::System, "get_audit_log", query("audit-pty-destroy")).await;
+        assert_eq!(log.payload["records"][0]["action"], "pty_destroy");
+        assert_eq!(log.payload["records"][0]["target"], session_id.as_str());
+
+        let log = client.request(ModuleType::System, "get_audit_log", query("audit-llm")).await;
+        assert_eq!(log.payload["records"][0]["action"], "network");
+        assert_eq!(log.payload["records"][0]["target"], upstream.endpoint());
+
+        // 未知的操作类型校验失败
+        let error = client.request(ModuleType::System, "get_audit_log", serde_json::json!({ "action": "bogus" })).await;
+        assert_eq!(error.msg_type, "error");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_file_watch() {
+        let vault = std::env::temp_dir().join(format!("testing-files-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&vault);
+        std::fs::create_dir_all(vault.join(".obsidian")).unwrap();
+
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        let payload = serde_json::json!({ "paths": [vault.to_string_lossy()], "debounce_ms": 50 });
+        let watching = client.request(ModuleType::Files, "watch", payload).await;
+        assert_eq!(watching.msg_type, "watching");
+        assert_eq!(watching.payload["ignore"], serde_json::json!([".git", ".obsidian", ".trash"]));
+        let session_id = watching.payload["session_id"].as_str().unwrap().to_string();
+
+        std::fs::write(vault.join("note.md"), "# note").unwrap();
+        let created = client.expect(ModuleType::Files, "file_created").await;
+        assert_eq!(created.payload["session_id"], session_id.as_str());
+        assert_eq!(created.payload["relative_path"], "note.md");
+
+        std::fs::write(vault.join("note.md"), "# note\nmore").unwrap();
+        let modified = client.expect(ModuleType::Files, "file_modified").await;
+        assert_eq!(modified.payload["relative_path"], "note.md");
+
+        std::fs::rename(vault.join("note.md"), vault.join("renamed.md")).unwrap();
+        let renamed = client.expect(ModuleType::Files, "file_renamed").await;
+        assert_eq!(renamed.payload["old_relative_path"], "note.md");
+        assert_eq!(renamed.payload["relative_path"], "renamed.md");
+
+        // 忽略的目录中的变更不产生事件
+        std::fs::write(vault.join(".obsidian").join("workspace.json"), "{}").unwrap();
+        std::fs::remove_file(vault.join("renamed.md")).unwrap();
+        let deleted = client.expect(ModuleType::Files, "file_deleted").await;
+        assert_eq!(deleted.payload["relative_path"], "renamed.md");
+        let ignored = |m: &crate::router::ServerResponse| m.payload["path"].as_str().is_some_and(|path| path.contains(".obsidian"));
+        client.assert_no_message(Duration::from_millis(200), ignored).await;
+
+        let list = client.request(ModuleType::Files, "list", serde_json::json!({})).await;
+        assert_eq!(list.payload["watches"][0]["session_id"], session_id.as_str());
+        let unwatched = client.request(ModuleType::Files, "unwatch", serde_json::json!({ "session_id": session_id })).await;
+        assert_eq!(unwatched.msg_type, "unwatched");
+        std::fs::write(vault.join("late.md"), "").unwrap();
+        client.assert_no_message(Duration::from_millis(200), |m| m.payload["relative_path"] == "late.md").await;
+
+        let error = client.request(ModuleType::Files, "unwatch", serde_json::json!({ "session_id": session_id })).await;
+        assert_eq!(error.payload["code"], "SESSION_NOT_FOUND");
+        let missing = vault.join("missing");
+        let error = client.request(ModuleType::Files, "watch", serde_json::json!({ "paths": [missing.to_string_lossy()] })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+
+        client.close().await;
+        server.shutdown().await;
+        let _ = std::fs::remove_dir_all(&vault);
+    }
+
+    #[tokio::test]
+    async fn test_clipboard() {
+        clipboard::install();
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        let written = client.request(ModuleType::Clipboard, "write", serde_json::json!({ "text": "转写结果" })).await;
+        assert_eq!(written.msg_type, "clipboard_written");
+        assert_eq!(clipboard::text().as_deref(), Some("转写结果"));
+        let content = client.request(ModuleType::Clipboard, "read", serde_json::json!({})).await;
+        assert_eq!(content.payload["format"], "text");
+        assert_eq!(content.payload["text"], "转写结果");
+
+        // 图片以 PNG 往返
+        let image = crate::clipboard::Image { width: 1, height: 2, rgba: vec![0, 128, 255, 255, 10, 20, 30, 40] };
+        let png = base64::engine::general_purpose::STANDARD.encode(image.to_png().unwrap());
+        client.request(ModuleType::Clipboard, "write", serde_json::json!({ "image": png })).await;
+        let content = client.request(ModuleType::Clipboard, "read", serde_json::json!({ "format": "image" })).await;
+        assert_eq!((content.payload["width"].as_u64(), content.payload["height"].as_u64()), (Some(1), Some(2)));
+        let data = base64::engine::general_purpose::STANDARD.decode(content.payload["data"].as_str().unwrap()).unwrap();
+        assert_eq!(crate::clipboard::Image::from_png(&data).unwrap(), image);
+        let content = client.request(ModuleType::Clipboard, "read", serde_json::json!({ "format": "text" })).await;
+        assert_eq!(content.payload, serde_json::json!({ "format": "text", "request_id": content.payload["request_id"] }));
+
+        // 其他程序复制时推送事件，自己写入的内容不推送
+        let started = client.request(ModuleType::Clipboard, "watch_start", serde_json::json!({ "interval_ms": 100 })).await;
+        assert_eq!(started.msg_type, "watch_started");
+        clipboard::set_text("copied elsewhere");
+        let changed = client.expect(ModuleType::Clipboard, "clipboard_changed").await;
+        assert_eq!(changed.payload["text"], "copied elsewhere");
+        client.request(ModuleType::Clipboard, "write", serde_json::json!({ "text": "own write" })).await;
+        client.assert_no_message(Duration::from_millis(300), |m| m.msg_type == "clipboard_changed").await;
+        let stopped = client.request(ModuleType::Clipboard, "watch_stop", serde_json::json!({})).await;
+        assert_eq!(stopped.msg_type, "watch_stopped");
+
+        let error = client.request(ModuleType::Clipboard, "write", serde_json::json!({ "image": "bm90IGEgcG5n" })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        let error = client.request(ModuleType::Clipboard, "write", serde_json::json!({})).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_hotkeys() {
+        hotkeys::install();
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+        let mut other = server.connect().await;
+
+        let registered = client.request(ModuleType::Hotkeys, "register", serde_json::json!({
+            "hotkey": "ctrl+shift+Space",
+            "name": "push_to_talk",
+        })).await;
+        assert_eq!(registered.msg_type, "registered");
+        assert_eq!(registered.payload["hotkey"], "shift+control+Space");
+        assert_eq!(registered.payload["name"], "push_to_talk");
+
+        // 事件只推送到注册快捷键的连接
+        assert!(hotkeys::press("ctrl+shift+Space"));
+        let pressed = client.expect(ModuleType::Hotkeys, "hotkey_pressed").await;
+        assert_eq!(pressed.payload["name"], "push_to_talk");
+        assert_eq!(pressed.payload["id"], registered.payload["id"]);
+        assert!(hotkeys::release("ctrl+shift+Space"));
+        client.expect(ModuleType::Hotkeys, "hotkey_released").await;
+        other.assert_no_message(Duration::from_millis(200), |m| m.module == ModuleType::Hotkeys).await;
+
+        let list = other.request(ModuleType::Hotkeys, "list", serde_json::json!({})).await;
+        assert_eq!(list.payload["hotkeys"].as_array().map(Vec::len), Some(1));
+
+        let error = client.request(ModuleType::Hotkeys, "register", serde_json::json!({ "hotkey": "Shift+Control+space" })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        let error = client.request(ModuleType::Hotkeys, "register", serde_json::json!({ "hotkey": "ctrl+NoSuchKey" })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        hotkeys::occupy("alt+F9");
+        let error = client.request(ModuleType::Hotkeys, "register", serde_json::json!({ "hotkey": "alt+F9" })).await;
+        assert_eq!(error.payload["code"], "DEVICE_ERROR");
+
+        let unregistered = client.request(ModuleType::Hotkeys, "unregister", serde_json::json!({ "hotkey": "ctrl+shift+Space" })).await;
+        assert_eq!(unregistered.msg_type, "unregistered");
+        assert!(!hotkeys::press("ctrl+shift+Space"));
+        let error = client.request(ModuleType::Hotkeys, "unregister", serde_json::json!({ "hotkey": "ctrl+shift+Space" })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+
+        // 连接全部关闭后注销剩余的快捷键
+        client.request(ModuleType::Hotkeys, "register", serde_json::json!({ "hotkey": "ctrl+alt+KeyR" })).await;
+        client.close().await;
+        other.close().await;
+        server.shutdown().await;
+        assert!(!hotkeys::press("ctrl+alt+KeyR"));
+    }
+
+    #[tokio::test]
+    async fn test_capture() {
+        capture::install();
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+        let decode = |payload: &serde_json::Value| {
+            let png = base64::engine::general_purpose::STANDARD.decode(payload["data"].as_str().unwrap()).unwrap();
+            image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap().to_rgba8()
+        };
+
+        let monitors = client.request(ModuleType::Capture, "list_monitors", serde_json::json!({})).await;
+        assert_eq!(monitors.payload["monitors"].as_array().map(Vec::len), Some(2));
+        let windows = client.request(ModuleType::Capture, "list_windows", serde_json::json!({})).await;
+        assert_eq!(windows.payload["windows"][0]["app_name"], "obsidian");
+
+        // 默认截取主显示器
+        let captured = client.request(ModuleType::Capture, "capture", serde_json::json!({})).await;
+        assert_eq!(captured.msg_type, "captured");
+        assert_eq!((captured.payload["width"].as_u64(), captured.payload["height"].as_u64()), (Some(64), Some(48)));
+        assert_eq!(decode(&captured.payload).get_pixel(5, 7).0, [5, 7, 1, 255]);
+
+        // 区域超出显示器的部分被裁掉；缩放比例为 2 的显示器按像素换算
+        let captured = client.request(ModuleType::Capture, "capture", serde_json::json!({
+            "target": "region", "x": 60, "y": 40, "width": 10, "height": 20,
+        })).await;
+        let image = decode(&captured.payload);
+        assert_eq!(image.dimensions(), (4, 8));
+        assert_eq!(image.get_pixel(0, 0).0, [60, 40, 1, 255]);
+        let captured = client.request(ModuleType::Capture, "capture", serde_json::json!({
+            "target": "region", "x": 70, "y": 2, "width": 3, "height": 2,
+        })).await;
+        let image = decode(&captured.payload);
+        assert_eq!(image.dimensions(), (6, 4));
+        assert_eq!(image.get_pixel(2, 0).0, [71, 2, 2, 255]);
+
+        // 窗口截图保存到文件
+        let dir = std::env::temp_dir().join(format!("capture-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("window.png");
+        let captured = client.request(ModuleType::Capture, "capture", serde_json::json!({
+            "target": "window", "window_id": 100, "path": path,
+        })).await;
+        assert_eq!(captured.payload["path"], path.display().to_string());
+        assert!(captured.payload.get("data").is_none());
+        let saved = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(saved.dimensions(), (16, 12));
+        assert_eq!(saved.get_pixel(0, 0).0, capture::WINDOW_COLOR);
+
+        let error = client.request(ModuleType::Capture, "capture", serde_json::json!({ "monitor": 9 })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+        let error = client.request(ModuleType::Capture, "capture", serde_json::json!({ "target": "window", "window_id": 9 })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+        let error = client.request(ModuleType::Capture, "capture", serde_json::json!({
+            "target": "region", "x": -50, "y": 0, "width": 10, "height": 10,
+        })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        let error = client.request(ModuleType::Capture, "capture", serde_json::json!({ "target": "region", "x": 0 })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        let error = client.request(ModuleType::Capture, "capture", serde_json::json!({
+            "path": dir.join("missing").join("shot.png"),
+        })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+
+        client.close().await;
+        server.shutdown().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// 发起 push / pull，返回完成事件 (pushed / pulled 或 error)
+    async fn git_remote(client: &mut TestClient, msg_type: &str, payload: serde_json::Value) -> crate::router::ServerResponse {
+        let started = client.request(ModuleType::Git, msg_type, payload).await;
+        assert_eq!(started.msg_type, format!("{}_started", msg_type), "{:?}", started.payload);
+        let request_id = started.payload["request_id"].as_str().unwrap().to_string();
+        client.recv_matching(|m| client::request_id_of(m) == Some(request_id.as_str()) && m.msg_type != "progress").await
+    }
+
+    #[tokio::test]
+    async fn test_git() {
+        let dir = std::env::temp_dir().join(format!("testing-git-{}", uuid::Uuid::new_v4()));
+        let (origin, vault, laptop) = (dir.join("origin.git"), dir.join("vault"), dir.join("laptop"));
+        git2::Repository::init_bare(&origin).unwrap();
+        git2::Repository::init(&vault).unwrap().remote("origin", &origin.to_string_lossy()).unwrap();
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+        let author = |message: &str| serde_json::json!({
+            "repo": vault, "message": message, "author_name": "Vault", "author_email": "vault@example.com",
+        });
+
+        std::fs::write(vault.join("note.md"), "# note\n").unwrap();
+        let status = client.request(ModuleType::Git, "status", serde_json::json!({ "repo": vault })).await;
+        assert_eq!(status.payload["head"], serde_json::Value::Null);
+        assert_eq!(status.payload["files"], serde_json::json!([{ "path": "note.md", "staged": null, "unstaged": "new" }]));
+        let error = client.request(ModuleType::Git, "commit", author("empty")).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+
+        let staged = client.request(ModuleType::Git, "stage", serde_json::json!({ "repo": vault, "paths": ["note.md"] })).await;
+        assert_eq!(staged.payload["files"][0]["staged"], "new");
+        let diff = client.request(ModuleType::Git, "diff", serde_json::json!({ "repo": vault, "staged": true })).await;
+        assert!(diff.payload["patch"].as_str().unwrap().contains("+# note"));
+        assert_eq!(diff.payload["insertions"], 1);
+        let committed = client.request(ModuleType::Git, "commit", author("First note")).await;
+        assert_eq!(committed.msg_type, "committed");
+        assert_eq!(committed.payload["author"], "Vault");
+
+        // all: 暂存修改、删除和未跟踪的文件后提交
+        std::fs::write(vault.join("note.md"), "# note\nedited by AI\n").unwrap();
+        std::fs::write(vault.join("other.md"), "other\n").unwrap();
+        let diff = client.request(ModuleType::Git, "diff", serde_json::json!({ "repo": vault, "paths": ["note.md"] })).await;
+        assert_eq!(diff.payload["files_changed"], 1);
+        let checkpoint = client.request(ModuleType::Git, "commit", serde_json::json!({
+            "repo": vault, "message": "Checkpoint", "all": true, "author_name": "Vault", "author_email": "vault@example.com",
+        })).await;
+        assert_eq!(checkpoint.payload["parents"][0], committed.payload["id"]);
+        let status = client.request(ModuleType::Git, "status", serde_json::json!({ "repo": vault })).await;
+        assert_eq!(status.payload["files"], serde_json::json!([]));
+        let log = client.request(ModuleType::Git, "log", serde_json::json!({ "repo": vault })).await;
+        let summaries: Vec<&str> = log.payload["commits"].as_array().unwrap().iter().map(|c| c["summary"].as_str().unwrap()).collect();
+        assert_eq!(summaries, ["Checkpoint", "First note"]);
+        let log = client.request(ModuleType::Git, "log", serde_json::json!({ "repo": vault, "path": "other.md" })).await;
+        assert_eq!(log.payload["commits"].as_array().map(Vec::len), Some(1));
+
+        // 推送后设置上游，另一台设备克隆、修改并推送
+        let pushed = git_remote(&mut client, "push", serde_json::json!({ "repo": vault })).await;
+        assert_eq!(pushed.msg_type, "pushed", "{:?}", pushed.payload);
+        assert_eq!(pushed.payload["commit"], checkpoint.payload["id"]);
+        let status = client.request(ModuleType::Git, "status", serde_json::json!({ "repo": vault })).await;
+        assert_eq!((status.payload["ahead"].as_u64(), status.payload["behind"].as_u64()), (Some(0), Some(0)));
+        assert!(status.payload["upstream"].as_str().unwrap().starts_with("origin/"));
+
+        git2::Repository::clone(&origin.to_string_lossy(), &laptop).unwrap();
+        std::fs::write(laptop.join("laptop.md"), "from laptop\n").unwrap();
+        client.request(ModuleType::Git, "commit", serde_json::json!({
+            "repo": laptop, "message": "Laptop note", "all": true, "author_name": "Laptop", "author_email": "laptop@example.com",
+        })).await;
+        let pushed = git_remote(&mut client, "push", serde_json::json!({ "repo": laptop })).await;
+        assert_eq!(pushed.msg_type, "pushed", "{:?}", pushed.payload);
+
+        // 分叉后推送被拒绝，拉取合并后可以推送
+        std::fs::write(vault.join("other.md"), "other\nedited\n").unwrap();
+        client.request(ModuleType::Git, "commit", serde_json::json!({
+            "repo": vault, "message": "Edit other", "all": true, "author_name": "Vault", "author_email": "vault@example.com",
+        })).await;
+        let rejected = git_remote(&mut client, "push", serde_json::json!({ "repo": vault })).await;
+        assert_eq!(rejected.msg_type, "error");
+        assert_eq!(rejected.payload["code"], "CONFLICT");
+        let pulled = git_remote(&mut client, "pull", serde_json::json!({
+            "repo": vault, "author_name": "Vault", "author_email": "vault@example.com",
+        })).await;
+        assert_eq!(pulled.msg_type, "pulled", "{:?}", pulled.payload);
+        assert_eq!(pulled.payload["result"], "merged");
+        assert_eq!(std::fs::read_to_string(vault.join("laptop.md")).unwrap(), "from laptop\n");
+        let pushed = git_remote(&mut client, "push", serde_json::json!({ "repo": vault })).await;
+        assert_eq!(pushed.msg_type, "pushed", "{:?}", pushed.payload);
+        let pulled = git_remote(&mut client, "pull", serde_json::json!({ "repo": laptop })).await;
+        assert_eq!(pulled.payload["result"], "fast_forward");
+        assert_eq!(std::fs::read_to_string(laptop.join("other.md")).unwrap(), "other\nedited\n");
+
+        // 同一行的修改冲突时拉取失败，工作目录保持不变
+        std::fs::write(laptop.join("note.md"), "# laptop title\n").unwrap();
+        client.request(ModuleType::Git, "commit", serde_json::json!({
+            "repo": laptop, "message": "Retitle", "all": true, "author_name": "Laptop", "author_email": "laptop@example.com",
+        })).await;
+        git_remote(&mut client, "push", serde_json::json!({ "repo": laptop })).await;
+        std::fs::write(vault.join("note.md"), "# vault title\nedited by AI\n").unwrap();
+        client.request(ModuleType::Git, "commit", serde_json::json!({
+            "repo": vault, "message": "Retitle in vault", "all": true, "author_name": "Vault", "author_email": "vault@example.com",
+        })).await;
+        let conflict = git_remote(&mut client, "pull", serde_json::json!({
+            "repo": vault, "author_name": "Vault", "author_email": "vault@example.com",
+        })).await;
+        assert_eq!(conflict.payload["code"], "CONFLICT");
+        assert!(conflict.payload["message"].as_str().unwrap().contains("note.md"));
+        assert_eq!(std::fs::read_to_string(vault.join("note.md")).unwrap(), "# vault title\nedited by AI\n");
+
+        let error = client.request(ModuleType::Git, "status", serde_json::json!({ "repo": dir })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+        let error = client.request(ModuleType::Git, "push", serde_json::json!({ "repo": vault, "remote": "backup" })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+
+        client.close().await;
+        server.shutdown().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+        let soon = |millis: i64| (chrono::Local::now() + chrono::Duration::milliseconds(millis)).to_rfc3339();
+
+        // 一次性任务按时触发后删除
+        let added = client.request(ModuleType::Scheduler, "add", serde_json::json!({
+            "id": "reminder", "name": "站起来活动", "at": soon(500), "data": { "text": "stand up" },
+        })).await;
+        assert_eq!(added.msg_type, "added");
+        assert_eq!(added.payload["replaced"], false);
+        assert_eq!(added.payload["missed"], "fire");
+        let fired = client.expect(ModuleType::Scheduler, "fired").await;
+        assert_eq!(fired.payload["id"], "reminder");
+        assert_eq!(fired.payload["data"], serde_json::json!({ "text": "stand up" }));
+        assert_eq!(fired.payload["missed"], false);
+        let list = client.request(ModuleType::Scheduler, "list", serde_json::json!({})).await;
+        assert_eq!(list.payload["jobs"], serde_json::json!([]));
+
+        // cron 任务 (带秒字段) 重复触发，暂停后不再触发
+        client.request(ModuleType::Scheduler, "add", serde_json::json!({ "id": "tick", "cron": "* * * * * *" })).await;
+        client.request(ModuleType::Scheduler, "add", serde_json::json!({ "id": "nightly", "cron": "0 3 * * *", "missed": "skip" })).await;
+        for _ in 0..2 {
+            let fired = client.expect(ModuleType::Scheduler, "fired").await;
+            assert_eq!(fired.payload["id"], "tick");
+        }
+        let paused = client.request(ModuleType::Scheduler, "pause", serde_json::json!({ "id": "tick" })).await;
+        assert_eq!(paused.payload["paused"], true);
+        assert_eq!(paused.payload["next_run"], serde_json::Value::Null);
+        client.assert_no_message(Duration::from_millis(1500), |m| m.msg_type == "fired" && m.payload["id"] == "tick").await;
+        let list = client.request(ModuleType::Scheduler, "list", serde_json::json!({})).await;
+        let ids: Vec<&str> = list.payload["jobs"].as_array().unwrap().iter().map(|job| job["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["nightly", "tick"]);
+        let resumed = client.request(ModuleType::Scheduler, "resume", serde_json::json!({ "id": "tick" })).await;
+        assert!(resumed.payload["next_run"].is_string());
+        client.request(ModuleType::Scheduler, "remove", serde_json::json!({ "id": "tick" })).await;
+
+        // 没有客户端连接期间到期的任务，在下一个客户端连接后补发
+        client.request(ModuleType::Scheduler, "add", serde_json::json!({ "id": "offline", "at": soon(300) })).await;
+        client.close().await;
+        tokio::time::sleep(Duration::from_millis(800)).await;
+        let mut client = server.connect().await;
+        let fired = client.recv_matching(|m| m.msg_type == "fired").await;
+        assert_eq!(fired.payload["id"], "offline");
+        assert_eq!(fired.payload["missed"], true);
+        assert_eq!(fired.payload["missed_count"], 1);
+
+        let error = client.request(ModuleType::Scheduler, "add", serde_json::json!({ "cron": "0 3 * * *", "at": soon(1000) })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        let error = client.request(ModuleType::Scheduler, "add", serde_json::json!({ "cron": "0 25 * * *" })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        let error = client.request(ModuleType::Scheduler, "add", serde_json::json!({ "at": soon(-1000) })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        let error = client.request(ModuleType::Scheduler, "remove", serde_json::json!({ "id": "tick" })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        notify::install();
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+        let mut other = server.connect().await;
+
+        let shown = client.request(ModuleType::Notify, "show", serde_json::json!({
+            "id": "build", "title": "构建完成", "body": "cargo build 用时 42s",
+            "actions": [{ "id": "open", "label": "打开终端" }], "urgency": "low", "timeout_ms": 0,
+        })).await;
+        assert_eq!(shown.msg_type, "shown");
+        assert_eq!(shown.payload["id"], "build");
+        assert_eq!(shown.payload["interactive"], true);
+        let notification = notify::shown().into_iter().rfind(|n| n.title == "构建完成").unwrap();
+        assert_eq!(notification.body.as_deref(), Some("cargo build 用时 42s"));
+        assert_eq!(notification.urgency, crate::notify::Urgency::Low);
+        assert_eq!(notification.timeout, Some(Duration::ZERO));
+
+        // 用户操作只推送给发起请求的连接
+        assert!(notify::respond("构建完成", crate::notify::UserAction::Action("open".to_string())));
+        let action = client.expect(ModuleType::Notify, "action").await;
+        assert_eq!(action.payload["id"], "build");
+        assert_eq!(action.payload["action"], "open");
+        other.assert_no_message(Duration::from_millis(300), |m| m.module == ModuleType::Notify).await;
+
+        // 省略 id 时自动生成
+        let shown = client.request(ModuleType::Notify, "show", serde_json::json!({ "title": "转写完成" })).await;
+        let id = shown.payload["id"].as_str().unwrap().to_string();
+        assert!(notify::respond("转写完成", crate::notify::UserAction::Clicked));
+        let clicked = client.expect(ModuleType::Notify, "clicked").await;
+        assert_eq!(clicked.payload["id"], id.as_str());
+
+        let error = client.request(ModuleType::Notify, "show", serde_json::json!({ "title": "" })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        let error = client.request(ModuleType::Notify, "show", serde_json::json!({ "body": "没有标题" })).await;
+        assert_eq!(error.payload["code"], "INVALID_MESSAGE");
+
+        client.close().await;
+        other.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_playback() {
+        playback::install();
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        let devices = client.request(ModuleType::Playback, "list_devices", serde_json::json!({})).await;
+        assert_eq!(devices.payload["devices"][1], serde_json::json!({ "name": "Fake Headphones", "is_default": false }));
+
+        // 内存中的 WAV (如 TTS 输出)，在指定设备上播放并推送位置
+        let wav = base64::engine::general_purpose::STANDARD.encode(asr::silent_wav(1500));
+        let playing = client.request(ModuleType::Playback, "play", serde_json::json!({
+            "id": "tts", "data": wav, "device": "Fake Headphones", "position_interval_ms": 200,
+        })).await;
+        assert_eq!(playing.msg_type, "playing");
+        assert_eq!(playing.payload["duration_ms"], 1500);
+        assert_eq!(playing.payload["state"], "playing");
+        assert_eq!(playback::last_device().as_deref(), Some("Fake Headphones"));
+        let position = client.expect(ModuleType::Playback, "position").await;
+        assert_eq!(position.payload["id"], "tts");
+        assert!(position.payload["position_ms"].as_u64().unwrap() > 0);
+
+        // 暂停后不再推送位置
+        let paused = client.request(ModuleType::Playback, "pause", serde_json::json!({ "id": "tts" })).await;
+        assert_eq!(paused.payload["state"], "paused");
+        client.assert_no_message(Duration::from_millis(400), |m| m.msg_type == "position").await;
+        let seeked = client.request(ModuleType::Playback, "seek", serde_json::json!({ "position_ms": 1300 })).await;
+        assert_eq!(seeked.payload["position_ms"], 1300);
+        let error = client.request(ModuleType::Playback, "seek", serde_json::json!({ "position_ms": 5000 })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        client.request(ModuleType::Playback, "resume", serde_json::json!({})).await;
+        let ended = client.expect(ModuleType::Playback, "ended").await;
+        assert_eq!(ended.payload["id"], "tts");
+        let status = client.request(ModuleType::Playback, "status", serde_json::json!({})).await;
+        assert_eq!(status.payload["state"], "idle");
+
+        // 本地文件，停止播放
+        let path = std::env::temp_dir().join(format!("testing-playback-{}.wav", std::process::id()));
+        std::fs::write(&path, asr::silent_wav(5000)).unwrap();
+        let playing = client.request(ModuleType::Playback, "play", serde_json::json!({
+            "path": path.to_string_lossy(), "start_ms": 1000, "position_interval_ms": 0,
+        })).await;
+        assert!(playing.payload["position_ms"].as_u64().unwrap() >= 1000);
+        let id = playing.payload["id"].as_str().unwrap().to_string();
+        let error = client.request(ModuleType::Playback, "stop", serde_json::json!({ "id": "tts" })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+        let stopped = client.request(ModuleType::Playback, "stop", serde_json::json!({ "id": id })).await;
+        assert_eq!(stopped.msg_type, "stopped");
+        let status = client.request(ModuleType::Playback, "status", serde_json::json!({})).await;
+        assert_eq!(status.payload["state"], "idle");
+
+        let error = client.request(ModuleType::Playback, "play", serde_json::json!({ "path": "/nonexistent/audio.wav" })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+        let error = client.request(ModuleType::Playback, "play", serde_json::json!({ "path": path.to_string_lossy(), "device": "HDMI" })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+        let error = client.request(ModuleType::Playback, "play", serde_json::json!({ "data": "bm90IGF1ZGlv" })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+
+        client.close().await;
+        server.shutdown().await;
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_tts() {
+        playback::install();
+        let upstream = tts::MockTts::with_interval(Duration::from_millis(50)).await;
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        // 逐句合成，第一个音频包到达即开始播放，全部播放完毕后推送 ended
+        let speaking = client.request(ModuleType::Tts, "speak", serde_json::json!({
+            "id": "note", "text": "第一句。第二句！\n\nThird one.", "doubao": upstream.config(), "device": "Fake Headphones",
+        })).await;
+        assert_eq!(speaking.msg_type, "speaking");
+        assert_eq!(speaking.payload["sentences"], 3);
+        let playing = client.expect(ModuleType::Tts, "playing").await;
+        assert_eq!(playing.payload["id"], "note");
+        assert!(playing.payload["first_audio_ms"].as_u64().unwrap() < 1000);
+        let status = client.request(ModuleType::Tts, "status", serde_json::json!({})).await;
+        assert_eq!(status.payload["state"], "speaking");
+        let ended = client.expect(ModuleType::Tts, "ended").await;
+        assert_eq!(ended.payload["sentences"], 3);
+        assert_eq!(ended.payload["duration_ms"], 900);
+        let requests = upstream.requests();
+        let texts: Vec<&str> = requests.iter().map(|request| request.text()).collect();
+        assert_eq!(texts, vec!["第一句。", "第二句！", "Third one."]);
+        assert_eq!(requests[0].authorization, "Bearer;mock-token");
+        assert_eq!(requests[0].body["audio"]["voice_type"], "mock_voice");
+        assert_eq!(playback::last_device().as_deref(), Some("Fake Headphones"));
+        let status = client.request(ModuleType::Tts, "status", serde_json::json!({})).await;
+        assert_eq!(status.payload["state"], "idle");
+
+        // 朗读期间登记为任务，stop 后不再推送 ended
+        client.request(ModuleType::Tts, "speak", serde_json::json!({
+            "id": "long", "text": "很长的一句话。".repeat(20), "doubao": upstream.config(),
+        })).await;
+        client.expect(ModuleType::Tts, "playing").await;
+        let jobs = client.request(ModuleType::System, "list_jobs", serde_json::json!({})).await;
+        assert!(jobs.payload["jobs"].as_array().unwrap().iter().any(|job| job["kind"] == "speech" && job["detail"] == "long"));
+        let error = client.request(ModuleType::Tts, "stop", serde_json::json!({ "id": "note" })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+        let stopped = client.request(ModuleType::Tts, "stop", serde_json::json!({})).await;
+        assert_eq!(stopped.payload["id"], "long");
+        client.assert_no_message(Duration::from_millis(300), |m| m.msg_type == "ended").await;
+
+        // 合成失败时推送 error 事件，参数错误不可重试
+        let failing = tts::MockTts::failing(3010, "text too long").await;
+        client.request(ModuleType::Tts, "speak", serde_json::json!({ "id": "bad", "text": "你好。", "doubao": failing.config() })).await;
+        let error = client.expect(ModuleType::Tts, "error").await;
+        assert_eq!(error.payload["id"], "bad");
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        assert_eq!(error.payload["sentence"], 0);
+        assert_eq!(error.payload["retryable"], false);
+
+        let error = client.request(ModuleType::Tts, "speak", serde_json::json!({ "text": "你好" })).await;
+        assert_eq!(error.payload["code"], "INVALID_CONFIG");
+        let error = client.request(ModuleType::Tts, "speak", serde_json::json!({ "text": " \n", "doubao": upstream.config() })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_backup() {
+        let root = std::env::temp_dir().join(format!("testing-backup-{}", std::process::id()));
+        let vault = root.join("vault");
+        let dest = root.join("backups");
+        std::fs::create_dir_all(vault.join("daily")).unwrap();
+        std::fs::write(vault.join("index.md"), "# Index").unwrap();
+        std::fs::write(vault.join("daily/2026-10-16.md"), "会议记录").unwrap();
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        // 后台创建快照，完成后推送 created
+        let started = client.request(ModuleType::Backup, "create", serde_json::json!({
+            "folders": [vault.to_string_lossy()], "name": "vault", "dest": dest.to_string_lossy(), "keep_last": 1,
+        })).await;
+        assert_eq!(started.msg_type, "create_started");
+        let progress = client.expect(ModuleType::Backup, "progress").await;
+        assert_eq!(progress.payload["stage"], "archiving");
+        let created = client.expect(ModuleType::Backup, "created").await;
+        assert_eq!(created.payload["files"], 2);
+        assert_eq!(created.payload["pruned"], serde_json::json!([]));
+        let id = created.payload["id"].as_str().unwrap().to_string();
+
+        let files = client.request(ModuleType::Backup, "files", serde_json::json!({
+            "id": id, "prefix": "vault/daily", "dest": dest.to_string_lossy(),
+        })).await;
+        assert_eq!(files.payload["files"][0]["path"], "vault/daily/2026-10-16.md");
+
+        // 按文件恢复到原位置
+        std::fs::remove_file(vault.join("daily/2026-10-16.md")).unwrap();
+        let restored = client.request(ModuleType::Backup, "restore", serde_json::json!({
+            "id": id, "paths": ["vault/daily/2026-10-16.md", "vault/index.md"], "dest": dest.to_string_lossy(),
+        })).await;
+        assert_eq!(restored.msg_type, "restored");
+        assert_eq!(restored.payload["restored"].as_array().unwrap().len(), 1);
+        assert_eq!(restored.payload["skipped"].as_array().unwrap().len(), 1);
+        assert_eq!(std::fs::read_to_string(vault.join("daily/2026-10-16.md")).unwrap(), "会议记录");
+
+        // 计划任务的 backup 动作创建快照，并按 keep_last 删除旧快照
+        let at = (chrono::Local::now() + chrono::Duration::milliseconds(1100)).to_rfc3339();
+        client.request(ModuleType::Scheduler, "add", serde_json::json!({
+            "id": "nightly-backup", "at": at, "action": "backup",
+            "data": { "folders": [vault.to_string_lossy()], "name": "vault", "dest": dest.to_string_lossy(), "keep_last": 1 },
+        })).await;
+        let completed = client.expect(ModuleType::Scheduler, "action_completed").await;
+        assert_eq!(completed.payload["action"], "backup");
+        assert_eq!(completed.payload["result"]["pruned"], serde_json::json!([id]));
+        let list = client.request(ModuleType::Backup, "list", serde_json::json!({ "dest": dest.to_string_lossy() })).await;
+        assert_eq!(list.payload["snapshots"].as_array().unwrap().len(), 1);
+
+        let error = client.request(ModuleType::Scheduler, "add", serde_json::json!({
+            "cron": "0 3 * * *", "action": "backup", "data": { "folders": [] },
+        })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+        let error = client.request(ModuleType::Backup, "restore", serde_json::json!({
+            "id": id, "paths": ["vault/index.md"], "dest": dest.to_string_lossy(),
+        })).await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+        let started = client.request(ModuleType::Backup, "create", serde_json::json!({
+            "folders": [root.join("missing").to_string_lossy()], "dest": dest.to_string_lossy(),
+        })).await;
+        assert_eq!(started.msg_type, "create_started");
+        let error = client.expect(ModuleType::Backup, "error").await;
+        assert_eq!(error.payload["code"], "NOT_FOUND");
+
+        client.close().await;
+        server.shutdown().await;
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_system_info() {
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+
+        let info = client.request(ModuleType::SystemInfo, "get", serde_json::json!({})).await;
+        assert_eq!(info.msg_type, "info");
+        assert!(info.payload["cpu"]["cores"].as_u64().unwrap() > 0);
+        assert!(info.payload["memory"]["total_bytes"].as_u64().unwrap() > 0);
+        assert!(info.payload.get("battery").is_some());
+        assert_eq!(info.payload["endpoints"], serde_json::json!([]));
+        assert!(info.payload["warnings"].is_array());
+
+        // 只返回请求的分类；vault 所在磁盘和不可达的端点
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let upstream = MockLlm::streaming(&["ok"]).await;
+        let info = client.request(ModuleType::SystemInfo, "get", serde_json::json!({
+            "sections": ["disk", "network"],
+            "paths": { "vault": std::env::temp_dir().to_string_lossy() },
+            "endpoints": { "llm": upstream.endpoint(), "offline": format!("ws://{}/", closed) },
+        })).await;
+        assert!(info.payload.get("cpu").is_none());
+        assert!(info.payload.get("battery").is_none());
+        let vault = info.payload["disks"].as_array().unwrap().iter().find(|disk| disk["name"] == "vault").unwrap();
+        assert!(vault["total_bytes"].as_u64().unwrap() >= vault["available_bytes"].as_u64().unwrap());
+        assert_eq!(info.payload["endpoints"][0]["name"], "llm");
+        assert_eq!(info.payload["endpoints"][0]["reachable"], true);
+        assert_eq!(info.payload["endpoints"][1]["reachable"], false);
+        let warnings = info.payload["warnings"].as_array().unwrap();
+        assert!(warnings.iter().any(|warning| warning["kind"] == "unreachable"));
+
+        let error = client.request(ModuleType::SystemInfo, "get", serde_json::json!({ "sections": ["gpu"] })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_meeting() {
+        audio_input::install();
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+        let engine = MockAsrEngine::replying("harness-meeting", "我们下周发布");
+        let asr_config = serde_json::json!({ "primary": engine.register(), "enable_fallback": false });
+        let upstream = MockLlm::streaming(&[
+            "{\"summary\": \"确定发布时间\", ",
+            "\"action_items\": [{\"task\": \"写发布说明\", \"owner\": \"local\"}], \"decisions\": [\"下周发布\"]}",
+        ]).await;
+        let encode = |wav: Vec<u8>| base64::engine::general_purpose::STANDARD.encode(wav);
+
+        // 已有录音：按来源分别转写，静音的系统音频不产生片段，最后生成纪要
+        let started = client.request(ModuleType::Meeting, "process", serde_json::json!({
+            "audio": encode(asr::tone_wav(12_000)),
+            "system_audio": encode(asr::silent_wav(12_000)),
+            "asr_config": asr_config,
+            "diarize": true,
+            "chunk_seconds": 10,
+            "summary": { "endpoint": upstream.endpoint(), "model": "test" },
+        })).await;
+        assert_eq!(started.msg_type, "process_started");
+        assert_eq!(started.payload["duration_ms"], 12_000);
+        let segment = client.expect(ModuleType::Meeting, "segment").await;
+        assert_eq!(segment.payload["speaker"], "local");
+        assert_eq!(segment.payload["text"], "我们下周发布");
+        let completed = client.expect(ModuleType::Meeting, "completed").await;
+        assert_eq!(completed.payload["id"], started.payload["id"]);
+        assert_eq!(completed.payload["segments"].as_array().unwrap().len(), 2);
+        assert_eq!(completed.payload["failed_segments"], 0);
+        assert!(completed.payload["transcript"].as_str().unwrap().starts_with("[00:00] local: 我们下周发布\n["));
+        assert_eq!(completed.payload["summary"]["summary"], "确定发布时间");
+        assert_eq!(completed.payload["summary"]["action_items"][0]["task"], "写发布说明");
+        assert_eq!(completed.payload["summary"]["decisions"][0], "下周发布");
+        assert_eq!(engine.calls(), 2);
+
+        // 实时录音：同时只能有一个会议在录音，stop 后转写剩余的音频
+        let started = client.request(ModuleType::Meeting, "start", serde_json::json!({ "asr_config": asr_config })).await;
+        assert_eq!(started.msg_type, "started");
+        assert_eq!(started.payload["sources"], serde_json::json!(["mic"]));
+        let again = client.request(ModuleType::Meeting, "start", serde_json::json!({ "asr_config": asr_config })).await;
+        assert_eq!(again.payload["code"], "ALREADY_RECORDING");
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        let status = client.request(ModuleType::Meeting, "status", serde_json::json!({})).await;
+        assert_eq!(status.payload["meetings"][0]["live"], true);
+        assert!(status.payload["meetings"][0]["position_ms"].as_u64().unwrap() >= 500);
+        let stopping = client.request(ModuleType::Meeting, "stop", serde_json::json!({})).await;
+        assert_eq!(stopping.payload["id"], started.payload["id"]);
+        let completed = client.expect(ModuleType::Meeting, "completed").await;
+        assert_eq!(completed.payload["id"], started.payload["id"]);
+        assert_eq!(completed.payload["transcript"], "[00:00] 我们下周发布");
+        assert!(completed.payload.get("summary").is_none());
+        let error = client.request(ModuleType::Meeting, "stop", serde_json::json!({})).await;
+        assert_eq!(error.payload["code"], "NOT_RECORDING");
+
+        // 取消后不推送 completed
+        let slow = MockAsrEngine::replying("harness-meeting-slow", "很慢").with_delay(Duration::from_secs(5));
+        let started = client.request(ModuleType::Meeting, "process", serde_json::json!({
+            "audio": encode(asr::tone_wav(2_000)),
+            "asr_config": { "primary": slow.register(), "enable_fallback": false },
+        })).await;
+        let cancelled = client.request(ModuleType::Meeting, "cancel", serde_json::json!({ "id": started.payload["id"] })).await;
+        assert_eq!(cancelled.msg_type, "cancelled");
+        client.assert_no_message(Duration::from_millis(300), |response| response.msg_type == "completed").await;
+        let status = client.request(ModuleType::Meeting, "status", serde_json::json!({})).await;
+        assert_eq!(status.payload["meetings"], serde_json::json!([]));
+
+        let error = client.request(ModuleType::Meeting, "start", serde_json::json!({
+            "asr_config": asr_config,
+            "chunk_seconds": 5,
+        })).await;
+        assert_eq!(error.payload["code"], "INVALID_PARAMS");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_workflow() {
+        audio_input::install();
+        let server = TestServer::start().await;
+        let mut client = server.connect().await;
+        let polish = MockLlm::streaming(&["润色后", "的文本"]).await;
+        let translate = MockLlm::streaming(&["Polished text"]).await;
+        let flaky = MockLlm::failing(503, "overloaded").await;
+        let note = std::env::temp_dir().join(format!("testing-workflow-{}.md", std::process::id()));
+        let _ = std::fs::remove_file(&note);
+        let llm = |id: &str, upstream: &MockLlm| serde_json::json!({
+            "id": id, "type": "llm", "endpoint": upstream.endpoint(), "model": "mock",
+        });
+        let mut translate_step = llm("translate", &translate);
+        translate_step["prompt"] = "Translate into English: {{polish}}".into();
+        translate_step["when"] = serde_json::json!({ "step": "polish", "op": "not_language", "value": "en" });
+        let mut flaky_step = llm("flaky", &flaky);
+        flaky_step["retries"] = 1.into();
+        flaky_step["retry_delay_ms"] = 10.into();
+        flaky_step["continue_on_error"] = true.into();
+        let workflow = serde_json::json!({
+            "name": "polish-and-save",
+            "input": "今天开会讨论了发布",
+            "vars": { "title": "周会" },
+            "steps": [
+                llm("polish", &polish),
+                translate_step,
+                { "id": "check", "type": "text", "template": "x", "when": { "op": "contains", "value": "不存在" } },
+                flaky_step,
+                { "id": "save", "type": "write", "path": note.to_string_lossy(), "mode": "create",
+                  "content": "# {{vars.title}}\n{{polish}}\n{{translate}}\n" },
+            ],
+        });
+
+        // 条件不满足的步骤跳过，可重试的错误按 retries 重试，continue_on_error 的步骤失败后继续
+        let started = client.request(ModuleType::Workflow, "run", workflow.clone()).await;
+        assert_eq!(started.msg_type, "run_started");
+        assert_eq!(started.payload["steps"], 5);
+        let completed = client.expect(ModuleType::Workflow, "completed").await;
+        assert_eq!(completed.payload["id"], started.payload["id"]);
+        assert_eq!(completed.payload["outputs"]["polish"], "润色后的文本");
+        assert_eq!(completed.payload["outputs"]["translate"], "Polished text");
+        assert_eq!(completed.payload["skipped"], serde_json::json!(["check"]));
+        assert_eq!(completed.payload["failed"], serde_json::json!(["flaky"]));
+        assert_eq!(completed.payload["output"], note.to_string_lossy().as_ref());
+        assert_eq!(std::fs::read_to_string(&note).unwrap(), "# 周会\n润色后的文本\nPolished text\n");
+        assert_eq!(flaky.requests(), 2);
+        let retrying = client.recv_matching(|response| response.msg_type == "step" && response.payload["status"] == "retrying").await;
+        assert_eq!(retrying.payload["step"], "flaky");
+        let skipped = client.recv_matching(|response| response.msg_type == "step" && response.payload["status"] == "skipped").await;
+        assert_eq!(skipped.payload["step"], "check");
+
+        // 步骤失败时以 error 事件结束
+        client.request(ModuleType::Workflow, "run", workflow).await;
+        let error = client.expect(ModuleType::Workflow, "error").await;
+        assert_eq!(error.payload["code"], "CONFLICT");
+        assert_eq!(error.payload["step"], "save");
+        let _ = std::fs::remove_file(&note);
+
+        // 录音步骤用 stop 结束，录音交给下一步转写
+        let engine = MockAsrEngine::replying("harness-workflow", "录音内容");
+        let started = client.request(ModuleType::Workflow, "run", serde_json::json!({
+            "steps": [
+                { "id": "rec", "type": "record", "max_seconds": 30 },
+                { "id": "text", "type": "transcribe", "asr_config": { "primary": engine.register(), "enable_fallback": false } },
+            ],
+        })).await;
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        let status = client.request(ModuleType::Workflow, "status", serde_json::json!({})).await;
+        assert_eq!(status.payload["workflows"][0]["recording"], true);
+        let stopping = client.request(ModuleType::Workflow, "stop", serde_json::json!({})).await;
+        assert_eq!(stopping.payload["id"], started.payload["id"]);
+        let completed = client.expect(ModuleType::Workflow, "completed").await;
+        assert_eq!(completed.payload["output"], "录音内容");
+        assert!(completed.payload["outputs"]["rec"]["duration_ms"].as_u64().unwrap() >= 400);
+        let error = client.request(ModuleType::Workflow, "stop", serde_json::json!({})).await;
+        assert_eq!(error.payload["code"], "NOT_RECORDING");
+
+        // 取消后不推送 completed
+        let slow = MockLlm::streaming_with_interval(&["a", "b"], Duration::from_secs(5)).await;
+        let started = client.request(ModuleType::Workflow, "run", serde_json::json!({ "steps": [llm("slow", &slow)] })).await;
+        let cancelled = client.request(ModuleType::Workflow, "cancel", serde_json::json!({ "id": started.payload["id"] })).await;
+        assert_eq!(cancelled.msg_type, "cancelled");
+        client.assert_no_message(Duration::from_millis(300), |response| response.msg_type == "completed").await;
+
+        let invalid = client.request(ModuleType::Workflow, "validate", serde_json::json!({
+            "steps": [{ "id": "a", "type": "text", "template": "{{later}}" }, { "id": "later", "type": "text", "template": "x" }],
+        })).await;
+        assert_eq!(invalid.payload["code"], "INVALID_PARAMS");
+
+        client.close().await;
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_tls() {
+        use futures_util::StreamExt;
+        use sha2::{Digest, Sha256};
+        use tokio_tungstenite::{Connector, MaybeTlsStream};
+
+        let dir = std::env::temp_dir().join(format!("testing-tls-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let identity = TlsIdentity::load_or_generate(&dir).unwrap();
+        let fingerprint = identity.fingerprint().to_string();
+        // 远程访问时使用配置文件中的固定令牌
+        let config = Config {
+            token: Some("tablet-token-0123456789".to_string()),
+            ..Config::default()
+        };
+        let server = TestServer::start_tls(config, identity).await;
+        assert_eq!(server.token(), "tablet-token-0123456789");
+
+        // 自签名证书：客户端不校验 CA，改为比对证书指纹
+        let connector = native_tls::TlsConnector::builder().danger_accept_invalid_certs(true).build().unwrap();
+        let url = format!("wss://{}/?token={}", server.address(), server.token());
+        let (mut stream, _) = tokio_tungstenite::connect_async_tls_with_config(url, None, false, Some(Connector::NativeTls(connector)))
+            .await
+            .unwrap();
+        let MaybeTlsStream::NativeTls(tls) = stream.get_ref() else {
+            panic!("expected a TLS stream");
+        };
+        let cert = tls.get_ref().peer_certificate().unwrap().unwrap().to_der().unwrap();
+        let actual: Vec<String> = Sha256::digest(&cert).iter().map(|byte| format!("{:02X}", byte)).collect();
+        assert_eq!(actual.join(":"), fingerprint);
+
+        let hello = stream.next().await.unwrap().unwrap();
+        assert!(hello.to_text().unwrap().contains("\"hello\""));
+
+        // 明文连接无法完成握手
+        assert!(TestClient::connect(server.address(), server.token()).await.is_err());
+
+        server.shutdown().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_remote_requires_tls() {
+        let config = Config {
+            host: Some(std::net::IpAddr::from([0, 0, 0, 0])),
+            ..Config::default()
+        };
+        assert!(config.is_remote());
+        assert!(Server::new(config).start().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_disabled_module() {
+        let config = Config {
+            disabled_modules: vec!["video".to_string()],
+            ..Config::default()
+        };
+        assert!(Server::new(config).start().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pty_restore() {
+        let dir = std::env::temp_dir().join(format!("testing-state-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let decode = |response: &crate::router::ServerResponse| {
+            let data = response.payload["data"].as_str().unwrap();
+            String::from_utf8(base64::engine::general_purpose::STANDARD.decode(data).unwrap()).unwrap()
+        };
+
+        // 退出前仍在运行的会话写入快照
+        let server = TestServer::start_with_state_dir(Config::default(), dir.clone()).await;
+        let mut client = server.connect().await;
+        let init = client.request(ModuleType::Pty, "init", serde_json::json!({ "shell_type": pty::MOCK_SHELL })).await;
+        let session_id = init.payload["session_id"].as_str().unwrap().to_string();
+        client.send_pty_input(&session_id, b"echo hi\r").await;
+        client.recv_pty_output("echo hi").await;
+        client.send(ModuleType::Pty, "resize", serde_json::json!({ "session_id": session_id, "cols": 100, "rows": 30 })).await;
+
+        let list = client.request(ModuleType::Pty, "list", serde_json::json!({})).await;
+        assert_eq!(list.payload["sessions"][0]["session_id"], session_id.as_str());
+        assert_eq!(list.payload["sessions"][0]["cols"], 100);
+        assert_eq!(list.payload["sessions"][0]["restored"], false);
+        server.shutdown().await;
+        client.close().await;
+
+        // 重启后以相同的会话 ID 恢复，回滚缓冲保留上次的输出
+        let server = TestServer::start_with_state_dir(Config::default(), dir.clone()).await;
+        let mut client = server.connect().await;
+        let list = client.request(ModuleType::Pty, "list", serde_json::json!({})).await;
+        let sessions = list.payload["sessions"].as_array().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0]["session_id"], session_id.as_str());
+        assert_eq!(sessions[0]["restored"], true);
+        assert_eq!(sessions[0]["running"], true);
+        assert_eq!(sessions[0]["rows"], 30);
+        let scrollback = client.request(ModuleType::Pty, "scrollback", serde_json::json!({ "session_id": session_id })).await;
+        assert!(decode(&scrollback).contains("echo hi"));
+        let missing = client.request(ModuleType::Pty, "scrollback", serde_json::json!({ "session_id": "missing" })).await;
+        assert_eq!(missing.payload["code"], "SESSION_NOT_FOUND");
+        server.shutdown().await;
+        client.close().await;
+
+        // fresh_start 时不恢复
+        let config = Config { fresh_start: true, ..Config::default() };
+        let server = TestServer::start_with_state_dir(config, dir.clone()).await;
+        let mut client = server.connect().await;
+        let list = client.request(ModuleType::Pty, "list", serde_json::json!({})).await;
+        assert_eq!(list.payload["sessions"], serde_json::json!([]));
+        client.close().await;
+        server.shutdown().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_pty_restore_per_vault() {
+        let dir = std::env::temp_dir().join(format!("testing-vault-state-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        async fn connect(server: &TestServer, vault: &str) -> TestClient {
+            let mut client = server.connect().await;
+            let hello = serde_json::json!({ "protocol_version": crate::system::PROTOCOL_VERSION, "vault": vault });
+            let ack = client.request(ModuleType::System, "hello", hello).await;
+            assert_eq!(ack.payload["vault"], vault);
+            client
+        }
+        let sessions = |list: crate::router::ServerResponse| -> Vec<String> {
+            list.payload["sessions"].as_array().unwrap().iter()
+                .map(|session| session["session_id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // 每个 vault 只看到自己的会话
+        let server = TestServer::start_with_state_dir(Config::default(), dir.clone()).await;
+        let mut work = connect(&server, "work").await;
+        let mut home = connect(&server, "home").await;
+        let init = work.request(ModuleType::Pty, "init", serde_json::json!({ "shell_type": pty::MOCK_SHELL })).await;
+        let work_session = init.payload["session_id"].as_str().unwrap().to_string();
+        let init = home.request(ModuleType::Pty, "init", serde_json::json!({ "shell_type": pty::MOCK_SHELL })).await;
+        let home_session = init.payload["session_id"].as_str().unwrap().to_string();
+        assert_eq!(sessions(work.request(ModuleType::Pty, "list", serde_json::json!({})).await), [work_session.as_str()]);
+        assert_eq!(sessions(home.request(ModuleType::Pty, "list", serde_json::json!({})).await), [home_session.as_str()]);
+
+        // 自定义词表使用 hello 中声明的 vault，而不是请求中的 vault 字段
+        let updated = work.request(ModuleType::Utils, "spellcheck_add_words", serde_json::json!({ "vault": "home", "words": ["Obsidian"] })).await;
+        assert_eq!(updated.payload["vault"], "work");
+        server.shutdown().await;
+        work.close().await;
+        home.close().await;
+        assert_eq!(crate::storage::vault_dirs(&dir).len(), 2);
+
+        // 重启后各 vault 的会话在该 vault 中恢复，未声明 vault 的连接看不到
+        let server = TestServer::start_with_state_dir(Config::default(), dir.clone()).await;
+        let mut work = connect(&server, "work").await;
+        let mut home = connect(&server, "home").await;
+        let mut other = server.connect().await;
+        let list = work.request(ModuleType::Pty, "list", serde_json::json!({})).await;
+        assert_eq!(list.payload["sessions"][0]["restored"], true);
+        assert_eq!(sessions(list), [work_session.as_str()]);
+        assert_eq!(sessions(home.request(ModuleType::Pty, "list", serde_json::json!({})).await), [home_session.as_str()]);
+        assert!(sessions(other.request(ModuleType::Pty, "list", serde_json::json!({})).await).is_empty());
+        server.shutdown().await;
+        work.close().await;
+        home.close().await;
+        other.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        let server = TestServer::start().await;
+
+        // 断开时仍有进行中的流和终端会话
+        let mut first = server.connect().await;
+        let slow = MockLlm::streaming_with_interval(&["a"; 50], Duration::from_millis(100)).await;
+        first.send(ModuleType::Llm, "stream_start", slow.stream_config()).await;
+        first.expect(ModuleType::Llm, "stream_chunk").await;
+        first.request(ModuleType::Pty, "init", serde_json::json!({ "shell_type": pty::MOCK_SHELL })).await;
+        first.close().await;
+
+        // 重新连接后模块照常工作
+        let mut second = server.connect().await;
+        second.expect(ModuleType::System, "hello").await;
+        let upstream = MockLlm::streaming(&["ok"]).await;
+        second.send(ModuleType::Llm, "stream_start", upstream.stream_config()).await;
+        let complete = second.expect(ModuleType::Llm, "stream_complete").await;
+        assert_eq!(complete.payload["full_content"], "ok");
+
+        let init = second.request(ModuleType::Pty, "init", serde_json::json!({ "shell_type": pty::MOCK_SHELL })).await;
+        let session_id = init.payload["session_id"].as_str().unwrap().to_string();
+        let (output_session, _) = second.recv_pty_output(pty::MOCK_PROMPT).await;
+        assert_eq!(output_session, session_id);
+
+        second.close().await;
+        server.shutdown().await;
+    }
+}
@@ -0,0 +1,57 @@
+// 模拟通知
+// 调用 install 后通知模块改用进程内的模拟通知 (测试环境通常没有通知服务)，
+// 测试可以查看显示过的通知，并模拟用户点击通知或按钮
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+use crate::notify::{Notification, NotifyBackend, NotifyError, UserAction};
+
+/// 是否已安装模拟通知
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// 显示过的通知及等待用户操作的回调
+static SHOWN: Mutex<Vec<(Notification, Option<oneshot::Sender<UserAction>>)>> = Mutex::new(Vec::new());
+
+fn shown_list() -> std::sync::MutexGuard<'static, Vec<(Notification, Option<oneshot::Sender<UserAction>>)>> {
+    SHOWN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 安装模拟通知 (此后显示的通知都是模拟通知)
+pub fn install() {
+    INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// 是否已安装模拟通知
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::SeqCst)
+}
+
+/// 显示过的通知 (按显示顺序)
+pub fn shown() -> Vec<Notification> {
+    shown_list().iter().map(|(notification, _)| notification.clone()).collect()
+}
+
+/// 模拟用户操作标题为 `title` 的最近一条通知，返回是否找到仍在等待操作的通知
+pub fn respond(title: &str, action: UserAction) -> bool {
+    let respond = shown_list()
+        .iter_mut()
+        .rev()
+        .find(|(notification, respond)| notification.title == title && respond.is_some())
+        .and_then(|(_, respond)| respond.take());
+    match respond {
+        Some(respond) => respond.send(action).is_ok(),
+        None => false,
+    }
+}
+
+/// 模拟通知
+pub struct FakeNotifier;
+
+impl NotifyBackend for FakeNotifier {
+    fn show(&mut self, notification: &Notification, respond: oneshot::Sender<UserAction>) -> Result<bool, NotifyError> {
+        shown_list().push((notification.clone(), Some(respond)));
+        Ok(true)
+    }
+}
@@ -0,0 +1,124 @@
+// 模拟音频输出
+// 调用 install 后播放模块改用模拟输出 (测试环境通常没有声卡)：音频照常解码，
+// 播放位置按真实时间推进 (流式音频不超过已写入的部分)，提供两个输出设备
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::playback::{OutputBackend, OutputDeviceInfo, PcmStream, PlaybackError, PlaybackOutput, Track};
+
+/// 是否已安装模拟输出
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// 模拟输出设备 (第一个为默认设备)
+pub const DEVICES: [&str; 2] = ["Fake Speakers", "Fake Headphones"];
+
+/// 最近一次播放使用的设备
+static LAST_DEVICE: Mutex<Option<String>> = Mutex::new(None);
+
+/// 安装模拟输出 (此后的播放都使用模拟输出)
+pub fn install() {
+    INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// 是否已安装模拟输出
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::SeqCst)
+}
+
+/// 最近一次播放使用的设备
+pub fn last_device() -> Option<String> {
+    LAST_DEVICE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 模拟输出
+pub struct FakeOutput;
+
+impl OutputBackend for FakeOutput {
+    fn devices(&self) -> Result<Vec<OutputDeviceInfo>, PlaybackError> {
+        Ok(DEVICES
+            .iter()
+            .enumerate()
+            .map(|(index, name)| OutputDeviceInfo { name: name.to_string(), is_default: index == 0 })
+            .collect())
+    }
+
+    fn play(&self, track: Track, device: Option<&str>, _volume: f32) -> Result<Arc<dyn PlaybackOutput>, PlaybackError> {
+        let device = device.unwrap_or(DEVICES[0]);
+        if !DEVICES.contains(&device) {
+            return Err(PlaybackError::DeviceNotFound(device.to_string()));
+        }
+        *LAST_DEVICE.lock().unwrap_or_else(|e| e.into_inner()) = Some(device.to_string());
+        Ok(Arc::new(FakePlayback {
+            duration: track.duration.unwrap_or_default(),
+            stream: track.pcm_stream().cloned(),
+            clock: Mutex::new(Clock { offset: Duration::ZERO, started: Some(Instant::now()) }),
+        }))
+    }
+}
+
+/// 播放时钟
+struct Clock {
+    /// 上次暂停或跳转时的位置
+    offset: Duration,
+    /// 从 offset 开始播放的时刻 (暂停时为 None)
+    started: Option<Instant>,
+}
+
+/// 模拟播放
+struct FakePlayback {
+    duration: Duration,
+    /// 流式音频 (时长为已写入的部分)
+    stream: Option<PcmStream>,
+    clock: Mutex<Clock>,
+}
+
+impl FakePlayback {
+    fn clock(&self) -> std::sync::MutexGuard<'_, Clock> {
+        self.clock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn duration(&self) -> Duration {
+        self.stream.as_ref().map_or(self.duration, PcmStream::written)
+    }
+}
+
+impl PlaybackOutput for FakePlayback {
+    fn pause(&self) {
+        let position = self.position();
+        let mut clock = self.clock();
+        clock.offset = position;
+        clock.started = None;
+    }
+
+    fn resume(&self) {
+        let mut clock = self.clock();
+        if clock.started.is_none() {
+            clock.started = Some(Instant::now());
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.clock().started.is_none()
+    }
+
+    fn seek(&self, position: Duration) -> Result<(), PlaybackError> {
+        let mut clock = self.clock();
+        clock.offset = position.min(self.duration());
+        if clock.started.is_some() {
+            clock.started = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    fn position(&self) -> Duration {
+        let clock = self.clock();
+        let elapsed = clock.started.map(|started| started.elapsed()).unwrap_or_default();
+        (clock.offset + elapsed).min(self.duration())
+    }
+
+    fn finished(&self) -> bool {
+        self.stream.as_ref().is_none_or(PcmStream::is_finished) && self.position() >= self.duration()
+    }
+}
@@ -0,0 +1,196 @@
+// 假 PTY
+// 内存中的终端：启动时输出提示符，回显写入的内容，收到 `exit` 行时以退出码 0 结束。
+// `init` 的 shell_type 为 `mock` 时代替真实的 PTY 和 shell 进程
+
+use portable_pty::{Child, ChildKiller, CommandBuilder, ExitStatus, MasterPty, PtyPair, PtySize, PtySystem, SlavePty};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// 选择假 PTY 的 shell_type
+pub const MOCK_SHELL: &str = "mock";
+
+/// 假 PTY 启动后输出的提示符
+pub const MOCK_PROMPT: &str = "mock$ ";
+
+/// 被终止时的退出码
+const KILLED_EXIT_CODE: u32 = 1;
+
+/// 终端状态 (主端和子进程共享)
+#[derive(Debug)]
+struct Terminal {
+    output: VecDeque<u8>,
+    line: Vec<u8>,
+    size: PtySize,
+    exit_code: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+struct SharedTerminal(Arc<(Mutex<Terminal>, Condvar)>);
+
+impl SharedTerminal {
+    fn new(size: PtySize) -> Self {
+        let terminal = Terminal {
+            output: MOCK_PROMPT.bytes().collect(),
+            line: Vec::new(),
+            size,
+            exit_code: None,
+        };
+        Self(Arc::new((Mutex::new(terminal), Condvar::new())))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Terminal> {
+        self.0.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn exit(&self, code: u32) {
+        let mut terminal = self.lock();
+        terminal.exit_code.get_or_insert(code);
+        self.0.1.notify_all();
+    }
+}
+
+/// 假 PTY 系统
+pub struct FakePtySystem;
+
+impl PtySystem for FakePtySystem {
+    fn openpty(&self, size: PtySize) -> anyhow::Result<PtyPair> {
+        let terminal = SharedTerminal::new(size);
+        Ok(PtyPair {
+            slave: Box::new(FakeSlave(terminal.clone())),
+            master: Box::new(FakeMaster(terminal)),
+        })
+    }
+}
+
+struct FakeMaster(SharedTerminal);
+
+impl MasterPty for FakeMaster {
+    fn resize(&self, size: PtySize) -> anyhow::Result<()> {
+        self.0.lock().size = size;
+        Ok(())
+    }
+
+    fn get_size(&self) -> anyhow::Result<PtySize> {
+        Ok(self.0.lock().size)
+    }
+
+    fn try_clone_reader(&self) -> anyhow::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(FakeReader(self.0.clone())))
+    }
+
+    fn take_writer(&self) -> anyhow::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(FakeWriter(self.0.clone())))
+    }
+
+    #[cfg(unix)]
+    fn process_group_leader(&self) -> Option<libc::pid_t> {
+        None
+    }
+
+    #[cfg(unix)]
+    fn as_raw_fd(&self) -> Option<std::os::unix::io::RawFd> {
+        None
+    }
+
+    #[cfg(unix)]
+    fn tty_name(&self) -> Option<std::path::PathBuf> {
+        None
+    }
+}
+
+struct FakeSlave(SharedTerminal);
+
+impl SlavePty for FakeSlave {
+    fn spawn_command(&self, _cmd: CommandBuilder) -> anyhow::Result<Box<dyn Child + Send + Sync>> {
+        Ok(Box::new(FakeChild(self.0.clone())))
+    }
+}
+
+/// 读取终端输出，没有输出时阻塞；进程退出且输出读完后返回 EOF
+struct FakeReader(SharedTerminal);
+
+impl Read for FakeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let (_, ready) = &*self.0.0;
+        let mut terminal = self.0.lock();
+        while terminal.output.is_empty() && terminal.exit_code.is_none() {
+            terminal = ready.wait(terminal).unwrap_or_else(|e| e.into_inner());
+        }
+        let n = buf.len().min(terminal.output.len());
+        for (slot, byte) in buf.iter_mut().zip(terminal.output.drain(..n)) {
+            *slot = byte;
+        }
+        Ok(n)
+    }
+}
+
+/// 写入终端输入：回显，遇到 `exit` 行时退出
+struct FakeWriter(SharedTerminal);
+
+impl Write for FakeWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut terminal = self.0.lock();
+        if terminal.exit_code.is_some() {
+            return Err(io::Error::from(io::ErrorKind::BrokenPipe));
+        }
+        terminal.output.extend(data);
+        for &byte in data {
+            if byte == b'\r' || byte == b'\n' {
+                let line = std::mem::take(&mut terminal.line);
+                if line.trim_ascii() == b"exit" {
+                    terminal.exit_code = Some(0);
+                    break;
+                }
+            } else {
+                terminal.line.push(byte);
+            }
+        }
+        self.0.0.1.notify_all();
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct FakeChild(SharedTerminal);
+
+impl ChildKiller for FakeChild {
+    fn kill(&mut self) -> io::Result<()> {
+        self.0.exit(KILLED_EXIT_CODE);
+        Ok(())
+    }
+
+    fn clone_killer(&self) -> Box<dyn ChildKiller + Send + Sync> {
+        Box::new(FakeChild(self.0.clone()))
+    }
+}
+
+impl Child for FakeChild {
+    fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        Ok(self.0.lock().exit_code.map(ExitStatus::with_exit_code))
+    }
+
+    fn wait(&mut self) -> io::Result<ExitStatus> {
+        let (_, ready) = &*self.0.0;
+        let mut terminal = self.0.lock();
+        loop {
+            if let Some(code) = terminal.exit_code {
+                return Ok(ExitStatus::with_exit_code(code));
+            }
+            terminal = ready.wait(terminal).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn process_id(&self) -> Option<u32> {
+        None
+    }
+
+    #[cfg(windows)]
+    fn as_raw_handle(&self) -> Option<std::os::windows::io::RawHandle> {
+        None
+    }
+}
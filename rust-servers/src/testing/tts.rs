@@ -0,0 +1,164 @@
+// 模拟豆包语音合成服务
+// 在本机随机端口上接受 WebSocket 连接，speak 的 doubao.endpoint 指向它即可。
+// 每个连接按提交文本的字数返回 PCM 音频包 (每字 50ms)，可设置发包间隔或返回错误
+
+use flate2::read::GzDecoder;
+use futures_util::{SinkExt, StreamExt};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::Message;
+
+/// 合成音频的采样率
+const SAMPLE_RATE: usize = 24_000;
+
+/// 每个字对应的音频字节数 (50ms，16 位单声道)
+const BYTES_PER_CHAR: usize = SAMPLE_RATE / 20 * 2;
+
+/// 模拟服务的响应
+#[derive(Debug, Clone)]
+enum Reply {
+    /// 按字数返回音频，每个音频包之前等待 `interval`
+    Audio { interval: Duration },
+    /// 错误码和错误信息
+    Error(u32, String),
+}
+
+/// 收到的合成请求
+#[derive(Debug, Clone)]
+pub struct TtsRequest {
+    /// Authorization 请求头
+    pub authorization: String,
+    /// 请求 JSON
+    pub body: serde_json::Value,
+}
+
+impl TtsRequest {
+    /// 提交的文本
+    pub fn text(&self) -> &str {
+        self.body["request"]["text"].as_str().unwrap_or_default()
+    }
+}
+
+/// 模拟豆包语音合成服务 (丢弃时停止监听)
+pub struct MockTts {
+    endpoint: String,
+    requests: Arc<Mutex<Vec<TtsRequest>>>,
+    task: JoinHandle<()>,
+}
+
+impl MockTts {
+    /// 启动立即返回音频的服务
+    pub async fn start() -> Self {
+        Self::with_interval(Duration::ZERO).await
+    }
+
+    /// 启动每个音频包之前等待 `interval` 的服务
+    pub async fn with_interval(interval: Duration) -> Self {
+        Self::serve(Reply::Audio { interval }).await
+    }
+
+    /// 启动总是返回错误的服务
+    pub async fn failing(code: u32, message: &str) -> Self {
+        Self::serve(Reply::Error(code, message.to_string())).await
+    }
+
+    async fn serve(reply: Reply) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock tts");
+        let endpoint = format!("ws://{}/api/v1/tts/ws_binary", listener.local_addr().expect("mock tts address"));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&requests);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle(stream, reply.clone(), Arc::clone(&received)));
+            }
+        });
+        Self { endpoint, requests, task }
+    }
+
+    /// speak 的 doubao 配置
+    pub fn config(&self) -> serde_json::Value {
+        serde_json::json!({
+            "app_id": "mock-app",
+            "access_token": "mock-token",
+            "voice_type": "mock_voice",
+            "endpoint": self.endpoint,
+        })
+    }
+
+    /// 收到的合成请求
+    pub fn requests(&self) -> Vec<TtsRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockTts {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 处理一个连接：读取合成请求后按预设响应
+async fn handle(stream: TcpStream, reply: Reply, requests: Arc<Mutex<Vec<TtsRequest>>>) {
+    let mut authorization = String::new();
+    // 回调的签名由 tungstenite 规定
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, response: Response| {
+        authorization = request.headers().get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        Ok(response)
+    };
+    let Ok(mut ws) = tokio_tungstenite::accept_hdr_async(stream, callback).await else {
+        return;
+    };
+    let Some(Ok(Message::Binary(frame))) = ws.next().await else {
+        return;
+    };
+    let Some(body) = parse_request(&frame) else {
+        return;
+    };
+    let request = TtsRequest { authorization, body };
+    let chars = request.text().chars().count();
+    requests.lock().unwrap().push(request);
+
+    match reply {
+        Reply::Error(code, message) => {
+            let mut frame = vec![0x11, 0xf0, 0x10, 0x00];
+            frame.extend_from_slice(&code.to_be_bytes());
+            frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            frame.extend_from_slice(message.as_bytes());
+            let _ = ws.send(Message::Binary(frame.into())).await;
+        }
+        Reply::Audio { interval } => {
+            // 先发送没有音频的确认包，再每两个字一个音频包
+            let _ = ws.send(Message::Binary(vec![0x11, 0xb0, 0x00, 0x00].into())).await;
+            let packets = chars.div_ceil(2).max(1);
+            for index in 0..packets {
+                tokio::time::sleep(interval).await;
+                let size = (chars.saturating_sub(index * 2)).min(2) * BYTES_PER_CHAR;
+                let sequence = if index + 1 == packets { -(index as i32 + 1) } else { index as i32 + 1 };
+                let mut frame = vec![0x11, 0xb1, 0x00, 0x00];
+                frame.extend_from_slice(&sequence.to_be_bytes());
+                frame.extend_from_slice(&(size as u32).to_be_bytes());
+                frame.resize(frame.len() + size, 0);
+                if ws.send(Message::Binary(frame.into())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+    let _ = ws.close(None).await;
+}
+
+/// 解析完整请求帧中的 JSON (gzip 压缩)
+fn parse_request(frame: &[u8]) -> Option<serde_json::Value> {
+    let size = u32::from_be_bytes(frame.get(4..8)?.try_into().ok()?) as usize;
+    let mut json = String::new();
+    GzDecoder::new(frame.get(8..8 + size)?).read_to_string(&mut json).ok()?;
+    serde_json::from_str(&json).ok()
+}
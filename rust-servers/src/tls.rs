@@ -0,0 +1,208 @@
+// TLS 监听
+// 启用后 TCP 监听改为 wss:// (REST 接口同时改为 https://)，用于服务器和 Obsidian 不在同一台机器上的场景。
+// 首次启动时在数据目录的 tls 子目录生成自签名证书并在之后复用；客户端按启动信息中的证书指纹校验服务器，
+// 不依赖 CA。将该目录下的 cert.pem / key.pem 替换为自己的证书 (PEM 格式) 即可使用其他证书
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{self, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+/// 证书文件名
+const CERT_FILE_NAME: &str = "cert.pem";
+
+/// 私钥文件名
+const KEY_FILE_NAME: &str = "key.pem";
+
+/// 自签名证书的主体名称
+const CERT_COMMON_NAME: &str = "Smart Workflow Server";
+
+/// 自签名证书的主体备用名称 (客户端按指纹校验，不检查主机名)
+const CERT_SUBJECT_ALT_NAMES: &[&str] = &["localhost", "127.0.0.1", "::1"];
+
+/// TLS 错误
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("无法读写证书文件 {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+
+    #[error("生成自签名证书失败: {0}")]
+    Generate(#[from] rcgen::Error),
+
+    #[error("证书文件无效 {path}: {message}")]
+    Invalid { path: PathBuf, message: String },
+}
+
+/// TLS 证书和对应的握手器
+#[derive(Clone)]
+pub struct TlsIdentity {
+    acceptor: TlsAcceptor,
+    fingerprint: String,
+    cert_path: PathBuf,
+}
+
+impl std::fmt::Debug for TlsIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsIdentity")
+            .field("fingerprint", &self.fingerprint)
+            .field("cert_path", &self.cert_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TlsIdentity {
+    /// 加载 `dir` 下的证书和私钥，两者都不存在时生成自签名证书
+    pub fn load_or_generate(dir: &Path) -> Result<Self, TlsError> {
+        let cert_path = dir.join(CERT_FILE_NAME);
+        let key_path = dir.join(KEY_FILE_NAME);
+        if !cert_path.exists() && !key_path.exists() {
+            generate(&cert_path, &key_path)?;
+            log_info!("已生成自签名证书: {}", cert_path.display());
+        }
+        let cert_pem = read(&cert_path)?;
+        let key_pem = read(&key_path)?;
+        Self::from_pem(&cert_pem, &key_pem, &cert_path, &key_path)
+    }
+
+    fn from_pem(cert_pem: &[u8], key_pem: &[u8], cert_path: &Path, key_path: &Path) -> Result<Self, TlsError> {
+        let invalid = |path: &Path, message: String| TlsError::Invalid { path: path.to_path_buf(), message };
+
+        let certs = CertificateDer::pem_slice_iter(cert_pem)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| invalid(cert_path, e.to_string()))?;
+        let Some(leaf) = certs.first() else {
+            return Err(invalid(cert_path, "没有证书".to_string()));
+        };
+        let fingerprint = fingerprint(leaf);
+        let key = PrivateKeyDer::from_pem_slice(key_pem).map_err(|e| invalid(key_path, e.to_string()))?;
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map_err(|e| invalid(cert_path, e.to_string()))?;
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+            fingerprint,
+            cert_path: cert_path.to_path_buf(),
+        })
+    }
+
+    /// TLS 握手器
+    pub fn acceptor(&self) -> &TlsAcceptor {
+        &self.acceptor
+    }
+
+    /// 证书的 SHA-256 指纹 (冒号分隔的大写十六进制，与浏览器显示的格式相同)
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// 证书文件路径
+    pub fn cert_path(&self) -> &Path {
+        &self.cert_path
+    }
+}
+
+/// 生成自签名证书，私钥仅当前用户可读
+fn generate(cert_path: &Path, key_path: &Path) -> Result<(), TlsError> {
+    let names: Vec<String> = CERT_SUBJECT_ALT_NAMES.iter().map(|name| name.to_string()).collect();
+    let mut params = rcgen::CertificateParams::new(names)?;
+    params.distinguished_name.push(rcgen::DnType::CommonName, CERT_COMMON_NAME);
+    let key_pair = rcgen::KeyPair::generate()?;
+    let cert = params.self_signed(&key_pair)?;
+
+    write_private(key_path, key_pair.serialize_pem().as_bytes())?;
+    fs::write(cert_path, cert.pem()).map_err(|source| TlsError::Io { path: cert_path.to_path_buf(), source })
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, TlsError> {
+    fs::read(path).map_err(|source| TlsError::Io { path: path.to_path_buf(), source })
+}
+
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), TlsError> {
+    let io_error = |source| TlsError::Io { path: path.to_path_buf(), source };
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    io::Write::write_all(&mut options.open(path).map_err(io_error)?, contents).map_err(io_error)
+}
+
+/// 证书 (DER) 的 SHA-256 指纹
+fn fingerprint(cert: &CertificateDer<'_>) -> String {
+    Sha256::digest(cert.as_ref())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tls-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_generate_and_reuse() {
+        let dir = test_dir("reuse");
+        let identity = TlsIdentity::load_or_generate(&dir).unwrap();
+        assert_eq!(identity.fingerprint().len(), 32 * 3 - 1);
+        assert!(identity.fingerprint().split(':').all(|byte| byte.len() == 2));
+        assert_eq!(identity.cert_path(), dir.join(CERT_FILE_NAME));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.join(KEY_FILE_NAME)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // 再次启动时复用同一证书
+        let reloaded = TlsIdentity::load_or_generate(&dir).unwrap();
+        assert_eq!(reloaded.fingerprint(), identity.fingerprint());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let dir = test_dir("invalid");
+
+        // 只有证书没有私钥时不覆盖，报告缺少的文件
+        fs::write(dir.join(CERT_FILE_NAME), "not a certificate").unwrap();
+        assert!(matches!(TlsIdentity::load_or_generate(&dir), Err(TlsError::Io { .. })));
+
+        fs::write(dir.join(KEY_FILE_NAME), "not a key").unwrap();
+        assert!(matches!(TlsIdentity::load_or_generate(&dir), Err(TlsError::Invalid { .. })));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,294 @@
+// 豆包 (火山引擎) 流式语音合成
+// 使用 WebSocket 二进制协议：每个连接提交一段文本，服务端边合成边返回 PCM 音频包，
+// 序号为负数的音频包是最后一包
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::time::Duration;
+use thiserror::Error;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, http::HeaderValue, Message};
+use uuid::Uuid;
+
+use crate::network::WsStream;
+use crate::router::{CodedError, ErrorCode, ModuleType};
+
+pub const WEBSOCKET_URL: &str = "wss://openspeech.bytedance.com/api/v1/tts/ws_binary";
+
+/// 默认集群
+const DEFAULT_CLUSTER: &str = "volcano_tts";
+
+/// 默认音色
+const DEFAULT_VOICE_TYPE: &str = "BV001_streaming";
+
+/// 语速的取值范围
+const SPEED_RATIO_RANGE: std::ops::RangeInclusive<f32> = 0.2..=3.0;
+
+/// 等待下一个音频包的超时时间
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 合成错误
+#[derive(Debug, Error)]
+pub enum TtsError {
+    #[error("TTS 配置无效: {0}")]
+    Config(String),
+    #[error("TTS 连接失败: {0}")]
+    Network(String),
+    #[error("TTS 服务返回错误: code={code}, {message}")]
+    Server { code: u32, message: String },
+    #[error("TTS 响应无法解析: {0}")]
+    Protocol(String),
+    #[error("等待 TTS 音频超时")]
+    Timeout,
+}
+
+impl CodedError for TtsError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            TtsError::Config(_) => ErrorCode::InvalidConfig,
+            TtsError::Network(_) => ErrorCode::NetworkError,
+            // 3xxx 为请求参数错误 (如文本过长、音色不存在)，其余为服务端错误
+            TtsError::Server { code, .. } if (3000..4000).contains(code) => ErrorCode::InvalidParams,
+            TtsError::Server { .. } => ErrorCode::HttpError,
+            TtsError::Protocol(_) => ErrorCode::ParseError,
+            TtsError::Timeout => ErrorCode::Timeout,
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        match self {
+            TtsError::Server { code, .. } => !(3000..4000).contains(code),
+            error => error.code().is_retryable(),
+        }
+    }
+}
+
+/// 豆包语音合成配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct DoubaoConfig {
+    pub app_id: String,
+    pub access_token: String,
+    /// 集群 (默认 volcano_tts)
+    #[serde(default)]
+    pub cluster: Option<String>,
+    /// 音色 (默认 BV001_streaming)
+    #[serde(default)]
+    pub voice_type: Option<String>,
+    /// 语速 (0.2-3.0，默认 1.0)
+    #[serde(default)]
+    pub speed_ratio: Option<f32>,
+    /// WebSocket 地址 (默认为官方地址，私有部署或代理时修改)
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+impl DoubaoConfig {
+    pub fn validate(&self) -> Result<(), TtsError> {
+        if self.app_id.trim().is_empty() {
+            return Err(TtsError::Config("app_id 不能为空".to_string()));
+        }
+        if self.access_token.trim().is_empty() {
+            return Err(TtsError::Config("access_token 不能为空".to_string()));
+        }
+        if let Some(speed) = self.speed_ratio.filter(|speed| !SPEED_RATIO_RANGE.contains(speed)) {
+            return Err(TtsError::Config(format!(
+                "speed_ratio 超出范围: {} (应为 {}-{})",
+                speed, SPEED_RATIO_RANGE.start(), SPEED_RATIO_RANGE.end()
+            )));
+        }
+        Ok(())
+    }
+
+    /// 合成请求
+    fn request(&self, text: &str, sample_rate: u32) -> serde_json::Value {
+        serde_json::json!({
+            "app": {
+                "appid": self.app_id,
+                "token": self.access_token,
+                "cluster": self.cluster.as_deref().unwrap_or(DEFAULT_CLUSTER),
+            },
+            "user": { "uid": "smart-workflow" },
+            "audio": {
+                "voice_type": self.voice_type.as_deref().unwrap_or(DEFAULT_VOICE_TYPE),
+                "encoding": "pcm",
+                "rate": sample_rate,
+                "speed_ratio": self.speed_ratio.unwrap_or(1.0),
+            },
+            "request": {
+                "reqid": Uuid::new_v4().to_string(),
+                "text": text,
+                "text_type": "plain",
+                "operation": "submit",
+            },
+        })
+    }
+}
+
+/// 一段文本的流式合成
+pub struct Synthesis {
+    ws: WsStream,
+    done: bool,
+}
+
+impl Synthesis {
+    /// 连接服务并提交文本
+    pub async fn start(config: &DoubaoConfig, text: &str, sample_rate: u32) -> Result<Self, TtsError> {
+        let mut request = config.endpoint.as_deref().unwrap_or(WEBSOCKET_URL)
+            .into_client_request()
+            .map_err(|e| TtsError::Config(format!("无效的 endpoint: {}", e)))?;
+        let authorization = HeaderValue::from_str(&format!("Bearer;{}", config.access_token))
+            .map_err(|_| TtsError::Config("access_token 含有无效字符".to_string()))?;
+        request.headers_mut().insert("Authorization", authorization);
+
+        let (mut ws, _) = crate::network::connect_websocket(ModuleType::Tts, request)
+            .await
+            .map_err(|e| TtsError::Network(e.to_string()))?;
+        let payload = serde_json::to_vec(&config.request(text, sample_rate))
+            .map_err(|e| TtsError::Protocol(e.to_string()))?;
+        ws.send(Message::Binary(build_request(&payload)?.into()))
+            .await
+            .map_err(|e| TtsError::Network(e.to_string()))?;
+        Ok(Self { ws, done: false })
+    }
+
+    /// 下一个音频包 (合成结束后返回 None)
+    pub async fn next_audio(&mut self) -> Result<Option<Vec<u8>>, TtsError> {
+        while !self.done {
+            let message = tokio::time::timeout(RECEIVE_TIMEOUT, self.ws.next())
+                .await
+                .map_err(|_| TtsError::Timeout)?;
+            let data = match message {
+                Some(Ok(Message::Binary(data))) => data,
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(TtsError::Network("连接在合成结束前关闭".to_string()));
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(TtsError::Network(e.to_string())),
+            };
+            match parse_response(&data)? {
+                Response::Audio { data, last } => {
+                    if last {
+                        self.done = true;
+                        let _ = self.ws.close(None).await;
+                    }
+                    if !data.is_empty() {
+                        return Ok(Some(data));
+                    }
+                }
+                Response::Other => {}
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// 构建完整请求帧 (JSON，gzip 压缩)
+fn build_request(payload: &[u8]) -> Result<Vec<u8>, TtsError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(payload).map_err(|e| TtsError::Protocol(format!("Gzip 压缩失败: {}", e)))?;
+    let compressed = encoder.finish().map_err(|e| TtsError::Protocol(format!("Gzip 压缩失败: {}", e)))?;
+
+    // 协议版本 1 / 头部 4 字节，完整请求，JSON 序列化 + gzip
+    let mut frame = vec![0x11, 0x10, 0x11, 0x00];
+    frame.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&compressed);
+    Ok(frame)
+}
+
+/// 服务端响应
+#[derive(Debug, PartialEq)]
+enum Response {
+    /// 音频包
+    Audio { data: Vec<u8>, last: bool },
+    /// 确认包、前端信息等
+    Other,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, TtsError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| TtsError::Protocol(format!("响应不完整: {} bytes", data.len())))
+}
+
+fn parse_response(data: &[u8]) -> Result<Response, TtsError> {
+    if data.len() < 4 {
+        return Err(TtsError::Protocol(format!("响应太短: {} bytes", data.len())));
+    }
+    let header_size = (data[0] & 0x0f) as usize * 4;
+    let message_type = data[1] >> 4;
+    let flags = data[1] & 0x0f;
+    let compression = data[2] & 0x0f;
+
+    match message_type {
+        // 仅音频响应：flags 为 0 时是没有音频的确认包
+        0xb if flags == 0 => Ok(Response::Other),
+        0xb => {
+            let sequence = read_u32(data, header_size)? as i32;
+            let size = read_u32(data, header_size + 4)? as usize;
+            let audio = data.get(header_size + 8..header_size + 8 + size)
+                .ok_or_else(|| TtsError::Protocol(format!("音频包不完整: 需要 {} bytes", size)))?;
+            Ok(Response::Audio { data: audio.to_vec(), last: sequence < 0 })
+        }
+        0xf => {
+            let code = read_u32(data, header_size)?;
+            let size = read_u32(data, header_size + 4)? as usize;
+            let body = data.get(header_size + 8..header_size + 8 + size).unwrap_or_default();
+            let message = if compression == 0x1 {
+                let mut text = String::new();
+                GzDecoder::new(body).read_to_string(&mut text)
+                    .map_err(|e| TtsError::Protocol(format!("Gzip 解压失败: {}", e)))?;
+                text
+            } else {
+                String::from_utf8_lossy(body).into_owned()
+            };
+            Err(TtsError::Server { code, message })
+        }
+        _ => Ok(Response::Other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audio_frame(sequence: i32, audio: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0x11, 0xb1, 0x00, 0x00];
+        frame.extend_from_slice(&sequence.to_be_bytes());
+        frame.extend_from_slice(&(audio.len() as u32).to_be_bytes());
+        frame.extend_from_slice(audio);
+        frame
+    }
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(parse_response(&audio_frame(1, &[1, 2])).unwrap(), Response::Audio { data: vec![1, 2], last: false });
+        assert_eq!(parse_response(&audio_frame(-2, &[3])).unwrap(), Response::Audio { data: vec![3], last: true });
+        assert_eq!(parse_response(&[0x11, 0xb0, 0x00, 0x00]).unwrap(), Response::Other);
+        assert!(matches!(parse_response(&audio_frame(1, &[1, 2])[..10]), Err(TtsError::Protocol(_))));
+
+        let mut error = vec![0x11, 0xf0, 0x10, 0x00];
+        error.extend_from_slice(&3010u32.to_be_bytes());
+        error.extend_from_slice(&4u32.to_be_bytes());
+        error.extend_from_slice(b"long");
+        let error = parse_response(&error).unwrap_err();
+        assert!(matches!(&error, TtsError::Server { code: 3010, message } if message == "long"));
+        assert_eq!(error.code(), ErrorCode::InvalidParams);
+        assert!(!error.retryable());
+    }
+
+    #[test]
+    fn test_validate() {
+        let config: DoubaoConfig = serde_json::from_value(serde_json::json!({ "app_id": "app", "access_token": "token" })).unwrap();
+        assert!(config.validate().is_ok());
+        let request = config.request("你好", 24000);
+        assert_eq!(request["app"]["cluster"], DEFAULT_CLUSTER);
+        assert_eq!(request["audio"]["encoding"], "pcm");
+        assert_eq!(request["request"]["text"], "你好");
+
+        let slow = DoubaoConfig { speed_ratio: Some(0.1), ..config.clone() };
+        assert!(matches!(slow.validate(), Err(TtsError::Config(_))));
+        let anonymous = DoubaoConfig { access_token: " ".to_string(), ..config };
+        assert_eq!(anonymous.validate().unwrap_err().code(), ErrorCode::InvalidConfig);
+    }
+}
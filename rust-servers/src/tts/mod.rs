@@ -0,0 +1,497 @@
+// 语音合成 (朗读) 模块
+// speak 把文本按句切分后逐句调用豆包流式合成，收到的 PCM 音频包立即写入播放流，
+// 第一句的第一个音频包到达即开始出声 (不等待全文合成完毕)，后面的句子在播放前面句子的同时合成。
+// 开始出声时推送 playing 事件，播放完毕推送 ended 事件，合成失败时推送 error 事件。
+// 同一时间只朗读一段文本，新的 speak 替换正在进行的朗读；stop、system/cancel 和 kill_job 可以中止朗读
+
+mod doubao;
+
+pub use doubao::{DoubaoConfig, TtsError};
+
+use doubao::Synthesis;
+
+use crate::jobs::{self, Job, JobKind};
+use crate::playback::{open_output, PcmStream, PlaybackError, PlaybackOutput, Track};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use futures_util::SinkExt;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex as TokioMutex;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "TTS", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "TTS", format!($($arg)*));
+        }
+    };
+}
+
+/// 合成音频的采样率
+const SAMPLE_RATE: u32 = 24_000;
+
+/// 每次合成的最大字节数 (豆包单次请求限制 1024 字节)
+const MAX_SENTENCE_BYTES: usize = 900;
+
+/// 朗读文本的最大字符数
+const MAX_TEXT_CHARS: usize = 100_000;
+
+/// 检查播放是否结束的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 句末标点 (其后切分)
+const SENTENCE_ENDS: &[char] = &['。', '！', '？', '；', '…', '!', '?', ';', '\n'];
+
+/// 紧跟在句末标点后、归入同一句的字符
+const CLOSING: &[char] = &['”', '’', '」', '』', '）', '》', '"', '\'', ')', ']'];
+
+/// 句子过长时优先切分的位置 (其后切分)
+const SOFT_BREAKS: &[char] = &['，', '、', '：', ',', ':', ' '];
+
+/// 创建语音合成模块错误
+fn tts_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Tts, code, message)
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+// ============================================================================
+// 分句
+// ============================================================================
+
+/// 把文本切分为逐句合成的片段：在句末标点 (及其后的引号、括号) 和后接空白的英文句点处切开，
+/// 超过 MAX_SENTENCE_BYTES 的句子在逗号等处或按长度继续切开，空白片段被丢弃
+fn split_sentences(text: &str) -> Vec<String> {
+    fn flush(sentences: &mut Vec<String>, current: &mut String) {
+        let sentence = current.trim();
+        if !sentence.is_empty() {
+            sentences.push(sentence.to_string());
+        }
+        current.clear();
+    }
+
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut ended = false;
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ended && !SENTENCE_ENDS.contains(&ch) && !CLOSING.contains(&ch) {
+            flush(&mut sentences, &mut current);
+            ended = false;
+        }
+        if current.len() + ch.len_utf8() > MAX_SENTENCE_BYTES {
+            match current.rfind(SOFT_BREAKS) {
+                Some(index) => {
+                    let split = index + current[index..].chars().next().map_or(1, char::len_utf8);
+                    let rest = current.split_off(split);
+                    flush(&mut sentences, &mut current);
+                    current = rest;
+                }
+                None => flush(&mut sentences, &mut current),
+            }
+        }
+        current.push(ch);
+        if SENTENCE_ENDS.contains(&ch) || (ch == '.' && chars.peek().is_none_or(|next| next.is_whitespace())) {
+            ended = true;
+        }
+    }
+    flush(&mut sentences, &mut current);
+    sentences
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// 语音合成服务
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Provider {
+    /// 豆包 (火山引擎)
+    #[default]
+    Doubao,
+}
+
+/// speak 请求
+#[derive(Debug, Deserialize)]
+struct SpeakRequest {
+    #[serde(default)]
+    id: Option<String>,
+    text: String,
+    #[serde(default)]
+    provider: Provider,
+    #[serde(default)]
+    doubao: Option<DoubaoConfig>,
+    #[serde(default)]
+    device: Option<String>,
+    #[serde(default)]
+    volume: Option<f32>,
+}
+
+impl SpeakRequest {
+    fn config(&self) -> Result<DoubaoConfig, ModuleError> {
+        let config = match self.provider {
+            Provider::Doubao => self.doubao.clone()
+                .ok_or_else(|| tts_error(ErrorCode::InvalidConfig, "缺少 doubao 配置"))?,
+        };
+        config.validate().map_err(|e| ModuleError::from_error(ModuleType::Tts, &e))?;
+        Ok(config)
+    }
+
+    fn volume(&self) -> Result<f32, ModuleError> {
+        let volume = self.volume.unwrap_or(1.0);
+        if !(0.0..=1.0).contains(&volume) {
+            return Err(tts_error(ErrorCode::InvalidParams, format!("volume 超出范围: {} (应为 0-1)", volume)));
+        }
+        Ok(volume)
+    }
+
+    fn sentences(&self) -> Result<Vec<String>, ModuleError> {
+        if self.text.chars().count() > MAX_TEXT_CHARS {
+            return Err(tts_error(ErrorCode::LimitExceeded, format!("朗读文本过长 (最多 {} 个字符)", MAX_TEXT_CHARS)));
+        }
+        let sentences = split_sentences(&self.text);
+        if sentences.is_empty() {
+            return Err(tts_error(ErrorCode::InvalidParams, "text 不能为空"));
+        }
+        Ok(sentences)
+    }
+}
+
+// ============================================================================
+// 朗读
+// ============================================================================
+
+/// 事件发送目标
+#[derive(Clone)]
+struct Events {
+    sender: WsSender,
+    request_id: Option<String>,
+}
+
+impl Events {
+    async fn send(&self, event: ServerResponse) {
+        let event = event.with_request_id(self.request_id.as_deref());
+        let _ = self.sender.lock().await.send(Message::Text(event.to_json().into())).await;
+    }
+}
+
+/// 正在进行的朗读 (丢弃时中止合成并停止播放)
+struct Speech {
+    id: String,
+    request_id: Option<String>,
+    cancel: CancellationToken,
+    output: Arc<dyn PlaybackOutput>,
+    stream: PcmStream,
+    sentences: usize,
+}
+
+impl Speech {
+    fn payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "state": "speaking",
+            "position_ms": millis(self.output.position()),
+            "synthesized_ms": millis(self.stream.written()),
+            "sentences": self.sentences,
+        })
+    }
+}
+
+impl Drop for Speech {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+type Current = Arc<Mutex<Option<Speech>>>;
+
+fn lock(current: &Current) -> std::sync::MutexGuard<'_, Option<Speech>> {
+    current.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 逐句合成并写入播放流的任务
+struct Reader {
+    id: String,
+    sentences: Vec<String>,
+    config: DoubaoConfig,
+    stream: PcmStream,
+    output: Arc<dyn PlaybackOutput>,
+    events: Events,
+    /// 收到 speak 请求的时刻
+    requested: Instant,
+}
+
+impl Reader {
+    /// 合成全部句子并等待播放完毕，失败时返回出错的句子序号
+    async fn run(&self) -> Result<(), (usize, TtsError)> {
+        let mut playing = false;
+        for (index, sentence) in self.sentences.iter().enumerate() {
+            let mut synthesis = Synthesis::start(&self.config, sentence, SAMPLE_RATE)
+                .await
+                .map_err(|e| (index, e))?;
+            while let Some(audio) = synthesis.next_audio().await.map_err(|e| (index, e))? {
+                self.stream.write(&audio);
+                if !playing {
+                    playing = true;
+                    let first_audio_ms = millis(self.requested.elapsed());
+                    log_info!("开始朗读: id={}, first_audio_ms={}", self.id, first_audio_ms);
+                    self.events.send(ServerResponse::new(ModuleType::Tts, "playing", serde_json::json!({
+                        "id": self.id,
+                        "first_audio_ms": first_audio_ms,
+                    }))).await;
+                }
+            }
+            log_debug!("第 {} 句合成完毕: id={}", index + 1, self.id);
+        }
+        self.stream.finish();
+
+        while !self.output.finished() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        self.events.send(ServerResponse::new(ModuleType::Tts, "ended", serde_json::json!({
+            "id": self.id,
+            "sentences": self.sentences.len(),
+            "duration_ms": millis(self.stream.written()),
+        }))).await;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// 语音合成处理器
+// ============================================================================
+
+/// 语音合成模块处理器
+pub struct TtsHandler {
+    current: Current,
+    /// WebSocket 发送器 (请求没有来源连接时用于推送事件)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl TtsHandler {
+    /// 创建新的语音合成处理器
+    pub fn new() -> Self {
+        Self {
+            current: Arc::new(Mutex::new(None)),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    /// 事件发送目标：优先使用发起请求的连接
+    async fn events(&self, msg: &ModuleMessage) -> Result<Events, ModuleError> {
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+        let sender = sender.ok_or_else(|| tts_error(ErrorCode::NotConnected, "WebSocket sender not set"))?;
+        Ok(Events { sender, request_id: msg.request_id() })
+    }
+
+    /// 处理 speak 消息 - 开始朗读 (替换正在进行的朗读)
+    async fn handle_speak(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let requested = Instant::now();
+        let request: SpeakRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| tts_error(ErrorCode::InvalidParams, format!("无效的 speak 请求: {}", e)))?;
+        let sentences = request.sentences()?;
+        let config = request.config()?;
+        let volume = request.volume()?;
+        let events = self.events(msg).await?;
+
+        let device = request.device.clone();
+        let (output, stream) = tokio::task::spawn_blocking(move || {
+            let (track, stream) = Track::stream(SAMPLE_RATE, 1);
+            let output = open_output().play(track, device.as_deref(), volume)?;
+            Ok::<_, PlaybackError>((output, stream))
+        })
+        .await
+        .map_err(|e| tts_error(ErrorCode::Internal, format!("播放任务失败: {}", e)))?
+        .map_err(|e| ModuleError::from_error(ModuleType::Tts, &e))?;
+
+        let id = request.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let cancel = CancellationToken::new();
+        let speech = Speech {
+            id: id.clone(),
+            request_id: events.request_id.clone(),
+            cancel: cancel.clone(),
+            output: Arc::clone(&output),
+            stream: stream.clone(),
+            sentences: sentences.len(),
+        };
+        let previous = lock(&self.current).replace(speech);
+        if let Some(previous) = previous {
+            log_info!("替换正在进行的朗读: id={}", previous.id);
+        }
+
+        let payload = serde_json::json!({ "id": id, "sentences": sentences.len(), "device": request.device });
+        let reader = Reader { id: id.clone(), sentences, config, stream, output, events: events.clone(), requested };
+        let current = Arc::clone(&self.current);
+        let job = jobs::register(
+            Job::new(JobKind::Speech).with_request_id(events.request_id.clone()).with_detail(id.clone()).with_cancel(cancel.clone()),
+        );
+        tokio::spawn(crate::audit::inherit(async move {
+            let _job = job;
+            let result = tokio::select! {
+                result = reader.run() => Some(result),
+                _ = cancel.cancelled() => None,
+            };
+            {
+                let mut guard = lock(&current);
+                if guard.as_ref().is_some_and(|speech| speech.id == reader.id) {
+                    guard.take();
+                }
+            }
+            match result {
+                Some(Ok(())) => {
+                    log_info!("朗读结束: id={}", reader.id);
+                }
+                Some(Err((index, e))) => {
+                    log_info!("朗读失败: id={}, 第 {} 句: {}", reader.id, index + 1, e);
+                    let error = ModuleError::from_error(ModuleType::Tts, &e);
+                    let mut event = ServerResponse::error(ModuleType::Tts, error.code, &error.message);
+                    event.payload["retryable"] = error.retryable.into();
+                    event.payload["id"] = reader.id.clone().into();
+                    event.payload["sentence"] = index.into();
+                    reader.events.send(event).await;
+                }
+                None => {
+                    log_info!("朗读已中止: id={}", reader.id);
+                }
+            }
+        }));
+
+        Ok(Some(ServerResponse::new(ModuleType::Tts, "speaking", payload)))
+    }
+
+    /// 处理 stop 消息 - 中止朗读 (指定 id 时必须与当前朗读一致)
+    fn handle_stop(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let id: Option<String> = msg.get_field("id");
+        let mut guard = lock(&self.current);
+        let speech = guard.take_if(|speech| id.as_ref().is_none_or(|id| *id == speech.id))
+            .ok_or_else(|| tts_error(ErrorCode::NotFound, format!("没有正在进行的朗读: {}", id.as_deref().unwrap_or("-"))))?;
+        log_info!("停止朗读: id={}", speech.id);
+        Ok(Some(ServerResponse::new(ModuleType::Tts, "stopped", serde_json::json!({
+            "id": speech.id,
+            "position_ms": millis(speech.output.position()),
+        }))))
+    }
+
+    /// 处理 status 消息 - 当前朗读状态
+    fn handle_status(&self) -> ServerResponse {
+        let payload = lock(&self.current)
+            .as_ref()
+            .map(Speech::payload)
+            .unwrap_or_else(|| serde_json::json!({ "state": "idle" }));
+        ServerResponse::new(ModuleType::Tts, "status", payload)
+    }
+}
+
+impl Default for TtsHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 语音合成模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("speak", &[
+        FieldSpec::optional("id", FieldKind::String),
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("provider", FieldKind::String).one_of(&["doubao"]),
+        FieldSpec::optional("doubao", FieldKind::Object),
+        FieldSpec::optional("device", FieldKind::String),
+        FieldSpec::optional("volume", FieldKind::Number),
+    ]),
+    MessageSpec::new("stop", &[FieldSpec::optional("id", FieldKind::String)]),
+    MessageSpec::new("status", &[]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 语音合成 (朗读) 模块
+    pub const Tts: ModuleType = ModuleType::named("tts");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for TtsHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Tts
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    /// 中止朗读 (连接关闭时调用)
+    async fn cleanup(&self) {
+        if let Some(speech) = lock(&self.current).take() {
+            log_info!("连接已关闭，停止朗读: id={}", speech.id);
+        }
+    }
+
+    /// 取消由 request_id 发起的朗读
+    async fn cancel(&self, request_id: &str) -> bool {
+        let speech = lock(&self.current).take_if(|speech| speech.request_id.as_deref() == Some(request_id));
+        match speech {
+            Some(speech) => {
+                log_info!("取消朗读: id={}", speech.id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理语音合成消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "speak" => self.handle_speak(msg).await,
+            "stop" => self.handle_stop(msg),
+            "status" => Ok(Some(self.handle_status())),
+            _ => Err(tts_error(ErrorCode::UnknownMessageType, format!("未知的语音合成消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_sentences() {
+        assert_eq!(
+            split_sentences("第一句。第二句！“第三句？”\n\nFourth one. v1.2 is out!"),
+            vec!["第一句。", "第二句！", "“第三句？”", "Fourth one.", "v1.2 is out!"],
+        );
+        assert_eq!(split_sentences("真的吗？！……好吧"), vec!["真的吗？！……", "好吧"]);
+        assert!(split_sentences(" \n \n").is_empty());
+
+        // 过长的句子优先在逗号处切开，没有逗号时按长度切开
+        let long = format!("{}，{}。", "字".repeat(200), "词".repeat(200));
+        let sentences = split_sentences(&long);
+        assert_eq!(sentences, vec![format!("{}，", "字".repeat(200)), format!("{}。", "词".repeat(200))]);
+        let sentences = split_sentences(&"a".repeat(2000));
+        assert_eq!(sentences.iter().map(String::len).collect::<Vec<_>>(), vec![900, 900, 200]);
+    }
+}
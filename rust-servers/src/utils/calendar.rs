@@ -0,0 +1,1078 @@
+// 日历模块
+// 解析 ICS (RFC 5545) 文本或订阅链接为结构化事件，并为口述的日程生成 ICS
+//
+// 只处理 VEVENT，重复规则 (RRULE) 原样返回而不展开。带 TZID 的时间使用文件中的 VTIMEZONE
+// 换算为固定偏移；找不到定义的时区按本地时间返回，并附带时区名称由插件处理
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::router::{CodedError, ErrorCode, ModuleType};
+use super::datetime;
+use super::language::TextLocale;
+
+/// 请求超时
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 日历文件最大字节数
+pub const MAX_ICS_BYTES: usize = 10 * 1024 * 1024;
+
+/// 最多返回的事件数量
+pub const MAX_EVENTS: usize = 5000;
+
+/// 未指定结束时间时的默认时长 (分钟)
+pub const DEFAULT_DURATION_MINUTES: u32 = 60;
+
+/// User-Agent (部分日历服务会拒绝没有 UA 的请求)
+const USER_AGENT: &str = concat!("smart-workflow-server/", env!("CARGO_PKG_VERSION"));
+
+/// 生成的日历的 PRODID
+const PRODID: &str = "-//Smart Workflow//Calendar//EN";
+
+/// 内容行的最大长度 (字节，不含换行)
+const FOLD_OCTETS: usize = 75;
+
+/// 日历错误
+#[derive(Debug, Error)]
+pub enum CalendarError {
+    #[error("无效的 URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("请求失败: {0}")]
+    Http(String),
+
+    #[error("服务器返回 HTTP {0}")]
+    Status(u16),
+
+    #[error("日历超过 {0} 字节")]
+    TooLarge(usize),
+
+    #[error("无效的 ICS 数据: {0}")]
+    Parse(String),
+
+    #[error("无效的事件时间: '{0}'")]
+    InvalidTime(String),
+
+    #[error("文本中没有找到日期: {0}")]
+    NoDate(String),
+}
+
+impl CodedError for CalendarError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            CalendarError::InvalidUrl(_)
+            | CalendarError::Parse(_)
+            | CalendarError::InvalidTime(_)
+            | CalendarError::NoDate(_) => ErrorCode::InvalidParams,
+            CalendarError::Http(_) => ErrorCode::NetworkError,
+            CalendarError::Status(_) => ErrorCode::HttpError,
+            CalendarError::TooLarge(_) => ErrorCode::LimitExceeded,
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        match self {
+            CalendarError::Status(status) => ErrorCode::is_retryable_status(*status),
+            _ => self.code().is_retryable(),
+        }
+    }
+}
+
+// ============================================================================
+// 结果
+// ============================================================================
+
+/// 解析出的日历
+#[derive(Debug, Clone, Serialize)]
+pub struct Calendar {
+    /// 日历名称 (X-WR-CALNAME)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 事件 (按开始时间排序)
+    pub events: Vec<CalendarEvent>,
+    /// 因缺少或无法解析开始时间而跳过的事件数
+    pub skipped: usize,
+    /// 事件数超过上限而被截断
+    pub truncated: bool,
+}
+
+/// 日历事件
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarEvent {
+    /// 唯一标识
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    /// 标题
+    pub summary: String,
+    /// 描述
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 地点
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// 开始时间 (全天事件为 YYYY-MM-DD，其余为 ISO 8601；不带偏移时为本地时间)
+    pub start: String,
+    /// 结束时间 (不含；格式同 start)
+    pub end: String,
+    /// 是否为全天事件
+    pub all_day: bool,
+    /// 无法换算的时区名称 (此时 start / end 为该时区的本地时间)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// 重复规则 (原样返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rrule: Option<String>,
+    /// 状态 (CONFIRMED / TENTATIVE / CANCELLED)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// 组织者邮箱
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organizer: Option<String>,
+    /// 参与者邮箱
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attendees: Vec<String>,
+    /// 链接
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// 分类
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    #[serde(skip)]
+    start_time: EventTime,
+    #[serde(skip)]
+    end_time: EventTime,
+}
+
+impl CalendarEvent {
+    /// 是否与 [from, to) 区间重叠 (本地时间按区间端点的偏移解释)
+    ///
+    /// 重复事件只要首次发生不晚于区间结束即保留
+    fn overlaps(&self, from: Option<DateTime<FixedOffset>>, to: Option<DateTime<FixedOffset>>) -> bool {
+        let Some(offset) = from.or(to).map(|bound| *bound.offset()) else {
+            return true;
+        };
+        let start = self.start_time.instant(offset);
+        let end = self.end_time.instant(offset);
+        // 没有时长的事件视为时间点
+        let before_end = to.is_none_or(|to| start < to);
+        let after_start = from.is_none_or(|from| end > from || (end == start && start >= from));
+        before_end && (self.rrule.is_some() || after_start)
+    }
+}
+
+/// 事件时间
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EventTime {
+    /// 全天 (仅日期)
+    Date(NaiveDate),
+    /// 带偏移的时间 (UTC 或已换算的时区)
+    Fixed(DateTime<FixedOffset>),
+    /// 本地时间 (浮动时间或无法换算的时区)
+    Floating(NaiveDateTime),
+}
+
+impl EventTime {
+    /// 返回给插件的字符串形式
+    fn to_iso(self) -> String {
+        match self {
+            EventTime::Date(date) => date.format("%Y-%m-%d").to_string(),
+            EventTime::Fixed(datetime) => datetime.to_rfc3339(),
+            EventTime::Floating(naive) => naive.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        }
+    }
+
+    /// ICS 中的属性参数和值
+    fn to_ics(self) -> (&'static str, String) {
+        match self {
+            EventTime::Date(date) => (";VALUE=DATE", date.format("%Y%m%d").to_string()),
+            EventTime::Fixed(datetime) => ("", datetime.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()),
+            EventTime::Floating(naive) => ("", naive.format("%Y%m%dT%H%M%S").to_string()),
+        }
+    }
+
+    /// 时间点 (日期和本地时间按给定偏移解释)
+    fn instant(self, offset: FixedOffset) -> DateTime<FixedOffset> {
+        let naive = match self {
+            EventTime::Fixed(datetime) => return datetime,
+            EventTime::Date(date) => date.and_time(chrono::NaiveTime::MIN),
+            EventTime::Floating(naive) => naive,
+        };
+        naive.and_local_timezone(offset).single().unwrap_or_else(|| naive.and_utc().fixed_offset())
+    }
+
+    fn add(self, duration: Duration) -> Self {
+        match self {
+            EventTime::Date(date) => EventTime::Date(date + Duration::days(duration.num_days())),
+            EventTime::Fixed(datetime) => EventTime::Fixed(datetime + duration),
+            EventTime::Floating(naive) => EventTime::Floating(naive + duration),
+        }
+    }
+}
+
+// ============================================================================
+// 抓取
+// ============================================================================
+
+/// 下载日历订阅 (http / https / webcal)
+pub async fn fetch(url: &str) -> Result<String, CalendarError> {
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    let parsed = reqwest::Url::parse(&url).map_err(|e| CalendarError::InvalidUrl(e.to_string()))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(CalendarError::InvalidUrl(format!("不支持的协议 '{}'", parsed.scheme())));
+    }
+
+    let request = crate::network::client()
+        .get(parsed)
+        .timeout(REQUEST_TIMEOUT)
+        .header(reqwest::header::USER_AGENT, USER_AGENT);
+    let response = crate::network::send(ModuleType::Utils, request)
+        .await
+        .map_err(|e| CalendarError::Http(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(CalendarError::Status(response.status().as_u16()));
+    }
+    if response.content_length().unwrap_or(0) as usize > MAX_ICS_BYTES {
+        return Err(CalendarError::TooLarge(MAX_ICS_BYTES));
+    }
+    let text = response.text().await.map_err(|e| CalendarError::Http(e.to_string()))?;
+    if text.len() > MAX_ICS_BYTES {
+        return Err(CalendarError::TooLarge(MAX_ICS_BYTES));
+    }
+    Ok(text)
+}
+
+// ============================================================================
+// 解析
+// ============================================================================
+
+/// 内容行
+#[derive(Debug, Clone)]
+struct Property {
+    /// 属性名 (大写)
+    name: String,
+    /// 参数 (名称大写，值去掉引号)
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// 时区定义 (VTIMEZONE)
+#[derive(Debug, Clone)]
+struct TimeZoneDef {
+    tzid: String,
+    transitions: Vec<Transition>,
+}
+
+/// 时区的一个 STANDARD / DAYLIGHT 分量
+#[derive(Debug, Clone, Default)]
+struct Transition {
+    offset_from: Option<FixedOffset>,
+    offset_to: Option<FixedOffset>,
+    /// 首次生效的本地时间
+    start: Option<NaiveDateTime>,
+    rule: Option<YearlyRule>,
+}
+
+/// 每年一次的切换规则 (如 3 月最后一个周日)
+#[derive(Debug, Clone, Copy)]
+struct YearlyRule {
+    month: u32,
+    /// 第几个 (负数从月末倒数)
+    week: i32,
+    weekday: Weekday,
+    until: Option<NaiveDateTime>,
+}
+
+impl Transition {
+    /// 不晚于 local 的最近一次生效时间
+    fn last_onset(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = self.start?;
+        let Some(rule) = self.rule else {
+            return (start <= local).then_some(start);
+        };
+        let limit = rule.until.map_or(local, |until| until.min(local));
+        [limit.year(), limit.year() - 1].into_iter().find_map(|year| {
+            let onset = nth_weekday(year, rule.month, rule.week, rule.weekday)?.and_time(start.time());
+            (onset >= start && onset <= limit).then_some(onset)
+        })
+    }
+}
+
+impl TimeZoneDef {
+    /// 本地时间对应的 UTC 偏移
+    fn offset_at(&self, local: NaiveDateTime) -> Option<FixedOffset> {
+        let latest = self
+            .transitions
+            .iter()
+            .filter_map(|transition| Some((transition.last_onset(local)?, transition.offset_to?)))
+            .max_by_key(|(onset, _)| *onset);
+        match latest {
+            Some((_, offset)) => Some(offset),
+            // 早于所有切换时使用最早分量切换前的偏移
+            None => self
+                .transitions
+                .iter()
+                .filter(|transition| transition.start.is_some())
+                .min_by_key(|transition| transition.start)
+                .and_then(|transition| transition.offset_from.or(transition.offset_to)),
+        }
+    }
+}
+
+/// 解析 ICS 文本
+pub fn parse(text: &str) -> Result<Calendar, CalendarError> {
+    let mut stack: Vec<String> = Vec::new();
+    let mut found_calendar = false;
+    let mut name = None;
+    let mut zones: Vec<TimeZoneDef> = Vec::new();
+    let mut raw_events: Vec<Vec<Property>> = Vec::new();
+
+    for line in unfold(text) {
+        let Some(property) = parse_property(&line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.trim().to_ascii_uppercase();
+                match component.as_str() {
+                    "VCALENDAR" => found_calendar = true,
+                    "VEVENT" => raw_events.push(Vec::new()),
+                    "VTIMEZONE" => zones.push(TimeZoneDef { tzid: String::new(), transitions: Vec::new() }),
+                    "STANDARD" | "DAYLIGHT" if stack.last().is_some_and(|top| top == "VTIMEZONE") => {
+                        if let Some(zone) = zones.last_mut() {
+                            zone.transitions.push(Transition::default());
+                        }
+                    }
+                    _ => {}
+                }
+                stack.push(component);
+            }
+            "END" => {
+                let component = property.value.trim().to_ascii_uppercase();
+                if let Some(position) = stack.iter().rposition(|open| *open == component) {
+                    stack.truncate(position);
+                }
+            }
+            _ => match stack.last().map(String::as_str) {
+                Some("VCALENDAR") if property.name == "X-WR-CALNAME" => {
+                    name = Some(unescape_text(&property.value));
+                }
+                Some("VEVENT") => {
+                    if let Some(event) = raw_events.last_mut() {
+                        event.push(property);
+                    }
+                }
+                Some("VTIMEZONE") if property.name == "TZID" => {
+                    if let Some(zone) = zones.last_mut() {
+                        zone.tzid = property.value.trim().to_string();
+                    }
+                }
+                Some("STANDARD" | "DAYLIGHT") => {
+                    if let Some(transition) = zones.last_mut().and_then(|zone| zone.transitions.last_mut()) {
+                        apply_transition_property(transition, &property);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    if !found_calendar {
+        return Err(CalendarError::Parse("缺少 BEGIN:VCALENDAR".to_string()));
+    }
+
+    let mut events = Vec::new();
+    let mut skipped = 0;
+    for properties in &raw_events {
+        match build_event(properties, &zones) {
+            Some(event) => events.push(event),
+            None => skipped += 1,
+        }
+    }
+    events.sort_by_key(|event| event.start_time.instant(Utc.fix()));
+    let truncated = events.len() > MAX_EVENTS;
+    events.truncate(MAX_EVENTS);
+
+    Ok(Calendar { name, events, skipped, truncated })
+}
+
+/// 只保留与 [from, to) 重叠的事件
+pub fn filter_range(calendar: &mut Calendar, from: Option<DateTime<FixedOffset>>, to: Option<DateTime<FixedOffset>>) {
+    calendar.events.retain(|event| event.overlaps(from, to));
+}
+
+/// 展开折叠行 (以空格或制表符开头的行续接上一行)
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// 解析内容行 `NAME;PARAM=VALUE:value` (参数值可以用引号包含 `:` 和 `;`)
+fn parse_property(line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let mut separators = Vec::new();
+    let mut colon = None;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => separators.push(index),
+            ':' if !in_quotes => {
+                colon = Some(index);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let colon = colon?;
+    let mut bounds = separators;
+    bounds.push(colon);
+
+    let name = line[..bounds[0]].trim().to_ascii_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+    let params = bounds
+        .windows(2)
+        .filter_map(|pair| {
+            let (key, value) = line[pair[0] + 1..pair[1]].split_once('=')?;
+            Some((key.trim().to_ascii_uppercase(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect();
+    Some(Property { name, params, value: line[colon + 1..].to_string() })
+}
+
+/// 记录 STANDARD / DAYLIGHT 分量的属性
+fn apply_transition_property(transition: &mut Transition, property: &Property) {
+    let value = property.value.trim();
+    match property.name.as_str() {
+        "TZOFFSETFROM" => transition.offset_from = parse_utc_offset(value),
+        "TZOFFSETTO" => transition.offset_to = parse_utc_offset(value),
+        "DTSTART" => transition.start = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok(),
+        "RRULE" => transition.rule = parse_yearly_rule(value),
+        _ => {}
+    }
+}
+
+/// 解析时区切换规则 (FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU)
+fn parse_yearly_rule(value: &str) -> Option<YearlyRule> {
+    let mut month = None;
+    let mut byday = None;
+    let mut until = None;
+    for part in value.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key.trim().to_ascii_uppercase().as_str() {
+            "FREQ" if !value.eq_ignore_ascii_case("YEARLY") => return None,
+            "BYMONTH" => month = value.parse::<u32>().ok(),
+            "BYDAY" => byday = Some(value.trim().to_ascii_uppercase()),
+            "UNTIL" => {
+                let value = value.trim().trim_end_matches('Z');
+                until = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+                    .ok()
+                    .or_else(|| NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|date| date.and_time(chrono::NaiveTime::MIN)));
+            }
+            _ => {}
+        }
+    }
+    let byday = byday?;
+    let split = byday.len().checked_sub(2)?;
+    let weekday = match &byday[split..] {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let week = byday[..split].trim_start_matches('+').parse::<i32>().ok().filter(|week| *week != 0)?;
+    Some(YearlyRule { month: month?, week, weekday, until })
+}
+
+/// 某月的第 n 个星期几 (n 为负数时从月末倒数)
+fn nth_weekday(year: i32, month: u32, week: i32, weekday: Weekday) -> Option<NaiveDate> {
+    if week > 0 {
+        return NaiveDate::from_weekday_of_month_opt(year, month, weekday, u8::try_from(week).ok()?);
+    }
+    let next_month = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }?;
+    let last = next_month.pred_opt()?;
+    let back = (last.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    let date = last - Duration::days(back as i64 + 7 * (-week as i64 - 1));
+    (date.month() == month).then_some(date)
+}
+
+/// 解析 UTC 偏移 (+0100 / -053000)
+fn parse_utc_offset(value: &str) -> Option<FixedOffset> {
+    let (sign, digits) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    if !matches!(digits.len(), 4 | 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[0..2].parse().ok()?;
+    let minutes: i32 = digits[2..4].parse().ok()?;
+    let seconds: i32 = digits.get(4..6).map_or(Some(0), |s| s.parse().ok())?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// 解析时长 (P1W / PT1H30M / -P1DT2H)
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (sign, rest) = match value.as_bytes().first()? {
+        b'-' => (-1, &value[1..]),
+        b'+' => (1, &value[1..]),
+        _ => (1, value),
+    };
+    let rest = rest.strip_prefix('P')?;
+    let mut total = 0i64;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += n * match (c, in_time) {
+                    ('W', false) => 7 * 86400,
+                    ('D', false) => 86400,
+                    ('H', true) => 3600,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    _ => return None,
+                };
+            }
+            _ => return None,
+        }
+    }
+    number.is_empty().then(|| Duration::seconds(sign * total))
+}
+
+/// 解析 DTSTART / DTEND，返回 (时间, 无法换算的时区名称)
+fn parse_event_time(property: &Property, zones: &[TimeZoneDef]) -> Option<(EventTime, Option<String>)> {
+    let value = property.value.trim();
+    if property.param("VALUE").is_some_and(|kind| kind.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|date| (EventTime::Date(date), None));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((EventTime::Fixed(naive.and_utc().fixed_offset()), None));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let Some(tzid) = property.param("TZID") else {
+        return Some((EventTime::Floating(naive), None));
+    };
+    let offset = zones
+        .iter()
+        .find(|zone| zone.tzid == tzid)
+        .and_then(|zone| zone.offset_at(naive))
+        .or_else(|| matches!(tzid.trim_start_matches('/'), "UTC" | "Etc/UTC" | "GMT" | "Etc/GMT").then(|| Utc.fix()));
+    match offset.and_then(|offset| naive.and_local_timezone(offset).single()) {
+        Some(datetime) => Some((EventTime::Fixed(datetime), None)),
+        None => Some((EventTime::Floating(naive), Some(tzid.to_string()))),
+    }
+}
+
+/// 由 VEVENT 的属性构建事件 (缺少或无法解析开始时间时返回 None)
+fn build_event(properties: &[Property], zones: &[TimeZoneDef]) -> Option<CalendarEvent> {
+    let find = |name: &str| properties.iter().find(|property| property.name == name);
+    let text = |name: &str| find(name).map(|property| unescape_text(&property.value)).filter(|value| !value.is_empty());
+
+    let (start, timezone) = parse_event_time(find("DTSTART")?, zones)?;
+    let end = match find("DTEND").and_then(|property| parse_event_time(property, zones)) {
+        Some((end, _)) => end,
+        None => match find("DURATION").and_then(|property| parse_duration(&property.value)) {
+            Some(duration) => start.add(duration),
+            None if matches!(start, EventTime::Date(_)) => start.add(Duration::days(1)),
+            None => start,
+        },
+    };
+
+    let attendees = properties
+        .iter()
+        .filter(|property| property.name == "ATTENDEE")
+        .map(|property| strip_mailto(&property.value))
+        .collect();
+    let categories = properties
+        .iter()
+        .filter(|property| property.name == "CATEGORIES")
+        .flat_map(|property| split_text_list(&property.value))
+        .collect();
+
+    Some(CalendarEvent {
+        uid: text("UID"),
+        summary: text("SUMMARY").unwrap_or_default(),
+        description: text("DESCRIPTION"),
+        location: text("LOCATION"),
+        start: start.to_iso(),
+        end: end.to_iso(),
+        all_day: matches!(start, EventTime::Date(_)),
+        timezone,
+        rrule: find("RRULE").map(|property| property.value.trim().to_string()),
+        status: find("STATUS").map(|property| property.value.trim().to_ascii_uppercase()),
+        organizer: find("ORGANIZER").map(|property| strip_mailto(&property.value)),
+        attendees,
+        url: find("URL").map(|property| property.value.trim().to_string()),
+        categories,
+        start_time: start,
+        end_time: end,
+    })
+}
+
+/// 去掉 mailto: 前缀
+fn strip_mailto(value: &str) -> String {
+    let value = value.trim();
+    match value.get(..7) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("mailto:") => value[7..].to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// 反转义 TEXT 值 (\n、\,、\;、\\)
+fn unescape_text(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result.trim().to_string()
+}
+
+/// 拆分以逗号分隔的 TEXT 列表 (忽略转义的逗号)
+fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            ',' if !escaped => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    items.push(current);
+    items.iter().map(|item| unescape_text(item)).filter(|item| !item.is_empty()).collect()
+}
+
+// ============================================================================
+// 生成
+// ============================================================================
+
+/// 要生成的事件
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventInput {
+    /// 标题
+    pub summary: String,
+    /// 开始时间 (RFC 3339、本地时间 YYYY-MM-DDTHH:MM[:SS] 或全天事件的 YYYY-MM-DD)
+    pub start: String,
+    /// 结束时间 (格式同 start)
+    #[serde(default)]
+    pub end: Option<String>,
+    /// 未指定结束时间时的时长 (分钟，全天事件忽略)
+    #[serde(default)]
+    pub duration_minutes: Option<u32>,
+    /// 地点
+    #[serde(default)]
+    pub location: Option<String>,
+    /// 描述
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 唯一标识 (缺省自动生成)
+    #[serde(default)]
+    pub uid: Option<String>,
+}
+
+/// 生成结果
+#[derive(Debug, Clone)]
+pub struct GeneratedCalendar {
+    /// ICS 文本 (CRLF 换行)
+    pub ics: String,
+    /// 生成的事件 (含补全的 UID 和结束时间)
+    pub events: Vec<CalendarEvent>,
+}
+
+/// 从口述文本中提取日程：识别日期时间作为开始时间，其余部分作为标题
+pub fn event_from_text(
+    text: &str,
+    locale: TextLocale,
+    reference: DateTime<FixedOffset>,
+) -> Result<EventInput, CalendarError> {
+    let parsed = datetime::parse(text, locale, reference)
+        .ok_or_else(|| CalendarError::NoDate(text.to_string()))?;
+    let start = if parsed.has_time {
+        parsed.datetime.clone()
+    } else {
+        parsed.datetime.chars().take(10).collect()
+    };
+
+    Ok(EventInput {
+        summary: summary_without(text, &parsed.matched_text),
+        start,
+        end: None,
+        duration_minutes: None,
+        location: None,
+        description: None,
+        uid: None,
+    })
+}
+
+/// 去掉日期片段后的标题 (为空时使用原文)
+fn summary_without(text: &str, matched: &str) -> String {
+    let is_separator = |c: char| c.is_whitespace() || ",，。.:：;；、!！".contains(c);
+    let summary = text
+        .replacen(matched, " ", 1)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(is_separator)
+        .to_string();
+    if summary.is_empty() {
+        text.trim().to_string()
+    } else {
+        summary
+    }
+}
+
+/// 生成 ICS 日历
+pub fn generate(inputs: &[EventInput], now: DateTime<Utc>) -> Result<GeneratedCalendar, CalendarError> {
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    let mut events = Vec::with_capacity(inputs.len());
+
+    for input in inputs {
+        let start = parse_input_time(&input.start)?;
+        let end = match (&input.end, start) {
+            (Some(end), _) => parse_input_time(end)?,
+            (None, EventTime::Date(_)) => start.add(Duration::days(1)),
+            (None, _) => {
+                let minutes = input.duration_minutes.unwrap_or(DEFAULT_DURATION_MINUTES);
+                start.add(Duration::minutes(minutes as i64))
+            }
+        };
+        let utc = Utc.fix();
+        if matches!(start, EventTime::Date(_)) != matches!(end, EventTime::Date(_))
+            || end.instant(utc) < start.instant(utc)
+        {
+            return Err(CalendarError::InvalidTime(input.end.clone().unwrap_or_default()));
+        }
+        let uid = input
+            .uid
+            .clone()
+            .unwrap_or_else(|| format!("{}@smart-workflow", Uuid::new_v4()));
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", uid));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        let (params, value) = start.to_ics();
+        lines.push(format!("DTSTART{}:{}", params, value));
+        let (params, value) = end.to_ics();
+        lines.push(format!("DTEND{}:{}", params, value));
+        lines.push(format!("SUMMARY:{}", escape_text(&input.summary)));
+        if let Some(location) = &input.location {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(description) = &input.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        lines.push("END:VEVENT".to_string());
+
+        events.push(CalendarEvent {
+            uid: Some(uid),
+            summary: input.summary.clone(),
+            description: input.description.clone(),
+            location: input.location.clone(),
+            start: start.to_iso(),
+            end: end.to_iso(),
+            all_day: matches!(start, EventTime::Date(_)),
+            timezone: None,
+            rrule: None,
+            status: None,
+            organizer: None,
+            attendees: Vec::new(),
+            url: None,
+            categories: Vec::new(),
+            start_time: start,
+            end_time: end,
+        });
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in &lines {
+        fold_line(line, &mut ics);
+    }
+    Ok(GeneratedCalendar { ics, events })
+}
+
+/// 解析插件提供的时间
+fn parse_input_time(value: &str) -> Result<EventTime, CalendarError> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(EventTime::Fixed(datetime));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(EventTime::Date(date));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .map(EventTime::Floating)
+        .map_err(|_| CalendarError::InvalidTime(value.to_string()))
+}
+
+/// 转义 TEXT 值
+fn escape_text(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            ';' => result.push_str("\\;"),
+            ',' => result.push_str("\\,"),
+            '\n' => result.push_str("\\n"),
+            '\r' => {}
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// 按 75 字节折叠内容行 (不拆分 UTF-8 字符)，以 CRLF 结尾
+fn fold_line(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > FOLD_OCTETS {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+X-WR-CALNAME:Team\r\n\
+BEGIN:VTIMEZONE\r\n\
+TZID:Europe/Berlin\r\n\
+BEGIN:DAYLIGHT\r\n\
+TZOFFSETFROM:+0100\r\n\
+TZOFFSETTO:+0200\r\n\
+DTSTART:19700329T020000\r\n\
+RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\n\
+END:DAYLIGHT\r\n\
+BEGIN:STANDARD\r\n\
+TZOFFSETFROM:+0200\r\n\
+TZOFFSETTO:+0100\r\n\
+DTSTART:19701025T030000\r\n\
+RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r\n\
+END:STANDARD\r\n\
+END:VTIMEZONE\r\n\
+BEGIN:VEVENT\r\n\
+UID:summer@example.com\r\n\
+DTSTART;TZID=Europe/Berlin:20260715T150000\r\n\
+DURATION:PT1H30M\r\n\
+SUMMARY:Design review\\, round 2\r\n\
+DESCRIPTION:Agenda:\\nslides\r\n\
+\x20and demo\r\n\
+ORGANIZER;CN=\"Doe; Jane\":mailto:jane@example.com\r\n\
+ATTENDEE;CN=Bob:MAILTO:bob@example.com\r\n\
+CATEGORIES:Work,Design\r\n\
+BEGIN:VALARM\r\n\
+DESCRIPTION:Reminder\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+DTSTART;TZID=Europe/Berlin:20261201T090000\r\n\
+DTEND;TZID=Europe/Berlin:20261201T100000\r\n\
+SUMMARY:Standup\r\n\
+RRULE:FREQ=WEEKLY;BYDAY=TU\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+DTSTART;VALUE=DATE:20260101\r\n\
+SUMMARY:Holiday\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+SUMMARY:Broken\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_events_with_vtimezone() {
+        let calendar = parse(SAMPLE).unwrap();
+        assert_eq!(calendar.name.as_deref(), Some("Team"));
+        assert_eq!(calendar.skipped, 1);
+        assert_eq!(calendar.events.len(), 3);
+
+        let holiday = &calendar.events[0];
+        assert!(holiday.all_day);
+        assert_eq!((holiday.start.as_str(), holiday.end.as_str()), ("2026-01-01", "2026-01-02"));
+
+        let review = &calendar.events[1];
+        assert_eq!(review.summary, "Design review, round 2");
+        assert_eq!(review.description.as_deref(), Some("Agenda:\nslidesand demo"));
+        assert_eq!(review.start, "2026-07-15T15:00:00+02:00");
+        assert_eq!(review.end, "2026-07-15T16:30:00+02:00");
+        assert_eq!(review.organizer.as_deref(), Some("jane@example.com"));
+        assert_eq!(review.attendees, vec!["bob@example.com"]);
+        assert_eq!(review.categories, vec!["Work", "Design"]);
+
+        let standup = &calendar.events[2];
+        assert_eq!(standup.start, "2026-12-01T09:00:00+01:00");
+        assert_eq!(standup.rrule.as_deref(), Some("FREQ=WEEKLY;BYDAY=TU"));
+    }
+
+    #[test]
+    fn test_unknown_timezone_and_floating_times() {
+        let ics = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART;TZID=Asia/Shanghai:20261021T150000\nSUMMARY:A\nEND:VEVENT\n\
+BEGIN:VEVENT\nDTSTART:20261022T080000Z\nDTEND:20261022T090000Z\nSUMMARY:B\nEND:VEVENT\nEND:VCALENDAR\n";
+        let calendar = parse(ics).unwrap();
+        let floating = &calendar.events[0];
+        assert_eq!(floating.start, "2026-10-21T15:00:00");
+        assert_eq!(floating.end, floating.start);
+        assert_eq!(floating.timezone.as_deref(), Some("Asia/Shanghai"));
+        assert_eq!(calendar.events[1].start, "2026-10-22T08:00:00+00:00");
+    }
+
+    #[test]
+    fn test_filter_range() {
+        let mut calendar = parse(SAMPLE).unwrap();
+        let from = DateTime::parse_from_rfc3339("2026-07-15T15:00:00+02:00").unwrap();
+        let to = DateTime::parse_from_rfc3339("2026-08-01T00:00:00+02:00").unwrap();
+        filter_range(&mut calendar, Some(from), Some(to));
+        let summaries: Vec<&str> = calendar.events.iter().map(|event| event.summary.as_str()).collect();
+        assert_eq!(summaries, vec!["Design review, round 2"]);
+    }
+
+    #[test]
+    fn test_parse_rejects_non_calendar() {
+        assert!(matches!(parse("hello"), Err(CalendarError::Parse(_))));
+    }
+
+    #[test]
+    fn test_duration_and_offset() {
+        assert_eq!(parse_duration("P1W"), Some(Duration::days(7)));
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_duration("P1DT2H"), Some(Duration::hours(26)));
+        assert_eq!(parse_duration("PT1D"), None);
+        assert_eq!(parse_utc_offset("-0530"), FixedOffset::west_opt(5 * 3600 + 30 * 60));
+        assert_eq!(nth_weekday(2026, 3, -1, Weekday::Sun), NaiveDate::from_ymd_opt(2026, 3, 29));
+        assert_eq!(nth_weekday(2026, 11, 1, Weekday::Sun), NaiveDate::from_ymd_opt(2026, 11, 1));
+    }
+
+    #[test]
+    fn test_event_from_dictation() {
+        let reference = DateTime::parse_from_rfc3339("2026-10-14T10:00:00+08:00").unwrap();
+        let event = event_from_text("下周三下午三点和设计组开会", TextLocale::Auto, reference).unwrap();
+        assert_eq!(event.start, "2026-10-21T15:00:00+08:00");
+        assert_eq!(event.summary, "和设计组开会");
+
+        let event = event_from_text("后天 体检", TextLocale::Auto, reference).unwrap();
+        assert_eq!(event.start, "2026-10-16");
+        assert_eq!(event.summary, "体检");
+
+        assert!(matches!(
+            event_from_text("buy milk", TextLocale::Auto, reference),
+            Err(CalendarError::NoDate(_))
+        ));
+    }
+
+    #[test]
+    fn test_generate_round_trip() {
+        let now = DateTime::parse_from_rfc3339("2026-10-14T02:00:00Z").unwrap().with_timezone(&Utc);
+        let inputs = vec![
+            EventInput {
+                summary: "Review; notes, v2".to_string(),
+                start: "2026-10-21T15:00:00+08:00".to_string(),
+                end: None,
+                duration_minutes: Some(30),
+                location: Some("会议室 A".to_string()),
+                description: Some("第一行\n".to_string() + &"很长的描述".repeat(20)),
+                uid: Some("fixed@example.com".to_string()),
+            },
+            EventInput {
+                summary: "Holiday".to_string(),
+                start: "2026-10-16".to_string(),
+                end: None,
+                duration_minutes: None,
+                location: None,
+                description: None,
+                uid: None,
+            },
+        ];
+        let generated = generate(&inputs, now).unwrap();
+        assert!(generated.ics.contains("DTSTART:20261021T070000Z\r\n"));
+        assert!(generated.ics.contains("DTEND:20261021T073000Z\r\n"));
+        assert!(generated.ics.contains("SUMMARY:Review\\; notes\\, v2\r\n"));
+        assert!(generated.ics.contains("DTSTART;VALUE=DATE:20261016\r\n"));
+        assert!(generated.ics.lines().all(|line| line.len() <= FOLD_OCTETS));
+        assert_eq!(generated.events[1].end, "2026-10-17");
+
+        let parsed = parse(&generated.ics).unwrap();
+        assert_eq!(parsed.events.len(), 2);
+        let review = parsed.events.iter().find(|event| event.uid.as_deref() == Some("fixed@example.com")).unwrap();
+        assert_eq!(review.summary, "Review; notes, v2");
+        assert_eq!(review.location.as_deref(), Some("会议室 A"));
+        assert!(review.description.as_deref().unwrap().ends_with("很长的描述"));
+    }
+
+    #[test]
+    fn test_generate_rejects_bad_times() {
+        let now = Utc::now();
+        let mut input = EventInput {
+            summary: "x".to_string(),
+            start: "tomorrow".to_string(),
+            end: None,
+            duration_minutes: None,
+            location: None,
+            description: None,
+            uid: None,
+        };
+        assert!(matches!(generate(&[input.clone()], now), Err(CalendarError::InvalidTime(_))));
+        input.start = "2026-10-21T15:00".to_string();
+        input.end = Some("2026-10-21T14:00".to_string());
+        assert!(matches!(generate(&[input], now), Err(CalendarError::InvalidTime(_))));
+    }
+}
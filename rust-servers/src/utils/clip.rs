@@ -0,0 +1,509 @@
+// 网页剪藏模块
+// 抓取网页，使用 Readability 算法提取正文并转换为 Markdown
+//
+// 可选下载正文中的图片，以 base64 返回并将 Markdown 中的链接改写为本地文件名，
+// 由插件负责写入附件目录
+
+use base64::Engine;
+use dom_smoothie::{Config, Readability};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode, ModuleType};
+use crate::system::progress::{percent_between, ProgressReporter};
+use super::slug::{self, SlugPlatform, SlugStyle};
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Clip", format!($($arg)*));
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Error, "Clip", format!($($arg)*));
+    };
+}
+
+/// 请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 网页最大字节数
+const MAX_PAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// 单张图片最大字节数
+const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// 最多下载的图片数量
+const MAX_IMAGES: usize = 50;
+
+/// Readability 解析的最大元素数 (防止超大页面耗尽 CPU)
+const MAX_ELEMENTS_TO_PARSE: usize = 50_000;
+
+/// User-Agent (部分站点会拒绝没有 UA 的请求)
+const USER_AGENT: &str = concat!("smart-workflow-server/", env!("CARGO_PKG_VERSION"));
+
+/// 剪藏错误
+#[derive(Debug, Error)]
+pub enum ClipError {
+    #[error("无效的 URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("请求失败: {0}")]
+    Http(String),
+
+    #[error("服务器返回 HTTP {0}")]
+    Status(u16),
+
+    #[error("不支持的内容类型: {0}")]
+    NotHtml(String),
+
+    #[error("页面超过 {0} 字节")]
+    TooLarge(usize),
+
+    #[error("无法提取正文: {0}")]
+    Extract(String),
+}
+
+impl CodedError for ClipError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            ClipError::InvalidUrl(_) | ClipError::NotHtml(_) => ErrorCode::InvalidParams,
+            ClipError::Http(_) => ErrorCode::NetworkError,
+            ClipError::Status(_) => ErrorCode::HttpError,
+            ClipError::TooLarge(_) => ErrorCode::LimitExceeded,
+            ClipError::Extract(_) => ErrorCode::Internal,
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        match self {
+            ClipError::Status(status) => ErrorCode::is_retryable_status(*status),
+            _ => self.code().is_retryable(),
+        }
+    }
+}
+
+// ============================================================================
+// 结果
+// ============================================================================
+
+/// 剪藏结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ClippedArticle {
+    /// 最终 URL (跟随重定向后)
+    pub url: String,
+    /// 标题
+    pub title: String,
+    /// 作者
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub byline: Option<String>,
+    /// 摘要
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    /// 站点名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+    /// 发布时间 (原始字符串)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_time: Option<String>,
+    /// 正文 Markdown
+    pub markdown: String,
+    /// 已下载的图片 (仅在 download_images 时)
+    pub images: Vec<ClippedImage>,
+}
+
+/// 已下载的图片
+#[derive(Debug, Clone, Serialize)]
+pub struct ClippedImage {
+    /// 原始 URL
+    pub url: String,
+    /// Markdown 中引用的文件名
+    pub filename: String,
+    /// MIME 类型
+    pub mime_type: String,
+    /// base64 编码的图片数据
+    pub data: String,
+}
+
+/// 正文提取结果 (在阻塞线程中生成)
+struct ExtractedArticle {
+    title: String,
+    byline: Option<String>,
+    excerpt: Option<String>,
+    site_name: Option<String>,
+    published_time: Option<String>,
+    content_html: String,
+}
+
+// ============================================================================
+// 剪藏器
+// ============================================================================
+
+/// 网页剪藏器
+pub struct WebClipper {
+    client: reqwest::Client,
+}
+
+impl WebClipper {
+    /// 创建新的剪藏器
+    pub fn new() -> Self {
+        Self { client: crate::network::client() }
+    }
+
+    /// 发送 GET 请求 (带超时和 User-Agent)
+    async fn get(&self, url: impl reqwest::IntoUrl) -> Result<reqwest::Response, ClipError> {
+        let request = self.client
+            .get(url)
+            .timeout(REQUEST_TIMEOUT)
+            .header(reqwest::header::USER_AGENT, USER_AGENT);
+        crate::network::send(ModuleType::Utils, request)
+            .await
+            .map_err(|e| ClipError::Http(e.to_string()))
+    }
+
+    /// 抓取并提取网页正文
+    ///
+    /// 依次报告 fetching、extracting 和 downloading_images (仅下载图片时) 阶段的进度
+    pub async fn clip(
+        &self,
+        url: &str,
+        download_images: bool,
+        progress: &ProgressReporter,
+    ) -> Result<ClippedArticle, ClipError> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| ClipError::InvalidUrl(e.to_string()))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(ClipError::InvalidUrl(format!(
+                "unsupported scheme '{}'",
+                parsed.scheme()
+            )));
+        }
+
+        progress.report("fetching", Some(0), None).await;
+        let (final_url, html) = self.fetch_page(parsed).await?;
+        log_info!("网页已下载: url={}, bytes={}", final_url, html.len());
+
+        progress.report("extracting", Some(40), None).await;
+        let document_url = final_url.clone();
+        let article = tokio::task::spawn_blocking(move || extract_article(&html, &document_url))
+            .await
+            .map_err(|e| ClipError::Extract(e.to_string()))??;
+
+        let mut markdown = htmd::convert(&article.content_html)
+            .map_err(|e| ClipError::Extract(e.to_string()))?
+            .trim()
+            .to_string();
+
+        let images = if download_images {
+            self.download_images(&mut markdown, progress).await
+        } else {
+            Vec::new()
+        };
+
+        Ok(ClippedArticle {
+            url: final_url,
+            title: article.title,
+            byline: article.byline,
+            excerpt: article.excerpt,
+            site_name: article.site_name,
+            published_time: article.published_time,
+            markdown,
+            images,
+        })
+    }
+
+    /// 下载网页 HTML，返回 (最终 URL, HTML 文本)
+    async fn fetch_page(&self, url: reqwest::Url) -> Result<(String, String), ClipError> {
+        let response = self.get(url).await?;
+
+        if !response.status().is_success() {
+            return Err(ClipError::Status(response.status().as_u16()));
+        }
+
+        let content_type = header_content_type(&response);
+        if !content_type.is_empty() && !content_type.contains("html") {
+            return Err(ClipError::NotHtml(content_type));
+        }
+
+        if response.content_length().unwrap_or(0) as usize > MAX_PAGE_BYTES {
+            return Err(ClipError::TooLarge(MAX_PAGE_BYTES));
+        }
+
+        let final_url = response.url().to_string();
+        let html = response.text().await.map_err(|e| ClipError::Http(e.to_string()))?;
+        if html.len() > MAX_PAGE_BYTES {
+            return Err(ClipError::TooLarge(MAX_PAGE_BYTES));
+        }
+
+        Ok((final_url, html))
+    }
+
+    /// 下载 Markdown 中引用的图片，并将链接改写为本地文件名
+    ///
+    /// 下载失败的图片保留原始链接
+    async fn download_images(&self, markdown: &mut String, progress: &ProgressReporter) -> Vec<ClippedImage> {
+        let urls: Vec<String> = image_urls(markdown)
+            .into_iter()
+            .filter(|u| u.starts_with("http://") || u.starts_with("https://"))
+            .take(MAX_IMAGES)
+            .collect();
+
+        let mut images = Vec::new();
+        let mut used_names = HashSet::new();
+
+        let total = urls.len();
+        for (index, url) in urls.into_iter().enumerate() {
+            let percent = percent_between(60, 100, index, total);
+            progress.report("downloading_images", Some(percent), Some(format!("{}/{}", index + 1, total))).await;
+            match self.fetch_image(&url).await {
+                Ok((bytes, mime_type)) => {
+                    let filename = unique_filename(&url, &mime_type, &mut used_names);
+                    *markdown = markdown.replace(&format!("]({}", url), &format!("]({}", filename));
+                    images.push(ClippedImage {
+                        url,
+                        filename,
+                        mime_type,
+                        data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+                    });
+                }
+                Err(e) => {
+                    log_error!("图片下载失败: url={}, error={}", url, e);
+                }
+            }
+        }
+
+        images
+    }
+
+    /// 下载单张图片，返回 (数据, MIME 类型)
+    async fn fetch_image(&self, url: &str) -> Result<(Vec<u8>, String), ClipError> {
+        let response = self.get(url).await?;
+
+        if !response.status().is_success() {
+            return Err(ClipError::Status(response.status().as_u16()));
+        }
+
+        let mime_type = header_content_type(&response);
+        if !mime_type.starts_with("image/") {
+            return Err(ClipError::NotHtml(mime_type));
+        }
+        if response.content_length().unwrap_or(0) as usize > MAX_IMAGE_BYTES {
+            return Err(ClipError::TooLarge(MAX_IMAGE_BYTES));
+        }
+
+        let bytes = response.bytes().await.map_err(|e| ClipError::Http(e.to_string()))?;
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return Err(ClipError::TooLarge(MAX_IMAGE_BYTES));
+        }
+
+        Ok((bytes.to_vec(), mime_type))
+    }
+}
+
+impl Default for WebClipper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// 辅助函数
+// ============================================================================
+
+/// 读取 Content-Type (不含参数，小写)
+fn header_content_type(response: &reqwest::Response) -> String {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+/// 使用 Readability 提取正文 (dom 树不是 Send，需在单个线程内完成)
+fn extract_article(html: &str, document_url: &str) -> Result<ExtractedArticle, ClipError> {
+    let config = Config {
+        max_elements_to_parse: MAX_ELEMENTS_TO_PARSE,
+        ..Default::default()
+    };
+
+    let mut readability = Readability::new(html, Some(document_url), Some(config))
+        .map_err(|e| ClipError::Extract(e.to_string()))?;
+    let article = readability
+        .parse()
+        .map_err(|e| ClipError::Extract(e.to_string()))?;
+
+    Ok(ExtractedArticle {
+        title: article.title,
+        byline: article.byline,
+        excerpt: article.excerpt,
+        site_name: article.site_name,
+        published_time: article.published_time,
+        content_html: article.content.to_string(),
+    })
+}
+
+/// 提取 Markdown 中的图片 URL (去重，保持顺序)
+fn image_urls(markdown: &str) -> Vec<String> {
+    static IMAGE_RE: OnceLock<Regex> = OnceLock::new();
+    let re = IMAGE_RE.get_or_init(|| {
+        Regex::new(r#"!\[[^\]]*\]\(\s*<?([^)\s>]+)>?(?:\s+"[^"]*")?\s*\)"#).expect("valid regex")
+    });
+
+    let mut seen = HashSet::new();
+    re.captures_iter(markdown)
+        .filter_map(|caps| caps.get(1).map(|m| m.as_str().to_string()))
+        .filter(|url| seen.insert(url.clone()))
+        .collect()
+}
+
+/// 根据 URL 和 MIME 类型生成不重复的文件名
+fn unique_filename(url: &str, mime_type: &str, used: &mut HashSet<String>) -> String {
+    let last_segment = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| {
+            u.path_segments()
+                .and_then(|mut s| s.next_back().map(str::to_string))
+        })
+        .unwrap_or_default();
+    let last_segment = percent_decode(&last_segment);
+    let stem = last_segment
+        .rsplit_once('.')
+        .map(|(stem, _)| stem.to_string())
+        .unwrap_or(last_segment);
+
+    let stem = if stem.trim().is_empty() {
+        "image".to_string()
+    } else {
+        slug::slugify(&stem, SlugStyle::Kebab, SlugPlatform::Portable, 80)
+    };
+    let extension = extension_for_mime(mime_type);
+
+    let mut filename = format!("{}.{}", stem, extension);
+    let mut counter = 1;
+    while !used.insert(filename.clone()) {
+        filename = format!("{}-{}.{}", stem, counter, extension);
+        counter += 1;
+    }
+    filename
+}
+
+/// 解码 URL 路径中的百分号编码
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// MIME 类型对应的扩展名
+fn extension_for_mime(mime_type: &str) -> &'static str {
+    match mime_type {
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "image/avif" => "avif",
+        "image/bmp" => "bmp",
+        _ => "jpg",
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE_HTML: &str = r#"<!DOCTYPE html>
+<html><head><title>Test Article - Example Site</title></head>
+<body>
+<nav><a href="/">Home</a> <a href="/about">About</a></nav>
+<article>
+<h1>Test Article</h1>
+<p>This is the first paragraph of a reasonably long article body that should be picked up by the readability algorithm as the main content of the page, because it has plenty of text.</p>
+<p>Here is a second paragraph, also long enough to count, with an <a href="/link">inline link</a> and an image below it, which should be converted to Markdown syntax.</p>
+<p><img src="/images/photo.png" alt="Photo"></p>
+<p>A third paragraph closes the article with even more text so that the content score comfortably exceeds the threshold used by the extractor.</p>
+</article>
+<footer>Copyright</footer>
+</body></html>"#;
+
+    #[test]
+    fn test_extract_article_resolves_relative_urls() {
+        let article = extract_article(ARTICLE_HTML, "https://example.com/posts/1").unwrap();
+        assert!(article.title.contains("Test Article"));
+
+        let markdown = htmd::convert(&article.content_html).unwrap();
+        assert!(markdown.contains("first paragraph"));
+        assert!(!markdown.contains("Copyright"));
+        assert!(markdown.contains("https://example.com/images/photo.png"));
+    }
+
+    #[test]
+    fn test_image_urls() {
+        let md = "![a](https://x.com/a.png) text ![b](<https://x.com/b.jpg> \"title\") ![a](https://x.com/a.png)";
+        assert_eq!(
+            image_urls(md),
+            vec!["https://x.com/a.png".to_string(), "https://x.com/b.jpg".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_unique_filename() {
+        let mut used = HashSet::new();
+        assert_eq!(
+            unique_filename("https://x.com/img/My Photo.jpeg?w=100", "image/jpeg", &mut used),
+            "my-photo.jpg"
+        );
+        assert_eq!(
+            unique_filename("https://y.com/a/My%20Photo.png", "image/jpeg", &mut used),
+            "my-photo-1.jpg"
+        );
+        assert_eq!(unique_filename("https://x.com/", "image/png", &mut used), "image.png");
+    }
+
+    #[tokio::test]
+    async fn test_clip_rejects_invalid_url() {
+        let clipper = WebClipper::new();
+        let progress = ProgressReporter::none(crate::router::ModuleType::Utils);
+        assert!(matches!(
+            clipper.clip("file:///etc/passwd", false, &progress).await,
+            Err(ClipError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            clipper.clip("not a url", false, &progress).await,
+            Err(ClipError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_clip_error_code() {
+        assert_eq!(ClipError::InvalidUrl("x".to_string()).code(), ErrorCode::InvalidParams);
+        assert_eq!(ClipError::Status(404).code(), ErrorCode::HttpError);
+        assert!(!ClipError::Status(404).retryable());
+        assert!(ClipError::Status(503).retryable());
+        assert!(ClipError::Status(429).retryable());
+        assert!(ClipError::Http("timeout".to_string()).retryable());
+    }
+}
@@ -0,0 +1,893 @@
+// 自然语言日期解析模块
+// 将 "下周三下午三点"、"next Friday 9am" 等表达解析为带时区的时间戳
+//
+// 基于规则匹配：依次识别相对偏移、日期、星期、时刻和时段，
+// 再以参考时间为基准组合出最终结果
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Timelike, Weekday,
+};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::ops::Range;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+use super::language::TextLocale;
+use super::numbers::{parse_zh_digits, parse_zh_integer};
+
+/// 日期解析错误
+#[derive(Debug, Error)]
+pub enum DateParseError {
+    #[error("无效的参考时间: {0}")]
+    InvalidReference(String),
+}
+
+impl CodedError for DateParseError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            DateParseError::InvalidReference(_) => ErrorCode::InvalidParams,
+        }
+    }
+}
+
+/// 解析结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParsedDateTime {
+    /// ISO 8601 时间戳 (使用参考时间的时区)
+    pub datetime: String,
+    /// 是否包含具体时刻 (否则仅为日期，时间部分为 00:00)
+    pub has_time: bool,
+    /// 原文中被识别的片段
+    pub matched_text: String,
+}
+
+// ============================================================================
+// 解析入口
+// ============================================================================
+
+/// 解析参考时间 (RFC 3339 或不带时区的本地时间)，缺省为当前时间
+pub fn parse_reference(reference: Option<&str>) -> Result<DateTime<FixedOffset>, DateParseError> {
+    let Some(reference) = reference else {
+        return Ok(Local::now().fixed_offset());
+    };
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(reference) {
+        return Ok(dt);
+    }
+
+    NaiveDateTime::parse_from_str(reference, "%Y-%m-%dT%H:%M:%S")
+        .ok()
+        .and_then(|naive| Local.from_local_datetime(&naive).single())
+        .map(|dt| dt.fixed_offset())
+        .ok_or_else(|| DateParseError::InvalidReference(reference.to_string()))
+}
+
+/// 从文本中解析日期时间，没有识别到任何日期表达时返回 None
+pub fn parse(text: &str, locale: TextLocale, reference: DateTime<FixedOffset>) -> Option<ParsedDateTime> {
+    let mut state = ParseState::new(reference);
+
+    if locale.includes_zh() {
+        parse_zh(text, &mut state);
+    }
+    if locale.includes_en() {
+        parse_en(text, &mut state);
+    }
+    parse_common(text, &mut state);
+
+    state.finish(text)
+}
+
+// ============================================================================
+// 解析状态
+// ============================================================================
+
+/// 时段提示，用于推断 12 小时制的上午 / 下午
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DayPeriod {
+    EarlyMorning,
+    Morning,
+    Noon,
+    Afternoon,
+    Evening,
+}
+
+impl DayPeriod {
+    /// 只给出时段时使用的默认时刻
+    fn default_hour(&self) -> u32 {
+        match self {
+            Self::EarlyMorning => 6,
+            Self::Morning => 9,
+            Self::Noon => 12,
+            Self::Afternoon => 15,
+            Self::Evening => 20,
+        }
+    }
+
+    /// 将 12 小时制的小时转换为 24 小时制，返回 (小时, 是否跨到次日)
+    fn adjust_hour(&self, hour: u32) -> (u32, bool) {
+        match self {
+            Self::EarlyMorning | Self::Morning => (if hour == 12 { 0 } else { hour }, false),
+            Self::Noon => (if hour <= 2 { hour + 12 } else { hour }, false),
+            Self::Afternoon => (if hour < 12 { hour + 12 } else { hour }, false),
+            Self::Evening => match hour {
+                12 => (0, true),
+                h if h < 5 && h > 0 => (h, true),
+                h if h < 12 => (h + 12, false),
+                h => (h, false),
+            },
+        }
+    }
+}
+
+struct ParseState {
+    reference: DateTime<FixedOffset>,
+    date: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+    /// 相对偏移直接给出的完整时间 ("3 小时后")
+    instant: Option<NaiveDateTime>,
+    period: Option<DayPeriod>,
+    /// 时刻已是 24 小时制，不再按时段调整
+    time_final: bool,
+    /// 时刻来自 "晚上12点" 这类跨日表达
+    next_day: bool,
+    spans: Vec<Range<usize>>,
+}
+
+impl ParseState {
+    fn new(reference: DateTime<FixedOffset>) -> Self {
+        Self {
+            reference,
+            date: None,
+            time: None,
+            instant: None,
+            period: None,
+            time_final: false,
+            next_day: false,
+            spans: Vec::new(),
+        }
+    }
+
+    fn today(&self) -> NaiveDate {
+        self.reference.date_naive()
+    }
+
+    /// 区间是否与已识别的片段重叠
+    fn overlaps(&self, range: &Range<usize>) -> bool {
+        self.spans
+            .iter()
+            .any(|s| s.start < range.end && range.start < s.end)
+    }
+
+    fn claim(&mut self, range: Range<usize>) {
+        self.spans.push(range);
+    }
+
+    fn set_date(&mut self, date: NaiveDate, range: Range<usize>) {
+        if self.date.is_none() && self.instant.is_none() && !self.overlaps(&range) {
+            self.date = Some(date);
+            self.claim(range);
+        }
+    }
+
+    fn set_period(&mut self, period: DayPeriod, range: Range<usize>) {
+        if self.period.is_none() && !self.overlaps(&range) {
+            self.period = Some(period);
+            self.claim(range);
+        }
+    }
+
+    /// 设置时刻，hour 为原文中的小时 (可能是 12 小时制)
+    ///
+    /// 未给出时段时，若之后识别到时段会在 finish 中再做调整
+    fn set_time(&mut self, hour: u32, minute: u32, period: Option<DayPeriod>, range: Range<usize>) {
+        let (hour, next_day) = match period {
+            Some(p) => p.adjust_hour(hour),
+            None => (hour, false),
+        };
+        self.store_time(hour, minute, next_day, period.is_some(), range);
+    }
+
+    /// 设置 24 小时制时刻 (9pm、15:30 等)
+    fn set_time_24h(&mut self, hour: u32, minute: u32, range: Range<usize>) {
+        self.store_time(hour, minute, false, true, range);
+    }
+
+    fn store_time(&mut self, hour: u32, minute: u32, next_day: bool, is_final: bool, range: Range<usize>) {
+        if self.time.is_some() || self.instant.is_some() {
+            return;
+        }
+        if let Some(time) = NaiveTime::from_hms_opt(hour, minute, 0) {
+            self.time = Some(time);
+            self.time_final = is_final;
+            self.next_day = next_day;
+            self.claim(range);
+        }
+    }
+
+    fn finish(self, text: &str) -> Option<ParsedDateTime> {
+        if self.spans.is_empty() {
+            return None;
+        }
+
+        let offset = *self.reference.offset();
+        let (naive, has_time) = if let Some(instant) = self.instant {
+            (instant, true)
+        } else {
+            // 只有时段没有具体时刻时使用时段默认值；时刻先于时段出现时补做 12 小时制调整
+            let mut next_day = self.next_day;
+            let time = match (self.time, self.period) {
+                (Some(time), Some(period)) if !self.time_final => {
+                    let (hour, rollover) = period.adjust_hour(time.hour());
+                    next_day = rollover;
+                    NaiveTime::from_hms_opt(hour, time.minute(), 0)
+                }
+                (Some(time), _) => Some(time),
+                (None, Some(period)) => NaiveTime::from_hms_opt(period.default_hour(), 0, 0),
+                (None, None) => None,
+            };
+
+            let explicit_date = self.date.is_some();
+            let mut date = self.date.unwrap_or_else(|| self.today());
+            if next_day {
+                date = date.succ_opt()?;
+            }
+
+            match time {
+                Some(time) => {
+                    let mut naive = date.and_time(time);
+                    // 只说了时刻且已经过去，视为明天
+                    if !explicit_date && naive < self.reference.naive_local() {
+                        naive += Duration::days(1);
+                    }
+                    (naive, true)
+                }
+                None => (date.and_hms_opt(0, 0, 0)?, false),
+            }
+        };
+
+        let datetime = offset.from_local_datetime(&naive).single()?;
+        let start = self.spans.iter().map(|s| s.start).min()?;
+        let end = self.spans.iter().map(|s| s.end).max()?;
+
+        Some(ParsedDateTime {
+            datetime: datetime.to_rfc3339(),
+            has_time,
+            matched_text: text[start..end].to_string(),
+        })
+    }
+}
+
+// ============================================================================
+// 公共规则 (ISO 日期、24 小时制时刻)
+// ============================================================================
+
+fn parse_common(text: &str, state: &mut ParseState) -> Option<()> {
+    static ISO_DATE: OnceLock<Regex> = OnceLock::new();
+    static CLOCK: OnceLock<Regex> = OnceLock::new();
+
+    let iso_date = ISO_DATE.get_or_init(|| {
+        Regex::new(r"(?-u:\b)(?P<y>\d{4})[-/.](?P<m>\d{1,2})[-/.](?P<d>\d{1,2})(?-u:\b)").expect("valid regex")
+    });
+    if let Some(caps) = iso_date.captures(text) {
+        if let Some(date) = ymd(num(&caps, "y"), num(&caps, "m"), num(&caps, "d")) {
+            state.set_date(date, whole(&caps));
+        }
+    }
+
+    let clock = CLOCK.get_or_init(|| {
+        Regex::new(r"(?-u:\b)(?P<h>[01]?\d|2[0-3])[:：](?P<mi>[0-5]\d)(?-u:\b)").expect("valid regex")
+    });
+    if let Some(caps) = clock.captures(text) {
+        let (hour, minute) = (num(&caps, "h")?, num(&caps, "mi")?);
+        match state.period {
+            Some(period) if hour <= 12 => state.set_time(hour, minute, Some(period), whole(&caps)),
+            _ => state.set_time_24h(hour, minute, whole(&caps)),
+        }
+    }
+
+    Some(())
+}
+
+// ============================================================================
+// 中文规则
+// ============================================================================
+
+/// 中文数字字符集 (用于正则)
+const ZH_NUM: &str = "[0-9零〇一二两三四五六七八九十百]";
+
+fn parse_zh(text: &str, state: &mut ParseState) -> Option<()> {
+    static PATTERNS: OnceLock<ZhPatterns> = OnceLock::new();
+    let p = PATTERNS.get_or_init(ZhPatterns::new);
+
+    // 相对偏移: 3天后 / 两个小时后 / 半小时后
+    if let Some(caps) = p.relative.captures(text) {
+        let amount = caps.name("n").map(|m| m.as_str()).unwrap_or_default();
+        let unit = caps.name("unit").map(|m| m.as_str()).unwrap_or_default();
+        let unit = match unit {
+            "分钟" => RelativeUnit::Minute,
+            "小时" | "钟头" => RelativeUnit::Hour,
+            "天" | "日" => RelativeUnit::Day,
+            "周" | "星期" | "礼拜" => RelativeUnit::Week,
+            "月" => RelativeUnit::Month,
+            _ => RelativeUnit::Year,
+        };
+        let half = amount == "半";
+        let amount = if half { Some(0) } else { parse_zh_number(amount) };
+        if let Some(amount) = amount {
+            apply_relative(state, amount, half, unit, whole(&caps));
+        }
+    }
+
+    // 日期词: 今天 / 明天 / 后天 / 今晚
+    if let Some(caps) = p.day_word.captures(text) {
+        let word = caps.name("w").map(|m| m.as_str()).unwrap_or_default();
+        let (days, period) = match word {
+            "大后天" => (3, None),
+            "后天" => (2, None),
+            "明天" | "明日" => (1, None),
+            "明早" => (1, Some(DayPeriod::Morning)),
+            "明晚" => (1, Some(DayPeriod::Evening)),
+            "今天" | "今日" => (0, None),
+            "今早" => (0, Some(DayPeriod::Morning)),
+            "今晚" | "今夜" => (0, Some(DayPeriod::Evening)),
+            "昨天" | "昨日" => (-1, None),
+            "昨晚" => (-1, Some(DayPeriod::Evening)),
+            _ => (-2, None),
+        };
+        let range = whole(&caps);
+        if let Some(date) = state.today().checked_add_signed(Duration::days(days)) {
+            state.set_date(date, range.clone());
+        }
+        if let Some(period) = period {
+            state.period = state.period.or(Some(period));
+        }
+    }
+
+    // 星期: 下周三 / 这个星期五 / 周日
+    if let Some(caps) = p.weekday.captures(text) {
+        let day = caps.name("d").map(|m| m.as_str()).unwrap_or_default();
+        let weekday = match day {
+            "一" | "1" => Weekday::Mon,
+            "二" | "2" => Weekday::Tue,
+            "三" | "3" => Weekday::Wed,
+            "四" | "4" => Weekday::Thu,
+            "五" | "5" => Weekday::Fri,
+            "六" | "6" => Weekday::Sat,
+            _ => Weekday::Sun,
+        };
+        let relation = match caps.name("prefix").map(|m| m.as_str().trim_end_matches('个')) {
+            Some("下下") => WeekRelation::Offset(2),
+            Some("下") => WeekRelation::Offset(1),
+            Some("上") => WeekRelation::Offset(-1),
+            Some(_) => WeekRelation::Offset(0),
+            None => WeekRelation::Upcoming,
+        };
+        if let Some(date) = resolve_weekday(state.today(), weekday, relation) {
+            state.set_date(date, whole(&caps));
+        }
+    }
+
+    // 日期: 2026年3月5日 / 3月5号 / 15号
+    if let Some(caps) = p.date.captures(text) {
+        let today = state.today();
+        let month = caps.name("m").and_then(|m| parse_zh_number(m.as_str()));
+        let day = caps.name("d").and_then(|m| parse_zh_number(m.as_str()));
+        let year = caps.name("y")
+            .and_then(|m| parse_zh_digits(m.as_str()))
+            .and_then(|y| i32::try_from(y).ok());
+        if let Some(date) = resolve_month_day(today, year, month, day?) {
+            state.set_date(date, whole(&caps));
+        }
+    }
+
+    // 时刻: 下午三点半 / 晚上8点15分 / 9点
+    if let Some(caps) = p.time.captures(text) {
+        let period = caps.name("period").map(|m| zh_period(m.as_str()));
+        let hour = parse_zh_number(caps.name("h")?.as_str())?;
+        let minute = if caps.name("half").is_some() {
+            30
+        } else if let Some(q) = caps.name("q") {
+            if q.as_str() == "一刻" { 15 } else { 45 }
+        } else {
+            caps.name("mi")
+                .and_then(|m| parse_zh_number(m.as_str()))
+                .unwrap_or(0)
+        };
+        let period = period.or(state.period);
+        state.set_time(hour, minute, period, whole(&caps));
+        if let Some(period) = period {
+            state.period = state.period.or(Some(period));
+        }
+    }
+
+    // 只有时段: 明天下午
+    if let Some(m) = p.period.find(text) {
+        state.set_period(zh_period(m.as_str()), m.range());
+    }
+
+    Some(())
+}
+
+struct ZhPatterns {
+    relative: Regex,
+    day_word: Regex,
+    weekday: Regex,
+    date: Regex,
+    time: Regex,
+    period: Regex,
+}
+
+impl ZhPatterns {
+    fn new() -> Self {
+        let period = "凌晨|清晨|早上|早晨|上午|中午|午后|下午|傍晚|晚上|夜里|夜间|晚";
+        Self {
+            relative: Regex::new(&format!(
+                r"(?P<n>{ZH_NUM}+|半)个?(?P<unit>分钟|小时|钟头|天|日|周|星期|礼拜|月|年)(?:以后|之后|后)"
+            ))
+            .expect("valid regex"),
+            day_word: Regex::new(
+                r"(?P<w>大后天|后天|明天|明日|明早|明晚|今天|今日|今早|今晚|今夜|昨天|昨日|昨晚|前天)",
+            )
+            .expect("valid regex"),
+            weekday: Regex::new(
+                r"(?P<prefix>下下个?|下个?|上个?|这个?|本)?(?:周|星期|礼拜)(?P<d>[一二三四五六日天1-7])",
+            )
+            .expect("valid regex"),
+            date: Regex::new(&format!(
+                r"(?:(?P<y>[0-9零〇一二三四五六七八九]{{4}})年)?(?:(?P<m>{ZH_NUM}{{1,3}})月)?(?P<d>{ZH_NUM}{{1,3}})[日号]"
+            ))
+            .expect("valid regex"),
+            time: Regex::new(&format!(
+                r"(?P<period>{period})?(?P<h>{ZH_NUM}{{1,3}})[点时](?:(?P<half>半)|(?P<q>一刻|三刻)|(?P<mi>{ZH_NUM}{{1,3}})分?)?"
+            ))
+            .expect("valid regex"),
+            period: Regex::new(period).expect("valid regex"),
+        }
+    }
+}
+
+fn zh_period(word: &str) -> DayPeriod {
+    match word {
+        "凌晨" => DayPeriod::EarlyMorning,
+        "清晨" | "早上" | "早晨" | "上午" => DayPeriod::Morning,
+        "中午" => DayPeriod::Noon,
+        "午后" | "下午" => DayPeriod::Afternoon,
+        _ => DayPeriod::Evening,
+    }
+}
+
+/// 解析中文或阿拉伯数字 ("二十三"、"两"、"15")
+fn parse_zh_number(text: &str) -> Option<u32> {
+    parse_zh_integer(text).and_then(|n| u32::try_from(n).ok())
+}
+
+// ============================================================================
+// 英文规则
+// ============================================================================
+
+const EN_NUMBER: &str = r"\d+|an?|one|two|three|four|five|six|seven|eight|nine|ten|eleven|twelve";
+const EN_WEEKDAY: &str = r"monday|tuesday|wednesday|thursday|friday|saturday|sunday";
+const EN_MONTH: &str =
+    r"jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sept?(?:ember)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?";
+
+fn parse_en(text: &str, state: &mut ParseState) -> Option<()> {
+    static PATTERNS: OnceLock<EnPatterns> = OnceLock::new();
+    let p = PATTERNS.get_or_init(EnPatterns::new);
+
+    // 相对偏移: in 3 days / half an hour from now
+    if let Some(caps) = p.relative.captures(text) {
+        let group = |a: &str, b: &str| {
+            caps.name(a)
+                .or_else(|| caps.name(b))
+                .map(|m| m.as_str().to_ascii_lowercase())
+                .unwrap_or_default()
+        };
+        let amount = group("n", "n2");
+        let unit = group("unit", "unit2");
+        let unit = if unit.starts_with("min") {
+            RelativeUnit::Minute
+        } else if unit.starts_with('h') {
+            RelativeUnit::Hour
+        } else if unit.starts_with('d') {
+            RelativeUnit::Day
+        } else if unit.starts_with('w') {
+            RelativeUnit::Week
+        } else if unit.starts_with("mo") {
+            RelativeUnit::Month
+        } else {
+            RelativeUnit::Year
+        };
+        let half = amount.starts_with("half");
+        let amount = if half { Some(0) } else { en_number(&amount) };
+        if let Some(amount) = amount {
+            apply_relative(state, amount, half, unit, whole(&caps));
+        }
+    }
+
+    // 日期词
+    if let Some(caps) = p.day_word.captures(text) {
+        let word = caps.name("w").map(|m| m.as_str().to_ascii_lowercase()).unwrap_or_default();
+        let (days, period) = match word.as_str() {
+            "tomorrow" => (1, None),
+            "today" => (0, None),
+            "tonight" => (0, Some(DayPeriod::Evening)),
+            "yesterday" => (-1, None),
+            _ => (2, None),
+        };
+        if let Some(date) = state.today().checked_add_signed(Duration::days(days)) {
+            state.set_date(date, whole(&caps));
+        }
+        if let Some(period) = period {
+            state.period = state.period.or(Some(period));
+        }
+    }
+
+    // 星期: next friday / this monday / sunday
+    if let Some(caps) = p.weekday.captures(text) {
+        let weekday: Weekday = caps.name("d")?.as_str().parse().ok()?;
+        let relation = match caps.name("prefix").map(|m| m.as_str().to_ascii_lowercase()) {
+            Some(prefix) if prefix == "next" => WeekRelation::Offset(1),
+            Some(prefix) if prefix == "last" => WeekRelation::Offset(-1),
+            Some(prefix) if prefix == "this" => WeekRelation::Offset(0),
+            _ => WeekRelation::Upcoming,
+        };
+        if let Some(date) = resolve_weekday(state.today(), weekday, relation) {
+            state.set_date(date, whole(&caps));
+        }
+    }
+
+    // 日期: March 5th, 2026 / 5 March
+    for re in [&p.month_day, &p.day_month] {
+        if let Some(caps) = re.captures(text) {
+            let month = en_month(caps.name("mon")?.as_str())?;
+            let day = num(&caps, "d")?;
+            let year = caps.name("y").and_then(|m| m.as_str().parse().ok());
+            if let Some(date) = resolve_month_day(state.today(), year, Some(month), day) {
+                state.set_date(date, whole(&caps));
+            }
+        }
+    }
+
+    // 时刻: 9am / 9:30 pm / noon / midnight / at 7
+    if let Some(caps) = p.ampm.captures(text) {
+        let hour = en_number(&caps.name("h")?.as_str().to_ascii_lowercase())?;
+        let minute = num(&caps, "mi").unwrap_or(0);
+        let pm = caps.name("ampm")?.as_str().to_ascii_lowercase().starts_with('p');
+        if (1..=12).contains(&hour) {
+            let hour = match (pm, hour) {
+                (true, 12) => 12,
+                (true, h) => h + 12,
+                (false, 12) => 0,
+                (false, h) => h,
+            };
+            state.set_time_24h(hour, minute, whole(&caps));
+        }
+    }
+    if let Some(m) = p.noon.find(text) {
+        if m.as_str().eq_ignore_ascii_case("midnight") {
+            state.set_time(12, 0, Some(DayPeriod::Evening), m.range());
+        } else {
+            state.set_time_24h(12, 0, m.range());
+        }
+    }
+    if let Some(caps) = p.at_hour.captures(text) {
+        let hour = en_number(&caps.name("h")?.as_str().to_ascii_lowercase())?;
+        let period = state.period;
+        state.set_time(hour, 0, period, whole(&caps));
+    }
+
+    // 时段: in the morning / evening
+    if let Some(caps) = p.period.captures(text) {
+        let period = match caps.name("p")?.as_str().to_ascii_lowercase().as_str() {
+            "morning" => DayPeriod::Morning,
+            "afternoon" => DayPeriod::Afternoon,
+            _ => DayPeriod::Evening,
+        };
+        state.set_period(period, whole(&caps));
+    }
+
+    Some(())
+}
+
+struct EnPatterns {
+    relative: Regex,
+    day_word: Regex,
+    weekday: Regex,
+    month_day: Regex,
+    day_month: Regex,
+    ampm: Regex,
+    noon: Regex,
+    at_hour: Regex,
+    period: Regex,
+}
+
+impl EnPatterns {
+    fn new() -> Self {
+        let unit = r"minutes?|mins?|hours?|hrs?|days?|weeks?|months?|years?";
+        Self {
+            relative: Regex::new(&format!(
+                r"(?i)(?:\bin\s+(?P<n>{EN_NUMBER}|half an?|a half)\s+(?P<unit>{unit})\b|\b(?P<n2>{EN_NUMBER})\s+(?P<unit2>{unit})\s+(?:from now|later)\b)"
+            ))
+            .expect("valid regex"),
+            day_word: Regex::new(r"(?i)\b(?P<w>(?:the\s+)?day after tomorrow|tomorrow|today|tonight|yesterday)\b")
+                .expect("valid regex"),
+            weekday: Regex::new(&format!(
+                r"(?i)\b(?:(?P<prefix>next|this|last|coming)\s+)?(?P<d>{EN_WEEKDAY})\b"
+            ))
+            .expect("valid regex"),
+            month_day: Regex::new(&format!(
+                r"(?i)\b(?P<mon>{EN_MONTH})\.?\s+(?P<d>\d{{1,2}})(?:st|nd|rd|th)?\b(?:,?\s+(?P<y>\d{{4}})\b)?"
+            ))
+            .expect("valid regex"),
+            day_month: Regex::new(&format!(
+                r"(?i)\b(?P<d>\d{{1,2}})(?:st|nd|rd|th)?\s+(?:of\s+)?(?P<mon>{EN_MONTH})\b(?:,?\s+(?P<y>\d{{4}})\b)?"
+            ))
+            .expect("valid regex"),
+            ampm: Regex::new(&format!(
+                r"(?i)\b(?P<h>\d{{1,2}}|{EN_NUMBER})(?::(?P<mi>[0-5]\d))?\s*(?P<ampm>a\.?m\b\.?|p\.?m\b\.?)"
+            ))
+            .expect("valid regex"),
+            noon: Regex::new(r"(?i)\b(?:noon|midday|midnight)\b").expect("valid regex"),
+            at_hour: Regex::new(&format!(r"(?i)\bat\s+(?P<h>\d{{1,2}}|{EN_NUMBER})\b(?:\s*o'?clock)?"))
+                .expect("valid regex"),
+            period: Regex::new(r"(?i)\b(?:in the\s+)?(?P<p>morning|afternoon|evening|night)\b")
+                .expect("valid regex"),
+        }
+    }
+}
+
+fn en_number(word: &str) -> Option<u32> {
+    let value = match word {
+        "a" | "an" | "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        "eleven" => 11,
+        "twelve" => 12,
+        digits => return digits.parse().ok(),
+    };
+    Some(value)
+}
+
+fn en_month(word: &str) -> Option<u32> {
+    let word = word.to_ascii_lowercase();
+    let month = match &word[..3.min(word.len())] {
+        "jan" => 1,
+        "feb" => 2,
+        "mar" => 3,
+        "apr" => 4,
+        "may" => 5,
+        "jun" => 6,
+        "jul" => 7,
+        "aug" => 8,
+        "sep" => 9,
+        "oct" => 10,
+        "nov" => 11,
+        "dec" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+// ============================================================================
+// 日期计算
+// ============================================================================
+
+#[derive(Debug, Clone, Copy)]
+enum RelativeUnit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum WeekRelation {
+    /// 相对本周的偏移 (以周一为一周开始)
+    Offset(i64),
+    /// 下一个出现的该星期 (包括今天)
+    Upcoming,
+}
+
+/// 应用相对偏移，half 表示 "半" (仅对小时有效，即 30 分钟)
+fn apply_relative(state: &mut ParseState, amount: u32, half: bool, unit: RelativeUnit, range: Range<usize>) {
+    if state.overlaps(&range) || state.instant.is_some() || state.date.is_some() {
+        return;
+    }
+
+    let now = state.reference.naive_local();
+    let amount = amount as i64;
+    match unit {
+        RelativeUnit::Minute | RelativeUnit::Hour => {
+            let minutes = match (unit, half) {
+                (RelativeUnit::Hour, true) => 30,
+                (RelativeUnit::Hour, false) => amount * 60,
+                (_, true) => return,
+                _ => amount,
+            };
+            state.instant = Some(now + Duration::minutes(minutes));
+            state.claim(range);
+        }
+        _ if half => {}
+        RelativeUnit::Day | RelativeUnit::Week => {
+            let days = if matches!(unit, RelativeUnit::Week) { amount * 7 } else { amount };
+            if let Some(date) = state.today().checked_add_signed(Duration::days(days)) {
+                state.set_date(date, range);
+            }
+        }
+        RelativeUnit::Month | RelativeUnit::Year => {
+            let months = if matches!(unit, RelativeUnit::Year) { amount * 12 } else { amount };
+            if let Some(date) = u32::try_from(months)
+                .ok()
+                .and_then(|m| state.today().checked_add_months(Months::new(m)))
+            {
+                state.set_date(date, range);
+            }
+        }
+    }
+}
+
+fn resolve_weekday(today: NaiveDate, weekday: Weekday, relation: WeekRelation) -> Option<NaiveDate> {
+    let today_index = today.weekday().num_days_from_monday() as i64;
+    let target_index = weekday.num_days_from_monday() as i64;
+
+    let delta = match relation {
+        WeekRelation::Offset(weeks) => weeks * 7 + target_index - today_index,
+        WeekRelation::Upcoming => (target_index - today_index).rem_euclid(7),
+    };
+    today.checked_add_signed(Duration::days(delta))
+}
+
+/// 组合年月日；省略年份时取今年，省略月份时取本月，
+/// 省略的部分导致日期已过去时顺延到下一年 / 下一个月
+fn resolve_month_day(today: NaiveDate, year: Option<i32>, month: Option<u32>, day: u32) -> Option<NaiveDate> {
+    match (year, month) {
+        (Some(year), Some(month)) => NaiveDate::from_ymd_opt(year, month, day),
+        (None, Some(month)) => {
+            let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+            if date < today {
+                NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+            } else {
+                Some(date)
+            }
+        }
+        (_, None) => {
+            let date = NaiveDate::from_ymd_opt(today.year(), today.month(), day);
+            match date {
+                Some(date) if date >= today => Some(date),
+                _ => {
+                    let next_month = today.with_day(1)?.checked_add_months(Months::new(1))?;
+                    next_month.with_day(day)
+                }
+            }
+        }
+    }
+}
+
+fn ymd(year: Option<u32>, month: Option<u32>, day: Option<u32>) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year? as i32, month?, day?)
+}
+
+fn num(caps: &Captures, name: &str) -> Option<u32> {
+    caps.name(name).and_then(|m| m.as_str().parse().ok())
+}
+
+fn whole(caps: &Captures) -> Range<usize> {
+    caps.get(0).map(|m| m.range()).unwrap_or(0..0)
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-14 (周三) 10:00 +08:00
+    fn reference() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2026-10-14T10:00:00+08:00").unwrap()
+    }
+
+    fn parse_at(text: &str) -> ParsedDateTime {
+        parse(text, TextLocale::Auto, reference()).unwrap()
+    }
+
+    #[test]
+    fn test_zh_next_weekday_afternoon() {
+        let result = parse_at("下周三下午三点开会");
+        assert_eq!(result.datetime, "2026-10-21T15:00:00+08:00");
+        assert!(result.has_time);
+        assert_eq!(result.matched_text, "下周三下午三点");
+    }
+
+    #[test]
+    fn test_zh_relative_days_and_periods() {
+        assert_eq!(parse_at("明天晚上8点半").datetime, "2026-10-15T20:30:00+08:00");
+        assert_eq!(parse_at("后天").datetime, "2026-10-16T00:00:00+08:00");
+        assert!(!parse_at("后天").has_time);
+        assert_eq!(parse_at("明天下午").datetime, "2026-10-15T15:00:00+08:00");
+        assert_eq!(parse_at("今晚十二点").datetime, "2026-10-15T00:00:00+08:00");
+        assert_eq!(parse_at("3天后").datetime, "2026-10-17T00:00:00+08:00");
+        assert_eq!(parse_at("两个小时后").datetime, "2026-10-14T12:00:00+08:00");
+        assert_eq!(parse_at("半小时后提醒我").datetime, "2026-10-14T10:30:00+08:00");
+    }
+
+    #[test]
+    fn test_zh_dates() {
+        assert_eq!(parse_at("3月5日").datetime, "2027-03-05T00:00:00+08:00");
+        assert_eq!(parse_at("十二月二十五号上午十点一刻").datetime, "2026-12-25T10:15:00+08:00");
+        assert_eq!(parse_at("二〇二七年一月一日").datetime, "2027-01-01T00:00:00+08:00");
+        assert_eq!(parse_at("20号").datetime, "2026-10-20T00:00:00+08:00");
+        assert_eq!(parse_at("5号").datetime, "2026-11-05T00:00:00+08:00");
+    }
+
+    #[test]
+    fn test_zh_weekdays() {
+        assert_eq!(parse_at("周五").datetime, "2026-10-16T00:00:00+08:00");
+        assert_eq!(parse_at("星期一").datetime, "2026-10-19T00:00:00+08:00");
+        assert_eq!(parse_at("这周一").datetime, "2026-10-12T00:00:00+08:00");
+        assert_eq!(parse_at("下下个星期天").datetime, "2026-11-01T00:00:00+08:00");
+    }
+
+    #[test]
+    fn test_en_expressions() {
+        assert_eq!(parse_at("next Friday 9am").datetime, "2026-10-23T09:00:00+08:00");
+        assert_eq!(parse_at("tomorrow at 3:30 pm").datetime, "2026-10-15T15:30:00+08:00");
+        assert_eq!(parse_at("friday").datetime, "2026-10-16T00:00:00+08:00");
+        assert_eq!(parse_at("in 2 weeks").datetime, "2026-10-28T00:00:00+08:00");
+        assert_eq!(parse_at("in half an hour").datetime, "2026-10-14T10:30:00+08:00");
+        assert_eq!(parse_at("three days from now").datetime, "2026-10-17T00:00:00+08:00");
+        assert_eq!(parse_at("March 5th, 2027 at noon").datetime, "2027-03-05T12:00:00+08:00");
+        assert_eq!(parse_at("5 Jan").datetime, "2027-01-05T00:00:00+08:00");
+        assert_eq!(parse_at("tonight").datetime, "2026-10-14T20:00:00+08:00");
+        assert_eq!(parse_at("tomorrow morning").datetime, "2026-10-15T09:00:00+08:00");
+        assert_eq!(parse_at("at midnight").datetime, "2026-10-15T00:00:00+08:00");
+    }
+
+    #[test]
+    fn test_common_formats_and_rollover() {
+        assert_eq!(parse_at("2026-11-02 14:00").datetime, "2026-11-02T14:00:00+08:00");
+        // 时刻已经过去，顺延到明天
+        assert_eq!(parse_at("9:00").datetime, "2026-10-15T09:00:00+08:00");
+        assert_eq!(parse_at("11:00").datetime, "2026-10-14T11:00:00+08:00");
+        assert_eq!(parse_at("明天下午3:30").datetime, "2026-10-15T15:30:00+08:00");
+    }
+
+    #[test]
+    fn test_locale_restriction_and_no_match() {
+        assert!(parse("明天", TextLocale::En, reference()).is_none());
+        assert!(parse("tomorrow", TextLocale::Zh, reference()).is_none());
+        assert!(parse("nothing here", TextLocale::Auto, reference()).is_none());
+    }
+
+    #[test]
+    fn test_parse_zh_number() {
+        assert_eq!(parse_zh_number("十"), Some(10));
+        assert_eq!(parse_zh_number("十五"), Some(15));
+        assert_eq!(parse_zh_number("二十三"), Some(23));
+        assert_eq!(parse_zh_number("两"), Some(2));
+        assert_eq!(parse_zh_number("一百零五"), Some(105));
+        assert_eq!(parse_zh_number("42"), Some(42));
+        assert_eq!(parse_zh_number("abc"), None);
+    }
+
+    #[test]
+    fn test_parse_reference() {
+        assert!(parse_reference(Some("2026-10-14T10:00:00+08:00")).is_ok());
+        assert!(parse_reference(Some("2026-10-14T10:00:00")).is_ok());
+        assert!(parse_reference(Some("yesterday")).is_err());
+        assert!(parse_reference(None).is_ok());
+    }
+}
@@ -0,0 +1,396 @@
+// Frontmatter 模块
+// 解析和更新 Markdown 文档的 YAML frontmatter
+//
+// 更新时只重写被修改的顶层键，其余行（注释、引号风格、缩进）保持原样
+
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use serde_yaml::Value as YamlValue;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// Frontmatter 错误
+#[derive(Debug, Error)]
+pub enum FrontmatterError {
+    #[error("无效的 YAML frontmatter: {0}")]
+    InvalidYaml(String),
+
+    #[error("Frontmatter 必须是映射")]
+    NotMapping,
+
+    #[error("无法序列化键 '{key}' 的值: {message}")]
+    Serialize { key: String, message: String },
+}
+
+impl CodedError for FrontmatterError {
+    fn code(&self) -> ErrorCode {
+        // frontmatter 来自请求内容，解析和序列化失败都属于参数错误
+        ErrorCode::InvalidParams
+    }
+}
+
+// ============================================================================
+// 文档拆分
+// ============================================================================
+
+/// 拆分后的 frontmatter 位置信息
+#[derive(Debug, Clone, PartialEq)]
+struct FrontmatterSpan {
+    /// YAML 内容起始字节偏移 (开始分隔线之后)
+    yaml_start: usize,
+    /// YAML 内容结束字节偏移 (结束分隔线之前)
+    yaml_end: usize,
+    /// 文档使用的换行符
+    newline: &'static str,
+}
+
+/// 定位文档中的 frontmatter
+///
+/// frontmatter 必须位于文档开头，以单独一行的 `---` 开始，
+/// 以单独一行的 `---` 或 `...` 结束
+fn locate(content: &str) -> Option<FrontmatterSpan> {
+    let first_line_end = content.find('\n')?;
+    let first_line = content[..first_line_end].trim_end_matches('\r');
+    if first_line.trim_end() != "---" {
+        return None;
+    }
+
+    let newline = if content[..first_line_end].ends_with('\r') {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let yaml_start = first_line_end + 1;
+    let mut offset = yaml_start;
+    for line in content[yaml_start..].split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\r', '\n']).trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some(FrontmatterSpan {
+                yaml_start,
+                yaml_end: offset,
+                newline,
+            });
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+/// 解析 YAML 文本为映射，空文本视为空映射
+fn parse_mapping(yaml: &str) -> Result<serde_yaml::Mapping, FrontmatterError> {
+    if yaml.trim().is_empty() {
+        return Ok(serde_yaml::Mapping::new());
+    }
+
+    match serde_yaml::from_str::<YamlValue>(yaml)
+        .map_err(|e| FrontmatterError::InvalidYaml(e.to_string()))?
+    {
+        YamlValue::Mapping(mapping) => Ok(mapping),
+        YamlValue::Null => Ok(serde_yaml::Mapping::new()),
+        _ => Err(FrontmatterError::NotMapping),
+    }
+}
+
+// ============================================================================
+// 读取
+// ============================================================================
+
+/// 读取文档的 frontmatter
+///
+/// 文档没有 frontmatter 时返回 `Ok(None)`
+pub fn get(content: &str) -> Result<Option<JsonValue>, FrontmatterError> {
+    let span = match locate(content) {
+        Some(span) => span,
+        None => return Ok(None),
+    };
+
+    let mapping = parse_mapping(&content[span.yaml_start..span.yaml_end])?;
+    let value = serde_json::to_value(YamlValue::Mapping(mapping))
+        .map_err(|e| FrontmatterError::InvalidYaml(e.to_string()))?;
+
+    Ok(Some(value))
+}
+
+/// 去掉文档开头的 frontmatter，返回正文 (没有 frontmatter 时返回原文)
+pub fn strip(content: &str) -> &str {
+    match locate(content) {
+        Some(span) => {
+            let rest = &content[span.yaml_end..];
+            rest.find('\n').map_or("", |end| &rest[end + 1..])
+        }
+        None => content,
+    }
+}
+
+// ============================================================================
+// 更新
+// ============================================================================
+
+/// 将更新应用到文档的 frontmatter 并返回新文档
+///
+/// - 值为 `null` 的键会被删除
+/// - 已存在的键原位替换，值未变化时保留原始写法
+/// - 新键追加到 frontmatter 末尾
+/// - 文档没有 frontmatter 时会在开头创建
+pub fn set(content: &str, updates: &JsonMap<String, JsonValue>) -> Result<String, FrontmatterError> {
+    let span = locate(content);
+    let (yaml, newline) = match &span {
+        Some(span) => (&content[span.yaml_start..span.yaml_end], span.newline),
+        None => ("", if content.contains("\r\n") { "\r\n" } else { "\n" }),
+    };
+
+    let existing = parse_mapping(yaml)?;
+    let mut lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+
+    for (key, value) in updates {
+        let yaml_key = YamlValue::String(key.clone());
+        let blocks = find_key_block(&lines, key);
+
+        if value.is_null() {
+            if let Some((start, end)) = blocks {
+                lines.drain(start..end);
+            }
+            continue;
+        }
+
+        let new_value = serde_yaml::to_value(value).map_err(|e| FrontmatterError::Serialize {
+            key: key.clone(),
+            message: e.to_string(),
+        })?;
+
+        if blocks.is_some() && existing.get(&yaml_key) == Some(&new_value) {
+            continue;
+        }
+
+        let rendered = render_entry(key, new_value)?;
+        match blocks {
+            Some((start, end)) => {
+                lines.splice(start..end, rendered);
+            }
+            None => lines.extend(rendered),
+        }
+    }
+
+    let new_yaml = if lines.is_empty() {
+        String::new()
+    } else {
+        let mut joined = lines.join(newline);
+        joined.push_str(newline);
+        joined
+    };
+
+    // 确保生成的 YAML 仍然可以解析
+    parse_mapping(&new_yaml)?;
+
+    let mut output = String::with_capacity(content.len() + new_yaml.len());
+    match span {
+        Some(span) => {
+            output.push_str(&content[..span.yaml_start]);
+            output.push_str(&new_yaml);
+            output.push_str(&content[span.yaml_end..]);
+        }
+        None => {
+            if new_yaml.is_empty() {
+                return Ok(content.to_string());
+            }
+            output.push_str("---");
+            output.push_str(newline);
+            output.push_str(&new_yaml);
+            output.push_str("---");
+            output.push_str(newline);
+            output.push_str(content);
+        }
+    }
+
+    Ok(output)
+}
+
+/// 将单个键值对渲染为 YAML 行
+fn render_entry(key: &str, value: YamlValue) -> Result<Vec<String>, FrontmatterError> {
+    let mut mapping = serde_yaml::Mapping::new();
+    mapping.insert(YamlValue::String(key.to_string()), value);
+
+    let rendered = serde_yaml::to_string(&mapping).map_err(|e| FrontmatterError::Serialize {
+        key: key.to_string(),
+        message: e.to_string(),
+    })?;
+
+    Ok(rendered.lines().map(str::to_string).collect())
+}
+
+/// 查找顶层键所占的行区间 `[start, end)`
+///
+/// 区间包含键所在行及其后的缩进行 / 序列项，不包含末尾的空行
+fn find_key_block(lines: &[String], key: &str) -> Option<(usize, usize)> {
+    let start = lines
+        .iter()
+        .position(|line| top_level_key(line).as_deref() == Some(key))?;
+
+    let mut end = start + 1;
+    let mut cursor = start + 1;
+    while cursor < lines.len() {
+        let line = &lines[cursor];
+        if line.trim().is_empty() {
+            cursor += 1;
+            continue;
+        }
+        if is_continuation(line) {
+            cursor += 1;
+            end = cursor;
+        } else {
+            break;
+        }
+    }
+
+    Some((start, end))
+}
+
+/// 判断是否为上一个键的延续行 (缩进内容或顶格的序列项)
+fn is_continuation(line: &str) -> bool {
+    line.starts_with([' ', '\t']) || line == "-" || line.starts_with("- ")
+}
+
+/// 解析顶层键名，非键行返回 None
+fn top_level_key(line: &str) -> Option<String> {
+    if line.is_empty() || is_continuation(line) || line.starts_with('#') {
+        return None;
+    }
+
+    // 带引号的键
+    if let Some(quote) = line.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let close = line[1..].find(quote)? + 1;
+        let rest = &line[close + 1..];
+        if rest.trim_start().starts_with(':') {
+            return Some(line[1..close].to_string());
+        }
+        return None;
+    }
+
+    // 普通键：第一个后面跟空白或行尾的冒号
+    let bytes = line.as_bytes();
+    for (i, b) in bytes.iter().enumerate() {
+        if *b == b':' && bytes.get(i + 1).is_none_or(|next| *next == b' ' || *next == b'\t') {
+            return Some(line[..i].trim_end().to_string());
+        }
+    }
+
+    None
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn updates(value: JsonValue) -> JsonMap<String, JsonValue> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_get_frontmatter() {
+        let doc = "---\ntitle: Hello\ntags:\n  - a\n  - b\n---\n# Body\n";
+        let fm = get(doc).unwrap().unwrap();
+
+        assert_eq!(fm["title"], "Hello");
+        assert_eq!(fm["tags"], json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_get_without_frontmatter() {
+        assert!(get("# Just a heading\n").unwrap().is_none());
+        assert!(get("---\nunterminated: true\n").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_strip() {
+        assert_eq!(strip("---\ntitle: Hello\n---\n# Body\n"), "# Body\n");
+        assert_eq!(strip("---\r\n---\r\nbody"), "body");
+        assert_eq!(strip("---\na: 1\n---"), "");
+        assert_eq!(strip("# Just a heading\n"), "# Just a heading\n");
+    }
+
+    #[test]
+    fn test_get_empty_frontmatter() {
+        let fm = get("---\n---\nbody").unwrap().unwrap();
+        assert_eq!(fm, json!({}));
+    }
+
+    #[test]
+    fn test_get_invalid_yaml() {
+        assert!(matches!(
+            get("---\ntitle: [unclosed\n---\n"),
+            Err(FrontmatterError::InvalidYaml(_))
+        ));
+        assert!(matches!(
+            get("---\n- a\n- b\n---\n"),
+            Err(FrontmatterError::NotMapping)
+        ));
+    }
+
+    #[test]
+    fn test_set_preserves_untouched_lines() {
+        let doc = "---\n# comment\ntitle: 'Quoted'\ncount: 1\n---\nbody\n";
+        let out = set(doc, &updates(json!({"count": 2}))).unwrap();
+
+        assert_eq!(out, "---\n# comment\ntitle: 'Quoted'\ncount: 2\n---\nbody\n");
+    }
+
+    #[test]
+    fn test_set_replaces_block_value() {
+        let doc = "---\ntags:\n- a\n- b\n\nstatus: draft\n---\n";
+        let out = set(doc, &updates(json!({"tags": ["x"]}))).unwrap();
+
+        assert_eq!(out, "---\ntags:\n- x\n\nstatus: draft\n---\n");
+    }
+
+    #[test]
+    fn test_set_unchanged_value_keeps_formatting() {
+        let doc = "---\ntags: [a, b]\n---\n";
+        let out = set(doc, &updates(json!({"tags": ["a", "b"]}))).unwrap();
+
+        assert_eq!(out, doc);
+    }
+
+    #[test]
+    fn test_set_appends_and_deletes() {
+        let doc = "---\ntitle: T\nobsolete:\n  nested: 1\n---\nbody";
+        let out = set(doc, &updates(json!({"obsolete": null, "new_key": "v"}))).unwrap();
+
+        assert_eq!(out, "---\ntitle: T\nnew_key: v\n---\nbody");
+    }
+
+    #[test]
+    fn test_set_creates_frontmatter() {
+        let out = set("body\r\n", &updates(json!({"title": "New"}))).unwrap();
+        assert_eq!(out, "---\r\ntitle: New\r\n---\r\nbody\r\n");
+
+        // 没有有效更新时不创建
+        let out = set("body", &updates(json!({"title": null}))).unwrap();
+        assert_eq!(out, "body");
+    }
+
+    #[test]
+    fn test_set_preserves_crlf() {
+        let doc = "---\r\na: 1\r\nb: 2\r\n---\r\nbody\r\n";
+        let out = set(doc, &updates(json!({"a": 3}))).unwrap();
+
+        assert_eq!(out, "---\r\na: 3\r\nb: 2\r\n---\r\nbody\r\n");
+    }
+
+    #[test]
+    fn test_top_level_key() {
+        assert_eq!(top_level_key("title: x").as_deref(), Some("title"));
+        assert_eq!(top_level_key("url: http://a.b").as_deref(), Some("url"));
+        assert_eq!(top_level_key("empty:").as_deref(), Some("empty"));
+        assert_eq!(top_level_key("\"a b\": 1").as_deref(), Some("a b"));
+        assert_eq!(top_level_key("  nested: 1"), None);
+        assert_eq!(top_level_key("- item"), None);
+        assert_eq!(top_level_key("# note: x"), None);
+    }
+}
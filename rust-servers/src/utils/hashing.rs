@@ -0,0 +1,322 @@
+// 内容哈希与重复检测模块
+// 计算文本或文件的内容哈希，并基于字符 shingle 的 SimHash 指纹查找 vault 内的重复笔记
+//
+// 完全相同 (规范化后) 的内容标记为 exact，其余按指纹的汉明距离判断近似重复
+
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// 默认 shingle 长度 (字符数)
+pub const DEFAULT_SHINGLE_SIZE: usize = 3;
+
+/// 默认近似重复阈值 (相似度)
+pub const DEFAULT_THRESHOLD: f64 = 0.9;
+
+/// 参与 SimHash 计算的文件最大字节数
+const MAX_TEXT_FILE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// FNV-1a 参数 (shingle 哈希需要跨版本稳定，不使用 std 的 DefaultHasher)
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// ============================================================================
+// 哈希
+// ============================================================================
+
+/// 哈希算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Sha1,
+    Md5,
+    /// 64 位 SimHash 指纹 (相似内容的指纹汉明距离小)
+    Simhash,
+}
+
+/// 计算文本哈希 (十六进制)
+pub fn hash_text(text: &str, algorithm: HashAlgorithm) -> String {
+    match algorithm {
+        HashAlgorithm::Sha256 => hex(&Sha256::digest(text.as_bytes())),
+        HashAlgorithm::Sha1 => hex(&Sha1::digest(text.as_bytes())),
+        HashAlgorithm::Md5 => hex(&Md5::digest(text.as_bytes())),
+        HashAlgorithm::Simhash => format!("{:016x}", simhash(text, DEFAULT_SHINGLE_SIZE)),
+    }
+}
+
+/// 计算文件哈希 (十六进制)，返回 (哈希, 文件大小)
+///
+/// 加密哈希以流式读取，SimHash 需要读入全文并按 UTF-8 解码
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> io::Result<(String, u64)> {
+    match algorithm {
+        HashAlgorithm::Sha256 => digest_file::<Sha256>(path),
+        HashAlgorithm::Sha1 => digest_file::<Sha1>(path),
+        HashAlgorithm::Md5 => digest_file::<Md5>(path),
+        HashAlgorithm::Simhash => {
+            let text = read_text(path)?;
+            let size = text.len() as u64;
+            Ok((hash_text(&text, algorithm), size))
+        }
+    }
+}
+
+fn digest_file<D: Digest + io::Write>(path: &Path) -> io::Result<(String, u64)> {
+    let mut hasher = D::new();
+    let size = io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok((hex(&hasher.finalize()), size))
+}
+
+/// 读取文本文件 (无效 UTF-8 以替换字符处理)
+pub fn read_text(path: &Path) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    File::open(path)?
+        .take(MAX_TEXT_FILE_BYTES)
+        .read_to_end(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// ============================================================================
+// SimHash
+// ============================================================================
+
+/// 规范化文本：小写，字母数字之外的字符合并为单个空格
+fn normalize(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut pending_space = false;
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            if pending_space && !result.is_empty() {
+                result.push(' ');
+            }
+            pending_space = false;
+            result.push(c);
+        } else {
+            pending_space = true;
+        }
+    }
+    result
+}
+
+fn fnv1a(text: &str) -> u64 {
+    text.bytes()
+        .fold(FNV_OFFSET, |hash, b| (hash ^ u64::from(b)).wrapping_mul(FNV_PRIME))
+}
+
+/// 计算文本的 64 位 SimHash (基于规范化文本的字符 shingle)
+pub fn simhash(text: &str, shingle_size: usize) -> u64 {
+    fingerprint(&normalize(text), shingle_size)
+}
+
+fn fingerprint(normalized: &str, shingle_size: usize) -> u64 {
+    if normalized.is_empty() {
+        return 0;
+    }
+
+    let shingle_size = shingle_size.max(1);
+    let boundaries: Vec<usize> = normalized
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(normalized.len()))
+        .collect();
+    let chars = boundaries.len() - 1;
+
+    let mut weights = [0i64; 64];
+    let windows = chars.saturating_sub(shingle_size) + 1;
+    for start in 0..windows {
+        let end = (start + shingle_size).min(chars);
+        let hash = fnv1a(&normalized[boundaries[start]..boundaries[end]]);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| **w > 0)
+        .fold(0u64, |acc, (bit, _)| acc | 1 << bit)
+}
+
+/// 两个指纹的相似度 (1 - 汉明距离 / 64)
+pub fn fingerprint_similarity(a: u64, b: u64) -> f64 {
+    1.0 - f64::from((a ^ b).count_ones()) / 64.0
+}
+
+// ============================================================================
+// 重复检测
+// ============================================================================
+
+/// 一组重复内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateGroup {
+    /// 成员在输入中的下标 (升序)
+    pub items: Vec<usize>,
+    /// 组内形成连接的最低相似度
+    pub similarity: f64,
+    /// 所有成员规范化后完全相同
+    pub exact: bool,
+}
+
+/// 查找重复或近似重复的文本
+///
+/// None (读取失败) 和规范化后为空的文本不参与比较
+pub fn find_duplicates(texts: &[Option<String>], threshold: f64, shingle_size: usize) -> Vec<DuplicateGroup> {
+    let entries: Vec<(usize, String)> = texts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, text)| Some((i, normalize(text.as_deref()?))))
+        .filter(|(_, text)| !text.is_empty())
+        .collect();
+    let fingerprints: Vec<u64> = entries
+        .iter()
+        .map(|(_, text)| fingerprint(text, shingle_size))
+        .collect();
+
+    let mut groups = UnionFind::new(entries.len());
+    for a in 0..entries.len() {
+        for b in a + 1..entries.len() {
+            let similarity = if entries[a].1 == entries[b].1 {
+                1.0
+            } else {
+                fingerprint_similarity(fingerprints[a], fingerprints[b])
+            };
+            if similarity >= threshold {
+                groups.union(a, b, similarity);
+            }
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..entries.len() {
+        members.entry(groups.find(i)).or_default().push(i);
+    }
+
+    let mut result: Vec<DuplicateGroup> = members
+        .into_iter()
+        .filter(|(_, items)| items.len() > 1)
+        .map(|(root, items)| DuplicateGroup {
+            exact: items.iter().all(|&i| entries[i].1 == entries[items[0]].1),
+            similarity: groups.min_similarity[root],
+            items: items.iter().map(|&i| entries[i].0).collect(),
+        })
+        .collect();
+    result.sort_by_key(|group| group.items[0]);
+    result
+}
+
+/// 并查集，记录每组合并时的最低相似度
+struct UnionFind {
+    parent: Vec<usize>,
+    min_similarity: Vec<f64>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        Self {
+            parent: (0..size).collect(),
+            min_similarity: vec![1.0; size],
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize, similarity: f64) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        let min = self.min_similarity[ra]
+            .min(self.min_similarity[rb])
+            .min(similarity);
+        let root = ra.min(rb);
+        self.parent[ra.max(rb)] = root;
+        self.min_similarity[root] = min;
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_text() {
+        assert_eq!(
+            hash_text("abc", HashAlgorithm::Sha256),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(hash_text("abc", HashAlgorithm::Sha1), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(hash_text("abc", HashAlgorithm::Md5), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(hash_text("abc", HashAlgorithm::Simhash).len(), 16);
+    }
+
+    #[test]
+    fn test_hash_file_matches_text() {
+        let path = std::env::temp_dir().join(format!("hashing-test-{}.md", std::process::id()));
+        std::fs::write(&path, "abc").unwrap();
+        let (hash, size) = hash_file(&path, HashAlgorithm::Sha256).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(hash, hash_text("abc", HashAlgorithm::Sha256));
+        assert_eq!(size, 3);
+    }
+
+    #[test]
+    fn test_simhash_similarity() {
+        let a = "The quick brown fox jumps over the lazy dog near the river bank today";
+        let b = "The quick brown fox jumped over the lazy dog near the river bank today";
+        let c = "完全不同的一段中文内容，讨论的是另一个话题";
+        assert_eq!(simhash(a, 3), simhash(&a.to_uppercase(), 3));
+        assert!(fingerprint_similarity(simhash(a, 3), simhash(b, 3)) > 0.8);
+        assert!(fingerprint_similarity(simhash(a, 3), simhash(c, 3)) < 0.8);
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let texts = vec![
+            Some("# 会议记录\n\n讨论了项目进度和下周计划".to_string()),
+            Some("完全无关的另一篇笔记，内容是读书摘录".to_string()),
+            Some("会议记录：讨论了项目进度和下周计划！".to_string()),
+            None,
+            Some("   ".to_string()),
+            Some("".to_string()),
+        ];
+        let groups = find_duplicates(&texts, DEFAULT_THRESHOLD, DEFAULT_SHINGLE_SIZE);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].items, vec![0, 2]);
+        assert!(groups[0].exact);
+        assert_eq!(groups[0].similarity, 1.0);
+    }
+
+    #[test]
+    fn test_find_near_duplicates() {
+        let base = "Rust ownership rules: each value has a single owner, and the value is dropped \
+            when the owner goes out of scope. Borrowing lets code use a value without owning it.";
+        let edited = base.replace("single owner", "unique owner");
+        let texts = vec![Some(base.to_string()), Some(edited)];
+        let groups = find_duplicates(&texts, 0.8, DEFAULT_SHINGLE_SIZE);
+        assert_eq!(groups.len(), 1);
+        assert!(!groups[0].exact);
+        assert!(groups[0].similarity >= 0.8 && groups[0].similarity < 1.0);
+    }
+}
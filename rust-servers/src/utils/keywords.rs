@@ -0,0 +1,322 @@
+// 关键词提取模块
+// 基于 TF-IDF、TextRank 和 RAKE 从笔记内容中提取关键词，用于保存时的标签建议
+//
+// 纯本地计算，不调用 LLM；输入按 Markdown 处理，标记和 frontmatter 会先被去除
+
+use jieba_rs::{KeywordExtract, KeywordExtractConfig, TextRank, TfIdf};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+use super::language::TextLocale;
+use super::markdown::{self, RenderTarget};
+use super::segment::jieba;
+
+/// 默认返回的关键词数量
+pub const DEFAULT_TOP_K: usize = 10;
+
+/// 允许返回的最大关键词数量
+pub const MAX_TOP_K: usize = 100;
+
+/// 关键词最少字符数
+const MIN_KEYWORD_CHARS: usize = 2;
+
+/// RAKE 候选短语最多包含的词数
+const RAKE_MAX_PHRASE_WORDS: usize = 4;
+
+/// TextRank 共现窗口大小
+const TEXTRANK_SPAN: usize = 5;
+
+/// TextRank 参与计算的词性 (名词、地名、动名词、动词、英文)
+const TEXTRANK_POS: &[&str] = &["n", "nr", "ns", "nt", "nz", "vn", "v", "eng"];
+
+/// 英文停用词
+const EN_STOP_WORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "against", "all", "also", "am", "an", "and", "any",
+    "are", "as", "at", "be", "because", "been", "before", "being", "below", "between", "both",
+    "but", "by", "can", "could", "did", "do", "does", "doing", "down", "during", "each", "few",
+    "for", "from", "further", "had", "has", "have", "having", "he", "her", "here", "hers",
+    "him", "his", "how", "i", "if", "in", "into", "is", "it", "its", "just", "me", "more",
+    "most", "my", "no", "nor", "not", "now", "of", "off", "on", "once", "one", "only", "or",
+    "other", "our", "ours", "out", "over", "own", "same", "she", "should", "so", "some", "such",
+    "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "those",
+    "through", "to", "too", "under", "until", "up", "us", "very", "was", "we", "were", "what",
+    "when", "where", "which", "while", "who", "whom", "why", "will", "with", "would", "you",
+    "your", "yours",
+];
+
+/// 中文停用词 (单字词只用于 RAKE 的短语切分)
+const ZH_STOP_WORDS: &[&str] = &[
+    "的", "了", "是", "在", "和", "与", "及", "或", "也", "就", "都", "而", "把", "被", "让",
+    "给", "对", "从", "到", "向", "于", "为", "以", "之", "着", "过", "吗", "呢", "吧", "啊",
+    "我", "你", "他", "她", "它", "这", "那", "有", "很", "又", "还", "再", "但", "等", "个",
+    "我们", "你们", "他们", "她们", "它们", "这个", "那个", "这些", "那些", "这样", "那样",
+    "什么", "怎么", "为什么", "因为", "所以", "但是", "而且", "并且", "或者", "如果", "虽然",
+    "然后", "可以", "可能", "应该", "需要", "没有", "已经", "一个", "一些", "就是", "还是",
+    "自己", "不是", "这里", "那里", "时候", "现在", "今天", "进行", "通过", "以及", "其中",
+];
+
+// ============================================================================
+// 选项与结果
+// ============================================================================
+
+/// 关键词提取算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeywordMethod {
+    /// 词频 × 逆文档频率 (使用 jieba 内置 IDF 词典)
+    #[default]
+    Tfidf,
+    /// 基于词语共现图的 TextRank
+    Textrank,
+    /// 以停用词和标点切分候选短语的 RAKE，可提取多词短语
+    Rake,
+}
+
+/// 单个关键词
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScoredKeyword {
+    /// 关键词文本
+    pub keyword: String,
+    /// 权重 (0.0 - 1.0，相对于得分最高的关键词)
+    pub weight: f64,
+}
+
+// ============================================================================
+// 提取入口
+// ============================================================================
+
+/// 从文本中提取至多 top_k 个关键词，按权重降序排列
+pub fn extract(text: &str, method: KeywordMethod, locale: TextLocale, top_k: usize) -> Vec<ScoredKeyword> {
+    let top_k = top_k.clamp(1, MAX_TOP_K);
+    let mut text = markdown::render(text, RenderTarget::Plain);
+    // 英文关键词不区分大小写
+    if locale.includes_en() {
+        text.make_ascii_lowercase();
+    }
+
+    let scored: Vec<(String, f64)> = match method {
+        KeywordMethod::Tfidf | KeywordMethod::Textrank => {
+            // 多取一些，过滤掉纯数字和另一种语言的词语后再截断
+            let candidates = top_k * 2 + 10;
+            let keywords = match method {
+                KeywordMethod::Tfidf => tfidf().extract_keywords(jieba(), &text, candidates, Vec::new()),
+                _ => textrank().extract_keywords(
+                    jieba(),
+                    &text,
+                    candidates,
+                    TEXTRANK_POS.iter().map(|pos| pos.to_string()).collect(),
+                ),
+            };
+            keywords
+                .into_iter()
+                .filter(|k| is_candidate(&k.keyword, locale))
+                .map(|k| (k.keyword, k.weight))
+                .collect()
+        }
+        KeywordMethod::Rake => rake(&text, locale),
+    };
+
+    normalize_weights(scored, top_k)
+}
+
+/// 关键词需要包含文字，并且符合语言选项
+fn is_candidate(word: &str, locale: TextLocale) -> bool {
+    if !word.chars().any(char::is_alphabetic) {
+        return false;
+    }
+    let has_cjk = word.chars().any(is_cjk);
+    match locale {
+        TextLocale::Auto => true,
+        TextLocale::Zh => has_cjk,
+        TextLocale::En => !has_cjk,
+    }
+}
+
+/// 截断到 top_k，并将权重缩放到 0 - 1
+fn normalize_weights(mut scored: Vec<(String, f64)>, top_k: usize) -> Vec<ScoredKeyword> {
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scored.truncate(top_k);
+
+    let max = scored.first().map(|(_, w)| *w).unwrap_or(0.0);
+    scored
+        .into_iter()
+        .map(|(keyword, weight)| ScoredKeyword {
+            keyword,
+            weight: if max > 0.0 { weight / max } else { 0.0 },
+        })
+        .collect()
+}
+
+// ============================================================================
+// TF-IDF / TextRank
+// ============================================================================
+
+/// 关键词提取使用的停用词 (中英文合并，大小写由 jieba 统一处理)
+fn stop_words() -> BTreeSet<String> {
+    let mut words = jieba_rs::DEFAULT_STOP_WORDS.clone();
+    words.extend(EN_STOP_WORDS.iter().chain(ZH_STOP_WORDS).map(|w| w.to_string()));
+    words
+}
+
+fn extract_config() -> KeywordExtractConfig {
+    KeywordExtractConfig::builder()
+        .set_stop_words(stop_words())
+        .min_keyword_length(MIN_KEYWORD_CHARS)
+        .build()
+        .expect("valid keyword config")
+}
+
+/// 全局 TF-IDF 提取器 (IDF 词典加载耗时较长，首次使用时加载)
+fn tfidf() -> &'static TfIdf {
+    static TFIDF: OnceLock<TfIdf> = OnceLock::new();
+    TFIDF.get_or_init(|| {
+        let mut tfidf = TfIdf::default();
+        *tfidf.config_mut() = extract_config();
+        tfidf
+    })
+}
+
+fn textrank() -> &'static TextRank {
+    static TEXTRANK: OnceLock<TextRank> = OnceLock::new();
+    TEXTRANK.get_or_init(|| TextRank::new(TEXTRANK_SPAN, extract_config()))
+}
+
+// ============================================================================
+// RAKE
+// ============================================================================
+
+/// RAKE 关键短语提取
+///
+/// 以停用词、标点和数字切分出候选短语，词得分为 度数 / 词频，短语得分为各词得分之和
+fn rake(text: &str, locale: TextLocale) -> Vec<(String, f64)> {
+    let stop_words = stop_words();
+    let mut phrases: Vec<Vec<&str>> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for word in jieba().cut(text, false) {
+        if word.trim().is_empty() {
+            // 英文单词之间的空格不切分短语，换行切分
+            if word.contains('\n') && !current.is_empty() {
+                phrases.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let is_word = word.chars().any(char::is_alphabetic) && !stop_words.contains(word);
+        if is_word {
+            current.push(word);
+        } else if !current.is_empty() {
+            phrases.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        phrases.push(current);
+    }
+    phrases.retain(|p| p.len() <= RAKE_MAX_PHRASE_WORDS);
+
+    // 词频与度数 (所在短语的词数之和)
+    let mut frequency: HashMap<&str, f64> = HashMap::new();
+    let mut degree: HashMap<&str, f64> = HashMap::new();
+    for phrase in &phrases {
+        for word in phrase {
+            *frequency.entry(word).or_default() += 1.0;
+            *degree.entry(word).or_default() += phrase.len() as f64;
+        }
+    }
+
+    let mut scores: HashMap<String, f64> = HashMap::new();
+    for phrase in &phrases {
+        let keyword = join_words(phrase);
+        if keyword.chars().count() < MIN_KEYWORD_CHARS || !is_candidate(&keyword, locale) {
+            continue;
+        }
+        let score: f64 = phrase.iter().map(|w| degree[w] / frequency[w]).sum();
+        scores.insert(keyword, score);
+    }
+
+    scores.into_iter().collect()
+}
+
+/// 连接短语中的词语，相邻的英文单词之间加空格
+fn join_words(words: &[&str]) -> String {
+    let mut result = String::new();
+    for word in words {
+        let needs_space = result.chars().last().is_some_and(|c| c.is_ascii_alphanumeric())
+            && word.chars().next().is_some_and(|c| c.is_ascii_alphanumeric());
+        if needs_space {
+            result.push(' ');
+        }
+        result.push_str(word);
+    }
+    result
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZH_TEXT: &str = "今天纽约的天气真好啊，京华大酒店的张尧经理吃了一只北京烤鸭。\
+        后天纽约的天气不好，昨天纽约的天气也不好，北京烤鸭真好吃";
+
+    fn words(keywords: &[ScoredKeyword]) -> Vec<&str> {
+        keywords.iter().map(|k| k.keyword.as_str()).collect()
+    }
+
+    #[test]
+    fn test_tfidf_chinese() {
+        let keywords = extract(ZH_TEXT, KeywordMethod::Tfidf, TextLocale::Auto, 3);
+        assert_eq!(words(&keywords), vec!["北京烤鸭", "纽约", "天气"]);
+        assert_eq!(keywords[0].weight, 1.0);
+        assert!(keywords.windows(2).all(|w| w[0].weight >= w[1].weight));
+    }
+
+    #[test]
+    fn test_textrank_chinese() {
+        let keywords = extract(ZH_TEXT, KeywordMethod::Textrank, TextLocale::Zh, 5);
+        assert!(words(&keywords).contains(&"天气"));
+        assert!(keywords.len() <= 5);
+    }
+
+    #[test]
+    fn test_rake_english_phrases() {
+        let text = "Compatibility of systems of linear constraints over the set of natural numbers. \
+            Criteria of compatibility of a system of linear Diophantine equations are considered.";
+        let keywords = extract(text, KeywordMethod::Rake, TextLocale::En, 3);
+        let words = words(&keywords);
+        assert!(words.contains(&"linear diophantine equations"));
+        assert!(words.contains(&"linear constraints"));
+    }
+
+    #[test]
+    fn test_markdown_and_numbers_are_ignored() {
+        let text = "---\ntags: [draft]\n---\n\n# Rust 异步\n\n**Rust** 的 async 运行时 2026 2026 2026";
+        let keywords = extract(text, KeywordMethod::Tfidf, TextLocale::Auto, 10);
+        let words = words(&keywords);
+        assert!(words.contains(&"rust"));
+        assert!(!words.contains(&"draft"));
+        assert!(!words.contains(&"2026"));
+    }
+
+    #[test]
+    fn test_locale_filters_keywords() {
+        let text = "Obsidian 插件 Obsidian 插件 workflow 工作流";
+        let zh = extract(text, KeywordMethod::Tfidf, TextLocale::Zh, 10);
+        assert!(zh.iter().all(|k| k.keyword.chars().any(is_cjk)));
+        let en = extract(text, KeywordMethod::Tfidf, TextLocale::En, 10);
+        assert_eq!(words(&en), vec!["obsidian", "workflow"]);
+    }
+
+    #[test]
+    fn test_empty_text() {
+        assert!(extract("", KeywordMethod::Rake, TextLocale::Auto, 5).is_empty());
+        assert!(extract("的了", KeywordMethod::Tfidf, TextLocale::Auto, 5).is_empty());
+    }
+}
@@ -1,18 +1,45 @@
 // 语言检测模块
 // 使用 whatlang 库实现语言检测，支持简繁中文区分
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use whatlang::{detect, Lang};
 
 /// 日志宏
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
         }
     };
 }
 
+// ============================================================================
+// 文本语言选项
+// ============================================================================
+
+/// 文本处理使用的语言规则 (日期解析、数字规范化等)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextLocale {
+    /// 同时应用中文和英文规则
+    #[default]
+    Auto,
+    Zh,
+    En,
+}
+
+impl TextLocale {
+    /// 是否应用中文规则
+    pub fn includes_zh(&self) -> bool {
+        matches!(self, Self::Auto | Self::Zh)
+    }
+
+    /// 是否应用英文规则
+    pub fn includes_en(&self) -> bool {
+        matches!(self, Self::Auto | Self::En)
+    }
+}
+
 // ============================================================================
 // 语言检测结果
 // ============================================================================
@@ -640,6 +667,153 @@ impl Default for LanguageDetector {
     }
 }
 
+// ============================================================================
+// 流式语言检测
+// ============================================================================
+
+/// 实时估计只使用最近的字符，便于口述中途切换语言时及时跟随
+pub const STREAM_WINDOW_CHARS: usize = 400;
+
+/// 会话保留的最大字符数，超出后丢弃最早的内容
+const STREAM_MAX_CHARS: usize = 100_000;
+
+/// 新语言的置信度达到该值时立即切换
+const STREAM_SWITCH_CONFIDENCE: f64 = 0.8;
+
+/// 置信度不足时，新语言需要连续出现的次数
+const STREAM_SWITCH_CONFIRMATIONS: usize = 2;
+
+/// 流式检测的一次更新
+#[derive(Debug, Clone, Serialize)]
+pub struct LanguageStreamUpdate {
+    /// 当前语言估计 (尚无可用文本时为 "und")
+    #[serde(flatten)]
+    pub estimate: LanguageDetectionResult,
+    /// 本次更新是否改变了估计的语言
+    pub changed: bool,
+    /// 会话累计的字符数
+    pub chars: usize,
+}
+
+/// 流式语言检测会话
+///
+/// 随 ASR 部分结果逐步更新语言估计：确认的文本累积保存，
+/// 未确认的部分结果每次整体替换；语言切换带有滞后，避免单次误判造成抖动
+pub struct LanguageStream {
+    /// 已确认的文本
+    committed: String,
+    /// 最新的未确认部分结果
+    partial: String,
+    /// 当前估计
+    estimate: Option<LanguageDetectionResult>,
+    /// 待确认的新语言及其连续出现次数
+    candidate: Option<(String, usize)>,
+}
+
+impl LanguageStream {
+    /// 创建新的检测会话
+    pub fn new() -> Self {
+        Self {
+            committed: String::new(),
+            partial: String::new(),
+            estimate: None,
+            candidate: None,
+        }
+    }
+
+    /// 追加文本并更新估计
+    ///
+    /// `partial` 为 true 时文本替换上一次的部分结果，否则作为确认文本追加
+    pub fn append(&mut self, detector: &LanguageDetector, text: &str, partial: bool) -> LanguageStreamUpdate {
+        if partial {
+            self.partial = text.to_string();
+        } else {
+            self.committed.push_str(text);
+            self.partial.clear();
+            self.trim_committed();
+        }
+
+        let window = self.window();
+        let detected = detector.detect(&window);
+        let changed = self.update_estimate(detected);
+
+        LanguageStreamUpdate {
+            estimate: self.current(),
+            changed,
+            chars: self.committed.chars().count() + self.partial.chars().count(),
+        }
+    }
+
+    /// 结束会话，基于全部文本给出最终结果
+    pub fn finish(self, detector: &LanguageDetector) -> LanguageDetectionResult {
+        let text = format!("{}{}", self.committed, self.partial);
+        let result = detector.detect(&text);
+        if result.language == "und" {
+            return self.estimate.unwrap_or(result);
+        }
+        result
+    }
+
+    /// 当前估计
+    pub fn current(&self) -> LanguageDetectionResult {
+        self.estimate
+            .clone()
+            .unwrap_or_else(|| LanguageDetectionResult::new("und", 0.0))
+    }
+
+    /// 最近 STREAM_WINDOW_CHARS 个字符
+    fn window(&self) -> String {
+        let text = format!("{}{}", self.committed, self.partial);
+        let count = text.chars().count();
+        text.chars().skip(count.saturating_sub(STREAM_WINDOW_CHARS)).collect()
+    }
+
+    fn trim_committed(&mut self) {
+        let count = self.committed.chars().count();
+        if count > STREAM_MAX_CHARS {
+            let drop = count - STREAM_MAX_CHARS / 2;
+            self.committed = self.committed.chars().skip(drop).collect();
+        }
+    }
+
+    /// 应用新的检测结果，返回估计的语言是否改变
+    fn update_estimate(&mut self, detected: LanguageDetectionResult) -> bool {
+        if detected.language == "und" {
+            return false;
+        }
+
+        let Some(estimate) = &self.estimate else {
+            self.estimate = Some(detected);
+            return true;
+        };
+
+        if estimate.language == detected.language {
+            self.estimate = Some(detected);
+            self.candidate = None;
+            return false;
+        }
+
+        let seen = match &self.candidate {
+            Some((language, count)) if *language == detected.language => count + 1,
+            _ => 1,
+        };
+        if detected.confidence >= STREAM_SWITCH_CONFIDENCE || seen >= STREAM_SWITCH_CONFIRMATIONS {
+            self.estimate = Some(detected);
+            self.candidate = None;
+            true
+        } else {
+            self.candidate = Some((detected.language, seen));
+            false
+        }
+    }
+}
+
+impl Default for LanguageStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 
 // ============================================================================
 // 测试
@@ -923,4 +1097,37 @@ mod tests {
         let result = korean_result.unwrap();
         assert_eq!(result.language, "ko");
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_language_stream_partial_and_final() {
+        let detector = LanguageDetector::new();
+        let mut stream = LanguageStream::new();
+        
+        let update = stream.append(&detector, "  ", true);
+        assert_eq!(update.estimate.language, "und");
+        assert!(!update.changed);
+        
+        let update = stream.append(&detector, "今天我们讨论一下", true);
+        assert_eq!(update.estimate.language, "zh");
+        assert!(update.changed);
+        
+        // 部分结果被替换而不是累积
+        let update = stream.append(&detector, "今天我们讨论一下项目进度。", false);
+        assert_eq!(update.chars, 13);
+        assert!(!update.changed);
+        
+        assert_eq!(stream.finish(&detector).language, "zh");
+    }
+    
+    #[test]
+    fn test_language_stream_switches_on_window() {
+        let detector = LanguageDetector::new();
+        let mut stream = LanguageStream::new();
+        stream.append(&detector, "这是一段很长的中文口述内容，", false);
+        
+        let english = "Now I am switching to English for the rest of this dictation session. ".repeat(8);
+        let update = stream.append(&detector, &english, false);
+        assert_eq!(update.estimate.language, "en");
+        assert!(update.changed);
+    }
+}
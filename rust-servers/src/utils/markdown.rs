@@ -0,0 +1,190 @@
+// Markdown 渲染模块
+// 使用 pulldown-cmark 将 Markdown 转换为纯文本或 HTML
+
+use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+/// 渲染目标格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderTarget {
+    /// 纯文本 (去除所有标记，用于 TTS / 剪贴板 / LLM 提示词)
+    #[default]
+    Plain,
+    /// HTML
+    Html,
+}
+
+/// 解析选项：GFM 扩展 + Obsidian 双链，frontmatter 不参与渲染
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_WIKILINKS
+}
+
+/// 渲染 Markdown 文本
+pub fn render(text: &str, target: RenderTarget) -> String {
+    let parser = Parser::new_ext(text, parser_options());
+
+    match target {
+        RenderTarget::Html => {
+            let mut output = String::with_capacity(text.len() * 3 / 2);
+            html::push_html(&mut output, parser);
+            output
+        }
+        RenderTarget::Plain => PlainTextWriter::new(text.len()).run(parser),
+    }
+}
+
+// ============================================================================
+// 纯文本输出
+// ============================================================================
+
+/// 纯文本写入器
+///
+/// 块级元素之间以空行分隔，列表项和表格行各占一行，
+/// 表格单元格以制表符分隔；HTML、脚注引用和 frontmatter 被丢弃
+struct PlainTextWriter {
+    output: String,
+    /// 正在跳过的元素层数 (frontmatter)
+    skip_depth: usize,
+}
+
+impl PlainTextWriter {
+    fn new(capacity: usize) -> Self {
+        Self {
+            output: String::with_capacity(capacity),
+            skip_depth: 0,
+        }
+    }
+
+    fn run<'a>(mut self, events: impl Iterator<Item = Event<'a>>) -> String {
+        for event in events {
+            self.handle(event);
+        }
+        self.output.trim().to_string()
+    }
+
+    fn handle(&mut self, event: Event<'_>) {
+        match event {
+            Event::Start(Tag::MetadataBlock(_)) => self.skip_depth += 1,
+            Event::End(TagEnd::MetadataBlock(_)) => {
+                self.skip_depth = self.skip_depth.saturating_sub(1)
+            }
+            _ if self.skip_depth > 0 => {}
+
+            Event::Text(text)
+            | Event::Code(text)
+            | Event::InlineMath(text)
+            | Event::DisplayMath(text) => self.output.push_str(&text),
+            Event::SoftBreak => self.output.push(' '),
+            Event::HardBreak => self.output.push('\n'),
+            Event::Rule => self.end_block(),
+
+            Event::Start(Tag::Item) | Event::Start(Tag::TableRow) | Event::Start(Tag::TableHead) => {
+                self.end_line()
+            }
+            Event::End(TagEnd::TableCell) => self.output.push('\t'),
+            Event::End(TagEnd::TableRow) | Event::End(TagEnd::TableHead) => {
+                let trimmed = self.output.trim_end_matches('\t').len();
+                self.output.truncate(trimmed);
+                self.end_line();
+            }
+            Event::End(TagEnd::Item)
+            | Event::End(TagEnd::DefinitionListTitle)
+            | Event::End(TagEnd::DefinitionListDefinition) => self.end_line(),
+            Event::End(TagEnd::Paragraph)
+            | Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::CodeBlock)
+            | Event::End(TagEnd::BlockQuote(_))
+            | Event::End(TagEnd::List(_))
+            | Event::End(TagEnd::Table)
+            | Event::End(TagEnd::FootnoteDefinition)
+            | Event::End(TagEnd::DefinitionList) => self.end_block(),
+
+            // HTML、脚注引用、任务列表标记等不输出
+            _ => {}
+        }
+    }
+
+    /// 确保当前位于新行开头
+    fn end_line(&mut self) {
+        if !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.output.push('\n');
+        }
+    }
+
+    /// 结束块级元素，保证后面有一个空行
+    fn end_block(&mut self) {
+        if self.output.is_empty() {
+            return;
+        }
+        self.end_line();
+        if !self.output.ends_with("\n\n") {
+            self.output.push('\n');
+        }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_strips_inline_markup() {
+        let text = "# Title\n\nSome **bold** and *italic* with `code` and [a link](http://x).";
+        assert_eq!(
+            render(text, RenderTarget::Plain),
+            "Title\n\nSome bold and italic with code and a link."
+        );
+    }
+
+    #[test]
+    fn test_plain_lists_and_tables() {
+        let text = "- [ ] one\n- [x] two\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+        assert_eq!(render(text, RenderTarget::Plain), "one\ntwo\n\na\tb\n1\t2");
+    }
+
+    #[test]
+    fn test_plain_skips_frontmatter_and_html() {
+        let text = "---\ntitle: x\n---\n\n<div>raw</div>\n\nText<br>here";
+        assert_eq!(render(text, RenderTarget::Plain), "Texthere");
+    }
+
+    #[test]
+    fn test_plain_wikilinks_and_images() {
+        let text = "See [[Note|the note]] and ![alt text](img.png).";
+        assert_eq!(
+            render(text, RenderTarget::Plain),
+            "See the note and alt text."
+        );
+    }
+
+    #[test]
+    fn test_plain_code_block_and_breaks() {
+        let text = "line one\nline two  \nline three\n\n```rust\nfn main() {}\n```\n";
+        assert_eq!(
+            render(text, RenderTarget::Plain),
+            "line one line two\nline three\n\nfn main() {}"
+        );
+    }
+
+    #[test]
+    fn test_html_output() {
+        let html = render("**hi** ~~there~~", RenderTarget::Html);
+        assert_eq!(html, "<p><strong>hi</strong> <del>there</del></p>\n");
+    }
+
+    #[test]
+    fn test_render_target_deserialization() {
+        let target: RenderTarget = serde_json::from_str(r#""html""#).unwrap();
+        assert_eq!(target, RenderTarget::Html);
+    }
+}
@@ -1,37 +1,82 @@
 // Utils 模块
-// 提供语言检测等通用工具功能
+// 提供语言检测 (含流式检测)、中文分词、frontmatter 编辑、Markdown 渲染、文件名生成、正则求值、网页剪藏、日历 (ICS) 解析与生成、日期解析、数字规范化、关键词提取、文本相似度、编码转换、内容哈希与重复检测、拼写检查、文本统计等通用工具功能
 
+pub mod calendar;
+pub mod clip;
+pub mod datetime;
+pub mod frontmatter;
+pub mod hashing;
+pub mod keywords;
 pub mod language;
+pub mod markdown;
+pub mod numbers;
+pub mod regex_eval;
+pub mod segment;
+pub mod similarity;
+pub mod spellcheck;
+pub mod slug;
+pub mod stats;
+pub mod transcode;
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex as TokioMutex;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
-use crate::router::{ModuleHandler, ModuleMessage, ModuleType, RouterError, ServerResponse};
+use crate::jobs::{self, Job, JobKind};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
 use crate::server::WsSender;
-use language::{LanguageDetector, LanguageDetectionResult};
+use crate::system::progress::ProgressReporter;
+use crate::workers::WorkKind;
+use calendar::{Calendar, CalendarEvent, EventInput};
+use clip::{ClippedArticle, WebClipper};
+use datetime::ParsedDateTime;
+use hashing::{DuplicateGroup, HashAlgorithm};
+use keywords::{KeywordMethod, ScoredKeyword};
+use language::{LanguageDetector, LanguageDetectionResult, LanguageStream, LanguageStreamUpdate, TextLocale};
+use markdown::RenderTarget;
+use regex_eval::{RegexCache, RegexMatch, RegexOp, RegexOutcome};
+use segment::{SegmentMode, SegmentToken, Segmenter};
+use similarity::{SimilarityInput, SimilarityMethod, SimilarityScore};
+use spellcheck::{Misspelling, SpellChecker};
+use slug::{SlugPlatform, SlugStyle};
+use stats::{LanguageShare, TextCounts};
+use transcode::{TranscodeError, TranscodeResult};
 
 /// 日志宏
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
     };
 }
 
 macro_rules! log_error {
     ($($arg:tt)*) => {
-        eprintln!("[ERROR] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Error, "", format!($($arg)*));
     };
 }
 
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
         }
     };
 }
 
+/// 创建 Utils 模块错误
+fn utils_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Utils, code, message)
+}
+
+/// 每个连接允许同时存在的流式语言检测会话数
+const MAX_LANGUAGE_STREAMS: usize = 16;
+
 // ============================================================================
 // 消息类型定义
 // ============================================================================
@@ -70,74 +115,1654 @@ impl LanguageDetectedResponse {
             is_simplified: result.is_simplified,
         }
     }
-}
-
-// ============================================================================
-// Utils 模块处理器
-// ============================================================================
-
-/// Utils 模块处理器
-/// 
-/// 提供语言检测等通用工具功能
-pub struct UtilsHandler {
-    /// 语言检测器
-    detector: LanguageDetector,
-    /// WebSocket 发送器
-    ws_sender: Arc<TokioMutex<Option<WsSender>>>,
-}
-
-impl UtilsHandler {
-    /// 创建新的 Utils 处理器
-    pub fn new() -> Self {
-        Self {
-            detector: LanguageDetector::new(),
-            ws_sender: Arc::new(TokioMutex::new(None)),
+}
+
+/// 流式语言检测会话开始请求
+#[derive(Debug, Deserialize)]
+pub struct LanguageStreamStartRequest {
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 流式语言检测会话开始响应
+#[derive(Debug, Serialize)]
+pub struct LanguageStreamStartedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 会话 ID
+    pub session_id: String,
+}
+
+/// 流式语言检测追加文本请求
+#[derive(Debug, Deserialize)]
+pub struct LanguageStreamAppendRequest {
+    /// 会话 ID
+    pub session_id: String,
+    /// ASR 文本
+    pub text: String,
+    /// 是否为部分结果 (替换上一次的部分结果，而不是追加)
+    #[serde(default)]
+    pub partial: bool,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 流式语言检测更新响应
+#[derive(Debug, Serialize)]
+pub struct LanguageStreamUpdatedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 会话 ID
+    pub session_id: String,
+    /// 更新结果
+    #[serde(flatten)]
+    pub update: LanguageStreamUpdate,
+}
+
+/// 流式语言检测会话结束请求
+#[derive(Debug, Deserialize)]
+pub struct LanguageStreamEndRequest {
+    /// 会话 ID
+    pub session_id: String,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 流式语言检测会话结束响应 (基于全部文本的最终结果)
+#[derive(Debug, Serialize)]
+pub struct LanguageStreamEndedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 会话 ID
+    pub session_id: String,
+    /// 最终检测结果
+    #[serde(flatten)]
+    pub result: LanguageDetectionResult,
+}
+
+/// 中文分词请求
+#[derive(Debug, Deserialize)]
+pub struct SegmentRequest {
+    /// 要分词的文本
+    pub text: String,
+    /// 分词模式 (default / search / full)
+    #[serde(default)]
+    pub mode: SegmentMode,
+    /// 是否启用 HMM 新词发现
+    #[serde(default = "default_hmm")]
+    pub hmm: bool,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+fn default_hmm() -> bool {
+    true
+}
+
+/// 中文分词响应
+#[derive(Debug, Serialize)]
+pub struct TextSegmentedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 使用的分词模式
+    pub mode: SegmentMode,
+    /// 分词结果
+    pub tokens: Vec<SegmentToken>,
+}
+
+/// Frontmatter 读取请求
+#[derive(Debug, Deserialize)]
+pub struct FrontmatterGetRequest {
+    /// Markdown 文档内容
+    pub content: String,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// Frontmatter 读取响应
+#[derive(Debug, Serialize)]
+pub struct FrontmatterParsedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 文档是否包含 frontmatter
+    pub has_frontmatter: bool,
+    /// 解析后的 frontmatter (没有时为空对象)
+    pub frontmatter: serde_json::Value,
+}
+
+/// Frontmatter 更新请求
+#[derive(Debug, Deserialize)]
+pub struct FrontmatterSetRequest {
+    /// Markdown 文档内容
+    pub content: String,
+    /// 要更新的键值 (值为 null 表示删除该键)
+    pub updates: serde_json::Map<String, serde_json::Value>,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// Frontmatter 更新响应
+#[derive(Debug, Serialize)]
+pub struct FrontmatterUpdatedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 更新后的完整文档
+    pub content: String,
+}
+
+/// Markdown 渲染请求
+#[derive(Debug, Deserialize)]
+pub struct RenderMarkdownRequest {
+    /// Markdown 文本
+    pub text: String,
+    /// 目标格式 (plain / html)
+    #[serde(default)]
+    pub target: RenderTarget,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// Markdown 渲染响应
+#[derive(Debug, Serialize)]
+pub struct MarkdownRenderedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 目标格式
+    pub target: RenderTarget,
+    /// 渲染结果
+    pub output: String,
+}
+
+/// 文件名 slug 生成请求
+#[derive(Debug, Deserialize)]
+pub struct SlugifyRequest {
+    /// 原始文本
+    pub text: String,
+    /// slug 风格 (filename / ascii / kebab)
+    #[serde(default)]
+    pub style: SlugStyle,
+    /// 目标平台 (portable / windows / macos / linux)
+    #[serde(default)]
+    pub platform: SlugPlatform,
+    /// 最大长度 (UTF-8 字节)
+    #[serde(default = "default_slug_max_length")]
+    pub max_length: usize,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+fn default_slug_max_length() -> usize {
+    slug::DEFAULT_MAX_LENGTH
+}
+
+/// 文件名 slug 生成响应
+#[derive(Debug, Serialize)]
+pub struct SlugifiedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 生成的 slug
+    pub slug: String,
+}
+
+/// 正则求值请求
+#[derive(Debug, Deserialize)]
+pub struct RegexRequest {
+    /// 正则表达式 (Rust regex 语法)
+    pub pattern: String,
+    /// 要处理的文本
+    pub text: String,
+    /// 操作类型 (match / extract / replace)
+    #[serde(default)]
+    pub op: RegexOp,
+    /// 标志 (i / m / s / x / U)
+    #[serde(default)]
+    pub flags: String,
+    /// 替换文本 (replace 操作必填，支持 $1 / ${name})
+    #[serde(default)]
+    pub replacement: Option<String>,
+    /// 执行超时 (毫秒)
+    #[serde(default = "default_regex_timeout_ms")]
+    pub timeout_ms: u64,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+fn default_regex_timeout_ms() -> u64 {
+    regex_eval::DEFAULT_TIMEOUT_MS
+}
+
+/// 正则求值响应
+#[derive(Debug, Serialize)]
+pub struct RegexEvaluatedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 操作类型
+    pub op: RegexOp,
+    /// 是否匹配 (match)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_match: Option<bool>,
+    /// 匹配列表 (extract)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<Vec<RegexMatch>>,
+    /// 匹配数超过上限被截断 (extract)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<bool>,
+    /// 替换结果 (replace)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+/// 网页剪藏请求
+#[derive(Debug, Deserialize)]
+pub struct ClipUrlRequest {
+    /// 网页 URL (http / https)
+    pub url: String,
+    /// 是否下载正文中的图片
+    #[serde(default)]
+    pub download_images: bool,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 网页剪藏响应
+#[derive(Debug, Serialize)]
+pub struct UrlClippedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 剪藏结果
+    #[serde(flatten)]
+    pub article: ClippedArticle,
+}
+
+/// 自然语言日期解析请求
+#[derive(Debug, Deserialize)]
+pub struct ParseDatetimeRequest {
+    /// 包含日期表达的文本
+    pub text: String,
+    /// 语言 (auto / zh / en)
+    #[serde(default)]
+    pub locale: TextLocale,
+    /// 参考时间 (RFC 3339)，缺省为服务器当前时间
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 自然语言日期解析响应
+#[derive(Debug, Serialize)]
+pub struct DatetimeParsedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 是否识别到日期
+    pub found: bool,
+    /// 解析结果 (未识别时省略)
+    #[serde(flatten)]
+    pub result: Option<ParsedDateTime>,
+}
+
+/// ICS 解析请求
+#[derive(Debug, Deserialize)]
+pub struct ParseIcsRequest {
+    /// ICS 文本 (优先于 url)
+    #[serde(default)]
+    pub data: Option<String>,
+    /// 日历订阅链接 (http / https / webcal)
+    #[serde(default)]
+    pub url: Option<String>,
+    /// 只返回在此时间之后结束的事件 (RFC 3339)
+    #[serde(default)]
+    pub from: Option<String>,
+    /// 只返回在此时间之前开始的事件 (RFC 3339)
+    #[serde(default)]
+    pub to: Option<String>,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// ICS 解析响应
+#[derive(Debug, Serialize)]
+pub struct IcsParsedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 解析结果
+    #[serde(flatten)]
+    pub calendar: Calendar,
+}
+
+/// ICS 生成请求
+#[derive(Debug, Deserialize)]
+pub struct GenerateIcsRequest {
+    /// 结构化事件
+    #[serde(default)]
+    pub events: Vec<EventInput>,
+    /// 口述的日程 (如 "下周三下午三点和设计组开会")，识别出的时间作为开始时间
+    #[serde(default)]
+    pub text: Option<String>,
+    /// 口述日程的标题 (缺省为去掉时间后的文本)
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// 口述日程的时长 (分钟)
+    #[serde(default)]
+    pub duration_minutes: Option<u32>,
+    /// 口述日程的地点
+    #[serde(default)]
+    pub location: Option<String>,
+    /// 语言 (auto / zh / en)
+    #[serde(default)]
+    pub locale: TextLocale,
+    /// 参考时间 (RFC 3339)，缺省为服务器当前时间
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// ICS 生成响应
+#[derive(Debug, Serialize)]
+pub struct IcsGeneratedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// ICS 文本
+    pub ics: String,
+    /// 生成的事件
+    pub events: Vec<CalendarEvent>,
+}
+
+/// 数字规范化请求
+#[derive(Debug, Deserialize)]
+pub struct NormalizeNumbersRequest {
+    /// 要规范化的文本
+    pub text: String,
+    /// 语言 (auto / zh / en)
+    #[serde(default)]
+    pub locale: TextLocale,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 数字规范化响应
+#[derive(Debug, Serialize)]
+pub struct NumbersNormalizedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 规范化后的文本
+    pub text: String,
+}
+
+/// 关键词提取请求
+#[derive(Debug, Deserialize)]
+pub struct ExtractKeywordsRequest {
+    /// 笔记内容 (Markdown)
+    pub text: String,
+    /// 返回的关键词数量
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    /// 语言 (auto / zh / en)
+    #[serde(default)]
+    pub language: TextLocale,
+    /// 提取算法 (tfidf / textrank / rake)
+    #[serde(default)]
+    pub method: KeywordMethod,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+fn default_top_k() -> usize {
+    keywords::DEFAULT_TOP_K
+}
+
+/// 关键词提取响应
+#[derive(Debug, Serialize)]
+pub struct KeywordsExtractedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 使用的提取算法
+    pub method: KeywordMethod,
+    /// 关键词 (按权重降序)
+    pub keywords: Vec<ScoredKeyword>,
+}
+
+/// 文本分析请求 (统计、语言、关键词和 token 估算合并为一次请求)
+#[derive(Debug, Deserialize)]
+pub struct AnalyzeRequest {
+    /// 要分析的文本
+    pub text: String,
+    /// 返回的关键词数量
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 文本分析响应
+#[derive(Debug, Serialize)]
+pub struct AnalyzedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 文本计数
+    pub counts: TextCounts,
+    /// 整体语言检测结果
+    pub language: LanguageDetectionResult,
+    /// 按段落汇总的语言占比 (降序)
+    pub languages: Vec<LanguageShare>,
+    /// 关键词 (TF-IDF，按权重降序)
+    pub keywords: Vec<ScoredKeyword>,
+    /// 估算的 LLM token 数
+    pub estimated_tokens: usize,
+}
+
+/// 相似度计算请求
+#[derive(Debug, Deserialize)]
+pub struct SimilarityRequest {
+    /// 第一个输入 (文本或嵌入向量)
+    pub a: SimilarityInput,
+    /// 第二个输入 (文本或嵌入向量)
+    pub b: SimilarityInput,
+    /// 相似度算法 (levenshtein / jaccard / cosine)
+    #[serde(default)]
+    pub method: SimilarityMethod,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 相似度计算响应
+#[derive(Debug, Serialize)]
+pub struct SimilarityScoredResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 使用的相似度算法
+    pub method: SimilarityMethod,
+    /// 计算结果
+    #[serde(flatten)]
+    pub result: SimilarityScore,
+}
+
+/// 编码转换请求 (bytes 与 path 二选一)
+#[derive(Debug, Deserialize)]
+pub struct TranscodeRequest {
+    /// base64 编码的原始字节
+    #[serde(default)]
+    pub bytes: Option<String>,
+    /// 文件路径
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 目标编码 (目前仅支持 utf8)
+    #[serde(default = "default_transcode_target")]
+    pub to: String,
+    /// 源编码标签，提供时跳过自动检测
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// 是否将转换结果以 UTF-8 写回 path (存在解码错误时不写回)
+    #[serde(default)]
+    pub write_back: bool,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+fn default_transcode_target() -> String {
+    "utf8".to_string()
+}
+
+/// 编码转换响应
+#[derive(Debug, Serialize)]
+pub struct TranscodedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 转换结果
+    #[serde(flatten)]
+    pub result: TranscodeResult,
+    /// 是否已写回文件
+    pub written: bool,
+}
+
+/// 内容哈希请求 (texts 与 paths 二选一)
+#[derive(Debug, Deserialize)]
+pub struct HashRequest {
+    /// 文本列表
+    #[serde(default)]
+    pub texts: Option<Vec<String>>,
+    /// 文件路径列表
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
+    /// 哈希算法 (sha256 / sha1 / md5 / simhash)
+    #[serde(default)]
+    pub algorithm: HashAlgorithm,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 单个输入的哈希结果
+#[derive(Debug, Serialize)]
+pub struct HashEntry {
+    /// 输入下标
+    pub index: usize,
+    /// 文件路径 (仅 paths 输入)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 十六进制哈希 (失败时省略)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// 内容字节数 (失败时省略)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// 读取失败原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 内容哈希响应
+#[derive(Debug, Serialize)]
+pub struct HashedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 使用的哈希算法
+    pub algorithm: HashAlgorithm,
+    /// 按输入顺序排列的结果
+    pub hashes: Vec<HashEntry>,
+}
+
+/// 重复检测请求 (texts 与 paths 二选一)
+#[derive(Debug, Deserialize)]
+pub struct FindDuplicatesRequest {
+    /// 文本列表
+    #[serde(default)]
+    pub texts: Option<Vec<String>>,
+    /// 文件路径列表
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
+    /// 近似重复的相似度阈值 (0.0 - 1.0)
+    #[serde(default = "default_duplicate_threshold")]
+    pub threshold: f64,
+    /// shingle 长度 (字符数)
+    #[serde(default = "default_shingle_size")]
+    pub shingle_size: usize,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+fn default_duplicate_threshold() -> f64 {
+    hashing::DEFAULT_THRESHOLD
+}
+
+fn default_shingle_size() -> usize {
+    hashing::DEFAULT_SHINGLE_SIZE
+}
+
+/// 重复检测响应
+#[derive(Debug, Serialize)]
+pub struct DuplicatesFoundResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 重复组 (成员为输入下标)
+    pub groups: Vec<DuplicateGroup>,
+    /// 读取失败的文件 (下标、路径和原因)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<HashEntry>,
+}
+
+/// 拼写检查请求
+#[derive(Debug, Deserialize)]
+pub struct SpellcheckRequest {
+    /// 要检查的文本
+    pub text: String,
+    /// 词典语言 (如 en_US)，任一词典接受即视为正确
+    #[serde(default = "default_spellcheck_languages")]
+    pub languages: Vec<String>,
+    /// vault 标识 (选择自定义词表；连接在 hello 中声明了 vault 时忽略此字段)
+    #[serde(default)]
+    pub vault: Option<String>,
+    /// 词典目录 (缺省搜索系统词典目录)
+    #[serde(default)]
+    pub dictionary_dir: Option<String>,
+    /// 是否返回候选词
+    #[serde(default = "default_suggest")]
+    pub suggest: bool,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+fn default_spellcheck_languages() -> Vec<String> {
+    vec!["en_US".to_string()]
+}
+
+fn default_suggest() -> bool {
+    true
+}
+
+/// 拼写检查响应
+#[derive(Debug, Serialize)]
+pub struct SpellcheckedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 拼写错误 (按出现顺序)
+    pub misspellings: Vec<Misspelling>,
+}
+
+/// 词典加载请求
+#[derive(Debug, Deserialize)]
+pub struct SpellcheckLoadRequest {
+    /// 词典语言 (对应 <language>.aff / <language>.dic)
+    pub language: String,
+    /// 词典目录 (缺省搜索系统词典目录)
+    #[serde(default)]
+    pub dictionary_dir: Option<String>,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 词典加载响应
+#[derive(Debug, Serialize)]
+pub struct DictionaryLoadedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// 本次加载的语言
+    pub language: String,
+    /// 所有已加载的语言
+    pub languages: Vec<String>,
+}
+
+/// 自定义词表更新请求
+#[derive(Debug, Deserialize)]
+pub struct SpellcheckWordsRequest {
+    /// vault 标识 (连接在 hello 中声明了 vault 时忽略此字段)
+    #[serde(default)]
+    pub vault: Option<String>,
+    /// 单词列表
+    pub words: Vec<String>,
+    /// 请求 ID (用于关联响应)
+    pub request_id: String,
+}
+
+/// 自定义词表更新响应
+#[derive(Debug, Serialize)]
+pub struct CustomWordsUpdatedResponse {
+    /// 请求 ID
+    pub request_id: String,
+    /// vault 标识
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault: Option<String>,
+    /// 词表当前大小
+    pub count: usize,
+}
+
+// ============================================================================
+// Utils 模块处理器
+// ============================================================================
+
+/// Utils 模块处理器
+/// 
+/// 提供语言检测、中文分词、frontmatter 编辑等通用工具功能
+pub struct UtilsHandler {
+    /// 语言检测器
+    detector: LanguageDetector,
+    /// 中文分词器
+    segmenter: Segmenter,
+    /// 正则编译缓存
+    regex_cache: Arc<RegexCache>,
+    /// 网页剪藏器
+    clipper: WebClipper,
+    /// 拼写检查器 (词典缓存和自定义词表)
+    spell_checker: Arc<SpellChecker>,
+    /// 流式语言检测会话: session_id → LanguageStream
+    language_streams: TokioMutex<HashMap<String, LanguageStream>>,
+    /// WebSocket 发送器
+    ws_sender: Arc<TokioMutex<Option<WsSender>>>,
+}
+
+impl UtilsHandler {
+    /// 创建新的 Utils 处理器
+    pub fn new() -> Self {
+        Self {
+            detector: LanguageDetector::new(),
+            segmenter: Segmenter::new(),
+            regex_cache: Arc::new(RegexCache::new()),
+            clipper: WebClipper::new(),
+            spell_checker: Arc::new(SpellChecker::new()),
+            language_streams: TokioMutex::new(HashMap::new()),
+            ws_sender: Arc::new(TokioMutex::new(None)),
+        }
+    }
+    
+    /// 处理语言检测请求
+    async fn handle_detect_language(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        // 解析请求
+        let request: DetectLanguageRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 detect_language 请求: {}", e)))?;
+        
+        log_debug!("语言检测请求: request_id={}, text_len={}", 
+            request.request_id, request.text.len());
+        
+        // 执行语言检测
+        let start_time = std::time::Instant::now();
+        let result = self.detector.detect(&request.text);
+        let elapsed = start_time.elapsed();
+        
+        log_info!("语言检测完成: language={}, confidence={:.2}, is_simplified={:?}, elapsed={:?}",
+            result.language, result.confidence, result.is_simplified, elapsed);
+        
+        // 构建响应
+        let response = LanguageDetectedResponse::from_result(request.request_id, result);
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "language_detected".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理流式语言检测会话开始请求
+    async fn handle_language_stream_start(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: LanguageStreamStartRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 detect_language_stream_start 请求: {}", e)))?;
+        
+        let session_id = Uuid::new_v4().to_string();
+        {
+            let mut streams = self.language_streams.lock().await;
+            if streams.len() >= MAX_LANGUAGE_STREAMS {
+                return Err(utils_error(
+                    ErrorCode::LimitExceeded,
+                    format!("语言检测流过多 (最多 {} 个)", MAX_LANGUAGE_STREAMS),
+                ).into());
+            }
+            streams.insert(session_id.clone(), LanguageStream::new());
+        }
+        
+        log_debug!("流式语言检测开始: session_id={}", session_id);
+        
+        let response = LanguageStreamStartedResponse {
+            request_id: request.request_id,
+            session_id,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "language_stream_started".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理流式语言检测追加文本请求
+    async fn handle_language_stream_append(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: LanguageStreamAppendRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 detect_language_stream_append 请求: {}", e)))?;
+        
+        let update = {
+            let mut streams = self.language_streams.lock().await;
+            let stream = streams.get_mut(&request.session_id).ok_or_else(|| {
+                utils_error(ErrorCode::SessionNotFound, format!("语言检测流不存在: {}", request.session_id))
+            })?;
+            stream.append(&self.detector, &request.text, request.partial)
+        };
+        
+        if update.changed {
+            log_info!("流式语言检测估计变化: session_id={}, language={}, confidence={:.2}",
+                request.session_id, update.estimate.language, update.estimate.confidence);
+        }
+        
+        let response = LanguageStreamUpdatedResponse {
+            request_id: request.request_id,
+            session_id: request.session_id,
+            update,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "language_stream_updated".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理流式语言检测会话结束请求
+    async fn handle_language_stream_end(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: LanguageStreamEndRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 detect_language_stream_end 请求: {}", e)))?;
+        
+        let stream = self.language_streams.lock().await
+            .remove(&request.session_id)
+            .ok_or_else(|| {
+                utils_error(ErrorCode::SessionNotFound, format!("语言检测流不存在: {}", request.session_id))
+            })?;
+        let result = stream.finish(&self.detector);
+        
+        log_debug!("流式语言检测结束: session_id={}, language={}", request.session_id, result.language);
+        
+        let response = LanguageStreamEndedResponse {
+            request_id: request.request_id,
+            session_id: request.session_id,
+            result,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "language_stream_ended".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理中文分词请求
+    async fn handle_segment(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SegmentRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 segment 请求: {}", e)))?;
+        
+        log_debug!("分词请求: request_id={}, mode={:?}, text_len={}",
+            request.request_id, request.mode, request.text.len());
+        
+        let start_time = std::time::Instant::now();
+        let tokens = self.segmenter.segment(&request.text, request.mode, request.hmm);
+        let elapsed = start_time.elapsed();
+        
+        log_info!("分词完成: tokens={}, elapsed={:?}", tokens.len(), elapsed);
+        
+        let response = TextSegmentedResponse {
+            request_id: request.request_id,
+            mode: request.mode,
+            tokens,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "text_segmented".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理 frontmatter 读取请求
+    async fn handle_frontmatter_get(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: FrontmatterGetRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 frontmatter_get 请求: {}", e)))?;
+        
+        log_debug!("frontmatter 读取请求: request_id={}, content_len={}",
+            request.request_id, request.content.len());
+        
+        let frontmatter = frontmatter::get(&request.content)
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        
+        let response = FrontmatterParsedResponse {
+            request_id: request.request_id,
+            has_frontmatter: frontmatter.is_some(),
+            frontmatter: frontmatter.unwrap_or_else(|| serde_json::json!({})),
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "frontmatter_parsed".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理 frontmatter 更新请求
+    async fn handle_frontmatter_set(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: FrontmatterSetRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 frontmatter_set 请求: {}", e)))?;
+        
+        log_debug!("frontmatter 更新请求: request_id={}, keys={}",
+            request.request_id, request.updates.len());
+        
+        let content = frontmatter::set(&request.content, &request.updates)
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        
+        let response = FrontmatterUpdatedResponse {
+            request_id: request.request_id,
+            content,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "frontmatter_updated".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理 Markdown 渲染请求
+    async fn handle_render_markdown(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: RenderMarkdownRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 render_markdown 请求: {}", e)))?;
+        
+        log_debug!("Markdown 渲染请求: request_id={}, target={:?}, text_len={}",
+            request.request_id, request.target, request.text.len());
+        
+        let output = markdown::render(&request.text, request.target);
+        
+        let response = MarkdownRenderedResponse {
+            request_id: request.request_id,
+            target: request.target,
+            output,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "markdown_rendered".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理文件名 slug 生成请求
+    async fn handle_slugify(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SlugifyRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 slugify 请求: {}", e)))?;
+        
+        log_debug!("slug 生成请求: request_id={}, style={:?}, platform={:?}",
+            request.request_id, request.style, request.platform);
+        
+        let slug = slug::slugify(&request.text, request.style, request.platform, request.max_length);
+        
+        let response = SlugifiedResponse {
+            request_id: request.request_id,
+            slug,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "slugified".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理正则求值请求
+    async fn handle_regex(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: RegexRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 regex 请求: {}", e)))?;
+        
+        log_debug!("正则求值请求: request_id={}, op={:?}, pattern_len={}, text_len={}",
+            request.request_id, request.op, request.pattern.len(), request.text.len());
+        
+        let start_time = std::time::Instant::now();
+        let regex = self.regex_cache.get_or_compile(&request.pattern, &request.flags)
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        let outcome = regex_eval::evaluate(
+            regex,
+            request.text,
+            request.op,
+            request.replacement,
+            request.timeout_ms,
+        )
+        .await
+        .map_err(|e| {
+            log_error!("正则求值失败: request_id={}, error={}", request.request_id, e);
+            ModuleError::from_error(ModuleType::Utils, &e)
+        })?;
+        
+        log_debug!("正则求值完成: elapsed={:?}", start_time.elapsed());
+        
+        let mut response = RegexEvaluatedResponse {
+            request_id: request.request_id,
+            op: request.op,
+            is_match: None,
+            matches: None,
+            truncated: None,
+            output: None,
+        };
+        match outcome {
+            RegexOutcome::Matched(is_match) => response.is_match = Some(is_match),
+            RegexOutcome::Extracted { matches, truncated } => {
+                response.matches = Some(matches);
+                response.truncated = Some(truncated);
+            }
+            RegexOutcome::Replaced(output) => response.output = Some(output),
+        }
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "regex_evaluated".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理网页剪藏请求
+    async fn handle_clip_url(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: ClipUrlRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 clip_url 请求: {}", e)))?;
+        
+        log_info!("网页剪藏请求: request_id={}, url={}, download_images={}",
+            request.request_id, request.url, request.download_images);
+        
+        let start_time = std::time::Instant::now();
+        let progress = ProgressReporter::new(ModuleType::Utils, msg.request_id(), msg.sender());
+        let cancel = CancellationToken::new();
+        let _job = jobs::register(
+            Job::new(JobKind::Download)
+                .with_request_id(msg.request_id())
+                .with_detail(request.url.clone())
+                .with_cancel(cancel.clone()),
+        );
+        let article = tokio::select! {
+            article = self.clipper.clip(&request.url, request.download_images, &progress) => article
+                .map_err(|e| {
+                    log_error!("网页剪藏失败: request_id={}, error={}", request.request_id, e);
+                    ModuleError::from_error(ModuleType::Utils, &e)
+                })?,
+            _ = cancel.cancelled() => return Err(utils_error(ErrorCode::Cancelled, "网页剪藏已终止").into()),
+        };
+        
+        log_info!("网页剪藏完成: title={}, markdown_len={}, images={}, elapsed={:?}",
+            article.title, article.markdown.len(), article.images.len(), start_time.elapsed());
+        
+        let response = UrlClippedResponse {
+            request_id: request.request_id,
+            article,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "url_clipped".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理自然语言日期解析请求
+    async fn handle_parse_datetime(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: ParseDatetimeRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 parse_datetime 请求: {}", e)))?;
+        
+        log_debug!("日期解析请求: request_id={}, locale={:?}, text={}",
+            request.request_id, request.locale, request.text);
+        
+        let reference = datetime::parse_reference(request.reference.as_deref())
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        let result = datetime::parse(&request.text, request.locale, reference);
+        
+        let response = DatetimeParsedResponse {
+            request_id: request.request_id,
+            found: result.is_some(),
+            result,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "datetime_parsed".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理 ICS 解析请求
+    async fn handle_parse_ics(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: ParseIcsRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 parse_ics 请求: {}", e)))?;
+        
+        let from = request.from.as_deref().map(|from| datetime::parse_reference(Some(from))).transpose()
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        let to = request.to.as_deref().map(|to| datetime::parse_reference(Some(to))).transpose()
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        
+        let data = match (request.data, request.url.as_deref()) {
+            (Some(data), _) => data,
+            (None, Some(url)) => {
+                log_info!("下载日历: request_id={}, url={}", request.request_id, url);
+                calendar::fetch(url).await.map_err(|e| {
+                    log_error!("日历下载失败: request_id={}, error={}", request.request_id, e);
+                    ModuleError::from_error(ModuleType::Utils, &e)
+                })?
+            }
+            (None, None) => {
+                return Err(utils_error(ErrorCode::InvalidParams, "必须提供 'data' 或 'url'").into());
+            }
+        };
+        if data.len() > calendar::MAX_ICS_BYTES {
+            return Err(ModuleError::from_error(ModuleType::Utils, &calendar::CalendarError::TooLarge(calendar::MAX_ICS_BYTES)).into());
+        }
+        
+        let mut parsed = tokio::task::spawn_blocking(move || calendar::parse(&data))
+            .await
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("日历任务失败: {}", e)))?
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        calendar::filter_range(&mut parsed, from, to);
+        
+        log_debug!("日历解析完成: request_id={}, events={}, skipped={}",
+            request.request_id, parsed.events.len(), parsed.skipped);
+        
+        let response = IcsParsedResponse {
+            request_id: request.request_id,
+            calendar: parsed,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "ics_parsed".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理 ICS 生成请求 (结构化事件和 / 或口述日程)
+    async fn handle_generate_ics(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: GenerateIcsRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 generate_ics 请求: {}", e)))?;
+        
+        let mut events = request.events;
+        if let Some(text) = request.text.as_deref() {
+            log_debug!("口述日程: request_id={}, locale={:?}, text={}", request.request_id, request.locale, text);
+            let reference = datetime::parse_reference(request.reference.as_deref())
+                .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+            let mut event = calendar::event_from_text(text, request.locale, reference)
+                .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+            if let Some(summary) = request.summary {
+                event.summary = summary;
+            }
+            event.duration_minutes = request.duration_minutes;
+            event.location = request.location;
+            events.push(event);
+        }
+        if events.is_empty() {
+            return Err(utils_error(ErrorCode::InvalidParams, "必须提供 'events' 或 'text'").into());
+        }
+        
+        let generated = calendar::generate(&events, chrono::Utc::now())
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        
+        let response = IcsGeneratedResponse {
+            request_id: request.request_id,
+            ics: generated.ics,
+            events: generated.events,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "ics_generated".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理数字规范化请求
+    async fn handle_normalize_numbers(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: NormalizeNumbersRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 normalize_numbers 请求: {}", e)))?;
+        
+        log_debug!("数字规范化请求: request_id={}, locale={:?}, text_len={}",
+            request.request_id, request.locale, request.text.len());
+        
+        let response = NumbersNormalizedResponse {
+            request_id: request.request_id,
+            text: numbers::normalize(&request.text, request.locale),
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "numbers_normalized".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理关键词提取请求
+    async fn handle_extract_keywords(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: ExtractKeywordsRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 extract_keywords 请求: {}", e)))?;
+        
+        log_debug!("关键词提取请求: request_id={}, method={:?}, language={:?}, top_k={}, text_len={}",
+            request.request_id, request.method, request.language, request.top_k, request.text.len());
+        
+        let start_time = std::time::Instant::now();
+        let keywords = keywords::extract(&request.text, request.method, request.language, request.top_k);
+        
+        log_info!("关键词提取完成: count={}, elapsed={:?}", keywords.len(), start_time.elapsed());
+        
+        let response = KeywordsExtractedResponse {
+            request_id: request.request_id,
+            method: request.method,
+            keywords,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "keywords_extracted".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理文本分析请求
+    async fn handle_analyze(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: AnalyzeRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 analyze 请求: {}", e)))?;
+        
+        log_debug!("文本分析请求: request_id={}, top_k={}, text_len={}",
+            request.request_id, request.top_k, request.text.len());
+        
+        let start_time = std::time::Instant::now();
+        let text = &request.text;
+        let response = AnalyzedResponse {
+            counts: stats::count(text),
+            language: self.detector.detect(text),
+            languages: stats::language_shares(&self.detector, text),
+            keywords: keywords::extract(text, KeywordMethod::Tfidf, TextLocale::Auto, request.top_k),
+            estimated_tokens: stats::estimate_tokens(text),
+            request_id: request.request_id,
+        };
+        
+        log_info!("文本分析完成: words={}, language={}, tokens={}, elapsed={:?}",
+            response.counts.words, response.language.language, response.estimated_tokens, start_time.elapsed());
+        
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "analyzed".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理相似度计算请求
+    async fn handle_similarity(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SimilarityRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 similarity 请求: {}", e)))?;
+        
+        log_debug!("相似度计算请求: request_id={}, method={:?}",
+            request.request_id, request.method);
+        
+        // 长文本的编辑距离计算量较大，放到阻塞线程池执行
+        let method = request.method;
+        let (a, b) = (request.a, request.b);
+        let result = tokio::task::spawn_blocking(move || similarity::compare(&a, &b, method))
+            .await
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("相似度计算任务失败: {}", e)))?
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        
+        let response = SimilarityScoredResponse {
+            request_id: request.request_id,
+            method,
+            result,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "similarity_scored".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理编码转换请求
+    async fn handle_transcode(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: TranscodeRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 transcode 请求: {}", e)))?;
+        
+        log_debug!("编码转换请求: request_id={}, path={:?}, encoding={:?}, write_back={}",
+            request.request_id, request.path, request.encoding, request.write_back);
+        
+        let request_id = request.request_id.clone();
+        let path = request.path.clone();
+        let (result, written) = tokio::task::spawn_blocking(move || Self::run_transcode(request))
+            .await
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("转码任务失败: {}", e)))?
+            .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        if let (Some(path), true) = (&path, written) {
+            crate::audit::record_file_write(ModuleType::Utils, std::path::Path::new(path));
         }
+        
+        log_info!("编码转换完成: encoding={}, had_errors={}, written={}",
+            result.encoding, result.had_errors, written);
+        
+        let response = TranscodedResponse {
+            request_id,
+            result,
+            written,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "transcoded".to_string(),
+            payload,
+        }))
     }
     
-    /// 设置 WebSocket 发送器
-    pub async fn set_ws_sender(&self, sender: WsSender) {
-        let mut ws = self.ws_sender.lock().await;
-        *ws = Some(sender);
+    /// 处理内容哈希请求
+    async fn handle_hash(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: HashRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 hash 请求: {}", e)))?;
+        
+        let algorithm = request.algorithm;
+        let hashes = match (request.texts, request.paths) {
+            (Some(texts), _) => texts
+                .iter()
+                .enumerate()
+                .map(|(index, text)| HashEntry {
+                    index,
+                    path: None,
+                    hash: Some(hashing::hash_text(text, algorithm)),
+                    size: Some(text.len() as u64),
+                    error: None,
+                })
+                .collect(),
+            (None, Some(paths)) => {
+                log_debug!("文件哈希请求: request_id={}, files={}", request.request_id, paths.len());
+                crate::workers::spawn_blocking(WorkKind::Indexing, move || Self::hash_paths(paths, algorithm))
+                    .await
+                    .map_err(|e| utils_error(ErrorCode::Internal, format!("哈希任务失败: {}", e)))?
+            }
+            (None, None) => {
+                return Err(utils_error(ErrorCode::InvalidParams, "必须提供 'texts' 或 'paths'").into());
+            }
+        };
+        
+        let response = HashedResponse {
+            request_id: request.request_id,
+            algorithm,
+            hashes,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "hashed".to_string(),
+            payload,
+        }))
     }
     
-    /// 处理语言检测请求
-    async fn handle_detect_language(
+    /// 逐个计算文件哈希，单个文件失败不影响其他文件
+    fn hash_paths(paths: Vec<String>, algorithm: HashAlgorithm) -> Vec<HashEntry> {
+        paths
+            .into_iter()
+            .enumerate()
+            .map(|(index, path)| match hashing::hash_file(std::path::Path::new(&path), algorithm) {
+                Ok((hash, size)) => HashEntry {
+                    index,
+                    path: Some(path),
+                    hash: Some(hash),
+                    size: Some(size),
+                    error: None,
+                },
+                Err(e) => HashEntry {
+                    index,
+                    path: Some(path),
+                    hash: None,
+                    size: None,
+                    error: Some(e.to_string()),
+                },
+            })
+            .collect()
+    }
+    
+    /// 处理重复检测请求
+    async fn handle_find_duplicates(
         &self,
         msg: &ModuleMessage,
     ) -> Result<Option<ServerResponse>, RouterError> {
-        // 解析请求
-        let request: DetectLanguageRequest = serde_json::from_value(msg.payload.clone())
-            .map_err(|e| RouterError::ModuleError(format!("Invalid detect_language request: {}", e)))?;
+        let request: FindDuplicatesRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 find_duplicates 请求: {}", e)))?;
         
-        log_debug!("语言检测请求: request_id={}, text_len={}", 
-            request.request_id, request.text.len());
+        let (threshold, shingle_size) = (request.threshold.clamp(0.0, 1.0), request.shingle_size);
+        let (texts, paths) = match (request.texts, request.paths) {
+            (Some(texts), _) => (texts.into_iter().map(Some).collect(), None),
+            (None, Some(paths)) => (Vec::new(), Some(paths)),
+            (None, None) => {
+                return Err(utils_error(ErrorCode::InvalidParams, "必须提供 'texts' 或 'paths'").into());
+            }
+        };
         
-        // 执行语言检测
         let start_time = std::time::Instant::now();
-        let result = self.detector.detect(&request.text);
-        let elapsed = start_time.elapsed();
+        let (groups, errors) = crate::workers::spawn_blocking(WorkKind::Indexing, move || {
+            let mut texts: Vec<Option<String>> = texts;
+            let mut errors = Vec::new();
+            for (index, path) in paths.into_iter().flatten().enumerate() {
+                match hashing::read_text(std::path::Path::new(&path)) {
+                    Ok(text) => texts.push(Some(text)),
+                    Err(e) => {
+                        texts.push(None);
+                        errors.push(HashEntry {
+                            index,
+                            path: Some(path),
+                            hash: None,
+                            size: None,
+                            error: Some(e.to_string()),
+                        });
+                    }
+                }
+            }
+            (hashing::find_duplicates(&texts, threshold, shingle_size), errors)
+        })
+        .await
+        .map_err(|e| utils_error(ErrorCode::Internal, format!("重复检测任务失败: {}", e)))?;
         
-        log_info!("语言检测完成: language={}, confidence={:.2}, is_simplified={:?}, elapsed={:?}",
-            result.language, result.confidence, result.is_simplified, elapsed);
+        log_info!("重复检测完成: groups={}, errors={}, elapsed={:?}",
+            groups.len(), errors.len(), start_time.elapsed());
         
-        // 构建响应
-        let response = LanguageDetectedResponse::from_result(request.request_id, result);
+        let response = DuplicatesFoundResponse {
+            request_id: request.request_id,
+            groups,
+            errors,
+        };
         let payload = serde_json::to_value(&response)
-            .map_err(|e| RouterError::ModuleError(format!("Failed to serialize response: {}", e)))?;
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
         
         Ok(Some(ServerResponse {
             module: ModuleType::Utils,
-            msg_type: "language_detected".to_string(),
+            msg_type: "duplicates_found".to_string(),
             payload,
         }))
     }
     
-    /// 清理资源
-    pub async fn cleanup(&self) {
-        log_debug!("Utils 模块清理资源");
-        // Utils 模块目前没有需要清理的资源
+    /// 处理拼写检查请求
+    async fn handle_spellcheck(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let mut request: SpellcheckRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 spellcheck 请求: {}", e)))?;
+        // 连接声明的 vault 优先，不能通过请求字段读取其他 vault 的词表
+        request.vault = crate::storage::current_vault().or(request.vault);
+        
+        log_debug!("拼写检查请求: request_id={}, languages={:?}, vault={:?}, text_len={}",
+            request.request_id, request.languages, request.vault, request.text.len());
+        
+        // 首次使用时需要加载词典，候选词计算也较慢，放到阻塞线程池执行
+        let checker = self.spell_checker.clone();
+        let request_id = request.request_id.clone();
+        let misspellings = tokio::task::spawn_blocking(move || {
+            checker.check(
+                &request.text,
+                &request.languages,
+                request.vault.as_deref(),
+                request.dictionary_dir.as_deref().map(std::path::Path::new),
+                request.suggest,
+            )
+        })
+        .await
+        .map_err(|e| utils_error(ErrorCode::Internal, format!("拼写检查任务失败: {}", e)))?
+        .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        
+        let response = SpellcheckedResponse {
+            request_id,
+            misspellings,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "spellchecked".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理词典加载请求
+    async fn handle_spellcheck_load(
+        &self,
+        msg: &ModuleMessage,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let request: SpellcheckLoadRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 spellcheck_load 请求: {}", e)))?;
+        
+        let checker = self.spell_checker.clone();
+        let language = request.language.clone();
+        let dictionary_dir = request.dictionary_dir.clone();
+        tokio::task::spawn_blocking(move || {
+            checker.load(&language, dictionary_dir.as_deref().map(std::path::Path::new))
+        })
+        .await
+        .map_err(|e| utils_error(ErrorCode::Internal, format!("词典加载任务失败: {}", e)))?
+        .map_err(|e| ModuleError::from_error(ModuleType::Utils, &e))?;
+        
+        let response = DictionaryLoadedResponse {
+            request_id: request.request_id,
+            language: request.language,
+            languages: self.spell_checker.loaded_languages(),
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "dictionary_loaded".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 处理自定义词表更新请求 (添加或移除)
+    async fn handle_spellcheck_words(
+        &self,
+        msg: &ModuleMessage,
+        add: bool,
+    ) -> Result<Option<ServerResponse>, RouterError> {
+        let mut request: SpellcheckWordsRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| utils_error(ErrorCode::InvalidParams, format!("无效的 {} 请求: {}", msg.msg_type, e)))?;
+        request.vault = crate::storage::current_vault().or(request.vault);
+        
+        let count = if add {
+            self.spell_checker.add_words(request.vault.as_deref(), &request.words)
+        } else {
+            self.spell_checker.remove_words(request.vault.as_deref(), &request.words)
+        };
+        
+        let response = CustomWordsUpdatedResponse {
+            request_id: request.request_id,
+            vault: request.vault,
+            count,
+        };
+        let payload = serde_json::to_value(&response)
+            .map_err(|e| utils_error(ErrorCode::Internal, format!("响应序列化失败: {}", e)))?;
+        
+        Ok(Some(ServerResponse {
+            module: ModuleType::Utils,
+            msg_type: "custom_words_updated".to_string(),
+            payload,
+        }))
+    }
+    
+    /// 读取输入、转换编码，并按需写回文件
+    fn run_transcode(request: TranscodeRequest) -> Result<(TranscodeResult, bool), TranscodeError> {
+        use base64::Engine;
+        
+        transcode::check_target(&request.to)?;
+        
+        let bytes = match (&request.bytes, &request.path) {
+            (Some(data), _) => base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|e| TranscodeError::InvalidBase64(e.to_string()))?,
+            (None, Some(path)) => transcode::read_file(std::path::Path::new(path))?,
+            (None, None) => return Err(TranscodeError::MissingInput),
+        };
+        
+        let result = transcode::transcode(&bytes, request.encoding.as_deref())?;
+        
+        // 存在解码错误时不写回，避免原文件内容丢失
+        let write_back = request.write_back && request.bytes.is_none() && !result.had_errors;
+        let written = match (&request.path, write_back) {
+            (Some(path), true) => {
+                std::fs::write(path, result.text.as_bytes())?;
+                true
+            }
+            _ => false,
+        };
+        
+        Ok((result, written))
     }
+    
+
 }
 
 impl Default for UtilsHandler {
@@ -150,12 +1775,186 @@ impl Default for UtilsHandler {
 // ModuleHandler 实现
 // ============================================================================
 
+/// Utils 模块支持的消息 (system/describe)
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("detect_language", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("detect_language_stream_start", &[
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("detect_language_stream_append", &[
+        FieldSpec::required("session_id", FieldKind::String),
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("partial", FieldKind::Boolean),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("detect_language_stream_end", &[
+        FieldSpec::required("session_id", FieldKind::String),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("segment", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("mode", FieldKind::String).one_of(&["default", "search", "full"]),
+        FieldSpec::optional("hmm", FieldKind::Boolean),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("frontmatter_get", &[
+        FieldSpec::required("content", FieldKind::String),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("frontmatter_set", &[
+        FieldSpec::required("content", FieldKind::String),
+        FieldSpec::required("updates", FieldKind::Object),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("render_markdown", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("target", FieldKind::String).one_of(&["plain", "html"]),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("slugify", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("style", FieldKind::String).one_of(&["filename", "ascii", "kebab"]),
+        FieldSpec::optional("platform", FieldKind::String).one_of(&["portable", "windows", "macos", "linux"]),
+        FieldSpec::optional("max_length", FieldKind::Integer),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("regex", &[
+        FieldSpec::required("pattern", FieldKind::String),
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("op", FieldKind::String).one_of(&["match", "extract", "replace"]),
+        FieldSpec::optional("flags", FieldKind::String),
+        FieldSpec::optional("replacement", FieldKind::String),
+        FieldSpec::optional("timeout_ms", FieldKind::Integer),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("clip_url", &[
+        FieldSpec::required("url", FieldKind::String),
+        FieldSpec::optional("download_images", FieldKind::Boolean),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]).concurrent(),
+    MessageSpec::new("parse_datetime", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("locale", FieldKind::String).one_of(&["auto", "zh", "en"]),
+        FieldSpec::optional("reference", FieldKind::String),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("parse_ics", &[
+        FieldSpec::optional("data", FieldKind::String),
+        FieldSpec::optional("url", FieldKind::String),
+        FieldSpec::optional("from", FieldKind::String),
+        FieldSpec::optional("to", FieldKind::String),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("generate_ics", &[
+        FieldSpec::optional("events", FieldKind::Array),
+        FieldSpec::optional("text", FieldKind::String),
+        FieldSpec::optional("summary", FieldKind::String),
+        FieldSpec::optional("duration_minutes", FieldKind::Integer),
+        FieldSpec::optional("location", FieldKind::String),
+        FieldSpec::optional("locale", FieldKind::String).one_of(&["auto", "zh", "en"]),
+        FieldSpec::optional("reference", FieldKind::String),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("normalize_numbers", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("locale", FieldKind::String).one_of(&["auto", "zh", "en"]),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("extract_keywords", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("top_k", FieldKind::Integer),
+        FieldSpec::optional("language", FieldKind::String).one_of(&["auto", "zh", "en"]),
+        FieldSpec::optional("method", FieldKind::String).one_of(&["tfidf", "textrank", "rake"]),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("analyze", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("top_k", FieldKind::Integer),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("similarity", &[
+        FieldSpec::required("a", FieldKind::Any),
+        FieldSpec::required("b", FieldKind::Any),
+        FieldSpec::optional("method", FieldKind::String).one_of(&["levenshtein", "jaccard", "cosine"]),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("transcode", &[
+        FieldSpec::optional("bytes", FieldKind::String),
+        FieldSpec::optional("path", FieldKind::String),
+        FieldSpec::optional("to", FieldKind::String),
+        FieldSpec::optional("encoding", FieldKind::String),
+        FieldSpec::optional("write_back", FieldKind::Boolean),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("hash", &[
+        FieldSpec::optional("texts", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("paths", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("algorithm", FieldKind::String).one_of(&["sha256", "sha1", "md5", "simhash"]),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("find_duplicates", &[
+        FieldSpec::optional("texts", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("paths", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("threshold", FieldKind::Number),
+        FieldSpec::optional("shingle_size", FieldKind::Integer),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("spellcheck", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("languages", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::optional("vault", FieldKind::String),
+        FieldSpec::optional("dictionary_dir", FieldKind::String),
+        FieldSpec::optional("suggest", FieldKind::Boolean),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("spellcheck_load", &[
+        FieldSpec::required("language", FieldKind::String),
+        FieldSpec::optional("dictionary_dir", FieldKind::String),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("spellcheck_add_words", &[
+        FieldSpec::optional("vault", FieldKind::String),
+        FieldSpec::required("words", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+    MessageSpec::new("spellcheck_remove_words", &[
+        FieldSpec::optional("vault", FieldKind::String),
+        FieldSpec::required("words", FieldKind::Array).items(FieldKind::String),
+        FieldSpec::required("request_id", FieldKind::String),
+    ]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 工具模块
+    pub const Utils: ModuleType = ModuleType::named("utils");
+}
+
 #[async_trait::async_trait]
 impl ModuleHandler for UtilsHandler {
     fn module_type(&self) -> ModuleType {
         ModuleType::Utils
     }
     
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+    
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        let mut ws = self.ws_sender.lock().await;
+        *ws = Some(sender);
+    }
+    
+    /// 清理资源
+    async fn cleanup(&self) {
+        log_debug!("Utils 模块清理资源");
+        self.language_streams.lock().await.clear();
+    }
+    
     async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
         log_info!("Utils 模块处理消息: {}", msg.msg_type);
         
@@ -163,12 +1962,84 @@ impl ModuleHandler for UtilsHandler {
             "detect_language" => {
                 self.handle_detect_language(msg).await
             }
+            "detect_language_stream_start" => {
+                self.handle_language_stream_start(msg).await
+            }
+            "detect_language_stream_append" => {
+                self.handle_language_stream_append(msg).await
+            }
+            "detect_language_stream_end" => {
+                self.handle_language_stream_end(msg).await
+            }
+            "segment" => {
+                self.handle_segment(msg).await
+            }
+            "frontmatter_get" => {
+                self.handle_frontmatter_get(msg).await
+            }
+            "frontmatter_set" => {
+                self.handle_frontmatter_set(msg).await
+            }
+            "render_markdown" => {
+                self.handle_render_markdown(msg).await
+            }
+            "slugify" => {
+                self.handle_slugify(msg).await
+            }
+            "regex" => {
+                self.handle_regex(msg).await
+            }
+            "clip_url" => {
+                self.handle_clip_url(msg).await
+            }
+            "parse_datetime" => {
+                self.handle_parse_datetime(msg).await
+            }
+            "parse_ics" => {
+                self.handle_parse_ics(msg).await
+            }
+            "generate_ics" => {
+                self.handle_generate_ics(msg).await
+            }
+            "normalize_numbers" => {
+                self.handle_normalize_numbers(msg).await
+            }
+            "extract_keywords" => {
+                self.handle_extract_keywords(msg).await
+            }
+            "analyze" => {
+                self.handle_analyze(msg).await
+            }
+            "similarity" => {
+                self.handle_similarity(msg).await
+            }
+            "transcode" => {
+                self.handle_transcode(msg).await
+            }
+            "hash" => {
+                self.handle_hash(msg).await
+            }
+            "find_duplicates" => {
+                self.handle_find_duplicates(msg).await
+            }
+            "spellcheck" => {
+                self.handle_spellcheck(msg).await
+            }
+            "spellcheck_load" => {
+                self.handle_spellcheck_load(msg).await
+            }
+            "spellcheck_add_words" => {
+                self.handle_spellcheck_words(msg, true).await
+            }
+            "spellcheck_remove_words" => {
+                self.handle_spellcheck_words(msg, false).await
+            }
             _ => {
                 log_error!("未知的 Utils 消息类型: {}", msg.msg_type);
-                Err(RouterError::ModuleError(format!(
-                    "Unknown Utils message type: {}",
-                    msg.msg_type
-                )))
+                Err(utils_error(
+                    ErrorCode::UnknownMessageType,
+                    format!("未知的 Utils 消息类型: {}", msg.msg_type),
+                ).into())
             }
         }
     }
@@ -229,6 +2100,7 @@ mod tests {
                 "text": "Hello, this is a test message.",
                 "request_id": "test-789"
             }),
+            connection: None,
         };
         
         let result = handler.handle(&msg).await;
@@ -255,17 +2127,136 @@ mod tests {
             module: ModuleType::Utils,
             msg_type: "unknown_type".to_string(),
             payload: serde_json::json!({}),
+            connection: None,
         };
         
         let result = handler.handle(&msg).await;
         assert!(result.is_err());
         
-        if let Err(RouterError::ModuleError(msg)) = result {
-            assert!(msg.contains("Unknown Utils message type"));
+        if let Err(RouterError::Module(error)) = result {
+            assert_eq!(error.code, ErrorCode::UnknownMessageType);
+            assert_eq!(error.module, ModuleType::Utils);
+            assert!(error.message.contains("未知的 Utils 消息类型"));
         } else {
             panic!("Expected ModuleError");
         }
     }
+
+    #[tokio::test]
+    async fn test_utils_handler_describes_all_messages() {
+        let handler = UtilsHandler::new();
+
+        // 描述中的每个消息类型都应被处理器识别 (缺少必填字段时返回参数错误而非未知类型)
+        for spec in handler.messages() {
+            let msg = ModuleMessage {
+                module: ModuleType::Utils,
+                msg_type: spec.msg_type.to_string(),
+                payload: serde_json::json!({}),
+                connection: None,
+            };
+            if let Err(RouterError::Module(error)) = handler.handle(&msg).await {
+                assert_ne!(error.code, ErrorCode::UnknownMessageType, "{}", spec.msg_type);
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_utils_handler_segment() {
+        let handler = UtilsHandler::new();
+        
+        let msg = ModuleMessage {
+            module: ModuleType::Utils,
+            msg_type: "segment".to_string(),
+            payload: serde_json::json!({
+                "text": "我爱北京天安门",
+                "request_id": "seg-1"
+            }),
+            connection: None,
+        };
+        
+        let response = handler.handle(&msg).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "text_segmented");
+        
+        let payload = response.payload;
+        assert_eq!(payload["request_id"], "seg-1");
+        assert_eq!(payload["mode"], "default");
+        let tokens = payload["tokens"].as_array().unwrap();
+        assert!(tokens.iter().any(|t| t["word"] == "北京" && t["pos"] == "ns"));
+    }
+    
+    #[tokio::test]
+    async fn test_utils_handler_analyze() {
+        let handler = UtilsHandler::new();
+        
+        let msg = ModuleMessage {
+            module: ModuleType::Utils,
+            msg_type: "analyze".to_string(),
+            payload: serde_json::json!({
+                "text": "机器学习是人工智能的一个分支。机器学习算法从数据中学习规律。",
+                "top_k": 3,
+                "request_id": "an-1"
+            }),
+            connection: None,
+        };
+        
+        let response = handler.handle(&msg).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "analyzed");
+        
+        let payload = response.payload;
+        assert_eq!(payload["request_id"], "an-1");
+        assert_eq!(payload["counts"]["sentences"], 2);
+        assert_eq!(payload["language"]["language"], "zh");
+        assert_eq!(payload["languages"][0]["language"], "zh");
+        assert!(payload["keywords"].as_array().unwrap().len() <= 3);
+        assert!(payload["estimated_tokens"].as_u64().unwrap() > 0);
+    }
+    
+    #[tokio::test]
+    async fn test_utils_handler_frontmatter_set() {
+        let handler = UtilsHandler::new();
+        
+        let msg = ModuleMessage {
+            module: ModuleType::Utils,
+            msg_type: "frontmatter_set".to_string(),
+            payload: serde_json::json!({
+                "content": "---\ntitle: Old\n---\nbody",
+                "updates": { "title": "New" },
+                "request_id": "fm-1"
+            }),
+            connection: None,
+        };
+        
+        let response = handler.handle(&msg).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "frontmatter_updated");
+        assert_eq!(response.payload["request_id"], "fm-1");
+        assert_eq!(response.payload["content"], "---\ntitle: New\n---\nbody");
+    }
+    
+    #[tokio::test]
+    async fn test_utils_handler_regex_extract() {
+        let handler = UtilsHandler::new();
+        
+        let msg = ModuleMessage {
+            module: ModuleType::Utils,
+            msg_type: "regex".to_string(),
+            payload: serde_json::json!({
+                "pattern": "(\\d+)",
+                "text": "a1 b22",
+                "op": "extract",
+                "request_id": "re-1"
+            }),
+            connection: None,
+        };
+        
+        let response = handler.handle(&msg).await.unwrap().unwrap();
+        assert_eq!(response.msg_type, "regex_evaluated");
+        
+        let matches = response.payload["matches"].as_array().unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1]["text"], "22");
+        assert_eq!(matches[1]["groups"][0]["text"], "22");
+        assert!(response.payload.get("output").is_none());
+    }
     
     #[tokio::test]
     async fn test_utils_handler_invalid_request() {
@@ -279,6 +2270,7 @@ mod tests {
                 "text": "Hello"
                 // 缺少 request_id
             }),
+            connection: None,
         };
         
         let result = handler.handle(&msg).await;
@@ -0,0 +1,586 @@
+// 数字规范化模块
+// 将口语化的数字表达 ("三千五百"、"twenty five percent") 转换为阿拉伯数字和符号，
+// 用于语音转写结果的后处理
+//
+// 规则偏保守：单独的个位数字 ("一个"、"one of")、以单位开头的词语 ("万一"、"千万")
+// 和钟点表达 ("三点半") 保持原样
+
+use regex::{Captures, Regex};
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use super::language::TextLocale;
+
+/// 规范化文本中的数字表达
+pub fn normalize(text: &str, locale: TextLocale) -> String {
+    let mut result = text.to_string();
+    if locale.includes_zh() {
+        result = normalize_zh(&result);
+    }
+    if locale.includes_en() {
+        result = normalize_en(&result);
+    }
+    result
+}
+
+// ============================================================================
+// 中文数字解析
+// ============================================================================
+
+/// 解析中文或阿拉伯整数
+///
+/// 支持 "二十三"、"两"、"一百零五"、"三万五千"、"一亿"，口语省略形式 "三千五" (3500)、
+/// "一万五" (15000)，以及逐位读法 "二〇二六"
+pub(crate) fn parse_zh_integer(text: &str) -> Option<u64> {
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().all(|c| c.is_ascii_digit()) {
+        return text.parse().ok();
+    }
+    if !text.chars().any(is_zh_unit) {
+        return parse_zh_digits(text);
+    }
+
+    let mut total = 0u64;
+    let mut section = 0u64;
+    let mut current = 0u64;
+    let mut seen_digit = false;
+    // 上一个字符是否为非零数字 (单位形式中不允许 "一五十")
+    let mut prev_nonzero_digit = false;
+    // 紧跟在单位后的数字所省略的单位 ("三千五" 中的 "五" 表示五百)
+    let mut prev_unit: Option<u64> = None;
+    let mut elided_unit: Option<u64> = None;
+
+    for c in text.chars() {
+        match c {
+            '十' | '百' | '千' => {
+                let unit = match c {
+                    '十' => 10,
+                    '百' => 100,
+                    _ => 1000,
+                };
+                // "十五" 省略了前面的 "一"
+                let base = if seen_digit { current } else { 1 };
+                section = section.checked_add(base.checked_mul(unit)?)?;
+                current = 0;
+                seen_digit = false;
+                prev_nonzero_digit = false;
+                prev_unit = Some(unit);
+                elided_unit = None;
+            }
+            '万' | '亿' => {
+                let unit = if c == '万' { 10_000 } else { 100_000_000 };
+                let value = section.checked_add(current)?;
+                if c == '亿' {
+                    total = total.checked_add(value)?.checked_mul(unit)?;
+                } else {
+                    total = total.checked_add(value.checked_mul(unit)?)?;
+                }
+                section = 0;
+                current = 0;
+                seen_digit = false;
+                prev_nonzero_digit = false;
+                prev_unit = Some(unit);
+                elided_unit = None;
+            }
+            _ => {
+                let digit = zh_digit(c)?;
+                if prev_nonzero_digit {
+                    return None;
+                }
+                elided_unit = prev_unit.take();
+                current = u64::from(digit);
+                seen_digit = true;
+                prev_nonzero_digit = digit != 0;
+            }
+        }
+    }
+
+    if let Some(unit) = elided_unit {
+        if unit > 10 && current > 0 {
+            current *= unit / 10;
+        }
+    }
+
+    total.checked_add(section)?.checked_add(current)
+}
+
+/// 逐位解析数字 ("二〇二六" -> 2026)
+pub(crate) fn parse_zh_digits(text: &str) -> Option<u64> {
+    if text.is_empty() {
+        return None;
+    }
+    text.chars().try_fold(0u64, |acc, c| {
+        acc.checked_mul(10)?.checked_add(u64::from(zh_digit(c)?))
+    })
+}
+
+fn zh_digit(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => c.to_digit(10),
+        '零' | '〇' => Some(0),
+        '一' => Some(1),
+        '二' | '两' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        _ => None,
+    }
+}
+
+fn is_zh_unit(c: char) -> bool {
+    matches!(c, '十' | '百' | '千' | '万' | '亿')
+}
+
+// ============================================================================
+// 中文规则
+// ============================================================================
+
+fn zh_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"(?P<pct>百分之)?(?P<int>[0-9零〇一二两三四五六七八九十百千万亿]+)(?:点(?P<frac>[0-9零〇一二三四五六七八九十]+))?(?P<suffix>[分刻钟半])?",
+        )
+        .expect("valid regex")
+    })
+}
+
+fn normalize_zh(text: &str) -> String {
+    replace_matches(text, zh_pattern(), |caps, rest| {
+        let int = caps.name("int")?.as_str();
+        let suffix = caps.name("suffix").map(|m| m.as_str()).unwrap_or_default();
+
+        if let Some(frac) = caps.name("frac") {
+            // "三点十五"、"三点五分" 是钟点
+            let frac = frac.as_str();
+            if frac.contains('十') || !suffix.is_empty() {
+                return None;
+            }
+            let number = format!("{}.{}", parse_zh_integer(int)?, zh_digit_string(frac)?);
+            return Some(match caps.name("pct") {
+                Some(_) => format!("{}%{}", number, suffix),
+                None => format!("{}{}", number, suffix),
+            });
+        }
+
+        if caps.name("pct").is_some() {
+            return Some(format!("{}%{}", parse_zh_integer(int)?, suffix));
+        }
+
+        // "三点"、"十二点半" 是钟点
+        if suffix.is_empty() && rest.starts_with('点') {
+            return None;
+        }
+        if !should_convert_zh(int) {
+            return None;
+        }
+        Some(format!("{}{}", parse_zh_integer(int)?, suffix))
+    })
+}
+
+/// 判断一段中文数字是否应被转换
+fn should_convert_zh(int: &str) -> bool {
+    let count = int.chars().count();
+    if int.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    if int.chars().any(is_zh_unit) {
+        // "万一"、"千万" 等以单位开头的通常是词语，单独的 "十" 也保持原样
+        let first = int.chars().next().unwrap_or_default();
+        return count >= 2 && !matches!(first, '百' | '千' | '万' | '亿');
+    }
+    // 逐位读法 ("二〇二六")，排除 "三三两两" 这样的叠词
+    count >= 3 && !int.contains('两')
+}
+
+/// 小数部分逐位转换
+fn zh_digit_string(text: &str) -> Option<String> {
+    text.chars()
+        .map(|c| zh_digit(c).and_then(|d| char::from_digit(d, 10)))
+        .collect()
+}
+
+// ============================================================================
+// 英文规则
+// ============================================================================
+
+/// 英文数字单词
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EnWord {
+    /// 0 - 19
+    Small(u64),
+    /// 20, 30, ... 90
+    Tens(u64),
+    /// hundred
+    Hundred,
+    /// thousand / million / billion
+    Scale(u64),
+}
+
+fn en_word(word: &str) -> Option<EnWord> {
+    let small = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen",
+        "nineteen",
+    ];
+    let tens = [
+        "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+
+    if let Some(i) = small.iter().position(|w| *w == word) {
+        return Some(EnWord::Small(i as u64));
+    }
+    if let Some(i) = tens.iter().position(|w| *w == word) {
+        return Some(EnWord::Tens((i as u64 + 2) * 10));
+    }
+    match word {
+        "hundred" => Some(EnWord::Hundred),
+        "thousand" => Some(EnWord::Scale(1_000)),
+        "million" => Some(EnWord::Scale(1_000_000)),
+        "billion" => Some(EnWord::Scale(1_000_000_000)),
+        _ => None,
+    }
+}
+
+fn single_digit(word: Option<EnWord>) -> Option<u64> {
+    match word {
+        Some(EnWord::Small(n)) if n < 10 => Some(n),
+        _ => None,
+    }
+}
+
+/// 英文单词及其位置
+struct Token {
+    range: Range<usize>,
+    lower: String,
+    word: Option<EnWord>,
+    /// 与前一个单词之间是否只有空白或单个连字符
+    joined: bool,
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    let word = WORD.get_or_init(|| Regex::new(r"[A-Za-z]+").expect("valid regex"));
+
+    let mut tokens: Vec<Token> = Vec::new();
+    for m in word.find_iter(text) {
+        let joined = tokens.last().is_some_and(|prev| {
+            let gap = &text[prev.range.end..m.start()];
+            !gap.contains('\n') && matches!(gap.trim(), "" | "-")
+        });
+        let lower = m.as_str().to_ascii_lowercase();
+        tokens.push(Token {
+            range: m.range(),
+            word: en_word(&lower),
+            lower,
+            joined,
+        });
+    }
+    tokens
+}
+
+/// 从 start 开始解析的数字短语
+struct EnNumber {
+    /// 整数部分
+    integer: String,
+    /// 小数部分
+    fraction: Option<String>,
+    /// 是否带百分号
+    percent: bool,
+    /// 数字单词个数 (不含 and / point)
+    words: usize,
+    /// 整数值 (逐位读法时为 None)
+    value: Option<u64>,
+    /// 最后一个被消耗的 token 下标
+    last: usize,
+}
+
+fn normalize_en(text: &str) -> String {
+    let tokens = tokenize(text);
+    let mut result = String::with_capacity(text.len());
+    let mut cursor = 0;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let Some(number) = parse_en_number(&tokens, i) else {
+            i += 1;
+            continue;
+        };
+
+        let convert = number.words >= 2
+            || number.percent
+            || number.fraction.is_some()
+            || number.value.is_some_and(|v| v >= 10);
+        if convert {
+            result.push_str(&text[cursor..tokens[i].range.start]);
+            result.push_str(&number.integer);
+            if let Some(fraction) = &number.fraction {
+                result.push('.');
+                result.push_str(fraction);
+            }
+            if number.percent {
+                result.push('%');
+            }
+            cursor = tokens[number.last].range.end;
+        }
+        i = number.last + 1;
+    }
+
+    result.push_str(&text[cursor..]);
+    result
+}
+
+fn parse_en_number(tokens: &[Token], start: usize) -> Option<EnNumber> {
+    let next_joined = |i: usize| tokens.get(i).filter(|t| t.joined);
+
+    let first = &tokens[start];
+    let mut i = start;
+
+    let (integer, value, mut words) =
+        if single_digit(first.word).is_some() && next_joined(start + 1).and_then(|t| single_digit(t.word)).is_some() {
+            // 逐位读法: "one two three" -> 123
+            let mut digits = String::new();
+            while let Some(d) = tokens.get(i).and_then(|t| single_digit(t.word)) {
+                if i > start && !tokens[i].joined {
+                    break;
+                }
+                digits.push(char::from_digit(d as u32, 10).unwrap_or('0'));
+                i += 1;
+            }
+            let words = digits.len();
+            (digits, None, words)
+        } else {
+            let (value, words, end) = parse_en_integer(tokens, start)?;
+            i = end;
+            (value.to_string(), Some(value), words)
+        };
+
+    // 小数: "three point one four"
+    let mut fraction = None;
+    if next_joined(i).is_some_and(|t| t.lower == "point") {
+        let mut digits = String::new();
+        let mut j = i + 1;
+        while let Some(d) = next_joined(j).and_then(|t| single_digit(t.word)) {
+            digits.push(char::from_digit(d as u32, 10).unwrap_or('0'));
+            j += 1;
+        }
+        if !digits.is_empty() {
+            words += digits.len();
+            fraction = Some(digits);
+            i = j;
+        }
+    }
+
+    // 百分比: "percent" / "per cent"
+    let mut percent = false;
+    if next_joined(i).is_some_and(|t| t.lower == "percent") {
+        percent = true;
+        i += 1;
+    } else if next_joined(i).is_some_and(|t| t.lower == "per")
+        && next_joined(i + 1).is_some_and(|t| t.lower == "cent")
+    {
+        percent = true;
+        i += 2;
+    }
+
+    Some(EnNumber {
+        integer,
+        fraction,
+        percent,
+        words,
+        value,
+        last: i - 1,
+    })
+}
+
+/// 按英文读数语法累加整数，返回 (数值, 数字单词个数, 结束下标)
+fn parse_en_integer(tokens: &[Token], start: usize) -> Option<(u64, usize, usize)> {
+    let mut total = 0u64;
+    let mut current = 0u64;
+    let mut words = 0;
+    let mut last_scale = u64::MAX;
+    let mut prev: Option<EnWord> = None;
+    let mut i = start;
+
+    while let Some(token) = tokens.get(i) {
+        if i > start && !token.joined {
+            break;
+        }
+
+        // "a hundred" / "a thousand"
+        if i == start && token.lower == "a" {
+            match tokens.get(i + 1).filter(|t| t.joined).and_then(|t| t.word) {
+                Some(EnWord::Hundred | EnWord::Scale(_)) => {
+                    current = 1;
+                    i += 1;
+                    continue;
+                }
+                _ => return None,
+            }
+        }
+
+        // "one hundred and five"
+        if token.lower == "and" && matches!(prev, Some(EnWord::Hundred | EnWord::Scale(_))) {
+            match tokens.get(i + 1).filter(|t| t.joined).and_then(|t| t.word) {
+                Some(EnWord::Small(_) | EnWord::Tens(_)) => {
+                    i += 1;
+                    continue;
+                }
+                _ => break,
+            }
+        }
+
+        let Some(word) = token.word else {
+            break;
+        };
+        let below_hundred = current % 100;
+        let accepted = match word {
+            EnWord::Small(n) if n < 10 => {
+                below_hundred == 0 || (below_hundred >= 20 && below_hundred.is_multiple_of(10))
+            }
+            EnWord::Small(_) | EnWord::Tens(_) => below_hundred == 0,
+            EnWord::Hundred => (1..100).contains(&current),
+            EnWord::Scale(scale) => current > 0 && scale < last_scale,
+        };
+        // 数字短语不能以单位开头，"zero" 只能单独出现
+        let leading_scale = words == 0 && i == start && matches!(word, EnWord::Hundred | EnWord::Scale(_));
+        let zero_inside = word == EnWord::Small(0) && words > 0;
+        if !accepted || leading_scale || zero_inside {
+            break;
+        }
+
+        match word {
+            EnWord::Small(n) | EnWord::Tens(n) => current += n,
+            EnWord::Hundred => current *= 100,
+            EnWord::Scale(scale) => {
+                total = total.checked_add(current.checked_mul(scale)?)?;
+                current = 0;
+                last_scale = scale;
+            }
+        }
+        words += 1;
+        prev = Some(word);
+        i += 1;
+        if word == EnWord::Small(0) {
+            break;
+        }
+    }
+
+    if words == 0 {
+        return None;
+    }
+    Some((total + current, words, i))
+}
+
+// ============================================================================
+// 工具函数
+// ============================================================================
+
+/// 对每个匹配调用 replace，返回 None 时保留原文
+///
+/// replace 的第二个参数为匹配之后的剩余文本
+fn replace_matches<F>(text: &str, pattern: &Regex, mut replace: F) -> String
+where
+    F: FnMut(&Captures<'_>, &str) -> Option<String>,
+{
+    let mut result = String::with_capacity(text.len());
+    let mut cursor = 0;
+
+    for caps in pattern.captures_iter(text) {
+        let whole = caps.get(0).expect("group 0 always participates");
+        if let Some(replacement) = replace(&caps, &text[whole.end()..]) {
+            result.push_str(&text[cursor..whole.start()]);
+            result.push_str(&replacement);
+            cursor = whole.end();
+        }
+    }
+
+    result.push_str(&text[cursor..]);
+    result
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_zh_integer() {
+        assert_eq!(parse_zh_integer("十"), Some(10));
+        assert_eq!(parse_zh_integer("十五"), Some(15));
+        assert_eq!(parse_zh_integer("二十三"), Some(23));
+        assert_eq!(parse_zh_integer("两"), Some(2));
+        assert_eq!(parse_zh_integer("一百零五"), Some(105));
+        assert_eq!(parse_zh_integer("42"), Some(42));
+        assert_eq!(parse_zh_integer("abc"), None);
+        assert_eq!(parse_zh_integer("三千五百"), Some(3500));
+        assert_eq!(parse_zh_integer("三千五"), Some(3500));
+        assert_eq!(parse_zh_integer("一万五"), Some(15000));
+        assert_eq!(parse_zh_integer("十二万三千零一"), Some(123_001));
+        assert_eq!(parse_zh_integer("一亿五千万"), Some(150_000_000));
+        assert_eq!(parse_zh_integer("二〇二六"), Some(2026));
+        assert_eq!(parse_zh_integer("一五一十"), None);
+    }
+
+    #[test]
+    fn test_normalize_zh() {
+        let zh = |text| normalize(text, TextLocale::Zh);
+        assert_eq!(zh("预算三千五百元"), "预算3500元");
+        assert_eq!(zh("大约一万五的样子"), "大约15000的样子");
+        assert_eq!(zh("增长了百分之二十五"), "增长了25%");
+        assert_eq!(zh("体重六十二点五公斤"), "体重62.5公斤");
+        assert_eq!(zh("二〇二六年十月"), "2026年十月");
+        assert_eq!(zh("第二十三章"), "第23章");
+    }
+
+    #[test]
+    fn test_normalize_zh_keeps_words_and_times() {
+        let zh = |text| normalize(text, TextLocale::Zh);
+        assert_eq!(zh("万一下雨了"), "万一下雨了");
+        assert_eq!(zh("我有一点儿累"), "我有一点儿累");
+        assert_eq!(zh("两个人十分开心"), "两个人十分开心");
+        assert_eq!(zh("三三两两"), "三三两两");
+        assert_eq!(zh("下午三点半"), "下午三点半");
+        assert_eq!(zh("十二点三十分开会"), "十二点三十分开会");
+    }
+
+    #[test]
+    fn test_normalize_en() {
+        let en = |text| normalize(text, TextLocale::En);
+        assert_eq!(en("twenty five percent of users"), "25% of users");
+        assert_eq!(en("Twenty-five per cent"), "25%");
+        assert_eq!(en("one hundred and five days"), "105 days");
+        assert_eq!(en("a thousand words"), "1000 words");
+        assert_eq!(en("two thousand and twenty six"), "2026");
+        assert_eq!(en("three point one four"), "3.14");
+        assert_eq!(en("call five five five one two"), "call 55512");
+        assert_eq!(en("I waited twelve minutes"), "I waited 12 minutes");
+    }
+
+    #[test]
+    fn test_normalize_en_keeps_words() {
+        let en = |text| normalize(text, TextLocale::En);
+        assert_eq!(en("one of them"), "one of them");
+        assert_eq!(en("a few hundred"), "a few hundred");
+        assert_eq!(en("rock and roll"), "rock and roll");
+        assert_eq!(en("someone"), "someone");
+        assert_eq!(en("twenty, thirty"), "20, 30");
+    }
+
+    #[test]
+    fn test_normalize_respects_locale() {
+        let text = "三千五百 and twenty five";
+        assert_eq!(normalize(text, TextLocale::Zh), "3500 and twenty five");
+        assert_eq!(normalize(text, TextLocale::En), "三千五百 and 25");
+        assert_eq!(normalize(text, TextLocale::Auto), "3500 and 25");
+    }
+}
@@ -0,0 +1,389 @@
+// 正则表达式求值模块
+// 为插件提供安全的用户正则执行：编译缓存、大小限制和执行超时
+//
+// regex crate 保证线性时间匹配，不存在灾难性回溯；
+// 超时用于限制超大文本上的总耗时
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 编译缓存容量
+const CACHE_CAPACITY: usize = 64;
+
+/// 编译后程序的大小上限 (字节)
+const COMPILED_SIZE_LIMIT: usize = 2 * 1024 * 1024;
+
+/// 默认执行超时 (毫秒)
+pub const DEFAULT_TIMEOUT_MS: u64 = 1000;
+
+/// 允许的最大执行超时 (毫秒)
+pub const MAX_TIMEOUT_MS: u64 = 10_000;
+
+/// extract 返回的最大匹配数
+pub const MAX_MATCHES: usize = 10_000;
+
+/// 正则求值错误
+#[derive(Debug, Error)]
+pub enum RegexEvalError {
+    #[error("无效的正则表达式: {0}")]
+    InvalidPattern(String),
+
+    #[error("不支持的正则标志: '{0}'")]
+    InvalidFlag(char),
+
+    #[error("替换操作需要 'replacement'")]
+    MissingReplacement,
+
+    #[error("正则求值超时 ({0} ms)")]
+    Timeout(u64),
+
+    #[error("正则求值失败: {0}")]
+    Internal(String),
+}
+
+impl CodedError for RegexEvalError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            RegexEvalError::InvalidPattern(_)
+            | RegexEvalError::InvalidFlag(_)
+            | RegexEvalError::MissingReplacement => ErrorCode::InvalidParams,
+            // 相同输入重试仍会超时，不视为可重试的 Timeout
+            RegexEvalError::Timeout(_) => ErrorCode::LimitExceeded,
+            RegexEvalError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+}
+
+// ============================================================================
+// 操作与结果
+// ============================================================================
+
+/// 正则操作类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegexOp {
+    /// 仅判断是否匹配
+    #[default]
+    Match,
+    /// 提取所有匹配及捕获组
+    Extract,
+    /// 替换匹配内容
+    Replace,
+}
+
+/// 单个捕获组
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegexGroup {
+    /// 组名 (命名捕获组)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 起始偏移 (UTF-16 码元)
+    pub start: usize,
+    /// 结束偏移 (UTF-16 码元)
+    pub end: usize,
+    /// 匹配文本
+    pub text: String,
+}
+
+/// 单个匹配
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegexMatch {
+    /// 起始偏移 (UTF-16 码元)
+    pub start: usize,
+    /// 结束偏移 (UTF-16 码元)
+    pub end: usize,
+    /// 匹配文本
+    pub text: String,
+    /// 捕获组 (按序号，未参与匹配的组为 null)
+    pub groups: Vec<Option<RegexGroup>>,
+}
+
+/// 求值结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegexOutcome {
+    Matched(bool),
+    Extracted { matches: Vec<RegexMatch>, truncated: bool },
+    Replaced(String),
+}
+
+// ============================================================================
+// 编译缓存
+// ============================================================================
+
+/// 已编译正则的 LRU 缓存
+pub struct RegexCache {
+    inner: Mutex<CacheInner>,
+}
+
+struct CacheInner {
+    entries: HashMap<(String, String), Arc<Regex>>,
+    /// 访问顺序，队尾为最近使用
+    order: VecDeque<(String, String)>,
+}
+
+impl RegexCache {
+    /// 创建新的缓存
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(CacheInner {
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// 获取或编译正则
+    pub fn get_or_compile(&self, pattern: &str, flags: &str) -> Result<Arc<Regex>, RegexEvalError> {
+        let key = (pattern.to_string(), normalize_flags(flags)?);
+
+        {
+            let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(regex) = inner.entries.get(&key).cloned() {
+                inner.order.retain(|k| k != &key);
+                inner.order.push_back(key);
+                return Ok(regex);
+            }
+        }
+
+        let regex = Arc::new(compile(pattern, &key.1)?);
+
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.entries.len() >= CACHE_CAPACITY {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.order.retain(|k| k != &key);
+        inner.order.push_back(key.clone());
+        inner.entries.insert(key, regex.clone());
+
+        Ok(regex)
+    }
+}
+
+impl Default for RegexCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 校验并规范化标志 (去重、排序)，支持 JavaScript 风格的 i/m/s/x/U，忽略 g/u
+fn normalize_flags(flags: &str) -> Result<String, RegexEvalError> {
+    let mut result: Vec<char> = Vec::new();
+    for c in flags.chars() {
+        match c {
+            'i' | 'm' | 's' | 'x' | 'U' => {
+                if !result.contains(&c) {
+                    result.push(c);
+                }
+            }
+            'g' | 'u' => {}
+            other => return Err(RegexEvalError::InvalidFlag(other)),
+        }
+    }
+    result.sort_unstable();
+    Ok(result.into_iter().collect())
+}
+
+/// 使用大小限制编译正则
+fn compile(pattern: &str, flags: &str) -> Result<Regex, RegexEvalError> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(flags.contains('i'))
+        .multi_line(flags.contains('m'))
+        .dot_matches_new_line(flags.contains('s'))
+        .ignore_whitespace(flags.contains('x'))
+        .swap_greed(flags.contains('U'))
+        .size_limit(COMPILED_SIZE_LIMIT)
+        .dfa_size_limit(COMPILED_SIZE_LIMIT)
+        .build()
+        .map_err(|e| RegexEvalError::InvalidPattern(e.to_string()))
+}
+
+// ============================================================================
+// 求值
+// ============================================================================
+
+/// 在阻塞线程池中执行正则操作，超时后立即返回错误
+///
+/// 超时的计算无法被中断，会在后台线程中自然结束，结果被丢弃
+pub async fn evaluate(
+    regex: Arc<Regex>,
+    text: String,
+    op: RegexOp,
+    replacement: Option<String>,
+    timeout_ms: u64,
+) -> Result<RegexOutcome, RegexEvalError> {
+    if op == RegexOp::Replace && replacement.is_none() {
+        return Err(RegexEvalError::MissingReplacement);
+    }
+
+    let timeout_ms = timeout_ms.clamp(1, MAX_TIMEOUT_MS);
+    let task = tokio::task::spawn_blocking(move || run(&regex, &text, op, replacement.as_deref()));
+
+    match tokio::time::timeout(Duration::from_millis(timeout_ms), task).await {
+        Ok(Ok(outcome)) => Ok(outcome),
+        Ok(Err(e)) => Err(RegexEvalError::Internal(e.to_string())),
+        Err(_) => Err(RegexEvalError::Timeout(timeout_ms)),
+    }
+}
+
+/// 同步执行正则操作
+pub fn run(regex: &Regex, text: &str, op: RegexOp, replacement: Option<&str>) -> RegexOutcome {
+    match op {
+        RegexOp::Match => RegexOutcome::Matched(regex.is_match(text)),
+        RegexOp::Replace => {
+            RegexOutcome::Replaced(regex.replace_all(text, replacement.unwrap_or_default()).into_owned())
+        }
+        RegexOp::Extract => {
+            let names: Vec<Option<&str>> = regex.capture_names().collect();
+            let mut offsets = Utf16Cursor::new();
+            let mut matches = Vec::new();
+            let mut truncated = false;
+
+            for caps in regex.captures_iter(text) {
+                if matches.len() >= MAX_MATCHES {
+                    truncated = true;
+                    break;
+                }
+
+                let whole = caps.get(0).expect("group 0 always participates");
+                let start = offsets.utf16_at(text, whole.start());
+                let end = offsets.utf16_at(text, whole.end());
+                let groups = (1..caps.len())
+                    .map(|i| {
+                        caps.get(i).map(|m| RegexGroup {
+                            name: names.get(i).copied().flatten().map(str::to_string),
+                            start: offsets.utf16_at(text, m.start()),
+                            end: offsets.utf16_at(text, m.end()),
+                            text: m.as_str().to_string(),
+                        })
+                    })
+                    .collect();
+
+                matches.push(RegexMatch {
+                    start,
+                    end,
+                    text: whole.as_str().to_string(),
+                    groups,
+                });
+            }
+
+            RegexOutcome::Extracted { matches, truncated }
+        }
+    }
+}
+
+/// 字节偏移到 UTF-16 偏移的增量转换器
+///
+/// 匹配按顺序产生，从上次位置继续计数，捕获组之间的小幅回退只重新计算差值
+struct Utf16Cursor {
+    byte: usize,
+    utf16: usize,
+}
+
+impl Utf16Cursor {
+    fn new() -> Self {
+        Self { byte: 0, utf16: 0 }
+    }
+
+    fn utf16_at(&mut self, text: &str, byte: usize) -> usize {
+        if byte >= self.byte {
+            self.utf16 += text[self.byte..byte].encode_utf16().count();
+        } else {
+            self.utf16 -= text[byte..self.byte].encode_utf16().count();
+        }
+        self.byte = byte;
+        self.utf16
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_with_flags() {
+        let cache = RegexCache::new();
+        let regex = cache.get_or_compile("^hello", "im").unwrap();
+        assert_eq!(run(&regex, "x\nHELLO", RegexOp::Match, None), RegexOutcome::Matched(true));
+    }
+
+    #[test]
+    fn test_extract_groups_with_utf16_offsets() {
+        let cache = RegexCache::new();
+        let regex = cache.get_or_compile(r"#(?P<tag>\w+)|(\d+)", "").unwrap();
+
+        let outcome = run(&regex, "😀 #标签 42", RegexOp::Extract, None);
+        let RegexOutcome::Extracted { matches, truncated } = outcome else {
+            panic!("expected extract outcome");
+        };
+
+        assert!(!truncated);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].text, "#标签");
+        assert_eq!((matches[0].start, matches[0].end), (3, 6));
+        let tag = matches[0].groups[0].as_ref().unwrap();
+        assert_eq!(tag.name.as_deref(), Some("tag"));
+        assert_eq!(tag.text, "标签");
+        assert!(matches[0].groups[1].is_none());
+        assert_eq!((matches[1].start, matches[1].end), (7, 9));
+    }
+
+    #[test]
+    fn test_replace() {
+        let cache = RegexCache::new();
+        let regex = cache.get_or_compile(r"(\w+)@(\w+)", "").unwrap();
+        assert_eq!(
+            run(&regex, "a@b c@d", RegexOp::Replace, Some("$2@$1")),
+            RegexOutcome::Replaced("b@a d@c".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_and_flags() {
+        let cache = RegexCache::new();
+        assert!(matches!(
+            cache.get_or_compile("(unclosed", ""),
+            Err(RegexEvalError::InvalidPattern(_))
+        ));
+        assert!(matches!(
+            cache.get_or_compile("a", "q"),
+            Err(RegexEvalError::InvalidFlag('q'))
+        ));
+        // 超出编译大小限制
+        assert!(matches!(
+            cache.get_or_compile(r"\w{1000}{1000}", ""),
+            Err(RegexEvalError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_cache_reuse_and_eviction() {
+        let cache = RegexCache::new();
+        let first = cache.get_or_compile("abc", "gi").unwrap();
+        let second = cache.get_or_compile("abc", "ig").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        for i in 0..CACHE_CAPACITY + 5 {
+            cache.get_or_compile(&format!("p{}", i), "").unwrap();
+        }
+        assert_eq!(cache.inner.lock().unwrap().entries.len(), CACHE_CAPACITY);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_requires_replacement() {
+        let regex = Arc::new(Regex::new("a").unwrap());
+        let result = evaluate(regex, "a".to_string(), RegexOp::Replace, None, 100).await;
+        assert!(matches!(result, Err(RegexEvalError::MissingReplacement)));
+    }
+}
@@ -0,0 +1,292 @@
+// 中文分词模块
+// 使用 jieba-rs 实现分词，返回词语偏移和词性标注
+
+use jieba_rs::{Jieba, TokenizeMode};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// 日志宏
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
+        }
+    };
+}
+
+/// 全局 jieba 实例
+///
+/// 词典加载耗时较长，所有连接共享同一个实例
+static JIEBA: OnceLock<Jieba> = OnceLock::new();
+
+/// 获取全局 jieba 实例（首次调用时加载词典）
+pub fn jieba() -> &'static Jieba {
+    JIEBA.get_or_init(|| {
+        let start_time = std::time::Instant::now();
+        let jieba = Jieba::new();
+        log_debug!("jieba 词典加载完成, elapsed={:?}", start_time.elapsed());
+        jieba
+    })
+}
+
+// ============================================================================
+// 分词模式和结果
+// ============================================================================
+
+/// 分词模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentMode {
+    /// 精确模式：不重叠的最佳切分
+    #[default]
+    Default,
+    /// 搜索引擎模式：在精确模式基础上对长词再切分
+    Search,
+    /// 全模式：列出所有可能成词的片段
+    Full,
+}
+
+/// 分词结果中的单个词语
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SegmentToken {
+    /// 词语文本
+    pub word: String,
+    /// 起始偏移 (UTF-16 码元，与 JavaScript 字符串索引一致)
+    pub start: usize,
+    /// 结束偏移 (UTF-16 码元，不含)
+    pub end: usize,
+    /// 词性标注 (ICTCLAS 标记集，如 n/v/ns/eng)
+    pub pos: String,
+}
+
+// ============================================================================
+// 分词器
+// ============================================================================
+
+/// 中文分词器
+pub struct Segmenter;
+
+impl Segmenter {
+    /// 创建新的分词器
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 对文本进行分词
+    ///
+    /// 空白词语会被过滤掉，偏移量仍对应原始文本
+    pub fn segment(&self, text: &str, mode: SegmentMode, hmm: bool) -> Vec<SegmentToken> {
+        if text.is_empty() {
+            return Vec::new();
+        }
+
+        let jieba = jieba();
+        let offsets = Utf16Offsets::new(text);
+
+        // 精确模式的词性标注结果，按字符区间索引
+        let tagged = self.tag_spans(jieba, text, hmm);
+
+        // 统一转换为 (词语, 起始字符, 结束字符)
+        let spans: Vec<(&str, usize, usize)> = match mode {
+            SegmentMode::Default | SegmentMode::Search => {
+                let tokenize_mode = if mode == SegmentMode::Search {
+                    TokenizeMode::Search
+                } else {
+                    TokenizeMode::Default
+                };
+                jieba
+                    .tokenize(text, tokenize_mode, hmm)
+                    .into_iter()
+                    .map(|t| (t.word, t.start, t.end))
+                    .collect()
+            }
+            SegmentMode::Full => self.full_mode_spans(jieba, text),
+        };
+
+        spans
+            .into_iter()
+            .filter(|(word, _, _)| !word.trim().is_empty())
+            .map(|(word, start, end)| {
+                let pos = tagged
+                    .iter()
+                    .find(|(s, e, _)| *s == start && *e == end)
+                    .map(|(_, _, tag)| tag.clone())
+                    .unwrap_or_else(|| self.tag_word(jieba, word));
+
+                SegmentToken {
+                    word: word.to_string(),
+                    start: offsets.to_utf16(start),
+                    end: offsets.to_utf16(end),
+                    pos,
+                }
+            })
+            .collect()
+    }
+
+    /// 精确模式下的词性标注，返回 (起始字符, 结束字符, 词性)
+    fn tag_spans(&self, jieba: &Jieba, text: &str, hmm: bool) -> Vec<(usize, usize, String)> {
+        let mut spans = Vec::new();
+        let mut char_pos = 0;
+
+        for tag in jieba.tag(text, hmm) {
+            let len = tag.word.chars().count();
+            spans.push((char_pos, char_pos + len, tag.tag.to_string()));
+            char_pos += len;
+        }
+
+        spans
+    }
+
+    /// 对单个词语进行词性标注 (用于搜索/全模式中的子词)
+    fn tag_word(&self, jieba: &Jieba, word: &str) -> String {
+        jieba
+            .tag(word, false)
+            .first()
+            .map(|t| t.tag.to_string())
+            .unwrap_or_else(|| "x".to_string())
+    }
+
+    /// 全模式分词并计算字符偏移
+    ///
+    /// cut_all 的结果按起始位置排列，但可能相互重叠，
+    /// 因此从上一个词的起始位置继续查找当前词
+    fn full_mode_spans<'a>(&self, jieba: &Jieba, text: &'a str) -> Vec<(&'a str, usize, usize)> {
+        let mut spans = Vec::new();
+        let mut search_from = 0;
+
+        for word in jieba.cut_all(text) {
+            if let Some(found) = text[search_from..].find(word) {
+                let byte_start = search_from + found;
+                let start = text[..byte_start].chars().count();
+                let end = start + word.chars().count();
+                spans.push((word, start, end));
+                search_from = byte_start;
+            }
+        }
+
+        spans
+    }
+}
+
+impl Default for Segmenter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// 偏移量转换
+// ============================================================================
+
+/// 字符偏移到 UTF-16 偏移的映射表
+struct Utf16Offsets {
+    /// 第 i 个字符之前的 UTF-16 码元数
+    prefix: Vec<usize>,
+}
+
+impl Utf16Offsets {
+    fn new(text: &str) -> Self {
+        let mut prefix = Vec::with_capacity(text.len() + 1);
+        let mut total = 0;
+        prefix.push(0);
+        for ch in text.chars() {
+            total += ch.len_utf16();
+            prefix.push(total);
+        }
+        Self { prefix }
+    }
+
+    fn to_utf16(&self, char_index: usize) -> usize {
+        self.prefix
+            .get(char_index)
+            .copied()
+            .unwrap_or_else(|| *self.prefix.last().unwrap_or(&0))
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_default_mode() {
+        let segmenter = Segmenter::new();
+        let tokens = segmenter.segment("我来到北京清华大学", SegmentMode::Default, true);
+
+        let words: Vec<&str> = tokens.iter().map(|t| t.word.as_str()).collect();
+        assert_eq!(words, vec!["我", "来到", "北京", "清华大学"]);
+
+        // 偏移量连续且覆盖全文
+        assert_eq!(tokens[0].start, 0);
+        assert_eq!(tokens.last().unwrap().end, 9);
+        for pair in tokens.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+    }
+
+    #[test]
+    fn test_segment_pos_tags() {
+        let segmenter = Segmenter::new();
+        let tokens = segmenter.segment("我爱北京天安门", SegmentMode::Default, true);
+
+        let beijing = tokens.iter().find(|t| t.word == "北京").unwrap();
+        assert_eq!(beijing.pos, "ns");
+        assert_eq!(beijing.start, 2);
+        assert_eq!(beijing.end, 4);
+    }
+
+    #[test]
+    fn test_segment_search_mode_contains_subwords() {
+        let segmenter = Segmenter::new();
+        let tokens = segmenter.segment("中华人民共和国", SegmentMode::Search, true);
+
+        let words: Vec<&str> = tokens.iter().map(|t| t.word.as_str()).collect();
+        assert!(words.contains(&"中华"));
+        assert!(words.contains(&"中华人民共和国"));
+    }
+
+    #[test]
+    fn test_segment_full_mode_offsets() {
+        let segmenter = Segmenter::new();
+        let text = "南京市长江大桥";
+        let tokens = segmenter.segment(text, SegmentMode::Full, true);
+
+        assert!(!tokens.is_empty());
+        let chars: Vec<char> = text.chars().collect();
+        for token in &tokens {
+            let expected: String = chars[token.start..token.end].iter().collect();
+            assert_eq!(token.word, expected);
+        }
+    }
+
+    #[test]
+    fn test_segment_skips_whitespace_and_uses_utf16_offsets() {
+        let segmenter = Segmenter::new();
+        let tokens = segmenter.segment("😀 hello 世界", SegmentMode::Default, true);
+
+        assert!(tokens.iter().all(|t| !t.word.trim().is_empty()));
+
+        // emoji 在 UTF-16 中占 2 个码元
+        let hello = tokens.iter().find(|t| t.word == "hello").unwrap();
+        assert_eq!(hello.start, 3);
+        assert_eq!(hello.end, 8);
+    }
+
+    #[test]
+    fn test_segment_empty_text() {
+        let segmenter = Segmenter::new();
+        assert!(segmenter.segment("", SegmentMode::Default, true).is_empty());
+    }
+
+    #[test]
+    fn test_segment_mode_deserialization() {
+        let mode: SegmentMode = serde_json::from_str(r#""search""#).unwrap();
+        assert_eq!(mode, SegmentMode::Search);
+        let mode: SegmentMode = serde_json::from_str(r#""full""#).unwrap();
+        assert_eq!(mode, SegmentMode::Full);
+    }
+}
@@ -0,0 +1,246 @@
+// 文本相似度模块
+// 提供编辑距离、词集合 Jaccard 和向量余弦三种本地相似度计算，
+// 用于去重和 "似曾相识" 检测，不需要调用 LLM
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+use super::segment::jieba;
+
+/// 编辑距离允许的最大输入长度 (字符数)，计算量为两者长度之积
+pub const MAX_LEVENSHTEIN_CHARS: usize = 10_000;
+
+/// 相似度计算错误
+#[derive(Debug, Error)]
+pub enum SimilarityError {
+    #[error("'{0}' 方法需要文本输入")]
+    TextRequired(&'static str),
+
+    #[error("'cosine' 方法需要两个输入的向量")]
+    VectorsRequired,
+
+    #[error("向量维度不一致: {0} 与 {1}")]
+    DimensionMismatch(usize, usize),
+
+    #[error("文本过长，无法计算 levenshtein: {0} 个字符 (最多 {MAX_LEVENSHTEIN_CHARS})")]
+    TooLong(usize),
+}
+
+impl CodedError for SimilarityError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            SimilarityError::TooLong(_) => ErrorCode::LimitExceeded,
+            _ => ErrorCode::InvalidParams,
+        }
+    }
+}
+
+// ============================================================================
+// 选项与输入
+// ============================================================================
+
+/// 相似度算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityMethod {
+    /// 字符级编辑距离，得分为 1 - 距离 / 较长文本长度
+    #[default]
+    Levenshtein,
+    /// 分词后词集合的交集 / 并集
+    Jaccard,
+    /// 嵌入向量的余弦相似度
+    Cosine,
+}
+
+impl SimilarityMethod {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Levenshtein => "levenshtein",
+            Self::Jaccard => "jaccard",
+            Self::Cosine => "cosine",
+        }
+    }
+}
+
+/// 参与比较的输入：文本或嵌入向量
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum SimilarityInput {
+    Text(String),
+    Vector(Vec<f64>),
+}
+
+/// 相似度结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimilarityScore {
+    /// 相似度 (Levenshtein / Jaccard 为 0.0 - 1.0，Cosine 为 -1.0 - 1.0)
+    pub score: f64,
+    /// 编辑距离 (仅 Levenshtein)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<usize>,
+}
+
+// ============================================================================
+// 计算
+// ============================================================================
+
+/// 计算两个输入的相似度
+pub fn compare(
+    a: &SimilarityInput,
+    b: &SimilarityInput,
+    method: SimilarityMethod,
+) -> Result<SimilarityScore, SimilarityError> {
+    match (method, a, b) {
+        (SimilarityMethod::Cosine, SimilarityInput::Vector(a), SimilarityInput::Vector(b)) => {
+            Ok(SimilarityScore {
+                score: cosine(a, b)?,
+                distance: None,
+            })
+        }
+        (SimilarityMethod::Cosine, _, _) => Err(SimilarityError::VectorsRequired),
+        (_, SimilarityInput::Text(a), SimilarityInput::Text(b)) => match method {
+            SimilarityMethod::Levenshtein => {
+                let (distance, score) = levenshtein(a, b)?;
+                Ok(SimilarityScore {
+                    score,
+                    distance: Some(distance),
+                })
+            }
+            _ => Ok(SimilarityScore {
+                score: jaccard(a, b),
+                distance: None,
+            }),
+        },
+        (method, _, _) => Err(SimilarityError::TextRequired(method.name())),
+    }
+}
+
+/// 字符级编辑距离及归一化得分
+pub fn levenshtein(a: &str, b: &str) -> Result<(usize, f64), SimilarityError> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest > MAX_LEVENSHTEIN_CHARS {
+        return Err(SimilarityError::TooLong(longest));
+    }
+    if longest == 0 {
+        return Ok((0, 1.0));
+    }
+
+    // 两行滚动数组
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let distance = previous[b.len()];
+    Ok((distance, 1.0 - distance as f64 / longest as f64))
+}
+
+/// 词集合 Jaccard 相似度 (jieba 分词，忽略大小写、空白和标点)
+pub fn jaccard(a: &str, b: &str) -> f64 {
+    let a = token_set(a);
+    let b = token_set(b);
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let intersection = a.intersection(&b).count();
+    let union = a.len() + b.len() - intersection;
+    intersection as f64 / union as f64
+}
+
+fn token_set(text: &str) -> HashSet<String> {
+    let text = text.to_lowercase();
+    jieba()
+        .cut(&text, false)
+        .into_iter()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(str::to_string)
+        .collect()
+}
+
+/// 余弦相似度，任一向量为零向量时返回 0
+pub fn cosine(a: &[f64], b: &[f64]) -> Result<f64, SimilarityError> {
+    if a.len() != b.len() {
+        return Err(SimilarityError::DimensionMismatch(a.len(), b.len()));
+    }
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return Ok(0.0);
+    }
+    Ok((dot / (norm_a * norm_b)).clamp(-1.0, 1.0))
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> SimilarityInput {
+        SimilarityInput::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting").unwrap().0, 3);
+        assert_eq!(levenshtein("", "").unwrap(), (0, 1.0));
+        let (distance, score) = levenshtein("会议记录", "会议纪要").unwrap();
+        assert_eq!(distance, 2);
+        assert!((score - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_jaccard() {
+        assert_eq!(jaccard("Hello world", "world, hello!"), 1.0);
+        assert_eq!(jaccard("apple banana", "cherry"), 0.0);
+        let score = jaccard("我来到北京清华大学", "他来到北京");
+        assert!(score > 0.0 && score < 1.0);
+    }
+
+    #[test]
+    fn test_cosine() {
+        assert!((cosine(&[1.0, 0.0], &[1.0, 0.0]).unwrap() - 1.0).abs() < 1e-9);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 1.0]).unwrap().abs() < 1e-9);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]).unwrap(), 0.0);
+        assert!(matches!(
+            cosine(&[1.0], &[1.0, 2.0]),
+            Err(SimilarityError::DimensionMismatch(1, 2))
+        ));
+    }
+
+    #[test]
+    fn test_compare_input_validation() {
+        let vector = SimilarityInput::Vector(vec![1.0, 2.0]);
+        assert!(matches!(
+            compare(&text("a"), &text("b"), SimilarityMethod::Cosine),
+            Err(SimilarityError::VectorsRequired)
+        ));
+        assert!(matches!(
+            compare(&vector, &text("b"), SimilarityMethod::Jaccard),
+            Err(SimilarityError::TextRequired("jaccard"))
+        ));
+        let result = compare(&text("abc"), &text("abd"), SimilarityMethod::Levenshtein).unwrap();
+        assert_eq!(result.distance, Some(1));
+    }
+
+    #[test]
+    fn test_input_deserialization() {
+        let input: SimilarityInput = serde_json::from_str("[0.5, 1]").unwrap();
+        assert_eq!(input, SimilarityInput::Vector(vec![0.5, 1.0]));
+        let input: SimilarityInput = serde_json::from_str(r#""text""#).unwrap();
+        assert_eq!(input, text("text"));
+    }
+}
@@ -0,0 +1,228 @@
+// 文件名 slug 生成模块
+// 将任意文本 (语音转写 / LLM 输出) 转换为各平台都合法的文件名
+
+use deunicode::deunicode;
+use serde::{Deserialize, Serialize};
+
+/// 默认最大长度 (字节)，为路径前缀和扩展名留出余量
+pub const DEFAULT_MAX_LENGTH: usize = 200;
+
+/// 结果为空时使用的名称
+const FALLBACK_NAME: &str = "Untitled";
+
+/// Windows 保留的设备名 (不区分大小写，带扩展名同样无效)
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// ============================================================================
+// 选项
+// ============================================================================
+
+/// slug 风格
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlugStyle {
+    /// 保留原文 (包括中文和大小写)，只处理非法字符
+    #[default]
+    Filename,
+    /// 音译为 ASCII 并去除变音符号，保留大小写和空格
+    Ascii,
+    /// 音译为 ASCII，小写并以连字符连接 (适合 URL / 附件名)
+    Kebab,
+}
+
+/// 目标平台
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlugPlatform {
+    /// 所有平台通用，并避开会破坏 Obsidian 双链的字符
+    #[default]
+    Portable,
+    Windows,
+    Macos,
+    Linux,
+}
+
+impl SlugPlatform {
+    /// 判断字符在该平台的文件名中是否非法
+    fn is_forbidden(&self, c: char) -> bool {
+        if c.is_control() {
+            return true;
+        }
+        match self {
+            Self::Portable => matches!(
+                c,
+                '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' | '#' | '^' | '[' | ']'
+            ),
+            Self::Windows => matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*'),
+            Self::Macos => matches!(c, '/' | ':'),
+            Self::Linux => c == '/',
+        }
+    }
+
+    /// 是否需要处理 Windows 的额外限制 (保留名、结尾的点和空格)
+    fn applies_windows_rules(&self) -> bool {
+        matches!(self, Self::Portable | Self::Windows)
+    }
+}
+
+// ============================================================================
+// slug 生成
+// ============================================================================
+
+/// 生成文件名安全的 slug
+///
+/// `max_length` 以 UTF-8 字节计算，截断时不会切断字符
+pub fn slugify(text: &str, style: SlugStyle, platform: SlugPlatform, max_length: usize) -> String {
+    let source = match style {
+        SlugStyle::Filename => text.to_string(),
+        SlugStyle::Ascii | SlugStyle::Kebab => deunicode(text),
+    };
+
+    let mut slug = match style {
+        SlugStyle::Kebab => kebab_case(&source),
+        SlugStyle::Filename | SlugStyle::Ascii => sanitize(&source, platform),
+    };
+
+    truncate_bytes(&mut slug, max_length.max(1));
+    slug = trim_name(&slug, platform).to_string();
+
+    if platform.applies_windows_rules() && is_windows_reserved(&slug) {
+        slug.insert(0, '_');
+    }
+
+    if slug.is_empty() {
+        return FALLBACK_NAME.to_string();
+    }
+
+    slug
+}
+
+/// 替换非法字符并合并连续空白
+fn sanitize(text: &str, platform: SlugPlatform) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut pending_space = false;
+
+    for c in text.chars() {
+        let c = if platform.is_forbidden(c) { ' ' } else { c };
+        if c.is_whitespace() {
+            pending_space = !result.is_empty();
+            continue;
+        }
+        if pending_space {
+            result.push(' ');
+            pending_space = false;
+        }
+        result.push(c);
+    }
+
+    result
+}
+
+/// 小写字母数字，其余字符合并为单个连字符
+fn kebab_case(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            result.push(c.to_ascii_lowercase());
+        } else if !result.is_empty() && !result.ends_with('-') {
+            result.push('-');
+        }
+    }
+
+    result.trim_end_matches('-').to_string()
+}
+
+/// 按字节截断，保证落在字符边界上
+fn truncate_bytes(text: &mut String, max_bytes: usize) {
+    if text.len() <= max_bytes {
+        return;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+}
+
+/// 去除首尾空白，以及 Windows 不允许的结尾点号和连字符残留
+fn trim_name(text: &str, platform: SlugPlatform) -> &str {
+    let trimmed = text.trim().trim_end_matches('-');
+    if platform.applies_windows_rules() {
+        trimmed.trim_end_matches(['.', ' '])
+    } else {
+        trimmed
+    }
+}
+
+/// 判断是否为 Windows 保留名 (忽略扩展名)
+fn is_windows_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn portable(text: &str, style: SlugStyle) -> String {
+        slugify(text, style, SlugPlatform::Portable, DEFAULT_MAX_LENGTH)
+    }
+
+    #[test]
+    fn test_filename_style_keeps_unicode() {
+        assert_eq!(portable("会议记录: 周一/周二", SlugStyle::Filename), "会议记录 周一 周二");
+        assert_eq!(portable("  What is [[this]]?  ", SlugStyle::Filename), "What is this");
+    }
+
+    #[test]
+    fn test_ascii_style_folds_diacritics() {
+        assert_eq!(portable("Café Crème Brûlée", SlugStyle::Ascii), "Cafe Creme Brulee");
+    }
+
+    #[test]
+    fn test_kebab_style() {
+        assert_eq!(portable("Hello, World! Ünïcode", SlugStyle::Kebab), "hello-world-unicode");
+        assert_eq!(portable("北京", SlugStyle::Kebab), "bei-jing");
+    }
+
+    #[test]
+    fn test_platform_specific_characters() {
+        let text = "a:b#c";
+        assert_eq!(slugify(text, SlugStyle::Filename, SlugPlatform::Portable, 50), "a b c");
+        assert_eq!(slugify(text, SlugStyle::Filename, SlugPlatform::Windows, 50), "a b#c");
+        assert_eq!(slugify(text, SlugStyle::Filename, SlugPlatform::Linux, 50), "a:b#c");
+    }
+
+    #[test]
+    fn test_windows_reserved_and_trailing_dots() {
+        assert_eq!(portable("con", SlugStyle::Filename), "_con");
+        assert_eq!(portable("Aux.md", SlugStyle::Filename), "_Aux.md");
+        assert_eq!(portable("Notes...", SlugStyle::Filename), "Notes");
+        assert_eq!(
+            slugify("Notes...", SlugStyle::Filename, SlugPlatform::Linux, 50),
+            "Notes..."
+        );
+    }
+
+    #[test]
+    fn test_truncate_on_char_boundary() {
+        // 每个汉字 3 字节
+        assert_eq!(slugify("一二三四", SlugStyle::Filename, SlugPlatform::Portable, 7), "一二");
+    }
+
+    #[test]
+    fn test_empty_result_falls_back() {
+        assert_eq!(portable("???", SlugStyle::Filename), "Untitled");
+        assert_eq!(portable("", SlugStyle::Kebab), "Untitled");
+    }
+}
@@ -0,0 +1,404 @@
+// 拼写检查模块
+// 使用 spellbook 加载 Hunspell 词典 (.aff / .dic)，检查文本并给出候选词
+//
+// 词典按语言缓存在内存中，首次使用时从词典目录加载；
+// 每个 vault 有独立的自定义词表，只影响该 vault 的检查结果
+
+use regex::Regex;
+use serde::Serialize;
+use spellbook::Dictionary;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Spellcheck", format!($($arg)*));
+    };
+}
+
+/// 每个错词最多返回的候选词数量
+pub const MAX_SUGGESTIONS: usize = 5;
+
+/// 未指定 vault 时使用的自定义词表
+const DEFAULT_VAULT: &str = "";
+
+/// 拼写检查错误
+#[derive(Debug, Error)]
+pub enum SpellCheckError {
+    #[error("找不到 '{0}' 的词典")]
+    DictionaryNotFound(String),
+
+    #[error("无效的语言代码: '{0}'")]
+    InvalidLanguage(String),
+
+    #[error("无法解析词典 '{language}': {message}")]
+    Parse { language: String, message: String },
+
+    #[error("无法读取词典: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl CodedError for SpellCheckError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            SpellCheckError::DictionaryNotFound(_) => ErrorCode::NotFound,
+            SpellCheckError::InvalidLanguage(_) => ErrorCode::InvalidParams,
+            SpellCheckError::Parse { .. } => ErrorCode::Internal,
+            SpellCheckError::Io(_) => ErrorCode::IoError,
+        }
+    }
+}
+
+/// 拼写错误
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Misspelling {
+    /// 错误的单词
+    pub word: String,
+    /// 起始偏移 (UTF-16 码元)
+    pub start: usize,
+    /// 结束偏移 (UTF-16 码元)
+    pub end: usize,
+    /// 候选词
+    pub suggestions: Vec<String>,
+}
+
+// ============================================================================
+// 拼写检查器
+// ============================================================================
+
+/// 拼写检查器 (词典缓存 + 各 vault 的自定义词表)
+pub struct SpellChecker {
+    dictionaries: RwLock<HashMap<String, Arc<Dictionary>>>,
+    custom_words: RwLock<HashMap<String, HashSet<String>>>,
+}
+
+impl SpellChecker {
+    /// 创建新的拼写检查器
+    pub fn new() -> Self {
+        Self {
+            dictionaries: RwLock::new(HashMap::new()),
+            custom_words: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 从目录加载词典 (`<dir>/<language>.aff` 和 `<dir>/<language>.dic`)
+    ///
+    /// 未指定目录时依次搜索系统词典目录，已加载的同名词典会被替换
+    pub fn load(&self, language: &str, dir: Option<&Path>) -> Result<(), SpellCheckError> {
+        let language = normalize_language(language)?;
+        let dirs = match dir {
+            Some(dir) => vec![dir.to_path_buf()],
+            None => default_dictionary_dirs(),
+        };
+
+        let (aff_path, dic_path) = dirs
+            .iter()
+            .map(|dir| (dir.join(format!("{}.aff", language)), dir.join(format!("{}.dic", language))))
+            .find(|(aff, dic)| aff.is_file() && dic.is_file())
+            .ok_or_else(|| SpellCheckError::DictionaryNotFound(language.clone()))?;
+
+        let start_time = std::time::Instant::now();
+        let aff = std::fs::read_to_string(&aff_path)?;
+        let dic = std::fs::read_to_string(&dic_path)?;
+        let dictionary = Dictionary::new(&aff, &dic).map_err(|e| SpellCheckError::Parse {
+            language: language.clone(),
+            message: e.to_string(),
+        })?;
+
+        log_info!("词典加载完成: {} ({}), elapsed={:?}",
+            language, dic_path.display(), start_time.elapsed());
+
+        self.dictionaries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(language, Arc::new(dictionary));
+        Ok(())
+    }
+
+    /// 直接注册已解析的词典
+    pub fn insert(&self, language: &str, dictionary: Dictionary) -> Result<(), SpellCheckError> {
+        let language = normalize_language(language)?;
+        self.dictionaries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(language, Arc::new(dictionary));
+        Ok(())
+    }
+
+    /// 已加载的语言列表
+    pub fn loaded_languages(&self) -> Vec<String> {
+        let mut languages: Vec<String> = self
+            .dictionaries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect();
+        languages.sort();
+        languages
+    }
+
+    /// 向 vault 的自定义词表添加单词，返回词表大小
+    pub fn add_words(&self, vault: Option<&str>, words: &[String]) -> usize {
+        let mut custom = self.custom_words.write().unwrap_or_else(|e| e.into_inner());
+        let list = custom.entry(vault.unwrap_or(DEFAULT_VAULT).to_string()).or_default();
+        list.extend(words.iter().map(|w| w.trim().to_string()).filter(|w| !w.is_empty()));
+        list.len()
+    }
+
+    /// 从 vault 的自定义词表移除单词，返回词表大小
+    pub fn remove_words(&self, vault: Option<&str>, words: &[String]) -> usize {
+        let mut custom = self.custom_words.write().unwrap_or_else(|e| e.into_inner());
+        let Some(list) = custom.get_mut(vault.unwrap_or(DEFAULT_VAULT)) else {
+            return 0;
+        };
+        for word in words {
+            list.remove(word.trim());
+        }
+        list.len()
+    }
+
+    /// 检查文本，任一语言的词典接受即视为正确
+    ///
+    /// 尚未加载的语言会从 `dictionary_dir` 或系统词典目录自动加载
+    pub fn check(
+        &self,
+        text: &str,
+        languages: &[String],
+        vault: Option<&str>,
+        dictionary_dir: Option<&Path>,
+        suggest: bool,
+    ) -> Result<Vec<Misspelling>, SpellCheckError> {
+        let mut dictionaries = Vec::with_capacity(languages.len());
+        for language in languages {
+            let key = normalize_language(language)?;
+            let cached = self.dictionaries.read().unwrap_or_else(|e| e.into_inner()).get(&key).cloned();
+            let dictionary = match cached {
+                Some(dictionary) => dictionary,
+                None => {
+                    self.load(&key, dictionary_dir)?;
+                    self.dictionaries.read().unwrap_or_else(|e| e.into_inner())[&key].clone()
+                }
+            };
+            dictionaries.push(dictionary);
+        }
+
+        let custom = self.custom_words.read().unwrap_or_else(|e| e.into_inner());
+        let custom_words = custom.get(vault.unwrap_or(DEFAULT_VAULT));
+        let is_custom = |word: &str| {
+            custom_words.is_some_and(|list| list.contains(word) || list.contains(&word.to_lowercase()))
+        };
+
+        let mut offsets = Utf16Offsets::new();
+        let mut misspellings = Vec::new();
+        for range in words(text) {
+            let word = &text[range.clone()];
+            if is_custom(word) || dictionaries.iter().any(|d| d.check(word)) {
+                continue;
+            }
+
+            let mut suggestions = Vec::new();
+            if suggest {
+                let mut out = Vec::new();
+                for dictionary in &dictionaries {
+                    dictionary.suggest(word, &mut out);
+                    for candidate in out.drain(..) {
+                        if !suggestions.contains(&candidate) {
+                            suggestions.push(candidate);
+                        }
+                    }
+                }
+                suggestions.truncate(MAX_SUGGESTIONS);
+            }
+
+            misspellings.push(Misspelling {
+                word: word.to_string(),
+                start: offsets.at(text, range.start),
+                end: offsets.at(text, range.end),
+                suggestions,
+            });
+        }
+
+        Ok(misspellings)
+    }
+}
+
+impl Default for SpellChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 规范化语言代码 ("en-US" -> "en_US")，拒绝可能构成路径的字符
+fn normalize_language(language: &str) -> Result<String, SpellCheckError> {
+    let language = language.trim().replace('-', "_");
+    let valid = !language.is_empty()
+        && language.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(language)
+    } else {
+        Err(SpellCheckError::InvalidLanguage(language))
+    }
+}
+
+/// 系统词典目录
+fn default_dictionary_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if cfg!(target_os = "macos") {
+        if let Ok(home) = std::env::var("HOME") {
+            dirs.push(Path::new(&home).join("Library/Spelling"));
+        }
+        dirs.push(PathBuf::from("/Library/Spelling"));
+    } else if cfg!(target_os = "linux") {
+        dirs.push(PathBuf::from("/usr/share/hunspell"));
+        dirs.push(PathBuf::from("/usr/share/myspell"));
+        dirs.push(PathBuf::from("/usr/share/myspell/dicts"));
+    }
+    dirs
+}
+
+// ============================================================================
+// 分词
+// ============================================================================
+
+/// 需要跳过的片段：行内代码、双链、URL、邮箱
+fn skip_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"`[^`\n]*`|\[\[[^\]\n]*\]\]|[a-zA-Z][a-zA-Z0-9+.-]*://\S+|[\w.+-]+@[\w-]+\.[\w.-]+")
+            .expect("valid regex")
+    })
+}
+
+/// 单词：字母开头，可包含撇号 (don't)，不含数字；中日韩文字不参与检查
+fn word_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"[\p{L}&&[^\p{Han}\p{Hiragana}\p{Katakana}\p{Hangul}]]+(?:['’][\p{L}&&[^\p{Han}]]+)*")
+            .expect("valid regex")
+    })
+}
+
+fn words(text: &str) -> Vec<Range<usize>> {
+    let skipped: Vec<Range<usize>> = skip_pattern().find_iter(text).map(|m| m.range()).collect();
+    let mut skip = skipped.iter().peekable();
+
+    word_pattern()
+        .find_iter(text)
+        .filter(|m| {
+            while skip.peek().is_some_and(|r| r.end <= m.start()) {
+                skip.next();
+            }
+            // 与数字相连的单词 (如 "3rd"、"v2") 不检查
+            let touches_digit = text[..m.start()].chars().next_back().is_some_and(|c| c.is_ascii_digit())
+                || text[m.end()..].chars().next().is_some_and(|c| c.is_ascii_digit());
+            !touches_digit && skip.peek().is_none_or(|r| r.start >= m.end())
+        })
+        .map(|m| m.range())
+        .collect()
+}
+
+/// 字节偏移到 UTF-16 偏移的增量转换 (偏移单调递增)
+struct Utf16Offsets {
+    byte: usize,
+    utf16: usize,
+}
+
+impl Utf16Offsets {
+    fn new() -> Self {
+        Self { byte: 0, utf16: 0 }
+    }
+
+    fn at(&mut self, text: &str, byte: usize) -> usize {
+        self.utf16 += text[self.byte..byte].encode_utf16().count();
+        self.byte = byte;
+        self.utf16
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8\nTRY esianrtolcdugmphbyfvkwzESIANRTOLCDUGMPHBYFVKWZ'\n\nSFX S Y 1\nSFX S 0 s .\n";
+    const DIC: &str = "5\nhello\nworld\nnote/S\nvault\ndon't\n";
+
+    fn checker() -> SpellChecker {
+        let checker = SpellChecker::new();
+        checker.insert("en-US", Dictionary::new(AFF, DIC).unwrap()).unwrap();
+        checker
+    }
+
+    fn check(checker: &SpellChecker, text: &str, vault: Option<&str>) -> Vec<Misspelling> {
+        checker.check(text, &["en_US".to_string()], vault, None, true).unwrap()
+    }
+
+    #[test]
+    fn test_misspellings_with_offsets_and_suggestions() {
+        let checker = checker();
+        let result = check(&checker, "你好 hello wrold, notes don't", None);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].word, "wrold");
+        assert_eq!((result[0].start, result[0].end), (9, 14));
+        assert!(result[0].suggestions.contains(&"world".to_string()));
+    }
+
+    #[test]
+    fn test_skips_code_links_and_urls() {
+        let checker = checker();
+        let text = "hello `xyzzy` [[Qwerty Note]] https://exmaple.com/pth me@exmaple.com v2beta";
+        assert!(check(&checker, text, None).is_empty());
+    }
+
+    #[test]
+    fn test_custom_words_are_per_vault() {
+        let checker = checker();
+        assert_eq!(checker.add_words(Some("work"), &["Obsidian".to_string()]), 1);
+        assert!(check(&checker, "Obsidian vault", Some("work")).is_empty());
+        assert_eq!(check(&checker, "Obsidian vault", Some("personal")).len(), 1);
+
+        assert_eq!(checker.remove_words(Some("work"), &["Obsidian".to_string()]), 0);
+        assert_eq!(check(&checker, "Obsidian vault", Some("work")).len(), 1);
+    }
+
+    #[test]
+    fn test_missing_and_invalid_dictionary() {
+        let checker = SpellChecker::new();
+        let dir = std::env::temp_dir();
+        assert!(matches!(
+            checker.check("hello", &["xx_YY".to_string()], None, Some(&dir), false),
+            Err(SpellCheckError::DictionaryNotFound(_))
+        ));
+        assert!(matches!(
+            checker.load("../etc/passwd", None),
+            Err(SpellCheckError::InvalidLanguage(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_from_directory() {
+        let dir = std::env::temp_dir().join(format!("spellcheck-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("en_TEST.aff"), AFF).unwrap();
+        std::fs::write(dir.join("en_TEST.dic"), DIC).unwrap();
+
+        let checker = SpellChecker::new();
+        let result = checker.check("hello wrld", &["en-TEST".to_string()], None, Some(&dir), false);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let result = result.unwrap();
+        assert_eq!(result.len(), 1);
+        assert!(result[0].suggestions.is_empty());
+        assert_eq!(checker.loaded_languages(), vec!["en_TEST".to_string()]);
+    }
+}
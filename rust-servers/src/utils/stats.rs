@@ -0,0 +1,262 @@
+// 文本统计模块
+// 统计字符、词、行、段落和句子数量，估算 LLM token 数，并按段落汇总语言占比
+//
+// 中日韩字符每个计为一个词；其余字母数字按连续片段计词
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::language::LanguageDetector;
+
+/// 英文等字母文字平均每个 token 对应的字符数
+const CHARS_PER_TOKEN: usize = 4;
+
+/// 参与语言占比汇总的最低占比，低于此值的语言被忽略
+const MIN_LANGUAGE_SHARE: f64 = 0.05;
+
+// ============================================================================
+// 计数
+// ============================================================================
+
+/// 文本计数
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TextCounts {
+    /// 字符数 (Unicode 标量值)
+    pub characters: usize,
+    /// 不含空白的字符数
+    pub characters_no_spaces: usize,
+    /// 词数 (中日韩字符每个计为一个词)
+    pub words: usize,
+    /// 中日韩字符数
+    pub cjk_characters: usize,
+    /// 行数
+    pub lines: usize,
+    /// 段落数 (以空行分隔的非空块)
+    pub paragraphs: usize,
+    /// 句子数
+    pub sentences: usize,
+}
+
+/// 统计文本
+pub fn count(text: &str) -> TextCounts {
+    let mut counts = TextCounts {
+        lines: text.lines().count(),
+        paragraphs: count_paragraphs(text),
+        ..TextCounts::default()
+    };
+
+    let mut in_word = false;
+    let mut in_sentence = false;
+    let mut prev = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        counts.characters += 1;
+        if !c.is_whitespace() {
+            counts.characters_no_spaces += 1;
+        }
+
+        if is_wide(c) {
+            counts.cjk_characters += 1;
+            counts.words += 1;
+            in_word = false;
+            in_sentence = true;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                counts.words += 1;
+            }
+            in_word = true;
+            in_sentence = true;
+        } else if in_word && is_word_joiner(c, prev, chars.peek().copied()) {
+            // don't / well-known / 3.14 视为一个词
+        } else {
+            in_word = false;
+            if in_sentence && is_sentence_end(c, chars.peek().copied()) {
+                counts.sentences += 1;
+                in_sentence = false;
+            }
+        }
+        prev = Some(c);
+    }
+    if in_sentence {
+        counts.sentences += 1;
+    }
+
+    counts
+}
+
+fn count_paragraphs(text: &str) -> usize {
+    let mut paragraphs = 0;
+    let mut in_paragraph = false;
+    for line in text.lines() {
+        let blank = line.trim().is_empty();
+        if !blank && !in_paragraph {
+            paragraphs += 1;
+        }
+        in_paragraph = !blank;
+    }
+    paragraphs
+}
+
+/// 估算 LLM token 数
+///
+/// 粗略估计：中日韩字符每个 1 token，字母数字片段每 4 个字符 1 token，其余非空白符号每个 1 token
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut run = 0usize;
+    for c in text.chars() {
+        if c.is_alphanumeric() && !is_wide(c) {
+            run += 1;
+            continue;
+        }
+        tokens += run.div_ceil(CHARS_PER_TOKEN);
+        run = 0;
+        if !c.is_whitespace() {
+            tokens += 1;
+        }
+    }
+    tokens + run.div_ceil(CHARS_PER_TOKEN)
+}
+
+/// 中日韩字符 (汉字、假名、谚文)
+pub(crate) fn is_wide(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}' |
+        '\u{20000}'..='\u{2A6DF}' | '\u{3040}'..='\u{30FF}' | '\u{AC00}'..='\u{D7AF}')
+}
+
+/// 词内连接符：撇号、连字符、下划线，以及数字之间的小数点和千分位逗号
+fn is_word_joiner(c: char, prev: Option<char>, next: Option<char>) -> bool {
+    let Some(next) = next.filter(|n| n.is_alphanumeric() && !is_wide(*n)) else {
+        return false;
+    };
+    match c {
+        '\'' | '\u{2019}' | '-' | '_' => true,
+        '.' | ',' => prev.is_some_and(|p| p.is_ascii_digit()) && next.is_ascii_digit(),
+        _ => false,
+    }
+}
+
+/// 句末标点 ('.' 仅在后跟空白或文本结尾时计入，避免把 3.14 和 example.com 拆开)
+fn is_sentence_end(c: char, next: Option<char>) -> bool {
+    match c {
+        '.' => next.is_none_or(char::is_whitespace),
+        '!' | '?' | '。' | '！' | '？' | '…' => true,
+        _ => false,
+    }
+}
+
+// ============================================================================
+// 语言占比
+// ============================================================================
+
+/// 单个语言的占比
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageShare {
+    /// ISO 639-1 语言代码
+    pub language: String,
+    /// 按非空白字符数计算的占比 (0.0 - 1.0)
+    pub share: f64,
+}
+
+/// 按段落检测语言并汇总占比 (降序)
+///
+/// 无法识别的段落不计入分母，占比低于 5% 的语言被忽略
+pub fn language_shares(detector: &LanguageDetector, text: &str) -> Vec<LanguageShare> {
+    let mut weights: HashMap<String, usize> = HashMap::new();
+    for paragraph in split_paragraphs(text) {
+        let result = detector.detect(paragraph);
+        if result.language == "und" {
+            continue;
+        }
+        let weight = paragraph.chars().filter(|c| !c.is_whitespace()).count();
+        *weights.entry(result.language).or_default() += weight;
+    }
+
+    let total: usize = weights.values().sum();
+    if total == 0 {
+        return Vec::new();
+    }
+
+    let mut shares: Vec<LanguageShare> = weights
+        .into_iter()
+        .map(|(language, weight)| LanguageShare {
+            language,
+            share: weight as f64 / total as f64,
+        })
+        .filter(|share| share.share >= MIN_LANGUAGE_SHARE)
+        .collect();
+    shares.sort_by(|a, b| b.share.total_cmp(&a.share).then_with(|| a.language.cmp(&b.language)));
+    shares
+}
+
+fn split_paragraphs(text: &str) -> Vec<&str> {
+    let mut paragraphs = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut end = 0;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            if let Some(s) = start.take() {
+                paragraphs.push(&text[s..end]);
+            }
+        } else {
+            start.get_or_insert(offset);
+            end = offset + line.len();
+        }
+        offset += line.len();
+    }
+    if let Some(s) = start {
+        paragraphs.push(&text[s..end]);
+    }
+    paragraphs
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_mixed_text() {
+        let counts = count("Hello, world! It's 3.14 today.\n\n今天天气很好。我们去公园吧！");
+        assert_eq!(counts.words, 5 + 12);
+        assert_eq!(counts.cjk_characters, 12);
+        assert_eq!(counts.lines, 3);
+        assert_eq!(counts.paragraphs, 2);
+        assert_eq!(counts.sentences, 4);
+        assert_eq!(counts.characters, 46);
+        assert_eq!(counts.characters_no_spaces, 40);
+    }
+
+    #[test]
+    fn test_count_empty_and_unterminated() {
+        assert_eq!(count(""), TextCounts::default());
+        let counts = count("no terminator here");
+        assert_eq!(counts.sentences, 1);
+        assert_eq!(counts.words, 3);
+        assert_eq!(count("visit example.com now").sentences, 1);
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("hello world"), 4);
+        assert_eq!(estimate_tokens("你好，世界"), 5);
+    }
+
+    #[test]
+    fn test_language_shares() {
+        let detector = LanguageDetector::new();
+        let text = "这是一段用于测试语言占比的中文内容，包含足够多的汉字，以便检测器能够给出可靠的结果。\n\n\
+            This is a short English paragraph for the mixed language test.";
+        let shares = language_shares(&detector, text);
+        assert_eq!(shares.len(), 2);
+        assert!(shares.iter().any(|s| s.language == "zh"));
+        assert!(shares.iter().any(|s| s.language == "en"));
+        assert!((shares.iter().map(|s| s.share).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(language_shares(&detector, "   ").is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+// 字符编码转换模块
+// 检测 GBK / Big5 / Shift-JIS / UTF-16 等旧编码并转换为 UTF-8，
+// 避免导入的旧文本文件在 vault 中显示为乱码
+//
+// 检测顺序：BOM → 无 BOM 的 UTF-16 (NUL 字节分布) → chardetng 统计检测
+
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use serde::Serialize;
+use std::path::Path;
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+
+/// 允许转换的最大字节数
+pub const MAX_INPUT_BYTES: usize = 20 * 1024 * 1024;
+
+/// 判断无 BOM UTF-16 时采样的字节数
+const UTF16_SNIFF_BYTES: usize = 4096;
+
+/// 编码转换错误
+#[derive(Debug, Error)]
+pub enum TranscodeError {
+    #[error("必须提供 'bytes' 或 'path'")]
+    MissingInput,
+
+    #[error("无效的 base64 数据: {0}")]
+    InvalidBase64(String),
+
+    #[error("不支持的目标编码: '{0}' (仅支持 utf8)")]
+    UnsupportedTarget(String),
+
+    #[error("未知的编码标签: '{0}'")]
+    UnknownEncoding(String),
+
+    #[error("输入过大: {0} 字节 (最多 {MAX_INPUT_BYTES})")]
+    TooLarge(u64),
+
+    #[error("文件错误: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl CodedError for TranscodeError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            TranscodeError::TooLarge(_) => ErrorCode::LimitExceeded,
+            TranscodeError::Io(_) => ErrorCode::IoError,
+            _ => ErrorCode::InvalidParams,
+        }
+    }
+}
+
+/// 转换结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscodeResult {
+    /// 转换后的 UTF-8 文本
+    pub text: String,
+    /// 源编码名称 (WHATWG 名称，如 "GBK"、"Big5"、"Shift_JIS"、"UTF-16LE")
+    pub encoding: String,
+    /// 源数据是否带 BOM
+    pub had_bom: bool,
+    /// 是否存在无法解码而被替换为 U+FFFD 的字节
+    pub had_errors: bool,
+}
+
+// ============================================================================
+// 转换
+// ============================================================================
+
+/// 校验目标编码 (接受 "utf8"、"utf-8"，不区分大小写)
+pub fn check_target(to: &str) -> Result<(), TranscodeError> {
+    if to.eq_ignore_ascii_case("utf8") || to.eq_ignore_ascii_case("utf-8") {
+        Ok(())
+    } else {
+        Err(TranscodeError::UnsupportedTarget(to.to_string()))
+    }
+}
+
+/// 读取文件并检查大小
+pub fn read_file(path: &Path) -> Result<Vec<u8>, TranscodeError> {
+    let size = std::fs::metadata(path)?.len();
+    if size > MAX_INPUT_BYTES as u64 {
+        return Err(TranscodeError::TooLarge(size));
+    }
+    Ok(std::fs::read(path)?)
+}
+
+/// 将字节转换为 UTF-8 文本
+///
+/// `encoding` 为编码标签 (如 "gbk"、"shift_jis")，提供时跳过检测
+pub fn transcode(bytes: &[u8], encoding: Option<&str>) -> Result<TranscodeResult, TranscodeError> {
+    if bytes.len() > MAX_INPUT_BYTES {
+        return Err(TranscodeError::TooLarge(bytes.len() as u64));
+    }
+
+    let (source, had_bom) = match encoding {
+        Some(label) => {
+            let encoding = Encoding::for_label(label.trim().as_bytes())
+                .ok_or_else(|| TranscodeError::UnknownEncoding(label.to_string()))?;
+            (encoding, Encoding::for_bom(bytes).is_some_and(|(bom, _)| bom == encoding))
+        }
+        None => match Encoding::for_bom(bytes) {
+            Some((encoding, _)) => (encoding, true),
+            None => (detect(bytes), false),
+        },
+    };
+
+    // decode 会识别并去除 BOM
+    let (text, actual, had_errors) = source.decode(bytes);
+
+    Ok(TranscodeResult {
+        text: text.into_owned(),
+        encoding: actual.name().to_string(),
+        had_bom,
+        had_errors,
+    })
+}
+
+/// 检测无 BOM 数据的编码
+pub fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return encoding;
+    }
+
+    let mut detector = EncodingDetector::new(Iso2022JpDetection::Allow);
+    detector.feed(bytes, true);
+    detector.guess(None, Utf8Detection::Allow)
+}
+
+/// 通过 NUL 字节的位置判断无 BOM 的 UTF-16
+///
+/// 以 ASCII 为主的 UTF-16LE 文本奇数位大多为 0，BE 则偶数位大多为 0
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(UTF16_SNIFF_BYTES) & !1];
+    if sample.len() < 4 {
+        return None;
+    }
+
+    let pairs = sample.len() / 2;
+    let (mut even_nul, mut odd_nul) = (0usize, 0usize);
+    for pair in sample.chunks_exact(2) {
+        even_nul += usize::from(pair[0] == 0);
+        odd_nul += usize::from(pair[1] == 0);
+    }
+
+    // 至少 30% 的码元在一侧为 0，另一侧几乎没有 0
+    let threshold = pairs * 3 / 10;
+    if odd_nul > threshold && even_nul * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_nul > threshold && odd_nul * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{BIG5, GBK, SHIFT_JIS};
+
+    fn encode(encoding: &'static Encoding, text: &str) -> Vec<u8> {
+        encoding.encode(text).0.into_owned()
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_detect_legacy_encodings() {
+        let text = "这是一个用于测试编码检测的中文段落，包含足够多的常用汉字以便统计判断。";
+        let result = transcode(&encode(GBK, text), None).unwrap();
+        assert_eq!(result.encoding, "GBK");
+        assert_eq!(result.text, text);
+        assert!(!result.had_errors);
+
+        let text = "這是一個用於測試編碼檢測的繁體中文段落，包含足夠多的常用漢字以便統計判斷。";
+        let result = transcode(&encode(BIG5, text), None).unwrap();
+        assert_eq!(result.encoding, "Big5");
+        assert_eq!(result.text, text);
+
+        let text = "これは文字コードの検出をテストするための日本語の文章です。";
+        let result = transcode(&encode(SHIFT_JIS, text), None).unwrap();
+        assert_eq!(result.encoding, "Shift_JIS");
+        assert_eq!(result.text, text);
+    }
+
+    #[test]
+    fn test_utf16_with_and_without_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16le("Hello 世界"));
+        let result = transcode(&bytes, None).unwrap();
+        assert_eq!(result.encoding, "UTF-16LE");
+        assert!(result.had_bom);
+        assert_eq!(result.text, "Hello 世界");
+
+        let result = transcode(&utf16le("Plain ASCII notes"), None).unwrap();
+        assert_eq!(result.encoding, "UTF-16LE");
+        assert!(!result.had_bom);
+        assert_eq!(result.text, "Plain ASCII notes");
+    }
+
+    #[test]
+    fn test_utf8_passthrough_and_bom() {
+        let result = transcode("普通 UTF-8 文本".as_bytes(), None).unwrap();
+        assert_eq!(result.encoding, "UTF-8");
+        assert_eq!(result.text, "普通 UTF-8 文本");
+
+        let result = transcode(b"\xEF\xBB\xBFbom", None).unwrap();
+        assert!(result.had_bom);
+        assert_eq!(result.text, "bom");
+    }
+
+    #[test]
+    fn test_explicit_encoding_and_errors() {
+        let result = transcode(&encode(GBK, "中文"), Some("gb2312")).unwrap();
+        assert_eq!(result.text, "中文");
+        assert!(matches!(
+            transcode(b"x", Some("no-such-encoding")),
+            Err(TranscodeError::UnknownEncoding(_))
+        ));
+        assert!(check_target("UTF-8").is_ok());
+        assert!(matches!(check_target("gbk"), Err(TranscodeError::UnsupportedTarget(_))));
+    }
+}
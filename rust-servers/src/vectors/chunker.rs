@@ -0,0 +1,407 @@
+// 笔记分块
+// 笔记正文 (已去掉 frontmatter) 切分为嵌入用的分块，支持四种策略：按段落 (默认，相邻段落合并到约 800 个字符)、
+// 按标题 (每个标题下的内容为一块)、按句子 (合并到指定长度，相邻分块重叠若干句) 和按固定 token 数 (相邻分块重叠若干 token)
+
+use serde::Deserialize;
+
+use crate::utils::stats::{estimate_tokens, is_wide};
+
+/// 每篇笔记最多保存的分块数
+pub const MAX_CHUNKS: usize = 256;
+
+/// 按字符计的分块长度：默认值和范围
+const DEFAULT_CHARS: usize = 800;
+const MIN_CHARS: usize = 100;
+const MAX_CHARS: usize = 8000;
+
+/// 按 token 计的分块长度：默认值和范围
+const DEFAULT_TOKENS: usize = 256;
+const MIN_TOKENS: usize = 16;
+const MAX_TOKENS: usize = 2048;
+
+/// 默认的重叠：句子策略为句数，token 策略为 token 数
+const DEFAULT_SENTENCE_OVERLAP: usize = 1;
+const DEFAULT_TOKEN_OVERLAP: usize = 32;
+
+/// 句子策略最多重叠的句数
+const MAX_SENTENCE_OVERLAP: usize = 5;
+
+/// 分块策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// 按空行分段，相邻段落合并
+    #[default]
+    Paragraph,
+    /// 按 Markdown 标题分节
+    Heading,
+    /// 按句子合并，相邻分块重叠
+    Sentence,
+    /// 固定 token 数的窗口，相邻分块重叠
+    Tokens,
+}
+
+impl Strategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Paragraph => "paragraph",
+            Strategy::Heading => "heading",
+            Strategy::Sentence => "sentence",
+            Strategy::Tokens => "tokens",
+        }
+    }
+}
+
+/// 分块设置 (请求中的 chunking 字段)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ChunkingConfig {
+    #[serde(default)]
+    pub strategy: Strategy,
+    /// 分块长度：tokens 策略为 token 数 (默认 256)，其余为字符数 (默认 800)
+    #[serde(default)]
+    pub size: Option<usize>,
+    /// 相邻分块的重叠：sentence 策略为句数 (默认 1)，tokens 策略为 token 数 (默认 32)
+    #[serde(default)]
+    pub overlap: Option<usize>,
+}
+
+impl ChunkingConfig {
+    fn size(&self) -> usize {
+        self.size.unwrap_or(match self.strategy {
+            Strategy::Tokens => DEFAULT_TOKENS,
+            _ => DEFAULT_CHARS,
+        })
+    }
+
+    fn overlap(&self) -> usize {
+        self.overlap.unwrap_or(match self.strategy {
+            Strategy::Sentence => DEFAULT_SENTENCE_OVERLAP,
+            Strategy::Tokens => DEFAULT_TOKEN_OVERLAP,
+            _ => 0,
+        })
+    }
+
+    /// 检查长度和重叠是否在范围内
+    pub fn validate(&self) -> Result<(), String> {
+        let size = self.size();
+        let overlap = self.overlap();
+        match self.strategy {
+            Strategy::Tokens => {
+                if !(MIN_TOKENS..=MAX_TOKENS).contains(&size) {
+                    return Err(format!("chunking.size 必须在 {} 到 {} 之间", MIN_TOKENS, MAX_TOKENS));
+                }
+                if overlap > size / 2 {
+                    return Err("chunking.overlap 不能超过 size 的一半".to_string());
+                }
+            }
+            strategy => {
+                if !(MIN_CHARS..=MAX_CHARS).contains(&size) {
+                    return Err(format!("chunking.size 必须在 {} 到 {} 之间", MIN_CHARS, MAX_CHARS));
+                }
+                if strategy == Strategy::Sentence && overlap > MAX_SENTENCE_OVERLAP {
+                    return Err(format!("chunking.overlap 不能超过 {} 句", MAX_SENTENCE_OVERLAP));
+                }
+                if strategy != Strategy::Sentence && overlap > 0 {
+                    return Err("chunking.overlap 只适用于 sentence 和 tokens 策略".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 计入笔记内容哈希的分块设置；默认设置返回 None，使之前建立的索引保持有效
+    pub fn fingerprint(&self) -> Option<String> {
+        if self.strategy == Strategy::Paragraph && self.size() == DEFAULT_CHARS {
+            return None;
+        }
+        Some(format!("{}:{}:{}", self.strategy.name(), self.size(), self.overlap()))
+    }
+
+    /// 切分笔记正文 (最多 MAX_CHUNKS 块)
+    pub fn split(&self, text: &str) -> Vec<String> {
+        let size = self.size();
+        let mut chunks = match self.strategy {
+            Strategy::Paragraph => paragraphs(text, size),
+            Strategy::Heading => headings(text, size),
+            Strategy::Sentence => sentences(text, size, self.overlap()),
+            Strategy::Tokens => tokens(text, size, self.overlap()),
+        };
+        chunks.truncate(MAX_CHUNKS);
+        chunks
+    }
+}
+
+fn char_count(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// 按字符数硬切分
+fn hard_split(text: &str, size: usize) -> Vec<String> {
+    let letters: Vec<char> = text.chars().collect();
+    letters.chunks(size).map(|part| part.iter().collect::<String>()).collect()
+}
+
+/// 按空行分段，相邻段落合并到约 size 个字符，超长段落按 size 切分
+fn paragraphs(text: &str, size: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let chars = char_count(paragraph);
+        if current_chars > 0 && current_chars + chars > size {
+            chunks.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        if chars > size {
+            chunks.extend(hard_split(paragraph, size));
+            continue;
+        }
+        if current_chars > 0 {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+        current_chars += chars;
+    }
+    if current_chars > 0 {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Markdown 标题行 (1-6 个 #，后跟空格)
+fn is_heading(line: &str) -> bool {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    (1..=6).contains(&level) && line[level..].starts_with([' ', '\t'])
+}
+
+/// 按标题分节 (代码块中的 # 行不算标题)，标题行留在所属的节中，超长的节再按段落切分
+fn headings(text: &str, size: usize) -> Vec<String> {
+    let mut sections = Vec::new();
+    let mut current = String::new();
+    let mut fence: Option<&str> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        match fence {
+            Some(marker) if trimmed.starts_with(marker) => fence = None,
+            Some(_) => {}
+            None if trimmed.starts_with("```") => fence = Some("```"),
+            None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+            None if is_heading(trimmed) && !current.trim().is_empty() => {
+                sections.push(std::mem::take(&mut current));
+            }
+            None => {}
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    sections.push(current);
+
+    sections
+        .iter()
+        .map(|section| section.trim())
+        .filter(|section| !section.is_empty())
+        .flat_map(|section| {
+            if char_count(section) > size {
+                paragraphs(section, size)
+            } else {
+                vec![section.to_string()]
+            }
+        })
+        .collect()
+}
+
+/// 句子在文本中的字节范围：以句末标点 (及其后的引号、括号) 或换行结束
+fn sentence_ranges(text: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let (_, c) = chars[i];
+        let next = chars.get(i + 1).map(|&(_, n)| n);
+        let boundary = match c {
+            '。' | '！' | '？' | '!' | '?' | '；' | '\n' => true,
+            '.' => next.is_none_or(char::is_whitespace),
+            _ => false,
+        };
+        i += 1;
+        if !boundary {
+            continue;
+        }
+        while c != '\n' && i < chars.len() && matches!(chars[i].1, '"' | '\'' | '”' | '’' | ')' | '）' | '」' | '』') {
+            i += 1;
+        }
+        let end = chars.get(i).map_or(text.len(), |&(offset, _)| offset);
+        if !text[start..end].trim().is_empty() {
+            ranges.push((start, end));
+        }
+        start = end;
+    }
+    if !text[start..].trim().is_empty() {
+        ranges.push((start, text.len()));
+    }
+    ranges
+}
+
+/// 相邻句子合并到约 size 个字符，下一块从上一块的最后 overlap 句开始；超长的单句按 size 切分
+fn sentences(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let ranges = sentence_ranges(text);
+    let lengths: Vec<usize> = ranges.iter().map(|&(start, end)| char_count(text[start..end].trim())).collect();
+    let mut chunks = Vec::new();
+    let mut first = 0;
+    while first < ranges.len() {
+        let mut last = first;
+        let mut chars = lengths[first];
+        while last + 1 < ranges.len() && chars + lengths[last + 1] <= size {
+            last += 1;
+            chars += lengths[last];
+        }
+        let piece = text[ranges[first].0..ranges[last].1].trim();
+        if char_count(piece) > size {
+            chunks.extend(hard_split(piece, size));
+        } else {
+            chunks.push(piece.to_string());
+        }
+        if last + 1 >= ranges.len() {
+            break;
+        }
+        // 至少前进一句
+        first = (last + 1).saturating_sub(overlap).max(first + 1);
+    }
+    chunks
+}
+
+/// 按 token 切分的最小单位 (字节范围和估算的 token 数)：字母数字连续片段、单个中日韩字符或单个符号，包含其后的空白
+fn token_pieces(text: &str) -> Vec<(usize, usize, usize)> {
+    let mut pieces: Vec<(usize, usize)> = Vec::new();
+    let mut in_word = false;
+    for (offset, c) in text.char_indices() {
+        let end = offset + c.len_utf8();
+        let word_char = c.is_alphanumeric() && !is_wide(c);
+        match pieces.last_mut() {
+            Some(last) if c.is_whitespace() || (word_char && in_word) => last.1 = end,
+            _ if c.is_whitespace() => {}
+            _ => pieces.push((offset, end)),
+        }
+        in_word = word_char;
+    }
+    pieces.into_iter()
+        .map(|(start, end)| (start, end, estimate_tokens(&text[start..end])))
+        .collect()
+}
+
+/// 约 size 个 token 的窗口，下一块与上一块重叠约 overlap 个 token
+fn tokens(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let pieces = token_pieces(text);
+    let mut chunks = Vec::new();
+    let mut first = 0;
+    while first < pieces.len() {
+        let mut last = first;
+        let mut count = pieces[first].2;
+        while last + 1 < pieces.len() && count + pieces[last + 1].2 <= size {
+            last += 1;
+            count += pieces[last].2;
+        }
+        chunks.push(text[pieces[first].0..pieces[last].1].trim().to_string());
+        if last + 1 >= pieces.len() {
+            break;
+        }
+        let mut next = last + 1;
+        let mut back = 0;
+        while next - 1 > first && back + pieces[next - 1].2 <= overlap {
+            next -= 1;
+            back += pieces[next].2;
+        }
+        first = next;
+    }
+    chunks
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(strategy: Strategy, size: usize, overlap: Option<usize>) -> ChunkingConfig {
+        ChunkingConfig { strategy, size: Some(size), overlap }
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let default = ChunkingConfig::default();
+        assert!(default.split("  \n\n ").is_empty());
+        assert_eq!(default.split("第一段\n\n第二段"), vec!["第一段\n\n第二段"]);
+
+        let long = "a".repeat(DEFAULT_CHARS + 10);
+        let chunks = default.split(&format!("intro\n\n{}\n\ntail", long));
+        let lengths: Vec<usize> = chunks.iter().map(|c| char_count(c)).collect();
+        assert_eq!(lengths, vec![5, DEFAULT_CHARS, 10, 4]);
+    }
+
+    #[test]
+    fn test_headings() {
+        let text = "intro\n\n# Plan\n\nship it\n\n```sh\n# not a heading\n```\n\n## Risks\n\n- delays\n#hashtag";
+        assert_eq!(config(Strategy::Heading, 800, None).split(text), vec![
+            "intro",
+            "# Plan\n\nship it\n\n```sh\n# not a heading\n```",
+            "## Risks\n\n- delays\n#hashtag",
+        ]);
+
+        // 超长的节按段落切分
+        let long = format!("# Long\n\n{}\n\n{}", "a".repeat(80), "b".repeat(80));
+        assert_eq!(config(Strategy::Heading, 100, None).split(&long).len(), 2);
+    }
+
+    #[test]
+    fn test_sentences() {
+        let text = "第一句。第二句！Third one. Fourth?\n- 列表项";
+        let ranges: Vec<&str> = sentence_ranges(text).iter().map(|&(s, e)| text[s..e].trim()).collect();
+        assert_eq!(ranges, vec!["第一句。", "第二句！", "Third one.", "Fourth?", "- 列表项"]);
+        assert_eq!(sentence_ranges("他说：“好。”然后走了。3.14 不分开").len(), 3);
+
+        // 每块不超过 size，相邻分块重叠一句
+        let sentence = "这是一个十个字的句子。";
+        let chunks = config(Strategy::Sentence, 100, None).split(&sentence.repeat(25));
+        assert_eq!(chunks.iter().map(|c| char_count(c)).collect::<Vec<_>>(), vec![99, 99, 99]);
+        let chunks = config(Strategy::Sentence, 100, Some(0)).split(&sentence.repeat(25));
+        assert_eq!(chunks.len(), 3);
+
+        // 超长的单句按长度切分
+        assert_eq!(config(Strategy::Sentence, 100, None).split(&"字".repeat(150)).len(), 2);
+    }
+
+    #[test]
+    fn test_tokens() {
+        let words: Vec<String> = (0..100).map(|i| format!("w{}", i)).collect();
+        let text = words.join(" ");
+        let chunks = config(Strategy::Tokens, 40, Some(10)).split(&text);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].starts_with("w0 ") && chunks[0].ends_with(" w39"));
+        assert!(chunks[1].starts_with("w30 ") && chunks[1].ends_with(" w69"));
+        assert!(chunks[2].starts_with("w60 ") && chunks[2].ends_with(" w99"));
+
+        // 中文每个字符一个 token
+        let chunks = config(Strategy::Tokens, 16, Some(0)).split(&"汉".repeat(40));
+        assert_eq!(chunks.iter().map(|c| char_count(c)).collect::<Vec<_>>(), vec![16, 16, 8]);
+    }
+
+    #[test]
+    fn test_validate_and_fingerprint() {
+        assert!(ChunkingConfig::default().validate().is_ok());
+        assert_eq!(ChunkingConfig::default().fingerprint(), None);
+        assert_eq!(config(Strategy::Paragraph, 800, None).fingerprint(), None);
+        assert_eq!(config(Strategy::Paragraph, 400, None).fingerprint().as_deref(), Some("paragraph:400:0"));
+        let sentence: ChunkingConfig = serde_json::from_str(r#"{"strategy": "sentence"}"#).unwrap();
+        assert_eq!(sentence.fingerprint().as_deref(), Some("sentence:800:1"));
+
+        assert!(config(Strategy::Paragraph, 50, None).validate().is_err());
+        assert!(config(Strategy::Heading, 800, Some(1)).validate().is_err());
+        assert!(config(Strategy::Sentence, 800, Some(6)).validate().is_err());
+        assert!(config(Strategy::Tokens, 800, None).validate().is_ok());
+        assert!(config(Strategy::Tokens, 64, Some(40)).validate().is_err());
+        assert!(config(Strategy::Tokens, 4096, None).validate().is_err());
+    }
+}
@@ -0,0 +1,111 @@
+// 嵌入向量
+// 调用客户端配置的嵌入端点，支持 OpenAI 兼容格式 (`/v1/embeddings`，返回 data[].embedding)
+// 和 Ollama 格式 (`/api/embed`，返回 embeddings)，输入按批发送
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::llm::LLMError;
+use crate::router::ModuleType;
+
+/// 每个请求最多发送的文本数
+const BATCH_SIZE: usize = 64;
+
+/// 嵌入端点配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbeddingConfig {
+    /// API 端点
+    pub endpoint: String,
+    /// 请求头 (如 Authorization)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 模型名称 (同一索引中的向量必须来自同一模型)
+    pub model: String,
+    /// 输出维度 (支持时传给端点，如 text-embedding-3 系列)
+    #[serde(default)]
+    pub dimensions: Option<usize>,
+}
+
+/// OpenAI 兼容格式的响应
+#[derive(Debug, Deserialize)]
+struct OpenAiResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbedding {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Ollama 格式的响应
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// 计算各文本的嵌入向量 (顺序与输入相同)
+pub async fn embed(config: &EmbeddingConfig, texts: &[String]) -> Result<Vec<Vec<f32>>, LLMError> {
+    let mut vectors = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(BATCH_SIZE) {
+        let mut body = serde_json::json!({ "model": config.model, "input": batch });
+        if let Some(dimensions) = config.dimensions {
+            body["dimensions"] = dimensions.into();
+        }
+        let mut request = crate::network::client().post(&config.endpoint).json(&body);
+        for (key, value) in &config.headers {
+            request = request.header(key, value);
+        }
+        let response = crate::network::send(ModuleType::Vectors, request).await
+            .map_err(|e| LLMError::NetworkError(e.to_string()))?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| LLMError::NetworkError(e.to_string()))?;
+        if !status.is_success() {
+            return Err(LLMError::HttpError { status: status.as_u16(), message: text });
+        }
+        vectors.extend(parse(&text, batch.len())?);
+    }
+    Ok(vectors)
+}
+
+/// 解析嵌入端点的响应，检查向量个数与输入一致
+fn parse(text: &str, expected: usize) -> Result<Vec<Vec<f32>>, LLMError> {
+    let vectors = if let Ok(response) = serde_json::from_str::<OpenAiResponse>(text) {
+        let mut data = response.data;
+        data.sort_by_key(|item| item.index);
+        data.into_iter().map(|item| item.embedding).collect()
+    } else {
+        serde_json::from_str::<OllamaResponse>(text)
+            .map_err(|e| LLMError::ParseError(format!("无法解析嵌入响应: {}", e)))?
+            .embeddings
+    };
+    if vectors.len() != expected {
+        return Err(LLMError::ParseError(format!("嵌入向量个数不符: 需要 {}，收到 {}", expected, vectors.len())));
+    }
+    Ok(vectors)
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let openai = r#"{"object": "list", "data": [
+            {"object": "embedding", "index": 1, "embedding": [0.5, 0.5]},
+            {"object": "embedding", "index": 0, "embedding": [1.0, 0.0]}
+        ], "model": "text-embedding-3-small"}"#;
+        assert_eq!(parse(openai, 2).unwrap(), vec![vec![1.0, 0.0], vec![0.5, 0.5]]);
+
+        let ollama = r#"{"model": "nomic-embed-text", "embeddings": [[0.1, 0.2]]}"#;
+        assert_eq!(parse(ollama, 1).unwrap(), vec![vec![0.1, 0.2]]);
+
+        assert!(matches!(parse(ollama, 2), Err(LLMError::ParseError(_))));
+        assert!(matches!(parse("{}", 1), Err(LLMError::ParseError(_))));
+    }
+}
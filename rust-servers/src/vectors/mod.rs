@@ -0,0 +1,618 @@
+// 向量模块
+// 笔记的嵌入索引：index 把笔记正文 (去掉 frontmatter) 分块后调用客户端配置的嵌入端点，向量保存在数据目录的 vectors 区域；
+// related 对给定的笔记文本计算向量并在索引中查找最相关的笔记，返回建议的 wiki 链接、相似度和最匹配的分块摘录，
+// 已在文本中链接的笔记和笔记自身不会被建议。相似度计算在阻塞线程中进行，不占用异步运行时。
+// 未变化的笔记和分块不重新嵌入；reembed_changed 扫描整个 vault 目录，只重新嵌入修改过的笔记。
+// 分块策略可由请求的 chunking 指定，index 的 dry_run 只返回分块结果，不调用嵌入端点。
+// 客户端在 hello 中声明 vault 时，每个 vault 使用独立的索引 (vectors/vaults/<名称>)
+
+mod chunker;
+mod embed;
+mod store;
+
+pub use chunker::{ChunkingConfig, Strategy};
+pub use embed::EmbeddingConfig;
+pub use store::{content_hash, Stats, Suggestion, VectorError, VectorStore};
+
+use crate::jobs::{self, Job, JobKind};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::system::progress::{percent_between, ProgressReporter};
+use crate::workers::WorkKind;
+use serde::Deserialize;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio_util::sync::CancellationToken;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Vectors", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Vectors", format!($($arg)*));
+        }
+    };
+}
+
+/// related 默认返回的建议数
+const DEFAULT_LIMIT: usize = 5;
+
+/// related 最多返回的建议数
+const MAX_LIMIT: usize = 50;
+
+/// related 默认的最低相似度
+const DEFAULT_MIN_SCORE: f32 = 0.5;
+
+/// related 最多嵌入的查询分块数 (长笔记只取开头部分)
+const MAX_QUERY_CHUNKS: usize = 16;
+
+/// reembed_changed 每处理多少篇笔记写入一次索引文件
+const SAVE_EVERY: usize = 32;
+
+/// 索引所在目录 (启动时设置)
+static STORE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 设置索引所在目录 (启动时调用一次；未设置时索引只保存在内存中)
+pub fn init_dir(dir: PathBuf) {
+    let _ = STORE_DIR.set(dir);
+}
+
+/// 创建向量模块错误
+fn vectors_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Vectors, code, message)
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// index 请求
+#[derive(Debug, Deserialize)]
+struct IndexRequest {
+    /// 笔记路径 (vault 内的相对路径，作为索引的键)
+    path: String,
+    /// 笔记全文
+    text: String,
+    /// 嵌入端点 (dry_run 时可省略)
+    #[serde(default)]
+    embedding: Option<EmbeddingConfig>,
+    #[serde(default)]
+    chunking: ChunkingConfig,
+    /// 只返回分块结果，不嵌入也不写入索引
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// related 请求
+#[derive(Debug, Deserialize)]
+struct RelatedRequest {
+    /// 笔记全文
+    text: String,
+    embedding: EmbeddingConfig,
+    /// 笔记自身的路径 (不建议链接到自身)
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    min_score: Option<f32>,
+    #[serde(default)]
+    chunking: ChunkingConfig,
+}
+
+/// remove 请求
+#[derive(Debug, Deserialize)]
+struct RemoveRequest {
+    path: String,
+}
+
+/// reembed_changed 请求
+#[derive(Debug, Deserialize)]
+struct ReembedRequest {
+    /// vault 根目录 (索引的键为相对此目录的路径)
+    root: String,
+    embedding: EmbeddingConfig,
+    /// 是否从索引中删除目录中已不存在的笔记 (默认是)
+    #[serde(default)]
+    remove_missing: Option<bool>,
+    #[serde(default)]
+    chunking: ChunkingConfig,
+}
+
+/// vault 中的一篇笔记
+struct NoteFile {
+    /// 相对 vault 根目录的路径 (以 / 分隔)
+    path: String,
+    text: String,
+    hash: String,
+}
+
+fn parse<T: serde::de::DeserializeOwned>(msg: &ModuleMessage) -> Result<T, ModuleError> {
+    serde_json::from_value(msg.payload.clone())
+        .map_err(|e| vectors_error(ErrorCode::InvalidParams, format!("无效的 {} 请求: {}", msg.msg_type, e)))
+}
+
+fn validate_embedding(config: &EmbeddingConfig) -> Result<(), ModuleError> {
+    if config.endpoint.is_empty() {
+        return Err(vectors_error(ErrorCode::InvalidParams, "embedding.endpoint 不能为空"));
+    }
+    if config.model.is_empty() {
+        return Err(vectors_error(ErrorCode::InvalidParams, "embedding.model 不能为空"));
+    }
+    Ok(())
+}
+
+fn validate_chunking(config: &ChunkingConfig) -> Result<(), ModuleError> {
+    config.validate().map_err(|e| vectors_error(ErrorCode::InvalidParams, e))
+}
+
+/// 笔记正文的分块 (去掉 frontmatter)
+fn note_chunks(text: &str, chunking: &ChunkingConfig) -> Vec<String> {
+    chunking.split(crate::utils::frontmatter::strip(text))
+}
+
+/// 笔记的内容哈希 (非默认的分块设置也计入，改变分块方式后笔记会重新索引)
+fn source_hash(text: &str, chunking: &ChunkingConfig) -> String {
+    match chunking.fingerprint() {
+        Some(fingerprint) => content_hash(&format!("{}\n{}", fingerprint, text)),
+        None => content_hash(text),
+    }
+}
+
+/// 计算分块的嵌入向量
+async fn embed(config: &EmbeddingConfig, chunks: &[String]) -> Result<Vec<Vec<f32>>, ModuleError> {
+    embed::embed(config, chunks).await.map_err(|e| ModuleError::from_error(ModuleType::Vectors, &e))
+}
+
+/// 读取目录下的所有 Markdown 笔记 (跳过 .obsidian、.git 等隐藏目录)，按路径排序
+fn read_notes(root: &Path, chunking: &ChunkingConfig) -> std::io::Result<Vec<NoteFile>> {
+    fn visit(root: &Path, dir: &Path, chunking: &ChunkingConfig, notes: &mut Vec<NoteFile>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                visit(root, &path, chunking, notes)?;
+            } else if file_type.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("md")) {
+                let text = String::from_utf8_lossy(&std::fs::read(&path)?).into_owned();
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                let parts: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+                notes.push(NoteFile { path: parts.join("/"), hash: source_hash(&text, chunking), text });
+            }
+        }
+        Ok(())
+    }
+    let mut notes = Vec::new();
+    visit(root, root, chunking, &mut notes)?;
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(notes)
+}
+
+/// 文本中已有的 wiki 链接目标 (小写，同时包含加上 .md 的形式，与索引中的路径和链接名比较)
+fn linked_notes(text: &str) -> HashSet<String> {
+    let mut linked = HashSet::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("]]") else {
+            break;
+        };
+        let target = rest[..end].split(['|', '#', '^']).next().unwrap_or_default().trim().to_lowercase();
+        if !target.is_empty() {
+            linked.insert(format!("{}.md", target));
+            linked.insert(target);
+        }
+        rest = &rest[end + 2..];
+    }
+    linked
+}
+
+// ============================================================================
+// 向量处理器
+// ============================================================================
+
+/// 向量模块处理器
+pub struct VectorsHandler {
+    /// 各 vault 的索引 (第一次使用时从数据目录加载；未声明 vault 的连接共用 None 对应的索引)
+    stores: Arc<Mutex<HashMap<Option<String>, VectorStore>>>,
+}
+
+impl VectorsHandler {
+    /// 创建新的向量处理器
+    pub fn new() -> Self {
+        Self { stores: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// 在阻塞线程中操作当前 vault 的索引
+    async fn with_store<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut VectorStore) -> Result<T, VectorError> + Send + 'static,
+    ) -> Result<T, ModuleError> {
+        let stores = Arc::clone(&self.stores);
+        let vault = crate::storage::current_vault();
+        let result = crate::workers::spawn_blocking(WorkKind::Indexing, move || {
+            let mut stores = stores.lock().unwrap_or_else(|e| e.into_inner());
+            let store = match stores.entry(vault) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let dir = STORE_DIR.get().map(|dir| crate::storage::vault_path(dir, entry.key().as_deref()));
+                    entry.insert(VectorStore::open(dir.as_deref())?)
+                }
+            };
+            f(store)
+        })
+        .await
+        .map_err(|e| vectors_error(ErrorCode::Internal, format!("向量索引任务失败: {}", e)))?;
+        result.map_err(|e| ModuleError::from_error(ModuleType::Vectors, &e))
+    }
+
+    /// 计算笔记分块的向量：索引中已有相同内容的分块沿用其向量，其余调用嵌入端点
+    ///
+    /// 返回向量 (顺序与分块相同) 和新嵌入的分块数
+    async fn embed_changed(&self, config: &EmbeddingConfig, chunks: &[String]) -> Result<(Vec<Vec<f32>>, usize), ModuleError> {
+        let hashes: Vec<String> = chunks.iter().map(|chunk| content_hash(chunk)).collect();
+        let wanted: HashSet<String> = hashes.iter().cloned().collect();
+        let model = config.model.clone();
+        let cached = self.with_store(move |store| Ok(store.cached_vectors(&model, &wanted))).await?;
+        let missing: Vec<String> = chunks.iter().zip(&hashes)
+            .filter(|(_, hash)| !cached.contains_key(*hash))
+            .map(|(chunk, _)| chunk.clone())
+            .collect();
+        let mut fresh = if missing.is_empty() { Vec::new() } else { embed(config, &missing).await? }.into_iter();
+        let vectors = hashes.iter()
+            .map(|hash| cached.get(hash).cloned().or_else(|| fresh.next()).unwrap_or_default())
+            .collect();
+        Ok((vectors, missing.len()))
+    }
+
+    /// 处理 index 消息 - 嵌入笔记并写入索引 (笔记未变化时跳过)
+    async fn handle_index(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: IndexRequest = parse(msg)?;
+        if request.path.is_empty() {
+            return Err(vectors_error(ErrorCode::InvalidParams, "path 不能为空").into());
+        }
+        validate_chunking(&request.chunking)?;
+        if request.dry_run {
+            return Ok(Some(Self::dry_run(&request)));
+        }
+        let embedding = request.embedding
+            .ok_or_else(|| vectors_error(ErrorCode::InvalidParams, "缺少 embedding (只有 dry_run 可以省略)"))?;
+        validate_embedding(&embedding)?;
+
+        let source_hash = source_hash(&request.text, &request.chunking);
+        let (path, model, hash) = (request.path.clone(), embedding.model.clone(), source_hash.clone());
+        if let Some(count) = self.with_store(move |store| Ok(store.unchanged(&path, &model, &hash))).await? {
+            log_debug!("{} 未变化，跳过索引", request.path);
+            return Ok(Some(ServerResponse::new(ModuleType::Vectors, "indexed", serde_json::json!({
+                "path": request.path,
+                "chunks": count,
+                "embedded": 0,
+                "unchanged": true,
+            }))));
+        }
+
+        let chunks = note_chunks(&request.text, &request.chunking);
+        let (vectors, embedded) = self.embed_changed(&embedding, &chunks).await?;
+        let model = embedding.model;
+        let path = request.path.clone();
+        let count = self.with_store(move |store| {
+            store.upsert(&path, &model, Some(source_hash), chunks.into_iter().zip(vectors).collect())
+        }).await?;
+        log_debug!("已索引 {} ({} 个分块，新嵌入 {} 个)", request.path, count, embedded);
+        Ok(Some(ServerResponse::new(ModuleType::Vectors, "indexed", serde_json::json!({
+            "path": request.path,
+            "chunks": count,
+            "embedded": embedded,
+            "unchanged": false,
+        }))))
+    }
+
+    /// index 的 dry_run - 返回分块结果，不嵌入也不写入索引
+    fn dry_run(request: &IndexRequest) -> ServerResponse {
+        let chunks: Vec<serde_json::Value> = note_chunks(&request.text, &request.chunking)
+            .into_iter()
+            .map(|chunk| serde_json::json!({
+                "chars": chunk.chars().count(),
+                "tokens": crate::utils::stats::estimate_tokens(&chunk),
+                "text": chunk,
+            }))
+            .collect();
+        ServerResponse::new(ModuleType::Vectors, "chunks", serde_json::json!({
+            "path": request.path,
+            "strategy": request.chunking.strategy.name(),
+            "chunks": chunks,
+        }))
+    }
+
+    /// 处理 reembed_changed 消息 - 扫描 vault 目录，只重新嵌入内容变化的笔记
+    async fn handle_reembed_changed(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: ReembedRequest = parse(msg)?;
+        validate_embedding(&request.embedding)?;
+        validate_chunking(&request.chunking)?;
+        let root = PathBuf::from(&request.root);
+        if !root.is_dir() {
+            return Err(vectors_error(ErrorCode::InvalidParams, format!("root 不是目录: {}", request.root)).into());
+        }
+        let progress = ProgressReporter::new(ModuleType::Vectors, msg.request_id(), msg.sender());
+        let cancel = CancellationToken::new();
+        let _job = jobs::register(
+            Job::new(JobKind::Indexing)
+                .with_request_id(msg.request_id())
+                .with_detail(request.root.clone())
+                .with_cancel(cancel.clone()),
+        );
+
+        progress.report("scanning", Some(0), None).await;
+        let chunking = request.chunking.clone();
+        let notes = crate::workers::spawn_blocking(WorkKind::Indexing, move || read_notes(&root, &chunking))
+            .await
+            .map_err(|e| vectors_error(ErrorCode::Internal, format!("扫描笔记任务失败: {}", e)))?
+            .map_err(|e| vectors_error(ErrorCode::IoError, format!("读取笔记失败: {}", e)))?;
+
+        // 找出变化的笔记和已不存在的笔记
+        let model = request.embedding.model.clone();
+        let remove_missing = request.remove_missing.unwrap_or(true);
+        let keys: Vec<(String, String)> = notes.iter().map(|note| (note.path.clone(), note.hash.clone())).collect();
+        let (changed, missing) = self.with_store(move |store| {
+            let changed: HashSet<String> = keys.iter()
+                .filter(|(path, hash)| store.unchanged(path, &model, hash).is_none())
+                .map(|(path, _)| path.clone())
+                .collect();
+            let present: HashSet<&str> = keys.iter().map(|(path, _)| path.as_str()).collect();
+            let missing: Vec<String> = if remove_missing {
+                store.paths().into_iter().filter(|path| !present.contains(path.as_str())).collect()
+            } else {
+                Vec::new()
+            };
+            Ok((changed, missing))
+        }).await?;
+        let total = notes.len();
+        let changed: Vec<NoteFile> = notes.into_iter().filter(|note| changed.contains(&note.path)).collect();
+
+        let mut embedded = 0;
+        let mut chunks_total = 0;
+        let embed_all = async {
+            for (done, note) in changed.iter().enumerate() {
+                progress.report("embedding", Some(percent_between(0, 100, done, changed.len())), Some(format!("{}/{}", done, changed.len()))).await;
+                let chunks = note_chunks(&note.text, &request.chunking);
+                let (vectors, count) = self.embed_changed(&request.embedding, &chunks).await?;
+                embedded += count;
+                chunks_total += chunks.len();
+                let (path, model, hash) = (note.path.clone(), request.embedding.model.clone(), note.hash.clone());
+                let save = (done + 1) % SAVE_EVERY == 0;
+                self.with_store(move |store| {
+                    store.put(&path, &model, Some(hash), chunks.into_iter().zip(vectors).collect())?;
+                    if save { store.save() } else { Ok(()) }
+                }).await?;
+            }
+            Ok::<(), ModuleError>(())
+        };
+        let result = tokio::select! {
+            result = embed_all => result,
+            _ = cancel.cancelled() => Err(vectors_error(ErrorCode::Cancelled, "重新索引已终止")),
+        };
+        // 失败或终止时保留已经完成的笔记
+        let removed = self.with_store(move |store| {
+            store.save()?;
+            store.remove_all(&missing)
+        }).await?;
+        result?;
+
+        log_info!(
+            "已重新索引 {}: {} 篇笔记中 {} 篇变化，新嵌入 {} 个分块，删除 {} 篇",
+            request.root, total, changed.len(), embedded, removed
+        );
+        Ok(Some(ServerResponse::new(ModuleType::Vectors, "reembedded", serde_json::json!({
+            "root": request.root,
+            "notes": total,
+            "changed": changed.len(),
+            "unchanged": total - changed.len(),
+            "removed": removed,
+            "embedded": embedded,
+            "reused": chunks_total - embedded,
+        }))))
+    }
+
+    /// 处理 related 消息 - 查找与文本相关的笔记
+    async fn handle_related(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: RelatedRequest = parse(msg)?;
+        validate_embedding(&request.embedding)?;
+        validate_chunking(&request.chunking)?;
+        let limit = request.limit.unwrap_or(DEFAULT_LIMIT);
+        if limit == 0 || limit > MAX_LIMIT {
+            return Err(vectors_error(ErrorCode::InvalidParams, format!("limit 必须在 1 到 {} 之间", MAX_LIMIT)).into());
+        }
+        let min_score = request.min_score.unwrap_or(DEFAULT_MIN_SCORE);
+
+        let mut chunks = note_chunks(&request.text, &request.chunking);
+        chunks.truncate(MAX_QUERY_CHUNKS);
+        let suggestions = if chunks.is_empty() {
+            Vec::new()
+        } else {
+            let queries = embed(&request.embedding, &chunks).await?;
+            let mut exclude = linked_notes(&request.text);
+            exclude.extend(request.path.as_deref().map(str::to_lowercase));
+            let model = request.embedding.model;
+            self.with_store(move |store| store.related(&model, &queries, &exclude, limit, min_score)).await?
+        };
+        log_debug!("相关笔记: {} 条建议", suggestions.len());
+        Ok(Some(ServerResponse::new(ModuleType::Vectors, "related", serde_json::json!({
+            "path": request.path,
+            "suggestions": suggestions,
+        }))))
+    }
+
+    /// 处理 remove 消息 - 从索引中删除笔记
+    async fn handle_remove(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: RemoveRequest = parse(msg)?;
+        let path = request.path.clone();
+        let removed = self.with_store(move |store| store.remove(&path)).await?;
+        Ok(Some(ServerResponse::new(ModuleType::Vectors, "removed", serde_json::json!({
+            "path": request.path,
+            "removed": removed,
+        }))))
+    }
+
+    /// 处理 stats 消息 - 索引统计
+    async fn handle_stats(&self) -> Result<Option<ServerResponse>, RouterError> {
+        let stats = self.with_store(|store| Ok(store.stats())).await?;
+        Ok(Some(ServerResponse::new(ModuleType::Vectors, "stats", serde_json::to_value(&stats).unwrap_or_default())))
+    }
+
+    /// 处理 clear 消息 - 清空索引 (切换嵌入模型前使用)
+    async fn handle_clear(&self) -> Result<Option<ServerResponse>, RouterError> {
+        let removed = self.with_store(|store| store.clear()).await?;
+        log_info!("已清空向量索引 ({} 篇笔记)", removed);
+        Ok(Some(ServerResponse::new(ModuleType::Vectors, "cleared", serde_json::json!({ "removed": removed }))))
+    }
+}
+
+impl Default for VectorsHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 嵌入端点配置的字段
+const EMBEDDING_FIELDS: &[FieldSpec] = &[
+    FieldSpec::required("endpoint", FieldKind::String),
+    FieldSpec::required("model", FieldKind::String),
+    FieldSpec::optional("headers", FieldKind::Object),
+    FieldSpec::optional("dimensions", FieldKind::Integer),
+];
+
+/// 分块设置字段 (对应 ChunkingConfig)
+const CHUNKING_FIELDS: &[FieldSpec] = &[
+    FieldSpec::optional("strategy", FieldKind::String).one_of(&["paragraph", "heading", "sentence", "tokens"]),
+    FieldSpec::optional("size", FieldKind::Integer),
+    FieldSpec::optional("overlap", FieldKind::Integer),
+];
+
+/// 向量模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("index", &[
+        FieldSpec::required("path", FieldKind::String),
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::optional("embedding", FieldKind::Object).with_fields(EMBEDDING_FIELDS),
+        FieldSpec::optional("chunking", FieldKind::Object).with_fields(CHUNKING_FIELDS),
+        FieldSpec::optional("dry_run", FieldKind::Boolean),
+    ]),
+    MessageSpec::new("related", &[
+        FieldSpec::required("text", FieldKind::String),
+        FieldSpec::required("embedding", FieldKind::Object).with_fields(EMBEDDING_FIELDS),
+        FieldSpec::optional("path", FieldKind::String),
+        FieldSpec::optional("limit", FieldKind::Integer),
+        FieldSpec::optional("min_score", FieldKind::Number),
+        FieldSpec::optional("chunking", FieldKind::Object).with_fields(CHUNKING_FIELDS),
+    ]),
+    MessageSpec::new("remove", &[
+        FieldSpec::required("path", FieldKind::String),
+    ]),
+    MessageSpec::new("reembed_changed", &[
+        FieldSpec::required("root", FieldKind::String),
+        FieldSpec::required("embedding", FieldKind::Object).with_fields(EMBEDDING_FIELDS),
+        FieldSpec::optional("remove_missing", FieldKind::Boolean),
+        FieldSpec::optional("chunking", FieldKind::Object).with_fields(CHUNKING_FIELDS),
+    ]).concurrent(),
+    MessageSpec::new("stats", &[]),
+    MessageSpec::new("clear", &[]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 向量模块
+    pub const Vectors: ModuleType = ModuleType::named("vectors");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for VectorsHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Vectors
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理向量消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "index" => self.handle_index(msg).await,
+            "related" => self.handle_related(msg).await,
+            "remove" => self.handle_remove(msg).await,
+            "reembed_changed" => self.handle_reembed_changed(msg).await,
+            "stats" => self.handle_stats().await,
+            "clear" => self.handle_clear().await,
+            _ => Err(vectors_error(ErrorCode::UnknownMessageType, format!("未知的向量消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linked_notes() {
+        let linked = linked_notes("见 [[Alpha]]、[[projects/Beta|别名]] 和 [[Gamma#标题]]，[[ ]] [[未闭合");
+        let mut linked: Vec<String> = linked.into_iter().collect();
+        linked.sort();
+        assert_eq!(linked, vec!["alpha", "alpha.md", "gamma", "gamma.md", "projects/beta", "projects/beta.md"]);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let request: IndexRequest = serde_json::from_value(serde_json::json!({
+            "path": "Plan.md",
+            "text": "---\ntags: [plan]\n---\n# Goals\n\nShip it.\n\n# Risks\n\n延期。",
+            "chunking": {"strategy": "heading"},
+            "dry_run": true,
+        })).unwrap();
+        let response = serde_json::to_value(VectorsHandler::dry_run(&request)).unwrap();
+        assert_eq!(response["type"], "chunks");
+        assert_eq!(response["strategy"], "heading");
+        let texts: Vec<&str> = response["chunks"].as_array().unwrap().iter().map(|c| c["text"].as_str().unwrap()).collect();
+        assert_eq!(texts, vec!["# Goals\n\nShip it.", "# Risks\n\n延期。"]);
+        assert_eq!(response["chunks"][1]["chars"], 12);
+
+        // 分块设置不同时内容哈希不同，默认设置与之前的哈希一致
+        let text = "正文";
+        assert_eq!(source_hash(text, &ChunkingConfig::default()), content_hash(text));
+        assert_ne!(source_hash(text, &request.chunking), content_hash(text));
+    }
+
+    #[tokio::test]
+    async fn test_vault_isolation() {
+        use crate::storage::vault_scope;
+
+        let handler = VectorsHandler::new();
+        let note = |path: &'static str| move |store: &mut VectorStore| {
+            store.upsert(path, "m", None, vec![("text".to_string(), vec![1.0, 0.0])])
+        };
+        vault_scope(Some("work".to_string()), handler.with_store(note("a.md"))).await.unwrap();
+        vault_scope(Some("home".to_string()), handler.with_store(note("b.md"))).await.unwrap();
+
+        let paths = |vault: Option<&str>| vault_scope(vault.map(str::to_string), handler.with_store(|store| Ok(store.paths())));
+        assert_eq!(paths(Some("work")).await.unwrap(), vec!["a.md"]);
+        assert_eq!(paths(Some("home")).await.unwrap(), vec!["b.md"]);
+        assert!(paths(None).await.unwrap().is_empty());
+    }
+}
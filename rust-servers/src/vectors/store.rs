@@ -0,0 +1,478 @@
+// 向量索引
+// 按笔记保存分块文本和归一化的嵌入向量 (MessagePack 文件，先写临时文件再替换)，
+// 查询时对每篇笔记取与查询向量最相似的分块作为得分和摘录。
+// 笔记全文和每个分块都记录内容哈希，重新索引时跳过未变化的笔记，并沿用未变化分块的向量
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::router::{CodedError, ErrorCode};
+use crate::utils::hashing::{hash_text, HashAlgorithm};
+
+/// 索引文件名
+const INDEX_FILE_NAME: &str = "notes.msgpack";
+
+/// 索引文件版本
+const INDEX_VERSION: u32 = 1;
+
+/// 摘录的最大长度 (字符数)
+const EXCERPT_CHARS: usize = 240;
+
+/// 向量索引错误
+#[derive(Debug, Error)]
+pub enum VectorError {
+    #[error("索引文件读写失败: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("无效的索引文件: {0}")]
+    Corrupt(String),
+
+    #[error("索引使用模型 {indexed} 生成，与请求的模型 {requested} 不同 (先 clear 再重新索引)")]
+    ModelMismatch { indexed: String, requested: String },
+
+    #[error("向量维度不一致: 索引为 {0}，收到 {1}")]
+    DimensionMismatch(usize, usize),
+}
+
+impl CodedError for VectorError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            VectorError::Io(_) => ErrorCode::IoError,
+            VectorError::Corrupt(_) => ErrorCode::Internal,
+            VectorError::ModelMismatch { .. } => ErrorCode::Conflict,
+            VectorError::DimensionMismatch(..) => ErrorCode::InvalidParams,
+        }
+    }
+}
+
+// ============================================================================
+// 向量计算
+// ============================================================================
+
+/// 归一化为单位向量 (零向量保持不变)，之后点积即余弦相似度
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// 内容哈希 (SHA-256，十六进制)
+pub fn content_hash(text: &str) -> String {
+    hash_text(text, HashAlgorithm::Sha256)
+}
+
+/// 截取摘录 (按字符截断并加省略号)
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+// ============================================================================
+// 索引
+// ============================================================================
+
+/// 笔记的一个分块
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Chunk {
+    text: String,
+    vector: Vec<f32>,
+    /// 分块文本的哈希 (旧索引中没有)
+    #[serde(default)]
+    hash: String,
+}
+
+/// 已索引的笔记
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NoteEntry {
+    chunks: Vec<Chunk>,
+    /// 索引时间 (Unix 毫秒)
+    indexed_at: i64,
+    /// 笔记全文的哈希 (旧索引中没有)
+    #[serde(default)]
+    source_hash: Option<String>,
+}
+
+/// 索引文件
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    model: Option<String>,
+    dimensions: usize,
+    notes: BTreeMap<String, NoteEntry>,
+}
+
+/// 相关笔记建议
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Suggestion {
+    /// 笔记路径 (索引时的 path)
+    pub path: String,
+    /// wiki 链接 (文件名在索引中唯一时只用文件名，否则用不带扩展名的路径)
+    pub link: String,
+    /// 余弦相似度
+    pub score: f32,
+    /// 最相似分块的摘录
+    pub excerpt: String,
+}
+
+/// 索引统计
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub notes: usize,
+    pub chunks: usize,
+    pub model: Option<String>,
+    pub dimensions: usize,
+}
+
+/// 向量索引 (path 为 None 时只保存在内存中)
+#[derive(Debug, Default)]
+pub struct VectorStore {
+    path: Option<PathBuf>,
+    model: Option<String>,
+    dimensions: usize,
+    notes: BTreeMap<String, NoteEntry>,
+}
+
+impl VectorStore {
+    /// 读取 `dir` 下的索引文件 (不存在时为空索引)
+    pub fn open(dir: Option<&Path>) -> Result<Self, VectorError> {
+        let Some(dir) = dir else {
+            return Ok(Self::default());
+        };
+        let path = dir.join(INDEX_FILE_NAME);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self { path: Some(path), ..Self::default() }),
+            Err(e) => return Err(e.into()),
+        };
+        let file: IndexFile = rmp_serde::from_slice(&data).map_err(|e| VectorError::Corrupt(e.to_string()))?;
+        if file.version != INDEX_VERSION {
+            return Err(VectorError::Corrupt(format!("不支持的索引版本: {}", file.version)));
+        }
+        Ok(Self { path: Some(path), model: file.model, dimensions: file.dimensions, notes: file.notes })
+    }
+
+    /// 写入索引文件 (`put` / `remove_all` 之后调用)
+    pub fn save(&self) -> Result<(), VectorError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = IndexFile {
+            version: INDEX_VERSION,
+            model: self.model.clone(),
+            dimensions: self.dimensions,
+            notes: self.notes.clone(),
+        };
+        let data = rmp_serde::to_vec(&file).map_err(|e| VectorError::Corrupt(e.to_string()))?;
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, data)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// 检查模型和维度与索引一致 (空索引接受任何模型)
+    fn check(&self, model: &str, dimensions: usize) -> Result<(), VectorError> {
+        if self.notes.is_empty() {
+            return Ok(());
+        }
+        if let Some(indexed) = self.model.as_deref().filter(|indexed| *indexed != model) {
+            return Err(VectorError::ModelMismatch { indexed: indexed.to_string(), requested: model.to_string() });
+        }
+        if dimensions != self.dimensions {
+            return Err(VectorError::DimensionMismatch(self.dimensions, dimensions));
+        }
+        Ok(())
+    }
+
+    /// 添加或替换笔记的分块和向量并写入索引文件，返回分块数 (没有分块时删除笔记)
+    ///
+    /// `source_hash` 为笔记全文的哈希，用于之后判断笔记是否变化
+    pub fn upsert(
+        &mut self,
+        path: &str,
+        model: &str,
+        source_hash: Option<String>,
+        chunks: Vec<(String, Vec<f32>)>,
+    ) -> Result<usize, VectorError> {
+        let count = self.put(path, model, source_hash, chunks)?;
+        self.save()?;
+        Ok(count)
+    }
+
+    /// 同 `upsert`，但不写入索引文件 (批量更新时最后统一 `save`)
+    pub fn put(
+        &mut self,
+        path: &str,
+        model: &str,
+        source_hash: Option<String>,
+        chunks: Vec<(String, Vec<f32>)>,
+    ) -> Result<usize, VectorError> {
+        let Some((_, first)) = chunks.first() else {
+            self.notes.remove(path);
+            return Ok(0);
+        };
+        let dimensions = first.len();
+        if let Some((_, vector)) = chunks.iter().find(|(_, vector)| vector.len() != dimensions) {
+            return Err(VectorError::DimensionMismatch(dimensions, vector.len()));
+        }
+        // 替换唯一的一篇笔记时允许切换模型
+        let replacing_only = self.notes.len() == 1 && self.notes.contains_key(path);
+        if !replacing_only {
+            self.check(model, dimensions)?;
+        }
+        let count = chunks.len();
+        let chunks = chunks.into_iter()
+            .map(|(text, vector)| Chunk { hash: content_hash(&text), text, vector: normalize(vector) })
+            .collect();
+        self.model = Some(model.to_string());
+        self.dimensions = dimensions;
+        let entry = NoteEntry { chunks, indexed_at: chrono::Utc::now().timestamp_millis(), source_hash };
+        self.notes.insert(path.to_string(), entry);
+        Ok(count)
+    }
+
+    /// 笔记以同一模型、按全文哈希为 `source_hash` 的内容索引过时返回其分块数
+    pub fn unchanged(&self, path: &str, model: &str, source_hash: &str) -> Option<usize> {
+        if self.model.as_deref() != Some(model) {
+            return None;
+        }
+        let note = self.notes.get(path)?;
+        (note.source_hash.as_deref() == Some(source_hash)).then_some(note.chunks.len())
+    }
+
+    /// 索引中哈希在 `hashes` 内的分块向量 (任意笔记中，需为同一模型)
+    pub fn cached_vectors(&self, model: &str, hashes: &HashSet<String>) -> HashMap<String, Vec<f32>> {
+        if self.model.as_deref() != Some(model) {
+            return HashMap::new();
+        }
+        self.notes.values()
+            .flat_map(|note| &note.chunks)
+            .filter(|chunk| hashes.contains(&chunk.hash))
+            .map(|chunk| (chunk.hash.clone(), chunk.vector.clone()))
+            .collect()
+    }
+
+    /// 已索引的笔记路径
+    pub fn paths(&self) -> Vec<String> {
+        self.notes.keys().cloned().collect()
+    }
+
+    /// 删除多篇笔记并写入索引文件，返回实际删除的数量
+    pub fn remove_all(&mut self, paths: &[String]) -> Result<usize, VectorError> {
+        let removed = paths.iter().filter(|path| self.notes.remove(path.as_str()).is_some()).count();
+        if removed > 0 {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// 删除笔记，返回是否存在
+    pub fn remove(&mut self, path: &str) -> Result<bool, VectorError> {
+        let removed = self.notes.remove(path).is_some();
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// 清空索引 (切换嵌入模型时使用)，返回删除的笔记数
+    pub fn clear(&mut self) -> Result<usize, VectorError> {
+        let count = self.notes.len();
+        self.notes.clear();
+        self.model = None;
+        self.dimensions = 0;
+        self.save()?;
+        Ok(count)
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            notes: self.notes.len(),
+            chunks: self.notes.values().map(|note| note.chunks.len()).sum(),
+            model: self.model.clone(),
+            dimensions: self.dimensions,
+        }
+    }
+
+    /// 查找与查询向量最相关的笔记 (得分从高到低)
+    ///
+    /// 查询向量为多个时 (长笔记的各分块) 使用它们的平均方向；`exclude` 中的路径和链接名 (小写) 被跳过
+    pub fn related(
+        &self,
+        model: &str,
+        queries: &[Vec<f32>],
+        exclude: &HashSet<String>,
+        limit: usize,
+        min_score: f32,
+    ) -> Result<Vec<Suggestion>, VectorError> {
+        let Some(first) = queries.first() else {
+            return Ok(Vec::new());
+        };
+        self.check(model, first.len())?;
+        if let Some(query) = queries.iter().find(|query| query.len() != first.len()) {
+            return Err(VectorError::DimensionMismatch(first.len(), query.len()));
+        }
+        let mut query = vec![0.0; first.len()];
+        for vector in queries {
+            let vector = normalize(vector.clone());
+            query.iter_mut().zip(&vector).for_each(|(sum, x)| *sum += x);
+        }
+        let query = normalize(query);
+
+        let links = self.links();
+        let mut suggestions: Vec<Suggestion> = self.notes.iter()
+            .filter(|(path, _)| {
+                !exclude.contains(&path.to_lowercase()) && !exclude.contains(&links[path.as_str()].to_lowercase())
+            })
+            .filter_map(|(path, note)| {
+                let (score, chunk) = note.chunks.iter()
+                    .map(|chunk| (dot(&query, &chunk.vector), chunk))
+                    .max_by(|a, b| a.0.total_cmp(&b.0))?;
+                (score >= min_score).then(|| Suggestion {
+                    path: path.clone(),
+                    link: format!("[[{}]]", links[path.as_str()]),
+                    score,
+                    excerpt: excerpt(&chunk.text),
+                })
+            })
+            .collect();
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+        suggestions.truncate(limit);
+        Ok(suggestions)
+    }
+
+    /// 各笔记的链接名：文件名 (不含 .md) 在索引中唯一时使用文件名，否则使用不带扩展名的完整路径
+    fn links(&self) -> HashMap<&str, String> {
+        let mut stems: HashMap<String, usize> = HashMap::new();
+        for path in self.notes.keys() {
+            *stems.entry(link_stem(path).to_lowercase()).or_default() += 1;
+        }
+        self.notes.keys()
+            .map(|path| {
+                let stem = link_stem(path);
+                let link = if stems[&stem.to_lowercase()] == 1 { stem } else { path.strip_suffix(".md").unwrap_or(path) };
+                (path.as_str(), link.to_string())
+            })
+            .collect()
+    }
+}
+
+/// 路径中的文件名 (去掉 .md 扩展名)
+fn link_stem(path: &str) -> &str {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    name.strip_suffix(".md").unwrap_or(name)
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试笔记：路径和 (分块文本, 向量)
+    type Note<'a> = (&'a str, &'a [(&'a str, [f32; 2])]);
+
+    fn store_with(notes: &[Note]) -> VectorStore {
+        let mut store = VectorStore::default();
+        for (path, chunks) in notes {
+            let chunks = chunks.iter().map(|(text, vector)| (text.to_string(), vector.to_vec())).collect();
+            store.upsert(path, "m", None, chunks).unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_related() {
+        let store = store_with(&[
+            ("projects/Alpha.md", &[("alpha intro", [1.0, 0.0]), ("alpha details", [0.6, 0.8])]),
+            ("Beta.md", &[("beta", [0.0, 1.0])]),
+            ("a/Gamma.md", &[("gamma one", [0.7, 0.7])]),
+            ("b/Gamma.md", &[("gamma two", [-1.0, 0.0])]),
+        ]);
+        let none = HashSet::new();
+        let results = store.related("m", &[vec![2.0, 0.0]], &none, 10, 0.1).unwrap();
+        let found: Vec<(&str, &str)> = results.iter().map(|s| (s.link.as_str(), s.excerpt.as_str())).collect();
+        assert_eq!(found, vec![("[[Alpha]]", "alpha intro"), ("[[a/Gamma]]", "gamma one")]);
+        assert!((results[0].score - 1.0).abs() < 1e-6);
+
+        // 多个查询向量取平均方向；按路径或链接名排除
+        let exclude: HashSet<String> = ["alpha".to_string(), "a/gamma.md".to_string()].into();
+        let results = store.related("m", &[vec![1.0, 0.0], vec![0.0, 1.0]], &exclude, 1, 0.0).unwrap();
+        assert_eq!(results[0].path, "Beta.md");
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_model_and_dimensions() {
+        let mut store = store_with(&[("a.md", &[("a", [1.0, 0.0])]), ("b.md", &[("b", [0.0, 1.0])])]);
+        assert!(matches!(
+            store.upsert("c.md", "other", None, vec![("c".to_string(), vec![1.0, 0.0])]),
+            Err(VectorError::ModelMismatch { .. })
+        ));
+        assert!(matches!(
+            store.upsert("c.md", "m", None, vec![("c".to_string(), vec![1.0, 0.0, 0.0])]),
+            Err(VectorError::DimensionMismatch(2, 3))
+        ));
+        assert!(store.related("m", &[vec![1.0]], &HashSet::new(), 5, 0.0).is_err());
+
+        // 空内容删除笔记，清空后可以换模型
+        assert_eq!(store.upsert("a.md", "m", None, Vec::new()).unwrap(), 0);
+        assert_eq!(store.stats().notes, 1);
+        assert_eq!(store.clear().unwrap(), 1);
+        store.upsert("c.md", "other", None, vec![("c".to_string(), vec![1.0, 0.0, 0.0])]).unwrap();
+        assert_eq!(store.stats().dimensions, 3);
+    }
+
+    #[test]
+    fn test_persistence() {
+        let dir = std::env::temp_dir().join(format!("sw-vectors-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut store = VectorStore::open(Some(&dir)).unwrap();
+        store.upsert("note.md", "m", Some(content_hash("text")), vec![("text".to_string(), vec![3.0, 4.0])]).unwrap();
+
+        let reopened = VectorStore::open(Some(&dir)).unwrap();
+        let stats = reopened.stats();
+        assert_eq!((stats.notes, stats.chunks, stats.model.as_deref(), stats.dimensions), (1, 1, Some("m"), 2));
+        let results = reopened.related("m", &[vec![0.6, 0.8]], &HashSet::new(), 5, 0.0).unwrap();
+        assert!((results[0].score - 1.0).abs() < 1e-6);
+        // 内容哈希随索引保存
+        assert_eq!(reopened.unchanged("note.md", "m", &content_hash("text")), Some(1));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_content_hashes() {
+        let mut store = store_with(&[("a.md", &[("one", [1.0, 0.0]), ("two", [0.0, 2.0])])]);
+        store.upsert("b.md", "m", Some(content_hash("b")), vec![("three".to_string(), vec![1.0, 1.0])]).unwrap();
+
+        // 全文哈希相同且模型相同时视为未变化
+        assert_eq!(store.unchanged("b.md", "m", &content_hash("b")), Some(1));
+        assert_eq!(store.unchanged("b.md", "m", &content_hash("b2")), None);
+        assert_eq!(store.unchanged("b.md", "other", &content_hash("b")), None);
+        assert_eq!(store.unchanged("a.md", "m", &content_hash("a")), None);
+
+        // 按分块哈希查找已有的向量 (已归一化)
+        let hashes: HashSet<String> = [content_hash("two"), content_hash("new")].into();
+        let cached = store.cached_vectors("m", &hashes);
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[&content_hash("two")], vec![0.0, 1.0]);
+        assert!(store.cached_vectors("other", &hashes).is_empty());
+
+        assert_eq!(store.remove_all(&["a.md".to_string(), "missing.md".to_string()]).unwrap(), 1);
+        assert_eq!(store.paths(), vec!["b.md"]);
+    }
+}
@@ -0,0 +1,161 @@
+// ASR 供应商能力
+// 各供应商支持的模式、语言、单次转写的最长时长、词级时间戳、说话人分离和热词，
+// 供插件在设置界面中禁用不支持的选项，而不是等到转写时才失败
+
+use serde::Serialize;
+
+use crate::voice::config::{ASRMode, ASRProvider, ASRProviderConfig, AudioFormat};
+
+/// OpenAI 兼容接口的上传大小上限 (字节)
+const OPENAI_MAX_UPLOAD_BYTES: u64 = 25 * 1024 * 1024;
+
+/// qwen3-asr-flash 单次转写的最长时长 (秒)
+const QWEN_MAX_AUDIO_SECS: u64 = 180;
+
+/// 豆包录音文件极速版单次转写的最长时长 (秒)
+const DOUBAO_MAX_AUDIO_SECS: u64 = 2 * 60 * 60;
+
+/// SenseVoice 单次转写的最长时长 (秒)
+const SENSEVOICE_MAX_AUDIO_SECS: u64 = 60 * 60;
+
+/// 供应商能力
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderCapabilities {
+    pub provider: ASRProvider,
+    /// 在配置中的角色 (primary / fallback)，列出全部供应商时省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<&'static str>,
+    /// 支持的模式
+    pub modes: Vec<ASRMode>,
+    /// 可识别的语言 (BCP 47 主标签)；None 表示取决于模型：
+    /// Whisper 和 OpenAI 兼容接口的模型支持大多数语言，Vosk 模型只识别下载的那种语言
+    pub languages: Option<&'static [&'static str]>,
+    /// HTTP 模式单次转写的最长音频时长 (秒)，None 为不限 (本地引擎和实时模式)
+    pub max_audio_secs: Option<u64>,
+    /// 是否提供词级时间戳 (word_timestamps)
+    pub word_timestamps: bool,
+    /// 是否区分说话人
+    pub diarization: bool,
+    /// 是否使用热词 / 文本提示 (如会议模块在分段之间传递的上下文)
+    pub hotwords: bool,
+    /// HTTP 模式可以上传的音频格式
+    pub upload_formats: &'static [AudioFormat],
+}
+
+/// 上传格式每秒音频的大致字节数 (16 kHz 单声道)
+fn bytes_per_sec(format: AudioFormat) -> u64 {
+    match format {
+        AudioFormat::Wav => 32_000,
+        // 无损压缩，语音约为 WAV 的一半
+        AudioFormat::Flac => 16_000,
+        // 编码码率 64 kbps / 24 kbps
+        AudioFormat::Mp3 => 8_000,
+        AudioFormat::Opus => 3_000,
+    }
+}
+
+impl ProviderCapabilities {
+    /// 供应商的默认能力 (OpenAI 兼容接口按默认模型 whisper-1 和 WAV 上传计算)
+    pub fn of(provider: ASRProvider) -> Self {
+        let both = vec![ASRMode::Http, ASRMode::Realtime];
+        let http = vec![ASRMode::Http];
+        let (modes, languages, max_audio_secs, word_timestamps, hotwords): (_, Option<&'static [&'static str]>, _, _, _) = match provider {
+            ASRProvider::Qwen => (
+                both,
+                Some(&["zh", "yue", "en", "ja", "ko", "de", "fr", "es", "it", "pt", "ru", "ar"]),
+                Some(QWEN_MAX_AUDIO_SECS),
+                false,
+                false,
+            ),
+            ASRProvider::Doubao => (both, Some(&["zh", "yue", "en", "ja", "ko"]), Some(DOUBAO_MAX_AUDIO_SECS), true, false),
+            ASRProvider::SenseVoice => (http, Some(&["zh", "yue", "en", "ja", "ko"]), Some(SENSEVOICE_MAX_AUDIO_SECS), false, false),
+            ASRProvider::Whisper => (http, None, None, false, true),
+            ASRProvider::OpenAi => (http, None, Some(OPENAI_MAX_UPLOAD_BYTES / bytes_per_sec(AudioFormat::Wav)), true, true),
+            ASRProvider::Vosk => (both, None, None, true, false),
+        };
+        Self {
+            upload_formats: provider.upload_formats(),
+            provider,
+            role: None,
+            modes,
+            languages,
+            max_audio_secs,
+            word_timestamps,
+            diarization: false,
+            hotwords,
+        }
+    }
+
+    /// 按配置计算的能力：OpenAI 兼容接口的最长时长取决于上传格式，
+    /// 只有 Whisper 系列模型提供词级时间戳 (gpt-4o-transcribe 等不提供)
+    pub fn for_config(config: &ASRProviderConfig, role: &'static str) -> Self {
+        let mut capabilities = Self::of(config.provider.clone());
+        capabilities.role = Some(role);
+        if config.provider == ASRProvider::OpenAi {
+            capabilities.max_audio_secs = Some(OPENAI_MAX_UPLOAD_BYTES / bytes_per_sec(config.upload_format));
+            capabilities.word_timestamps = config.model.as_deref().is_none_or(|model| model.contains("whisper"));
+        }
+        capabilities
+    }
+
+    /// 全部供应商的默认能力
+    pub fn all() -> Vec<Self> {
+        [
+            ASRProvider::Qwen,
+            ASRProvider::Doubao,
+            ASRProvider::SenseVoice,
+            ASRProvider::Whisper,
+            ASRProvider::OpenAi,
+            ASRProvider::Vosk,
+        ]
+        .into_iter()
+        .map(Self::of)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let all = ProviderCapabilities::all();
+        assert_eq!(all.len(), 6);
+        for capabilities in &all {
+            // 与配置校验一致：不支持实时模式的供应商只列出 HTTP 模式
+            let mut config = ASRProviderConfig::qwen(ASRMode::Realtime, "key".to_string());
+            config.provider = capabilities.provider.clone();
+            config.app_id = Some("app".to_string());
+            config.access_token = Some("token".to_string());
+            config.siliconflow_api_key = Some("key".to_string());
+            config.model_path = Some("/models/asr".to_string());
+            let realtime_rejected = matches!(
+                config.validate(),
+                Err(crate::voice::config::ConfigError::UnsupportedMode { .. })
+            );
+            assert_eq!(capabilities.modes.contains(&ASRMode::Realtime), !realtime_rejected, "{}", capabilities.provider);
+            assert!(capabilities.modes.contains(&ASRMode::Http));
+        }
+
+        let value = serde_json::to_value(ProviderCapabilities::of(ASRProvider::SenseVoice)).unwrap();
+        assert_eq!(value["provider"], "sensevoice");
+        assert_eq!(value["modes"], serde_json::json!(["http"]));
+        assert_eq!(value["upload_formats"], serde_json::json!(["wav", "opus", "mp3"]));
+        assert!(value.get("role").is_none());
+        assert_eq!(ProviderCapabilities::of(ASRProvider::Whisper).languages, None);
+
+        // OpenAI 兼容接口按上传格式和模型计算
+        let mut config = ASRProviderConfig::qwen(ASRMode::Http, String::new());
+        config.provider = ASRProvider::OpenAi;
+        let capabilities = ProviderCapabilities::for_config(&config, "fallback");
+        assert_eq!(capabilities.role, Some("fallback"));
+        assert_eq!(capabilities.max_audio_secs, Some(819));
+        assert!(capabilities.word_timestamps);
+        config.upload_format = AudioFormat::Opus;
+        config.model = Some("gpt-4o-transcribe".to_string());
+        let capabilities = ProviderCapabilities::for_config(&config, "primary");
+        assert_eq!(capabilities.max_audio_secs, Some(8738));
+        assert!(!capabilities.word_timestamps);
+    }
+}
@@ -4,10 +4,23 @@
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use crate::voice::asr::{ASREngine, ASRError, RetryConfig, TranscriptionResult};
+use crate::voice::asr::{run_transcription, ASREngine, ASRError, RetryConfig, Transcription, TranscriptionResult};
 use crate::voice::audio::AudioData;
 use crate::voice::config::ASRConfig;
 
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
+    };
+}
+
 /// 兜底策略
 pub struct FallbackStrategy {
     primary: Box<dyn ASREngine>,
@@ -65,8 +78,8 @@ impl FallbackStrategy {
                 let delay = Duration::from_millis(
                     self.retry_config.base_delay_ms * (1 << (attempt - 1))
                 );
-                eprintln!(
-                    "[INFO] 主引擎重试 {}/{}, 等待 {}ms",
+                log_info!(
+                    "主引擎重试 {}/{}, 等待 {}ms",
                     attempt,
                     self.retry_config.max_retries,
                     delay.as_millis()
@@ -77,8 +90,8 @@ impl FallbackStrategy {
             match self.primary.transcribe(audio).await {
                 Ok(text) => {
                     let duration_ms = start_time.elapsed().as_millis() as u64;
-                    eprintln!(
-                        "[INFO] 主引擎 {} 转录成功 (尝试 {}), 耗时 {}ms",
+                    log_info!(
+                        "主引擎 {} 转录成功 (尝试 {}), 耗时 {}ms",
                         self.primary.name(),
                         attempt + 1,
                         duration_ms
@@ -91,8 +104,8 @@ impl FallbackStrategy {
                     ));
                 }
                 Err(e) => {
-                    eprintln!(
-                        "[WARN] 主引擎 {} 转录失败 (尝试 {}/{}): {}",
+                    log_warn!(
+                        "主引擎 {} 转录失败 (尝试 {}/{}): {}",
                         self.primary.name(),
                         attempt + 1,
                         self.retry_config.max_retries + 1,
@@ -106,12 +119,12 @@ impl FallbackStrategy {
         // 主引擎失败，尝试备用引擎
         if self.enable_fallback {
             if let Some(ref fallback) = self.fallback {
-                eprintln!("[INFO] 主引擎所有重试失败，尝试兜底引擎...");
+                log_info!("主引擎所有重试失败，尝试兜底引擎...");
                 match fallback.transcribe(audio).await {
                     Ok(text) => {
                         let duration_ms = start_time.elapsed().as_millis() as u64;
-                        eprintln!(
-                            "[INFO] 兜底引擎 {} 转录成功，耗时 {}ms",
+                        log_info!(
+                            "兜底引擎 {} 转录成功，耗时 {}ms",
                             fallback.name(),
                             duration_ms
                         );
@@ -165,6 +178,10 @@ pub struct RaceStrategy {
     fallback_config: Option<crate::voice::config::ASRProviderConfig>,
     enable_fallback: bool,
     retry_config: RetryConfig,
+    /// 传给主引擎和兜底引擎的上下文提示
+    prompt: Option<String>,
+    /// 是否请求词级时间戳
+    word_timestamps: bool,
 }
 
 impl RaceStrategy {
@@ -174,6 +191,8 @@ impl RaceStrategy {
             fallback_config: config.fallback,
             enable_fallback: config.enable_fallback,
             retry_config: RetryConfig::default(),
+            prompt: None,
+            word_timestamps: config.word_timestamps,
         }
     }
 
@@ -182,30 +201,37 @@ impl RaceStrategy {
         self
     }
 
+    pub fn with_prompt(mut self, prompt: Option<String>) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
     pub async fn transcribe(&self, audio: &AudioData) -> Result<TranscriptionResult, ASRError> {
         let start_time = Instant::now();
-        let fallback_result: Arc<Mutex<Option<Result<String, String>>>> =
+        let fallback_result: Arc<Mutex<Option<Result<Transcription, String>>>> =
             Arc::new(Mutex::new(None));
 
         let mut fallback_handle = if self.enable_fallback && self.fallback_config.is_some() {
             let fallback_config = self.fallback_config.clone().unwrap();
             let audio_clone = audio.clone();
             let result_holder = Arc::clone(&fallback_result);
+            let prompt = self.prompt.clone();
+            let word_timestamps = self.word_timestamps;
 
-            Some(tokio::spawn(async move {
+            Some(tokio::spawn(crate::audit::inherit(async move {
                 let engine = crate::voice::asr::create_engine(&fallback_config)?;
-                let result = engine.transcribe(&audio_clone).await;
+                let result = run_transcription(engine.as_ref(), &audio_clone, prompt.as_deref(), word_timestamps).await;
                 let mut holder = result_holder.lock().unwrap();
                 match &result {
-                    Ok(text) => {
-                        *holder = Some(Ok(text.clone()));
+                    Ok(transcription) => {
+                        *holder = Some(Ok(transcription.clone()));
                     }
                     Err(error) => {
                         *holder = Some(Err(error.to_string()));
                     }
                 }
                 result
-            }))
+            })))
         } else {
             None
         };
@@ -224,17 +250,17 @@ impl RaceStrategy {
             if attempt > 0 {
                 if let Some(ref result) = *fallback_result.lock().unwrap() {
                     match result {
-                        Ok(text) => {
+                        Ok(transcription) => {
                             if let Some(handle) = fallback_handle.take() {
                                 handle.abort();
                             }
                             let duration_ms = start_time.elapsed().as_millis() as u64;
                             return Ok(TranscriptionResult::new(
-                                text.clone(),
+                                transcription.text.clone(),
                                 fallback_name,
                                 true,
                                 duration_ms,
-                            ));
+                            ).with_words(transcription.words.clone()));
                         }
                         Err(_) => {}
                     }
@@ -243,8 +269,8 @@ impl RaceStrategy {
                 let delay = Duration::from_millis(
                     self.retry_config.base_delay_ms * (1 << (attempt - 1)),
                 );
-                eprintln!(
-                    "[INFO] 主引擎重试 {}/{}, 等待 {}ms",
+                log_info!(
+                    "主引擎重试 {}/{}, 等待 {}ms",
                     attempt,
                     self.retry_config.max_retries,
                     delay.as_millis()
@@ -252,11 +278,11 @@ impl RaceStrategy {
                 tokio::time::sleep(delay).await;
             }
 
-            match primary_engine.transcribe(audio).await {
-                Ok(text) => {
+            match run_transcription(primary_engine.as_ref(), audio, self.prompt.as_deref(), self.word_timestamps).await {
+                Ok(transcription) => {
                     let duration_ms = start_time.elapsed().as_millis() as u64;
-                    eprintln!(
-                        "[INFO] 主引擎 {} 转录成功 (尝试 {}), 耗时 {}ms",
+                    log_info!(
+                        "主引擎 {} 转录成功 (尝试 {}), 耗时 {}ms",
                         primary_name,
                         attempt + 1,
                         duration_ms
@@ -267,15 +293,15 @@ impl RaceStrategy {
                     }
 
                     return Ok(TranscriptionResult::new(
-                        text,
+                        transcription.text,
                         primary_name,
                         false,
                         duration_ms,
-                    ));
+                    ).with_words(transcription.words));
                 }
                 Err(e) => {
-                    eprintln!(
-                        "[WARN] 主引擎 {} 转录失败 (尝试 {}/{}): {}",
+                    log_warn!(
+                        "主引擎 {} 转录失败 (尝试 {}/{}): {}",
                         primary_name,
                         attempt + 1,
                         self.retry_config.max_retries + 1,
@@ -287,23 +313,23 @@ impl RaceStrategy {
         }
 
         if let Some(handle) = fallback_handle {
-            eprintln!("[INFO] 主引擎所有重试失败，等待兜底引擎结果...");
+            log_info!("主引擎所有重试失败，等待兜底引擎结果...");
 
             match handle.await {
-                Ok(Ok(text)) => {
+                Ok(Ok(transcription)) => {
                     let duration_ms = start_time.elapsed().as_millis() as u64;
-                    eprintln!(
-                        "[INFO] 兜底引擎 {} 转录成功，耗时 {}ms",
+                    log_info!(
+                        "兜底引擎 {} 转录成功，耗时 {}ms",
                         fallback_name,
                         duration_ms
                     );
 
                     return Ok(TranscriptionResult::new(
-                        text,
+                        transcription.text,
                         fallback_name,
                         true,
                         duration_ms,
-                    ));
+                    ).with_words(transcription.words));
                 }
                 Ok(Err(fallback_error)) => {
                     return Err(ASRError::AllEnginesFailed {
@@ -362,10 +388,10 @@ impl ParallelFallbackStrategy {
             let fallback_config = self.fallback_config.clone().unwrap();
             let audio_clone = audio.clone();
             
-            Some(tokio::spawn(async move {
+            Some(tokio::spawn(crate::audit::inherit(async move {
                 let engine = crate::voice::asr::create_engine(&fallback_config)?;
                 engine.transcribe(&audio_clone).await
-            }))
+            })))
         } else {
             None
         };
@@ -380,8 +406,8 @@ impl ParallelFallbackStrategy {
                 let delay = Duration::from_millis(
                     self.retry_config.base_delay_ms * (1 << (attempt - 1))
                 );
-                eprintln!(
-                    "[INFO] 主引擎重试 {}/{}, 等待 {}ms",
+                log_info!(
+                    "主引擎重试 {}/{}, 等待 {}ms",
                     attempt,
                     self.retry_config.max_retries,
                     delay.as_millis()
@@ -392,8 +418,8 @@ impl ParallelFallbackStrategy {
             match primary_engine.transcribe(audio).await {
                 Ok(text) => {
                     let duration_ms = start_time.elapsed().as_millis() as u64;
-                    eprintln!(
-                        "[INFO] 主引擎 {} 转录成功 (尝试 {}), 耗时 {}ms",
+                    log_info!(
+                        "主引擎 {} 转录成功 (尝试 {}), 耗时 {}ms",
                         primary_name,
                         attempt + 1,
                         duration_ms
@@ -411,8 +437,8 @@ impl ParallelFallbackStrategy {
                     ));
                 }
                 Err(e) => {
-                    eprintln!(
-                        "[WARN] 主引擎 {} 转录失败 (尝试 {}/{}): {}",
+                    log_warn!(
+                        "主引擎 {} 转录失败 (尝试 {}/{}): {}",
                         primary_name,
                         attempt + 1,
                         self.retry_config.max_retries + 1,
@@ -425,7 +451,7 @@ impl ParallelFallbackStrategy {
         
         // 主引擎所有重试都失败，等待后台任务结果
         if let Some(handle) = fallback_handle {
-            eprintln!("[INFO] 主引擎所有重试失败，等待兜底引擎结果...");
+            log_info!("主引擎所有重试失败，等待兜底引擎结果...");
             
             match handle.await {
                 Ok(Ok(text)) => {
@@ -435,8 +461,8 @@ impl ParallelFallbackStrategy {
                         .map(|c| c.provider.to_string())
                         .unwrap_or_else(|| "fallback".to_string());
                     
-                    eprintln!(
-                        "[INFO] 兜底引擎 {} 转录成功，耗时 {}ms",
+                    log_info!(
+                        "兜底引擎 {} 转录成功，耗时 {}ms",
                         fallback_name,
                         duration_ms
                     );
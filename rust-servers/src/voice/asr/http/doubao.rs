@@ -5,10 +5,32 @@ use async_trait::async_trait;
 use base64::{Engine as _, engine::general_purpose};
 use std::time::{Duration, Instant};
 
-use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession, RetryConfig};
+use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession, RetryConfig, Transcription, WordTiming};
 use crate::voice::audio::AudioData;
+use crate::voice::config::AudioFormat;
 
-const DOUBAO_API_URL: &str = "https://openspeech.bytedance.com/api/v3/auc/bigmodel/recognize/flash";
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
+        }
+    };
+}
+
+pub const DOUBAO_API_URL: &str = "https://openspeech.bytedance.com/api/v3/auc/bigmodel/recognize/flash";
 const RESOURCE_ID: &str = "volc.bigasr.auc_turbo";
 
 pub struct DoubaoHttpEngine {
@@ -16,6 +38,7 @@ pub struct DoubaoHttpEngine {
     access_key: String,
     client: reqwest::Client,
     retry_config: RetryConfig,
+    upload_format: AudioFormat,
 }
 
 impl DoubaoHttpEngine {
@@ -24,28 +47,33 @@ impl DoubaoHttpEngine {
     }
     
     pub fn with_config(app_id: String, access_key: String, retry_config: RetryConfig) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_millis(retry_config.timeout_ms))
-            .build()
-            .unwrap_or_default();
+        let client = crate::network::client();
         
         Self {
             app_id,
             access_key,
             client,
             retry_config,
+            upload_format: AudioFormat::default(),
         }
     }
     
-    async fn transcribe_once(&self, audio: &AudioData) -> Result<String, ASRError> {
-        let wav_data = audio.to_wav()
+    /// 上传录音使用的编码
+    pub fn with_upload_format(mut self, upload_format: AudioFormat) -> Self {
+        self.upload_format = upload_format;
+        self
+    }
+    
+    /// `words` 为 true 时请求分句信息，从中取出词级时间戳
+    async fn transcribe_once(&self, audio: &AudioData, words: bool) -> Result<Transcription, ASRError> {
+        let encoded = audio.encode_as(self.upload_format)
             .map_err(|e| ASRError::InvalidAudio(e.to_string()))?;
         
-        let audio_base64 = general_purpose::STANDARD.encode(&wav_data);
+        let audio_base64 = general_purpose::STANDARD.encode(&encoded.data);
         
-        eprintln!("[INFO] 豆包 ASR: 音频数据大小 {} bytes", wav_data.len());
+        log_info!("豆包 ASR: 音频数据大小 {} bytes ({})", encoded.data.len(), encoded.mime());
         
-        let request_body = serde_json::json!({
+        let mut request_body = serde_json::json!({
             "user": {
                 "uid": &self.app_id
             },
@@ -53,21 +81,34 @@ impl DoubaoHttpEngine {
                 "data": audio_base64
             },
             "request": {
-                "model_name": "bigmodel"
+                "model_name": "bigmodel",
+                "show_utterances": words
             }
         });
+        match encoded.format {
+            AudioFormat::Opus => {
+                request_body["audio"]["format"] = serde_json::json!("ogg");
+                request_body["audio"]["codec"] = serde_json::json!("opus");
+            }
+            AudioFormat::Mp3 => {
+                request_body["audio"]["format"] = serde_json::json!("mp3");
+            }
+            // 默认按 WAV 解析；FLAC 不在支持的格式中，配置校验时已拒绝
+            AudioFormat::Wav | AudioFormat::Flac => {}
+        }
         
         let request_id = generate_request_id();
         
-        let response = self.client
+        let request = self.client
             .post(DOUBAO_API_URL)
+            .timeout(Duration::from_millis(self.retry_config.timeout_ms))
             .header("X-Api-App-Key", &self.app_id)
             .header("X-Api-Access-Key", &self.access_key)
             .header("X-Api-Resource-Id", RESOURCE_ID)
             .header("X-Api-Request-Id", &request_id)
             .header("X-Api-Sequence", "-1")
-            .json(&request_body)
-            .send()
+            .json(&request_body);
+        let response = crate::network::send(crate::router::ModuleType::Voice, request)
             .await
             .map_err(|e| {
                 if e.is_timeout() {
@@ -89,7 +130,7 @@ impl DoubaoHttpEngine {
             .and_then(|v| v.to_str().ok())
             .unwrap_or("");
         
-        eprintln!("[INFO] 豆包 ASR 响应: status_code={}, message={}", status_code, api_message);
+        log_info!("豆包 ASR 响应: status_code={}, message={}", status_code, api_message);
         
         if status_code != "20000000" {
             return match status_code {
@@ -110,7 +151,7 @@ impl DoubaoHttpEngine {
         let result: serde_json::Value = response.json().await
             .map_err(|e| ASRError::InternalError(format!("解析响应失败: {}", e)))?;
         
-        eprintln!("[DEBUG] 豆包 ASR 响应体: {}", serde_json::to_string_pretty(&result).unwrap_or_default());
+        log_debug!("豆包 ASR 响应体: {}", serde_json::to_string_pretty(&result).unwrap_or_default());
         
         let text = result["result"]["text"]
             .as_str()
@@ -122,21 +163,13 @@ impl DoubaoHttpEngine {
         let mut text = text.to_string();
         strip_trailing_punctuation(&mut text);
         
-        Ok(text)
-    }
-}
-
-#[async_trait]
-impl ASREngine for DoubaoHttpEngine {
-    fn name(&self) -> &str {
-        "doubao"
-    }
-    
-    fn supported_modes(&self) -> Vec<ASRMode> {
-        vec![ASRMode::Http]
+        Ok(Transcription {
+            text,
+            words: if words { utterance_words(&result["result"]) } else { None },
+        })
     }
     
-    async fn transcribe(&self, audio: &AudioData) -> Result<String, ASRError> {
+    async fn transcribe_with_retry(&self, audio: &AudioData, words: bool) -> Result<Transcription, ASRError> {
         if audio.is_empty() {
             return Err(ASRError::InvalidAudio("音频数据为空".to_string()));
         }
@@ -151,15 +184,15 @@ impl ASREngine for DoubaoHttpEngine {
                 )).await;
             }
             
-            match self.transcribe_once(audio).await {
-                Ok(text) => {
+            match self.transcribe_once(audio, words).await {
+                Ok(transcription) => {
                     let duration = start_time.elapsed().as_millis() as u64;
-                    eprintln!("[INFO] 豆包 HTTP 转录成功，耗时 {}ms: {}", duration, text);
-                    return Ok(text);
+                    log_info!("豆包 HTTP 转录成功，耗时 {}ms: {}", duration, transcription.text);
+                    return Ok(transcription);
                 }
                 Err(e) => {
-                    eprintln!(
-                        "[WARN] 豆包 HTTP 转录失败 (尝试 {}/{}): {}",
+                    log_warn!(
+                        "豆包 HTTP 转录失败 (尝试 {}/{}): {}",
                         attempt + 1,
                         self.retry_config.max_retries + 1,
                         e
@@ -171,6 +204,47 @@ impl ASREngine for DoubaoHttpEngine {
         
         Err(last_error.unwrap_or_else(|| ASRError::InternalError("转录失败，未知错误".to_string())))
     }
+}
+
+/// 从识别结果的分句 (utterances) 中取出词级时间戳 (毫秒)，实时模式同样使用；没有分句信息时返回 None
+pub(crate) fn utterance_words(result: &serde_json::Value) -> Option<Vec<WordTiming>> {
+    let utterances = result["utterances"].as_array()?;
+    let words = utterances
+        .iter()
+        .filter_map(|utterance| utterance["words"].as_array())
+        .flatten()
+        .filter_map(|word| {
+            let text = word["text"].as_str()?.trim();
+            let start_ms = word["start_time"].as_i64()?;
+            let end_ms = word["end_time"].as_i64()?;
+            // 未确定的词时间为 -1
+            (!text.is_empty() && start_ms >= 0 && end_ms >= start_ms).then(|| WordTiming {
+                text: text.to_string(),
+                start_ms: start_ms as u64,
+                end_ms: end_ms as u64,
+            })
+        })
+        .collect();
+    Some(words)
+}
+
+#[async_trait]
+impl ASREngine for DoubaoHttpEngine {
+    fn name(&self) -> &str {
+        "doubao"
+    }
+    
+    fn supported_modes(&self) -> Vec<ASRMode> {
+        vec![ASRMode::Http]
+    }
+    
+    async fn transcribe(&self, audio: &AudioData) -> Result<String, ASRError> {
+        self.transcribe_with_retry(audio, false).await.map(|transcription| transcription.text)
+    }
+    
+    async fn transcribe_timed(&self, audio: &AudioData, _prompt: Option<&str>) -> Result<Transcription, ASRError> {
+        self.transcribe_with_retry(audio, true).await
+    }
     
     async fn create_realtime_session(&self) -> Result<Box<dyn RealtimeSession>, ASRError> {
         Err(ASRError::UnsupportedOperation(
@@ -202,3 +276,33 @@ fn strip_trailing_punctuation(text: &mut String) {
         }
     }
 }
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utterance_words() {
+        let result = serde_json::json!({
+            "text": "明天开会",
+            "utterances": [
+                { "text": "明天", "words": [
+                    { "text": "明", "start_time": 240, "end_time": 400 },
+                    { "text": "天", "start_time": 400, "end_time": 560 },
+                ] },
+                { "text": "开会", "words": [
+                    { "text": "开会", "start_time": 900, "end_time": 1300 },
+                    { "text": "", "start_time": -1, "end_time": -1 },
+                ] },
+            ],
+        });
+        let words = utterance_words(&result).unwrap();
+        assert_eq!(words.iter().map(|word| word.text.as_str()).collect::<Vec<_>>(), ["明", "天", "开会"]);
+        assert_eq!((words[2].start_ms, words[2].end_ms), (900, 1300));
+        assert_eq!(utterance_words(&serde_json::json!({ "text": "明天开会" })), None);
+    }
+}
@@ -4,7 +4,9 @@
 pub mod qwen;
 pub mod doubao;
 pub mod sensevoice;
+pub mod openai;
 
 pub use qwen::QwenHttpEngine;
 pub use doubao::DoubaoHttpEngine;
 pub use sensevoice::SenseVoiceHttpEngine;
+pub use openai::OpenAiHttpEngine;
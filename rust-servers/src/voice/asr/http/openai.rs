@@ -0,0 +1,287 @@
+// OpenAI 兼容 ASR HTTP 模式实现
+// 调用 OpenAI 格式的 /v1/audio/transcriptions 接口，可用于 OpenAI、Groq 和 LiteLLM 等代理
+
+use async_trait::async_trait;
+use std::time::{Duration, Instant};
+
+use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession, RetryConfig, Transcription, WordTiming};
+use crate::voice::audio::AudioData;
+use crate::voice::config::AudioFormat;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
+    };
+}
+
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+pub const DEFAULT_MODEL: &str = "whisper-1";
+
+pub struct OpenAiHttpEngine {
+    url: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+    retry_config: RetryConfig,
+    model: String,
+    upload_format: AudioFormat,
+}
+
+impl OpenAiHttpEngine {
+    /// `base_url` 为 API 根地址 (如 `https://api.groq.com/openai/v1`)，没有 API Key 时不发送 Authorization
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self::with_config(base_url, api_key, RetryConfig::default())
+    }
+
+    pub fn with_config(base_url: &str, api_key: Option<String>, retry_config: RetryConfig) -> Self {
+        Self {
+            url: transcriptions_url(base_url),
+            api_key,
+            client: crate::network::client(),
+            retry_config,
+            model: DEFAULT_MODEL.to_string(),
+            upload_format: AudioFormat::default(),
+        }
+    }
+
+    pub fn with_model(mut self, model: String) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// 上传录音使用的编码
+    pub fn with_upload_format(mut self, upload_format: AudioFormat) -> Self {
+        self.upload_format = upload_format;
+        self
+    }
+
+    /// `words` 为 true 时请求 verbose_json 格式和词级时间戳 (需要模型支持，如 whisper-1)
+    async fn transcribe_once(&self, audio: &AudioData, prompt: Option<&str>, words: bool) -> Result<Transcription, ASRError> {
+        let encoded = audio.encode_as(self.upload_format)
+            .map_err(|e| ASRError::InvalidAudio(e.to_string()))?;
+
+        log_info!("OpenAI 兼容 ASR: 音频数据大小 {} bytes ({}), 模型 {}", encoded.data.len(), encoded.mime(), self.model);
+
+        let (file_name, mime) = (encoded.file_name(), encoded.mime());
+        let file_part = reqwest::multipart::Part::bytes(encoded.data)
+            .file_name(file_name)
+            .mime_str(mime)
+            .map_err(|e| ASRError::InternalError(format!("创建文件部分失败: {}", e)))?;
+
+        let mut form = reqwest::multipart::Form::new()
+            .part("file", file_part)
+            .text("model", self.model.clone());
+        form = if words {
+            form.text("response_format", "verbose_json")
+                .text("timestamp_granularities[]", "word")
+        } else {
+            form.text("response_format", "json")
+        };
+        if let Some(prompt) = prompt {
+            form = form.text("prompt", prompt.to_string());
+        }
+
+        let mut request = self.client
+            .post(&self.url)
+            .timeout(Duration::from_millis(self.retry_config.timeout_ms))
+            .multipart(form);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        let response = crate::network::send(crate::router::ModuleType::Voice, request)
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    ASRError::Timeout { timeout_ms: self.retry_config.timeout_ms }
+                } else {
+                    ASRError::NetworkError(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "无法读取错误响应".to_string());
+
+            return match status.as_u16() {
+                401 | 403 => Err(ASRError::AuthFailed {
+                    engine: "openai".to_string(),
+                    message: error_text,
+                }),
+                429 => Err(ASRError::QuotaExceeded {
+                    engine: "openai".to_string(),
+                }),
+                404 => Err(ASRError::ConfigError(format!(
+                    "接口或模型不存在 ({}): {}",
+                    self.url, error_text
+                ))),
+                _ => Err(ASRError::NetworkError(format!(
+                    "API 请求失败 ({}): {}",
+                    status, error_text
+                ))),
+            };
+        }
+
+        let result: TranscriptionResponse = response.json().await
+            .map_err(|e| ASRError::InternalError(format!("解析响应失败: {}", e)))?;
+
+        Ok(Transcription {
+            text: result.text.trim().to_string(),
+            words: result.words.map(|words| {
+                words.iter().map(|word| WordTiming::from_secs(&word.word, word.start, word.end)).collect()
+            }),
+        })
+    }
+
+    async fn transcribe_with_retry(&self, audio: &AudioData, prompt: Option<&str>, words: bool) -> Result<Transcription, ASRError> {
+        if audio.is_empty() {
+            return Err(ASRError::InvalidAudio("音频数据为空".to_string()));
+        }
+
+        let start_time = Instant::now();
+        let mut last_error = None;
+
+        for attempt in 0..=self.retry_config.max_retries {
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_millis(
+                    self.retry_config.base_delay_ms * (1 << (attempt - 1))
+                )).await;
+            }
+
+            match self.transcribe_once(audio, prompt, words).await {
+                Ok(transcription) => {
+                    let duration = start_time.elapsed().as_millis() as u64;
+                    log_info!("OpenAI 兼容 HTTP 转录成功，耗时 {}ms: {}", duration, transcription.text);
+                    return Ok(transcription);
+                }
+                // 认证和配置错误重试也不会成功
+                Err(e @ (ASRError::AuthFailed { .. } | ASRError::ConfigError(_))) => return Err(e),
+                Err(e) => {
+                    log_warn!(
+                        "OpenAI 兼容 HTTP 转录失败 (尝试 {}/{}): {}",
+                        attempt + 1,
+                        self.retry_config.max_retries + 1,
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ASRError::InternalError("转录失败，未知错误".to_string())))
+    }
+}
+
+/// 转写接口地址
+fn transcriptions_url(base_url: &str) -> String {
+    format!("{}/audio/transcriptions", base_url.trim_end_matches('/'))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+    /// verbose_json 格式且请求了词级时间戳时返回
+    #[serde(default)]
+    words: Option<Vec<ResponseWord>>,
+}
+
+/// 词级时间戳 (秒)
+#[derive(Debug, serde::Deserialize)]
+struct ResponseWord {
+    word: String,
+    start: f64,
+    end: f64,
+}
+
+#[async_trait]
+impl ASREngine for OpenAiHttpEngine {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn supported_modes(&self) -> Vec<ASRMode> {
+        vec![ASRMode::Http]
+    }
+
+    async fn transcribe(&self, audio: &AudioData) -> Result<String, ASRError> {
+        self.transcribe_with_prompt(audio, None).await
+    }
+
+    async fn transcribe_with_prompt(&self, audio: &AudioData, prompt: Option<&str>) -> Result<String, ASRError> {
+        self.transcribe_with_retry(audio, prompt, false).await.map(|transcription| transcription.text)
+    }
+
+    async fn transcribe_timed(&self, audio: &AudioData, prompt: Option<&str>) -> Result<Transcription, ASRError> {
+        self.transcribe_with_retry(audio, prompt, true).await
+    }
+
+    async fn create_realtime_session(&self) -> Result<Box<dyn RealtimeSession>, ASRError> {
+        Err(ASRError::UnsupportedOperation(
+            "OpenAI 兼容接口不支持 Realtime 模式，仅支持 HTTP 模式".to_string()
+        ))
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::llm::MockLlm;
+
+    /// 模拟上游的 API 根地址
+    fn base_url(upstream: &MockLlm) -> String {
+        upstream.endpoint().trim_end_matches("/chat/completions").to_string()
+    }
+
+    fn audio() -> AudioData {
+        AudioData::new(vec![0.1; 16000], 16000, 1)
+    }
+
+    #[test]
+    fn test_transcriptions_url() {
+        assert_eq!(transcriptions_url(DEFAULT_BASE_URL), "https://api.openai.com/v1/audio/transcriptions");
+        assert_eq!(transcriptions_url("http://localhost:4000/v1/"), "http://localhost:4000/v1/audio/transcriptions");
+    }
+
+    #[tokio::test]
+    async fn test_transcribe() {
+        let upstream = MockLlm::replying(200, r#"{"text": " 明天上午十点开会。 "}"#).await;
+        let engine = OpenAiHttpEngine::new(&base_url(&upstream), Some("sk-test".to_string()));
+        assert_eq!(engine.transcribe(&audio()).await.unwrap(), "明天上午十点开会。");
+        assert!(!upstream.last_body().contains("name=\"prompt\""));
+
+        // 上下文提示作为 prompt 字段发送
+        engine.transcribe_with_prompt(&audio(), Some("张伟确认了排期")).await.unwrap();
+        assert!(upstream.last_body().contains("name=\"prompt\"\r\n\r\n张伟确认了排期"));
+
+        // 认证失败不重试
+        let upstream = MockLlm::replying(401, r#"{"error": {"message": "Invalid API key"}}"#).await;
+        let engine = OpenAiHttpEngine::new(&base_url(&upstream), None);
+        assert!(matches!(engine.transcribe(&audio()).await, Err(ASRError::AuthFailed { .. })));
+        assert_eq!(upstream.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_timed() {
+        let upstream = MockLlm::replying(200, r#"{"text": "Ship it.", "words": [{"word": "Ship", "start": 0.12, "end": 0.4}, {"word": "it", "start": 0.4, "end": 0.61}]}"#).await;
+        let engine = OpenAiHttpEngine::new(&base_url(&upstream), None);
+        let transcription = engine.transcribe_timed(&audio(), None).await.unwrap();
+        assert_eq!(transcription.text, "Ship it.");
+        assert_eq!(transcription.words.unwrap(), vec![
+            WordTiming { text: "Ship".to_string(), start_ms: 120, end_ms: 400 },
+            WordTiming { text: "it".to_string(), start_ms: 400, end_ms: 610 },
+        ]);
+        let body = upstream.last_body();
+        assert!(body.contains("verbose_json") && body.contains("name=\"timestamp_granularities[]\"\r\n\r\nword"));
+    }
+}
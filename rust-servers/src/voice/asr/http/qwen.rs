@@ -7,8 +7,22 @@ use std::time::{Duration, Instant};
 
 use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession, RetryConfig};
 use crate::voice::audio::AudioData;
+use crate::voice::config::AudioFormat;
 
-const QWEN_API_URL: &str = "https://dashscope.aliyuncs.com/api/v1/services/aigc/multimodal-generation/generation";
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
+    };
+}
+
+pub const QWEN_API_URL: &str = "https://dashscope.aliyuncs.com/api/v1/services/aigc/multimodal-generation/generation";
 const DEFAULT_MODEL: &str = "qwen3-asr-flash";
 
 pub struct QwenHttpEngine {
@@ -16,6 +30,7 @@ pub struct QwenHttpEngine {
     client: reqwest::Client,
     retry_config: RetryConfig,
     model: String,
+    upload_format: AudioFormat,
 }
 
 impl QwenHttpEngine {
@@ -24,16 +39,14 @@ impl QwenHttpEngine {
     }
     
     pub fn with_config(api_key: String, retry_config: RetryConfig) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_millis(retry_config.timeout_ms))
-            .build()
-            .unwrap_or_default();
+        let client = crate::network::client();
         
         Self {
             api_key,
             client,
             retry_config,
             model: DEFAULT_MODEL.to_string(),
+            upload_format: AudioFormat::default(),
         }
     }
     
@@ -42,11 +55,17 @@ impl QwenHttpEngine {
         self
     }
     
+    /// 上传录音使用的编码
+    pub fn with_upload_format(mut self, upload_format: AudioFormat) -> Self {
+        self.upload_format = upload_format;
+        self
+    }
+    
     async fn transcribe_once(&self, audio: &AudioData) -> Result<String, ASRError> {
-        let wav_data = audio.to_wav()
+        let encoded = audio.encode_as(self.upload_format)
             .map_err(|e| ASRError::InvalidAudio(e.to_string()))?;
         
-        let audio_base64 = general_purpose::STANDARD.encode(&wav_data);
+        let audio_base64 = general_purpose::STANDARD.encode(&encoded.data);
         
         let request_body = serde_json::json!({
             "model": self.model,
@@ -59,7 +78,7 @@ impl QwenHttpEngine {
                     {
                         "role": "user",
                         "content": [{
-                            "audio": format!("data:audio/wav;base64,{}", audio_base64)
+                            "audio": format!("data:{};base64,{}", encoded.mime(), audio_base64)
                         }]
                     }
                 ]
@@ -72,12 +91,13 @@ impl QwenHttpEngine {
             }
         });
         
-        let response = self.client
+        let request = self.client
             .post(QWEN_API_URL)
+            .timeout(Duration::from_millis(self.retry_config.timeout_ms))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
+            .json(&request_body);
+        let response = crate::network::send(crate::router::ModuleType::Voice, request)
             .await
             .map_err(|e| {
                 if e.is_timeout() {
@@ -157,12 +177,12 @@ impl ASREngine for QwenHttpEngine {
             match self.transcribe_once(audio).await {
                 Ok(text) => {
                     let duration = start_time.elapsed().as_millis() as u64;
-                    eprintln!("[INFO] Qwen HTTP 转录成功，耗时 {}ms", duration);
+                    log_info!("Qwen HTTP 转录成功，耗时 {}ms", duration);
                     return Ok(text);
                 }
                 Err(e) => {
-                    eprintln!(
-                        "[WARN] Qwen HTTP 转录失败 (尝试 {}/{}): {}",
+                    log_warn!(
+                        "Qwen HTTP 转录失败 (尝试 {}/{}): {}",
                         attempt + 1,
                         self.retry_config.max_retries + 1,
                         e
@@ -6,8 +6,30 @@ use std::time::{Duration, Instant};
 
 use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession, RetryConfig};
 use crate::voice::audio::AudioData;
+use crate::voice::config::AudioFormat;
 
-const SILICONFLOW_API_URL: &str = "https://api.siliconflow.cn/v1/audio/transcriptions";
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
+        }
+    };
+}
+
+pub const SILICONFLOW_API_URL: &str = "https://api.siliconflow.cn/v1/audio/transcriptions";
 const DEFAULT_MODEL: &str = "FunAudioLLM/SenseVoiceSmall";
 
 pub struct SenseVoiceHttpEngine {
@@ -15,6 +37,7 @@ pub struct SenseVoiceHttpEngine {
     client: reqwest::Client,
     retry_config: RetryConfig,
     model: String,
+    upload_format: AudioFormat,
 }
 
 impl SenseVoiceHttpEngine {
@@ -23,16 +46,14 @@ impl SenseVoiceHttpEngine {
     }
     
     pub fn with_config(api_key: String, retry_config: RetryConfig) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_millis(retry_config.timeout_ms))
-            .build()
-            .unwrap_or_default();
+        let client = crate::network::client();
         
         Self {
             api_key,
             client,
             retry_config,
             model: DEFAULT_MODEL.to_string(),
+            upload_format: AudioFormat::default(),
         }
     }
     
@@ -41,26 +62,34 @@ impl SenseVoiceHttpEngine {
         self
     }
     
+    /// 上传录音使用的编码
+    pub fn with_upload_format(mut self, upload_format: AudioFormat) -> Self {
+        self.upload_format = upload_format;
+        self
+    }
+    
     async fn transcribe_once(&self, audio: &AudioData) -> Result<String, ASRError> {
-        let wav_data = audio.to_wav()
+        let encoded = audio.encode_as(self.upload_format)
             .map_err(|e| ASRError::InvalidAudio(e.to_string()))?;
         
-        eprintln!("[INFO] SenseVoice ASR: 音频数据大小 {} bytes", wav_data.len());
+        log_info!("SenseVoice ASR: 音频数据大小 {} bytes ({})", encoded.data.len(), encoded.mime());
         
-        let file_part = reqwest::multipart::Part::bytes(wav_data)
-            .file_name("audio.wav")
-            .mime_str("audio/wav")
+        let (file_name, mime) = (encoded.file_name(), encoded.mime());
+        let file_part = reqwest::multipart::Part::bytes(encoded.data)
+            .file_name(file_name)
+            .mime_str(mime)
             .map_err(|e| ASRError::InternalError(format!("创建文件部分失败: {}", e)))?;
         
         let form = reqwest::multipart::Form::new()
             .part("file", file_part)
             .text("model", self.model.clone());
         
-        let response = self.client
+        let request = self.client
             .post(SILICONFLOW_API_URL)
+            .timeout(Duration::from_millis(self.retry_config.timeout_ms))
             .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
+            .multipart(form);
+        let response = crate::network::send(crate::router::ModuleType::Voice, request)
             .await
             .map_err(|e| {
                 if e.is_timeout() {
@@ -102,7 +131,7 @@ impl SenseVoiceHttpEngine {
         let result: SenseVoiceResponse = response.json().await
             .map_err(|e| ASRError::InternalError(format!("解析响应失败: {}", e)))?;
         
-        eprintln!("[DEBUG] SenseVoice ASR 响应: text={}", result.text);
+        log_debug!("SenseVoice ASR 响应: text={}", result.text);
         
         let mut text = result.text;
         strip_trailing_punctuation(&mut text);
@@ -144,12 +173,12 @@ impl ASREngine for SenseVoiceHttpEngine {
             match self.transcribe_once(audio).await {
                 Ok(text) => {
                     let duration = start_time.elapsed().as_millis() as u64;
-                    eprintln!("[INFO] SenseVoice HTTP 转录成功，耗时 {}ms: {}", duration, text);
+                    log_info!("SenseVoice HTTP 转录成功，耗时 {}ms: {}", duration, text);
                     return Ok(text);
                 }
                 Err(e) => {
-                    eprintln!(
-                        "[WARN] SenseVoice HTTP 转录失败 (尝试 {}/{}): {}",
+                    log_warn!(
+                        "SenseVoice HTTP 转录失败 (尝试 {}/{}): {}",
                         attempt + 1,
                         self.retry_config.max_retries + 1,
                         e
@@ -0,0 +1,203 @@
+// 部分结果延迟统计
+// 实时模式下记录从音频块送出到收到新的部分结果的时间，按引擎汇总供 voice/get_metrics 查询，
+// 用于比较各实时引擎的响应速度
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// 每个引擎保留的最近样本数 (用于计算分位数)
+const RECENT_SAMPLES: usize = 200;
+
+/// 判断延迟过高时取最近几次的平均值，避免单次抖动触发警告
+const WARNING_WINDOW: usize = 5;
+
+/// 相邻音频块的时间戳相差超过此值 (毫秒) 视为停顿 (静音块未发送)，重新开始计时
+const PAUSE_GAP_MS: u64 = 1000;
+
+/// 单次录音的延迟计时
+///
+/// 以上一个部分结果之后送出的第一个音频块为起点，收到内容变化的部分结果时为终点
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    pending_since: Option<Instant>,
+    last_chunk_ms: Option<u64>,
+    last_text: String,
+    window: VecDeque<u64>,
+    warned: bool,
+}
+
+impl LatencyTracker {
+    /// 记录送出的音频块 (`timestamp_ms` 为录音开始后的采集时间)
+    pub fn on_chunk(&mut self, timestamp_ms: u64, now: Instant) {
+        let paused = self.last_chunk_ms.is_some_and(|last| timestamp_ms.saturating_sub(last) > PAUSE_GAP_MS);
+        if paused || self.pending_since.is_none() {
+            self.pending_since = Some(now);
+        }
+        self.last_chunk_ms = Some(timestamp_ms);
+    }
+
+    /// 记录收到的部分结果，内容有变化且有待计时的音频时返回延迟 (毫秒)
+    pub fn on_partial(&mut self, text: &str, now: Instant) -> Option<u64> {
+        let text = text.trim();
+        if text.is_empty() || text == self.last_text {
+            return None;
+        }
+        self.last_text = text.to_string();
+        let since = self.pending_since.take()?;
+        let latency = now.saturating_duration_since(since).as_millis() as u64;
+        self.window.push_back(latency);
+        if self.window.len() > WARNING_WINDOW {
+            self.window.pop_front();
+        }
+        Some(latency)
+    }
+
+    /// 最近几次的平均延迟超过阈值时返回该平均值 (每次录音每个引擎只警告一次)
+    pub fn check_warning(&mut self, threshold_ms: u64) -> Option<u64> {
+        if threshold_ms == 0 || self.warned || self.window.len() < WARNING_WINDOW {
+            return None;
+        }
+        let average = self.window.iter().sum::<u64>() / self.window.len() as u64;
+        if average <= threshold_ms {
+            return None;
+        }
+        self.warned = true;
+        Some(average)
+    }
+}
+
+/// 单个引擎的累计统计
+#[derive(Debug, Default)]
+struct EngineLatency {
+    count: u64,
+    total_ms: u64,
+    max_ms: u64,
+    recent: VecDeque<u64>,
+}
+
+/// 单个引擎的延迟统计结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencyMetrics {
+    pub engine: String,
+    /// 样本数
+    pub count: u64,
+    pub avg_ms: u64,
+    /// 最近样本的中位数和 95 分位数
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+fn stats() -> &'static Mutex<HashMap<String, EngineLatency>> {
+    static STATS: OnceLock<Mutex<HashMap<String, EngineLatency>>> = OnceLock::new();
+    STATS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 记录一次部分结果延迟
+pub fn record(engine: &str, latency_ms: u64) {
+    let mut stats = stats().lock().unwrap_or_else(|e| e.into_inner());
+    let stat = stats.entry(engine.to_string()).or_default();
+    stat.count += 1;
+    stat.total_ms += latency_ms;
+    stat.max_ms = stat.max_ms.max(latency_ms);
+    stat.recent.push_back(latency_ms);
+    if stat.recent.len() > RECENT_SAMPLES {
+        stat.recent.pop_front();
+    }
+}
+
+/// 服务器启动以来各引擎的延迟统计 (按引擎名称排序)
+pub fn snapshot() -> Vec<LatencyMetrics> {
+    let stats = stats().lock().unwrap_or_else(|e| e.into_inner());
+    let mut metrics: Vec<LatencyMetrics> = stats
+        .iter()
+        .map(|(engine, stat)| {
+            let mut recent: Vec<u64> = stat.recent.iter().copied().collect();
+            recent.sort_unstable();
+            LatencyMetrics {
+                engine: engine.clone(),
+                count: stat.count,
+                avg_ms: stat.total_ms / stat.count.max(1),
+                p50_ms: percentile(&recent, 50),
+                p95_ms: percentile(&recent, 95),
+                max_ms: stat.max_ms,
+            }
+        })
+        .collect();
+    metrics.sort_by(|a, b| a.engine.cmp(&b.engine));
+    metrics
+}
+
+/// 已排序样本的分位数 (最近秩法)
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_tracker() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut tracker = LatencyTracker::default();
+
+        // 从上一个结果之后的第一个音频块开始计时
+        tracker.on_chunk(0, at(0));
+        tracker.on_chunk(200, at(200));
+        assert_eq!(tracker.on_partial("今天", at(450)), Some(450));
+        // 内容不变或没有新音频时不计
+        assert_eq!(tracker.on_partial("今天", at(500)), None);
+        assert_eq!(tracker.on_partial("今天下午", at(600)), None);
+
+        tracker.on_chunk(400, at(700));
+        tracker.on_chunk(600, at(900));
+        assert_eq!(tracker.on_partial("今天下午开会", at(1000)), Some(300));
+
+        // 停顿后重新计时
+        tracker.on_chunk(800, at(1100));
+        tracker.on_chunk(5000, at(5300));
+        assert_eq!(tracker.on_partial("今天下午开会，记得", at(5500)), Some(200));
+    }
+
+    #[test]
+    fn test_warning() {
+        let start = Instant::now();
+        let mut tracker = LatencyTracker::default();
+        for i in 0..WARNING_WINDOW as u64 {
+            tracker.on_chunk(i * 200, start);
+            tracker.on_partial(&"字".repeat(i as usize + 1), start + Duration::from_millis(2500));
+        }
+        assert_eq!(tracker.check_warning(0), None);
+        assert_eq!(tracker.check_warning(3000), None);
+        assert_eq!(tracker.check_warning(2000), Some(2500));
+        // 只警告一次
+        assert_eq!(tracker.check_warning(2000), None);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        for latency in 1..=100 {
+            record("latency-test-engine", latency * 10);
+        }
+        let metrics = snapshot().into_iter().find(|m| m.engine == "latency-test-engine").unwrap();
+        assert_eq!(
+            (metrics.count, metrics.avg_ms, metrics.p50_ms, metrics.p95_ms, metrics.max_ms),
+            (100, 505, 500, 950, 1000)
+        );
+        assert_eq!(percentile(&[], 50), 0);
+        assert_eq!(percentile(&[7], 95), 7);
+    }
+}
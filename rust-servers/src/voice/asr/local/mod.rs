@@ -0,0 +1,8 @@
+// ASR 本地引擎
+// 在本机运行的离线识别引擎，不需要 API Key
+
+pub mod vosk;
+pub mod whisper;
+
+pub use vosk::VoskEngine;
+pub use whisper::WhisperEngine;
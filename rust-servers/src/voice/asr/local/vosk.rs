@@ -0,0 +1,561 @@
+// Vosk 本地识别实现
+// 使用 Vosk (Kaldi) 离线识别，支持实时模式 (边录边给出部分结果) 和整段录音转写；
+// 运行时加载 libvosk 动态库，不需要特殊编译选项，模型目录在首次使用时加载并缓存
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_float, c_int, c_short, c_void, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::storage::StorageArea;
+use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession, Transcription, WordTiming};
+use crate::voice::audio::recorder::{convert_f32_to_i16, resample, to_mono};
+use crate::voice::audio::{AudioData, TARGET_SAMPLE_RATE};
+use crate::voice::config::ASRProviderConfig;
+use crate::workers::WorkKind;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+/// 整段转写时每次送入识别器的样本数 (200ms)
+const CHUNK_SAMPLES: usize = TARGET_SAMPLE_RATE as usize / 5;
+
+pub struct VoskEngine {
+    model_dir: PathBuf,
+}
+
+impl VoskEngine {
+    /// 检查模型目录并加载 libvosk
+    pub fn from_config(config: &ASRProviderConfig) -> Result<Self, ASRError> {
+        let model_dir = model_dir(config)?;
+        api()?;
+        Ok(Self { model_dir })
+    }
+
+    /// 加载 (或取出已缓存的) 模型，加载大模型需要数秒，在阻塞线程中进行
+    async fn model(&self) -> Result<Arc<Model>, ASRError> {
+        let dir = self.model_dir.clone();
+        tokio::task::spawn_blocking(move || Model::load(&dir))
+            .await
+            .map_err(|e| ASRError::InternalError(format!("加载 Vosk 模型任务失败: {}", e)))?
+    }
+}
+
+/// 模型目录 (解压后的 Vosk 模型，如 vosk-model-small-cn-0.22)
+fn model_dir(config: &ASRProviderConfig) -> Result<PathBuf, ASRError> {
+    let path = config.model_path.as_deref()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| ASRError::ConfigError("vosk 需要 model_path".to_string()))?;
+    if !path.is_dir() {
+        return Err(ASRError::ConfigError(format!("Vosk 模型目录不存在: {}", path.display())));
+    }
+    Ok(path)
+}
+
+/// 转为 Vosk 识别器使用的 16kHz 单声道 16 位样本
+fn prepare_samples(audio: &AudioData) -> Vec<i16> {
+    let mono = to_mono(&audio.samples, audio.channels);
+    convert_f32_to_i16(&resample(&mono, audio.sample_rate, TARGET_SAMPLE_RATE))
+}
+
+/// 实时音频块 (16 位小端 PCM) 转为样本
+fn pcm_samples(chunk: &[u8]) -> Vec<i16> {
+    chunk.chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect()
+}
+
+#[async_trait]
+impl ASREngine for VoskEngine {
+    fn name(&self) -> &str {
+        "vosk"
+    }
+
+    fn supported_modes(&self) -> Vec<ASRMode> {
+        vec![ASRMode::Realtime, ASRMode::Http]
+    }
+
+    async fn transcribe(&self, audio: &AudioData) -> Result<String, ASRError> {
+        self.transcribe_timed(audio, None).await.map(|transcription| transcription.text)
+    }
+
+    async fn transcribe_timed(&self, audio: &AudioData, _prompt: Option<&str>) -> Result<Transcription, ASRError> {
+        if audio.is_empty() {
+            return Err(ASRError::InvalidAudio("音频数据为空".to_string()));
+        }
+
+        let start_time = Instant::now();
+        let model = self.model().await?;
+        let samples = prepare_samples(audio);
+        let transcript = crate::workers::spawn_blocking(WorkKind::LocalAsr, move || {
+            let mut recognizer = Recognizer::new(model)?;
+            let mut transcript = Transcript::default();
+            for chunk in samples.chunks(CHUNK_SAMPLES) {
+                if recognizer.accept(chunk)? {
+                    transcript.finish_sentence(recognizer.result());
+                }
+            }
+            transcript.finish_sentence(recognizer.final_result());
+            Ok::<_, ASRError>(transcript)
+        })
+            .await
+            .map_err(|e| ASRError::InternalError(format!("Vosk 识别任务失败: {}", e)))??;
+
+        let text = transcript.text();
+        log_info!("Vosk 本地转录完成，耗时 {}ms: {}", start_time.elapsed().as_millis(), text);
+        Ok(Transcription { text, words: Some(transcript.words) })
+    }
+
+    async fn create_realtime_session(&self) -> Result<Box<dyn RealtimeSession>, ASRError> {
+        let recognizer = Recognizer::new(self.model().await?)?;
+        log_info!("已创建 Vosk 实时会话: {}", self.model_dir.display());
+        Ok(Box::new(VoskSession {
+            recognizer: Arc::new(Mutex::new(recognizer)),
+            transcript: Transcript::default(),
+            partial_callback: None,
+        }))
+    }
+}
+
+// ============================================================================
+// 实时会话
+// ============================================================================
+
+/// 部分结果回调 (参数为到目前为止的完整文本)
+type PartialCallback = Box<dyn Fn(&str) + Send + 'static>;
+
+pub struct VoskSession {
+    recognizer: Arc<Mutex<Recognizer>>,
+    transcript: Transcript,
+    partial_callback: Option<PartialCallback>,
+}
+
+/// 一个音频块的识别结果
+enum Step {
+    /// 检测到停顿，一句话结束
+    Finished(Sentence),
+    /// 当前句子的部分结果
+    Partial(String),
+}
+
+impl VoskSession {
+    /// 在阻塞线程中操作识别器 (Kaldi 解码会占用数毫秒到数十毫秒)
+    async fn run<T: Send + 'static>(
+        recognizer: Arc<Mutex<Recognizer>>,
+        f: impl FnOnce(&mut Recognizer) -> Result<T, ASRError> + Send + 'static,
+    ) -> Result<T, ASRError> {
+        tokio::task::spawn_blocking(move || f(&mut recognizer.lock().unwrap_or_else(|e| e.into_inner())))
+            .await
+            .map_err(|e| ASRError::InternalError(format!("Vosk 识别任务失败: {}", e)))?
+    }
+}
+
+// 本地会话没有需要保活的连接，不覆盖 `keep_alive_interval`：暂停和静音期间不发送任何数据，
+// 实时任务此时处于空闲等待，不计入看门狗的停滞超时
+#[async_trait]
+impl RealtimeSession for VoskSession {
+    async fn send_chunk(&mut self, chunk: &[u8]) -> Result<(), ASRError> {
+        let samples = pcm_samples(chunk);
+        let step = Self::run(Arc::clone(&self.recognizer), move |recognizer| {
+            Ok(if recognizer.accept(&samples)? {
+                Step::Finished(recognizer.result())
+            } else {
+                Step::Partial(recognizer.partial())
+            })
+        }).await?;
+
+        let changed = match step {
+            Step::Finished(sentence) => self.transcript.finish_sentence(sentence),
+            Step::Partial(text) => self.transcript.set_partial(&text),
+        };
+        if changed {
+            if let Some(callback) = &self.partial_callback {
+                callback(&self.transcript.text());
+            }
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<String, ASRError> {
+        let sentence = Self::run(Arc::clone(&self.recognizer), |recognizer| Ok(recognizer.final_result())).await?;
+        self.transcript.finish_sentence(sentence);
+        Ok(self.transcript.text())
+    }
+
+    fn words(&self) -> Option<Vec<WordTiming>> {
+        Some(self.transcript.words.clone())
+    }
+
+    fn set_partial_callback(&mut self, callback: Box<dyn Fn(&str) + Send + 'static>) {
+        self.partial_callback = Some(callback);
+    }
+}
+
+// ============================================================================
+// 识别文本
+// ============================================================================
+
+/// 已结束的句子和当前句子的部分结果
+#[derive(Debug, Default)]
+struct Transcript {
+    finished: String,
+    partial: String,
+    /// 已结束句子的词级时间戳
+    words: Vec<WordTiming>,
+}
+
+/// 一句话的识别结果
+#[derive(Debug, Default)]
+struct Sentence {
+    text: String,
+    words: Vec<WordTiming>,
+}
+
+impl Transcript {
+    /// 结束当前句子，返回文本是否变化
+    fn finish(&mut self, text: &str) -> bool {
+        let before = self.text();
+        self.finished = join_sentences(&self.finished, &join_words(text));
+        self.partial.clear();
+        self.text() != before
+    }
+
+    /// 结束当前句子并记录词级时间戳，返回文本是否变化
+    fn finish_sentence(&mut self, sentence: Sentence) -> bool {
+        self.words.extend(sentence.words);
+        self.finish(&sentence.text)
+    }
+
+    /// 更新当前句子的部分结果，返回文本是否变化
+    fn set_partial(&mut self, text: &str) -> bool {
+        let text = join_words(text);
+        if text == self.partial {
+            return false;
+        }
+        self.partial = text;
+        true
+    }
+
+    /// 完整文本 (含当前句子的部分结果)
+    fn text(&self) -> String {
+        join_sentences(&self.finished, &self.partial)
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}' | '\u{3040}'..='\u{30FF}')
+}
+
+/// 拼接两段文本：两侧都不是中日文字符时以空格分隔
+fn join_sentences(left: &str, right: &str) -> String {
+    match (left.chars().next_back(), right.chars().next()) {
+        (None, _) => right.to_string(),
+        (_, None) => left.to_string(),
+        (Some(a), Some(b)) if is_cjk(a) || is_cjk(b) => format!("{}{}", left, right),
+        _ => format!("{} {}", left, right),
+    }
+}
+
+/// Vosk 输出以空格分隔的词 (中文模型同样如此)，去掉中日文字符之间的空格
+fn join_words(text: &str) -> String {
+    text.split_whitespace().fold(String::new(), |joined, word| join_sentences(&joined, word))
+}
+
+/// 从识别器的 JSON 结果中取出文本字段
+fn result_text(json: &str, field: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(json)
+        .ok()
+        .and_then(|value| value.get(field)?.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// 解析一句话的结果：文本和 `result` 中各词的时间 (秒，相对识别开始)
+fn parse_sentence(json: &str) -> Sentence {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return Sentence::default();
+    };
+    let words = value["result"]
+        .as_array()
+        .map(|words| {
+            words.iter()
+                .filter_map(|word| Some(WordTiming::from_secs(word["word"].as_str()?, word["start"].as_f64()?, word["end"].as_f64()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    Sentence {
+        text: value["text"].as_str().unwrap_or_default().to_string(),
+        words,
+    }
+}
+
+// ============================================================================
+// libvosk 接口
+// ============================================================================
+
+type ModelNew = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+type Free = unsafe extern "C" fn(*mut c_void);
+type RecognizerNew = unsafe extern "C" fn(*mut c_void, c_float) -> *mut c_void;
+type AcceptWaveform = unsafe extern "C" fn(*mut c_void, *const c_short, c_int) -> c_int;
+type GetResult = unsafe extern "C" fn(*mut c_void) -> *const c_char;
+type SetLogLevel = unsafe extern "C" fn(c_int);
+type SetWords = unsafe extern "C" fn(*mut c_void, c_int);
+
+/// libvosk 的 C 函数
+struct Api {
+    model_new: ModelNew,
+    model_free: Free,
+    recognizer_new: RecognizerNew,
+    recognizer_free: Free,
+    set_words: SetWords,
+    accept_waveform: AcceptWaveform,
+    result: GetResult,
+    partial_result: GetResult,
+    final_result: GetResult,
+    /// 函数指针依赖动态库一直保持加载
+    _library: libloading::Library,
+}
+
+impl Api {
+    /// # Safety
+    /// `library` 必须是 libvosk (函数签名与 vosk_api.h 一致)
+    unsafe fn from_library(library: libloading::Library) -> Result<Self, libloading::Error> {
+        let model_new = *library.get::<ModelNew>(b"vosk_model_new\0")?;
+        let model_free = *library.get::<Free>(b"vosk_model_free\0")?;
+        let recognizer_new = *library.get::<RecognizerNew>(b"vosk_recognizer_new\0")?;
+        let recognizer_free = *library.get::<Free>(b"vosk_recognizer_free\0")?;
+        let set_words = *library.get::<SetWords>(b"vosk_recognizer_set_words\0")?;
+        let accept_waveform = *library.get::<AcceptWaveform>(b"vosk_recognizer_accept_waveform_s\0")?;
+        let result = *library.get::<GetResult>(b"vosk_recognizer_result\0")?;
+        let partial_result = *library.get::<GetResult>(b"vosk_recognizer_partial_result\0")?;
+        let final_result = *library.get::<GetResult>(b"vosk_recognizer_final_result\0")?;
+        // Kaldi 默认把日志写到 stderr，关闭
+        let set_log_level = *library.get::<SetLogLevel>(b"vosk_set_log_level\0")?;
+        set_log_level(-1);
+        Ok(Self {
+            model_new,
+            model_free,
+            recognizer_new,
+            recognizer_free,
+            set_words,
+            accept_waveform,
+            result,
+            partial_result,
+            final_result,
+            _library: library,
+        })
+    }
+}
+
+/// 加载 libvosk：先找数据目录的 models 区域，再找系统库路径
+fn load_api() -> Result<Api, String> {
+    let name = libloading::library_filename("vosk");
+    let mut candidates = Vec::new();
+    if let Some(dirs) = crate::storage::data_dirs() {
+        candidates.push(dirs.path(StorageArea::Models).join(&name));
+    }
+    candidates.push(PathBuf::from(&name));
+
+    let mut last_error = String::new();
+    for candidate in candidates {
+        // SAFETY: 加载的是 libvosk，初始化代码没有额外要求
+        match unsafe { libloading::Library::new(&candidate) } {
+            // SAFETY: 同上
+            Ok(library) => return unsafe { Api::from_library(library) }.map_err(|e| format!("libvosk 版本不兼容: {}", e)),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(format!(
+        "未找到 Vosk 库 {}，请放入数据目录的 models 区域或安装到系统库路径 ({})",
+        name.to_string_lossy(), last_error
+    ))
+}
+
+fn api() -> Result<&'static Api, ASRError> {
+    static API: OnceLock<Result<Api, String>> = OnceLock::new();
+    API.get_or_init(load_api).as_ref().map_err(|e| ASRError::ConfigError(e.clone()))
+}
+
+/// 已加载的 Vosk 模型
+struct Model {
+    api: &'static Api,
+    handle: *mut c_void,
+}
+
+// SAFETY: Vosk 模型加载后只读，可以在线程间共享
+unsafe impl Send for Model {}
+unsafe impl Sync for Model {}
+
+impl Model {
+    /// 加载模型目录 (按路径缓存)
+    fn load(dir: &Path) -> Result<Arc<Model>, ASRError> {
+        static MODELS: OnceLock<Mutex<HashMap<PathBuf, Arc<Model>>>> = OnceLock::new();
+        let mut models = MODELS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(model) = models.get(dir) {
+            return Ok(Arc::clone(model));
+        }
+        let api = api()?;
+        let path = CString::new(dir.to_string_lossy().as_bytes())
+            .map_err(|_| ASRError::ConfigError(format!("无效的模型路径: {}", dir.display())))?;
+        // SAFETY: path 是以 NUL 结尾的字符串，失败时返回空指针
+        let handle = unsafe { (api.model_new)(path.as_ptr()) };
+        if handle.is_null() {
+            return Err(ASRError::ConfigError(format!("加载 Vosk 模型失败: {}", dir.display())));
+        }
+        let model = Arc::new(Model { api, handle });
+        models.insert(dir.to_path_buf(), Arc::clone(&model));
+        Ok(model)
+    }
+}
+
+impl Drop for Model {
+    fn drop(&mut self) {
+        // SAFETY: handle 由 vosk_model_new 创建，识别器持有模型的引用，此时已全部释放
+        unsafe { (self.api.model_free)(self.handle) }
+    }
+}
+
+/// Vosk 识别器 (16kHz)
+struct Recognizer {
+    handle: *mut c_void,
+    model: Arc<Model>,
+}
+
+// SAFETY: 识别器不能并发使用，但可以在线程间移动 (通过 &mut 访问)
+unsafe impl Send for Recognizer {}
+
+impl Recognizer {
+    fn new(model: Arc<Model>) -> Result<Self, ASRError> {
+        // SAFETY: 模型句柄有效
+        let handle = unsafe { (model.api.recognizer_new)(model.handle, TARGET_SAMPLE_RATE as c_float) };
+        if handle.is_null() {
+            return Err(ASRError::InternalError("创建 Vosk 识别器失败".to_string()));
+        }
+        // 结果中附带各词的时间
+        // SAFETY: 识别器句柄刚创建，有效
+        unsafe { (model.api.set_words)(handle, 1) };
+        Ok(Self { handle, model })
+    }
+
+    /// 送入样本，返回是否检测到停顿 (一句话结束，可以取出 result)
+    fn accept(&mut self, samples: &[i16]) -> Result<bool, ASRError> {
+        // SAFETY: 指针和长度来自同一切片
+        match unsafe { (self.model.api.accept_waveform)(self.handle, samples.as_ptr(), samples.len() as c_int) } {
+            1 => Ok(true),
+            0 => Ok(false),
+            _ => Err(ASRError::InternalError("Vosk 识别失败".to_string())),
+        }
+    }
+
+    fn json(&self, get: GetResult) -> String {
+        // SAFETY: 返回的字符串归识别器所有，在下一次调用前有效，这里立即复制
+        unsafe {
+            let ptr = get(self.handle);
+            if ptr.is_null() {
+                return String::new();
+            }
+            CStr::from_ptr(ptr).to_string_lossy().into_owned()
+        }
+    }
+
+    /// 已结束的句子
+    fn result(&mut self) -> Sentence {
+        parse_sentence(&self.json(self.model.api.result))
+    }
+
+    /// 当前句子的部分结果
+    fn partial(&mut self) -> String {
+        result_text(&self.json(self.model.api.partial_result), "partial")
+    }
+
+    /// 结束识别，取出剩余的句子
+    fn final_result(&mut self) -> Sentence {
+        parse_sentence(&self.json(self.model.api.final_result))
+    }
+}
+
+impl Drop for Recognizer {
+    fn drop(&mut self) {
+        // SAFETY: handle 由 vosk_recognizer_new 创建
+        unsafe { (self.model.api.recognizer_free)(self.handle) }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_words() {
+        assert_eq!(join_words("今天 下午 三点 开会"), "今天下午三点开会");
+        assert_eq!(join_words(" schedule  a meeting "), "schedule a meeting");
+        assert_eq!(join_words("明天 review 一下 pull request"), "明天review一下pull request");
+        assert_eq!(join_words(""), "");
+    }
+
+    #[test]
+    fn test_transcript() {
+        let mut transcript = Transcript::default();
+        assert!(transcript.set_partial("今天 下午"));
+        assert!(!transcript.set_partial("今天 下午"));
+        assert_eq!(transcript.text(), "今天下午");
+
+        // 句子结束后部分结果从下一句开始
+        assert!(transcript.finish("今天 下午 开会"));
+        assert!(transcript.set_partial("记得 带 电脑"));
+        assert_eq!(transcript.text(), "今天下午开会记得带电脑");
+        assert!(!transcript.finish("记得 带 电脑"));
+        assert!(!transcript.finish(""));
+        assert!(transcript.set_partial("bring the laptop"));
+        assert_eq!(transcript.text(), "今天下午开会记得带电脑bring the laptop");
+
+        let mut english = Transcript::default();
+        english.finish("hello there");
+        english.finish("how are you");
+        assert_eq!(english.text(), "hello there how are you");
+    }
+
+    #[test]
+    fn test_result_text() {
+        assert_eq!(result_text(r#"{"text" : "今天 下午"}"#, "text"), "今天 下午");
+        assert_eq!(result_text(r#"{"partial" : ""}"#, "partial"), "");
+        assert_eq!(result_text("not json", "text"), "");
+
+        let sentence = parse_sentence(r#"{"result": [{"conf": 1.0, "end": 0.84, "start": 0.27, "word": "今天"}, {"conf": 0.9, "end": 1.5, "start": 0.84, "word": "开会"}], "text": "今天 开会"}"#);
+        assert_eq!(sentence.text, "今天 开会");
+        assert_eq!(sentence.words[1], WordTiming { text: "开会".to_string(), start_ms: 840, end_ms: 1500 });
+        assert!(parse_sentence(r#"{"text": ""}"#).words.is_empty());
+    }
+
+    #[test]
+    fn test_model_dir() {
+        let dir = std::env::temp_dir().join(format!("vosk-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = ASRProviderConfig::vosk(crate::voice::config::ASRMode::Realtime, dir.to_string_lossy().into_owned());
+        assert_eq!(model_dir(&config).unwrap(), dir);
+
+        config.model_path = Some(dir.join("missing").to_string_lossy().into_owned());
+        assert!(matches!(model_dir(&config), Err(ASRError::ConfigError(_))));
+        config.model_path = None;
+        assert!(model_dir(&config).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_samples() {
+        assert_eq!(pcm_samples(&[0x01, 0x00, 0xFF, 0xFF, 0x7F]), vec![1, -1]);
+        let stereo = AudioData::new([0.5, 0.5].repeat(32000), 32000, 2);
+        let samples = prepare_samples(&stereo);
+        assert_eq!(samples.len(), 16000);
+        assert!(samples.iter().all(|&s| (s - i16::MAX / 2).abs() <= 1));
+    }
+}
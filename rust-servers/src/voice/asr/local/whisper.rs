@@ -0,0 +1,217 @@
+// Whisper 本地识别实现
+// 使用 whisper.cpp (whisper-rs) 离线转写整段录音，模型在首次使用时加载并缓存；
+// 推理需要编译时启用 local-whisper 特性
+
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::storage::StorageArea;
+use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession};
+use crate::voice::audio::recorder::{resample, to_mono};
+use crate::voice::audio::{AudioData, TARGET_SAMPLE_RATE};
+use crate::voice::config::ASRProviderConfig;
+use crate::workers::WorkKind;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+pub struct WhisperEngine {
+    model: PathBuf,
+}
+
+impl WhisperEngine {
+    /// 按配置查找模型文件并创建引擎
+    pub fn from_config(config: &ASRProviderConfig) -> Result<Self, ASRError> {
+        if !cfg!(feature = "local-whisper") {
+            return Err(ASRError::ConfigError(
+                "此版本未包含本地 Whisper，需要启用 local-whisper 特性重新编译".to_string(),
+            ));
+        }
+        let models_dir = crate::storage::data_dirs().map(|dirs| dirs.path(StorageArea::Models));
+        Ok(Self { model: model_file(config, models_dir.as_deref())? })
+    }
+}
+
+/// 模型文件：model_path 优先，否则为 models 区域中 model_size 对应的文件
+fn model_file(config: &ASRProviderConfig, models_dir: Option<&Path>) -> Result<PathBuf, ASRError> {
+    let path = match (&config.model_path, config.model_size) {
+        (Some(path), _) if !path.is_empty() => PathBuf::from(path),
+        (_, Some(size)) => models_dir
+            .ok_or_else(|| ASRError::ConfigError("未配置数据目录，无法按 model_size 查找模型".to_string()))?
+            .join(size.file_name()),
+        _ => return Err(ASRError::ConfigError("whisper 需要 model_path 或 model_size".to_string())),
+    };
+    if !path.is_file() {
+        return Err(ASRError::ConfigError(format!("Whisper 模型文件不存在: {}", path.display())));
+    }
+    Ok(path)
+}
+
+/// 转为 Whisper 要求的 16kHz 单声道
+fn prepare_samples(audio: &AudioData) -> Vec<f32> {
+    let mono = to_mono(&audio.samples, audio.channels);
+    resample(&mono, audio.sample_rate, TARGET_SAMPLE_RATE)
+}
+
+#[async_trait]
+impl ASREngine for WhisperEngine {
+    fn name(&self) -> &str {
+        "whisper"
+    }
+
+    fn supported_modes(&self) -> Vec<ASRMode> {
+        vec![ASRMode::Http]
+    }
+
+    async fn transcribe(&self, audio: &AudioData) -> Result<String, ASRError> {
+        self.transcribe_with_prompt(audio, None).await
+    }
+
+    async fn transcribe_with_prompt(&self, audio: &AudioData, prompt: Option<&str>) -> Result<String, ASRError> {
+        if audio.is_empty() {
+            return Err(ASRError::InvalidAudio("音频数据为空".to_string()));
+        }
+
+        let start_time = Instant::now();
+        let model = self.model.clone();
+        let samples = prepare_samples(audio);
+        let prompt = prompt.map(str::to_string);
+        let text = crate::workers::spawn_blocking(WorkKind::LocalAsr, move || inference::run(&model, &samples, prompt.as_deref()))
+            .await
+            .map_err(|e| ASRError::InternalError(format!("Whisper 推理任务失败: {}", e)))??;
+
+        log_info!("Whisper 本地转录完成，耗时 {}ms: {}", start_time.elapsed().as_millis(), text);
+        Ok(text)
+    }
+
+    async fn create_realtime_session(&self) -> Result<Box<dyn RealtimeSession>, ASRError> {
+        Err(ASRError::UnsupportedOperation(
+            "Whisper 不支持 Realtime 模式，仅支持 HTTP 模式".to_string()
+        ))
+    }
+}
+
+/// whisper.cpp 推理
+#[cfg(feature = "local-whisper")]
+mod inference {
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex, OnceLock};
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    use crate::voice::asr::ASRError;
+
+    /// 推理线程数上限
+    const MAX_THREADS: usize = 8;
+
+    /// 已加载的模型 (按路径缓存，加载大模型需要数秒)
+    fn models() -> &'static Mutex<HashMap<PathBuf, Arc<WhisperContext>>> {
+        static MODELS: OnceLock<Mutex<HashMap<PathBuf, Arc<WhisperContext>>>> = OnceLock::new();
+        MODELS.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    fn load(path: &Path) -> Result<Arc<WhisperContext>, ASRError> {
+        let mut models = models().lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(context) = models.get(path) {
+            return Ok(Arc::clone(context));
+        }
+        // whisper.cpp 默认把日志写到 stderr，改为丢弃
+        whisper_rs::install_logging_hooks();
+        let path_str = path.to_str()
+            .ok_or_else(|| ASRError::ConfigError(format!("模型路径不是有效的 UTF-8: {}", path.display())))?;
+        let context = WhisperContext::new_with_params(path_str, WhisperContextParameters::default())
+            .map_err(|e| ASRError::ConfigError(format!("加载 Whisper 模型失败 ({}): {}", path.display(), e)))?;
+        let context = Arc::new(context);
+        models.insert(path.to_path_buf(), Arc::clone(&context));
+        Ok(context)
+    }
+
+    /// 转写 16kHz 单声道音频 (自动检测语言)，`prompt` 作为解码的初始上下文
+    pub fn run(model: &Path, samples: &[f32], prompt: Option<&str>) -> Result<String, ASRError> {
+        let context = load(model)?;
+        let mut state = context.create_state()
+            .map_err(|e| ASRError::InternalError(format!("创建 Whisper 状态失败: {}", e)))?;
+
+        let threads = crate::workers::pool().limits().threads.min(MAX_THREADS);
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some("auto"));
+        params.set_n_threads(threads as i32);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        if let Some(prompt) = prompt {
+            params.set_initial_prompt(prompt);
+        }
+
+        state.full(params, samples)
+            .map_err(|e| ASRError::InternalError(format!("Whisper 推理失败: {}", e)))?;
+        let segments = state.full_n_segments()
+            .map_err(|e| ASRError::InternalError(format!("读取 Whisper 结果失败: {}", e)))?;
+        let mut text = String::new();
+        for segment in 0..segments {
+            let segment_text = state.full_get_segment_text_lossy(segment)
+                .map_err(|e| ASRError::InternalError(format!("读取 Whisper 结果失败: {}", e)))?;
+            text.push_str(&segment_text);
+        }
+        Ok(text.trim().to_string())
+    }
+}
+
+/// 未启用 local-whisper 时不会创建引擎 (from_config 返回错误)
+#[cfg(not(feature = "local-whisper"))]
+mod inference {
+    use std::path::Path;
+
+    use crate::voice::asr::ASRError;
+
+    pub fn run(_model: &Path, _samples: &[f32], _prompt: Option<&str>) -> Result<String, ASRError> {
+        Err(ASRError::UnsupportedOperation("此版本未包含本地 Whisper".to_string()))
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::config::WhisperModelSize;
+
+    #[test]
+    fn test_model_file() {
+        let dir = std::env::temp_dir().join(format!("whisper-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let base = dir.join("ggml-base.bin");
+        std::fs::write(&base, b"model").unwrap();
+
+        let by_size = ASRProviderConfig::whisper(None, Some(WhisperModelSize::Base));
+        assert_eq!(model_file(&by_size, Some(&dir)).unwrap(), base);
+        // 没有数据目录或模型文件不存在
+        assert!(matches!(model_file(&by_size, None), Err(ASRError::ConfigError(_))));
+        let missing = ASRProviderConfig::whisper(None, Some(WhisperModelSize::Large));
+        assert!(model_file(&missing, Some(&dir)).is_err());
+
+        // model_path 优先
+        let custom = dir.join("custom.bin");
+        std::fs::write(&custom, b"model").unwrap();
+        let by_path = ASRProviderConfig::whisper(Some(custom.to_string_lossy().into_owned()), Some(WhisperModelSize::Large));
+        assert_eq!(model_file(&by_path, Some(&dir)).unwrap(), custom);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_prepare_samples() {
+        let stereo = AudioData::new([0.5, -0.5, 0.25, 0.25].repeat(24000), 48000, 2);
+        let samples = prepare_samples(&stereo);
+        assert_eq!(samples.len(), 16000);
+        assert!(samples.iter().all(|s| s.abs() <= 0.25 + f32::EPSILON));
+    }
+}
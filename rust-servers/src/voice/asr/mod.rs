@@ -5,7 +5,10 @@ use async_trait::async_trait;
 use crate::voice::audio::AudioData;
 use crate::voice::config::{ASRProviderConfig, ASRProvider, ASRMode as ConfigASRMode};
 
+pub mod capabilities;
 pub mod http;
+pub mod latency;
+pub mod local;
 pub mod realtime;
 pub mod realtime_task;
 pub mod fallback;
@@ -13,9 +16,11 @@ pub mod fallback;
 pub use http::QwenHttpEngine;
 pub use http::DoubaoHttpEngine;
 pub use http::SenseVoiceHttpEngine;
+pub use http::OpenAiHttpEngine;
+pub use local::{VoskEngine, WhisperEngine};
 pub use realtime::QwenRealtimeEngine;
 pub use realtime::DoubaoRealtimeEngine;
-pub use realtime_task::{RealtimeTranscriptionTask, PartialResultCallback, RealtimeTaskResult};
+pub use realtime_task::{RealtimeTranscriptionTask, PartialResultCallback, RealtimeTaskResult, EngineSwitch, EngineSwitchCallback, SwitchReason, LatencyWarning, LatencyWarningCallback};
 pub use fallback::{FallbackStrategy, ParallelFallbackStrategy, RaceStrategy};
 
 // ============================================================================
@@ -106,6 +111,9 @@ pub struct TranscriptionResult {
     pub engine: String,
     pub used_fallback: bool,
     pub duration_ms: u64,
+    /// 词级时间戳 (引擎提供时间信息时)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub words: Option<Vec<WordTiming>>,
 }
 
 impl TranscriptionResult {
@@ -115,8 +123,46 @@ impl TranscriptionResult {
             engine,
             used_fallback,
             duration_ms,
+            words: None,
         }
     }
+
+    pub fn with_words(mut self, words: Option<Vec<WordTiming>>) -> Self {
+        self.words = words;
+        self
+    }
+}
+
+/// 一个词 (中文为一个字或词) 在音频中的时间范围，相对音频开头
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct WordTiming {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+impl WordTiming {
+    /// 以秒为单位的时间 (OpenAI、Vosk 的格式)
+    pub fn from_secs(text: &str, start: f64, end: f64) -> Self {
+        Self {
+            text: text.trim().to_string(),
+            start_ms: (start.max(0.0) * 1000.0).round() as u64,
+            end_ms: (end.max(0.0) * 1000.0).round() as u64,
+        }
+    }
+}
+
+/// 引擎的转写文本和可选的词级时间戳
+#[derive(Debug, Clone, Default)]
+pub struct Transcription {
+    pub text: String,
+    pub words: Option<Vec<WordTiming>>,
+}
+
+impl From<String> for Transcription {
+    fn from(text: String) -> Self {
+        Self { text, words: None }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -145,13 +191,44 @@ pub trait ASREngine: Send + Sync {
     }
     
     async fn transcribe(&self, audio: &AudioData) -> Result<String, ASRError>;
+
+    /// 带上下文提示转写 (如分段录音中上一段的末尾文本)，提示用于改善人名和术语的前后一致；
+    /// 不支持提示的引擎忽略它
+    async fn transcribe_with_prompt(&self, audio: &AudioData, prompt: Option<&str>) -> Result<String, ASRError> {
+        let _ = prompt;
+        self.transcribe(audio).await
+    }
+
+    /// 转写并返回词级时间戳；不提供时间信息的引擎 words 为 None。
+    /// 部分引擎需要更详细的响应格式，只在需要时间戳时调用
+    async fn transcribe_timed(&self, audio: &AudioData, prompt: Option<&str>) -> Result<Transcription, ASRError> {
+        self.transcribe_with_prompt(audio, prompt).await.map(Transcription::from)
+    }
+
     async fn create_realtime_session(&self) -> Result<Box<dyn RealtimeSession>, ASRError>;
 }
 
+/// 转写一次；`word_timestamps` 为 true 时请求词级时间戳
+pub async fn run_transcription(
+    engine: &dyn ASREngine,
+    audio: &AudioData,
+    prompt: Option<&str>,
+    word_timestamps: bool,
+) -> Result<Transcription, ASRError> {
+    if word_timestamps {
+        engine.transcribe_timed(audio, prompt).await
+    } else {
+        engine.transcribe_with_prompt(audio, prompt).await.map(Transcription::from)
+    }
+}
+
 // ============================================================================
 // 实时会话 Trait
 // ============================================================================
 
+/// 保活帧的时长 (毫秒，16kHz 16 位单声道静音)
+pub const KEEP_ALIVE_SILENCE_MS: usize = 100;
+
 #[async_trait]
 pub trait RealtimeSession: Send {
     async fn send_chunk(&mut self, chunk: &[u8]) -> Result<(), ASRError>;
@@ -161,6 +238,22 @@ pub trait RealtimeSession: Send {
     }
     
     async fn close(&mut self) -> Result<String, ASRError>;
+
+    /// `close` 之后的词级时间戳 (相对会话开始)，不提供时间信息的会话返回 None
+    fn words(&self) -> Option<Vec<WordTiming>> {
+        None
+    }
+
+    /// 停顿期间 (静音块不发送) 多久没有音频时发送保活帧；服务端不会因收不到音频而断开的会话返回 None
+    fn keep_alive_interval(&self) -> Option<std::time::Duration> {
+        None
+    }
+
+    /// 发送保活帧，默认为一小段静音
+    async fn keep_alive(&mut self) -> Result<(), ASRError> {
+        self.send_chunk(&[0u8; KEEP_ALIVE_SILENCE_MS * 16 * 2]).await
+    }
+
     fn set_partial_callback(&mut self, callback: Box<dyn Fn(&str) + Send + 'static>);
 }
 
@@ -194,6 +287,9 @@ pub enum EngineType {
     Qwen,
     Doubao,
     SenseVoice,
+    Whisper,
+    OpenAi,
+    Vosk,
 }
 
 impl From<ASRProvider> for EngineType {
@@ -202,6 +298,9 @@ impl From<ASRProvider> for EngineType {
             ASRProvider::Qwen => EngineType::Qwen,
             ASRProvider::Doubao => EngineType::Doubao,
             ASRProvider::SenseVoice => EngineType::SenseVoice,
+            ASRProvider::Whisper => EngineType::Whisper,
+            ASRProvider::OpenAi => EngineType::OpenAi,
+            ASRProvider::Vosk => EngineType::Vosk,
         }
     }
 }
@@ -212,6 +311,9 @@ impl std::fmt::Display for EngineType {
             EngineType::Qwen => write!(f, "qwen"),
             EngineType::Doubao => write!(f, "doubao"),
             EngineType::SenseVoice => write!(f, "sensevoice"),
+            EngineType::Whisper => write!(f, "whisper"),
+            EngineType::OpenAi => write!(f, "openai"),
+            EngineType::Vosk => write!(f, "vosk"),
         }
     }
 }
@@ -243,6 +345,12 @@ impl EngineCredentials {
 /// 创建 ASR 引擎
 pub fn create_engine(config: &ASRProviderConfig) -> Result<Box<dyn ASREngine>, ASRError> {
     config.validate().map_err(|e| ASRError::ConfigError(e.to_string()))?;
+
+    // 测试中 `mock:<名称>` API Key 选用注册的模拟引擎
+    #[cfg(any(test, feature = "test-support"))]
+    if let Some(engine) = crate::testing::asr::engine_for(config) {
+        return engine;
+    }
     
     let engine_type = EngineType::from(config.provider.clone());
     let mode = ASRMode::from(config.mode.clone());
@@ -253,7 +361,7 @@ pub fn create_engine(config: &ASRProviderConfig) -> Result<Box<dyn ASREngine>, A
                 .ok_or_else(|| ASRError::ConfigError("缺少 dashscope_api_key".to_string()))?;
             
             match mode {
-                ASRMode::Http => Ok(Box::new(QwenHttpEngine::new(api_key))),
+                ASRMode::Http => Ok(Box::new(QwenHttpEngine::new(api_key).with_upload_format(config.upload_format))),
                 ASRMode::Realtime => Ok(Box::new(QwenRealtimeEngine::new(api_key))),
             }
         }
@@ -264,18 +372,43 @@ pub fn create_engine(config: &ASRProviderConfig) -> Result<Box<dyn ASREngine>, A
                 .ok_or_else(|| ASRError::ConfigError("缺少 access_token".to_string()))?;
             
             match mode {
-                ASRMode::Http => Ok(Box::new(DoubaoHttpEngine::new(app_id, access_token))),
+                ASRMode::Http => Ok(Box::new(DoubaoHttpEngine::new(app_id, access_token).with_upload_format(config.upload_format))),
                 ASRMode::Realtime => Ok(Box::new(DoubaoRealtimeEngine::new(app_id, access_token))),
             }
         }
         EngineType::SenseVoice => {
             let api_key = config.siliconflow_api_key.clone()
                 .ok_or_else(|| ASRError::ConfigError("缺少 siliconflow_api_key".to_string()))?;
-            Ok(Box::new(SenseVoiceHttpEngine::new(api_key)))
+            Ok(Box::new(SenseVoiceHttpEngine::new(api_key).with_upload_format(config.upload_format)))
+        }
+        EngineType::Whisper => Ok(Box::new(WhisperEngine::from_config(config)?)),
+        EngineType::OpenAi => {
+            let base_url = config.base_url.as_deref().unwrap_or(http::openai::DEFAULT_BASE_URL);
+            let engine = OpenAiHttpEngine::new(base_url, config.api_key.clone())
+                .with_upload_format(config.upload_format);
+            Ok(Box::new(match &config.model {
+                Some(model) => engine.with_model(model.clone()),
+                None => engine,
+            }))
         }
+        EngineType::Vosk => Ok(Box::new(VoskEngine::from_config(config)?)),
     }
 }
 
+/// 供应商和模式对应的服务地址 (用于连通性诊断；SenseVoice 只有 HTTP 模式，本地引擎没有服务地址)
+pub fn endpoint_url(config: &ASRProviderConfig) -> Option<String> {
+    let url = match (&config.provider, &config.mode) {
+        (ASRProvider::Qwen, ConfigASRMode::Http) => http::qwen::QWEN_API_URL,
+        (ASRProvider::Qwen, ConfigASRMode::Realtime) => realtime::qwen::WEBSOCKET_URL,
+        (ASRProvider::Doubao, ConfigASRMode::Http) => http::doubao::DOUBAO_API_URL,
+        (ASRProvider::Doubao, ConfigASRMode::Realtime) => realtime::doubao::WEBSOCKET_URL,
+        (ASRProvider::SenseVoice, _) => http::sensevoice::SILICONFLOW_API_URL,
+        (ASRProvider::Whisper | ASRProvider::Vosk, _) => return None,
+        (ASRProvider::OpenAi, _) => config.base_url.as_deref().unwrap_or(http::openai::DEFAULT_BASE_URL),
+    };
+    Some(url.to_string())
+}
+
 /// 根据引擎类型创建引擎
 pub fn create_engine_by_type(
     engine_type: EngineType,
@@ -308,5 +441,9 @@ pub fn create_engine_by_type(
                 .ok_or_else(|| ASRError::ConfigError("缺少 API Key".to_string()))?;
             Ok(Box::new(SenseVoiceHttpEngine::new(api_key)))
         }
+        EngineType::OpenAi => Ok(Box::new(OpenAiHttpEngine::new(http::openai::DEFAULT_BASE_URL, credentials.api_key))),
+        EngineType::Whisper | EngineType::Vosk => Err(ASRError::ConfigError(
+            format!("{} 需要模型配置，请使用 create_engine", engine_type)
+        )),
     }
 }
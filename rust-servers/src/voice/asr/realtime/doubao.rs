@@ -11,19 +11,49 @@ use std::time::Duration;
 use tokio::sync::{Mutex, mpsc, oneshot};
 use tokio::net::TcpStream;
 use tokio_tungstenite::{
-    connect_async, 
     tungstenite::{Message, http},
     MaybeTlsStream, 
     WebSocketStream
 };
 
-use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession, RetryConfig};
+use crate::voice::asr::http::doubao::utterance_words;
+use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession, RetryConfig, Transcription, WordTiming};
 use crate::voice::audio::AudioData;
 
-const WEBSOCKET_URL: &str = "wss://openspeech.bytedance.com/api/v3/sauc/bigmodel_nostream";
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Error, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "", format!($($arg)*));
+        }
+    };
+}
+
+pub const WEBSOCKET_URL: &str = "wss://openspeech.bytedance.com/api/v3/sauc/bigmodel_nostream";
 const RESOURCE_ID: &str = "volc.seedasr.sauc.duration";
 const TRANSCRIPTION_TIMEOUT_SECS: u64 = 10;
 
+/// 停顿期间的保活间隔 (服务端收不到音频包一段时间后会结束会话)
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
 type WsSink = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
 
 pub struct DoubaoRealtimeEngine {
@@ -76,8 +106,10 @@ enum SessionCommand {
 
 pub struct DoubaoRealtimeSession {
     cmd_sender: mpsc::Sender<SessionCommand>,
-    result_receiver: Option<oneshot::Receiver<Result<String, ASRError>>>,
+    result_receiver: Option<oneshot::Receiver<Result<Transcription, ASRError>>>,
     partial_callback: Option<Arc<Mutex<Box<dyn Fn(&str) + Send + 'static>>>>,
+    /// 最终结果的词级时间戳
+    words: Option<Vec<WordTiming>>,
 }
 
 impl DoubaoRealtimeSession {
@@ -85,7 +117,7 @@ impl DoubaoRealtimeSession {
         let websocket_key = generate_websocket_key();
         let request_id = generate_request_id();
         
-        eprintln!("[INFO] 创建豆包 Realtime WebSocket 连接");
+        log_info!("创建豆包 Realtime WebSocket 连接");
         
         let request = http::Request::builder()
             .uri(WEBSOCKET_URL)
@@ -101,20 +133,20 @@ impl DoubaoRealtimeSession {
             .body(())
             .map_err(|e| ASRError::WebSocketError(format!("构建请求失败: {}", e)))?;
         
-        let (ws_stream, _) = connect_async(request).await
+        let (ws_stream, _) = crate::network::connect_websocket(crate::router::ModuleType::Voice, request).await
             .map_err(|e| ASRError::WebSocketError(format!("WebSocket 连接失败: {}", e)))?;
         
-        eprintln!("[INFO] 豆包 Realtime WebSocket 连接成功");
+        log_info!("豆包 Realtime WebSocket 连接成功");
         
         let (mut write, mut read) = ws_stream.split();
         
         let config = serde_json::json!({
             "user": {"uid": &app_id},
             "audio": {"format": "pcm", "rate": 16000, "bits": 16, "channel": 1},
-            "request": {"model_name": "bigmodel", "enable_itn": true, "enable_punc": true}
+            "request": {"model_name": "bigmodel", "enable_itn": true, "enable_punc": true, "show_utterances": true}
         });
         
-        eprintln!("[DEBUG] 豆包 Full Client Request: {}", serde_json::to_string_pretty(&config).unwrap_or_default());
+        log_debug!("豆包 Full Client Request: {}", serde_json::to_string_pretty(&config).unwrap_or_default());
         
         let msg = build_message(0x1, 0x1, 1, &serde_json::to_vec(&config)
             .map_err(|e| ASRError::InternalError(format!("序列化配置失败: {}", e)))?, 0x1)?;
@@ -122,25 +154,25 @@ impl DoubaoRealtimeSession {
         write.send(Message::Binary(msg.clone().into())).await
             .map_err(|e| ASRError::WebSocketError(format!("发送 Full Client Request 失败: {}", e)))?;
         
-        eprintln!("[DEBUG] 豆包 Full Client Request 已发送: {} bytes", msg.len());
+        log_debug!("豆包 Full Client Request 已发送: {} bytes", msg.len());
         
         if let Some(response) = read.next().await {
             match response {
                 Ok(Message::Binary(data)) => {
-                    eprintln!("[DEBUG] 豆包 Full Client Request 响应: {} bytes", data.len());
+                    log_debug!("豆包 Full Client Request 响应: {} bytes", data.len());
                     match parse_response(&data) {
-                        Ok((text, _is_last)) => {
-                            if !text.is_empty() {
-                                eprintln!("[DEBUG] 豆包初始响应包含文本（意外）: {}", text);
+                        Ok((transcription, _is_last)) => {
+                            if !transcription.text.is_empty() {
+                                log_debug!("豆包初始响应包含文本（意外）: {}", transcription.text);
                             }
                         }
                         Err(e) => {
-                            eprintln!("[DEBUG] 豆包初始响应（预期无文本）: {}", e);
+                            log_debug!("豆包初始响应（预期无文本）: {}", e);
                         }
                     }
                 }
                 Ok(other) => {
-                    eprintln!("[WARN] 豆包 Full Client Request 收到非二进制响应: {:?}", other);
+                    log_warn!("豆包 Full Client Request 收到非二进制响应: {:?}", other);
                 }
                 Err(e) => {
                     return Err(ASRError::WebSocketError(format!(
@@ -151,7 +183,7 @@ impl DoubaoRealtimeSession {
         }
         
         let (cmd_tx, mut cmd_rx) = mpsc::channel::<SessionCommand>(100);
-        let (result_tx, result_rx) = oneshot::channel::<Result<String, ASRError>>();
+        let (result_tx, result_rx) = oneshot::channel::<Result<Transcription, ASRError>>();
         let (partial_tx, mut partial_rx) = mpsc::channel::<String>(100);
         
         let write: Arc<Mutex<WsSink>> = Arc::new(Mutex::new(write));
@@ -168,29 +200,29 @@ impl DoubaoRealtimeSession {
                             Ok(msg) => {
                                 let mut w = write_clone.lock().await;
                                 if let Err(e) = w.send(Message::Binary(msg.into())).await {
-                                    eprintln!("[ERROR] 豆包发送音频块失败: {}", e);
+                                    log_error!("豆包发送音频块失败: {}", e);
                                     break;
                                 }
                             }
                             Err(e) => {
-                                eprintln!("[ERROR] 豆包构建音频消息失败: {}", e);
+                                log_error!("豆包构建音频消息失败: {}", e);
                             }
                         }
                     }
                     SessionCommand::Finish => {
                         sequence += 1;
                         let last_seq = -sequence;
-                        eprintln!("[DEBUG] 豆包发送结束标志，sequence={}", last_seq);
+                        log_debug!("豆包发送结束标志，sequence={}", last_seq);
                         
                         match build_message(0x2, 0x3, last_seq, &[], 0x0) {
                             Ok(msg) => {
                                 let mut w = write_clone.lock().await;
                                 if let Err(e) = w.send(Message::Binary(msg.into())).await {
-                                    eprintln!("[ERROR] 豆包发送结束标志失败: {}", e);
+                                    log_error!("豆包发送结束标志失败: {}", e);
                                 }
                             }
                             Err(e) => {
-                                eprintln!("[ERROR] 豆包构建结束消息失败: {}", e);
+                                log_error!("豆包构建结束消息失败: {}", e);
                             }
                         }
                         break;
@@ -202,42 +234,46 @@ impl DoubaoRealtimeSession {
         let partial_tx_clone = partial_tx.clone();
         tokio::spawn(async move {
             let mut accumulated_text = String::new();
+            let mut accumulated_words = None;
             let mut result_tx = Some(result_tx);
             
             while let Some(msg) = read.next().await {
                 match msg {
                     Ok(Message::Binary(data)) => {
-                        eprintln!("[DEBUG] 豆包 WebSocket 收到二进制消息: {} bytes", data.len());
+                        log_debug!("豆包 WebSocket 收到二进制消息: {} bytes", data.len());
                         match parse_response(&data) {
-                            Ok((text, is_final)) => {
+                            Ok((Transcription { text, words }, is_final)) => {
                                 if !text.is_empty() {
                                     accumulated_text = text.clone();
-                                    eprintln!("[DEBUG] 豆包累积文本: {}", accumulated_text);
+                                    if words.is_some() {
+                                        accumulated_words = words;
+                                    }
+                                    log_debug!("豆包累积文本: {}", accumulated_text);
                                     let _ = partial_tx_clone.send(accumulated_text.clone()).await;
                                 }
                                 if is_final {
                                     let final_text = accumulated_text.clone();
-                                    eprintln!("[INFO] 豆包流式转录结果（最终包）: {}", final_text);
+                                    log_info!("豆包流式转录结果（最终包）: {}", final_text);
                                     if let Some(tx) = result_tx.take() {
-                                        let _ = tx.send(Ok(final_text));
+                                        let _ = tx.send(Ok(Transcription { text: final_text, words: accumulated_words.take() }));
                                     }
                                     break;
                                 }
                             }
                             Err(e) => {
-                                eprintln!("[DEBUG] 豆包响应解析（非最终结果）: {}", e);
+                                log_debug!("豆包响应解析（非最终结果）: {}", e);
                             }
                         }
                     }
                     Ok(Message::Close(frame)) => {
-                        eprintln!("[WARN] 豆包 WebSocket 连接关闭: {:?}", frame);
+                        log_warn!("豆包 WebSocket 连接关闭: {:?}", frame);
                         if !accumulated_text.is_empty() {
-                            eprintln!("[INFO] 豆包连接关闭，返回累积文本: {}", accumulated_text);
+                            log_info!("豆包连接关闭，返回累积文本: {}", accumulated_text);
                             if let Some(tx) = result_tx.take() {
-                                let _ = tx.send(Ok(accumulated_text.clone()));
+                                let _ = tx.send(Ok(Transcription { text: accumulated_text.clone(), words: accumulated_words.take() }));
                             }
                         } else {
-                            eprintln!("[WARN] 豆包连接关闭，无转录结果");
+                            log_warn!("豆包连接关闭，无转录结果");
                             if let Some(tx) = result_tx.take() {
                                 let _ = tx.send(Err(ASRError::WebSocketError(
                                     "WebSocket 连接被关闭".to_string()
@@ -247,10 +283,10 @@ impl DoubaoRealtimeSession {
                         break;
                     }
                     Ok(other) => {
-                        eprintln!("[DEBUG] 豆包 WebSocket 收到其他消息类型: {:?}", other);
+                        log_debug!("豆包 WebSocket 收到其他消息类型: {:?}", other);
                     }
                     Err(e) => {
-                        eprintln!("[ERROR] 豆包 WebSocket 接收错误: {}", e);
+                        log_error!("豆包 WebSocket 接收错误: {}", e);
                         if let Some(tx) = result_tx.take() {
                             let _ = tx.send(Err(ASRError::WebSocketError(
                                 format!("WebSocket 错误: {}", e)
@@ -263,12 +299,12 @@ impl DoubaoRealtimeSession {
             
             if result_tx.is_some() {
                 if !accumulated_text.is_empty() {
-                    eprintln!("[INFO] 豆包连接结束，返回累积文本: {}", accumulated_text);
+                    log_info!("豆包连接结束，返回累积文本: {}", accumulated_text);
                     if let Some(tx) = result_tx.take() {
-                        let _ = tx.send(Ok(accumulated_text));
+                        let _ = tx.send(Ok(Transcription { text: accumulated_text, words: accumulated_words }));
                     }
                 } else {
-                    eprintln!("[WARN] 豆包连接结束，无转录结果");
+                    log_warn!("豆包连接结束，无转录结果");
                     if let Some(tx) = result_tx.take() {
                         let _ = tx.send(Err(ASRError::WebSocketError(
                             "WebSocket 连接结束，无转录结果".to_string()
@@ -276,7 +312,7 @@ impl DoubaoRealtimeSession {
                     }
                 }
             }
-            eprintln!("[DEBUG] 豆包 WebSocket 接收任务结束");
+            log_debug!("豆包 WebSocket 接收任务结束");
         });
         
         let partial_callback: Option<Arc<Mutex<Box<dyn Fn(&str) + Send + 'static>>>> = None;
@@ -294,6 +330,7 @@ impl DoubaoRealtimeSession {
             cmd_sender: cmd_tx,
             result_receiver: Some(result_rx),
             partial_callback,
+            words: None,
         })
     }
 }
@@ -316,9 +353,18 @@ impl RealtimeSession for DoubaoRealtimeSession {
             result_rx
         ).await
             .map_err(|_| ASRError::Timeout { timeout_ms: TRANSCRIPTION_TIMEOUT_SECS * 1000 })?
-            .map_err(|_| ASRError::InternalError("结果通道已关闭".to_string()))?;
+            .map_err(|_| ASRError::InternalError("结果通道已关闭".to_string()))??;
         
-        result
+        self.words = result.words;
+        Ok(result.text)
+    }
+    
+    fn words(&self) -> Option<Vec<WordTiming>> {
+        self.words.clone()
+    }
+
+    fn keep_alive_interval(&self) -> Option<Duration> {
+        Some(KEEP_ALIVE_INTERVAL)
     }
     
     fn set_partial_callback(&mut self, callback: Box<dyn Fn(&str) + Send + 'static>) {
@@ -376,7 +422,7 @@ fn build_message(
     Ok(msg)
 }
 
-fn parse_response(data: &[u8]) -> Result<(String, bool), ASRError> {
+fn parse_response(data: &[u8]) -> Result<(Transcription, bool), ASRError> {
     if data.len() < 4 {
         return Err(ASRError::InternalError(format!("响应太短: {} bytes", data.len())));
     }
@@ -386,8 +432,8 @@ fn parse_response(data: &[u8]) -> Result<(String, bool), ASRError> {
     let message_flags = data[1] & 0x0f;
     let compression = data[2] & 0x0f;
     
-    eprintln!(
-        "[DEBUG] 豆包响应 header: size={}, type={:#x}, flags={:#x}, compression={}",
+    log_debug!(
+        "豆包响应 header: size={}, type={:#x}, flags={:#x}, compression={}",
         header_size, message_type, message_flags, compression
     );
     
@@ -417,7 +463,7 @@ fn parse_response(data: &[u8]) -> Result<(String, bool), ASRError> {
             data[offset + 2],
             data[offset + 3],
         ]);
-        eprintln!("[DEBUG] 豆包响应 sequence: {}", sequence);
+        log_debug!("豆包响应 sequence: {}", sequence);
         offset += 4;
     }
     
@@ -452,7 +498,7 @@ fn parse_response(data: &[u8]) -> Result<(String, bool), ASRError> {
             .map_err(|e| ASRError::InternalError(format!("UTF-8 解码失败: {}", e)))?
     };
     
-    eprintln!("[DEBUG] 豆包响应 JSON: {}", json_str);
+    log_debug!("豆包响应 JSON: {}", json_str);
     
     let result: serde_json::Value = serde_json::from_str(&json_str)
         .map_err(|e| ASRError::InternalError(format!("JSON 解析失败: {}", e)))?;
@@ -461,7 +507,8 @@ fn parse_response(data: &[u8]) -> Result<(String, bool), ASRError> {
     let text = result["result"]["text"].as_str().unwrap_or("").to_string();
     
     if is_last || !text.is_empty() {
-        return Ok((text, is_last));
+        let words = utterance_words(&result["result"]);
+        return Ok((Transcription { text, words }, is_last));
     }
     
     Err(ASRError::InternalError("中间响应，等待更多数据".to_string()))
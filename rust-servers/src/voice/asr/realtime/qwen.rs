@@ -9,7 +9,6 @@ use std::time::Duration;
 use tokio::sync::{Mutex, mpsc, oneshot};
 use tokio::net::TcpStream;
 use tokio_tungstenite::{
-    connect_async, 
     tungstenite::{Message, http},
     MaybeTlsStream, 
     WebSocketStream
@@ -18,10 +17,32 @@ use tokio_tungstenite::{
 use crate::voice::asr::{ASREngine, ASRError, ASRMode, RealtimeSession, RetryConfig};
 use crate::voice::audio::AudioData;
 
-const WEBSOCKET_URL: &str = "wss://dashscope.aliyuncs.com/api-ws/v1/realtime";
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Error, "", format!($($arg)*));
+    };
+}
+
+pub const WEBSOCKET_URL: &str = "wss://dashscope.aliyuncs.com/api-ws/v1/realtime";
 const DEFAULT_MODEL: &str = "qwen3-asr-flash-realtime";
 const TRANSCRIPTION_TIMEOUT_SECS: u64 = 10;
 
+/// 停顿期间的保活间隔 (服务端长时间收不到音频时会关闭连接)
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
 type WsSink = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
 
 pub struct QwenRealtimeEngine {
@@ -89,7 +110,7 @@ pub struct QwenRealtimeSession {
 impl QwenRealtimeSession {
     async fn connect(api_key: String, model: String) -> Result<Self, ASRError> {
         let url = format!("{}?model={}", WEBSOCKET_URL, model);
-        eprintln!("[INFO] 创建 Qwen Realtime WebSocket 连接: {}", url);
+        log_info!("创建 Qwen Realtime WebSocket 连接: {}", url);
         
         let request = http::Request::builder()
             .uri(&url)
@@ -103,10 +124,10 @@ impl QwenRealtimeSession {
             .body(())
             .map_err(|e| ASRError::WebSocketError(format!("构建请求失败: {}", e)))?;
         
-        let (ws_stream, _) = connect_async(request).await
+        let (ws_stream, _) = crate::network::connect_websocket(crate::router::ModuleType::Voice, request).await
             .map_err(|e| ASRError::WebSocketError(format!("WebSocket 连接失败: {}", e)))?;
         
-        eprintln!("[INFO] Qwen Realtime WebSocket 连接成功");
+        log_info!("Qwen Realtime WebSocket 连接成功");
         
         let (mut write, mut read) = ws_stream.split();
         
@@ -127,7 +148,7 @@ impl QwenRealtimeSession {
         write.send(Message::Text(session_update.to_string().into())).await
             .map_err(|e| ASRError::WebSocketError(format!("发送 session.update 失败: {}", e)))?;
         
-        eprintln!("[INFO] 已发送 session.update 配置");
+        log_info!("已发送 session.update 配置");
         
         let (cmd_tx, mut cmd_rx) = mpsc::channel::<SessionCommand>(100);
         let (result_tx, result_rx) = oneshot::channel::<Result<String, ASRError>>();
@@ -149,7 +170,7 @@ impl QwenRealtimeSession {
                         
                         let mut w = write_clone.lock().await;
                         if let Err(e) = w.send(Message::Text(event.to_string().into())).await {
-                            eprintln!("[ERROR] 发送音频块失败: {}", e);
+                            log_error!("发送音频块失败: {}", e);
                             break;
                         }
                     }
@@ -161,9 +182,9 @@ impl QwenRealtimeSession {
                         
                         let mut w = write_clone.lock().await;
                         if let Err(e) = w.send(Message::Text(event.to_string().into())).await {
-                            eprintln!("[ERROR] 发送 commit 失败: {}", e);
+                            log_error!("发送 commit 失败: {}", e);
                         }
-                        eprintln!("[INFO] 已发送 input_audio_buffer.commit");
+                        log_info!("已发送 input_audio_buffer.commit");
                     }
                     SessionCommand::Close => {
                         let mut w = write_clone.lock().await;
@@ -189,16 +210,16 @@ impl QwenRealtimeSession {
                                 
                                 match event_type {
                                     "session.created" | "session.updated" => {
-                                        eprintln!("[INFO] 会话已创建/更新");
+                                        log_info!("会话已创建/更新");
                                     }
                                     "input_audio_buffer.committed" => {
-                                        eprintln!("[INFO] 音频缓冲区已提交");
+                                        log_info!("音频缓冲区已提交");
                                     }
                                     "conversation.item.input_audio_transcription.completed" => {
                                         if let Some(transcript) = data["transcript"].as_str() {
                                             final_text = transcript.to_string();
                                             has_result = true;
-                                            eprintln!("[INFO] 转录完成: {}", final_text);
+                                            log_info!("转录完成: {}", final_text);
                                         }
                                     }
                                     "response.audio_transcript.delta" => {
@@ -212,7 +233,7 @@ impl QwenRealtimeSession {
                                             final_text = transcript.to_string();
                                         }
                                         has_result = true;
-                                        eprintln!("[INFO] 转录完成: {}", final_text);
+                                        log_info!("转录完成: {}", final_text);
                                     }
                                     "response.done" => {
                                         has_result = true;
@@ -221,7 +242,7 @@ impl QwenRealtimeSession {
                                         let error_msg = data["error"]["message"]
                                             .as_str()
                                             .unwrap_or("未知错误");
-                                        eprintln!("[ERROR] API 错误: {}", error_msg);
+                                        log_error!("API 错误: {}", error_msg);
                                         if let Some(tx) = result_tx.take() {
                                             let _ = tx.send(Err(ASRError::WebSocketError(
                                                 format!("API 错误: {}", error_msg)
@@ -233,16 +254,16 @@ impl QwenRealtimeSession {
                                 }
                             }
                             Err(e) => {
-                                eprintln!("[WARN] 解析消息失败: {}", e);
+                                log_warn!("解析消息失败: {}", e);
                             }
                         }
                     }
                     Ok(Message::Close(_)) => {
-                        eprintln!("[INFO] WebSocket 连接关闭");
+                        log_info!("WebSocket 连接关闭");
                         break;
                     }
                     Err(e) => {
-                        eprintln!("[ERROR] WebSocket 错误: {}", e);
+                        log_error!("WebSocket 错误: {}", e);
                         if let Some(tx) = result_tx.take() {
                             let _ = tx.send(Err(ASRError::WebSocketError(
                                 format!("WebSocket 错误: {}", e)
@@ -300,6 +321,10 @@ impl RealtimeSession for QwenRealtimeSession {
         self.cmd_sender.send(SessionCommand::Commit).await
             .map_err(|_| ASRError::WebSocketError("提交音频失败：通道已关闭".to_string()))
     }
+
+    fn keep_alive_interval(&self) -> Option<Duration> {
+        Some(KEEP_ALIVE_INTERVAL)
+    }
     
     async fn close(&mut self) -> Result<String, ASRError> {
         let _ = self.cmd_sender.send(SessionCommand::Commit).await;
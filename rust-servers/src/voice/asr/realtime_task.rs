@@ -1,34 +1,40 @@
 // 实时转录任务模块
 // 协调 StreamingRecorder 和 RealtimeSession，实现边录边转录
 
-use std::sync::Arc;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Instant;
 use tokio::sync::{mpsc, Mutex, oneshot};
 
-use crate::voice::asr::{ASRError, TranscriptionResult, create_engine};
+use crate::voice::asr::latency::{self, LatencyTracker};
+use crate::voice::asr::{ASRError, RealtimeSession, TranscriptionResult, create_engine};
 use crate::voice::audio::streaming::AudioChunkData;
 use crate::voice::config::ASRProviderConfig;
+use crate::watchdog::Heartbeat;
 
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] [realtime_task] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "realtime_task", format!($($arg)*));
     };
 }
 
 macro_rules! log_debug {
     ($($arg:tt)*) => {
-        eprintln!("[DEBUG] [realtime_task] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Debug, "realtime_task", format!($($arg)*));
     };
 }
 
 macro_rules! log_warn {
     ($($arg:tt)*) => {
-        eprintln!("[WARN] [realtime_task] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Warn, "realtime_task", format!($($arg)*));
     };
 }
 
 macro_rules! log_error {
     ($($arg:tt)*) => {
-        eprintln!("[ERROR] [realtime_task] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Error, "realtime_task", format!($($arg)*));
     };
 }
 
@@ -67,12 +73,139 @@ impl RealtimeTaskResult {
 /// 部分结果回调类型
 pub type PartialResultCallback = Box<dyn Fn(&str) + Send + 'static>;
 
+/// 引擎切换回调类型
+pub type EngineSwitchCallback = Box<dyn Fn(&EngineSwitch) + Send + Sync + 'static>;
+
+/// 延迟警告回调类型
+pub type LatencyWarningCallback = Box<dyn Fn(&LatencyWarning) + Send + Sync + 'static>;
+
+/// 连续发送失败的次数上限
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// 有语音但没有有效部分结果的最长时长 (ms)，超过后判定主引擎失效
+const MAX_SPEECH_WITHOUT_TEXT_MS: u64 = 8000;
+
+/// 连续乱码部分结果的次数上限
+const MAX_GARBLED_PARTIALS: u32 = 3;
+
+/// 判定为语音的音频块 RMS (i16 幅度，约 -36 dBFS)
+const SPEECH_RMS: f64 = 500.0;
+
+/// 回放缓冲的最长时长 (ms)，录音超过后不再切换 (切换后的转写会缺少开头)
+const MAX_REPLAY_MS: u64 = 5 * 60 * 1000;
+
+/// 音频采样率 (流式录音输出 16 kHz)
+const SAMPLE_RATE: u64 = 16000;
+
+/// 切换到备用引擎的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchReason {
+    /// 主引擎无法建立会话
+    ConnectFailed,
+    /// 连续发送音频块失败
+    SendFailed,
+    /// 持续有语音但部分结果一直为空
+    EmptyPartials,
+    /// 部分结果连续为乱码
+    GarbledPartials,
+}
+
+/// 录音中途切换引擎的信息 (engine_switched 事件的内容)
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineSwitch {
+    pub from: String,
+    pub to: String,
+    pub reason: SwitchReason,
+    /// 回放给备用引擎的音频块数和时长
+    pub replayed_chunks: usize,
+    pub replayed_ms: u64,
+}
+
+/// 部分结果延迟过高 (latency_warning 事件的内容)
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyWarning {
+    pub engine: String,
+    /// 最近几次部分结果的平均延迟
+    pub latency_ms: u64,
+    pub threshold_ms: u64,
+}
+
+/// 部分结果健康度：统计最近一次有效部分结果之后的语音时长和连续乱码次数
+#[derive(Debug, Default)]
+struct PartialMonitor {
+    speech_ms_since_text: u64,
+    garbled: u32,
+}
+
+impl PartialMonitor {
+    /// 记录发送的音频块 (只累计有语音的块)
+    fn on_audio(&mut self, samples: &[i16]) {
+        if !samples.is_empty() && rms(samples) >= SPEECH_RMS {
+            self.speech_ms_since_text += samples.len() as u64 * 1000 / SAMPLE_RATE;
+        }
+    }
+
+    /// 记录收到的部分结果
+    fn on_partial(&mut self, text: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        if is_garbled(text) {
+            self.garbled += 1;
+        } else {
+            *self = Self::default();
+        }
+    }
+
+    /// 需要切换时返回原因
+    fn verdict(&self) -> Option<SwitchReason> {
+        if self.garbled >= MAX_GARBLED_PARTIALS {
+            Some(SwitchReason::GarbledPartials)
+        } else if self.speech_ms_since_text >= MAX_SPEECH_WITHOUT_TEXT_MS {
+            Some(SwitchReason::EmptyPartials)
+        } else {
+            None
+        }
+    }
+}
+
+/// 判断部分结果是否为乱码：含替换字符、文字占比过低，或较长的文本只由一两个字符重复组成
+fn is_garbled(text: &str) -> bool {
+    if text.contains('\u{FFFD}') {
+        return true;
+    }
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let letters: Vec<char> = chars.iter().copied().filter(|c| c.is_alphanumeric()).collect();
+    if chars.len() >= 6 && letters.len() * 10 < chars.len() * 3 {
+        return true;
+    }
+    if letters.len() >= 12 {
+        let distinct: HashSet<char> = letters.iter().flat_map(|c| c.to_lowercase()).collect();
+        return distinct.len() <= 2;
+    }
+    false
+}
+
+fn rms(samples: &[i16]) -> f64 {
+    let sum: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    (sum / samples.len() as f64).sqrt()
+}
+
 /// 实时转录任务
 pub struct RealtimeTranscriptionTask {
     asr_config: ASRProviderConfig,
     chunk_receiver: mpsc::Receiver<AudioChunkData>,
     partial_callback: Arc<Mutex<Option<PartialResultCallback>>>,
     stop_receiver: Option<oneshot::Receiver<()>>,
+    heartbeat: Option<Heartbeat>,
+    fallback: Option<ASRProviderConfig>,
+    switch_callback: Option<EngineSwitchCallback>,
+    latency_warning: Option<(u64, Arc<LatencyWarningCallback>)>,
+    /// 当前会话的代次，切换后丢弃旧会话迟到的部分结果
+    generation: Arc<AtomicU64>,
+    monitor: Arc<StdMutex<PartialMonitor>>,
+    latency: Arc<StdMutex<LatencyTracker>>,
 }
 
 impl RealtimeTranscriptionTask {
@@ -88,11 +221,38 @@ impl RealtimeTranscriptionTask {
             chunk_receiver,
             partial_callback: Arc::new(Mutex::new(partial_callback)),
             stop_receiver: Some(stop_rx),
+            heartbeat: None,
+            fallback: None,
+            switch_callback: None,
+            latency_warning: None,
+            generation: Arc::new(AtomicU64::new(0)),
+            monitor: Arc::new(StdMutex::new(PartialMonitor::default())),
+            latency: Arc::new(StdMutex::new(LatencyTracker::default())),
         };
         
         (task, stop_tx)
     }
     
+    /// 向看门狗上报进度 (会话建立和每个音频块发送成功时)
+    pub fn with_heartbeat(mut self, heartbeat: Heartbeat) -> Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+    
+    /// 主引擎失效时 (无法连接、连续发送失败、部分结果持续为空或乱码) 切换到备用实时引擎，
+    /// 回放已录制的音频后继续录音，切换最多一次
+    pub fn with_fallback(mut self, fallback: ASRProviderConfig, on_switch: Option<EngineSwitchCallback>) -> Self {
+        self.fallback = Some(fallback);
+        self.switch_callback = on_switch;
+        self
+    }
+    
+    /// 最近几次部分结果的平均延迟超过 `threshold_ms` 时回调 (每个引擎只回调一次，0 为不检查)
+    pub fn with_latency_warning(mut self, threshold_ms: u64, on_warning: LatencyWarningCallback) -> Self {
+        self.latency_warning = Some((threshold_ms, Arc::new(on_warning)));
+        self
+    }
+    
     pub async fn run(self) -> Result<TranscriptionResult, ASRError> {
         match self.run_with_details().await {
             RealtimeTaskResult::Success(result) => Ok(result),
@@ -108,9 +268,9 @@ impl RealtimeTranscriptionTask {
     
     pub async fn run_with_details(mut self) -> RealtimeTaskResult {
         let start_time = std::time::Instant::now();
-        let mut engine_name = String::from("unknown");
         let mut chunk_count = 0u64;
         let mut total_samples = 0u64;
+        let mut used_fallback = false;
         
         log_info!(
             "启动实时转录任务，供应商: {}, 模式: {}",
@@ -118,53 +278,60 @@ impl RealtimeTranscriptionTask {
             self.asr_config.mode
         );
         
-        let engine = match create_engine(&self.asr_config) {
-            Ok(e) => e,
-            Err(e) => {
-                log_error!("创建 ASR 引擎失败: {}", e);
-                return RealtimeTaskResult::Failed {
-                    error: e,
-                    engine_name,
-                    chunks_sent: 0,
-                    samples_sent: 0,
+        let (mut engine_name, mut session) = match self.open_session(&self.asr_config).await {
+            Ok(opened) => opened,
+            Err((engine_name, error)) => {
+                let Some(fallback) = self.fallback.take() else {
+                    return RealtimeTaskResult::Failed { error, engine_name, chunks_sent: 0, samples_sent: 0 };
                 };
+                log_warn!("主引擎 {} 无法建立会话，切换到备用引擎: {}", engine_name, error);
+                match self.open_session(&fallback).await {
+                    Ok((name, session)) => {
+                        self.notify_switch(EngineSwitch {
+                            from: engine_name,
+                            to: name.clone(),
+                            reason: SwitchReason::ConnectFailed,
+                            replayed_chunks: 0,
+                            replayed_ms: 0,
+                        });
+                        used_fallback = true;
+                        (name, session)
+                    }
+                    Err((_, fallback_error)) => {
+                        log_error!("备用引擎也无法建立会话: {}", fallback_error);
+                        return RealtimeTaskResult::Failed { error, engine_name, chunks_sent: 0, samples_sent: 0 };
+                    }
+                }
             }
         };
-        engine_name = engine.name().to_string();
         
-        log_debug!("创建 ASR 引擎: {}", engine_name);
-        
-        let mut session = match engine.create_realtime_session().await {
-            Ok(s) => s,
-            Err(e) => {
-                log_error!("创建实时会话失败 (WebSocket 连接失败): {}", e);
-                return RealtimeTaskResult::Failed {
-                    error: e,
-                    engine_name,
-                    chunks_sent: 0,
-                    samples_sent: 0,
-                };
+        log_info!("实时会话已创建");
+        // 只有等待引擎 (发送音频块、保活帧和等待最终结果) 计入停滞超时，
+        // 等待客户端的音频块或停止信号 (包括暂停录音) 属于空闲等待
+        let heartbeat = self.heartbeat.take();
+        let beat = || {
+            if let Some(ref heartbeat) = heartbeat {
+                heartbeat.beat();
+            }
+        };
+        let idle = || {
+            if let Some(ref heartbeat) = heartbeat {
+                heartbeat.idle();
             }
         };
-        
-        log_info!("实时会话已创建");
-        
-        let partial_callback = Arc::clone(&self.partial_callback);
-        session.set_partial_callback(Box::new(move |text| {
-            let text_owned = text.to_string();
-            let callback = partial_callback.clone();
-            tokio::spawn(async move {
-                if let Some(ref cb) = *callback.lock().await {
-                    cb(&text_owned);
-                }
-            });
-        }));
         
         let mut stop_rx = self.stop_receiver.take();
         let mut consecutive_send_failures = 0u32;
-        const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+        // 已发送的音频，切换引擎时回放
+        let mut replay: Vec<Vec<u8>> = Vec::new();
+        // 最近一次向会话发送数据 (音频块或保活帧) 的时间
+        let mut last_sent = Instant::now();
+        let mut keep_alives = 0u64;
         
         loop {
+            // 停顿期间静音块不发送，超过会话的保活间隔时发送保活帧，避免服务端断开
+            let keep_alive_at = session.keep_alive_interval().map(|interval| last_sent + interval);
+            idle();
             tokio::select! {
                 _ = async {
                     if let Some(ref mut rx) = stop_rx {
@@ -177,17 +344,54 @@ impl RealtimeTranscriptionTask {
                     break;
                 }
                 
+                _ = async {
+                    match keep_alive_at {
+                        Some(at) => tokio::time::sleep_until(at.into()).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    beat();
+                    match session.keep_alive().await {
+                        Ok(()) => {
+                            keep_alives += 1;
+                            beat();
+                            log_debug!("停顿期间发送保活帧 ({})", keep_alives);
+                        }
+                        Err(e) => {
+                            // 连接已断开时由下一个音频块的发送失败处理 (重试或切换备用引擎)
+                            log_warn!("发送保活帧失败: {}", e);
+                        }
+                    }
+                    last_sent = Instant::now();
+                }
+                
                 chunk = self.chunk_receiver.recv() => {
                     match chunk {
                         Some(audio_chunk) => {
+                            beat();
+                            let received = Instant::now();
                             chunk_count += 1;
                             total_samples += audio_chunk.samples.len() as u64;
                             
                             let pcm_bytes = samples_to_bytes(&audio_chunk.samples);
+                            if self.fallback.is_some() {
+                                if total_samples * 1000 / SAMPLE_RATE > MAX_REPLAY_MS {
+                                    log_info!("录音超过 {} 秒，不再切换备用引擎", MAX_REPLAY_MS / 1000);
+                                    self.fallback = None;
+                                    replay = Vec::new();
+                                } else {
+                                    replay.push(pcm_bytes.clone());
+                                    lock(&self.monitor).on_audio(&audio_chunk.samples);
+                                }
+                            }
                             
+                            let mut switch_reason = None;
                             match session.send_chunk(&pcm_bytes).await {
                                 Ok(()) => {
+                                    last_sent = Instant::now();
                                     consecutive_send_failures = 0;
+                                    lock(&self.latency).on_chunk(audio_chunk.timestamp_ms, received);
+                                    beat();
                                 }
                                 Err(e) => {
                                     consecutive_send_failures += 1;
@@ -199,10 +403,52 @@ impl RealtimeTranscriptionTask {
                                     );
                                     
                                     if consecutive_send_failures >= MAX_CONSECUTIVE_FAILURES {
-                                        log_error!("连续发送失败次数过多，中止任务");
+                                        if self.fallback.is_none() {
+                                            log_error!("连续发送失败次数过多，中止任务");
+                                            return RealtimeTaskResult::Failed {
+                                                error: ASRError::WebSocketError(format!(
+                                                    "连续 {} 次发送失败: {}",
+                                                    consecutive_send_failures, e
+                                                )),
+                                                engine_name,
+                                                chunks_sent: chunk_count,
+                                                samples_sent: total_samples,
+                                            };
+                                        }
+                                        switch_reason = Some(SwitchReason::SendFailed);
+                                    }
+                                }
+                            }
+                            if switch_reason.is_none() && self.fallback.is_some() {
+                                switch_reason = lock(&self.monitor).verdict();
+                            }
+                            
+                            if let Some(reason) = switch_reason {
+                                let fallback = self.fallback.take().expect("切换时备用引擎存在");
+                                let replayed = std::mem::take(&mut replay);
+                                match self.switch_session(&fallback, &replayed).await {
+                                    Ok((name, new_session)) => {
+                                        let mut old_session = std::mem::replace(&mut session, new_session);
+                                        tokio::spawn(async move {
+                                            let _ = old_session.close().await;
+                                        });
+                                        let replayed_samples: usize = replayed.iter().map(|chunk| chunk.len() / 2).sum();
+                                        self.notify_switch(EngineSwitch {
+                                            from: std::mem::replace(&mut engine_name, name),
+                                            to: engine_name.clone(),
+                                            reason,
+                                            replayed_chunks: replayed.len(),
+                                            replayed_ms: replayed_samples as u64 * 1000 / SAMPLE_RATE,
+                                        });
+                                        used_fallback = true;
+                                        consecutive_send_failures = 0;
+                                        beat();
+                                    }
+                                    Err(e) if reason == SwitchReason::SendFailed => {
+                                        log_error!("切换备用引擎失败，中止任务: {}", e);
                                         return RealtimeTaskResult::Failed {
                                             error: ASRError::WebSocketError(format!(
-                                                "连续 {} 次发送失败: {}",
+                                                "连续 {} 次发送失败，备用引擎也不可用: {}",
                                                 consecutive_send_failures, e
                                             )),
                                             engine_name,
@@ -210,6 +456,9 @@ impl RealtimeTranscriptionTask {
                                             samples_sent: total_samples,
                                         };
                                     }
+                                    Err(e) => {
+                                        log_warn!("切换备用引擎失败，继续使用 {}: {}", engine_name, e);
+                                    }
                                 }
                             }
                             
@@ -231,13 +480,15 @@ impl RealtimeTranscriptionTask {
         }
         
         log_info!(
-            "共发送 {} 个音频块，{} 样本，约 {:.1} 秒",
+            "共发送 {} 个音频块，{} 样本，约 {:.1} 秒，保活帧 {} 个",
             chunk_count,
             total_samples,
-            total_samples as f64 / 16000.0
+            total_samples as f64 / 16000.0,
+            keep_alives
         );
         
         log_info!("关闭 ASR 会话，等待最终结果...");
+        beat();
         let final_text = match session.close().await {
             Ok(text) => text,
             Err(e) => {
@@ -266,12 +517,99 @@ impl RealtimeTranscriptionTask {
         RealtimeTaskResult::Success(TranscriptionResult::new(
             final_text,
             engine_name,
-            false,
+            used_fallback,
             duration_ms,
-        ))
+        ).with_words(session.words()))
+    }
+    
+    /// 创建引擎和实时会话，部分结果经健康度统计后转发给回调 (失败时返回引擎名称和错误)
+    async fn open_session(&self, config: &ASRProviderConfig) -> Result<(String, Box<dyn RealtimeSession>), (String, ASRError)> {
+        let engine = create_engine(config).map_err(|e| {
+            log_error!("创建 ASR 引擎失败: {}", e);
+            ("unknown".to_string(), e)
+        })?;
+        let engine_name = engine.name().to_string();
+        log_debug!("创建 ASR 引擎: {}", engine_name);
+        
+        let mut session = match engine.create_realtime_session().await {
+            Ok(s) => s,
+            Err(e) => {
+                log_error!("创建实时会话失败 (WebSocket 连接失败): {}", e);
+                return Err((engine_name, e));
+            }
+        };
+        
+        let current = self.generation.load(Ordering::SeqCst);
+        let generation = Arc::clone(&self.generation);
+        let monitor = Arc::clone(&self.monitor);
+        let latency = Arc::clone(&self.latency);
+        let latency_warning = self.latency_warning.clone();
+        let partial_callback = Arc::clone(&self.partial_callback);
+        let engine = engine_name.clone();
+        session.set_partial_callback(Box::new(move |text| {
+            if generation.load(Ordering::SeqCst) != current {
+                return;
+            }
+            lock(&monitor).on_partial(text);
+            let mut tracker = lock(&latency);
+            if let Some(latency_ms) = tracker.on_partial(text, Instant::now()) {
+                latency::record(&engine, latency_ms);
+                if let Some((threshold_ms, ref on_warning)) = latency_warning {
+                    if let Some(average) = tracker.check_warning(threshold_ms) {
+                        log_warn!("{} 部分结果延迟过高: 平均 {}ms (阈值 {}ms)", engine, average, threshold_ms);
+                        on_warning(&LatencyWarning { engine: engine.clone(), latency_ms: average, threshold_ms });
+                    }
+                }
+            }
+            drop(tracker);
+            let text_owned = text.to_string();
+            let callback = partial_callback.clone();
+            tokio::spawn(async move {
+                if let Some(ref cb) = *callback.lock().await {
+                    cb(&text_owned);
+                }
+            });
+        }));
+        Ok((engine_name, session))
+    }
+    
+    /// 建立备用引擎的会话并回放已发送的音频
+    async fn switch_session(
+        &self,
+        fallback: &ASRProviderConfig,
+        replay: &[Vec<u8>],
+    ) -> Result<(String, Box<dyn RealtimeSession>), ASRError> {
+        // 先停用旧会话的部分结果，备用引擎从头输出；切换失败时恢复
+        let previous = self.generation.fetch_add(1, Ordering::SeqCst);
+        *lock(&self.monitor) = PartialMonitor::default();
+        *lock(&self.latency) = LatencyTracker::default();
+        let result = async {
+            let (name, mut session) = self.open_session(fallback).await
+                .map_err(|(_, e)| e)?;
+            log_info!("切换到备用引擎 {}，回放 {} 个音频块", name, replay.len());
+            for chunk in replay {
+                session.send_chunk(chunk).await?;
+            }
+            Ok((name, session))
+        }.await;
+        if result.is_err() {
+            self.generation.store(previous, Ordering::SeqCst);
+        }
+        result
+    }
+    
+    fn notify_switch(&self, switch: EngineSwitch) {
+        log_info!("实时转录引擎切换: {} -> {} ({:?})", switch.from, switch.to, switch.reason);
+        if let Some(ref callback) = self.switch_callback {
+            callback(&switch);
+        }
     }
 }
 
+fn lock<T>(mutex: &StdMutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 fn samples_to_bytes(samples: &[i16]) -> Vec<u8> {
     let mut bytes = Vec::with_capacity(samples.len() * 2);
     for sample in samples {
@@ -279,3 +617,164 @@ fn samples_to_bytes(samples: &[i16]) -> Vec<u8> {
     }
     bytes
 }
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::asr::MockAsrEngine;
+
+    /// 1 秒的音频块 (amplitude 为 0 时是静音)
+    fn second(amplitude: i16) -> Vec<i16> {
+        (0..SAMPLE_RATE).map(|i| if i % 2 == 0 { amplitude } else { -amplitude }).collect()
+    }
+
+    fn provider(engine: &MockAsrEngine) -> ASRProviderConfig {
+        serde_json::from_value(engine.register()).unwrap()
+    }
+
+    async fn run(primary: ASRProviderConfig, fallback: ASRProviderConfig, seconds: usize) -> (RealtimeTaskResult, Vec<EngineSwitch>) {
+        let (chunk_tx, chunk_rx) = mpsc::channel(64);
+        let switches = Arc::new(StdMutex::new(Vec::new()));
+        let recorded = Arc::clone(&switches);
+        let (task, _stop_tx) = RealtimeTranscriptionTask::new(primary, chunk_rx, None);
+        let task = task.with_fallback(fallback, Some(Box::new(move |switch: &EngineSwitch| {
+            recorded.lock().unwrap().push(switch.clone());
+        })));
+        for i in 0..seconds {
+            chunk_tx.send(AudioChunkData { samples: second(3000), timestamp_ms: i as u64 * 1000 }).await.unwrap();
+        }
+        drop(chunk_tx);
+        let result = task.run_with_details().await;
+        let switches = switches.lock().unwrap().clone();
+        (result, switches)
+    }
+
+    #[test]
+    fn test_is_garbled() {
+        assert!(is_garbled("你好\u{FFFD}世界"));
+        assert!(is_garbled("#@!%&*()"));
+        assert!(is_garbled("的的的的的的的的的的的的的"));
+        assert!(is_garbled("aaaa AAAA aaaa"));
+        assert!(!is_garbled("今天下午三点开会"));
+        assert!(!is_garbled("ok"));
+        assert!(!is_garbled("Hello, world! How are you?"));
+    }
+
+    #[test]
+    fn test_partial_monitor() {
+        let mut monitor = PartialMonitor::default();
+        for _ in 0..7 {
+            monitor.on_audio(&second(3000));
+            monitor.on_partial("");
+        }
+        // 静音不计入
+        monitor.on_audio(&second(0));
+        assert_eq!(monitor.verdict(), None);
+        monitor.on_audio(&second(3000));
+        assert_eq!(monitor.verdict(), Some(SwitchReason::EmptyPartials));
+
+        monitor.on_partial("今天下午");
+        assert_eq!(monitor.verdict(), None);
+        for _ in 0..MAX_GARBLED_PARTIALS {
+            monitor.on_partial("\u{FFFD}\u{FFFD}");
+        }
+        assert_eq!(monitor.verdict(), Some(SwitchReason::GarbledPartials));
+    }
+
+    #[tokio::test]
+    async fn test_switch_on_empty_partials() {
+        let primary = MockAsrEngine::realtime("realtime-silent", &[""], "");
+        let fallback = MockAsrEngine::realtime("realtime-backup", &["今天", "今天下午"], "今天下午开会");
+        let (result, switches) = run(provider(&primary), provider(&fallback), 12).await;
+
+        let result = result.into_result().unwrap();
+        assert_eq!((result.text.as_str(), result.engine.as_str(), result.used_fallback), ("今天下午开会", "realtime-backup", true));
+        assert_eq!(switches.len(), 1);
+        let switch = &switches[0];
+        assert_eq!((switch.from.as_str(), switch.to.as_str(), switch.reason), ("realtime-silent", "realtime-backup", SwitchReason::EmptyPartials));
+        assert_eq!((switch.replayed_chunks, switch.replayed_ms), (8, 8000));
+        assert_eq!(fallback.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_switch_on_connect_failure() {
+        let missing: ASRProviderConfig = serde_json::from_value(serde_json::json!({
+            "provider": "qwen",
+            "mode": "realtime",
+            "dashscope_api_key": "mock:realtime-unregistered",
+        })).unwrap();
+        let fallback = MockAsrEngine::realtime("realtime-standby", &["好的"], "好的");
+        let (result, switches) = run(missing, provider(&fallback), 2).await;
+
+        assert_eq!(result.into_result().unwrap().engine, "realtime-standby");
+        assert_eq!(switches.len(), 1);
+        assert_eq!((switches[0].reason, switches[0].replayed_chunks), (SwitchReason::ConnectFailed, 0));
+    }
+
+    #[tokio::test]
+    async fn test_healthy_primary_keeps_session() {
+        let primary = MockAsrEngine::realtime("realtime-healthy", &["明天", "明天见"], "明天见");
+        let fallback = MockAsrEngine::realtime("realtime-unused", &["x"], "x");
+        let (result, switches) = run(provider(&primary), provider(&fallback), 12).await;
+
+        let result = result.into_result().unwrap();
+        assert_eq!((result.engine.as_str(), result.used_fallback), ("realtime-healthy", false));
+        assert!(switches.is_empty());
+        assert_eq!(fallback.calls(), 0);
+
+        // 第二个音频块之后收到内容变化的部分结果，记录一次延迟
+        let metrics = latency::snapshot().into_iter().find(|m| m.engine == "realtime-healthy").unwrap();
+        assert_eq!(metrics.count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_pause_is_not_a_stall() {
+        use crate::config::StallTimeouts;
+        use crate::watchdog::{TaskKind, WatchedTask, Watchdog};
+        
+        // 没有保活帧的引擎 (如本地 Vosk) 在暂停期间不发送任何数据
+        let engine = MockAsrEngine::realtime("realtime-no-keepalive", &["好"], "好的，继续");
+        let watchdog: &'static Watchdog = Box::leak(Box::new(Watchdog::new()));
+        let timeouts = StallTimeouts::none().with(TaskKind::RealtimeAsr, std::time::Duration::from_millis(20));
+        let (chunk_tx, chunk_rx) = mpsc::channel(8);
+        let (task, _stop_tx) = RealtimeTranscriptionTask::new(provider(&engine), chunk_rx, None);
+        let heartbeat = watchdog.watch(WatchedTask::new(TaskKind::RealtimeAsr));
+        let stalled = heartbeat.stall_token();
+        let task = tokio::spawn(task.with_heartbeat(heartbeat).run_with_details());
+        
+        // 暂停远超停滞超时，不判定为停滞
+        chunk_tx.send(AudioChunkData { samples: second(3000), timestamp_ms: 0 }).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(watchdog.check(&timeouts).is_empty());
+        assert!(!stalled.is_cancelled());
+        
+        chunk_tx.send(AudioChunkData { samples: second(3000), timestamp_ms: 1000 }).await.unwrap();
+        drop(chunk_tx);
+        let result = task.await.unwrap().into_result().unwrap();
+        assert_eq!(result.text, "好的，继续");
+    }
+    
+    #[tokio::test]
+    async fn test_keep_alive_during_pause() {
+        let engine = MockAsrEngine::realtime("realtime-keepalive", &["嗯"], "嗯，我想想")
+            .with_keep_alive(std::time::Duration::from_millis(40));
+        let (chunk_tx, chunk_rx) = mpsc::channel(8);
+        let (task, _stop_tx) = RealtimeTranscriptionTask::new(provider(&engine), chunk_rx, None);
+        let task = tokio::spawn(task.run_with_details());
+
+        // 说话后停顿：静音块不发送，会话收到保活帧
+        chunk_tx.send(AudioChunkData { samples: second(3000), timestamp_ms: 0 }).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+        assert!(engine.keep_alives() >= 2, "keep-alives: {}", engine.keep_alives());
+
+        // 停顿之后继续说话，仍在同一会话中完成转写
+        chunk_tx.send(AudioChunkData { samples: second(3000), timestamp_ms: 1000 }).await.unwrap();
+        drop(chunk_tx);
+        let result = task.await.unwrap().into_result().unwrap();
+        assert_eq!(result.text, "嗯，我想想");
+    }
+}
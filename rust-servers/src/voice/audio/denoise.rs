@@ -0,0 +1,132 @@
+// RNNoise 降噪
+// 使用 nnnoiseless (RNNoise 的纯 Rust 移植，内置模型)，不依赖系统库。
+// RNNoise 只处理 48kHz、每帧 480 个样本的单声道音频，其他采样率先换算到 48kHz，处理后再换算回原采样率
+
+use nnnoiseless::DenoiseState;
+
+use super::recorder::resample;
+
+/// RNNoise 的采样率
+const SAMPLE_RATE: u32 = 48000;
+
+/// 每帧样本数 (10ms @ 48kHz)
+const FRAME_SAMPLES: usize = DenoiseState::FRAME_SIZE;
+
+/// RNNoise 的样本以 16 位整数的幅度表示
+const SCALE: f32 = 32768.0;
+
+/// 单声道流式降噪器：按整帧处理，不足一帧的样本留到下一次
+pub struct NoiseSuppressor {
+    state: Box<DenoiseState<'static>>,
+    sample_rate: u32,
+    /// 尚未处理的 48kHz 样本 (不足一帧)
+    pending: Vec<f32>,
+}
+
+impl NoiseSuppressor {
+    /// 创建 `sample_rate` 采样率的降噪器
+    pub fn new(sample_rate: u32) -> Self {
+        Self { state: DenoiseState::new(), sample_rate, pending: Vec::new() }
+    }
+
+    /// 处理一段样本，返回已处理的样本
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let state = &mut self.state;
+        process_frames(&mut self.pending, self.sample_rate, samples, |input, output| {
+            state.process_frame(output, input);
+        })
+    }
+
+    /// 处理剩余的样本 (补零到一帧)，返回其中有效的部分
+    pub fn finish(&mut self) -> Vec<f32> {
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+        let valid = self.pending.len() as u64 * self.sample_rate as u64 / SAMPLE_RATE as u64;
+        self.pending.resize(FRAME_SAMPLES, 0.0);
+        let mut output = self.process(&[]);
+        output.truncate(valid as usize);
+        output
+    }
+}
+
+/// 对整段单声道录音降噪
+pub fn suppress(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    let mut suppressor = NoiseSuppressor::new(sample_rate);
+    let mut output = suppressor.process(samples);
+    output.extend(suppressor.finish());
+    output
+}
+
+/// 把样本换算到 48kHz 后按整帧交给 `process_frame` (以 16 位整数的幅度)，返回换算回原采样率的结果；
+/// 不足一帧的样本留在 `pending` 中
+fn process_frames(
+    pending: &mut Vec<f32>,
+    sample_rate: u32,
+    samples: &[f32],
+    mut process_frame: impl FnMut(&[f32], &mut [f32]),
+) -> Vec<f32> {
+    pending.extend(resample(samples, sample_rate, SAMPLE_RATE));
+    let frames = pending.len() / FRAME_SAMPLES;
+    let mut output = Vec::with_capacity(frames * FRAME_SAMPLES);
+    let mut input = [0.0f32; FRAME_SAMPLES];
+    let mut frame_output = [0.0f32; FRAME_SAMPLES];
+    for frame in pending.chunks_exact(FRAME_SAMPLES) {
+        for (scaled, sample) in input.iter_mut().zip(frame) {
+            *scaled = sample * SCALE;
+        }
+        process_frame(&input, &mut frame_output);
+        output.extend(frame_output.iter().map(|sample| (sample / SCALE).clamp(-1.0, 1.0)));
+    }
+    pending.drain(..frames * FRAME_SAMPLES);
+    resample(&output, SAMPLE_RATE, sample_rate)
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_frames() {
+        // 48kHz：整帧原样交给处理函数，不足一帧的样本留到下一次
+        let samples: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+        let mut pending = Vec::new();
+        let mut frames = 0;
+        let output = process_frames(&mut pending, SAMPLE_RATE, &samples, |input, output| {
+            assert_eq!(input.len(), FRAME_SAMPLES);
+            frames += 1;
+            output.copy_from_slice(input);
+        });
+        assert_eq!(frames, 2);
+        assert_eq!(pending.len(), 40);
+        assert_eq!(output.len(), 960);
+        assert!(output.iter().zip(&samples).all(|(a, b)| (a - b).abs() < 1e-6));
+
+        // 处理函数收到 16 位整数幅度，输出换算回 [-1, 1]
+        let output = process_frames(&mut pending, SAMPLE_RATE, &samples[..440], |input, output| {
+            assert!(input.iter().any(|s| s.abs() > 1.0));
+            output.fill(SCALE * 2.0);
+        });
+        assert!(pending.is_empty());
+        assert_eq!(output, vec![1.0; FRAME_SAMPLES]);
+
+        // 16kHz：换算到 48kHz 处理后换算回来
+        let mut pending = Vec::new();
+        let output = process_frames(&mut pending, 16000, &[0.25; 480], |input, output| output.copy_from_slice(input));
+        assert_eq!(pending.len(), 0);
+        assert_eq!(output.len(), 480);
+        assert!(output.iter().all(|s| (s - 0.25).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_suppress() {
+        // 处理后样本数不变，静音仍是静音
+        let output = suppress(&[0.0; 1000], 16000);
+        assert_eq!(output.len(), 1000);
+        assert!(output.iter().all(|s| s.abs() < 1e-3));
+    }
+}
@@ -1,12 +1,22 @@
 // 音频编码模块
-// 使用 hound 实现 WAV 编码
+// 使用 hound 实现 WAV 编码和解码；上传和保存录音时可选 Ogg Opus (opus.rs)、FLAC (flac.rs) 或 MP3 (mp3.rs)
 
-use hound::{SampleFormat, WavSpec, WavWriter};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
 use std::io::Cursor;
+use std::path::PathBuf;
 use thiserror::Error;
 
 use super::recorder::TARGET_SAMPLE_RATE;
-use super::AudioData;
+use super::{flac, mp3, opus, AudioData};
+use crate::storage::StorageArea;
+use crate::voice::config::AudioFormat;
+
+/// 日志宏
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "", format!($($arg)*));
+    };
+}
 
 /// 编码错误类型
 #[derive(Debug, Error)]
@@ -160,3 +170,92 @@ pub fn encode_i16_to_wav(
     let encoder = WavEncoder::new(sample_rate, channels, 16);
     encoder.encode_i16_samples(samples)
 }
+
+/// 编码后的音频
+#[derive(Debug, Clone)]
+pub struct EncodedAudio {
+    pub data: Vec<u8>,
+    /// 实际使用的编码 (请求 Opus 但找不到 libopus 时为 WAV)
+    pub format: AudioFormat,
+}
+
+impl EncodedAudio {
+    /// MIME 类型
+    pub fn mime(&self) -> &'static str {
+        self.format.mime()
+    }
+
+    /// 上传使用的文件名
+    pub fn file_name(&self) -> String {
+        format!("audio.{}", self.format.extension())
+    }
+}
+
+/// 按指定格式编码；其他格式编码失败 (如找不到 libopus) 时改用 WAV
+pub fn encode_as(audio: &AudioData, format: AudioFormat) -> Result<EncodedAudio, EncodingError> {
+    if audio.is_empty() {
+        return Err(EncodingError::InvalidAudioData);
+    }
+    let encoded = match format {
+        AudioFormat::Wav => None,
+        AudioFormat::Opus => Some(opus::encode(audio)),
+        AudioFormat::Flac => Some(flac::encode(audio)),
+        AudioFormat::Mp3 => Some(mp3::encode(audio)),
+    };
+    match encoded {
+        Some(Ok(data)) => return Ok(EncodedAudio { data, format }),
+        Some(Err(e)) => {
+            log_warn!("{} 编码失败，改用 WAV: {}", format, e);
+        }
+        None => {}
+    }
+    Ok(EncodedAudio { data: encode_to_wav(audio)?, format: AudioFormat::Wav })
+}
+
+/// 加载编码器或降噪的动态库：先找数据目录的 models 区域，再找系统库路径
+///
+/// `versioned` 为 Linux 发行版通常安装的带版本号的文件名，`label` 用于错误信息
+pub(super) fn load_library(name: &str, versioned: &str, label: &str) -> Result<libloading::Library, String> {
+    let file_name = libloading::library_filename(name);
+    let mut candidates = Vec::new();
+    if let Some(dirs) = crate::storage::data_dirs() {
+        candidates.push(dirs.path(StorageArea::Models).join(&file_name));
+    }
+    candidates.push(PathBuf::from(&file_name));
+    if cfg!(target_os = "linux") {
+        candidates.push(PathBuf::from(versioned));
+    }
+
+    let mut last_error = String::new();
+    for candidate in candidates {
+        // SAFETY: 编码器和降噪库的初始化代码没有额外要求
+        match unsafe { libloading::Library::new(&candidate) } {
+            Ok(library) => return Ok(library),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(format!(
+        "未找到 {} 库 {}，请放入数据目录的 models 区域或安装到系统库路径 ({})",
+        label, file_name.to_string_lossy(), last_error
+    ))
+}
+
+/// 解码 WAV 格式字节数组 (整数或浮点采样，多声道保持交错排列)
+pub fn decode_wav(bytes: &[u8]) -> Result<AudioData, EncodingError> {
+    let reader = WavReader::new(Cursor::new(bytes))?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample.clamp(1, 32) - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    if samples.is_empty() {
+        return Err(EncodingError::InvalidAudioData);
+    }
+    Ok(AudioData::new(samples, spec.sample_rate, spec.channels))
+}
@@ -0,0 +1,372 @@
+// FLAC 编码
+// 纯 Rust 实现的无损编码 (固定阶预测 + Rice 编码残差)，不依赖外部库，体积通常为 WAV 的一半左右；
+// 保留录音的采样率和声道数，样本量化为 16 位
+
+use md5::{Digest, Md5};
+
+use super::recorder::convert_f32_to_i16;
+use super::AudioData;
+
+/// 每帧每个声道的样本数
+const BLOCK_SIZE: usize = 4096;
+
+/// 样本位数
+const BITS_PER_SAMPLE: u32 = 16;
+
+/// FLAC 支持的最大声道数
+const MAX_CHANNELS: u16 = 8;
+
+/// FLAC 支持的最大采样率 (Hz)
+const MAX_SAMPLE_RATE: u32 = 655_350;
+
+/// 固定预测的系数 (按阶数，从当前样本往前)
+const FIXED_COEFFICIENTS: [&[i64]; 5] = [&[1], &[1, -1], &[1, -2, 1], &[1, -3, 3, -1], &[1, -4, 6, -4, 1]];
+
+/// 残差分区阶数的上限
+const MAX_PARTITION_ORDER: u32 = 6;
+
+/// 4 位 Rice 参数的上限 (15 表示转义编码，不使用)
+const MAX_RICE_PARAM: u32 = 14;
+
+/// 把录音编码为 FLAC 文件
+pub fn encode(audio: &AudioData) -> Result<Vec<u8>, String> {
+    if audio.channels == 0 || audio.channels > MAX_CHANNELS {
+        return Err(format!("FLAC 不支持 {} 个声道", audio.channels));
+    }
+    if audio.sample_rate == 0 || audio.sample_rate > MAX_SAMPLE_RATE {
+        return Err(format!("FLAC 不支持采样率 {} Hz", audio.sample_rate));
+    }
+    let channels = audio.channels as usize;
+    let mut samples = convert_f32_to_i16(&audio.samples);
+    // 丢弃不完整的最后一组样本
+    samples.truncate(samples.len() / channels * channels);
+
+    let mut frames = Vec::new();
+    let (mut min_frame, mut max_frame) = (usize::MAX, 0);
+    for (number, block) in samples.chunks(BLOCK_SIZE * channels).enumerate() {
+        let frame = encode_frame(block, channels, number as u64);
+        min_frame = min_frame.min(frame.len());
+        max_frame = max_frame.max(frame.len());
+        frames.extend(frame);
+    }
+
+    let mut md5 = Md5::new();
+    for sample in &samples {
+        md5.update(sample.to_le_bytes());
+    }
+
+    let mut header = BitWriter::default();
+    header.write(1, 1); // 最后一个元数据块
+    header.write(0, 7); // STREAMINFO
+    header.write(34, 24);
+    header.write(BLOCK_SIZE as u64, 16);
+    header.write(BLOCK_SIZE as u64, 16);
+    header.write(if frames.is_empty() { 0 } else { min_frame as u64 }, 24);
+    header.write(max_frame as u64, 24);
+    header.write(audio.sample_rate as u64, 20);
+    header.write(channels as u64 - 1, 3);
+    header.write(BITS_PER_SAMPLE as u64 - 1, 5);
+    let total = (samples.len() / channels) as u64;
+    header.write(total >> 32, 4);
+    header.write(total & 0xffff_ffff, 32);
+
+    let mut bytes = b"fLaC".to_vec();
+    bytes.extend(header.into_bytes());
+    bytes.extend_from_slice(&md5.finalize());
+    bytes.extend(frames);
+    Ok(bytes)
+}
+
+/// 编码一帧 (`block` 为交错排列的样本)
+fn encode_frame(block: &[i16], channels: usize, number: u64) -> Vec<u8> {
+    let len = block.len() / channels;
+    let mut writer = BitWriter::default();
+    writer.write(0b11_1111_1111_1110, 14); // 同步码
+    writer.write(0, 1);
+    writer.write(0, 1); // 固定块大小
+    writer.write(0b0111, 4); // 块大小见帧头末尾的 16 位
+    writer.write(0b0000, 4); // 采样率见 STREAMINFO
+    writer.write(channels as u64 - 1, 4); // 各声道独立编码
+    writer.write(0b100, 3); // 16 位
+    writer.write(0, 1);
+    write_utf8(&mut writer, number);
+    writer.write(len as u64 - 1, 16);
+    let crc = crc8(writer.bytes());
+    writer.write(crc as u64, 8);
+
+    for channel in 0..channels {
+        let signal: Vec<i32> = block.iter().skip(channel).step_by(channels).map(|&s| s as i32).collect();
+        write_subframe(&mut writer, &signal);
+    }
+    writer.align();
+    let crc = crc16(writer.bytes());
+    writer.write(crc as u64, 16);
+    writer.into_bytes()
+}
+
+/// 帧号按 UTF-8 的方式变长编码
+fn write_utf8(writer: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        writer.write(value, 8);
+        return;
+    }
+    let mut len = 2;
+    while value >= 1 << (5 * len + 1) {
+        len += 1;
+    }
+    let lead = (0xff00u64 >> len) & 0xff;
+    writer.write(lead | (value >> (6 * (len - 1))), 8);
+    for index in (0..len - 1).rev() {
+        writer.write(0x80 | ((value >> (6 * index)) & 0x3f), 8);
+    }
+}
+
+/// Rice 编码方案
+struct RicePlan {
+    /// 分区阶数 (2^order 个分区)
+    order: u32,
+    /// 各分区的 Rice 参数
+    params: Vec<u32>,
+    /// 估计的位数
+    bits: u64,
+}
+
+/// 单声道子帧：全部相同时用常数子帧，否则取位数最少的固定阶预测，都不比原样存储小时原样存储
+fn write_subframe(writer: &mut BitWriter, signal: &[i32]) {
+    if signal.iter().all(|&s| s == signal[0]) {
+        writer.write(0, 1);
+        writer.write(0b000000, 6);
+        writer.write(0, 1);
+        writer.write_signed(signal[0], BITS_PER_SAMPLE);
+        return;
+    }
+
+    let verbatim_bits = signal.len() as u64 * BITS_PER_SAMPLE as u64;
+    let mut best: Option<(u64, usize, Vec<u32>, RicePlan)> = None;
+    for order in 0..FIXED_COEFFICIENTS.len().min(signal.len()) {
+        let residual = fixed_residual(signal, order);
+        let plan = plan_rice(&residual, signal.len(), order);
+        let bits = order as u64 * BITS_PER_SAMPLE as u64 + plan.bits;
+        if best.as_ref().is_none_or(|(best_bits, ..)| bits < *best_bits) {
+            best = Some((bits, order, residual, plan));
+        }
+    }
+
+    match best {
+        Some((bits, order, residual, plan)) if bits < verbatim_bits => {
+            writer.write(0, 1);
+            writer.write(0b001000 | order as u64, 6);
+            writer.write(0, 1);
+            for &sample in &signal[..order] {
+                writer.write_signed(sample, BITS_PER_SAMPLE);
+            }
+            write_residual(writer, &residual, &plan, signal.len(), order);
+        }
+        _ => {
+            writer.write(0, 1);
+            writer.write(0b000001, 6);
+            writer.write(0, 1);
+            for &sample in signal {
+                writer.write_signed(sample, BITS_PER_SAMPLE);
+            }
+        }
+    }
+}
+
+/// 固定阶预测的残差 (折叠为无符号数：0, -1, 1, -2 ... 对应 0, 1, 2, 3 ...)
+fn fixed_residual(signal: &[i32], order: usize) -> Vec<u32> {
+    let coefficients = FIXED_COEFFICIENTS[order];
+    signal
+        .windows(order + 1)
+        .map(|window| {
+            let residual: i64 = coefficients.iter()
+                .enumerate()
+                .map(|(index, &c)| c * window[order - index] as i64)
+                .sum();
+            ((residual << 1) ^ (residual >> 63)) as u32
+        })
+        .collect()
+}
+
+/// 选择分区阶数和各分区的 Rice 参数
+fn plan_rice(residual: &[u32], block_size: usize, predictor_order: usize) -> RicePlan {
+    let mut best: Option<RicePlan> = None;
+    for order in 0..=MAX_PARTITION_ORDER {
+        let partitions = 1usize << order;
+        // 块大小须能整除，且第一个分区在去掉预热样本后仍有残差
+        if !block_size.is_multiple_of(partitions) || block_size / partitions <= predictor_order {
+            break;
+        }
+        let mut params = Vec::with_capacity(partitions);
+        let mut bits = 2 + 4;
+        for part in partition(residual, block_size, predictor_order, order) {
+            let sum: u64 = part.iter().map(|&u| u as u64).sum();
+            let param = rice_param(sum, part.len());
+            bits += 4 + part.len() as u64 * (param as u64 + 1) + (sum >> param);
+            params.push(param);
+        }
+        if best.as_ref().is_none_or(|best| bits < best.bits) {
+            best = Some(RicePlan { order, params, bits });
+        }
+    }
+    // 块大小至少为 1 且大于预测阶数，分区阶数 0 总是可用
+    best.expect("partition order 0")
+}
+
+/// 按分区阶数切分残差 (第一个分区少预热样本数个残差)
+fn partition(residual: &[u32], block_size: usize, predictor_order: usize, order: u32) -> impl Iterator<Item = &[u32]> {
+    let size = block_size >> order;
+    (0..1usize << order).map(move |index| {
+        let start = (index * size).saturating_sub(predictor_order);
+        let end = (index + 1) * size - predictor_order;
+        &residual[start..end]
+    })
+}
+
+/// 使平均码长最短的 Rice 参数 (近似为平均值的对数)
+fn rice_param(sum: u64, len: usize) -> u32 {
+    let mut param = 0;
+    while param < MAX_RICE_PARAM && (len as u64) << (param + 1) < sum {
+        param += 1;
+    }
+    param
+}
+
+fn write_residual(writer: &mut BitWriter, residual: &[u32], plan: &RicePlan, block_size: usize, predictor_order: usize) {
+    writer.write(0b00, 2); // 4 位 Rice 参数
+    writer.write(plan.order as u64, 4);
+    for (part, &param) in partition(residual, block_size, predictor_order, plan.order).zip(&plan.params) {
+        writer.write(param as u64, 4);
+        for &value in part {
+            writer.write_rice(value, param);
+        }
+    }
+}
+
+/// 按位写入 (高位在前)
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    pending: u64,
+    pending_bits: u32,
+}
+
+impl BitWriter {
+    /// 写入 `value` 的低 `count` 位 (最多 32 位)
+    fn write(&mut self, value: u64, count: u32) {
+        debug_assert!(count <= 32);
+        self.pending = (self.pending << count) | (value & ((1u64 << count) - 1));
+        self.pending_bits += count;
+        while self.pending_bits >= 8 {
+            self.pending_bits -= 8;
+            self.bytes.push((self.pending >> self.pending_bits) as u8);
+        }
+    }
+
+    fn write_signed(&mut self, value: i32, count: u32) {
+        self.write(value as u32 as u64, count);
+    }
+
+    /// Rice 编码：商用一元码 (若干个 0 加一个 1)，余数用 `param` 位
+    fn write_rice(&mut self, value: u32, param: u32) {
+        let mut quotient = value >> param;
+        while quotient >= 32 {
+            self.write(0, 32);
+            quotient -= 32;
+        }
+        self.write(1, quotient + 1);
+        self.write(value as u64, param);
+    }
+
+    /// 补零到字节边界
+    fn align(&mut self) {
+        if self.pending_bits > 0 {
+            self.write(0, 8 - self.pending_bits);
+        }
+    }
+
+    /// 已写满的字节
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+/// 帧头校验 (多项式 x^8 + x^2 + x + 1)
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// 帧校验 (多项式 x^16 + x^15 + x^2 + 1)
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: Vec<u8>) -> (claxon::metadata::StreamInfo, Vec<i32>) {
+        let mut reader = claxon::FlacReader::new(std::io::Cursor::new(bytes)).unwrap();
+        let info = reader.streaminfo();
+        let samples = reader.samples().collect::<Result<Vec<_>, _>>().unwrap();
+        (info, samples)
+    }
+
+    #[test]
+    fn test_encode() {
+        // 立体声：左声道为正弦波，右声道为噪声，最后一帧不足一个块
+        let frames = BLOCK_SIZE * 2 + 1000;
+        let mut seed = 1u32;
+        let mut samples = Vec::with_capacity(frames * 2);
+        for i in 0..frames {
+            samples.push((i as f32 * 0.05).sin() * 0.5);
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            samples.push((seed >> 16) as f32 / 65536.0 - 0.5);
+        }
+        let audio = AudioData::new(samples, 44100, 2);
+        let bytes = encode(&audio).unwrap();
+        assert!(bytes.len() < audio.samples.len() * 2);
+
+        let (info, decoded) = decode(bytes);
+        assert_eq!((info.sample_rate, info.channels, info.bits_per_sample), (44100, 2, 16));
+        assert_eq!(info.samples, Some(frames as u64));
+        let expected: Vec<i32> = convert_f32_to_i16(&audio.samples).into_iter().map(i32::from).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn test_encode_silence() {
+        // 静音使用常数子帧；单个短块
+        let audio = AudioData::new(vec![0.0; 300], 16000, 1);
+        let bytes = encode(&audio).unwrap();
+        assert!(bytes.len() < 100);
+        let (info, decoded) = decode(bytes);
+        assert_eq!((info.sample_rate, info.channels), (16000, 1));
+        assert_eq!(decoded, vec![0; 300]);
+
+        assert!(encode(&AudioData::new(vec![0.0; 10], 16000, 9)).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+// 音频输入线程
+// cpal 的输入流不能跨线程传递，录音器在专用线程中打开并持有输入流，通过通道控制：
+// 打开的结果经通道返回，停止时通知线程关闭输入流并等待线程退出，此后不会再有音频回调
+
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use super::RecordingError;
+
+/// 等待音频回调应答停止请求的最长时间 (设备正常时一个回调周期内即可应答)
+pub const DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 打开的输入流的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// 持有输入流的线程 (丢弃时停止)
+pub struct InputThread {
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl InputThread {
+    /// 启动线程并在其中调用 `open` 打开输入流，输入流由线程持有直到停止
+    pub fn spawn<S, F>(name: &str, open: F) -> Result<(Self, InputFormat), RecordingError>
+    where
+        S: 'static,
+        F: FnOnce() -> Result<(S, InputFormat), RecordingError> + Send + 'static,
+    {
+        let (opened_tx, opened_rx) = mpsc::channel();
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+
+        let handle = std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let stream = match open() {
+                    Ok((stream, format)) => {
+                        if opened_tx.send(Ok(format)).is_err() {
+                            return;
+                        }
+                        stream
+                    }
+                    Err(e) => {
+                        let _ = opened_tx.send(Err(e));
+                        return;
+                    }
+                };
+                // 收到停止信号或 InputThread 丢弃后关闭输入流
+                let _ = stop_rx.recv();
+                drop(stream);
+            })
+            .map_err(|e| RecordingError::DeviceError(format!("无法启动录音线程: {}", e)))?;
+
+        let format = opened_rx
+            .recv()
+            .map_err(|_| RecordingError::DeviceError("录音线程意外退出".to_string()))?;
+        let thread = Self { stop: Some(stop_tx), handle: Some(handle) };
+        format.map(|format| (thread, format))
+    }
+
+    /// 关闭输入流并等待线程退出
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for InputThread {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// 停止握手 (录音器与音频回调共享)
+///
+/// 录音器停止时请求排空，下一次音频回调处理完当前缓冲区、送出剩余数据后应答，
+/// 录音器收到应答后即可关闭输入流，不会丢失末尾的音频
+#[derive(Clone, Default)]
+pub struct DrainSignal {
+    request: Arc<Mutex<Option<mpsc::Sender<()>>>>,
+}
+
+impl DrainSignal {
+    /// 音频回调中调用：取出待应答的停止请求，处理完当前缓冲区后通过它应答
+    pub fn take_request(&self) -> Option<mpsc::Sender<()>> {
+        self.request.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    /// 请求排空并等待回调应答，超时 (设备不再产生回调) 时返回 false
+    pub fn drain(&self, timeout: Duration) -> bool {
+        let (ack_tx, ack_rx) = mpsc::channel();
+        *self.request.lock().unwrap_or_else(|e| e.into_inner()) = Some(ack_tx);
+        let acknowledged = ack_rx.recv_timeout(timeout).is_ok();
+        // 超时后撤回请求，避免下次录音的回调误应答
+        self.take_request();
+        acknowledged
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// 模拟输入流：不能跨线程传递，丢弃时记录
+    struct FakeStream {
+        closed: Arc<AtomicBool>,
+        _not_send: Rc<()>,
+    }
+
+    impl Drop for FakeStream {
+        fn drop(&mut self) {
+            self.closed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    const FORMAT: InputFormat = InputFormat { sample_rate: 48000, channels: 2 };
+
+    fn open(closed: &Arc<AtomicBool>) -> Result<(InputThread, InputFormat), RecordingError> {
+        let closed = Arc::clone(closed);
+        InputThread::spawn("input-thread-test", move || Ok((FakeStream { closed, _not_send: Rc::new(()) }, FORMAT)))
+    }
+
+    #[test]
+    fn test_stop_closes_stream() {
+        let closed = Arc::new(AtomicBool::new(false));
+        let (thread, format) = open(&closed).unwrap();
+        assert_eq!(format, FORMAT);
+        assert!(!closed.load(Ordering::SeqCst));
+        // stop 返回时输入流已经关闭
+        thread.stop();
+        assert!(closed.load(Ordering::SeqCst));
+
+        let closed = Arc::new(AtomicBool::new(false));
+        drop(open(&closed).unwrap());
+        assert!(closed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_drain() {
+        let drain = DrainSignal::default();
+        assert!(drain.take_request().is_none());
+
+        // 模拟音频回调：应答停止请求前先处理完当前缓冲区
+        let flushed = Arc::new(AtomicBool::new(false));
+        let callback = {
+            let drain = drain.clone();
+            let flushed = Arc::clone(&flushed);
+            std::thread::spawn(move || loop {
+                if let Some(ack) = drain.take_request() {
+                    flushed.store(true, Ordering::SeqCst);
+                    let _ = ack.send(());
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            })
+        };
+        assert!(drain.drain(DRAIN_TIMEOUT));
+        assert!(flushed.load(Ordering::SeqCst));
+        callback.join().unwrap();
+
+        // 没有回调时超时，并撤回请求
+        assert!(!drain.drain(Duration::from_millis(20)));
+        assert!(drain.take_request().is_none());
+    }
+
+    #[test]
+    fn test_open_error() {
+        let result = InputThread::spawn::<FakeStream, _>("input-thread-test", || {
+            Err(RecordingError::MicrophoneUnavailable("没有找到默认音频输入设备".to_string()))
+        });
+        assert!(matches!(result, Err(RecordingError::MicrophoneUnavailable(_))));
+    }
+}
@@ -1,17 +1,24 @@
 // 音频模块
-// 包含录音、流式处理、编码和工具函数
+// 包含录音、流式处理、降噪、语音活动检测、编码 (WAV、Opus、FLAC、MP3)、质量分析、语音活动时间线和工具函数
 
+pub mod denoise;
 pub mod encoder;
+pub mod flac;
+pub mod input_thread;
+pub mod mp3;
+pub mod opus;
+pub mod quality;
 pub mod recorder;
 pub mod streaming;
+pub mod timeline;
 pub mod utils;
 
 use cpal::traits::{DeviceTrait, HostTrait};
 
 // 重新导出常用类型
-pub use encoder::{encode_to_wav, encode_samples_to_wav, encode_i16_to_wav, WavEncoder, EncodingError};
+pub use encoder::{decode_wav, encode_to_wav, encode_samples_to_wav, encode_i16_to_wav, EncodedAudio, WavEncoder, EncodingError};
 pub use recorder::{AudioRecorder, RecordingError, RecordingMode, TARGET_SAMPLE_RATE};
-pub use streaming::{StreamingRecorder, AudioChunkData, CHUNK_SAMPLES};
+pub use streaming::{StreamingRecorder, AudioChunkData, ChunkLayout, CHUNK_SAMPLES};
 
 /// 输入设备信息
 #[derive(Debug, Clone, serde::Serialize)]
@@ -114,6 +121,11 @@ impl AudioData {
     pub fn to_wav(&self) -> Result<Vec<u8>, EncodingError> {
         encode_to_wav(self)
     }
+
+    /// 按指定格式编码 (HTTP 模式上传、保存录音)
+    pub fn encode_as(&self, format: crate::voice::config::AudioFormat) -> Result<EncodedAudio, EncodingError> {
+        encoder::encode_as(self, format)
+    }
 }
 
 /// 音频块 (用于流式传输)
@@ -191,6 +203,43 @@ mod tests {
         assert_eq!(&wav[0..4], b"RIFF");
     }
 
+    #[test]
+    fn test_decode_wav() {
+        let audio = AudioData::new(vec![0.0f32, 0.5, -0.5, 0.25], 44100, 2);
+        let decoded = decode_wav(&audio.to_wav().unwrap()).unwrap();
+
+        assert_eq!(decoded.sample_rate, 44100);
+        assert_eq!(decoded.channels, 2);
+        assert_eq!(decoded.sample_count(), 4);
+        assert!((decoded.samples[1] - 0.5).abs() < 0.001);
+        assert!(decode_wav(b"not a wav file").is_err());
+    }
+
+    #[test]
+    fn test_encode_as() {
+        use crate::voice::config::AudioFormat;
+        let audio = AudioData::new(vec![0.1f32; 48000], 48000, 1);
+
+        let wav = audio.encode_as(AudioFormat::Wav).unwrap();
+        assert_eq!((wav.format, wav.mime(), &wav.data[0..4]), (AudioFormat::Wav, "audio/wav", &b"RIFF"[..]));
+
+        let flac = audio.encode_as(AudioFormat::Flac).unwrap();
+        assert_eq!((flac.format, flac.file_name().as_str(), &flac.data[0..4]), (AudioFormat::Flac, "audio.flac", &b"fLaC"[..]));
+
+        // 没有 libopus 时改用 WAV
+        let opus = audio.encode_as(AudioFormat::Opus).unwrap();
+        match opus.format {
+            AudioFormat::Opus => assert_eq!((opus.file_name().as_str(), &opus.data[0..4]), ("audio.ogg", &b"OggS"[..])),
+            _ => assert_eq!(opus.data, wav.data),
+        }
+        let mp3 = audio.encode_as(AudioFormat::Mp3).unwrap();
+        assert_eq!((mp3.format, mp3.file_name().as_str(), mp3.mime()), (AudioFormat::Mp3, "audio.mp3", "audio/mpeg"));
+        // 立体声的交错样本数为奇数时丢弃不成对的样本
+        let stereo = AudioData::new(vec![0.1f32; 48001], 48000, 2).encode_as(AudioFormat::Mp3).unwrap();
+        assert_eq!(stereo.format, AudioFormat::Mp3);
+        assert!(AudioData::new(Vec::new(), 16000, 1).encode_as(AudioFormat::Opus).is_err());
+    }
+
     #[test]
     fn test_waveform_data() {
         let waveform = WaveformData::new(vec![0.5; 9], 1000);
@@ -0,0 +1,58 @@
+// MP3 编码
+// 部分接口只接受 MP3，保存的录音也常需要在移动端直接播放；使用 mp3lame-encoder (LAME 源码随服务器编译，不依赖系统库)。
+// 单声道和立体声保持不变 (更多声道混为单声道)，采样率由 LAME 换算为 MP3 支持的采样率
+
+use mp3lame_encoder::{Bitrate, BuildError, Builder, DualPcm, FlushNoGap, Mode, MonoPcm, Quality};
+
+use super::recorder::{convert_f32_to_i16, to_mono};
+use super::AudioData;
+
+/// 单声道比特率 (恒定码率，每个声道 64 kbps)
+const BITRATE_MONO: Bitrate = Bitrate::Kbps64;
+
+/// 立体声比特率
+const BITRATE_STEREO: Bitrate = Bitrate::Kbps128;
+
+/// 编码质量 (LAME 的默认值 5)
+const QUALITY: Quality = Quality::Good;
+
+/// 每次送入编码器的样本数 (每个声道)
+const CHUNK_SAMPLES: usize = 8192;
+
+/// flush 需要的输出缓冲区大小 (lame.h)
+const FLUSH_BYTES: usize = 7200;
+
+/// 把录音编码为 MP3
+pub fn encode(audio: &AudioData) -> Result<Vec<u8>, String> {
+    let stereo = audio.channels == 2;
+    let channels = if stereo { 2 } else { 1 };
+    let invalid = |e: BuildError| format!("MP3 编码器不支持 {} Hz、{} 声道: {}", audio.sample_rate, channels, e);
+    let mut builder = Builder::new().ok_or_else(|| "创建 MP3 编码器失败".to_string())?;
+    builder.set_num_channels(channels).map_err(invalid)?;
+    builder.set_sample_rate(audio.sample_rate).map_err(invalid)?;
+    builder.set_mode(if stereo { Mode::JointStereo } else { Mode::Mono }).map_err(invalid)?;
+    builder.set_brate(if stereo { BITRATE_STEREO } else { BITRATE_MONO }).map_err(invalid)?;
+    builder.set_quality(QUALITY).map_err(invalid)?;
+    let mut encoder = builder.build().map_err(invalid)?;
+
+    let mut mp3 = Vec::new();
+    if stereo {
+        let samples = convert_f32_to_i16(&audio.samples);
+        // 交错样本数为奇数时丢弃最后一个不成对的样本，两个声道等长
+        let (left, right): (Vec<i16>, Vec<i16>) = samples.chunks_exact(2).map(|frame| (frame[0], frame[1])).unzip();
+        for (left, right) in left.chunks(CHUNK_SAMPLES).zip(right.chunks(CHUNK_SAMPLES)) {
+            mp3.reserve(mp3lame_encoder::max_required_buffer_size(left.len()));
+            encoder.encode_to_vec(DualPcm { left, right }, &mut mp3).map_err(|e| format!("MP3 编码失败: {}", e))?;
+        }
+    } else {
+        let samples = convert_f32_to_i16(&to_mono(&audio.samples, audio.channels));
+        for chunk in samples.chunks(CHUNK_SAMPLES) {
+            mp3.reserve(mp3lame_encoder::max_required_buffer_size(chunk.len()));
+            encoder.encode_to_vec(MonoPcm(chunk), &mut mp3).map_err(|e| format!("MP3 编码失败: {}", e))?;
+        }
+    }
+    mp3.reserve(FLUSH_BYTES);
+    encoder.flush_to_vec::<FlushNoGap>(&mut mp3).map_err(|e| format!("MP3 编码失败: {}", e))?;
+    Ok(mp3)
+}
+
@@ -0,0 +1,197 @@
+// 录音质量报告
+// 转写完成时附带音频诊断 (音量、峰值、削波、信噪比估计、静音占比)，
+// 用于区分转写质量差是音频本身的问题还是引擎的问题
+
+use serde::Serialize;
+
+use super::utils::{calculate_peak, calculate_rms, VAD_VOICE_THRESHOLD};
+use super::AudioData;
+
+/// 分析帧长 (ms)
+const FRAME_MS: u64 = 20;
+
+/// 视为削波的幅度
+const CLIPPING_LEVEL: f32 = 0.99;
+
+/// 电平下限 (dBFS)，完全静音时使用
+const FLOOR_DBFS: f32 = -100.0;
+
+/// 削波占比超过此值时报告 clipping (%)
+const MAX_CLIPPING_PERCENT: f32 = 0.1;
+
+/// 语音电平低于此值时报告 too_quiet (dBFS)
+const MIN_SPEECH_DBFS: f32 = -40.0;
+
+/// 信噪比低于此值时报告 noisy (dB)
+const MIN_SNR_DB: f32 = 10.0;
+
+/// 静音占比超过此值时报告 mostly_silent (%)
+const MAX_SILENCE_PERCENT: f32 = 90.0;
+
+/// 音频问题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityIssue {
+    /// 没有检测到语音
+    NoSpeech,
+    /// 语音音量过低
+    TooQuiet,
+    /// 削波失真
+    Clipping,
+    /// 背景噪声大
+    Noisy,
+    /// 大部分为静音
+    MostlySilent,
+}
+
+/// 录音质量报告 (transcription_complete 的 quality 字段)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityReport {
+    pub duration_ms: u64,
+    /// 整段音频的平均电平 (dBFS)
+    pub rms_dbfs: f32,
+    /// 峰值电平 (dBFS)
+    pub peak_dbfs: f32,
+    /// 削波样本占比 (%)
+    pub clipping_percent: f32,
+    /// 信噪比估计 (语音帧与最安静帧的电平差，dB；没有语音时为 null)
+    pub snr_db: Option<f32>,
+    /// 静音帧占比 (%)
+    pub silence_percent: f32,
+    pub issues: Vec<QualityIssue>,
+}
+
+/// 分析音频质量
+pub fn analyze(audio: &AudioData) -> QualityReport {
+    let samples = &audio.samples;
+    let frame_len = (audio.sample_rate as u64 * audio.channels as u64 * FRAME_MS / 1000).max(1) as usize;
+    let mut frames: Vec<f32> = samples.chunks(frame_len).map(calculate_rms).collect();
+
+    let clipped = samples.iter().filter(|s| s.abs() >= CLIPPING_LEVEL).count();
+    let clipping_percent = percent(clipped, samples.len());
+    let silent = frames.iter().filter(|&&rms| rms <= VAD_VOICE_THRESHOLD).count();
+    let silence_percent = percent(silent, frames.len());
+
+    // 语音电平取有声帧的平均能量，噪声取最安静的 10% 帧
+    let voiced: Vec<f32> = frames.iter().copied().filter(|&rms| rms > VAD_VOICE_THRESHOLD).collect();
+    let speech_rms = (!voiced.is_empty()).then(|| mean_power(&voiced).sqrt());
+    frames.sort_by(|a, b| a.total_cmp(b));
+    let quietest = &frames[..frames.len().div_ceil(10)];
+    let snr_db = speech_rms.map(|speech| dbfs(speech) - dbfs(mean_power(quietest).sqrt()));
+
+    let mut issues = Vec::new();
+    match speech_rms {
+        None => issues.push(QualityIssue::NoSpeech),
+        Some(speech) if dbfs(speech) < MIN_SPEECH_DBFS => issues.push(QualityIssue::TooQuiet),
+        Some(_) => {}
+    }
+    if clipping_percent > MAX_CLIPPING_PERCENT {
+        issues.push(QualityIssue::Clipping);
+    }
+    if snr_db.is_some_and(|snr| snr < MIN_SNR_DB) {
+        issues.push(QualityIssue::Noisy);
+    }
+    if speech_rms.is_some() && silence_percent > MAX_SILENCE_PERCENT {
+        issues.push(QualityIssue::MostlySilent);
+    }
+
+    QualityReport {
+        duration_ms: audio.duration_ms,
+        rms_dbfs: round(dbfs(calculate_rms(samples))),
+        peak_dbfs: round(dbfs(calculate_peak(samples))),
+        clipping_percent: round(clipping_percent),
+        snr_db: snr_db.map(round),
+        silence_percent: round(silence_percent),
+        issues,
+    }
+}
+
+fn dbfs(level: f32) -> f32 {
+    if level <= 0.0 {
+        return FLOOR_DBFS;
+    }
+    (20.0 * level.log10()).max(FLOOR_DBFS)
+}
+
+fn mean_power(frames: &[f32]) -> f32 {
+    if frames.is_empty() {
+        return 0.0;
+    }
+    frames.iter().map(|rms| rms * rms).sum::<f32>() / frames.len() as f32
+}
+
+fn percent(count: usize, total: usize) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    count as f32 * 100.0 / total as f32
+}
+
+/// 保留一位小数
+fn round(value: f32) -> f32 {
+    (value * 10.0).round() / 10.0
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 16 kHz 单声道音频：先 `quiet_ms` 毫秒的底噪，再 `tone_ms` 毫秒幅度为 `amplitude` 的正弦波
+    fn audio(quiet_ms: usize, tone_ms: usize, amplitude: f32) -> AudioData {
+        let mut samples: Vec<f32> = (0..quiet_ms * 16).map(|i| if i % 2 == 0 { 0.001 } else { -0.001 }).collect();
+        samples.extend((0..tone_ms * 16).map(|i| (i as f32 * 0.1).sin() * amplitude));
+        AudioData::new(samples, 16000, 1)
+    }
+
+    #[test]
+    fn test_clean_speech() {
+        let report = analyze(&audio(500, 1500, 0.3));
+        assert_eq!(report.duration_ms, 2000);
+        assert!((report.peak_dbfs - -10.5).abs() < 0.2);
+        assert_eq!(report.clipping_percent, 0.0);
+        assert_eq!(report.silence_percent, 25.0);
+        assert!(report.snr_db.unwrap() > 40.0);
+        assert!(report.issues.is_empty());
+    }
+
+    #[test]
+    fn test_issues() {
+        let silent = analyze(&AudioData::new(vec![0.0; 16000], 16000, 1));
+        assert_eq!((silent.rms_dbfs, silent.snr_db), (FLOOR_DBFS, None));
+        assert_eq!(silent.issues, vec![QualityIssue::NoSpeech]);
+
+        let clipped = analyze(&audio(0, 1000, 1.5));
+        assert!(clipped.clipping_percent > 10.0);
+        assert!(clipped.issues.contains(&QualityIssue::Clipping));
+
+        let quiet = analyze(&audio(0, 1000, 0.008));
+        assert_eq!(quiet.issues.first(), Some(&QualityIssue::TooQuiet));
+
+        let mostly_silent = analyze(&audio(19000, 1000, 0.3));
+        assert!(mostly_silent.issues.contains(&QualityIssue::MostlySilent));
+
+        let empty = analyze(&AudioData::new(Vec::new(), 16000, 1));
+        assert_eq!((empty.duration_ms, empty.silence_percent), (0, 0.0));
+        assert_eq!(empty.issues, vec![QualityIssue::NoSpeech]);
+    }
+
+    #[test]
+    fn test_noisy() {
+        // 白噪声上叠加语音，信噪比约 6 dB
+        let mut state = 12345u32;
+        let samples: Vec<f32> = (0..32000)
+            .map(|i| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                let noise = ((state >> 16) as f32 / 32768.0 - 1.0) * 0.1;
+                if i >= 16000 { noise + (i as f32 * 0.1).sin() * 0.1 } else { noise }
+            })
+            .collect();
+        let report = analyze(&AudioData::new(samples, 16000, 1));
+        assert!(report.snr_db.unwrap() < MIN_SNR_DB);
+        assert!(report.issues.contains(&QualityIssue::Noisy));
+    }
+}
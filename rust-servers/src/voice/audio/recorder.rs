@@ -3,37 +3,38 @@
 
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] [recorder] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "recorder", format!($($arg)*));
     };
 }
 
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] [recorder] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "recorder", format!($($arg)*));
         }
     };
 }
 
 macro_rules! log_warn {
     ($($arg:tt)*) => {
-        eprintln!("[WARN] [recorder] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Warn, "recorder", format!($($arg)*));
     };
 }
 
 macro_rules! log_error {
     ($($arg:tt)*) => {{
-        eprintln!("[ERROR] [recorder] {}", format!($($arg)*))
+        crate::logging::write(crate::logging::Level::Error, "recorder", format!($($arg)*))
     }};
 }
 
 use cpal::traits::{DeviceTrait, StreamTrait};
-use cpal::Stream;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
-use super::{AudioData, select_input_device, utils};
+use super::input_thread::{DrainSignal, InputFormat, InputThread, DRAIN_TIMEOUT};
+use super::utils::{self, PauseState, SilenceTracker};
+use super::{denoise, AudioData, select_input_device};
 use crate::voice::config::AudioCompressionLevel;
 
 /// API 要求的目标采样率 (16kHz)
@@ -87,11 +88,15 @@ pub struct AudioRecorder {
     audio_data: Arc<Mutex<Vec<f32>>>,
     is_recording: Arc<Mutex<bool>>,
     recording_mode: Arc<Mutex<Option<RecordingMode>>>,
-    stream: Option<Stream>,
+    input: Option<InputThread>,
+    drain: DrainSignal,
     level_callback: Arc<Mutex<Option<AudioLevelCallback>>>,
     smoothed_level: Arc<Mutex<f32>>,
     last_emit_time: Arc<Mutex<Instant>>,
     compression_level: AudioCompressionLevel,
+    noise_suppression: bool,
+    silence: SilenceTracker,
+    pause: PauseState,
 }
 
 impl AudioRecorder {
@@ -102,11 +107,15 @@ impl AudioRecorder {
             audio_data: Arc::new(Mutex::new(Vec::new())),
             is_recording: Arc::new(Mutex::new(false)),
             recording_mode: Arc::new(Mutex::new(None)),
-            stream: None,
+            input: None,
+            drain: DrainSignal::default(),
             level_callback: Arc::new(Mutex::new(None)),
             smoothed_level: Arc::new(Mutex::new(0.0)),
             last_emit_time: Arc::new(Mutex::new(Instant::now())),
             compression_level: AudioCompressionLevel::Minimum,
+            noise_suppression: false,
+            silence: SilenceTracker::new(),
+            pause: PauseState::new(),
         })
     }
 
@@ -118,6 +127,37 @@ impl AudioRecorder {
         *cb = Some(Box::new(callback));
     }
 
+    /// 设置停止录音时是否降噪
+    pub fn set_noise_suppression(&mut self, enabled: bool) {
+        self.noise_suppression = enabled;
+    }
+
+    /// 距最近一次音量超过语音阈值 (或开始录音、继续录音) 的时长，暂停期间为 0
+    pub fn silent_for(&self) -> Duration {
+        if self.pause.is_paused() {
+            return Duration::ZERO;
+        }
+        self.silence.silent_for()
+    }
+
+    /// 暂停录音：之后的输入不写入录音，也不回调音频级别；已暂停时返回 false
+    pub fn pause(&self) -> bool {
+        self.pause.pause()
+    }
+
+    /// 继续录音，未暂停时返回 false
+    pub fn resume(&self) -> bool {
+        let resumed = self.pause.resume().is_some();
+        if resumed {
+            self.silence.reset();
+        }
+        resumed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
     pub fn start(
         &mut self,
         mode: RecordingMode,
@@ -138,44 +178,38 @@ impl AudioRecorder {
         *self.recording_mode.lock().unwrap() = Some(mode);
         *self.smoothed_level.lock().unwrap() = 0.0;
         *self.last_emit_time.lock().unwrap() = Instant::now();
+        self.silence.reset();
+        self.pause.reset();
         self.compression_level = compression_level;
 
-        let device = select_input_device(device_name)?;
-
-        let supported_config = device
-            .default_input_config()
-            .map_err(|e| RecordingError::DeviceError(format!("无法获取默认音频配置: {}", e)))?;
-
-        log_debug!("设备支持的配置: {:?}", supported_config);
-
-        let config = supported_config.config();
-        self.device_sample_rate = config.sample_rate.0;
-        self.channels = config.channels;
-        let target_sample_rate = utils::resolve_compression_sample_rate(
-            self.device_sample_rate,
-            self.compression_level,
-        );
-
-        log_info!(
-            "设备配置: 采样率={}Hz, 声道={}, 目标采样率={}Hz",
-            self.device_sample_rate,
-            self.channels,
-            target_sample_rate
-        );
-
         let audio_data = Arc::clone(&self.audio_data);
         let is_recording = Arc::clone(&self.is_recording);
         let level_callback = Arc::clone(&self.level_callback);
         let smoothed_level = Arc::clone(&self.smoothed_level);
         let last_emit_time = Arc::clone(&self.last_emit_time);
-        let device_sample_rate = self.device_sample_rate;
-        let channels = self.channels;
+        let silence = self.silence.clone();
+        let pause = self.pause.clone();
+        let drain = self.drain.clone();
+        let device_name = device_name.map(str::to_string);
+
+        // 输入流在录音线程中打开并持有，stop/cancel 时由该线程关闭
+        let opened = InputThread::spawn("voice-recorder", move || {
+            let device = select_input_device(device_name.as_deref())?;
+
+            let supported_config = device
+                .default_input_config()
+                .map_err(|e| RecordingError::DeviceError(format!("无法获取默认音频配置: {}", e)))?;
+
+            log_debug!("设备支持的配置: {:?}", supported_config);
+
+            let config = supported_config.config();
+            let device_sample_rate = config.sample_rate.0;
+            let channels = config.channels;
 
-        let err_fn = |err| log_error!("录音流错误: {}", err);
+            let err_fn = |err| log_error!("录音流错误: {}", err);
 
-        let stream = match supported_config.sample_format() {
-            cpal::SampleFormat::F32 => {
-                device
+            let stream = match supported_config.sample_format() {
+                cpal::SampleFormat::F32 => device
                     .build_input_stream(
                         &config,
                         move |data: &[f32], _: &cpal::InputCallbackInfo| {
@@ -186,22 +220,16 @@ impl AudioRecorder {
                                 &level_callback,
                                 &smoothed_level,
                                 &last_emit_time,
-                                device_sample_rate,
-                                channels,
+                                &silence,
+                                &pause,
+                                &drain,
                             );
                         },
                         err_fn,
                         None,
                     )
-                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?
-            }
-            cpal::SampleFormat::I16 => {
-                let audio_data = Arc::clone(&audio_data);
-                let is_recording = Arc::clone(&is_recording);
-                let level_callback = Arc::clone(&level_callback);
-                let smoothed_level = Arc::clone(&smoothed_level);
-
-                device
+                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?,
+                cpal::SampleFormat::I16 => device
                     .build_input_stream(
                         &config,
                         move |data: &[i16], _: &cpal::InputCallbackInfo| {
@@ -213,22 +241,16 @@ impl AudioRecorder {
                                 &level_callback,
                                 &smoothed_level,
                                 &last_emit_time,
-                                device_sample_rate,
-                                channels,
+                                &silence,
+                                &pause,
+                                &drain,
                             );
                         },
                         err_fn,
                         None,
                     )
-                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?
-            }
-            cpal::SampleFormat::U16 => {
-                let audio_data = Arc::clone(&audio_data);
-                let is_recording = Arc::clone(&is_recording);
-                let level_callback = Arc::clone(&level_callback);
-                let smoothed_level = Arc::clone(&smoothed_level);
-
-                device
+                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?,
+                cpal::SampleFormat::U16 => device
                     .build_input_stream(
                         &config,
                         move |data: &[u16], _: &cpal::InputCallbackInfo| {
@@ -240,29 +262,56 @@ impl AudioRecorder {
                                 &level_callback,
                                 &smoothed_level,
                                 &last_emit_time,
-                                device_sample_rate,
-                                channels,
+                                &silence,
+                                &pause,
+                                &drain,
                             );
                         },
                         err_fn,
                         None,
                     )
-                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?
-            }
-            format => {
-                return Err(RecordingError::UnsupportedSampleFormat(format!("{:?}", format)));
+                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?,
+                format => {
+                    return Err(RecordingError::UnsupportedSampleFormat(format!("{:?}", format)));
+                }
+            };
+
+            stream
+                .play()
+                .map_err(|e| RecordingError::DeviceError(e.to_string()))?;
+
+            Ok((stream, InputFormat { sample_rate: device_sample_rate, channels }))
+        });
+
+        let (input, format) = match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                *self.is_recording.lock().unwrap() = false;
+                *self.recording_mode.lock().unwrap() = None;
+                return Err(e);
             }
         };
 
-        stream
-            .play()
-            .map_err(|e| RecordingError::DeviceError(e.to_string()))?;
+        self.device_sample_rate = format.sample_rate;
+        self.channels = format.channels;
+        let target_sample_rate = utils::resolve_compression_sample_rate(
+            self.device_sample_rate,
+            self.compression_level,
+        );
+
+        log_info!(
+            "设备配置: 采样率={}Hz, 声道={}, 目标采样率={}Hz",
+            self.device_sample_rate,
+            self.channels,
+            target_sample_rate
+        );
 
-        self.stream = Some(stream);
+        self.input = Some(input);
         log_info!("录音已启动");
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn handle_audio_callback(
         data: &[f32],
         audio_data: &Arc<Mutex<Vec<f32>>>,
@@ -270,14 +319,34 @@ impl AudioRecorder {
         level_callback: &Arc<Mutex<Option<AudioLevelCallback>>>,
         smoothed_level: &Arc<Mutex<f32>>,
         last_emit_time: &Arc<Mutex<Instant>>,
-        _device_sample_rate: u32,
-        _channels: u16,
+        silence: &SilenceTracker,
+        pause: &PauseState,
+        drain: &DrainSignal,
     ) {
         if !*is_recording.lock().unwrap() {
             return;
         }
 
+        // 暂停期间丢弃输入，停止请求照常应答
+        if pause.is_paused() {
+            if let Some(ack) = drain.take_request() {
+                *is_recording.lock().unwrap() = false;
+                let _ = ack.send(());
+            }
+            return;
+        }
+
         audio_data.lock().unwrap().extend_from_slice(data);
+        if utils::is_voice_active(data) {
+            silence.voice_detected();
+        }
+
+        // 收到停止请求：当前缓冲区已写入，应答后不再接收
+        if let Some(ack) = drain.take_request() {
+            *is_recording.lock().unwrap() = false;
+            let _ = ack.send(());
+            return;
+        }
 
         let mut last_emit = last_emit_time.lock().unwrap();
         if last_emit.elapsed().as_millis() >= AUDIO_LEVEL_EMIT_INTERVAL_MS {
@@ -303,11 +372,17 @@ impl AudioRecorder {
 
         log_info!("停止录音...");
 
+        // 等待音频回调写入最后的缓冲区
+        if !self.drain.drain(DRAIN_TIMEOUT) {
+            log_warn!("等待音频回调应答超时");
+        }
         *self.is_recording.lock().unwrap() = false;
         *self.recording_mode.lock().unwrap() = None;
-        self.stream = None;
-
-        std::thread::sleep(std::time::Duration::from_millis(100));
+        self.pause.reset();
+        // 等待录音线程关闭输入流，之后不会再有音频回调
+        if let Some(input) = self.input.take() {
+            input.stop();
+        }
 
         let raw_audio = self.audio_data.lock().unwrap().clone();
         let original_len = raw_audio.len();
@@ -317,9 +392,13 @@ impl AudioRecorder {
             return Ok(AudioData::new(Vec::new(), TARGET_SAMPLE_RATE, 1));
         }
 
-        let mono_audio = to_mono(&raw_audio, self.channels);
+        let mut mono_audio = to_mono(&raw_audio, self.channels);
         log_debug!("转单声道: {} -> {} 样本", original_len, mono_audio.len());
 
+        if self.noise_suppression {
+            mono_audio = denoise::suppress(&mono_audio, self.device_sample_rate);
+        }
+
         let target_sample_rate = utils::resolve_compression_sample_rate(
             self.device_sample_rate,
             self.compression_level,
@@ -352,7 +431,10 @@ impl AudioRecorder {
         log_info!("取消录音");
         *self.is_recording.lock().unwrap() = false;
         *self.recording_mode.lock().unwrap() = None;
-        self.stream = None;
+        self.pause.reset();
+        if let Some(input) = self.input.take() {
+            input.stop();
+        }
         self.audio_data.lock().unwrap().clear();
     }
 
@@ -432,6 +514,3 @@ pub fn resample(input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
 
     output
 }
-
-unsafe impl Send for AudioRecorder {}
-unsafe impl Sync for AudioRecorder {}
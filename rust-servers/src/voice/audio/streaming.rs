@@ -3,44 +3,89 @@
 
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] [streaming] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "streaming", format!($($arg)*));
     };
 }
 
 macro_rules! log_warn {
     ($($arg:tt)*) => {
-        eprintln!("[WARN] [streaming] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Warn, "streaming", format!($($arg)*));
     };
 }
 
 macro_rules! log_error {
     ($($arg:tt)*) => {{
-        eprintln!("[ERROR] [streaming] {}", format!($($arg)*))
+        crate::logging::write(crate::logging::Level::Error, "streaming", format!($($arg)*))
     }};
 }
 
 use cpal::traits::{DeviceTrait, StreamTrait};
-use cpal::Stream;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
 use super::recorder::{
     convert_i16_to_f32, convert_u16_to_f32, resample, to_mono, RecordingError, RecordingMode,
     TARGET_SAMPLE_RATE,
 };
-use super::{select_input_device, utils};
-use crate::voice::config::AudioCompressionLevel;
+use super::denoise::{self, NoiseSuppressor};
+use super::input_thread::{DrainSignal, InputFormat, InputThread, DRAIN_TIMEOUT};
+use super::utils::{self, PauseState, SilenceTracker, VoiceDetector, DEFAULT_VAD_AGGRESSIVENESS};
+use super::select_input_device;
+use crate::voice::config::{ASRProviderConfig, AudioCompressionLevel, DEFAULT_CHUNK_MS, MAX_CHUNK_MS, MIN_CHUNK_MS};
 use super::AudioData;
 
-/// 每个音频块的样本数 (0.2秒 @ 16kHz = 3200 样本)
+/// 每个音频块的默认样本数 (0.2秒 @ 16kHz = 3200 样本)
 pub const CHUNK_SAMPLES: usize = 3200;
 
-/// 音频块通道缓冲大小 (约 10 秒的音频)
-pub const CHUNK_CHANNEL_BUFFER: usize = 50;
+/// 音频块通道缓冲的时长 (毫秒，约 10 秒的音频)
+pub const CHUNK_CHANNEL_BUFFER_MS: u32 = 10_000;
 
-/// VAD 拖尾块数 (默认 3 块 = 0.6 秒)
-pub const VAD_HANGOVER_CHUNKS: usize = 3;
+/// VAD 拖尾时长 (毫秒)
+pub const VAD_HANGOVER_MS: u32 = 600;
+
+/// 音频块的切分方式：每块的样本数和与上一块重叠的样本数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkLayout {
+    pub samples: usize,
+    pub overlap: usize,
+}
+
+impl Default for ChunkLayout {
+    fn default() -> Self {
+        Self { samples: CHUNK_SAMPLES, overlap: 0 }
+    }
+}
+
+impl ChunkLayout {
+    /// 按供应商配置的 chunk_ms 和 chunk_overlap_ms 切分 (16 kHz)
+    pub fn for_provider(config: &ASRProviderConfig) -> Self {
+        let samples_per_ms = TARGET_SAMPLE_RATE as usize / 1000;
+        let chunk_ms = config.chunk_ms.unwrap_or(DEFAULT_CHUNK_MS).clamp(MIN_CHUNK_MS, MAX_CHUNK_MS);
+        let samples = chunk_ms as usize * samples_per_ms;
+        let overlap = config.chunk_overlap_ms.unwrap_or(0) as usize * samples_per_ms;
+        Self { samples, overlap: overlap.min(samples / 2) }
+    }
+
+    /// 相邻两块起点之间的样本数
+    pub fn step(&self) -> usize {
+        self.samples - self.overlap
+    }
+
+    fn step_ms(&self) -> u32 {
+        (self.step() * 1000 / TARGET_SAMPLE_RATE as usize).max(1) as u32
+    }
+
+    /// 音频块通道的容量 (按时长折算)
+    pub fn channel_capacity(&self) -> usize {
+        CHUNK_CHANNEL_BUFFER_MS.div_ceil(self.step_ms()) as usize
+    }
+
+    /// VAD 拖尾的块数 (按时长折算)
+    pub fn hangover_chunks(&self) -> usize {
+        VAD_HANGOVER_MS.div_ceil(self.step_ms()) as usize
+    }
+}
 
 /// 音频级别发送间隔 (毫秒)，目标 ~30Hz
 pub const AUDIO_LEVEL_EMIT_INTERVAL_MS: u128 = 33;
@@ -61,7 +106,8 @@ pub struct StreamingRecorder {
     channels: u16,
     is_recording: Arc<Mutex<bool>>,
     recording_mode: Arc<Mutex<Option<RecordingMode>>>,
-    stream: Option<Stream>,
+    input: Option<InputThread>,
+    drain: DrainSignal,
     chunk_sender: Option<mpsc::Sender<AudioChunkData>>,
     full_audio_data: Arc<Mutex<Vec<f32>>>,
     level_callback: Arc<Mutex<Option<StreamingLevelCallback>>>,
@@ -71,6 +117,11 @@ pub struct StreamingRecorder {
     agc_gain: Arc<Mutex<f32>>,
     last_emit_time: Arc<Mutex<Instant>>,
     compression_level: AudioCompressionLevel,
+    chunk_layout: ChunkLayout,
+    noise_suppression: bool,
+    vad_aggressiveness: u8,
+    silence: SilenceTracker,
+    pause: PauseState,
 }
 
 impl StreamingRecorder {
@@ -80,7 +131,8 @@ impl StreamingRecorder {
             channels: 1,
             is_recording: Arc::new(Mutex::new(false)),
             recording_mode: Arc::new(Mutex::new(None)),
-            stream: None,
+            input: None,
+            drain: DrainSignal::default(),
             chunk_sender: None,
             full_audio_data: Arc::new(Mutex::new(Vec::new())),
             level_callback: Arc::new(Mutex::new(None)),
@@ -90,6 +142,11 @@ impl StreamingRecorder {
             agc_gain: Arc::new(Mutex::new(1.0)),
             last_emit_time: Arc::new(Mutex::new(Instant::now())),
             compression_level: AudioCompressionLevel::Minimum,
+            chunk_layout: ChunkLayout::default(),
+            noise_suppression: false,
+            vad_aggressiveness: DEFAULT_VAD_AGGRESSIVENESS,
+            silence: SilenceTracker::new(),
+            pause: PauseState::new(),
         })
     }
 
@@ -101,11 +158,52 @@ impl StreamingRecorder {
         *cb = Some(Box::new(callback));
     }
 
+    /// 设置是否降噪 (音频块和完整录音都降噪，下次开始录音时生效)
+    pub fn set_noise_suppression(&mut self, enabled: bool) {
+        self.noise_suppression = enabled;
+    }
+
+    /// 设置丢弃无语音音频块时 VAD 的检测模式 (0-3，下次开始录音时生效)
+    pub fn set_vad_aggressiveness(&mut self, aggressiveness: u8) {
+        self.vad_aggressiveness = aggressiveness;
+    }
+
+    /// 距最近一个有语音的音频块 (或开始录音、继续录音) 的时长，暂停期间为 0
+    pub fn silent_for(&self) -> Duration {
+        if self.pause.is_paused() {
+            return Duration::ZERO;
+        }
+        self.silence.silent_for()
+    }
+
+    /// 暂停录音：之后的输入不写入完整录音，也不产生音频块和音频级别；已暂停时返回 false
+    pub fn pause(&self) -> bool {
+        self.pause.pause()
+    }
+
+    /// 继续录音，未暂停时返回 false
+    pub fn resume(&self) -> bool {
+        let Some(paused_for) = self.pause.resume() else {
+            return false;
+        };
+        // 音频块的时间戳不计入暂停的时长
+        if let Some(start) = self.start_time.lock().unwrap().as_mut() {
+            *start += paused_for;
+        }
+        self.silence.reset();
+        true
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
     pub fn start_streaming(
         &mut self,
         mode: RecordingMode,
         device_name: Option<&str>,
         compression_level: AudioCompressionLevel,
+        chunk_layout: ChunkLayout,
     ) -> Result<mpsc::Receiver<AudioChunkData>, RecordingError> {
         {
             let is_recording = self.is_recording.lock().unwrap();
@@ -124,34 +222,14 @@ impl StreamingRecorder {
         *self.vad_hangover.lock().unwrap() = 0;
         *self.agc_gain.lock().unwrap() = 1.0;
         *self.last_emit_time.lock().unwrap() = Instant::now();
+        self.silence.reset();
+        self.pause.reset();
         self.compression_level = compression_level;
+        self.chunk_layout = chunk_layout;
 
-        let (chunk_tx, chunk_rx) = mpsc::channel::<AudioChunkData>(CHUNK_CHANNEL_BUFFER);
+        let (chunk_tx, chunk_rx) = mpsc::channel::<AudioChunkData>(chunk_layout.channel_capacity());
         self.chunk_sender = Some(chunk_tx.clone());
 
-        let device = select_input_device(device_name)?;
-
-        let supported_config = device
-            .default_input_config()
-            .map_err(|e| RecordingError::DeviceError(format!("无法获取默认音频配置: {}", e)))?;
-
-        let config = supported_config.config();
-        self.device_sample_rate = config.sample_rate.0;
-        self.channels = config.channels;
-
-        let target_sample_rate = utils::resolve_compression_sample_rate(
-            self.device_sample_rate,
-            self.compression_level,
-        );
-
-        log_info!(
-            "流式录音配置: 采样率={}Hz, 声道={}, 压缩采样率={}Hz, 块大小={}样本",
-            self.device_sample_rate,
-            self.channels,
-            target_sample_rate,
-            CHUNK_SAMPLES
-        );
-
         let is_recording = Arc::clone(&self.is_recording);
         let full_audio_data = Arc::clone(&self.full_audio_data);
         let level_callback = Arc::clone(&self.level_callback);
@@ -160,22 +238,35 @@ impl StreamingRecorder {
         let vad_hangover = Arc::clone(&self.vad_hangover);
         let agc_gain = Arc::clone(&self.agc_gain);
         let last_emit_time = Arc::clone(&self.last_emit_time);
-        let device_sample_rate = self.device_sample_rate;
-        let channels = self.channels;
-
-        let pending_samples: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
-
-        let err_fn = |err| log_error!("录音流错误: {}", err);
-
-        let stream = match supported_config.sample_format() {
-            cpal::SampleFormat::F32 => {
-                let pending = Arc::clone(&pending_samples);
-                let chunk_tx = chunk_tx.clone();
-                let vad_hangover = Arc::clone(&vad_hangover);
-                let agc_gain = Arc::clone(&agc_gain);
-                let last_emit_time = Arc::clone(&last_emit_time);
-
-                device
+        let silence = self.silence.clone();
+        let pause = self.pause.clone();
+        let drain = self.drain.clone();
+        let device_name = device_name.map(str::to_string);
+        let noise_suppression = self.noise_suppression;
+        let vad_aggressiveness = self.vad_aggressiveness;
+
+        // 输入流在录音线程中打开并持有，stop_streaming/cancel 时由该线程关闭
+        let opened = InputThread::spawn("voice-streaming", move || {
+            let device = select_input_device(device_name.as_deref())?;
+
+            let supported_config = device
+                .default_input_config()
+                .map_err(|e| RecordingError::DeviceError(format!("无法获取默认音频配置: {}", e)))?;
+
+            let config = supported_config.config();
+            let device_sample_rate = config.sample_rate.0;
+            let channels = config.channels;
+
+            let pending_samples: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
+            let noise_suppressor = Arc::new(Mutex::new(
+                noise_suppression.then(|| NoiseSuppressor::new(device_sample_rate)),
+            ));
+            let voice_detector = Arc::new(Mutex::new(VoiceDetector::new(vad_aggressiveness)));
+
+            let err_fn = |err| log_error!("录音流错误: {}", err);
+
+            let stream = match supported_config.sample_format() {
+                cpal::SampleFormat::F32 => device
                     .build_input_stream(
                         &config,
                         move |data: &[f32], _: &cpal::InputCallbackInfo| {
@@ -183,36 +274,29 @@ impl StreamingRecorder {
                                 data,
                                 &is_recording,
                                 &full_audio_data,
-                                &pending,
+                                &pending_samples,
+                                &noise_suppressor,
                                 &chunk_tx,
                                 &level_callback,
                                 &smoothed_level,
                                 &start_time,
+                                &voice_detector,
+                                &silence,
+                                &pause,
                                 &vad_hangover,
                                 &agc_gain,
                                 &last_emit_time,
+                                &drain,
                                 device_sample_rate,
                                 channels,
+                                chunk_layout,
                             );
                         },
                         err_fn,
                         None,
                     )
-                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?
-            }
-            cpal::SampleFormat::I16 => {
-                let is_recording = Arc::clone(&is_recording);
-                let full_audio_data = Arc::clone(&full_audio_data);
-                let pending = Arc::clone(&pending_samples);
-                let level_callback = Arc::clone(&level_callback);
-                let smoothed_level = Arc::clone(&smoothed_level);
-                let start_time = Arc::clone(&start_time);
-                let chunk_tx = chunk_tx.clone();
-                let vad_hangover = Arc::clone(&vad_hangover);
-                let agc_gain = Arc::clone(&agc_gain);
-                let last_emit_time = Arc::clone(&last_emit_time);
-
-                device
+                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?,
+                cpal::SampleFormat::I16 => device
                     .build_input_stream(
                         &config,
                         move |data: &[i16], _: &cpal::InputCallbackInfo| {
@@ -221,36 +305,29 @@ impl StreamingRecorder {
                                 &f32_data,
                                 &is_recording,
                                 &full_audio_data,
-                                &pending,
+                                &pending_samples,
+                                &noise_suppressor,
                                 &chunk_tx,
                                 &level_callback,
                                 &smoothed_level,
                                 &start_time,
+                                &voice_detector,
+                                &silence,
+                                &pause,
                                 &vad_hangover,
                                 &agc_gain,
                                 &last_emit_time,
+                                &drain,
                                 device_sample_rate,
                                 channels,
+                                chunk_layout,
                             );
                         },
                         err_fn,
                         None,
                     )
-                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?
-            }
-            cpal::SampleFormat::U16 => {
-                let is_recording = Arc::clone(&is_recording);
-                let full_audio_data = Arc::clone(&full_audio_data);
-                let pending = Arc::clone(&pending_samples);
-                let level_callback = Arc::clone(&level_callback);
-                let smoothed_level = Arc::clone(&smoothed_level);
-                let start_time = Arc::clone(&start_time);
-                let chunk_tx = chunk_tx.clone();
-                let vad_hangover = Arc::clone(&vad_hangover);
-                let agc_gain = Arc::clone(&agc_gain);
-                let last_emit_time = Arc::clone(&last_emit_time);
-
-                device
+                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?,
+                cpal::SampleFormat::U16 => device
                     .build_input_stream(
                         &config,
                         move |data: &[u16], _: &cpal::InputCallbackInfo| {
@@ -259,36 +336,71 @@ impl StreamingRecorder {
                                 &f32_data,
                                 &is_recording,
                                 &full_audio_data,
-                                &pending,
+                                &pending_samples,
+                                &noise_suppressor,
                                 &chunk_tx,
                                 &level_callback,
                                 &smoothed_level,
                                 &start_time,
+                                &voice_detector,
+                                &silence,
+                                &pause,
                                 &vad_hangover,
                                 &agc_gain,
                                 &last_emit_time,
+                                &drain,
                                 device_sample_rate,
                                 channels,
+                                chunk_layout,
                             );
                         },
                         err_fn,
                         None,
                     )
-                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?
-            }
-            format => {
-                return Err(RecordingError::UnsupportedSampleFormat(format!(
-                    "{:?}",
-                    format
-                )));
+                    .map_err(|e| RecordingError::DeviceError(e.to_string()))?,
+                format => {
+                    return Err(RecordingError::UnsupportedSampleFormat(format!(
+                        "{:?}",
+                        format
+                    )));
+                }
+            };
+
+            stream
+                .play()
+                .map_err(|e| RecordingError::DeviceError(e.to_string()))?;
+
+            Ok((stream, InputFormat { sample_rate: device_sample_rate, channels }))
+        });
+
+        let (input, format) = match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                *self.is_recording.lock().unwrap() = false;
+                *self.recording_mode.lock().unwrap() = None;
+                self.chunk_sender = None;
+                return Err(e);
             }
         };
 
-        stream
-            .play()
-            .map_err(|e| RecordingError::DeviceError(e.to_string()))?;
+        self.device_sample_rate = format.sample_rate;
+        self.channels = format.channels;
 
-        self.stream = Some(stream);
+        let target_sample_rate = utils::resolve_compression_sample_rate(
+            self.device_sample_rate,
+            self.compression_level,
+        );
+
+        log_info!(
+            "流式录音配置: 采样率={}Hz, 声道={}, 压缩采样率={}Hz, 块大小={}样本, 重叠={}样本",
+            self.device_sample_rate,
+            self.channels,
+            target_sample_rate,
+            chunk_layout.samples,
+            chunk_layout.overlap
+        );
+
+        self.input = Some(input);
 
         log_info!("流式录音已启动");
         Ok(chunk_rx)
@@ -300,26 +412,40 @@ impl StreamingRecorder {
         is_recording: &Arc<Mutex<bool>>,
         full_audio_data: &Arc<Mutex<Vec<f32>>>,
         pending_samples: &Arc<Mutex<Vec<f32>>>,
+        noise_suppressor: &Arc<Mutex<Option<NoiseSuppressor>>>,
         chunk_tx: &mpsc::Sender<AudioChunkData>,
         level_callback: &Arc<Mutex<Option<StreamingLevelCallback>>>,
         smoothed_level: &Arc<Mutex<f32>>,
         start_time: &Arc<Mutex<Option<std::time::Instant>>>,
+        voice_detector: &Arc<Mutex<VoiceDetector>>,
+        silence: &SilenceTracker,
+        pause: &PauseState,
         vad_hangover: &Arc<Mutex<usize>>,
         agc_gain: &Arc<Mutex<f32>>,
         last_emit_time: &Arc<Mutex<Instant>>,
+        drain: &DrainSignal,
         device_sample_rate: u32,
         channels: u16,
+        chunk_layout: ChunkLayout,
     ) {
         if !*is_recording.lock().unwrap() {
             return;
         }
 
+        // 暂停期间丢弃输入 (不写入完整录音，也不产生音频块和音频级别)，停止请求照常处理
+        let data: &[f32] = if pause.is_paused() { &[] } else { data };
+
         full_audio_data.lock().unwrap().extend_from_slice(data);
 
         let mono = to_mono(data, channels);
+        // 在设备采样率上降噪 (不足一帧的样本留到下一次回调)
+        let mono = match noise_suppressor.lock().unwrap().as_mut() {
+            Some(suppressor) => suppressor.process(&mono),
+            None => mono,
+        };
         let resampled = resample(&mono, device_sample_rate, TARGET_SAMPLE_RATE);
 
-        {
+        if !resampled.is_empty() {
             let mut last_emit = last_emit_time.lock().unwrap();
             if last_emit.elapsed().as_millis() >= AUDIO_LEVEL_EMIT_INTERVAL_MS {
                 let level = utils::calculate_audio_level(&resampled);
@@ -338,14 +464,31 @@ impl StreamingRecorder {
         let mut pending = pending_samples.lock().unwrap();
         pending.extend(resampled);
 
-        while pending.len() >= CHUNK_SAMPLES {
-            let mut chunk_f32: Vec<f32> = pending.drain(..CHUNK_SAMPLES).collect();
+        // 每次前进 step 个样本，块末尾的 overlap 个样本留给下一块
+        let mut chunks = Vec::new();
+        while pending.len() >= chunk_layout.samples {
+            chunks.push(pending[..chunk_layout.samples].to_vec());
+            pending.drain(..chunk_layout.step());
+        }
 
-            let is_active = utils::is_voice_active(&chunk_f32);
+        // 收到停止请求：剩余样本中除与上一块重叠的部分外还有新样本时，作为最后一块 (不足一块) 送出，应答后不再接收
+        let ack = drain.take_request();
+        if ack.is_some() {
+            if pending.len() > chunk_layout.overlap {
+                chunks.push(std::mem::take(&mut *pending));
+            }
+            pending.clear();
+            *is_recording.lock().unwrap() = false;
+        }
+        drop(pending);
+
+        for mut chunk_f32 in chunks {
+            let is_active = voice_detector.lock().unwrap().is_active(&chunk_f32);
             let mut hangover = vad_hangover.lock().unwrap();
 
             if is_active {
-                *hangover = VAD_HANGOVER_CHUNKS;
+                silence.voice_detected();
+                *hangover = chunk_layout.hangover_chunks();
             } else if *hangover > 0 {
                 *hangover -= 1;
             }
@@ -381,6 +524,10 @@ impl StreamingRecorder {
                 log_warn!("音频块通道已满，丢弃块");
             }
         }
+
+        if let Some(ack) = ack {
+            let _ = ack.send(());
+        }
     }
 
     pub fn stop_streaming(&mut self) -> Result<AudioData, RecordingError> {
@@ -393,14 +540,18 @@ impl StreamingRecorder {
 
         log_info!("停止流式录音...");
 
-        std::thread::sleep(std::time::Duration::from_millis(200));
-
+        // 等待音频回调送出最后的缓冲区
+        if !self.drain.drain(DRAIN_TIMEOUT) {
+            log_warn!("等待音频回调应答超时");
+        }
         *self.is_recording.lock().unwrap() = false;
         *self.recording_mode.lock().unwrap() = None;
+        self.pause.reset();
 
-        std::thread::sleep(std::time::Duration::from_millis(100));
-
-        self.stream = None;
+        // 等待录音线程关闭输入流，之后不会再有音频回调
+        if let Some(input) = self.input.take() {
+            input.stop();
+        }
         self.chunk_sender = None;
 
         let raw_audio = self.full_audio_data.lock().unwrap().clone();
@@ -410,7 +561,10 @@ impl StreamingRecorder {
             return Ok(AudioData::new(Vec::new(), TARGET_SAMPLE_RATE, 1));
         }
 
-        let mono_audio = to_mono(&raw_audio, self.channels);
+        let mut mono_audio = to_mono(&raw_audio, self.channels);
+        if self.noise_suppression {
+            mono_audio = denoise::suppress(&mono_audio, self.device_sample_rate);
+        }
         let target_sample_rate = utils::resolve_compression_sample_rate(
             self.device_sample_rate,
             self.compression_level,
@@ -435,7 +589,10 @@ impl StreamingRecorder {
 
         *self.is_recording.lock().unwrap() = false;
         *self.recording_mode.lock().unwrap() = None;
-        self.stream = None;
+        self.pause.reset();
+        if let Some(input) = self.input.take() {
+            input.stop();
+        }
         self.chunk_sender = None;
         self.full_audio_data.lock().unwrap().clear();
     }
@@ -449,5 +606,29 @@ impl StreamingRecorder {
     }
 }
 
-unsafe impl Send for StreamingRecorder {}
-unsafe impl Sync for StreamingRecorder {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::config::ASRMode;
+
+    #[test]
+    fn test_chunk_layout() {
+        let mut config = ASRProviderConfig::qwen(ASRMode::Realtime, "test-key".to_string());
+        let default = ChunkLayout::for_provider(&config);
+        assert_eq!(default, ChunkLayout::default());
+        assert_eq!((default.channel_capacity(), default.hangover_chunks()), (50, 3));
+
+        config.chunk_ms = Some(40);
+        config.chunk_overlap_ms = Some(10);
+        let layout = ChunkLayout::for_provider(&config);
+        assert_eq!((layout.samples, layout.overlap, layout.step()), (640, 160, 480));
+        assert_eq!((layout.channel_capacity(), layout.hangover_chunks()), (334, 20));
+
+        // 超出范围的配置按上下限处理
+        config.chunk_ms = Some(0);
+        config.chunk_overlap_ms = Some(500);
+        let layout = ChunkLayout::for_provider(&config);
+        assert_eq!((layout.samples, layout.overlap), (MIN_CHUNK_MS as usize * 16, MIN_CHUNK_MS as usize * 8));
+    }
+}
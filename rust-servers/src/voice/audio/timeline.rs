@@ -0,0 +1,83 @@
+// 语音活动时间线
+// 转写完成时附带录音中有语音的区间 (相对录音开始的毫秒)，区间之间为静音，
+// 插件据此绘制录音缩略图，回放时可以跳到说话的部分
+
+use super::utils::{calculate_rms, VAD_VOICE_THRESHOLD};
+use super::AudioData;
+
+/// 分析帧长 (ms)
+const FRAME_MS: u64 = 20;
+
+/// 短于此值的停顿并入前后的语音 (ms)
+const MIN_SILENCE_MS: u64 = 300;
+
+/// 短于此值的语音 (如按键声) 忽略 (ms)
+const MIN_SPEECH_MS: u64 = 100;
+
+/// 有语音的区间 `[start_ms, end_ms]`，按时间排列且互不重叠 (transcription_complete 的 timeline 字段)
+pub fn speech_intervals(audio: &AudioData) -> Vec<[u64; 2]> {
+    let samples_per_sec = audio.sample_rate as u64 * audio.channels as u64;
+    if samples_per_sec == 0 {
+        return Vec::new();
+    }
+    let frame_len = (samples_per_sec * FRAME_MS / 1000).max(1) as usize;
+    let to_ms = |offset: usize| offset as u64 * 1000 / samples_per_sec;
+
+    let mut intervals: Vec<[u64; 2]> = Vec::new();
+    for (index, frame) in audio.samples.chunks(frame_len).enumerate() {
+        if calculate_rms(frame) <= VAD_VOICE_THRESHOLD {
+            continue;
+        }
+        let start = to_ms(index * frame_len);
+        let end = to_ms(index * frame_len + frame.len());
+        match intervals.last_mut() {
+            Some(last) if start - last[1] < MIN_SILENCE_MS => last[1] = end,
+            _ => intervals.push([start, end]),
+        }
+    }
+    intervals.retain(|[start, end]| end - start >= MIN_SPEECH_MS);
+    intervals
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 16 kHz 单声道：依次为 (毫秒数, 是否有声音) 的片段
+    fn audio(parts: &[(usize, bool)]) -> AudioData {
+        let samples = parts
+            .iter()
+            .flat_map(|&(ms, voiced)| {
+                (0..ms * 16).map(move |i| if voiced { (i as f32 * 0.1).sin() * 0.3 } else { 0.0 })
+            })
+            .collect();
+        AudioData::new(samples, 16000, 1)
+    }
+
+    #[test]
+    fn test_speech_intervals() {
+        // 200ms 的停顿并入语音，1 秒的停顿分开两段
+        let recording = audio(&[(500, false), (1000, true), (200, false), (600, true), (1000, false), (400, true), (300, false)]);
+        assert_eq!(speech_intervals(&recording), vec![[500, 2300], [3300, 3700]]);
+
+        // 按键声忽略，结尾不足一帧的语音也计入
+        let click = audio(&[(1000, false), (40, true), (1000, false), (510, true)]);
+        assert_eq!(speech_intervals(&click), vec![[2040, 2550]]);
+
+        assert!(speech_intervals(&audio(&[(2000, false)])).is_empty());
+        assert!(speech_intervals(&AudioData::new(Vec::new(), 16000, 1)).is_empty());
+    }
+
+    #[test]
+    fn test_stereo() {
+        let samples: Vec<f32> = (0..48000).flat_map(|i| {
+            let value = if i >= 24000 { (i as f32 * 0.1).sin() * 0.3 } else { 0.0 };
+            [value, value]
+        }).collect();
+        assert_eq!(speech_intervals(&AudioData::new(samples, 48000, 2)), vec![[500, 1000]]);
+    }
+}
@@ -1,6 +1,12 @@
 // 音频工具函数模块
-// 提供 AGC (自动增益控制)、VAD (静音检测)、RMS 计算、波形生成等功能
+// 提供 AGC (自动增益控制)、VAD (语音活动检测)、RMS 计算、波形生成等功能
 
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use webrtc_vad::{SampleRate, Vad, VadMode};
+
+use super::recorder::convert_f32_to_i16;
 use crate::voice::config::AudioCompressionLevel;
 
 // ============================================================================
@@ -189,6 +195,138 @@ pub fn is_silence(samples: &[f32]) -> bool {
     !is_voice_active(samples)
 }
 
+/// 默认的 VAD 检测模式 (0-3，越高越严格)
+pub const DEFAULT_VAD_AGGRESSIVENESS: u8 = 1;
+
+/// 最高的 VAD 检测模式 (越高越严格，越少把噪声判为语音)
+pub const MAX_VAD_AGGRESSIVENESS: u8 = 3;
+
+/// WebRTC VAD 每帧的样本数 (30ms @ 16kHz；WebRTC VAD 只接受 10/20/30ms 的帧)
+const VAD_FRAME_SAMPLES: usize = super::TARGET_SAMPLE_RATE as usize * 30 / 1000;
+
+/// 判为语音所需的语音帧比例 (1/4)
+const VOICED_FRAME_RATIO: usize = 4;
+
+/// 语音活动检测器 (16kHz 音频块)
+///
+/// 先用 RMS 阈值排除静音，再由 WebRTC VAD (webrtc-vad，随服务器编译) 逐帧判断，至少 1/4 的帧有语音时视为有语音；
+/// 键盘声、风扇声等响度足够但不是语音的声音因此不会被送去识别
+pub struct VoiceDetector {
+    vad: WebRtcVad,
+}
+
+/// webrtc_vad::Vad 持有裸指针，默认不是 Send
+struct WebRtcVad(Vad);
+
+// SAFETY: Vad 中的指针指向 fvad_new 分配的实例，只由 Vad 持有 (Drop 时释放)；WebRTC VAD 没有全局或线程局部状态，
+// 实例也不绑定创建它的线程。实例只通过 &mut self 使用，不会并发访问，因此可以移动到其他线程。
+// 没有实现 Sync：&self 不能跨线程共享
+unsafe impl Send for WebRtcVad {}
+
+impl VoiceDetector {
+    /// 创建指定检测模式 (0-3，超出时按 3) 的检测器
+    pub fn new(aggressiveness: u8) -> Self {
+        let mode = match aggressiveness {
+            0 => VadMode::Quality,
+            1 => VadMode::LowBitrate,
+            2 => VadMode::Aggressive,
+            _ => VadMode::VeryAggressive,
+        };
+        // 音频块为 TARGET_SAMPLE_RATE (16kHz)
+        Self { vad: WebRtcVad(Vad::new_with_rate_and_mode(SampleRate::Rate16kHz, mode)) }
+    }
+
+    /// 音频块中是否有语音
+    pub fn is_active(&mut self, samples: &[f32]) -> bool {
+        if !is_voice_active(samples) {
+            return false;
+        }
+        let samples = convert_f32_to_i16(samples);
+        let frames = samples.chunks_exact(VAD_FRAME_SAMPLES);
+        if frames.len() == 0 {
+            return true;
+        }
+        let total = frames.len();
+        // 处理失败的帧按有语音处理，宁可多送也不丢语音
+        let voiced = frames.filter(|frame| self.vad.0.is_voice_segment(frame).unwrap_or(true)).count();
+        is_mostly_voiced(voiced, total)
+    }
+}
+
+/// 语音帧是否达到判为语音所需的比例
+fn is_mostly_voiced(voiced: usize, total: usize) -> bool {
+    voiced * VOICED_FRAME_RATIO >= total
+}
+
+/// 静音计时：录音回调检测到语音时更新，检查方据此判断已静音多久 (克隆后共享同一计时)
+#[derive(Clone)]
+pub struct SilenceTracker {
+    last_voice: Arc<Mutex<Instant>>,
+}
+
+impl SilenceTracker {
+    pub fn new() -> Self {
+        Self { last_voice: Arc::new(Mutex::new(Instant::now())) }
+    }
+
+    /// 重新开始计时 (开始录音时)
+    pub fn reset(&self) {
+        self.voice_detected();
+    }
+
+    /// 记录检测到语音
+    pub fn voice_detected(&self) {
+        *self.last_voice.lock().unwrap() = Instant::now();
+    }
+
+    /// 距最近一次检测到语音 (或开始计时) 的时长
+    pub fn silent_for(&self) -> Duration {
+        self.last_voice.lock().unwrap().elapsed()
+    }
+}
+
+impl Default for SilenceTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 录音暂停状态：控制方暂停 / 继续，录音回调据此丢弃暂停期间的输入 (克隆后共享同一状态)
+#[derive(Clone, Default)]
+pub struct PauseState {
+    paused_since: Arc<Mutex<Option<Instant>>>,
+}
+
+impl PauseState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 暂停，已暂停时返回 false
+    pub fn pause(&self) -> bool {
+        let mut paused_since = self.paused_since.lock().unwrap();
+        if paused_since.is_some() {
+            return false;
+        }
+        *paused_since = Some(Instant::now());
+        true
+    }
+
+    /// 继续，返回本次暂停的时长；未暂停时返回 None
+    pub fn resume(&self) -> Option<Duration> {
+        self.paused_since.lock().unwrap().take().map(|since| since.elapsed())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_since.lock().unwrap().is_some()
+    }
+
+    /// 清除暂停状态 (开始和结束录音时)
+    pub fn reset(&self) {
+        *self.paused_since.lock().unwrap() = None;
+    }
+}
+
 /// 计算音频时长 (毫秒)
 pub fn calculate_duration_ms(sample_count: usize, sample_rate: u32, channels: u16) -> u64 {
     if sample_rate == 0 || channels == 0 {
@@ -226,3 +364,58 @@ pub fn resolve_compression_sample_rate(device_sample_rate: u32, level: AudioComp
     };
     target.min(device_sample_rate)
 }
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_voice_detector() {
+        assert!(is_mostly_voiced(2, 6));
+        assert!(!is_mostly_voiced(1, 6));
+        assert!(is_mostly_voiced(1, 1));
+
+        // 静音不经过 VAD 直接判为无语音
+        let mut detector = VoiceDetector::new(DEFAULT_VAD_AGGRESSIVENESS);
+        assert!(!detector.is_active(&[0.0; 3200]));
+        assert!(!detector.is_active(&[]));
+
+        // 不足一帧的响亮音频块不经过 VAD，按有语音处理；整帧的音频块交给 WebRTC VAD 判断
+        assert!(detector.is_active(&[0.5; 100]));
+        let tone: Vec<f32> = (0..3200).map(|i| (i as f32 * 0.3).sin() * 0.5).collect();
+        let _ = VoiceDetector::new(MAX_VAD_AGGRESSIVENESS + 1).is_active(&tone);
+    }
+
+    #[test]
+    fn test_silence_tracker() {
+        let tracker = SilenceTracker::new();
+        let shared = tracker.clone();
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(shared.silent_for() >= Duration::from_millis(30));
+
+        // 克隆共享同一计时
+        tracker.voice_detected();
+        assert!(shared.silent_for() < Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_pause_state() {
+        let state = PauseState::new();
+        let shared = state.clone();
+        assert_eq!(state.resume(), None);
+        assert!(state.pause());
+        assert!(!shared.pause());
+        assert!(shared.is_paused());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(shared.resume().unwrap() >= Duration::from_millis(30));
+        assert!(!state.is_paused());
+
+        state.pause();
+        state.reset();
+        assert_eq!(shared.resume(), None);
+    }
+}
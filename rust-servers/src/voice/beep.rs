@@ -10,14 +10,14 @@ use std::time::Duration;
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] [beep] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "beep", format!($($arg)*));
         }
     };
 }
 
 macro_rules! log_error {
     ($($arg:tt)*) => {
-        eprintln!("[ERROR] [beep] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Error, "beep", format!($($arg)*));
     };
 }
 
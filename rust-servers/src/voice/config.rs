@@ -3,6 +3,10 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::audio::utils::DEFAULT_VAD_AGGRESSIVENESS;
+use super::audio::utils::MAX_VAD_AGGRESSIVENESS;
+use super::language_check::is_valid_language;
+
 /// ASR 供应商类型
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -14,6 +18,13 @@ pub enum ASRProvider {
     /// 硅基流动 SenseVoice
     #[serde(rename = "sensevoice")]
     SenseVoice,
+    /// 本地 Whisper (whisper.cpp，离线)
+    Whisper,
+    /// OpenAI 兼容的转写接口 (OpenAI、Groq、LiteLLM 等)
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// 本地 Vosk (离线，支持实时模式)
+    Vosk,
 }
 
 impl std::fmt::Display for ASRProvider {
@@ -22,6 +33,20 @@ impl std::fmt::Display for ASRProvider {
             ASRProvider::Qwen => write!(f, "qwen"),
             ASRProvider::Doubao => write!(f, "doubao"),
             ASRProvider::SenseVoice => write!(f, "sensevoice"),
+            ASRProvider::Whisper => write!(f, "whisper"),
+            ASRProvider::OpenAi => write!(f, "openai"),
+            ASRProvider::Vosk => write!(f, "vosk"),
+        }
+    }
+}
+
+impl ASRProvider {
+    /// HTTP 模式可以上传的音频格式 (本地引擎不上传录音，只有 WAV)
+    pub fn upload_formats(&self) -> &'static [AudioFormat] {
+        match self {
+            ASRProvider::Qwen | ASRProvider::OpenAi => &[AudioFormat::Wav, AudioFormat::Opus, AudioFormat::Flac, AudioFormat::Mp3],
+            ASRProvider::Doubao | ASRProvider::SenseVoice => &[AudioFormat::Wav, AudioFormat::Opus, AudioFormat::Mp3],
+            ASRProvider::Whisper | ASRProvider::Vosk => &[AudioFormat::Wav],
         }
     }
 }
@@ -60,6 +85,78 @@ impl Default for AudioCompressionLevel {
     }
 }
 
+/// 音频文件格式 (HTTP 模式上传和保存录音)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    /// 16 位 PCM WAV
+    #[default]
+    Wav,
+    /// Ogg 封装的 Opus (16 kHz 单声道，约为 WAV 的十分之一；需要 libopus，找不到时改用 WAV)
+    Opus,
+    /// FLAC (无损，约为 WAV 的一半；内置编码器)
+    Flac,
+    /// MP3 (恒定码率)
+    Mp3,
+}
+
+impl AudioFormat {
+    /// MIME 类型
+    pub fn mime(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Opus => "audio/ogg",
+            AudioFormat::Flac => "audio/flac",
+            AudioFormat::Mp3 => "audio/mpeg",
+        }
+    }
+
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Opus => "ogg",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Mp3 => "mp3",
+        }
+    }
+}
+
+impl std::fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioFormat::Wav => write!(f, "wav"),
+            AudioFormat::Opus => write!(f, "opus"),
+            AudioFormat::Flac => write!(f, "flac"),
+            AudioFormat::Mp3 => write!(f, "mp3"),
+        }
+    }
+}
+
+/// 本地 Whisper 模型大小 (对应数据目录 models 区域中 whisper.cpp 的 ggml 模型文件)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WhisperModelSize {
+    Tiny,
+    Base,
+    Small,
+    Medium,
+    Large,
+}
+
+impl WhisperModelSize {
+    /// 模型文件名 (与 whisper.cpp 发布的文件名一致)
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            WhisperModelSize::Tiny => "ggml-tiny.bin",
+            WhisperModelSize::Base => "ggml-base.bin",
+            WhisperModelSize::Small => "ggml-small.bin",
+            WhisperModelSize::Medium => "ggml-medium.bin",
+            WhisperModelSize::Large => "ggml-large-v3.bin",
+        }
+    }
+}
+
 /// ASR 供应商配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ASRProviderConfig {
@@ -85,8 +182,47 @@ pub struct ASRProviderConfig {
     /// 硅基流动 API Key
     #[serde(skip_serializing_if = "Option::is_none")]
     pub siliconflow_api_key: Option<String>,
+    
+    // 本地引擎配置
+    /// 模型路径：Whisper 为模型文件 (优先于 model_size)，Vosk 为解压后的模型目录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_path: Option<String>,
+    /// 模型大小，使用数据目录 models 区域中对应的模型文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_size: Option<WhisperModelSize>,
+    
+    // OpenAI 兼容接口配置
+    /// API 根地址 (默认 https://api.openai.com/v1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// 模型名称 (默认 whisper-1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// API Key (省略时不发送 Authorization，用于无需认证的本地代理)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    
+    // 实时模式的音频块配置
+    /// 每个音频块的时长 (毫秒，默认 200)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_ms: Option<u32>,
+    /// 相邻音频块重叠的时长 (毫秒，默认 0，最多为块时长的一半)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_overlap_ms: Option<u32>,
+    
+    // HTTP 模式的上传格式
+    /// 上传录音使用的格式 (默认 WAV，可选的格式见 `ASRProvider::upload_formats`)
+    #[serde(default)]
+    pub upload_format: AudioFormat,
 }
 
+/// 默认的音频块时长 (毫秒)
+pub const DEFAULT_CHUNK_MS: u32 = 200;
+
+/// 音频块时长的范围 (毫秒)
+pub const MIN_CHUNK_MS: u32 = 20;
+pub const MAX_CHUNK_MS: u32 = 1000;
+
 impl ASRProviderConfig {
     /// 创建 Qwen 配置
     pub fn qwen(mode: ASRMode, api_key: String) -> Self {
@@ -97,6 +233,14 @@ impl ASRProviderConfig {
             app_id: None,
             access_token: None,
             siliconflow_api_key: None,
+            model_path: None,
+            model_size: None,
+            base_url: None,
+            model: None,
+            api_key: None,
+            chunk_ms: None,
+            chunk_overlap_ms: None,
+            upload_format: AudioFormat::default(),
         }
     }
     
@@ -109,6 +253,14 @@ impl ASRProviderConfig {
             app_id: Some(app_id),
             access_token: Some(access_token),
             siliconflow_api_key: None,
+            model_path: None,
+            model_size: None,
+            base_url: None,
+            model: None,
+            api_key: None,
+            chunk_ms: None,
+            chunk_overlap_ms: None,
+            upload_format: AudioFormat::default(),
         }
     }
     
@@ -121,6 +273,74 @@ impl ASRProviderConfig {
             app_id: None,
             access_token: None,
             siliconflow_api_key: Some(api_key),
+            model_path: None,
+            model_size: None,
+            base_url: None,
+            model: None,
+            api_key: None,
+            chunk_ms: None,
+            chunk_overlap_ms: None,
+            upload_format: AudioFormat::default(),
+        }
+    }
+    
+    /// 创建本地 Whisper 配置 (仅支持 HTTP 模式，即整段录音转写)
+    pub fn whisper(model_path: Option<String>, model_size: Option<WhisperModelSize>) -> Self {
+        Self {
+            provider: ASRProvider::Whisper,
+            mode: ASRMode::Http,
+            dashscope_api_key: None,
+            app_id: None,
+            access_token: None,
+            siliconflow_api_key: None,
+            model_path,
+            model_size,
+            base_url: None,
+            model: None,
+            api_key: None,
+            chunk_ms: None,
+            chunk_overlap_ms: None,
+            upload_format: AudioFormat::default(),
+        }
+    }
+    
+    /// 创建 OpenAI 兼容接口配置 (仅支持 HTTP 模式)
+    pub fn openai(base_url: Option<String>, model: Option<String>, api_key: Option<String>) -> Self {
+        Self {
+            provider: ASRProvider::OpenAi,
+            mode: ASRMode::Http,
+            dashscope_api_key: None,
+            app_id: None,
+            access_token: None,
+            siliconflow_api_key: None,
+            model_path: None,
+            model_size: None,
+            base_url,
+            model,
+            api_key,
+            chunk_ms: None,
+            chunk_overlap_ms: None,
+            upload_format: AudioFormat::default(),
+        }
+    }
+    
+    /// 创建本地 Vosk 配置 (`model_path` 为模型目录)
+    pub fn vosk(mode: ASRMode, model_path: String) -> Self {
+        Self {
+            provider: ASRProvider::Vosk,
+            mode,
+            dashscope_api_key: None,
+            app_id: None,
+            access_token: None,
+            siliconflow_api_key: None,
+            model_path: Some(model_path),
+            model_size: None,
+            base_url: None,
+            model: None,
+            api_key: None,
+            chunk_ms: None,
+            chunk_overlap_ms: None,
+            upload_format: AudioFormat::default(),
         }
     }
     
@@ -152,6 +372,59 @@ impl ASRProviderConfig {
                     });
                 }
             }
+            ASRProvider::Whisper => {
+                if self.model_path.as_ref().is_none_or(|p| p.is_empty()) && self.model_size.is_none() {
+                    return Err(ConfigError::InvalidConfig("whisper 需要 model_path 或 model_size".to_string()));
+                }
+                // 本地 Whisper 只转写整段录音
+                if self.mode != ASRMode::Http {
+                    return Err(ConfigError::UnsupportedMode {
+                        provider: self.provider.to_string(),
+                        mode: self.mode.to_string(),
+                    });
+                }
+            }
+            ASRProvider::OpenAi => {
+                if let Some(base_url) = &self.base_url {
+                    if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+                        return Err(ConfigError::InvalidConfig(format!("base_url 必须以 http:// 或 https:// 开头: {}", base_url)));
+                    }
+                }
+                if self.model.as_ref().is_some_and(|model| model.is_empty()) {
+                    return Err(ConfigError::InvalidConfig("model 不能为空".to_string()));
+                }
+                if self.mode != ASRMode::Http {
+                    return Err(ConfigError::UnsupportedMode {
+                        provider: self.provider.to_string(),
+                        mode: self.mode.to_string(),
+                    });
+                }
+            }
+            ASRProvider::Vosk => {
+                if self.model_path.as_ref().is_none_or(|p| p.is_empty()) {
+                    return Err(ConfigError::InvalidConfig("vosk 需要 model_path".to_string()));
+                }
+            }
+        }
+        if !self.provider.upload_formats().contains(&self.upload_format) {
+            return Err(ConfigError::InvalidConfig(format!(
+                "{} 不支持上传格式 {}", self.provider, self.upload_format
+            )));
+        }
+        if let Some(chunk_ms) = self.chunk_ms {
+            if !(MIN_CHUNK_MS..=MAX_CHUNK_MS).contains(&chunk_ms) {
+                return Err(ConfigError::InvalidConfig(format!(
+                    "chunk_ms 超出范围: {} ({}-{})", chunk_ms, MIN_CHUNK_MS, MAX_CHUNK_MS
+                )));
+            }
+        }
+        if let Some(overlap_ms) = self.chunk_overlap_ms {
+            let chunk_ms = self.chunk_ms.unwrap_or(DEFAULT_CHUNK_MS);
+            if overlap_ms > chunk_ms / 2 {
+                return Err(ConfigError::InvalidConfig(format!(
+                    "chunk_overlap_ms 不能超过块时长的一半: {} (块时长 {})", overlap_ms, chunk_ms
+                )));
+            }
         }
         Ok(())
     }
@@ -176,6 +449,33 @@ pub struct ASRConfig {
     /// 音频压缩等级
     #[serde(default)]
     pub audio_compression: AudioCompressionLevel,
+    /// 是否对录音做 RNNoise 降噪
+    #[serde(default)]
+    pub enable_noise_suppression: bool,
+    /// 实时模式丢弃无语音音频块时 VAD 的检测模式 (0-3，越高越严格)
+    #[serde(default = "default_vad_aggressiveness")]
+    pub vad_aggressiveness: u8,
+    /// toggle 模式下连续这么多毫秒没有语音时自动停止录音并转写 (0 为不自动停止)
+    #[serde(default)]
+    pub auto_stop_silence_ms: u64,
+    /// 是否将转写结果中的口语数字转换为阿拉伯数字
+    #[serde(default)]
+    pub normalize_numbers: bool,
+    /// 实时模式部分结果的平均延迟超过此值 (毫秒) 时发送 latency_warning 事件 (0 为不检查)
+    #[serde(default = "default_partial_latency_warning_ms")]
+    pub partial_latency_warning_ms: u64,
+    /// 是否在转写结果中附带词级时间戳 (words)
+    #[serde(default)]
+    pub word_timestamps: bool,
+    /// 录音结束后保存完整录音的目录 (空则不保存)，保存的路径见 transcription_complete 的 recording_path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save_recordings_dir: Option<String>,
+    /// 保存录音使用的格式 (默认 WAV)
+    #[serde(default)]
+    pub save_recordings_format: AudioFormat,
+    /// 预期的转写语言 (如 zh、en)，转写结果的语言与之不符时发送 language_mismatch 事件 (省略时不检查)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_language: Option<String>,
 }
 
 /// 默认启用音频反馈
@@ -183,6 +483,19 @@ fn default_enable_audio_feedback() -> bool {
     true
 }
 
+/// 默认的部分结果延迟警告阈值 (毫秒)
+fn default_partial_latency_warning_ms() -> u64 {
+    2000
+}
+
+/// 自动停止录音所需的最短静音时长 (毫秒)，更短时句间停顿就会结束录音
+pub const MIN_AUTO_STOP_SILENCE_MS: u64 = 500;
+
+/// 默认的 VAD 检测模式
+fn default_vad_aggressiveness() -> u8 {
+    DEFAULT_VAD_AGGRESSIVENESS
+}
+
 impl ASRConfig {
     /// 创建仅主引擎的配置
     pub fn primary_only(primary: ASRProviderConfig) -> Self {
@@ -193,6 +506,15 @@ impl ASRConfig {
             enable_audio_feedback: true,
             recording_device: None,
             audio_compression: AudioCompressionLevel::default(),
+            enable_noise_suppression: false,
+            vad_aggressiveness: default_vad_aggressiveness(),
+            auto_stop_silence_ms: 0,
+            normalize_numbers: false,
+            partial_latency_warning_ms: default_partial_latency_warning_ms(),
+            word_timestamps: false,
+            save_recordings_dir: None,
+            save_recordings_format: AudioFormat::default(),
+            expected_language: None,
         }
     }
     
@@ -205,6 +527,15 @@ impl ASRConfig {
             enable_audio_feedback: true,
             recording_device: None,
             audio_compression: AudioCompressionLevel::default(),
+            enable_noise_suppression: false,
+            vad_aggressiveness: default_vad_aggressiveness(),
+            auto_stop_silence_ms: 0,
+            normalize_numbers: false,
+            partial_latency_warning_ms: default_partial_latency_warning_ms(),
+            word_timestamps: false,
+            save_recordings_dir: None,
+            save_recordings_format: AudioFormat::default(),
+            expected_language: None,
         }
     }
     
@@ -214,6 +545,21 @@ impl ASRConfig {
         if let Some(ref fallback) = self.fallback {
             fallback.validate()?;
         }
+        self.validate_recording()?;
+        if let Some(language) = self.expected_language.as_deref().filter(|language| !is_valid_language(language)) {
+            return Err(ConfigError::InvalidConfig(format!("无效的 expected_language: {}", language)));
+        }
+        Ok(())
+    }
+    
+    /// 验证录音相关的选项 (开始录音时检查，不涉及引擎配置)
+    pub fn validate_recording(&self) -> Result<(), ConfigError> {
+        if self.vad_aggressiveness > MAX_VAD_AGGRESSIVENESS {
+            return Err(ConfigError::InvalidConfig(format!("vad_aggressiveness 超出范围: {} (应为 0-{})", self.vad_aggressiveness, MAX_VAD_AGGRESSIVENESS)));
+        }
+        if self.auto_stop_silence_ms != 0 && self.auto_stop_silence_ms < MIN_AUTO_STOP_SILENCE_MS {
+            return Err(ConfigError::InvalidConfig(format!("auto_stop_silence_ms 过短: {} (至少 {}，0 为不自动停止)", self.auto_stop_silence_ms, MIN_AUTO_STOP_SILENCE_MS)));
+        }
         Ok(())
     }
 }
@@ -250,6 +596,14 @@ mod tests {
             app_id: None,
             access_token: None,
             siliconflow_api_key: None,
+            model_path: None,
+            model_size: None,
+            base_url: None,
+            model: None,
+            api_key: None,
+            chunk_ms: None,
+            chunk_overlap_ms: None,
+            upload_format: AudioFormat::default(),
         };
         assert!(invalid_config.validate().is_err());
     }
@@ -271,6 +625,14 @@ mod tests {
             app_id: None,
             access_token: Some("token".to_string()),
             siliconflow_api_key: None,
+            model_path: None,
+            model_size: None,
+            base_url: None,
+            model: None,
+            api_key: None,
+            chunk_ms: None,
+            chunk_overlap_ms: None,
+            upload_format: AudioFormat::default(),
         };
         assert!(invalid_config.validate().is_err());
     }
@@ -286,6 +648,89 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_whisper_config_validation() {
+        assert!(ASRProviderConfig::whisper(None, Some(WhisperModelSize::Base)).validate().is_ok());
+        assert!(ASRProviderConfig::whisper(Some("/models/ggml-small.bin".to_string()), None).validate().is_ok());
+        // 缺少模型
+        assert!(ASRProviderConfig::whisper(Some(String::new()), None).validate().is_err());
+        
+        let mut config = ASRProviderConfig::whisper(None, Some(WhisperModelSize::Tiny));
+        config.mode = ASRMode::Realtime;
+        assert!(config.validate().is_err());
+        
+        let parsed: ASRProviderConfig = serde_json::from_value(serde_json::json!({
+            "provider": "whisper", "mode": "http", "model_size": "large"
+        })).unwrap();
+        assert_eq!(parsed.model_size.map(|size| size.file_name()), Some("ggml-large-v3.bin"));
+    }
+
+    #[test]
+    fn test_openai_config_validation() {
+        assert!(ASRProviderConfig::openai(None, None, Some("sk-test".to_string())).validate().is_ok());
+        // 本地代理可以不需要 API Key
+        let proxy = ASRProviderConfig::openai(Some("http://localhost:4000/v1".to_string()), Some("groq/whisper-large-v3".to_string()), None);
+        assert!(proxy.validate().is_ok());
+        
+        assert!(ASRProviderConfig::openai(Some("api.groq.com/openai/v1".to_string()), None, None).validate().is_err());
+        assert!(ASRProviderConfig::openai(None, Some(String::new()), None).validate().is_err());
+        let mut config = ASRProviderConfig::openai(None, None, None);
+        config.mode = ASRMode::Realtime;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_vosk_config_validation() {
+        // Vosk 两种模式都支持
+        assert!(ASRProviderConfig::vosk(ASRMode::Realtime, "/models/vosk-model-small-cn-0.22".to_string()).validate().is_ok());
+        assert!(ASRProviderConfig::vosk(ASRMode::Http, "/models/vosk-model-small-cn-0.22".to_string()).validate().is_ok());
+        assert!(ASRProviderConfig::vosk(ASRMode::Realtime, String::new()).validate().is_err());
+        
+        let parsed: ASRProviderConfig = serde_json::from_value(serde_json::json!({
+            "provider": "vosk", "mode": "realtime", "model_path": "/models/vosk-model-en-us-0.22"
+        })).unwrap();
+        assert_eq!(parsed.provider, ASRProvider::Vosk);
+    }
+
+    #[test]
+    fn test_chunk_validation() {
+        let mut config = ASRProviderConfig::qwen(ASRMode::Realtime, "test-key".to_string());
+        config.chunk_ms = Some(40);
+        config.chunk_overlap_ms = Some(20);
+        assert!(config.validate().is_ok());
+        
+        config.chunk_overlap_ms = Some(21);
+        assert!(config.validate().is_err());
+        config.chunk_overlap_ms = None;
+        config.chunk_ms = Some(MAX_CHUNK_MS + 1);
+        assert!(config.validate().is_err());
+        config.chunk_ms = Some(MIN_CHUNK_MS - 1);
+        assert!(config.validate().is_err());
+        
+        // 默认块时长 200ms，重叠最多 100ms
+        config.chunk_ms = None;
+        config.chunk_overlap_ms = Some(100);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_upload_format_validation() {
+        let mut config = ASRProviderConfig::openai(None, None, Some("sk-test".to_string()));
+        config.upload_format = AudioFormat::Flac;
+        assert!(config.validate().is_ok());
+
+        // 豆包不接受 FLAC，本地引擎只有 WAV
+        let mut config = ASRProviderConfig::doubao(ASRMode::Http, "app".to_string(), "token".to_string());
+        config.upload_format = AudioFormat::Mp3;
+        assert!(config.validate().is_ok());
+        config.upload_format = AudioFormat::Flac;
+        assert!(config.validate().is_err());
+        assert!(!ASRProvider::Vosk.upload_formats().contains(&AudioFormat::Opus));
+
+        let parsed: AudioFormat = serde_json::from_str("\"mp3\"").unwrap();
+        assert_eq!((parsed, parsed.extension(), parsed.mime()), (AudioFormat::Mp3, "mp3", "audio/mpeg"));
+    }
+
     #[test]
     fn test_asr_config_serialization() {
         let config = ASRConfig::with_fallback(
@@ -330,6 +775,27 @@ mod tests {
         assert_eq!(fallback.siliconflow_api_key, Some("sf-xxx".to_string()));
         
         assert!(config.enable_fallback);
+        assert_eq!(config.vad_aggressiveness, DEFAULT_VAD_AGGRESSIVENESS);
+    }
+
+    #[test]
+    fn test_vad_aggressiveness_validation() {
+        let mut config = ASRConfig::primary_only(ASRProviderConfig::qwen(ASRMode::Realtime, "key".to_string()));
+        config.vad_aggressiveness = MAX_VAD_AGGRESSIVENESS;
+        assert!(config.validate().is_ok());
+        config.vad_aggressiveness = MAX_VAD_AGGRESSIVENESS + 1;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_auto_stop_silence_validation() {
+        let mut config = ASRConfig::primary_only(ASRProviderConfig::qwen(ASRMode::Http, "key".to_string()));
+        assert_eq!(config.auto_stop_silence_ms, 0);
+        assert!(config.validate().is_ok());
+        config.auto_stop_silence_ms = MIN_AUTO_STOP_SILENCE_MS;
+        assert!(config.validate().is_ok());
+        config.auto_stop_silence_ms = MIN_AUTO_STOP_SILENCE_MS - 1;
+        assert!(config.validate().is_err());
     }
 
     #[test]
@@ -0,0 +1,278 @@
+// 语音日记
+// journal_entry 录音结束并转写后，把文本整理为带时间戳的 Markdown 段落，可选附带关键词标签 (本地提取)
+// 和 LLM 判断的心情，插件直接追加到当天的日记即可
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::llm::response::ApiFormat;
+use crate::llm::{LLMError, Priority, StreamConfig};
+use crate::utils::keywords::{self, KeywordMethod};
+use crate::utils::language::TextLocale;
+
+/// 默认的时间格式
+const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+/// 最多提取的关键词数
+pub const MAX_KEYWORDS: usize = 20;
+
+/// 心情的最大长度 (字符数)
+const MAX_MOOD_CHARS: usize = 40;
+
+/// 默认的心情提示词
+const DEFAULT_MOOD_PROMPT: &str = "Identify the overall mood of the journal entry provided by the user. \
+Reply in the language of the entry with one emoji followed by one or two words describing the mood, and nothing else.";
+
+/// 心情判断的 LLM 配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct MoodConfig {
+    /// API 端点
+    pub endpoint: String,
+    /// 请求头 (如 Authorization)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 模型名称
+    pub model: String,
+    #[serde(default)]
+    pub api_format: ApiFormat,
+    /// 替换默认的系统提示词
+    #[serde(default)]
+    pub prompt: Option<String>,
+}
+
+/// 日记格式选项 (journal_entry 消息中的字段)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JournalOptions {
+    /// 时间格式 (strftime，默认 `%H:%M`)
+    #[serde(default)]
+    pub time_format: Option<String>,
+    /// 提取的关键词数 (0 为不提取)，以标签的形式附在段落末尾
+    #[serde(default)]
+    pub keywords: usize,
+    /// 心情判断 (省略时不判断)
+    #[serde(default)]
+    pub mood: Option<MoodConfig>,
+    /// 替换默认格式的模板，可以使用 `{{date}}`、`{{time}}`、`{{text}}`、`{{mood}}`、`{{keywords}}` 和 `{{tags}}`
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+impl JournalOptions {
+    /// 检查选项
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(format) = &self.time_format {
+            if format.is_empty() || StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                return Err(format!("无效的时间格式: {}", format));
+            }
+        }
+        if self.keywords > MAX_KEYWORDS {
+            return Err(format!("keywords 超出范围: {} (最多 {})", self.keywords, MAX_KEYWORDS));
+        }
+        if self.mood.as_ref().is_some_and(|mood| mood.endpoint.is_empty()) {
+            return Err("mood.endpoint 不能为空".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 日记段落 (journal_entry 事件的内容)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JournalEntry {
+    /// 可以直接追加到日记的 Markdown (转写为空时为空字符串)
+    pub markdown: String,
+    /// 转写文本
+    pub text: String,
+    /// 录音开始的日期 (YYYY-MM-DD，用于定位当天的日记)
+    pub date: String,
+    /// 按 time_format 格式化的录音开始时间
+    pub time: String,
+    /// 录音开始时间 (RFC 3339)
+    pub timestamp: String,
+    pub keywords: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mood: Option<String>,
+    /// 心情判断失败的原因 (不影响段落的生成)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mood_error: Option<String>,
+}
+
+/// 由转写文本生成日记段落
+pub async fn compose(
+    options: &JournalOptions,
+    started_at: DateTime<Local>,
+    text: &str,
+    request_id: Option<String>,
+) -> JournalEntry {
+    let text = text.trim();
+    let keywords = if options.keywords > 0 && !text.is_empty() {
+        keywords::extract(text, KeywordMethod::Tfidf, TextLocale::Auto, options.keywords)
+            .into_iter()
+            .map(|keyword| keyword.keyword)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let (mood, mood_error) = match &options.mood {
+        Some(config) if !text.is_empty() => match detect_mood(config, text, request_id).await {
+            Ok(mood) => (mood, None),
+            Err(e) => (None, Some(e.to_string())),
+        },
+        _ => (None, None),
+    };
+
+    let time = started_at.format(options.time_format.as_deref().unwrap_or(DEFAULT_TIME_FORMAT)).to_string();
+    let date = started_at.format("%Y-%m-%d").to_string();
+    let mut entry = JournalEntry {
+        markdown: String::new(),
+        text: text.to_string(),
+        date,
+        time,
+        timestamp: started_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        keywords,
+        mood,
+        mood_error,
+    };
+    if !text.is_empty() {
+        entry.markdown = format(&entry, options.template.as_deref());
+    }
+    entry
+}
+
+/// 排版日记段落：默认为 `### 时间 · 心情`、正文和标签行
+fn format(entry: &JournalEntry, template: Option<&str>) -> String {
+    let tags = entry.keywords.iter().map(|keyword| tag(keyword)).collect::<Vec<_>>().join(" ");
+    if let Some(template) = template {
+        return crate::workflow::render(template, |name| match name {
+            "date" => Some(entry.date.clone()),
+            "time" => Some(entry.time.clone()),
+            "text" => Some(entry.text.clone()),
+            "mood" => Some(entry.mood.clone().unwrap_or_default()),
+            "keywords" => Some(entry.keywords.join(", ")),
+            "tags" => Some(tags.clone()),
+            _ => None,
+        });
+    }
+    let mut markdown = format!("### {}", entry.time);
+    if let Some(mood) = &entry.mood {
+        markdown.push_str(" · ");
+        markdown.push_str(mood);
+    }
+    markdown.push_str("\n\n");
+    markdown.push_str(&entry.text);
+    markdown.push('\n');
+    if !tags.is_empty() {
+        markdown.push('\n');
+        markdown.push_str(&tags);
+        markdown.push('\n');
+    }
+    markdown
+}
+
+/// 关键词转为 Obsidian 标签 (空白替换为 `-`，去掉标签中不允许的标点)
+fn tag(keyword: &str) -> String {
+    let body: String = keyword
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+        .collect();
+    format!("#{}", body)
+}
+
+/// 请求 LLM 判断心情
+async fn detect_mood(config: &MoodConfig, text: &str, request_id: Option<String>) -> Result<Option<String>, LLMError> {
+    let prompt = config.prompt.as_deref().unwrap_or(DEFAULT_MOOD_PROMPT);
+    let reply = crate::llm::complete(StreamConfig {
+        endpoint: config.endpoint.clone(),
+        headers: config.headers.clone(),
+        body: crate::llm::prompt_body(config.api_format, &config.model, prompt, text),
+        api_format: config.api_format,
+        request_id,
+        priority: Priority::Background,
+        sinks: Vec::new(),
+    })
+    .await?;
+    Ok(parse_mood(&reply))
+}
+
+/// 取回复的第一行非空文本作为心情 (去掉引号和句末标点，过长时截断)
+fn parse_mood(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let mood = line
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '“' | '”' | '`' | '*'))
+        .trim_end_matches(['.', '。', '!', '！'])
+        .trim();
+    if mood.is_empty() {
+        return None;
+    }
+    Some(mood.chars().take(MAX_MOOD_CHARS).collect())
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry(mood: Option<&str>, keywords: &[&str]) -> JournalEntry {
+        JournalEntry {
+            markdown: String::new(),
+            text: "和设计组讨论了发布计划".to_string(),
+            date: "2026-10-16".to_string(),
+            time: "21:05".to_string(),
+            timestamp: "2026-10-16T21:05:00+08:00".to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            mood: mood.map(str::to_string),
+            mood_error: None,
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format(&entry(None, &[]), None), "### 21:05\n\n和设计组讨论了发布计划\n");
+        assert_eq!(
+            format(&entry(Some("😌 平静"), &["发布计划", "design team"]), None),
+            "### 21:05 · 😌 平静\n\n和设计组讨论了发布计划\n\n#发布计划 #design-team\n"
+        );
+        assert_eq!(
+            format(&entry(None, &["发布计划"]), Some("- {{time}} {{text}} {{tags}}{{mood}} {{unknown}}")),
+            "- 21:05 和设计组讨论了发布计划 #发布计划 {{unknown}}"
+        );
+    }
+
+    #[test]
+    fn test_parse_mood() {
+        assert_eq!(parse_mood("\n  \"😊 Excited.\"  \nbecause ...").as_deref(), Some("😊 Excited"));
+        assert_eq!(parse_mood("   \n"), None);
+        assert_eq!(parse_mood(&"很".repeat(100)).map(|m| m.chars().count()), Some(MAX_MOOD_CHARS));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(JournalOptions::default().validate().is_ok());
+        let options = |value: serde_json::Value| serde_json::from_value::<JournalOptions>(value).unwrap().validate();
+        assert!(options(serde_json::json!({ "time_format": "%H:%M:%S", "keywords": 5 })).is_ok());
+        assert!(options(serde_json::json!({ "time_format": "%Q" })).is_err());
+        assert!(options(serde_json::json!({ "keywords": MAX_KEYWORDS + 1 })).is_err());
+        assert!(options(serde_json::json!({ "mood": { "endpoint": "", "model": "m" } })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compose() {
+        let started_at = Local.with_ymd_and_hms(2026, 10, 16, 7, 30, 0).unwrap();
+        let options = JournalOptions { time_format: Some("%H:%M".to_string()), keywords: 2, ..Default::default() };
+        let entry = compose(&options, started_at, "  今天整理了发布计划，发布计划下周评审。 ", None).await;
+        assert_eq!((entry.date.as_str(), entry.time.as_str()), ("2026-10-16", "07:30"));
+        assert!(entry.markdown.starts_with("### 07:30\n\n今天整理了发布计划"));
+        assert!(entry.keywords.iter().any(|k| k == "发布计划" || k == "发布"));
+
+        let empty = compose(&options, started_at, "  ", None).await;
+        assert_eq!((empty.markdown.as_str(), empty.keywords.len()), ("", 0));
+    }
+}
@@ -0,0 +1,88 @@
+// 转写语言检查
+// 配置 expected_language 时，对转写结果做语言检测，与预期不符时发送 language_mismatch 事件。
+// 选错 ASR 语言配置 (如中文引擎收到英文录音) 时结果常是另一种语言的音译或乱码，插件可以据此提示用户
+
+use serde::Serialize;
+
+use crate::utils::language::LanguageDetector;
+
+/// 参与检测的最少字符数 (不含空白)，过短的文本检测结果不可靠
+const MIN_CHARS: usize = 8;
+
+/// 检测结果的最低置信度
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// 语言不符 (language_mismatch 事件的内容)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageMismatch {
+    /// 预期的语言 (配置中的 expected_language)
+    pub expected: String,
+    /// 检测到的语言 (ISO 639-1)
+    pub detected: String,
+    /// 检测的置信度
+    pub confidence: f64,
+}
+
+/// 语言代码的主标签 (`zh-CN` → `zh`)
+fn primary_tag(language: &str) -> String {
+    language.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase()
+}
+
+/// 是否为有效的语言代码 (主标签为 2 到 3 个字母)
+pub fn is_valid_language(language: &str) -> bool {
+    let primary = primary_tag(language);
+    (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// 检查转写结果的语言；文本过短、无法判断或与预期一致时返回 None
+pub fn check(expected: &str, text: &str) -> Option<LanguageMismatch> {
+    if text.chars().filter(|c| !c.is_whitespace()).count() < MIN_CHARS {
+        return None;
+    }
+    let result = LanguageDetector::new().detect(text);
+    if result.language == "und" || result.confidence < MIN_CONFIDENCE {
+        return None;
+    }
+    if result.language == primary_tag(expected) {
+        return None;
+    }
+    Some(LanguageMismatch {
+        expected: expected.to_string(),
+        detected: result.language,
+        confidence: result.confidence,
+    })
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check("zh-CN", "明天下午三点在会议室开会讨论预算"), None);
+        assert_eq!(check("en", "The meeting is moved to three o'clock tomorrow afternoon"), None);
+
+        let mismatch = check("zh", "The meeting is moved to three o'clock tomorrow afternoon").unwrap();
+        assert_eq!((mismatch.expected.as_str(), mismatch.detected.as_str()), ("zh", "en"));
+        let mismatch = check("EN", "明天下午三点在会议室开会讨论预算").unwrap();
+        assert_eq!(mismatch.detected, "zh");
+
+        // 过短的文本不检查
+        assert_eq!(check("zh", "OK thanks"), None);
+        assert_eq!(check("zh", ""), None);
+    }
+
+    #[test]
+    fn test_is_valid_language() {
+        assert!(is_valid_language("zh"));
+        assert!(is_valid_language("zh-Hans"));
+        assert!(is_valid_language("yue"));
+        assert!(!is_valid_language(""));
+        assert!(!is_valid_language("chinese"));
+        assert!(!is_valid_language("z1"));
+    }
+}
@@ -1,50 +1,77 @@
 // Voice 模块
-// 提供语音录制和 ASR 转录功能
+// 提供语音录制和 ASR 转录功能，以及录音转写后生成日记段落的语音日记 (journal_entry)
 
 pub mod audio;
 pub mod asr;
 pub mod beep;
 pub mod config;
+pub mod journal;
+pub mod language_check;
+pub mod recordings;
 
-use crate::router::{ModuleHandler, ModuleMessage, ModuleType, RouterError, ServerResponse};
+use crate::jobs::{self, Job, JobGuard, JobKind};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
 use crate::server::WsSender;
+use crate::utils::language::TextLocale;
+use crate::utils::numbers;
+use crate::watchdog::{self, TaskKind, WatchedTask};
+use chrono::{DateTime, Local};
 use futures_util::SinkExt;
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot, Mutex as TokioMutex};
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 
 use audio::{
     AudioRecorder,
     RecordingMode as AudioRecordingMode,
     StreamingRecorder,
     AudioData,
+    ChunkLayout,
+    decode_wav,
     list_input_devices,
+    quality,
+    timeline,
 };
-use asr::{RaceStrategy, TranscriptionResult, ASRError, RealtimeTaskResult, RealtimeTranscriptionTask};
+use asr::{RaceStrategy, TranscriptionResult, ASRError, RealtimeTaskResult, RealtimeTranscriptionTask, EngineSwitch, EngineSwitchCallback, LatencyWarning};
+use asr::capabilities::ProviderCapabilities;
 use beep::BeepPlayer;
 use config::{ASRConfig, ASRMode};
+use journal::JournalOptions;
 
 /// 日志宏
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] [Voice] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Info, "Voice", format!($($arg)*));
     };
 }
 
 macro_rules! log_error {
     ($($arg:tt)*) => {
-        eprintln!("[ERROR] [Voice] {}", format!($($arg)*));
+        crate::logging::write(crate::logging::Level::Error, "Voice", format!($($arg)*));
     };
 }
 
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if cfg!(debug_assertions) {
-            eprintln!("[DEBUG] [Voice] {}", format!($($arg)*));
+            crate::logging::write(crate::logging::Level::Debug, "Voice", format!($($arg)*));
         }
     };
 }
 
+/// 创建 Voice 模块错误
+fn voice_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Voice, code, message)
+}
+
+/// 检查 toggle 录音是否已静音足够久的间隔
+const AUTO_STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
 // ============================================================================
 // 录音模式
 // ============================================================================
@@ -116,6 +143,12 @@ struct ConnectionState {
     beep_player: BeepPlayer,
     /// 音频级别发送器
     audio_level_tx: Option<mpsc::UnboundedSender<AudioLevelData>>,
+    /// 开始录音请求的 ID (回显到本次录音产生的事件上)
+    request_id: Option<String>,
+    /// 语音日记录音的格式选项和开始时间 (转写完成后生成日记段落)
+    journal: Option<(JournalOptions, DateTime<Local>)>,
+    /// 录音在任务列表中的登记 (按开始录音请求的 ID 取消)
+    job: Option<JobGuard>,
 }
 
 impl ConnectionState {
@@ -131,6 +164,9 @@ impl ConnectionState {
             stop_signal: None,
             beep_player: BeepPlayer::new(),
             audio_level_tx: None,
+            request_id: None,
+            journal: None,
+            job: None,
         }
     }
 }
@@ -142,51 +178,85 @@ impl ConnectionState {
 /// Voice 模块处理器
 /// 
 /// 管理语音录制和 ASR 转录
+/// 
+/// 克隆共享同一份状态 (静音自动停止等后台任务持有克隆)
+#[derive(Clone)]
 pub struct VoiceHandler {
-    /// 连接状态
-    state: TokioMutex<ConnectionState>,
+    /// 连接状态 (实时转录任务停滞时由任务自身释放)
+    state: Arc<TokioMutex<ConnectionState>>,
     /// WebSocket 发送器
-    ws_sender: TokioMutex<Option<WsSender>>,
+    ws_sender: Arc<TokioMutex<Option<WsSender>>>,
+    /// 当前录音的客户端连接 (录音事件只发送给发起录音的连接)
+    client: Arc<TokioMutex<Option<WsSender>>>,
 }
 
 impl VoiceHandler {
     /// 创建新的 Voice 处理器
     pub fn new() -> Self {
         Self {
-            state: TokioMutex::new(ConnectionState::new()),
-            ws_sender: TokioMutex::new(None),
+            state: Arc::new(TokioMutex::new(ConnectionState::new())),
+            ws_sender: Arc::new(TokioMutex::new(None)),
+            client: Arc::new(TokioMutex::new(None)),
         }
     }
     
-    /// 设置 WebSocket 发送器
-    pub async fn set_ws_sender(&self, sender: WsSender) {
-        let mut ws_sender = self.ws_sender.lock().await;
-        *ws_sender = Some(sender);
+    /// 获取事件发送器：优先使用发起录音的连接，否则使用默认发送器
+    async fn event_sender(&self) -> Option<WsSender> {
+        if let Some(client) = self.client.lock().await.clone() {
+            return Some(client);
+        }
+        self.ws_sender.lock().await.clone()
     }
     
     /// 发送消息给客户端
-    async fn send_message(&self, msg_type: &str, payload: serde_json::Value) -> Result<(), RouterError> {
-        let ws_sender = self.ws_sender.lock().await;
-        if let Some(ref sender) = *ws_sender {
-            let response = serde_json::json!({
-                "module": "voice",
-                "type": msg_type,
-            });
-            
-            // 合并 payload 到 response
-            let mut response = response.as_object().unwrap().clone();
-            if let serde_json::Value::Object(payload_obj) = payload {
-                for (k, v) in payload_obj {
-                    response.insert(k, v);
+    /// 
+    /// `request_id` 为产生该事件的请求 ID，存在时附加到消息上
+    async fn send_message(
+        &self,
+        msg_type: &str,
+        payload: serde_json::Value,
+        request_id: Option<&str>,
+    ) -> Result<(), RouterError> {
+        if let Some(sender) = self.event_sender().await {
+            send_event(&sender, msg_type, payload, request_id).await?;
+        }
+        Ok(())
+    }
+
+    /// 发送转录结果 (附带录音质量报告和语音活动时间线)；结果语言与 expected_language 不符时发送 language_mismatch 事件，
+    /// 语音日记录音还会生成日记段落并发送 journal_entry 事件
+    async fn complete_transcription(
+        &self,
+        mut payload: serde_json::Value,
+        audio_data: &AudioData,
+        asr_config: &ASRConfig,
+        journal: Option<(JournalOptions, DateTime<Local>)>,
+        request_id: Option<&str>,
+    ) -> Result<(), RouterError> {
+        let text = payload["text"].as_str().unwrap_or_default().to_string();
+        payload["quality"] = serde_json::to_value(quality::analyze(audio_data)).unwrap_or_default();
+        payload["timeline"] = serde_json::to_value(timeline::speech_intervals(audio_data)).unwrap_or_default();
+        // 保存失败不影响转写结果，只是不附带 recording_path
+        if let Some(dir) = asr_config.save_recordings_dir.as_deref().filter(|dir| !dir.is_empty() && !audio_data.is_empty()) {
+            match recordings::save(dir, audio_data, asr_config.save_recordings_format, Local::now()).await {
+                Ok(path) => {
+                    log_info!("录音已保存: {}", path.display());
+                    payload["recording_path"] = serde_json::Value::String(path.display().to_string());
+                }
+                Err(e) => {
+                    log_error!("保存录音失败: {}", e);
                 }
             }
-            
-            let json = serde_json::to_string(&response)
-                .map_err(|e| RouterError::ModuleError(format!("JSON 序列化失败: {}", e)))?;
-            
-            let mut sender = sender.lock().await;
-            sender.send(tokio_tungstenite::tungstenite::Message::Text(json.into())).await
-                .map_err(|e| RouterError::ModuleError(format!("发送消息失败: {}", e)))?;
+        }
+        let mismatch = language_mismatch(asr_config, &payload);
+        self.send_message("transcription_complete", payload, request_id).await?;
+        if let Some(warning) = mismatch {
+            self.send_message("language_mismatch", warning, request_id).await?;
+        }
+        if let Some((options, started_at)) = journal {
+            let entry = journal::compose(&options, started_at, &text, request_id.map(str::to_string)).await;
+            log_info!("日记段落已生成: {} 个字符", entry.markdown.chars().count());
+            self.send_message("journal_entry", serde_json::to_value(&entry).unwrap_or_default(), request_id).await?;
         }
         Ok(())
     }
@@ -196,6 +266,9 @@ impl VoiceHandler {
         &self,
         mode: RecordingMode,
         asr_config: ASRConfig,
+        request_id: Option<String>,
+        client: Option<WsSender>,
+        journal: Option<JournalOptions>,
     ) -> Result<Option<ServerResponse>, RouterError> {
         log_info!("收到开始录音命令，模式: {:?}", mode);
         
@@ -205,8 +278,11 @@ impl VoiceHandler {
         
         // 检查是否已在录音
         if state.is_recording {
-            return Err(RouterError::ModuleError("已在录音中".to_string()));
+            return Err(voice_error(ErrorCode::AlreadyRecording, "已在录音中").into());
         }
+        asr_config.validate_recording()
+            .map_err(|e| voice_error(ErrorCode::InvalidConfig, e.to_string()))?;
+        *self.client.lock().await = client;
         
         // 创建音频级别 channel
         let (audio_level_tx, mut audio_level_rx) = mpsc::unbounded_channel::<AudioLevelData>();
@@ -217,39 +293,51 @@ impl VoiceHandler {
         if is_realtime_mode {
             log_info!("使用 Realtime 模式，启动流式录音器");
             
+            // 主引擎配置无效 (如音频块时长超出范围) 时实时会话无法建立，提前拒绝
+            asr_config.primary.validate()
+                .map_err(|e| voice_error(ErrorCode::InvalidConfig, e.to_string()))?;
+            
             // 创建流式录音器
             let mut streaming_recorder = StreamingRecorder::new()
-                .map_err(|e| RouterError::ModuleError(format!("创建流式录音器失败: {}", e)))?;
+                .map_err(|e| voice_error(ErrorCode::DeviceError, format!("创建流式录音器失败: {}", e)))?;
             
             // 设置音频级别回调
             let tx = audio_level_tx.clone();
             streaming_recorder.set_level_callback(move |level, waveform| {
                 let _ = tx.send(AudioLevelData { level, waveform });
             });
+            streaming_recorder.set_noise_suppression(asr_config.enable_noise_suppression);
+            streaming_recorder.set_vad_aggressiveness(asr_config.vad_aggressiveness);
             
             // 启动流式录音，获取音频块接收通道
             let chunk_rx = streaming_recorder.start_streaming(
                 mode.clone().into(),
                 recording_device.as_deref(),
                 compression_level,
+                ChunkLayout::for_provider(&asr_config.primary),
             )
-                .map_err(|e| RouterError::ModuleError(format!("启动流式录音失败: {}", e)))?;
+                .map_err(|e| voice_error(ErrorCode::DeviceError, format!("启动流式录音失败: {}", e)))?;
             
             // 创建实时转录任务
             let primary_config = asr_config.primary.clone();
-            let ws_sender = self.ws_sender.lock().await.clone();
+            let ws_sender = self.event_sender().await;
             
             // 创建部分结果回调
+            let partial_request_id = request_id.clone();
             let partial_callback: Option<Box<dyn Fn(&str) + Send + 'static>> = if let Some(sender) = ws_sender.clone() {
                 Some(Box::new(move |text: &str| {
                     let text_owned = text.to_string();
                     let sender = sender.clone();
+                    let request_id = partial_request_id.clone();
                     tokio::spawn(async move {
-                        let msg = serde_json::json!({
+                        let mut msg = serde_json::json!({
                             "module": "voice",
                             "type": "transcription_progress",
                             "partial_text": text_owned,
                         });
+                        if let Some(id) = request_id {
+                            msg["request_id"] = serde_json::Value::String(id);
+                        }
                         let json = serde_json::to_string(&msg).unwrap();
                         let mut s = sender.lock().await;
                         let _ = s.send(tokio_tungstenite::tungstenite::Message::Text(json.into())).await;
@@ -265,11 +353,54 @@ impl VoiceHandler {
                 chunk_rx,
                 partial_callback,
             );
+            let heartbeat = watchdog::watch(WatchedTask::new(TaskKind::RealtimeAsr).with_request_id(request_id.clone()));
+            let stalled = heartbeat.stall_token();
+            let mut task = task.with_heartbeat(heartbeat);
             
-            // 启动实时转录任务
-            let task_handle = tokio::spawn(async move {
-                task.run_with_details().await
-            });
+            // 备用引擎支持实时模式时，主引擎失效后在录音中途切换过去
+            if let Some(fallback) = asr_config.fallback.clone().filter(|f| asr_config.enable_fallback && f.mode == ASRMode::Realtime) {
+                let switch_callback: Option<EngineSwitchCallback> = ws_sender.clone().map(|sender| {
+                    let request_id = request_id.clone();
+                    Box::new(move |switch: &EngineSwitch| {
+                        let sender = sender.clone();
+                        let request_id = request_id.clone();
+                        let payload = serde_json::to_value(switch).unwrap_or_default();
+                        tokio::spawn(async move {
+                            let _ = send_event(&sender, "engine_switched", payload, request_id.as_deref()).await;
+                        });
+                    }) as EngineSwitchCallback
+                });
+                task = task.with_fallback(fallback, switch_callback);
+            }
+            if let Some(sender) = ws_sender.clone() {
+                let request_id = request_id.clone();
+                task = task.with_latency_warning(asr_config.partial_latency_warning_ms, Box::new(move |warning: &LatencyWarning| {
+                    let sender = sender.clone();
+                    let request_id = request_id.clone();
+                    let payload = serde_json::to_value(warning).unwrap_or_default();
+                    tokio::spawn(async move {
+                        let _ = send_event(&sender, "latency_warning", payload, request_id.as_deref()).await;
+                    });
+                }));
+            }
+            
+            // 启动实时转录任务，看门狗判定停滞时中止任务并释放录音状态
+            let stall_state = Arc::clone(&self.state);
+            let stall_request_id = request_id.clone();
+            let task_handle = tokio::spawn(crate::audit::inherit(async move {
+                tokio::select! {
+                    result = task.run_with_details() => result,
+                    _ = stalled.cancelled() => {
+                        release_stalled_recording(&stall_state, ws_sender, stall_request_id).await;
+                        RealtimeTaskResult::Failed {
+                            error: ASRError::WebSocketError("实时转录长时间无进度，已中止".to_string()),
+                            engine_name: "unknown".to_string(),
+                            chunks_sent: 0,
+                            samples_sent: 0,
+                        }
+                    }
+                }
+            }));
             
             state.asr_config = Some(asr_config.clone());
             state.is_recording = true;
@@ -285,13 +416,14 @@ impl VoiceHandler {
             
             // 创建普通录音器
             let mut recorder = AudioRecorder::new()
-                .map_err(|e| RouterError::ModuleError(format!("创建录音器失败: {}", e)))?;
+                .map_err(|e| voice_error(ErrorCode::DeviceError, format!("创建录音器失败: {}", e)))?;
             
             // 设置音频级别回调
             let tx = audio_level_tx.clone();
             recorder.set_level_callback(move |level, waveform| {
                 let _ = tx.send(AudioLevelData { level, waveform });
             });
+            recorder.set_noise_suppression(asr_config.enable_noise_suppression);
             
             // 启动录音
             recorder.start(
@@ -299,7 +431,7 @@ impl VoiceHandler {
                 recording_device.as_deref(),
                 compression_level,
             )
-                .map_err(|e| RouterError::ModuleError(format!("启动录音失败: {}", e)))?;
+                .map_err(|e| voice_error(ErrorCode::DeviceError, format!("启动录音失败: {}", e)))?;
             
             state.asr_config = Some(asr_config.clone());
             state.is_recording = true;
@@ -309,16 +441,29 @@ impl VoiceHandler {
             state.recorder = Some(recorder);
         }
         
+        state.request_id = request_id.clone();
+        state.journal = journal.map(|options| (options, Local::now()));
+        state.job = Some(jobs::register(Job::new(JobKind::Recording).with_request_id(request_id.clone())));
+        
         // 根据配置设置音频反馈
         state.beep_player.set_enabled(asr_config.enable_audio_feedback);
         
         // 播放开始提示音
         state.beep_player.play_start();
         
+        let started = state.recording_start_time;
         drop(state);
         
+        // toggle 录音静音足够久时自动停止
+        if let (RecordingMode::Toggle, Some(started)) = (&mode, started) {
+            if asr_config.auto_stop_silence_ms > 0 {
+                let silence = Duration::from_millis(asr_config.auto_stop_silence_ms);
+                tokio::spawn(crate::audit::inherit(self.clone().auto_stop_on_silence(started, silence)));
+            }
+        }
+        
         // 启动音频级别转发任务
-        let ws_sender = self.ws_sender.lock().await.clone();
+        let ws_sender = self.event_sender().await;
         if let Some(sender) = ws_sender {
             tokio::spawn(async move {
                 while let Some(data) = audio_level_rx.recv().await {
@@ -340,22 +485,58 @@ impl VoiceHandler {
         // 发送录音开始状态
         self.send_message("recording_state", serde_json::json!({
             "state": "started"
-        })).await?;
+        }), request_id.as_deref()).await?;
         
         Ok(None)
     }
 
     /// 处理停止录音命令
-    async fn handle_stop_recording(&self) -> Result<Option<ServerResponse>, RouterError> {
+    /// 
+    /// 转录结果优先回显停止请求的 ID，未提供时回显开始录音请求的 ID
+    async fn handle_stop_recording(&self, request_id: Option<String>) -> Result<Option<ServerResponse>, RouterError> {
         log_info!("收到停止录音命令");
+        self.stop_recording(request_id, "stopped").await
+    }
+    
+    /// toggle 录音连续 `silence` 没有语音时自动停止，之后与 stop_recording 相同 (录音状态为 auto_stopped)
+    async fn auto_stop_on_silence(self, started: Instant, silence: Duration) {
+        let mut interval = tokio::time::interval(AUTO_STOP_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let state = self.state.lock().await;
+            // 录音已结束，或已经是另一次录音
+            if !state.is_recording || state.recording_start_time != Some(started) {
+                return;
+            }
+            let silent_for = match (&state.recorder, &state.streaming_recorder) {
+                (Some(recorder), _) => recorder.silent_for(),
+                (None, Some(streaming_recorder)) => streaming_recorder.silent_for(),
+                (None, None) => return,
+            };
+            if silent_for >= silence {
+                break;
+            }
+        }
+        log_info!("连续 {}ms 没有语音，自动停止录音", silence.as_millis());
+        if let Err(e) = self.stop_recording(None, "auto_stopped").await {
+            log_error!("自动停止录音失败: {}", e);
+        }
+    }
+    
+    /// 停止录音并转写，录音状态事件为 `stop_state` (stopped 或 auto_stopped)
+    async fn stop_recording(&self, request_id: Option<String>, stop_state: &str) -> Result<Option<ServerResponse>, RouterError> {
         
         let mut state = self.state.lock().await;
         
         // 检查是否在录音
         if !state.is_recording {
-            return Err(RouterError::ModuleError("未在录音中".to_string()));
+            return Err(voice_error(ErrorCode::NotRecording, "未在录音中").into());
         }
         
+        let request_id = request_id.or_else(|| state.request_id.take());
+        let journal = state.journal.take();
+        state.job = None;
+        
         // 播放结束提示音
         state.beep_player.play_stop();
         
@@ -364,7 +545,7 @@ impl VoiceHandler {
         
         // 获取 ASR 配置
         let asr_config = state.asr_config.clone()
-            .ok_or_else(|| RouterError::ModuleError("ASR 配置未设置".to_string()))?;
+            .ok_or_else(|| voice_error(ErrorCode::InvalidConfig, "ASR 配置未设置"))?;
         
         // 检查是否是 realtime 模式
         let is_realtime_mode = state.streaming_recorder.is_some();
@@ -381,9 +562,9 @@ impl VoiceHandler {
             // 停止流式录音并获取完整音频数据 (用于回退)
             let audio_data = if let Some(ref mut streaming_recorder) = state.streaming_recorder {
                 streaming_recorder.stop_streaming()
-                    .map_err(|e| RouterError::ModuleError(format!("停止流式录音失败: {}", e)))?
+                    .map_err(|e| voice_error(ErrorCode::DeviceError, format!("停止流式录音失败: {}", e)))?
             } else {
-                return Err(RouterError::ModuleError("流式录音器未初始化".to_string()));
+                return Err(voice_error(ErrorCode::Internal, "流式录音器未初始化").into());
             };
             
             // 获取实时转录任务句柄
@@ -397,8 +578,8 @@ impl VoiceHandler {
             
             // 发送录音停止状态
             self.send_message("recording_state", serde_json::json!({
-                "state": "stopped"
-            })).await?;
+                "state": stop_state
+            }), request_id.as_deref()).await?;
             
             // 等待实时转录任务完成
             let realtime_result = if let Some(task_handle) = realtime_task {
@@ -425,12 +606,7 @@ impl VoiceHandler {
                         &result.text
                     );
                     
-                    self.send_message("transcription_complete", serde_json::json!({
-                        "text": result.text,
-                        "engine": result.engine,
-                        "used_fallback": false,
-                        "duration_ms": result.duration_ms,
-                    })).await?;
+                    self.complete_transcription(transcription_payload(result, &asr_config), &audio_data, &asr_config, journal, request_id.as_deref()).await?;
                 }
                 Some(RealtimeTaskResult::Failed { error, engine_name, .. }) => {
                     log_error!("实时转录失败 ({}): {}，尝试回退到 HTTP 模式", engine_name, error);
@@ -447,23 +623,19 @@ impl VoiceHandler {
                                 &result.text
                             );
                             
-                            self.send_message("transcription_complete", serde_json::json!({
-                                "text": result.text,
-                                "engine": result.engine,
-                                "used_fallback": true,
-                                "duration_ms": result.duration_ms,
-                            })).await?;
+                            self.complete_transcription(transcription_payload(result, &asr_config), &audio_data, &asr_config, journal, request_id.as_deref()).await?;
                         }
                         Err(fallback_error) => {
                             log_error!("HTTP 回退也失败: {}", fallback_error);
                             
                             self.send_message("error", serde_json::json!({
-                                "code": "TRANSCRIPTION_FAILED",
+                                "code": ErrorCode::TranscriptionFailed,
+                                "retryable": ErrorCode::TranscriptionFailed.is_retryable(),
                                 "message": format!(
                                     "实时转录失败: {}; HTTP 回退也失败: {}",
                                     error, fallback_error
                                 ),
-                            })).await?;
+                            }), request_id.as_deref()).await?;
                         }
                     }
                 }
@@ -482,23 +654,19 @@ impl VoiceHandler {
                                 &result.text
                             );
                             
-                            self.send_message("transcription_complete", serde_json::json!({
-                                "text": result.text,
-                                "engine": result.engine,
-                                "used_fallback": true,
-                                "duration_ms": result.duration_ms,
-                            })).await?;
+                            self.complete_transcription(transcription_payload(result, &asr_config), &audio_data, &asr_config, journal, request_id.as_deref()).await?;
                         }
                         Err(fallback_error) => {
                             log_error!("HTTP 回退也失败: {}", fallback_error);
                             
                             self.send_message("error", serde_json::json!({
-                                "code": "TRANSCRIPTION_FAILED",
+                                "code": ErrorCode::TranscriptionFailed,
+                                "retryable": ErrorCode::TranscriptionFailed.is_retryable(),
                                 "message": format!(
                                     "实时转录任务异常; HTTP 回退也失败: {}",
                                     fallback_error
                                 ),
-                            })).await?;
+                            }), request_id.as_deref()).await?;
                         }
                     }
                 }
@@ -509,9 +677,9 @@ impl VoiceHandler {
             
             // 停止录音并获取音频数据
             let audio_data = if let Some(ref mut recorder) = state.recorder {
-                recorder.stop().map_err(|e| RouterError::ModuleError(format!("停止录音失败: {}", e)))?
+                recorder.stop().map_err(|e| voice_error(ErrorCode::DeviceError, format!("停止录音失败: {}", e)))?
             } else {
-                return Err(RouterError::ModuleError("录音器未初始化".to_string()));
+                return Err(voice_error(ErrorCode::Internal, "录音器未初始化").into());
             };
             
             // 更新状态
@@ -522,18 +690,18 @@ impl VoiceHandler {
             
             // 发送录音停止状态
             self.send_message("recording_state", serde_json::json!({
-                "state": "stopped"
-            })).await?;
+                "state": stop_state
+            }), request_id.as_deref()).await?;
             
             // 检查音频数据是否为空
             if audio_data.is_empty() {
                 log_info!("录音数据为空，跳过转录");
-                self.send_message("transcription_complete", serde_json::json!({
+                self.complete_transcription(serde_json::json!({
                     "text": "",
                     "engine": "none",
                     "used_fallback": false,
                     "duration_ms": 0,
-                })).await?;
+                }), &audio_data, &asr_config, journal, request_id.as_deref()).await?;
                 return Ok(None);
             }
             
@@ -552,20 +720,16 @@ impl VoiceHandler {
                         &result.text
                     );
                     
-                    self.send_message("transcription_complete", serde_json::json!({
-                        "text": result.text,
-                        "engine": result.engine,
-                        "used_fallback": result.used_fallback,
-                        "duration_ms": result.duration_ms,
-                    })).await?;
+                    self.complete_transcription(transcription_payload(result, &asr_config), &audio_data, &asr_config, journal, request_id.as_deref()).await?;
                 }
                 Err(e) => {
                     log_error!("转录失败: {}", e);
                     
                     self.send_message("error", serde_json::json!({
-                        "code": "TRANSCRIPTION_FAILED",
+                        "code": ErrorCode::TranscriptionFailed,
+                        "retryable": ErrorCode::TranscriptionFailed.is_retryable(),
                         "message": e.to_string(),
-                    })).await?;
+                    }), request_id.as_deref()).await?;
                 }
             }
         }
@@ -574,16 +738,18 @@ impl VoiceHandler {
     }
 
     /// 处理取消录音命令
-    async fn handle_cancel_recording(&self) -> Result<Option<ServerResponse>, RouterError> {
+    async fn handle_cancel_recording(&self, request_id: Option<String>) -> Result<Option<ServerResponse>, RouterError> {
         log_info!("收到取消录音命令");
         
         let mut state = self.state.lock().await;
         
         // 检查是否在录音
         if !state.is_recording {
-            return Err(RouterError::ModuleError("未在录音中".to_string()));
+            return Err(voice_error(ErrorCode::NotRecording, "未在录音中").into());
         }
         
+        let request_id = request_id.or_else(|| state.request_id.take());
+        
         // 关闭音频级别 channel
         state.audio_level_tx = None;
         
@@ -621,12 +787,45 @@ impl VoiceHandler {
         // 更新状态
         state.is_recording = false;
         state.recording_mode = None;
+        state.journal = None;
+        state.job = None;
         drop(state);
         
         // 发送录音取消状态
         self.send_message("recording_state", serde_json::json!({
             "state": "cancelled"
-        })).await?;
+        }), request_id.as_deref()).await?;
+        
+        Ok(None)
+    }
+
+    /// 处理暂停 / 继续录音命令
+    /// 
+    /// 暂停期间的输入不计入最终音频，也不产生音频块和 audio_level 事件；实时会话靠保活帧维持。
+    /// 录音状态事件优先回显本次请求的 ID，未提供时回显开始录音请求的 ID
+    async fn handle_pause_recording(&self, pause: bool, request_id: Option<String>) -> Result<Option<ServerResponse>, RouterError> {
+        let state = self.state.lock().await;
+        if !state.is_recording {
+            return Err(voice_error(ErrorCode::NotRecording, "未在录音中").into());
+        }
+        let changed = match (&state.recorder, &state.streaming_recorder, pause) {
+            (Some(recorder), _, true) => recorder.pause(),
+            (Some(recorder), _, false) => recorder.resume(),
+            (None, Some(streaming_recorder), true) => streaming_recorder.pause(),
+            (None, Some(streaming_recorder), false) => streaming_recorder.resume(),
+            (None, None, _) => return Err(voice_error(ErrorCode::NotRecording, "未在录音中").into()),
+        };
+        let request_id = request_id.or_else(|| state.request_id.clone());
+        drop(state);
+        
+        if changed {
+            log_info!("{}", if pause { "录音已暂停" } else { "录音已继续" });
+        }
+        
+        // 重复暂停或继续时不改变状态，仍回复当前状态
+        self.send_message("recording_state", serde_json::json!({
+            "state": if pause { "paused" } else { "resumed" }
+        }), request_id.as_deref()).await?;
         
         Ok(None)
     }
@@ -649,7 +848,7 @@ impl VoiceHandler {
         request_id: Option<String>,
     ) -> Result<Option<ServerResponse>, RouterError> {
         let devices = list_input_devices()
-            .map_err(|e| RouterError::ModuleError(format!("获取录音设备失败: {}", e)))?;
+            .map_err(|e| voice_error(ErrorCode::DeviceError, format!("获取录音设备失败: {}", e)))?;
 
         let payload = serde_json::json!({
             "devices": devices,
@@ -659,14 +858,191 @@ impl VoiceHandler {
         Ok(Some(ServerResponse::new(ModuleType::Voice, "input_devices", payload)))
     }
     
+    /// 转录音频文件 (base64 编码的 WAV)，与录音状态无关
+    async fn handle_transcribe(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let asr_config: ASRConfig = msg.get_field("asr_config")
+            .ok_or_else(|| voice_error(ErrorCode::InvalidParams, "缺少 asr_config 字段"))?;
+        let audio: String = msg.get_field("audio")
+            .ok_or_else(|| voice_error(ErrorCode::InvalidParams, "缺少 audio 字段"))?;
+        let audio_data = decode_audio(&audio)?;
+        
+        log_info!("转录音频文件，音频时长: {}ms", audio_data.duration_ms);
+        let cancel = CancellationToken::new();
+        let _job = jobs::register(
+            Job::new(JobKind::Transcription)
+                .with_request_id(msg.request_id())
+                .with_detail(format!("{}ms", audio_data.duration_ms))
+                .with_cancel(cancel.clone()),
+        );
+        let result = tokio::select! {
+            result = perform_transcription(&audio_data, &asr_config) => result
+                .map_err(|e| voice_error(ErrorCode::TranscriptionFailed, e.to_string()))?,
+            _ = cancel.cancelled() => return Err(voice_error(ErrorCode::Cancelled, "转录已终止").into()),
+        };
+        
+        let mut payload = transcription_payload(result, &asr_config);
+        payload["quality"] = serde_json::to_value(quality::analyze(&audio_data)).unwrap_or_default();
+        payload["timeline"] = serde_json::to_value(timeline::speech_intervals(&audio_data)).unwrap_or_default();
+        // 请求应答式的转写没有事件流，语言检查结果放在应答中
+        if let Some(warning) = language_mismatch(&asr_config, &payload) {
+            payload["language_mismatch"] = warning;
+        }
+        Ok(Some(ServerResponse::new(ModuleType::Voice, "transcription_complete", payload)))
+    }
+    
+    /// 处理语音日记命令
+    /// 
+    /// 没有 audio 时开始录音 (默认 toggle 模式)，stop_recording 转写后在 transcription_complete 之后发送 journal_entry 事件；
+    /// 有 audio (base64 编码的 WAV) 时直接转写并以 journal_entry 应答
+    async fn handle_journal_entry(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let options: JournalOptions = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| voice_error(ErrorCode::InvalidParams, format!("无效的 journal_entry 请求: {}", e)))?;
+        options.validate().map_err(|e| voice_error(ErrorCode::InvalidParams, e))?;
+        let asr_config: ASRConfig = msg.get_field("asr_config")
+            .ok_or_else(|| voice_error(ErrorCode::InvalidParams, "缺少 asr_config 字段"))?;
+        
+        let Some(audio) = msg.get_field::<String>("audio") else {
+            let mode = msg.get_field("mode").unwrap_or(RecordingMode::Toggle);
+            return self.handle_start_recording(mode, asr_config, msg.request_id(), msg.sender(), Some(options)).await;
+        };
+        let audio_data = decode_audio(&audio)?;
+        // 未指定录音时间时按音频时长倒推
+        let started_at = match msg.get_field::<DateTime<chrono::FixedOffset>>("recorded_at") {
+            Some(recorded_at) => recorded_at.with_timezone(&Local),
+            None => Local::now() - chrono::Duration::milliseconds(audio_data.duration_ms as i64),
+        };
+        
+        log_info!("转录日记音频，音频时长: {}ms", audio_data.duration_ms);
+        let text = if audio_data.is_empty() {
+            String::new()
+        } else {
+            let result = perform_transcription(&audio_data, &asr_config).await
+                .map_err(|e| voice_error(ErrorCode::TranscriptionFailed, e.to_string()))?;
+            post_process_text(result.text, &asr_config)
+        };
+        let entry = journal::compose(&options, started_at, &text, msg.request_id()).await;
+        Ok(Some(ServerResponse::new(ModuleType::Voice, "journal_entry", serde_json::to_value(&entry).unwrap_or_default())))
+    }
+    
     /// 检查是否正在录音
     pub async fn is_recording(&self) -> bool {
         let state = self.state.lock().await;
         state.is_recording
     }
     
+
+}
+
+impl Default for VoiceHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// ASR 供应商配置字段 (对应 ASRProviderConfig)
+const ASR_PROVIDER_FIELDS: &[FieldSpec] = &[
+    FieldSpec::required("provider", FieldKind::String).one_of(&["qwen", "doubao", "sensevoice", "whisper", "openai", "vosk"]),
+    FieldSpec::required("mode", FieldKind::String).one_of(&["realtime", "http"]),
+    FieldSpec::optional("dashscope_api_key", FieldKind::String),
+    FieldSpec::optional("app_id", FieldKind::String),
+    FieldSpec::optional("access_token", FieldKind::String),
+    FieldSpec::optional("siliconflow_api_key", FieldKind::String),
+    FieldSpec::optional("model_path", FieldKind::String),
+    FieldSpec::optional("model_size", FieldKind::String).one_of(&["tiny", "base", "small", "medium", "large"]),
+    FieldSpec::optional("base_url", FieldKind::String),
+    FieldSpec::optional("model", FieldKind::String),
+    FieldSpec::optional("api_key", FieldKind::String),
+    FieldSpec::optional("chunk_ms", FieldKind::Integer),
+    FieldSpec::optional("chunk_overlap_ms", FieldKind::Integer),
+    FieldSpec::optional("upload_format", FieldKind::String).one_of(&["wav", "opus", "flac", "mp3"]),
+];
+
+/// ASR 配置字段 (对应 ASRConfig)
+pub(crate) const ASR_CONFIG_FIELDS: &[FieldSpec] = &[
+    FieldSpec::required("primary", FieldKind::Object).with_fields(ASR_PROVIDER_FIELDS),
+    FieldSpec::optional("fallback", FieldKind::Object).with_fields(ASR_PROVIDER_FIELDS),
+    FieldSpec::required("enable_fallback", FieldKind::Boolean),
+    FieldSpec::optional("enable_audio_feedback", FieldKind::Boolean),
+    FieldSpec::optional("recording_device", FieldKind::String),
+    FieldSpec::optional("audio_compression", FieldKind::String).one_of(&["original", "medium", "minimum"]),
+    FieldSpec::optional("enable_noise_suppression", FieldKind::Boolean),
+    FieldSpec::optional("vad_aggressiveness", FieldKind::Integer),
+    FieldSpec::optional("auto_stop_silence_ms", FieldKind::Integer),
+    FieldSpec::optional("normalize_numbers", FieldKind::Boolean),
+    FieldSpec::optional("partial_latency_warning_ms", FieldKind::Integer),
+    FieldSpec::optional("word_timestamps", FieldKind::Boolean),
+    FieldSpec::optional("save_recordings_dir", FieldKind::String),
+    FieldSpec::optional("save_recordings_format", FieldKind::String).one_of(&["wav", "opus", "flac", "mp3"]),
+    FieldSpec::optional("expected_language", FieldKind::String),
+];
+
+/// 心情判断的 LLM 配置字段 (对应 MoodConfig)
+const MOOD_FIELDS: &[FieldSpec] = &[
+    FieldSpec::required("endpoint", FieldKind::String),
+    FieldSpec::required("model", FieldKind::String),
+    FieldSpec::optional("headers", FieldKind::Object),
+    FieldSpec::optional("api_format", FieldKind::String).one_of(&["chat_completions", "responses"]),
+    FieldSpec::optional("prompt", FieldKind::String),
+];
+
+/// Voice 模块支持的消息 (system/describe)
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("start_recording", &[
+        FieldSpec::required("mode", FieldKind::String).one_of(&["press", "toggle"]),
+        FieldSpec::required("asr_config", FieldKind::Object).with_fields(ASR_CONFIG_FIELDS),
+    ]),
+    MessageSpec::new("stop_recording", &[]),
+    MessageSpec::new("cancel_recording", &[]),
+    MessageSpec::new("pause_recording", &[]),
+    MessageSpec::new("resume_recording", &[]),
+    MessageSpec::new("update_config", &[
+        FieldSpec::required("asr_config", FieldKind::Object).with_fields(ASR_CONFIG_FIELDS),
+    ]),
+    MessageSpec::new("list_input_devices", &[]),
+    MessageSpec::new("get_metrics", &[]),
+    MessageSpec::new("get_provider_capabilities", &[
+        FieldSpec::optional("asr_config", FieldKind::Object).with_fields(ASR_CONFIG_FIELDS),
+    ]),
+    MessageSpec::new("transcribe", &[
+        FieldSpec::required("asr_config", FieldKind::Object).with_fields(ASR_CONFIG_FIELDS),
+        FieldSpec::required("audio", FieldKind::String),
+    ]).concurrent(),
+    MessageSpec::new("journal_entry", &[
+        FieldSpec::required("asr_config", FieldKind::Object).with_fields(ASR_CONFIG_FIELDS),
+        FieldSpec::optional("mode", FieldKind::String).one_of(&["press", "toggle"]),
+        FieldSpec::optional("audio", FieldKind::String),
+        FieldSpec::optional("recorded_at", FieldKind::String),
+        FieldSpec::optional("time_format", FieldKind::String),
+        FieldSpec::optional("keywords", FieldKind::Integer),
+        FieldSpec::optional("mood", FieldKind::Object).with_fields(MOOD_FIELDS),
+        FieldSpec::optional("template", FieldKind::String),
+    ]).concurrent(),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 语音模块
+    pub const Voice: ModuleType = ModuleType::named("voice");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for VoiceHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Voice
+    }
+    
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+    
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        let mut ws_sender = self.ws_sender.lock().await;
+        *ws_sender = Some(sender);
+    }
+    
     /// 清理资源
-    pub async fn cleanup(&self) {
+    async fn cleanup(&self) {
         let mut state = self.state.lock().await;
         
         if state.is_recording {
@@ -674,6 +1050,7 @@ impl VoiceHandler {
             state.recording_mode = None;
             log_info!("连接关闭，取消录音");
         }
+        *self.client.lock().await = None;
         
         // 取消实时转录任务
         if let Some(stop_tx) = state.stop_signal.take() {
@@ -694,19 +1071,20 @@ impl VoiceHandler {
         state.streaming_recorder = None;
         state.recorder = None;
         state.audio_level_tx = None;
+        state.request_id = None;
+        state.journal = None;
+        state.job = None;
     }
-}
-
-impl Default for VoiceHandler {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-#[async_trait::async_trait]
-impl ModuleHandler for VoiceHandler {
-    fn module_type(&self) -> ModuleType {
-        ModuleType::Voice
+    
+    /// 取消指定请求发起的录音 (及实时转录)
+    async fn cancel(&self, request_id: &str) -> bool {
+        {
+            let state = self.state.lock().await;
+            if !state.is_recording || state.request_id.as_deref() != Some(request_id) {
+                return false;
+            }
+        }
+        self.handle_cancel_recording(None).await.is_ok()
     }
     
     async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
@@ -715,21 +1093,27 @@ impl ModuleHandler for VoiceHandler {
         match msg.msg_type.as_str() {
             "start_recording" => {
                 let mode: RecordingMode = msg.get_field("mode")
-                    .ok_or_else(|| RouterError::ModuleError("缺少 mode 字段".to_string()))?;
+                    .ok_or_else(|| voice_error(ErrorCode::InvalidParams, "缺少 mode 字段"))?;
                 let asr_config: ASRConfig = msg.get_field("asr_config")
-                    .ok_or_else(|| RouterError::ModuleError("缺少 asr_config 字段".to_string()))?;
+                    .ok_or_else(|| voice_error(ErrorCode::InvalidParams, "缺少 asr_config 字段"))?;
                 
-                self.handle_start_recording(mode, asr_config).await
+                self.handle_start_recording(mode, asr_config, msg.request_id(), msg.sender(), None).await
             }
             "stop_recording" => {
-                self.handle_stop_recording().await
+                self.handle_stop_recording(msg.request_id()).await
             }
             "cancel_recording" => {
-                self.handle_cancel_recording().await
+                self.handle_cancel_recording(msg.request_id()).await
+            }
+            "pause_recording" => {
+                self.handle_pause_recording(true, msg.request_id()).await
+            }
+            "resume_recording" => {
+                self.handle_pause_recording(false, msg.request_id()).await
             }
             "update_config" => {
                 let asr_config: ASRConfig = msg.get_field("asr_config")
-                    .ok_or_else(|| RouterError::ModuleError("缺少 asr_config 字段".to_string()))?;
+                    .ok_or_else(|| voice_error(ErrorCode::InvalidParams, "缺少 asr_config 字段"))?;
                 
                 self.handle_update_config(asr_config).await
             }
@@ -737,9 +1121,24 @@ impl ModuleHandler for VoiceHandler {
                 let request_id: Option<String> = msg.get_field("request_id");
                 self.handle_list_input_devices(request_id).await
             }
+            "get_metrics" => {
+                Ok(Some(ServerResponse::new(ModuleType::Voice, "metrics", serde_json::json!({
+                    "partial_latency": asr::latency::snapshot(),
+                }))))
+            }
+            "get_provider_capabilities" => {
+                let asr_config: Option<ASRConfig> = msg.get_field("asr_config");
+                Ok(Some(provider_capabilities(asr_config.as_ref())))
+            }
+            "transcribe" => {
+                self.handle_transcribe(msg).await
+            }
+            "journal_entry" => {
+                self.handle_journal_entry(msg).await
+            }
             _ => {
                 log_debug!("未知的 Voice 消息类型: {}", msg.msg_type);
-                Err(RouterError::ModuleError(format!("未知的 Voice 消息类型: {}", msg.msg_type)))
+                Err(voice_error(ErrorCode::UnknownMessageType, format!("未知的 Voice 消息类型: {}", msg.msg_type)).into())
             }
         }
     }
@@ -749,17 +1148,164 @@ impl ModuleHandler for VoiceHandler {
 // 辅助函数
 // ============================================================================
 
+/// 供应商能力：给出配置时只列出主供应商和备用供应商，否则列出全部供应商；
+/// 另附运行时加载的音频处理库是否可用 (不可用时对应选项不起作用)
+fn provider_capabilities(asr_config: Option<&ASRConfig>) -> ServerResponse {
+    let providers = match asr_config {
+        Some(config) => std::iter::once(ProviderCapabilities::for_config(&config.primary, "primary"))
+            .chain(config.fallback.as_ref().map(|fallback| ProviderCapabilities::for_config(fallback, "fallback")))
+            .collect(),
+        None => ProviderCapabilities::all(),
+    };
+    ServerResponse::new(ModuleType::Voice, "provider_capabilities", serde_json::json!({
+        "providers": providers,
+        "audio_processing": {
+            "noise_suppression": true,
+            "vad": true,
+        },
+    }))
+}
+
+/// 发送 Voice 事件 (`request_id` 存在时附加到消息上)
+async fn send_event(
+    sender: &WsSender,
+    msg_type: &str,
+    payload: serde_json::Value,
+    request_id: Option<&str>,
+) -> Result<(), RouterError> {
+    let mut response = serde_json::json!({
+        "module": "voice",
+        "type": msg_type,
+    });
+    if let Some(id) = request_id {
+        response["request_id"] = serde_json::Value::String(id.to_string());
+    }
+    
+    // 合并 payload 到 response
+    let mut response = response.as_object().unwrap().clone();
+    if let serde_json::Value::Object(payload_obj) = payload {
+        for (k, v) in payload_obj {
+            response.insert(k, v);
+        }
+    }
+    
+    let json = serde_json::to_string(&response)
+        .map_err(|e| voice_error(ErrorCode::Internal, format!("JSON 序列化失败: {}", e)))?;
+    
+    let mut sender = sender.lock().await;
+    sender.send(tokio_tungstenite::tungstenite::Message::Text(json.into())).await
+        .map_err(|e| voice_error(ErrorCode::NotConnected, format!("发送消息失败: {}", e)))?;
+    Ok(())
+}
+
+/// 实时转录任务停滞时释放录音状态
+/// 
+/// 仅在该任务仍是当前录音的转录任务时取消录音 (已调用 stop_recording 时由其回退到 HTTP 转录)，
+/// 并通知客户端录音已取消，之后可以重新开始录音
+async fn release_stalled_recording(
+    state: &TokioMutex<ConnectionState>,
+    sender: Option<WsSender>,
+    request_id: Option<String>,
+) {
+    let mut state = state.lock().await;
+    let is_current = state.realtime_task.as_ref().is_some_and(|task| task.id() == tokio::task::id());
+    if !is_current {
+        return;
+    }
+    
+    log_error!("实时转录任务停滞，取消录音");
+    if let Some(ref mut streaming_recorder) = state.streaming_recorder {
+        streaming_recorder.cancel();
+    }
+    state.is_recording = false;
+    state.recording_mode = None;
+    state.streaming_recorder = None;
+    state.realtime_task = None;
+    state.stop_signal = None;
+    state.audio_level_tx = None;
+    state.request_id = None;
+    state.journal = None;
+    state.job = None;
+    drop(state);
+    
+    let Some(sender) = sender else {
+        return;
+    };
+    let _ = send_event(&sender, "recording_state", serde_json::json!({
+        "state": "cancelled"
+    }), request_id.as_deref()).await;
+    let _ = send_event(&sender, "error", serde_json::json!({
+        "code": ErrorCode::Timeout,
+        "retryable": ErrorCode::Timeout.is_retryable(),
+        "message": "实时转录长时间无进度，录音已取消",
+    }), request_id.as_deref()).await;
+}
+
+/// 解码 base64 编码的 WAV 音频
+fn decode_audio(audio: &str) -> Result<AudioData, ModuleError> {
+    use base64::Engine;
+    
+    let wav = base64::engine::general_purpose::STANDARD.decode(audio.as_bytes())
+        .map_err(|e| voice_error(ErrorCode::InvalidParams, format!("audio 不是有效的 base64: {}", e)))?;
+    decode_wav(&wav)
+        .map_err(|e| voice_error(ErrorCode::InvalidParams, format!("无法解码 WAV 音频: {}", e)))
+}
+
+/// 按配置对转写文本进行后处理
+pub(crate) fn post_process_text(text: String, asr_config: &ASRConfig) -> String {
+    if asr_config.normalize_numbers {
+        numbers::normalize(&text, TextLocale::Auto)
+    } else {
+        text
+    }
+}
+
+/// 转写结果的语言与 expected_language 不符时返回 language_mismatch 的内容 (附带引擎名)
+fn language_mismatch(asr_config: &ASRConfig, payload: &serde_json::Value) -> Option<serde_json::Value> {
+    let expected = asr_config.expected_language.as_deref()?;
+    let mismatch = language_check::check(expected, payload["text"].as_str().unwrap_or_default())?;
+    log_info!("转写语言与预期不符: 预期 {}，检测到 {} ({:.2})", mismatch.expected, mismatch.detected, mismatch.confidence);
+    let mut warning = serde_json::to_value(&mismatch).unwrap_or_default();
+    warning["engine"] = payload["engine"].clone();
+    Some(warning)
+}
+
+/// transcription_complete 的负载；启用 word_timestamps 且引擎提供了时间信息时附带 words
+/// (引擎的原始分词，不经过数字规范化等后处理)
+fn transcription_payload(result: TranscriptionResult, asr_config: &ASRConfig) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "text": post_process_text(result.text, asr_config),
+        "engine": result.engine,
+        "used_fallback": result.used_fallback,
+        "duration_ms": result.duration_ms,
+    });
+    if let Some(words) = result.words.filter(|_| asr_config.word_timestamps) {
+        payload["words"] = serde_json::to_value(words).unwrap_or_default();
+    }
+    payload
+}
+
 /// 执行 ASR 转录
-async fn perform_transcription(
+pub(crate) async fn perform_transcription(
+    audio_data: &AudioData,
+    asr_config: &ASRConfig,
+) -> Result<TranscriptionResult, ASRError> {
+    perform_transcription_with_prompt(audio_data, asr_config, None).await
+}
+
+/// 带上下文提示执行 ASR 转录 (分段录音中传入上一段的末尾文本)
+pub(crate) async fn perform_transcription_with_prompt(
     audio_data: &AudioData,
     asr_config: &ASRConfig,
+    prompt: Option<&str>,
 ) -> Result<TranscriptionResult, ASRError> {
     // 验证配置
     asr_config.validate()
         .map_err(|e| ASRError::ConfigError(e.to_string()))?;
     
     // 创建竞速策略
-    let strategy = RaceStrategy::from_config(asr_config.clone());
+    let strategy = RaceStrategy::from_config(asr_config.clone())
+        .with_prompt(prompt.map(str::to_string));
     
     log_info!(
         "使用 ASR 引擎: primary={}, fallback={:?}, enable_fallback={}",
@@ -799,15 +1345,15 @@ async fn perform_fallback_transcription(
             let engine = asr::create_engine(fallback_config)?;
             
             let start_time = std::time::Instant::now();
-            let text = engine.transcribe(audio_data).await?;
+            let transcription = asr::run_transcription(engine.as_ref(), audio_data, None, asr_config.word_timestamps).await?;
             let duration_ms = start_time.elapsed().as_millis() as u64;
             
             return Ok(TranscriptionResult::new(
-                text,
+                transcription.text,
                 engine.name().to_string(),
                 true,
                 duration_ms,
-            ));
+            ).with_words(transcription.words));
         }
     }
     
@@ -822,13 +1368,13 @@ async fn perform_fallback_transcription(
     let engine = asr::create_engine(&http_config)?;
     
     let start_time = std::time::Instant::now();
-    let text = engine.transcribe(audio_data).await?;
+    let transcription = asr::run_transcription(engine.as_ref(), audio_data, None, asr_config.word_timestamps).await?;
     let duration_ms = start_time.elapsed().as_millis() as u64;
     
     Ok(TranscriptionResult::new(
-        text,
+        transcription.text,
         format!("{}-http", engine.name()),
         true,
         duration_ms,
-    ))
+    ).with_words(transcription.words))
 }
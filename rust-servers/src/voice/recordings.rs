@@ -0,0 +1,91 @@
+// 录音存档
+// 配置 save_recordings_dir 时，每次录音结束后把完整录音按 save_recordings_format 写成文件 (如 vault 内的附件目录)，
+// transcription_complete 的 recording_path 字段为保存的路径，插件可以在笔记中嵌入录音
+
+use chrono::{DateTime, Local};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::audio::AudioData;
+use super::config::AudioFormat;
+use crate::router::ModuleType;
+
+/// 同一秒内结束的录音最多尝试的文件名数
+const MAX_NAME_ATTEMPTS: usize = 100;
+
+/// 录音文件名 (按结束时间命名，同名时追加序号)
+fn file_name(finished_at: DateTime<Local>, attempt: usize, extension: &str) -> String {
+    let stamp = finished_at.format("%Y%m%d-%H%M%S");
+    match attempt {
+        0 => format!("recording-{}.{}", stamp, extension),
+        n => format!("recording-{}-{}.{}", stamp, n + 1, extension),
+    }
+}
+
+/// 把录音按 `format` 编码后写入 `dir` (目录不存在时创建)，返回文件路径；不会覆盖已有文件
+///
+/// 找不到 Opus 编码库时保存为 WAV，扩展名随实际格式
+pub async fn save(dir: &str, audio: &AudioData, format: AudioFormat, finished_at: DateTime<Local>) -> Result<PathBuf, String> {
+    let audio = audio.clone();
+    let encoded = tokio::task::spawn_blocking(move || audio.encode_as(format))
+        .await
+        .map_err(|e| format!("编码任务失败: {}", e))?
+        .map_err(|e| format!("编码录音失败: {}", e))?;
+    let dir = PathBuf::from(dir);
+    let path = tokio::task::spawn_blocking(move || write_new(&dir, &encoded.data, encoded.format.extension(), finished_at))
+        .await
+        .map_err(|e| format!("写入任务失败: {}", e))??;
+    crate::audit::record_file_write(ModuleType::Voice, &path);
+    Ok(path)
+}
+
+fn write_new(dir: &Path, data: &[u8], extension: &str, finished_at: DateTime<Local>) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("无法创建目录 {}: {}", dir.display(), e))?;
+    for attempt in 0..MAX_NAME_ATTEMPTS {
+        let path = dir.join(file_name(finished_at, attempt, extension));
+        let mut file = match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("无法写入 {}: {}", path.display(), e)),
+        };
+        file.write_all(data).map_err(|e| format!("无法写入 {}: {}", path.display(), e))?;
+        return Ok(path);
+    }
+    Err(format!("{} 中同名录音过多", dir.display()))
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::audio::decode_wav;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_save() {
+        let dir = std::env::temp_dir().join(format!("recordings-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let target = dir.join("Attachments");
+        let audio = AudioData::new(vec![0.0, 0.25, -0.25, 0.5], 16000, 1);
+        let finished_at = Local.with_ymd_and_hms(2024, 3, 1, 9, 30, 5).unwrap();
+
+        // 目录不存在时创建，同一秒结束的录音不覆盖
+        let first = save(target.to_str().unwrap(), &audio, AudioFormat::Wav, finished_at).await.unwrap();
+        let second = save(target.to_str().unwrap(), &audio, AudioFormat::Wav, finished_at).await.unwrap();
+        assert_eq!(first, target.join("recording-20240301-093005.wav"));
+        assert_eq!(second, target.join("recording-20240301-093005-2.wav"));
+
+        let decoded = decode_wav(&std::fs::read(&first).unwrap()).unwrap();
+        assert_eq!(decoded.sample_rate, 16000);
+        assert_eq!(decoded.samples.len(), 4);
+
+        // 扩展名随格式
+        let flac = save(target.to_str().unwrap(), &audio, AudioFormat::Flac, finished_at).await.unwrap();
+        assert_eq!(flac, target.join("recording-20240301-093005.flac"));
+        assert_eq!(&std::fs::read(&flac).unwrap()[..4], b"fLaC");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
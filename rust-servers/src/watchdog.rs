@@ -0,0 +1,362 @@
+// 任务看门狗
+// 长时间运行的后台任务 (实时 ASR、LLM 流、PTY 读取) 注册后定期上报进度，
+// 超过停滞超时仍无进度时通知任务中止并释放关联状态，同时向客户端发送诊断事件
+
+use futures_util::SinkExt;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::StallTimeouts;
+use crate::router::{ModuleType, ServerResponse};
+use crate::server::WsSender;
+
+/// 日志宏
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Warn, "Watchdog", format!($($arg)*));
+    };
+}
+
+/// 检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 任务处于空闲等待时的进度时间标记 (不计入停滞超时)
+const IDLE: u64 = u64::MAX;
+
+// ============================================================================
+// 被监视的任务
+// ============================================================================
+
+/// 任务类型 (各自有独立的停滞超时)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// 实时 ASR 转录 (发送音频块、等待最终结果)
+    RealtimeAsr,
+    /// LLM 流式响应
+    LlmStream,
+    /// PTY 输出读取 (仅在 shell 进程退出后仍未读到 EOF 时视为停滞)
+    PtyRead,
+}
+
+impl TaskKind {
+    /// 任务所属模块
+    pub fn module(&self) -> ModuleType {
+        match self {
+            TaskKind::RealtimeAsr => ModuleType::Voice,
+            TaskKind::LlmStream => ModuleType::Llm,
+            TaskKind::PtyRead => ModuleType::Pty,
+        }
+    }
+}
+
+/// 空闲探测：返回 true 表示任务虽无进度但仍属正常 (如 shell 进程仍在运行)
+pub type IdleProbe = Box<dyn Fn() -> bool + Send + Sync>;
+
+/// 任务描述
+pub struct WatchedTask {
+    kind: TaskKind,
+    request_id: Option<String>,
+    session_id: Option<String>,
+    idle_probe: Option<IdleProbe>,
+}
+
+impl WatchedTask {
+    pub fn new(kind: TaskKind) -> Self {
+        Self {
+            kind,
+            request_id: None,
+            session_id: None,
+            idle_probe: None,
+        }
+    }
+
+    /// 发起任务的请求 ID (回显到诊断事件上)
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// 任务所属的会话 ID
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// 设置空闲探测，探测返回 true 时不判定为停滞
+    pub fn with_idle_probe(mut self, probe: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        self.idle_probe = Some(Box::new(probe));
+        self
+    }
+}
+
+/// 任务心跳
+///
+/// 任务每次取得进度时调用 `beat`，等待不属于被监视工作的输入 (如客户端的音频) 前调用 `idle`；
+/// 看门狗判定停滞后 `stalled` 完成，任务应随即中止并释放状态。心跳被丢弃时自动注销
+pub struct Heartbeat {
+    id: u64,
+    last_progress: Arc<AtomicU64>,
+    stalled: CancellationToken,
+    watchdog: &'static Watchdog,
+}
+
+impl Heartbeat {
+    /// 上报进度
+    pub fn beat(&self) {
+        self.last_progress.store(self.watchdog.elapsed_ms(), Ordering::Relaxed);
+    }
+
+    /// 进入空闲等待：到下一次 `beat` 之前不判定停滞
+    pub fn idle(&self) {
+        self.last_progress.store(IDLE, Ordering::Relaxed);
+    }
+
+    /// 等待看门狗判定任务停滞
+    pub async fn stalled(&self) {
+        self.stalled.cancelled().await
+    }
+
+    /// 停滞信号 (可移动到其他任务中等待)
+    pub fn stall_token(&self) -> CancellationToken {
+        self.stalled.clone()
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        if let Ok(mut tasks) = self.watchdog.tasks.lock() {
+            tasks.remove(&self.id);
+        }
+    }
+}
+
+// ============================================================================
+// 看门狗
+// ============================================================================
+
+/// 停滞任务报告 (作为 `task_stalled` 事件发送给客户端)
+#[derive(Debug, Clone, Serialize)]
+pub struct StallReport {
+    /// 任务所属模块
+    pub stalled_module: ModuleType,
+    /// 任务类型
+    pub task: TaskKind,
+    /// 发起任务的请求 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// 任务所属的会话 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// 最近一次进度距今的毫秒数
+    pub idle_ms: u64,
+}
+
+impl StallReport {
+    /// 转换为服务器事件
+    pub fn into_response(self) -> ServerResponse {
+        let payload = serde_json::to_value(&self).unwrap_or_default();
+        ServerResponse::new(ModuleType::System, "task_stalled", payload)
+    }
+}
+
+struct Entry {
+    task: WatchedTask,
+    last_progress: Arc<AtomicU64>,
+    stalled: CancellationToken,
+}
+
+/// 看门狗：记录已注册任务的最近进度时间
+pub struct Watchdog {
+    epoch: Instant,
+    next_id: AtomicU64,
+    tasks: Mutex<HashMap<u64, Entry>>,
+}
+
+impl Watchdog {
+    pub(crate) fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            next_id: AtomicU64::new(1),
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
+    /// 注册任务，注册时视为刚取得进度
+    pub fn watch(&'static self, task: WatchedTask) -> Heartbeat {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let last_progress = Arc::new(AtomicU64::new(self.elapsed_ms()));
+        let stalled = CancellationToken::new();
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.insert(id, Entry {
+                task,
+                last_progress: Arc::clone(&last_progress),
+                stalled: stalled.clone(),
+            });
+        }
+        Heartbeat { id, last_progress, stalled, watchdog: self }
+    }
+
+    /// 检查所有任务：超时未取得进度且不处于正常空闲的任务被判定为停滞并注销
+    pub fn check(&self, timeouts: &StallTimeouts) -> Vec<StallReport> {
+        let now = self.elapsed_ms();
+        let Ok(mut tasks) = self.tasks.lock() else {
+            return Vec::new();
+        };
+
+        let stalled: Vec<u64> = tasks.iter()
+            .filter(|(_, entry)| {
+                let Some(timeout) = timeouts.get(entry.task.kind) else {
+                    return false;
+                };
+                let last_progress = entry.last_progress.load(Ordering::Relaxed);
+                if last_progress == IDLE {
+                    return false;
+                }
+                let idle = now.saturating_sub(last_progress);
+                idle > timeout.as_millis() as u64 && !entry.task.idle_probe.as_ref().is_some_and(|probe| probe())
+            })
+            .map(|(id, _)| *id)
+            .collect();
+
+        stalled.into_iter()
+            .filter_map(|id| tasks.remove(&id))
+            .map(|entry| {
+                entry.stalled.cancel();
+                StallReport {
+                    stalled_module: entry.task.kind.module(),
+                    task: entry.task.kind,
+                    request_id: entry.task.request_id,
+                    session_id: entry.task.session_id,
+                    idle_ms: now.saturating_sub(entry.last_progress.load(Ordering::Relaxed)),
+                }
+            })
+            .collect()
+    }
+
+    /// 已注册的任务数
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.tasks.lock().map(|tasks| tasks.len()).unwrap_or(0)
+    }
+}
+
+/// 全局看门狗
+fn watchdog() -> &'static Watchdog {
+    static WATCHDOG: OnceLock<Watchdog> = OnceLock::new();
+    WATCHDOG.get_or_init(Watchdog::new)
+}
+
+/// 向全局看门狗注册任务
+pub fn watch(task: WatchedTask) -> Heartbeat {
+    watchdog().watch(task)
+}
+
+/// 定期检查停滞任务，向 `sender` 发送 `task_stalled` 诊断事件
+pub async fn run(timeouts: StallTimeouts, sender: WsSender) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        for report in watchdog().check(&timeouts) {
+            log_warn!(
+                "任务停滞已中止: module={}, task={:?}, request_id={:?}, session_id={:?}, {}ms 无进度",
+                report.stalled_module, report.task, report.request_id, report.session_id, report.idle_ms
+            );
+            let event = report.into_response();
+            let _ = sender.lock().await.send(Message::Text(event.to_json().into())).await;
+        }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    fn timeouts(ms: u64) -> StallTimeouts {
+        StallTimeouts::none()
+            .with(TaskKind::LlmStream, Duration::from_millis(ms))
+            .with(TaskKind::PtyRead, Duration::from_millis(ms))
+    }
+
+    #[tokio::test]
+    async fn test_stalled_task() {
+        let watchdog: &'static Watchdog = Box::leak(Box::new(Watchdog::new()));
+        let heartbeat = watchdog.watch(
+            WatchedTask::new(TaskKind::LlmStream).with_request_id(Some("req-1".to_string())),
+        );
+        let busy = watchdog.watch(WatchedTask::new(TaskKind::LlmStream));
+        let unlimited = watchdog.watch(WatchedTask::new(TaskKind::RealtimeAsr));
+
+        // 超时未上报进度的任务被判定停滞并注销，未配置超时的任务不受监视
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        busy.beat();
+        let reports = watchdog.check(&timeouts(20));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].request_id.as_deref(), Some("req-1"));
+        assert_eq!(watchdog.len(), 2);
+        heartbeat.stalled().await;
+        assert!(!busy.stall_token().is_cancelled());
+        assert!(!unlimited.stall_token().is_cancelled());
+
+        let report = serde_json::to_value(reports[0].clone().into_response()).unwrap();
+        assert_eq!(report["type"], "task_stalled");
+        assert_eq!(report["request_id"], "req-1");
+        assert_eq!(report["stalled_module"], "llm");
+        assert_eq!(report["task"], "llm_stream");
+
+        drop(busy);
+        drop(unlimited);
+        assert_eq!(watchdog.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_idle_probe() {
+        let watchdog: &'static Watchdog = Box::leak(Box::new(Watchdog::new()));
+        let running = Arc::new(AtomicBool::new(true));
+        let probe = Arc::clone(&running);
+        let heartbeat = watchdog.watch(
+            WatchedTask::new(TaskKind::PtyRead)
+                .with_session_id("abc")
+                .with_idle_probe(move || probe.load(Ordering::Relaxed)),
+        );
+
+        // 进程仍在运行时长时间无输出属正常
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(watchdog.check(&timeouts(20)).is_empty());
+
+        running.store(false, Ordering::Relaxed);
+        let reports = watchdog.check(&timeouts(20));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].session_id.as_deref(), Some("abc"));
+        assert!(heartbeat.stall_token().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_idle_wait() {
+        let watchdog: &'static Watchdog = Box::leak(Box::new(Watchdog::new()));
+        let heartbeat = watchdog.watch(WatchedTask::new(TaskKind::LlmStream));
+
+        // 空闲等待期间不计时，再次上报进度后重新计时
+        heartbeat.idle();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(watchdog.check(&timeouts(20)).is_empty());
+        heartbeat.beat();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(watchdog.check(&timeouts(20)).len(), 1);
+    }
+}
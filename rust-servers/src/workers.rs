@@ -0,0 +1,282 @@
+// CPU 密集的本地任务
+// 本地 Whisper / Vosk 推理、向量索引扫描和文件哈希等在阻塞线程中运行。同时运行的任务数受总数上限和各任务类型的上限约束，
+// 超出的任务排队等待，避免后台推理占满所有核心、让实时音频回调和 PTY 读取得不到 CPU。
+// 上限来自配置文件的 [workers] 段，运行中可以用 system/set_limits 调整 (已在运行的任务不受影响)
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::oneshot;
+
+/// 任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkKind {
+    /// 本地语音识别 (Whisper、Vosk 的整段转写)
+    LocalAsr,
+    /// 索引 (向量索引扫描和检索、文件哈希、重复检测)
+    Indexing,
+}
+
+impl WorkKind {
+    /// 全部任务类型
+    pub const ALL: [WorkKind; 2] = [WorkKind::LocalAsr, WorkKind::Indexing];
+
+    fn index(self) -> usize {
+        match self {
+            WorkKind::LocalAsr => 0,
+            WorkKind::Indexing => 1,
+        }
+    }
+}
+
+/// 并发上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WorkerLimits {
+    /// 同时运行的任务总数，也是单次本地推理使用的线程数上限
+    pub threads: usize,
+    /// 同时运行的本地语音识别任务数
+    pub local_asr: usize,
+    /// 同时运行的索引任务数
+    pub indexing: usize,
+}
+
+impl WorkerLimits {
+    /// 任务类型的上限
+    pub fn cap(&self, kind: WorkKind) -> usize {
+        match kind {
+            WorkKind::LocalAsr => self.local_asr,
+            WorkKind::Indexing => self.indexing,
+        }
+    }
+}
+
+impl Default for WorkerLimits {
+    /// 默认给实时音频和事件循环留出一个核心
+    fn default() -> Self {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            threads: cores.saturating_sub(1).max(1),
+            local_asr: 1,
+            indexing: 2,
+        }
+    }
+}
+
+/// 各任务类型正在运行和排队的任务数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct KindUsage {
+    pub kind: WorkKind,
+    pub running: usize,
+    pub waiting: usize,
+}
+
+struct PoolState {
+    limits: WorkerLimits,
+    /// 各类型正在运行的任务数
+    running: [usize; 2],
+    /// 各类型排队的任务
+    waiting: [VecDeque<oneshot::Sender<WorkPermit>>; 2],
+}
+
+impl PoolState {
+    fn can_start(&self, kind: WorkKind) -> bool {
+        let total: usize = self.running.iter().sum();
+        total < self.limits.threads && self.running[kind.index()] < self.limits.cap(kind)
+    }
+}
+
+/// 任务池 (只限制并发，任务仍在 tokio 的阻塞线程池中运行)
+#[derive(Clone)]
+pub struct WorkerPool {
+    state: Arc<Mutex<PoolState>>,
+}
+
+/// 运行名额 (丢弃时归还，交给排队中的下一个任务)
+pub struct WorkPermit {
+    pool: WorkerPool,
+    kind: WorkKind,
+}
+
+impl Drop for WorkPermit {
+    fn drop(&mut self) {
+        let mut state = self.pool.lock();
+        state.running[self.kind.index()] -= 1;
+        let next = self.pool.dispatch(&mut state);
+        drop(state);
+        WorkerPool::hand_over(next);
+    }
+}
+
+impl WorkerPool {
+    /// 创建任务池 (上限为 0 时按 1 处理)
+    pub fn new(limits: WorkerLimits) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(PoolState {
+                limits: normalize(limits),
+                running: [0, 0],
+                waiting: [VecDeque::new(), VecDeque::new()],
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 当前上限
+    pub fn limits(&self) -> WorkerLimits {
+        self.lock().limits
+    }
+
+    /// 调整上限：放宽时立即启动排队中的任务，收紧时等运行中的任务结束后生效
+    pub fn set_limits(&self, limits: WorkerLimits) {
+        let mut state = self.lock();
+        state.limits = normalize(limits);
+        let next = self.dispatch(&mut state);
+        drop(state);
+        Self::hand_over(next);
+    }
+
+    /// 各任务类型的运行和排队情况
+    pub fn usage(&self) -> Vec<KindUsage> {
+        let state = self.lock();
+        WorkKind::ALL.iter()
+            .map(|&kind| KindUsage {
+                kind,
+                running: state.running[kind.index()],
+                waiting: state.waiting[kind.index()].len(),
+            })
+            .collect()
+    }
+
+    /// 等待名额；同一类型按到达顺序
+    pub async fn acquire(&self, kind: WorkKind) -> WorkPermit {
+        let receiver = {
+            let mut state = self.lock();
+            if state.waiting[kind.index()].is_empty() && state.can_start(kind) {
+                state.running[kind.index()] += 1;
+                return WorkPermit { pool: self.clone(), kind };
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.waiting[kind.index()].push_back(sender);
+            receiver
+        };
+        // 发送端只在交出名额时使用，不会在发送前被丢弃
+        receiver.await.expect("queued permit")
+    }
+
+    /// 为可以开始的排队任务分配名额 (本地语音识别优先，用户通常在等待转写结果)
+    fn dispatch(&self, state: &mut PoolState) -> Vec<(oneshot::Sender<WorkPermit>, WorkPermit)> {
+        let mut next = Vec::new();
+        for kind in WorkKind::ALL {
+            while state.can_start(kind) {
+                let Some(sender) = state.waiting[kind.index()].pop_front() else {
+                    break;
+                };
+                state.running[kind.index()] += 1;
+                next.push((sender, WorkPermit { pool: self.clone(), kind }));
+            }
+        }
+        next
+    }
+
+    /// 在释放锁之后交出名额；等待者已放弃 (如请求被取消) 时名额随之丢弃并归还
+    fn hand_over(next: Vec<(oneshot::Sender<WorkPermit>, WorkPermit)>) {
+        for (sender, permit) in next {
+            let _ = sender.send(permit);
+        }
+    }
+}
+
+/// 上限至少为 1
+fn normalize(limits: WorkerLimits) -> WorkerLimits {
+    WorkerLimits {
+        threads: limits.threads.max(1),
+        local_asr: limits.local_asr.max(1),
+        indexing: limits.indexing.max(1),
+    }
+}
+
+static POOL: OnceLock<WorkerPool> = OnceLock::new();
+
+/// 按配置初始化全局任务池，需在运行任何本地任务之前调用 (之后的调用无效)
+pub fn init(limits: WorkerLimits) {
+    let _ = POOL.set(WorkerPool::new(limits));
+}
+
+/// 全局任务池
+pub fn pool() -> &'static WorkerPool {
+    POOL.get_or_init(|| WorkerPool::new(WorkerLimits::default()))
+}
+
+/// 取得 `kind` 的名额后在阻塞线程中运行 `f`，名额在 `f` 返回后归还
+pub async fn spawn_blocking<F, R>(kind: WorkKind, f: F) -> Result<R, tokio::task::JoinError>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let permit = pool().acquire(kind).await;
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        f()
+    })
+    .await
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 确认 `acquire` 仍在等待
+    async fn pending(task: &tokio::task::JoinHandle<WorkPermit>) {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!task.is_finished());
+    }
+
+    fn spawn_acquire(pool: &WorkerPool, kind: WorkKind) -> tokio::task::JoinHandle<WorkPermit> {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.acquire(kind).await })
+    }
+
+    #[tokio::test]
+    async fn test_caps() {
+        let pool = WorkerPool::new(WorkerLimits { threads: 2, local_asr: 1, indexing: 2 });
+
+        // 本地识别最多 1 个，索引仍可使用剩余名额
+        let asr = pool.acquire(WorkKind::LocalAsr).await;
+        let queued_asr = spawn_acquire(&pool, WorkKind::LocalAsr);
+        pending(&queued_asr).await;
+        let index = pool.acquire(WorkKind::Indexing).await;
+
+        // 总数已满
+        let queued_index = spawn_acquire(&pool, WorkKind::Indexing);
+        pending(&queued_index).await;
+        let usage = pool.usage();
+        assert_eq!((usage[0].running, usage[0].waiting), (1, 1));
+        assert_eq!((usage[1].running, usage[1].waiting), (1, 1));
+
+        // 释放的名额先交给排队的识别任务
+        drop(asr);
+        let _asr = queued_asr.await.unwrap();
+        pending(&queued_index).await;
+
+        // 放宽上限后排队的索引任务立即开始
+        pool.set_limits(WorkerLimits { threads: 3, local_asr: 1, indexing: 2 });
+        let _queued = tokio::time::timeout(Duration::from_secs(1), queued_index).await.unwrap().unwrap();
+        assert_eq!(pool.usage().iter().map(|usage| usage.running).sum::<usize>(), 3);
+        drop(index);
+        assert_eq!(pool.usage()[1].running, 1);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_blocking() {
+        let result = spawn_blocking(WorkKind::Indexing, || 21 * 2).await.unwrap();
+        assert_eq!(result, 42);
+    }
+}
@@ -0,0 +1,495 @@
+// 工作流模块
+// 在服务端按顺序执行用户定义的流水线 (如 录音 → 转写 → 润色 → 翻译 → 写入笔记)，
+// 各步骤直接调用服务器内的实现，中间结果不必往返客户端。
+// run 立即返回 run_started，之后推送 step 事件 (running / retrying / completed / skipped / failed) 和 progress 事件，
+// 最后以 completed 事件 (各步骤输出) 或 error 事件结束。录音步骤用 stop 结束，cancel 或 system/cancel 取消整个工作流
+
+mod pipeline;
+mod steps;
+
+pub use pipeline::{render, Action, Condition, Output, Step, Test, WriteMode};
+
+use crate::jobs::{self, Job, JobKind};
+use crate::router::{
+    ErrorCode, FieldKind, FieldSpec, MessageSpec, ModuleError, ModuleHandler, ModuleMessage, ModuleType, RouterError,
+    ServerResponse,
+};
+use crate::server::WsSender;
+use crate::system::progress::{percent_between, ProgressReporter};
+use futures_util::SinkExt;
+use pipeline::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Mutex as TokioMutex, Notify};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// 日志宏
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        crate::logging::write(crate::logging::Level::Info, "Workflow", format!($($arg)*));
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            crate::logging::write(crate::logging::Level::Debug, "Workflow", format!($($arg)*));
+        }
+    };
+}
+
+/// 同时运行的工作流上限
+const MAX_RUNS: usize = 8;
+
+/// 创建工作流模块错误
+pub(crate) fn workflow_error(code: ErrorCode, message: impl Into<String>) -> ModuleError {
+    ModuleError::new(ModuleType::Workflow, code, message)
+}
+
+// ============================================================================
+// 请求
+// ============================================================================
+
+/// run / validate 请求
+#[derive(Debug, Deserialize)]
+struct RunRequest {
+    /// 工作流名称 (仅用于日志和事件)
+    #[serde(default)]
+    name: Option<String>,
+    steps: Vec<Step>,
+    /// 第一个步骤的输入文本
+    #[serde(default)]
+    input: Option<String>,
+    /// 模板变量 (`{{vars.<名称>}}`)
+    #[serde(default)]
+    vars: HashMap<String, String>,
+}
+
+impl RunRequest {
+    fn parse(msg: &ModuleMessage) -> Result<Self, ModuleError> {
+        let request: RunRequest = serde_json::from_value(msg.payload.clone())
+            .map_err(|e| workflow_error(ErrorCode::InvalidParams, format!("无效的 {} 请求: {}", msg.msg_type, e)))?;
+        pipeline::validate(&request.steps).map_err(|e| workflow_error(ErrorCode::InvalidParams, e))?;
+        Ok(request)
+    }
+}
+
+/// stop / cancel 请求
+#[derive(Debug, Default, Deserialize)]
+struct RunIdRequest {
+    /// 工作流运行 ID (省略时为唯一运行中的工作流)
+    #[serde(default)]
+    id: Option<String>,
+}
+
+// ============================================================================
+// 运行状态
+// ============================================================================
+
+/// 运行状态 (status 消息返回)
+#[derive(Debug, Clone, Serialize)]
+struct Status {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// 当前步骤
+    step: Option<String>,
+    /// 当前步骤的序号 (从 0 开始)
+    index: usize,
+    /// 步骤总数
+    total: usize,
+    /// 当前是否在录音
+    recording: bool,
+}
+
+/// 运行中的工作流
+#[derive(Clone)]
+struct Run {
+    request_id: Option<String>,
+    cancel: CancellationToken,
+    /// 结束录音步骤
+    stop: Arc<Notify>,
+    recording: Arc<AtomicBool>,
+    status: Arc<Mutex<Status>>,
+}
+
+/// 步骤事件
+#[derive(Debug, Serialize)]
+struct StepEvent<'a> {
+    /// 运行 ID
+    id: &'a str,
+    step: &'a str,
+    index: usize,
+    action: &'static str,
+    /// running / retrying / completed / skipped / failed
+    status: &'static str,
+    /// 第几次尝试 (从 1 开始)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attempt: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// 事件发送目标
+#[derive(Clone)]
+struct Events {
+    sender: WsSender,
+    request_id: Option<String>,
+}
+
+impl Events {
+    async fn send(&self, event: ServerResponse) {
+        let event = event.with_request_id(self.request_id.as_deref());
+        let _ = self.sender.lock().await.send(Message::Text(event.to_json().into())).await;
+    }
+
+    async fn step(&self, event: StepEvent<'_>) {
+        let payload = serde_json::to_value(&event).unwrap_or_default();
+        self.send(ServerResponse::new(ModuleType::Workflow, "step", payload)).await;
+    }
+}
+
+/// 运行结果
+enum Outcome {
+    Completed(serde_json::Value),
+    /// 失败的步骤和错误
+    Failed(String, ModuleError),
+}
+
+/// 依次执行步骤
+async fn execute(id: &str, request: RunRequest, events: &Events, run: &Run) -> Outcome {
+    let progress = ProgressReporter::new(ModuleType::Workflow, events.request_id.clone(), Some(events.sender.clone()));
+    let runtime = steps::Runtime {
+        request_id: events.request_id.clone(),
+        stop: &run.stop,
+        recording: &run.recording,
+        progress: &progress,
+    };
+    let total = request.steps.len();
+    let mut context = Context::new(request.input, request.vars);
+    let (mut skipped, mut failed) = (Vec::new(), Vec::new());
+
+    for (index, step) in request.steps.iter().enumerate() {
+        {
+            let mut status = run.status.lock().unwrap_or_else(|e| e.into_inner());
+            status.step = Some(step.id.clone());
+            status.index = index;
+        }
+        let event = |status, attempt, output, error| StepEvent {
+            id,
+            step: &step.id,
+            index,
+            action: step.action.name(),
+            status,
+            attempt,
+            output,
+            error,
+        };
+
+        if let Some(when) = &step.when {
+            if !when.test.matches(context.output(when.step.as_deref()).text()) {
+                log_debug!("跳过步骤 {} (条件不满足)", step.id);
+                context.record(&step.id, Output::None);
+                skipped.push(step.id.clone());
+                events.step(event("skipped", None, None, None)).await;
+                continue;
+            }
+        }
+
+        progress.report("running", Some(percent_between(0, 100, index, total)), Some(step.id.clone())).await;
+        let delay = Duration::from_millis(step.retry_delay_ms.unwrap_or(pipeline::DEFAULT_RETRY_DELAY_MS));
+        let mut attempt = 1;
+        let result = loop {
+            events.step(event("running", Some(attempt), None, None)).await;
+            match steps::execute(&step.action, &context, &runtime).await {
+                Err(e) if e.retryable && attempt <= step.retries => {
+                    log_info!("步骤 {} 第 {} 次执行失败，{}ms 后重试: {}", step.id, attempt, delay.as_millis(), e.message);
+                    events.step(event("retrying", Some(attempt), None, Some(e.message.clone()))).await;
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+
+        match result {
+            Ok(output) => {
+                events.step(event("completed", Some(attempt), Some(output.to_json()), None)).await;
+                context.record(&step.id, output);
+            }
+            Err(e) if step.continue_on_error => {
+                log_info!("步骤 {} 失败，继续执行: {}", step.id, e.message);
+                events.step(event("failed", Some(attempt), None, Some(e.message.clone()))).await;
+                context.record(&step.id, Output::None);
+                failed.push(step.id.clone());
+            }
+            Err(e) => {
+                events.step(event("failed", Some(attempt), None, Some(e.message.clone()))).await;
+                return Outcome::Failed(step.id.clone(), e);
+            }
+        }
+    }
+    progress.report("running", Some(100), None).await;
+
+    let outputs: serde_json::Map<String, serde_json::Value> = request.steps
+        .iter()
+        .map(|step| (step.id.clone(), context.output(Some(&step.id)).to_json()))
+        .collect();
+    Outcome::Completed(serde_json::json!({
+        "id": id,
+        "name": request.name,
+        "output": context.input.to_json(),
+        "outputs": outputs,
+        "skipped": skipped,
+        "failed": failed,
+    }))
+}
+
+// ============================================================================
+// 工作流处理器
+// ============================================================================
+
+/// 工作流模块处理器
+pub struct WorkflowHandler {
+    /// 运行中的工作流: 运行 ID → 运行状态
+    runs: Arc<Mutex<HashMap<String, Run>>>,
+    /// WebSocket 发送器 (请求没有来源连接时使用)
+    ws_sender: TokioMutex<Option<WsSender>>,
+}
+
+impl WorkflowHandler {
+    /// 创建新的工作流处理器
+    pub fn new() -> Self {
+        Self {
+            runs: Arc::new(Mutex::new(HashMap::new())),
+            ws_sender: TokioMutex::new(None),
+        }
+    }
+
+    fn runs(&self) -> std::sync::MutexGuard<'_, HashMap<String, Run>> {
+        self.runs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 查找运行：指定 ID 时按 ID 查找，否则只有一个运行中的工作流时使用它
+    fn find(runs: &HashMap<String, Run>, id: Option<&str>) -> Option<String> {
+        match id {
+            Some(id) => runs.contains_key(id).then(|| id.to_string()),
+            None if runs.len() == 1 => runs.keys().next().cloned(),
+            None => None,
+        }
+    }
+
+    /// 处理 run 消息 - 在后台运行工作流
+    async fn handle_run(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request = RunRequest::parse(msg)?;
+        let sender = match msg.sender() {
+            Some(sender) => Some(sender),
+            None => self.ws_sender.lock().await.clone(),
+        };
+        let sender = sender.ok_or_else(|| workflow_error(ErrorCode::NotConnected, "WebSocket sender not set"))?;
+        let events = Events { sender, request_id: msg.request_id() };
+
+        let id = Uuid::new_v4().to_string();
+        let total = request.steps.len();
+        let name = request.name.clone();
+        let cancel = CancellationToken::new();
+        let run = Run {
+            request_id: events.request_id.clone(),
+            cancel: cancel.clone(),
+            stop: Arc::new(Notify::new()),
+            recording: Arc::new(AtomicBool::new(false)),
+            status: Arc::new(Mutex::new(Status {
+                id: id.clone(),
+                name: name.clone(),
+                step: None,
+                index: 0,
+                total,
+                recording: false,
+            })),
+        };
+        let handle = run.clone();
+        {
+            let mut runs = self.runs();
+            if runs.len() >= MAX_RUNS {
+                return Err(workflow_error(ErrorCode::LimitExceeded, format!("运行中的工作流过多 (最多 {} 个)", MAX_RUNS)).into());
+            }
+            runs.insert(id.clone(), run);
+        }
+        log_info!("运行工作流: {} {} ({} 个步骤)", name.as_deref().unwrap_or("-"), id, total);
+
+        let runs = Arc::clone(&self.runs);
+        let run_id = id.clone();
+        let job = jobs::register(
+            Job::new(JobKind::Workflow).with_request_id(events.request_id.clone()).with_detail(id.clone()).with_cancel(cancel.clone()),
+        );
+        tokio::spawn(crate::storage::inherit_vault(crate::audit::inherit(async move {
+            let _job = job;
+            let outcome = tokio::select! {
+                outcome = execute(&run_id, request, &events, &handle) => Some(outcome),
+                _ = cancel.cancelled() => None,
+            };
+            runs.lock().unwrap_or_else(|e| e.into_inner()).remove(&run_id);
+            match outcome {
+                Some(Outcome::Completed(payload)) => {
+                    log_info!("工作流完成: {}", run_id);
+                    events.send(ServerResponse::new(ModuleType::Workflow, "completed", payload)).await;
+                }
+                Some(Outcome::Failed(step, error)) => {
+                    log_info!("工作流失败: {} (步骤 {}): {}", run_id, step, error.message);
+                    let mut response = ServerResponse::error(ModuleType::Workflow, error.code, &error.message);
+                    response.payload["id"] = run_id.into();
+                    response.payload["step"] = step.into();
+                    events.send(response).await;
+                }
+                None => {
+                    log_info!("工作流已取消: {}", run_id);
+                }
+            }
+        })));
+
+        Ok(Some(ServerResponse::new(ModuleType::Workflow, "run_started", serde_json::json!({
+            "id": id,
+            "name": name,
+            "steps": total,
+        }))))
+    }
+
+    /// 处理 validate 消息 - 只校验工作流定义
+    async fn handle_validate(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request = RunRequest::parse(msg)?;
+        let steps: Vec<serde_json::Value> = request.steps
+            .iter()
+            .map(|step| serde_json::json!({ "id": step.id, "action": step.action.name() }))
+            .collect();
+        Ok(Some(ServerResponse::new(ModuleType::Workflow, "valid", serde_json::json!({ "steps": steps }))))
+    }
+
+    /// 处理 stop 消息 - 结束当前的录音步骤
+    async fn handle_stop(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: RunIdRequest = serde_json::from_value(msg.payload.clone()).unwrap_or_default();
+        let runs = self.runs();
+        let stopped = Self::find(&runs, request.id.as_deref())
+            .filter(|id| steps::stop_recording(&runs[id].stop, &runs[id].recording));
+        let Some(id) = stopped else {
+            return Err(workflow_error(ErrorCode::NotRecording, "没有正在录音的工作流").into());
+        };
+        log_debug!("结束工作流录音: {}", id);
+        Ok(Some(ServerResponse::new(ModuleType::Workflow, "stopping", serde_json::json!({ "id": id }))))
+    }
+
+    /// 处理 cancel 消息 - 取消工作流 (不推送 completed 事件)
+    async fn handle_cancel(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        let request: RunIdRequest = serde_json::from_value(msg.payload.clone()).unwrap_or_default();
+        let mut runs = self.runs();
+        let run = Self::find(&runs, request.id.as_deref()).and_then(|id| runs.remove(&id).map(|run| (id, run)));
+        let Some((id, run)) = run else {
+            return Err(workflow_error(ErrorCode::NotFound, "工作流不存在").into());
+        };
+        run.cancel.cancel();
+        log_info!("取消工作流: {}", id);
+        Ok(Some(ServerResponse::new(ModuleType::Workflow, "cancelled", serde_json::json!({ "id": id }))))
+    }
+
+    /// 处理 status 消息 - 列出运行中的工作流
+    async fn handle_status(&self) -> Result<Option<ServerResponse>, RouterError> {
+        let workflows: Vec<Status> = self.runs()
+            .values()
+            .map(|run| {
+                let mut status = run.status.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                status.recording = run.recording.load(std::sync::atomic::Ordering::SeqCst);
+                status
+            })
+            .collect();
+        Ok(Some(ServerResponse::new(ModuleType::Workflow, "status", serde_json::json!({ "workflows": workflows }))))
+    }
+}
+
+impl Default for WorkflowHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 工作流模块的消息
+const MESSAGES: &[MessageSpec] = &[
+    MessageSpec::new("run", &[
+        FieldSpec::required("steps", FieldKind::Array).items(FieldKind::Object),
+        FieldSpec::optional("name", FieldKind::String),
+        FieldSpec::optional("input", FieldKind::String),
+        FieldSpec::optional("vars", FieldKind::Object),
+    ]),
+    MessageSpec::new("validate", &[
+        FieldSpec::required("steps", FieldKind::Array).items(FieldKind::Object),
+        FieldSpec::optional("name", FieldKind::String),
+        FieldSpec::optional("input", FieldKind::String),
+        FieldSpec::optional("vars", FieldKind::Object),
+    ]),
+    MessageSpec::new("stop", &[FieldSpec::optional("id", FieldKind::String)]),
+    MessageSpec::new("cancel", &[FieldSpec::optional("id", FieldKind::String)]),
+    MessageSpec::new("status", &[]),
+];
+
+#[allow(non_upper_case_globals)]
+impl ModuleType {
+    /// 工作流模块
+    pub const Workflow: ModuleType = ModuleType::named("workflow");
+}
+
+#[async_trait::async_trait]
+impl ModuleHandler for WorkflowHandler {
+    fn module_type(&self) -> ModuleType {
+        ModuleType::Workflow
+    }
+
+    fn messages(&self) -> &'static [MessageSpec] {
+        MESSAGES
+    }
+
+    /// 设置 WebSocket 发送器
+    async fn set_ws_sender(&self, sender: WsSender) {
+        *self.ws_sender.lock().await = Some(sender);
+    }
+
+    /// 取消运行中的工作流 (连接关闭时调用)
+    async fn cleanup(&self) {
+        for (_, run) in self.runs().drain() {
+            run.cancel.cancel();
+        }
+    }
+
+    /// 取消由 request_id 发起的工作流
+    async fn cancel(&self, request_id: &str) -> bool {
+        let mut runs = self.runs();
+        let id = runs.iter()
+            .find(|(_, run)| run.request_id.as_deref() == Some(request_id))
+            .map(|(id, _)| id.clone());
+        match id.and_then(|id| runs.remove(&id)) {
+            Some(run) => {
+                log_info!("取消工作流: request_id={}", request_id);
+                run.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn handle(&self, msg: &ModuleMessage) -> Result<Option<ServerResponse>, RouterError> {
+        log_debug!("处理工作流消息: {}", msg.msg_type);
+
+        match msg.msg_type.as_str() {
+            "run" => self.handle_run(msg).await,
+            "validate" => self.handle_validate(msg).await,
+            "stop" => self.handle_stop(msg).await,
+            "cancel" => self.handle_cancel(msg).await,
+            "status" => self.handle_status().await,
+            _ => Err(workflow_error(ErrorCode::UnknownMessageType, format!("未知的工作流消息类型: {}", msg.msg_type)).into()),
+        }
+    }
+}
@@ -0,0 +1,463 @@
+// 工作流定义
+// 工作流是按顺序执行的步骤列表，以 JSON 声明。每个步骤的输出 (文本或音频) 默认作为下一步的输入，
+// 模板中可以引用：`{{input}}` 为最近一个执行了的步骤的输出，`{{<步骤 ID>}}` 为指定步骤的输出，
+// `{{vars.<名称>}}` 为 run 请求中的变量。步骤可以设置执行条件 (when)、失败重试次数 (retries)，
+// 以及失败后是否继续 (continue_on_error)
+
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::llm::response::ApiFormat;
+use crate::llm::Priority;
+use crate::voice::audio::AudioData;
+use crate::voice::config::ASRConfig;
+
+/// 步骤数上限
+pub const MAX_STEPS: usize = 32;
+
+/// 每个步骤的重试次数上限
+pub const MAX_RETRIES: u32 = 5;
+
+/// 默认的重试间隔 (毫秒)
+pub const DEFAULT_RETRY_DELAY_MS: u64 = 1000;
+
+/// 重试间隔上限 (毫秒)
+const MAX_RETRY_DELAY_MS: u64 = 60_000;
+
+/// 录音步骤的默认时长上限 (秒)
+pub const DEFAULT_RECORD_SECS: u32 = 60;
+
+/// 录音步骤的时长范围 (秒)
+const RECORD_SECS_RANGE: std::ops::RangeInclusive<u32> = 1..=600;
+
+/// 步骤 ID 的长度上限
+const MAX_ID_LEN: usize = 64;
+
+/// 模板中表示上一步输出的名称
+const INPUT: &str = "input";
+
+/// 模板中变量名的前缀
+const VARS_PREFIX: &str = "vars.";
+
+// ============================================================================
+// 步骤
+// ============================================================================
+
+/// 工作流步骤
+#[derive(Debug, Clone, Deserialize)]
+pub struct Step {
+    /// 步骤 ID (字母、数字、- 和 _，在工作流内唯一)
+    pub id: String,
+    /// 步骤动作 (type 字段区分)
+    #[serde(flatten)]
+    pub action: Action,
+    /// 执行条件 (不满足时跳过)
+    #[serde(default)]
+    pub when: Option<Condition>,
+    /// 可重试的错误 (网络错误、超时等) 的重试次数
+    #[serde(default)]
+    pub retries: u32,
+    /// 重试间隔 (毫秒)
+    #[serde(default)]
+    pub retry_delay_ms: Option<u64>,
+    /// 失败后继续执行后续步骤 (该步骤没有输出)
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+/// 步骤动作
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    /// 录制麦克风，直到 stop 消息或达到时长上限，输出音频
+    Record {
+        #[serde(default)]
+        device: Option<String>,
+        #[serde(default)]
+        max_seconds: Option<u32>,
+    },
+    /// 转写音频 (默认为上一步的音频，或 audio 字段中的 base64 WAV)，输出文本
+    Transcribe {
+        asr_config: Box<ASRConfig>,
+        #[serde(default)]
+        audio: Option<String>,
+    },
+    /// 调用 LLM (润色、翻译等)，输出回复内容
+    Llm(LlmStep),
+    /// 按模板拼接文本
+    Text { template: String },
+    /// 写入文件，输出文件路径
+    Write {
+        path: String,
+        #[serde(default)]
+        mode: WriteMode,
+        /// 写入内容的模板 (默认为上一步的输出)
+        #[serde(default)]
+        content: Option<String>,
+    },
+}
+
+impl Action {
+    /// 动作名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Record { .. } => "record",
+            Action::Transcribe { .. } => "transcribe",
+            Action::Llm(_) => "llm",
+            Action::Text { .. } => "text",
+            Action::Write { .. } => "write",
+        }
+    }
+
+    /// 动作中的模板
+    fn templates(&self) -> Vec<&str> {
+        match self {
+            Action::Llm(llm) => [llm.system.as_deref(), llm.prompt.as_deref()].into_iter().flatten().collect(),
+            Action::Text { template } => vec![template],
+            Action::Write { path, content, .. } => [Some(path.as_str()), content.as_deref()].into_iter().flatten().collect(),
+            Action::Record { .. } | Action::Transcribe { .. } => Vec::new(),
+        }
+    }
+}
+
+/// LLM 步骤
+#[derive(Debug, Clone, Deserialize)]
+pub struct LlmStep {
+    /// API 端点
+    pub endpoint: String,
+    /// 请求头 (如 Authorization)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 模型名称
+    pub model: String,
+    #[serde(default)]
+    pub api_format: ApiFormat,
+    /// 系统提示词模板
+    #[serde(default)]
+    pub system: Option<String>,
+    /// 用户消息模板 (默认为 `{{input}}`)
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// 请求优先级 (批量处理的工作流可设为 background)
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+/// 文件写入方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// 覆盖已有文件
+    #[default]
+    Overwrite,
+    /// 追加到文件末尾 (文件不存在时创建)
+    Append,
+    /// 只创建新文件 (文件已存在时失败)
+    Create,
+}
+
+// ============================================================================
+// 条件
+// ============================================================================
+
+/// 步骤的执行条件
+#[derive(Debug, Clone, Deserialize)]
+pub struct Condition {
+    /// 检查的步骤 (省略时为上一步的输出)
+    #[serde(default)]
+    pub step: Option<String>,
+    #[serde(flatten)]
+    pub test: Test,
+}
+
+/// 条件判断 (op 字段区分，参数在 value 字段)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", content = "value", rename_all = "snake_case")]
+pub enum Test {
+    NotEmpty,
+    Empty,
+    Contains(String),
+    Equals(String),
+    /// 检测到的语言 (ISO 639-1) 为指定语言
+    Language(String),
+    NotLanguage(String),
+}
+
+impl Test {
+    /// 判断文本是否满足条件
+    pub fn matches(&self, text: &str) -> bool {
+        let language = || crate::utils::language::LanguageDetector::new().detect(text).language;
+        match self {
+            Test::NotEmpty => !text.trim().is_empty(),
+            Test::Empty => text.trim().is_empty(),
+            Test::Contains(value) => text.contains(value.as_str()),
+            Test::Equals(value) => text.trim() == value.trim(),
+            Test::Language(value) => language() == *value,
+            Test::NotLanguage(value) => language() != *value,
+        }
+    }
+}
+
+// ============================================================================
+// 步骤输出和上下文
+// ============================================================================
+
+/// 步骤输出
+#[derive(Debug, Clone)]
+pub enum Output {
+    Text(String),
+    /// 16 kHz 单声道音频
+    Audio(AudioData),
+    /// 跳过或失败的步骤
+    None,
+}
+
+impl Output {
+    /// 文本内容 (音频和空输出为空字符串)
+    pub fn text(&self) -> &str {
+        match self {
+            Output::Text(text) => text,
+            Output::Audio(_) | Output::None => "",
+        }
+    }
+
+    /// 事件中的表示：文本原样返回，音频只返回时长
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Output::Text(text) => serde_json::Value::String(text.clone()),
+            Output::Audio(audio) => serde_json::json!({ "duration_ms": audio.duration_ms }),
+            Output::None => serde_json::Value::Null,
+        }
+    }
+}
+
+/// 执行中的上下文
+pub struct Context {
+    /// 最近一个执行了的步骤的输出 (开始时为 run 请求的 input)
+    pub input: Output,
+    /// 各步骤的输出
+    pub outputs: HashMap<String, Output>,
+    /// run 请求中的变量
+    pub vars: HashMap<String, String>,
+}
+
+impl Context {
+    pub fn new(input: Option<String>, vars: HashMap<String, String>) -> Self {
+        Self {
+            input: input.map_or(Output::None, Output::Text),
+            outputs: HashMap::new(),
+            vars,
+        }
+    }
+
+    /// 条件检查的输出
+    pub fn output(&self, step: Option<&str>) -> &Output {
+        match step {
+            Some(step) => self.outputs.get(step).unwrap_or(&Output::None),
+            None => &self.input,
+        }
+    }
+
+    /// 记录步骤的输出 (跳过的步骤不改变 input)
+    pub fn record(&mut self, step: &str, output: Output) {
+        if !matches!(output, Output::None) {
+            self.input = output.clone();
+        }
+        self.outputs.insert(step.to_string(), output);
+    }
+
+    /// 渲染模板
+    pub fn render(&self, template: &str) -> String {
+        render(template, |name| {
+            if name == INPUT {
+                Some(self.input.text().to_string())
+            } else if let Some(var) = name.strip_prefix(VARS_PREFIX) {
+                Some(self.vars.get(var).cloned().unwrap_or_default())
+            } else {
+                self.outputs.get(name).map(|output| output.text().to_string())
+            }
+        })
+    }
+}
+
+/// 替换模板中的 `{{名称}}` (名称两侧可以有空格)，无法解析的占位符原样保留
+pub fn render(template: &str, resolve: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let placeholder = &rest[start..start + 2 + len + 2];
+        output.push_str(&rest[..start]);
+        match resolve(rest[start + 2..start + 2 + len].trim()) {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(placeholder),
+        }
+        rest = &rest[start + placeholder.len()..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 模板中引用的名称
+fn placeholders(template: &str) -> Vec<String> {
+    let names = std::cell::RefCell::new(Vec::new());
+    render(template, |name| {
+        names.borrow_mut().push(name.to_string());
+        None
+    });
+    names.into_inner()
+}
+
+// ============================================================================
+// 校验
+// ============================================================================
+
+/// 校验工作流：步骤 ID 唯一，模板和条件只引用之前的步骤，参数在范围内
+pub fn validate(steps: &[Step]) -> Result<(), String> {
+    if steps.is_empty() {
+        return Err("steps 不能为空".to_string());
+    }
+    if steps.len() > MAX_STEPS {
+        return Err(format!("步骤过多 (最多 {} 个)", MAX_STEPS));
+    }
+    let mut seen: HashSet<&str> = HashSet::new();
+    for step in steps {
+        let id = step.id.as_str();
+        let valid_id = !id.is_empty()
+            && id.len() <= MAX_ID_LEN
+            && id != INPUT
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_id {
+            return Err(format!("无效的步骤 ID: '{}' (只能包含字母、数字、- 和 _，最多 {} 个字符)", id, MAX_ID_LEN));
+        }
+        if seen.contains(id) {
+            return Err(format!("步骤 ID 重复: {}", id));
+        }
+        if step.retries > MAX_RETRIES {
+            return Err(format!("步骤 {} 的 retries 超出范围: {} (应为 0-{})", id, step.retries, MAX_RETRIES));
+        }
+        if let Some(delay) = step.retry_delay_ms.filter(|delay| *delay > MAX_RETRY_DELAY_MS) {
+            return Err(format!("步骤 {} 的 retry_delay_ms 超出范围: {} (应为 0-{})", id, delay, MAX_RETRY_DELAY_MS));
+        }
+        if let Some(referenced) = step.when.as_ref().and_then(|when| when.step.as_deref()) {
+            if !seen.contains(referenced) {
+                return Err(format!("步骤 {} 的条件引用了未知或之后的步骤: {}", id, referenced));
+            }
+        }
+        for name in step.action.templates().into_iter().flat_map(placeholders) {
+            if name != INPUT && !name.starts_with(VARS_PREFIX) && !seen.contains(name.as_str()) {
+                return Err(format!("步骤 {} 的模板引用了未知或之后的步骤: {}", id, name));
+            }
+        }
+        validate_action(id, &step.action)?;
+        seen.insert(id);
+    }
+    Ok(())
+}
+
+/// 校验动作的参数
+fn validate_action(id: &str, action: &Action) -> Result<(), String> {
+    match action {
+        Action::Record { max_seconds, .. } => {
+            if let Some(secs) = max_seconds.filter(|secs| !RECORD_SECS_RANGE.contains(secs)) {
+                return Err(format!(
+                    "步骤 {} 的 max_seconds 超出范围: {} (应为 {}-{})",
+                    id, secs, RECORD_SECS_RANGE.start(), RECORD_SECS_RANGE.end()
+                ));
+            }
+        }
+        Action::Transcribe { asr_config, .. } => {
+            asr_config.validate().map_err(|e| format!("步骤 {} 的 asr_config 无效: {}", id, e))?;
+        }
+        Action::Llm(llm) => {
+            if llm.endpoint.is_empty() {
+                return Err(format!("步骤 {} 的 endpoint 不能为空", id));
+            }
+        }
+        Action::Text { .. } => {}
+        Action::Write { path, .. } => {
+            // 包含模板的路径在执行时检查
+            if !path.contains("{{") && !Path::new(path).is_absolute() {
+                return Err(format!("步骤 {} 的 path 必须是绝对路径: {}", id, path));
+            }
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(value: serde_json::Value) -> Vec<Step> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn write_path() -> String {
+        std::env::temp_dir().join("workflow-note.md").to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_parse_steps() {
+        let parsed = steps(serde_json::json!([
+            { "id": "rec", "type": "record", "max_seconds": 30 },
+            { "id": "polish", "type": "llm", "endpoint": "http://localhost/v1/chat/completions", "model": "m",
+              "when": { "op": "not_empty" }, "retries": 2 },
+            { "id": "en", "type": "llm", "endpoint": "http://localhost/v1/chat/completions", "model": "m",
+              "prompt": "Translate: {{polish}}", "when": { "step": "polish", "op": "not_language", "value": "en" } },
+            { "id": "save", "type": "write", "path": write_path(), "mode": "append", "continue_on_error": true },
+        ]));
+        assert_eq!(parsed.iter().map(|step| step.action.name()).collect::<Vec<_>>(), vec!["record", "llm", "llm", "write"]);
+        assert!(matches!(parsed[1].when.as_ref().unwrap().test, Test::NotEmpty));
+        assert!(matches!(&parsed[2].when.as_ref().unwrap().test, Test::NotLanguage(lang) if lang == "en"));
+        assert!(matches!(parsed[3].action, Action::Write { mode: WriteMode::Append, .. }));
+        assert!(parsed[3].continue_on_error);
+        assert_eq!(validate(&parsed), Ok(()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let text = |id: &str, template: &str| serde_json::json!({ "id": id, "type": "text", "template": template });
+        assert!(validate(&[]).is_err());
+        // 模板只能引用之前的步骤
+        let forward = steps(serde_json::json!([text("a", "{{b}}"), text("b", "x")]));
+        assert!(validate(&forward).unwrap_err().contains("b"));
+        let duplicate = steps(serde_json::json!([text("a", "x"), text("a", "y")]));
+        assert!(validate(&duplicate).unwrap_err().contains("重复"));
+        let bad_id = steps(serde_json::json!([text("a b", "x")]));
+        assert!(validate(&bad_id).is_err());
+        let retries = steps(serde_json::json!([{ "id": "a", "type": "text", "template": "x", "retries": 9 }]));
+        assert!(validate(&retries).is_err());
+        let relative = steps(serde_json::json!([{ "id": "a", "type": "write", "path": "notes/a.md" }]));
+        assert!(validate(&relative).unwrap_err().contains("绝对路径"));
+        let ok = steps(serde_json::json!([text("a", "{{input}} {{vars.title}}"), text("b", "{{ a }}!")]));
+        assert_eq!(validate(&ok), Ok(()));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut context = Context::new(Some("原文".to_string()), HashMap::from([("title".to_string(), "周会".to_string())]));
+        assert_eq!(context.render("# {{vars.title}}\n{{input}}"), "# 周会\n原文");
+        context.record("polish", Output::Text("润色后".to_string()));
+        context.record("skipped", Output::None);
+        // 跳过的步骤不改变 input
+        assert_eq!(context.render("{{input}}|{{ polish }}|{{skipped}}|{{vars.none}}|{{unknown}}|{{open"), "润色后|润色后|||{{unknown}}|{{open");
+    }
+
+    #[test]
+    fn test_conditions() {
+        assert!(Test::NotEmpty.matches("文本"));
+        assert!(Test::Empty.matches("  \n"));
+        assert!(Test::Contains("TODO".to_string()).matches("a TODO b"));
+        assert!(Test::Equals("yes".to_string()).matches(" yes\n"));
+        assert!(Test::Language("en".to_string()).matches("This is a sentence written in English for the detector."));
+        assert!(Test::NotLanguage("en".to_string()).matches("这是一段用中文写的句子，用于测试语言检测。"));
+    }
+}
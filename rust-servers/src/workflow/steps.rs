@@ -0,0 +1,180 @@
+// 步骤执行
+// 各步骤直接调用服务器内其他模块的实现 (录音、转写、LLM、文件写入)，中间结果不经过客户端
+
+use base64::{Engine as _, engine::general_purpose};
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use super::pipeline::{Action, Context, LlmStep, Output, WriteMode, DEFAULT_RECORD_SECS};
+use super::workflow_error;
+use crate::meeting::{SourceKind, SourceSpec};
+use crate::router::{ErrorCode, ModuleError, ModuleType};
+use crate::system::progress::ProgressReporter;
+use crate::voice::audio::recorder::{resample, to_mono};
+use crate::voice::audio::{decode_wav, AudioData, TARGET_SAMPLE_RATE};
+use crate::voice::config::{ASRConfig, ASRMode};
+
+/// 录音期间报告进度的间隔
+const RECORD_TICK: Duration = Duration::from_millis(500);
+
+/// 步骤执行环境
+pub struct Runtime<'a> {
+    pub request_id: Option<String>,
+    /// 结束录音步骤的信号 (stop 消息)
+    pub stop: &'a Notify,
+    /// 是否正在录音 (stop 消息据此判断)
+    pub recording: &'a std::sync::atomic::AtomicBool,
+    pub progress: &'a ProgressReporter,
+}
+
+/// 执行一个步骤
+pub async fn execute(action: &Action, context: &Context, runtime: &Runtime<'_>) -> Result<Output, ModuleError> {
+    match action {
+        Action::Record { device, max_seconds } => {
+            record(device.clone(), max_seconds.unwrap_or(DEFAULT_RECORD_SECS), runtime).await
+        }
+        Action::Transcribe { asr_config, audio } => {
+            let audio = match audio {
+                Some(data) => decode_audio(data)?,
+                None => match &context.input {
+                    Output::Audio(audio) => audio.clone(),
+                    _ => {
+                        return Err(workflow_error(ErrorCode::InvalidParams, "没有可转写的音频 (需要之前的 record 步骤或 audio 字段)"));
+                    }
+                },
+            };
+            transcribe(&audio, asr_config).await
+        }
+        Action::Llm(llm) => complete(llm, context, runtime.request_id.clone()).await,
+        Action::Text { template } => Ok(Output::Text(context.render(template))),
+        Action::Write { path, mode, content } => {
+            let path = PathBuf::from(context.render(path));
+            let content = match content {
+                Some(template) => context.render(template),
+                None => context.input.text().to_string(),
+            };
+            write(path, *mode, content).await
+        }
+    }
+}
+
+/// 录制麦克风，直到收到 stop 消息或达到时长上限
+async fn record(device: Option<String>, max_seconds: u32, runtime: &Runtime<'_>) -> Result<Output, ModuleError> {
+    use std::sync::atomic::Ordering;
+
+    let sources = vec![SourceSpec { kind: SourceKind::Mic, device }];
+    let capture = tokio::task::spawn_blocking(move || crate::meeting::open_input().open(&sources))
+        .await
+        .map_err(|e| workflow_error(ErrorCode::Internal, format!("打开录音设备失败: {}", e)))?
+        .map_err(|e| workflow_error(ErrorCode::DeviceError, format!("打开录音设备失败: {}", e)))?;
+
+    runtime.recording.store(true, Ordering::SeqCst);
+    let deadline = tokio::time::sleep(Duration::from_secs(max_seconds as u64));
+    tokio::pin!(deadline);
+    let mut ticker = tokio::time::interval(RECORD_TICK);
+    let started = tokio::time::Instant::now();
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let detail = format!("{}s", started.elapsed().as_secs());
+                runtime.progress.report("recording", None, Some(detail)).await;
+            }
+            _ = runtime.stop.notified() => break,
+            _ = &mut deadline => break,
+        }
+    }
+    runtime.recording.store(false, Ordering::SeqCst);
+
+    let samples: Vec<f32> = capture
+        .stop()
+        .iter()
+        .flat_map(|buffer| resample(&buffer.drain(), buffer.sample_rate, TARGET_SAMPLE_RATE))
+        .collect();
+    Ok(Output::Audio(AudioData::new(samples, TARGET_SAMPLE_RATE, 1)))
+}
+
+/// 解码 base64 WAV 为 16 kHz 单声道音频
+fn decode_audio(data: &str) -> Result<AudioData, ModuleError> {
+    let wav = general_purpose::STANDARD
+        .decode(data.as_bytes())
+        .map_err(|e| workflow_error(ErrorCode::InvalidParams, format!("audio 不是有效的 base64: {}", e)))?;
+    let audio = decode_wav(&wav)
+        .map_err(|e| workflow_error(ErrorCode::InvalidParams, format!("无法解码 WAV 音频: {}", e)))?;
+    let samples = resample(&to_mono(&audio.samples, audio.channels), audio.sample_rate, TARGET_SAMPLE_RATE);
+    Ok(AudioData::new(samples, TARGET_SAMPLE_RATE, 1))
+}
+
+/// 转写音频 (使用 HTTP 模式，实时模式的配置同样适用)
+async fn transcribe(audio: &AudioData, asr_config: &ASRConfig) -> Result<Output, ModuleError> {
+    let mut config = asr_config.clone();
+    config.primary.mode = ASRMode::Http;
+    if let Some(fallback) = config.fallback.as_mut() {
+        fallback.mode = ASRMode::Http;
+    }
+    let result = crate::voice::perform_transcription(audio, &config)
+        .await
+        .map_err(|e| workflow_error(ErrorCode::TranscriptionFailed, e.to_string()))?;
+    Ok(Output::Text(crate::voice::post_process_text(result.text.trim().to_string(), &config)))
+}
+
+/// 调用 LLM，返回完整回复
+async fn complete(llm: &LlmStep, context: &Context, request_id: Option<String>) -> Result<Output, ModuleError> {
+    let system = llm.system.as_deref().map(|system| context.render(system)).unwrap_or_default();
+    let prompt = context.render(llm.prompt.as_deref().unwrap_or("{{input}}"));
+    let config = crate::llm::StreamConfig {
+        endpoint: llm.endpoint.clone(),
+        headers: llm.headers.clone(),
+        body: crate::llm::prompt_body(llm.api_format, &llm.model, &system, &prompt),
+        api_format: llm.api_format,
+        request_id,
+        priority: llm.priority,
+        sinks: Vec::new(),
+    };
+    let content = crate::llm::complete(config)
+        .await
+        .map_err(|e| ModuleError::from_error(ModuleType::Workflow, &e))?;
+    Ok(Output::Text(content.trim().to_string()))
+}
+
+/// 写入文件 (父目录必须存在)，输出文件路径
+async fn write(path: PathBuf, mode: WriteMode, content: String) -> Result<Output, ModuleError> {
+    if !path.is_absolute() {
+        return Err(workflow_error(ErrorCode::InvalidParams, format!("path 必须是绝对路径: {}", path.display())));
+    }
+    if let Some(parent) = path.parent().filter(|parent| !parent.is_dir()) {
+        return Err(workflow_error(ErrorCode::NotFound, format!("目录不存在: {}", parent.display())));
+    }
+    let target = path.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut options = std::fs::OpenOptions::new();
+        match mode {
+            WriteMode::Overwrite => options.write(true).create(true).truncate(true),
+            WriteMode::Append => options.append(true).create(true),
+            WriteMode::Create => options.write(true).create_new(true),
+        };
+        let mut file = options.open(&target).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => {
+                workflow_error(ErrorCode::Conflict, format!("文件已存在: {}", target.display()))
+            }
+            _ => workflow_error(ErrorCode::IoError, format!("无法写入文件: {}", e)),
+        })?;
+        file.write_all(content.as_bytes())
+            .map_err(|e| workflow_error(ErrorCode::IoError, format!("无法写入文件: {}", e)))
+    })
+    .await
+    .map_err(|e| workflow_error(ErrorCode::Internal, format!("写入任务失败: {}", e)))??;
+    crate::audit::record_file_write(ModuleType::Workflow, &path);
+    Ok(Output::Text(path.display().to_string()))
+}
+
+/// 停止录音步骤 (当前没有录音时返回 false)
+pub fn stop_recording(stop: &Arc<Notify>, recording: &std::sync::atomic::AtomicBool) -> bool {
+    if !recording.load(std::sync::atomic::Ordering::SeqCst) {
+        return false;
+    }
+    stop.notify_one();
+    true
+}
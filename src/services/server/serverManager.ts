@@ -72,6 +72,9 @@ export class ServerManager {
   /** 服务器端口 */
   private port: number | null = null;
   
+  /** 服务器认证令牌 */
+  private token: string | null = null;
+  
   /** 是否正在关闭 */
   private isShuttingDown = false;
   
@@ -276,6 +279,7 @@ export class ServerManager {
     
     // 清理状态
     this.port = null;
+    this.token = null;
     this.serverStartPromise = null;
     this.wsConnectPromise = null;
     
@@ -388,11 +392,12 @@ export class ServerManager {
       });
       
       // 等待端口信息
-      const port = await this.waitForServerPort();
-      this.port = port;
+      const info = await this.waitForServerInfo();
+      this.port = info.port;
+      this.token = info.token ?? null;
       this.restartAttempts = 0;
       
-      debugLog(`[ServerManager] 服务器已启动，端口: ${port}`);
+      debugLog(`[ServerManager] 服务器已启动，端口: ${info.port}`);
       
       // 设置退出处理器
       this.setupServerExitHandler();
@@ -517,9 +522,9 @@ export class ServerManager {
   }
 
   /**
-   * 等待服务器输出端口信息
+   * 等待服务器输出端口和认证令牌信息
    */
-  private async waitForServerPort(): Promise<number> {
+  private async waitForServerInfo(): Promise<ServerInfo> {
     return new Promise((resolve, reject) => {
       if (!this.process || !this.process.stdout) {
         reject(new ServerManagerError(
@@ -549,8 +554,8 @@ export class ServerManager {
             if (info.port && typeof info.port === 'number') {
               clearTimeout(timeout);
               this.process?.stdout?.off('data', onData);
-              debugLog('[ServerManager] 解析到服务器信息:', info);
-              resolve(info.port);
+              debugLog('[ServerManager] 解析到服务器信息: port=', info.port, 'pid=', info.pid);
+              resolve(info);
             }
           }
         } catch {
@@ -598,7 +603,9 @@ export class ServerManager {
       const wsUrl = `ws://127.0.0.1:${this.port}`;
       debugLog('[ServerManager] 连接 WebSocket:', wsUrl);
       
-      this.ws = new WebSocket(wsUrl);
+      this.ws = new WebSocket(
+        this.token ? `${wsUrl}/?token=${encodeURIComponent(this.token)}` : wsUrl
+      );
       
       const timeout = setTimeout(() => {
         this.wsConnectPromise = null;
@@ -805,6 +812,7 @@ export class ServerManager {
 
     this.process.on('exit', (code, signal) => {
       this.port = null;
+      this.token = null;
       this.serverStartPromise = null;
       
       if (this.isShuttingDown) {
@@ -26,6 +26,8 @@ export interface ServerInfo {
   port: number;
   /** 进程 PID */
   pid: number;
+  /** 本次启动的认证令牌 (连接 WebSocket 时通过 ?token= 传递) */
+  token?: string;
 }
 
 // ============================================================================